@@ -9,6 +9,13 @@ pub struct Config {
     pub max_tokens: u32,
     pub temperature: f32,
     pub timeout_seconds: u64,
+    pub prompt_templates_path: String,
+    pub fallback_models: Vec<String>,
+    pub model_health_check_seconds: u64,
+    pub model_retry_after_seconds: u64,
+    pub batch_max_documents: usize,
+    pub batch_concurrency: usize,
+    pub embedding_model: String,
 }
 
 impl Config {
@@ -34,6 +41,32 @@ impl Config {
                 .unwrap_or_else(|_| "30".to_string())
                 .parse()
                 .expect("Invalid timeout"),
+            prompt_templates_path: env::var("PROMPT_TEMPLATES_PATH")
+                .unwrap_or_else(|_| "data/prompt_templates.json".to_string()),
+            fallback_models: env::var("AI_FALLBACK_MODELS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|model| model.trim().to_string())
+                .filter(|model| !model.is_empty())
+                .collect(),
+            model_health_check_seconds: env::var("AI_MODEL_HEALTH_CHECK_SECONDS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("Invalid model health check interval"),
+            model_retry_after_seconds: env::var("AI_MODEL_RETRY_AFTER_SECONDS")
+                .unwrap_or_else(|_| "120".to_string())
+                .parse()
+                .expect("Invalid model retry interval"),
+            batch_max_documents: env::var("AI_BATCH_MAX_DOCUMENTS")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .expect("Invalid batch size"),
+            batch_concurrency: env::var("AI_BATCH_CONCURRENCY")
+                .unwrap_or_else(|_| "4".to_string())
+                .parse()
+                .expect("Invalid batch concurrency"),
+            embedding_model: env::var("AI_EMBEDDING_MODEL")
+                .unwrap_or_else(|_| "nomic-embed-text".to_string()),
         }
     }
 }
\ No newline at end of file
@@ -2,14 +2,22 @@ use actix_web::{web, App, HttpServer, middleware::Logger};
 use env_logger::Env;
 use dotenv::dotenv;
 use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 mod config;
 mod routes;
 mod ollama_client;
 mod metrics;
+mod templates;
+mod model_chain;
 
 use config::Config;
 use metrics::setup_metrics;
+use templates::TemplateStore;
+use model_chain::ModelChain;
+use ollama_client::OllamaClient;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -30,19 +38,56 @@ async fn main() -> std::io::Result<()> {
     // Setup metrics
     let metrics = setup_metrics();
     
+    // Load prompt templates (built-in defaults apply until overridden)
+    let templates = web::Data::new(
+        TemplateStore::load(Some(PathBuf::from(&config.prompt_templates_path)))
+            .expect("Failed to load prompt templates"),
+    );
+    
+    // Primary model with fallbacks, health-checked in the background
+    let model_chain = Arc::new(ModelChain::new(
+        config.default_model.clone(),
+        config.fallback_models.clone(),
+        Duration::from_secs(config.model_retry_after_seconds),
+    ));
+    if !config.fallback_models.is_empty() {
+        log::info!("🔁 Fallback models: {}", config.fallback_models.join(", "));
+    }
+    model_chain::spawn_health_checks(
+        model_chain.clone(),
+        OllamaClient::new(config.ollama_url.clone(), config.timeout_seconds),
+        Duration::from_secs(config.model_health_check_seconds),
+    );
+    let model_chain = web::Data::from(model_chain);
+    
     // Start HTTP server
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(config.clone()))
             .app_data(web::Data::new(metrics.clone()))
+            .app_data(templates.clone())
+            .app_data(model_chain.clone())
             .wrap(Logger::default())
             .service(
                 web::scope("/api/v1")
                     .route("/health", web::get().to(routes::health_check))
                     .route("/summarize", web::post().to(routes::summarize_text))
                     .route("/classify", web::post().to(routes::classify_text))
+                    .route("/classify/batch", web::post().to(routes::classify_batch))
                     .route("/explain", web::post().to(routes::explain_data))
+                    .route("/embed", web::post().to(routes::embed_text))
                     .route("/metrics", web::get().to(routes::get_metrics))
+                    .route("/models", web::get().to(routes::list_models))
+                    .route("/models/pull", web::post().to(routes::pull_model))
+                    .route("/models/chain", web::get().to(routes::model_chain_status))
+                    .route("/models/{name}", web::delete().to(routes::delete_model))
+                    .route("/templates", web::get().to(routes::list_templates))
+                    .route("/templates/{name}", web::get().to(routes::get_template))
+                    .route("/templates/{name}", web::put().to(routes::save_template))
+                    .route("/templates/{name}", web::delete().to(routes::delete_template))
+                    .route("/templates/{name}/versions", web::get().to(routes::list_template_versions))
+                    .route("/templates/{name}/rollback", web::post().to(routes::rollback_template))
+                    .route("/templates/{name}/preview", web::post().to(routes::preview_template))
             )
             .route("/", web::get().to(routes::index))
     })
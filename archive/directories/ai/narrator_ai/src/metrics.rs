@@ -7,6 +7,7 @@ pub struct Metrics {
     pub ai_tasks_total: IntCounterVec,
     pub ai_latency_seconds: HistogramVec,
     pub ai_errors_total: IntCounterVec,
+    pub model_failovers_total: IntCounterVec,
     pub ollama_health: Gauge,
     pub active_requests: Gauge,
 }
@@ -36,6 +37,13 @@ pub fn setup_metrics() -> Metrics {
         &["task_type", "error_type"]
     ).expect("Failed to create ai_errors_total metric");
 
+    // Counter for model failovers
+    let model_failovers_total = IntCounterVec::new(
+        prometheus::Opts::new("model_failovers_total", "Total number of times a model failed and the next model in the chain was tried")
+            .namespace("narrator_ai"),
+        &["model_name"]
+    ).expect("Failed to create model_failovers_total metric");
+
     // Gauge for Ollama health status
     let ollama_health = Gauge::new(
         "ollama_health_status", 
@@ -52,6 +60,7 @@ pub fn setup_metrics() -> Metrics {
     registry.register(Box::new(ai_tasks_total.clone())).expect("Failed to register ai_tasks_total");
     registry.register(Box::new(ai_latency_seconds.clone())).expect("Failed to register ai_latency_seconds");
     registry.register(Box::new(ai_errors_total.clone())).expect("Failed to register ai_errors_total");
+    registry.register(Box::new(model_failovers_total.clone())).expect("Failed to register model_failovers_total");
     registry.register(Box::new(ollama_health.clone())).expect("Failed to register ollama_health");
     registry.register(Box::new(active_requests.clone())).expect("Failed to register active_requests");
 
@@ -60,6 +69,7 @@ pub fn setup_metrics() -> Metrics {
         ai_tasks_total,
         ai_latency_seconds,
         ai_errors_total,
+        model_failovers_total,
         ollama_health,
         active_requests,
     }
@@ -94,6 +104,12 @@ impl Metrics {
         self.active_requests.dec();
     }
 
+    pub fn record_failover(&self, model_name: &str) {
+        self.model_failovers_total
+            .with_label_values(&[model_name])
+            .inc();
+    }
+
     pub fn update_ollama_health(&self, healthy: bool) {
         self.ollama_health.set(if healthy { 1.0 } else { 0.0 });
     }
@@ -0,0 +1,193 @@
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::metrics::Metrics;
+use crate::ollama_client::{OllamaClient, TokenStream};
+
+/// Ordered list of models to try: the primary first, then fallbacks.
+///
+/// Models that fail or time out are marked unhealthy and moved to the back
+/// of the chain until `retry_after` has passed or a health check sees them
+/// installed again.
+pub struct ModelChain {
+    models: Vec<String>,
+    unhealthy: RwLock<HashMap<String, Instant>>,
+    retry_after: Duration,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelStatus {
+    pub name: String,
+    pub role: &'static str,
+    pub healthy: bool,
+}
+
+impl ModelChain {
+    pub fn new(primary: String, fallbacks: Vec<String>, retry_after: Duration) -> Self {
+        let mut models = vec![primary];
+        for model in fallbacks {
+            if !models.contains(&model) {
+                models.push(model);
+            }
+        }
+
+        Self {
+            models,
+            unhealthy: RwLock::new(HashMap::new()),
+            retry_after,
+        }
+    }
+
+    pub fn is_healthy(&self, model: &str) -> bool {
+        let unhealthy = self.unhealthy.read().expect("model chain lock poisoned");
+        match unhealthy.get(model) {
+            Some(marked_at) => marked_at.elapsed() >= self.retry_after,
+            None => true,
+        }
+    }
+
+    pub fn mark_unhealthy(&self, model: &str) {
+        let mut unhealthy = self.unhealthy.write().expect("model chain lock poisoned");
+        unhealthy.insert(model.to_string(), Instant::now());
+    }
+
+    pub fn mark_healthy(&self, model: &str) {
+        let mut unhealthy = self.unhealthy.write().expect("model chain lock poisoned");
+        unhealthy.remove(model);
+    }
+
+    /// Models to try for a request, healthy ones first
+    pub fn candidates(&self, requested: Option<&str>) -> Vec<String> {
+        let mut ordered: Vec<String> = Vec::new();
+        if let Some(model) = requested {
+            ordered.push(model.to_string());
+        }
+        for model in &self.models {
+            if !ordered.contains(model) {
+                ordered.push(model.clone());
+            }
+        }
+
+        // Unhealthy models stay in the list as a last resort
+        let (mut healthy, unhealthy): (Vec<_>, Vec<_>) =
+            ordered.into_iter().partition(|model| self.is_healthy(model));
+        healthy.extend(unhealthy);
+        healthy
+    }
+
+    /// Generate text, failing over along the chain. Returns the text and the model used.
+    pub async fn generate_text(
+        &self,
+        client: &OllamaClient,
+        requested: Option<&str>,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f32,
+        metrics: &Metrics,
+    ) -> Result<(String, String)> {
+        let mut last_error = None;
+
+        for model in self.candidates(requested) {
+            match client.generate_text(&model, prompt, max_tokens, temperature).await {
+                Ok(result) => {
+                    self.mark_healthy(&model);
+                    return Ok((result, model));
+                }
+                Err(e) => {
+                    log::warn!("Model {} failed, trying next in chain: {}", model, e);
+                    self.mark_unhealthy(&model);
+                    metrics.record_failover(&model);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| anyhow!("No models configured")))
+    }
+
+    /// Start a token stream on the first model that accepts the request
+    pub async fn generate_stream(
+        &self,
+        client: &OllamaClient,
+        requested: Option<&str>,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f32,
+        metrics: &Metrics,
+    ) -> Result<(TokenStream, String)> {
+        let mut last_error = None;
+
+        for model in self.candidates(requested) {
+            match client.generate_stream(&model, prompt, max_tokens, temperature).await {
+                Ok(tokens) => {
+                    self.mark_healthy(&model);
+                    return Ok((tokens, model));
+                }
+                Err(e) => {
+                    log::warn!("Model {} failed to stream, trying next in chain: {}", model, e);
+                    self.mark_unhealthy(&model);
+                    metrics.record_failover(&model);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| anyhow!("No models configured")))
+    }
+
+    /// Mark chain models healthy or unhealthy based on what Ollama has installed
+    pub async fn check_health(&self, client: &OllamaClient) {
+        match client.list_models().await {
+            Ok(installed) => {
+                for model in &self.models {
+                    if is_installed(&installed, model) {
+                        self.mark_healthy(model);
+                    } else {
+                        log::warn!("Model {} in fallback chain is not installed", model);
+                        self.mark_unhealthy(model);
+                    }
+                }
+            }
+            Err(e) => {
+                log::warn!("Model health check failed: {}", e);
+                for model in &self.models {
+                    self.mark_unhealthy(model);
+                }
+            }
+        }
+    }
+
+    pub fn status(&self) -> Vec<ModelStatus> {
+        self.models
+            .iter()
+            .enumerate()
+            .map(|(index, model)| ModelStatus {
+                name: model.clone(),
+                role: if index == 0 { "primary" } else { "fallback" },
+                healthy: self.is_healthy(model),
+            })
+            .collect()
+    }
+}
+
+/// Ollama reports `llama2:latest` for a model pulled as `llama2`
+fn is_installed(installed: &[String], model: &str) -> bool {
+    installed
+        .iter()
+        .any(|name| name == model || name.strip_suffix(":latest") == Some(model))
+}
+
+/// Periodically refresh model health
+pub fn spawn_health_checks(chain: Arc<ModelChain>, client: OllamaClient, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+            chain.check_health(&client).await;
+        }
+    });
+}
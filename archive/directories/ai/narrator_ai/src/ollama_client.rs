@@ -1,9 +1,14 @@
 use anyhow::{Result, anyhow};
+use futures::{Stream, StreamExt};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::pin::Pin;
 use std::time::Duration;
 use uuid::Uuid;
 
+/// Tokens produced by a streaming generation
+pub type TokenStream = Pin<Box<dyn Stream<Item = Result<String>> + Send>>;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OllamaRequest {
     pub model: String,
@@ -37,6 +42,7 @@ pub struct OllamaResponse {
 
 pub struct OllamaClient {
     client: Client,
+    stream_client: Client,
     base_url: String,
 }
 
@@ -47,7 +53,13 @@ impl OllamaClient {
             .build()
             .expect("Failed to create HTTP client");
 
-        Self { client, base_url }
+        // A total timeout would cut off long streams, so only bound the connect
+        let stream_client = Client::builder()
+            .connect_timeout(Duration::from_secs(timeout_seconds))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self { client, stream_client, base_url }
     }
 
     pub async fn generate_text(
@@ -97,6 +109,49 @@ impl OllamaClient {
         Ok(ollama_response.response)
     }
 
+    /// Start a streaming generation, yielding tokens as Ollama produces them
+    pub async fn generate_stream(
+        &self,
+        model: &str,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> Result<TokenStream> {
+        let request = OllamaRequest {
+            model: model.to_string(),
+            prompt: prompt.to_string(),
+            stream: true,
+            options: OllamaOptions {
+                num_predict: max_tokens,
+                temperature,
+                top_k: 40,
+                top_p: 0.9,
+            },
+        };
+
+        let url = format!("{}/api/generate", self.base_url);
+
+        log::debug!("Sending streaming request to Ollama: {}", url);
+
+        let response = self
+            .stream_client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to send request to Ollama: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Ollama API returned error: {} - {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        Ok(Box::pin(ndjson_tokens(Box::pin(response.bytes_stream()))))
+    }
+
     pub async fn check_health(&self) -> Result<bool> {
         let url = format!("{}/api/tags", self.base_url);
         
@@ -141,34 +196,122 @@ impl OllamaClient {
             .map(|m| m.name)
             .collect())
     }
-}
 
-// Specialized prompts for property assessment tasks
-pub struct PromptTemplates;
-
-impl PromptTemplates {
-    pub fn summarize_property(text: &str) -> String {
-        format!(
-            "Summarize this property assessment data in clear, professional language suitable for county staff. \
-            Focus on key details like property type, location, and important characteristics. \
-            Keep the summary concise but informative:\n\n{}\n\nSummary:",
-            text
-        )
+    /// Generate an embedding vector for a piece of text
+    pub async fn embed(&self, model: &str, text: &str) -> Result<Vec<f32>> {
+        let url = format!("{}/api/embeddings", self.base_url);
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({ "model": model, "prompt": text }))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to send embedding request to Ollama: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Ollama API returned error: {} - {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        #[derive(Deserialize)]
+        struct EmbeddingResponse {
+            embedding: Vec<f32>,
+        }
+
+        let embedding_response: EmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse embedding response: {}", e))?;
+
+        Ok(embedding_response.embedding)
     }
 
-    pub fn classify_property_type(text: &str) -> String {
-        format!(
-            "Classify this property into one of these categories: Residential, Commercial, Industrial, Agricultural, or Mixed-Use. \
-            Provide the classification and a brief explanation:\n\n{}\n\nClassification:",
-            text
-        )
+    /// Download a model; blocks until the pull completes
+    pub async fn pull_model(&self, name: &str) -> Result<()> {
+        let url = format!("{}/api/pull", self.base_url);
+
+        // Pulls can take many minutes, so bypass the request timeout
+        let response = self
+            .stream_client
+            .post(&url)
+            .json(&serde_json::json!({ "name": name, "stream": false }))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to pull model {}: {}", name, e))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Ollama failed to pull model {}: {} - {}",
+                name,
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        Ok(())
     }
 
-    pub fn explain_assessment_data(text: &str) -> String {
-        format!(
-            "Explain this property assessment data in simple terms that a property owner or county resident could understand. \
-            Focus on what the data means and why it matters:\n\n{}\n\nExplanation:",
-            text
-        )
+    pub async fn delete_model(&self, name: &str) -> Result<()> {
+        let url = format!("{}/api/delete", self.base_url);
+
+        let response = self
+            .client
+            .delete(&url)
+            .json(&serde_json::json!({ "name": name }))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to delete model {}: {}", name, e))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Ollama failed to delete model {}: {}",
+                name,
+                response.status()
+            ));
+        }
+
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Split Ollama's newline-delimited JSON stream into response tokens
+fn ndjson_tokens<S, B>(bytes: S) -> impl Stream<Item = Result<String>> + Send
+where
+    S: Stream<Item = reqwest::Result<B>> + Unpin + Send + 'static,
+    B: AsRef<[u8]>,
+{
+    futures::stream::unfold((bytes, Vec::new(), false), |(mut bytes, mut buffer, done)| async move {
+        if done {
+            return None;
+        }
+
+        loop {
+            if let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=pos).collect();
+                if line.iter().all(|b| b.is_ascii_whitespace()) {
+                    continue;
+                }
+
+                return match serde_json::from_slice::<OllamaResponse>(&line) {
+                    Ok(chunk) => Some((Ok(chunk.response), (bytes, buffer, chunk.done))),
+                    Err(e) => Some((
+                        Err(anyhow!("Failed to parse Ollama stream chunk: {}", e)),
+                        (bytes, buffer, true),
+                    )),
+                };
+            }
+
+            match bytes.next().await {
+                Some(Ok(chunk)) => buffer.extend_from_slice(chunk.as_ref()),
+                Some(Err(e)) => {
+                    return Some((Err(anyhow!("Ollama stream interrupted: {}", e)), (bytes, buffer, true)))
+                }
+                None => return None,
+            }
+        }
+    })
+}
@@ -1,11 +1,19 @@
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Result};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 use uuid::Uuid;
 
 use crate::config::Config;
 use crate::metrics::Metrics;
-use crate::ollama_client::{OllamaClient, PromptTemplates};
+use crate::model_chain::ModelChain;
+use crate::ollama_client::{OllamaClient, TokenStream};
+use crate::templates::{
+    TemplateInput, TemplateStore, CLASSIFY_PROPERTY_TYPE, EXPLAIN_ASSESSMENT_DATA,
+    SUMMARIZE_PROPERTY, SUMMARIZE_SYNC_OPERATION,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct TextRequest {
@@ -13,6 +21,14 @@ pub struct TextRequest {
     pub model: Option<String>,
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
+    /// Kind of text being summarized: `property` (default) or `sync_operation`
+    pub context: Option<String>,
+    /// Stream tokens as Server-Sent Events (also enabled by `Accept: text/event-stream`)
+    pub stream: Option<bool>,
+    /// County whose prompt overrides should be used
+    pub county_id: Option<String>,
+    /// Extra template variables alongside `text`
+    pub variables: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -47,16 +63,20 @@ pub async fn index() -> Result<HttpResponse> {
         "description": "AI-powered natural language processing for TerraFusion Platform",
         "endpoints": {
             "health": "GET /api/v1/health",
-            "summarize": "POST /api/v1/summarize",
+            "summarize": "POST /api/v1/summarize (set \"stream\": true for Server-Sent Events)",
             "classify": "POST /api/v1/classify",
-            "explain": "POST /api/v1/explain",
-            "metrics": "GET /api/v1/metrics"
+            "classify_batch": "POST /api/v1/classify/batch",
+            "embed": "POST /api/v1/embed",
+            "explain": "POST /api/v1/explain (set \"stream\": true for Server-Sent Events)",
+            "metrics": "GET /api/v1/metrics",
+            "templates": "GET /api/v1/templates, GET|PUT|DELETE /api/v1/templates/{name}"
         },
         "example_request": {
             "text": "This is a 2-story residential home located in tax district 503...",
             "model": "llama2",
             "max_tokens": 500,
-            "temperature": 0.7
+            "temperature": 0.7,
+            "context": "property"
         }
     })))
 }
@@ -92,9 +112,12 @@ pub async fn health_check(
 }
 
 pub async fn summarize_text(
+    http_req: HttpRequest,
     req: web::Json<TextRequest>,
     config: web::Data<Config>,
     metrics: web::Data<Metrics>,
+    templates: web::Data<TemplateStore>,
+    model_chain: web::Data<ModelChain>,
 ) -> Result<HttpResponse> {
     let start_time = Instant::now();
     let task_type = "summarize";
@@ -102,15 +125,29 @@ pub async fn summarize_text(
     
     metrics.record_task_start(task_type);
 
-    let model = req.model.as_ref().unwrap_or(&config.default_model).clone();
     let max_tokens = req.max_tokens.unwrap_or(config.max_tokens);
     let temperature = req.temperature.unwrap_or(config.temperature);
 
     let client = OllamaClient::new(config.ollama_url.clone(), config.timeout_seconds);
-    let prompt = PromptTemplates::summarize_property(&req.text);
+    let template_name = match req.context.as_deref() {
+        Some("sync_operation") => SUMMARIZE_SYNC_OPERATION,
+        _ => SUMMARIZE_PROPERTY,
+    };
+    let prompt = match build_prompt(&templates, template_name, &req) {
+        Ok(prompt) => prompt,
+        Err(response) => {
+            metrics.record_error(task_type, "template_error");
+            return Ok(response);
+        }
+    };
 
-    match client.generate_text(&model, &prompt, max_tokens, temperature).await {
-        Ok(result) => {
+    if wants_stream(&http_req, &req) {
+        let requested_model = req.model.clone();
+        return stream_text(task_type, request_id, client, model_chain, requested_model, prompt, max_tokens, temperature, metrics).await;
+    }
+
+    match model_chain.generate_text(&client, req.model.as_deref(), &prompt, max_tokens, temperature, &metrics).await {
+        Ok((result, model)) => {
             let duration = start_time.elapsed();
             metrics.record_task_completion(task_type, &model, duration.as_secs_f64(), true);
 
@@ -140,6 +177,8 @@ pub async fn classify_text(
     req: web::Json<TextRequest>,
     config: web::Data<Config>,
     metrics: web::Data<Metrics>,
+    templates: web::Data<TemplateStore>,
+    model_chain: web::Data<ModelChain>,
 ) -> Result<HttpResponse> {
     let start_time = Instant::now();
     let task_type = "classify";
@@ -147,15 +186,20 @@ pub async fn classify_text(
     
     metrics.record_task_start(task_type);
 
-    let model = req.model.as_ref().unwrap_or(&config.default_model).clone();
     let max_tokens = req.max_tokens.unwrap_or(config.max_tokens);
     let temperature = req.temperature.unwrap_or(config.temperature);
 
     let client = OllamaClient::new(config.ollama_url.clone(), config.timeout_seconds);
-    let prompt = PromptTemplates::classify_property_type(&req.text);
+    let prompt = match build_prompt(&templates, CLASSIFY_PROPERTY_TYPE, &req) {
+        Ok(prompt) => prompt,
+        Err(response) => {
+            metrics.record_error(task_type, "template_error");
+            return Ok(response);
+        }
+    };
 
-    match client.generate_text(&model, &prompt, max_tokens, temperature).await {
-        Ok(result) => {
+    match model_chain.generate_text(&client, req.model.as_deref(), &prompt, max_tokens, temperature, &metrics).await {
+        Ok((result, model)) => {
             let duration = start_time.elapsed();
             metrics.record_task_completion(task_type, &model, duration.as_secs_f64(), true);
 
@@ -181,10 +225,234 @@ pub async fn classify_text(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct BatchDocument {
+    pub id: String,
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchClassifyRequest {
+    pub documents: Vec<BatchDocument>,
+    pub model: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub county_id: Option<String>,
+    pub variables: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchClassification {
+    pub id: String,
+    /// Category parsed from the model output, if one was recognized
+    pub category: Option<String>,
+    pub result: Option<String>,
+    pub model_used: Option<String>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchClassifyResponse {
+    pub id: String,
+    pub results: Vec<BatchClassification>,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub processing_time_ms: u64,
+}
+
+const PROPERTY_CATEGORIES: &[&str] = &["Mixed-Use", "Residential", "Commercial", "Industrial", "Agricultural"];
+
+/// First known category mentioned in the model output
+fn parse_category(result: &str) -> Option<String> {
+    let lower = result.to_lowercase();
+    PROPERTY_CATEGORIES
+        .iter()
+        .filter_map(|category| lower.find(&category.to_lowercase()).map(|pos| (pos, *category)))
+        .min_by_key(|(pos, _)| *pos)
+        .map(|(_, category)| category.to_string())
+}
+
+/// Classify many documents in one call with bounded concurrency.
+///
+/// Results are returned in request order; a failed document does not fail
+/// the batch.
+pub async fn classify_batch(
+    req: web::Json<BatchClassifyRequest>,
+    config: web::Data<Config>,
+    metrics: web::Data<Metrics>,
+    templates: web::Data<TemplateStore>,
+    model_chain: web::Data<ModelChain>,
+) -> Result<HttpResponse> {
+    let start_time = Instant::now();
+    let task_type = "classify_batch";
+    let req = req.into_inner();
+
+    if req.documents.is_empty() || req.documents.len() > config.batch_max_documents {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("A batch must contain between 1 and {} documents", config.batch_max_documents),
+            details: Some(format!("Received {} documents", req.documents.len())),
+        }));
+    }
+
+    let max_tokens = req.max_tokens.unwrap_or(config.max_tokens);
+    let temperature = req.temperature.unwrap_or(config.temperature);
+    let client = OllamaClient::new(config.ollama_url.clone(), config.timeout_seconds);
+
+    log::info!(
+        "Classifying batch of {} documents with concurrency {}",
+        req.documents.len(),
+        config.batch_concurrency
+    );
+
+    let results: Vec<BatchClassification> = futures::stream::iter(req.documents.iter())
+        .map(|document| {
+            let client = &client;
+            let metrics = &metrics;
+            let templates = &templates;
+            let model_chain = &model_chain;
+            let req = &req;
+
+            async move {
+                metrics.record_task_start(task_type);
+                let document_start = Instant::now();
+
+                let prompt = render_prompt(
+                    templates,
+                    CLASSIFY_PROPERTY_TYPE,
+                    &document.text,
+                    req.county_id.as_deref(),
+                    req.variables.clone(),
+                );
+
+                let outcome = match prompt {
+                    Ok(prompt) => model_chain
+                        .generate_text(client, req.model.as_deref(), &prompt, max_tokens, temperature, metrics)
+                        .await,
+                    Err(e) => Err(e),
+                };
+
+                match outcome {
+                    Ok((result, model)) => {
+                        metrics.record_task_completion(task_type, &model, document_start.elapsed().as_secs_f64(), true);
+                        BatchClassification {
+                            id: document.id.clone(),
+                            category: parse_category(&result),
+                            result: Some(result),
+                            model_used: Some(model),
+                            success: true,
+                            error: None,
+                        }
+                    }
+                    Err(e) => {
+                        metrics.record_error(task_type, "ollama_error");
+                        BatchClassification {
+                            id: document.id.clone(),
+                            category: None,
+                            result: None,
+                            model_used: None,
+                            success: false,
+                            error: Some(e.to_string()),
+                        }
+                    }
+                }
+            }
+        })
+        .buffered(config.batch_concurrency.max(1))
+        .collect()
+        .await;
+
+    let succeeded = results.iter().filter(|r| r.success).count();
+    let failed = results.len() - succeeded;
+
+    log::info!("Batch classification completed: {} succeeded, {} failed", succeeded, failed);
+
+    Ok(HttpResponse::Ok().json(BatchClassifyResponse {
+        id: Uuid::new_v4().to_string(),
+        results,
+        succeeded,
+        failed,
+        processing_time_ms: start_time.elapsed().as_millis() as u64,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmbedRequest {
+    /// Texts to embed; one vector is returned per text, in order
+    pub texts: Vec<String>,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbedResponse {
+    pub id: String,
+    pub model_used: String,
+    pub dimensions: usize,
+    pub embeddings: Vec<Vec<f32>>,
+    pub processing_time_ms: u64,
+}
+
+/// Generate embeddings with the configured embedding model
+pub async fn embed_text(
+    req: web::Json<EmbedRequest>,
+    config: web::Data<Config>,
+    metrics: web::Data<Metrics>,
+) -> Result<HttpResponse> {
+    let start_time = Instant::now();
+    let task_type = "embed";
+
+    if req.texts.is_empty() || req.texts.len() > config.batch_max_documents {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Between 1 and {} texts may be embedded per request", config.batch_max_documents),
+            details: Some(format!("Received {} texts", req.texts.len())),
+        }));
+    }
+
+    metrics.record_task_start(task_type);
+
+    let model = req.model.clone().unwrap_or_else(|| config.embedding_model.clone());
+    let client = OllamaClient::new(config.ollama_url.clone(), config.timeout_seconds);
+
+    let embeddings: std::result::Result<Vec<Vec<f32>>, anyhow::Error> = futures::stream::iter(req.texts.iter())
+        .map(|text| client.embed(&model, text))
+        .buffered(config.batch_concurrency.max(1))
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect();
+
+    match embeddings {
+        Ok(embeddings) => {
+            let duration = start_time.elapsed();
+            metrics.record_task_completion(task_type, &model, duration.as_secs_f64(), true);
+
+            Ok(HttpResponse::Ok().json(EmbedResponse {
+                id: Uuid::new_v4().to_string(),
+                dimensions: embeddings.first().map(|e| e.len()).unwrap_or(0),
+                model_used: model,
+                embeddings,
+                processing_time_ms: duration.as_millis() as u64,
+            }))
+        }
+        Err(e) => {
+            metrics.record_error(task_type, "ollama_error");
+            log::error!("Embedding failed: {}", e);
+
+            Ok(HttpResponse::ServiceUnavailable().json(ErrorResponse {
+                error: "AI processing failed".to_string(),
+                details: Some(e.to_string()),
+            }))
+        }
+    }
+}
+
 pub async fn explain_data(
+    http_req: HttpRequest,
     req: web::Json<TextRequest>,
     config: web::Data<Config>,
     metrics: web::Data<Metrics>,
+    templates: web::Data<TemplateStore>,
+    model_chain: web::Data<ModelChain>,
 ) -> Result<HttpResponse> {
     let start_time = Instant::now();
     let task_type = "explain";
@@ -192,15 +460,25 @@ pub async fn explain_data(
     
     metrics.record_task_start(task_type);
 
-    let model = req.model.as_ref().unwrap_or(&config.default_model).clone();
     let max_tokens = req.max_tokens.unwrap_or(config.max_tokens);
     let temperature = req.temperature.unwrap_or(config.temperature);
 
     let client = OllamaClient::new(config.ollama_url.clone(), config.timeout_seconds);
-    let prompt = PromptTemplates::explain_assessment_data(&req.text);
+    let prompt = match build_prompt(&templates, EXPLAIN_ASSESSMENT_DATA, &req) {
+        Ok(prompt) => prompt,
+        Err(response) => {
+            metrics.record_error(task_type, "template_error");
+            return Ok(response);
+        }
+    };
+
+    if wants_stream(&http_req, &req) {
+        let requested_model = req.model.clone();
+        return stream_text(task_type, request_id, client, model_chain, requested_model, prompt, max_tokens, temperature, metrics).await;
+    }
 
-    match client.generate_text(&model, &prompt, max_tokens, temperature).await {
-        Ok(result) => {
+    match model_chain.generate_text(&client, req.model.as_deref(), &prompt, max_tokens, temperature, &metrics).await {
+        Ok((result, model)) => {
             let duration = start_time.elapsed();
             metrics.record_task_completion(task_type, &model, duration.as_secs_f64(), true);
 
@@ -226,6 +504,140 @@ pub async fn explain_data(
     }
 }
 
+/// Render the prompt for a request, returning a 400 response on template errors
+fn build_prompt(
+    templates: &TemplateStore,
+    name: &str,
+    req: &TextRequest,
+) -> std::result::Result<String, HttpResponse> {
+    render_prompt(templates, name, &req.text, req.county_id.as_deref(), req.variables.clone())
+        .map_err(|e| {
+            log::error!("Failed to render prompt {}: {}", name, e);
+            HttpResponse::BadRequest().json(ErrorResponse {
+                error: "Prompt template error".to_string(),
+                details: Some(e.to_string()),
+            })
+        })
+}
+
+fn render_prompt(
+    templates: &TemplateStore,
+    name: &str,
+    text: &str,
+    county_id: Option<&str>,
+    variables: Option<serde_json::Map<String, serde_json::Value>>,
+) -> anyhow::Result<String> {
+    let mut variables = variables.unwrap_or_default();
+    variables.insert("text".to_string(), serde_json::Value::String(text.to_string()));
+    if let Some(county_id) = county_id {
+        variables.insert("county_id".to_string(), serde_json::Value::String(county_id.to_string()));
+    }
+
+    templates.render(name, county_id, &serde_json::Value::Object(variables))
+}
+
+/// Streaming is opt-in via the request body or the Accept header
+fn wants_stream(http_req: &HttpRequest, req: &TextRequest) -> bool {
+    req.stream.unwrap_or_else(|| {
+        http_req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.contains("text/event-stream"))
+            .unwrap_or(false)
+    })
+}
+
+fn sse_event(event: &str, data: &serde_json::Value) -> web::Bytes {
+    web::Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
+/// Forward Ollama's token stream as Server-Sent Events.
+///
+/// Emits `token` events as text arrives, then a final `done` event. If
+/// Ollama cannot start a stream, the complete response is generated and
+/// sent as a single `token` event so clients handle both paths the same way.
+#[allow(clippy::too_many_arguments)]
+async fn stream_text(
+    task_type: &'static str,
+    request_id: String,
+    client: OllamaClient,
+    model_chain: web::Data<ModelChain>,
+    requested_model: Option<String>,
+    prompt: String,
+    max_tokens: u32,
+    temperature: f32,
+    metrics: web::Data<Metrics>,
+) -> Result<HttpResponse> {
+    let start_time = Instant::now();
+
+    let requested_model = requested_model.as_deref();
+    let (tokens, model): (TokenStream, String) = match model_chain
+        .generate_stream(&client, requested_model, &prompt, max_tokens, temperature, &metrics)
+        .await
+    {
+        Ok(stream) => stream,
+        Err(e) => {
+            log::warn!("Streaming {} unavailable, falling back to a complete response: {}", task_type, e);
+
+            match model_chain.generate_text(&client, requested_model, &prompt, max_tokens, temperature, &metrics).await {
+                Ok((result, model)) => (Box::pin(futures::stream::once(async move { Ok(result) })), model),
+                Err(e) => {
+                    metrics.record_error(task_type, "ollama_error");
+                    log::error!("{} failed: {}", task_type, e);
+
+                    return Ok(HttpResponse::ServiceUnavailable().json(ErrorResponse {
+                        error: "AI processing failed".to_string(),
+                        details: Some(e.to_string()),
+                    }));
+                }
+            }
+        }
+    };
+
+    let failed = Arc::new(AtomicBool::new(false));
+    let stream_failed = failed.clone();
+
+    let token_events = tokens.map(move |token| match token {
+        Ok(token) => sse_event("token", &serde_json::json!({ "token": token })),
+        Err(e) => {
+            stream_failed.store(true, Ordering::SeqCst);
+            log::error!("Streaming {} failed: {}", task_type, e);
+            sse_event("error", &serde_json::json!({
+                "error": "AI processing failed",
+                "details": e.to_string()
+            }))
+        }
+    });
+
+    let done_event = futures::stream::once(async move {
+        let duration = start_time.elapsed();
+        let success = !failed.load(Ordering::SeqCst);
+
+        if success {
+            metrics.record_task_completion(task_type, &model, duration.as_secs_f64(), true);
+        } else {
+            metrics.record_error(task_type, "ollama_stream_error");
+        }
+
+        sse_event("done", &serde_json::json!({
+            "id": request_id,
+            "model_used": model,
+            "processing_time_ms": duration.as_millis() as u64,
+            "success": success
+        }))
+    });
+
+    let body = token_events
+        .chain(done_event)
+        .map(Ok::<_, actix_web::Error>);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(body))
+}
+
 pub async fn get_metrics(metrics: web::Data<Metrics>) -> Result<HttpResponse> {
     use prometheus::Encoder;
     
@@ -239,4 +651,201 @@ pub async fn get_metrics(metrics: web::Data<Metrics>) -> Result<HttpResponse> {
     Ok(HttpResponse::Ok()
         .content_type("text/plain; version=0.0.4; charset=utf-8")
         .body(metrics_text))
-}
\ No newline at end of file
+}
+#[derive(Debug, Deserialize)]
+pub struct TemplateScope {
+    pub county_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RollbackRequest {
+    pub version: u32,
+    pub county_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewRequest {
+    /// Unsaved template body; defaults to the active template
+    pub body: Option<String>,
+    pub county_id: Option<String>,
+    pub variables: serde_json::Value,
+}
+
+fn template_error(e: anyhow::Error) -> HttpResponse {
+    HttpResponse::BadRequest().json(ErrorResponse {
+        error: "Prompt template error".to_string(),
+        details: Some(e.to_string()),
+    })
+}
+
+pub async fn list_templates(templates: web::Data<TemplateStore>) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "templates": templates.list()
+    })))
+}
+
+pub async fn get_template(
+    path: web::Path<String>,
+    scope: web::Query<TemplateScope>,
+    templates: web::Data<TemplateStore>,
+) -> Result<HttpResponse> {
+    match templates.get(&path, scope.county_id.as_deref()) {
+        Some(template) => Ok(HttpResponse::Ok().json(template)),
+        None => Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("Prompt template {} not found", path),
+            details: None,
+        })),
+    }
+}
+
+/// Create or update a template; every save adds a new version
+pub async fn save_template(
+    path: web::Path<String>,
+    req: web::Json<TemplateInput>,
+    templates: web::Data<TemplateStore>,
+) -> Result<HttpResponse> {
+    match templates.save(&path, req.into_inner()) {
+        Ok(template) => {
+            log::info!("Saved prompt template {} version {}", template.name, template.version);
+            Ok(HttpResponse::Ok().json(template))
+        }
+        Err(e) => Ok(template_error(e)),
+    }
+}
+
+pub async fn delete_template(
+    path: web::Path<String>,
+    scope: web::Query<TemplateScope>,
+    templates: web::Data<TemplateStore>,
+) -> Result<HttpResponse> {
+    match templates.delete(&path, scope.county_id.as_deref()) {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("Prompt template {} has no stored versions", path),
+            details: None,
+        })),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to delete prompt template".to_string(),
+            details: Some(e.to_string()),
+        })),
+    }
+}
+
+pub async fn list_template_versions(
+    path: web::Path<String>,
+    scope: web::Query<TemplateScope>,
+    templates: web::Data<TemplateStore>,
+) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "versions": templates.versions(&path, scope.county_id.as_deref())
+    })))
+}
+
+pub async fn rollback_template(
+    path: web::Path<String>,
+    req: web::Json<RollbackRequest>,
+    templates: web::Data<TemplateStore>,
+) -> Result<HttpResponse> {
+    match templates.rollback(&path, req.county_id.as_deref(), req.version) {
+        Ok(template) => Ok(HttpResponse::Ok().json(template)),
+        Err(e) => Ok(template_error(e)),
+    }
+}
+
+pub async fn preview_template(
+    path: web::Path<String>,
+    req: web::Json<PreviewRequest>,
+    templates: web::Data<TemplateStore>,
+) -> Result<HttpResponse> {
+    let rendered = match &req.body {
+        Some(body) => templates.render_body(body, &req.variables),
+        None => templates.render(&path, req.county_id.as_deref(), &req.variables),
+    };
+
+    match rendered {
+        Ok(prompt) => Ok(HttpResponse::Ok().json(serde_json::json!({ "prompt": prompt }))),
+        Err(e) => Ok(template_error(e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullModelRequest {
+    pub name: String,
+}
+
+/// Installed models plus the configured fallback chain
+pub async fn list_models(
+    config: web::Data<Config>,
+    model_chain: web::Data<ModelChain>,
+) -> Result<HttpResponse> {
+    let client = OllamaClient::new(config.ollama_url.clone(), config.timeout_seconds);
+
+    match client.list_models().await {
+        Ok(installed) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "installed": installed,
+            "chain": model_chain.status()
+        }))),
+        Err(e) => Ok(HttpResponse::ServiceUnavailable().json(ErrorResponse {
+            error: "Failed to list models".to_string(),
+            details: Some(e.to_string()),
+        })),
+    }
+}
+
+pub async fn pull_model(
+    req: web::Json<PullModelRequest>,
+    config: web::Data<Config>,
+    model_chain: web::Data<ModelChain>,
+) -> Result<HttpResponse> {
+    let client = OllamaClient::new(config.ollama_url.clone(), config.timeout_seconds);
+
+    log::info!("Pulling model {}", req.name);
+
+    match client.pull_model(&req.name).await {
+        Ok(()) => {
+            model_chain.mark_healthy(&req.name);
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "name": req.name,
+                "status": "pulled"
+            })))
+        }
+        Err(e) => Ok(HttpResponse::BadGateway().json(ErrorResponse {
+            error: "Failed to pull model".to_string(),
+            details: Some(e.to_string()),
+        })),
+    }
+}
+
+pub async fn delete_model(
+    path: web::Path<String>,
+    config: web::Data<Config>,
+    model_chain: web::Data<ModelChain>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    if model_chain.status().iter().any(|status| status.name == name) {
+        return Ok(HttpResponse::Conflict().json(ErrorResponse {
+            error: format!("Model {} is part of the fallback chain", name),
+            details: Some("Remove it from AI_MODEL_NAME / AI_FALLBACK_MODELS first".to_string()),
+        }));
+    }
+
+    let client = OllamaClient::new(config.ollama_url.clone(), config.timeout_seconds);
+
+    match client.delete_model(&name).await {
+        Ok(()) => {
+            log::info!("Deleted model {}", name);
+            Ok(HttpResponse::NoContent().finish())
+        }
+        Err(e) => Ok(HttpResponse::BadGateway().json(ErrorResponse {
+            error: "Failed to delete model".to_string(),
+            details: Some(e.to_string()),
+        })),
+    }
+}
+
+pub async fn model_chain_status(model_chain: web::Data<ModelChain>) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "chain": model_chain.status()
+    })))
+}
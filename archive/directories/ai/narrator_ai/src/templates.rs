@@ -0,0 +1,255 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use handlebars::Handlebars;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+pub const SUMMARIZE_PROPERTY: &str = "summarize_property";
+pub const SUMMARIZE_SYNC_OPERATION: &str = "summarize_sync_operation";
+pub const CLASSIFY_PROPERTY_TYPE: &str = "classify_property_type";
+pub const EXPLAIN_ASSESSMENT_DATA: &str = "explain_assessment_data";
+
+/// Built-in prompts, used until an admin stores an override
+const DEFAULT_TEMPLATES: &[(&str, &str)] = &[
+    (
+        SUMMARIZE_PROPERTY,
+        "Summarize this property assessment data in clear, professional language suitable for county staff. \
+        Focus on key details like property type, location, and important characteristics. \
+        Keep the summary concise but informative:\n\n{{text}}\n\nSummary:",
+    ),
+    (
+        SUMMARIZE_SYNC_OPERATION,
+        "Write a short plain-English summary of this data synchronization run for county staff. \
+        State how many records were created, updated or deleted, how many failed and the most common failure reasons. \
+        Use whole numbers with thousands separators and do not speculate beyond the data given:\n\n{{text}}\n\nSummary:",
+    ),
+    (
+        CLASSIFY_PROPERTY_TYPE,
+        "Classify this property into one of these categories: Residential, Commercial, Industrial, Agricultural, or Mixed-Use. \
+        Provide the classification and a brief explanation:\n\n{{text}}\n\nClassification:",
+    ),
+    (
+        EXPLAIN_ASSESSMENT_DATA,
+        "Explain this property assessment data in simple terms that a property owner or county resident could understand. \
+        Focus on what the data means and why it matters:\n\n{{text}}\n\nExplanation:",
+    ),
+];
+
+/// One version of a prompt template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    /// County override; `None` applies to every county
+    pub county_id: Option<String>,
+    pub version: u32,
+    pub body: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+}
+
+/// Fields supplied when creating a new template version
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateInput {
+    pub body: String,
+    pub county_id: Option<String>,
+    pub description: Option<String>,
+    pub created_by: Option<String>,
+}
+
+/// Versioned prompt templates with optional per-county overrides.
+///
+/// Every update appends a version; the latest version is active. The store
+/// is persisted as JSON so edits survive restarts.
+pub struct TemplateStore {
+    path: Option<PathBuf>,
+    templates: RwLock<HashMap<String, Vec<PromptTemplate>>>,
+    renderer: Handlebars<'static>,
+}
+
+impl TemplateStore {
+    /// Load the store from `path`, starting empty if the file does not exist
+    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let templates = match &path {
+            Some(path) if path.exists() => {
+                let content = fs::read_to_string(path)
+                    .map_err(|e| anyhow!("Failed to read prompt templates: {}", e))?;
+                serde_json::from_str(&content)
+                    .map_err(|e| anyhow!("Failed to parse prompt templates: {}", e))?
+            }
+            _ => HashMap::new(),
+        };
+
+        let mut renderer = Handlebars::new();
+        // Prompts are plain text, not HTML
+        renderer.register_escape_fn(handlebars::no_escape);
+
+        Ok(Self {
+            path,
+            templates: RwLock::new(templates),
+            renderer,
+        })
+    }
+
+    /// Active template for a name, preferring the county override
+    pub fn get(&self, name: &str, county_id: Option<&str>) -> Option<PromptTemplate> {
+        let templates = self.templates.read().expect("template store lock poisoned");
+
+        county_id
+            .and_then(|county| templates.get(&key(name, Some(county))))
+            .or_else(|| templates.get(&key(name, None)))
+            .and_then(|versions| versions.last().cloned())
+            .or_else(|| default_template(name))
+    }
+
+    /// Active version of every stored template, plus untouched defaults
+    pub fn list(&self) -> Vec<PromptTemplate> {
+        let templates = self.templates.read().expect("template store lock poisoned");
+
+        let mut active: Vec<PromptTemplate> = templates
+            .values()
+            .filter_map(|versions| versions.last().cloned())
+            .collect();
+
+        for (name, _) in DEFAULT_TEMPLATES {
+            if !templates.contains_key(&key(name, None)) {
+                active.extend(default_template(name));
+            }
+        }
+
+        active.sort_by(|a, b| (&a.name, &a.county_id).cmp(&(&b.name, &b.county_id)));
+        active
+    }
+
+    /// All stored versions of a template, oldest first
+    pub fn versions(&self, name: &str, county_id: Option<&str>) -> Vec<PromptTemplate> {
+        let templates = self.templates.read().expect("template store lock poisoned");
+        templates.get(&key(name, county_id)).cloned().unwrap_or_default()
+    }
+
+    /// Store a new version of a template and make it active
+    pub fn save(&self, name: &str, input: TemplateInput) -> Result<PromptTemplate> {
+        if name.trim().is_empty() {
+            return Err(anyhow!("Template name cannot be empty"));
+        }
+        self.validate(&input.body)?;
+
+        let template = {
+            let mut templates = self.templates.write().expect("template store lock poisoned");
+            let versions = templates.entry(key(name, input.county_id.as_deref())).or_default();
+
+            let template = PromptTemplate {
+                name: name.to_string(),
+                county_id: input.county_id,
+                version: versions.last().map(|t| t.version + 1).unwrap_or(1),
+                body: input.body,
+                description: input.description,
+                created_at: Utc::now(),
+                created_by: input.created_by,
+            };
+            versions.push(template.clone());
+            template
+        };
+
+        self.persist()?;
+        Ok(template)
+    }
+
+    /// Re-publish an earlier version as the newest version
+    pub fn rollback(&self, name: &str, county_id: Option<&str>, version: u32) -> Result<PromptTemplate> {
+        let previous = self
+            .versions(name, county_id)
+            .into_iter()
+            .find(|t| t.version == version)
+            .ok_or_else(|| anyhow!("Version {} of template {} not found", version, name))?;
+
+        self.save(name, TemplateInput {
+            body: previous.body,
+            county_id: previous.county_id,
+            description: Some(format!("Rollback to version {}", version)),
+            created_by: None,
+        })
+    }
+
+    /// Remove all versions of a template, reverting to the built-in default
+    pub fn delete(&self, name: &str, county_id: Option<&str>) -> Result<bool> {
+        let removed = {
+            let mut templates = self.templates.write().expect("template store lock poisoned");
+            templates.remove(&key(name, county_id)).is_some()
+        };
+
+        if removed {
+            self.persist()?;
+        }
+        Ok(removed)
+    }
+
+    /// Render the active template for `name` with the given variables
+    pub fn render(&self, name: &str, county_id: Option<&str>, variables: &serde_json::Value) -> Result<String> {
+        let template = self
+            .get(name, county_id)
+            .ok_or_else(|| anyhow!("Prompt template {} not found", name))?;
+
+        self.render_body(&template.body, variables)
+    }
+
+    /// Render an arbitrary template body, e.g. to preview an edit
+    pub fn render_body(&self, body: &str, variables: &serde_json::Value) -> Result<String> {
+        self.renderer
+            .render_template(body, variables)
+            .map_err(|e| anyhow!("Failed to render prompt template: {}", e))
+    }
+
+    fn validate(&self, body: &str) -> Result<()> {
+        handlebars::Template::compile(body)
+            .map(|_| ())
+            .map_err(|e| anyhow!("Invalid template syntax: {}", e))
+    }
+
+    fn persist(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let content = {
+            let templates = self.templates.read().expect("template store lock poisoned");
+            serde_json::to_string_pretty(&*templates)?
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write then rename so a crash never leaves a truncated file
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, path)?;
+
+        Ok(())
+    }
+}
+
+fn key(name: &str, county_id: Option<&str>) -> String {
+    match county_id {
+        Some(county) => format!("{}@{}", name, county),
+        None => name.to_string(),
+    }
+}
+
+fn default_template(name: &str) -> Option<PromptTemplate> {
+    DEFAULT_TEMPLATES
+        .iter()
+        .find(|(default_name, _)| *default_name == name)
+        .map(|(name, body)| PromptTemplate {
+            name: name.to_string(),
+            county_id: None,
+            version: 0,
+            body: body.to_string(),
+            description: Some("Built-in default".to_string()),
+            created_at: DateTime::<Utc>::MIN_UTC,
+            created_by: None,
+        })
+}
@@ -0,0 +1,51 @@
+## Navigation
+
+nav-brand = TerraFusion Platform
+nav-sign-out = Sign out
+nav-dashboard = Dashboard
+nav-sync-dashboard = Sync Dashboard
+nav-gis-export = GIS Export
+nav-administration = Administration
+nav-users = Users
+nav-counties = Counties
+nav-settings = Settings
+nav-language = Language
+nav-main = Main navigation
+nav-skip-to-content = Skip to main content
+nav-theme = Color theme
+nav-theme-auto = Match system
+nav-theme-light = Light
+nav-theme-dark = Dark
+nav-toggle = Toggle navigation
+
+## Login
+
+login-subtitle = Sign in to your account
+login-username = Username
+login-password = Password
+login-submit = Sign in
+login-error-invalid = The username or password is incorrect.
+login-error-unavailable = Sign-in is temporarily unavailable. Please try again.
+login-signed-out = You have been signed out.
+
+## Maintenance
+
+maintenance-title = Scheduled maintenance
+maintenance-unavailable = TerraFusion is unavailable while maintenance is in progress.
+maintenance-unavailable-county = TerraFusion is unavailable for { $county } while maintenance is in progress.
+maintenance-back-by = Expected to be back by
+maintenance-no-support = This page will work again as soon as maintenance ends; there is no need to contact support.
+
+## API errors
+
+error-authentication = Please sign in to continue.
+error-authorization = You do not have permission to do this.
+error-bad-request = The request could not be understood.
+error-database = The request could not be completed because of a database problem.
+error-internal = Something went wrong on our side. Please try again.
+error-not-found = The requested item was not found.
+error-service-unavailable = The service is temporarily unavailable. Please try again later.
+error-template = The page could not be displayed.
+error-validation = Some of the information provided is not valid.
+error-external-service = A connected service did not respond correctly. Please try again.
+error-payload-too-large = The request is larger than allowed.
@@ -0,0 +1,51 @@
+## Navegación
+
+nav-brand = Plataforma TerraFusion
+nav-sign-out = Cerrar sesión
+nav-dashboard = Panel
+nav-sync-dashboard = Panel de sincronización
+nav-gis-export = Exportación SIG
+nav-administration = Administración
+nav-users = Usuarios
+nav-counties = Condados
+nav-settings = Configuración
+nav-language = Idioma
+nav-main = Navegación principal
+nav-skip-to-content = Saltar al contenido principal
+nav-theme = Tema de color
+nav-theme-auto = Según el sistema
+nav-theme-light = Claro
+nav-theme-dark = Oscuro
+nav-toggle = Mostrar u ocultar la navegación
+
+## Inicio de sesión
+
+login-subtitle = Inicie sesión en su cuenta
+login-username = Usuario
+login-password = Contraseña
+login-submit = Iniciar sesión
+login-error-invalid = El usuario o la contraseña no son correctos.
+login-error-unavailable = El inicio de sesión no está disponible temporalmente. Inténtelo de nuevo.
+login-signed-out = Ha cerrado la sesión.
+
+## Mantenimiento
+
+maintenance-title = Mantenimiento programado
+maintenance-unavailable = TerraFusion no está disponible mientras se realiza el mantenimiento.
+maintenance-unavailable-county = TerraFusion no está disponible para { $county } mientras se realiza el mantenimiento.
+maintenance-back-by = Se espera que vuelva a estar disponible a las
+maintenance-no-support = Esta página volverá a funcionar en cuanto termine el mantenimiento; no es necesario contactar con soporte.
+
+## Errores de la API
+
+error-authentication = Inicie sesión para continuar.
+error-authorization = No tiene permiso para realizar esta acción.
+error-bad-request = No se pudo interpretar la solicitud.
+error-database = No se pudo completar la solicitud debido a un problema con la base de datos.
+error-internal = Se produjo un error en nuestro sistema. Vuelva a intentarlo.
+error-not-found = No se encontró el elemento solicitado.
+error-service-unavailable = El servicio no está disponible temporalmente. Vuelva a intentarlo más tarde.
+error-template = No se pudo mostrar la página.
+error-validation = Parte de la información proporcionada no es válida.
+error-external-service = Un servicio conectado no respondió correctamente. Vuelva a intentarlo.
+error-payload-too-large = La solicitud supera el tamaño permitido.
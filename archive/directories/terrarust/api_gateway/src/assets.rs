@@ -0,0 +1,144 @@
+//! Templates and static files compiled into the gateway binary.
+//!
+//! Static files are served under content-hashed names such as
+//! `/static/css/terrafusion.3f2a9c1e.css`, cached for a year; templates link
+//! to them with `{{asset "css/terrafusion.css"}}`. A changed file gets a new
+//! name, so browsers never keep a stale copy. Debug builds read both
+//! directories from the crate on disk, so edits show up without a rebuild.
+
+use std::collections::HashMap;
+
+use actix_web::{get, http::header, web, HttpRequest, HttpResponse};
+use handlebars::{handlebars_helper, Handlebars};
+use lazy_static::lazy_static;
+use rust_embed::RustEmbed;
+
+/// Cache lifetime for hashed URLs, whose content never changes
+const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Hex digits of the content hash kept in file names
+const HASH_LENGTH: usize = 8;
+
+#[derive(RustEmbed)]
+#[folder = "static/"]
+struct StaticAssets;
+
+#[derive(RustEmbed)]
+#[folder = "templates/"]
+struct Templates;
+
+/// Mapping between the embedded files and their hashed names
+struct AssetManifest {
+    /// `css/terrafusion.css` -> `css/terrafusion.3f2a9c1e.css`
+    hashed: HashMap<String, String>,
+    /// The reverse, for resolving requests
+    logical: HashMap<String, String>,
+}
+
+impl AssetManifest {
+    fn build() -> Self {
+        let mut hashed = HashMap::new();
+        let mut logical = HashMap::new();
+        for path in StaticAssets::iter() {
+            if let Some(file) = StaticAssets::get(&path) {
+                let name = hashed_name(&path, &hex(&file.metadata.sha256_hash()));
+                logical.insert(name.clone(), path.to_string());
+                hashed.insert(path.to_string(), name);
+            }
+        }
+        Self { hashed, logical }
+    }
+}
+
+lazy_static! {
+    static ref MANIFEST: AssetManifest = AssetManifest::build();
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Insert the first `HASH_LENGTH` hash digits before the file extension
+fn hashed_name(path: &str, hash: &str) -> String {
+    let hash = &hash[..HASH_LENGTH.min(hash.len())];
+    let file_start = path.rfind('/').map(|i| i + 1).unwrap_or(0);
+    match path[file_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let dot = file_start + dot;
+            format!("{}.{}{}", &path[..dot], hash, &path[dot..])
+        }
+        _ => format!("{}.{}", path, hash),
+    }
+}
+
+/// URL for a static file. Files that are not embedded keep their plain
+/// path, so a missing asset 404s instead of breaking template rendering.
+pub fn asset_url(path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    match MANIFEST.hashed.get(path) {
+        Some(name) => format!("/static/{}", name),
+        None => format!("/static/{}", path),
+    }
+}
+
+handlebars_helper!(asset_helper: |path: str| asset_url(path));
+
+/// Handlebars with the embedded templates and the `asset` helper registered.
+///
+/// Templates are named after their file without `.hbs`, as before.
+pub fn handlebars() -> Result<Handlebars<'static>, handlebars::TemplateError> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_helper("asset", Box::new(asset_helper));
+
+    for path in Templates::iter() {
+        let Some(name) = path.strip_suffix(".hbs") else { continue };
+        let Some(file) = Templates::get(&path) else { continue };
+        handlebars.register_template_string(name, String::from_utf8_lossy(&file.data))?;
+    }
+
+    Ok(handlebars)
+}
+
+/// Configure static file routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(static_file);
+}
+
+/// Serve an embedded static file.
+///
+/// Hashed names are cached for a year. Plain names still work for links
+/// outside the templates, but must be revalidated against the ETag.
+#[get("/static/{path:.*}")]
+async fn static_file(req: HttpRequest, path: web::Path<String>) -> HttpResponse {
+    let requested = path.into_inner();
+    let (logical, immutable) = match MANIFEST.logical.get(&requested) {
+        Some(logical) => (logical.as_str(), true),
+        None => (requested.as_str(), false),
+    };
+
+    let Some(file) = StaticAssets::get(logical) else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    let etag = format!("\"{}\"", hex(&file.metadata.sha256_hash()));
+    let cache_control = if immutable { IMMUTABLE_CACHE_CONTROL } else { "no-cache" };
+    let not_modified = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(',').any(|tag| tag.trim() == etag))
+        .unwrap_or(false);
+
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .insert_header((header::CACHE_CONTROL, cache_control))
+            .finish();
+    }
+
+    HttpResponse::Ok()
+        .content_type(mime_guess::from_path(logical).first_or_octet_stream().as_ref())
+        .insert_header((header::ETAG, etag))
+        .insert_header((header::CACHE_CONTROL, cache_control))
+        .body(file.data.into_owned())
+}
@@ -1,5 +1,7 @@
 use std::env;
 use std::time::Duration;
+use actix_web::cookie::{Cookie, CookieBuilder, SameSite};
+use terrafusion_common::compression::CompressionSettings;
 
 /// Configuration for the API Gateway application
 #[derive(Debug, Clone)]
@@ -30,6 +32,18 @@ pub struct AppConfig {
     pub session_secret: String,
     pub session_expiry: Duration,
     pub cookie_secure: bool,
+    pub cookie_same_site: SameSite,
+    
+    // Validation configuration
+    pub validate_responses: bool,
+    
+    // Request body limits
+    pub max_json_body_bytes: usize,
+    pub max_geometry_body_bytes: usize,
+    pub max_upload_bytes: usize,
+    
+    // Response compression
+    pub compression: CompressionSettings,
     
     // Logging configuration
     pub log_format: String,
@@ -65,7 +79,8 @@ impl AppConfig {
         let ssl_cert_file = env::var("SSL_CERT_FILE").unwrap_or_else(|_| "certs/server.crt".to_string());
         let ssl_key_file = env::var("SSL_KEY_FILE").unwrap_or_else(|_| "certs/server.key".to_string());
         
-        let jwt_secret = env::var("JWT_SECRET").expect("JWT_SECRET is required");
+        // Resolved through the configured secrets provider at startup
+        let jwt_secret = env::var("JWT_SECRET").unwrap_or_default();
         let jwt_expiry_hours = env::var("JWT_EXPIRY_HOURS")
             .unwrap_or_else(|_| "24".to_string())
             .parse::<u64>()
@@ -103,6 +118,46 @@ impl AppConfig {
             .parse::<bool>()
             .expect("COOKIE_SECURE must be true or false");
         
+        let cookie_same_site = match env::var("COOKIE_SAME_SITE")
+            .unwrap_or_else(|_| "lax".to_string())
+            .to_lowercase()
+            .as_str()
+        {
+            "strict" => SameSite::Strict,
+            "lax" => SameSite::Lax,
+            "none" => SameSite::None,
+            other => panic!("COOKIE_SAME_SITE must be strict, lax or none, got {}", other),
+        };
+        // Browsers drop SameSite=None cookies that are not also Secure
+        if cookie_same_site == SameSite::None && !cookie_secure {
+            panic!("COOKIE_SAME_SITE=none requires COOKIE_SECURE=true");
+        }
+        
+        // Log responses that do not match their schema; off in production by default
+        let validate_responses = env::var("VALIDATE_RESPONSES")
+            .unwrap_or_else(|_| (environment == "development").to_string())
+            .parse::<bool>()
+            .expect("VALIDATE_RESPONSES must be true or false");
+        
+        // Ordinary JSON bodies are small; bodies carrying geometries (export
+        // AOIs, county boundaries) get more room, and file uploads are streamed
+        // through to the service, so their limit bounds transfer, not memory
+        let max_json_body_bytes = env::var("MAX_JSON_BODY_BYTES")
+            .unwrap_or_else(|_| (2 * 1024 * 1024).to_string())
+            .parse::<usize>()
+            .expect("MAX_JSON_BODY_BYTES must be a valid integer");
+        let max_geometry_body_bytes = env::var("MAX_GEOMETRY_BODY_BYTES")
+            .unwrap_or_else(|_| (32 * 1024 * 1024).to_string())
+            .parse::<usize>()
+            .expect("MAX_GEOMETRY_BODY_BYTES must be a valid integer");
+        let max_upload_bytes = env::var("MAX_UPLOAD_BYTES")
+            .unwrap_or_else(|_| (512 * 1024 * 1024).to_string())
+            .parse::<usize>()
+            .expect("MAX_UPLOAD_BYTES must be a valid integer");
+        
+        // JSON, GeoJSON and HTML responses are compressed for county WAN links
+        let compression = CompressionSettings::from_env().expect("Invalid response compression settings");
+        
         // Logging configuration
         let log_format = env::var("LOG_FORMAT").unwrap_or_else(|_| "json".to_string());
         let log_level = env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
@@ -136,6 +191,12 @@ impl AppConfig {
             session_secret,
             session_expiry: Duration::from_secs(session_expiry_hours * 3600),
             cookie_secure,
+            cookie_same_site,
+            validate_responses,
+            max_json_body_bytes,
+            max_geometry_body_bytes,
+            max_upload_bytes,
+            compression,
             log_format,
             log_level,
             metrics_enabled,
@@ -166,4 +227,17 @@ impl AppConfig {
             Some(self.host.clone())
         }
     }
-}
\ No newline at end of file
+    
+    /// Start a cookie with the configured `Secure`, `SameSite` and domain
+    /// attributes, valid for the whole site
+    pub fn cookie<'c>(&self, name: &'c str, value: String) -> CookieBuilder<'c> {
+        let mut builder = Cookie::build(name, value)
+            .path("/")
+            .secure(self.cookie_secure)
+            .same_site(self.cookie_same_site);
+        if let Some(domain) = self.cookie_domain() {
+            builder = builder.domain(domain);
+        }
+        builder
+    }
+}
@@ -1,9 +1,11 @@
-use actix_web::{HttpResponse, ResponseError};
+use actix_web::error::JsonPayloadError;
+use actix_web::{web, HttpResponse, ResponseError};
 use thiserror::Error;
 use handlebars::RenderError;
 use serde_json::{json, Value};
 use std::fmt;
 use actix_http::StatusCode;
+use crate::i18n::Locale;
 
 /// Main application error type
 #[derive(Error, Debug)]
@@ -37,24 +39,25 @@ pub enum AppError {
     
     #[error("External service error: {0}")]
     ExternalService(String),
+    
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+    
+    #[error("Request body failed validation ({} field errors)", .0.len())]
+    InvalidFields(Vec<FieldError>),
+}
+
+/// One problem found while validating a request body against its schema
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FieldError {
+    /// JSON pointer to the offending value, e.g. `/layers/0`; empty for the body itself
+    pub field: String,
+    pub message: String,
 }
 
 impl ResponseError for AppError {
     fn error_response(&self) -> HttpResponse {
-        let status_code = self.status_code();
-        
-        // Create common error response structure
-        let error_response = json!({
-            "error": {
-                "code": status_code.as_u16(),
-                "message": self.to_string(),
-                "type": self.error_type(),
-            }
-        });
-        
-        HttpResponse::build(status_code)
-            .content_type("application/json")
-            .json(error_response)
+        self.localized_response(&Locale::default(), None)
     }
     
     fn status_code(&self) -> StatusCode {
@@ -69,6 +72,8 @@ impl ResponseError for AppError {
             AppError::TemplateError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Validation(_) => StatusCode::BAD_REQUEST,
             AppError::ExternalService(_) => StatusCode::BAD_GATEWAY,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::InvalidFields(_) => StatusCode::UNPROCESSABLE_ENTITY,
         }
     }
 }
@@ -87,9 +92,55 @@ impl AppError {
             AppError::TemplateError(_) => "template_error",
             AppError::Validation(_) => "validation_error",
             AppError::ExternalService(_) => "external_service_error",
+            AppError::PayloadTooLarge(_) => "payload_too_large",
+            AppError::InvalidFields(_) => "validation_error",
+        }
+    }
+    
+    /// Fluent message ID of the user-facing summary for this error
+    pub fn message_id(&self) -> &'static str {
+        match self {
+            AppError::Authentication(_) => "error-authentication",
+            AppError::Authorization(_) => "error-authorization",
+            AppError::BadRequest(_) => "error-bad-request",
+            AppError::Database(_) => "error-database",
+            AppError::InternalServerError(_) => "error-internal",
+            AppError::NotFound(_) => "error-not-found",
+            AppError::ServiceUnavailable(_) => "error-service-unavailable",
+            AppError::TemplateError(_) => "error-template",
+            AppError::Validation(_) => "error-validation",
+            AppError::ExternalService(_) => "error-external-service",
+            AppError::PayloadTooLarge(_) => "error-payload-too-large",
+            AppError::InvalidFields(_) => "error-validation",
         }
     }
     
+    /// JSON error response in the shared envelope. `message` keeps the
+    /// English detail for logs and support; `localized_message` is a summary
+    /// in the caller's language.
+    pub fn localized_response(&self, locale: &Locale, correlation_id: Option<&str>) -> HttpResponse {
+        let status_code = self.status_code();
+        
+        let mut error_response = json!({
+            "error": {
+                "code": status_code.as_u16(),
+                "type": self.error_type(),
+                "message": self.to_string(),
+                "localized_message": locale.tr(self.message_id()),
+            }
+        });
+        if let AppError::InvalidFields(fields) = self {
+            error_response["error"]["details"] = json!({ "fields": fields });
+        }
+        if let Some(correlation_id) = correlation_id {
+            error_response["error"]["correlation_id"] = json!(correlation_id);
+        }
+        
+        HttpResponse::build(status_code)
+            .content_type("application/json")
+            .json(error_response)
+    }
+    
     /// Create an error response for HTML templates
     pub fn to_html_response(&self) -> HttpResponse {
         let status = self.status_code();
@@ -130,6 +181,20 @@ impl AppError {
     }
 }
 
+/// JSON extractor settings with the gateway's error envelope; a body over
+/// `limit` bytes is answered with 413
+pub fn json_config(limit: usize) -> web::JsonConfig {
+    web::JsonConfig::default().limit(limit).error_handler(|err, _req| {
+        log::error!("JSON parsing error: {:?}", err);
+        match err {
+            JsonPayloadError::Overflow { limit } | JsonPayloadError::OverflowKnownLength { limit, .. } => {
+                AppError::PayloadTooLarge(format!("JSON body exceeds {} bytes", limit)).into()
+            }
+            err => AppError::BadRequest(err.to_string()).into(),
+        }
+    })
+}
+
 /// Result type alias with AppError
 pub type AppResult<T> = Result<T, AppError>;
 
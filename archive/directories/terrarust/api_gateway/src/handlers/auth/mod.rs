@@ -3,6 +3,7 @@ use actix_web::{web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use crate::AppState;
 use crate::middleware::auth::{generate_token, Claims};
+use crate::middlewares::csrf::CsrfToken;
 
 /// Login form data
 #[derive(Debug, Deserialize)]
@@ -16,6 +17,7 @@ pub struct LoginForm {
 struct LoginPageData {
     title: String,
     error_message: Option<String>,
+    csrf_token: String,
 }
 
 /// Current user information
@@ -30,6 +32,7 @@ pub struct CurrentUser {
 pub async fn login_page(
     query: web::Query<Option<LoginQuery>>,
     hb: web::Data<handlebars::Handlebars<'_>>,
+    csrf: CsrfToken,
 ) -> impl Responder {
     let error_message = query
         .as_ref()
@@ -38,6 +41,7 @@ pub async fn login_page(
     let data = LoginPageData {
         title: "TerraFusion Platform - Login".to_string(),
         error_message,
+        csrf_token: csrf.0,
     };
     
     let body = hb.render("login", &data).unwrap_or_else(|err| {
@@ -71,12 +71,16 @@ pub async fn dashboard(
         }
     };
     
-    // Get dashboard statistics
-    // In a real implementation, this would query the services for actual data
-    let sync_operations_count = 15;
-    let active_sync_pairs = 8;
-    let recent_exports = 12;
-    let pending_exports = 3;
+    // Counts shown as zero if the sync service cannot be reached
+    let stats = fetch_dashboard_stats(&state, &current_user).await.unwrap_or_else(|e| {
+        log::warn!("Could not load dashboard stats: {}", e);
+        serde_json::Value::Null
+    });
+    let count = |pointer: &str| stats.pointer(pointer).and_then(|v| v.as_i64()).unwrap_or(0);
+    let sync_operations_count = count("/operations/last_24h/total");
+    let active_sync_pairs = count("/active_sync_pairs");
+    let recent_exports = count("/exports/last_7d/completed");
+    let pending_exports = count("/exports/pending");
     
     // Prepare template data
     let data = DashboardData {
@@ -103,6 +107,28 @@ pub async fn dashboard(
         .body(body)
 }
 
+/// Aggregate stats from the sync service, scoped to the user's county
+async fn fetch_dashboard_stats(
+    state: &AppState,
+    user: &crate::handlers::auth::CurrentUser,
+) -> Result<serde_json::Value, reqwest::Error> {
+    use terrafusion_common::http_client;
+    use terrafusion_common::tenancy::{COUNTY_HEADER, PLATFORM_ADMIN_HEADER, ROLES_HEADER};
+    
+    let url = format!("{}/api/v1/dashboard/stats", state.config.sync_service_url);
+    http_client::shared()
+        .get(&url)
+        .header(COUNTY_HEADER, &user.county_id)
+        .header(PLATFORM_ADMIN_HEADER, (user.role == "platform_admin").to_string())
+        .header(ROLES_HEADER, &user.role)
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
 /// Sync dashboard page data
 #[derive(Debug, Serialize)]
 struct SyncDashboardData {
@@ -135,6 +161,7 @@ struct SyncOperationView {
     records_processed: Option<i32>,
     records_succeeded: Option<i32>,
     records_failed: Option<i32>,
+    narrative: Option<String>,
 }
 
 /// Sync dashboard page handler
@@ -187,6 +214,7 @@ pub async fn sync_dashboard(
             records_processed: Some(1250),
             records_succeeded: Some(1245),
             records_failed: Some(5),
+            narrative: Some("1,245 parcels updated, 5 failed due to missing geometry.".to_string()),
         },
         SyncOperationView {
             id: "op-12346".to_string(),
@@ -197,6 +225,7 @@ pub async fn sync_dashboard(
             records_processed: Some(850),
             records_succeeded: Some(850),
             records_failed: Some(0),
+            narrative: Some("850 assessment records updated with no failures.".to_string()),
         },
     ];
     
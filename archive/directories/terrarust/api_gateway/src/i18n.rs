@@ -0,0 +1,144 @@
+//! UI text and API error messages in the caller's language.
+//!
+//! Messages are Fluent files under `locales/<lang>/`, compiled into the
+//! binary. English and Spanish are supported; anything missing from a
+//! translation falls back to English.
+
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+
+use actix_web::{dev::Payload, web, FromRequest, HttpMessage, HttpRequest};
+use fluent_templates::fluent_bundle::FluentValue;
+use fluent_templates::{static_loader, FluentLoader, Loader};
+use handlebars::Handlebars;
+use terrafusion_common::tenancy::CountyContext;
+use unic_langid::{langid, LanguageIdentifier};
+
+use crate::middlewares::auth::Claims;
+
+static_loader! {
+    static LOCALES = {
+        locales: "./locales",
+        fallback_language: "en-US",
+        // Placeables go into HTML, where the bidi isolation marks show up as junk
+        customise: |bundle| bundle.set_use_isolating(false),
+    };
+}
+
+/// Cookie written by the language switcher
+pub const LOCALE_COOKIE: &str = "tf_locale";
+
+/// Locales with a full set of messages
+pub const SUPPORTED_LOCALES: &[&str] = &["en-US", "es"];
+
+/// Language for one request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale(LanguageIdentifier);
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale(langid!("en-US"))
+    }
+}
+
+impl Locale {
+    /// Match a tag such as `es-MX` or `EN` to a supported locale by language
+    pub fn parse(tag: &str) -> Option<Self> {
+        let requested: LanguageIdentifier = tag.trim().parse().ok()?;
+        SUPPORTED_LOCALES
+            .iter()
+            .filter_map(|supported| supported.parse::<LanguageIdentifier>().ok())
+            .find(|supported| supported.language == requested.language)
+            .map(Locale)
+    }
+
+    /// Best supported match for an `Accept-Language` header, by q-value
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        let mut tags: Vec<(f32, &str)> = header
+            .split(',')
+            .filter_map(|part| {
+                let mut pieces = part.split(';');
+                let tag = pieces.next()?.trim();
+                let q = pieces
+                    .find_map(|piece| piece.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                Some((q, tag))
+            })
+            .collect();
+        // Stable, so tags with equal weight keep the browser's order
+        tags.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        tags.into_iter()
+            .filter(|(q, _)| *q > 0.0)
+            .find_map(|(_, tag)| Self::parse(tag))
+    }
+
+    /// Pick the locale for a request. In order: `?lang=`, the switcher
+    /// cookie, the user's `locale` claim, the county default, and the
+    /// browser's `Accept-Language`.
+    pub fn detect(req: &HttpRequest, county_locales: &HashMap<String, String>) -> Self {
+        let query = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+            .ok()
+            .and_then(|query| query.get("lang").cloned());
+        let cookie = req.cookie(LOCALE_COOKIE).map(|cookie| cookie.value().to_string());
+        let extensions = req.extensions();
+        let user = extensions.get::<Claims>().and_then(|claims| claims.locale.clone());
+        let county = extensions
+            .get::<CountyContext>()
+            .and_then(|county| county_locales.get(&county.county_id).cloned());
+
+        [query, cookie, user, county]
+            .into_iter()
+            .flatten()
+            .find_map(|tag| Self::parse(&tag))
+            .or_else(|| {
+                req.headers()
+                    .get(actix_web::http::header::ACCEPT_LANGUAGE)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(Self::from_accept_language)
+            })
+            .unwrap_or_default()
+    }
+
+    /// BCP 47 tag, for `<html lang>` and `Content-Language`
+    pub fn code(&self) -> String {
+        self.0.to_string()
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Message text for `id`
+    pub fn tr(&self, id: &str) -> String {
+        LOCALES.lookup(&self.0, id)
+    }
+
+    /// Message text for `id` with `{ $name }` placeables filled in
+    pub fn tr_with(&self, id: &str, args: &[(&str, &str)]) -> String {
+        let args: HashMap<String, FluentValue> = args
+            .iter()
+            .map(|(name, value)| (name.to_string(), FluentValue::from(value.to_string())))
+            .collect();
+        LOCALES.lookup_with_args(&self.0, id, &args)
+    }
+}
+
+/// The locale chosen by `LocaleMiddleware`, or the default outside it
+impl FromRequest for Locale {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Ok(req.extensions().get::<Locale>().cloned().unwrap_or_default()))
+    }
+}
+
+/// Register `{{fluent "message-id"}}`, which translates into the template's `lang`
+pub fn register_helpers(handlebars: &mut Handlebars<'static>) {
+    handlebars.register_helper(
+        "fluent",
+        Box::new(FluentLoader::new(&*LOCALES).with_default_lang(langid!("en-US"))),
+    );
+}
@@ -1,14 +1,16 @@
 use actix_web::{web, App, HttpServer};
-use actix_web::middleware::{Logger, NormalizePath};
-use actix_files as fs;
+use actix_web::middleware::{Compress, Logger, NormalizePath};
 use env_logger::Env;
 use dotenv::dotenv;
 use std::env;
 use handlebars::Handlebars;
 use std::io;
 use std::sync::Arc;
-use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
+use terrafusion_common::compression::web::CompressionPolicy;
+use terrafusion_common::errors::web::ErrorEnvelopeMiddleware;
 
+mod assets;
+mod i18n;
 mod routes;
 mod handlers;
 mod middlewares;
@@ -16,6 +18,7 @@ mod models;
 mod services;
 mod config;
 mod errors;
+mod proxy;
 mod utils;
 
 #[actix_web::main]
@@ -38,13 +41,43 @@ async fn main() -> io::Result<()> {
     API Gateway - Starting up...
     ");
     
-    let config = config::AppConfig::from_env();
+    let mut config = config::AppConfig::from_env();
+    
+    // Resolve secrets from the configured provider (env, Vault, AWS, Azure, DPAPI)
+    let secrets = terrafusion_common::secrets::SecretsManager::from_env()
+        .expect("Failed to initialize secrets provider");
+    config.jwt_secret = secrets.get_value(terrafusion_common::secrets::JWT_SECRET)
+        .await
+        .expect("JWT_SECRET is required");
+    
     log::info!("Starting TerraFusion API Gateway on {}:{}", config.host, config.port);
     
-    // Register and configure Handlebars for templates
-    let mut handlebars = Handlebars::new();
-    handlebars.register_templates_directory(".hbs", "./templates").expect("Failed to register Handlebars templates");
-    handlebars.set_dev_mode(config.environment != "production");
+    // Templates are embedded in the binary, so the working directory does not matter
+    let mut handlebars = assets::handlebars().expect("Failed to register Handlebars templates");
+    i18n::register_helpers(&mut handlebars);
+    middlewares::csrf::register_helpers(&mut handlebars);
+    
+    // Load hot-reloadable runtime settings and watch the config file
+    let runtime_config = terrafusion_common::config::ReloadHandle::from_env()
+        .expect("Failed to load runtime settings");
+    runtime_config.spawn_watcher(std::time::Duration::from_secs(30));
+    
+    // Follow maintenance mode, which the sync service stores
+    let maintenance = terrafusion_common::maintenance::MaintenanceHandle::new();
+    middlewares::maintenance::spawn_status_poll(
+        maintenance.clone(),
+        config.sync_service_url.clone(),
+        std::time::Duration::from_secs(15),
+    );
+    
+    // Sessions live in the shared database unless SESSION_STORE selects Redis
+    let db_pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(config.database_pool_size)
+        .connect_lazy(&config.database_url)
+        .expect("Invalid DATABASE_URL");
+    let sessions = terrafusion_common::sessions::SessionManager::from_env(db_pool.clone())
+        .expect("Failed to initialize session store");
+    sessions.spawn_purge(std::time::Duration::from_secs(300));
     
     // Create shared application state
     let app_state = web::Data::new(AppState {
@@ -52,14 +85,19 @@ async fn main() -> io::Result<()> {
         config: config.clone(),
         sync_service_client: services::SyncServiceClient::new(&config.sync_service_url),
         gis_export_client: services::GisExportClient::new(&config.gis_export_service_url),
+        runtime_config,
+        maintenance,
+        db_pool,
+        sessions,
     });
     
     // Configure and start HTTP server
     let server = if config.use_ssl {
-        // Configure SSL
-        let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
-        builder.set_private_key_file(&config.ssl_key_file, SslFiletype::PEM).unwrap();
-        builder.set_certificate_chain_file(&config.ssl_cert_file).unwrap();
+        // Configure SSL; renewed certificates are picked up without a restart
+        let tls = terrafusion_common::tls::ReloadingTls::load(&config.ssl_cert_file, &config.ssl_key_file)
+            .expect("Failed to load TLS certificate");
+        tls.spawn_watcher(std::time::Duration::from_secs(30));
+        let builder = tls.acceptor().expect("Failed to configure TLS");
         
         // Start HTTPS server
         HttpServer::new(move || create_app(app_state.clone()))
@@ -86,14 +124,37 @@ fn create_app(app_state: web::Data<AppState>) -> App<
     >,
 > {
     App::new()
+        .wrap(middlewares::MaintenanceMiddleware::new(
+            app_state.maintenance.clone(),
+            app_state.handlebars.clone(),
+        ))
+        .wrap(middlewares::CsrfMiddleware::new(app_state.config.clone()))
+        .wrap(middlewares::LocaleMiddleware::new(app_state.runtime_config.clone()))
         .wrap(Logger::default())
-        .wrap(middlewares::AuthMiddleware::default())
+        .wrap(
+            middlewares::AuthMiddleware::with_secret(app_state.config.jwt_secret.clone())
+                .with_sessions(app_state.sessions.clone())
+        )
         .wrap(middlewares::SecurityHeadersMiddleware::default())
+        .wrap(ErrorEnvelopeMiddleware)
+        // Compress picks gzip, brotli or zstd from Accept-Encoding for the
+        // responses the policy lets through
+        .wrap(CompressionPolicy::new(app_state.config.compression))
+        .wrap(Compress::default())
         .wrap(NormalizePath::trim())
         .app_data(app_state.clone())
         
-        // Static files
-        .service(fs::Files::new("/static", "./static").show_files_listing(false))
+        // Embedded static files with content-hashed URLs
+        .configure(assets::configure)
+        
+        // Public portal for published exports; no login, tighter rate limit
+        .configure(routes::public::configure)
+        
+        // Inbound webhooks that trigger sync pairs, authenticated by hook secret
+        .configure(routes::hooks::configure)
+        
+        // Sign-in and sign-out
+        .configure(routes::auth::configure)
         
         // UI Routes
         .service(routes::ui::configure())
@@ -101,8 +162,10 @@ fn create_app(app_state: web::Data<AppState>) -> App<
         // API Routes
         .service(
             web::scope("/api/v1")
+                .wrap(middlewares::SchemaValidationMiddleware::new(app_state.config.validate_responses))
                 .wrap(middlewares::ApiKeyMiddleware::default())
-                .configure(routes::api::configure)
+                .wrap(middlewares::RateLimitMiddleware::from_runtime_config(app_state.runtime_config.clone()))
+                .configure(|cfg| routes::api::configure(cfg, &app_state.config))
         )
         
         // Health and metrics endpoints
@@ -111,11 +174,9 @@ fn create_app(app_state: web::Data<AppState>) -> App<
                 .configure(routes::system::configure)
         )
         
-        // Error handlers
-        .app_data(web::JsonConfig::default().error_handler(|err, _req| {
-            log::error!("JSON parsing error: {:?}", err);
-            errors::AppError::BadRequest(err.to_string()).into()
-        }))
+        // JSON body limit and error handler; routes taking geometries or
+        // files set their own limits
+        .app_data(errors::json_config(app_state.config.max_json_body_bytes))
 }
 
 pub struct AppState {
@@ -123,4 +184,8 @@ pub struct AppState {
     pub config: config::AppConfig,
     pub sync_service_client: services::SyncServiceClient,
     pub gis_export_client: services::GisExportClient,
+    pub runtime_config: terrafusion_common::config::ReloadHandle,
+    pub maintenance: terrafusion_common::maintenance::MaintenanceHandle,
+    pub db_pool: sqlx::PgPool,
+    pub sessions: terrafusion_common::sessions::SessionManager,
 }
\ No newline at end of file
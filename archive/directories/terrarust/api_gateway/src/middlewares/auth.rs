@@ -8,6 +8,8 @@ use actix_web::{
 use futures_util::future::LocalBoxFuture;
 use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
 use crate::errors::AppError;
+use terrafusion_common::sessions::SessionManager;
+use terrafusion_common::tenancy::CountyContext;
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use actix_web::http::header;
@@ -15,6 +17,24 @@ use actix_web::http::header;
 /// AuthMiddleware for handling JWT-based authentication
 pub struct AuthMiddleware {
     pub exclude_paths: Vec<String>,
+    pub jwt_secret: Option<String>,
+    pub sessions: Option<SessionManager>,
+}
+
+impl AuthMiddleware {
+    /// Create middleware that validates tokens with the given secret
+    pub fn with_secret(jwt_secret: String) -> Self {
+        Self {
+            jwt_secret: Some(jwt_secret),
+            ..Self::default()
+        }
+    }
+    
+    /// Also require tokens carrying a session id to have an active session
+    pub fn with_sessions(mut self, sessions: SessionManager) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
 }
 
 impl Default for AuthMiddleware {
@@ -25,10 +45,14 @@ impl Default for AuthMiddleware {
                 "/login".to_string(),
                 "/logout".to_string(),
                 "/static".to_string(),
+                "/public".to_string(),
+                "/hooks".to_string(),
                 "/api/v1/auth".to_string(),
                 "/system/health".to_string(),
                 "/system/metrics".to_string(),
             ],
+            jwt_secret: None,
+            sessions: None,
         }
     }
 }
@@ -48,6 +72,8 @@ where
         ready(Ok(AuthMiddlewareService {
             service: Rc::new(service),
             exclude_paths: self.exclude_paths.clone(),
+            jwt_secret: self.jwt_secret.clone(),
+            sessions: self.sessions.clone(),
         }))
     }
 }
@@ -55,6 +81,8 @@ where
 pub struct AuthMiddlewareService<S> {
     service: Rc<S>,
     exclude_paths: Vec<String>,
+    jwt_secret: Option<String>,
+    sessions: Option<SessionManager>,
 }
 
 impl<S, B> Service<ServiceRequest> for AuthMiddlewareService<S>
@@ -90,12 +118,34 @@ where
                 // Validate the token
                 match self.validate_token(&token) {
                     Ok(claims) => {
-                        // Store user info in request extensions
-                        req.extensions_mut().insert(claims);
-                        let fut = self.service.call(req);
+                        let service = self.service.clone();
+                        let sessions = self.sessions.clone();
                         Box::pin(async move {
-                            let res = fut.await?;
-                            Ok(res)
+                            // Tokens issued at login end with their session (logout,
+                            // timeout or an administrator signing the user out)
+                            if let (Some(sid), Some(sessions)) = (&claims.sid, &sessions) {
+                                match sessions.validate(sid).await {
+                                    Ok(Some(_)) => {}
+                                    Ok(None) => {
+                                        return Err(AppError::Authentication("Session has ended".to_string()).into());
+                                    }
+                                    Err(e) => {
+                                        log::error!("Session lookup failed: {}", e);
+                                        return Err(AppError::ServiceUnavailable("Session store unavailable".to_string()).into());
+                                    }
+                                }
+                            }
+                            
+                            // Store user info and the county scope in request extensions
+                            let county = if claims.has_role("platform_admin") {
+                                CountyContext::platform_admin(&claims.county_id)
+                            } else {
+                                CountyContext::new(&claims.county_id)
+                            }
+                            .with_roles(claims.roles.clone());
+                            req.extensions_mut().insert(county);
+                            req.extensions_mut().insert(claims);
+                            service.call(req).await
                         })
                     }
                     Err(err) => {
@@ -152,18 +202,25 @@ impl<S> AuthMiddlewareService<S> {
     
     /// Validate JWT token and extract claims
     fn validate_token(&self, token: &str) -> Result<Claims, String> {
-        // TODO: Get JWT secret from config
-        let jwt_secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "default_secret_for_development".to_string());
+        let jwt_secret = match &self.jwt_secret {
+            Some(secret) => secret.clone(),
+            None => std::env::var("JWT_SECRET").unwrap_or_else(|_| "default_secret_for_development".to_string()),
+        };
         
-        let validation = Validation::new(Algorithm::HS256);
-        match decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(jwt_secret.as_bytes()),
-            &validation,
-        ) {
-            Ok(token_data) => Ok(token_data.claims),
-            Err(err) => Err(err.to_string()),
-        }
+        decode_claims(token, &jwt_secret)
+    }
+}
+
+/// Check a token's signature and expiry and return its claims
+pub fn decode_claims(token: &str, jwt_secret: &str) -> Result<Claims, String> {
+    let validation = Validation::new(Algorithm::HS256);
+    match decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret.as_bytes()),
+        &validation,
+    ) {
+        Ok(token_data) => Ok(token_data.claims),
+        Err(err) => Err(err.to_string()),
     }
 }
 
@@ -177,6 +234,10 @@ pub struct Claims {
     pub county_id: String,   // User's county ID
     pub exp: u64,            // Expiration time (Unix timestamp)
     pub iat: u64,            // Issued at time (Unix timestamp)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>, // User's preferred UI language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>, // Server-side session, checked on every request
 }
 
 impl Claims {
@@ -202,6 +263,8 @@ impl Claims {
             county_id: county_id.to_string(),
             exp: now + expiry.as_secs(),
             iat: now,
+            locale: None,
+            sid: None,
         }
     }
     
@@ -0,0 +1,227 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header, Method},
+    web, Error, FromRequest, HttpMessage, HttpRequest,
+};
+use futures_util::future::LocalBoxFuture;
+use futures_util::StreamExt;
+use handlebars::{Context as TemplateContext, Handlebars, Helper, HelperResult, Output, RenderContext};
+use rand::RngCore;
+use serde::Deserialize;
+use crate::config::AppConfig;
+use crate::errors::AppError;
+
+/// Cookie holding the token; scripts read it, so it is not `HttpOnly`
+pub const CSRF_COOKIE: &str = "tf_csrf";
+
+/// Header the dashboards send the token in
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// Hidden form field written by `{{csrf_field}}`
+pub const CSRF_FORM_FIELD: &str = "csrf_token";
+
+/// Largest form body read while looking for the token
+const MAX_FORM_SIZE: usize = 64 * 1024;
+
+/// Double-submit CSRF protection.
+///
+/// Every browser gets a random token in the `tf_csrf` cookie. State-changing
+/// requests authenticated by cookie must repeat it in the `X-CSRF-Token`
+/// header or the `csrf_token` form field; another site can make the browser
+/// send the cookie but cannot read it. Requests carrying an `Authorization`
+/// or `X-API-KEY` header are exempt, since browsers never add those on their
+/// own.
+pub struct CsrfMiddleware {
+    config: AppConfig,
+}
+
+impl CsrfMiddleware {
+    pub fn new(config: AppConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for CsrfMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = CsrfMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CsrfMiddlewareService {
+            service: Rc::new(service),
+            config: self.config.clone(),
+        }))
+    }
+}
+
+pub struct CsrfMiddlewareService<S> {
+    service: Rc<S>,
+    config: AppConfig,
+}
+
+impl<S, B> Service<ServiceRequest> for CsrfMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let config = self.config.clone();
+
+        Box::pin(async move {
+            let existing = req.cookie(CSRF_COOKIE).map(|cookie| cookie.value().to_string());
+
+            if needs_check(&req) {
+                let submitted = match header_token(&req) {
+                    Some(token) => Some(token),
+                    None => match form_token(&mut req).await {
+                        Ok(token) => token,
+                        Err(error) => return Ok(req.error_response(error).map_into_right_body()),
+                    },
+                };
+                let valid = matches!(
+                    (&existing, &submitted),
+                    (Some(expected), Some(actual)) if constant_time_eq(expected.as_bytes(), actual.as_bytes())
+                );
+                if !valid {
+                    log::warn!("Rejected {} {}: missing or invalid CSRF token", req.method(), req.path());
+                    let error = AppError::Authorization("CSRF token missing or invalid".to_string());
+                    return Ok(req.error_response(error).map_into_right_body());
+                }
+            }
+
+            let issue = existing.is_none();
+            let token = existing.unwrap_or_else(generate_token);
+            req.extensions_mut().insert(CsrfToken(token.clone()));
+
+            let mut res = service.call(req).await?.map_into_left_body();
+            if issue {
+                let cookie = config.cookie(CSRF_COOKIE, token).http_only(false).finish();
+                if let Err(e) = res.response_mut().add_cookie(&cookie) {
+                    log::error!("Failed to set CSRF cookie: {}", e);
+                }
+            }
+            Ok(res)
+        })
+    }
+}
+
+/// Unsafe methods relying on the browser's cookies. Inbound webhooks never
+/// carry cookies; they prove themselves with the hook secret.
+fn needs_check(req: &ServiceRequest) -> bool {
+    let safe = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE);
+    let explicit_credentials = req.headers().contains_key(header::AUTHORIZATION)
+        || req.headers().contains_key("X-API-KEY")
+        || req.path().starts_with("/hooks/");
+    !safe && !explicit_credentials
+}
+
+fn header_token(req: &ServiceRequest) -> Option<String> {
+    req.headers()
+        .get(CSRF_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+#[derive(Deserialize)]
+struct TokenField {
+    csrf_token: Option<String>,
+}
+
+/// Read the token from a urlencoded form, then put the body back for the
+/// handler
+async fn form_token(req: &mut ServiceRequest) -> Result<Option<String>, Error> {
+    if req.content_type() != "application/x-www-form-urlencoded" {
+        return Ok(None);
+    }
+
+    let mut payload = req.take_payload();
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > MAX_FORM_SIZE {
+            return Err(AppError::BadRequest("Form body too large".to_string()).into());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = body.freeze();
+
+    let token = std::str::from_utf8(&body)
+        .ok()
+        .and_then(|body| web::Query::<TokenField>::from_query(body).ok())
+        .and_then(|field| field.into_inner().csrf_token);
+
+    let (_, mut restored) = actix_http::h1::Payload::create(true);
+    restored.unread_data(body);
+    req.set_payload(restored.into());
+    Ok(token)
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Compare without returning early, so timing does not reveal the token
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// This request's CSRF token, for rendering into templates as `csrf_token`
+#[derive(Debug, Clone)]
+pub struct CsrfToken(pub String);
+
+impl FromRequest for CsrfToken {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<CsrfToken>()
+                .cloned()
+                .ok_or_else(|| AppError::InternalServerError("CSRF middleware is not registered".to_string()).into()),
+        )
+    }
+}
+
+/// `{{csrf_field}}`: a hidden input carrying the template's `csrf_token`
+fn csrf_field(
+    _: &Helper,
+    _: &Handlebars,
+    ctx: &TemplateContext,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let token = ctx.data().get(CSRF_FORM_FIELD).and_then(|value| value.as_str()).unwrap_or_default();
+    out.write(&format!(
+        r#"<input type="hidden" name="{}" value="{}">"#,
+        CSRF_FORM_FIELD,
+        handlebars::html_escape(token)
+    ))?;
+    Ok(())
+}
+
+/// Register the `csrf_field` helper
+pub fn register_helpers(handlebars: &mut Handlebars<'static>) {
+    handlebars.register_helper("csrf_field", Box::new(csrf_field));
+}
@@ -0,0 +1,98 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{self, HeaderValue},
+    Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+use terrafusion_common::config::ReloadHandle;
+use terrafusion_common::errors::web::CorrelationId;
+use crate::errors::AppError;
+use crate::i18n::Locale;
+
+/// Middleware choosing the language for each request.
+///
+/// Stores the `Locale` in the request extensions for handlers and
+/// templates, sets `Content-Language`, and rebuilds `AppError` bodies with
+/// a translated summary and the request's correlation id. Must be
+/// registered inside the auth middleware so the user's and county's
+/// preferences are known, and inside `ErrorEnvelopeMiddleware`.
+pub struct LocaleMiddleware {
+    pub runtime_config: ReloadHandle,
+}
+
+impl LocaleMiddleware {
+    pub fn new(runtime_config: ReloadHandle) -> Self {
+        Self { runtime_config }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for LocaleMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = LocaleMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(LocaleMiddlewareService {
+            service: Rc::new(service),
+            runtime_config: self.runtime_config.clone(),
+        }))
+    }
+}
+
+pub struct LocaleMiddlewareService<S> {
+    service: Rc<S>,
+    runtime_config: ReloadHandle,
+}
+
+impl<S, B> Service<ServiceRequest> for LocaleMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let settings = self.runtime_config.current();
+        let locale = Locale::detect(req.request(), &settings.county_locales);
+        req.extensions_mut().insert(locale.clone());
+        let correlation_id = req.extensions().get::<CorrelationId>().map(|id| id.0.clone());
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+
+            // Error bodies are built without the request, so they default to
+            // English and carry no correlation id; rebuild them here
+            let localized = res
+                .response()
+                .error()
+                .and_then(|error| error.as_error::<AppError>())
+                .map(|error| error.localized_response(&locale, correlation_id.as_deref()));
+            let mut res = match localized {
+                Some(response) => res.into_response(response),
+                None => res.map_into_boxed_body(),
+            };
+
+            if let Ok(value) = HeaderValue::from_str(&locale.code()) {
+                res.headers_mut().insert(header::CONTENT_LANGUAGE, value);
+            }
+            Ok(res)
+        })
+    }
+}
@@ -0,0 +1,201 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage, HttpResponse,
+};
+use futures_util::future::LocalBoxFuture;
+use handlebars::Handlebars;
+use serde_json::json;
+use terrafusion_common::http_client::{self, HttpClient};
+use terrafusion_common::maintenance::{MaintenanceHandle, MaintenanceStatus, MaintenanceWindow};
+use terrafusion_common::tenancy::CountyContext;
+use crate::errors::AppError;
+use crate::i18n::Locale;
+
+/// Middleware answering 503 while the caller's county (or the platform) is in maintenance.
+///
+/// Must be registered inside the auth middleware so the county scope is
+/// known. Platform administrators pass through so they can check the
+/// system and end maintenance.
+pub struct MaintenanceMiddleware {
+    pub maintenance: MaintenanceHandle,
+    pub handlebars: Arc<Handlebars<'static>>,
+    pub exclude_paths: Vec<String>,
+}
+
+impl MaintenanceMiddleware {
+    pub fn new(maintenance: MaintenanceHandle, handlebars: Arc<Handlebars<'static>>) -> Self {
+        Self {
+            maintenance,
+            handlebars,
+            exclude_paths: vec![
+                "/static".to_string(),
+                "/login".to_string(),
+                "/logout".to_string(),
+                "/api/v1/auth".to_string(),
+                "/system".to_string(),
+            ],
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for MaintenanceMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = MaintenanceMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MaintenanceMiddlewareService {
+            service: Rc::new(service),
+            maintenance: self.maintenance.clone(),
+            handlebars: self.handlebars.clone(),
+            exclude_paths: self.exclude_paths.clone(),
+        }))
+    }
+}
+
+pub struct MaintenanceMiddlewareService<S> {
+    service: Rc<S>,
+    maintenance: MaintenanceHandle,
+    handlebars: Arc<Handlebars<'static>>,
+    exclude_paths: Vec<String>,
+}
+
+impl<S, B> Service<ServiceRequest> for MaintenanceMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let status = self.maintenance.current();
+        let window = if status.is_empty() || self.is_excluded(req.path()) {
+            None
+        } else {
+            let county = req.extensions().get::<CountyContext>().cloned();
+            match county {
+                Some(county) if county.is_platform_admin => None,
+                Some(county) => status.active_for(Some(&county.county_id)).cloned(),
+                None => status.active_for(None).cloned(),
+            }
+        };
+
+        let Some(window) = window else {
+            let fut = self.service.call(req);
+            return Box::pin(async move {
+                let res = fut.await?;
+                Ok(res)
+            });
+        };
+
+        let locale = req.extensions().get::<Locale>().cloned().unwrap_or_default();
+        let response = if req.path().starts_with("/api/") {
+            api_response(&window, &locale)
+        } else {
+            self.page_response(&window, &locale)
+        };
+        let error = AppError::ServiceUnavailable(window.message.clone());
+
+        Box::pin(async move {
+            Err(actix_web::error::InternalError::from_response(error, response).into())
+        })
+    }
+}
+
+impl<S> MaintenanceMiddlewareService<S> {
+    fn is_excluded(&self, path: &str) -> bool {
+        self.exclude_paths.iter().any(|excluded| path.starts_with(excluded))
+    }
+
+    /// Friendly HTML page for browser routes
+    fn page_response(&self, window: &MaintenanceWindow, locale: &Locale) -> HttpResponse {
+        let data = json!({
+            "title": locale.tr("maintenance-title"),
+            "lang": locale.code(),
+            "message": window.message,
+            "county_id": (!window.is_platform()).then(|| window.scope.clone()),
+            "ends_at": window.ends_at.map(|ends_at| ends_at.format("%Y-%m-%d %H:%M UTC").to_string()),
+        });
+
+        let body = self.handlebars.render("maintenance", &data).unwrap_or_else(|e| {
+            log::error!("Template rendering error: {}", e);
+            format!("<h1>{}</h1><p>{}</p>", locale.tr("maintenance-title"), window.message)
+        });
+
+        with_retry_after(HttpResponse::ServiceUnavailable(), window)
+            .content_type("text/html")
+            .body(body)
+    }
+}
+
+/// Machine-readable 503 for API clients
+fn api_response(window: &MaintenanceWindow, locale: &Locale) -> HttpResponse {
+    with_retry_after(HttpResponse::ServiceUnavailable(), window).json(json!({
+        "error": {
+            "code": 503,
+            "message": window.message,
+            "localized_message": locale.tr("maintenance-unavailable"),
+            "type": "maintenance",
+            "scope": window.scope,
+            "started_at": window.started_at,
+            "ends_at": window.ends_at,
+        }
+    }))
+}
+
+fn with_retry_after(mut builder: actix_web::HttpResponseBuilder, window: &MaintenanceWindow) -> actix_web::HttpResponseBuilder {
+    if let Some(seconds) = window.retry_after(chrono::Utc::now()) {
+        builder.append_header(("Retry-After", seconds.to_string()));
+    }
+    builder
+}
+
+/// Keep the gateway's copy of the maintenance status in step with the sync service.
+///
+/// The last known status is kept when the sync service cannot be reached.
+pub fn spawn_status_poll(
+    maintenance: MaintenanceHandle,
+    sync_service_url: String,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = http_client::shared();
+        let url = format!("{}/system/maintenance", sync_service_url);
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+            match fetch_status(client, &url).await {
+                Ok(status) => maintenance.replace(status),
+                Err(e) => log::debug!("Could not refresh maintenance status: {}", e),
+            }
+        }
+    })
+}
+
+async fn fetch_status(client: &HttpClient, url: &str) -> Result<MaintenanceStatus, reqwest::Error> {
+    #[derive(serde::Deserialize)]
+    struct Body {
+        status: MaintenanceStatus,
+    }
+
+    let body: Body = client.get(url).send().await?.error_for_status()?.json().await?;
+    Ok(body.status)
+}
@@ -1,12 +1,20 @@
-mod auth;
+pub mod auth;
 mod security;
 mod api_key;
 mod rate_limit;
 mod logging;
+pub mod maintenance;
+mod locale;
+pub mod csrf;
+mod schema_validation;
 
 // Re-export middleware components
 pub use auth::AuthMiddleware;
 pub use security::SecurityHeadersMiddleware;
 pub use api_key::ApiKeyMiddleware;
 pub use rate_limit::RateLimitMiddleware;
-pub use logging::LoggingMiddleware;
\ No newline at end of file
+pub use logging::LoggingMiddleware;
+pub use maintenance::MaintenanceMiddleware;
+pub use locale::LocaleMiddleware;
+pub use csrf::CsrfMiddleware;
+pub use schema_validation::SchemaValidationMiddleware;
\ No newline at end of file
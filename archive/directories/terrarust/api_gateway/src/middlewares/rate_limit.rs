@@ -9,6 +9,7 @@ use actix_web::{
     Error, HttpResponse,
 };
 use futures_util::future::LocalBoxFuture;
+use terrafusion_common::config::ReloadHandle;
 use crate::errors::AppError;
 
 /// Rate limiter implementation using token bucket algorithm
@@ -74,6 +75,10 @@ impl RateLimitStore {
         }
         
         if let Some(bucket) = buckets.get_mut(key) {
+            // Pick up limits changed by a settings reload
+            bucket.capacity = capacity;
+            bucket.refill_rate = refill_rate;
+            bucket.tokens = bucket.tokens.min(capacity);
             Some(bucket.consume(1))
         } else {
             None
@@ -86,6 +91,18 @@ pub struct RateLimitMiddleware {
     pub requests_per_second: usize,
     pub burst_size: usize,
     pub exclude_paths: Vec<String>,
+    /// When set, limits are read from the hot-reloadable runtime settings
+    pub runtime_config: Option<ReloadHandle>,
+}
+
+impl RateLimitMiddleware {
+    /// Create a rate limiter that follows runtime settings reloads
+    pub fn from_runtime_config(runtime_config: ReloadHandle) -> Self {
+        Self {
+            runtime_config: Some(runtime_config),
+            ..Self::default()
+        }
+    }
 }
 
 impl Default for RateLimitMiddleware {
@@ -93,6 +110,7 @@ impl Default for RateLimitMiddleware {
         Self {
             requests_per_second: 10,
             burst_size: 20,
+            runtime_config: None,
             exclude_paths: vec![
                 "/static".to_string(),
                 "/system/health".to_string(),
@@ -120,6 +138,7 @@ where
             requests_per_second: self.requests_per_second,
             burst_size: self.burst_size,
             exclude_paths: self.exclude_paths.clone(),
+            runtime_config: self.runtime_config.clone(),
         }))
     }
 }
@@ -130,6 +149,7 @@ pub struct RateLimitMiddlewareService<S> {
     requests_per_second: usize,
     burst_size: usize,
     exclude_paths: Vec<String>,
+    runtime_config: Option<ReloadHandle>,
 }
 
 impl<S, B> Service<ServiceRequest> for RateLimitMiddlewareService<S>
@@ -166,7 +186,8 @@ where
         
         // Perform rate limiting check
         let key = format!("{}:{}", client_ip, req.path());
-        let allowed = self.store.get_bucket(&key, self.burst_size, self.requests_per_second).unwrap_or(false);
+        let (burst_size, requests_per_second) = self.current_limits();
+        let allowed = self.store.get_bucket(&key, burst_size, requests_per_second).unwrap_or(false);
         
         if allowed {
             // Request is allowed, continue
@@ -203,4 +224,15 @@ impl<S> RateLimitMiddlewareService<S> {
     fn should_skip_rate_limit(&self, path: &str) -> bool {
         self.exclude_paths.iter().any(|excluded| path.starts_with(excluded))
     }
+    
+    /// Current (burst size, requests per second), preferring runtime settings
+    fn current_limits(&self) -> (usize, usize) {
+        match &self.runtime_config {
+            Some(runtime_config) => {
+                let settings = runtime_config.current();
+                (settings.rate_limit_burst_size, settings.rate_limit_requests_per_second)
+            }
+            None => (self.burst_size, self.requests_per_second),
+        }
+    }
 }
\ No newline at end of file
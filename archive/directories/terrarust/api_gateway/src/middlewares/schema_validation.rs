@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use actix_web::{
+    body::{self, BoxBody, EitherBody, MessageBody},
+    dev::{ResourceDef, Service, ServiceRequest, ServiceResponse, Transform},
+    http::Method,
+    web, Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+use futures_util::StreamExt;
+use jsonschema::{error::ValidationErrorKind, JSONSchema};
+use lazy_static::lazy_static;
+use terrafusion_common::schemas;
+use crate::errors::{AppError, FieldError};
+
+/// Largest request body buffered for validation
+const MAX_BODY_SIZE: usize = 256 * 1024;
+
+/// Payload schemas per endpoint
+struct SchemaRoute {
+    method: Method,
+    path: &'static str,
+    request: Option<&'static str>,
+    response: Option<&'static str>,
+}
+
+lazy_static! {
+    static ref SCHEMA_ROUTES: Vec<(SchemaRoute, ResourceDef)> = [
+        SchemaRoute { method: Method::POST, path: "/api/v1/sync-pairs", request: Some(schemas::CREATE_SYNC_PAIR), response: None },
+        SchemaRoute { method: Method::PUT, path: "/api/v1/sync-pairs/{id}", request: Some(schemas::UPDATE_SYNC_PAIR), response: None },
+        SchemaRoute { method: Method::PATCH, path: "/api/v1/sync-pairs/{id}", request: Some(schemas::UPDATE_SYNC_PAIR), response: None },
+        SchemaRoute { method: Method::POST, path: "/api/v1/sync-operations", request: Some(schemas::CREATE_SYNC_OPERATION), response: None },
+        SchemaRoute { method: Method::POST, path: "/api/v1/gis-exports", request: Some(schemas::CREATE_GIS_EXPORT), response: None },
+        SchemaRoute { method: Method::GET, path: "/api/v1/gis-exports/{id}", request: None, response: Some(schemas::GIS_EXPORT_STATUS) },
+    ]
+    .into_iter()
+    .map(|route| {
+        let pattern = ResourceDef::new(route.path);
+        (route, pattern)
+    })
+    .collect();
+
+    /// Compiled once; a schema that does not compile is logged and skipped
+    static ref VALIDATORS: HashMap<&'static str, JSONSchema> = schemas::all()
+        .into_iter()
+        .filter_map(|(name, schema)| {
+            let schema = serde_json::to_value(schema).ok()?;
+            match JSONSchema::compile(&schema) {
+                Ok(validator) => Some((name, validator)),
+                Err(e) => {
+                    log::error!("Schema {} does not compile: {}", name, e);
+                    None
+                }
+            }
+        })
+        .collect();
+}
+
+/// Check JSON bodies against the shared model schemas at the edge.
+///
+/// Requests to endpoints with a request schema are rejected with 422 and a
+/// list of field errors before they reach a service. Response checking is
+/// for development: mismatches are logged and the response is sent
+/// unchanged.
+pub struct SchemaValidationMiddleware {
+    validate_responses: bool,
+}
+
+impl SchemaValidationMiddleware {
+    pub fn new(validate_responses: bool) -> Self {
+        Self { validate_responses }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SchemaValidationMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = SchemaValidationMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SchemaValidationMiddlewareService {
+            service: Rc::new(service),
+            validate_responses: self.validate_responses,
+        }))
+    }
+}
+
+pub struct SchemaValidationMiddlewareService<S> {
+    service: Rc<S>,
+    validate_responses: bool,
+}
+
+impl<S, B> Service<ServiceRequest> for SchemaValidationMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let route = SCHEMA_ROUTES
+            .iter()
+            .find(|(route, pattern)| route.method == req.method() && pattern.is_match(req.path()))
+            .map(|(route, _)| route);
+        let request_schema = route.and_then(|route| route.request);
+        let response_schema = route.and_then(|route| route.response).filter(|_| self.validate_responses);
+
+        Box::pin(async move {
+            if let Some(schema) = request_schema {
+                if let Err(error) = validate_request(&mut req, schema).await {
+                    return Ok(req.error_response(error).map_into_right_body());
+                }
+            }
+
+            let res = service.call(req).await?;
+            match response_schema {
+                Some(schema) if res.status().is_success() => check_response(res, schema).await,
+                _ => Ok(res.map_into_left_body()),
+            }
+        })
+    }
+}
+
+/// Buffer and check the body, then put it back for the handler
+async fn validate_request(req: &mut ServiceRequest, schema: &str) -> Result<(), AppError> {
+    let mut payload = req.take_payload();
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| AppError::BadRequest(format!("Failed to read request body: {}", e)))?;
+        if body.len() + chunk.len() > MAX_BODY_SIZE {
+            return Err(AppError::BadRequest("Request body too large".to_string()));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = body.freeze();
+
+    let (_, mut restored) = actix_http::h1::Payload::create(true);
+    restored.unread_data(body.clone());
+    req.set_payload(restored.into());
+
+    if req.content_type() != "application/json" {
+        return Err(AppError::BadRequest("Expected a JSON request body".to_string()));
+    }
+    let instance: serde_json::Value = serde_json::from_slice(&body)
+        .map_err(|e| AppError::BadRequest(format!("Invalid JSON: {}", e)))?;
+
+    let fields = field_errors(schema, &instance);
+    if fields.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::InvalidFields(fields))
+    }
+}
+
+/// Log where a response departs from its schema and pass it on
+async fn check_response<B>(res: ServiceResponse<B>, schema: &str) -> Result<ServiceResponse<EitherBody<B>>, Error>
+where
+    B: MessageBody + 'static,
+{
+    let (req, res) = res.into_parts();
+    let (res, body) = res.into_parts();
+    let bytes = body::to_bytes(body)
+        .await
+        .map_err(|_| AppError::InternalServerError("Failed to read response body".to_string()))?;
+
+    if let Ok(instance) = serde_json::from_slice::<serde_json::Value>(&bytes) {
+        for field in field_errors(schema, &instance) {
+            log::warn!(
+                "Response of {} {} does not match {}: {} {}",
+                req.method(),
+                req.path(),
+                schema,
+                field.field,
+                field.message
+            );
+        }
+    }
+
+    let res = res.set_body(BoxBody::new(bytes));
+    Ok(ServiceResponse::new(req, res).map_into_right_body())
+}
+
+/// Errors for `instance` against the named schema; unknown schemas accept anything
+fn field_errors(schema: &str, instance: &serde_json::Value) -> Vec<FieldError> {
+    let Some(validator) = VALIDATORS.get(schema) else {
+        return Vec::new();
+    };
+    let Err(errors) = validator.validate(instance) else {
+        return Vec::new();
+    };
+
+    errors
+        .map(|error| {
+            let mut field = error.instance_path.to_string();
+            // A missing property is reported on its parent; point at the property instead
+            if let ValidationErrorKind::Required { property } = &error.kind {
+                if let Some(property) = property.as_str() {
+                    field = format!("{}/{}", field, property);
+                }
+            }
+            FieldError { field, message: error.to_string() }
+        })
+        .collect()
+}
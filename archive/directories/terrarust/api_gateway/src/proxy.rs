@@ -0,0 +1,114 @@
+//! Streaming pass-through for large request bodies.
+//!
+//! Most API routes parse their JSON body and forward it, which holds the
+//! whole body in memory. Geodata and connector files run to hundreds of
+//! megabytes, so the routes carrying them hand the body to the service chunk
+//! by chunk as it arrives and stream the answer back the same way. The size
+//! limit is checked against `Content-Length` up front and against the bytes
+//! actually received while streaming, so chunked uploads cannot get past it.
+
+use std::io;
+use actix_web::{http::header, http::StatusCode, web, HttpRequest, HttpResponse, Result};
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use terrafusion_common::http_client;
+use terrafusion_common::tenancy::{CountyContext, COUNTY_HEADER, PLATFORM_ADMIN_HEADER, ROLES_HEADER};
+use crate::errors::AppError;
+
+/// Chunks held between the client and the service, so a slow service slows
+/// the upload down instead of filling the gateway's memory
+const BUFFERED_CHUNKS: usize = 16;
+
+/// Request headers the service needs to interpret the body
+const FORWARDED_REQUEST_HEADERS: &[&str] = &["content-type", "content-encoding", "content-disposition"];
+
+/// Response headers passed back to the client
+const FORWARDED_RESPONSE_HEADERS: &[&str] = &["content-type", "content-disposition", "location"];
+
+/// Forward the request body to `url` as it arrives, on behalf of `county`,
+/// and stream the service's answer back. `service` names the service in
+/// errors; bodies over `limit` bytes are refused with 413.
+pub async fn stream_request(
+    req: &HttpRequest,
+    payload: web::Payload,
+    county: &CountyContext,
+    url: &str,
+    service: &str,
+    limit: usize,
+) -> Result<HttpResponse> {
+    let declared = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared.is_some_and(|length| length > limit) {
+        return Err(too_large(limit).into());
+    }
+    let method = reqwest::Method::from_bytes(req.method().as_str().as_bytes())
+        .map_err(|_| AppError::BadRequest(format!("Unsupported method {}", req.method())))?;
+
+    // The payload is tied to this worker's thread, so a local task reads it
+    // and hands chunks to the client through a bounded channel
+    let (mut chunks, body) = mpsc::channel::<io::Result<web::Bytes>>(BUFFERED_CHUNKS);
+    let reader = actix_web::rt::spawn(async move {
+        let mut payload = payload;
+        let mut received = 0usize;
+        while let Some(chunk) = payload.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    let _ = chunks.send(Err(io::Error::new(io::ErrorKind::Other, e.to_string()))).await;
+                    return Err(AppError::BadRequest(format!("Failed to read request body: {}", e)));
+                }
+            };
+            received += chunk.len();
+            if received > limit {
+                let _ = chunks.send(Err(io::Error::new(io::ErrorKind::Other, "request body too large"))).await;
+                return Err(too_large(limit));
+            }
+            if chunks.send(Ok(chunk)).await.is_err() {
+                // The service answered without reading the rest
+                break;
+            }
+        }
+        Ok(received)
+    });
+
+    let mut request = http_client::shared()
+        .request(method, url)
+        .header(COUNTY_HEADER, &county.county_id)
+        .header(PLATFORM_ADMIN_HEADER, county.is_platform_admin.to_string())
+        .header(ROLES_HEADER, county.roles.join(","))
+        .body(reqwest::Body::wrap_stream(body));
+    for name in FORWARDED_REQUEST_HEADERS {
+        if let Some(value) = req.headers().get(*name).and_then(|value| value.to_str().ok()) {
+            request = request.header(*name, value);
+        }
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            // A body the gateway cut off explains the failure better than
+            // the broken connection does
+            if let Ok(Err(error)) = reader.await {
+                return Err(error.into());
+            }
+            log::error!("Streaming proxy to {} failed: {}", url, e);
+            return Err(AppError::ServiceUnavailable(format!("{} unavailable", service)).into());
+        }
+    };
+
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut builder = HttpResponse::build(status);
+    for name in FORWARDED_RESPONSE_HEADERS {
+        if let Some(value) = response.headers().get(*name).and_then(|value| value.to_str().ok()) {
+            builder.insert_header((*name, value.to_string()));
+        }
+    }
+    Ok(builder.streaming(response.bytes_stream()))
+}
+
+fn too_large(limit: usize) -> AppError {
+    AppError::PayloadTooLarge(format!("Request body exceeds {} bytes", limit))
+}
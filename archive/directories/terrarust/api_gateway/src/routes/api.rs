@@ -1,16 +1,28 @@
-use actix_web::{web, HttpRequest, HttpResponse, Result};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Result};
 use serde_json::Value;
+use terrafusion_common::http_client;
+use terrafusion_common::tenancy::{COUNTY_HEADER, PLATFORM_ADMIN_HEADER, ROLES_HEADER};
+use crate::config::AppConfig;
+use crate::errors::{json_config, AppError};
+use crate::middlewares::auth::Claims;
 use crate::AppState;
 
 /// Configure API routes that proxy to Python services
-pub fn configure(cfg: &mut web::ServiceConfig) {
+pub fn configure(cfg: &mut web::ServiceConfig, config: &AppConfig) {
     cfg.service(
         web::scope("/gis-export")
+            // Export requests and comparisons can carry large AOI geometries
+            .app_data(json_config(config.max_geometry_body_bytes))
             .route("/jobs", web::get().to(list_gis_jobs))
             .route("/jobs", web::post().to(create_gis_job))
             .route("/jobs/{job_id}", web::get().to(get_gis_job))
+            .route("/jobs/{job_id}/process", web::post().to(process_gis_job))
             .route("/jobs/{job_id}/cancel", web::post().to(cancel_gis_job))
             .route("/download/{job_id}", web::get().to(download_gis_export))
+            .route("/tiles/{county_id}/{layer_id}/{z}/{x}/{y}", web::get().to(get_map_tile))
+            .route("/exports/compare", web::post().to(compare_gis_exports))
+            .route("/jobs/{job_id}/publish", web::put().to(super::public::publish_export))
+            .route("/jobs/{job_id}/publish", web::delete().to(super::public::unpublish_export))
     )
     .service(
         web::scope("/district-lookup")
@@ -25,7 +37,13 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .route("/jobs", web::get().to(list_sync_jobs))
             .route("/jobs", web::post().to(create_sync_job))
             .route("/jobs/{job_id}", web::get().to(get_sync_job))
-    );
+            .route("/jobs/{job_id}/cancel", web::post().to(cancel_sync_job))
+    )
+    .configure(|cfg| super::counties::configure(cfg, config))
+    .configure(super::sync_pairs::configure)
+    .configure(super::sessions::configure)
+    .configure(super::users::configure)
+    .configure(super::preferences::configure);
 }
 
 /// Proxy GIS export job listing to Python service
@@ -36,28 +54,37 @@ async fn list_gis_jobs(
     let query_string = req.query_string();
     let url = format!("http://localhost:5000/api/v1/gis-export/jobs?{}", query_string);
     
-    match reqwest::get(&url).await {
+    match http_client::shared().get(&url).send().await {
         Ok(response) => {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "GIS Export service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("GIS Export service unavailable".to_string()).into())
+        }
     }
 }
 
-/// Proxy GIS export job creation to Python service
+/// Proxy GIS export job creation to Python service; the job is recorded as
+/// the signed-in user's
 async fn create_gis_job(
+    req: HttpRequest,
     req_body: web::Json<Value>,
     data: web::Data<AppState>
 ) -> Result<HttpResponse> {
     let url = "http://localhost:5000/api/v1/gis-export/jobs";
     
-    let client = reqwest::Client::new();
+    let mut body = req_body.into_inner();
+    let username = req.extensions().get::<Claims>().map(|claims| claims.email.clone());
+    if let (Some(username), Some(object)) = (username, body.as_object_mut()) {
+        object.insert("username".to_string(), Value::String(username));
+    }
+    
+    let client = http_client::shared();
     match client.post(url)
-        .json(&req_body.into_inner())
+        .json(&body)
         .send()
         .await
     {
@@ -66,9 +93,10 @@ async fn create_gis_job(
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "GIS Export service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("GIS Export service unavailable".to_string()).into())
+        }
     }
 }
 
@@ -80,15 +108,38 @@ async fn get_gis_job(
     let job_id = path.into_inner();
     let url = format!("http://localhost:5000/api/v1/gis-export/jobs/{}", job_id);
     
-    match reqwest::get(&url).await {
+    match http_client::shared().get(&url).send().await {
+        Ok(response) => {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Ok(HttpResponse::build(status).body(body))
+        }
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("GIS Export service unavailable".to_string()).into())
+        }
+    }
+}
+
+/// Proxy the start of GIS job processing to Python service
+async fn process_gis_job(
+    path: web::Path<String>,
+    data: web::Data<AppState>
+) -> Result<HttpResponse> {
+    let job_id = path.into_inner();
+    let url = format!("http://localhost:5000/api/v1/gis-export/jobs/{}/process", job_id);
+    
+    let client = http_client::shared();
+    match client.post(&url).send().await {
         Ok(response) => {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "GIS Export service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("GIS Export service unavailable".to_string()).into())
+        }
     }
 }
 
@@ -100,16 +151,17 @@ async fn cancel_gis_job(
     let job_id = path.into_inner();
     let url = format!("http://localhost:5000/api/v1/gis-export/jobs/{}/cancel", job_id);
     
-    let client = reqwest::Client::new();
+    let client = http_client::shared();
     match client.post(&url).send().await {
         Ok(response) => {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "GIS Export service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("GIS Export service unavailable".to_string()).into())
+        }
     }
 }
 
@@ -121,7 +173,7 @@ async fn download_gis_export(
     let job_id = path.into_inner();
     let url = format!("http://localhost:5000/api/v1/gis-export/download/{}", job_id);
     
-    match reqwest::get(&url).await {
+    match http_client::shared().get(&url).send().await {
         Ok(response) => {
             let status = response.status();
             let headers = response.headers().clone();
@@ -139,12 +191,108 @@ async fn download_gis_export(
             
             Ok(http_response.body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "GIS Export service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("GIS Export service unavailable".to_string()).into())
+        }
     }
 }
 
+/// Response headers passed through from the GIS export service on tiles
+const TILE_HEADERS: &[&str] = &["content-type", "cache-control"];
+
+/// Proxy a layer preview tile to the GIS export service as the caller's county
+async fn get_map_tile(
+    req: HttpRequest,
+    path: web::Path<(String, String, u32, u32, String)>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let (county_id, layer_id, z, x, y) = path.into_inner();
+    let url = format!(
+        "{}/gis-export/tiles/{}/{}/{}/{}/{}",
+        data.config.gis_export_service_url, county_id, layer_id, z, x, y
+    );
+
+    let response = http_client::shared()
+        .get(&url)
+        .header(COUNTY_HEADER, &county.county_id)
+        .header(PLATFORM_ADMIN_HEADER, county.is_platform_admin.to_string())
+        .header(ROLES_HEADER, county.roles.join(","))
+        .send()
+        .await
+        .map_err(|e| {
+            log::error!("Proxy to {} failed: {}", url, e);
+            AppError::ServiceUnavailable("GIS Export service unavailable".to_string())
+        })?;
+
+    let status = actix_web::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(actix_web::http::StatusCode::BAD_GATEWAY);
+    let mut builder = HttpResponse::build(status);
+    for name in TILE_HEADERS {
+        if let Some(value) = response.headers().get(*name).and_then(|value| value.to_str().ok()) {
+            builder.insert_header((*name, value.to_string()));
+        }
+    }
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::ExternalService(format!("Invalid GIS Export service response: {}", e)))?;
+    Ok(builder.body(body))
+}
+
+/// Response headers passed through from the GIS export service on change reports
+const CHANGE_REPORT_HEADERS: &[&str] = &[
+    "content-type",
+    "content-disposition",
+    "x-changes-added",
+    "x-changes-removed",
+    "x-changes-modified",
+];
+
+/// Proxy a change report between two exports, limited to the caller's county
+async fn compare_gis_exports(
+    req: HttpRequest,
+    body: web::Json<Value>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let mut body = body.into_inner();
+    let fields = body
+        .as_object_mut()
+        .ok_or_else(|| AppError::Validation("Request body must be a JSON object".to_string()))?;
+    if county.is_platform_admin {
+        fields.remove("county_id");
+    } else {
+        fields.insert("county_id".to_string(), Value::String(county.county_id.clone()));
+    }
+
+    let url = format!("{}/gis-export/exports/compare", data.config.gis_export_service_url);
+    let response = http_client::shared()
+        .post(&url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| {
+            log::error!("Proxy to {} failed: {}", url, e);
+            AppError::ServiceUnavailable("GIS Export service unavailable".to_string())
+        })?;
+
+    let status = actix_web::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(actix_web::http::StatusCode::BAD_GATEWAY);
+    let mut builder = HttpResponse::build(status);
+    for name in CHANGE_REPORT_HEADERS {
+        if let Some(value) = response.headers().get(*name).and_then(|value| value.to_str().ok()) {
+            builder.insert_header((*name, value.to_string()));
+        }
+    }
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::ExternalService(format!("Invalid GIS Export service response: {}", e)))?;
+    Ok(builder.body(body))
+}
+
 /// Proxy district lookup by coordinates to Python service
 async fn lookup_coordinates(
     req: HttpRequest,
@@ -153,15 +301,16 @@ async fn lookup_coordinates(
     let query_string = req.query_string();
     let url = format!("http://localhost:5000/api/v1/district-lookup/coordinates?{}", query_string);
     
-    match reqwest::get(&url).await {
+    match http_client::shared().get(&url).send().await {
         Ok(response) => {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "District lookup service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("District lookup service unavailable".to_string()).into())
+        }
     }
 }
 
@@ -173,15 +322,16 @@ async fn lookup_address(
     let query_string = req.query_string();
     let url = format!("http://localhost:5000/api/v1/district-lookup/address?{}", query_string);
     
-    match reqwest::get(&url).await {
+    match http_client::shared().get(&url).send().await {
         Ok(response) => {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "District lookup service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("District lookup service unavailable".to_string()).into())
+        }
     }
 }
 
@@ -193,15 +343,16 @@ async fn list_districts(
     let query_string = req.query_string();
     let url = format!("http://localhost:5000/api/v1/district-lookup/districts?{}", query_string);
     
-    match reqwest::get(&url).await {
+    match http_client::shared().get(&url).send().await {
         Ok(response) => {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "District lookup service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("District lookup service unavailable".to_string()).into())
+        }
     }
 }
 
@@ -213,15 +364,16 @@ async fn get_district_info(
     let (district_type, district_id) = path.into_inner();
     let url = format!("http://localhost:5000/api/v1/district-lookup/districts/{}/{}", district_type, district_id);
     
-    match reqwest::get(&url).await {
+    match http_client::shared().get(&url).send().await {
         Ok(response) => {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "District lookup service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("District lookup service unavailable".to_string()).into())
+        }
     }
 }
 
@@ -231,15 +383,16 @@ async fn district_lookup_info(
 ) -> Result<HttpResponse> {
     let url = "http://localhost:5000/api/v1/district-lookup";
     
-    match reqwest::get(url).await {
+    match http_client::shared().get(url).send().await {
         Ok(response) => {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "District lookup service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("District lookup service unavailable".to_string()).into())
+        }
     }
 }
 
@@ -251,15 +404,16 @@ async fn list_sync_jobs(
     let query_string = req.query_string();
     let url = format!("http://localhost:8080/operations?{}", query_string);
     
-    match reqwest::get(&url).await {
+    match http_client::shared().get(&url).send().await {
         Ok(response) => {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "Sync service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("Sync service unavailable".to_string()).into())
+        }
     }
 }
 
@@ -274,16 +428,17 @@ async fn create_sync_job(
     
     let url = format!("http://localhost:8080/sync/{}/start?sync_type={}", pair_id, sync_type);
     
-    let client = reqwest::Client::new();
+    let client = http_client::shared();
     match client.post(&url).send().await {
         Ok(response) => {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "Sync service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("Sync service unavailable".to_string()).into())
+        }
     }
 }
 
@@ -293,16 +448,39 @@ async fn get_sync_job(
     data: web::Data<AppState>
 ) -> Result<HttpResponse> {
     let job_id = path.into_inner();
-    let url = format!("http://localhost:8080/operations");
+    let url = format!("http://localhost:8080/operations/{}", job_id);
+    
+    match http_client::shared().get(&url).send().await {
+        Ok(response) => {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Ok(HttpResponse::build(status).body(body))
+        }
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("Sync service unavailable".to_string()).into())
+        }
+    }
+}
+
+/// Proxy sync job cancellation to SyncService
+async fn cancel_sync_job(
+    path: web::Path<String>,
+    data: web::Data<AppState>
+) -> Result<HttpResponse> {
+    let job_id = path.into_inner();
+    let url = format!("http://localhost:8080/operations/{}/cancel", job_id);
     
-    match reqwest::get(&url).await {
+    let client = http_client::shared();
+    match client.post(&url).send().await {
         Ok(response) => {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             Ok(HttpResponse::build(status).body(body))
         }
-        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "error": "Sync service unavailable"
-        })))
+        Err(e) => {
+            log::error!("Proxy to {} failed: {}", url, e);
+            Err(AppError::ServiceUnavailable("Sync service unavailable".to_string()).into())
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,215 @@
+use actix_web::{cookie::time, http::header, web, HttpRequest, HttpResponse, Result};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde::Deserialize;
+use serde_json::json;
+use crate::errors::AppError;
+use crate::i18n::Locale;
+use crate::middlewares::auth::{decode_claims, Claims};
+use crate::middlewares::csrf::CsrfToken;
+use crate::routes::preferences::{theme_cookie, Theme};
+use crate::AppState;
+
+/// Cookie carrying the signed-in user's token, read by the auth middleware
+pub const TOKEN_COOKIE: &str = "token";
+
+/// Configure sign-in and sign-out for the UI
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/login")
+            .route(web::get().to(login_page))
+            .route(web::post().to(login))
+    )
+    .service(
+        web::resource("/logout")
+            .route(web::get().to(logout))
+            .route(web::post().to(logout))
+    );
+}
+
+/// Outcome shown on the login page
+#[derive(Debug, Deserialize)]
+pub struct LoginPageQuery {
+    pub error: Option<String>,
+    pub signed_out: Option<bool>,
+}
+
+/// Login form fields; the CSRF token is checked by the middleware
+#[derive(Debug, Deserialize)]
+pub struct LoginForm {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct UserRow {
+    id: uuid::Uuid,
+    username: String,
+    email: String,
+    password_hash: String,
+    role: String,
+    county_id: String,
+    theme: String,
+}
+
+/// Sign-in form
+async fn login_page(
+    data: web::Data<AppState>,
+    locale: Locale,
+    csrf: CsrfToken,
+    query: web::Query<LoginPageQuery>,
+) -> Result<HttpResponse> {
+    let error_message = match query.error.as_deref() {
+        Some("unavailable") => Some(locale.tr("login-error-unavailable")),
+        Some(_) => Some(locale.tr("login-error-invalid")),
+        None => None,
+    };
+    let info_message = query.signed_out.unwrap_or(false).then(|| locale.tr("login-signed-out"));
+
+    let template_data = json!({
+        "title": locale.tr("login-submit"),
+        "lang": locale.code(),
+        "csrf_token": csrf.0,
+        "error_message": error_message,
+        "info_message": info_message
+    });
+
+    let body = data.handlebars
+        .render("login", &template_data)
+        .map_err(|e| {
+            log::error!("Template rendering error: {}", e);
+            actix_web::error::ErrorInternalServerError("Template rendering failed")
+        })?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(body))
+}
+
+/// Check the credentials, start a server-side session and set the token
+/// cookie, whose lifetime matches the session's absolute timeout. The
+/// user's saved theme replaces whatever this browser had.
+async fn login(req: HttpRequest, form: web::Form<LoginForm>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let form = form.into_inner();
+
+    let user = match authenticate(&data, &form).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            log::warn!("Failed sign-in for {}", form.username);
+            return Ok(redirect("/login?error=invalid"));
+        }
+        Err(e) => {
+            log::error!("Sign-in failed: {}", e);
+            return Ok(redirect("/login?error=unavailable"));
+        }
+    };
+
+    let ip_address = req.connection_info().realip_remote_addr().map(str::to_string);
+    let user_agent = req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let session = match data.sessions.start(&user.email, &user.county_id, ip_address, user_agent).await {
+        Ok(session) => session,
+        Err(e) => {
+            log::error!("Failed to start session for {}: {}", user.email, e);
+            return Ok(redirect("/login?error=unavailable"));
+        }
+    };
+
+    let lifetime = data.sessions.timeouts().absolute;
+    let mut claims = Claims::new(
+        &user.id.to_string(),
+        &user.username,
+        &user.email,
+        vec![user.role.clone()],
+        &user.county_id,
+        lifetime,
+    );
+    claims.sid = Some(session.id.clone());
+
+    let token = encode(
+        &Header::new(Algorithm::HS256),
+        &claims,
+        &EncodingKey::from_secret(data.config.jwt_secret.as_bytes()),
+    )
+    .map_err(|e| AppError::InternalServerError(format!("Failed to generate token: {}", e)))?;
+
+    if let Err(e) = sqlx::query("UPDATE users SET last_login = NOW() WHERE id = $1")
+        .bind(user.id)
+        .execute(&data.db_pool)
+        .await
+    {
+        log::warn!("Failed to record last login for {}: {}", user.email, e);
+    }
+
+    log::info!("User {} signed in", user.email);
+
+    let cookie = data.config
+        .cookie(TOKEN_COOKIE, token)
+        .http_only(true)
+        .max_age(time::Duration::seconds(lifetime.as_secs() as i64))
+        .finish();
+    Ok(HttpResponse::SeeOther()
+        .append_header((header::LOCATION, "/dashboard"))
+        .cookie(cookie)
+        .cookie(theme_cookie(&data.config, Theme::parse(&user.theme).unwrap_or_default()))
+        .finish())
+}
+
+/// Active user matching the username or email and password
+async fn authenticate(data: &AppState, form: &LoginForm) -> std::result::Result<Option<UserRow>, String> {
+    let user = sqlx::query_as::<_, UserRow>(
+        r#"
+        SELECT id, username, email, password_hash, role, county_id, theme
+        FROM users
+        WHERE (username = $1 OR email = $1) AND is_active
+        "#,
+    )
+    .bind(&form.username)
+    .fetch_optional(&data.db_pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let Some(user) = user else {
+        return Ok(None);
+    };
+
+    // bcrypt is deliberately slow; keep it off the async workers
+    let password = form.password.clone();
+    let hash = user.password_hash.clone();
+    let valid = web::block(move || bcrypt::verify(password, &hash))
+        .await
+        .map_err(|e| e.to_string())?
+        .unwrap_or(false);
+
+    Ok(valid.then_some(user))
+}
+
+/// End the session behind the token cookie and clear the cookie
+async fn logout(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let claims = req
+        .cookie(TOKEN_COOKIE)
+        .and_then(|cookie| decode_claims(cookie.value(), &data.config.jwt_secret).ok());
+
+    if let Some(claims) = claims {
+        if let Some(sid) = &claims.sid {
+            if let Err(e) = data.sessions.revoke(sid, None).await {
+                log::error!("Failed to end session for {}: {}", claims.email, e);
+            }
+        }
+        log::info!("User {} signed out", claims.email);
+    }
+
+    let mut cookie = data.config.cookie(TOKEN_COOKIE, String::new()).http_only(true).finish();
+    cookie.make_removal();
+    Ok(HttpResponse::SeeOther()
+        .append_header((header::LOCATION, "/login?signed_out=true"))
+        .cookie(cookie)
+        .finish())
+}
+
+fn redirect(location: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location.to_string()))
+        .finish()
+}
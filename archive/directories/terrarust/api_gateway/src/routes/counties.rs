@@ -0,0 +1,161 @@
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde_json::json;
+use terrafusion_common::conditional;
+use terrafusion_common::error::Error as CountyConfigError;
+use terrafusion_common::http_client;
+use terrafusion_common::tenancy::{COUNTY_HEADER, PLATFORM_ADMIN_HEADER, ROLES_HEADER};
+use terrafusion_common::utils::county_config;
+use crate::config::AppConfig;
+use crate::errors::AppError;
+use crate::proxy;
+use crate::AppState;
+
+/// Configure the county lookups behind the export wizard. Platform
+/// administrators see every configured county, everyone else their own.
+/// Onboarding new counties and uploading their boundary and seed data live
+/// in the same scope.
+pub fn configure(cfg: &mut web::ServiceConfig, config: &AppConfig) {
+    cfg.service(
+        web::scope("/counties")
+            .route("", web::get().to(list_counties))
+            .configure(|cfg| super::onboarding::configure(cfg, config))
+            .route("/{county_id}/export-options", web::get().to(get_export_options))
+            .route("/{county_id}/boundary", web::post().to(upload_boundary))
+            .route("/{county_id}/layers/{layer_id}/features", web::post().to(upload_layer_features))
+            .route("/{county_id}/layers/{layer_id}/stats", web::get().to(get_layer_stats))
+    );
+}
+
+/// Counties the caller can export from
+async fn list_counties(req: HttpRequest) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let county_ids = if county.is_platform_admin {
+        county_config::list_configured_counties().map_err(|e| {
+            log::error!("Failed to list county configurations: {}", e);
+            AppError::InternalServerError("County configurations unavailable".to_string())
+        })?
+    } else {
+        vec![county.county_id.clone()]
+    };
+
+    let mut counties = Vec::with_capacity(county_ids.len());
+    for county_id in county_ids {
+        let name = county_config::load_county_configuration(&county_id)
+            .await
+            .map(|config| config.county_name)
+            .unwrap_or_else(|_| county_id.clone());
+        counties.push(json!({ "county_id": county_id, "county_name": name }));
+    }
+    let body = json!({ "counties": counties }).to_string();
+    Ok(conditional::web::respond(&req, body.into_bytes(), "application/json", None))
+}
+
+/// Formats, layers, boundary and default parameters of a county's exports
+async fn get_export_options(req: HttpRequest, path: web::Path<String>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let county_id = path.into_inner();
+    if !county.can_access(&county_id) {
+        return Err(AppError::NotFound(format!("County {} not found", county_id)).into());
+    }
+
+    let config = county_config::load_county_configuration(&county_id).await.map_err(|e| match e {
+        CountyConfigError::NotFound(_) => AppError::NotFound(format!("County {} has no configuration", county_id)),
+        e => {
+            log::error!("Failed to load configuration of county {}: {}", county_id, e);
+            AppError::InternalServerError("County configuration unavailable".to_string())
+        }
+    })?;
+
+    let body = json!({
+        "county_id": config.county_id,
+        "county_name": config.county_name,
+        "export_formats": config.available_export_formats,
+        "default_export_format": config.default_export_format,
+        "layers": config.available_layers,
+        "boundary": config.boundary,
+        "default_parameters": config.default_parameters
+    });
+    // The export wizard asks on every visit and configurations rarely change
+    Ok(conditional::web::respond(&req, body.to_string().into_bytes(), "application/json", None))
+}
+
+/// Attribute distributions of a layer's uploaded data, for the export
+/// wizard's filter builder and the data quality pages
+async fn get_layer_stats(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let (county_id, layer_id) = path.into_inner();
+    if !county.can_access(&county_id) {
+        return Err(AppError::NotFound(format!("County {} not found", county_id)).into());
+    }
+
+    let url = format!(
+        "{}/gis-export/counties/{}/layers/{}/stats",
+        data.config.gis_export_service_url, county_id, layer_id
+    );
+    let response = http_client::shared()
+        .get(&url)
+        .header(COUNTY_HEADER, &county.county_id)
+        .header(PLATFORM_ADMIN_HEADER, county.is_platform_admin.to_string())
+        .header(ROLES_HEADER, county.roles.join(","))
+        .send()
+        .await
+        .map_err(|e| {
+            log::error!("Proxy to {} failed: {}", url, e);
+            AppError::ServiceUnavailable("GIS Export service unavailable".to_string())
+        })?;
+    let status = actix_web::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(actix_web::http::StatusCode::BAD_GATEWAY);
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::ExternalService(format!("Invalid GIS Export service response: {}", e)))?;
+    Ok(HttpResponse::build(status).content_type("application/json").body(body))
+}
+
+/// Replace the county's boundary with an uploaded GeoJSON, zipped Shapefile
+/// or GeoPackage file, streamed through to the GIS Export service
+async fn upload_boundary(
+    req: HttpRequest,
+    path: web::Path<String>,
+    payload: web::Payload,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let county_id = path.into_inner();
+    if !county.can_access(&county_id) {
+        return Err(AppError::NotFound(format!("County {} not found", county_id)).into());
+    }
+
+    let url = geodata_url(&data, &req, &format!("/gis-export/counties/{}/boundary", county_id));
+    proxy::stream_request(&req, payload, &county, &url, "GIS Export service", data.config.max_upload_bytes).await
+}
+
+/// Replace a layer's seed features with an uploaded file
+async fn upload_layer_features(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    payload: web::Payload,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let (county_id, layer_id) = path.into_inner();
+    if !county.can_access(&county_id) {
+        return Err(AppError::NotFound(format!("County {} not found", county_id)).into());
+    }
+
+    let path = format!("/gis-export/counties/{}/layers/{}/features", county_id, layer_id);
+    let url = geodata_url(&data, &req, &path);
+    proxy::stream_request(&req, payload, &county, &url, "GIS Export service", data.config.max_upload_bytes).await
+}
+
+/// The GIS Export service URL for `path`, keeping the upload's query options
+fn geodata_url(data: &AppState, req: &HttpRequest, path: &str) -> String {
+    match req.query_string() {
+        "" => format!("{}{}", data.config.gis_export_service_url, path),
+        query => format!("{}{}?{}", data.config.gis_export_service_url, path, query),
+    }
+}
@@ -0,0 +1,66 @@
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use terrafusion_common::http_client;
+use crate::errors::AppError;
+use crate::middlewares::RateLimitMiddleware;
+use crate::AppState;
+
+/// Deliveries per second per client and path. Vendors send one event per
+/// commit, so anything faster is a misbehaving sender.
+const HOOK_REQUESTS_PER_SECOND: usize = 5;
+const HOOK_BURST_SIZE: usize = 20;
+
+/// Largest delivery accepted, matching the sync service's own limit
+const MAX_PAYLOAD_BYTES: usize = 256 * 1024;
+
+/// Request headers the sync service needs to authenticate and deduplicate
+/// a delivery
+const FORWARDED_HEADERS: &[&str] = &[
+    "content-type",
+    "x-terrafusion-signature",
+    "x-terrafusion-hook-token",
+    "idempotency-key",
+];
+
+/// Configure the inbound webhook endpoints. They carry no session or API
+/// key; the sync service checks the hook secret instead.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/hooks")
+            .wrap(RateLimitMiddleware {
+                requests_per_second: HOOK_REQUESTS_PER_SECOND,
+                burst_size: HOOK_BURST_SIZE,
+                ..RateLimitMiddleware::default()
+            })
+            .app_data(web::PayloadConfig::new(MAX_PAYLOAD_BYTES))
+            .route("/sync-pairs/{id}/trigger", web::post().to(trigger_sync_pair))
+    );
+}
+
+/// Pass a delivery to the sync service byte for byte, since the signature
+/// covers the exact body the sender produced
+async fn trigger_sync_pair(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Bytes,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let url = format!("{}/hooks/sync-pairs/{}/trigger", data.config.sync_service_url, path.into_inner());
+    let mut request = http_client::shared().post(&url).body(body.to_vec());
+    for name in FORWARDED_HEADERS {
+        if let Some(value) = req.headers().get(*name).and_then(|value| value.to_str().ok()) {
+            request = request.header(*name, value);
+        }
+    }
+
+    let response = request.send().await.map_err(|e| {
+        log::error!("Hook delivery to {} failed: {}", url, e);
+        AppError::ServiceUnavailable("Sync service unavailable".to_string())
+    })?;
+    let status = actix_web::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(actix_web::http::StatusCode::BAD_GATEWAY);
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::ExternalService(format!("Invalid sync service response: {}", e)))?;
+    Ok(HttpResponse::build(status).content_type("application/json").body(body))
+}
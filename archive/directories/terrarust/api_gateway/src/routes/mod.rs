@@ -1,3 +1,12 @@
 pub mod ui;
 pub mod api;
-pub mod system;
\ No newline at end of file
+pub mod system;
+pub mod public;
+pub mod hooks;
+pub mod auth;
+pub mod sessions;
+pub mod sync_pairs;
+pub mod counties;
+pub mod users;
+pub mod preferences;
+pub mod onboarding;
@@ -0,0 +1,533 @@
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use terrafusion_common::api_keys;
+use terrafusion_common::geo::{parse_geometry, Boundary, Measurement};
+use terrafusion_common::models::gis_export::{CountyConfiguration, ExportApprovalPolicy, LayerDefinition, RateLimits};
+use terrafusion_common::usage::UsageQuotas;
+use terrafusion_common::utils::county_config;
+use uuid::Uuid;
+use crate::config::AppConfig;
+use crate::errors::{json_config, AppError};
+use crate::AppState;
+
+/// Onboarding steps in the order the wizard walks them. `county` is done
+/// by starting the onboarding; the others can be done, and most redone, in
+/// any order until every step is complete. More API keys can be issued
+/// after that.
+pub const STEPS: &[&str] = &["county", "boundary", "layers", "export_limits", "admin_user", "api_keys"];
+
+/// Scopes an API key can be issued with; the same ones the API key
+/// middleware checks
+const API_KEY_SCOPES: &[&str] = &["read", "write"];
+
+/// Keys issued per call of the `api_keys` step
+const MAX_KEYS_PER_STEP: usize = 10;
+
+/// Configure the county onboarding flow, nested in the `/counties` scope.
+/// Only platform administrators onboard counties.
+pub fn configure(cfg: &mut web::ServiceConfig, config: &AppConfig) {
+    cfg.route("/onboard", web::get().to(list_onboardings))
+        .route("/onboard", web::post().to(start_onboarding))
+        .route("/onboard/{county_id}", web::get().to(get_onboarding))
+        .service(
+            // The boundary step carries the county's full boundary geometry
+            web::resource("/onboard/{county_id}/{step}")
+                .app_data(json_config(config.max_geometry_body_bytes))
+                .route(web::put().to(complete_step))
+        );
+}
+
+/// Progress of one county through onboarding
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CountyOnboarding {
+    pub county_id: String,
+    pub county_name: String,
+    /// Completed steps: name to `{"completed_at": ..., "summary": {...}}`
+    pub steps: Value,
+    /// `in_progress` or `completed`
+    pub status: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+const ONBOARDING_COLUMNS: &str = "county_id, county_name, steps, status, created_by, created_at, updated_at, completed_at";
+
+impl CountyOnboarding {
+    fn is_done(&self, step: &str) -> bool {
+        self.steps.get(step).is_some()
+    }
+
+    /// The first step the wizard should resume at
+    fn next_step(&self) -> Option<&'static str> {
+        STEPS.iter().copied().find(|step| !self.is_done(step))
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "onboarding": self,
+            "steps": STEPS,
+            "next_step": self.next_step()
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartOnboardingRequest {
+    pub county_id: String,
+    pub county_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CountyStep {
+    county_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BoundaryStep {
+    /// GeoJSON, WKT or WKB polygon in WGS 84
+    boundary: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct LayersStep {
+    layers: Vec<LayerDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportLimitsStep {
+    rate_limits: RateLimits,
+    #[serde(default)]
+    usage_quotas: UsageQuotas,
+    export_approval: Option<ExportApprovalPolicy>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdminUserStep {
+    username: String,
+    email: String,
+    password: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiKeysStep {
+    keys: Vec<NewApiKey>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NewApiKey {
+    name: String,
+    scopes: Vec<String>,
+}
+
+/// Onboardings, unfinished ones first
+async fn list_onboardings(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    require_platform_admin(&req)?;
+    let onboardings = sqlx::query_as::<_, CountyOnboarding>(&format!(
+        "SELECT {} FROM county_onboardings ORDER BY status = 'completed', updated_at DESC",
+        ONBOARDING_COLUMNS
+    ))
+    .fetch_all(&data.db_pool)
+    .await
+    .map_err(AppError::from)?;
+    let onboardings: Vec<Value> = onboardings.iter().map(CountyOnboarding::to_json).collect();
+    Ok(HttpResponse::Ok().json(json!({ "onboardings": onboardings })))
+}
+
+/// Start onboarding a county: writes its configuration with the platform
+/// defaults and records the `county` step. Starting a county whose
+/// onboarding is under way returns it unchanged, so the wizard can resume.
+async fn start_onboarding(
+    req: HttpRequest,
+    body: web::Json<StartOnboardingRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let admin = require_platform_admin(&req)?;
+    let body = body.into_inner();
+    let county_id = body.county_id.trim().to_lowercase();
+    let county_name = body.county_name.trim().to_string();
+    validate_county_id(&county_id)?;
+    if county_name.is_empty() {
+        return Err(AppError::Validation("County name is required".to_string()).into());
+    }
+
+    if let Some(onboarding) = find_onboarding(&data, &county_id).await? {
+        if onboarding.status == "completed" {
+            return Err(AppError::Validation(format!("County {} is already onboarded", county_id)).into());
+        }
+        return Ok(HttpResponse::Ok().json(onboarding.to_json()));
+    }
+    let configured = county_config::list_configured_counties().map_err(|e| {
+        log::error!("Failed to list county configurations: {}", e);
+        AppError::InternalServerError("County configurations unavailable".to_string())
+    })?;
+    if configured.contains(&county_id) {
+        return Err(AppError::Validation(format!("County {} is already configured", county_id)).into());
+    }
+
+    let mut config = county_config::generate_default_config(&county_id);
+    config.county_name = county_name.clone();
+    save_config(&config).await?;
+
+    let steps = json!({ "county": step_record(json!({ "county_name": county_name })) });
+    let onboarding = sqlx::query_as::<_, CountyOnboarding>(&format!(
+        r#"
+        INSERT INTO county_onboardings (county_id, county_name, steps, created_by)
+        VALUES ($1, $2, $3, $4)
+        RETURNING {}
+        "#,
+        ONBOARDING_COLUMNS
+    ))
+    .bind(&county_id)
+    .bind(&county_name)
+    .bind(&steps)
+    .bind(&admin)
+    .fetch_one(&data.db_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    audit(&data, &req, &admin, &county_id, "county_onboarding_started", format!("Started onboarding {}", county_name)).await;
+    log::info!("{} started onboarding county {}", admin, county_id);
+    Ok(HttpResponse::Created().json(onboarding.to_json()))
+}
+
+async fn get_onboarding(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    require_platform_admin(&req)?;
+    let onboarding = load_onboarding(&data, &path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(onboarding.to_json()))
+}
+
+/// Complete one onboarding step. The body depends on the step; the
+/// response is the updated onboarding plus what the step produced, which
+/// for `api_keys` includes the keys themselves, shown this one time only.
+async fn complete_step(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    body: web::Json<Value>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let admin = require_platform_admin(&req)?;
+    let (county_id, step) = path.into_inner();
+    let onboarding = load_onboarding(&data, &county_id).await?;
+    // Keys can still be issued once onboarding is done
+    if onboarding.status == "completed" && step != "api_keys" {
+        return Err(AppError::Validation(format!("Onboarding of {} is finished", county_id)).into());
+    }
+    let body = body.into_inner();
+
+    let (summary, result) = match step.as_str() {
+        "county" => county_step(&onboarding, parse_step(body)?).await?,
+        "boundary" => boundary_step(&onboarding, parse_step(body)?).await?,
+        "layers" => layers_step(&onboarding, parse_step(body)?).await?,
+        "export_limits" => export_limits_step(&onboarding, parse_step(body)?).await?,
+        "admin_user" => admin_user_step(&data, &req, &admin, &onboarding, parse_step(body)?).await?,
+        "api_keys" => api_keys_step(&data, &admin, &onboarding, parse_step(body)?).await?,
+        _ => {
+            return Err(AppError::NotFound(format!(
+                "Unknown onboarding step {}; expected one of {}",
+                step,
+                STEPS.join(", ")
+            ))
+            .into())
+        }
+    };
+
+    let onboarding = record_step(&data, onboarding, &step, summary).await?;
+    audit(&data, &req, &admin, &county_id, "county_onboarding_step", format!("Completed onboarding step {} of {}", step, county_id)).await;
+    if onboarding.status == "completed" {
+        log::info!("{} finished onboarding county {}", admin, county_id);
+    }
+
+    let mut response = onboarding.to_json();
+    response["result"] = result;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Rename the county
+async fn county_step(onboarding: &CountyOnboarding, step: CountyStep) -> Result<(Value, Value)> {
+    let county_name = step.county_name.trim().to_string();
+    if county_name.is_empty() {
+        return Err(AppError::Validation("County name is required".to_string()).into());
+    }
+    let mut config = load_config(&onboarding.county_id).await?;
+    config.county_name = county_name.clone();
+    save_config(&config).await?;
+    Ok((json!({ "county_name": county_name }), Value::Null))
+}
+
+/// Set the boundary exports are clipped to and sync records are checked
+/// against
+async fn boundary_step(onboarding: &CountyOnboarding, step: BoundaryStep) -> Result<(Value, Value)> {
+    Boundary::from_value(&step.boundary).map_err(|e| AppError::Validation(e.to_string()))?;
+    let parsed = parse_geometry(&step.boundary).map_err(|e| AppError::Validation(e.to_string()))?;
+    let area_km2 = Measurement::Geodesic.area_km2(&parsed.geometry);
+
+    let mut config = load_config(&onboarding.county_id).await?;
+    config.boundary = Some(parsed.to_geojson());
+    save_config(&config).await?;
+    Ok((json!({ "area_km2": area_km2 }), json!({ "boundary": config.boundary })))
+}
+
+/// Replace the county's layers
+async fn layers_step(onboarding: &CountyOnboarding, step: LayersStep) -> Result<(Value, Value)> {
+    if step.layers.is_empty() {
+        return Err(AppError::Validation("Define at least one layer".to_string()).into());
+    }
+    let mut ids = std::collections::HashSet::new();
+    for layer in &step.layers {
+        if layer.id.trim().is_empty() || layer.name.trim().is_empty() {
+            return Err(AppError::Validation("Every layer needs an id and a name".to_string()).into());
+        }
+        if !ids.insert(layer.id.as_str()) {
+            return Err(AppError::Validation(format!("Layer id {} is used more than once", layer.id)).into());
+        }
+    }
+
+    let mut config = load_config(&onboarding.county_id).await?;
+    config.available_layers = step.layers;
+    save_config(&config).await?;
+    let layer_ids: Vec<&str> = config.available_layers.iter().map(|layer| layer.id.as_str()).collect();
+    Ok((json!({ "layers": layer_ids }), Value::Null))
+}
+
+/// Set export rate limits, monthly usage quotas and when exports need approval
+async fn export_limits_step(onboarding: &CountyOnboarding, step: ExportLimitsStep) -> Result<(Value, Value)> {
+    let limits = &step.rate_limits;
+    if limits.max_concurrent_exports == 0 || limits.max_exports_per_day == 0 || limits.max_exports_per_user == 0 {
+        return Err(AppError::Validation("Export limits must be at least 1".to_string()).into());
+    }
+    if limits.max_area_square_miles <= 0.0 {
+        return Err(AppError::Validation("The largest export area must be positive".to_string()).into());
+    }
+
+    let mut config = load_config(&onboarding.county_id).await?;
+    if let Some(unknown) = step
+        .export_approval
+        .iter()
+        .flat_map(|policy| policy.layers.iter())
+        .find(|layer| !config.available_layers.iter().any(|defined| &defined.id == *layer))
+    {
+        return Err(AppError::Validation(format!("Approval policy names unknown layer {}", unknown)).into());
+    }
+    config.rate_limits = step.rate_limits;
+    config.usage_quotas = step.usage_quotas;
+    config.export_approval = step.export_approval;
+    save_config(&config).await?;
+    Ok((
+        json!({
+            "rate_limits": config.rate_limits,
+            "usage_quotas": config.usage_quotas,
+            "export_approval": config.export_approval
+        }),
+        Value::Null,
+    ))
+}
+
+/// Create the county's first administrator. Done once; further accounts
+/// are managed under user administration.
+async fn admin_user_step(
+    data: &AppState,
+    req: &HttpRequest,
+    admin: &str,
+    onboarding: &CountyOnboarding,
+    step: AdminUserStep,
+) -> Result<(Value, Value)> {
+    if onboarding.is_done("admin_user") {
+        return Err(AppError::Validation(format!(
+            "The administrator of {} is already created; manage accounts under user administration",
+            onboarding.county_id
+        ))
+        .into());
+    }
+    let user = super::users::insert_user(data, &step.username, &step.email, step.password, "admin", &onboarding.county_id).await?;
+    super::users::audit(data, req, admin, &user, "user_created", format!("Created user {} as admin", user.username), None).await;
+    Ok((
+        json!({ "user_id": user.id, "username": user.username, "email": user.email }),
+        json!({ "user": user }),
+    ))
+}
+
+/// Issue API keys for the county's integrations. Each call adds keys to
+/// the ones issued before; the keys are returned once and only their
+/// hashes are kept.
+async fn api_keys_step(
+    data: &AppState,
+    admin: &str,
+    onboarding: &CountyOnboarding,
+    step: ApiKeysStep,
+) -> Result<(Value, Value)> {
+    if step.keys.is_empty() || step.keys.len() > MAX_KEYS_PER_STEP {
+        return Err(AppError::Validation(format!("Request between 1 and {} keys", MAX_KEYS_PER_STEP)).into());
+    }
+    for key in &step.keys {
+        if key.name.trim().is_empty() {
+            return Err(AppError::Validation("Every key needs a name".to_string()).into());
+        }
+        if let Some(scope) = key.scopes.iter().find(|scope| !API_KEY_SCOPES.contains(&scope.as_str())) {
+            return Err(AppError::Validation(format!(
+                "Unknown scope {}; expected one of {}",
+                scope,
+                API_KEY_SCOPES.join(", ")
+            ))
+            .into());
+        }
+    }
+
+    let mut issued = onboarding
+        .steps
+        .pointer("/api_keys/summary/keys")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let mut keys = Vec::with_capacity(step.keys.len());
+    let mut tx = data.db_pool.begin().await.map_err(AppError::from)?;
+    for key in step.keys {
+        let id = Uuid::new_v4();
+        let generated = api_keys::generate();
+        sqlx::query(
+            r#"
+            INSERT INTO api_keys (id, county_id, name, key_prefix, key_hash, scopes, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(id)
+        .bind(&onboarding.county_id)
+        .bind(key.name.trim())
+        .bind(&generated.prefix)
+        .bind(&generated.hash)
+        .bind(&key.scopes)
+        .bind(admin)
+        .execute(&mut tx)
+        .await
+        .map_err(AppError::from)?;
+
+        let listed = json!({ "id": id, "name": key.name.trim(), "prefix": generated.prefix, "scopes": key.scopes });
+        issued.push(listed.clone());
+        let mut shown = listed;
+        shown["key"] = json!(generated.key);
+        keys.push(shown);
+    }
+    tx.commit().await.map_err(AppError::from)?;
+    Ok((json!({ "keys": issued }), json!({ "keys": keys })))
+}
+
+fn parse_step<T: serde::de::DeserializeOwned>(body: Value) -> Result<T> {
+    serde_json::from_value(body).map_err(|e| AppError::Validation(format!("Invalid step: {}", e)).into())
+}
+
+fn step_record(summary: Value) -> Value {
+    json!({ "completed_at": Utc::now(), "summary": summary })
+}
+
+/// Save a completed step and finish the onboarding once every step is done
+async fn record_step(data: &AppState, onboarding: CountyOnboarding, step: &str, summary: Value) -> Result<CountyOnboarding> {
+    let mut steps = match onboarding.steps {
+        Value::Object(steps) => steps,
+        _ => Map::new(),
+    };
+    steps.insert(step.to_string(), step_record(summary.clone()));
+    let finished = STEPS.iter().all(|step| steps.contains_key(*step));
+    let county_name = match step {
+        "county" => summary["county_name"].as_str().unwrap_or(&onboarding.county_name).to_string(),
+        _ => onboarding.county_name,
+    };
+
+    sqlx::query_as::<_, CountyOnboarding>(&format!(
+        r#"
+        UPDATE county_onboardings
+        SET county_name = $2, steps = $3, status = $4, updated_at = NOW(),
+            completed_at = CASE WHEN $4 = 'completed' THEN COALESCE(completed_at, NOW()) END
+        WHERE county_id = $1
+        RETURNING {}
+        "#,
+        ONBOARDING_COLUMNS
+    ))
+    .bind(&onboarding.county_id)
+    .bind(&county_name)
+    .bind(Value::Object(steps))
+    .bind(if finished { "completed" } else { "in_progress" })
+    .fetch_one(&data.db_pool)
+    .await
+    .map_err(|e| AppError::from(e).into())
+}
+
+async fn find_onboarding(data: &AppState, county_id: &str) -> Result<Option<CountyOnboarding>> {
+    sqlx::query_as::<_, CountyOnboarding>(&format!(
+        "SELECT {} FROM county_onboardings WHERE county_id = $1",
+        ONBOARDING_COLUMNS
+    ))
+    .bind(county_id)
+    .fetch_optional(&data.db_pool)
+    .await
+    .map_err(|e| AppError::from(e).into())
+}
+
+async fn load_onboarding(data: &AppState, county_id: &str) -> Result<CountyOnboarding> {
+    find_onboarding(data, county_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("No onboarding of county {}", county_id)).into())
+}
+
+async fn load_config(county_id: &str) -> Result<CountyConfiguration> {
+    county_config::load_county_configuration(county_id).await.map_err(|e| {
+        log::error!("Failed to load configuration of county {}: {}", county_id, e);
+        AppError::InternalServerError("County configuration unavailable".to_string()).into()
+    })
+}
+
+async fn save_config(config: &CountyConfiguration) -> Result<()> {
+    county_config::save_county_configuration(config).await.map_err(|e| {
+        log::error!("Failed to save configuration of county {}: {}", config.county_id, e);
+        AppError::InternalServerError("County configuration could not be saved".to_string()).into()
+    })
+}
+
+/// County IDs name configuration directories, so only lowercase letters,
+/// digits, `-` and `_` are allowed
+fn validate_county_id(county_id: &str) -> Result<()> {
+    let valid = !county_id.is_empty()
+        && county_id.len() <= 64
+        && county_id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        return Err(AppError::Validation(
+            "County ID must be 1-64 lowercase letters, digits, '-' or '_'".to_string(),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn require_platform_admin(req: &HttpRequest) -> Result<String> {
+    let (admin, county) = super::system::require_admin(req)?;
+    if !county.is_platform_admin {
+        return Err(AppError::Authorization("Only platform administrators onboard counties".to_string()).into());
+    }
+    Ok(admin)
+}
+
+/// Record an onboarding change in the audit log; best effort
+async fn audit(data: &AppState, req: &HttpRequest, admin: &str, county_id: &str, event_type: &str, description: String) {
+    let ip_address = req.connection_info().realip_remote_addr().map(str::to_string);
+    let result = sqlx::query(
+        r#"
+        INSERT INTO audit_log (
+            id, event_type, resource_type, resource_id, description, username, county_id,
+            ip_address, severity, created_at
+        ) VALUES ($1, $2, 'county', $3, $4, $5, $3, $6, 'INFO', NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(event_type)
+    .bind(county_id)
+    .bind(&description)
+    .bind(admin)
+    .bind(ip_address)
+    .execute(&data.db_pool)
+    .await;
+    if let Err(e) = result {
+        log::error!("Failed to audit {} of county {}: {}", event_type, county_id, e);
+    }
+}
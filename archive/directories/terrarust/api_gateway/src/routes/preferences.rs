@@ -0,0 +1,102 @@
+use actix_web::{cookie::Cookie, web, HttpMessage, HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::config::AppConfig;
+use crate::errors::AppError;
+use crate::middlewares::auth::Claims;
+use crate::AppState;
+
+/// Cookie the layout reads before first paint to pick the color theme.
+/// Not HttpOnly: the page script is what applies it.
+pub const THEME_COOKIE: &str = "tf_theme";
+
+/// Color theme of the UI; `auto` follows the operating system
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(Theme::Auto),
+            "light" => Some(Theme::Light),
+            "dark" => Some(Theme::Dark),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::Auto => "auto",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+}
+
+/// The theme cookie, kept for a year like the language switcher's
+pub fn theme_cookie(config: &AppConfig, theme: Theme) -> Cookie<'static> {
+    config
+        .cookie(THEME_COOKIE, theme.as_str().to_string())
+        .max_age(actix_web::cookie::time::Duration::days(365))
+        .finish()
+}
+
+/// Configure the signed-in user's UI preferences
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/preferences")
+            .route(web::get().to(get_preferences))
+            .route(web::put().to(update_preferences))
+    );
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePreferencesRequest {
+    pub theme: Theme,
+}
+
+async fn get_preferences(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let user_id = signed_in_user(&req)?;
+    let theme = sqlx::query_scalar::<_, String>("SELECT theme FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&data.db_pool)
+        .await
+        .map_err(AppError::from)?
+        .and_then(|theme| Theme::parse(&theme))
+        .unwrap_or_default();
+    Ok(HttpResponse::Ok().json(json!({ "theme": theme })))
+}
+
+/// Save the theme to the user's profile, so it follows them to other
+/// browsers, and to the cookie for this one
+async fn update_preferences(
+    req: HttpRequest,
+    body: web::Json<UpdatePreferencesRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let user_id = signed_in_user(&req)?;
+    let theme = body.theme;
+    sqlx::query("UPDATE users SET theme = $2, updated_at = NOW() WHERE id = $1")
+        .bind(user_id)
+        .bind(theme.as_str())
+        .execute(&data.db_pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(HttpResponse::Ok()
+        .cookie(theme_cookie(&data.config, theme))
+        .json(json!({ "theme": theme })))
+}
+
+fn signed_in_user(req: &HttpRequest) -> Result<uuid::Uuid> {
+    req.extensions()
+        .get::<Claims>()
+        .and_then(|claims| claims.sub.parse().ok())
+        .ok_or_else(|| AppError::Authentication("Sign in required".to_string()).into())
+}
@@ -0,0 +1,159 @@
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Result};
+use futures_util::TryStreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use terrafusion_common::http_client;
+use uuid::Uuid;
+use crate::errors::AppError;
+use crate::middlewares::RateLimitMiddleware;
+use crate::AppState;
+
+/// Requests per second per client and path on the public portal, well below
+/// the authenticated API since anyone can reach it
+const PUBLIC_REQUESTS_PER_SECOND: usize = 2;
+const PUBLIC_BURST_SIZE: usize = 10;
+
+/// Response headers passed through from the GIS export service on downloads
+const DOWNLOAD_HEADERS: &[&str] = &["content-type", "content-disposition", "content-length", "x-checksum-sha256"];
+
+/// Configure the unauthenticated, read-only portal for published exports
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/public")
+            .wrap(RateLimitMiddleware {
+                requests_per_second: PUBLIC_REQUESTS_PER_SECOND,
+                burst_size: PUBLIC_BURST_SIZE,
+                ..RateLimitMiddleware::default()
+            })
+            .route("/exports", web::get().to(list_published_exports))
+            .route("/exports/{id}", web::get().to(get_published_export))
+            .route("/exports/{id}/download", web::get().to(download_published_export))
+    );
+}
+
+/// Exports counties have published, newest first; filter with `county_id`
+/// and `export_format`. `Accept` or `format` picks JSON, GeoJSON, a DCAT
+/// catalog in JSON-LD for open-data aggregators, or an HTML page.
+async fn list_published_exports(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let mut url = format!("{}/gis-export/public/exports", data.config.gis_export_service_url);
+    if !req.query_string().is_empty() {
+        url = format!("{}?{}", url, req.query_string());
+    }
+    forward_json(with_accept(&req, http_client::shared().get(&url))).await
+}
+
+/// Title, description and file metadata of one published export, in the
+/// same representations as the listing
+async fn get_published_export(req: HttpRequest, path: web::Path<Uuid>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let mut url = format!("{}/gis-export/public/exports/{}", data.config.gis_export_service_url, path.into_inner());
+    if !req.query_string().is_empty() {
+        url = format!("{}?{}", url, req.query_string());
+    }
+    forward_json(with_accept(&req, http_client::shared().get(&url))).await
+}
+
+/// Pass the client's `Accept` header on, so the service can negotiate
+fn with_accept(req: &HttpRequest, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match req.headers().get(header::ACCEPT).and_then(|value| value.to_str().ok()) {
+        Some(accept) => request.header(header::ACCEPT.as_str(), accept),
+        None => request,
+    }
+}
+
+/// Stream a published export's file to the citizen
+async fn download_published_export(path: web::Path<Uuid>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let url = format!("{}/gis-export/public/exports/{}/download", data.config.gis_export_service_url, path.into_inner());
+    let response = http_client::shared()
+        .get(&url)
+        .send()
+        .await
+        .map_err(|_| AppError::ServiceUnavailable("GIS Export service unavailable".to_string()))?;
+
+    let status = actix_web::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(actix_web::http::StatusCode::BAD_GATEWAY);
+    let mut builder = HttpResponse::build(status);
+    for name in DOWNLOAD_HEADERS {
+        if let Some(value) = response.headers().get(*name).and_then(|value| value.to_str().ok()) {
+            builder.insert_header((*name, value.to_string()));
+        }
+    }
+
+    let body = response
+        .bytes_stream()
+        .map_err(|e| actix_web::error::ErrorBadGateway(format!("Download interrupted: {}", e)));
+    Ok(builder.streaming(body))
+}
+
+/// Body for publishing an export from the admin UI
+#[derive(Debug, Deserialize)]
+pub struct PublishExportBody {
+    pub title: String,
+    pub description: Option<String>,
+}
+
+/// Publish a completed export of the administrator's county on the portal
+pub async fn publish_export(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    body: web::Json<PublishExportBody>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (user, county) = super::system::require_admin(&req)?;
+    let body = body.into_inner();
+    let url = format!("{}/gis-export/jobs/{}/publish", data.config.gis_export_service_url, path.into_inner());
+
+    let request = http_client::shared().put(&url).json(&json!({
+        "title": body.title,
+        "description": body.description,
+        "published_by": user,
+        "county_id": (!county.is_platform_admin).then(|| county.county_id.clone()),
+    }));
+    forward_json(request).await
+}
+
+/// Withdraw an export from the portal
+pub async fn unpublish_export(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let url = format!("{}/gis-export/jobs/{}/publish", data.config.gis_export_service_url, path.into_inner());
+
+    let mut request = http_client::shared().delete(&url);
+    if !county.is_platform_admin {
+        request = request.query(&[("county_id", &county.county_id)]);
+    }
+    forward_json(request).await
+}
+
+/// Send a request to the GIS export service and relay its answer, JSON
+/// unless the service says otherwise
+async fn forward_json(request: reqwest::RequestBuilder) -> Result<HttpResponse> {
+    let response = request
+        .send()
+        .await
+        .map_err(|_| AppError::ServiceUnavailable("GIS Export service unavailable".to_string()))?;
+    let status = actix_web::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(actix_web::http::StatusCode::BAD_GATEWAY);
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE.as_str())
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/json")
+        .to_string();
+    let vary = response.headers().get(header::VARY.as_str()).and_then(|value| value.to_str().ok()).map(str::to_string);
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::ExternalService(format!("Invalid GIS Export service response: {}", e)))?;
+
+    let mut builder = HttpResponse::build(status);
+    if let Some(vary) = vary {
+        builder.insert_header((header::VARY, vary));
+    }
+    if body.is_empty() {
+        return Ok(builder.finish());
+    }
+    Ok(builder.content_type(content_type).body(body))
+}
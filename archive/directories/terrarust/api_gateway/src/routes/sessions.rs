@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
+use serde_json::json;
+use terrafusion_common::sessions::{Session, SessionFilter};
+use crate::errors::AppError;
+use crate::AppState;
+
+/// Configure session administration; county administrators see and end
+/// sessions of their own county only
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/sessions")
+            .route("", web::get().to(list_sessions))
+            .route("/users/{user_id}", web::delete().to(revoke_user_sessions))
+            .route("/{id}", web::delete().to(revoke_session))
+    );
+}
+
+/// Query parameters for listing sessions
+#[derive(Debug, Deserialize)]
+pub struct ListSessionsQuery {
+    /// Only this user's sessions (their email)
+    pub user_id: Option<String>,
+}
+
+/// Active sessions grouped by user
+async fn list_sessions(
+    req: HttpRequest,
+    query: web::Query<ListSessionsQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let filter = SessionFilter {
+        user_id: query.into_inner().user_id,
+        county_id: (!county.is_platform_admin).then(|| county.county_id.clone()),
+    };
+
+    let sessions = data.sessions.list_active(&filter).await.map_err(session_error)?;
+    let total = sessions.len();
+    let mut by_user: BTreeMap<String, Vec<Session>> = BTreeMap::new();
+    for session in sessions {
+        by_user.entry(session.user_id.clone()).or_default().push(session);
+    }
+    let users: Vec<_> = by_user
+        .into_iter()
+        .map(|(user_id, sessions)| json!({ "user_id": user_id, "sessions": sessions }))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "users": users,
+        "total": total,
+        "idle_timeout_seconds": data.sessions.timeouts().idle.as_secs(),
+        "absolute_timeout_seconds": data.sessions.timeouts().absolute.as_secs()
+    })))
+}
+
+/// Force one session to sign out
+async fn revoke_session(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (admin, county) = super::system::require_admin(&req)?;
+    let id = path.into_inner();
+    let scope = (!county.is_platform_admin).then_some(county.county_id.as_str());
+
+    if !data.sessions.revoke(&id, scope).await.map_err(session_error)? {
+        return Err(AppError::NotFound("Session not found".to_string()).into());
+    }
+    log::info!("{} ended session {}", admin, id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Force a user to sign out everywhere
+async fn revoke_user_sessions(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (admin, county) = super::system::require_admin(&req)?;
+    let user_id = path.into_inner();
+    let scope = (!county.is_platform_admin).then_some(county.county_id.as_str());
+
+    let revoked = data.sessions.revoke_user(&user_id, scope).await.map_err(session_error)?;
+    log::info!("{} ended {} sessions of {}", admin, revoked, user_id);
+    Ok(HttpResponse::Ok().json(json!({
+        "user_id": user_id,
+        "revoked": revoked
+    })))
+}
+
+fn session_error(e: terrafusion_common::Error) -> AppError {
+    log::error!("Session store error: {}", e);
+    AppError::ServiceUnavailable("Session store unavailable".to_string())
+}
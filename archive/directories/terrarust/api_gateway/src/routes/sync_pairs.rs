@@ -0,0 +1,490 @@
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Result};
+use serde_json::Value;
+use terrafusion_common::http_client;
+use terrafusion_common::tenancy::{CountyContext, COUNTY_HEADER, PLATFORM_ADMIN_HEADER, ROLES_HEADER};
+use crate::errors::AppError;
+use crate::{proxy, AppState};
+
+/// Configure the sync pair, sync operation, pipeline, credential and import API
+/// used by the admin pages. Anyone signed in can read their county's pairs,
+/// pipelines and imports; changing them, starting or canceling operations and
+/// runs, uploading files and anything to do with credentials takes an
+/// administrator.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/sync-pairs")
+            .route("", web::get().to(list_sync_pairs))
+            .route("", web::post().to(create_sync_pair))
+            .route("/discover-mappings", web::post().to(discover_mappings))
+            .route("/preview", web::post().to(preview_sync_pair))
+            .route("/{id}", web::get().to(get_sync_pair))
+            .route("/{id}", web::put().to(update_sync_pair))
+            .route("/{id}/field-mappings", web::put().to(update_field_mappings))
+            .route("/{id}/toggle", web::post().to(toggle_sync_pair))
+            .route("/{id}/validate", web::post().to(validate_sync_pair))
+            .route("/{id}/schema-drift", web::get().to(get_schema_drift))
+            .route("/{id}/schema-drift/check", web::post().to(check_schema_drift))
+            .route("/{id}/schema-drift/acknowledge", web::post().to(acknowledge_schema_drift))
+            .route("/{id}/hooks", web::get().to(list_hooks))
+            .route("/{id}/hooks", web::post().to(create_hook))
+            .route("/{id}/hooks/{hook_id}", web::delete().to(delete_hook))
+            .route("/{id}/hooks/{hook_id}/rotate-secret", web::post().to(rotate_hook_secret))
+            .route("/{id}/hooks/{hook_id}/toggle", web::post().to(toggle_hook))
+    )
+    .service(
+        web::scope("/sync-operations")
+            .route("", web::get().to(list_sync_operations))
+            .route("", web::post().to(start_sync_operation))
+            .route("/{id}", web::get().to(get_sync_operation))
+            .route("/{id}/detail", web::get().to(get_sync_operation_detail))
+            .route("/{id}/diffs", web::get().to(list_sync_operation_diffs))
+            .route("/{id}/cancel", web::post().to(cancel_sync_operation))
+    )
+    .service(
+        web::scope("/pipelines")
+            .route("", web::get().to(list_pipelines))
+            .route("", web::post().to(create_pipeline))
+            .route("/{id}", web::get().to(get_pipeline))
+            .route("/{id}", web::put().to(update_pipeline))
+            .route("/{id}", web::delete().to(delete_pipeline))
+            .route("/{id}/toggle", web::post().to(toggle_pipeline))
+            .route("/{id}/runs", web::get().to(list_pipeline_runs))
+            .route("/{id}/runs", web::post().to(start_pipeline_run))
+            .route("/{id}/runs/{run_id}", web::get().to(get_pipeline_run))
+            .route("/{id}/runs/{run_id}/cancel", web::post().to(cancel_pipeline_run))
+    )
+    .service(
+        web::scope("/credentials")
+            .route("", web::get().to(list_credentials))
+            .route("", web::post().to(create_credential))
+            .route("/{id}", web::get().to(get_credential))
+            .route("/{id}", web::delete().to(delete_credential))
+            .route("/{id}/rotate", web::post().to(rotate_credential))
+            .route("/{id}/test", web::post().to(test_credential))
+    )
+    .service(
+        web::scope("/imports")
+            .route("/files", web::get().to(list_imports))
+            .route("/files", web::post().to(upload_import))
+            .route("/files/{id}", web::get().to(get_import))
+            .route("/files/{id}", web::delete().to(delete_import))
+    );
+}
+
+async fn list_sync_pairs(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let path = match req.query_string() {
+        "" => "/sync-pairs".to_string(),
+        query => format!("/sync-pairs?{}", query),
+    };
+    forward_conditional(&req, &data, &county, &path).await
+}
+
+async fn get_sync_pair(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    forward_conditional(&req, &data, &county, &format!("/sync-pairs/{}", path)).await
+}
+
+async fn create_sync_pair(req: HttpRequest, body: web::Json<Value>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::POST, "/sync-pairs", Some(body.into_inner())).await
+}
+
+async fn update_sync_pair(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<Value>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::PUT, &format!("/sync-pairs/{}", path), Some(body.into_inner())).await
+}
+
+async fn update_field_mappings(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<Value>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let path = format!("/sync-pairs/{}/field-mappings", path);
+    forward(&data, &county, reqwest::Method::PUT, &path, Some(body.into_inner())).await
+}
+
+async fn toggle_sync_pair(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<Value>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let path = format!("/sync-pairs/{}/toggle", path);
+    forward(&data, &county, reqwest::Method::POST, &path, Some(body.into_inner())).await
+}
+
+async fn get_schema_drift(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    forward(&data, &county, reqwest::Method::GET, &format!("/sync-pairs/{}/schema-drift", path), None).await
+}
+
+async fn check_schema_drift(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let path = format!("/sync-pairs/{}/schema-drift/check", path);
+    forward(&data, &county, reqwest::Method::POST, &path, None).await
+}
+
+async fn acknowledge_schema_drift(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let path = format!("/sync-pairs/{}/schema-drift/acknowledge", path);
+    forward(&data, &county, reqwest::Method::POST, &path, None).await
+}
+
+async fn list_hooks(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::GET, &format!("/sync-pairs/{}/hooks", path), None).await
+}
+
+/// Create an inbound hook; the answer holds its secret, shown only this once
+async fn create_hook(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<Value>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (user, county) = super::system::require_admin(&req)?;
+    let mut body = body.into_inner();
+    if let Some(fields) = body.as_object_mut() {
+        fields.insert("created_by".to_string(), Value::String(user));
+    }
+    forward(&data, &county, reqwest::Method::POST, &format!("/sync-pairs/{}/hooks", path), Some(body)).await
+}
+
+async fn rotate_hook_secret(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let (id, hook_id) = path.into_inner();
+    let path = format!("/sync-pairs/{}/hooks/{}/rotate-secret", id, hook_id);
+    forward(&data, &county, reqwest::Method::POST, &path, None).await
+}
+
+async fn toggle_hook(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    body: web::Json<Value>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let (id, hook_id) = path.into_inner();
+    let path = format!("/sync-pairs/{}/hooks/{}/toggle", id, hook_id);
+    forward(&data, &county, reqwest::Method::POST, &path, Some(body.into_inner())).await
+}
+
+async fn delete_hook(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let (id, hook_id) = path.into_inner();
+    let path = format!("/sync-pairs/{}/hooks/{}", id, hook_id);
+    forward(&data, &county, reqwest::Method::DELETE, &path, None).await
+}
+
+/// Config checks and a live connection test of both ends
+async fn validate_sync_pair(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::POST, &format!("/sync-pairs/{}/validate", path), None).await
+}
+
+/// Source and target fields with proposed mappings, for the mapping editor
+async fn discover_mappings(req: HttpRequest, body: web::Json<Value>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::POST, "/sync-pairs/discover-mappings", Some(body.into_inner())).await
+}
+
+async fn preview_sync_pair(req: HttpRequest, body: web::Json<Value>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::POST, "/sync-pairs/preview", Some(body.into_inner())).await
+}
+
+/// The county's operations, filtered and paged like the sync service's list
+async fn list_sync_operations(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let path = match req.query_string() {
+        "" => "/sync-operations".to_string(),
+        query => format!("/sync-operations?{}", query),
+    };
+    forward(&data, &county, reqwest::Method::GET, &path, None).await
+}
+
+async fn start_sync_operation(req: HttpRequest, body: web::Json<Value>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::POST, "/sync-operations", Some(body.into_inner())).await
+}
+
+async fn get_sync_operation(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    forward(&data, &county, reqwest::Method::GET, &format!("/sync-operations/{}", path), None).await
+}
+
+/// Stored operation with its timeline, for the operation detail page
+async fn get_sync_operation_detail(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    forward(&data, &county, reqwest::Method::GET, &format!("/sync-operations/{}/detail", path), None).await
+}
+
+async fn list_sync_operation_diffs(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let path = match req.query_string() {
+        "" => format!("/sync-operations/{}/diffs", path),
+        query => format!("/sync-operations/{}/diffs?{}", path, query),
+    };
+    forward(&data, &county, reqwest::Method::GET, &path, None).await
+}
+
+async fn cancel_sync_operation(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::DELETE, &format!("/sync-operations/{}", path), None).await
+}
+
+async fn list_pipelines(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let path = match req.query_string() {
+        "" => "/pipelines".to_string(),
+        query => format!("/pipelines?{}", query),
+    };
+    forward(&data, &county, reqwest::Method::GET, &path, None).await
+}
+
+async fn create_pipeline(req: HttpRequest, body: web::Json<Value>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (user, county) = super::system::require_admin(&req)?;
+    let mut body = body.into_inner();
+    if let Some(fields) = body.as_object_mut() {
+        fields.insert("created_by".to_string(), Value::String(user));
+    }
+    forward(&data, &county, reqwest::Method::POST, "/pipelines", Some(body)).await
+}
+
+async fn get_pipeline(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    forward(&data, &county, reqwest::Method::GET, &format!("/pipelines/{}", path), None).await
+}
+
+async fn update_pipeline(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<Value>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::PUT, &format!("/pipelines/{}", path), Some(body.into_inner())).await
+}
+
+async fn delete_pipeline(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::DELETE, &format!("/pipelines/{}", path), None).await
+}
+
+async fn toggle_pipeline(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<Value>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::POST, &format!("/pipelines/{}/toggle", path), Some(body.into_inner())).await
+}
+
+async fn list_pipeline_runs(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let path = match req.query_string() {
+        "" => format!("/pipelines/{}/runs", path),
+        query => format!("/pipelines/{}/runs?{}", path, query),
+    };
+    forward(&data, &county, reqwest::Method::GET, &path, None).await
+}
+
+/// Runs are credited to the signed-in administrator
+async fn start_pipeline_run(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (user, county) = super::system::require_admin(&req)?;
+    let body = serde_json::json!({ "initiated_by": user });
+    forward(&data, &county, reqwest::Method::POST, &format!("/pipelines/{}/runs", path), Some(body)).await
+}
+
+/// Status of a run and each of its steps
+async fn get_pipeline_run(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let (id, run_id) = path.into_inner();
+    forward(&data, &county, reqwest::Method::GET, &format!("/pipelines/{}/runs/{}", id, run_id), None).await
+}
+
+async fn cancel_pipeline_run(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let (id, run_id) = path.into_inner();
+    let path = format!("/pipelines/{}/runs/{}/cancel", id, run_id);
+    forward(&data, &county, reqwest::Method::POST, &path, None).await
+}
+
+async fn list_credentials(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let path = match req.query_string() {
+        "" => "/credentials".to_string(),
+        query => format!("/credentials?{}", query),
+    };
+    forward(&data, &county, reqwest::Method::GET, &path, None).await
+}
+
+async fn create_credential(req: HttpRequest, body: web::Json<Value>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (user, county) = super::system::require_admin(&req)?;
+    let mut body = body.into_inner();
+    if let Some(fields) = body.as_object_mut() {
+        fields.insert("created_by".to_string(), Value::String(user));
+    }
+    forward(&data, &county, reqwest::Method::POST, "/credentials", Some(body)).await
+}
+
+async fn get_credential(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::GET, &format!("/credentials/{}", path), None).await
+}
+
+async fn delete_credential(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::DELETE, &format!("/credentials/{}", path), None).await
+}
+
+async fn rotate_credential(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<Value>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let path = format!("/credentials/{}/rotate", path);
+    forward(&data, &county, reqwest::Method::POST, &path, Some(body.into_inner())).await
+}
+
+/// Test a credential against the config in the body, or the first pair using it
+async fn test_credential(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<web::Json<Value>>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let path = format!("/credentials/{}/test", path);
+    forward(&data, &county, reqwest::Method::POST, &path, body.map(web::Json::into_inner)).await
+}
+
+async fn list_imports(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    let path = match req.query_string() {
+        "" => "/imports/files".to_string(),
+        query => format!("/imports/files?{}", query),
+    };
+    forward(&data, &county, reqwest::Method::GET, &path, None).await
+}
+
+/// Stream a multipart upload through to the sync service, credited to the
+/// signed-in administrator. Uploads can run to hundreds of megabytes, so the
+/// body is never held here; clients passing `upload_id` can poll the import
+/// for progress meanwhile.
+async fn upload_import(req: HttpRequest, payload: web::Payload, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (user, county) = super::system::require_admin(&req)?;
+    let query = web::Query::<Vec<(String, String)>>::from_query(req.query_string())
+        .map_err(|e| AppError::BadRequest(format!("Invalid query string: {}", e)))?;
+    let params = query
+        .into_inner()
+        .into_iter()
+        .filter(|(key, _)| key != "uploaded_by")
+        .chain([("uploaded_by".to_string(), user)]);
+    let url = reqwest::Url::parse_with_params(&format!("{}/imports/files", data.config.sync_service_url), params)
+        .map_err(|e| AppError::InternalServerError(format!("Invalid sync service URL: {}", e)))?;
+    proxy::stream_request(&req, payload, &county, url.as_str(), "Sync service", data.config.max_upload_bytes).await
+}
+
+/// An import with its upload progress
+async fn get_import(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let county = super::system::require_county(&req)?;
+    forward(&data, &county, reqwest::Method::GET, &format!("/imports/files/{}", path), None).await
+}
+
+async fn delete_import(req: HttpRequest, path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    forward(&data, &county, reqwest::Method::DELETE, &format!("/imports/files/{}", path), None).await
+}
+
+/// Pass a request on to the sync service as the caller's county and return
+/// its answer unchanged
+async fn forward(
+    data: &AppState,
+    county: &CountyContext,
+    method: reqwest::Method,
+    path: &str,
+    body: Option<Value>,
+) -> Result<HttpResponse> {
+    let url = format!("{}{}", data.config.sync_service_url, path);
+    let mut request = service_request(county, method, &url);
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    relay(&url, request).await
+}
+
+/// A GET passed on with the client's `If-None-Match` and `If-Modified-Since`,
+/// so a dashboard polling an unchanged resource gets the service's 304
+async fn forward_conditional(req: &HttpRequest, data: &AppState, county: &CountyContext, path: &str) -> Result<HttpResponse> {
+    let url = format!("{}{}", data.config.sync_service_url, path);
+    let mut request = service_request(county, reqwest::Method::GET, &url);
+    for name in [header::IF_NONE_MATCH, header::IF_MODIFIED_SINCE] {
+        if let Some(value) = req.headers().get(&name).and_then(|value| value.to_str().ok()) {
+            request = request.header(name.as_str(), value);
+        }
+    }
+    relay(&url, request).await
+}
+
+fn service_request(county: &CountyContext, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+    http_client::shared()
+        .request(method, url)
+        .header(COUNTY_HEADER, &county.county_id)
+        .header(PLATFORM_ADMIN_HEADER, county.is_platform_admin.to_string())
+        .header(ROLES_HEADER, county.roles.join(","))
+}
+
+/// Send a request to the sync service and relay its status, JSON body and
+/// cache validators
+async fn relay(url: &str, request: reqwest::RequestBuilder) -> Result<HttpResponse> {
+    let response = request.send().await.map_err(|e| {
+        log::error!("Proxy to {} failed: {}", url, e);
+        AppError::ServiceUnavailable("Sync service unavailable".to_string())
+    })?;
+    let status = actix_web::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(actix_web::http::StatusCode::BAD_GATEWAY);
+    let mut builder = HttpResponse::build(status);
+    for name in [header::ETAG, header::LAST_MODIFIED, header::CACHE_CONTROL] {
+        if let Some(value) = response.headers().get(name.as_str()).and_then(|value| value.to_str().ok()) {
+            builder.insert_header((name, value.to_string()));
+        }
+    }
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::ExternalService(format!("Invalid sync service response: {}", e)))?;
+
+    if body.is_empty() {
+        return Ok(builder.finish());
+    }
+    Ok(builder.content_type("application/json").body(body))
+}
@@ -1,5 +1,10 @@
-use actix_web::{web, HttpResponse, Result};
-use serde_json::json;
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Result};
+use serde_json::{json, Value};
+use terrafusion_common::http_client;
+use terrafusion_common::maintenance::MaintenanceStatus;
+use terrafusion_common::tenancy::{CountyContext, COUNTY_HEADER, PLATFORM_ADMIN_HEADER, ROLES_HEADER};
+use crate::errors::AppError;
+use crate::middlewares::auth::Claims;
 use crate::AppState;
 
 /// Configure system routes
@@ -15,6 +20,16 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     .service(
         web::resource("/status")
             .route(web::get().to(status))
+    )
+    .service(
+        web::resource("/config/reload")
+            .route(web::post().to(reload_config))
+    )
+    .service(
+        web::resource("/maintenance")
+            .route(web::get().to(get_maintenance))
+            .route(web::post().to(enable_maintenance))
+            .route(web::delete().to(disable_maintenance))
     );
 }
 
@@ -67,8 +82,143 @@ async fn status(data: web::Data<AppState>) -> Result<HttpResponse> {
 
 /// Helper function to check service health
 async fn check_service_health(url: &str) -> &'static str {
-    match reqwest::get(&format!("{}/health", url)).await {
+    match http_client::shared().get(&format!("{}/health", url)).send().await {
         Ok(response) if response.status().is_success() => "healthy",
         _ => "unavailable"
     }
-}
\ No newline at end of file
+}
+
+/// Reload runtime settings on the gateway and downstream services (admin only)
+async fn reload_config(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let is_admin = req.extensions()
+        .get::<Claims>()
+        .map(|claims| claims.has_role("admin"))
+        .unwrap_or(false);
+    
+    if !is_admin {
+        return Err(AppError::Authorization("Administrator role required".to_string()).into());
+    }
+    
+    let changed = data.runtime_config
+        .reload()
+        .map_err(|e| AppError::InternalServerError(format!("Failed to reload settings: {}", e)))?;
+    
+    // Ask the sync service to reload as well; failures are reported, not fatal
+    let sync_service = match http_client::shared()
+        .post(&format!("{}/system/config/reload", data.config.sync_service_url))
+        .send()
+        .await
+    {
+        Ok(response) if response.status().is_success() => "reloaded",
+        _ => "unavailable"
+    };
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "gateway": {
+            "changed": changed,
+            "settings": *data.runtime_config.current()
+        },
+        "services": {
+            "sync_service": sync_service
+        },
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    })))
+}
+
+/// Claims and county scope of an administrator, or an authorization error
+pub(crate) fn require_admin(req: &HttpRequest) -> Result<(String, CountyContext)> {
+    let extensions = req.extensions();
+    let user = extensions
+        .get::<Claims>()
+        .filter(|claims| claims.has_role("admin") || claims.has_role("platform_admin"))
+        .map(|claims| claims.email.clone());
+    let county = extensions.get::<CountyContext>().cloned();
+    
+    match (user, county) {
+        (Some(user), Some(county)) => Ok((user, county)),
+        _ => Err(AppError::Authorization("Administrator role required".to_string()).into()),
+    }
+}
+
+/// The signed-in caller's county
+pub(crate) fn require_county(req: &HttpRequest) -> Result<CountyContext> {
+    req.extensions()
+        .get::<CountyContext>()
+        .cloned()
+        .ok_or_else(|| AppError::Authentication("Sign in required".to_string()).into())
+}
+
+/// Current maintenance windows, as last seen by the gateway and the sync service
+async fn get_maintenance(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    require_admin(&req)?;
+    forward_maintenance(&data, reqwest::Method::GET, None, None, None).await
+}
+
+/// Put a county (`county_id` in the body) or the whole platform into maintenance mode.
+///
+/// New scheduled operations are held back and the gateway starts answering
+/// 503; running operations are allowed to finish.
+async fn enable_maintenance(
+    req: HttpRequest,
+    body: web::Json<Value>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (user, county) = require_admin(&req)?;
+    let mut body = body.into_inner();
+    if let Some(object) = body.as_object_mut() {
+        object.insert("started_by".to_string(), json!(user));
+    }
+    forward_maintenance(&data, reqwest::Method::PUT, Some(&county), None, Some(body)).await
+}
+
+/// End maintenance mode for a county (`?county_id=`) or the whole platform
+async fn disable_maintenance(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = require_admin(&req)?;
+    forward_maintenance(&data, reqwest::Method::DELETE, Some(&county), Some(req.query_string()), None).await
+}
+
+/// Send a maintenance request to the sync service, which owns the state, and
+/// update the gateway's copy from its answer so the change applies immediately
+async fn forward_maintenance(
+    data: &AppState,
+    method: reqwest::Method,
+    county: Option<&CountyContext>,
+    query: Option<&str>,
+    body: Option<Value>,
+) -> Result<HttpResponse> {
+    let mut url = format!("{}/system/maintenance", data.config.sync_service_url);
+    if let Some(query) = query.filter(|query| !query.is_empty()) {
+        url = format!("{}?{}", url, query);
+    }
+    
+    let mut request = http_client::shared().request(method, &url);
+    if let Some(county) = county {
+        request = request
+            .header(COUNTY_HEADER, &county.county_id)
+            .header(PLATFORM_ADMIN_HEADER, county.is_platform_admin.to_string())
+            .header(ROLES_HEADER, county.roles.join(","));
+    }
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    
+    let response = request
+        .send()
+        .await
+        .map_err(|_| AppError::ServiceUnavailable("Sync service unavailable".to_string()))?;
+    let status = response.status();
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| AppError::ExternalService(format!("Invalid sync service response: {}", e)))?;
+    
+    if status.is_success() {
+        if let Some(maintenance) = body.get("status").and_then(|s| serde_json::from_value::<MaintenanceStatus>(s.clone()).ok()) {
+            data.maintenance.replace(maintenance);
+        }
+    }
+    
+    let status = actix_web::http::StatusCode::from_u16(status.as_u16())
+        .unwrap_or(actix_web::http::StatusCode::BAD_GATEWAY);
+    Ok(HttpResponse::build(status).json(body))
+}
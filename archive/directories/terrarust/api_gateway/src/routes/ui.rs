@@ -1,6 +1,11 @@
-use actix_web::{web, HttpRequest, HttpResponse, Result};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
 use serde_json::json;
+use terrafusion_common::tenancy::CountyContext;
 use crate::AppState;
+use crate::errors::AppError;
+use crate::i18n::{Locale, LOCALE_COOKIE};
+use crate::middlewares::csrf::CsrfToken;
 
 /// Configure UI routes
 pub fn configure() -> actix_web::Scope {
@@ -10,15 +15,24 @@ pub fn configure() -> actix_web::Scope {
         .route("/gis/dashboard", web::get().to(gis_dashboard))
         .route("/district-lookup", web::get().to(district_lookup_dashboard))
         .route("/sync/dashboard", web::get().to(sync_dashboard))
+        .route("/sync-pairs/new", web::get().to(new_sync_pair))
+        .route("/sync-pairs/{id}/edit", web::get().to(edit_sync_pair))
+        .route("/sync-operations/{id}", web::get().to(sync_operation_detail))
+        .route("/admin/users", web::get().to(admin_users))
+        .route("/admin/users/{id}", web::get().to(admin_user_detail))
+        .route("/admin/counties/onboard", web::get().to(county_onboarding))
+        .route("/locale/{lang}", web::get().to(set_locale))
 }
 
 /// Main dashboard view
-async fn dashboard(data: web::Data<AppState>) -> Result<HttpResponse> {
+async fn dashboard(data: web::Data<AppState>, locale: Locale, csrf: CsrfToken) -> Result<HttpResponse> {
     let template_data = json!({
         "title": "TerraFusion Platform",
         "service": "Rust Gateway",
         "version": "0.1.0",
-        "timestamp": chrono::Utc::now().to_rfc3339()
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "lang": locale.code(),
+        "csrf_token": csrf.0
     });
 
     let body = data.handlebars
@@ -32,12 +46,14 @@ async fn dashboard(data: web::Data<AppState>) -> Result<HttpResponse> {
 }
 
 /// GIS Export dashboard
-async fn gis_dashboard(data: web::Data<AppState>) -> Result<HttpResponse> {
+async fn gis_dashboard(data: web::Data<AppState>, locale: Locale, csrf: CsrfToken) -> Result<HttpResponse> {
     let template_data = json!({
         "title": "GIS Export Dashboard",
         "service": "TerraFusion GIS Export",
         "version": "0.1.0",
-        "timestamp": chrono::Utc::now().to_rfc3339()
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "lang": locale.code(),
+        "csrf_token": csrf.0
     });
 
     let body = data.handlebars
@@ -51,12 +67,14 @@ async fn gis_dashboard(data: web::Data<AppState>) -> Result<HttpResponse> {
 }
 
 /// District lookup dashboard
-async fn district_lookup_dashboard(data: web::Data<AppState>) -> Result<HttpResponse> {
+async fn district_lookup_dashboard(data: web::Data<AppState>, locale: Locale, csrf: CsrfToken) -> Result<HttpResponse> {
     let template_data = json!({
         "title": "District Lookup",
         "service": "Benton County District Lookup",
         "version": "0.1.0",
-        "timestamp": chrono::Utc::now().to_rfc3339()
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "lang": locale.code(),
+        "csrf_token": csrf.0
     });
 
     let body = data.handlebars
@@ -70,12 +88,14 @@ async fn district_lookup_dashboard(data: web::Data<AppState>) -> Result<HttpResp
 }
 
 /// Sync dashboard
-async fn sync_dashboard(data: web::Data<AppState>) -> Result<HttpResponse> {
+async fn sync_dashboard(data: web::Data<AppState>, locale: Locale, csrf: CsrfToken) -> Result<HttpResponse> {
     let template_data = json!({
         "title": "Data Synchronization",
         "service": "TerraFusion SyncService",
         "version": "0.1.0",
-        "timestamp": chrono::Utc::now().to_rfc3339()
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "lang": locale.code(),
+        "csrf_token": csrf.0
     });
 
     let body = data.handlebars
@@ -86,4 +106,213 @@ async fn sync_dashboard(data: web::Data<AppState>) -> Result<HttpResponse> {
         })?;
 
     Ok(HttpResponse::Ok().content_type("text/html").body(body))
-}
\ No newline at end of file
+}
+
+/// Form for a new sync pair
+async fn new_sync_pair(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    locale: Locale,
+    csrf: CsrfToken,
+) -> Result<HttpResponse> {
+    render_sync_pair_form(&req, &data, &locale, csrf, None)
+}
+
+/// Form for an existing sync pair; the page loads the pair itself
+async fn edit_sync_pair(
+    req: HttpRequest,
+    path: web::Path<uuid::Uuid>,
+    data: web::Data<AppState>,
+    locale: Locale,
+    csrf: CsrfToken,
+) -> Result<HttpResponse> {
+    render_sync_pair_form(&req, &data, &locale, csrf, Some(path.into_inner()))
+}
+
+fn render_sync_pair_form(
+    req: &HttpRequest,
+    data: &AppState,
+    locale: &Locale,
+    csrf: CsrfToken,
+    sync_pair_id: Option<uuid::Uuid>,
+) -> Result<HttpResponse> {
+    let template_data = json!({
+        "title": if sync_pair_id.is_some() { "Edit Sync Pair" } else { "New Sync Pair" },
+        "service": "TerraFusion SyncService",
+        "version": "0.1.0",
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "lang": locale.code(),
+        "csrf_token": csrf.0,
+        "sync_pair_id": sync_pair_id,
+        "county_id": req.extensions().get::<CountyContext>().map(|county| county.county_id.clone())
+    });
+
+    let body = data.handlebars
+        .render("sync_pair_form", &template_data)
+        .map_err(|e| {
+            log::error!("Template rendering error: {}", e);
+            actix_web::error::ErrorInternalServerError("Template rendering failed")
+        })?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(body))
+}
+
+/// One sync operation's timeline, progress and diffs; the page loads them itself
+async fn sync_operation_detail(
+    path: web::Path<uuid::Uuid>,
+    data: web::Data<AppState>,
+    locale: Locale,
+    csrf: CsrfToken,
+) -> Result<HttpResponse> {
+    let template_data = json!({
+        "title": "Sync Operation",
+        "service": "TerraFusion SyncService",
+        "version": "0.1.0",
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "lang": locale.code(),
+        "csrf_token": csrf.0,
+        "operation_id": path.into_inner()
+    });
+
+    let body = data.handlebars
+        .render("sync_operation", &template_data)
+        .map_err(|e| {
+            log::error!("Template rendering error: {}", e);
+            actix_web::error::ErrorInternalServerError("Template rendering failed")
+        })?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(body))
+}
+
+/// User list with the new account form; administrators only
+async fn admin_users(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    locale: Locale,
+    csrf: CsrfToken,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    render_user_admin(&data, &locale, csrf, &county, "User Administration", "users", None)
+}
+
+/// One account's roles, county, password reset and audit events; the page
+/// loads the account itself
+async fn admin_user_detail(
+    req: HttpRequest,
+    path: web::Path<uuid::Uuid>,
+    data: web::Data<AppState>,
+    locale: Locale,
+    csrf: CsrfToken,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    render_user_admin(&data, &locale, csrf, &county, "User", "user_detail", Some(path.into_inner()))
+}
+
+/// Wizard that onboards a new county, or resumes one under way; platform
+/// administrators only
+async fn county_onboarding(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    locale: Locale,
+    csrf: CsrfToken,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    if !county.is_platform_admin {
+        return Err(AppError::Authorization("Platform administrator role required".to_string()).into());
+    }
+    let template_data = json!({
+        "title": "Onboard a County",
+        "service": "TerraFusion API Gateway",
+        "version": "0.1.0",
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "lang": locale.code(),
+        "csrf_token": csrf.0,
+        "active_page": "counties",
+        "role": "admin",
+        "steps": super::onboarding::STEPS
+    });
+
+    let body = data.handlebars
+        .render("county_onboarding", &template_data)
+        .map_err(|e| {
+            log::error!("Template rendering error: {}", e);
+            actix_web::error::ErrorInternalServerError("Template rendering failed")
+        })?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(body))
+}
+
+fn render_user_admin(
+    data: &AppState,
+    locale: &Locale,
+    csrf: CsrfToken,
+    county: &CountyContext,
+    title: &str,
+    template: &str,
+    user_id: Option<uuid::Uuid>,
+) -> Result<HttpResponse> {
+    let template_data = json!({
+        "title": title,
+        "service": "TerraFusion API Gateway",
+        "version": "0.1.0",
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "lang": locale.code(),
+        "csrf_token": csrf.0,
+        "active_page": "users",
+        "role": "admin",
+        "county_id": county.county_id,
+        "is_platform_admin": county.is_platform_admin,
+        "user_id": user_id
+    });
+
+    let body = data.handlebars
+        .render(template, &template_data)
+        .map_err(|e| {
+            log::error!("Template rendering error: {}", e);
+            actix_web::error::ErrorInternalServerError("Template rendering failed")
+        })?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(body))
+}
+
+/// Query parameters for the language switcher
+#[derive(Debug, Deserialize)]
+pub struct SetLocaleQuery {
+    /// Page to return to; only local paths are followed
+    pub next: Option<String>,
+}
+
+/// Remember the chosen language in a cookie and go back to the page
+async fn set_locale(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<SetLocaleQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let locale = Locale::parse(&path)
+        .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Unsupported language: {}", path)))?;
+
+    let referer = req
+        .headers()
+        .get(actix_web::http::header::REFERER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|referer| referer.find("://").map(|i| &referer[i + 3..]))
+        .and_then(|rest| rest.find('/').map(|i| rest[i..].to_string()));
+    let next = query
+        .next
+        .clone()
+        .or(referer)
+        .filter(|next| next.starts_with('/') && !next.starts_with("//") && !next.starts_with("/locale/"))
+        .unwrap_or_else(|| "/".to_string());
+
+    let cookie = data.config
+        .cookie(LOCALE_COOKIE, locale.code())
+        .max_age(actix_web::cookie::time::Duration::days(365))
+        .http_only(true)
+        .finish();
+
+    Ok(HttpResponse::SeeOther()
+        .append_header(("Location", next))
+        .cookie(cookie)
+        .finish())
+}
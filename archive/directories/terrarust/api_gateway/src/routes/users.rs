@@ -0,0 +1,486 @@
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use chrono::{DateTime, Utc};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use terrafusion_common::tenancy::CountyContext;
+use uuid::Uuid;
+use crate::errors::AppError;
+use crate::AppState;
+
+/// Roles a user account can hold, least privileged first
+pub const ROLES: &[&str] = &["user", "admin", "platform_admin"];
+
+/// Shortest password an administrator may set
+const MIN_PASSWORD_LENGTH: usize = 12;
+
+/// Length of the temporary passwords handed out on reset
+const TEMPORARY_PASSWORD_LENGTH: usize = 16;
+
+/// Configure user administration. County administrators manage the
+/// accounts of their own county; only platform administrators can move
+/// accounts between counties or grant `platform_admin`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/users")
+            .route("", web::get().to(list_users))
+            .route("", web::post().to(create_user))
+            .route("/roles", web::get().to(list_roles))
+            .route("/{id}", web::get().to(get_user))
+            .route("/{id}", web::put().to(update_user))
+            .route("/{id}/reset-password", web::post().to(reset_password))
+            .route("/{id}/audit", web::get().to(list_user_audit_events))
+    );
+}
+
+/// A user account as shown to administrators; never carries the hash
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct UserAccount {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub role: String,
+    pub county_id: String,
+    pub is_active: bool,
+    pub last_login: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const USER_COLUMNS: &str = "id, username, email, role, county_id, is_active, last_login, created_at, updated_at";
+
+/// Query parameters for listing users
+#[derive(Debug, Deserialize)]
+pub struct ListUsersQuery {
+    pub county_id: Option<String>,
+    pub role: Option<String>,
+    /// Part of the username or email
+    pub search: Option<String>,
+    pub include_inactive: Option<bool>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    pub role: String,
+    pub county_id: Option<String>,
+}
+
+/// Fields left out stay unchanged
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserRequest {
+    pub email: Option<String>,
+    pub role: Option<String>,
+    pub county_id: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ResetPasswordRequest {
+    /// New password; a temporary one is generated when absent
+    pub password: Option<String>,
+}
+
+/// An audit log entry about or by a user
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct UserAuditEvent {
+    pub id: Uuid,
+    pub event_type: String,
+    pub resource_type: String,
+    pub resource_id: Option<String>,
+    pub description: String,
+    pub username: Option<String>,
+    pub county_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub severity: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Roles the caller may hand out
+async fn list_roles(req: HttpRequest) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let roles: Vec<&str> = ROLES.iter().copied().filter(|role| can_grant(&county, role)).collect();
+    Ok(HttpResponse::Ok().json(json!({ "roles": roles })))
+}
+
+/// Users of the caller's county, or of any county for platform administrators
+async fn list_users(
+    req: HttpRequest,
+    query: web::Query<ListUsersQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let query = query.into_inner();
+    let county_id = match query.county_id {
+        Some(county_id) if county.can_access(&county_id) => Some(county_id),
+        Some(county_id) => return Err(AppError::Authorization(format!("No access to county {}", county_id)).into()),
+        None => (!county.is_platform_admin).then(|| county.county_id.clone()),
+    };
+    let search = query.search.filter(|search| !search.trim().is_empty()).map(|search| format!("%{}%", search.trim()));
+    let include_inactive = query.include_inactive.unwrap_or(true);
+    let per_page = query.per_page.unwrap_or(25).clamp(1, 100);
+    let page = query.page.unwrap_or(1).max(1);
+
+    const FILTERS: &str = r#"
+        WHERE ($1::text IS NULL OR county_id = $1)
+        AND ($2::text IS NULL OR role = $2)
+        AND ($3::text IS NULL OR username ILIKE $3 OR email ILIKE $3)
+        AND ($4 OR is_active)
+    "#;
+    let users = sqlx::query_as::<_, UserAccount>(&format!(
+        "SELECT {} FROM users {} ORDER BY username LIMIT $5 OFFSET $6",
+        USER_COLUMNS, FILTERS
+    ))
+    .bind(&county_id)
+    .bind(&query.role)
+    .bind(&search)
+    .bind(include_inactive)
+    .bind(per_page as i64)
+    .bind(((page - 1) * per_page) as i64)
+    .fetch_all(&data.db_pool)
+    .await
+    .map_err(AppError::from)?;
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM users {}", FILTERS))
+        .bind(&county_id)
+        .bind(&query.role)
+        .bind(&search)
+        .bind(include_inactive)
+        .fetch_one(&data.db_pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "users": users,
+        "total": total,
+        "page": page,
+        "per_page": per_page
+    })))
+}
+
+async fn get_user(req: HttpRequest, path: web::Path<Uuid>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let user = load_user(&data, &county, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// Create an account; county administrators create it in their own county
+async fn create_user(
+    req: HttpRequest,
+    body: web::Json<CreateUserRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (admin, county) = super::system::require_admin(&req)?;
+    let body = body.into_inner();
+    let county_id = body.county_id.unwrap_or_else(|| county.county_id.clone());
+    check_assignment(&county, &body.role, &county_id)?;
+    let user = insert_user(&data, &body.username, &body.email, body.password, &body.role, &county_id).await?;
+
+    audit(&data, &req, &admin, &user, "user_created", format!("Created user {} as {}", user.username, user.role), None).await;
+    log::info!("{} created user {} in {}", admin, user.username, user.county_id);
+    Ok(HttpResponse::Created().json(user))
+}
+
+/// Validate and insert an active account. The caller checks that it may
+/// grant `role` in `county_id`.
+pub(crate) async fn insert_user(
+    data: &AppState,
+    username: &str,
+    email: &str,
+    password: String,
+    role: &str,
+    county_id: &str,
+) -> Result<UserAccount> {
+    let username = username.trim();
+    let email = email.trim().to_lowercase();
+    if username.is_empty() {
+        return Err(AppError::Validation("Username is required".to_string()).into());
+    }
+    validate_email(&email)?;
+    validate_password(&password)?;
+
+    let password_hash = hash_password(password).await?;
+    let user = sqlx::query_as::<_, UserAccount>(&format!(
+        r#"
+        INSERT INTO users (id, username, email, password_hash, role, county_id, is_active, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, TRUE, NOW(), NOW())
+        RETURNING {}
+        "#,
+        USER_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(username)
+    .bind(&email)
+    .bind(&password_hash)
+    .bind(role)
+    .bind(county_id)
+    .fetch_one(&data.db_pool)
+    .await
+    .map_err(unique_violation)?;
+    Ok(user)
+}
+
+/// Change a user's email, role, county or whether they can sign in. A
+/// changed role, county or deactivation signs the user out everywhere,
+/// since their sessions carry the old grants.
+async fn update_user(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    body: web::Json<UpdateUserRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (admin, county) = super::system::require_admin(&req)?;
+    let body = body.into_inner();
+    let current = load_user(&data, &county, path.into_inner()).await?;
+    if current.role == "platform_admin" && !county.is_platform_admin {
+        return Err(AppError::Authorization("Only platform administrators can change platform administrators".to_string()).into());
+    }
+
+    let email = body.email.map(|email| email.trim().to_lowercase()).unwrap_or_else(|| current.email.clone());
+    let role = body.role.unwrap_or_else(|| current.role.clone());
+    let county_id = body.county_id.unwrap_or_else(|| current.county_id.clone());
+    let is_active = body.is_active.unwrap_or(current.is_active);
+    validate_email(&email)?;
+    if role != current.role || county_id != current.county_id {
+        check_assignment(&county, &role, &county_id)?;
+    }
+    if !is_active && current.email == admin {
+        return Err(AppError::Validation("You cannot deactivate your own account".to_string()).into());
+    }
+
+    let user = sqlx::query_as::<_, UserAccount>(&format!(
+        r#"
+        UPDATE users
+        SET email = $2, role = $3, county_id = $4, is_active = $5, updated_at = NOW()
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        USER_COLUMNS
+    ))
+    .bind(current.id)
+    .bind(&email)
+    .bind(&role)
+    .bind(&county_id)
+    .bind(is_active)
+    .fetch_one(&data.db_pool)
+    .await
+    .map_err(unique_violation)?;
+
+    let grants_changed = user.role != current.role || user.county_id != current.county_id;
+    if grants_changed || (current.is_active && !user.is_active) || user.email != current.email {
+        revoke_sessions(&data, &current.email).await;
+    }
+
+    let previous = json!({
+        "email": current.email,
+        "role": current.role,
+        "county_id": current.county_id,
+        "is_active": current.is_active
+    });
+    audit(&data, &req, &admin, &user, "user_updated", format!("Updated user {}", user.username), Some(previous)).await;
+    log::info!("{} updated user {}", admin, user.username);
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// Set a new password and sign the user out everywhere. Without a password
+/// in the body a temporary one is generated and returned once.
+async fn reset_password(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    body: Option<web::Json<ResetPasswordRequest>>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (admin, county) = super::system::require_admin(&req)?;
+    let user = load_user(&data, &county, path.into_inner()).await?;
+    if user.role == "platform_admin" && !county.is_platform_admin {
+        return Err(AppError::Authorization("Only platform administrators can reset platform administrators".to_string()).into());
+    }
+
+    let (password, generated) = match body.and_then(|body| body.into_inner().password) {
+        Some(password) => {
+            validate_password(&password)?;
+            (password, false)
+        }
+        None => (Alphanumeric.sample_string(&mut rand::thread_rng(), TEMPORARY_PASSWORD_LENGTH), true),
+    };
+    let password_hash = hash_password(password.clone()).await?;
+    sqlx::query("UPDATE users SET password_hash = $2, updated_at = NOW() WHERE id = $1")
+        .bind(user.id)
+        .bind(&password_hash)
+        .execute(&data.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let revoked = revoke_sessions(&data, &user.email).await;
+
+    audit(&data, &req, &admin, &user, "user_password_reset", format!("Reset password of {}", user.username), None).await;
+    log::info!("{} reset the password of {}", admin, user.username);
+    Ok(HttpResponse::Ok().json(json!({
+        "user_id": user.id,
+        "temporary_password": generated.then_some(password),
+        "sessions_revoked": revoked
+    })))
+}
+
+/// Query parameters for a user's audit events
+#[derive(Debug, Deserialize)]
+pub struct UserAuditQuery {
+    pub limit: Option<i64>,
+}
+
+/// Recent audit log entries made by the user or about their account
+async fn list_user_audit_events(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    query: web::Query<UserAuditQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_, county) = super::system::require_admin(&req)?;
+    let user = load_user(&data, &county, path.into_inner()).await?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+
+    let events = sqlx::query_as::<_, UserAuditEvent>(
+        r#"
+        SELECT id, event_type, resource_type, resource_id, description, username, county_id,
+               ip_address, severity, created_at
+        FROM audit_log
+        WHERE (username IN ($1, $2) OR user_id = $3 OR (resource_type = 'user' AND resource_id = $3))
+        AND ($4::text IS NULL OR county_id = $4)
+        ORDER BY created_at DESC
+        LIMIT $5
+        "#,
+    )
+    .bind(&user.username)
+    .bind(&user.email)
+    .bind(user.id.to_string())
+    .bind((!county.is_platform_admin).then(|| county.county_id.clone()))
+    .bind(limit)
+    .fetch_all(&data.db_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(HttpResponse::Ok().json(json!({ "user_id": user.id, "events": events })))
+}
+
+/// The account, if it is in a county the caller administers
+async fn load_user(data: &AppState, county: &CountyContext, id: Uuid) -> Result<UserAccount> {
+    let user = sqlx::query_as::<_, UserAccount>(&format!("SELECT {} FROM users WHERE id = $1", USER_COLUMNS))
+        .bind(id)
+        .fetch_optional(&data.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    user.filter(|user| county.can_access(&user.county_id))
+        .ok_or_else(|| AppError::NotFound(format!("User {} not found", id)).into())
+}
+
+/// Whether the caller may hand out `role`
+fn can_grant(county: &CountyContext, role: &str) -> bool {
+    role != "platform_admin" || county.is_platform_admin
+}
+
+fn check_assignment(county: &CountyContext, role: &str, county_id: &str) -> Result<()> {
+    if !ROLES.contains(&role) {
+        return Err(AppError::Validation(format!("Unknown role {}; expected one of {}", role, ROLES.join(", "))).into());
+    }
+    if !can_grant(county, role) {
+        return Err(AppError::Authorization("Only platform administrators can grant platform_admin".to_string()).into());
+    }
+    if county_id.trim().is_empty() {
+        return Err(AppError::Validation("County is required".to_string()).into());
+    }
+    if !county.can_access(county_id) {
+        return Err(AppError::Authorization(format!("No access to county {}", county_id)).into());
+    }
+    Ok(())
+}
+
+fn validate_email(email: &str) -> Result<()> {
+    match email.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && domain.contains('.') => Ok(()),
+        _ => Err(AppError::Validation(format!("{} is not a valid email address", email)).into()),
+    }
+}
+
+fn validate_password(password: &str) -> Result<()> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+async fn hash_password(password: String) -> Result<String> {
+    // bcrypt is deliberately slow; keep it off the async workers
+    web::block(move || bcrypt::hash(password, bcrypt::DEFAULT_COST))
+        .await
+        .map_err(|e| AppError::InternalServerError(e.to_string()))?
+        .map_err(|e| AppError::InternalServerError(format!("Failed to hash password: {}", e)).into())
+}
+
+/// End every session of the user (sessions are keyed by email). A failure
+/// is logged rather than undoing the change that triggered it.
+async fn revoke_sessions(data: &AppState, email: &str) -> u64 {
+    match data.sessions.revoke_user(email, None).await {
+        Ok(revoked) => revoked,
+        Err(e) => {
+            log::error!("Failed to end sessions of {}: {}", email, e);
+            0
+        }
+    }
+}
+
+fn unique_violation(e: sqlx::Error) -> AppError {
+    match &e {
+        sqlx::Error::Database(db) if db.code().as_deref() == Some("23505") => {
+            AppError::Validation("Username or email is already in use".to_string())
+        }
+        _ => AppError::from(e),
+    }
+}
+
+/// Record a change to an account in the audit log; best effort, like the
+/// session cleanup
+pub(crate) async fn audit(
+    data: &AppState,
+    req: &HttpRequest,
+    admin: &str,
+    user: &UserAccount,
+    event_type: &str,
+    description: String,
+    previous_state: Option<serde_json::Value>,
+) {
+    let ip_address = req.connection_info().realip_remote_addr().map(str::to_string);
+    let new_state = json!({
+        "email": user.email,
+        "role": user.role,
+        "county_id": user.county_id,
+        "is_active": user.is_active
+    });
+    let result = sqlx::query(
+        r#"
+        INSERT INTO audit_log (
+            id, event_type, resource_type, resource_id, description, username, county_id,
+            ip_address, previous_state, new_state, severity, created_at
+        ) VALUES ($1, $2, 'user', $3, $4, $5, $6, $7, $8, $9, 'INFO', NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(event_type)
+    .bind(user.id.to_string())
+    .bind(&description)
+    .bind(admin)
+    .bind(&user.county_id)
+    .bind(ip_address)
+    .bind(previous_state)
+    .bind(new_state)
+    .execute(&data.db_pool)
+    .await;
+    if let Err(e) = result {
+        log::error!("Failed to audit {} of user {}: {}", event_type, user.username, e);
+    }
+}
@@ -22,10 +22,15 @@ struct GisExportsResponse {
     total_count: i64,
 }
 
+/// Error envelope returned by the backend services
 #[derive(Debug, Serialize, Deserialize)]
 struct ErrorResponse {
-    error: String,
-    status: u16,
+    error: ErrorBody,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ErrorBody {
+    message: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -95,7 +100,7 @@ impl GisExportClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("GIS Export service error: {}", error.error)));
+            return Err(Error::External(format!("GIS Export service error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -121,7 +126,7 @@ impl GisExportClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("GIS Export service error: {}", error.error)));
+            return Err(Error::External(format!("GIS Export service error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -146,7 +151,7 @@ impl GisExportClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("GIS Export service error: {}", error.error)));
+            return Err(Error::External(format!("GIS Export service error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -171,7 +176,7 @@ impl GisExportClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("GIS Export service error: {}", error.error)));
+            return Err(Error::External(format!("GIS Export service error: {}", error.error.message)));
         }
         
         Ok(())
@@ -217,7 +222,7 @@ impl GisExportClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("GIS Export service error: {}", error.error)));
+            return Err(Error::External(format!("GIS Export service error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -242,7 +247,7 @@ impl GisExportClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("GIS Export service error: {}", error.error)));
+            return Err(Error::External(format!("GIS Export service error: {}", error.error.message)));
         }
         
         // Parse the response
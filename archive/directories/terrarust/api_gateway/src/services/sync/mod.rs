@@ -248,11 +248,14 @@ impl SyncServiceClient {
         let response_body = response.json::<serde_json::Value>().await
             .map_err(|e| Error::InternalServer(format!("Failed to parse SyncService response: {}", e)))?;
             
-        let operations = response_body.get("operations")
+        // Listings return the shared page envelope: `items` plus `total` for offset pages
+        let operations = response_body.get("items")
+            .or_else(|| response_body.get("operations"))
             .and_then(|v| serde_json::from_value::<Vec<SyncOperation>>(v.clone()).ok())
             .unwrap_or_default();
             
-        let total_count = response_body.get("total_count")
+        let total_count = response_body.get("total")
+            .or_else(|| response_body.get("total_count"))
             .and_then(|v| v.as_i64())
             .unwrap_or(0);
             
@@ -47,10 +47,15 @@ struct SyncDiffsResponse {
     total_count: i64,
 }
 
+/// Error envelope returned by the backend services
 #[derive(Debug, Serialize, Deserialize)]
 struct ErrorResponse {
-    error: String,
-    status: u16,
+    error: ErrorBody,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ErrorBody {
+    message: String,
 }
 
 impl SyncServiceClient {
@@ -111,7 +116,7 @@ impl SyncServiceClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("SyncService error: {}", error.error)));
+            return Err(Error::External(format!("SyncService error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -137,7 +142,7 @@ impl SyncServiceClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("SyncService error: {}", error.error)));
+            return Err(Error::External(format!("SyncService error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -162,7 +167,7 @@ impl SyncServiceClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("SyncService error: {}", error.error)));
+            return Err(Error::External(format!("SyncService error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -187,7 +192,7 @@ impl SyncServiceClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("SyncService error: {}", error.error)));
+            return Err(Error::External(format!("SyncService error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -252,7 +257,7 @@ impl SyncServiceClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("SyncService error: {}", error.error)));
+            return Err(Error::External(format!("SyncService error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -278,7 +283,7 @@ impl SyncServiceClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("SyncService error: {}", error.error)));
+            return Err(Error::External(format!("SyncService error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -303,7 +308,7 @@ impl SyncServiceClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("SyncService error: {}", error.error)));
+            return Err(Error::External(format!("SyncService error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -331,7 +336,7 @@ impl SyncServiceClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("SyncService error: {}", error.error)));
+            return Err(Error::External(format!("SyncService error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -395,7 +400,7 @@ impl SyncServiceClient {
             let error: ErrorResponse = response.json().await
                 .map_err(|e| Error::External(format!("Failed to parse error response: {}", e)))?;
             
-            return Err(Error::External(format!("SyncService error: {}", error.error)));
+            return Err(Error::External(format!("SyncService error: {}", error.error.message)));
         }
         
         // Parse the response
@@ -4,17 +4,20 @@
 
 // Initialize the application when the DOM is fully loaded
 document.addEventListener('DOMContentLoaded', function() {
-  // Initialize Feather icons
+  // Initialize Feather icons; they are decorative, so hide them from screen readers
   if (typeof feather !== 'undefined') {
-    feather.replace();
+    feather.replace({ 'aria-hidden': 'true', focusable: 'false' });
   }
   
   // Set up AJAX request headers with CSRF token if available
   const csrfToken = document.querySelector('meta[name="csrf-token"]')?.getAttribute('content');
   if (csrfToken) {
-    // Add CSRF token to all AJAX requests
+    // Add CSRF token to same-origin AJAX requests; other sites must not see it
     const originalFetch = window.fetch;
     window.fetch = function(url, options = {}) {
+      if (new URL(url, window.location.href).origin !== window.location.origin) {
+        return originalFetch(url, options);
+      }
       if (!options.headers) {
         options.headers = {};
       }
@@ -51,6 +54,7 @@ document.addEventListener('DOMContentLoaded', function() {
   sidebarLinks.forEach(link => {
     if (link.getAttribute('href') === currentPath) {
       link.classList.add('active');
+      link.setAttribute('aria-current', 'page');
     }
   });
   
@@ -259,6 +263,18 @@ function calculateSuccessRate(succeeded, total) {
   return rate.toFixed(1);
 }
 
+/**
+ * Message from an API error body: the envelope's localized summary or
+ * message, or a plain-string error from older services
+ */
+function errorMessage(data) {
+  const error = data && data.error;
+  if (error && typeof error === 'object') {
+    return error.localized_message || error.message;
+  }
+  return error || 'Unknown error';
+}
+
 /**
  * Show a notification toast
  */
@@ -272,24 +288,26 @@ function showNotification(message, type = 'info') {
     document.body.appendChild(toastContainer);
   }
   
-  // Create the toast element
+  // Create the toast element. text-bg-* picks a text color with enough
+  // contrast for the background (white on yellow does not), and only
+  // errors interrupt a screen reader.
   const toastId = 'toast-' + Date.now();
   const toast = document.createElement('div');
   toast.id = toastId;
-  toast.className = `toast align-items-center text-white bg-${type}`;
-  toast.setAttribute('role', 'alert');
-  toast.setAttribute('aria-live', 'assertive');
+  toast.className = `toast align-items-center text-bg-${type}`;
+  const urgent = type === 'danger' || type === 'warning';
+  toast.setAttribute('role', urgent ? 'alert' : 'status');
+  toast.setAttribute('aria-live', urgent ? 'assertive' : 'polite');
   toast.setAttribute('aria-atomic', 'true');
   
-  // Create toast content
+  // Create toast content; messages often carry server text, so never as HTML
   toast.innerHTML = `
     <div class="d-flex">
-      <div class="toast-body">
-        ${message}
-      </div>
+      <div class="toast-body"></div>
       <button type="button" class="btn-close me-2 m-auto" data-bs-dismiss="toast" aria-label="Close"></button>
     </div>
   `;
+  toast.querySelector('.toast-body').textContent = message;
   
   // Add the toast to the container
   toastContainer.appendChild(toast);
@@ -304,4 +322,63 @@ function showNotification(message, type = 'info') {
       this.remove();
     });
   }
-}
\ No newline at end of file
+}
+
+/**
+ * Apply a theme (`auto`, `light` or `dark`) to the page; `auto` follows
+ * the operating system
+ */
+function applyTheme(theme) {
+  const dark = theme === 'dark' ||
+    (theme === 'auto' && window.matchMedia('(prefers-color-scheme: dark)').matches);
+  document.documentElement.setAttribute('data-bs-theme', dark ? 'dark' : 'light');
+  document.documentElement.setAttribute('data-tf-theme', theme);
+  applyChartTheme();
+}
+
+/**
+ * Switch theme and save it to the user's profile. The cookie is set here
+ * too so the choice sticks even if saving fails.
+ */
+function setTheme(theme) {
+  applyTheme(theme);
+  document.cookie = 'tf_theme=' + theme + '; path=/; max-age=31536000; samesite=lax';
+  fetch('/api/v1/preferences', {
+    method: 'PUT',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ theme })
+  }).catch(error => console.error('Failed to save theme preference:', error));
+}
+
+/**
+ * Show the current theme in the header selector and follow system changes
+ * while on `auto`
+ */
+function initThemeSelect() {
+  const select = document.getElementById('themeSelect');
+  if (select) {
+    select.value = document.documentElement.getAttribute('data-tf-theme') || 'auto';
+  }
+  window.matchMedia('(prefers-color-scheme: dark)').addEventListener('change', function() {
+    if (document.documentElement.getAttribute('data-tf-theme') === 'auto') {
+      applyTheme('auto');
+    }
+  });
+}
+
+/**
+ * Point Chart.js text and grid colors at the theme's, and redraw any
+ * charts already on the page
+ */
+function applyChartTheme() {
+  if (typeof Chart === 'undefined') {
+    return;
+  }
+  const style = getComputedStyle(document.documentElement);
+  Chart.defaults.color = style.getPropertyValue('--bs-body-color').trim() || '#212529';
+  Chart.defaults.borderColor = style.getPropertyValue('--bs-border-color-translucent').trim() || 'rgba(0, 0, 0, .1)';
+  Object.values(Chart.instances || {}).forEach(chart => chart.update());
+}
+
+// Charts are built on DOMContentLoaded, after this runs
+applyChartTheme();
@@ -0,0 +1,93 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Gateway used when neither a flag, the environment nor `login` names one
+pub const DEFAULT_URL: &str = "http://localhost:8000";
+
+/// What `terrafusion login` remembers between runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl Config {
+    /// `TERRAFUSION_CONFIG`, or `terrafusion/config.json` in the user's config directory
+    pub fn path() -> Result<PathBuf> {
+        if let Some(path) = std::env::var_os("TERRAFUSION_CONFIG") {
+            return Ok(PathBuf::from(path));
+        }
+        let dir = dirs::config_dir().context("Could not determine the user configuration directory")?;
+        Ok(dir.join("terrafusion").join("config.json"))
+    }
+
+    /// The saved configuration, empty before the first login
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).with_context(|| format!("{} is not valid", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Write the configuration, readable only by the current user since it holds credentials
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options.open(&path).with_context(|| format!("Failed to write {}", path.display()))?;
+        #[cfg(unix)]
+        {
+            // `mode` only applies to new files; tighten one left by an older version too
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(path)
+    }
+
+    /// Gateway URL: the flag or `TERRAFUSION_URL` first, then the saved one
+    pub fn url(&self, flag: Option<String>) -> String {
+        flag.or_else(|| self.url.clone()).unwrap_or_else(|| DEFAULT_URL.to_string())
+    }
+
+    pub fn has_credentials(&self) -> bool {
+        self.api_key.is_some() || self.token.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_prefers_flag_then_saved_then_default() {
+        let saved = Config { url: Some("https://saved.example.org".to_string()), ..Default::default() };
+        assert_eq!(saved.url(Some("https://flag.example.org".to_string())), "https://flag.example.org");
+        assert_eq!(saved.url(None), "https://saved.example.org");
+        assert_eq!(Config::default().url(None), DEFAULT_URL);
+    }
+
+    #[test]
+    fn test_logged_out_config_omits_credentials() {
+        let config = Config { url: Some("https://tf.example.org".to_string()), ..Default::default() };
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json, serde_json::json!({ "url": "https://tf.example.org" }));
+        assert!(!config.has_credentials());
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use futures_util::{StreamExt, TryStreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use terrafusion_client::{Client, ExportJob, ExportJobFilter, GisExportClient, NewExportJob};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+use crate::output::Output;
+use crate::watch;
+
+const JOB_COLUMNS: &[&str] = &["job_id", "county_id", "export_format", "status", "created_at", "completed_at"];
+const JOB_FIELDS: &[&str] = &[
+    "job_id",
+    "county_id",
+    "username",
+    "export_format",
+    "status",
+    "message",
+    "file_size",
+    "created_at",
+    "completed_at",
+    "checksum_sha256",
+];
+
+#[derive(Subcommand)]
+pub enum ExportCommand {
+    /// List GIS export jobs, newest first
+    List {
+        /// Only show jobs with this status
+        #[arg(long)]
+        status: Option<String>,
+        /// Only show jobs of this county
+        #[arg(long)]
+        county: Option<String>,
+        /// Maximum number of jobs to show
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+    /// Create a GIS export job and queue it
+    Create {
+        /// County ID
+        #[arg(long)]
+        county: String,
+        /// Export format (e.g. shapefile, geojson, kml, geopackage)
+        #[arg(long, default_value = "geojson")]
+        format: String,
+        /// Layers to export (repeatable)
+        #[arg(long = "layer", required = true)]
+        layers: Vec<String>,
+        /// GeoJSON file containing the area-of-interest geometry
+        #[arg(long)]
+        area: PathBuf,
+        /// Export the data as it was at this date or RFC 3339 timestamp
+        #[arg(long)]
+        as_of: Option<String>,
+        /// User the export is made for (default: the current OS user)
+        #[arg(long, env = "TERRAFUSION_USERNAME")]
+        username: Option<String>,
+        /// Wait for the job to finish
+        #[arg(long)]
+        wait: bool,
+        /// Download the result to this path once finished (implies --wait)
+        #[arg(long)]
+        download: Option<PathBuf>,
+        /// Give up waiting after this many minutes
+        #[arg(long, default_value = "60")]
+        timeout: u64,
+    },
+    /// Show a GIS export job
+    Status {
+        job_id: Uuid,
+        /// Wait for the job to finish
+        #[arg(long)]
+        wait: bool,
+    },
+    /// Cancel a GIS export job
+    Cancel {
+        job_id: Uuid,
+    },
+    /// Download a finished GIS export
+    Download {
+        job_id: Uuid,
+        /// Output file or directory (default: <job id>.zip in the current directory)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Run an `export` subcommand
+pub async fn run(client: &Client, output: Output, command: ExportCommand) -> Result<()> {
+    let gis = client.gis_export();
+    match command {
+        ExportCommand::List { status, county, limit } => {
+            let filter = ExportJobFilter {
+                county_id: county,
+                status,
+                per_page: Some(limit.clamp(1, 100) as u32),
+                ..Default::default()
+            };
+            let jobs: Vec<ExportJob> = gis.jobs(filter).take(limit).try_collect().await?;
+            output.list(&jobs, JOB_COLUMNS)?;
+        }
+        ExportCommand::Create { county, format, layers, area, as_of, username, wait, download, timeout } => {
+            let text = tokio::fs::read_to_string(&area)
+                .await
+                .with_context(|| format!("Failed to read {}", area.display()))?;
+            let area_of_interest: Value = serde_json::from_str(&text).context("Area of interest is not valid GeoJSON")?;
+
+            let mut parameters = HashMap::new();
+            if let Some(as_of) = as_of {
+                parameters.insert("as_of".to_string(), Value::String(as_of));
+            }
+            let job = NewExportJob {
+                county_id: county,
+                username: username.unwrap_or_else(current_user),
+                export_format: format,
+                area_of_interest,
+                layers,
+                parameters,
+            };
+
+            let job = gis.create_job(&job).await?;
+            if job.status == "PENDING" {
+                gis.process_job(job.job_id).await?;
+            } else {
+                output.note(format!("Export job {} is {}; it will run once approved", job.job_id, job.status));
+            }
+
+            if !wait && download.is_none() {
+                return output.item(&job, JOB_FIELDS);
+            }
+            output.note(format!("Created export job {}", job.job_id));
+            let job = tokio::time::timeout(
+                Duration::from_secs(timeout * 60),
+                watch::export(&gis, job.job_id, Duration::from_secs(3)),
+            )
+            .await
+            .with_context(|| format!("Export job {} did not finish within {} minutes", job.job_id, timeout))??;
+            output.item(&job, JOB_FIELDS)?;
+            if !job.is_completed() {
+                bail!("Export job {} ended {}", job.job_id, job.status);
+            }
+            if let Some(path) = download {
+                let path = save(&gis, job.job_id, Some(path)).await?;
+                output.note(format!("Saved {}", path.display()));
+            }
+        }
+        ExportCommand::Status { job_id, wait } => {
+            let job = if wait {
+                watch::export(&gis, job_id, Duration::from_secs(3)).await?
+            } else {
+                gis.get_job(job_id).await?
+            };
+            output.item(&job, JOB_FIELDS)?;
+        }
+        ExportCommand::Cancel { job_id } => {
+            let job = gis.cancel_job(job_id).await?;
+            output.item(&job, JOB_FIELDS)?;
+        }
+        ExportCommand::Download { job_id, output: path } => {
+            let path = save(&gis, job_id, path).await?;
+            output.note(format!("Saved {}", path.display()));
+        }
+    }
+    Ok(())
+}
+
+/// Stream an export to disk. A directory target gets `<job id>.zip` inside it.
+async fn save(gis: &GisExportClient, job_id: Uuid, path: Option<PathBuf>) -> Result<PathBuf> {
+    let default_name = format!("{}.zip", job_id);
+    let path = match path {
+        Some(path) if path.is_dir() => path.join(default_name),
+        Some(path) => path,
+        None => PathBuf::from(default_name),
+    };
+
+    let mut body = Box::pin(gis.download_stream(job_id).await?);
+    let mut file = tokio::fs::File::create(&path)
+        .await
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    while let Some(chunk) = body.try_next().await? {
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok(path)
+}
+
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "terrafusion-cli".to_string())
+}
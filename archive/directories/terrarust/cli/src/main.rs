@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use terrafusion_client::{Client, Credentials};
+
+mod config;
+mod export;
+mod output;
+mod sync;
+mod watch;
+
+use config::Config;
+use output::{Format, Output};
+
+#[derive(Parser)]
+#[command(name = "terrafusion", version)]
+#[command(about = "Work with sync pairs, sync operations and GIS exports through the TerraFusion API")]
+struct Cli {
+    /// API gateway base URL (default: the one saved by `login`)
+    #[arg(long, global = true, env = "TERRAFUSION_URL")]
+    url: Option<String>,
+
+    /// API key; overrides the credentials saved by `login`
+    #[arg(long, global = true, env = "TERRAFUSION_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    /// How results are printed
+    #[arg(long, short, global = true, value_enum, default_value = "table")]
+    format: Format,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Save the gateway URL and credentials for later commands
+    Login {
+        /// Session token instead of an API key
+        #[arg(long, conflicts_with = "key")]
+        token: Option<String>,
+        /// API key (prompted for when neither this nor --token is given)
+        #[arg(long)]
+        key: Option<String>,
+    },
+    /// Forget the saved credentials
+    Logout,
+    /// List and run sync operations
+    Sync {
+        #[command(subcommand)]
+        command: sync::SyncCommand,
+    },
+    /// Create, follow and download GIS exports
+    Export {
+        #[command(subcommand)]
+        command: export::ExportCommand,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let output = Output { format: cli.format };
+    let mut config = Config::load()?;
+    let url = config.url(cli.url);
+
+    match cli.command {
+        Commands::Login { token, key } => {
+            let key = match (&token, key.or(cli.api_key)) {
+                (Some(_), _) => None,
+                (None, Some(key)) => Some(key),
+                (None, None) => Some(
+                    dialoguer::Password::new().with_prompt("API key").interact().context("No API key entered")?,
+                ),
+            };
+            config.url = Some(url.clone());
+            config.api_key = key.map(|key| key.trim().to_string());
+            config.token = token;
+
+            // Any authenticated read proves the credentials before they are saved
+            let client = build(&url, credentials(None, &config)?)?;
+            client
+                .sync()
+                .list_sync_pairs(1, 1)
+                .await
+                .with_context(|| format!("Could not sign in to {}", url))?;
+
+            let path = config.save()?;
+            output.note(format!("Signed in to {}; credentials saved to {}", url, path.display()));
+        }
+        Commands::Logout => {
+            if config.has_credentials() {
+                config.api_key = None;
+                config.token = None;
+                config.save()?;
+            }
+            output.note("Signed out");
+        }
+        Commands::Sync { command } => {
+            let client = build(&url, credentials(cli.api_key, &config)?)?;
+            sync::run(&client, output, command).await?;
+        }
+        Commands::Export { command } => {
+            let client = build(&url, credentials(cli.api_key, &config)?)?;
+            export::run(&client, output, command).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `--api-key` or `TERRAFUSION_API_KEY` first, then what `login` saved
+fn credentials(api_key: Option<String>, config: &Config) -> Result<Credentials> {
+    if let Some(key) = api_key.or_else(|| config.api_key.clone()) {
+        return Ok(Credentials::api_key(&key)?);
+    }
+    if let Some(token) = &config.token {
+        return Ok(Credentials::bearer(token)?);
+    }
+    anyhow::bail!("Not signed in; run `terrafusion login` or set TERRAFUSION_API_KEY")
+}
+
+fn build(url: &str, credentials: Credentials) -> Result<Client> {
+    Ok(Client::builder(url)
+        .credentials(credentials)
+        .user_agent(format!("terrafusion-cli/{}", env!("CARGO_PKG_VERSION")))
+        .build()?)
+}
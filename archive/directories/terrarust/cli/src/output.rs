@@ -0,0 +1,118 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+/// How results are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned columns for people
+    Table,
+    /// One pretty-printed JSON document
+    Json,
+    /// One compact JSON object per line, for piping into other tools
+    Jsonl,
+}
+
+/// Prints results in the chosen format. Notes meant for people go to
+/// stderr in the machine-readable formats so stdout stays parseable.
+#[derive(Debug, Clone, Copy)]
+pub struct Output {
+    pub format: Format,
+}
+
+impl Output {
+    pub fn is_table(&self) -> bool {
+        self.format == Format::Table
+    }
+
+    /// Print a list with the given columns in table form
+    pub fn list<T: Serialize>(&self, items: &[T], columns: &[&str]) -> Result<()> {
+        let rows = items.iter().map(serde_json::to_value).collect::<serde_json::Result<Vec<_>>>()?;
+        match self.format {
+            Format::Table if rows.is_empty() => println!("Nothing found"),
+            Format::Table => print!("{}", render_table(&rows, columns)),
+            Format::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+            Format::Jsonl => {
+                for row in &rows {
+                    println!("{}", serde_json::to_string(row)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Print one object; in table form only the given fields, one per line
+    pub fn item<T: Serialize>(&self, item: &T, fields: &[&str]) -> Result<()> {
+        let value = serde_json::to_value(item)?;
+        match self.format {
+            Format::Table => {
+                let width = fields.iter().map(|field| field.len()).max().unwrap_or(0);
+                for field in fields {
+                    println!("{:<width$}  {}", field, cell(&value, field), width = width);
+                }
+            }
+            Format::Json => println!("{}", serde_json::to_string_pretty(&value)?),
+            Format::Jsonl => println!("{}", serde_json::to_string(&value)?),
+        }
+        Ok(())
+    }
+
+    pub fn note(&self, message: impl std::fmt::Display) {
+        if self.is_table() {
+            println!("{}", message);
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+}
+
+fn cell(row: &Value, column: &str) -> String {
+    match row.get(column) {
+        None | Some(Value::Null) => "-".to_string(),
+        Some(Value::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// Rows as aligned columns under an upper-case header
+fn render_table(rows: &[Value], columns: &[&str]) -> String {
+    let widths: Vec<usize> = columns
+        .iter()
+        .map(|column| rows.iter().map(|row| cell(row, column).len()).max().unwrap_or(0).max(column.len()))
+        .collect();
+    let line = |cells: Vec<String>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+
+    let mut table = line(columns.iter().map(|column| column.to_uppercase()).collect());
+    for row in rows {
+        table.push_str(&line(columns.iter().map(|column| cell(row, column)).collect()));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_table_aligns_columns_and_marks_missing_values() {
+        let rows = vec![
+            json!({ "id": "a1", "status": "COMPLETED", "records": 12 }),
+            json!({ "id": "b22", "status": null }),
+        ];
+        assert_eq!(
+            render_table(&rows, &["id", "status", "records"]),
+            "ID   STATUS     RECORDS\n\
+             a1   COMPLETED  12\n\
+             b22  -          -\n"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use futures_util::{StreamExt, TryStreamExt};
+use serde_json::{Map, Value};
+use std::time::Duration;
+use terrafusion_client::{Client, SyncOperation, SyncStatus};
+use uuid::Uuid;
+
+use crate::output::Output;
+use crate::watch;
+
+const PAIR_COLUMNS: &[&str] =
+    &["id", "name", "source_system", "target_system", "is_active", "last_sync_status", "last_sync_time"];
+const OPERATION_FIELDS: &[&str] =
+    &["id", "sync_pair_id", "status", "start_time", "records_processed", "records_succeeded", "records_failed"];
+const DIFF_COLUMNS: &[&str] = &["entity_type", "entity_id", "change_type", "sync_status", "error_message"];
+
+#[derive(Subcommand)]
+pub enum SyncCommand {
+    /// List sync pairs
+    Pairs {
+        /// Page to show, from 1
+        #[arg(long, default_value = "1")]
+        page: u32,
+        #[arg(long, default_value = "50")]
+        per_page: u32,
+    },
+    /// Start a sync operation for a sync pair
+    Run {
+        /// Sync pair ID
+        pair_id: Uuid,
+        /// Parameter handed to the pair's connectors, as key=value (repeatable)
+        #[arg(long = "param", value_parser = parse_param)]
+        params: Vec<(String, Value)>,
+        /// Follow the operation until it finishes
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between progress checks while watching
+        #[arg(long, default_value = "2")]
+        interval: u64,
+    },
+    /// Show a sync operation
+    Status {
+        operation_id: Uuid,
+        /// Follow the operation until it finishes
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between progress checks while watching
+        #[arg(long, default_value = "2")]
+        interval: u64,
+    },
+    /// Cancel a running sync operation
+    Cancel {
+        operation_id: Uuid,
+    },
+    /// List the records a sync operation compared
+    Diffs {
+        operation_id: Uuid,
+        /// Stop after this many records (0 for all)
+        #[arg(long, default_value = "100")]
+        limit: usize,
+    },
+}
+
+/// Run a `sync` subcommand
+pub async fn run(client: &Client, output: Output, command: SyncCommand) -> Result<()> {
+    let sync = client.sync();
+    match command {
+        SyncCommand::Pairs { page, per_page } => {
+            let list = sync.list_sync_pairs(page, per_page).await?;
+            output.list(&list.items, PAIR_COLUMNS)?;
+        }
+        SyncCommand::Run { pair_id, params, watch, interval } => {
+            let parameters = (!params.is_empty()).then(|| Value::Object(params.into_iter().collect::<Map<_, _>>()));
+            let started = sync.start_operation(pair_id, parameters).await?;
+            if watch {
+                output.note(format!("Started sync operation {}", started.operation_id));
+                let operation = watch::operation(&sync, started.operation_id, Duration::from_secs(interval)).await?;
+                finish(output, &operation)?;
+            } else {
+                output.item(&started, &["operation_id", "status", "created_at"])?;
+            }
+        }
+        SyncCommand::Status { operation_id, watch, interval } => {
+            if watch {
+                let operation = watch::operation(&sync, operation_id, Duration::from_secs(interval)).await?;
+                finish(output, &operation)?;
+            } else {
+                output.item(&sync.get_operation(operation_id).await?, OPERATION_FIELDS)?;
+            }
+        }
+        SyncCommand::Cancel { operation_id } => {
+            sync.cancel_operation(operation_id).await?;
+            output.note(format!("Cancellation requested for sync operation {}", operation_id));
+        }
+        SyncCommand::Diffs { operation_id, limit } => {
+            let diffs = sync.operation_diffs(operation_id, 100);
+            let diffs: Vec<_> = if limit == 0 {
+                diffs.try_collect().await?
+            } else {
+                diffs.take(limit).try_collect().await?
+            };
+            output.list(&diffs, DIFF_COLUMNS)?;
+        }
+    }
+    Ok(())
+}
+
+/// Print a watched operation and fail unless it completed, so scripts can
+/// rely on the exit code
+fn finish(output: Output, operation: &SyncOperation) -> Result<()> {
+    output.item(operation, OPERATION_FIELDS)?;
+    if operation.status != SyncStatus::Completed {
+        bail!("Sync operation {} ended {:?}", operation.id, operation.status);
+    }
+    Ok(())
+}
+
+/// `key=value`; values that parse as JSON (numbers, booleans, objects) are
+/// passed as such, anything else as a string
+fn parse_param(param: &str) -> Result<(String, Value)> {
+    let (key, value) = param.split_once('=').context("Parameters are written key=value")?;
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    Ok((key.trim().to_string(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_param_keeps_json_values_and_falls_back_to_strings() {
+        assert_eq!(parse_param("batch_size=500").unwrap(), ("batch_size".to_string(), json!(500)));
+        assert_eq!(parse_param("full=true").unwrap(), ("full".to_string(), json!(true)));
+        assert_eq!(parse_param("since=2024-03-01").unwrap(), ("since".to_string(), json!("2024-03-01")));
+        assert!(parse_param("no-equals").is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::time::Duration;
+use terrafusion_client::{ExportJob, GisExportClient, SyncOperation, SyncServiceClient};
+use uuid::Uuid;
+
+/// Poll a sync operation until it finishes, showing its record counts live.
+/// The bar draws on stderr and only when it is a terminal.
+pub async fn operation(sync: &SyncServiceClient, id: Uuid, interval: Duration) -> Result<SyncOperation> {
+    let bar = spinner()?;
+    loop {
+        let operation = sync.get_operation(id).await?;
+        bar.set_message(format!(
+            "{:?}: {} processed, {} succeeded, {} failed",
+            operation.status, operation.records_processed, operation.records_succeeded, operation.records_failed
+        ));
+        if operation.status.is_finished() {
+            bar.finish_and_clear();
+            return Ok(operation);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Poll an export job until it finishes. Exports do not report partial
+/// progress, so this shows the status and the time spent.
+pub async fn export(gis: &GisExportClient, id: Uuid, interval: Duration) -> Result<ExportJob> {
+    let bar = spinner()?;
+    loop {
+        let job = gis.get_job(id).await?;
+        bar.set_message(format!("Export {} {}", id, job.status));
+        if job.is_finished() {
+            bar.finish_and_clear();
+            return Ok(job);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+fn spinner() -> Result<ProgressBar> {
+    let bar = ProgressBar::new_spinner();
+    bar.set_style(ProgressStyle::with_template("{spinner} [{elapsed_precise}] {msg}")?);
+    bar.enable_steady_tick(Duration::from_millis(120));
+    Ok(bar)
+}
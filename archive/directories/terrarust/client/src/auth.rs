@@ -0,0 +1,69 @@
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::RequestBuilder;
+
+use crate::error::{ClientError, Result};
+
+/// Header the gateway reads API keys from
+pub(crate) const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Prefix of keys issued by TerraFusion
+const API_KEY_PREFIX: &str = "tfk_";
+
+/// How requests authenticate with the gateway
+#[derive(Clone)]
+pub enum Credentials {
+    /// An API key issued to the integration
+    ApiKey(HeaderValue),
+    /// A session token, for tools acting on behalf of a signed-in user
+    Bearer(HeaderValue),
+    /// Unauthenticated; only health checks and public endpoints answer
+    None,
+}
+
+impl Credentials {
+    /// An API key as issued, `tfk_` followed by 32 characters
+    pub fn api_key(key: &str) -> Result<Self> {
+        let key = key.trim();
+        if !key.starts_with(API_KEY_PREFIX) {
+            return Err(ClientError::Config(format!("API keys start with {}", API_KEY_PREFIX)));
+        }
+        Ok(Credentials::ApiKey(sensitive(key)?))
+    }
+
+    pub fn bearer(token: &str) -> Result<Self> {
+        Ok(Credentials::Bearer(sensitive(&format!("Bearer {}", token.trim()))?))
+    }
+
+    /// The key in `TERRAFUSION_API_KEY`
+    pub fn from_env() -> Result<Self> {
+        let key = std::env::var("TERRAFUSION_API_KEY")
+            .map_err(|_| ClientError::Config("TERRAFUSION_API_KEY is not set".to_string()))?;
+        Self::api_key(&key)
+    }
+
+    pub(crate) fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            Credentials::ApiKey(key) => request.header(API_KEY_HEADER, key.clone()),
+            Credentials::Bearer(token) => request.header(AUTHORIZATION, token.clone()),
+            Credentials::None => request,
+        }
+    }
+}
+
+// Keys never show up in Debug output or logs
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Credentials::ApiKey(_) => f.write_str("ApiKey(..)"),
+            Credentials::Bearer(_) => f.write_str("Bearer(..)"),
+            Credentials::None => f.write_str("None"),
+        }
+    }
+}
+
+fn sensitive(value: &str) -> Result<HeaderValue> {
+    let mut value = HeaderValue::from_str(value)
+        .map_err(|_| ClientError::Config("Credentials contain characters not allowed in a header".to_string()))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+use thiserror::Error;
+
+/// An error answer from the API, as carried in its error envelope
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiError {
+    pub code: u16,
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub message: String,
+    #[serde(default)]
+    pub details: Option<serde_json::Value>,
+    /// Request id the failure was logged under; quote it to support
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorEnvelope {
+    error: ApiError,
+}
+
+impl ApiError {
+    /// Read an error response body. Bodies without the envelope (e.g. from
+    /// a proxy in front of the gateway) become a message with the status.
+    pub(crate) fn from_body(status: StatusCode, body: &str) -> Self {
+        match serde_json::from_str::<ErrorEnvelope>(body) {
+            Ok(envelope) => envelope.error,
+            Err(_) => ApiError {
+                code: status.as_u16(),
+                error_type: status.canonical_reason().unwrap_or("error").to_lowercase().replace(' ', "_"),
+                message: if body.trim().is_empty() { status.to_string() } else { body.trim().to_string() },
+                details: None,
+                correlation_id: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The API answered with an error status
+    #[error("{} ({}): {}", .error.error_type, .error.code, .error.message)]
+    Api {
+        error: ApiError,
+        /// From `Retry-After`, when the answer carried one
+        retry_after: Option<Duration>,
+    },
+
+    /// The request did not get an answer
+    #[error("Request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The answer was not what the API documents
+    #[error("Unexpected response: {0}")]
+    Decode(String),
+
+    /// A job did not finish in the time allowed
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error("Invalid client configuration: {0}")]
+    Config(String),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+impl ClientError {
+    /// HTTP status of an API error
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ClientError::Api { error, .. } => Some(error.code),
+            _ => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(404)
+    }
+
+    /// Whether trying again later may succeed
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ClientError::Api { error, .. } => matches!(error.code, 429 | 502 | 503 | 504),
+            ClientError::Http(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+use futures_util::{Stream, TryStreamExt};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::error::{ClientError, Result};
+use crate::models::{ExportJob, ExportJobFilter, NewExportJob};
+use crate::pagination::{paginate, Page, PageStream};
+use crate::transport::{Call, Transport};
+
+/// GIS export jobs of the caller's county
+#[derive(Clone)]
+pub struct GisExportClient {
+    transport: Transport,
+}
+
+impl GisExportClient {
+    pub(crate) fn new(transport: Transport) -> Self {
+        Self { transport }
+    }
+
+    /// Every job matching the filter, newest first
+    pub fn jobs(&self, filter: ExportJobFilter) -> PageStream<'static, ExportJob> {
+        let transport = self.transport.clone();
+        paginate(move |cursor| {
+            let transport = transport.clone();
+            let filter = filter.clone();
+            async move {
+                let call = Call::get("/gis-export/jobs")
+                    .query("cursor", cursor)
+                    .query_opt("per_page", filter.per_page)
+                    .query_opt("county_id", filter.county_id)
+                    .query_opt("username", filter.username)
+                    .query_opt("status", filter.status);
+                transport.json::<Page<ExportJob>>(call).await
+            }
+        })
+    }
+
+    /// Create an export job. It waits for `process_job` unless the county
+    /// requires approval first.
+    pub async fn create_job(&self, job: &NewExportJob) -> Result<ExportJob> {
+        self.transport.json(Call::post("/gis-export/jobs").json(job)?.once()).await
+    }
+
+    pub async fn get_job(&self, id: Uuid) -> Result<ExportJob> {
+        self.transport.json(Call::get(format!("/gis-export/jobs/{}", id))).await
+    }
+
+    /// Queue the export for generation
+    pub async fn process_job(&self, id: Uuid) -> Result<Value> {
+        // Queueing a job twice is harmless; the key just lets the call be retried
+        self.transport.json(Call::post(format!("/gis-export/jobs/{}/process", id)).once()).await
+    }
+
+    pub async fn cancel_job(&self, id: Uuid) -> Result<ExportJob> {
+        self.transport.json(Call::post(format!("/gis-export/jobs/{}/cancel", id))).await
+    }
+
+    /// Poll a job every `interval` until it finishes, giving up after `timeout`
+    pub async fn wait_for_job(&self, id: Uuid, interval: Duration, timeout: Duration) -> Result<ExportJob> {
+        let started = Instant::now();
+        loop {
+            let job = self.get_job(id).await?;
+            if job.is_finished() {
+                return Ok(job);
+            }
+            if started.elapsed() + interval > timeout {
+                return Err(ClientError::Timeout(format!(
+                    "Export {} still {} after {:?}",
+                    id, job.status, timeout
+                )));
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// The file of a completed export, in memory
+    pub async fn download(&self, id: Uuid) -> Result<Vec<u8>> {
+        let response = self.transport.send(Call::get(format!("/gis-export/download/{}", id))).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// The file of a completed export as it arrives, for files too large
+    /// to hold in memory
+    pub async fn download_stream(&self, id: Uuid) -> Result<impl Stream<Item = Result<bytes::Bytes>>> {
+        let response = self.transport.send(Call::get(format!("/gis-export/download/{}", id))).await?;
+        Ok(response.bytes_stream().map_err(ClientError::from))
+    }
+}
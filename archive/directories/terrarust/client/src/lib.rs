@@ -0,0 +1,156 @@
+//! Client for the TerraFusion public API.
+//!
+//! County vendors integrating with TerraFusion talk to the API gateway at
+//! `/api/v1` with an API key. This crate wraps those calls in typed models:
+//!
+//! ```no_run
+//! # async fn run() -> terrafusion_client::Result<()> {
+//! use futures_util::TryStreamExt;
+//! use terrafusion_client::{Client, Credentials};
+//!
+//! let client = Client::builder("https://terrafusion.example.org")
+//!     .credentials(Credentials::api_key("tfk_...")?)
+//!     .build()?;
+//!
+//! let pairs = client.sync().list_sync_pairs(1, 50).await?;
+//! let operation = client.sync().start_operation(pairs.items[0].id, None).await?;
+//!
+//! let mut jobs = client.gis_export().jobs(Default::default());
+//! while let Some(job) = jobs.try_next().await? {
+//!     println!("{} {}", job.job_id, job.status);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Failed requests are retried with exponential backoff when the failure is
+//! transient (connection errors, 429, 502, 503 and 504), honouring
+//! `Retry-After`. Requests that create something are sent with an
+//! `Idempotency-Key`, so a retry returns the original result instead of
+//! creating a second sync operation or export.
+//!
+//! Models keep the fields integrations rely on; anything else the API
+//! returns is kept in their `extra` map, so newer servers do not break
+//! older clients.
+
+mod auth;
+mod error;
+mod gis_export;
+mod models;
+mod pagination;
+mod retry;
+mod sync;
+mod transport;
+
+use std::time::Duration;
+
+pub use auth::Credentials;
+pub use error::{ApiError, ClientError, Result};
+pub use gis_export::GisExportClient;
+pub use models::*;
+pub use pagination::{Page, PageStream};
+pub use retry::RetryPolicy;
+pub use sync::SyncServiceClient;
+
+use transport::Transport;
+
+/// Default time allowed for one request, including reading the body
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Entry point to the API; cheap to clone
+#[derive(Clone)]
+pub struct Client {
+    transport: Transport,
+}
+
+impl Client {
+    /// Start configuring a client for the gateway at `base_url`, e.g.
+    /// `https://terrafusion.example.org`
+    pub fn builder(base_url: impl Into<String>) -> ClientBuilder {
+        ClientBuilder {
+            base_url: base_url.into(),
+            credentials: Credentials::None,
+            retry: RetryPolicy::default(),
+            timeout: DEFAULT_TIMEOUT,
+            user_agent: None,
+            http: None,
+        }
+    }
+
+    /// A client configured from `TERRAFUSION_URL` and `TERRAFUSION_API_KEY`
+    pub fn from_env() -> Result<Self> {
+        let base_url = std::env::var("TERRAFUSION_URL")
+            .map_err(|_| ClientError::Config("TERRAFUSION_URL is not set".to_string()))?;
+        Self::builder(base_url).credentials(Credentials::from_env()?).build()
+    }
+
+    /// Sync pairs and sync operations
+    pub fn sync(&self) -> SyncServiceClient {
+        SyncServiceClient::new(self.transport.clone())
+    }
+
+    /// GIS export jobs and downloads
+    pub fn gis_export(&self) -> GisExportClient {
+        GisExportClient::new(self.transport.clone())
+    }
+}
+
+/// Configuration of a [`Client`]
+pub struct ClientBuilder {
+    base_url: String,
+    credentials: Credentials,
+    retry: RetryPolicy,
+    timeout: Duration,
+    user_agent: Option<String>,
+    http: Option<reqwest::Client>,
+}
+
+impl ClientBuilder {
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Identify the integration in the gateway's request logs
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Use a preconfigured HTTP client, e.g. with a proxy or custom root
+    /// certificates. `timeout` and `user_agent` are then ignored.
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let base_url = reqwest::Url::parse(self.base_url.trim_end_matches('/'))
+            .map_err(|e| ClientError::Config(format!("Invalid base URL {}: {}", self.base_url, e)))?;
+        let http = match self.http {
+            Some(http) => http,
+            None => reqwest::Client::builder()
+                .timeout(self.timeout)
+                .user_agent(
+                    self.user_agent
+                        .unwrap_or_else(|| format!("terrafusion-client/{}", env!("CARGO_PKG_VERSION"))),
+                )
+                .build()
+                .map_err(|e| ClientError::Config(format!("Failed to build HTTP client: {}", e)))?,
+        };
+
+        Ok(Client {
+            transport: Transport::new(http, base_url, self.credentials, self.retry),
+        })
+    }
+}
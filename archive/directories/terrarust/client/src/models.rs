@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+/// Fields the client does not model
+pub type Extra = Map<String, Value>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SyncStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Canceled,
+}
+
+impl SyncStatus {
+    /// The status as the API writes it
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncStatus::Pending => "PENDING",
+            SyncStatus::Running => "RUNNING",
+            SyncStatus::Completed => "COMPLETED",
+            SyncStatus::Failed => "FAILED",
+            SyncStatus::Canceled => "CANCELED",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, SyncStatus::Completed | SyncStatus::Failed | SyncStatus::Canceled)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ConflictStrategy {
+    #[serde(alias = "SOURCE_WINS")]
+    SourceWins,
+    #[serde(alias = "TARGET_WINS")]
+    TargetWins,
+    #[serde(alias = "NEWER_WINS")]
+    NewerWins,
+    #[default]
+    Manual,
+}
+
+/// A configured source-to-target synchronization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPair {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub source_system: String,
+    pub source_config: Value,
+    pub target_system: String,
+    pub target_config: Value,
+    pub county_id: String,
+    pub is_active: bool,
+    pub sync_interval_minutes: i32,
+    #[serde(default)]
+    pub sync_conflict_strategy: ConflictStrategy,
+    #[serde(default)]
+    pub last_sync_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_sync_status: Option<SyncStatus>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A sync pair to create
+#[derive(Debug, Clone, Serialize)]
+pub struct NewSyncPair {
+    pub name: String,
+    pub description: Option<String>,
+    pub source_system: String,
+    pub source_config: Value,
+    pub target_system: String,
+    pub target_config: Value,
+    pub county_id: String,
+    pub is_active: bool,
+    pub sync_interval_minutes: i32,
+    pub sync_conflict_strategy: ConflictStrategy,
+}
+
+/// A sync pair list page, numbered from 1
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPairList {
+    #[serde(rename = "sync_pairs")]
+    pub items: Vec<SyncPair>,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+/// Acknowledgement of a started operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartedOperation {
+    pub operation_id: Uuid,
+    pub status: SyncStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Progress of a sync operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncOperation {
+    pub id: Uuid,
+    pub sync_pair_id: Uuid,
+    pub status: SyncStatus,
+    pub start_time: DateTime<Utc>,
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "count")]
+    pub records_processed: i64,
+    #[serde(default, deserialize_with = "count")]
+    pub records_succeeded: i64,
+    #[serde(default, deserialize_with = "count")]
+    pub records_failed: i64,
+    #[serde(default)]
+    pub error_message: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Filters for listing sync operations
+#[derive(Debug, Clone, Default)]
+pub struct SyncOperationFilter {
+    pub sync_pair_id: Option<Uuid>,
+    pub status: Option<SyncStatus>,
+    /// Operations started at or after this time
+    pub from_date: Option<DateTime<Utc>>,
+    /// Operations started before this time
+    pub to_date: Option<DateTime<Utc>>,
+    /// Operations per request while paging; the server's default when unset
+    pub per_page: Option<u32>,
+}
+
+/// One record a sync operation compared
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncDiff {
+    pub id: Uuid,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub change_type: String,
+    pub sync_status: String,
+    #[serde(default)]
+    pub source_data: Option<Value>,
+    #[serde(default)]
+    pub target_data: Option<Value>,
+    #[serde(default)]
+    pub diff_details: Option<Value>,
+    #[serde(default)]
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A GIS export to create
+#[derive(Debug, Clone, Serialize)]
+pub struct NewExportJob {
+    pub county_id: String,
+    pub username: String,
+    /// e.g. `geojson`, `shapefile`, `csv`
+    pub export_format: String,
+    /// GeoJSON, WKT or WKB geometry in WGS 84
+    pub area_of_interest: Value,
+    pub layers: Vec<String>,
+    /// Compression, delivery, `as_of` and other options
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub parameters: HashMap<String, Value>,
+}
+
+/// A GIS export job and, once completed, its file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportJob {
+    pub job_id: Uuid,
+    pub county_id: String,
+    pub username: String,
+    pub export_format: String,
+    /// `PENDING`, `PROCESSING`, `COMPLETED`, `FAILED`, `CANCELLED`, or the
+    /// review states of counties that approve exports
+    pub status: String,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub file_size: Option<i64>,
+    #[serde(default)]
+    pub download_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// SHA-256 of the downloadable file
+    #[serde(default)]
+    pub checksum_sha256: Option<String>,
+    /// Data version of each layer the export was generated from
+    #[serde(default)]
+    pub data_versions: Option<HashMap<String, i64>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl ExportJob {
+    pub fn is_completed(&self) -> bool {
+        self.status == "COMPLETED"
+    }
+
+    /// Whether the job will not change status any more
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status.as_str(), "COMPLETED" | "FAILED" | "CANCELLED" | "REJECTED")
+    }
+}
+
+/// Filters for listing export jobs
+#[derive(Debug, Clone, Default)]
+pub struct ExportJobFilter {
+    pub county_id: Option<String>,
+    pub username: Option<String>,
+    pub status: Option<String>,
+    /// Jobs per request while paging; the server's default when unset
+    pub per_page: Option<u32>,
+}
+
+// Stored operations report null counts until their first batch is written
+fn count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    Ok(Option::<i64>::deserialize(deserializer)?.unwrap_or(0))
+}
@@ -0,0 +1,63 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use futures_util::stream::{self, Stream, StreamExt, TryStreamExt};
+use serde::Deserialize;
+
+use crate::error::Result;
+
+/// One page of a list, as the API returns it
+#[derive(Debug, Clone, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub per_page: u32,
+    /// Page number, for lists paged by number
+    #[serde(default)]
+    pub page: Option<u64>,
+    /// Total across pages, when the list reports one
+    #[serde(default)]
+    pub total: Option<i64>,
+    /// Cursor of the next page; absent on the last
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Every item of a cursor-paged list, fetching pages as they are consumed
+pub type PageStream<'a, T> = Pin<Box<dyn Stream<Item = Result<T>> + Send + 'a>>;
+
+/// Walk a cursor-paged list from the first page. `fetch` gets the cursor of
+/// the page to load, empty for the first.
+pub(crate) fn paginate<'a, T, F, Fut>(fetch: F) -> PageStream<'a, T>
+where
+    T: Send + 'a,
+    F: Fn(String) -> Fut + Send + Sync + 'a,
+    Fut: Future<Output = Result<Page<T>>> + Send + 'a,
+{
+    let pages = stream::try_unfold((Some(String::new()), fetch), |(cursor, fetch)| async move {
+        let Some(cursor) = cursor else {
+            return Ok(None);
+        };
+        let page = fetch(cursor).await?;
+        Ok(Some((page.items, (page.next_cursor, fetch))))
+    });
+    pages.map_ok(|items| stream::iter(items.into_iter().map(Ok))).try_flatten().boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_paginate_follows_cursors_to_the_last_page() {
+        let stream = paginate(|cursor: String| async move {
+            let (items, next_cursor) = match cursor.as_str() {
+                "" => (vec![1, 2], Some("a".to_string())),
+                "a" => (vec![3], Some("b".to_string())),
+                _ => (vec![], None),
+            };
+            Ok(Page { items, per_page: 2, page: None, total: None, next_cursor })
+        });
+        let items: Vec<i32> = stream.try_collect().await.unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+}
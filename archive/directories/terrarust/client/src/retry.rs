@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+/// How transient failures are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts per request, the first included; 1 disables retries
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubled for each one after
+    pub initial_backoff: Duration,
+    /// Longest wait between attempts, `Retry-After` included
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// Wait before retrying after failed attempt `attempt` (1-based), or
+    /// `None` when attempts are used up. The server's `Retry-After` wins over
+    /// the backoff, within `max_backoff`.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let backoff = retry_after.unwrap_or_else(|| {
+            self.initial_backoff.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        });
+        Some(backoff.min(self.max_backoff))
+    }
+}
+
+/// `Retry-After` in seconds; HTTP dates are not sent by the gateway
+pub(crate) fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_and_honours_retry_after() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(1, None), Some(Duration::from_millis(500)));
+        assert_eq!(policy.delay(2, None), Some(Duration::from_secs(1)));
+        assert_eq!(policy.delay(3, None), Some(Duration::from_secs(2)));
+        assert_eq!(policy.delay(4, None), None);
+
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(5))), Some(Duration::from_secs(5)));
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(600))), Some(Duration::from_secs(30)));
+        assert_eq!(RetryPolicy::none().delay(1, None), None);
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+    }
+}
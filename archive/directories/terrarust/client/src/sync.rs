@@ -0,0 +1,99 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::models::{
+    NewSyncPair, StartedOperation, SyncDiff, SyncOperation, SyncOperationFilter, SyncPair, SyncPairList,
+};
+use crate::pagination::{paginate, Page, PageStream};
+use crate::transport::{Call, Transport};
+
+/// Sync pairs and sync operations of the caller's county
+#[derive(Clone)]
+pub struct SyncServiceClient {
+    transport: Transport,
+}
+
+#[derive(Deserialize)]
+struct DiffPage {
+    page: Page<SyncDiff>,
+}
+
+impl SyncServiceClient {
+    pub(crate) fn new(transport: Transport) -> Self {
+        Self { transport }
+    }
+
+    /// One page of sync pairs, numbered from 1
+    pub async fn list_sync_pairs(&self, page: u32, per_page: u32) -> Result<SyncPairList> {
+        self.transport
+            .json(Call::get("/sync-pairs").query("page", page.max(1)).query("per_page", per_page))
+            .await
+    }
+
+    pub async fn get_sync_pair(&self, id: Uuid) -> Result<SyncPair> {
+        self.transport.json(Call::get(format!("/sync-pairs/{}", id))).await
+    }
+
+    /// Create a sync pair; needs an administrator's key
+    pub async fn create_sync_pair(&self, pair: &NewSyncPair) -> Result<SyncPair> {
+        self.transport.json(Call::post("/sync-pairs").json(pair)?.once()).await
+    }
+
+    /// Turn scheduled syncs of a pair on or off
+    pub async fn set_sync_pair_active(&self, id: Uuid, is_active: bool) -> Result<Value> {
+        let call = Call::post(format!("/sync-pairs/{}/toggle", id)).json(&json!({ "is_active": is_active }))?;
+        self.transport.json(call).await
+    }
+
+    /// Start syncing a pair now. `custom_parameters` are handed to the
+    /// pair's connectors.
+    pub async fn start_operation(&self, sync_pair_id: Uuid, custom_parameters: Option<Value>) -> Result<StartedOperation> {
+        let body = json!({ "sync_pair_id": sync_pair_id, "custom_parameters": custom_parameters });
+        self.transport.json(Call::post("/sync-operations").json(&body)?.once()).await
+    }
+
+    pub async fn get_operation(&self, id: Uuid) -> Result<SyncOperation> {
+        self.transport.json(Call::get(format!("/sync-operations/{}", id))).await
+    }
+
+    /// Every operation matching the filter, newest first
+    pub fn operations(&self, filter: SyncOperationFilter) -> PageStream<'static, SyncOperation> {
+        let transport = self.transport.clone();
+        paginate(move |cursor| {
+            let transport = transport.clone();
+            let filter = filter.clone();
+            async move {
+                let call = Call::get("/sync-operations")
+                    .query("cursor", cursor)
+                    .query_opt("per_page", filter.per_page)
+                    .query_opt("sync_pair_id", filter.sync_pair_id)
+                    .query_opt("status", filter.status.map(|status| status.as_str()))
+                    .query_opt("from_date", filter.from_date.map(|date| date.to_rfc3339()))
+                    .query_opt("to_date", filter.to_date.map(|date| date.to_rfc3339()));
+                transport.json::<Page<SyncOperation>>(call).await
+            }
+        })
+    }
+
+    /// Cancel a running operation
+    pub async fn cancel_operation(&self, id: Uuid) -> Result<Value> {
+        self.transport.json(Call::post(format!("/sync-operations/{}/cancel", id))).await
+    }
+
+    /// Every record an operation compared, in pages of `per_page`
+    pub fn operation_diffs(&self, id: Uuid, per_page: u32) -> PageStream<'static, SyncDiff> {
+        let transport = self.transport.clone();
+        paginate(move |cursor| {
+            let transport = transport.clone();
+            async move {
+                let call = Call::get(format!("/sync-operations/{}/diffs", id))
+                    .query("cursor", cursor)
+                    .query("per_page", per_page);
+                let page: DiffPage = transport.json(call).await?;
+                Ok(page.page)
+            }
+        })
+    }
+}
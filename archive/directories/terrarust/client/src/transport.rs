@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use reqwest::{Method, Response, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::auth::Credentials;
+use crate::error::{ApiError, ClientError, Result};
+use crate::retry::{self, RetryPolicy};
+
+/// Where the gateway serves the public API
+const API_PREFIX: &str = "/api/v1";
+
+/// Header the services deduplicate creating requests by
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Sends requests to the gateway with credentials and retries
+#[derive(Clone)]
+pub(crate) struct Transport {
+    http: reqwest::Client,
+    api_root: String,
+    credentials: Arc<Credentials>,
+    retry: RetryPolicy,
+}
+
+/// One request; `idempotency_key` makes a POST safe to retry
+pub(crate) struct Call<'a> {
+    pub method: Method,
+    pub path: String,
+    pub query: Vec<(&'a str, String)>,
+    pub body: Option<serde_json::Value>,
+    pub idempotency_key: Option<String>,
+}
+
+impl<'a> Call<'a> {
+    pub fn get(path: impl Into<String>) -> Self {
+        Self { method: Method::GET, path: path.into(), query: Vec::new(), body: None, idempotency_key: None }
+    }
+
+    pub fn post(path: impl Into<String>) -> Self {
+        Self { method: Method::POST, ..Self::get(path) }
+    }
+
+    pub fn query(mut self, name: &'a str, value: impl ToString) -> Self {
+        self.query.push((name, value.to_string()));
+        self
+    }
+
+    pub fn query_opt(self, name: &'a str, value: Option<impl ToString>) -> Self {
+        match value {
+            Some(value) => self.query(name, value),
+            None => self,
+        }
+    }
+
+    pub fn json(mut self, body: &impl Serialize) -> Result<Self> {
+        self.body = Some(serde_json::to_value(body).map_err(|e| ClientError::Decode(e.to_string()))?);
+        Ok(self)
+    }
+
+    /// Send with a fresh `Idempotency-Key`, reused by every retry
+    pub fn once(mut self) -> Self {
+        self.idempotency_key = Some(uuid::Uuid::new_v4().to_string());
+        self
+    }
+
+    fn retryable(&self) -> bool {
+        self.method != Method::POST || self.idempotency_key.is_some()
+    }
+}
+
+impl Transport {
+    pub fn new(http: reqwest::Client, base_url: Url, credentials: Credentials, retry: RetryPolicy) -> Self {
+        let base = base_url.as_str().trim_end_matches('/');
+        let api_root = if base.ends_with(API_PREFIX) { base.to_string() } else { format!("{}{}", base, API_PREFIX) };
+        Self { http, api_root, credentials: Arc::new(credentials), retry }
+    }
+
+    /// Send and decode a JSON answer
+    pub async fn json<T: DeserializeOwned>(&self, call: Call<'_>) -> Result<T> {
+        let response = self.send(call).await?;
+        let body = response.bytes().await?;
+        serde_json::from_slice(&body).map_err(|e| ClientError::Decode(e.to_string()))
+    }
+
+    /// Send and return the successful response, retrying transient failures
+    pub async fn send(&self, call: Call<'_>) -> Result<Response> {
+        let url = format!("{}{}", self.api_root, call.path);
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut request = self.http.request(call.method.clone(), &url).query(&call.query);
+            request = self.credentials.apply(request);
+            if let Some(key) = &call.idempotency_key {
+                request = request.header(IDEMPOTENCY_KEY_HEADER, key);
+            }
+            if let Some(body) = &call.body {
+                request = request.json(body);
+            }
+
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status();
+                    let retry_after = retry::retry_after(response.headers());
+                    let body = response.text().await.unwrap_or_default();
+                    ClientError::Api { error: ApiError::from_body(status, &body), retry_after }
+                }
+                Err(e) => ClientError::Http(e),
+            };
+
+            let retry_after = match &error {
+                ClientError::Api { retry_after, .. } => *retry_after,
+                _ => None,
+            };
+            let delay = match self.retry.delay(attempt, retry_after) {
+                Some(delay) if error.is_transient() && call.retryable() => delay,
+                _ => return Err(error),
+            };
+            log::debug!("{} {} failed ({}); retrying in {:?}", call.method, call.path, error, delay);
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
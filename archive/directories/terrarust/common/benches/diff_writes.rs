@@ -0,0 +1,104 @@
+//! Throughput of writing 10k sync diffs one INSERT at a time, as multi-row
+//! INSERTs and through COPY, both in batches of 1000.
+//!
+//! Needs a PostgreSQL database: run with
+//! `BENCH_DATABASE_URL=postgres://... cargo bench -p terrafusion-common --bench diff_writes`.
+//! Diffs go to a temporary `sync_diffs` table with the real table's columns
+//! and indexes, which hides any real one for the bench's single connection,
+//! so nothing is left behind and no sync operations need to exist.
+
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Executor, PgPool};
+use terrafusion_common::database::diff_writer::{write_diffs, DiffWriteMode, NewSyncDiff};
+use uuid::Uuid;
+
+const DIFFS: usize = 10_000;
+const BATCH_SIZE: usize = 1000;
+
+const CREATE_TABLE: &str = r#"
+    CREATE TEMPORARY TABLE sync_diffs (
+        id UUID PRIMARY KEY,
+        sync_operation_id UUID NOT NULL,
+        entity_id VARCHAR(255) NOT NULL,
+        entity_type VARCHAR(255) NOT NULL,
+        change_type VARCHAR(50) NOT NULL,
+        source_data JSONB,
+        target_data JSONB,
+        diff_details JSONB,
+        sync_status VARCHAR(50) NOT NULL,
+        error_message TEXT,
+        created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+        updated_at TIMESTAMP WITH TIME ZONE NOT NULL
+    );
+    CREATE INDEX ON sync_diffs (sync_operation_id);
+    CREATE INDEX ON sync_diffs (entity_type, entity_id);
+    CREATE INDEX ON sync_diffs (created_at);
+"#;
+
+fn diffs(operation_id: Uuid) -> Vec<NewSyncDiff> {
+    (0..DIFFS)
+        .map(|id| NewSyncDiff {
+            id: Uuid::new_v4(),
+            sync_operation_id: operation_id,
+            entity_id: format!("1{:010}", id),
+            entity_type: "parcel".to_string(),
+            change_type: "MODIFIED".to_string(),
+            source_data: Some(json!({
+                "parcel_id": format!("1{:010}", id),
+                "owner": "BENTON COUNTY",
+                "assessed_value": 150_000 + id % 500_000,
+                "situs": format!("{} W KENNEWICK AVE", id % 9000),
+            })),
+            target_data: Some(json!({ "parcel_id": format!("1{:010}", id), "assessed_value": 149_000 + id % 500_000 })),
+            diff_details: None,
+            sync_status: "SYNCED".to_string(),
+            error_message: None,
+            created_at: Utc::now(),
+        })
+        .collect()
+}
+
+async fn write_all(pool: &PgPool, mode: DiffWriteMode, rows: &[NewSyncDiff], batch_size: usize) {
+    for batch in rows.chunks(batch_size) {
+        write_diffs(pool, mode, batch).await.unwrap();
+    }
+}
+
+fn bench_diff_writes(c: &mut Criterion) {
+    let Ok(url) = std::env::var("BENCH_DATABASE_URL") else {
+        println!("BENCH_DATABASE_URL is not set; skipping the diff write benchmark");
+        return;
+    };
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    // One connection, so every write sees the temporary table
+    let pool = runtime.block_on(async {
+        let pool = PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap();
+        pool.execute(CREATE_TABLE).await.unwrap();
+        pool
+    });
+
+    let mut group = c.benchmark_group("diff_writes");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(DIFFS as u64));
+    for (name, mode, batch_size) in [
+        ("single_row_insert", DiffWriteMode::Insert, 1),
+        ("multi_row_insert_1000", DiffWriteMode::Insert, BATCH_SIZE),
+        ("copy_1000", DiffWriteMode::Copy, BATCH_SIZE),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || diffs(Uuid::new_v4()),
+                |rows| runtime.block_on(write_all(&pool, mode, &rows, batch_size)),
+                BatchSize::LargeInput,
+            )
+        });
+        runtime.block_on(sqlx::query("TRUNCATE sync_diffs").execute(&pool)).unwrap();
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_diff_writes);
+criterion_main!(benches);
@@ -0,0 +1,111 @@
+//! Streaming GeoJSON throughput, and peak heap use for 10k and 1M features.
+//!
+//! Run with `cargo bench -p terrafusion-common --bench geojson_stream`. The
+//! peak-memory lines printed before the timings should be about the same for
+//! both sizes: the reader and writer hold one feature at a time.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use geojson::{Feature, Geometry, Value};
+use terrafusion_common::geo::{FeatureReader, FeatureWriter};
+
+/// Tracks live and peak heap bytes
+struct PeakAlloc;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(live, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAlloc = PeakAlloc;
+
+/// Heap bytes allocated at the peak of `f`, above what was live before it
+fn peak_during<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let baseline = LIVE.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let result = f();
+    (result, PEAK.load(Ordering::Relaxed) - baseline)
+}
+
+fn parcel(id: u64) -> Feature {
+    let x = -119.5 + (id % 1000) as f64 * 0.001;
+    let y = 46.0 + (id / 1000) as f64 * 0.0001;
+    let mut properties = serde_json::Map::new();
+    properties.insert("parcel_id".to_string(), format!("1{:010}", id).into());
+    properties.insert("owner".to_string(), "BENTON COUNTY".into());
+    properties.insert("assessed_value".to_string(), (150_000 + id % 500_000).into());
+    Feature {
+        bbox: None,
+        geometry: Some(Geometry::new(Value::Polygon(vec![vec![
+            vec![x, y],
+            vec![x + 0.0005, y],
+            vec![x + 0.0005, y + 0.0005],
+            vec![x, y + 0.0005],
+            vec![x, y],
+        ]]))),
+        id: None,
+        properties: Some(properties),
+        foreign_members: None,
+    }
+}
+
+fn write_file(path: &Path, features: u64) {
+    let mut writer = FeatureWriter::create(path).unwrap();
+    for id in 0..features {
+        writer.write(&parcel(id)).unwrap();
+    }
+    writer.finish().unwrap();
+}
+
+fn read_file(path: &Path) -> u64 {
+    FeatureReader::open(path).unwrap().map(|feature| feature.unwrap()).count() as u64
+}
+
+fn bench_geojson_stream(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+
+    for features in [10_000u64, 1_000_000] {
+        let path = dir.path().join(format!("parcels_{}.geojson", features));
+        let ((), write_peak) = peak_during(|| write_file(&path, features));
+        let (read, read_peak) = peak_during(|| read_file(&path));
+        assert_eq!(read, features);
+        println!(
+            "{} features ({} MB): peak heap {} KB writing, {} KB reading",
+            features,
+            std::fs::metadata(&path).unwrap().len() / 1_000_000,
+            write_peak / 1024,
+            read_peak / 1024
+        );
+    }
+
+    let path = dir.path().join("parcels_1000000.geojson");
+    let mut group = c.benchmark_group("geojson_stream");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(1_000_000));
+    group.bench_function("read_1m_features", |b| b.iter(|| read_file(&path)));
+    group.bench_function("write_1m_features", |b| {
+        b.iter(|| write_file(&dir.path().join("parcels_out.geojson"), 1_000_000))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_geojson_stream);
+criterion_main!(benches);
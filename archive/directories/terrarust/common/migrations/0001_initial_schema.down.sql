@@ -0,0 +1,10 @@
+-- Drop all tables in reverse order of creation
+DROP TABLE IF EXISTS metrics;
+DROP TABLE IF EXISTS gis_exports;
+DROP TABLE IF EXISTS users;
+DROP TABLE IF EXISTS audit_log;
+DROP TABLE IF EXISTS sync_stats;
+DROP TABLE IF EXISTS validation_issues;
+DROP TABLE IF EXISTS sync_diffs;
+DROP TABLE IF EXISTS sync_operations;
+DROP TABLE IF EXISTS sync_pairs;
\ No newline at end of file
@@ -0,0 +1,8 @@
+DROP INDEX IF EXISTS idx_sync_diffs_entity;
+DROP INDEX IF EXISTS idx_sync_pairs_county_id;
+DROP TABLE IF EXISTS sync_records;
+
+ALTER TABLE sync_operations DROP COLUMN IF EXISTS custom_parameters;
+
+ALTER TABLE sync_pairs DROP COLUMN IF EXISTS last_sync_status;
+ALTER TABLE sync_pairs DROP COLUMN IF EXISTS updated_by;
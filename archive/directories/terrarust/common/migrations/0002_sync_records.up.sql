@@ -0,0 +1,28 @@
+-- Columns and tables used by the sync engine models
+
+ALTER TABLE sync_pairs ADD COLUMN IF NOT EXISTS updated_by VARCHAR(255);
+ALTER TABLE sync_pairs ADD COLUMN IF NOT EXISTS last_sync_status VARCHAR(50);
+
+ALTER TABLE sync_operations ADD COLUMN IF NOT EXISTS custom_parameters JSONB;
+
+-- Create sync records table
+CREATE TABLE IF NOT EXISTS sync_records (
+    id UUID PRIMARY KEY,
+    sync_operation_id UUID NOT NULL REFERENCES sync_operations(id) ON DELETE CASCADE,
+    source_id VARCHAR(255) NOT NULL,
+    target_id VARCHAR(255),
+    record_type VARCHAR(255) NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    source_data JSONB NOT NULL,
+    target_data JSONB,
+    error_message TEXT,
+    conflict BOOLEAN,
+    resolution VARCHAR(50),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_records_sync_operation_id ON sync_records(sync_operation_id);
+CREATE INDEX IF NOT EXISTS idx_sync_records_source_id ON sync_records(source_id);
+CREATE INDEX IF NOT EXISTS idx_sync_pairs_county_id ON sync_pairs(county_id);
+CREATE INDEX IF NOT EXISTS idx_sync_diffs_entity ON sync_diffs(entity_type, entity_id);
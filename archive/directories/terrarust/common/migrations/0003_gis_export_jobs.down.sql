@@ -0,0 +1 @@
+DROP TABLE IF EXISTS gis_export_jobs;
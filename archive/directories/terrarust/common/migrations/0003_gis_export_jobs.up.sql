@@ -0,0 +1,24 @@
+-- GIS export job queue used by the gis_export service
+
+CREATE TABLE IF NOT EXISTS gis_export_jobs (
+    id SERIAL PRIMARY KEY,
+    job_id UUID NOT NULL UNIQUE,
+    county_id VARCHAR(255) NOT NULL,
+    username VARCHAR(255) NOT NULL,
+    export_format VARCHAR(50) NOT NULL,
+    area_of_interest JSONB NOT NULL,
+    layers JSONB NOT NULL,
+    parameters JSONB,
+    status VARCHAR(50) NOT NULL,
+    message TEXT,
+    file_path VARCHAR(1024),
+    file_size BIGINT,
+    download_url VARCHAR(1024),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_gis_export_jobs_county_id ON gis_export_jobs(county_id);
+CREATE INDEX IF NOT EXISTS idx_gis_export_jobs_status ON gis_export_jobs(status);
+CREATE INDEX IF NOT EXISTS idx_gis_export_jobs_created_at ON gis_export_jobs(created_at);
@@ -0,0 +1 @@
+ALTER TABLE sync_operations DROP COLUMN IF EXISTS narrative;
@@ -0,0 +1,3 @@
+-- Plain-English summary generated by NarratorAI when an operation finishes
+
+ALTER TABLE sync_operations ADD COLUMN IF NOT EXISTS narrative TEXT;
@@ -0,0 +1,29 @@
+-- Embedding vectors for similarity matching (requires the pgvector extension).
+-- Skipped on servers without pgvector; similarity matching is then unavailable.
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_available_extensions WHERE name = 'vector') THEN
+        CREATE EXTENSION IF NOT EXISTS vector;
+
+        CREATE TABLE IF NOT EXISTS entity_embeddings (
+            id UUID PRIMARY KEY,
+            entity_type VARCHAR(100) NOT NULL,
+            entity_id VARCHAR(255) NOT NULL,
+            county_id VARCHAR(255) NOT NULL,
+            model VARCHAR(255) NOT NULL,
+            embedding vector(768) NOT NULL,
+            content_hash VARCHAR(64) NOT NULL,
+            metadata JSONB,
+            created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+            updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+            UNIQUE (entity_type, entity_id, county_id, model)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_entity_embeddings_scope
+            ON entity_embeddings(entity_type, county_id, model);
+        CREATE INDEX IF NOT EXISTS idx_entity_embeddings_vector
+            ON entity_embeddings USING hnsw (embedding vector_cosine_ops);
+    END IF;
+END
+$$;
@@ -0,0 +1,16 @@
+-- Saved GIS export settings, managed per county and through configuration bundles
+
+CREATE TABLE IF NOT EXISTS export_templates (
+    id UUID PRIMARY KEY,
+    county_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    export_format VARCHAR(50) NOT NULL,
+    layers JSONB NOT NULL,
+    parameters JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (county_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_export_templates_county_id ON export_templates(county_id);
@@ -0,0 +1 @@
+ALTER TABLE sync_pairs DROP COLUMN IF EXISTS field_mappings;
@@ -0,0 +1,3 @@
+-- Source-to-target field mappings for each sync pair
+
+ALTER TABLE sync_pairs ADD COLUMN IF NOT EXISTS field_mappings JSONB NOT NULL DEFAULT '[]';
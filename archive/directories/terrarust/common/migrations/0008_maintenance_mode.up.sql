@@ -0,0 +1,9 @@
+-- Active maintenance windows; scope is a county ID or '*' for the whole platform
+
+CREATE TABLE IF NOT EXISTS maintenance_mode (
+    scope VARCHAR(50) PRIMARY KEY,
+    message TEXT NOT NULL,
+    started_by VARCHAR(100) NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ends_at TIMESTAMPTZ
+);
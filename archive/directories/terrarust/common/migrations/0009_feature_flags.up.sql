@@ -0,0 +1,23 @@
+-- Feature flags with per-county overrides
+
+CREATE TABLE IF NOT EXISTS feature_flags (
+    key VARCHAR(100) PRIMARY KEY,
+    description TEXT NOT NULL DEFAULT '',
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    roles TEXT[] NOT NULL DEFAULT '{}',
+    updated_by VARCHAR(100) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS feature_flag_overrides (
+    flag_key VARCHAR(100) NOT NULL REFERENCES feature_flags(key) ON DELETE CASCADE,
+    county_id VARCHAR(50) NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    PRIMARY KEY (flag_key, county_id)
+);
+
+-- Features being rolled out county by county, off until enabled
+INSERT INTO feature_flags (key, description, updated_by) VALUES
+    ('bidirectional_sync', 'Write target changes back to the source system', 'migration'),
+    ('fgdb_export', 'Esri File Geodatabase export format', 'migration')
+ON CONFLICT (key) DO NOTHING;
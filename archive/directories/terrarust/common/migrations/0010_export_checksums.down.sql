@@ -0,0 +1,2 @@
+ALTER TABLE gis_export_jobs DROP COLUMN IF EXISTS manifest;
+ALTER TABLE gis_export_jobs DROP COLUMN IF EXISTS checksum_sha256;
@@ -0,0 +1,4 @@
+-- Integrity data for completed GIS exports
+
+ALTER TABLE gis_export_jobs ADD COLUMN IF NOT EXISTS checksum_sha256 VARCHAR(64);
+ALTER TABLE gis_export_jobs ADD COLUMN IF NOT EXISTS manifest JSONB;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_sync_diffs_created_at;
+DROP TABLE IF EXISTS sync_stats_daily;
+DROP TABLE IF EXISTS retention_policies;
@@ -0,0 +1,32 @@
+-- Per-county retention of sync diffs, with per-operation stats rolled up into daily totals
+
+CREATE TABLE IF NOT EXISTS retention_policies (
+    -- '*' holds the platform default
+    county_id VARCHAR(50) PRIMARY KEY,
+    diff_retention_days INTEGER NOT NULL CHECK (diff_retention_days > 0),
+    stats_retention_days INTEGER NOT NULL CHECK (stats_retention_days > 0),
+    updated_by VARCHAR(100) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO retention_policies (county_id, diff_retention_days, stats_retention_days, updated_by)
+VALUES ('*', 90, 365, 'migration')
+ON CONFLICT (county_id) DO NOTHING;
+
+-- Kept forever; one row per county and day the operations started
+CREATE TABLE IF NOT EXISTS sync_stats_daily (
+    county_id VARCHAR(255) NOT NULL,
+    day DATE NOT NULL,
+    operations BIGINT NOT NULL,
+    total_records BIGINT NOT NULL,
+    added_count BIGINT NOT NULL,
+    modified_count BIGINT NOT NULL,
+    deleted_count BIGINT NOT NULL,
+    unchanged_count BIGINT NOT NULL,
+    error_count BIGINT NOT NULL,
+    validation_issues_count BIGINT NOT NULL,
+    duration_seconds DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (county_id, day)
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_diffs_created_at ON sync_diffs(created_at);
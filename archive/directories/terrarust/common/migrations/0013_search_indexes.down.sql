@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_gis_export_jobs_job_id_text;
+DROP INDEX IF EXISTS idx_sync_operations_error_search;
+DROP INDEX IF EXISTS idx_sync_pairs_search;
@@ -0,0 +1,12 @@
+-- Full-text indexes behind the global search box
+
+CREATE INDEX IF NOT EXISTS idx_sync_pairs_search
+    ON sync_pairs USING GIN (to_tsvector('english', name || ' ' || coalesce(description, '')));
+
+CREATE INDEX IF NOT EXISTS idx_sync_operations_error_search
+    ON sync_operations USING GIN (to_tsvector('english', error_message))
+    WHERE error_message IS NOT NULL;
+
+-- Prefix lookups of export job IDs, e.g. the first block of a UUID
+CREATE INDEX IF NOT EXISTS idx_gis_export_jobs_job_id_text
+    ON gis_export_jobs ((job_id::text) text_pattern_ops);
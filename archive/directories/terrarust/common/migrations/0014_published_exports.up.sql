@@ -0,0 +1,15 @@
+-- Completed exports a county has chosen to offer on the public portal
+
+CREATE TABLE IF NOT EXISTS published_exports (
+    id UUID PRIMARY KEY,
+    job_id UUID NOT NULL UNIQUE REFERENCES gis_export_jobs(job_id) ON DELETE CASCADE,
+    county_id VARCHAR(255) NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    description TEXT,
+    published_by VARCHAR(255) NOT NULL,
+    published_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    download_count BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_published_exports_county_published_at
+    ON published_exports(county_id, published_at DESC);
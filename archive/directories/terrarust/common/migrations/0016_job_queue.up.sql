@@ -0,0 +1,30 @@
+-- Background jobs shared by every service instance; workers claim rows with FOR UPDATE SKIP LOCKED
+
+CREATE TABLE IF NOT EXISTS job_queue (
+    id UUID PRIMARY KEY,
+    queue VARCHAR(100) NOT NULL,
+    kind VARCHAR(255) NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}',
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL DEFAULT 5,
+    run_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    unique_key VARCHAR(255),
+    last_error TEXT,
+    locked_by VARCHAR(255),
+    locked_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT job_queue_status_check
+        CHECK (status IN ('pending', 'running', 'succeeded', 'failed', 'cancelled'))
+);
+
+-- Due jobs per queue, in the order workers claim them
+CREATE INDEX IF NOT EXISTS idx_job_queue_pending ON job_queue(queue, run_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_job_queue_running ON job_queue(locked_at) WHERE status = 'running';
+CREATE INDEX IF NOT EXISTS idx_job_queue_status ON job_queue(status, created_at);
+
+-- At most one active job per unique key
+CREATE UNIQUE INDEX IF NOT EXISTS idx_job_queue_unique_key
+    ON job_queue(unique_key) WHERE status IN ('pending', 'running');
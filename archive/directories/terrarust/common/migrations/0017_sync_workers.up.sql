@@ -0,0 +1,14 @@
+-- Sync service processes and what they are running, refreshed by each process's heartbeat
+
+CREATE TABLE IF NOT EXISTS sync_workers (
+    worker_id VARCHAR(100) PRIMARY KEY,
+    hostname VARCHAR(255) NOT NULL,
+    pid INTEGER NOT NULL,
+    role VARCHAR(20) NOT NULL,
+    version VARCHAR(50) NOT NULL,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_heartbeat_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    current_operations JSONB NOT NULL DEFAULT '[]'
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_workers_heartbeat ON sync_workers(last_heartbeat_at);
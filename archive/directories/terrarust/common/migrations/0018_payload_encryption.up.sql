@@ -0,0 +1,14 @@
+-- Versions of each county's payload encryption key. The key material stays
+-- in the secrets provider; rows only name the secret holding it.
+
+CREATE TABLE IF NOT EXISTS county_encryption_keys (
+    county_id VARCHAR(255) NOT NULL,
+    version INTEGER NOT NULL CHECK (version > 0),
+    secret_name VARCHAR(255) NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (county_id, version)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_county_encryption_keys_active
+    ON county_encryption_keys(county_id) WHERE active;
@@ -0,0 +1,3 @@
+-- Results of pushing completed exports to county GIS portals
+
+ALTER TABLE gis_export_jobs ADD COLUMN IF NOT EXISTS publications JSONB;
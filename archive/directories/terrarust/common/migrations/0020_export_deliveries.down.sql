@@ -0,0 +1 @@
+ALTER TABLE gis_export_jobs DROP COLUMN IF EXISTS deliveries;
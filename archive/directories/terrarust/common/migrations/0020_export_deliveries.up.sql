@@ -0,0 +1,3 @@
+-- Results of delivering completed exports to FTP and SFTP destinations
+
+ALTER TABLE gis_export_jobs ADD COLUMN IF NOT EXISTS deliveries JSONB;
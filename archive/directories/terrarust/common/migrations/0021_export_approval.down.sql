@@ -0,0 +1,3 @@
+ALTER TABLE gis_export_jobs DROP COLUMN IF EXISTS review_comment;
+ALTER TABLE gis_export_jobs DROP COLUMN IF EXISTS reviewed_at;
+ALTER TABLE gis_export_jobs DROP COLUMN IF EXISTS reviewed_by;
@@ -0,0 +1,5 @@
+-- Supervisor sign-off on exports above a county's approval thresholds
+
+ALTER TABLE gis_export_jobs ADD COLUMN IF NOT EXISTS reviewed_by VARCHAR(255);
+ALTER TABLE gis_export_jobs ADD COLUMN IF NOT EXISTS reviewed_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE gis_export_jobs ADD COLUMN IF NOT EXISTS review_comment TEXT;
@@ -0,0 +1,15 @@
+-- Monthly usage per county and user, for chargeback in hosted deployments
+
+CREATE TABLE IF NOT EXISTS usage_monthly (
+    county_id VARCHAR(255) NOT NULL,
+    -- '' for usage no user started, such as scheduled syncs and storage
+    username VARCHAR(255) NOT NULL DEFAULT '',
+    month DATE NOT NULL,
+    metric VARCHAR(50) NOT NULL,
+    quantity BIGINT NOT NULL DEFAULT 0,
+    bytes BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (county_id, month, metric, username)
+);
+
+CREATE INDEX IF NOT EXISTS idx_usage_monthly_month ON usage_monthly(month);
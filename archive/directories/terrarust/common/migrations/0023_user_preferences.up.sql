@@ -0,0 +1,3 @@
+-- Per-user UI preferences; the gateway copies them into cookies at sign-in
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS theme VARCHAR(16) NOT NULL DEFAULT 'auto';
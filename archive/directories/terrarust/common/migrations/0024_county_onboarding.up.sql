@@ -0,0 +1,30 @@
+-- County onboarding progress and the API keys it issues
+
+CREATE TABLE IF NOT EXISTS county_onboardings (
+    county_id VARCHAR(255) PRIMARY KEY,
+    county_name VARCHAR(255) NOT NULL,
+    -- Step name to {"completed_at": ..., "summary": {...}}
+    steps JSONB NOT NULL DEFAULT '{}'::jsonb,
+    status VARCHAR(20) NOT NULL DEFAULT 'in_progress',
+    created_by VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY,
+    county_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    -- First characters of the key, so administrators can tell keys apart
+    key_prefix VARCHAR(16) NOT NULL,
+    -- SHA-256 of the full key; the key itself is only shown once
+    key_hash VARCHAR(64) NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    created_by VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_county ON api_keys(county_id);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS geodata_uploads;
+DROP TABLE IF EXISTS layer_features;
+DROP TABLE IF EXISTS county_boundaries;
@@ -0,0 +1,41 @@
+-- County boundaries and layer seed data uploaded through the GIS export service
+
+CREATE EXTENSION IF NOT EXISTS postgis;
+
+CREATE TABLE IF NOT EXISTS county_boundaries (
+    county_id VARCHAR(255) PRIMARY KEY,
+    geom geometry(MultiPolygon, 4326) NOT NULL,
+    upload_id UUID NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_county_boundaries_geom ON county_boundaries USING GIST (geom);
+
+CREATE TABLE IF NOT EXISTS layer_features (
+    id BIGSERIAL PRIMARY KEY,
+    county_id VARCHAR(255) NOT NULL,
+    layer_id VARCHAR(255) NOT NULL,
+    upload_id UUID NOT NULL,
+    properties JSONB NOT NULL DEFAULT '{}'::jsonb,
+    geom geometry(Geometry, 4326) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_layer_features_layer ON layer_features(county_id, layer_id);
+CREATE INDEX IF NOT EXISTS idx_layer_features_geom ON layer_features USING GIST (geom);
+
+CREATE TABLE IF NOT EXISTS geodata_uploads (
+    id UUID PRIMARY KEY,
+    county_id VARCHAR(255) NOT NULL,
+    -- 'boundary' or the ID of the layer the features were loaded into
+    target VARCHAR(255) NOT NULL,
+    format VARCHAR(20) NOT NULL,
+    file_name VARCHAR(255),
+    -- CRS the file was in before reprojection to EPSG:4326
+    source_crs VARCHAR(64) NOT NULL,
+    feature_count INTEGER NOT NULL,
+    bbox JSONB,
+    uploaded_by VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_geodata_uploads_county ON geodata_uploads(county_id, created_at DESC);
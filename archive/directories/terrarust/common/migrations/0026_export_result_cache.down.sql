@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_gis_export_jobs_fingerprint;
+ALTER TABLE gis_export_jobs DROP COLUMN IF EXISTS reused_from;
+ALTER TABLE gis_export_jobs DROP COLUMN IF EXISTS result_fingerprint;
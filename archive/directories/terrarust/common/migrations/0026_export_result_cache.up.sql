@@ -0,0 +1,9 @@
+-- Reuse of identical GIS export results
+
+ALTER TABLE gis_export_jobs ADD COLUMN IF NOT EXISTS result_fingerprint VARCHAR(64);
+-- Completed export whose file this job serves instead of generating its own
+ALTER TABLE gis_export_jobs ADD COLUMN IF NOT EXISTS reused_from UUID;
+
+CREATE INDEX IF NOT EXISTS idx_gis_export_jobs_fingerprint
+    ON gis_export_jobs(result_fingerprint, completed_at DESC)
+    WHERE status = 'COMPLETED' AND reused_from IS NULL;
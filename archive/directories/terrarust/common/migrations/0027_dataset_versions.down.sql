@@ -0,0 +1,2 @@
+ALTER TABLE gis_export_jobs DROP COLUMN IF EXISTS data_versions;
+DROP TABLE IF EXISTS dataset_versions;
@@ -0,0 +1,19 @@
+-- Per-layer data versions, bumped by completed syncs and seed data uploads
+
+CREATE TABLE IF NOT EXISTS dataset_versions (
+    county_id VARCHAR(255) NOT NULL,
+    layer_id VARCHAR(255) NOT NULL,
+    version BIGINT NOT NULL,
+    -- 'sync' or 'upload'
+    source VARCHAR(20) NOT NULL,
+    sync_operation_id UUID,
+    upload_id UUID,
+    records_changed BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (county_id, layer_id, version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_dataset_versions_operation ON dataset_versions(sync_operation_id);
+
+-- Layer versions each export was generated from
+ALTER TABLE gis_export_jobs ADD COLUMN IF NOT EXISTS data_versions JSONB;
@@ -0,0 +1,23 @@
+-- Inbound webhooks that start a sync pair when an external system calls them
+
+CREATE TABLE IF NOT EXISTS sync_pair_hooks (
+    id UUID PRIMARY KEY,
+    sync_pair_id UUID NOT NULL REFERENCES sync_pairs(id) ON DELETE CASCADE,
+    county_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    -- Shared secret as a JSON string, or a sealed envelope when payload
+    -- encryption is enabled
+    secret JSONB NOT NULL,
+    -- Custom parameter name -> JSON pointer into the delivered payload
+    payload_mapping JSONB NOT NULL DEFAULT '{}',
+    -- JSON pointer -> value the payload must have for the hook to fire
+    conditions JSONB NOT NULL DEFAULT '{}',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_triggered_at TIMESTAMPTZ,
+    last_operation_id UUID,
+    UNIQUE (sync_pair_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_pair_hooks_pair ON sync_pair_hooks(sync_pair_id);
@@ -0,0 +1,36 @@
+-- Pipelines: sync pairs and exports run in dependency order
+
+CREATE TABLE IF NOT EXISTS pipelines (
+    id UUID PRIMARY KEY,
+    county_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    -- Ordered step definitions: name, type, depends_on, on_failure and the
+    -- sync pair or export to run
+    steps JSONB NOT NULL,
+    -- What a failed step does to the rest of the run unless the step says
+    -- otherwise: stop or continue
+    on_failure VARCHAR(20) NOT NULL DEFAULT 'stop',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (county_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS pipeline_runs (
+    id UUID PRIMARY KEY,
+    pipeline_id UUID NOT NULL REFERENCES pipelines(id) ON DELETE CASCADE,
+    county_id VARCHAR(255) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING',
+    initiated_by VARCHAR(255) NOT NULL,
+    -- The step definitions the run was started with, and for each step its
+    -- status, the operation or export it started, timing and error
+    steps JSONB NOT NULL DEFAULT '{}',
+    error_message TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_pipeline_runs_pipeline ON pipeline_runs(pipeline_id, created_at DESC);
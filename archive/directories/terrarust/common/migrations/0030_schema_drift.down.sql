@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS schema_snapshots;
+ALTER TABLE sync_pairs DROP COLUMN IF EXISTS attention_reason;
+ALTER TABLE sync_pairs DROP COLUMN IF EXISTS needs_attention;
@@ -0,0 +1,17 @@
+-- Schema drift: the last seen schema of each side of a sync pair, and a flag
+-- on pairs whose mapped fields have changed under them
+
+ALTER TABLE sync_pairs ADD COLUMN IF NOT EXISTS needs_attention BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE sync_pairs ADD COLUMN IF NOT EXISTS attention_reason TEXT;
+
+CREATE TABLE IF NOT EXISTS schema_snapshots (
+    sync_pair_id UUID NOT NULL REFERENCES sync_pairs(id) ON DELETE CASCADE,
+    -- source or target
+    side VARCHAR(10) NOT NULL,
+    -- Fields as the connector describes them: name, field_type, nullable
+    fields JSONB NOT NULL,
+    -- Problems with the mapped fields found by the last check
+    drift JSONB NOT NULL DEFAULT '[]',
+    checked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (sync_pair_id, side)
+);
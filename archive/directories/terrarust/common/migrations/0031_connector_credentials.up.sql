@@ -0,0 +1,24 @@
+-- Connector credentials: secrets that sync pair configs reference by ID
+-- instead of embedding them
+
+CREATE TABLE IF NOT EXISTS connector_credentials (
+    id UUID PRIMARY KEY,
+    county_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    -- Names of the config keys the credential supplies, e.g. connection_string
+    -- or api_key; the values are only in the sealed secret
+    fields JSONB NOT NULL DEFAULT '[]',
+    -- The key/value pairs, sealed with the county's payload key
+    secret JSONB NOT NULL,
+    -- Bumped on every rotation
+    version INTEGER NOT NULL DEFAULT 1,
+    created_by VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    rotated_at TIMESTAMPTZ,
+    last_tested_at TIMESTAMPTZ,
+    -- pass, warn or fail
+    last_test_status VARCHAR(10),
+    UNIQUE (county_id, name)
+);
@@ -0,0 +1,30 @@
+-- Files uploaded for ad-hoc imports: a one-off source for a sync operation
+-- or preview, kept until they expire
+
+CREATE TABLE IF NOT EXISTS import_files (
+    id UUID PRIMARY KEY,
+    county_id VARCHAR(255) NOT NULL,
+    file_name TEXT NOT NULL,
+    -- csv, geojson or shapefile
+    format VARCHAR(20) NOT NULL,
+    -- uploading, validating, ready or failed
+    status VARCHAR(20) NOT NULL DEFAULT 'uploading',
+    -- Content-Length of the upload request, when the client sent one
+    bytes_expected BIGINT,
+    bytes_received BIGINT NOT NULL DEFAULT 0,
+    sha256 VARCHAR(64),
+    -- The file as uploaded, and the file connectors read once it is ready
+    -- (the extracted .shp for a zipped Shapefile)
+    storage_path TEXT NOT NULL,
+    source_path TEXT,
+    record_count BIGINT,
+    fields JSONB,
+    error_message TEXT,
+    uploaded_by VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_import_files_county ON import_files (county_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_import_files_expires ON import_files (expires_at);
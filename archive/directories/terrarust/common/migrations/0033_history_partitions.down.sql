@@ -0,0 +1,22 @@
+-- Back to plain tables; the rows of every partition are kept
+
+ALTER TABLE audit_log RENAME TO audit_log_partitioned;
+CREATE TABLE audit_log (LIKE audit_log_partitioned INCLUDING DEFAULTS INCLUDING CONSTRAINTS);
+INSERT INTO audit_log SELECT * FROM audit_log_partitioned;
+DROP TABLE audit_log_partitioned;
+ALTER TABLE audit_log ADD PRIMARY KEY (id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_event_type ON audit_log(event_type);
+CREATE INDEX IF NOT EXISTS idx_audit_log_resource_type ON audit_log(resource_type);
+CREATE INDEX IF NOT EXISTS idx_audit_log_county_id ON audit_log(county_id);
+
+ALTER TABLE sync_diffs RENAME TO sync_diffs_partitioned;
+CREATE TABLE sync_diffs (LIKE sync_diffs_partitioned INCLUDING DEFAULTS INCLUDING CONSTRAINTS);
+INSERT INTO sync_diffs SELECT * FROM sync_diffs_partitioned;
+DROP TABLE sync_diffs_partitioned;
+ALTER TABLE sync_diffs ADD PRIMARY KEY (id);
+ALTER TABLE sync_diffs ADD FOREIGN KEY (sync_operation_id) REFERENCES sync_operations(id);
+CREATE INDEX IF NOT EXISTS idx_sync_diffs_sync_operation_id ON sync_diffs(sync_operation_id);
+CREATE INDEX IF NOT EXISTS idx_sync_diffs_entity ON sync_diffs(entity_type, entity_id);
+CREATE INDEX IF NOT EXISTS idx_sync_diffs_created_at ON sync_diffs(created_at);
+
+DROP FUNCTION IF EXISTS create_monthly_partition(TEXT, DATE);
@@ -0,0 +1,68 @@
+-- Monthly range partitions for sync_diffs and audit_log, which grow with
+-- every record synced and every change audited. Once a whole month is past
+-- retention, dropping its partition replaces deleting the rows one by one,
+-- and queries bounded by created_at only read the months they cover.
+--
+-- Partitions are named <table>_pYYYYMM and hold one UTC calendar month. The
+-- partition maintenance job keeps the coming months created; the default
+-- partition only catches rows outside every monthly one. Primary keys
+-- include created_at, as PostgreSQL requires of partitioned tables.
+
+CREATE OR REPLACE FUNCTION create_monthly_partition(parent TEXT, for_month DATE) RETURNS TEXT AS $$
+DECLARE
+    first_day DATE := date_trunc('month', for_month)::date;
+    partition_name TEXT := format('%s_p%s', parent, to_char(first_day, 'YYYYMM'));
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE IF NOT EXISTS %I PARTITION OF %I FOR VALUES FROM (%L) TO (%L)',
+        partition_name,
+        parent,
+        first_day::timestamp AT TIME ZONE 'UTC',
+        (first_day + INTERVAL '1 month')::timestamp AT TIME ZONE 'UTC'
+    );
+    RETURN partition_name;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Sync diffs
+ALTER TABLE sync_diffs RENAME TO sync_diffs_unpartitioned;
+CREATE TABLE sync_diffs (LIKE sync_diffs_unpartitioned INCLUDING DEFAULTS INCLUDING CONSTRAINTS)
+    PARTITION BY RANGE (created_at);
+CREATE TABLE sync_diffs_default PARTITION OF sync_diffs DEFAULT;
+
+SELECT create_monthly_partition('sync_diffs', month::date)
+FROM generate_series(
+    date_trunc('month', LEAST(COALESCE((SELECT MIN(created_at) FROM sync_diffs_unpartitioned), NOW()), NOW()) AT TIME ZONE 'UTC'),
+    date_trunc('month', NOW() AT TIME ZONE 'UTC') + INTERVAL '3 months',
+    INTERVAL '1 month'
+) AS month;
+
+INSERT INTO sync_diffs SELECT * FROM sync_diffs_unpartitioned;
+DROP TABLE sync_diffs_unpartitioned;
+
+ALTER TABLE sync_diffs ADD PRIMARY KEY (id, created_at);
+ALTER TABLE sync_diffs ADD FOREIGN KEY (sync_operation_id) REFERENCES sync_operations(id);
+CREATE INDEX IF NOT EXISTS idx_sync_diffs_sync_operation_id ON sync_diffs(sync_operation_id);
+CREATE INDEX IF NOT EXISTS idx_sync_diffs_entity ON sync_diffs(entity_type, entity_id);
+CREATE INDEX IF NOT EXISTS idx_sync_diffs_created_at ON sync_diffs(created_at);
+
+-- Audit log
+ALTER TABLE audit_log RENAME TO audit_log_unpartitioned;
+CREATE TABLE audit_log (LIKE audit_log_unpartitioned INCLUDING DEFAULTS INCLUDING CONSTRAINTS)
+    PARTITION BY RANGE (created_at);
+CREATE TABLE audit_log_default PARTITION OF audit_log DEFAULT;
+
+SELECT create_monthly_partition('audit_log', month::date)
+FROM generate_series(
+    date_trunc('month', LEAST(COALESCE((SELECT MIN(created_at) FROM audit_log_unpartitioned), NOW()), NOW()) AT TIME ZONE 'UTC'),
+    date_trunc('month', NOW() AT TIME ZONE 'UTC') + INTERVAL '3 months',
+    INTERVAL '1 month'
+) AS month;
+
+INSERT INTO audit_log SELECT * FROM audit_log_unpartitioned;
+DROP TABLE audit_log_unpartitioned;
+
+ALTER TABLE audit_log ADD PRIMARY KEY (id, created_at);
+CREATE INDEX IF NOT EXISTS idx_audit_log_event_type ON audit_log(event_type);
+CREATE INDEX IF NOT EXISTS idx_audit_log_resource_type ON audit_log(resource_type);
+CREATE INDEX IF NOT EXISTS idx_audit_log_county_id ON audit_log(county_id);
@@ -0,0 +1,52 @@
+use rand::distributions::{Alphanumeric, DistString};
+use sha2::{Digest, Sha256};
+
+/// Marks a string as a TerraFusion API key, so a leaked key is easy to grep for
+const KEY_PREFIX: &str = "tfk_";
+
+/// Random characters after the prefix; about 190 bits of entropy
+const SECRET_LENGTH: usize = 32;
+
+/// Characters of the key kept in the clear to tell keys apart
+pub const DISPLAY_PREFIX_LENGTH: usize = 12;
+
+/// A newly generated API key. Only the hash is stored; `key` is handed to
+/// the caller once and cannot be recovered later.
+#[derive(Debug, Clone)]
+pub struct GeneratedKey {
+    pub key: String,
+    pub prefix: String,
+    pub hash: String,
+}
+
+/// Generate a new random API key
+pub fn generate() -> GeneratedKey {
+    let key = format!("{}{}", KEY_PREFIX, Alphanumeric.sample_string(&mut rand::thread_rng(), SECRET_LENGTH));
+    GeneratedKey {
+        prefix: key[..DISPLAY_PREFIX_LENGTH].to_string(),
+        hash: hash(&key),
+        key,
+    }
+}
+
+/// Hex-encoded SHA-256 of a key, as stored in `api_keys.key_hash`. Keys
+/// are long and random, so a fast unsalted hash is enough to look them up.
+pub fn hash(key: &str) -> String {
+    Sha256::digest(key.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_key_matches_its_hash_and_prefix() {
+        let generated = generate();
+        assert!(generated.key.starts_with(KEY_PREFIX));
+        assert_eq!(generated.key.len(), KEY_PREFIX.len() + SECRET_LENGTH);
+        assert!(generated.key.starts_with(&generated.prefix));
+        assert_eq!(generated.hash, hash(&generated.key));
+        assert_eq!(generated.hash.len(), 64);
+        assert_ne!(generate().key, generated.key);
+    }
+}
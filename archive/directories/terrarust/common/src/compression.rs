@@ -0,0 +1,180 @@
+//! Which responses get compressed.
+//!
+//! Diff listings and GeoJSON downloads are mostly repeated keys and
+//! coordinates, and shrink several times over with gzip, brotli or zstd;
+//! over county WAN links that is most of the wait. Files that are already
+//! compressed (zipped shapefiles, GeoPackages, gzipped exports) only cost CPU
+//! to compress again, and small bodies are not worth the framing.
+//!
+//! Services wrap actix's `Compress`, which picks the encoding from
+//! `Accept-Encoding`, around `web::CompressionPolicy`, which marks every
+//! response this policy rejects with `Content-Encoding: identity` so
+//! `Compress` leaves it alone.
+
+use crate::errors::{Error, Result};
+
+/// When responses are compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionSettings {
+    pub enabled: bool,
+    /// Bodies of known length below this are sent as they are
+    pub min_bytes: u64,
+}
+
+impl Default for CompressionSettings {
+    fn default() -> Self {
+        Self { enabled: true, min_bytes: 1024 }
+    }
+}
+
+impl CompressionSettings {
+    /// Read `RESPONSE_COMPRESSION` (`true`/`false`) and `COMPRESSION_MIN_BYTES`
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let enabled = match std::env::var("RESPONSE_COMPRESSION") {
+            Ok(value) => value
+                .parse::<bool>()
+                .map_err(|_| Error::Config("Invalid RESPONSE_COMPRESSION value".to_string()))?,
+            Err(_) => defaults.enabled,
+        };
+        let min_bytes = match std::env::var("COMPRESSION_MIN_BYTES") {
+            Ok(value) => value
+                .parse::<u64>()
+                .map_err(|_| Error::Config("Invalid COMPRESSION_MIN_BYTES value".to_string()))?,
+            Err(_) => defaults.min_bytes,
+        };
+        Ok(Self { enabled, min_bytes })
+    }
+
+    /// Whether a response of `content_type` and `length` should be
+    /// compressed. Streamed bodies have no length and are judged by type alone.
+    pub fn should_compress(&self, content_type: Option<&str>, length: Option<u64>) -> bool {
+        self.enabled
+            && content_type.map_or(false, is_compressible)
+            && length.map_or(true, |length| length >= self.min_bytes)
+    }
+}
+
+/// Text formats: JSON and its GeoJSON and JSON-LD flavours, XML (KML
+/// included), CSV, HTML and scripts. Archives, images and binary formats
+/// are not.
+pub fn is_compressible(content_type: &str) -> bool {
+    let media_type = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    media_type.starts_with("text/")
+        || media_type.ends_with("+json")
+        || media_type.ends_with("+xml")
+        || matches!(
+            media_type.as_str(),
+            "application/json" | "application/xml" | "application/javascript" | "application/x-ndjson"
+        )
+}
+
+#[cfg(feature = "actix")]
+pub mod web {
+    use std::future::{ready, Ready};
+    use std::rc::Rc;
+    use std::task::{Context, Poll};
+    use actix_web::{
+        body::{BodySize, MessageBody},
+        dev::{Service, ServiceRequest, ServiceResponse, Transform},
+        http::header::{self, HeaderValue},
+    };
+    use futures::future::LocalBoxFuture;
+
+    use super::CompressionSettings;
+
+    /// Mark responses the settings say not to compress, for an outer
+    /// `actix_web::middleware::Compress`
+    #[derive(Clone)]
+    pub struct CompressionPolicy {
+        settings: CompressionSettings,
+    }
+
+    impl CompressionPolicy {
+        pub fn new(settings: CompressionSettings) -> Self {
+            Self { settings }
+        }
+    }
+
+    impl<S, B> Transform<S, ServiceRequest> for CompressionPolicy
+    where
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+        B: MessageBody + 'static,
+    {
+        type Response = ServiceResponse<B>;
+        type Error = actix_web::Error;
+        type Transform = CompressionPolicyService<S>;
+        type InitError = ();
+        type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+        fn new_transform(&self, service: S) -> Self::Future {
+            ready(Ok(CompressionPolicyService { service: Rc::new(service), settings: self.settings }))
+        }
+    }
+
+    pub struct CompressionPolicyService<S> {
+        service: Rc<S>,
+        settings: CompressionSettings,
+    }
+
+    impl<S, B> Service<ServiceRequest> for CompressionPolicyService<S>
+    where
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+        B: MessageBody + 'static,
+    {
+        type Response = ServiceResponse<B>;
+        type Error = actix_web::Error;
+        type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.service.poll_ready(cx)
+        }
+
+        fn call(&self, req: ServiceRequest) -> Self::Future {
+            let service = self.service.clone();
+            let settings = self.settings;
+
+            Box::pin(async move {
+                let mut res = service.call(req).await?;
+                if res.headers().contains_key(header::CONTENT_ENCODING) {
+                    return Ok(res);
+                }
+
+                let length = match res.response().body().size() {
+                    BodySize::Sized(length) => Some(length),
+                    BodySize::None => Some(0),
+                    BodySize::Stream => None,
+                };
+                let content_type = res.headers().get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok());
+                if !settings.should_compress(content_type, length) {
+                    res.headers_mut().insert(header::CONTENT_ENCODING, HeaderValue::from_static("identity"));
+                }
+                Ok(res)
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_compress() {
+        assert!(is_compressible("application/geo+json"));
+        assert!(is_compressible("application/ld+json; charset=utf-8"));
+        assert!(is_compressible("application/vnd.google-earth.kml+xml"));
+        assert!(is_compressible("text/csv"));
+        assert!(!is_compressible("application/zip"));
+        assert!(!is_compressible("application/gzip"));
+        assert!(!is_compressible("application/geopackage+sqlite3"));
+
+        let settings = CompressionSettings::default();
+        assert!(settings.should_compress(Some("application/json"), Some(4096)));
+        assert!(settings.should_compress(Some("application/geo+json"), None));
+        assert!(!settings.should_compress(Some("application/json"), Some(200)));
+        assert!(!settings.should_compress(Some("application/zip"), None));
+        assert!(!settings.should_compress(None, Some(4096)));
+        assert!(!CompressionSettings { enabled: false, ..settings }.should_compress(Some("text/html"), Some(4096)));
+    }
+}
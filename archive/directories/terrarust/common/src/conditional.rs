@@ -0,0 +1,128 @@
+//! Conditional GET for endpoints dashboards poll.
+//!
+//! Most polls of a list or a status find nothing changed. Responses carry a
+//! strong ETag computed from the body and, where the endpoint knows when its
+//! data last changed, a Last-Modified date. A request whose `If-None-Match`
+//! lists the ETag, or that sends only `If-Modified-Since` and is not older
+//! than the data, gets `304 Not Modified` without a body.
+//!
+//! Lists only get an ETag: a deleted row leaves no timestamp behind, so the
+//! newest remaining `updated_at` cannot tell a client its copy is current.
+//! `Cache-Control: private, no-cache` lets clients keep a copy but makes
+//! them revalidate it on every use, and keeps shared caches out of county data.
+
+use chrono::{DateTime, TimeZone, Utc};
+use sha2::{Digest, Sha256};
+
+pub const CACHE_CONTROL: &str = "private, no-cache";
+
+/// Strong ETag of a response body
+pub fn etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex: String = digest[..16].iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("\"{}\"", hex)
+}
+
+/// HTTP date of a moment, as sent in `Last-Modified`
+pub fn http_date(moment: DateTime<Utc>) -> String {
+    moment.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Whether an `If-None-Match` value lists `etag`. The comparison is weak,
+/// as RFC 9110 asks for this header, so `W/` prefixes are ignored.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    if_none_match.split(',').any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+/// Whether data last changed at `last_modified` is unchanged since an
+/// `If-Modified-Since` date. HTTP dates have whole seconds, so the data's
+/// sub-second part is dropped. An unreadable date counts as changed.
+pub fn unmodified_since(if_modified_since: &str, last_modified: DateTime<Utc>) -> bool {
+    let Ok(since) = DateTime::parse_from_rfc2822(if_modified_since.trim()) else {
+        return false;
+    };
+    let Some(last_modified) = Utc.timestamp_opt(last_modified.timestamp(), 0).single() else {
+        return false;
+    };
+    last_modified <= since
+}
+
+/// Whether the client's copy is current. `If-None-Match` decides when sent;
+/// `If-Modified-Since` is only consulted without it.
+pub fn is_fresh(
+    if_none_match: Option<&str>,
+    if_modified_since: Option<&str>,
+    etag: &str,
+    last_modified: Option<DateTime<Utc>>,
+) -> bool {
+    match (if_none_match, if_modified_since, last_modified) {
+        (Some(if_none_match), _, _) => etag_matches(if_none_match, etag),
+        (None, Some(since), Some(last_modified)) => unmodified_since(since, last_modified),
+        _ => false,
+    }
+}
+
+#[cfg(feature = "actix")]
+pub mod web {
+    use actix_web::{http::header, HttpRequest, HttpResponse};
+    use chrono::{DateTime, Utc};
+    use serde::Serialize;
+
+    use super::{etag, http_date, is_fresh, CACHE_CONTROL};
+    use crate::errors::{Error, Result};
+
+    /// `body` as JSON with validators, or 304 when the request's copy is current
+    pub fn json<T: Serialize>(req: &HttpRequest, body: &T, last_modified: Option<DateTime<Utc>>) -> Result<HttpResponse> {
+        let body = serde_json::to_vec(body).map_err(|e| Error::Serialization(e.to_string()))?;
+        Ok(respond(req, body, "application/json", last_modified))
+    }
+
+    /// `body` with validators, or 304 when the request's copy is current
+    pub fn respond(req: &HttpRequest, body: Vec<u8>, content_type: &str, last_modified: Option<DateTime<Utc>>) -> HttpResponse {
+        let header = |name: header::HeaderName| req.headers().get(name).and_then(|value| value.to_str().ok());
+        let etag = etag(&body);
+        let fresh = is_fresh(header(header::IF_NONE_MATCH), header(header::IF_MODIFIED_SINCE), &etag, last_modified);
+
+        let mut response = if fresh { HttpResponse::NotModified() } else { HttpResponse::Ok() };
+        response
+            .insert_header((header::ETAG, etag))
+            .insert_header((header::CACHE_CONTROL, CACHE_CONTROL));
+        if let Some(last_modified) = last_modified {
+            response.insert_header((header::LAST_MODIFIED, http_date(last_modified)));
+        }
+        if fresh {
+            return response.finish();
+        }
+        response.content_type(content_type).body(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditional_requests() {
+        let tag = etag(br#"{"sync_pairs":[]}"#);
+        assert_eq!(tag.len(), 34);
+        assert_eq!(tag, etag(br#"{"sync_pairs":[]}"#));
+        assert_ne!(tag, etag(br#"{"sync_pairs":[{}]}"#));
+
+        assert!(etag_matches(&format!("\"other\", W/{}", tag), &tag));
+        assert!(etag_matches("*", &tag));
+        assert!(!etag_matches("\"other\"", &tag));
+
+        let changed = Utc.with_ymd_and_hms(2025, 10, 21, 7, 28, 0).unwrap() + chrono::Duration::milliseconds(250);
+        assert_eq!(http_date(changed), "Tue, 21 Oct 2025 07:28:00 GMT");
+        assert!(unmodified_since("Tue, 21 Oct 2025 07:28:00 GMT", changed));
+        assert!(!unmodified_since("Tue, 21 Oct 2025 07:27:59 GMT", changed));
+        assert!(!unmodified_since("yesterday", changed));
+
+        // If-None-Match wins over If-Modified-Since
+        assert!(!is_fresh(Some("\"other\""), Some("Tue, 21 Oct 2025 07:28:00 GMT"), &tag, Some(changed)));
+        assert!(is_fresh(None, Some("Tue, 21 Oct 2025 07:28:00 GMT"), &tag, Some(changed)));
+        assert!(!is_fresh(None, Some("Tue, 21 Oct 2025 07:28:00 GMT"), &tag, None));
+    }
+}
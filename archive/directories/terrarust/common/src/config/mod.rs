@@ -7,6 +7,10 @@ use toml;
 
 use crate::error::{Error, Result};
 
+pub mod runtime;
+
+pub use runtime::{ReloadHandle, RuntimeSettings};
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Config {
     pub database: DatabaseConfig,
@@ -14,6 +18,8 @@ pub struct Config {
     pub logging: LoggingConfig,
     pub telemetry: TelemetryConfig,
     pub security: SecurityConfig,
+    #[serde(default)]
+    pub runtime: RuntimeSettings,
 }
 
 #[derive(Clone, Debug, Deserialize)]
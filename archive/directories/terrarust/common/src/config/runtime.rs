@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+
+use crate::error::{Error, Result};
+
+/// Settings that can be changed while a service is running.
+///
+/// Values are layered: built-in defaults, then the `[runtime]` table of the
+/// TOML config file, then `TERRAFUSION_*` environment variables.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuntimeSettings {
+    pub log_level: String,
+    pub rate_limit_requests_per_second: usize,
+    pub rate_limit_burst_size: usize,
+    pub max_concurrent_syncs: usize,
+    pub max_concurrent_exports: usize,
+    pub county_config_cache_ttl_seconds: u64,
+    /// Default UI language per county, e.g. `benton = "es"`
+    pub county_locales: HashMap<String, String>,
+}
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self {
+            log_level: "info".to_string(),
+            rate_limit_requests_per_second: 10,
+            rate_limit_burst_size: 20,
+            max_concurrent_syncs: 5,
+            max_concurrent_exports: 5,
+            county_config_cache_ttl_seconds: 300,
+            county_locales: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct RuntimeFile {
+    #[serde(default)]
+    runtime: Option<RuntimeSettings>,
+}
+
+impl RuntimeSettings {
+    /// Load settings from the given file (if any) and apply environment overrides
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let mut settings = match path {
+            Some(path) if path.exists() => Self::from_file(path)?,
+            _ => Self::default(),
+        };
+
+        settings.apply_env();
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| Error::ConfigError(format!("Failed to read config file: {}", e)))?;
+
+        let file: RuntimeFile = toml::from_str(&content)
+            .map_err(|e| Error::ConfigError(format!("Failed to parse config file: {}", e)))?;
+
+        Ok(file.runtime.unwrap_or_default())
+    }
+
+    fn apply_env(&mut self) {
+        if let Ok(level) = env::var("TERRAFUSION_LOG_LEVEL") {
+            self.log_level = level;
+        }
+        override_from_env("TERRAFUSION_RATE_LIMIT_RPS", &mut self.rate_limit_requests_per_second);
+        override_from_env("TERRAFUSION_RATE_LIMIT_BURST", &mut self.rate_limit_burst_size);
+        override_from_env("MAX_CONCURRENT_SYNCS", &mut self.max_concurrent_syncs);
+        override_from_env("MAX_CONCURRENT_EXPORTS", &mut self.max_concurrent_exports);
+        override_from_env("COUNTY_CONFIG_CACHE_TTL_SECONDS", &mut self.county_config_cache_ttl_seconds);
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.log_filter().is_none() {
+            return Err(Error::ConfigError(format!("Invalid log level: {}", self.log_level)));
+        }
+        if self.max_concurrent_syncs == 0 || self.max_concurrent_exports == 0 {
+            return Err(Error::ConfigError("Concurrency caps must be greater than zero".to_string()));
+        }
+        if self.rate_limit_requests_per_second == 0 || self.rate_limit_burst_size == 0 {
+            return Err(Error::ConfigError("Rate limits must be greater than zero".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Parsed log level filter
+    pub fn log_filter(&self) -> Option<log::LevelFilter> {
+        self.log_level.parse().ok()
+    }
+
+    /// County configuration cache TTL as a Duration
+    pub fn county_config_cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.county_config_cache_ttl_seconds)
+    }
+}
+
+fn override_from_env<T: std::str::FromStr>(key: &str, target: &mut T) {
+    if let Ok(value) = env::var(key) {
+        match value.parse() {
+            Ok(parsed) => *target = parsed,
+            Err(_) => log::warn!("Ignoring invalid value for {}: {}", key, value),
+        }
+    }
+}
+
+/// Shared handle to the current runtime settings.
+///
+/// Cloning is cheap; every clone observes the same reloads.
+#[derive(Clone)]
+pub struct ReloadHandle {
+    path: Option<PathBuf>,
+    sender: Arc<watch::Sender<Arc<RuntimeSettings>>>,
+}
+
+impl ReloadHandle {
+    /// Load the initial settings and create a handle
+    pub fn new(path: Option<PathBuf>) -> Result<Self> {
+        let settings = RuntimeSettings::load(path.as_deref())?;
+        apply_log_level(&settings);
+        crate::utils::county_config::set_cache_ttl(settings.county_config_cache_ttl());
+
+        let (sender, _) = watch::channel(Arc::new(settings));
+
+        Ok(Self {
+            path,
+            sender: Arc::new(sender),
+        })
+    }
+
+    /// Create a handle from the TERRAFUSION_CONFIG path
+    pub fn from_env() -> Result<Self> {
+        let path = env::var("TERRAFUSION_CONFIG")
+            .unwrap_or_else(|_| "config/default.toml".to_string());
+
+        Self::new(Some(PathBuf::from(path)))
+    }
+
+    /// Current settings snapshot
+    pub fn current(&self) -> Arc<RuntimeSettings> {
+        self.sender.borrow().clone()
+    }
+
+    /// Subscribe to settings changes
+    pub fn subscribe(&self) -> watch::Receiver<Arc<RuntimeSettings>> {
+        self.sender.subscribe()
+    }
+
+    /// Re-read the config file and environment, publishing any changes.
+    ///
+    /// Returns `true` if the settings changed. Invalid files leave the
+    /// current settings in place.
+    pub fn reload(&self) -> Result<bool> {
+        let settings = RuntimeSettings::load(self.path.as_deref())?;
+
+        if *self.current() == settings {
+            return Ok(false);
+        }
+
+        log::info!("Runtime settings reloaded: {:?}", settings);
+        apply_log_level(&settings);
+        crate::utils::county_config::set_cache_ttl(settings.county_config_cache_ttl());
+        self.sender.send_replace(Arc::new(settings));
+
+        Ok(true)
+    }
+
+    /// Poll the config file for modifications and reload when it changes
+    pub fn spawn_watcher(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let handle = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut last_modified = handle.modified_time();
+
+            loop {
+                ticker.tick().await;
+
+                let modified = handle.modified_time();
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                if let Err(e) = handle.reload() {
+                    log::error!("Failed to reload runtime settings: {}", e);
+                }
+            }
+        })
+    }
+
+    fn modified_time(&self) -> Option<SystemTime> {
+        self.path
+            .as_ref()
+            .and_then(|path| fs::metadata(path).ok())
+            .and_then(|meta| meta.modified().ok())
+    }
+}
+
+fn apply_log_level(settings: &RuntimeSettings) {
+    if let Some(filter) = settings.log_filter() {
+        log::set_max_level(filter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_file_overrides_defaults() {
+        let file: RuntimeFile = toml::from_str(
+            "[runtime]\nlog_level = \"debug\"\nmax_concurrent_syncs = 2\n",
+        )
+        .unwrap();
+        let settings = file.runtime.unwrap();
+
+        assert_eq!(settings.log_level, "debug");
+        assert_eq!(settings.max_concurrent_syncs, 2);
+        assert_eq!(settings.rate_limit_burst_size, 20);
+    }
+
+    #[test]
+    fn test_invalid_log_level_rejected() {
+        let settings = RuntimeSettings {
+            log_level: "loud".to_string(),
+            ..Default::default()
+        };
+
+        assert!(settings.validate().is_err());
+    }
+}
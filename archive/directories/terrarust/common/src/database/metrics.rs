@@ -0,0 +1,53 @@
+use lazy_static::lazy_static;
+use prometheus::{register_histogram_vec, register_int_gauge_vec, HistogramVec, IntGaugeVec};
+use std::time::Duration;
+
+use super::DbPool;
+
+lazy_static! {
+    /// Connections currently checked out, by pool role
+    pub static ref DB_POOL_CONNECTIONS_IN_USE: IntGaugeVec = register_int_gauge_vec!(
+        "db_pool_connections_in_use",
+        "Database connections currently checked out of the pool",
+        &["role"]
+    ).expect("Failed to register db_pool_connections_in_use");
+
+    /// Idle connections, by pool role
+    pub static ref DB_POOL_CONNECTIONS_IDLE: IntGaugeVec = register_int_gauge_vec!(
+        "db_pool_connections_idle",
+        "Idle database connections held by the pool",
+        &["role"]
+    ).expect("Failed to register db_pool_connections_idle");
+
+    /// Configured pool capacity, by pool role
+    pub static ref DB_POOL_CONNECTIONS_MAX: IntGaugeVec = register_int_gauge_vec!(
+        "db_pool_connections_max",
+        "Maximum database connections allowed in the pool",
+        &["role"]
+    ).expect("Failed to register db_pool_connections_max");
+
+    /// Time spent waiting to acquire a connection, by pool role
+    pub static ref DB_POOL_ACQUIRE_WAIT_SECONDS: HistogramVec = register_histogram_vec!(
+        "db_pool_acquire_wait_seconds",
+        "Time spent waiting for a database connection",
+        &["role"],
+        vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    ).expect("Failed to register db_pool_acquire_wait_seconds");
+}
+
+/// Publish the current size of a pool
+pub fn record_pool_state(role: &str, pool: &DbPool, max_connections: u32) {
+    let size = pool.size() as i64;
+    let idle = pool.num_idle() as i64;
+
+    DB_POOL_CONNECTIONS_IN_USE.with_label_values(&[role]).set(size - idle);
+    DB_POOL_CONNECTIONS_IDLE.with_label_values(&[role]).set(idle);
+    DB_POOL_CONNECTIONS_MAX.with_label_values(&[role]).set(max_connections as i64);
+}
+
+/// Record how long an acquire took
+pub fn record_acquire_wait(role: &str, waited: Duration) {
+    DB_POOL_ACQUIRE_WAIT_SECONDS
+        .with_label_values(&[role])
+        .observe(waited.as_secs_f64());
+}
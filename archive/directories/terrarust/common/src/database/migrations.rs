@@ -5,6 +5,232 @@ use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use std::time::Duration;
 use log::{info, warn, error};
+use sha2::{Digest, Sha256};
+use sqlx::Executor;
+
+/// A SQL migration compiled into the binary
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedMigration {
+    pub version: &'static str,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+impl EmbeddedMigration {
+    /// SHA-256 checksum of the up script
+    pub fn checksum(&self) -> String {
+        checksum(self.up)
+    }
+}
+
+/// Platform schema migrations, applied in version order
+pub const EMBEDDED_MIGRATIONS: &[EmbeddedMigration] = &[
+    EmbeddedMigration {
+        version: "0001",
+        name: "initial_schema",
+        up: include_str!("../../migrations/0001_initial_schema.up.sql"),
+        down: include_str!("../../migrations/0001_initial_schema.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0002",
+        name: "sync_records",
+        up: include_str!("../../migrations/0002_sync_records.up.sql"),
+        down: include_str!("../../migrations/0002_sync_records.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0003",
+        name: "gis_export_jobs",
+        up: include_str!("../../migrations/0003_gis_export_jobs.up.sql"),
+        down: include_str!("../../migrations/0003_gis_export_jobs.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0004",
+        name: "operation_narrative",
+        up: include_str!("../../migrations/0004_operation_narrative.up.sql"),
+        down: include_str!("../../migrations/0004_operation_narrative.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0005",
+        name: "entity_embeddings",
+        up: include_str!("../../migrations/0005_entity_embeddings.up.sql"),
+        down: include_str!("../../migrations/0005_entity_embeddings.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0006",
+        name: "export_templates",
+        up: include_str!("../../migrations/0006_export_templates.up.sql"),
+        down: include_str!("../../migrations/0006_export_templates.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0007",
+        name: "sync_pair_field_mappings",
+        up: include_str!("../../migrations/0007_sync_pair_field_mappings.up.sql"),
+        down: include_str!("../../migrations/0007_sync_pair_field_mappings.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0008",
+        name: "maintenance_mode",
+        up: include_str!("../../migrations/0008_maintenance_mode.up.sql"),
+        down: include_str!("../../migrations/0008_maintenance_mode.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0009",
+        name: "feature_flags",
+        up: include_str!("../../migrations/0009_feature_flags.up.sql"),
+        down: include_str!("../../migrations/0009_feature_flags.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0010",
+        name: "export_checksums",
+        up: include_str!("../../migrations/0010_export_checksums.up.sql"),
+        down: include_str!("../../migrations/0010_export_checksums.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0011",
+        name: "idempotency_keys",
+        up: include_str!("../../migrations/0011_idempotency_keys.up.sql"),
+        down: include_str!("../../migrations/0011_idempotency_keys.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0012",
+        name: "data_retention",
+        up: include_str!("../../migrations/0012_data_retention.up.sql"),
+        down: include_str!("../../migrations/0012_data_retention.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0013",
+        name: "search_indexes",
+        up: include_str!("../../migrations/0013_search_indexes.up.sql"),
+        down: include_str!("../../migrations/0013_search_indexes.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0014",
+        name: "published_exports",
+        up: include_str!("../../migrations/0014_published_exports.up.sql"),
+        down: include_str!("../../migrations/0014_published_exports.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0015",
+        name: "user_sessions",
+        up: include_str!("../../migrations/0015_user_sessions.up.sql"),
+        down: include_str!("../../migrations/0015_user_sessions.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0016",
+        name: "job_queue",
+        up: include_str!("../../migrations/0016_job_queue.up.sql"),
+        down: include_str!("../../migrations/0016_job_queue.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0017",
+        name: "sync_workers",
+        up: include_str!("../../migrations/0017_sync_workers.up.sql"),
+        down: include_str!("../../migrations/0017_sync_workers.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0018",
+        name: "payload_encryption",
+        up: include_str!("../../migrations/0018_payload_encryption.up.sql"),
+        down: include_str!("../../migrations/0018_payload_encryption.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0019",
+        name: "export_publications",
+        up: include_str!("../../migrations/0019_export_publications.up.sql"),
+        down: include_str!("../../migrations/0019_export_publications.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0020",
+        name: "export_deliveries",
+        up: include_str!("../../migrations/0020_export_deliveries.up.sql"),
+        down: include_str!("../../migrations/0020_export_deliveries.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0021",
+        name: "export_approval",
+        up: include_str!("../../migrations/0021_export_approval.up.sql"),
+        down: include_str!("../../migrations/0021_export_approval.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0022",
+        name: "usage_accounting",
+        up: include_str!("../../migrations/0022_usage_accounting.up.sql"),
+        down: include_str!("../../migrations/0022_usage_accounting.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0023",
+        name: "user_preferences",
+        up: include_str!("../../migrations/0023_user_preferences.up.sql"),
+        down: include_str!("../../migrations/0023_user_preferences.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0024",
+        name: "county_onboarding",
+        up: include_str!("../../migrations/0024_county_onboarding.up.sql"),
+        down: include_str!("../../migrations/0024_county_onboarding.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0025",
+        name: "county_geodata",
+        up: include_str!("../../migrations/0025_county_geodata.up.sql"),
+        down: include_str!("../../migrations/0025_county_geodata.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0026",
+        name: "export_result_cache",
+        up: include_str!("../../migrations/0026_export_result_cache.up.sql"),
+        down: include_str!("../../migrations/0026_export_result_cache.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0027",
+        name: "dataset_versions",
+        up: include_str!("../../migrations/0027_dataset_versions.up.sql"),
+        down: include_str!("../../migrations/0027_dataset_versions.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0028",
+        name: "sync_pair_hooks",
+        up: include_str!("../../migrations/0028_sync_pair_hooks.up.sql"),
+        down: include_str!("../../migrations/0028_sync_pair_hooks.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0029",
+        name: "pipelines",
+        up: include_str!("../../migrations/0029_pipelines.up.sql"),
+        down: include_str!("../../migrations/0029_pipelines.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0030",
+        name: "schema_drift",
+        up: include_str!("../../migrations/0030_schema_drift.up.sql"),
+        down: include_str!("../../migrations/0030_schema_drift.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0031",
+        name: "connector_credentials",
+        up: include_str!("../../migrations/0031_connector_credentials.up.sql"),
+        down: include_str!("../../migrations/0031_connector_credentials.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0032",
+        name: "import_files",
+        up: include_str!("../../migrations/0032_import_files.up.sql"),
+        down: include_str!("../../migrations/0032_import_files.down.sql"),
+    },
+    EmbeddedMigration {
+        version: "0033",
+        name: "history_partitions",
+        up: include_str!("../../migrations/0033_history_partitions.up.sql"),
+        down: include_str!("../../migrations/0033_history_partitions.down.sql"),
+    },
+];
+
+/// Hex-encoded SHA-256 of a migration script
+pub fn checksum(sql: &str) -> String {
+    let digest = Sha256::digest(sql.as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
 
 /// Migration status
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -35,6 +261,16 @@ pub struct Migration {
     pub applied_at: Option<DateTime<Utc>>,
     pub duration_ms: Option<i64>,
     pub error: Option<String>,
+    pub checksum: Option<String>,
+}
+
+/// A completed migration whose script no longer matches what was applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecksumMismatch {
+    pub version: String,
+    pub name: String,
+    pub applied_checksum: String,
+    pub embedded_checksum: String,
 }
 
 /// Migration handler
@@ -42,6 +278,7 @@ pub struct Migration {
 pub struct Migrator {
     pool: PgPool,
     migrations: HashMap<String, Box<dyn MigrationFn>>,
+    checksums: HashMap<String, String>,
 }
 
 /// Migration function trait
@@ -56,9 +293,52 @@ impl Migrator {
         Self {
             pool,
             migrations: HashMap::new(),
+            checksums: HashMap::new(),
+        }
+    }
+    
+    /// Create a migrator with all embedded platform migrations registered
+    pub fn with_embedded_migrations(pool: PgPool) -> Self {
+        let mut migrator = Self::new(pool);
+        migrator.register_embedded_migrations();
+        migrator
+    }
+    
+    /// Register every migration in `EMBEDDED_MIGRATIONS`
+    pub fn register_embedded_migrations(&mut self) {
+        for migration in EMBEDDED_MIGRATIONS {
+            self.register_sql_migration(migration);
         }
     }
     
+    /// Register a plain SQL migration, recording its checksum
+    pub fn register_sql_migration(&mut self, migration: &EmbeddedMigration) {
+        let up_sql = migration.up;
+        let down_sql = migration.down;
+        
+        self.register_migration(
+            migration.version,
+            migration.name,
+            move |tx| Box::pin(async move {
+                tx.execute(up_sql)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| Error::Database(DatabaseError::Migration(e.to_string())))
+            }),
+            move |tx| Box::pin(async move {
+                tx.execute(down_sql)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| Error::Database(DatabaseError::Migration(e.to_string())))
+            }),
+        );
+        
+        self.checksums.insert(
+            format!("{}_{}", migration.version, migration.name),
+            migration.checksum(),
+        );
+    }
+    
     /// Register a migration
     pub fn register_migration(
         &mut self,
@@ -114,6 +394,12 @@ impl Migrator {
         .await
         .map_err(|e| Error::Database(DatabaseError::Migration(format!("Failed to create migrations table: {}", e))))?;
         
+        // Older installations created the table without checksums
+        sqlx::query("ALTER TABLE migrations ADD COLUMN IF NOT EXISTS checksum VARCHAR(64)")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::Database(DatabaseError::Migration(format!("Failed to add checksum column: {}", e))))?;
+        
         Ok(())
     }
     
@@ -129,7 +415,8 @@ impl Migrator {
                 status, 
                 applied_at, 
                 duration_ms, 
-                error
+                error,
+                checksum
             FROM migrations
             ORDER BY version ASC, name ASC
             "#,
@@ -154,6 +441,7 @@ impl Migrator {
                 applied_at: m.applied_at,
                 duration_ms: m.duration_ms,
                 error: m.error,
+                checksum: m.checksum,
             });
         }
         
@@ -172,6 +460,7 @@ impl Migrator {
                         applied_at: None,
                         duration_ms: None,
                         error: None,
+                        checksum: self.checksums.get(key).cloned(),
                     });
                 }
             }
@@ -194,6 +483,15 @@ impl Migrator {
         // Initialize migrations table
         self.init().await?;
         
+        // Refuse to continue if an applied migration was edited after the fact
+        let mismatches = self.verify_checksums().await?;
+        if let Some(mismatch) = mismatches.first() {
+            return Err(Error::Database(DatabaseError::Migration(format!(
+                "Checksum mismatch for migration {}_{}: applied {}, embedded {}",
+                mismatch.version, mismatch.name, mismatch.applied_checksum, mismatch.embedded_checksum
+            ))));
+        }
+        
         // Get all migrations
         let migrations = self.get_migrations().await?;
         
@@ -259,6 +557,7 @@ impl Migrator {
                             applied_at: Some(Utc::now()),
                             duration_ms: Some(duration_ms),
                             error: None,
+                            checksum: self.checksums.get(&key).cloned(),
                         });
                     }
                     Err(e) => {
@@ -281,6 +580,7 @@ impl Migrator {
                             applied_at: Some(Utc::now()),
                             duration_ms: Some(duration_ms),
                             error: Some(error_msg),
+                            checksum: self.checksums.get(&key).cloned(),
                         });
                         
                         return Err(Error::Database(DatabaseError::Migration(format!(
@@ -297,6 +597,69 @@ impl Migrator {
         Ok(results)
     }
     
+    /// Compare checksums of completed migrations against the registered scripts
+    pub async fn verify_checksums(&self) -> Result<Vec<ChecksumMismatch>> {
+        let migrations = self.get_migrations().await?;
+        let mut mismatches = Vec::new();
+        
+        for migration in migrations.iter().filter(|m| m.status == MigrationStatus::Completed) {
+            let key = format!("{}_{}", migration.version, migration.name);
+            
+            if let (Some(applied), Some(embedded)) = (&migration.checksum, self.checksums.get(&key)) {
+                if applied != embedded {
+                    warn!("Checksum mismatch for migration {}", key);
+                    mismatches.push(ChecksumMismatch {
+                        version: migration.version.clone(),
+                        name: migration.name.clone(),
+                        applied_checksum: applied.clone(),
+                        embedded_checksum: embedded.clone(),
+                    });
+                }
+            }
+        }
+        
+        Ok(mismatches)
+    }
+    
+    /// Revert the most recently applied migration
+    pub async fn rollback_last(&self) -> Result<Option<Migration>> {
+        self.init().await?;
+        
+        let last = self.get_migrations()
+            .await?
+            .into_iter()
+            .filter(|m| m.status == MigrationStatus::Completed)
+            .last();
+        
+        let migration = match last {
+            Some(migration) => migration,
+            None => {
+                info!("No applied migrations to roll back");
+                return Ok(None);
+            }
+        };
+        
+        let key = format!("{}_{}", migration.version, migration.name);
+        let migration_fn = self.migrations.get(&key).ok_or_else(|| {
+            Error::Database(DatabaseError::Migration(format!("No migration function found for {}", key)))
+        })?;
+        
+        info!("Rolling back migration {}", key);
+        self.run_migration(&migration.version, &migration.name, |tx| migration_fn.down(tx)).await?;
+        
+        sqlx::query("DELETE FROM migrations WHERE version = $1 AND name = $2")
+            .bind(&migration.version)
+            .bind(&migration.name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::Database(DatabaseError::Migration(format!("Failed to remove migration record: {}", e))))?;
+        
+        Ok(Some(Migration {
+            status: MigrationStatus::Pending,
+            ..migration
+        }))
+    }
+    
     /// Run a specific migration in a transaction
     async fn run_migration<F, Fut>(&self, version: &str, name: &str, f: F) -> Result<()>
     where
@@ -333,16 +696,18 @@ impl Migrator {
     ) -> Result<()> {
         let status_str = status.to_string();
         let now = Utc::now();
+        let checksum = self.checksums.get(&format!("{}_{}", version, name));
         
         sqlx::query(
             r#"
-            INSERT INTO migrations (version, name, status, applied_at, duration_ms, error)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO migrations (version, name, status, applied_at, duration_ms, error, checksum)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             ON CONFLICT (version, name) DO UPDATE SET
                 status = $3,
                 applied_at = $4,
                 duration_ms = $5,
-                error = $6
+                error = $6,
+                checksum = $7
             "#,
         )
         .bind(version)
@@ -351,6 +716,7 @@ impl Migrator {
         .bind(now)
         .bind(duration_ms)
         .bind(error)
+        .bind(checksum)
         .execute(&self.pool)
         .await
         .map_err(|e| Error::Database(DatabaseError::Migration(format!("Failed to update migration status: {}", e))))?;
@@ -368,4 +734,26 @@ struct DbMigration {
     applied_at: Option<DateTime<Utc>>,
     duration_ms: Option<i64>,
     error: Option<String>,
-}
\ No newline at end of file
+    checksum: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_embedded_migrations_are_ordered() {
+        let versions: Vec<&str> = EMBEDDED_MIGRATIONS.iter().map(|m| m.version).collect();
+        let mut sorted = versions.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(versions, sorted);
+    }
+    
+    #[test]
+    fn test_checksum_is_stable() {
+        assert_eq!(checksum("SELECT 1;"), checksum("SELECT 1;"));
+        assert_ne!(checksum("SELECT 1;"), checksum("SELECT 2;"));
+        assert_eq!(checksum("").len(), 64);
+    }
+}
@@ -0,0 +1,164 @@
+//! Monthly partitions of the history tables.
+//!
+//! `sync_diffs` and `audit_log` are range-partitioned on `created_at`, one
+//! partition per UTC month named `<table>_pYYYYMM` (migration 0033). Months
+//! have to exist before rows arrive for them, or the rows land in the
+//! table's default partition, so `ensure_partitions` creates the current
+//! month and a few ahead. Once a month is entirely past retention,
+//! `drop_partitions_before` drops it in one statement instead of deleting
+//! its rows.
+
+use chrono::{DateTime, Months, NaiveDate, TimeZone, Utc};
+use sqlx::PgPool;
+
+use crate::errors::{Error, Result};
+use crate::usage::month_start;
+
+/// Tables partitioned by month
+pub const PARTITIONED_TABLES: &[&str] = &["sync_diffs", "audit_log"];
+
+/// How far ahead partitions are created and how long audit history is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartitionSettings {
+    /// Months created past the current one
+    pub months_ahead: u32,
+    /// Audit log months older than this are dropped; kept forever when unset
+    pub audit_log_retention_days: Option<u32>,
+}
+
+impl Default for PartitionSettings {
+    fn default() -> Self {
+        Self { months_ahead: 3, audit_log_retention_days: None }
+    }
+}
+
+impl PartitionSettings {
+    /// Read `PARTITION_MONTHS_AHEAD` and `AUDIT_LOG_RETENTION_DAYS`
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let months_ahead = match std::env::var("PARTITION_MONTHS_AHEAD") {
+            Ok(value) => value
+                .parse::<u32>()
+                .ok()
+                .filter(|months| *months > 0)
+                .ok_or_else(|| Error::Config("Invalid PARTITION_MONTHS_AHEAD value".to_string()))?,
+            Err(_) => defaults.months_ahead,
+        };
+        let audit_log_retention_days = match std::env::var("AUDIT_LOG_RETENTION_DAYS") {
+            Ok(value) if value.trim().is_empty() => None,
+            Ok(value) => Some(
+                value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|days| *days > 0)
+                    .ok_or_else(|| Error::Config("Invalid AUDIT_LOG_RETENTION_DAYS value".to_string()))?,
+            ),
+            Err(_) => defaults.audit_log_retention_days,
+        };
+        Ok(Self { months_ahead, audit_log_retention_days })
+    }
+}
+
+/// Name of `table`'s partition for the month starting `month`
+pub fn partition_name(table: &str, month: NaiveDate) -> String {
+    format!("{}_p{}", table, month.format("%Y%m"))
+}
+
+/// The month a partition of `table` holds, or `None` for anything not named
+/// like a monthly partition (the default partition included)
+pub fn partition_month(table: &str, partition: &str) -> Option<NaiveDate> {
+    let suffix = partition.strip_prefix(table)?.strip_prefix("_p")?;
+    if suffix.len() != 6 || !suffix.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    NaiveDate::parse_from_str(&format!("{}01", suffix), "%Y%m%d").ok()
+}
+
+/// Start of the month after `month`; every row of that month's partition
+/// is older than this
+pub fn month_end(month: NaiveDate) -> DateTime<Utc> {
+    let next = month + Months::new(1);
+    Utc.from_utc_datetime(&next.and_hms_opt(0, 0, 0).expect("midnight is a valid time"))
+}
+
+/// Whether every row a partition can hold was created before `cutoff`
+pub fn expired(table: &str, partition: &str, cutoff: DateTime<Utc>) -> bool {
+    partition_month(table, partition).map_or(false, |month| month_end(month) <= cutoff)
+}
+
+/// Create `table`'s partitions for the month of `now` and `months_ahead`
+/// after it, where missing. Returns the partition names.
+pub async fn ensure_partitions(pool: &PgPool, table: &str, now: DateTime<Utc>, months_ahead: u32) -> Result<Vec<String>> {
+    let first = month_start(now);
+    let mut partitions = Vec::new();
+    for offset in 0..=months_ahead {
+        let month = first + Months::new(offset);
+        let partition = sqlx::query_scalar::<_, String>("SELECT create_monthly_partition($1, $2)")
+            .bind(table)
+            .bind(month)
+            .fetch_one(pool)
+            .await?;
+        partitions.push(partition);
+    }
+    Ok(partitions)
+}
+
+/// Partitions currently attached to `table`, default partition included
+pub async fn list_partitions(pool: &PgPool, table: &str) -> Result<Vec<String>> {
+    let partitions = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT c.relname::text FROM pg_inherits i
+        JOIN pg_class c ON c.oid = i.inhrelid
+        WHERE i.inhparent = $1::regclass
+        ORDER BY c.relname
+        "#,
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+    Ok(partitions)
+}
+
+/// Drop `table`'s monthly partitions whose rows were all created before
+/// `cutoff`. Returns the dropped partitions with the rows each held.
+pub async fn drop_partitions_before(pool: &PgPool, table: &str, cutoff: DateTime<Utc>) -> Result<Vec<(String, i64)>> {
+    let mut dropped = Vec::new();
+    for partition in list_partitions(pool, table).await? {
+        if !expired(table, &partition, cutoff) {
+            continue;
+        }
+        // Names come from pg_class and matched the partition pattern, so
+        // quoting is all they need
+        let rows = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM \"{}\"", partition))
+            .fetch_one(pool)
+            .await?;
+        sqlx::query(&format!("DROP TABLE IF EXISTS \"{}\"", partition))
+            .execute(pool)
+            .await?;
+        dropped.push((partition, rows));
+    }
+    Ok(dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition_months() {
+        let march = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        assert_eq!(partition_name("sync_diffs", march), "sync_diffs_p202503");
+        assert_eq!(partition_month("sync_diffs", "sync_diffs_p202503"), Some(march));
+        assert_eq!(partition_month("sync_diffs", "sync_diffs_default"), None);
+        assert_eq!(partition_month("sync_diffs", "audit_log_p202503"), None);
+        assert_eq!(partition_month("sync_diffs", "sync_diffs_p202513"), None);
+
+        let december = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+        assert_eq!(month_end(december), Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+
+        let cutoff = Utc.with_ymd_and_hms(2025, 4, 1, 0, 0, 0).unwrap();
+        assert!(expired("sync_diffs", "sync_diffs_p202503", cutoff));
+        assert!(!expired("sync_diffs", "sync_diffs_p202504", cutoff));
+        assert!(!expired("sync_diffs", "sync_diffs_default", cutoff));
+    }
+}
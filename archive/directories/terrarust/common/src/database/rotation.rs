@@ -0,0 +1,210 @@
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use sqlx::pool::PoolConnection;
+use sqlx::Postgres;
+use tokio::sync::Mutex;
+
+use crate::errors::{Error, Result};
+use crate::secrets::{self, SecretsManager};
+use super::{create_pool, metrics, DbConfig, DbPool, QueryIntent};
+
+/// Connection pools whose credentials come from a secrets provider.
+///
+/// Holds the primary (read-write) pool and an optional read-replica pool.
+/// When a database secret changes a new pool is built and swapped in; the
+/// old pool is closed once its checked-out connections are returned.
+#[derive(Clone)]
+pub struct RotatingPool {
+    writer: Arc<RwLock<DbPool>>,
+    reader: Arc<RwLock<Option<DbPool>>>,
+    secrets: SecretsManager,
+    config: DbConfig,
+    active_urls: Arc<Mutex<ActiveUrls>>,
+}
+
+struct ActiveUrls {
+    writer: String,
+    reader: Option<String>,
+}
+
+impl RotatingPool {
+    /// Resolve DATABASE_URL (and DATABASE_READ_URL, if set) and connect
+    pub async fn connect(secrets: SecretsManager, config: DbConfig) -> Result<Self> {
+        let writer_url = secrets.get_value(secrets::DATABASE_URL).await?;
+        let writer = create_pool(&DbConfig { url: writer_url.clone(), ..config.clone() }).await?;
+
+        let reader_url = read_replica_url(&secrets).await;
+        let reader = match &reader_url {
+            Some(url) => Some(create_pool(&config.for_reader(url)).await?),
+            None => None,
+        };
+
+        if reader.is_some() {
+            log::info!("Read replica pool enabled");
+        }
+
+        Ok(Self {
+            writer: Arc::new(RwLock::new(writer)),
+            reader: Arc::new(RwLock::new(reader)),
+            secrets,
+            config,
+            active_urls: Arc::new(Mutex::new(ActiveUrls { writer: writer_url, reader: reader_url })),
+        })
+    }
+
+    /// Primary (read-write) pool; cheap to clone, fetch it per unit of work
+    pub fn pool(&self) -> DbPool {
+        self.writer.read().expect("database pool lock poisoned").clone()
+    }
+
+    /// Read-replica pool, falling back to the primary when no replica is configured
+    pub fn read_pool(&self) -> DbPool {
+        let reader = self.reader.read().expect("database pool lock poisoned");
+        match reader.as_ref() {
+            Some(pool) => pool.clone(),
+            None => self.pool(),
+        }
+    }
+
+    /// Pool for the given intent
+    pub fn route(&self, intent: QueryIntent) -> DbPool {
+        match intent {
+            QueryIntent::Read => self.read_pool(),
+            QueryIntent::Write => self.pool(),
+        }
+    }
+
+    /// Pool for a SQL statement, sending plain SELECTs to the replica
+    pub fn for_sql(&self, sql: &str) -> DbPool {
+        self.route(QueryIntent::from_sql(sql))
+    }
+
+    /// Acquire a connection, recording the wait time
+    pub async fn acquire(&self, intent: QueryIntent) -> Result<PoolConnection<Postgres>> {
+        let pool = self.route(intent);
+        let started = Instant::now();
+        let connection = pool.acquire().await.map_err(Error::Sqlx);
+        metrics::record_acquire_wait(intent.role(), started.elapsed());
+        connection
+    }
+
+    /// Re-read the database secrets and swap pools if they changed
+    pub async fn rotate(&self) -> Result<bool> {
+        let mut active_urls = self.active_urls.lock().await;
+        let mut rotated = false;
+
+        let secret = self.secrets.refresh(secrets::DATABASE_URL).await?;
+        if secret.value != active_urls.writer {
+            let new_pool = create_pool(&DbConfig { url: secret.value.clone(), ..self.config.clone() }).await?;
+            let old_pool = {
+                let mut writer = self.writer.write().expect("database pool lock poisoned");
+                std::mem::replace(&mut *writer, new_pool)
+            };
+            active_urls.writer = secret.value;
+            close_in_background(old_pool);
+
+            log::info!("Database credentials rotated (version {:?})", secret.version);
+            rotated = true;
+        }
+
+        if active_urls.reader.is_some() {
+            let reader_url = self.secrets.refresh(secrets::DATABASE_READ_URL).await?.value;
+            if Some(&reader_url) != active_urls.reader.as_ref() {
+                let new_pool = create_pool(&self.config.for_reader(&reader_url)).await?;
+                let old_pool = {
+                    let mut reader = self.reader.write().expect("database pool lock poisoned");
+                    reader.replace(new_pool)
+                };
+                active_urls.reader = Some(reader_url);
+                if let Some(old_pool) = old_pool {
+                    close_in_background(old_pool);
+                }
+
+                log::info!("Read replica credentials rotated");
+                rotated = true;
+            }
+        }
+
+        Ok(rotated)
+    }
+
+    /// Rotate immediately if an error indicates the credentials were revoked
+    pub async fn handle_error(&self, error: &sqlx::Error) {
+        if is_auth_error(error) {
+            log::warn!("Database authentication failed, refreshing credentials");
+            if let Err(e) = self.rotate().await {
+                log::error!("Failed to rotate database credentials: {}", e);
+            }
+        }
+    }
+
+    /// Periodically check the secrets provider for new credentials
+    pub fn spawn_rotation(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let pool = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+                if let Err(e) = pool.rotate().await {
+                    log::error!("Failed to check database credentials: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Periodically publish pool usage gauges
+    pub fn spawn_metrics(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let pool = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+                pool.record_metrics();
+            }
+        })
+    }
+
+    /// Publish pool usage gauges once
+    pub fn record_metrics(&self) {
+        metrics::record_pool_state("write", &self.pool(), self.config.max_connections);
+
+        let reader = self.reader.read().expect("database pool lock poisoned");
+        if let Some(pool) = reader.as_ref() {
+            metrics::record_pool_state("read", pool, self.config.read_max_connections);
+        }
+    }
+}
+
+/// The replica URL is optional; a missing secret simply disables the replica
+async fn read_replica_url(secrets: &SecretsManager) -> Option<String> {
+    match secrets.get_value(secrets::DATABASE_READ_URL).await {
+        Ok(url) if !url.is_empty() => Some(url),
+        Ok(_) => None,
+        Err(e) => {
+            log::debug!("No read replica configured: {}", e);
+            None
+        }
+    }
+}
+
+fn close_in_background(pool: DbPool) {
+    // Let in-flight queries finish on the old credentials
+    tokio::spawn(async move {
+        pool.close().await;
+    });
+}
+
+/// SQLSTATE 28000 (invalid authorization) and 28P01 (invalid password)
+fn is_auth_error(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(db_error) => {
+            matches!(db_error.code().as_deref(), Some("28000") | Some("28P01"))
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,48 @@
+/// Whether a query may be served by a read replica
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryIntent {
+    Read,
+    Write,
+}
+
+impl QueryIntent {
+    /// Classify a SQL statement; anything not clearly read-only is a write
+    pub fn from_sql(sql: &str) -> Self {
+        let normalized = sql.trim_start().to_uppercase();
+
+        let locks_rows = normalized.contains(" FOR UPDATE") || normalized.contains(" FOR SHARE");
+        let modifies = ["INSERT ", "UPDATE ", "DELETE ", "MERGE "]
+            .iter()
+            .any(|keyword| normalized.contains(keyword));
+
+        if normalized.starts_with("SELECT") && !locks_rows {
+            QueryIntent::Read
+        } else if normalized.starts_with("WITH") && !locks_rows && !modifies {
+            QueryIntent::Read
+        } else {
+            QueryIntent::Write
+        }
+    }
+
+    /// Label used for pool metrics
+    pub fn role(&self) -> &'static str {
+        match self {
+            QueryIntent::Read => "read",
+            QueryIntent::Write => "write",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_intent_from_sql() {
+        assert_eq!(QueryIntent::from_sql("  select * from sync_pairs"), QueryIntent::Read);
+        assert_eq!(QueryIntent::from_sql("SELECT * FROM sync_pairs FOR UPDATE"), QueryIntent::Write);
+        assert_eq!(QueryIntent::from_sql("WITH x AS (SELECT 1) SELECT * FROM x"), QueryIntent::Read);
+        assert_eq!(QueryIntent::from_sql("WITH x AS (DELETE FROM t RETURNING *) SELECT * FROM x"), QueryIntent::Write);
+        assert_eq!(QueryIntent::from_sql("UPDATE sync_pairs SET is_active = false"), QueryIntent::Write);
+    }
+}
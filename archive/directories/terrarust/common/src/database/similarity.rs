@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::errors::{Error, Result};
+use super::DbPool;
+
+/// Dimensions of the `entity_embeddings.embedding` column
+pub const EMBEDDING_DIMENSIONS: usize = 768;
+
+/// An entity whose embedding is close to the query vector
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SimilarEntity {
+    pub entity_id: String,
+    /// Cosine similarity in [-1, 1]; 1 means identical direction
+    pub similarity: f64,
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// pgvector-backed store of entity embeddings.
+///
+/// Vectors are passed as text literals cast to `vector`, so no client-side
+/// pgvector type is needed.
+#[derive(Clone)]
+pub struct SimilarityStore {
+    pool: DbPool,
+    model: String,
+}
+
+impl SimilarityStore {
+    /// Create a store for embeddings produced by `model`
+    pub fn new(pool: DbPool, model: impl Into<String>) -> Self {
+        Self {
+            pool,
+            model: model.into(),
+        }
+    }
+
+    /// Whether the pgvector migration created the embeddings table
+    pub async fn is_available(&self) -> Result<bool> {
+        let exists: Option<String> = sqlx::query_scalar("SELECT to_regclass('entity_embeddings')::text")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(exists.is_some())
+    }
+
+    /// Insert or replace the embedding for an entity.
+    ///
+    /// `content_hash` identifies the text that was embedded, letting callers
+    /// skip re-embedding records that have not changed.
+    pub async fn upsert(
+        &self,
+        entity_type: &str,
+        entity_id: &str,
+        county_id: &str,
+        embedding: &[f32],
+        content_hash: &str,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<()> {
+        check_dimensions(embedding)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO entity_embeddings (
+                id, entity_type, entity_id, county_id, model, embedding, content_hash, metadata
+            ) VALUES ($1, $2, $3, $4, $5, $6::vector, $7, $8)
+            ON CONFLICT (entity_type, entity_id, county_id, model) DO UPDATE SET
+                embedding = EXCLUDED.embedding,
+                content_hash = EXCLUDED.content_hash,
+                metadata = EXCLUDED.metadata,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(entity_type)
+        .bind(entity_id)
+        .bind(county_id)
+        .bind(&self.model)
+        .bind(vector_literal(embedding))
+        .bind(content_hash)
+        .bind(metadata)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Hash of the text last embedded for an entity, if any
+    pub async fn content_hash(&self, entity_type: &str, entity_id: &str, county_id: &str) -> Result<Option<String>> {
+        let hash = sqlx::query_scalar(
+            r#"
+            SELECT content_hash FROM entity_embeddings
+            WHERE entity_type = $1 AND entity_id = $2 AND county_id = $3 AND model = $4
+            "#,
+        )
+        .bind(entity_type)
+        .bind(entity_id)
+        .bind(county_id)
+        .bind(&self.model)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(hash)
+    }
+
+    /// Nearest entities of the same type and county, most similar first
+    pub async fn find_similar(
+        &self,
+        entity_type: &str,
+        county_id: &str,
+        embedding: &[f32],
+        min_similarity: f64,
+        limit: i64,
+    ) -> Result<Vec<SimilarEntity>> {
+        check_dimensions(embedding)?;
+
+        // `<=>` is cosine distance; similarity = 1 - distance
+        let matches = sqlx::query_as::<_, SimilarEntity>(
+            r#"
+            SELECT entity_id, 1 - (embedding <=> $1::vector) AS similarity, metadata
+            FROM entity_embeddings
+            WHERE entity_type = $2 AND county_id = $3 AND model = $4
+              AND 1 - (embedding <=> $1::vector) >= $5
+            ORDER BY embedding <=> $1::vector
+            LIMIT $6
+            "#,
+        )
+        .bind(vector_literal(embedding))
+        .bind(entity_type)
+        .bind(county_id)
+        .bind(&self.model)
+        .bind(min_similarity)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(matches)
+    }
+
+    /// Remove an entity's embedding
+    pub async fn delete(&self, entity_type: &str, entity_id: &str, county_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM entity_embeddings WHERE entity_type = $1 AND entity_id = $2 AND county_id = $3")
+            .bind(entity_type)
+            .bind(entity_id)
+            .bind(county_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn check_dimensions(embedding: &[f32]) -> Result<()> {
+    if embedding.len() != EMBEDDING_DIMENSIONS {
+        return Err(Error::Validation(format!(
+            "Embedding has {} dimensions, expected {}",
+            embedding.len(),
+            EMBEDDING_DIMENSIONS
+        )));
+    }
+    Ok(())
+}
+
+/// Format a vector as a pgvector text literal, e.g. `[0.1,0.2]`
+fn vector_literal(embedding: &[f32]) -> String {
+    let values: Vec<String> = embedding.iter().map(|v| v.to_string()).collect();
+    format!("[{}]", values.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_literal() {
+        assert_eq!(vector_literal(&[0.5, -1.0, 2.25]), "[0.5,-1,2.25]");
+    }
+}
@@ -0,0 +1,57 @@
+use sqlx::{Postgres, Transaction};
+
+use crate::errors::{DatabaseError, Error, Result};
+use crate::tenancy::CountyContext;
+use super::DbPool;
+
+/// Tables that carry a `county_id` column and are isolated per county
+pub const COUNTY_SCOPED_TABLES: &[&str] = &[
+    "sync_pairs",
+    "sync_operations",
+    "audit_log",
+    "users",
+    "gis_exports",
+    "gis_export_jobs",
+    "export_templates",
+];
+
+/// Append a county filter to a query that already has a WHERE clause.
+///
+/// `param_index` is the placeholder number to use; the caller binds the
+/// county returned by `CountyContext::effective_county`. When that returns
+/// `None` (platform admin, no filter) the query is left unchanged.
+pub fn with_county_filter(sql: &str, county_id: Option<&str>, param_index: usize) -> String {
+    match county_id {
+        Some(_) => format!("{} AND county_id = ${}", sql, param_index),
+        None => sql.to_string(),
+    }
+}
+
+/// Begin a transaction with `app.county_id` set for row-level security
+pub async fn begin_scoped(pool: &DbPool, context: &CountyContext) -> Result<Transaction<'static, Postgres>> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| Error::Database(DatabaseError::Transaction(e.to_string())))?;
+
+    // is_local = true limits the setting to this transaction
+    sqlx::query("SELECT set_config('app.county_id', $1, true)")
+        .bind(context.session_value())
+        .execute(&mut tx)
+        .await
+        .map_err(|e| Error::Database(DatabaseError::Query(e.to_string())))?;
+
+    Ok(tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_county_filter() {
+        let sql = "SELECT * FROM sync_pairs WHERE 1=1";
+        assert_eq!(with_county_filter(sql, Some("benton"), 1), "SELECT * FROM sync_pairs WHERE 1=1 AND county_id = $1");
+        assert_eq!(with_county_filter(sql, None, 1), sql);
+    }
+}
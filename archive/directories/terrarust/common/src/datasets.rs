@@ -0,0 +1,216 @@
+//! Data versions of county datasets.
+//!
+//! Each export layer of a county has a version number that goes up by one
+//! whenever its data changes: when a sync operation feeding it completes
+//! with changes, or when seed data is uploaded for it. Exports record the
+//! versions they were generated from, so a consumer holding an export can
+//! ask what changed since.
+//!
+//! A sync pair feeds the layer named by `layer` in its target config, or
+//! failing that the target `table`.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::errors::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionSource {
+    /// A completed sync operation
+    Sync,
+    /// A seed data upload, which replaces the layer's features
+    Upload,
+}
+
+impl VersionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VersionSource::Sync => "sync",
+            VersionSource::Upload => "upload",
+        }
+    }
+}
+
+/// One version of a layer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DatasetVersion {
+    pub county_id: String,
+    pub layer_id: String,
+    pub version: i64,
+    pub source: String,
+    pub sync_operation_id: Option<Uuid>,
+    pub upload_id: Option<Uuid>,
+    pub records_changed: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A record changed by a sync after some version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DatasetChange {
+    pub version: i64,
+    pub entity_id: String,
+    pub entity_type: String,
+    pub change_type: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// The layer a sync pair's target feeds, if any
+pub fn layer_for_target(target_config: &Value) -> Option<String> {
+    ["layer", "table"]
+        .iter()
+        .filter_map(|key| target_config.get(*key).and_then(Value::as_str))
+        .map(str::trim)
+        .find(|layer| !layer.is_empty())
+        .map(str::to_string)
+}
+
+/// Persistence for dataset versions
+pub struct DatasetQueries;
+
+impl DatasetQueries {
+    /// Record a change to a layer and return its new version. Concurrent
+    /// bumps of one layer are serialized by an advisory lock.
+    pub async fn bump(
+        pool: &PgPool,
+        county_id: &str,
+        layer_id: &str,
+        source: VersionSource,
+        sync_operation_id: Option<Uuid>,
+        upload_id: Option<Uuid>,
+        records_changed: i64,
+    ) -> Result<i64> {
+        let mut tx = pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('dataset_versions:' || $1 || ':' || $2))")
+            .bind(county_id)
+            .bind(layer_id)
+            .execute(&mut *tx)
+            .await?;
+        let version: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO dataset_versions
+                (county_id, layer_id, version, source, sync_operation_id, upload_id, records_changed)
+            SELECT $1, $2, COALESCE(MAX(version), 0) + 1, $3, $4, $5, $6
+            FROM dataset_versions
+            WHERE county_id = $1 AND layer_id = $2
+            RETURNING version
+            "#,
+        )
+        .bind(county_id)
+        .bind(layer_id)
+        .bind(source.as_str())
+        .bind(sync_operation_id)
+        .bind(upload_id)
+        .bind(records_changed)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(version)
+    }
+
+    /// Current version of each of the layers, or the version they were at
+    /// at `as_of`; layers that never changed are at version 0
+    pub async fn current(
+        pool: &PgPool,
+        county_id: &str,
+        layers: &[String],
+        as_of: Option<DateTime<Utc>>,
+    ) -> Result<HashMap<String, i64>> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT layer_id, MAX(version)
+            FROM dataset_versions
+            WHERE county_id = $1 AND layer_id = ANY($2)
+              AND ($3::timestamptz IS NULL OR created_at <= $3)
+            GROUP BY layer_id
+            "#,
+        )
+        .bind(county_id)
+        .bind(layers)
+        .bind(as_of)
+        .fetch_all(pool)
+        .await?;
+
+        let mut versions: HashMap<String, i64> = layers.iter().map(|layer| (layer.clone(), 0)).collect();
+        versions.extend(rows);
+        Ok(versions)
+    }
+
+    /// Current version of every layer of the county that has one
+    pub async fn latest(pool: &PgPool, county_id: &str) -> Result<Vec<DatasetVersion>> {
+        let versions = sqlx::query_as::<_, DatasetVersion>(
+            r#"
+            SELECT DISTINCT ON (layer_id) *
+            FROM dataset_versions
+            WHERE county_id = $1
+            ORDER BY layer_id, version DESC
+            "#,
+        )
+        .bind(county_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(versions)
+    }
+
+    /// Versions of a layer after `since`, oldest first
+    pub async fn since(pool: &PgPool, county_id: &str, layer_id: &str, since: i64) -> Result<Vec<DatasetVersion>> {
+        let versions = sqlx::query_as::<_, DatasetVersion>(
+            r#"
+            SELECT * FROM dataset_versions
+            WHERE county_id = $1 AND layer_id = $2 AND version > $3
+            ORDER BY version
+            "#,
+        )
+        .bind(county_id)
+        .bind(layer_id)
+        .bind(since)
+        .fetch_all(pool)
+        .await?;
+        Ok(versions)
+    }
+
+    /// Records the syncs of versions after `since` changed, at most `limit`
+    pub async fn changes_since(
+        pool: &PgPool,
+        county_id: &str,
+        layer_id: &str,
+        since: i64,
+        limit: i64,
+    ) -> Result<Vec<DatasetChange>> {
+        let changes = sqlx::query_as::<_, DatasetChange>(
+            r#"
+            SELECT v.version, d.entity_id, d.entity_type, d.change_type, d.created_at AS changed_at
+            FROM dataset_versions v
+            JOIN sync_diffs d ON d.sync_operation_id = v.sync_operation_id
+            WHERE v.county_id = $1 AND v.layer_id = $2 AND v.version > $3
+            ORDER BY v.version, d.created_at, d.entity_id
+            LIMIT $4
+            "#,
+        )
+        .bind(county_id)
+        .bind(layer_id)
+        .bind(since)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_layer_for_target() {
+        assert_eq!(layer_for_target(&json!({ "layer": "parcels", "table": "pacs_parcels" })), Some("parcels".to_string()));
+        assert_eq!(layer_for_target(&json!({ "layer": " ", "table": "parcels" })), Some("parcels".to_string()));
+        assert_eq!(layer_for_target(&json!({ "endpoint": "https://gis.example.org" })), None);
+    }
+}
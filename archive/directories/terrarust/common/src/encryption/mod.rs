@@ -0,0 +1,365 @@
+//! Envelope encryption of sensitive JSON payloads at rest.
+//!
+//! Each payload is sealed with AES-256-GCM under a fresh data key, and the
+//! data key is wrapped with a key-encryption key (KEK) belonging to the
+//! payload's county. KEKs are 32 random bytes, base64-encoded, kept in the
+//! secrets provider; `county_encryption_keys` only records which secret
+//! holds each version of a county's key. Counties without a key of their own
+//! use version 0, the shared PAYLOAD_ENCRYPTION_KEY secret.
+//!
+//! Rotating a county's key adds a version and makes it the active one.
+//! Older versions stay readable. Moving a payload to the new version only
+//! re-wraps its data key; the payload ciphertext is left as it is.
+//!
+//! A sealed payload is stored in place of the plaintext as a JSON object
+//! marked with a `$encrypted` format number, so encrypted and not yet
+//! encrypted rows can share a column.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use tokio::sync::RwLock;
+
+use crate::database::RotatingPool;
+use crate::errors::{Error, Result};
+use crate::secrets::SecretsManager;
+
+/// Secret holding the key of counties without their own (version 0)
+pub const DEFAULT_KEY_SECRET: &str = "PAYLOAD_ENCRYPTION_KEY";
+
+/// Envelope format written by this version
+const ENVELOPE_FORMAT: u8 = 1;
+
+const NONCE_LEN: usize = 12;
+
+/// A sealed payload as stored in a JSON column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    #[serde(rename = "$encrypted")]
+    pub format: u8,
+    /// County key version the data key is wrapped with
+    pub key_version: i32,
+    /// Nonce and wrapped data key, base64
+    pub wrapped_key: String,
+    /// Nonce and payload ciphertext, base64
+    pub ciphertext: String,
+}
+
+impl Envelope {
+    /// The envelope stored in `value`, or `None` for a plaintext payload
+    pub fn parse(value: &Value) -> Result<Option<Self>> {
+        if value.get("$encrypted").is_none() {
+            return Ok(None);
+        }
+        let envelope: Self = serde_json::from_value(value.clone())
+            .map_err(|e| Error::Serialization(format!("Invalid encrypted payload: {}", e)))?;
+        if envelope.format != ENVELOPE_FORMAT {
+            return Err(Error::Serialization(format!("Unsupported payload encryption format {}", envelope.format)));
+        }
+        Ok(Some(envelope))
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::json!({
+            "$encrypted": self.format,
+            "key_version": self.key_version,
+            "wrapped_key": self.wrapped_key,
+            "ciphertext": self.ciphertext,
+        })
+    }
+}
+
+/// A key-encryption key
+#[derive(Clone)]
+pub struct Kek([u8; 32]);
+
+impl Kek {
+    /// Parse a base64-encoded 32-byte key
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|_| Error::Config("Payload encryption keys must be base64".to_string()))?;
+        let key: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| Error::Config("Payload encryption keys must be 32 bytes".to_string()))?;
+        Ok(Self(key))
+    }
+}
+
+impl std::fmt::Debug for Kek {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Kek(<redacted>)")
+    }
+}
+
+fn seal_bytes(key: &[u8; 32], aad: &[u8], plaintext: &[u8]) -> Result<String> {
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| Error::Internal("Payload encryption failed".to_string()))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+    Ok(BASE64.encode(sealed))
+}
+
+fn open_bytes(key: &[u8; 32], aad: &[u8], sealed: &str) -> Result<Vec<u8>> {
+    let sealed = BASE64
+        .decode(sealed)
+        .map_err(|_| Error::Serialization("Encrypted payload is not base64".to_string()))?;
+    if sealed.len() < NONCE_LEN {
+        return Err(Error::Serialization("Encrypted payload is truncated".to_string()));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| Error::Internal("Encrypted payload could not be decrypted with the county's key".to_string()))
+}
+
+/// The data key is bound to the county and key version, the payload to the county
+fn key_aad(county_id: &str, version: i32) -> Vec<u8> {
+    format!("{}:{}", county_id, version).into_bytes()
+}
+
+/// Seal `value` for `county_id` under `kek`, version `version`
+pub fn seal_with(kek: &Kek, county_id: &str, version: i32, value: &Value) -> Result<Envelope> {
+    let data_key: [u8; 32] = rand::random();
+    let plaintext = serde_json::to_vec(value).map_err(|e| Error::Serialization(e.to_string()))?;
+
+    Ok(Envelope {
+        format: ENVELOPE_FORMAT,
+        key_version: version,
+        wrapped_key: seal_bytes(&kek.0, &key_aad(county_id, version), &data_key)?,
+        ciphertext: seal_bytes(&data_key, county_id.as_bytes(), &plaintext)?,
+    })
+}
+
+fn unwrap_data_key(kek: &Kek, county_id: &str, envelope: &Envelope) -> Result<[u8; 32]> {
+    open_bytes(&kek.0, &key_aad(county_id, envelope.key_version), &envelope.wrapped_key)?
+        .try_into()
+        .map_err(|_| Error::Serialization("Wrapped data key has the wrong length".to_string()))
+}
+
+/// Decrypt an envelope sealed for `county_id`
+pub fn open_with(kek: &Kek, county_id: &str, envelope: &Envelope) -> Result<Value> {
+    let data_key = unwrap_data_key(kek, county_id, envelope)?;
+    let plaintext = open_bytes(&data_key, county_id.as_bytes(), &envelope.ciphertext)?;
+    serde_json::from_slice(&plaintext).map_err(|e| Error::Serialization(format!("Decrypted payload is not JSON: {}", e)))
+}
+
+/// Wrap an envelope's data key with another key version
+pub fn rewrap_with(old: &Kek, new: &Kek, county_id: &str, new_version: i32, envelope: &Envelope) -> Result<Envelope> {
+    let data_key = unwrap_data_key(old, county_id, envelope)?;
+    Ok(Envelope {
+        format: ENVELOPE_FORMAT,
+        key_version: new_version,
+        wrapped_key: seal_bytes(&new.0, &key_aad(county_id, new_version), &data_key)?,
+        ciphertext: envelope.ciphertext.clone(),
+    })
+}
+
+/// Wrap a data key used outside JSON payloads (e.g. for files) with `kek`.
+/// `aad` must be given again to unwrap it.
+pub fn wrap_key(kek: &Kek, aad: &[u8], data_key: &[u8; 32]) -> Result<String> {
+    seal_bytes(&kek.0, aad, data_key)
+}
+
+/// Unwrap a data key wrapped by `wrap_key`
+pub fn unwrap_key(kek: &Kek, aad: &[u8], wrapped: &str) -> Result<[u8; 32]> {
+    open_bytes(&kek.0, aad, wrapped)?
+        .try_into()
+        .map_err(|_| Error::Serialization("Wrapped data key has the wrong length".to_string()))
+}
+
+/// A registered version of a county's key
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct CountyKey {
+    pub county_id: String,
+    pub version: i32,
+    pub secret_name: String,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Seals and opens payloads with county keys from the secrets provider
+#[derive(Clone)]
+pub struct PayloadCipher {
+    pool: RotatingPool,
+    secrets: SecretsManager,
+    /// Keys never change once registered (rotation adds a version), so they
+    /// are kept for the life of the process
+    keys: Arc<RwLock<HashMap<(String, i32), Kek>>>,
+}
+
+impl PayloadCipher {
+    pub fn new(pool: RotatingPool, secrets: SecretsManager) -> Self {
+        Self {
+            pool,
+            secrets,
+            keys: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// A cipher when PAYLOAD_ENCRYPTION_ENABLED is true, using the provider
+    /// selected by SECRETS_PROVIDER
+    pub fn from_env(pool: RotatingPool) -> Result<Option<Self>> {
+        let enabled = std::env::var("PAYLOAD_ENCRYPTION_ENABLED")
+            .map(|value| value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        if !enabled {
+            return Ok(None);
+        }
+        Ok(Some(Self::new(pool, SecretsManager::from_env()?)))
+    }
+
+    /// Version new payloads for the county are sealed with
+    pub async fn active_version(&self, county_id: &str) -> Result<i32> {
+        let version = sqlx::query_scalar::<_, i32>(
+            "SELECT version FROM county_encryption_keys WHERE county_id = $1 AND active",
+        )
+        .bind(county_id)
+        .fetch_optional(&self.pool.pool())
+        .await?;
+        Ok(version.unwrap_or(0))
+    }
+
+    async fn kek(&self, county_id: &str, version: i32) -> Result<Kek> {
+        let cache_key = (county_id.to_string(), version);
+        if let Some(kek) = self.keys.read().await.get(&cache_key) {
+            return Ok(kek.clone());
+        }
+
+        let secret_name = if version == 0 {
+            DEFAULT_KEY_SECRET.to_string()
+        } else {
+            sqlx::query_scalar::<_, String>(
+                "SELECT secret_name FROM county_encryption_keys WHERE county_id = $1 AND version = $2",
+            )
+            .bind(county_id)
+            .bind(version)
+            .fetch_optional(&self.pool.pool())
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Key version {} of county {} is not registered", version, county_id)))?
+        };
+        let kek = Kek::from_base64(&self.secrets.get_value(&secret_name).await?)?;
+
+        self.keys.write().await.insert(cache_key, kek.clone());
+        Ok(kek)
+    }
+
+    /// Seal `value` with the county's active key
+    pub async fn seal(&self, county_id: &str, value: &Value) -> Result<Value> {
+        let version = self.active_version(county_id).await?;
+        self.seal_version(county_id, version, value).await
+    }
+
+    /// Bring a payload to key `version`: plaintext is sealed, an envelope on
+    /// another version is re-wrapped. Returns `None` when nothing changed.
+    pub async fn reseal(&self, county_id: &str, version: i32, value: &Value) -> Result<Option<Value>> {
+        match Envelope::parse(value)? {
+            None => Ok(Some(self.seal_version(county_id, version, value).await?)),
+            Some(envelope) if envelope.key_version == version => Ok(None),
+            Some(envelope) => {
+                let old = self.kek(county_id, envelope.key_version).await?;
+                let new = self.kek(county_id, version).await?;
+                Ok(Some(rewrap_with(&old, &new, county_id, version, &envelope)?.to_value()))
+            }
+        }
+    }
+
+    async fn seal_version(&self, county_id: &str, version: i32, value: &Value) -> Result<Value> {
+        let kek = self.kek(county_id, version).await?;
+        Ok(seal_with(&kek, county_id, version, value)?.to_value())
+    }
+
+    /// Decrypt a payload of the county; plaintext is returned unchanged
+    pub async fn open(&self, county_id: &str, value: Value) -> Result<Value> {
+        match Envelope::parse(&value)? {
+            Some(envelope) => open_with(&self.kek(county_id, envelope.key_version).await?, county_id, &envelope),
+            None => Ok(value),
+        }
+    }
+
+    /// Key versions registered for the county, newest first
+    pub async fn keys(&self, county_id: &str) -> Result<Vec<CountyKey>> {
+        let keys = sqlx::query_as::<_, CountyKey>(
+            "SELECT * FROM county_encryption_keys WHERE county_id = $1 ORDER BY version DESC",
+        )
+        .bind(county_id)
+        .fetch_all(&self.pool.pool())
+        .await?;
+        Ok(keys)
+    }
+
+    /// Register `secret_name` as the county's next key version and make it
+    /// active. The secret must already hold a valid key.
+    pub async fn rotate(&self, county_id: &str, secret_name: &str) -> Result<CountyKey> {
+        Kek::from_base64(&self.secrets.refresh(secret_name).await?.value)?;
+
+        let mut tx = self.pool.pool().begin().await?;
+        sqlx::query("UPDATE county_encryption_keys SET active = false WHERE county_id = $1 AND active")
+            .bind(county_id)
+            .execute(&mut tx)
+            .await?;
+        let key = sqlx::query_as::<_, CountyKey>(
+            r#"
+            INSERT INTO county_encryption_keys (county_id, version, secret_name, active)
+            SELECT $1, COALESCE(MAX(version), 0) + 1, $2, true
+            FROM county_encryption_keys WHERE county_id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(county_id)
+        .bind(secret_name)
+        .fetch_one(&mut tx)
+        .await?;
+        tx.commit().await?;
+
+        log::info!("County {} payload key rotated to version {}", county_id, key.version);
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn kek(byte: u8) -> Kek {
+        Kek::from_base64(&BASE64.encode([byte; 32])).unwrap()
+    }
+
+    #[test]
+    fn test_seal_open_and_rewrap() {
+        let payload = json!({ "owner": "Jane Doe", "situs": "1 Main St" });
+        let envelope = seal_with(&kek(1), "benton", 0, &payload).unwrap();
+
+        assert_eq!(Envelope::parse(&envelope.to_value()).unwrap(), Some(envelope.clone()));
+        assert_eq!(Envelope::parse(&payload).unwrap(), None);
+        assert_eq!(open_with(&kek(1), "benton", &envelope).unwrap(), payload);
+
+        // Bound to the county and key
+        assert!(open_with(&kek(1), "franklin", &envelope).is_err());
+        assert!(open_with(&kek(2), "benton", &envelope).is_err());
+
+        let rewrapped = rewrap_with(&kek(1), &kek(2), "benton", 1, &envelope).unwrap();
+        assert_eq!(rewrapped.ciphertext, envelope.ciphertext);
+        assert_eq!(open_with(&kek(2), "benton", &rewrapped).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_kek_parsing() {
+        assert!(Kek::from_base64("not base64!").is_err());
+        assert!(Kek::from_base64(&BASE64.encode([0u8; 16])).is_err());
+        assert!(!format!("{:?}", kek(7)).contains('7'));
+    }
+}
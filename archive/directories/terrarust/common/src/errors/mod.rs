@@ -71,6 +71,18 @@ pub enum Error {
     /// Parse errors
     #[error("Parse error: {0}")]
     Parse(String),
+    
+    /// Request conflicts with the current state (e.g. a retry still in progress)
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    
+    /// Temporarily unavailable (maintenance, dependency not ready)
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+    
+    /// A county has used up a monthly quota
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
 }
 
 impl Error {
@@ -93,6 +105,9 @@ impl Error {
             Error::Io(_) => 500,
             Error::HttpClient(_) => 500,
             Error::Parse(_) => 400,
+            Error::Conflict(_) => 409,
+            Error::ServiceUnavailable(_) => 503,
+            Error::QuotaExceeded(_) => 429,
         }
     }
     
@@ -115,6 +130,9 @@ impl Error {
             Error::Io(_) => "io_error",
             Error::HttpClient(_) => "http_client_error",
             Error::Parse(_) => "parse_error",
+            Error::Conflict(_) => "conflict",
+            Error::ServiceUnavailable(_) => "service_unavailable",
+            Error::QuotaExceeded(_) => "quota_exceeded",
         }
     }
     
@@ -125,6 +143,7 @@ impl Error {
             error_type: self.error_type().to_string(),
             message: self.to_string(),
             details: None,
+            correlation_id: None,
         }
     }
 }
@@ -172,6 +191,54 @@ pub struct ErrorResponse {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<serde_json::Value>,
+    /// Request id the failure was logged under
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
+
+impl ErrorResponse {
+    /// Response for a status that carries no `Error`, e.g. an extractor failure
+    pub fn from_status(code: u16, message: impl Into<String>) -> Self {
+        let error_type = match code {
+            400 => "bad_request",
+            401 => "authentication_error",
+            403 => "authorization_error",
+            404 => "not_found",
+            405 => "method_not_allowed",
+            409 => "conflict",
+            413 => "payload_too_large",
+            415 => "unsupported_media_type",
+            422 => "validation_error",
+            429 => "rate_limited",
+            503 => "service_unavailable",
+            500..=599 => "internal_server_error",
+            _ => "client_error",
+        };
+        Self {
+            code,
+            error_type: error_type.to_string(),
+            message: message.into(),
+            details: None,
+            correlation_id: None,
+        }
+    }
+
+    pub fn with_correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.correlation_id = Some(correlation_id.into());
+        self
+    }
+}
+
+/// Body of every error response: `{"error": {code, type, message, details, correlation_id}}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorEnvelope {
+    pub error: ErrorResponse,
+}
+
+impl From<ErrorResponse> for ErrorEnvelope {
+    fn from(error: ErrorResponse) -> Self {
+        Self { error }
+    }
 }
 
 impl fmt::Display for ErrorResponse {
@@ -222,6 +289,157 @@ pub fn map_sqlx_error(error: sqlx::Error) -> Error {
     }
 }
 
+/// Actix support: `Error` as a response, plus a middleware that gives every
+/// error response the envelope and a correlation id
+#[cfg(feature = "actix")]
+pub mod web {
+    use std::future::{ready, Ready};
+    use std::rc::Rc;
+    use std::task::{Context, Poll};
+    use actix_web::{
+        body::{BoxBody, MessageBody},
+        dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform},
+        http::{header::{self, HeaderName, HeaderValue}, StatusCode},
+        FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError,
+    };
+    use futures::future::LocalBoxFuture;
+
+    use super::{Error, ErrorEnvelope, ErrorResponse};
+
+    /// Header carrying the correlation id, shared with the gateway
+    pub const REQUEST_ID_HEADER: &str = "X-Request-ID";
+
+    impl ResponseError for Error {
+        fn status_code(&self) -> StatusCode {
+            StatusCode::from_u16(Error::status_code(self)).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+
+        fn error_response(&self) -> HttpResponse {
+            HttpResponse::build(ResponseError::status_code(self)).json(ErrorEnvelope::from(self.to_response()))
+        }
+    }
+
+    /// Id of the current request, from `X-Request-ID` or generated
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CorrelationId(pub String);
+
+    impl FromRequest for CorrelationId {
+        type Error = actix_web::Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+            let id = req
+                .extensions()
+                .get::<CorrelationId>()
+                .cloned()
+                .unwrap_or_else(|| CorrelationId(request_id(req.headers())));
+            ready(Ok(id))
+        }
+    }
+
+    fn request_id(headers: &header::HeaderMap) -> String {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty() && value.len() <= 128)
+            .map(str::to_string)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Give every error response the common envelope.
+    ///
+    /// Responses produced from an `Error` are rebuilt with the request's
+    /// correlation id; other actix errors (bad JSON, unmatched path
+    /// parameters) are wrapped with a type derived from their status. The
+    /// correlation id is echoed in `X-Request-ID` on every response.
+    pub struct ErrorEnvelopeMiddleware;
+
+    impl<S, B> Transform<S, ServiceRequest> for ErrorEnvelopeMiddleware
+    where
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+        B: MessageBody + 'static,
+    {
+        type Response = ServiceResponse<BoxBody>;
+        type Error = actix_web::Error;
+        type Transform = ErrorEnvelopeMiddlewareService<S>;
+        type InitError = ();
+        type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+        fn new_transform(&self, service: S) -> Self::Future {
+            ready(Ok(ErrorEnvelopeMiddlewareService { service: Rc::new(service) }))
+        }
+    }
+
+    pub struct ErrorEnvelopeMiddlewareService<S> {
+        service: Rc<S>,
+    }
+
+    impl<S, B> Service<ServiceRequest> for ErrorEnvelopeMiddlewareService<S>
+    where
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+        B: MessageBody + 'static,
+    {
+        type Response = ServiceResponse<BoxBody>;
+        type Error = actix_web::Error;
+        type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.service.poll_ready(cx)
+        }
+
+        fn call(&self, req: ServiceRequest) -> Self::Future {
+            let service = self.service.clone();
+            let correlation_id = request_id(req.headers());
+            req.extensions_mut().insert(CorrelationId(correlation_id.clone()));
+
+            let request = req.request().clone();
+
+            Box::pin(async move {
+                // Errors raised by inner middleware are turned into responses here
+                // so that they get the envelope too
+                let res = match service.call(req).await {
+                    Ok(res) => res,
+                    Err(err) => ServiceResponse::new(request, HttpResponse::from_error(err)),
+                };
+                let status = res.status();
+
+                let envelope = match res.response().error() {
+                    Some(err) if status.is_client_error() || status.is_server_error() => {
+                        let response = match err.as_error::<Error>() {
+                            Some(error) => error.to_response(),
+                            None => ErrorResponse::from_status(status.as_u16(), err.to_string()),
+                        };
+                        if status.is_server_error() {
+                            log::error!("[{}] {} {}: {}", correlation_id, res.request().method(), res.request().path(), err);
+                        }
+                        Some(ErrorEnvelope::from(response.with_correlation_id(correlation_id.clone())))
+                    }
+                    _ => None,
+                };
+
+                let mut res = match envelope {
+                    Some(envelope) => {
+                        // Keep headers such as Retry-After; only the body changes
+                        let mut response = HttpResponse::build(status).json(envelope);
+                        for (name, value) in res.headers() {
+                            if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+                                response.headers_mut().append(name.clone(), value.clone());
+                            }
+                        }
+                        res.into_response(response)
+                    }
+                    None => res.map_into_boxed_body(),
+                };
+                if let Ok(value) = HeaderValue::from_str(&correlation_id) {
+                    res.headers_mut().insert(HeaderName::from_static("x-request-id"), value);
+                }
+                Ok(res)
+            })
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,4 +460,20 @@ mod tests {
         assert_eq!(resp.error_type, "not_found");
         assert_eq!(resp.message, "Not found: Resource not found");
     }
+
+    #[test]
+    fn test_error_envelope_shape() {
+        let resp = Error::Conflict("Export already running".to_string())
+            .to_response()
+            .with_correlation_id("req-1");
+        let body = serde_json::to_value(ErrorEnvelope::from(resp)).unwrap();
+
+        assert_eq!(body["error"]["code"], 409);
+        assert_eq!(body["error"]["type"], "conflict");
+        assert_eq!(body["error"]["correlation_id"], "req-1");
+        assert!(body["error"].get("details").is_none());
+
+        let unmatched = ErrorResponse::from_status(415, "Content type error");
+        assert_eq!(unmatched.error_type, "unsupported_media_type");
+    }
 }
\ No newline at end of file
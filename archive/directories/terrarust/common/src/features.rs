@@ -0,0 +1,351 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::database::RotatingPool;
+use crate::errors::{Error, Result};
+use crate::tenancy::CountyContext;
+
+/// Two-way sync pairs (target changes written back to the source)
+pub const BIDIRECTIONAL_SYNC: &str = "bidirectional_sync";
+/// Esri File Geodatabase export format
+pub const FGDB_EXPORT: &str = "fgdb_export";
+
+/// A feature that can be switched on per county.
+///
+/// Evaluation: the county override if there is one, otherwise the default;
+/// when `roles` is non-empty the caller must also hold one of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureFlag {
+    pub key: String,
+    pub description: String,
+    /// Default for counties without an override
+    pub enabled: bool,
+    /// Roles allowed to use the feature; empty means everyone
+    pub roles: Vec<String>,
+    pub county_overrides: BTreeMap<String, bool>,
+    pub updated_by: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FeatureFlag {
+    pub fn is_enabled_for(&self, county: &CountyContext) -> bool {
+        let county_enabled = self
+            .county_overrides
+            .get(&county.county_id)
+            .copied()
+            .unwrap_or(self.enabled);
+
+        county_enabled && (self.roles.is_empty() || self.roles.iter().any(|role| county.has_role(role)))
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct FeatureFlagRow {
+    key: String,
+    description: String,
+    enabled: bool,
+    roles: Vec<String>,
+    updated_by: String,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct FeatureFlagOverrideRow {
+    flag_key: String,
+    county_id: String,
+    enabled: bool,
+}
+
+/// Persistence for feature flags
+pub struct FeatureFlagQueries;
+
+impl FeatureFlagQueries {
+    pub async fn list(pool: &PgPool) -> Result<Vec<FeatureFlag>> {
+        let rows = sqlx::query_as::<_, FeatureFlagRow>(
+            "SELECT key, description, enabled, roles, updated_by, updated_at FROM feature_flags ORDER BY key",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let overrides = sqlx::query_as::<_, FeatureFlagOverrideRow>(
+            "SELECT flag_key, county_id, enabled FROM feature_flag_overrides",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut flags: Vec<FeatureFlag> = rows
+            .into_iter()
+            .map(|row| FeatureFlag {
+                key: row.key,
+                description: row.description,
+                enabled: row.enabled,
+                roles: row.roles,
+                county_overrides: BTreeMap::new(),
+                updated_by: row.updated_by,
+                updated_at: row.updated_at,
+            })
+            .collect();
+
+        for row in overrides {
+            if let Some(flag) = flags.iter_mut().find(|flag| flag.key == row.flag_key) {
+                flag.county_overrides.insert(row.county_id, row.enabled);
+            }
+        }
+
+        Ok(flags)
+    }
+
+    /// Create or update a flag's default and role restriction
+    pub async fn upsert(
+        pool: &PgPool,
+        key: &str,
+        description: &str,
+        enabled: bool,
+        roles: &[String],
+        updated_by: &str,
+    ) -> Result<()> {
+        validate_key(key)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO feature_flags (key, description, enabled, roles, updated_by, updated_at)
+            VALUES ($1, $2, $3, $4, $5, NOW())
+            ON CONFLICT (key) DO UPDATE
+            SET description = EXCLUDED.description, enabled = EXCLUDED.enabled, roles = EXCLUDED.roles,
+                updated_by = EXCLUDED.updated_by, updated_at = NOW()
+            "#,
+        )
+        .bind(key)
+        .bind(description)
+        .bind(enabled)
+        .bind(roles)
+        .bind(updated_by)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Set a county override, or remove it with `None` so the default applies again
+    pub async fn set_override(pool: &PgPool, key: &str, county_id: &str, enabled: Option<bool>, updated_by: &str) -> Result<()> {
+        let mut tx = pool.begin().await?;
+
+        let touched = sqlx::query("UPDATE feature_flags SET updated_by = $2, updated_at = NOW() WHERE key = $1")
+            .bind(key)
+            .bind(updated_by)
+            .execute(&mut tx)
+            .await?;
+        if touched.rows_affected() == 0 {
+            return Err(Error::NotFound(format!("Feature flag {} not found", key)));
+        }
+
+        match enabled {
+            Some(enabled) => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO feature_flag_overrides (flag_key, county_id, enabled)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (flag_key, county_id) DO UPDATE SET enabled = EXCLUDED.enabled
+                    "#,
+                )
+                .bind(key)
+                .bind(county_id)
+                .bind(enabled)
+                .execute(&mut tx)
+                .await?;
+            }
+            None => {
+                sqlx::query("DELETE FROM feature_flag_overrides WHERE flag_key = $1 AND county_id = $2")
+                    .bind(key)
+                    .bind(county_id)
+                    .execute(&mut tx)
+                    .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Delete a flag and its overrides; returns whether it existed
+    pub async fn delete(pool: &PgPool, key: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM feature_flags WHERE key = $1")
+            .bind(key)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn validate_key(key: &str) -> Result<()> {
+    let valid = !key.is_empty()
+        && key.len() <= 100
+        && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::Validation(format!(
+            "Invalid feature flag key '{}': use lowercase letters, digits and underscores",
+            key
+        )))
+    }
+}
+
+/// Cached feature flags, refreshed from the database.
+///
+/// Cloning is cheap; every clone observes the same refreshes. Unknown flags
+/// are treated as disabled.
+#[derive(Clone, Default)]
+pub struct FeatureFlags {
+    flags: Arc<RwLock<Arc<HashMap<String, FeatureFlag>>>>,
+}
+
+impl FeatureFlags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All flags, keyed by name
+    pub fn snapshot(&self) -> Arc<HashMap<String, FeatureFlag>> {
+        self.flags.read().expect("feature flag lock poisoned").clone()
+    }
+
+    pub fn replace(&self, flags: Vec<FeatureFlag>) {
+        let flags = flags.into_iter().map(|flag| (flag.key.clone(), flag)).collect();
+        *self.flags.write().expect("feature flag lock poisoned") = Arc::new(flags);
+    }
+
+    pub fn is_enabled(&self, key: &str, county: &CountyContext) -> bool {
+        self.snapshot()
+            .get(key)
+            .map_or(false, |flag| flag.is_enabled_for(county))
+    }
+
+    /// Return an authorization error unless the feature is enabled for the caller
+    pub fn ensure_enabled(&self, key: &str, county: &CountyContext) -> Result<()> {
+        if self.is_enabled(key, county) {
+            Ok(())
+        } else {
+            Err(Error::Authorization(format!(
+                "Feature '{}' is not enabled for county {}",
+                key, county.county_id
+            )))
+        }
+    }
+
+    /// Reload the flags from the database now
+    pub async fn refresh(&self, pool: &PgPool) -> Result<()> {
+        let flags = FeatureFlagQueries::list(pool).await?;
+        self.replace(flags);
+        Ok(())
+    }
+
+    /// Poll the database so flag changes reach every instance
+    pub fn spawn_refresh(&self, pool: RotatingPool, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let flags = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+                if let Err(e) = flags.refresh(&pool.read_pool()).await {
+                    log::error!("Failed to refresh feature flags: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Compile-time name of a feature, for use with [`RequireFeature`]
+pub trait Feature: 'static {
+    const KEY: &'static str;
+}
+
+pub struct BidirectionalSync;
+
+impl Feature for BidirectionalSync {
+    const KEY: &'static str = BIDIRECTIONAL_SYNC;
+}
+
+pub struct FgdbExport;
+
+impl Feature for FgdbExport {
+    const KEY: &'static str = FGDB_EXPORT;
+}
+
+/// Handler argument that rejects the request with 403 unless feature `F`
+/// is enabled for the caller's county and roles.
+///
+/// Needs `web::Data<FeatureFlags>` registered as app data.
+pub struct RequireFeature<F: Feature> {
+    pub county: CountyContext,
+    _feature: std::marker::PhantomData<F>,
+}
+
+#[cfg(feature = "actix")]
+mod extractor {
+    use actix_web::{dev::Payload, error, web, FromRequest, HttpRequest};
+    use std::future::{ready, Ready};
+
+    use super::{Feature, FeatureFlags, RequireFeature};
+    use crate::tenancy::extractor::county_from_request;
+
+    impl<F: Feature> FromRequest for RequireFeature<F> {
+        type Error = actix_web::Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+            let county = match county_from_request(req) {
+                Ok(county) => county,
+                Err(e) => return ready(Err(e)),
+            };
+
+            let Some(flags) = req.app_data::<web::Data<FeatureFlags>>() else {
+                log::error!("FeatureFlags app data is not registered");
+                return ready(Err(error::ErrorInternalServerError("Feature flags unavailable")));
+            };
+
+            ready(match flags.ensure_enabled(F::KEY, &county) {
+                Ok(()) => Ok(RequireFeature { county, _feature: std::marker::PhantomData }),
+                Err(e) => Err(error::ErrorForbidden(e.to_string())),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flag_evaluation() {
+        let mut flag = FeatureFlag {
+            key: FGDB_EXPORT.to_string(),
+            description: String::new(),
+            enabled: false,
+            roles: Vec::new(),
+            county_overrides: BTreeMap::new(),
+            updated_by: "admin".to_string(),
+            updated_at: Utc::now(),
+        };
+        flag.county_overrides.insert("benton".to_string(), true);
+
+        let benton = CountyContext::new("benton");
+        assert!(flag.is_enabled_for(&benton));
+        assert!(!flag.is_enabled_for(&CountyContext::new("franklin")));
+
+        flag.roles = vec!["gis_analyst".to_string()];
+        assert!(!flag.is_enabled_for(&benton));
+        assert!(flag.is_enabled_for(&benton.with_roles(vec!["gis_analyst".to_string()])));
+
+        assert!(validate_key("fgdb_export").is_ok());
+        assert!(validate_key("FGDB export").is_err());
+    }
+}
@@ -0,0 +1,106 @@
+//! Checking that geometries fall within a county boundary.
+//!
+//! Boundaries are configured in WGS 84. Most geometries that land outside
+//! their county are not data about another county but a typo or a
+//! projection mistake, so a failed check says which one it looks like:
+//! latitude and longitude swapped, or projected coordinates (feet or meters)
+//! where degrees were expected.
+
+use geo::{Coord, Geometry, Intersects, MapCoords};
+use serde_json::Value;
+
+use super::measure::Bbox;
+use super::parse::parse_geometry;
+use crate::errors::{Error, Result};
+
+/// A county boundary in the CRS of the geometries it checks
+#[derive(Debug, Clone)]
+pub struct Boundary {
+    geometry: Geometry<f64>,
+    /// Whether checked geometries are in degrees
+    geographic: bool,
+}
+
+impl Boundary {
+    /// A boundary given as GeoJSON, WKT or WKB in WGS 84
+    pub fn from_value(value: &Value) -> Result<Self> {
+        let parsed = parse_geometry(value)?;
+        if !matches!(parsed.geometry, Geometry::Polygon(_) | Geometry::MultiPolygon(_)) {
+            return Err(Error::GeoProcessing("A county boundary must be a Polygon or MultiPolygon".to_string()));
+        }
+        if parsed.srid.is_some_and(|srid| srid != 4326) {
+            return Err(Error::GeoProcessing("A county boundary must be in WGS 84 (EPSG:4326)".to_string()));
+        }
+        Ok(Self { geometry: parsed.geometry, geographic: true })
+    }
+
+    /// The same boundary in a projected CRS, for checking geometries in
+    /// that CRS without reprojecting each one
+    pub fn to_crs(&self, crs: &str) -> Result<Self> {
+        let proj = proj::Proj::new_known_crs("EPSG:4326", crs, None)
+            .map_err(|e| Error::GeoProcessing(format!("Cannot project to {}: {}", crs, e)))?;
+        let geometry = self
+            .geometry
+            .try_map_coords(|Coord { x, y }| proj.convert((x, y)).map(|(x, y)| Coord { x, y }))
+            .map_err(|e| Error::GeoProcessing(format!("Cannot project boundary to {}: {}", crs, e)))?;
+        Ok(Self { geometry, geographic: super::Measurement::for_crs(crs).ok() == Some(super::Measurement::Geodesic) })
+    }
+
+    /// `None` when the geometry is at least partly inside the boundary,
+    /// otherwise why it is not
+    pub fn check(&self, geometry: &Geometry<f64>) -> Option<String> {
+        if self.geometry.intersects(geometry) {
+            return None;
+        }
+        let swapped = geometry.map_coords(|Coord { x, y }| Coord { x: y, y: x });
+        let hint = if self.geometry.intersects(&swapped) {
+            "; latitude and longitude look swapped"
+        } else if self.geographic
+            && Bbox::of(geometry).is_some_and(|bbox| {
+                bbox.min_x.abs().max(bbox.max_x.abs()) > 180.0 || bbox.min_y.abs().max(bbox.max_y.abs()) > 90.0
+            })
+        {
+            "; coordinates look projected rather than in degrees"
+        } else {
+            ""
+        };
+        Some(format!("Geometry is outside the county boundary{}", hint))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::{point, polygon};
+    use serde_json::json;
+
+    fn benton() -> Boundary {
+        Boundary::from_value(&json!("POLYGON((-119.9 45.9, -119.0 45.9, -119.0 46.6, -119.9 46.6, -119.9 45.9))")).unwrap()
+    }
+
+    #[test]
+    fn test_check() {
+        let boundary = benton();
+        assert_eq!(boundary.check(&point!(x: -119.28, y: 46.21).into()), None);
+
+        // A parcel straddling the county line is in
+        let straddling: Geometry<f64> =
+            polygon![(x: -119.05, y: 46.0), (x: -118.95, y: 46.0), (x: -118.95, y: 46.1), (x: -119.05, y: 46.1)].into();
+        assert_eq!(boundary.check(&straddling), None);
+
+        let swapped = boundary.check(&point!(x: 46.21, y: -119.28).into()).unwrap();
+        assert!(swapped.ends_with("look swapped"));
+        let projected = boundary.check(&point!(x: 1_960_000.0, y: 320_000.0).into()).unwrap();
+        assert!(projected.ends_with("rather than in degrees"));
+        assert_eq!(
+            boundary.check(&point!(x: -122.3, y: 47.6).into()).as_deref(),
+            Some("Geometry is outside the county boundary")
+        );
+    }
+
+    #[test]
+    fn test_boundary_must_be_an_area() {
+        assert!(Boundary::from_value(&json!("POINT(-119.28 46.21)")).is_err());
+        assert!(Boundary::from_value(&json!("SRID=2927;POLYGON((0 0, 1 0, 1 1, 0 0))")).is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! Streaming GeoJSON FeatureCollection reader and writer.
+//!
+//! `FeatureReader` scans the input for the collection's `features` array and
+//! deserializes one feature at a time; only the feature being read is held
+//! in memory. Other members of the collection (`bbox`, `crs`, `name`, ...)
+//! are skipped, and members after `features` are not read at all.
+//! `FeatureWriter` is its counterpart for output. Both are blocking; run
+//! them in `spawn_blocking` from async code.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use ::geojson::Feature;
+
+use crate::errors::{Error, Result};
+
+enum State {
+    Start,
+    Features { first: bool },
+    Done,
+}
+
+/// Iterator over the features of a GeoJSON FeatureCollection
+pub struct FeatureReader<R> {
+    reader: R,
+    state: State,
+    /// Bytes consumed so far, for error positions
+    offset: u64,
+    /// Raw JSON of the value being read
+    buffer: Vec<u8>,
+}
+
+impl FeatureReader<BufReader<File>> {
+    /// Read the features of a GeoJSON file
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> FeatureReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            state: State::Start,
+            offset: 0,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn error(&self, message: impl std::fmt::Display) -> Error {
+        Error::GeoProcessing(format!("Invalid GeoJSON at byte {}: {}", self.offset, message))
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    fn bump(&mut self) {
+        self.reader.consume(1);
+        self.offset += 1;
+    }
+
+    fn next_byte(&mut self) -> Result<u8> {
+        let byte = self.peek()?.ok_or_else(|| self.error("unexpected end of input"))?;
+        self.bump();
+        Ok(byte)
+    }
+
+    /// The next byte that is not whitespace, left unconsumed
+    fn peek_token(&mut self) -> Result<Option<u8>> {
+        while let Some(byte) = self.peek()? {
+            if !byte.is_ascii_whitespace() {
+                return Ok(Some(byte));
+            }
+            self.bump();
+        }
+        Ok(None)
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        match self.peek_token()? {
+            Some(byte) if byte == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(byte) => Err(self.error(format!("expected '{}', found '{}'", expected as char, byte as char))),
+            None => Err(self.error(format!("expected '{}', found end of input", expected as char))),
+        }
+    }
+
+    /// Copy the rest of a string whose opening quote is already in `out`
+    fn read_string_tail(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        loop {
+            let byte = self.next_byte()?;
+            out.push(byte);
+            match byte {
+                b'\\' => out.push(self.next_byte()?),
+                b'"' => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Copy the raw bytes of the next JSON value into `out`
+    fn read_value(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        match self.peek_token()? {
+            None => Err(self.error("unexpected end of input")),
+            Some(b'"') => {
+                out.push(self.next_byte()?);
+                self.read_string_tail(out)
+            }
+            Some(b'{') | Some(b'[') => {
+                let mut depth = 0usize;
+                loop {
+                    let byte = self.next_byte()?;
+                    out.push(byte);
+                    match byte {
+                        b'"' => self.read_string_tail(out)?,
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return Ok(());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Some(_) => {
+                // Number, true, false or null
+                while let Some(byte) = self.peek()? {
+                    if byte.is_ascii_whitespace() || matches!(byte, b',' | b'}' | b']') {
+                        break;
+                    }
+                    out.push(byte);
+                    self.bump();
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Read the collection's members up to the opening of `features`.
+    /// Returns false when the collection has no `features` member.
+    fn open_collection(&mut self) -> Result<bool> {
+        self.expect(b'{')?;
+        let mut buffer = std::mem::take(&mut self.buffer);
+        loop {
+            match self.peek_token()? {
+                Some(b'}') => return Ok(false),
+                Some(b',') => {
+                    self.bump();
+                    continue;
+                }
+                Some(b'"') => {}
+                _ => return Err(self.error("expected a member name")),
+            }
+
+            buffer.clear();
+            self.read_value(&mut buffer)?;
+            let name: String = serde_json::from_slice(&buffer).map_err(|e| self.error(e))?;
+            self.expect(b':')?;
+            if name == "features" {
+                self.expect(b'[')?;
+                self.buffer = buffer;
+                return Ok(true);
+            }
+
+            buffer.clear();
+            self.read_value(&mut buffer)?;
+            if name == "type" && buffer.as_slice() != b"\"FeatureCollection\"" {
+                return Err(self.error(format!("expected a FeatureCollection, found type {}", String::from_utf8_lossy(&buffer))));
+            }
+        }
+    }
+
+    fn read_feature(&mut self, first: bool) -> Result<Option<Feature>> {
+        match self.peek_token()? {
+            Some(b']') => {
+                self.bump();
+                return Ok(None);
+            }
+            Some(b',') if !first => self.bump(),
+            Some(_) if first => {}
+            _ => return Err(self.error("expected ',' or ']' in features")),
+        }
+
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        let feature = self
+            .read_value(&mut buffer)
+            .and_then(|_| serde_json::from_slice::<Feature>(&buffer).map_err(|e| self.error(e)));
+        self.buffer = buffer;
+        feature.map(Some)
+    }
+
+    fn advance(&mut self) -> Result<Option<Feature>> {
+        loop {
+            match self.state {
+                State::Start => {
+                    self.state = if self.open_collection()? { State::Features { first: true } } else { State::Done };
+                }
+                State::Features { first } => {
+                    let feature = self.read_feature(first)?;
+                    self.state = match feature {
+                        Some(_) => State::Features { first: false },
+                        None => State::Done,
+                    };
+                    return Ok(feature);
+                }
+                State::Done => return Ok(None),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for FeatureReader<R> {
+    type Item = Result<Feature>;
+
+    /// The next feature; iteration ends after the first error
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.advance() {
+            Ok(feature) => feature.map(Ok),
+            Err(e) => {
+                self.state = State::Done;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Writes a GeoJSON FeatureCollection one feature at a time
+pub struct FeatureWriter<W: Write> {
+    writer: W,
+    count: u64,
+}
+
+impl FeatureWriter<BufWriter<File>> {
+    /// Write a GeoJSON file, replacing any existing one
+    pub fn create(path: &Path) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> FeatureWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(br#"{"type":"FeatureCollection","features":["#)?;
+        Ok(Self { writer, count: 0 })
+    }
+
+    pub fn write(&mut self, feature: &Feature) -> Result<()> {
+        if self.count > 0 {
+            self.writer.write_all(b",")?;
+        }
+        self.writer.write_all(b"\n")?;
+        serde_json::to_writer(&mut self.writer, feature)
+            .map_err(|e| Error::Serialization(format!("Failed to write feature: {}", e)))?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Features written so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Close the collection and return the underlying writer, flushed
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.write_all(b"\n]}\n")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::geojson::{Geometry, Value};
+
+    fn point(id: u64, name: &str) -> Feature {
+        let mut properties = serde_json::Map::new();
+        properties.insert("id".to_string(), id.into());
+        properties.insert("name".to_string(), name.into());
+        Feature {
+            bbox: None,
+            geometry: Some(Geometry::new(Value::Point(vec![-119.2, 46.2]))),
+            id: None,
+            properties: Some(properties),
+            foreign_members: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let names = ["plain", "quote \" and ] bracket", "brace } and \\ backslash"];
+        let mut writer = FeatureWriter::new(Vec::new()).unwrap();
+        for (id, name) in names.iter().enumerate() {
+            writer.write(&point(id as u64, name)).unwrap();
+        }
+        assert_eq!(writer.count(), 3);
+        let output = writer.finish().unwrap();
+
+        let features: Vec<Feature> = FeatureReader::new(output.as_slice()).collect::<Result<_>>().unwrap();
+        assert_eq!(features, names.iter().enumerate().map(|(id, name)| point(id as u64, name)).collect::<Vec<_>>());
+
+        let empty = FeatureWriter::new(Vec::new()).unwrap().finish().unwrap();
+        assert_eq!(FeatureReader::new(empty.as_slice()).count(), 0);
+    }
+
+    #[test]
+    fn test_reader_skips_other_members() {
+        let input = r#"{
+            "name": "parcels", "crs": {"type": "name", "properties": {"name": "EPSG:4326"}},
+            "type": "FeatureCollection", "bbox": [-120, 45, -118, 47],
+            "features": [
+                {"type": "Feature", "geometry": null, "properties": {"id": 1}},
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [-119, 46]}, "properties": null}
+            ],
+            "totalFeatures": 2
+        }"#;
+        let features: Vec<Feature> = FeatureReader::new(input.as_bytes()).collect::<Result<_>>().unwrap();
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[0].property("id"), Some(&serde_json::json!(1)));
+
+        assert_eq!(FeatureReader::new(r#"{"type": "FeatureCollection"}"#.as_bytes()).count(), 0);
+    }
+
+    #[test]
+    fn test_reader_errors_end_iteration() {
+        let mut reader = FeatureReader::new(r#"{"type": "Feature", "features": []}"#.as_bytes());
+        assert!(matches!(reader.next(), Some(Err(Error::GeoProcessing(_)))));
+        assert!(reader.next().is_none());
+
+        let truncated = r#"{"features": [{"type": "Feature", "geometry": null, "properties": {}}, {"type": "Fea"#;
+        let results: Vec<_> = FeatureReader::new(truncated.as_bytes()).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok() && results[1].is_err());
+    }
+}
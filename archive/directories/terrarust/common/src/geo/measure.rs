@@ -0,0 +1,312 @@
+//! Bounding boxes, centroids, and area and length measurement.
+//!
+//! How a geometry is measured depends on its CRS. Coordinates in degrees
+//! (WGS 84, NAD83) are measured geodesically on the ellipsoid. Projected
+//! coordinates (State Plane, UTM) are measured in the plane and converted
+//! from the CRS unit, which for the Washington State Plane zones is the US
+//! survey foot. Web Mercator is not measured in the plane, where areas are
+//! badly inflated at our latitudes; it is unprojected and measured
+//! geodesically.
+
+use geo::{
+    Area, BoundingRect, Centroid, Coord, EuclideanLength, GeodesicArea, GeodesicLength, Geometry, HausdorffDistance,
+    MapCoords, Point,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{Error, Result};
+
+/// Meters in a US survey foot
+const US_SURVEY_FOOT: f64 = 1200.0 / 3937.0;
+
+/// Radius of the Web Mercator sphere, in meters
+const WEB_MERCATOR_RADIUS: f64 = 6_378_137.0;
+
+/// Meters per degree of latitude, and of longitude at the equator
+const METERS_PER_DEGREE_LAT: f64 = 110_574.0;
+const METERS_PER_DEGREE_LON: f64 = 111_320.0;
+
+/// Geographic CRSs (degrees)
+const GEOGRAPHIC: &[u32] = &[4326, 4269, 4152, 6318];
+
+/// Projected CRSs in meters: UTM 10N/11N (NAD83, WGS 84) and Washington
+/// State Plane North/South (NAD83, HARN, 2011)
+const PROJECTED_METERS: &[u32] = &[26910, 26911, 32610, 32611, 32148, 32149, 2855, 2856, 6596, 6598];
+
+/// Washington State Plane North/South in US survey feet (NAD83, HARN, 2011)
+const PROJECTED_US_FEET: &[u32] = &[2285, 2286, 2926, 2927, 6597, 6599];
+
+const WEB_MERCATOR: &[u32] = &[3857, 900913];
+
+/// How lengths and areas are computed for a CRS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Measurement {
+    /// On the WGS 84 ellipsoid, for coordinates in degrees
+    Geodesic,
+    /// In the plane, for projected coordinates in units of this many meters
+    Planar { meters_per_unit: f64 },
+    /// Web Mercator meters, unprojected and measured geodesically
+    WebMercator,
+}
+
+impl Measurement {
+    /// Measurement for a CRS name such as `EPSG:2927`,
+    /// `urn:ogc:def:crs:EPSG::4326` or `CRS84`
+    pub fn for_crs(crs: &str) -> Result<Self> {
+        let crs = crs.trim();
+        if crs.to_uppercase().ends_with("CRS84") {
+            return Ok(Measurement::Geodesic);
+        }
+        let code = crs
+            .rsplit(':')
+            .next()
+            .and_then(|code| code.parse::<u32>().ok())
+            .ok_or_else(|| Error::GeoProcessing(format!("Unrecognized CRS: {}", crs)))?;
+
+        if GEOGRAPHIC.contains(&code) {
+            Ok(Measurement::Geodesic)
+        } else if PROJECTED_METERS.contains(&code) {
+            Ok(Measurement::Planar { meters_per_unit: 1.0 })
+        } else if PROJECTED_US_FEET.contains(&code) {
+            Ok(Measurement::Planar { meters_per_unit: US_SURVEY_FOOT })
+        } else if WEB_MERCATOR.contains(&code) {
+            Ok(Measurement::WebMercator)
+        } else {
+            Err(Error::GeoProcessing(format!(
+                "No measurement rule for EPSG:{}; reproject to EPSG:4326 or a State Plane zone",
+                code
+            )))
+        }
+    }
+
+    /// Measurement for an optional CRS; GeoJSON without one is WGS 84
+    pub fn for_optional_crs(crs: Option<&str>) -> Result<Self> {
+        crs.map_or(Ok(Measurement::Geodesic), Self::for_crs)
+    }
+
+    /// Area in square meters
+    pub fn area_m2(&self, geometry: &Geometry<f64>) -> f64 {
+        match self {
+            Measurement::Geodesic => geodesic_area(geometry),
+            Measurement::Planar { meters_per_unit } => geometry.unsigned_area() * meters_per_unit * meters_per_unit,
+            Measurement::WebMercator => geodesic_area(&unproject_web_mercator(geometry)),
+        }
+    }
+
+    /// Area in square kilometers, as used for area-of-interest limits
+    pub fn area_km2(&self, geometry: &Geometry<f64>) -> f64 {
+        self.area_m2(geometry) / 1_000_000.0
+    }
+
+    /// Length of the linear parts of a geometry in meters; polygons and
+    /// points have no length
+    pub fn length_m(&self, geometry: &Geometry<f64>) -> f64 {
+        match self {
+            Measurement::Geodesic => geodesic_length(geometry),
+            Measurement::Planar { meters_per_unit } => planar_length(geometry) * meters_per_unit,
+            Measurement::WebMercator => geodesic_length(&unproject_web_mercator(geometry)),
+        }
+    }
+
+    /// Hausdorff distance between two geometries' vertices in meters: how
+    /// far the farthest vertex of either is from the other. Geographic
+    /// coordinates are projected onto a plane at the shapes' latitude
+    /// first, which is accurate to well under a percent at parcel scale.
+    pub fn hausdorff_m(&self, a: &Geometry<f64>, b: &Geometry<f64>) -> f64 {
+        match self {
+            Measurement::Planar { meters_per_unit } => a.hausdorff_distance(b) * meters_per_unit,
+            Measurement::Geodesic => local_hausdorff(a, b),
+            Measurement::WebMercator => local_hausdorff(&unproject_web_mercator(a), &unproject_web_mercator(b)),
+        }
+    }
+}
+
+fn local_hausdorff(a: &Geometry<f64>, b: &Geometry<f64>) -> f64 {
+    let Some(extent) = Bbox::of_all([a, b]) else {
+        return 0.0;
+    };
+    let x_scale = METERS_PER_DEGREE_LON * ((extent.min_y + extent.max_y) / 2.0).to_radians().cos();
+    let to_meters = |geometry: &Geometry<f64>| {
+        geometry.map_coords(|Coord { x, y }| Coord { x: x * x_scale, y: y * METERS_PER_DEGREE_LAT })
+    };
+    to_meters(a).hausdorff_distance(&to_meters(b))
+}
+
+fn geodesic_area(geometry: &Geometry<f64>) -> f64 {
+    match geometry {
+        Geometry::Polygon(polygon) => polygon.geodesic_area_unsigned(),
+        Geometry::MultiPolygon(polygons) => polygons.geodesic_area_unsigned(),
+        Geometry::Rect(rect) => rect.to_polygon().geodesic_area_unsigned(),
+        Geometry::Triangle(triangle) => triangle.to_polygon().geodesic_area_unsigned(),
+        Geometry::GeometryCollection(collection) => collection.iter().map(geodesic_area).sum(),
+        _ => 0.0,
+    }
+}
+
+fn geodesic_length(geometry: &Geometry<f64>) -> f64 {
+    match geometry {
+        Geometry::Line(line) => line.geodesic_length(),
+        Geometry::LineString(line) => line.geodesic_length(),
+        Geometry::MultiLineString(lines) => lines.geodesic_length(),
+        Geometry::GeometryCollection(collection) => collection.iter().map(geodesic_length).sum(),
+        _ => 0.0,
+    }
+}
+
+fn planar_length(geometry: &Geometry<f64>) -> f64 {
+    match geometry {
+        Geometry::Line(line) => line.euclidean_length(),
+        Geometry::LineString(line) => line.euclidean_length(),
+        Geometry::MultiLineString(lines) => lines.euclidean_length(),
+        Geometry::GeometryCollection(collection) => collection.iter().map(planar_length).sum(),
+        _ => 0.0,
+    }
+}
+
+/// Web Mercator meters to WGS 84 degrees
+fn unproject_web_mercator(geometry: &Geometry<f64>) -> Geometry<f64> {
+    geometry.map_coords(|Coord { x, y }| Coord {
+        x: (x / WEB_MERCATOR_RADIUS).to_degrees(),
+        y: (2.0 * (y / WEB_MERCATOR_RADIUS).exp().atan() - std::f64::consts::FRAC_PI_2).to_degrees(),
+    })
+}
+
+/// An axis-aligned bounding box in the geometry's own coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "[f64; 4]", from = "[f64; 4]")]
+pub struct Bbox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl Bbox {
+    /// Bounding box of a geometry; `None` when it is empty
+    pub fn of(geometry: &Geometry<f64>) -> Option<Self> {
+        geometry.bounding_rect().map(|rect| Self {
+            min_x: rect.min().x,
+            min_y: rect.min().y,
+            max_x: rect.max().x,
+            max_y: rect.max().y,
+        })
+    }
+
+    /// Bounding box of all the geometries; `None` when every one is empty
+    pub fn of_all<'a>(geometries: impl IntoIterator<Item = &'a Geometry<f64>>) -> Option<Self> {
+        geometries
+            .into_iter()
+            .filter_map(Self::of)
+            .reduce(|a, b| a.union(&b))
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    pub fn contains(&self, point: &Point<f64>) -> bool {
+        (self.min_x..=self.max_x).contains(&point.x()) && (self.min_y..=self.max_y).contains(&point.y())
+    }
+}
+
+/// GeoJSON `bbox` order: west, south, east, north
+impl From<Bbox> for [f64; 4] {
+    fn from(bbox: Bbox) -> Self {
+        [bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y]
+    }
+}
+
+impl From<[f64; 4]> for Bbox {
+    fn from([min_x, min_y, max_x, max_y]: [f64; 4]) -> Self {
+        Self { min_x, min_y, max_x, max_y }
+    }
+}
+
+/// Centroid for placing a label; `None` for an empty geometry
+pub fn centroid(geometry: &Geometry<f64>) -> Option<Point<f64>> {
+    geometry.centroid()
+}
+
+/// A GeoJSON geometry as a `geo` geometry for measurement
+pub fn from_geojson(geometry: ::geojson::Geometry) -> Result<Geometry<f64>> {
+    Geometry::<f64>::try_from(geometry).map_err(|e| Error::GeoProcessing(format!("Unsupported geometry: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::{polygon, Rect};
+
+    fn square(min: f64, size: f64) -> Geometry<f64> {
+        Geometry::Rect(Rect::new(Coord { x: min, y: min }, Coord { x: min + size, y: min + size }))
+    }
+
+    #[test]
+    fn test_measurement_for_crs() {
+        assert_eq!(Measurement::for_crs("EPSG:4326").unwrap(), Measurement::Geodesic);
+        assert_eq!(Measurement::for_crs("urn:ogc:def:crs:OGC:1.3:CRS84").unwrap(), Measurement::Geodesic);
+        assert_eq!(Measurement::for_crs("urn:ogc:def:crs:EPSG::3857").unwrap(), Measurement::WebMercator);
+        assert_eq!(
+            Measurement::for_crs("EPSG:2927").unwrap(),
+            Measurement::Planar { meters_per_unit: US_SURVEY_FOOT }
+        );
+        assert!(Measurement::for_crs("EPSG:27700").is_err());
+        assert_eq!(Measurement::for_optional_crs(None).unwrap(), Measurement::Geodesic);
+    }
+
+    #[test]
+    fn test_area() {
+        // One degree square at the equator is about 12,308 km²
+        let degree = square(0.0, 1.0);
+        assert!((Measurement::Geodesic.area_km2(&degree) - 12_308.8).abs() < 1.0);
+
+        // A 1000 ft square section in State Plane feet
+        let section = square(0.0, 1000.0);
+        let acres = Measurement::for_crs("EPSG:2927").unwrap().area_m2(&section) / 4046.873;
+        assert!((acres - 22.957).abs() < 0.01);
+
+        // The same degree square in Web Mercator meters measures the same
+        let mercator = Geometry::Rect(Rect::new(
+            Coord { x: 0.0, y: 0.0 },
+            Coord { x: 111_319.49, y: 111_325.14 },
+        ));
+        assert!((Measurement::WebMercator.area_km2(&mercator) - 12_308.8).abs() < 5.0);
+    }
+
+    #[test]
+    fn test_hausdorff() {
+        // A 10 ft boundary shift in State Plane feet
+        let moved = square(10.0, 1000.0);
+        let feet = Measurement::for_crs("EPSG:2927").unwrap().hausdorff_m(&square(0.0, 1000.0), &moved);
+        assert!((feet - 10.0 * 2f64.sqrt() * US_SURVEY_FOOT).abs() < 1e-6);
+
+        // 0.0001° of latitude is about 11 m
+        let parcel = square(46.0, 0.001);
+        let shifted = parcel.map_coords(|Coord { x, y }| Coord { x, y: y + 0.0001 });
+        assert!((Measurement::Geodesic.hausdorff_m(&parcel, &shifted) - 11.06).abs() < 0.1);
+        assert_eq!(Measurement::Geodesic.hausdorff_m(&parcel, &parcel), 0.0);
+    }
+
+    #[test]
+    fn test_bbox_and_centroid() {
+        let parcel: Geometry<f64> = polygon![
+            (x: -119.3, y: 46.2), (x: -119.1, y: 46.2), (x: -119.1, y: 46.3), (x: -119.3, y: 46.3),
+        ]
+        .into();
+        let bbox = Bbox::of(&parcel).unwrap();
+        assert_eq!(<[f64; 4]>::from(bbox), [-119.3, 46.2, -119.1, 46.3]);
+
+        let combined = Bbox::of_all([&parcel, &square(0.0, 1.0)]).unwrap();
+        assert_eq!(<[f64; 4]>::from(combined), [-119.3, 0.0, 1.0, 46.3]);
+        assert_eq!(serde_json::to_value(combined).unwrap(), serde_json::json!([-119.3, 0.0, 1.0, 46.3]));
+
+        let center = centroid(&parcel).unwrap();
+        assert!((center.x() + 119.2).abs() < 1e-9 && (center.y() - 46.25).abs() < 1e-9);
+        assert!(bbox.contains(&center));
+    }
+}
@@ -0,0 +1,19 @@
+//! Geospatial utilities shared by the export pipeline and geo-aware
+//! connectors.
+//!
+//! `geojson` reads and writes GeoJSON FeatureCollections one feature at a
+//! time, so files with millions of features are processed in constant
+//! memory. `measure` computes bounding boxes, centroids, and areas and
+//! lengths measured the way the geometry's CRS calls for. `parse` accepts
+//! geometries as GeoJSON, WKT or hex WKB and converts them to GeoJSON.
+//! `boundary` checks that geometries fall within a county.
+
+pub mod boundary;
+pub mod geojson;
+pub mod measure;
+pub mod parse;
+
+pub use boundary::Boundary;
+pub use self::geojson::{FeatureReader, FeatureWriter};
+pub use measure::{Bbox, Measurement};
+pub use parse::{normalize_geometry, parse_geometry, GeometryFormat, ParsedGeometry};
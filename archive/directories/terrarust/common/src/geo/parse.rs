@@ -0,0 +1,399 @@
+//! Geometry input in GeoJSON, WKT or WKB.
+//!
+//! Clients send areas of interest as GeoJSON, but database connectors hand
+//! us whatever the source stores: `row_to_json` renders PostGIS columns as
+//! hex EWKB, and county GIS exports often carry WKT text. `parse_geometry`
+//! detects the format of a JSON value and converts it to a `geo` geometry;
+//! `normalize_geometry` goes one step further, to the GeoJSON geometry
+//! object stored and exported everywhere else.
+//!
+//! WKT may carry an EWKT `SRID=n;` prefix. WKB is accepted as hex, with or
+//! without PostgreSQL's `\x` prefix, in either byte order, in the ISO and
+//! PostGIS (EWKB) flavours. Z and M values are read and dropped.
+
+use std::str::FromStr;
+
+use geo::{Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon};
+use serde_json::Value;
+
+use crate::errors::{Error, Result};
+
+const WKT_KEYWORDS: &[&str] = &[
+    "POINT",
+    "LINESTRING",
+    "POLYGON",
+    "MULTIPOINT",
+    "MULTILINESTRING",
+    "MULTIPOLYGON",
+    "GEOMETRYCOLLECTION",
+];
+
+/// EWKB flags on the geometry type
+const EWKB_Z: u32 = 0x8000_0000;
+const EWKB_M: u32 = 0x4000_0000;
+const EWKB_SRID: u32 = 0x2000_0000;
+
+/// Deepest collection nesting read from WKB
+const MAX_DEPTH: usize = 32;
+
+/// The encoding a geometry arrived in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryFormat {
+    GeoJson,
+    Wkt,
+    Wkb,
+}
+
+/// A geometry converted from its input format
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedGeometry {
+    pub geometry: Geometry<f64>,
+    /// SRID from an EWKT prefix or EWKB header, when the input had one
+    pub srid: Option<u32>,
+    pub format: GeometryFormat,
+}
+
+impl ParsedGeometry {
+    /// The geometry as a GeoJSON geometry object
+    pub fn to_geojson(&self) -> Value {
+        let geometry = ::geojson::Geometry::new(::geojson::Value::from(&self.geometry));
+        serde_json::to_value(geometry).unwrap_or(Value::Null)
+    }
+}
+
+/// The format of a geometry string, when it looks like one
+pub fn detect_format(text: &str) -> Option<GeometryFormat> {
+    let text = text.trim();
+    if text.starts_with('{') {
+        return Some(GeometryFormat::GeoJson);
+    }
+    let (_, wkt) = split_srid(text);
+    let upper = wkt.trim_start().to_ascii_uppercase();
+    if WKT_KEYWORDS.iter().any(|keyword| upper.strip_prefix(keyword).is_some_and(is_wkt_body)) {
+        return Some(GeometryFormat::Wkt);
+    }
+    let hex = strip_hex_prefix(text);
+    if hex.len() >= 10
+        && hex.len() % 2 == 0
+        && (hex.starts_with("00") || hex.starts_with("01"))
+        && hex.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        return Some(GeometryFormat::Wkb);
+    }
+    None
+}
+
+/// What follows the type keyword: optional dimensions, then coordinates
+/// or EMPTY
+fn is_wkt_body(rest: &str) -> bool {
+    let rest = rest.trim_start();
+    let rest = ["ZM", "Z", "M"]
+        .iter()
+        .find_map(|dims| rest.strip_prefix(dims))
+        .map_or(rest, str::trim_start);
+    rest.starts_with('(') || rest.trim_end() == "EMPTY"
+}
+
+/// Parse a geometry given as a GeoJSON object or a GeoJSON, WKT or hex WKB
+/// string
+pub fn parse_geometry(value: &Value) -> Result<ParsedGeometry> {
+    match value {
+        Value::Object(_) => parse_geojson(value.clone()),
+        Value::String(text) => match detect_format(text) {
+            Some(GeometryFormat::GeoJson) => {
+                let value = serde_json::from_str(text)
+                    .map_err(|e| Error::GeoProcessing(format!("Invalid GeoJSON geometry: {}", e)))?;
+                parse_geojson(value)
+            }
+            Some(GeometryFormat::Wkt) => parse_wkt(text),
+            Some(GeometryFormat::Wkb) => parse_wkb(text),
+            None => Err(Error::GeoProcessing(
+                "Geometry is not GeoJSON, WKT or hex-encoded WKB".to_string(),
+            )),
+        },
+        _ => Err(Error::GeoProcessing(
+            "Geometry must be a GeoJSON object or a WKT or WKB string".to_string(),
+        )),
+    }
+}
+
+/// Convert a geometry in any accepted format to a GeoJSON geometry object
+pub fn normalize_geometry(value: &Value) -> Result<Value> {
+    Ok(parse_geometry(value)?.to_geojson())
+}
+
+fn parse_geojson(value: Value) -> Result<ParsedGeometry> {
+    let geometry = ::geojson::Geometry::from_json_value(value)
+        .map_err(|e| Error::GeoProcessing(format!("Invalid GeoJSON geometry: {}", e)))?;
+    Ok(ParsedGeometry {
+        geometry: super::measure::from_geojson(geometry)?,
+        srid: None,
+        format: GeometryFormat::GeoJson,
+    })
+}
+
+/// `SRID=2927;POINT(...)` into the SRID and the WKT
+fn split_srid(text: &str) -> (Option<&str>, &str) {
+    match text.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("SRID=") => match text[5..].split_once(';') {
+            Some((srid, wkt)) => (Some(srid.trim()), wkt),
+            None => (None, text),
+        },
+        _ => (None, text),
+    }
+}
+
+fn parse_wkt(text: &str) -> Result<ParsedGeometry> {
+    let (srid, wkt) = split_srid(text.trim());
+    let srid = srid
+        .map(|srid| srid.parse::<u32>().map_err(|_| Error::GeoProcessing(format!("Invalid SRID: {}", srid))))
+        .transpose()?;
+    let parsed = wkt::Wkt::<f64>::from_str(wkt.trim()).map_err(|e| Error::GeoProcessing(format!("Invalid WKT: {}", e)))?;
+    let geometry =
+        Geometry::try_from(parsed).map_err(|e| Error::GeoProcessing(format!("Unsupported WKT geometry: {}", e)))?;
+    Ok(ParsedGeometry { geometry, srid, format: GeometryFormat::Wkt })
+}
+
+fn strip_hex_prefix(text: &str) -> &str {
+    text.strip_prefix("\\x")
+        .or_else(|| text.strip_prefix("0x"))
+        .unwrap_or(text)
+}
+
+fn parse_wkb(text: &str) -> Result<ParsedGeometry> {
+    let hex = strip_hex_prefix(text.trim()).as_bytes();
+    let bytes = hex
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| Error::GeoProcessing("Invalid hex in WKB".to_string()))
+        })
+        .collect::<Result<Vec<u8>>>()?;
+
+    let mut reader = WkbReader { bytes: &bytes, pos: 0, srid: None };
+    let geometry = reader.geometry(0)?;
+    if reader.pos != bytes.len() {
+        return Err(reader.error("trailing bytes after the geometry"));
+    }
+    Ok(ParsedGeometry { geometry, srid: reader.srid, format: GeometryFormat::Wkb })
+}
+
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// SRID of the outermost geometry
+    srid: Option<u32>,
+}
+
+/// Byte order and coordinate layout of one WKB geometry
+#[derive(Clone, Copy)]
+struct WkbHeader {
+    little_endian: bool,
+    kind: u32,
+    dimensions: usize,
+}
+
+impl WkbReader<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error::GeoProcessing(format!("Invalid WKB at byte {}: {}", self.pos, message))
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        self.pos += N;
+        Ok(bytes.try_into().expect("slice has N bytes"))
+    }
+
+    fn u32(&mut self, little_endian: bool) -> Result<u32> {
+        let bytes = self.take::<4>()?;
+        Ok(if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    fn f64(&mut self, little_endian: bool) -> Result<f64> {
+        let bytes = self.take::<8>()?;
+        Ok(if little_endian { f64::from_le_bytes(bytes) } else { f64::from_be_bytes(bytes) })
+    }
+
+    /// An element count, checked against the bytes left so a corrupt count
+    /// cannot trigger a huge allocation
+    fn count(&mut self, little_endian: bool, min_element_size: usize) -> Result<usize> {
+        let count = self.u32(little_endian)? as usize;
+        if count.saturating_mul(min_element_size) > self.bytes.len() - self.pos {
+            return Err(self.error("element count exceeds the input"));
+        }
+        Ok(count)
+    }
+
+    fn header(&mut self) -> Result<WkbHeader> {
+        let little_endian = match self.take::<1>()?[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(self.error("invalid byte order")),
+        };
+        let raw = self.u32(little_endian)?;
+        if raw & EWKB_SRID != 0 {
+            let srid = self.u32(little_endian)?;
+            self.srid.get_or_insert(srid);
+        }
+
+        // ISO puts the dimensions in the thousands (1001 is POINT Z); EWKB
+        // uses the high flag bits instead
+        let code = raw & 0x0FFF_FFFF;
+        let (has_z, has_m) = match code / 1000 {
+            0 => (raw & EWKB_Z != 0, raw & EWKB_M != 0),
+            1 => (true, false),
+            2 => (false, true),
+            3 => (true, true),
+            _ => return Err(self.error("unknown geometry type")),
+        };
+        Ok(WkbHeader {
+            little_endian,
+            kind: code % 1000,
+            dimensions: 2 + has_z as usize + has_m as usize,
+        })
+    }
+
+    fn coord(&mut self, header: WkbHeader) -> Result<Coord<f64>> {
+        let x = self.f64(header.little_endian)?;
+        let y = self.f64(header.little_endian)?;
+        for _ in 2..header.dimensions {
+            self.f64(header.little_endian)?;
+        }
+        Ok(Coord { x, y })
+    }
+
+    fn line_string(&mut self, header: WkbHeader) -> Result<LineString<f64>> {
+        let count = self.count(header.little_endian, header.dimensions * 8)?;
+        (0..count).map(|_| self.coord(header)).collect::<Result<Vec<_>>>().map(LineString::new)
+    }
+
+    fn polygon(&mut self, header: WkbHeader) -> Result<Polygon<f64>> {
+        let count = self.count(header.little_endian, 4)?;
+        let mut rings = (0..count).map(|_| self.line_string(header)).collect::<Result<Vec<_>>>()?;
+        if rings.is_empty() {
+            return Ok(Polygon::new(LineString::new(Vec::new()), Vec::new()));
+        }
+        let exterior = rings.remove(0);
+        Ok(Polygon::new(exterior, rings))
+    }
+
+    /// Members of a multi-geometry or collection
+    fn members(&mut self, header: WkbHeader, depth: usize) -> Result<Vec<Geometry<f64>>> {
+        let count = self.count(header.little_endian, 5)?;
+        (0..count).map(|_| self.geometry(depth + 1)).collect()
+    }
+
+    fn geometry(&mut self, depth: usize) -> Result<Geometry<f64>> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("geometry collections nested too deeply"));
+        }
+        let header = self.header()?;
+        let geometry = match header.kind {
+            1 => {
+                let coord = self.coord(header)?;
+                if coord.x.is_nan() && coord.y.is_nan() {
+                    // POINT EMPTY; geo has no empty point
+                    Geometry::MultiPoint(MultiPoint::new(Vec::new()))
+                } else {
+                    Geometry::Point(Point::from(coord))
+                }
+            }
+            2 => Geometry::LineString(self.line_string(header)?),
+            3 => Geometry::Polygon(self.polygon(header)?),
+            4 => Geometry::MultiPoint(MultiPoint::new(
+                self.members(header, depth)?
+                    .into_iter()
+                    .filter_map(|member| match member {
+                        Geometry::Point(point) => Some(Ok(point)),
+                        Geometry::MultiPoint(empty) if empty.0.is_empty() => None,
+                        _ => Some(Err(self.error("MultiPoint member is not a Point"))),
+                    })
+                    .collect::<Result<_>>()?,
+            )),
+            5 => Geometry::MultiLineString(MultiLineString::new(
+                self.members(header, depth)?
+                    .into_iter()
+                    .map(|member| match member {
+                        Geometry::LineString(line) => Ok(line),
+                        _ => Err(self.error("MultiLineString member is not a LineString")),
+                    })
+                    .collect::<Result<_>>()?,
+            )),
+            6 => Geometry::MultiPolygon(MultiPolygon::new(
+                self.members(header, depth)?
+                    .into_iter()
+                    .map(|member| match member {
+                        Geometry::Polygon(polygon) => Ok(polygon),
+                        _ => Err(self.error("MultiPolygon member is not a Polygon")),
+                    })
+                    .collect::<Result<_>>()?,
+            )),
+            7 => Geometry::GeometryCollection(GeometryCollection::new_from(self.members(header, depth)?)),
+            _ => return Err(self.error("unsupported geometry type")),
+        };
+        Ok(geometry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::polygon;
+    use serde_json::json;
+
+    /// POINT(-119.2 46.2) as little-endian WKB
+    const POINT_WKB: &str = "0101000000CDCCCCCCCCCC5DC09A99999999194740";
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format(r#"{"type": "Point", "coordinates": [0, 0]}"#), Some(GeometryFormat::GeoJson));
+        assert_eq!(detect_format("POINT (-119.2 46.2)"), Some(GeometryFormat::Wkt));
+        assert_eq!(detect_format("SRID=2927;multipolygon(((0 0,1 0,1 1,0 0)))"), Some(GeometryFormat::Wkt));
+        assert_eq!(detect_format("POINT Z (1 2 3)"), Some(GeometryFormat::Wkt));
+        assert_eq!(detect_format(POINT_WKB), Some(GeometryFormat::Wkb));
+        assert_eq!(detect_format(&format!("\\x{}", POINT_WKB)), Some(GeometryFormat::Wkb));
+        assert_eq!(detect_format("Pointe Rd"), None);
+        assert_eq!(detect_format("1-2345-678"), None);
+    }
+
+    #[test]
+    fn test_wkt_and_wkb_agree() {
+        let from_wkt = parse_geometry(&json!("POINT(-119.2 46.2)")).unwrap();
+        let from_wkb = parse_geometry(&json!(POINT_WKB)).unwrap();
+        assert_eq!(from_wkt.geometry, Geometry::Point(Point::new(-119.2, 46.2)));
+        assert_eq!(from_wkb.geometry, from_wkt.geometry);
+        assert_eq!(from_wkb.format, GeometryFormat::Wkb);
+        assert_eq!(
+            normalize_geometry(&json!(POINT_WKB)).unwrap(),
+            json!({"type": "Point", "coordinates": [-119.2, 46.2]})
+        );
+    }
+
+    #[test]
+    fn test_ewkt_and_ewkb_srid() {
+        let parsed = parse_geometry(&json!("SRID=2927;POLYGON((0 0, 10 0, 10 10, 0 0))")).unwrap();
+        assert_eq!(parsed.srid, Some(2927));
+        assert_eq!(parsed.geometry, Geometry::Polygon(polygon![(x: 0.0, y: 0.0), (x: 10.0, y: 0.0), (x: 10.0, y: 10.0)]));
+
+        // Big-endian EWKB POINT Z with SRID 4326, as PostGIS writes it
+        let ewkb = "00A0000001000010E6C05DCCCCCCCCCCCD404719999999999A4059000000000000";
+        let parsed = parse_geometry(&json!(ewkb)).unwrap();
+        assert_eq!(parsed.srid, Some(4326));
+        assert_eq!(parsed.geometry, Geometry::Point(Point::new(-119.2, 46.2)));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(parse_geometry(&json!("POINT(1)")).is_err());
+        assert!(parse_geometry(&json!(&POINT_WKB[..30])).is_err());
+        assert!(parse_geometry(&json!(format!("{}00", POINT_WKB))).is_err());
+        // A MultiPolygon claiming four billion members
+        assert!(parse_geometry(&json!("0106000000FFFFFFFF")).is_err());
+        assert!(parse_geometry(&json!(42)).is_err());
+    }
+}
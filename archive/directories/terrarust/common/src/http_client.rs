@@ -0,0 +1,309 @@
+//! HTTP clients for calls between services and to outside APIs.
+//!
+//! A `reqwest::Client` owns a connection pool, so one built per request
+//! opens a new TCP (and TLS) connection every time. Services share a client
+//! per purpose instead, built here from `HTTP_CLIENT_*` settings: keep-alive
+//! pools with bounded idle connections, connect and request timeouts, HTTP/2
+//! where the server offers it, and a resolver that caches DNS answers.
+//!
+//! Reuse is visible in Prometheus: `http_client_requests_total` counts
+//! requests and `http_client_connections_total` counts the connections the
+//! pool had to open for them, both by client name. The pool resolves a host
+//! only when it has no connection to reuse, so resolutions stand in for new
+//! connections; URLs with an IP address skip the resolver and are not counted.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{IntoUrl, Method, RequestBuilder};
+
+use crate::errors::{Error, Result};
+
+lazy_static! {
+    /// Requests sent, by client
+    pub static ref HTTP_CLIENT_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "http_client_requests_total",
+        "Outgoing HTTP requests",
+        &["client"]
+    ).expect("Failed to register http_client_requests_total");
+
+    /// Connections opened, by client
+    pub static ref HTTP_CLIENT_CONNECTIONS: IntCounterVec = register_int_counter_vec!(
+        "http_client_connections_total",
+        "Outgoing HTTP connections opened because none could be reused",
+        &["client"]
+    ).expect("Failed to register http_client_connections_total");
+
+    /// Host lookups answered from the DNS cache, by client
+    pub static ref HTTP_CLIENT_DNS_CACHE_HITS: IntCounterVec = register_int_counter_vec!(
+        "http_client_dns_cache_hits_total",
+        "Host lookups answered from the HTTP client DNS cache",
+        &["client"]
+    ).expect("Failed to register http_client_dns_cache_hits_total");
+
+    static ref SHARED: HttpClient = HttpClient::new("shared", &HttpClientSettings::from_env().unwrap_or_else(|e| {
+        log::warn!("{}; using default HTTP client settings", e);
+        HttpClientSettings::default()
+    }))
+    .expect("Failed to build the shared HTTP client");
+}
+
+/// When to speak HTTP/2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Http2 {
+    /// HTTP/2 when a TLS server offers it through ALPN, HTTP/1.1 otherwise
+    Negotiate,
+    /// HTTP/2 without negotiation, for plain-text upstreams known to speak it
+    PriorKnowledge,
+    /// HTTP/1.1 only
+    Off,
+}
+
+impl Http2 {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "negotiate" | "auto" => Some(Self::Negotiate),
+            "prior_knowledge" | "prior-knowledge" => Some(Self::PriorKnowledge),
+            "off" | "false" => Some(Self::Off),
+            _ => None,
+        }
+    }
+}
+
+/// Pool, timeout and protocol settings of a client
+#[derive(Debug, Clone)]
+pub struct HttpClientSettings {
+    /// Idle connections kept per host
+    pub pool_max_idle_per_host: usize,
+    /// Idle connections are closed after this long
+    pub pool_idle_timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Whole-request limit, from connecting to the end of the body. None by
+    /// default, as proxied uploads and downloads can take as long as they need.
+    pub timeout: Option<Duration>,
+    /// TCP keep-alive probes, and HTTP/2 pings, on idle connections
+    pub keepalive: Duration,
+    pub http2: Http2,
+    /// How long resolved addresses are reused; zero turns the cache off
+    pub dns_cache_ttl: Duration,
+}
+
+impl Default for HttpClientSettings {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: 32,
+            pool_idle_timeout: Duration::from_secs(90),
+            connect_timeout: Duration::from_secs(5),
+            timeout: None,
+            keepalive: Duration::from_secs(30),
+            http2: Http2::Negotiate,
+            dns_cache_ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+impl HttpClientSettings {
+    /// Read `HTTP_CLIENT_POOL_MAX_IDLE`, `HTTP_CLIENT_IDLE_TIMEOUT_SECONDS`,
+    /// `HTTP_CLIENT_CONNECT_TIMEOUT_SECONDS`, `HTTP_CLIENT_TIMEOUT_SECONDS`,
+    /// `HTTP_CLIENT_KEEPALIVE_SECONDS`, `HTTP_CLIENT_HTTP2` and
+    /// `HTTP_CLIENT_DNS_CACHE_SECONDS`
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let number = |name: &str, allow_zero: bool| -> Result<Option<u64>> {
+            match std::env::var(name) {
+                Ok(value) => value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|value| allow_zero || *value > 0)
+                    .map(Some)
+                    .ok_or_else(|| Error::Config(format!("Invalid {} value", name))),
+                Err(_) => Ok(None),
+            }
+        };
+        let seconds = |name: &str, default: Duration| -> Result<Duration> {
+            Ok(number(name, false)?.map_or(default, Duration::from_secs))
+        };
+        let http2 = match std::env::var("HTTP_CLIENT_HTTP2") {
+            Ok(value) => Http2::parse(&value)
+                .ok_or_else(|| Error::Config("Invalid HTTP_CLIENT_HTTP2 value (use negotiate, prior_knowledge or off)".to_string()))?,
+            Err(_) => defaults.http2,
+        };
+
+        Ok(Self {
+            pool_max_idle_per_host: number("HTTP_CLIENT_POOL_MAX_IDLE", true)?
+                .map_or(defaults.pool_max_idle_per_host, |max| max as usize),
+            pool_idle_timeout: seconds("HTTP_CLIENT_IDLE_TIMEOUT_SECONDS", defaults.pool_idle_timeout)?,
+            connect_timeout: seconds("HTTP_CLIENT_CONNECT_TIMEOUT_SECONDS", defaults.connect_timeout)?,
+            timeout: number("HTTP_CLIENT_TIMEOUT_SECONDS", false)?.map(Duration::from_secs).or(defaults.timeout),
+            keepalive: seconds("HTTP_CLIENT_KEEPALIVE_SECONDS", defaults.keepalive)?,
+            http2,
+            dns_cache_ttl: number("HTTP_CLIENT_DNS_CACHE_SECONDS", true)?
+                .map_or(defaults.dns_cache_ttl, Duration::from_secs),
+        })
+    }
+
+    /// These settings with another whole-request limit, for clients whose
+    /// calls are known to be slower or faster than most
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// A pooled client that counts its requests. Cloning shares the pool.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    name: &'static str,
+    inner: reqwest::Client,
+}
+
+impl HttpClient {
+    /// A client with its own pool, reported as `name` in metrics
+    pub fn new(name: &'static str, settings: &HttpClientSettings) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .pool_max_idle_per_host(settings.pool_max_idle_per_host)
+            .pool_idle_timeout(settings.pool_idle_timeout)
+            .connect_timeout(settings.connect_timeout)
+            .tcp_keepalive(settings.keepalive)
+            .tcp_nodelay(true)
+            .dns_resolver(Arc::new(CachingResolver::new(name, settings.dns_cache_ttl)));
+        if let Some(timeout) = settings.timeout {
+            builder = builder.timeout(timeout);
+        }
+        builder = match settings.http2 {
+            Http2::Off => builder.http1_only(),
+            Http2::PriorKnowledge => builder.http2_prior_knowledge(),
+            Http2::Negotiate => builder,
+        };
+        if settings.http2 != Http2::Off {
+            builder = builder
+                .http2_adaptive_window(true)
+                .http2_keep_alive_interval(settings.keepalive)
+                .http2_keep_alive_while_idle(true);
+        }
+
+        let inner = builder
+            .build()
+            .map_err(|e| Error::Config(format!("Failed to build HTTP client {}: {}", name, e)))?;
+        Ok(Self { name, inner })
+    }
+
+    pub fn request<U: IntoUrl>(&self, method: Method, url: U) -> RequestBuilder {
+        HTTP_CLIENT_REQUESTS.with_label_values(&[self.name]).inc();
+        self.inner.request(method, url)
+    }
+
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+
+    pub fn put<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.request(Method::PUT, url)
+    }
+
+    pub fn patch<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.request(Method::PATCH, url)
+    }
+
+    pub fn delete<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.request(Method::DELETE, url)
+    }
+}
+
+/// The process-wide client for calls that need no settings of their own
+pub fn shared() -> &'static HttpClient {
+    &SHARED
+}
+
+/// Resolved addresses by host, each with the moment it was resolved
+#[derive(Default)]
+struct DnsCache {
+    entries: HashMap<String, (Instant, Vec<SocketAddr>)>,
+}
+
+impl DnsCache {
+    fn get(&self, host: &str, ttl: Duration, now: Instant) -> Option<Vec<SocketAddr>> {
+        self.entries
+            .get(host)
+            .filter(|(resolved_at, _)| now.duration_since(*resolved_at) < ttl)
+            .map(|(_, addrs)| addrs.clone())
+    }
+
+    fn insert(&mut self, host: String, addrs: Vec<SocketAddr>, now: Instant) {
+        self.entries.insert(host, (now, addrs));
+    }
+}
+
+/// System resolver behind a time-limited cache. The ports of the returned
+/// addresses are replaced by the connector with the URL's.
+struct CachingResolver {
+    client: &'static str,
+    ttl: Duration,
+    cache: Arc<Mutex<DnsCache>>,
+}
+
+impl CachingResolver {
+    fn new(client: &'static str, ttl: Duration) -> Self {
+        Self { client, ttl, cache: Arc::new(Mutex::new(DnsCache::default())) }
+    }
+}
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        HTTP_CLIENT_CONNECTIONS.with_label_values(&[self.client]).inc();
+        Box::pin(lookup(self.client, name.as_str().to_string(), self.ttl, self.cache.clone()))
+    }
+}
+
+async fn lookup(
+    client: &'static str,
+    host: String,
+    ttl: Duration,
+    cache: Arc<Mutex<DnsCache>>,
+) -> std::result::Result<Addrs, Box<dyn std::error::Error + Send + Sync>> {
+    let cached = cache.lock().ok().and_then(|cache| cache.get(&host, ttl, Instant::now()));
+    if let Some(addrs) = cached {
+        HTTP_CLIENT_DNS_CACHE_HITS.with_label_values(&[client]).inc();
+        return Ok(Box::new(addrs.into_iter()));
+    }
+
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+    if !ttl.is_zero() && !addrs.is_empty() {
+        if let Ok(mut cache) = cache.lock() {
+            cache.insert(host, addrs.clone(), Instant::now());
+        }
+    }
+    Ok(Box::new(addrs.into_iter()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_and_dns_cache() {
+        assert_eq!(Http2::parse("Prior_Knowledge"), Some(Http2::PriorKnowledge));
+        assert_eq!(Http2::parse("auto"), Some(Http2::Negotiate));
+        assert_eq!(Http2::parse("h3"), None);
+        assert_eq!(HttpClientSettings::default().with_timeout(Duration::from_secs(5)).timeout, Some(Duration::from_secs(5)));
+
+        let now = Instant::now();
+        let ttl = Duration::from_secs(60);
+        let addrs: Vec<SocketAddr> = vec!["10.0.0.7:0".parse().unwrap()];
+        let mut cache = DnsCache::default();
+        assert!(cache.get("sync-service", ttl, now).is_none());
+        cache.insert("sync-service".to_string(), addrs.clone(), now);
+        assert_eq!(cache.get("sync-service", ttl, now + Duration::from_secs(59)), Some(addrs));
+        assert!(cache.get("sync-service", ttl, now + ttl).is_none());
+        assert!(cache.get("sync-service", Duration::ZERO, now).is_none());
+    }
+}
@@ -0,0 +1,244 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::database::RotatingPool;
+use crate::errors::{Error, Result};
+
+/// Request header carrying the client's idempotency key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// How long a stored response is replayed
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+const MAX_KEY_LENGTH: usize = 255;
+
+/// A response saved for replay
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredResponse {
+    pub status_code: u16,
+    pub body: serde_json::Value,
+}
+
+/// Result of claiming an idempotency key
+#[derive(Debug)]
+pub enum Claim {
+    /// First time this key was seen; run the request, then `complete` or `release`
+    New(IdempotentRequest),
+    /// The request already ran; send the stored response again
+    Replay(StoredResponse),
+}
+
+/// A claimed key whose response is not stored yet
+#[derive(Debug)]
+pub struct IdempotentRequest {
+    scope: String,
+    key: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct KeyRow {
+    request_hash: String,
+    status_code: Option<i32>,
+    response: Option<serde_json::Value>,
+    expires_at: DateTime<Utc>,
+}
+
+/// Hash of the request body; a reused key with a different body is rejected
+pub fn request_hash(body: &serde_json::Value) -> String {
+    let digest = Sha256::digest(body.to_string().as_bytes());
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn validate_key(key: &str) -> Result<()> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH || !key.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(Error::Validation(format!(
+            "{} must be 1-{} printable ASCII characters",
+            IDEMPOTENCY_KEY_HEADER, MAX_KEY_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// Claim `key` for a request.
+///
+/// `scope` separates endpoints and counties, e.g. `benton:POST /sync-operations`,
+/// so the same key from different callers never collides. A retry while the
+/// first request is still running gets a conflict error.
+pub async fn claim(
+    pool: &PgPool,
+    scope: &str,
+    key: &str,
+    body: &serde_json::Value,
+    ttl: Duration,
+) -> Result<Claim> {
+    validate_key(key)?;
+    let hash = request_hash(body);
+    let expires_at = Utc::now() + chrono::Duration::from_std(ttl).map_err(|e| Error::Internal(e.to_string()))?;
+
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO idempotency_keys (scope, key, request_hash, expires_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (scope, key) DO UPDATE
+        SET request_hash = EXCLUDED.request_hash, status_code = NULL, response = NULL,
+            created_at = NOW(), expires_at = EXCLUDED.expires_at
+        WHERE idempotency_keys.expires_at <= NOW()
+        "#,
+    )
+    .bind(scope)
+    .bind(key)
+    .bind(&hash)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+
+    if inserted.rows_affected() > 0 {
+        return Ok(Claim::New(IdempotentRequest { scope: scope.to_string(), key: key.to_string() }));
+    }
+
+    let row = sqlx::query_as::<_, KeyRow>(
+        "SELECT request_hash, status_code, response, expires_at FROM idempotency_keys WHERE scope = $1 AND key = $2",
+    )
+    .bind(scope)
+    .bind(key)
+    .fetch_one(pool)
+    .await?;
+
+    if row.request_hash != hash {
+        return Err(Error::Validation(format!(
+            "{} was already used with a different request body",
+            IDEMPOTENCY_KEY_HEADER
+        )));
+    }
+
+    match (row.status_code, row.response) {
+        (Some(status_code), Some(body)) if row.expires_at > Utc::now() => Ok(Claim::Replay(StoredResponse {
+            status_code: status_code as u16,
+            body,
+        })),
+        _ => Err(Error::Conflict("A request with this idempotency key is still being processed".to_string())),
+    }
+}
+
+impl IdempotentRequest {
+    /// Store the response so retries get it back
+    pub async fn complete(self, pool: &PgPool, status_code: u16, body: &serde_json::Value) -> Result<()> {
+        sqlx::query("UPDATE idempotency_keys SET status_code = $3, response = $4 WHERE scope = $1 AND key = $2")
+            .bind(&self.scope)
+            .bind(&self.key)
+            .bind(status_code as i32)
+            .bind(body)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Forget the claim after a failure so the client can retry with the same key
+    pub async fn release(self, pool: &PgPool) -> Result<()> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE scope = $1 AND key = $2 AND response IS NULL")
+            .bind(&self.scope)
+            .bind(&self.key)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Delete expired keys periodically
+pub fn spawn_purge(pool: RotatingPool, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+            match sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= NOW()")
+                .execute(&pool.pool())
+                .await
+            {
+                Ok(result) if result.rows_affected() > 0 => {
+                    log::debug!("Purged {} expired idempotency keys", result.rows_affected())
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("Failed to purge idempotency keys: {}", e),
+            }
+        }
+    })
+}
+
+/// Actix handler support: run a handler once per `Idempotency-Key`
+#[cfg(feature = "actix")]
+pub mod web {
+    use std::future::Future;
+    use actix_web::{http::StatusCode, HttpRequest, HttpResponse};
+    use sqlx::PgPool;
+
+    use super::{claim, Claim, DEFAULT_TTL, IDEMPOTENCY_KEY_HEADER};
+    use crate::errors::Result;
+
+    /// Set on responses replayed from an earlier request
+    pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+    /// Run `handler` unless the request's idempotency key was already used,
+    /// in which case the stored response is returned instead.
+    ///
+    /// Requests without the header run normally. Failed requests are not
+    /// stored, so the client may retry them with the same key.
+    pub async fn run_once<F, Fut>(
+        req: &HttpRequest,
+        pool: &PgPool,
+        scope: &str,
+        body: &serde_json::Value,
+        handler: F,
+    ) -> Result<HttpResponse>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(StatusCode, serde_json::Value)>>,
+    {
+        let key = req.headers().get(IDEMPOTENCY_KEY_HEADER).and_then(|value| value.to_str().ok());
+        let Some(key) = key else {
+            let (status, body) = handler().await?;
+            return Ok(HttpResponse::build(status).json(body));
+        };
+
+        match claim(pool, scope, key, body, DEFAULT_TTL).await? {
+            Claim::Replay(stored) => {
+                let status = StatusCode::from_u16(stored.status_code).unwrap_or(StatusCode::OK);
+                Ok(HttpResponse::build(status)
+                    .insert_header((REPLAYED_HEADER, "true"))
+                    .json(stored.body))
+            }
+            Claim::New(claimed) => match handler().await {
+                Ok((status, body)) => {
+                    if let Err(e) = claimed.complete(pool, status.as_u16(), &body).await {
+                        log::error!("Failed to store idempotent response for key {}: {}", key, e);
+                    }
+                    Ok(HttpResponse::build(status).json(body))
+                }
+                Err(e) => {
+                    if let Err(release_err) = claimed.release(pool).await {
+                        log::error!("Failed to release idempotency key {}: {}", key, release_err);
+                    }
+                    Err(e)
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_request_hash_and_key_validation() {
+        assert_eq!(request_hash(&json!({"a": 1, "b": 2})), request_hash(&json!({"b": 2, "a": 1})));
+        assert_ne!(request_hash(&json!({"a": 1})), request_hash(&json!({"a": 2})));
+
+        assert!(validate_key("3f1c9a2e-retry").is_ok());
+        assert!(validate_key("").is_err());
+        assert!(validate_key("has space").is_err());
+    }
+}
@@ -0,0 +1,560 @@
+//! Background job queue.
+//!
+//! Work that has to survive a restart, be retried, or run on only one
+//! instance is enqueued here rather than `tokio::spawn`ed. A `Worker` claims
+//! jobs from its queues, runs the handler registered for the job's kind, and
+//! retries failures with exponential backoff until `max_attempts` is used
+//! up. Jobs can be scheduled for later, and pending or failed jobs can be
+//! listed, retried and cancelled by administrators.
+//!
+//! The default backend is the `job_queue` table, claimed with
+//! `FOR UPDATE SKIP LOCKED` so any number of instances can share it.
+//! `JOB_QUEUE_BACKEND=redis` (feature `redis-jobs`) or `nats` (feature
+//! `nats-jobs`) use an external broker instead.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+use crate::errors::{Error, Result};
+
+#[cfg(feature = "nats-jobs")]
+pub mod nats;
+pub mod postgres;
+#[cfg(feature = "redis-jobs")]
+pub mod redis;
+
+#[cfg(feature = "nats-jobs")]
+pub use self::nats::NatsJobBackend;
+pub use postgres::PgJobBackend;
+#[cfg(feature = "redis-jobs")]
+pub use self::redis::RedisJobBackend;
+
+/// Boxed future returned by job backends
+pub type QueueFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// First retry delay; doubled for every further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+
+/// Longest delay between retries
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Attempts a job gets unless it asks for another number
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+
+/// Where a job is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting for `run_at`, or for a free worker
+    Pending,
+    /// Claimed by a worker
+    Running,
+    Succeeded,
+    /// Out of attempts, or failed with an error that is not retried
+    Failed,
+    /// Withdrawn before it ran
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the job has stopped for good (until an administrator retries it)
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+/// A unit of background work
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Job {
+    pub id: Uuid,
+    /// Queue the job waits in; workers subscribe to queues
+    pub queue: String,
+    /// Selects the handler, e.g. `gis_export.process`
+    pub kind: String,
+    pub payload: Value,
+    pub status: JobStatus,
+    /// Attempts started so far, including the running one
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// Earliest time the job may run
+    pub run_at: DateTime<Utc>,
+    /// At most one pending or running job has a given key
+    pub unique_key: Option<String>,
+    pub last_error: Option<String>,
+    pub locked_by: Option<String>,
+    /// Last heartbeat of the worker running the job
+    pub locked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl Job {
+    /// Deserialize the payload
+    pub fn payload<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_value(self.payload.clone())
+            .map_err(|e| Error::Validation(format!("Invalid payload for {} job {}: {}", self.kind, self.id, e)))
+    }
+}
+
+/// A job to enqueue
+#[derive(Debug, Clone)]
+pub struct NewJob {
+    pub id: Uuid,
+    pub queue: String,
+    pub kind: String,
+    pub payload: Value,
+    pub run_at: DateTime<Utc>,
+    pub max_attempts: i32,
+    pub unique_key: Option<String>,
+}
+
+impl NewJob {
+    pub fn new(queue: &str, kind: &str, payload: Value) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            queue: queue.to_string(),
+            kind: kind.to_string(),
+            payload,
+            run_at: Utc::now(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            unique_key: None,
+        }
+    }
+
+    /// Use the id of the record the job works on, so the job can be found
+    /// from it
+    pub fn id(mut self, id: Uuid) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Run no earlier than `at`
+    pub fn run_at(mut self, at: DateTime<Utc>) -> Self {
+        self.run_at = at;
+        self
+    }
+
+    pub fn max_attempts(mut self, max_attempts: i32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Enqueueing while a pending or running job has the same key returns
+    /// that job instead of adding another
+    pub fn unique(mut self, key: impl Into<String>) -> Self {
+        self.unique_key = Some(key.into());
+        self
+    }
+
+    /// The job as stored when first enqueued
+    pub(crate) fn into_job(self) -> Job {
+        let now = Utc::now();
+        Job {
+            id: self.id,
+            queue: self.queue,
+            kind: self.kind,
+            payload: self.payload,
+            status: JobStatus::Pending,
+            attempts: 0,
+            max_attempts: self.max_attempts,
+            run_at: self.run_at,
+            unique_key: self.unique_key,
+            last_error: None,
+            locked_by: None,
+            locked_at: None,
+            created_at: now,
+            updated_at: now,
+            finished_at: None,
+        }
+    }
+}
+
+/// Which jobs to list
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JobFilter {
+    pub queue: Option<String>,
+    pub kind: Option<String>,
+    pub status: Option<JobStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl JobFilter {
+    pub fn matches(&self, job: &Job) -> bool {
+        self.queue.as_ref().map_or(true, |queue| &job.queue == queue)
+            && self.kind.as_ref().map_or(true, |kind| &job.kind == kind)
+            && self.status.map_or(true, |status| job.status == status)
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(50).clamp(1, 500)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+}
+
+/// Number of jobs in one queue and status
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct QueueCount {
+    pub queue: String,
+    pub status: JobStatus,
+    pub count: i64,
+    /// Earliest `run_at` among the jobs, showing how far behind a queue is
+    pub oldest_run_at: Option<DateTime<Utc>>,
+}
+
+/// Storage and claiming of jobs
+pub trait JobBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Store a job, or return the active job with the same unique key
+    fn enqueue<'a>(&'a self, job: NewJob) -> QueueFuture<'a, Job>;
+
+    /// Mark up to `limit` due jobs from `queues` as running for `worker`
+    fn claim<'a>(&'a self, queues: &'a [String], worker: &'a str, limit: usize) -> QueueFuture<'a, Vec<Job>>;
+
+    /// Record that the worker running the job is still alive
+    fn heartbeat<'a>(&'a self, id: Uuid) -> QueueFuture<'a, ()>;
+
+    fn complete<'a>(&'a self, id: Uuid) -> QueueFuture<'a, ()>;
+
+    /// Record a failed attempt; retried at `retry_at`, or failed for good
+    /// when there is none
+    fn fail<'a>(&'a self, id: Uuid, error: &'a str, retry_at: Option<DateTime<Utc>>) -> QueueFuture<'a, ()>;
+
+    fn get<'a>(&'a self, id: Uuid) -> QueueFuture<'a, Option<Job>>;
+
+    /// Jobs matching the filter, newest first
+    fn list<'a>(&'a self, filter: &'a JobFilter) -> QueueFuture<'a, Vec<Job>>;
+
+    fn stats<'a>(&'a self) -> QueueFuture<'a, Vec<QueueCount>>;
+
+    /// Queue a failed or cancelled job again with fresh attempts
+    fn retry<'a>(&'a self, id: Uuid) -> QueueFuture<'a, bool>;
+
+    /// Withdraw a pending job
+    fn cancel<'a>(&'a self, id: Uuid) -> QueueFuture<'a, bool>;
+
+    /// Put running jobs whose worker stopped heartbeating before `stale_before` back in the queue
+    fn recover<'a>(&'a self, stale_before: DateTime<Utc>) -> QueueFuture<'a, u64>;
+
+    /// Remove succeeded and cancelled jobs that finished before `before`
+    fn purge<'a>(&'a self, before: DateTime<Utc>) -> QueueFuture<'a, u64>;
+}
+
+/// Timing of workers and housekeeping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueSettings {
+    /// Wait between claims when the queues are empty
+    pub poll_interval: Duration,
+    /// Running jobs without a heartbeat for this long are handed to another worker
+    pub lease: Duration,
+    /// How long succeeded and cancelled jobs stay listed
+    pub retention: Duration,
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(2),
+            lease: Duration::from_secs(5 * 60),
+            retention: Duration::from_secs(7 * 24 * 60 * 60),
+        }
+    }
+}
+
+impl QueueSettings {
+    /// Read `JOB_POLL_INTERVAL_SECONDS`, `JOB_LEASE_SECONDS` and `JOB_RETENTION_DAYS`
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let positive = |name: &str| -> Result<Option<u64>> {
+            match std::env::var(name) {
+                Ok(value) => value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|value| *value > 0)
+                    .map(Some)
+                    .ok_or_else(|| Error::Config(format!("Invalid {} value", name))),
+                Err(_) => Ok(None),
+            }
+        };
+
+        Ok(Self {
+            poll_interval: positive("JOB_POLL_INTERVAL_SECONDS")?.map_or(defaults.poll_interval, Duration::from_secs),
+            lease: positive("JOB_LEASE_SECONDS")?.map_or(defaults.lease, Duration::from_secs),
+            retention: positive("JOB_RETENTION_DAYS")?
+                .map_or(defaults.retention, |days| Duration::from_secs(days * 24 * 60 * 60)),
+        })
+    }
+}
+
+/// Delay before attempt `attempts + 1`
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(exponent)).min(RETRY_MAX_DELAY)
+}
+
+/// Errors that retrying cannot fix: the request itself was wrong
+fn is_retryable(error: &Error) -> bool {
+    error.status_code() >= 500
+}
+
+/// Enqueues jobs and runs workers against the configured backend
+#[derive(Clone)]
+pub struct JobQueue {
+    backend: Arc<dyn JobBackend>,
+    settings: QueueSettings,
+}
+
+impl JobQueue {
+    pub fn new(backend: Arc<dyn JobBackend>, settings: QueueSettings) -> Self {
+        Self { backend, settings }
+    }
+
+    /// Backend from JOB_QUEUE_BACKEND: `postgres` (default, using `postgres`),
+    /// `redis` or `nats`
+    pub fn from_env(postgres: PgJobBackend) -> Result<Self> {
+        let settings = QueueSettings::from_env()?;
+        let backend: Arc<dyn JobBackend> = match std::env::var("JOB_QUEUE_BACKEND").as_deref() {
+            Err(_) | Ok("postgres") => Arc::new(postgres),
+            #[cfg(feature = "redis-jobs")]
+            Ok("redis") => Arc::new(RedisJobBackend::from_env()?),
+            #[cfg(not(feature = "redis-jobs"))]
+            Ok("redis") => {
+                return Err(Error::Config(
+                    "JOB_QUEUE_BACKEND=redis requires the redis-jobs feature".to_string(),
+                ))
+            }
+            #[cfg(feature = "nats-jobs")]
+            Ok("nats") => Arc::new(NatsJobBackend::from_env(settings.lease)?),
+            #[cfg(not(feature = "nats-jobs"))]
+            Ok("nats") => {
+                return Err(Error::Config(
+                    "JOB_QUEUE_BACKEND=nats requires the nats-jobs feature".to_string(),
+                ))
+            }
+            Ok(other) => return Err(Error::Config(format!("Unknown JOB_QUEUE_BACKEND: {}", other))),
+        };
+        log::info!("Job queue backend: {}", backend.name());
+        Ok(Self::new(backend, settings))
+    }
+
+    pub fn backend(&self) -> &dyn JobBackend {
+        self.backend.as_ref()
+    }
+
+    pub fn settings(&self) -> QueueSettings {
+        self.settings
+    }
+
+    pub async fn enqueue(&self, job: NewJob) -> Result<Job> {
+        let job = self.backend.enqueue(job).await?;
+        log::debug!("Enqueued {} job {} on {}", job.kind, job.id, job.queue);
+        Ok(job)
+    }
+
+    /// A worker for `queues`; register handlers, then `spawn` it
+    pub fn worker(&self, queues: &[&str]) -> Worker {
+        Worker {
+            queue: self.clone(),
+            queues: queues.iter().map(|queue| queue.to_string()).collect(),
+            handlers: HashMap::new(),
+            concurrency: 4,
+        }
+    }
+
+    /// Recover jobs of dead workers and purge old finished jobs periodically
+    pub fn spawn_maintenance(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let queue = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+                let now = Utc::now();
+                let lease = chrono::Duration::from_std(queue.settings.lease).unwrap_or_else(|_| chrono::Duration::minutes(5));
+                match queue.backend.recover(now - lease).await {
+                    Ok(recovered) if recovered > 0 => log::warn!("Requeued {} jobs of unresponsive workers", recovered),
+                    Ok(_) => {}
+                    Err(e) => log::error!("Failed to recover stale jobs: {}", e),
+                }
+
+                let retention = chrono::Duration::from_std(queue.settings.retention).unwrap_or_else(|_| chrono::Duration::days(7));
+                match queue.backend.purge(now - retention).await {
+                    Ok(purged) if purged > 0 => log::debug!("Purged {} finished jobs", purged),
+                    Ok(_) => {}
+                    Err(e) => log::error!("Failed to purge finished jobs: {}", e),
+                }
+            }
+        })
+    }
+}
+
+/// Handler for one job kind
+pub type JobHandler = Arc<dyn Fn(Job) -> QueueFuture<'static, ()> + Send + Sync>;
+
+/// Runs the jobs of some queues with the registered handlers.
+///
+/// A handler's `Err` is retried with backoff when it is a server-side error
+/// (status 500 and up, e.g. `ExternalService`); client errors such as
+/// `Validation` or `Conflict` fail the job at once.
+pub struct Worker {
+    queue: JobQueue,
+    queues: Vec<String>,
+    handlers: HashMap<String, JobHandler>,
+    concurrency: usize,
+}
+
+impl Worker {
+    pub fn handle<F, Fut>(mut self, kind: &str, handler: F) -> Self
+    where
+        F: Fn(Job) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.handlers.insert(kind.to_string(), Arc::new(move |job| Box::pin(handler(job))));
+        self
+    }
+
+    /// Jobs run at the same time by this worker
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        let worker_id = format!("{}-{}", std::process::id(), &Uuid::new_v4().simple().to_string()[..8]);
+        let handlers = Arc::new(self.handlers);
+        let slots = Arc::new(Semaphore::new(self.concurrency));
+        let queue = self.queue;
+        let queues = self.queues;
+        log::info!("Job worker {} started for {}", worker_id, queues.join(", "));
+
+        tokio::spawn(async move {
+            loop {
+                let free = slots.available_permits();
+                if free == 0 {
+                    // Wait for a running job to finish
+                    drop(slots.acquire().await);
+                    continue;
+                }
+
+                let jobs = match queue.backend.claim(&queues, &worker_id, free).await {
+                    Ok(jobs) => jobs,
+                    Err(e) => {
+                        log::error!("Failed to claim jobs: {}", e);
+                        Vec::new()
+                    }
+                };
+                if jobs.is_empty() {
+                    tokio::time::sleep(queue.settings.poll_interval).await;
+                    continue;
+                }
+
+                for job in jobs {
+                    let Ok(permit) = slots.clone().acquire_owned().await else {
+                        return;
+                    };
+                    let queue = queue.clone();
+                    let handlers = handlers.clone();
+                    tokio::spawn(async move {
+                        run_job(&queue, &handlers, job).await;
+                        drop(permit);
+                    });
+                }
+            }
+        })
+    }
+}
+
+/// Run one claimed job, heartbeating until the handler returns
+async fn run_job(queue: &JobQueue, handlers: &HashMap<String, JobHandler>, job: Job) {
+    let backend = queue.backend.as_ref();
+    let (id, kind, attempts, max_attempts) = (job.id, job.kind.clone(), job.attempts, job.max_attempts);
+
+    let Some(handler) = handlers.get(&kind) else {
+        let message = format!("No handler for job kind {}", kind);
+        log::error!("{} (job {})", message, id);
+        if let Err(e) = backend.fail(id, &message, None).await {
+            log::error!("Failed to record failure of job {}: {}", id, e);
+        }
+        return;
+    };
+
+    let mut heartbeat = tokio::time::interval(queue.settings.lease / 3);
+    heartbeat.tick().await;
+    let run = handler(job);
+    tokio::pin!(run);
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break result,
+            _ = heartbeat.tick() => {
+                if let Err(e) = backend.heartbeat(id).await {
+                    log::warn!("Failed to record heartbeat of job {}: {}", id, e);
+                }
+            }
+        }
+    };
+
+    let recorded = match result {
+        Ok(()) => backend.complete(id).await,
+        Err(e) => {
+            let retry_at = (is_retryable(&e) && attempts < max_attempts).then(|| {
+                Utc::now() + chrono::Duration::from_std(retry_delay(attempts)).unwrap_or_else(|_| chrono::Duration::minutes(1))
+            });
+            match retry_at {
+                Some(at) => log::warn!("{} job {} failed (attempt {} of {}), retrying at {}: {}", kind, id, attempts, max_attempts, at, e),
+                None => log::error!("{} job {} failed after {} attempts: {}", kind, id, attempts, e),
+            }
+            backend.fail(id, &e.to_string(), retry_at).await
+        }
+    };
+    if let Err(e) = recorded {
+        log::error!("Failed to record outcome of job {}: {}", id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_and_retryable() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(3), Duration::from_secs(120));
+        assert_eq!(retry_delay(30), RETRY_MAX_DELAY);
+
+        assert!(is_retryable(&Error::ExternalService("timeout".to_string())));
+        assert!(!is_retryable(&Error::Conflict("already processed".to_string())));
+
+        let job = NewJob::new("maintenance", "retention.run", serde_json::json!({}))
+            .max_attempts(0)
+            .unique("retention.run")
+            .into_job();
+        assert_eq!(job.max_attempts, 1);
+        assert_eq!(job.status, JobStatus::Pending);
+        assert!(JobFilter { status: Some(JobStatus::Pending), ..Default::default() }.matches(&job));
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use async_nats::jetstream::{self, consumer::PullConsumer, kv, AckKind, Message};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex, OnceCell};
+use uuid::Uuid;
+
+use crate::errors::{Error, Result};
+use super::{Job, JobBackend, JobFilter, JobStatus, NewJob, QueueCount, QueueFuture};
+
+/// How long a claim waits for messages on an idle queue
+const FETCH_WAIT: Duration = Duration::from_millis(200);
+
+struct Connection {
+    jetstream: jetstream::Context,
+    stream: jetstream::stream::Stream,
+    jobs: kv::Store,
+}
+
+/// Jobs in NATS JetStream.
+///
+/// Job ids are published to the work-queue stream `{prefix}` on subject
+/// `{prefix}.{queue}`, and each queue has a durable pull consumer. Job
+/// records live in the key-value bucket `{prefix}_jobs`, which is what the
+/// admin API reads. A claimed message stays unacknowledged while the job
+/// runs; heartbeats extend its ack deadline, and JetStream redelivers it if
+/// the worker disappears. Retries are negative acknowledgements with a delay.
+pub struct NatsJobBackend {
+    url: String,
+    prefix: String,
+    lease: Duration,
+    connection: OnceCell<Connection>,
+    consumers: Mutex<HashMap<String, PullConsumer>>,
+    in_flight: Mutex<HashMap<Uuid, Message>>,
+}
+
+impl NatsJobBackend {
+    /// Connect lazily to NATS_URL, with the stream named by NATS_JOB_PREFIX
+    pub fn from_env(lease: Duration) -> Result<Self> {
+        let url = std::env::var("NATS_URL")
+            .map_err(|_| Error::Config("NATS_URL is required for the NATS job queue".to_string()))?;
+
+        Ok(Self {
+            url,
+            prefix: std::env::var("NATS_JOB_PREFIX").unwrap_or_else(|_| "terrafusion_jobs".to_string()),
+            lease,
+            connection: OnceCell::new(),
+            consumers: Mutex::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+        })
+    }
+
+    async fn connection(&self) -> Result<&Connection> {
+        self.connection
+            .get_or_try_init(|| async {
+                let client = async_nats::connect(&self.url).await.map_err(nats_error)?;
+                let jetstream = jetstream::new(client);
+                let stream = jetstream
+                    .get_or_create_stream(jetstream::stream::Config {
+                        name: self.prefix.clone(),
+                        subjects: vec![format!("{}.>", self.prefix)],
+                        retention: jetstream::stream::RetentionPolicy::WorkQueue,
+                        ..Default::default()
+                    })
+                    .await
+                    .map_err(nats_error)?;
+                let bucket = format!("{}_jobs", self.prefix);
+                let jobs = match jetstream.get_key_value(&bucket).await {
+                    Ok(jobs) => jobs,
+                    Err(_) => jetstream
+                        .create_key_value(kv::Config { bucket, history: 1, ..Default::default() })
+                        .await
+                        .map_err(nats_error)?,
+                };
+                Ok(Connection { jetstream, stream, jobs })
+            })
+            .await
+    }
+
+    async fn consumer(&self, queue: &str) -> Result<PullConsumer> {
+        let mut consumers = self.consumers.lock().await;
+        if let Some(consumer) = consumers.get(queue) {
+            return Ok(consumer.clone());
+        }
+        let name = format!("{}_{}", self.prefix, queue);
+        let consumer = self
+            .connection()
+            .await?
+            .stream
+            .get_or_create_consumer(
+                &name,
+                jetstream::consumer::pull::Config {
+                    durable_name: Some(name.clone()),
+                    filter_subject: self.subject(queue),
+                    ack_wait: self.lease,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(nats_error)?;
+        consumers.insert(queue.to_string(), consumer.clone());
+        Ok(consumer)
+    }
+
+    fn subject(&self, queue: &str) -> String {
+        format!("{}.{}", self.prefix, queue)
+    }
+
+    /// Bucket key for a unique key, which may contain characters NATS keys cannot
+    fn unique_entry(key: &str) -> String {
+        format!("unique.{:x}", Sha256::digest(key.as_bytes()))
+    }
+
+    async fn load(&self, id: Uuid) -> Result<Option<Job>> {
+        let jobs = &self.connection().await?.jobs;
+        let value = jobs.get(format!("job.{}", id)).await.map_err(nats_error)?;
+        value.map(|value| decode(&value)).transpose()
+    }
+
+    async fn save(&self, job: &Job) -> Result<()> {
+        let jobs = &self.connection().await?.jobs;
+        jobs.put(format!("job.{}", job.id), encode(job)?.into()).await.map_err(nats_error)?;
+        Ok(())
+    }
+
+    async fn load_all(&self) -> Result<Vec<Job>> {
+        let jobs = &self.connection().await?.jobs;
+        let mut keys = jobs.keys().await.map_err(nats_error)?;
+        let mut loaded = Vec::new();
+        while let Some(key) = keys.next().await {
+            let key = key.map_err(nats_error)?;
+            let Some(id) = key.strip_prefix("job.").and_then(|id| Uuid::parse_str(id).ok()) else {
+                continue;
+            };
+            if let Some(job) = self.load(id).await? {
+                loaded.push(job);
+            }
+        }
+        loaded.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(loaded)
+    }
+
+    async fn publish(&self, job: &Job) -> Result<()> {
+        let jetstream = &self.connection().await?.jetstream;
+        jetstream
+            .publish(self.subject(&job.queue), job.id.to_string().into())
+            .await
+            .map_err(nats_error)?
+            .await
+            .map_err(nats_error)?;
+        Ok(())
+    }
+
+    async fn release_unique(&self, job: &Job) -> Result<()> {
+        let Some(key) = &job.unique_key else {
+            return Ok(());
+        };
+        let jobs = &self.connection().await?.jobs;
+        let entry = Self::unique_entry(key);
+        let holder = jobs.get(&entry).await.map_err(nats_error)?;
+        if holder.as_deref() == Some(job.id.to_string().as_bytes()) {
+            jobs.delete(&entry).await.map_err(nats_error)?;
+        }
+        Ok(())
+    }
+
+    async fn acknowledge(&self, id: Uuid, kind: AckKind) -> Result<()> {
+        let message = self.in_flight.lock().await.remove(&id);
+        if let Some(message) = message {
+            message.ack_with(kind).await.map_err(nats_error)?;
+        }
+        Ok(())
+    }
+
+    async fn finish(&self, mut job: Job, status: JobStatus) -> Result<()> {
+        let now = Utc::now();
+        job.status = status;
+        job.locked_by = None;
+        job.locked_at = None;
+        job.updated_at = now;
+        job.finished_at = Some(now);
+        self.save(&job).await?;
+        self.acknowledge(job.id, AckKind::Ack).await?;
+        self.release_unique(&job).await
+    }
+}
+
+fn nats_error(e: impl std::fmt::Display) -> Error {
+    Error::ExternalService(format!("NATS job queue: {}", e))
+}
+
+fn encode(job: &Job) -> Result<Vec<u8>> {
+    serde_json::to_vec(job).map_err(|e| Error::Serialization(e.to_string()))
+}
+
+fn decode(value: &[u8]) -> Result<Job> {
+    serde_json::from_slice(value).map_err(|e| Error::Serialization(e.to_string()))
+}
+
+impl JobBackend for NatsJobBackend {
+    fn name(&self) -> &'static str {
+        "nats"
+    }
+
+    fn enqueue<'a>(&'a self, job: NewJob) -> QueueFuture<'a, Job> {
+        Box::pin(async move {
+            let job = job.into_job();
+
+            if let Some(key) = &job.unique_key {
+                let jobs = &self.connection().await?.jobs;
+                let entry = Self::unique_entry(key);
+                if jobs.create(&entry, job.id.to_string().into()).await.is_err() {
+                    let holder = jobs.get(&entry).await.map_err(nats_error)?;
+                    let existing = holder.and_then(|id| Uuid::parse_str(&String::from_utf8_lossy(&id)).ok());
+                    if let Some(existing) = existing {
+                        if let Some(existing) = self.load(existing).await? {
+                            if !existing.status.is_finished() {
+                                return Ok(existing);
+                            }
+                        }
+                    }
+                    jobs.put(&entry, job.id.to_string().into()).await.map_err(nats_error)?;
+                }
+            }
+
+            self.save(&job).await?;
+            self.publish(&job).await?;
+            Ok(job)
+        })
+    }
+
+    fn claim<'a>(&'a self, queues: &'a [String], worker: &'a str, limit: usize) -> QueueFuture<'a, Vec<Job>> {
+        Box::pin(async move {
+            let mut claimed = Vec::new();
+
+            for queue in queues {
+                if claimed.len() >= limit {
+                    break;
+                }
+                let consumer = self.consumer(queue).await?;
+                let mut batch = consumer
+                    .fetch()
+                    .max_messages(limit - claimed.len())
+                    .expires(FETCH_WAIT)
+                    .messages()
+                    .await
+                    .map_err(nats_error)?;
+
+                while let Some(message) = batch.next().await {
+                    let message = message.map_err(nats_error)?;
+                    let id = Uuid::parse_str(&String::from_utf8_lossy(&message.payload)).ok();
+                    let Some(mut job) = (match id {
+                        Some(id) => self.load(id).await?,
+                        None => None,
+                    }) else {
+                        message.ack_with(AckKind::Term).await.map_err(nats_error)?;
+                        continue;
+                    };
+
+                    // Cancelled, or a duplicate delivery of a finished job
+                    if job.status.is_finished() {
+                        message.ack().await.map_err(nats_error)?;
+                        continue;
+                    }
+                    let now = Utc::now();
+                    if job.run_at > now {
+                        let wait = (job.run_at - now).to_std().unwrap_or_default();
+                        message.ack_with(AckKind::Nak(Some(wait))).await.map_err(nats_error)?;
+                        continue;
+                    }
+
+                    job.status = JobStatus::Running;
+                    job.attempts += 1;
+                    job.locked_by = Some(worker.to_string());
+                    job.locked_at = Some(now);
+                    job.updated_at = now;
+                    self.save(&job).await?;
+                    self.in_flight.lock().await.insert(job.id, message);
+                    claimed.push(job);
+                }
+            }
+            Ok(claimed)
+        })
+    }
+
+    fn heartbeat<'a>(&'a self, id: Uuid) -> QueueFuture<'a, ()> {
+        Box::pin(async move {
+            let in_flight = self.in_flight.lock().await;
+            if let Some(message) = in_flight.get(&id) {
+                message.ack_with(AckKind::Progress).await.map_err(nats_error)?;
+            }
+            drop(in_flight);
+
+            if let Some(mut job) = self.load(id).await? {
+                job.locked_at = Some(Utc::now());
+                self.save(&job).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn complete<'a>(&'a self, id: Uuid) -> QueueFuture<'a, ()> {
+        Box::pin(async move {
+            let Some(mut job) = self.load(id).await? else {
+                return self.acknowledge(id, AckKind::Ack).await;
+            };
+            job.last_error = None;
+            self.finish(job, JobStatus::Succeeded).await
+        })
+    }
+
+    fn fail<'a>(&'a self, id: Uuid, error: &'a str, retry_at: Option<DateTime<Utc>>) -> QueueFuture<'a, ()> {
+        Box::pin(async move {
+            let Some(mut job) = self.load(id).await? else {
+                return self.acknowledge(id, AckKind::Term).await;
+            };
+            job.last_error = Some(error.to_string());
+
+            let Some(retry_at) = retry_at else {
+                return self.finish(job, JobStatus::Failed).await;
+            };
+            let now = Utc::now();
+            job.status = JobStatus::Pending;
+            job.run_at = retry_at;
+            job.locked_by = None;
+            job.locked_at = None;
+            job.updated_at = now;
+            self.save(&job).await?;
+            let wait = (retry_at - now).to_std().unwrap_or_default();
+            self.acknowledge(id, AckKind::Nak(Some(wait))).await
+        })
+    }
+
+    fn get<'a>(&'a self, id: Uuid) -> QueueFuture<'a, Option<Job>> {
+        Box::pin(self.load(id))
+    }
+
+    fn list<'a>(&'a self, filter: &'a JobFilter) -> QueueFuture<'a, Vec<Job>> {
+        Box::pin(async move {
+            Ok(self
+                .load_all()
+                .await?
+                .into_iter()
+                .filter(|job| filter.matches(job))
+                .skip(filter.offset() as usize)
+                .take(filter.limit() as usize)
+                .collect())
+        })
+    }
+
+    fn stats<'a>(&'a self) -> QueueFuture<'a, Vec<QueueCount>> {
+        Box::pin(async move {
+            let mut counts: BTreeMap<(String, &'static str), QueueCount> = BTreeMap::new();
+            for job in self.load_all().await? {
+                let entry = counts.entry((job.queue.clone(), job.status.as_str())).or_insert_with(|| QueueCount {
+                    queue: job.queue.clone(),
+                    status: job.status,
+                    count: 0,
+                    oldest_run_at: None,
+                });
+                entry.count += 1;
+                entry.oldest_run_at = Some(entry.oldest_run_at.map_or(job.run_at, |oldest| oldest.min(job.run_at)));
+            }
+            Ok(counts.into_values().collect())
+        })
+    }
+
+    fn retry<'a>(&'a self, id: Uuid) -> QueueFuture<'a, bool> {
+        Box::pin(async move {
+            let Some(mut job) = self.load(id).await? else {
+                return Ok(false);
+            };
+            if !matches!(job.status, JobStatus::Failed | JobStatus::Cancelled) {
+                return Ok(false);
+            }
+            let now = Utc::now();
+            job.status = JobStatus::Pending;
+            job.attempts = 0;
+            job.run_at = now;
+            job.finished_at = None;
+            job.updated_at = now;
+            if let Some(key) = &job.unique_key {
+                let jobs = &self.connection().await?.jobs;
+                jobs.put(Self::unique_entry(key), id.to_string().into()).await.map_err(nats_error)?;
+            }
+            self.save(&job).await?;
+            self.publish(&job).await?;
+            Ok(true)
+        })
+    }
+
+    fn cancel<'a>(&'a self, id: Uuid) -> QueueFuture<'a, bool> {
+        Box::pin(async move {
+            let Some(mut job) = self.load(id).await? else {
+                return Ok(false);
+            };
+            if job.status != JobStatus::Pending {
+                return Ok(false);
+            }
+            // The message is dropped when next delivered
+            let now = Utc::now();
+            job.status = JobStatus::Cancelled;
+            job.updated_at = now;
+            job.finished_at = Some(now);
+            self.save(&job).await?;
+            self.release_unique(&job).await?;
+            Ok(true)
+        })
+    }
+
+    fn recover<'a>(&'a self, stale_before: DateTime<Utc>) -> QueueFuture<'a, u64> {
+        Box::pin(async move {
+            // JetStream redelivers the messages itself once their ack deadline
+            // passes; only the records need to show the jobs as pending again
+            let mut recovered = 0;
+            for mut job in self.load_all().await? {
+                if job.status == JobStatus::Running && job.locked_at.map_or(true, |at| at < stale_before) {
+                    job.status = JobStatus::Pending;
+                    job.locked_by = None;
+                    job.locked_at = None;
+                    job.updated_at = Utc::now();
+                    self.save(&job).await?;
+                    recovered += 1;
+                }
+            }
+            Ok(recovered)
+        })
+    }
+
+    fn purge<'a>(&'a self, before: DateTime<Utc>) -> QueueFuture<'a, u64> {
+        Box::pin(async move {
+            let jobs = &self.connection().await?.jobs;
+            let mut purged = 0;
+            for job in self.load_all().await? {
+                let expired = matches!(job.status, JobStatus::Succeeded | JobStatus::Cancelled)
+                    && job.finished_at.map_or(false, |finished| finished < before);
+                if expired {
+                    jobs.purge(format!("job.{}", job.id)).await.map_err(nats_error)?;
+                    purged += 1;
+                }
+            }
+            Ok(purged)
+        })
+    }
+}
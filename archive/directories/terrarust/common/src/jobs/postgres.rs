@@ -0,0 +1,262 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::RotatingPool;
+use super::{Job, JobBackend, JobFilter, NewJob, QueueCount, QueueFuture};
+
+const JOB_COLUMNS: &str = "id, queue, kind, payload, status, attempts, max_attempts, run_at, unique_key, \
+     last_error, locked_by, locked_at, created_at, updated_at, finished_at";
+
+enum PoolSource {
+    Fixed(PgPool),
+    Rotating(RotatingPool),
+}
+
+/// Jobs in the `job_queue` table.
+///
+/// Workers claim due rows with `FOR UPDATE SKIP LOCKED`, so concurrent
+/// claims from several instances never hand out the same job.
+pub struct PgJobBackend {
+    pool: PoolSource,
+}
+
+impl PgJobBackend {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool: PoolSource::Fixed(pool) }
+    }
+
+    /// Use whichever primary pool is current after credential rotation
+    pub fn rotating(pool: RotatingPool) -> Self {
+        Self { pool: PoolSource::Rotating(pool) }
+    }
+
+    fn pool(&self) -> PgPool {
+        match &self.pool {
+            PoolSource::Fixed(pool) => pool.clone(),
+            PoolSource::Rotating(pool) => pool.pool(),
+        }
+    }
+}
+
+impl JobBackend for PgJobBackend {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn enqueue<'a>(&'a self, job: NewJob) -> QueueFuture<'a, Job> {
+        Box::pin(async move {
+            let pool = self.pool();
+            let job = job.into_job();
+            let sql = format!(
+                r#"
+                INSERT INTO job_queue (id, queue, kind, payload, status, attempts, max_attempts, run_at, unique_key)
+                VALUES ($1, $2, $3, $4, 'pending', 0, $5, $6, $7)
+                ON CONFLICT (unique_key) WHERE status IN ('pending', 'running') DO NOTHING
+                RETURNING {}
+                "#,
+                JOB_COLUMNS
+            );
+            let inserted = sqlx::query_as::<_, Job>(&sql)
+                .bind(job.id)
+                .bind(&job.queue)
+                .bind(&job.kind)
+                .bind(&job.payload)
+                .bind(job.max_attempts)
+                .bind(job.run_at)
+                .bind(&job.unique_key)
+                .fetch_optional(&pool)
+                .await?;
+            if let Some(inserted) = inserted {
+                return Ok(inserted);
+            }
+
+            let sql = format!(
+                "SELECT {} FROM job_queue WHERE unique_key = $1 AND status IN ('pending', 'running')",
+                JOB_COLUMNS
+            );
+            Ok(sqlx::query_as::<_, Job>(&sql).bind(&job.unique_key).fetch_one(&pool).await?)
+        })
+    }
+
+    fn claim<'a>(&'a self, queues: &'a [String], worker: &'a str, limit: usize) -> QueueFuture<'a, Vec<Job>> {
+        Box::pin(async move {
+            let sql = format!(
+                r#"
+                UPDATE job_queue
+                SET status = 'running', attempts = attempts + 1, locked_by = $3,
+                    locked_at = NOW(), updated_at = NOW()
+                WHERE id IN (
+                    SELECT id FROM job_queue
+                    WHERE status = 'pending' AND run_at <= NOW() AND queue = ANY($1)
+                    ORDER BY run_at
+                    LIMIT $2
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING {}
+                "#,
+                JOB_COLUMNS
+            );
+            Ok(sqlx::query_as::<_, Job>(&sql)
+                .bind(queues)
+                .bind(limit as i64)
+                .bind(worker)
+                .fetch_all(&self.pool())
+                .await?)
+        })
+    }
+
+    fn heartbeat<'a>(&'a self, id: Uuid) -> QueueFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query("UPDATE job_queue SET locked_at = NOW() WHERE id = $1 AND status = 'running'")
+                .bind(id)
+                .execute(&self.pool())
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn complete<'a>(&'a self, id: Uuid) -> QueueFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                r#"
+                UPDATE job_queue
+                SET status = 'succeeded', last_error = NULL, locked_by = NULL, locked_at = NULL,
+                    updated_at = NOW(), finished_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(id)
+            .execute(&self.pool())
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn fail<'a>(&'a self, id: Uuid, error: &'a str, retry_at: Option<DateTime<Utc>>) -> QueueFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                r#"
+                UPDATE job_queue
+                SET status = CASE WHEN $3::TIMESTAMPTZ IS NULL THEN 'failed' ELSE 'pending' END,
+                    run_at = COALESCE($3, run_at),
+                    finished_at = CASE WHEN $3::TIMESTAMPTZ IS NULL THEN NOW() END,
+                    last_error = $2, locked_by = NULL, locked_at = NULL, updated_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(id)
+            .bind(error)
+            .bind(retry_at)
+            .execute(&self.pool())
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, id: Uuid) -> QueueFuture<'a, Option<Job>> {
+        Box::pin(async move {
+            let sql = format!("SELECT {} FROM job_queue WHERE id = $1", JOB_COLUMNS);
+            Ok(sqlx::query_as::<_, Job>(&sql).bind(id).fetch_optional(&self.pool()).await?)
+        })
+    }
+
+    fn list<'a>(&'a self, filter: &'a JobFilter) -> QueueFuture<'a, Vec<Job>> {
+        Box::pin(async move {
+            let sql = format!(
+                r#"
+                SELECT {} FROM job_queue
+                WHERE ($1::VARCHAR IS NULL OR queue = $1)
+                  AND ($2::VARCHAR IS NULL OR kind = $2)
+                  AND ($3::VARCHAR IS NULL OR status = $3)
+                ORDER BY created_at DESC
+                LIMIT $4 OFFSET $5
+                "#,
+                JOB_COLUMNS
+            );
+            Ok(sqlx::query_as::<_, Job>(&sql)
+                .bind(&filter.queue)
+                .bind(&filter.kind)
+                .bind(filter.status.map(|status| status.as_str()))
+                .bind(filter.limit())
+                .bind(filter.offset())
+                .fetch_all(&self.pool())
+                .await?)
+        })
+    }
+
+    fn stats<'a>(&'a self) -> QueueFuture<'a, Vec<QueueCount>> {
+        Box::pin(async move {
+            Ok(sqlx::query_as::<_, QueueCount>(
+                r#"
+                SELECT queue, status, COUNT(*) AS count, MIN(run_at) AS oldest_run_at
+                FROM job_queue
+                GROUP BY queue, status
+                ORDER BY queue, status
+                "#,
+            )
+            .fetch_all(&self.pool())
+            .await?)
+        })
+    }
+
+    fn retry<'a>(&'a self, id: Uuid) -> QueueFuture<'a, bool> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                r#"
+                UPDATE job_queue
+                SET status = 'pending', attempts = 0, run_at = NOW(), finished_at = NULL, updated_at = NOW()
+                WHERE id = $1 AND status IN ('failed', 'cancelled')
+                "#,
+            )
+            .bind(id)
+            .execute(&self.pool())
+            .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn cancel<'a>(&'a self, id: Uuid) -> QueueFuture<'a, bool> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                r#"
+                UPDATE job_queue
+                SET status = 'cancelled', updated_at = NOW(), finished_at = NOW()
+                WHERE id = $1 AND status = 'pending'
+                "#,
+            )
+            .bind(id)
+            .execute(&self.pool())
+            .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn recover<'a>(&'a self, stale_before: DateTime<Utc>) -> QueueFuture<'a, u64> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                r#"
+                UPDATE job_queue
+                SET status = 'pending', locked_by = NULL, locked_at = NULL, run_at = NOW(), updated_at = NOW()
+                WHERE status = 'running' AND locked_at < $1
+                "#,
+            )
+            .bind(stale_before)
+            .execute(&self.pool())
+            .await?;
+            Ok(result.rows_affected())
+        })
+    }
+
+    fn purge<'a>(&'a self, before: DateTime<Utc>) -> QueueFuture<'a, u64> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                "DELETE FROM job_queue WHERE status IN ('succeeded', 'cancelled') AND finished_at < $1",
+            )
+            .bind(before)
+            .execute(&self.pool())
+            .await?;
+            Ok(result.rows_affected())
+        })
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use uuid::Uuid;
+
+use crate::errors::{Error, Result};
+use super::{Job, JobBackend, JobFilter, JobStatus, NewJob, QueueCount, QueueFuture};
+
+/// Move a job id from one sorted set to another if it is still in the first;
+/// only one of several competing workers sees 1
+const MOVE_SCRIPT: &str = r#"
+if redis.call('ZREM', KEYS[1], ARGV[1]) == 1 then
+    redis.call('ZADD', KEYS[2], ARGV[2], ARGV[1])
+    return 1
+end
+return 0
+"#;
+
+/// Jobs in Redis.
+///
+/// Each job is a JSON value under `{prefix}job:{id}`. Pending ids wait in
+/// the sorted set `{prefix}ready:{queue}` scored by `run_at`, running ids in
+/// `{prefix}running` scored by their last heartbeat, and `{prefix}jobs`
+/// indexes every job by creation time for listing. `{prefix}unique:{key}`
+/// holds the id of the active job with that unique key.
+pub struct RedisJobBackend {
+    client: redis::Client,
+    prefix: String,
+    connection: tokio::sync::OnceCell<ConnectionManager>,
+    move_script: redis::Script,
+}
+
+impl RedisJobBackend {
+    /// Connect lazily to REDIS_URL, with keys under REDIS_JOB_PREFIX
+    pub fn from_env() -> Result<Self> {
+        let url = std::env::var("REDIS_URL")
+            .map_err(|_| Error::Config("REDIS_URL is required for the Redis job queue".to_string()))?;
+        let client = redis::Client::open(url).map_err(|e| Error::Config(format!("Invalid REDIS_URL: {}", e)))?;
+
+        Ok(Self {
+            client,
+            prefix: std::env::var("REDIS_JOB_PREFIX").unwrap_or_else(|_| "terrafusion:jobs:".to_string()),
+            connection: tokio::sync::OnceCell::new(),
+            move_script: redis::Script::new(MOVE_SCRIPT),
+        })
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager> {
+        self.connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+            .map_err(redis_error)
+    }
+
+    fn job_key(&self, id: Uuid) -> String {
+        format!("{}job:{}", self.prefix, id)
+    }
+
+    fn ready_key(&self, queue: &str) -> String {
+        format!("{}ready:{}", self.prefix, queue)
+    }
+
+    fn running_key(&self) -> String {
+        format!("{}running", self.prefix)
+    }
+
+    fn index_key(&self) -> String {
+        format!("{}jobs", self.prefix)
+    }
+
+    fn unique_key(&self, key: &str) -> String {
+        format!("{}unique:{}", self.prefix, key)
+    }
+
+    async fn load(&self, conn: &mut ConnectionManager, id: Uuid) -> Result<Option<Job>> {
+        let value: Option<String> = conn.get(self.job_key(id)).await.map_err(redis_error)?;
+        value.map(|value| decode(&value)).transpose()
+    }
+
+    async fn save(&self, conn: &mut ConnectionManager, job: &Job) -> Result<()> {
+        conn.set::<_, _, ()>(self.job_key(job.id), encode(job)?).await.map_err(redis_error)
+    }
+
+    /// Every job in the index, newest first, dropping ids whose value is gone
+    async fn load_all(&self, conn: &mut ConnectionManager) -> Result<Vec<Job>> {
+        let ids: Vec<String> = conn.zrevrange(self.index_key(), 0, -1).await.map_err(redis_error)?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let keys: Vec<String> = ids.iter().map(|id| format!("{}job:{}", self.prefix, id)).collect();
+        let values: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query_async(conn).await.map_err(redis_error)?;
+
+        let mut jobs = Vec::new();
+        let mut stale = Vec::new();
+        for (id, value) in ids.into_iter().zip(values) {
+            match value.map(|value| decode(&value)).transpose()? {
+                Some(job) => jobs.push(job),
+                None => stale.push(id),
+            }
+        }
+        if !stale.is_empty() {
+            conn.zrem::<_, _, ()>(self.index_key(), &stale).await.map_err(redis_error)?;
+        }
+        Ok(jobs)
+    }
+
+    async fn move_id(&self, conn: &mut ConnectionManager, from: &str, to: &str, id: Uuid, score: i64) -> Result<bool> {
+        let moved: i32 = self
+            .move_script
+            .key(from)
+            .key(to)
+            .arg(id.to_string())
+            .arg(score)
+            .invoke_async(conn)
+            .await
+            .map_err(redis_error)?;
+        Ok(moved == 1)
+    }
+
+    /// Release the unique key if it still points at this job
+    async fn release_unique(&self, conn: &mut ConnectionManager, job: &Job) -> Result<()> {
+        let Some(key) = &job.unique_key else {
+            return Ok(());
+        };
+        let holder: Option<String> = conn.get(self.unique_key(key)).await.map_err(redis_error)?;
+        if holder.as_deref() == Some(job.id.to_string().as_str()) {
+            conn.del::<_, ()>(self.unique_key(key)).await.map_err(redis_error)?;
+        }
+        Ok(())
+    }
+
+    /// Store the final state of a job that left the running set
+    async fn finish(&self, conn: &mut ConnectionManager, mut job: Job, status: JobStatus) -> Result<()> {
+        let now = Utc::now();
+        job.status = status;
+        job.locked_by = None;
+        job.locked_at = None;
+        job.updated_at = now;
+        job.finished_at = Some(now);
+        conn.zrem::<_, _, ()>(self.running_key(), job.id.to_string()).await.map_err(redis_error)?;
+        self.save(conn, &job).await?;
+        self.release_unique(conn, &job).await
+    }
+}
+
+fn redis_error(e: redis::RedisError) -> Error {
+    Error::ExternalService(format!("Redis job queue: {}", e))
+}
+
+fn encode(job: &Job) -> Result<String> {
+    serde_json::to_string(job).map_err(|e| Error::Serialization(e.to_string()))
+}
+
+fn decode(value: &str) -> Result<Job> {
+    serde_json::from_str(value).map_err(|e| Error::Serialization(e.to_string()))
+}
+
+impl JobBackend for RedisJobBackend {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    fn enqueue<'a>(&'a self, job: NewJob) -> QueueFuture<'a, Job> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let job = job.into_job();
+
+            if let Some(key) = &job.unique_key {
+                let claimed: bool = redis::cmd("SET")
+                    .arg(self.unique_key(key))
+                    .arg(job.id.to_string())
+                    .arg("NX")
+                    .query_async::<_, Option<String>>(&mut conn)
+                    .await
+                    .map_err(redis_error)?
+                    .is_some();
+                if !claimed {
+                    let holder: Option<String> = conn.get(self.unique_key(key)).await.map_err(redis_error)?;
+                    if let Some(existing) = holder.and_then(|id| Uuid::parse_str(&id).ok()) {
+                        if let Some(existing) = self.load(&mut conn, existing).await? {
+                            return Ok(existing);
+                        }
+                    }
+                    // The holder vanished; take the key over
+                    conn.set::<_, _, ()>(self.unique_key(key), job.id.to_string()).await.map_err(redis_error)?;
+                }
+            }
+
+            redis::pipe()
+                .atomic()
+                .set(self.job_key(job.id), encode(&job)?)
+                .ignore()
+                .zadd(self.index_key(), job.id.to_string(), job.created_at.timestamp_millis())
+                .ignore()
+                .zadd(self.ready_key(&job.queue), job.id.to_string(), job.run_at.timestamp_millis())
+                .ignore()
+                .query_async::<_, ()>(&mut conn)
+                .await
+                .map_err(redis_error)?;
+            Ok(job)
+        })
+    }
+
+    fn claim<'a>(&'a self, queues: &'a [String], worker: &'a str, limit: usize) -> QueueFuture<'a, Vec<Job>> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let now = Utc::now();
+            let mut claimed = Vec::new();
+
+            for queue in queues {
+                if claimed.len() >= limit {
+                    break;
+                }
+                let due: Vec<String> = conn
+                    .zrangebyscore_limit(self.ready_key(queue), "-inf", now.timestamp_millis(), 0, (limit - claimed.len()) as isize)
+                    .await
+                    .map_err(redis_error)?;
+
+                for id in due.iter().filter_map(|id| Uuid::parse_str(id).ok()) {
+                    if !self.move_id(&mut conn, &self.ready_key(queue), &self.running_key(), id, now.timestamp_millis()).await? {
+                        // Another worker got there first
+                        continue;
+                    }
+                    let Some(mut job) = self.load(&mut conn, id).await? else {
+                        conn.zrem::<_, _, ()>(self.running_key(), id.to_string()).await.map_err(redis_error)?;
+                        continue;
+                    };
+                    job.status = JobStatus::Running;
+                    job.attempts += 1;
+                    job.locked_by = Some(worker.to_string());
+                    job.locked_at = Some(now);
+                    job.updated_at = now;
+                    self.save(&mut conn, &job).await?;
+                    claimed.push(job);
+                }
+            }
+            Ok(claimed)
+        })
+    }
+
+    fn heartbeat<'a>(&'a self, id: Uuid) -> QueueFuture<'a, ()> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            // XX: a job recovered by another instance is not put back
+            redis::cmd("ZADD")
+                .arg(self.running_key())
+                .arg("XX")
+                .arg(Utc::now().timestamp_millis())
+                .arg(id.to_string())
+                .query_async::<_, ()>(&mut conn)
+                .await
+                .map_err(redis_error)
+        })
+    }
+
+    fn complete<'a>(&'a self, id: Uuid) -> QueueFuture<'a, ()> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let Some(mut job) = self.load(&mut conn, id).await? else {
+                return Ok(());
+            };
+            job.last_error = None;
+            self.finish(&mut conn, job, JobStatus::Succeeded).await
+        })
+    }
+
+    fn fail<'a>(&'a self, id: Uuid, error: &'a str, retry_at: Option<DateTime<Utc>>) -> QueueFuture<'a, ()> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let Some(mut job) = self.load(&mut conn, id).await? else {
+                return Ok(());
+            };
+            job.last_error = Some(error.to_string());
+
+            let Some(retry_at) = retry_at else {
+                return self.finish(&mut conn, job, JobStatus::Failed).await;
+            };
+            job.status = JobStatus::Pending;
+            job.run_at = retry_at;
+            job.locked_by = None;
+            job.locked_at = None;
+            job.updated_at = Utc::now();
+            self.save(&mut conn, &job).await?;
+            self.move_id(&mut conn, &self.running_key(), &self.ready_key(&job.queue), id, retry_at.timestamp_millis())
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, id: Uuid) -> QueueFuture<'a, Option<Job>> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            self.load(&mut conn, id).await
+        })
+    }
+
+    fn list<'a>(&'a self, filter: &'a JobFilter) -> QueueFuture<'a, Vec<Job>> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            Ok(self
+                .load_all(&mut conn)
+                .await?
+                .into_iter()
+                .filter(|job| filter.matches(job))
+                .skip(filter.offset() as usize)
+                .take(filter.limit() as usize)
+                .collect())
+        })
+    }
+
+    fn stats<'a>(&'a self) -> QueueFuture<'a, Vec<QueueCount>> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let mut counts: BTreeMap<(String, &'static str), QueueCount> = BTreeMap::new();
+            for job in self.load_all(&mut conn).await? {
+                let entry = counts.entry((job.queue.clone(), job.status.as_str())).or_insert_with(|| QueueCount {
+                    queue: job.queue.clone(),
+                    status: job.status,
+                    count: 0,
+                    oldest_run_at: None,
+                });
+                entry.count += 1;
+                entry.oldest_run_at = Some(entry.oldest_run_at.map_or(job.run_at, |oldest| oldest.min(job.run_at)));
+            }
+            Ok(counts.into_values().collect())
+        })
+    }
+
+    fn retry<'a>(&'a self, id: Uuid) -> QueueFuture<'a, bool> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let Some(mut job) = self.load(&mut conn, id).await? else {
+                return Ok(false);
+            };
+            if !matches!(job.status, JobStatus::Failed | JobStatus::Cancelled) {
+                return Ok(false);
+            }
+            let now = Utc::now();
+            job.status = JobStatus::Pending;
+            job.attempts = 0;
+            job.run_at = now;
+            job.finished_at = None;
+            job.updated_at = now;
+            if let Some(key) = &job.unique_key {
+                conn.set::<_, _, ()>(self.unique_key(key), id.to_string()).await.map_err(redis_error)?;
+            }
+            self.save(&mut conn, &job).await?;
+            conn.zadd::<_, _, _, ()>(self.ready_key(&job.queue), id.to_string(), now.timestamp_millis())
+                .await
+                .map_err(redis_error)?;
+            Ok(true)
+        })
+    }
+
+    fn cancel<'a>(&'a self, id: Uuid) -> QueueFuture<'a, bool> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let Some(mut job) = self.load(&mut conn, id).await? else {
+                return Ok(false);
+            };
+            let removed: u64 = conn.zrem(self.ready_key(&job.queue), id.to_string()).await.map_err(redis_error)?;
+            if removed == 0 {
+                // Claimed or finished in the meantime
+                return Ok(false);
+            }
+            let now = Utc::now();
+            job.status = JobStatus::Cancelled;
+            job.updated_at = now;
+            job.finished_at = Some(now);
+            self.save(&mut conn, &job).await?;
+            self.release_unique(&mut conn, &job).await?;
+            Ok(true)
+        })
+    }
+
+    fn recover<'a>(&'a self, stale_before: DateTime<Utc>) -> QueueFuture<'a, u64> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let stale: Vec<String> = conn
+                .zrangebyscore(self.running_key(), "-inf", stale_before.timestamp_millis())
+                .await
+                .map_err(redis_error)?;
+
+            let mut recovered = 0;
+            let now = Utc::now();
+            for id in stale.iter().filter_map(|id| Uuid::parse_str(id).ok()) {
+                let Some(mut job) = self.load(&mut conn, id).await? else {
+                    conn.zrem::<_, _, ()>(self.running_key(), id.to_string()).await.map_err(redis_error)?;
+                    continue;
+                };
+                if !self.move_id(&mut conn, &self.running_key(), &self.ready_key(&job.queue), id, now.timestamp_millis()).await? {
+                    continue;
+                }
+                job.status = JobStatus::Pending;
+                job.locked_by = None;
+                job.locked_at = None;
+                job.run_at = now;
+                job.updated_at = now;
+                self.save(&mut conn, &job).await?;
+                recovered += 1;
+            }
+            Ok(recovered)
+        })
+    }
+
+    fn purge<'a>(&'a self, before: DateTime<Utc>) -> QueueFuture<'a, u64> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let mut purged = 0;
+            for job in self.load_all(&mut conn).await? {
+                let expired = matches!(job.status, JobStatus::Succeeded | JobStatus::Cancelled)
+                    && job.finished_at.map_or(false, |finished| finished < before);
+                if expired {
+                    conn.del::<_, ()>(self.job_key(job.id)).await.map_err(redis_error)?;
+                    conn.zrem::<_, _, ()>(self.index_key(), job.id.to_string()).await.map_err(redis_error)?;
+                    purged += 1;
+                }
+            }
+            Ok(purged)
+        })
+    }
+}
@@ -10,11 +10,35 @@ pub mod models;
 pub mod telemetry;
 pub mod config;
 pub mod utils;
+pub mod secrets;
+pub mod encryption;
+pub mod tenancy;
 pub mod geo;
+pub mod features;
+pub mod maintenance;
+pub mod idempotency;
+pub mod masking;
+pub mod notifications;
+pub mod pagination;
+pub mod sessions;
+pub mod jobs;
+pub mod locks;
+pub mod schemas;
+pub mod usage;
+pub mod api_keys;
+pub mod datasets;
+pub mod temporal;
+pub mod negotiation;
+pub mod conditional;
+pub mod http_client;
+pub mod compression;
+#[cfg(feature = "tls")]
+pub mod tls;
 
 // Re-export common types for convenience
 pub use errors::{Error, Result};
 pub use database::DbPool;
+pub use tenancy::CountyContext;
 
 /// Version information for the TerraFusion Platform
 pub struct Version {
@@ -0,0 +1,147 @@
+//! Locks shared by every instance of a service.
+//!
+//! In-process guards (a `Mutex`, a set of running ids) only protect one
+//! instance. Work that must not run twice across a deployment, such as a
+//! sync operation for one pair or a scheduler tick, takes a named lock here
+//! first. Locks are non-blocking: `try_acquire` returns `None` when another
+//! holder has it, and the caller skips or reports the conflict.
+//!
+//! The default backend uses PostgreSQL session advisory locks, which the
+//! server releases by itself if the holder's connection drops.
+//! `LOCK_BACKEND=redis` (feature `redis-locks`) uses expiring Redis keys.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::errors::{Error, Result};
+
+pub mod postgres;
+#[cfg(feature = "redis-locks")]
+pub mod redis;
+
+pub use postgres::PgLockBackend;
+#[cfg(feature = "redis-locks")]
+pub use self::redis::RedisLockBackend;
+
+/// Boxed future returned by lock backends
+pub type LockFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// A lock held by this instance
+pub trait HeldLock: Send + Sync {
+    /// Give the lock up
+    fn release(self: Box<Self>) -> LockFuture<'static, ()>;
+}
+
+/// Storage of named locks
+pub trait LockBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Take the lock if nobody holds it
+    fn try_acquire<'a>(&'a self, key: &'a str) -> LockFuture<'a, Option<Box<dyn HeldLock>>>;
+}
+
+/// Named locks on the configured backend
+#[derive(Clone)]
+pub struct LockManager {
+    backend: Arc<dyn LockBackend>,
+}
+
+impl LockManager {
+    pub fn new(backend: Arc<dyn LockBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Backend from LOCK_BACKEND: `postgres` (default, using `postgres`) or `redis`
+    pub fn from_env(postgres: PgLockBackend) -> Result<Self> {
+        let backend: Arc<dyn LockBackend> = match std::env::var("LOCK_BACKEND").as_deref() {
+            Err(_) | Ok("postgres") => Arc::new(postgres),
+            #[cfg(feature = "redis-locks")]
+            Ok("redis") => Arc::new(RedisLockBackend::from_env()?),
+            #[cfg(not(feature = "redis-locks"))]
+            Ok("redis") => {
+                return Err(Error::Config("LOCK_BACKEND=redis requires the redis-locks feature".to_string()))
+            }
+            Ok(other) => return Err(Error::Config(format!("Unknown LOCK_BACKEND: {}", other))),
+        };
+        log::info!("Distributed lock backend: {}", backend.name());
+        Ok(Self::new(backend))
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    /// Take `key` if no instance holds it. The lock is released when the
+    /// guard is released or dropped.
+    pub async fn try_acquire(&self, key: &str) -> Result<Option<LockGuard>> {
+        let held = self.backend.try_acquire(key).await?;
+        Ok(held.map(|held| LockGuard { key: key.to_string(), held: Some(held) }))
+    }
+
+    /// Run `work` under `key`, or return `None` without running it when
+    /// another instance holds the lock
+    pub async fn with_lock<F, T>(&self, key: &str, work: F) -> Result<Option<T>>
+    where
+        F: Future<Output = T>,
+    {
+        let Some(guard) = self.try_acquire(key).await? else {
+            return Ok(None);
+        };
+        let output = work.await;
+        guard.release().await;
+        Ok(Some(output))
+    }
+}
+
+/// A held lock; released on `release` or, in the background, on drop
+pub struct LockGuard {
+    key: String,
+    held: Option<Box<dyn HeldLock>>,
+}
+
+impl LockGuard {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub async fn release(mut self) {
+        if let Some(held) = self.held.take() {
+            if let Err(e) = held.release().await {
+                log::error!("Failed to release lock {}: {}", self.key, e);
+            }
+        }
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        if let Some(held) = self.held.take() {
+            let key = std::mem::take(&mut self.key);
+            match tokio::runtime::Handle::try_current() {
+                Ok(runtime) => {
+                    runtime.spawn(async move {
+                        if let Err(e) = held.release().await {
+                            log::error!("Failed to release lock {}: {}", key, e);
+                        }
+                    });
+                }
+                // Without a runtime the backend's own expiry frees the lock
+                Err(_) => log::warn!("Lock {} dropped outside a runtime", key),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advisory_key() {
+        let key = postgres::advisory_key("sync_pair:4a8f");
+        assert_eq!(key, postgres::advisory_key("sync_pair:4a8f"));
+        assert_ne!(key, postgres::advisory_key("sync_pair:4a8e"));
+        assert_ne!(postgres::advisory_key("scheduler"), postgres::advisory_key("retention"));
+    }
+}
@@ -0,0 +1,90 @@
+use sha2::{Digest, Sha256};
+use sqlx::pool::PoolConnection;
+use sqlx::{PgPool, Postgres};
+
+use crate::database::RotatingPool;
+use super::{HeldLock, LockBackend, LockFuture};
+
+enum PoolSource {
+    Fixed(PgPool),
+    Rotating(RotatingPool),
+}
+
+/// Locks as PostgreSQL session advisory locks.
+///
+/// A held lock keeps its pool connection checked out, since the lock belongs
+/// to that session. If the instance dies the server ends the session and the
+/// lock is freed with it.
+pub struct PgLockBackend {
+    pool: PoolSource,
+}
+
+impl PgLockBackend {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool: PoolSource::Fixed(pool) }
+    }
+
+    /// Use whichever primary pool is current after credential rotation
+    pub fn rotating(pool: RotatingPool) -> Self {
+        Self { pool: PoolSource::Rotating(pool) }
+    }
+
+    fn pool(&self) -> PgPool {
+        match &self.pool {
+            PoolSource::Fixed(pool) => pool.clone(),
+            PoolSource::Rotating(pool) => pool.pool(),
+        }
+    }
+}
+
+/// Advisory lock id for a lock name: the first eight bytes of its SHA-256
+pub(crate) fn advisory_key(key: &str) -> i64 {
+    let digest = Sha256::digest(key.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    i64::from_be_bytes(bytes)
+}
+
+struct PgHeldLock {
+    key: i64,
+    connection: PoolConnection<Postgres>,
+}
+
+impl HeldLock for PgHeldLock {
+    fn release(self: Box<Self>) -> LockFuture<'static, ()> {
+        Box::pin(async move {
+            let PgHeldLock { key, mut connection } = *self;
+            let unlocked = sqlx::query_scalar::<_, bool>("SELECT pg_advisory_unlock($1)")
+                .bind(key)
+                .fetch_one(&mut *connection)
+                .await;
+            match unlocked {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    // Never hand a session that may still hold the lock back to the pool
+                    drop(connection.detach());
+                    Err(e.into())
+                }
+            }
+        })
+    }
+}
+
+impl LockBackend for PgLockBackend {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn try_acquire<'a>(&'a self, key: &'a str) -> LockFuture<'a, Option<Box<dyn HeldLock>>> {
+        Box::pin(async move {
+            let key = advisory_key(key);
+            let mut connection = self.pool().acquire().await?;
+            let acquired = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock($1)")
+                .bind(key)
+                .fetch_one(&mut *connection)
+                .await?;
+
+            Ok(acquired.then(|| Box::new(PgHeldLock { key, connection }) as Box<dyn HeldLock>))
+        })
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use redis::aio::ConnectionManager;
+use uuid::Uuid;
+
+use crate::errors::{Error, Result};
+use super::{HeldLock, LockBackend, LockFuture};
+
+/// Extend the key's expiry only while it still holds our token
+const RENEW_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+/// Delete the key only while it still holds our token
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Locks as expiring Redis keys.
+///
+/// `{prefix}lock:{name}` holds a random token for LOCK_TTL_SECONDS, and the
+/// holder renews it at a third of that. A holder that stops renewing (a
+/// crash, a partition) loses the lock when the key expires.
+pub struct RedisLockBackend {
+    client: redis::Client,
+    prefix: String,
+    ttl: Duration,
+    connection: tokio::sync::OnceCell<ConnectionManager>,
+}
+
+impl RedisLockBackend {
+    /// Connect lazily to REDIS_URL, with keys under REDIS_LOCK_PREFIX
+    pub fn from_env() -> Result<Self> {
+        let url = std::env::var("REDIS_URL")
+            .map_err(|_| Error::Config("REDIS_URL is required for Redis locks".to_string()))?;
+        let client = redis::Client::open(url).map_err(|e| Error::Config(format!("Invalid REDIS_URL: {}", e)))?;
+        let ttl = match std::env::var("LOCK_TTL_SECONDS") {
+            Ok(value) => value
+                .parse::<u64>()
+                .ok()
+                .filter(|seconds| *seconds >= 3)
+                .map(Duration::from_secs)
+                .ok_or_else(|| Error::Config("LOCK_TTL_SECONDS must be at least 3".to_string()))?,
+            Err(_) => Duration::from_secs(30),
+        };
+
+        Ok(Self {
+            client,
+            prefix: std::env::var("REDIS_LOCK_PREFIX").unwrap_or_else(|_| "terrafusion:".to_string()),
+            ttl,
+            connection: tokio::sync::OnceCell::new(),
+        })
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager> {
+        self.connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+            .map_err(redis_error)
+    }
+}
+
+fn redis_error(e: redis::RedisError) -> Error {
+    Error::ExternalService(format!("Redis locks: {}", e))
+}
+
+struct RedisHeldLock {
+    key: String,
+    token: String,
+    connection: ConnectionManager,
+    renewal: tokio::task::JoinHandle<()>,
+}
+
+impl HeldLock for RedisHeldLock {
+    fn release(self: Box<Self>) -> LockFuture<'static, ()> {
+        Box::pin(async move {
+            let RedisHeldLock { key, token, mut connection, renewal } = *self;
+            renewal.abort();
+            redis::Script::new(RELEASE_SCRIPT)
+                .key(&key)
+                .arg(&token)
+                .invoke_async::<_, i64>(&mut connection)
+                .await
+                .map_err(redis_error)?;
+            Ok(())
+        })
+    }
+}
+
+impl LockBackend for RedisLockBackend {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    fn try_acquire<'a>(&'a self, key: &'a str) -> LockFuture<'a, Option<Box<dyn HeldLock>>> {
+        Box::pin(async move {
+            let mut connection = self.connection().await?;
+            let key = format!("{}lock:{}", self.prefix, key);
+            let token = Uuid::new_v4().to_string();
+            let ttl_ms = self.ttl.as_millis() as u64;
+
+            let acquired: Option<String> = redis::cmd("SET")
+                .arg(&key)
+                .arg(&token)
+                .arg("NX")
+                .arg("PX")
+                .arg(ttl_ms)
+                .query_async(&mut connection)
+                .await
+                .map_err(redis_error)?;
+            if acquired.is_none() {
+                return Ok(None);
+            }
+
+            let renewal = {
+                let mut connection = connection.clone();
+                let (key, token) = (key.clone(), token.clone());
+                let period = self.ttl / 3;
+                tokio::spawn(async move {
+                    let script = redis::Script::new(RENEW_SCRIPT);
+                    let mut ticker = tokio::time::interval(period);
+                    ticker.tick().await;
+                    loop {
+                        ticker.tick().await;
+                        match script.key(&key).arg(&token).arg(ttl_ms).invoke_async::<_, i64>(&mut connection).await {
+                            Ok(1) => {}
+                            Ok(_) => {
+                                log::error!("Lost lock {}; another instance may now hold it", key);
+                                break;
+                            }
+                            Err(e) => log::warn!("Failed to renew lock {}: {}", key, e),
+                        }
+                    }
+                })
+            };
+
+            Ok(Some(Box::new(RedisHeldLock { key, token, connection, renewal }) as Box<dyn HeldLock>))
+        })
+    }
+}
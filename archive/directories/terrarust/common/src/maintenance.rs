@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::database::RotatingPool;
+use crate::errors::{Error, Result};
+use crate::tenancy::ALL_COUNTIES;
+
+/// Maintenance mode for the whole platform or a single county.
+///
+/// While a window is active, schedulers start no new operations for the
+/// scope and the gateway answers requests with 503. Operations that are
+/// already running are left to finish.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MaintenanceWindow {
+    /// County ID, or `*` for the whole platform
+    pub scope: String,
+    pub message: String,
+    pub started_by: String,
+    pub started_at: DateTime<Utc>,
+    /// Expected end, shown to users and sent as Retry-After; the window
+    /// stays active until explicitly disabled
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+impl MaintenanceWindow {
+    pub fn is_platform(&self) -> bool {
+        self.scope == ALL_COUNTIES
+    }
+
+    /// Seconds until the expected end, for the Retry-After header
+    pub fn retry_after(&self, now: DateTime<Utc>) -> Option<i64> {
+        self.ends_at.map(|ends_at| (ends_at - now).num_seconds().max(60))
+    }
+}
+
+/// All active maintenance windows
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    pub platform: Option<MaintenanceWindow>,
+    pub counties: BTreeMap<String, MaintenanceWindow>,
+}
+
+impl MaintenanceStatus {
+    pub fn from_windows(windows: Vec<MaintenanceWindow>) -> Self {
+        let mut status = Self::default();
+        for window in windows {
+            if window.is_platform() {
+                status.platform = Some(window);
+            } else {
+                status.counties.insert(window.scope.clone(), window);
+            }
+        }
+        status
+    }
+
+    /// The window affecting a county; platform maintenance applies to every county.
+    /// Requests without a county are only affected by platform maintenance.
+    pub fn active_for(&self, county_id: Option<&str>) -> Option<&MaintenanceWindow> {
+        self.platform
+            .as_ref()
+            .or_else(|| county_id.and_then(|county_id| self.counties.get(county_id)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.platform.is_none() && self.counties.is_empty()
+    }
+}
+
+/// Persistence for maintenance windows
+pub struct MaintenanceQueries;
+
+impl MaintenanceQueries {
+    pub async fn load(pool: &PgPool) -> Result<MaintenanceStatus> {
+        let windows = sqlx::query_as::<_, MaintenanceWindow>(
+            "SELECT scope, message, started_by, started_at, ends_at FROM maintenance_mode ORDER BY scope",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(MaintenanceStatus::from_windows(windows))
+    }
+
+    /// Start (or update) maintenance for a scope
+    pub async fn enable(pool: &PgPool, window: &MaintenanceWindow) -> Result<()> {
+        if window.message.trim().is_empty() {
+            return Err(Error::Validation("A maintenance message is required".to_string()));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO maintenance_mode (scope, message, started_by, started_at, ends_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (scope) DO UPDATE
+            SET message = EXCLUDED.message, started_by = EXCLUDED.started_by, ends_at = EXCLUDED.ends_at
+            "#,
+        )
+        .bind(&window.scope)
+        .bind(&window.message)
+        .bind(&window.started_by)
+        .bind(window.started_at)
+        .bind(window.ends_at)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// End maintenance for a scope; returns whether it was active
+    pub async fn disable(pool: &PgPool, scope: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM maintenance_mode WHERE scope = $1")
+            .bind(scope)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Shared, periodically refreshed view of the maintenance status.
+///
+/// Cloning is cheap; every clone observes the same updates.
+#[derive(Clone, Default)]
+pub struct MaintenanceHandle {
+    status: Arc<RwLock<Arc<MaintenanceStatus>>>,
+}
+
+impl MaintenanceHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current status snapshot
+    pub fn current(&self) -> Arc<MaintenanceStatus> {
+        self.status.read().expect("maintenance lock poisoned").clone()
+    }
+
+    /// Replace the status, logging windows that started or ended
+    pub fn replace(&self, status: MaintenanceStatus) {
+        let mut current = self.status.write().expect("maintenance lock poisoned");
+        if **current == status {
+            return;
+        }
+
+        if current.platform.is_none() && status.platform.is_some() {
+            log::warn!("Platform maintenance mode enabled");
+        } else if current.platform.is_some() && status.platform.is_none() {
+            log::info!("Platform maintenance mode disabled");
+        }
+        for county in status.counties.keys().filter(|c| !current.counties.contains_key(*c)) {
+            log::warn!("Maintenance mode enabled for county {}", county);
+        }
+        for county in current.counties.keys().filter(|c| !status.counties.contains_key(*c)) {
+            log::info!("Maintenance mode disabled for county {}", county);
+        }
+
+        *current = Arc::new(status);
+    }
+
+    /// Whether new work for the county should be held back
+    pub fn is_active(&self, county_id: Option<&str>) -> bool {
+        self.current().active_for(county_id).is_some()
+    }
+
+    /// Reload the status from the database now
+    pub async fn refresh(&self, pool: &PgPool) -> Result<()> {
+        let status = MaintenanceQueries::load(pool).await?;
+        self.replace(status);
+        Ok(())
+    }
+
+    /// Poll the database so changes made through another instance are picked up
+    pub fn spawn_refresh(&self, pool: RotatingPool, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let handle = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+                if let Err(e) = handle.refresh(&pool.read_pool()).await {
+                    log::error!("Failed to refresh maintenance status: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(scope: &str) -> MaintenanceWindow {
+        MaintenanceWindow {
+            scope: scope.to_string(),
+            message: "Upgrading".to_string(),
+            started_by: "admin".to_string(),
+            started_at: Utc::now(),
+            ends_at: None,
+        }
+    }
+
+    #[test]
+    fn test_active_for_scopes() {
+        let status = MaintenanceStatus::from_windows(vec![window("benton")]);
+        assert!(status.active_for(Some("benton")).is_some());
+        assert!(status.active_for(Some("franklin")).is_none());
+        assert!(status.active_for(None).is_none());
+
+        let status = MaintenanceStatus::from_windows(vec![window("benton"), window(ALL_COUNTIES)]);
+        assert!(status.active_for(Some("franklin")).unwrap().is_platform());
+        assert!(status.active_for(None).is_some());
+    }
+}
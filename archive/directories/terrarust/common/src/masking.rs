@@ -0,0 +1,196 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+/// Environment variable holding the key for hashes and tokens
+pub const MASKING_KEY_ENV: &str = "TERRAFUSION_MASKING_KEY";
+
+lazy_static! {
+    static ref GLOBAL_MASKER: Masker = Masker::from_env();
+}
+
+/// How a sensitive value is hidden
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum MaskStrategy {
+    /// Keyed SHA-256, hex encoded; equal inputs give equal hashes
+    Hash,
+    /// Replace letters and digits with `*`, keeping the last few and any
+    /// separators, e.g. `***-**-6789`
+    Partial {
+        #[serde(default = "default_keep_last")]
+        keep_last: usize,
+    },
+    /// Short opaque token, stable for the same input so records still join
+    Tokenize,
+    /// Drop the field entirely
+    Remove,
+}
+
+fn default_keep_last() -> usize {
+    4
+}
+
+impl MaskStrategy {
+    /// Strategy for a field mapping transformation name, e.g. `mask_partial`
+    pub fn from_transformation(name: &str) -> Option<Self> {
+        match name {
+            "mask_hash" => Some(Self::Hash),
+            "mask_partial" => Some(Self::Partial { keep_last: default_keep_last() }),
+            "tokenize" => Some(Self::Tokenize),
+            "redact" => Some(Self::Remove),
+            _ => None,
+        }
+    }
+}
+
+/// Applies mask strategies with a secret key.
+///
+/// Without a key, hashes of low-entropy values such as SSNs could be
+/// reversed by hashing every candidate, so the key must stay private and
+/// stable: changing it changes every hash and token.
+#[derive(Clone)]
+pub struct Masker {
+    key: Vec<u8>,
+}
+
+impl Masker {
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self { key: key.into() }
+    }
+
+    /// Key from `TERRAFUSION_MASKING_KEY`, or a random per-process key
+    /// (tokens then differ between restarts)
+    pub fn from_env() -> Self {
+        match std::env::var(MASKING_KEY_ENV) {
+            Ok(key) if !key.is_empty() => Self::new(key),
+            _ => {
+                log::warn!("{} is not set; masked hashes and tokens will change on restart", MASKING_KEY_ENV);
+                Self::new(rand::random::<[u8; 32]>().to_vec())
+            }
+        }
+    }
+
+    /// Process-wide masker used by field mapping transformations
+    pub fn global() -> &'static Masker {
+        &GLOBAL_MASKER
+    }
+
+    /// Masked value, or `None` when the field should be removed. Nulls stay null.
+    pub fn mask(&self, value: &Value, strategy: &MaskStrategy) -> Option<Value> {
+        if value.is_null() {
+            return (*strategy != MaskStrategy::Remove).then_some(Value::Null);
+        }
+        let text = match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+
+        match strategy {
+            MaskStrategy::Hash => Some(Value::String(self.digest("hash", &text))),
+            MaskStrategy::Partial { keep_last } => Some(Value::String(partial_mask(&text, *keep_last))),
+            MaskStrategy::Tokenize => Some(Value::String(format!("tok_{}", &self.digest("token", &text)[..16]))),
+            MaskStrategy::Remove => None,
+        }
+    }
+
+    fn digest(&self, purpose: &str, text: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.key);
+        hasher.update([0]);
+        hasher.update(purpose.as_bytes());
+        hasher.update([0]);
+        hasher.update(text.as_bytes());
+        hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+fn partial_mask(text: &str, keep_last: usize) -> String {
+    let total = text.chars().filter(|c| c.is_alphanumeric()).count();
+    let mut seen = 0;
+    text.chars()
+        .map(|c| {
+            if !c.is_alphanumeric() {
+                return c;
+            }
+            seen += 1;
+            if seen > total.saturating_sub(keep_last) { c } else { '*' }
+        })
+        .collect()
+}
+
+/// One field hidden by a redaction policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionRule {
+    /// Attribute name, matched case-insensitively
+    pub field: String,
+    #[serde(flatten)]
+    pub strategy: MaskStrategy,
+}
+
+/// Fields a county hides from public-records exports.
+///
+/// Applied to export output only; internal syncs keep the original values
+/// unless a field mapping masks them explicitly.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionPolicy {
+    #[serde(default)]
+    pub rules: Vec<RedactionRule>,
+}
+
+impl RedactionPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Redact the matching attributes of one record in place
+    pub fn apply(&self, record: &mut Map<String, Value>, masker: &Masker) {
+        for rule in &self.rules {
+            let matching: Vec<String> = record
+                .keys()
+                .filter(|key| key.eq_ignore_ascii_case(&rule.field))
+                .cloned()
+                .collect();
+
+            for key in matching {
+                match masker.mask(&record[&key], &rule.strategy) {
+                    Some(masked) => {
+                        record.insert(key, masked);
+                    }
+                    None => {
+                        record.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redaction_policy() {
+        let policy: RedactionPolicy = serde_json::from_value(json!({
+            "rules": [
+                { "field": "owner_name", "strategy": "tokenize" },
+                { "field": "ssn", "strategy": "partial" },
+                { "field": "phone", "strategy": "remove" },
+            ]
+        }))
+        .unwrap();
+        let masker = Masker::new("test-key");
+
+        let mut record = json!({"OWNER_NAME": "Jane Smith", "ssn": "123-45-6789", "phone": "555-0100", "acres": 2.5});
+        policy.apply(record.as_object_mut().unwrap(), &masker);
+
+        assert_eq!(record["ssn"], "***-**-6789");
+        assert!(record.get("phone").is_none());
+        assert_eq!(record["acres"], 2.5);
+        assert_eq!(record["OWNER_NAME"], masker.mask(&json!("Jane Smith"), &MaskStrategy::Tokenize).unwrap());
+        assert_ne!(record["OWNER_NAME"], Masker::new("other-key").mask(&json!("Jane Smith"), &MaskStrategy::Tokenize).unwrap());
+    }
+}
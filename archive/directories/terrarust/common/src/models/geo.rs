@@ -1,4 +1,5 @@
 use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use super::BaseModel;
@@ -23,7 +24,7 @@ pub struct GisExport {
 }
 
 /// GIS Export format
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum GisExportFormat {
     Shapefile,
@@ -50,7 +51,7 @@ impl AsRef<str> for GisExportFormat {
 }
 
 /// GIS Export status
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum GisExportStatus {
     Pending,
@@ -132,17 +133,20 @@ pub struct CountyGisConfig {
 }
 
 /// GIS Export creation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CreateGisExportRequest {
+    #[schemars(length(min = 1))]
     pub county_id: String,
     pub export_format: GisExportFormat,
+    #[schemars(length(min = 1))]
     pub layers: Vec<String>,
     pub area_of_interest: Option<serde_json::Value>, // GeoJSON geometry
+    #[serde(default)]
     pub parameters: serde_json::Value,
 }
 
 /// GIS Export job status response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GisExportStatusResponse {
     pub id: Uuid,
     pub status: GisExportStatus,
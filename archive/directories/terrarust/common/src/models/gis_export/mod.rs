@@ -31,6 +31,19 @@ pub struct LayerDefinition {
     pub metadata: serde_json::Value,
 }
 
+/// Saved export settings a county can run repeatedly
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportTemplate {
+    pub county_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub export_format: String,
+    pub layers: Vec<String>,
+    #[serde(default)]
+    pub parameters: serde_json::Value,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CountyConfiguration {
     pub county_id: String,
@@ -41,6 +54,61 @@ pub struct CountyConfiguration {
     pub rate_limits: RateLimits,
     pub default_parameters: serde_json::Value,
     pub authentication_required: bool,
+    /// Fields masked or removed in this county's exports
+    #[serde(default)]
+    pub export_redaction: crate::masking::RedactionPolicy,
+    /// County boundary as a GeoJSON or WKT polygon in WGS 84, used to check
+    /// that synced geometries are in the county
+    #[serde(default)]
+    pub boundary: Option<serde_json::Value>,
+    /// GIS portals (GeoServer, ArcGIS Online) that completed exports of
+    /// standard layers are pushed to
+    #[serde(default)]
+    pub publishing_targets: Vec<serde_json::Value>,
+    /// FTP and SFTP sites exports can be delivered to, by name
+    #[serde(default)]
+    pub delivery_destinations: Vec<serde_json::Value>,
+    /// Exports that need a supervisor's sign-off before they run; none do
+    /// when absent
+    #[serde(default)]
+    pub export_approval: Option<ExportApprovalPolicy>,
+    /// Monthly limits on sync operations and exports; unlimited when absent
+    #[serde(default)]
+    pub usage_quotas: crate::usage::UsageQuotas,
+}
+
+/// Thresholds above which an export waits for approval
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportApprovalPolicy {
+    /// Areas of interest larger than this need approval
+    pub max_area_km2: Option<f64>,
+    /// Exports of more layers than this need approval
+    pub max_layers: Option<usize>,
+    /// Layers that always need approval, such as ones with owner names
+    pub layers: Vec<String>,
+}
+
+impl ExportApprovalPolicy {
+    /// Why an export needs approval; empty when it does not
+    pub fn reasons(&self, area_km2: f64, layers: &[String]) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if let Some(max_area_km2) = self.max_area_km2.filter(|max| area_km2 > *max) {
+            reasons.push(format!("area of interest is {:.1} km², above {:.1} km²", area_km2, max_area_km2));
+        }
+        if let Some(max_layers) = self.max_layers.filter(|max| layers.len() > *max) {
+            reasons.push(format!("{} layers requested, above {}", layers.len(), max_layers));
+        }
+        let restricted: Vec<&str> = layers
+            .iter()
+            .filter(|layer| self.layers.contains(layer))
+            .map(String::as_str)
+            .collect();
+        if !restricted.is_empty() {
+            reasons.push(format!("includes restricted layers {}", restricted.join(", ")));
+        }
+        reasons
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,4 +137,27 @@ impl CountyConfiguration {
             .iter()
             .find(|l| l.id == layer_id)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approval_reasons() {
+        let policy = ExportApprovalPolicy {
+            max_area_km2: Some(500.0),
+            max_layers: Some(2),
+            layers: vec!["owners".to_string()],
+        };
+        let layers = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+        assert!(policy.reasons(120.0, &layers(&["parcels", "roads"])).is_empty());
+        assert_eq!(
+            policy.reasons(120.0, &layers(&["parcels", "owners"])),
+            vec!["includes restricted layers owners"]
+        );
+        assert_eq!(policy.reasons(4400.0, &layers(&["parcels", "roads", "buildings"])).len(), 2);
+        assert!(ExportApprovalPolicy::default().reasons(f64::MAX, &layers(&["owners"])).is_empty());
+    }
+}
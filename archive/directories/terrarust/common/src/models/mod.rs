@@ -2,6 +2,7 @@ pub mod sync;
 pub mod geo;
 pub mod audit;
 pub mod user;
+pub mod gis_export;
 
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
@@ -131,13 +132,77 @@ impl Default for SortDirection {
     }
 }
 
-/// Sort parameters
+impl SortDirection {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Ascending => "ASC",
+            Self::Descending => "DESC",
+        }
+    }
+}
+
+/// Sort parameters, read from `sort_field` and `sort_direction` query parameters
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SortParams {
+    #[serde(rename = "sort_field")]
     pub field: Option<String>,
+    #[serde(rename = "sort_direction")]
     pub direction: Option<SortDirection>,
 }
 
+/// A validated `ORDER BY` clause
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortOrder {
+    pub clause: String,
+    /// Whether this is the resource's default order (its first field, descending),
+    /// the only order cursor pagination supports
+    pub is_default: bool,
+}
+
+impl SortParams {
+    /// Build the `ORDER BY` clause for a resource.
+    ///
+    /// `allowed` maps public field names to SQL columns; the first entry is
+    /// the default sort. `tiebreak` (usually the primary key) keeps pages stable
+    /// when sort values repeat. Unknown fields are rejected, so the column
+    /// names never come from the request.
+    pub fn order_by(&self, allowed: &[(&str, &str)], tiebreak: &str) -> crate::Result<SortOrder> {
+        let (default_field, _) = allowed.first().expect("at least one sortable field");
+        let field = self.field.as_deref().unwrap_or(default_field);
+        let column = allowed
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, column)| *column)
+            .ok_or_else(|| {
+                let names: Vec<&str> = allowed.iter().map(|(name, _)| *name).collect();
+                crate::Error::Validation(format!("Cannot sort by {}; use one of: {}", field, names.join(", ")))
+            })?;
+        let direction = self.direction.unwrap_or_default();
+
+        Ok(SortOrder {
+            clause: format!(
+                "ORDER BY {column} {dir} NULLS LAST, {tiebreak} {dir}",
+                column = column,
+                dir = direction.as_sql(),
+                tiebreak = tiebreak
+            ),
+            is_default: field == *default_field && direction == SortDirection::Descending,
+        })
+    }
+}
+
+impl SortOrder {
+    /// Cursors follow the default order, so other sorts must page by offset
+    pub fn ensure_cursor_compatible(&self, pagination: &crate::pagination::Pagination) -> crate::Result<()> {
+        if pagination.is_cursor() && !self.is_default {
+            return Err(crate::Error::Validation(
+                "Cursor pagination only supports the default sort; use page numbers with sort_field".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Health status for services
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum HealthStatus {
@@ -206,4 +271,29 @@ impl<T> ApiResponse<T> {
             timestamp: Utc::now(),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_params_order_by() {
+        const FIELDS: &[(&str, &str)] = &[("created_at", "o.created_at"), ("status", "o.status")];
+
+        let order = SortParams::default().order_by(FIELDS, "o.id").unwrap();
+        assert_eq!(order.clause, "ORDER BY o.created_at DESC NULLS LAST, o.id DESC");
+        assert!(order.is_default);
+
+        let params: SortParams = serde_json::from_value(serde_json::json!({
+            "sort_field": "status",
+            "sort_direction": "asc"
+        }))
+        .unwrap();
+        let order = params.order_by(FIELDS, "o.id").unwrap();
+        assert_eq!(order.clause, "ORDER BY o.status ASC NULLS LAST, o.id ASC");
+        assert!(!order.is_default);
+
+        let params = SortParams { field: Some("id; DROP TABLE x".to_string()), direction: None };
+        assert!(params.order_by(FIELDS, "o.id").is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use super::BaseModel;
@@ -10,12 +11,16 @@ pub struct SyncPair {
     pub base: BaseModel,
     pub name: String,
     pub description: Option<String>,
+    #[schemars(length(min = 1))]
     pub source_system: String,
     pub source_config: serde_json::Value,
+    #[schemars(length(min = 1))]
     pub target_system: String,
     pub target_config: serde_json::Value,
+    #[schemars(length(min = 1))]
     pub county_id: String,
     pub is_active: bool,
+    #[schemars(range(min = 1))]
     pub sync_interval_minutes: i32,
     pub sync_conflict_strategy: SyncConflictStrategy,
     pub last_sync_time: Option<DateTime<Utc>>,
@@ -39,6 +44,9 @@ pub struct SyncOperation {
     pub error_message: Option<String>,
     pub custom_parameters: Option<serde_json::Value>,
     pub initiated_by: String,
+    /// Plain-English summary generated after the operation finishes
+    #[serde(default)]
+    pub narrative: Option<String>,
 }
 
 /// Sync record represents a single record processed during a sync
@@ -109,11 +117,15 @@ impl Default for SyncRecordStatus {
 }
 
 /// Sync conflict strategy enum
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum SyncConflictStrategy {
+    // Stored pairs spell the strategies with underscores
+    #[serde(alias = "SOURCE_WINS")]
     SourceWins,
+    #[serde(alias = "TARGET_WINS")]
     TargetWins,
+    #[serde(alias = "NEWER_WINS")]
     NewerWins,
     Manual,
 }
@@ -135,8 +147,9 @@ pub enum SyncConflictResolution {
 }
 
 /// SyncPair creation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CreateSyncPairRequest {
+    #[schemars(length(min = 1, max = 255))]
     pub name: String,
     pub description: Option<String>,
     pub source_system: String,
@@ -150,8 +163,9 @@ pub struct CreateSyncPairRequest {
 }
 
 /// SyncPair update request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UpdateSyncPairRequest {
+    #[schemars(length(min = 1, max = 255))]
     pub name: Option<String>,
     pub description: Option<String>,
     pub source_system: Option<String>,
@@ -159,12 +173,13 @@ pub struct UpdateSyncPairRequest {
     pub target_system: Option<String>,
     pub target_config: Option<serde_json::Value>,
     pub is_active: Option<bool>,
+    #[schemars(range(min = 1))]
     pub sync_interval_minutes: Option<i32>,
     pub sync_conflict_strategy: Option<SyncConflictStrategy>,
 }
 
 /// SyncOperation creation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CreateSyncOperationRequest {
     pub sync_pair_id: Uuid,
     pub custom_parameters: Option<serde_json::Value>,
@@ -0,0 +1,247 @@
+//! Content negotiation for entity and dataset resources.
+//!
+//! One URL can serve several audiences: API clients read plain JSON, GIS
+//! tools read GeoJSON, state open-data aggregators harvest JSON-LD, and a
+//! person following a portal link gets an HTML page. `?format=` wins over
+//! the `Accept` header; an `Accept` header naming none of the four, or none
+//! at all, gets JSON so existing clients see no change.
+//!
+//! The HTML view is a plain rendering of the JSON with the JSON-LD embedded,
+//! so crawlers that only fetch pages still find the linked data.
+
+use serde_json::{Map, Value};
+
+use crate::errors::{Error, Result};
+use crate::geo::normalize_geometry;
+
+/// Vocabularies used in JSON-LD documents
+pub const SCHEMA_ORG: &str = "https://schema.org/";
+pub const DCAT: &str = "http://www.w3.org/ns/dcat#";
+pub const DCTERMS: &str = "http://purl.org/dc/terms/";
+
+/// A representation of a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Representation {
+    Json,
+    GeoJson,
+    JsonLd,
+    Html,
+}
+
+impl Representation {
+    /// `?format=` value
+    pub fn parse(format: &str) -> Result<Self> {
+        match format.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "geojson" => Ok(Self::GeoJson),
+            "jsonld" | "json-ld" => Ok(Self::JsonLd),
+            "html" => Ok(Self::Html),
+            other => Err(Error::Validation(format!(
+                "Unsupported format: {} (use json, geojson, jsonld or html)",
+                other
+            ))),
+        }
+    }
+
+    /// The representation a media type asks for; wildcards mean JSON
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "application/json" | "*/*" | "application/*" => Some(Self::Json),
+            "application/geo+json" | "application/vnd.geo+json" => Some(Self::GeoJson),
+            "application/ld+json" => Some(Self::JsonLd),
+            "text/html" | "application/xhtml+xml" | "text/*" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    /// `format` if given, otherwise the `Accept` media type with the highest
+    /// quality; ties go to the one listed first
+    pub fn negotiate(accept: Option<&str>, format: Option<&str>) -> Result<Self> {
+        if let Some(format) = format {
+            return Self::parse(format);
+        }
+
+        let mut best: Option<(Self, f32)> = None;
+        for range in accept.unwrap_or_default().split(',') {
+            let mut parts = range.split(';').map(str::trim);
+            let Some(representation) = parts.next().and_then(|media_type| Self::from_media_type(&media_type.to_lowercase()))
+            else {
+                continue;
+            };
+            let quality = parts
+                .filter_map(|param| param.strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > 0.0 && best.map_or(true, |(_, best)| quality > best) {
+                best = Some((representation, quality));
+            }
+        }
+        Ok(best.map_or(Self::Json, |(representation, _)| representation))
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::GeoJson => "application/geo+json",
+            Self::JsonLd => "application/ld+json",
+            Self::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+/// A GeoJSON Feature of a record, with the record's `geometry_field` as its
+/// geometry and the other fields as properties. A geometry that cannot be
+/// read stays among the properties and the feature gets a null geometry.
+pub fn record_feature(id: &str, record: &Value, geometry_field: &str) -> Value {
+    let mut properties = record.as_object().cloned().unwrap_or_default();
+    let geometry = properties
+        .get(geometry_field)
+        .filter(|value| !value.is_null())
+        .and_then(|value| normalize_geometry(value).ok());
+    if geometry.is_some() {
+        properties.remove(geometry_field);
+    }
+    feature(id, geometry, properties)
+}
+
+/// A GeoJSON Feature
+pub fn feature(id: &str, geometry: Option<Value>, properties: Map<String, Value>) -> Value {
+    serde_json::json!({
+        "type": "Feature",
+        "id": id,
+        "geometry": geometry,
+        "properties": properties,
+    })
+}
+
+/// `schema:PropertyValue`s of a record's fields, for JSON-LD documents
+pub fn property_values(record: &Value) -> Vec<Value> {
+    record
+        .as_object()
+        .map(|fields| {
+            fields
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(name, value)| serde_json::json!({ "@type": "PropertyValue", "name": name, "value": value }))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// An HTML page showing `body` as nested lists, with `linked_data` embedded
+/// as JSON-LD
+pub fn html_document(title: &str, body: &Value, linked_data: Option<&Value>) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape_html(title)));
+    if let Some(linked_data) = linked_data {
+        // `<` is escaped so a value cannot close the script element
+        let json = linked_data.to_string().replace('<', "\\u003c");
+        html.push_str(&format!("<script type=\"application/ld+json\">{}</script>\n", json));
+    }
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!("<h1>{}</h1>\n", escape_html(title)));
+    push_html(&mut html, body);
+    html.push_str("\n</body>\n</html>\n");
+    html
+}
+
+fn push_html(html: &mut String, value: &Value) {
+    match value {
+        Value::Object(fields) => {
+            html.push_str("<dl>");
+            for (name, value) in fields {
+                html.push_str(&format!("<dt>{}</dt><dd>", escape_html(name)));
+                push_html(html, value);
+                html.push_str("</dd>");
+            }
+            html.push_str("</dl>");
+        }
+        Value::Array(items) => {
+            html.push_str("<ul>");
+            for item in items {
+                html.push_str("<li>");
+                push_html(html, item);
+                html.push_str("</li>");
+            }
+            html.push_str("</ul>");
+        }
+        // Only web links become anchors; anything else, `javascript:` included, stays text
+        Value::String(text) if text.starts_with("https://") || text.starts_with("http://") => {
+            let text = escape_html(text);
+            html.push_str(&format!("<a href=\"{}\">{}</a>", text, text));
+        }
+        Value::String(text) => html.push_str(&escape_html(text)),
+        Value::Null => html.push('—'),
+        other => html.push_str(&escape_html(&other.to_string())),
+    }
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(feature = "actix")]
+mod actix {
+    use actix_web::{http::header, HttpRequest, HttpResponse};
+
+    use super::Representation;
+    use crate::errors::Result;
+
+    impl Representation {
+        /// The representation a request asks for
+        pub fn from_request(req: &HttpRequest, format: Option<&str>) -> Result<Self> {
+            let accept = req.headers().get(header::ACCEPT).and_then(|value| value.to_str().ok());
+            Self::negotiate(accept, format)
+        }
+
+        /// A 200 response with `body` in this representation. Caches are told
+        /// the answer depends on `Accept`.
+        pub fn respond(&self, body: String) -> HttpResponse {
+            HttpResponse::Ok()
+                .content_type(self.content_type())
+                .insert_header((header::VARY, "Accept"))
+                .body(body)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_negotiate_and_render() {
+        let negotiate = |accept| Representation::negotiate(Some(accept), None).unwrap();
+        assert_eq!(negotiate("text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"), Representation::Html);
+        assert_eq!(negotiate("application/ld+json;q=0.5, application/geo+json"), Representation::GeoJson);
+        assert_eq!(negotiate("application/geo+json;q=0, application/json"), Representation::Json);
+        assert_eq!(negotiate("image/png"), Representation::Json);
+        assert_eq!(Representation::negotiate(None, None).unwrap(), Representation::Json);
+        assert_eq!(Representation::negotiate(Some("text/html"), Some("jsonld")).unwrap(), Representation::JsonLd);
+        assert!(Representation::negotiate(None, Some("kml")).is_err());
+
+        let record = json!({ "apn": "1-234", "geometry": "POINT(-119.2 46.2)" });
+        let feature = record_feature("1-234", &record, "geometry");
+        assert_eq!(feature["geometry"]["type"], "Point");
+        assert_eq!(feature["properties"], json!({ "apn": "1-234" }));
+        assert_eq!(record_feature("x", &json!({ "geometry": "nowhere" }), "geometry")["geometry"], Value::Null);
+
+        let html = html_document("<Parcel>", &json!({ "owner": "O'Neil & Sons", "link": "javascript:alert(1)" }), Some(&json!({ "name": "</script>" })));
+        assert!(html.contains("<h1>&lt;Parcel&gt;</h1>"));
+        assert!(html.contains("O&#39;Neil &amp; Sons"));
+        assert!(!html.contains("href=\"javascript"));
+        assert!(html.contains("\\u003c/script>"));
+    }
+}
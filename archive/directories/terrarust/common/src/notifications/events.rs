@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::errors::{Error, Result};
+use crate::http_client::HttpClient;
+use crate::secrets::azure::managed_identity_token;
+use super::{ChannelKind, Message, Notification, NotificationChannel, SendFuture};
+
+const EVENT_GRID_RESOURCE: &str = "https://eventgrid.azure.net";
+const GCP_TOKEN_URL: &str = "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+const PUBSUB_API: &str = "https://pubsub.googleapis.com/v1";
+
+/// Tokens are renewed this long before they expire
+const TOKEN_MARGIN: Duration = Duration::from_secs(300);
+
+/// A topic on one of the supported cloud event services, told apart by the
+/// shape of its name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Topic<'a> {
+    /// `arn:aws:sns:{region}:{account}:{name}`
+    Sns { arn: &'a str, region: &'a str },
+    /// The topic endpoint, `https://{topic}.{region}.eventgrid.azure.net/api/events`
+    EventGrid { endpoint: &'a str },
+    /// `projects/{project}/topics/{topic}`
+    PubSub { name: &'a str },
+}
+
+impl<'a> Topic<'a> {
+    pub fn parse(topic: &'a str) -> Result<Self> {
+        if let Some(rest) = topic.strip_prefix("arn:") {
+            let parts: Vec<&str> = rest.splitn(5, ':').collect();
+            return match parts.as_slice() {
+                [_, "sns", region, _, name] if !region.is_empty() && !name.is_empty() => {
+                    Ok(Self::Sns { arn: topic, region: *region })
+                }
+                _ => Err(Error::Config(format!("'{}' is not an SNS topic ARN", topic))),
+            };
+        }
+        if topic.starts_with("https://") {
+            return Ok(Self::EventGrid { endpoint: topic });
+        }
+        let parts: Vec<&str> = topic.split('/').collect();
+        if matches!(parts.as_slice(), ["projects", project, "topics", name] if !project.is_empty() && !name.is_empty()) {
+            return Ok(Self::PubSub { name: topic });
+        }
+        Err(Error::Config(format!(
+            "Unrecognized event topic '{}': expected an SNS ARN, an Event Grid endpoint or projects/{{project}}/topics/{{topic}}",
+            topic
+        )))
+    }
+}
+
+/// The notification as a CloudEvents 1.0 event. Facts become snake_case
+/// fields of `data`, so "Sync pair" is read as `data.sync_pair`.
+pub fn cloud_event(notification: &Notification) -> Value {
+    let kind = serde_json::to_value(notification.kind).unwrap_or(Value::Null);
+    let kind = kind.as_str().unwrap_or_default();
+    let source = match &notification.county_id {
+        Some(county_id) => format!("/terrafusion/counties/{}", county_id),
+        None => "/terrafusion".to_string(),
+    };
+
+    let mut data = Map::new();
+    data.insert("county_id".to_string(), json!(notification.county_id));
+    data.insert("title".to_string(), json!(notification.title));
+    data.insert("message".to_string(), json!(notification.message));
+    for (name, value) in &notification.facts {
+        data.insert(name.to_lowercase().replace(' ', "_"), json!(value));
+    }
+
+    json!({
+        "specversion": "1.0",
+        "id": Uuid::new_v4().to_string(),
+        "source": source,
+        "type": format!("com.terrafusion.{}", kind),
+        "time": notification.occurred_at.to_rfc3339(),
+        "datacontenttype": "application/json",
+        "data": data,
+    })
+}
+
+struct CachedToken {
+    value: String,
+    expires_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct GcpToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Publishes each notification as a CloudEvent to AWS SNS, Azure Event Grid
+/// or Google Pub/Sub. There are no keys to configure: requests carry the
+/// identity of the instance or container the service runs as, which needs
+/// `sns:Publish`, the EventGrid Data Sender role, or `roles/pubsub.publisher`.
+/// Event Grid topics must accept the CloudEvents v1.0 schema.
+pub struct EventBusChannel {
+    client: HttpClient,
+    /// User-assigned managed identity on Azure, from AZURE_CLIENT_ID
+    azure_client_id: Option<String>,
+    tokens: Mutex<HashMap<&'static str, CachedToken>>,
+    #[cfg(feature = "aws-sns")]
+    aws: tokio::sync::OnceCell<aws_config::SdkConfig>,
+}
+
+impl EventBusChannel {
+    pub fn new(client: HttpClient) -> Self {
+        Self {
+            client,
+            azure_client_id: std::env::var("AZURE_CLIENT_ID").ok(),
+            tokens: Mutex::new(HashMap::new()),
+            #[cfg(feature = "aws-sns")]
+            aws: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    async fn publish(&self, topic: &str, event: &Value) -> Result<()> {
+        match Topic::parse(topic)? {
+            Topic::Sns { arn, region } => self.publish_sns(arn, region, event).await,
+            Topic::EventGrid { endpoint } => self.publish_event_grid(endpoint, event).await,
+            Topic::PubSub { name } => self.publish_pubsub(name, event).await,
+        }
+    }
+
+    #[cfg(feature = "aws-sns")]
+    async fn publish_sns(&self, arn: &str, region: &str, event: &Value) -> Result<()> {
+        use aws_sdk_sns::types::MessageAttributeValue;
+
+        let shared = self.aws.get_or_init(aws_config::load_from_env).await;
+        let config = aws_sdk_sns::config::Builder::from(shared)
+            .region(aws_sdk_sns::config::Region::new(region.to_string()))
+            .build();
+        let attribute = |value: &Value| {
+            MessageAttributeValue::builder()
+                .data_type("String")
+                .string_value(value.as_str().unwrap_or_default())
+                .build()
+        };
+
+        let mut request = aws_sdk_sns::Client::from_conf(config)
+            .publish()
+            .topic_arn(arn)
+            .message(event.to_string())
+            .message_attributes("type", attribute(&event["type"]));
+        if event["data"]["county_id"].is_string() {
+            request = request.message_attributes("county_id", attribute(&event["data"]["county_id"]));
+        }
+        request
+            .send()
+            .await
+            .map_err(|e| Error::ExternalService(format!("SNS publish to {} failed: {}", arn, e)))?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "aws-sns"))]
+    async fn publish_sns(&self, arn: &str, _region: &str, _event: &Value) -> Result<()> {
+        Err(Error::Config(format!("Cannot publish to {}: built without the 'aws-sns' feature", arn)))
+    }
+
+    async fn publish_event_grid(&self, endpoint: &str, event: &Value) -> Result<()> {
+        let token = self.token("azure").await?;
+        let response = self
+            .client
+            .post(endpoint)
+            .bearer_auth(token)
+            .header(reqwest::header::CONTENT_TYPE, "application/cloudevents-batch+json; charset=utf-8")
+            .body(json!([event]).to_string())
+            .send()
+            .await?;
+        check(response, "Event Grid").await
+    }
+
+    async fn publish_pubsub(&self, name: &str, event: &Value) -> Result<()> {
+        let mut attributes = json!({ "ce-type": event["type"], "ce-source": event["source"] });
+        if let Some(county_id) = event["data"]["county_id"].as_str() {
+            attributes["county_id"] = json!(county_id);
+        }
+        let message = json!({
+            "data": base64::engine::general_purpose::STANDARD.encode(event.to_string()),
+            "attributes": attributes,
+        });
+
+        let token = self.token("gcp").await?;
+        let response = self
+            .client
+            .post(format!("{}/{}:publish", PUBSUB_API, name))
+            .bearer_auth(token)
+            .json(&json!({ "messages": [message] }))
+            .send()
+            .await?;
+        check(response, "Pub/Sub").await
+    }
+
+    /// A cached access token for `cloud`, fetched again shortly before it expires
+    async fn token(&self, cloud: &'static str) -> Result<String> {
+        let mut tokens = self.tokens.lock().await;
+        if let Some(token) = tokens.get(cloud).filter(|token| token.expires_at > Instant::now()) {
+            return Ok(token.value.clone());
+        }
+
+        let (value, lifetime) = match cloud {
+            "azure" => {
+                let token = managed_identity_token(&self.client, EVENT_GRID_RESOURCE, self.azure_client_id.as_deref()).await?;
+                let lifetime = token.expires_in.as_deref().and_then(|s| s.parse().ok()).unwrap_or(3600);
+                (token.access_token, lifetime)
+            }
+            _ => {
+                let response = self.client.get(GCP_TOKEN_URL).header("Metadata-Flavor", "Google").send().await?;
+                if !response.status().is_success() {
+                    return Err(Error::ExternalService(format!(
+                        "GCP metadata token request failed with status {}",
+                        response.status()
+                    )));
+                }
+                let token: GcpToken = response.json().await?;
+                (token.access_token, token.expires_in)
+            }
+        };
+
+        let expires_at = Instant::now() + Duration::from_secs(lifetime).saturating_sub(TOKEN_MARGIN);
+        tokens.insert(cloud, CachedToken { value: value.clone(), expires_at });
+        Ok(value)
+    }
+}
+
+async fn check(response: reqwest::Response, service: &str) -> Result<()> {
+    if response.status().is_success() {
+        return Ok(());
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::ExternalService(format!("{} returned status {}: {}", service, status, body.trim())))
+}
+
+impl NotificationChannel for EventBusChannel {
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::EventBus
+    }
+
+    /// One event per notification, even for digests, so subscribers see
+    /// the same stream whatever the rule's mode
+    fn send<'a>(&'a self, destinations: &'a [String], message: &'a Message) -> SendFuture<'a> {
+        Box::pin(async move {
+            for notification in &message.items {
+                let event = cloud_event(notification);
+                for topic in destinations {
+                    self.publish(topic, &event).await?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topics_and_events() {
+        assert_eq!(
+            Topic::parse("arn:aws:sns:us-west-2:123456789012:terrafusion-events").unwrap(),
+            Topic::Sns { arn: "arn:aws:sns:us-west-2:123456789012:terrafusion-events", region: "us-west-2" }
+        );
+        assert!(matches!(
+            Topic::parse("https://benton.westus2-1.eventgrid.azure.net/api/events").unwrap(),
+            Topic::EventGrid { .. }
+        ));
+        assert!(matches!(Topic::parse("projects/county-gis/topics/sync").unwrap(), Topic::PubSub { .. }));
+        assert!(Topic::parse("arn:aws:sqs:us-west-2:123456789012:queue").is_err());
+        assert!(Topic::parse("sync-events").is_err());
+
+        let event = cloud_event(&Notification::operation_failed("benton", "CAMA", 7, "timeout"));
+        assert_eq!(event["type"], "com.terrafusion.operation_failed");
+        assert_eq!(event["source"], "/terrafusion/counties/benton");
+        assert_eq!(event["data"]["sync_pair"], "CAMA");
+        assert_eq!(event["data"]["operation"], "7");
+    }
+}
@@ -0,0 +1,729 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::errors::{Error, Result};
+use crate::http_client::{HttpClient, HttpClientSettings};
+
+pub mod smtp;
+pub mod teams;
+pub mod slack;
+pub mod events;
+
+pub use smtp::{DirectEmail, EmailAttachment, SmtpChannel, SmtpSettings};
+pub use teams::TeamsChannel;
+pub use slack::SlackChannel;
+pub use events::EventBusChannel;
+
+/// Boxed future returned by notification channels
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Events that can trigger a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    OperationFailed,
+    ExportCompleted,
+    ConflictsFound,
+    DiskNearlyFull,
+    PublishingFailed,
+    DeliveryFailed,
+    ApprovalRequested,
+    OperationStarted,
+    OperationCompleted,
+    ExportStarted,
+    ExportFailed,
+    SchemaDrift,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 12] = [
+        Self::OperationStarted,
+        Self::OperationCompleted,
+        Self::OperationFailed,
+        Self::ConflictsFound,
+        Self::ExportStarted,
+        Self::ExportCompleted,
+        Self::ExportFailed,
+        Self::ApprovalRequested,
+        Self::PublishingFailed,
+        Self::DeliveryFailed,
+        Self::DiskNearlyFull,
+        Self::SchemaDrift,
+    ];
+
+    /// Human-readable label used in subjects and digests
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::OperationFailed => "Sync operation failed",
+            Self::ExportCompleted => "Export completed",
+            Self::ConflictsFound => "Sync conflicts found",
+            Self::DiskNearlyFull => "Disk nearly full",
+            Self::PublishingFailed => "Export publishing failed",
+            Self::DeliveryFailed => "Export delivery failed",
+            Self::ApprovalRequested => "Export awaiting approval",
+            Self::OperationStarted => "Sync operation started",
+            Self::OperationCompleted => "Sync operation completed",
+            Self::ExportStarted => "Export started",
+            Self::ExportFailed => "Export failed",
+            Self::SchemaDrift => "Schema drift detected",
+        }
+    }
+
+    /// Whether the event needs someone's attention
+    pub fn is_problem(&self) -> bool {
+        !matches!(
+            self,
+            Self::ExportCompleted | Self::OperationStarted | Self::OperationCompleted | Self::ExportStarted
+        )
+    }
+}
+
+/// Delivery channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    Email,
+    Teams,
+    Slack,
+    /// AWS SNS, Azure Event Grid or Google Pub/Sub topics
+    EventBus,
+}
+
+/// Send each notification as it happens or batch them into a periodic digest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryMode {
+    #[default]
+    Immediate,
+    Digest,
+}
+
+/// Something that happened and may be worth telling people about
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub kind: EventKind,
+    /// County the event belongs to; platform-wide events go to the default recipients only
+    pub county_id: Option<String>,
+    pub title: String,
+    pub message: String,
+    /// Extra key/value lines shown under the message
+    pub facts: Vec<(String, String)>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl Notification {
+    pub fn new(kind: EventKind, county_id: Option<&str>, title: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            county_id: county_id.map(str::to_string),
+            title: title.into(),
+            message: message.into(),
+            facts: Vec::new(),
+            occurred_at: Utc::now(),
+        }
+    }
+
+    pub fn fact(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.facts.push((name.into(), value.to_string()));
+        self
+    }
+
+    pub fn operation_failed(county_id: &str, sync_pair: &str, operation_id: impl ToString, error: &str) -> Self {
+        Self::new(
+            EventKind::OperationFailed,
+            Some(county_id),
+            format!("Sync '{}' failed", sync_pair),
+            error,
+        )
+        .fact("Sync pair", sync_pair)
+        .fact("Operation", operation_id)
+    }
+
+    pub fn operation_started(county_id: &str, sync_pair: &str, operation_id: impl ToString) -> Self {
+        Self::new(
+            EventKind::OperationStarted,
+            Some(county_id),
+            format!("Sync '{}' started", sync_pair),
+            "A sync operation has started.",
+        )
+        .fact("Sync pair", sync_pair)
+        .fact("Operation", operation_id)
+    }
+
+    pub fn operation_completed(county_id: &str, sync_pair: &str, operation_id: impl ToString, succeeded: i64, failed: i64) -> Self {
+        Self::new(
+            EventKind::OperationCompleted,
+            Some(county_id),
+            format!("Sync '{}' completed", sync_pair),
+            format!("{} record(s) synced, {} failed.", succeeded, failed),
+        )
+        .fact("Sync pair", sync_pair)
+        .fact("Operation", operation_id)
+        .fact("Records succeeded", succeeded)
+        .fact("Records failed", failed)
+    }
+
+    pub fn conflicts_found(county_id: &str, sync_pair: &str, operation_id: impl ToString, conflicts: u64) -> Self {
+        Self::new(
+            EventKind::ConflictsFound,
+            Some(county_id),
+            format!("{} conflict(s) in sync '{}'", conflicts, sync_pair),
+            "Records changed in both systems and need review.",
+        )
+        .fact("Sync pair", sync_pair)
+        .fact("Operation", operation_id)
+        .fact("Conflicts", conflicts)
+    }
+
+    pub fn export_completed(county_id: &str, export_id: impl ToString, format: &str, result_url: Option<&str>) -> Self {
+        let notification = Self::new(
+            EventKind::ExportCompleted,
+            Some(county_id),
+            format!("{} export ready", format),
+            "A GIS export has finished and is ready to download.",
+        )
+        .fact("Export", export_id)
+        .fact("Format", format);
+
+        match result_url {
+            Some(url) => notification.fact("Download", url),
+            None => notification,
+        }
+    }
+
+    pub fn export_started(county_id: &str, export_id: impl ToString, format: &str) -> Self {
+        Self::new(
+            EventKind::ExportStarted,
+            Some(county_id),
+            format!("{} export started", format),
+            "A GIS export is being generated.",
+        )
+        .fact("Export", export_id)
+        .fact("Format", format)
+    }
+
+    pub fn export_failed(county_id: &str, export_id: impl ToString, format: &str, error: &str) -> Self {
+        Self::new(
+            EventKind::ExportFailed,
+            Some(county_id),
+            format!("{} export failed", format),
+            error,
+        )
+        .fact("Export", export_id)
+        .fact("Format", format)
+    }
+
+    pub fn publishing_failed(county_id: &str, export_id: impl ToString, target: &str, error: &str) -> Self {
+        Self::new(
+            EventKind::PublishingFailed,
+            Some(county_id),
+            format!("Export could not be published to {}", target),
+            error,
+        )
+        .fact("Export", export_id)
+        .fact("Target", target)
+    }
+
+    pub fn delivery_failed(county_id: &str, export_id: impl ToString, destination: &str, attempts: u32, error: &str) -> Self {
+        Self::new(
+            EventKind::DeliveryFailed,
+            Some(county_id),
+            format!("Export could not be delivered to {}", destination),
+            error,
+        )
+        .fact("Export", export_id)
+        .fact("Destination", destination)
+        .fact("Attempts", attempts)
+    }
+
+    pub fn approval_requested(county_id: &str, export_id: impl ToString, requested_by: &str, reasons: &[String]) -> Self {
+        Self::new(
+            EventKind::ApprovalRequested,
+            Some(county_id),
+            format!("Export by {} needs approval", requested_by),
+            format!("The export will not run until it is approved: {}.", reasons.join("; ")),
+        )
+        .fact("Export", export_id)
+        .fact("Requested by", requested_by)
+    }
+
+    pub fn schema_drift(county_id: &str, sync_pair: &str, sync_pair_id: impl ToString, problems: &[String]) -> Self {
+        Self::new(
+            EventKind::SchemaDrift,
+            Some(county_id),
+            format!("Mapped fields changed for sync '{}'", sync_pair),
+            format!("The next sync is likely to fail until the mappings are updated: {}.", problems.join("; ")),
+        )
+        .fact("Sync pair", sync_pair)
+        .fact("Sync pair ID", sync_pair_id)
+    }
+
+    pub fn disk_nearly_full(mount_point: &Path, used_percent: f64, available_bytes: u64) -> Self {
+        Self::new(
+            EventKind::DiskNearlyFull,
+            None,
+            format!("Disk {} is {:.0}% full", mount_point.display(), used_percent),
+            "Exports, backups and logs may start failing when the disk fills up.",
+        )
+        .fact("Mount point", mount_point.display())
+        .fact("Free space", format!("{:.1} GB", available_bytes as f64 / (1024.0 * 1024.0 * 1024.0)))
+    }
+}
+
+/// Rendered message handed to a channel
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub subject: String,
+    /// Whether any included notification needs attention (used for card colors)
+    pub is_problem: bool,
+    pub items: Vec<Notification>,
+}
+
+impl Message {
+    pub fn single(notification: Notification) -> Self {
+        Self {
+            subject: format!("[TerraFusion] {}", notification.title),
+            is_problem: notification.kind.is_problem(),
+            items: vec![notification],
+        }
+    }
+
+    pub fn digest(items: Vec<Notification>) -> Self {
+        Self {
+            subject: format!("[TerraFusion] {} notification(s) since the last digest", items.len()),
+            is_problem: items.iter().any(|n| n.kind.is_problem()),
+            items,
+        }
+    }
+
+    /// Plain-text body, one block per notification
+    pub fn to_text(&self) -> String {
+        self.items
+            .iter()
+            .map(|n| {
+                let mut block = format!("{} ({})\n{}", n.title, n.occurred_at.format("%Y-%m-%d %H:%M UTC"), n.message);
+                for (name, value) in &n.facts {
+                    block.push_str(&format!("\n  {}: {}", name, value));
+                }
+                block
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// A way of delivering messages
+pub trait NotificationChannel: Send + Sync + 'static {
+    fn kind(&self) -> ChannelKind;
+
+    /// Deliver a message to the given destinations (addresses or webhook URLs)
+    fn send<'a>(&'a self, destinations: &'a [String], message: &'a Message) -> SendFuture<'a>;
+}
+
+/// Which events go where
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationRule {
+    pub event: EventKind,
+    pub channels: Vec<ChannelKind>,
+    #[serde(default)]
+    pub mode: DeliveryMode,
+    /// Limit the rule to these counties; empty means every county
+    #[serde(default)]
+    pub counties: Vec<String>,
+}
+
+impl NotificationRule {
+    fn matches(&self, notification: &Notification) -> bool {
+        self.event == notification.kind
+            && (self.counties.is_empty()
+                || notification.county_id.as_ref().map_or(false, |county| self.counties.contains(county)))
+    }
+}
+
+/// Recipients for one county
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CountyRecipients {
+    pub emails: Vec<String>,
+    pub teams_webhook_url: Option<String>,
+    pub slack_webhook_url: Option<String>,
+    /// Sender of emails about this county's exports, instead of the SMTP
+    /// `from` address
+    pub email_from: Option<String>,
+    /// Cloud topics for this county's events, such as its own SNS topic
+    pub event_topics: Vec<String>,
+}
+
+/// The `[notifications]` table of the TERRAFUSION_CONFIG file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub smtp: Option<SmtpSettings>,
+    /// Default recipients, used for platform-wide events and counties without their own
+    pub emails: Vec<String>,
+    pub teams_webhook_url: Option<String>,
+    pub slack_webhook_url: Option<String>,
+    /// SNS topic ARNs, Event Grid topic endpoints or Pub/Sub topic names
+    pub event_topics: Vec<String>,
+    pub digest_interval_minutes: u64,
+    pub disk_threshold_percent: f64,
+    pub rules: Vec<NotificationRule>,
+    pub counties: HashMap<String, CountyRecipients>,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        let all_channels = vec![ChannelKind::Email, ChannelKind::Teams, ChannelKind::Slack];
+        let rule = |event, mode| NotificationRule {
+            event,
+            channels: all_channels.clone(),
+            mode,
+            counties: Vec::new(),
+        };
+
+        Self {
+            enabled: false,
+            smtp: None,
+            emails: Vec::new(),
+            teams_webhook_url: None,
+            slack_webhook_url: None,
+            event_topics: Vec::new(),
+            digest_interval_minutes: 60,
+            disk_threshold_percent: 90.0,
+            rules: vec![
+                rule(EventKind::OperationFailed, DeliveryMode::Immediate),
+                rule(EventKind::DiskNearlyFull, DeliveryMode::Immediate),
+                rule(EventKind::PublishingFailed, DeliveryMode::Immediate),
+                rule(EventKind::DeliveryFailed, DeliveryMode::Immediate),
+                rule(EventKind::ApprovalRequested, DeliveryMode::Immediate),
+                rule(EventKind::SchemaDrift, DeliveryMode::Immediate),
+                rule(EventKind::ConflictsFound, DeliveryMode::Digest),
+                rule(EventKind::ExportCompleted, DeliveryMode::Digest),
+            ]
+            .into_iter()
+            // Every event goes to the event topics as it happens, if any are configured
+            .chain(EventKind::ALL.into_iter().map(|event| NotificationRule {
+                event,
+                channels: vec![ChannelKind::EventBus],
+                mode: DeliveryMode::Immediate,
+                counties: Vec::new(),
+            }))
+            .collect(),
+            counties: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct NotificationsFile {
+    #[serde(default)]
+    notifications: Option<NotificationSettings>,
+}
+
+impl NotificationSettings {
+    /// Load the `[notifications]` table, defaulting to disabled when the file or table is missing
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let settings = match path {
+            Some(path) if path.exists() => {
+                let content = std::fs::read_to_string(path)?;
+                let file: NotificationsFile = toml::from_str(&content)
+                    .map_err(|e| Error::Config(format!("Failed to parse notification settings: {}", e)))?;
+                file.notifications.unwrap_or_default()
+            }
+            _ => Self::default(),
+        };
+
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.digest_interval_minutes == 0 {
+            return Err(Error::Config("digest_interval_minutes must be greater than zero".to_string()));
+        }
+        if !(0.0..=100.0).contains(&self.disk_threshold_percent) {
+            return Err(Error::Config("disk_threshold_percent must be between 0 and 100".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn digest_interval(&self) -> Duration {
+        Duration::from_secs(self.digest_interval_minutes * 60)
+    }
+
+    /// Destinations for a channel, preferring the county's own recipients
+    pub fn destinations(&self, channel: ChannelKind, county_id: Option<&str>) -> Vec<String> {
+        let county = county_id.and_then(|id| self.counties.get(id));
+
+        match channel {
+            ChannelKind::Email => match county {
+                Some(county) if !county.emails.is_empty() => county.emails.clone(),
+                _ => self.emails.clone(),
+            },
+            ChannelKind::Teams => county
+                .and_then(|c| c.teams_webhook_url.clone())
+                .or_else(|| self.teams_webhook_url.clone())
+                .into_iter()
+                .collect(),
+            ChannelKind::Slack => county
+                .and_then(|c| c.slack_webhook_url.clone())
+                .or_else(|| self.slack_webhook_url.clone())
+                .into_iter()
+                .collect(),
+            ChannelKind::EventBus => match county {
+                Some(county) if !county.event_topics.is_empty() => county.event_topics.clone(),
+                _ => self.event_topics.clone(),
+            },
+        }
+    }
+}
+
+/// Route notifications to channels according to the configured rules
+#[derive(Clone)]
+pub struct Notifier {
+    settings: Arc<NotificationSettings>,
+    channels: Arc<HashMap<ChannelKind, Arc<dyn NotificationChannel>>>,
+    pending: Arc<Mutex<HashMap<(ChannelKind, String), Vec<Notification>>>>,
+    /// For direct emails, which the rules do not route
+    smtp: Option<Arc<SmtpChannel>>,
+}
+
+impl Notifier {
+    pub fn new(settings: NotificationSettings) -> Result<Self> {
+        let mut channels: HashMap<ChannelKind, Arc<dyn NotificationChannel>> = HashMap::new();
+        let client = HttpClient::new("notifications", &HttpClientSettings::from_env()?.with_timeout(Duration::from_secs(15)))?;
+
+        let smtp = settings.smtp.as_ref().map(SmtpChannel::new).transpose()?.map(Arc::new);
+        if let Some(smtp) = &smtp {
+            channels.insert(ChannelKind::Email, smtp.clone());
+        }
+        channels.insert(ChannelKind::Teams, Arc::new(TeamsChannel::new(client.clone())));
+        channels.insert(ChannelKind::Slack, Arc::new(SlackChannel::new(client.clone())));
+        channels.insert(ChannelKind::EventBus, Arc::new(EventBusChannel::new(client)));
+
+        Ok(Self { smtp, ..Self::with_channels(settings, channels) })
+    }
+
+    /// Create a notifier with explicit channel implementations
+    pub fn with_channels(
+        settings: NotificationSettings,
+        channels: HashMap<ChannelKind, Arc<dyn NotificationChannel>>,
+    ) -> Self {
+        Self {
+            settings: Arc::new(settings),
+            channels: Arc::new(channels),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            smtp: None,
+        }
+    }
+
+    /// Create a notifier from the TERRAFUSION_CONFIG file
+    pub fn from_env() -> Result<Self> {
+        let path = std::env::var("TERRAFUSION_CONFIG")
+            .unwrap_or_else(|_| "config/default.toml".to_string());
+        let settings = NotificationSettings::load(Some(&PathBuf::from(path)))?;
+
+        if settings.enabled {
+            log::info!("Notifications enabled with {} rule(s)", settings.rules.len());
+        }
+
+        Self::new(settings)
+    }
+
+    pub fn settings(&self) -> &NotificationSettings {
+        &self.settings
+    }
+
+    /// Send an email outside the notification rules, such as an export to the
+    /// recipients its job named. It goes out whether or not notifications are
+    /// enabled, from the county's `email_from` when it has one.
+    pub async fn send_email(&self, county_id: Option<&str>, email: &DirectEmail) -> Result<()> {
+        let smtp = self
+            .smtp
+            .as_ref()
+            .ok_or_else(|| Error::Config("Sending email requires [notifications.smtp] settings".to_string()))?;
+        let from = county_id
+            .and_then(|county_id| self.settings.counties.get(county_id))
+            .and_then(|county| county.email_from.as_deref());
+        smtp.send_direct(from, email).await
+    }
+
+    /// Deliver or queue a notification; returns immediately
+    pub fn notify(&self, notification: Notification) {
+        if !self.settings.enabled {
+            return;
+        }
+
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            notifier.dispatch(notification).await;
+        });
+    }
+
+    async fn dispatch(&self, notification: Notification) {
+        let county_id = notification.county_id.as_deref();
+
+        for rule in self.settings.rules.iter().filter(|rule| rule.matches(&notification)) {
+            for channel in &rule.channels {
+                let destinations = self.settings.destinations(*channel, county_id);
+                if destinations.is_empty() {
+                    continue;
+                }
+
+                match rule.mode {
+                    DeliveryMode::Immediate => {
+                        self.deliver(*channel, &destinations, &Message::single(notification.clone())).await;
+                    }
+                    DeliveryMode::Digest => {
+                        let mut pending = self.pending.lock().await;
+                        for destination in destinations {
+                            pending.entry((*channel, destination)).or_default().push(notification.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn deliver(&self, channel: ChannelKind, destinations: &[String], message: &Message) {
+        let Some(sender) = self.channels.get(&channel) else {
+            log::warn!("No {:?} channel configured, dropping '{}'", channel, message.subject);
+            return;
+        };
+
+        if let Err(e) = sender.send(destinations, message).await {
+            log::error!("Failed to send {:?} notification '{}': {}", channel, message.subject, e);
+        }
+    }
+
+    /// Send everything queued for the digest
+    pub async fn flush_digest(&self) {
+        let pending = std::mem::take(&mut *self.pending.lock().await);
+
+        for ((channel, destination), items) in pending {
+            self.deliver(channel, &[destination], &Message::digest(items)).await;
+        }
+    }
+
+    /// Flush the digest every `digest_interval_minutes`
+    pub fn spawn_digest(&self) -> tokio::task::JoinHandle<()> {
+        let notifier = self.clone();
+        let interval = self.settings.digest_interval();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+                notifier.flush_digest().await;
+            }
+        })
+    }
+
+    /// Watch the disks holding `paths` and notify once when one crosses the threshold
+    pub fn spawn_disk_monitor(&self, paths: Vec<PathBuf>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        use sysinfo::{DiskExt, System, SystemExt};
+
+        let notifier = self.clone();
+        let threshold = self.settings.disk_threshold_percent;
+
+        tokio::spawn(async move {
+            let mut system = System::new();
+            system.refresh_disks_list();
+            let mut alerted: Vec<PathBuf> = Vec::new();
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+                system.refresh_disks();
+
+                for path in &paths {
+                    // The disk holding a path is the one with the longest matching mount point
+                    let Some(disk) = system
+                        .disks()
+                        .iter()
+                        .filter(|disk| path.starts_with(disk.mount_point()))
+                        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+                    else {
+                        continue;
+                    };
+
+                    let mount_point = disk.mount_point().to_path_buf();
+                    let used_percent = used_percent(disk.total_space(), disk.available_space());
+
+                    if used_percent >= threshold {
+                        if !alerted.contains(&mount_point) {
+                            notifier.notify(Notification::disk_nearly_full(&mount_point, used_percent, disk.available_space()));
+                            alerted.push(mount_point);
+                        }
+                    } else {
+                        alerted.retain(|alerted| alerted != &mount_point);
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// POST a JSON payload to a chat webhook
+pub(crate) async fn post_webhook(client: &HttpClient, url: &str, payload: &serde_json::Value, service: &str) -> Result<()> {
+    let response = client.post(url).json(payload).send().await?;
+
+    if !response.status().is_success() {
+        return Err(Error::ExternalService(format!(
+            "{} webhook returned status {}",
+            service,
+            response.status()
+        )));
+    }
+
+    Ok(())
+}
+
+fn used_percent(total: u64, available: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (total - available.min(total)) as f64 / total as f64 * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_destinations_prefer_county_recipients() {
+        let mut settings = NotificationSettings {
+            emails: vec!["it@example.gov".to_string()],
+            teams_webhook_url: Some("https://teams.example/default".to_string()),
+            ..Default::default()
+        };
+        settings.counties.insert("benton".to_string(), CountyRecipients {
+            emails: vec!["assessor@benton.example.gov".to_string()],
+            ..Default::default()
+        });
+
+        assert_eq!(settings.destinations(ChannelKind::Email, Some("benton")), vec!["assessor@benton.example.gov"]);
+        assert_eq!(settings.destinations(ChannelKind::Email, Some("franklin")), vec!["it@example.gov"]);
+        assert_eq!(settings.destinations(ChannelKind::Teams, Some("benton")), vec!["https://teams.example/default"]);
+        assert!(settings.destinations(ChannelKind::Slack, None).is_empty());
+
+        let rule = NotificationRule {
+            event: EventKind::OperationFailed,
+            channels: vec![ChannelKind::Email],
+            mode: DeliveryMode::Immediate,
+            counties: vec!["benton".to_string()],
+        };
+        assert!(rule.matches(&Notification::operation_failed("benton", "CAMA", 1, "timeout")));
+        assert!(!rule.matches(&Notification::operation_failed("franklin", "CAMA", 1, "timeout")));
+    }
+}
@@ -0,0 +1,49 @@
+use serde_json::{json, Value};
+
+use crate::http_client::HttpClient;
+use super::{post_webhook, ChannelKind, Message, NotificationChannel, SendFuture};
+
+/// Slack incoming webhook
+pub struct SlackChannel {
+    client: HttpClient,
+}
+
+impl SlackChannel {
+    pub fn new(client: HttpClient) -> Self {
+        Self { client }
+    }
+
+    fn payload(message: &Message) -> Value {
+        let mut blocks = vec![json!({
+            "type": "header",
+            "text": { "type": "plain_text", "text": message.subject },
+        })];
+
+        for n in &message.items {
+            let mut text = format!("*{}*\n{}", n.title, n.message);
+            for (name, value) in &n.facts {
+                text.push_str(&format!("\n• {}: {}", name, value));
+            }
+            blocks.push(json!({ "type": "section", "text": { "type": "mrkdwn", "text": text } }));
+        }
+
+        // `text` is the fallback shown in notifications and by clients without block support
+        json!({ "text": message.subject, "blocks": blocks })
+    }
+}
+
+impl NotificationChannel for SlackChannel {
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::Slack
+    }
+
+    fn send<'a>(&'a self, destinations: &'a [String], message: &'a Message) -> SendFuture<'a> {
+        Box::pin(async move {
+            let payload = Self::payload(message);
+            for url in destinations {
+                post_webhook(&self.client, url, &payload, "Slack").await?;
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,150 @@
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message as Email, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{Error, Result};
+use super::{ChannelKind, Message, NotificationChannel, SendFuture};
+
+/// How to secure the SMTP connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    #[default]
+    Starttls,
+    Tls,
+    /// Plain connection, for internal relays that do not offer TLS
+    None,
+}
+
+/// The `[notifications.smtp]` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpSettings {
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    pub from: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Prefer the SMTP_PASSWORD environment variable over storing this in the file
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+}
+
+/// An email sent directly rather than through the notification rules
+#[derive(Debug, Clone)]
+pub struct DirectEmail {
+    pub to: Vec<String>,
+    pub subject: String,
+    pub body: String,
+    pub attachment: Option<EmailAttachment>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmailAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
+
+/// Email via an SMTP relay
+pub struct SmtpChannel {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpChannel {
+    pub fn new(settings: &SmtpSettings) -> Result<Self> {
+        let mut builder = match settings.security {
+            SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.host),
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.host),
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.host)),
+        }
+        .map_err(|e| Error::Config(format!("Invalid SMTP host {}: {}", settings.host, e)))?;
+
+        if let Some(port) = settings.port {
+            builder = builder.port(port);
+        }
+
+        if let Some(username) = &settings.username {
+            let password = std::env::var("SMTP_PASSWORD")
+                .ok()
+                .or_else(|| settings.password.clone())
+                .ok_or_else(|| Error::Config("SMTP username set without SMTP_PASSWORD".to_string()))?;
+            builder = builder.credentials(Credentials::new(username.clone(), password));
+        }
+
+        let from = settings.from.parse()
+            .map_err(|e| Error::Config(format!("Invalid SMTP from address {}: {}", settings.from, e)))?;
+
+        Ok(Self { transport: builder.build(), from })
+    }
+
+    fn build_email(&self, destinations: &[String], message: &Message) -> Result<Email> {
+        let mut builder = Email::builder()
+            .from(self.from.clone())
+            .subject(message.subject.clone());
+
+        for destination in destinations {
+            builder = builder.to(mailbox(destination)?);
+        }
+
+        builder
+            .body(message.to_text())
+            .map_err(|e| Error::Internal(format!("Failed to build email: {}", e)))
+    }
+
+    /// Send an email from `from`, or the configured sender when not given
+    pub async fn send_direct(&self, from: Option<&str>, email: &DirectEmail) -> Result<()> {
+        let mut builder = Email::builder()
+            .from(from.map(mailbox).transpose()?.unwrap_or_else(|| self.from.clone()))
+            .subject(email.subject.clone());
+        for destination in &email.to {
+            builder = builder.to(mailbox(destination)?);
+        }
+
+        let text = SinglePart::plain(email.body.clone());
+        let email = match &email.attachment {
+            Some(attachment) => {
+                let content_type = ContentType::parse(&attachment.content_type)
+                    .map_err(|e| Error::Validation(format!("Invalid attachment type {}: {}", attachment.content_type, e)))?;
+                let attachment = Attachment::new(attachment.file_name.clone()).body(attachment.content.clone(), content_type);
+                builder.multipart(MultiPart::mixed().singlepart(text).singlepart(attachment))
+            }
+            None => builder.singlepart(text),
+        }
+        .map_err(|e| Error::Internal(format!("Failed to build email: {}", e)))?;
+
+        self.transport
+            .send(email)
+            .await
+            .map_err(|e| Error::ExternalService(format!("SMTP send failed: {}", e)))?;
+        Ok(())
+    }
+}
+
+fn mailbox(address: &str) -> Result<Mailbox> {
+    address
+        .parse()
+        .map_err(|e| Error::Validation(format!("Invalid email address {}: {}", address, e)))
+}
+
+impl NotificationChannel for SmtpChannel {
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::Email
+    }
+
+    fn send<'a>(&'a self, destinations: &'a [String], message: &'a Message) -> SendFuture<'a> {
+        Box::pin(async move {
+            let email = self.build_email(destinations, message)?;
+            self.transport
+                .send(email)
+                .await
+                .map_err(|e| Error::ExternalService(format!("SMTP send failed: {}", e)))?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use serde_json::{json, Value};
+
+use crate::http_client::HttpClient;
+use super::{post_webhook, ChannelKind, Message, NotificationChannel, SendFuture};
+
+/// Microsoft Teams incoming webhook (MessageCard format)
+pub struct TeamsChannel {
+    client: HttpClient,
+}
+
+impl TeamsChannel {
+    pub fn new(client: HttpClient) -> Self {
+        Self { client }
+    }
+
+    fn card(message: &Message) -> Value {
+        let sections: Vec<Value> = message
+            .items
+            .iter()
+            .map(|n| {
+                let facts: Vec<Value> = n.facts
+                    .iter()
+                    .map(|(name, value)| json!({ "name": name, "value": value }))
+                    .collect();
+                json!({
+                    "activityTitle": n.title,
+                    "activitySubtitle": n.occurred_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+                    "text": n.message,
+                    "facts": facts,
+                })
+            })
+            .collect();
+
+        json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": message.subject,
+            "title": message.subject,
+            "themeColor": if message.is_problem { "D13438" } else { "2E7D32" },
+            "sections": sections,
+        })
+    }
+}
+
+impl NotificationChannel for TeamsChannel {
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::Teams
+    }
+
+    fn send<'a>(&'a self, destinations: &'a [String], message: &'a Message) -> SendFuture<'a> {
+        Box::pin(async move {
+            let card = Self::card(message);
+            for url in destinations {
+                post_webhook(&self.client, url, &card, "Teams").await?;
+            }
+            Ok(())
+        })
+    }
+}
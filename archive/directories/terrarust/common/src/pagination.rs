@@ -0,0 +1,227 @@
+//! Shared list pagination: page numbers, or keyset cursors for large tables.
+//!
+//! Clients pass `?page=3&per_page=50` for offset pagination, or `?cursor=`
+//! (empty for the first page) and then the returned `next_cursor` for keyset
+//! pagination. Offset pages get slower the deeper they go on tables like
+//! `sync_diffs`; cursor pages cost the same at any depth but skip the total.
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::errors::{Error, Result};
+
+pub const DEFAULT_PER_PAGE: u32 = 50;
+pub const MAX_PER_PAGE: u32 = 500;
+
+/// Raw query parameters; `limit` and `offset` are accepted for older clients
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageParams {
+    pub page: Option<u32>,
+    #[serde(alias = "limit")]
+    pub per_page: Option<u32>,
+    pub offset: Option<u64>,
+    pub cursor: Option<String>,
+}
+
+/// Position after the last row of a page, for lists sorted newest first by
+/// `(timestamp, id)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub sort_key: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn new(sort_key: DateTime<Utc>, id: Uuid) -> Self {
+        Self { sort_key, id }
+    }
+
+    /// Opaque string form handed to clients
+    pub fn encode(&self) -> String {
+        format!("{}_{}", self.sort_key.timestamp_micros(), self.id.simple())
+    }
+
+    pub fn decode(value: &str) -> Result<Self> {
+        let invalid = || Error::Validation("Invalid pagination cursor".to_string());
+        let (micros, id) = value.split_once('_').ok_or_else(invalid)?;
+        let micros: i64 = micros.parse().map_err(|_| invalid())?;
+        let sort_key = Utc.timestamp_micros(micros).single().ok_or_else(invalid)?;
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+        Ok(Self { sort_key, id })
+    }
+}
+
+/// How a list request wants to be paged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pagination {
+    Offset { offset: u64, per_page: u32 },
+    Cursor { after: Option<Cursor>, per_page: u32 },
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Pagination::Offset { offset: 0, per_page: DEFAULT_PER_PAGE }
+    }
+}
+
+impl Pagination {
+    pub fn from_params(params: &PageParams) -> Result<Self> {
+        let per_page = params.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if per_page == 0 || per_page > MAX_PER_PAGE {
+            return Err(Error::Validation(format!("per_page must be between 1 and {}", MAX_PER_PAGE)));
+        }
+
+        if let Some(cursor) = &params.cursor {
+            if params.page.is_some() || params.offset.is_some() {
+                return Err(Error::Validation("cursor cannot be combined with page or offset".to_string()));
+            }
+            let after = match cursor.trim() {
+                "" => None,
+                cursor => Some(Cursor::decode(cursor)?),
+            };
+            return Ok(Pagination::Cursor { after, per_page });
+        }
+
+        let offset = match (params.offset, params.page) {
+            (Some(offset), _) => offset,
+            (None, Some(0)) => return Err(Error::Validation("page starts at 1".to_string())),
+            (None, page) => (page.unwrap_or(1) as u64 - 1) * per_page as u64,
+        };
+        Ok(Pagination::Offset { offset, per_page })
+    }
+
+    pub fn per_page(&self) -> u32 {
+        match self {
+            Pagination::Offset { per_page, .. } | Pagination::Cursor { per_page, .. } => *per_page,
+        }
+    }
+
+    pub fn is_cursor(&self) -> bool {
+        matches!(self, Pagination::Cursor { .. })
+    }
+
+    /// Rows to fetch. Cursor mode fetches one extra to learn whether another page exists.
+    pub fn fetch_limit(&self) -> i64 {
+        match self {
+            Pagination::Offset { per_page, .. } => *per_page as i64,
+            Pagination::Cursor { per_page, .. } => *per_page as i64 + 1,
+        }
+    }
+
+    pub fn offset(&self) -> i64 {
+        match self {
+            Pagination::Offset { offset, .. } => *offset as i64,
+            Pagination::Cursor { .. } => 0,
+        }
+    }
+
+    pub fn after(&self) -> Option<Cursor> {
+        match self {
+            Pagination::Cursor { after, .. } => *after,
+            Pagination::Offset { .. } => None,
+        }
+    }
+
+    /// Keyset condition for a cursor page, binding the cursor's timestamp
+    /// and id as `$param_index` and `$param_index + 1`. Pair it with
+    /// `ORDER BY sort_column DESC, id_column DESC`.
+    pub fn keyset_condition(&self, sort_column: &str, id_column: &str, param_index: usize) -> Option<String> {
+        self.after().map(|_| {
+            format!("({}, {}) < (${}, ${})", sort_column, id_column, param_index, param_index + 1)
+        })
+    }
+
+    /// Build the response page from the fetched rows
+    pub fn into_page<T>(self, mut items: Vec<T>, total: Option<i64>, cursor_of: impl Fn(&T) -> Cursor) -> Page<T> {
+        match self {
+            Pagination::Offset { offset, per_page } => Page {
+                page: Some(offset / per_page as u64 + 1),
+                per_page,
+                total,
+                next_cursor: None,
+                items,
+            },
+            Pagination::Cursor { per_page, .. } => {
+                let has_more = items.len() > per_page as usize;
+                items.truncate(per_page as usize);
+                let next_cursor = if has_more { items.last().map(|item| cursor_of(item).encode()) } else { None };
+                Page { page: None, per_page, total: None, next_cursor, items }
+            }
+        }
+    }
+}
+
+/// One page of a list response
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub per_page: u32,
+    /// Offset mode only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    /// Cursor mode only; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[cfg(feature = "actix")]
+mod actix {
+    use std::future::{ready, Ready};
+    use actix_web::{dev::Payload, FromRequest, HttpRequest};
+
+    use super::{PageParams, Pagination};
+
+    /// Reads `page`/`per_page` or `cursor` from the query string
+    impl FromRequest for Pagination {
+        type Error = actix_web::Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+            let result = actix_web::web::Query::<PageParams>::from_query(req.query_string())
+                .map_err(actix_web::Error::from)
+                .and_then(|params| {
+                    Pagination::from_params(&params).map_err(|e| actix_web::error::ErrorBadRequest(e.to_string()))
+                });
+            ready(result)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_and_cursor_modes() {
+        let params = PageParams { page: Some(3), per_page: Some(20), ..Default::default() };
+        let pagination = Pagination::from_params(&params).unwrap();
+        assert_eq!(pagination.offset(), 40);
+        let page = pagination.into_page(vec![1, 2], Some(42), |_| unreachable!());
+        assert_eq!((page.page, page.total), (Some(3), Some(42)));
+
+        let params = PageParams { cursor: Some(String::new()), per_page: Some(2), ..Default::default() };
+        let pagination = Pagination::from_params(&params).unwrap();
+        assert_eq!(pagination.fetch_limit(), 3);
+        assert!(pagination.keyset_condition("created_at", "id", 1).is_none());
+
+        let now = Utc.timestamp_micros(Utc::now().timestamp_micros()).unwrap();
+        let rows = vec![Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        let page = pagination.into_page(rows.clone(), None, |id| Cursor::new(now, *id));
+        assert_eq!(page.items.len(), 2);
+
+        let next = Cursor::decode(page.next_cursor.as_deref().unwrap()).unwrap();
+        assert_eq!(next, Cursor::new(now, rows[1]));
+        let params = PageParams { cursor: page.next_cursor, ..Default::default() };
+        let pagination = Pagination::from_params(&params).unwrap();
+        assert_eq!(
+            pagination.keyset_condition("d.created_at", "d.id", 4).as_deref(),
+            Some("(d.created_at, d.id) < ($4, $5)")
+        );
+
+        assert!(Cursor::decode("not-a-cursor").is_err());
+        assert!(Pagination::from_params(&PageParams { per_page: Some(0), ..Default::default() }).is_err());
+    }
+}
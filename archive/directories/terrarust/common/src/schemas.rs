@@ -0,0 +1,58 @@
+//! JSON Schemas of API payloads, generated from the shared models.
+//!
+//! The gateway checks request bodies against these before forwarding them,
+//! so the rules at the edge are the ones the services deserialize with.
+//! Length and range limits come from `#[schemars(...)]` attributes on the
+//! model fields.
+
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+
+use crate::models::geo::{CreateGisExportRequest, GisExportStatusResponse};
+use crate::models::sync::{CreateSyncOperationRequest, CreateSyncPairRequest, UpdateSyncPairRequest};
+
+pub const CREATE_SYNC_PAIR: &str = "CreateSyncPairRequest";
+pub const UPDATE_SYNC_PAIR: &str = "UpdateSyncPairRequest";
+pub const CREATE_SYNC_OPERATION: &str = "CreateSyncOperationRequest";
+pub const CREATE_GIS_EXPORT: &str = "CreateGisExportRequest";
+pub const GIS_EXPORT_STATUS: &str = "GisExportStatusResponse";
+
+/// Every schema by name
+pub fn all() -> Vec<(&'static str, RootSchema)> {
+    vec![
+        (CREATE_SYNC_PAIR, schema_for!(CreateSyncPairRequest)),
+        (UPDATE_SYNC_PAIR, schema_for!(UpdateSyncPairRequest)),
+        (CREATE_SYNC_OPERATION, schema_for!(CreateSyncOperationRequest)),
+        (CREATE_GIS_EXPORT, schema_for!(CreateGisExportRequest)),
+        (GIS_EXPORT_STATUS, schema_for!(GisExportStatusResponse)),
+    ]
+}
+
+/// One schema as JSON
+pub fn get(name: &str) -> Option<serde_json::Value> {
+    all()
+        .into_iter()
+        .find(|(schema_name, _)| *schema_name == name)
+        .and_then(|(_, schema)| serde_json::to_value(schema).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gis_export_schema_requirements() {
+        let schema = get(CREATE_GIS_EXPORT).unwrap();
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|value| value.as_str())
+            .collect();
+
+        assert!(required.contains(&"county_id"));
+        assert!(required.contains(&"layers"));
+        assert!(!required.contains(&"parameters"));
+        assert_eq!(schema["properties"]["layers"]["minItems"], 1);
+    }
+}
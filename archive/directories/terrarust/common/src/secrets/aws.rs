@@ -0,0 +1,82 @@
+use crate::errors::{Error, Result};
+use super::{Secret, SecretFuture, SecretProvider};
+
+/// AWS Secrets Manager provider
+///
+/// Secrets are looked up as `{AWS_SECRET_PREFIX}{key}`. A JSON secret string
+/// is also accepted, in which case the field named after the key is used.
+pub struct AwsSecretsManagerProvider {
+    prefix: String,
+    #[cfg(feature = "aws-secrets")]
+    client: tokio::sync::OnceCell<aws_sdk_secretsmanager::Client>,
+}
+
+impl AwsSecretsManagerProvider {
+    /// Create a provider using the default AWS credential chain
+    pub fn from_env() -> Result<Self> {
+        if cfg!(not(feature = "aws-secrets")) {
+            return Err(Error::Config(
+                "AWS secrets provider requires the 'aws-secrets' feature".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            prefix: std::env::var("AWS_SECRET_PREFIX").unwrap_or_else(|_| "terrafusion/".to_string()),
+            #[cfg(feature = "aws-secrets")]
+            client: tokio::sync::OnceCell::new(),
+        })
+    }
+
+    #[cfg(feature = "aws-secrets")]
+    async fn fetch(&self, key: &str) -> Result<Secret> {
+        let client = self.client
+            .get_or_init(|| async {
+                let config = aws_config::load_from_env().await;
+                aws_sdk_secretsmanager::Client::new(&config)
+            })
+            .await;
+
+        let secret_id = format!("{}{}", self.prefix, key);
+        let output = client
+            .get_secret_value()
+            .secret_id(&secret_id)
+            .send()
+            .await
+            .map_err(|e| Error::ExternalService(format!("AWS Secrets Manager error for {}: {}", secret_id, e)))?;
+
+        let raw = output.secret_string()
+            .ok_or_else(|| Error::NotFound(format!("Secret {} has no string value", secret_id)))?;
+
+        Ok(Secret {
+            value: extract_field(raw, key),
+            version: output.version_id().map(|v| v.to_string()),
+            lease_duration: None,
+        })
+    }
+
+    #[cfg(not(feature = "aws-secrets"))]
+    async fn fetch(&self, key: &str) -> Result<Secret> {
+        Err(Error::Config(format!(
+            "Cannot read {}{}: built without the 'aws-secrets' feature",
+            self.prefix, key
+        )))
+    }
+}
+
+/// Use the named field when the secret is a JSON object, otherwise the raw string
+fn extract_field(raw: &str, key: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(raw)
+        .ok()
+        .and_then(|json| json.get(key).and_then(|v| v.as_str()).map(|s| s.to_string()))
+        .unwrap_or_else(|| raw.to_string())
+}
+
+impl SecretProvider for AwsSecretsManagerProvider {
+    fn name(&self) -> &'static str {
+        "aws"
+    }
+
+    fn get_secret<'a>(&'a self, key: &'a str) -> SecretFuture<'a> {
+        Box::pin(self.fetch(key))
+    }
+}
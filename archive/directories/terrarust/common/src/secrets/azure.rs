@@ -0,0 +1,115 @@
+use serde::Deserialize;
+
+use crate::errors::{Error, Result};
+use crate::http_client::{self, HttpClient};
+use super::{Secret, SecretFuture, SecretProvider};
+
+const IMDS_TOKEN_URL: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+const KEY_VAULT_API_VERSION: &str = "7.4";
+
+/// Azure Key Vault provider authenticating with a managed identity
+pub struct AzureKeyVaultProvider {
+    client: HttpClient,
+    vault_url: String,
+    client_id: Option<String>,
+}
+
+/// Access token issued to the VM's or container's managed identity
+#[derive(Deserialize)]
+pub(crate) struct TokenResponse {
+    pub access_token: String,
+    /// Seconds until the token expires, as a string
+    #[serde(default)]
+    pub expires_in: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SecretBundle {
+    value: String,
+    id: String,
+}
+
+impl AzureKeyVaultProvider {
+    /// Create a provider from AZURE_KEY_VAULT_URL (and optional AZURE_CLIENT_ID)
+    pub fn from_env() -> Result<Self> {
+        let vault_url = std::env::var("AZURE_KEY_VAULT_URL")
+            .map_err(|_| Error::Config("AZURE_KEY_VAULT_URL environment variable not set".to_string()))?;
+
+        Ok(Self {
+            client: http_client::shared().clone(),
+            vault_url: vault_url.trim_end_matches('/').to_string(),
+            client_id: std::env::var("AZURE_CLIENT_ID").ok(),
+        })
+    }
+
+    async fn access_token(&self) -> Result<String> {
+        Ok(managed_identity_token(&self.client, "https://vault.azure.net", self.client_id.as_deref()).await?.access_token)
+    }
+
+    async fn fetch(&self, key: &str) -> Result<Secret> {
+        // Key Vault names only allow alphanumerics and dashes
+        let name = key.replace('_', "-").to_lowercase();
+        let url = format!("{}/secrets/{}?api-version={}", self.vault_url, name, KEY_VAULT_API_VERSION);
+
+        let token = self.access_token().await?;
+        let response = self.client
+            .get(&url)
+            .bearer_auth(token)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Error::ExternalService(format!(
+                "Key Vault returned status {} for secret {}",
+                response.status(),
+                name
+            )));
+        }
+
+        let bundle: SecretBundle = response.json().await?;
+
+        Ok(Secret {
+            value: bundle.value,
+            // The secret id ends with the version identifier
+            version: bundle.id.rsplit('/').next().map(|v| v.to_string()),
+            lease_duration: None,
+        })
+    }
+}
+
+impl SecretProvider for AzureKeyVaultProvider {
+    fn name(&self) -> &'static str {
+        "azure"
+    }
+
+    fn get_secret<'a>(&'a self, key: &'a str) -> SecretFuture<'a> {
+        Box::pin(self.fetch(key))
+    }
+}
+
+/// A token for `resource` from the instance metadata service, for the
+/// user-assigned identity `client_id` or the system-assigned one
+pub(crate) async fn managed_identity_token(
+    client: &HttpClient,
+    resource: &str,
+    client_id: Option<&str>,
+) -> Result<TokenResponse> {
+    let mut request = client
+        .get(IMDS_TOKEN_URL)
+        .header("Metadata", "true")
+        .query(&[("api-version", "2018-02-01"), ("resource", resource)]);
+
+    if let Some(client_id) = client_id {
+        request = request.query(&[("client_id", client_id)]);
+    }
+
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(Error::ExternalService(format!(
+            "Managed identity token request failed with status {}",
+            response.status()
+        )));
+    }
+
+    Ok(response.json().await?)
+}
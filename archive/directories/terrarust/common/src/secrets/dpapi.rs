@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+
+use crate::errors::{Error, Result};
+use super::{Secret, SecretFuture, SecretProvider};
+
+/// Local secrets encrypted with Windows DPAPI (machine scope)
+///
+/// Each secret is stored as `{SECRETS_DIR}\{key}.bin`. The file modification
+/// time is used as the version so rewritten files are picked up as rotations.
+pub struct DpapiFileProvider {
+    directory: PathBuf,
+}
+
+impl DpapiFileProvider {
+    /// Create a provider reading from SECRETS_DIR
+    pub fn from_env() -> Result<Self> {
+        let directory = std::env::var("SECRETS_DIR")
+            .unwrap_or_else(|_| "C:\\ProgramData\\TerraFusion\\secrets".to_string());
+
+        Ok(Self { directory: PathBuf::from(directory) })
+    }
+
+    fn secret_path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{}.bin", key))
+    }
+
+    /// Encrypt and write a secret, replacing any existing value
+    pub async fn store_secret(&self, key: &str, value: &str) -> Result<()> {
+        let encrypted = protect(value.as_bytes())?;
+        tokio::fs::create_dir_all(&self.directory).await?;
+        tokio::fs::write(self.secret_path(key), encrypted).await?;
+        Ok(())
+    }
+
+    async fn fetch(&self, key: &str) -> Result<Secret> {
+        let path = self.secret_path(key);
+        let encrypted = tokio::fs::read(&path)
+            .await
+            .map_err(|e| Error::NotFound(format!("Secret file {} not readable: {}", path.display(), e)))?;
+
+        let version = tokio::fs::metadata(&path)
+            .await
+            .ok()
+            .and_then(|meta| meta.modified().ok())
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|since_epoch| since_epoch.as_secs().to_string());
+
+        let decrypted = unprotect(&encrypted)?;
+        let value = String::from_utf8(decrypted)
+            .map_err(|_| Error::Parse(format!("Secret {} is not valid UTF-8", key)))?;
+
+        Ok(Secret {
+            value: value.trim_end().to_string(),
+            version,
+            lease_duration: None,
+        })
+    }
+}
+
+impl SecretProvider for DpapiFileProvider {
+    fn name(&self) -> &'static str {
+        "dpapi"
+    }
+
+    fn get_secret<'a>(&'a self, key: &'a str) -> SecretFuture<'a> {
+        Box::pin(self.fetch(key))
+    }
+}
+
+#[cfg(windows)]
+fn protect(data: &[u8]) -> Result<Vec<u8>> {
+    dpapi_call(data, true)
+}
+
+#[cfg(windows)]
+fn unprotect(data: &[u8]) -> Result<Vec<u8>> {
+    dpapi_call(data, false)
+}
+
+#[cfg(windows)]
+fn dpapi_call(data: &[u8], encrypt: bool) -> Result<Vec<u8>> {
+    use std::ptr;
+    use winapi::um::dpapi::{CryptProtectData, CryptUnprotectData, CRYPTPROTECT_LOCAL_MACHINE, CRYPTPROTECT_UI_FORBIDDEN};
+    use winapi::um::winbase::LocalFree;
+    use winapi::um::wincrypt::DATA_BLOB;
+
+    let mut input = DATA_BLOB {
+        cbData: data.len() as u32,
+        pbData: data.as_ptr() as *mut u8,
+    };
+    let mut output = DATA_BLOB {
+        cbData: 0,
+        pbData: ptr::null_mut(),
+    };
+    let flags = CRYPTPROTECT_UI_FORBIDDEN | CRYPTPROTECT_LOCAL_MACHINE;
+
+    // SAFETY: input points at a live slice and output is allocated by DPAPI,
+    // copied out, then released with LocalFree.
+    unsafe {
+        let ok = if encrypt {
+            CryptProtectData(&mut input, ptr::null(), ptr::null_mut(), ptr::null_mut(), ptr::null_mut(), flags, &mut output)
+        } else {
+            CryptUnprotectData(&mut input, ptr::null_mut(), ptr::null_mut(), ptr::null_mut(), ptr::null_mut(), flags, &mut output)
+        };
+
+        if ok == 0 {
+            return Err(Error::Internal(format!(
+                "DPAPI call failed: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+
+        let result = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+        LocalFree(output.pbData as *mut _);
+        Ok(result)
+    }
+}
+
+#[cfg(not(windows))]
+fn protect(_data: &[u8]) -> Result<Vec<u8>> {
+    Err(Error::Config("DPAPI secrets are only available on Windows".to_string()))
+}
+
+#[cfg(not(windows))]
+fn unprotect(_data: &[u8]) -> Result<Vec<u8>> {
+    Err(Error::Config("DPAPI secrets are only available on Windows".to_string()))
+}
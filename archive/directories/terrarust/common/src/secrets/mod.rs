@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use crate::errors::{Error, Result};
+
+pub mod vault;
+pub mod aws;
+pub mod azure;
+pub mod dpapi;
+
+pub use vault::VaultProvider;
+pub use aws::AwsSecretsManagerProvider;
+pub use azure::AzureKeyVaultProvider;
+pub use dpapi::DpapiFileProvider;
+
+/// Well-known secret names used across services
+pub const DATABASE_URL: &str = "DATABASE_URL";
+pub const DATABASE_READ_URL: &str = "DATABASE_READ_URL";
+pub const JWT_SECRET: &str = "JWT_SECRET";
+
+/// Boxed future returned by secret providers
+pub type SecretFuture<'a> = Pin<Box<dyn Future<Output = Result<Secret>> + Send + 'a>>;
+
+/// A secret value with optional lease information
+#[derive(Clone)]
+pub struct Secret {
+    pub value: String,
+    /// Provider-specific version identifier, used to detect rotation
+    pub version: Option<String>,
+    /// How long the provider allows this value to be cached
+    pub lease_duration: Option<Duration>,
+}
+
+impl Secret {
+    /// Create a secret without version or lease information
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            version: None,
+            lease_duration: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Secret")
+            .field("value", &"<redacted>")
+            .field("version", &self.version)
+            .field("lease_duration", &self.lease_duration)
+            .finish()
+    }
+}
+
+/// Source of secret values
+pub trait SecretProvider: Send + Sync + 'static {
+    /// Provider name for logging
+    fn name(&self) -> &'static str;
+
+    /// Fetch the current value of a secret
+    fn get_secret<'a>(&'a self, key: &'a str) -> SecretFuture<'a>;
+}
+
+/// Reads secrets from plain environment variables
+#[derive(Debug, Default)]
+pub struct EnvProvider;
+
+impl SecretProvider for EnvProvider {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn get_secret<'a>(&'a self, key: &'a str) -> SecretFuture<'a> {
+        Box::pin(async move {
+            std::env::var(key)
+                .map(Secret::new)
+                .map_err(|_| Error::Config(format!("{} environment variable not set", key)))
+        })
+    }
+}
+
+struct CachedSecret {
+    secret: Secret,
+    fetched_at: Instant,
+}
+
+/// Caching front for a secret provider
+#[derive(Clone)]
+pub struct SecretsManager {
+    provider: Arc<dyn SecretProvider>,
+    cache: Arc<RwLock<HashMap<String, CachedSecret>>>,
+    default_ttl: Duration,
+}
+
+impl SecretsManager {
+    /// Create a manager around a provider
+    pub fn new(provider: Arc<dyn SecretProvider>, default_ttl: Duration) -> Self {
+        Self {
+            provider,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+            default_ttl,
+        }
+    }
+
+    /// Create a manager using the provider selected by SECRETS_PROVIDER
+    ///
+    /// Supported values: `env` (default), `vault`, `aws`, `azure`, `dpapi`.
+    pub fn from_env() -> Result<Self> {
+        let provider_name = std::env::var("SECRETS_PROVIDER").unwrap_or_else(|_| "env".to_string());
+
+        let provider: Arc<dyn SecretProvider> = match provider_name.to_lowercase().as_str() {
+            "env" => Arc::new(EnvProvider),
+            "vault" => Arc::new(VaultProvider::from_env()?),
+            "aws" => Arc::new(AwsSecretsManagerProvider::from_env()?),
+            "azure" => Arc::new(AzureKeyVaultProvider::from_env()?),
+            "dpapi" => Arc::new(DpapiFileProvider::from_env()?),
+            other => return Err(Error::Config(format!("Unknown secrets provider: {}", other))),
+        };
+
+        let ttl_seconds = std::env::var("SECRETS_CACHE_TTL_SECONDS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse::<u64>()
+            .map_err(|_| Error::Config("Invalid SECRETS_CACHE_TTL_SECONDS value".to_string()))?;
+
+        log::info!("Using '{}' secrets provider", provider.name());
+
+        Ok(Self::new(provider, Duration::from_secs(ttl_seconds)))
+    }
+
+    /// Get a secret, served from cache while its lease is valid
+    pub async fn get(&self, key: &str) -> Result<Secret> {
+        {
+            let cache = self.cache.read().await;
+            if let Some(cached) = cache.get(key) {
+                let ttl = cached.secret.lease_duration.unwrap_or(self.default_ttl);
+                if cached.fetched_at.elapsed() < ttl {
+                    return Ok(cached.secret.clone());
+                }
+            }
+        }
+
+        self.refresh(key).await
+    }
+
+    /// Fetch a secret from the provider, bypassing the cache
+    pub async fn refresh(&self, key: &str) -> Result<Secret> {
+        let secret = self.provider.get_secret(key).await?;
+
+        let mut cache = self.cache.write().await;
+        cache.insert(key.to_string(), CachedSecret {
+            secret: secret.clone(),
+            fetched_at: Instant::now(),
+        });
+
+        Ok(secret)
+    }
+
+    /// Convenience accessor for the secret value only
+    pub async fn get_value(&self, key: &str) -> Result<String> {
+        self.get(key).await.map(|secret| secret.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_debug_is_redacted() {
+        let secret = Secret::new("hunter2");
+        assert!(!format!("{:?}", secret).contains("hunter2"));
+    }
+}
@@ -0,0 +1,310 @@
+//! Server-side login sessions.
+//!
+//! A session is created at login and its id is carried in the user's token.
+//! Every request checks it against the store, so a session can be ended
+//! before the token expires: on logout, when it sits idle too long, when its
+//! absolute lifetime runs out, or when an administrator forces a logout.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::errors::{Error, Result};
+
+pub mod postgres;
+#[cfg(feature = "redis-sessions")]
+pub mod redis;
+
+pub use postgres::PgSessionStore;
+#[cfg(feature = "redis-sessions")]
+pub use self::redis::RedisSessionStore;
+
+/// Boxed future returned by session stores
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Last-seen times closer together than this are not written back, so
+/// busy pages do not cost a store write per request
+const TOUCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One signed-in browser or client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Session {
+    pub id: String,
+    pub user_id: String,
+    pub county_id: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    /// End of the absolute lifetime, however active the session is
+    pub expires_at: DateTime<Utc>,
+}
+
+/// How long sessions live
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionTimeouts {
+    /// Inactivity after which the session ends
+    pub idle: Duration,
+    /// Lifetime from login, regardless of activity
+    pub absolute: Duration,
+}
+
+impl Default for SessionTimeouts {
+    fn default() -> Self {
+        Self {
+            idle: Duration::from_secs(30 * 60),
+            absolute: Duration::from_secs(12 * 60 * 60),
+        }
+    }
+}
+
+impl SessionTimeouts {
+    /// Read `SESSION_IDLE_TIMEOUT_MINUTES` and `SESSION_ABSOLUTE_TIMEOUT_HOURS`
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let idle = match std::env::var("SESSION_IDLE_TIMEOUT_MINUTES") {
+            Ok(value) => Duration::from_secs(
+                value
+                    .parse::<u64>()
+                    .map_err(|_| Error::Config("Invalid SESSION_IDLE_TIMEOUT_MINUTES value".to_string()))?
+                    * 60,
+            ),
+            Err(_) => defaults.idle,
+        };
+        let absolute = match std::env::var("SESSION_ABSOLUTE_TIMEOUT_HOURS") {
+            Ok(value) => Duration::from_secs(
+                value
+                    .parse::<u64>()
+                    .map_err(|_| Error::Config("Invalid SESSION_ABSOLUTE_TIMEOUT_HOURS value".to_string()))?
+                    * 3600,
+            ),
+            Err(_) => defaults.absolute,
+        };
+        Ok(Self { idle, absolute })
+    }
+
+    /// Sessions last seen before this have gone idle
+    pub fn idle_cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - chrono::Duration::from_std(self.idle).unwrap_or_else(|_| chrono::Duration::zero())
+    }
+
+    pub fn is_active(&self, session: &Session, now: DateTime<Utc>) -> bool {
+        session.expires_at > now && session.last_seen_at > self.idle_cutoff(now)
+    }
+}
+
+/// Which sessions to list
+#[derive(Debug, Clone, Default)]
+pub struct SessionFilter {
+    pub user_id: Option<String>,
+    pub county_id: Option<String>,
+}
+
+/// Storage for sessions
+pub trait SessionStore: Send + Sync + 'static {
+    /// Store name for logging
+    fn name(&self) -> &'static str;
+
+    fn insert<'a>(&'a self, session: &'a Session) -> StoreFuture<'a, ()>;
+
+    fn get<'a>(&'a self, id: &'a str) -> StoreFuture<'a, Option<Session>>;
+
+    /// Record activity on a session
+    fn touch<'a>(&'a self, id: &'a str, at: DateTime<Utc>) -> StoreFuture<'a, ()>;
+
+    /// Sessions matching `filter` that were seen after `idle_cutoff` and have
+    /// not reached their absolute expiry
+    fn list_active<'a>(&'a self, filter: &'a SessionFilter, idle_cutoff: DateTime<Utc>) -> StoreFuture<'a, Vec<Session>>;
+
+    /// Remove one session; false if it did not exist
+    fn delete<'a>(&'a self, id: &'a str) -> StoreFuture<'a, bool>;
+
+    /// Remove every session of a user, optionally only within one county
+    fn delete_for_user<'a>(&'a self, user_id: &'a str, county_id: Option<&'a str>) -> StoreFuture<'a, u64>;
+
+    /// Remove sessions that are idle or past their absolute expiry
+    fn purge<'a>(&'a self, idle_cutoff: DateTime<Utc>) -> StoreFuture<'a, u64>;
+}
+
+/// Creates, checks and ends sessions against the configured store
+#[derive(Clone)]
+pub struct SessionManager {
+    store: Arc<dyn SessionStore>,
+    timeouts: SessionTimeouts,
+}
+
+impl SessionManager {
+    pub fn new(store: Arc<dyn SessionStore>, timeouts: SessionTimeouts) -> Self {
+        Self { store, timeouts }
+    }
+
+    /// Create a manager using the store selected by SESSION_STORE
+    ///
+    /// Supported values: `postgres` (default), which keeps sessions in `pool`,
+    /// and `redis`, which connects to REDIS_URL and needs the
+    /// `redis-sessions` feature.
+    pub fn from_env(pool: PgPool) -> Result<Self> {
+        let store_name = std::env::var("SESSION_STORE").unwrap_or_else(|_| "postgres".to_string());
+
+        let store: Arc<dyn SessionStore> = match store_name.to_lowercase().as_str() {
+            "postgres" => Arc::new(PgSessionStore::new(pool)),
+            #[cfg(feature = "redis-sessions")]
+            "redis" => Arc::new(RedisSessionStore::from_env()?),
+            #[cfg(not(feature = "redis-sessions"))]
+            "redis" => {
+                return Err(Error::Config(
+                    "Redis session store requires the 'redis-sessions' feature".to_string(),
+                ))
+            }
+            other => return Err(Error::Config(format!("Unknown session store: {}", other))),
+        };
+
+        let timeouts = SessionTimeouts::from_env()?;
+        log::info!(
+            "Using '{}' session store (idle timeout {}s, absolute {}s)",
+            store.name(),
+            timeouts.idle.as_secs(),
+            timeouts.absolute.as_secs()
+        );
+
+        Ok(Self::new(store, timeouts))
+    }
+
+    pub fn timeouts(&self) -> SessionTimeouts {
+        self.timeouts
+    }
+
+    /// Start a session for a user who just signed in
+    pub async fn start(
+        &self,
+        user_id: &str,
+        county_id: &str,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+    ) -> Result<Session> {
+        let now = Utc::now();
+        let absolute = chrono::Duration::from_std(self.timeouts.absolute).map_err(|e| Error::Config(e.to_string()))?;
+        let session = Session {
+            id: generate_id(),
+            user_id: user_id.to_string(),
+            county_id: county_id.to_string(),
+            ip_address,
+            user_agent,
+            created_at: now,
+            last_seen_at: now,
+            expires_at: now + absolute,
+        };
+        self.store.insert(&session).await?;
+        Ok(session)
+    }
+
+    /// The session if it is still active, recording the activity. Expired
+    /// sessions are removed and give `None`.
+    pub async fn validate(&self, id: &str) -> Result<Option<Session>> {
+        let Some(mut session) = self.store.get(id).await? else {
+            return Ok(None);
+        };
+
+        let now = Utc::now();
+        if !self.timeouts.is_active(&session, now) {
+            self.store.delete(id).await?;
+            return Ok(None);
+        }
+
+        if needs_touch(&session, now) {
+            self.store.touch(id, now).await?;
+            session.last_seen_at = now;
+        }
+        Ok(Some(session))
+    }
+
+    pub async fn list_active(&self, filter: &SessionFilter) -> Result<Vec<Session>> {
+        self.store.list_active(filter, self.timeouts.idle_cutoff(Utc::now())).await
+    }
+
+    /// End a session. With `county_id`, sessions of other counties are
+    /// treated as missing.
+    pub async fn revoke(&self, id: &str, county_id: Option<&str>) -> Result<bool> {
+        if let Some(county_id) = county_id {
+            match self.store.get(id).await? {
+                Some(session) if session.county_id == county_id => {}
+                _ => return Ok(false),
+            }
+        }
+        self.store.delete(id).await
+    }
+
+    /// End all of a user's sessions, e.g. after a password change
+    pub async fn revoke_user(&self, user_id: &str, county_id: Option<&str>) -> Result<u64> {
+        self.store.delete_for_user(user_id, county_id).await
+    }
+
+    /// Remove idle and expired sessions periodically
+    pub fn spawn_purge(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+                let cutoff = manager.timeouts.idle_cutoff(Utc::now());
+                match manager.store.purge(cutoff).await {
+                    Ok(purged) if purged > 0 => log::debug!("Purged {} expired sessions", purged),
+                    Ok(_) => {}
+                    Err(e) => log::error!("Failed to purge sessions: {}", e),
+                }
+            }
+        })
+    }
+}
+
+fn needs_touch(session: &Session, now: DateTime<Utc>) -> bool {
+    (now - session.last_seen_at)
+        .to_std()
+        .map(|elapsed| elapsed >= TOUCH_INTERVAL)
+        .unwrap_or(false)
+}
+
+/// 256 random bits, hex-encoded
+fn generate_id() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(last_seen_minutes_ago: i64, expires_in_minutes: i64) -> Session {
+        let now = Utc::now();
+        Session {
+            id: generate_id(),
+            user_id: "clerk@benton.example".to_string(),
+            county_id: "benton".to_string(),
+            ip_address: None,
+            user_agent: None,
+            created_at: now - chrono::Duration::hours(1),
+            last_seen_at: now - chrono::Duration::minutes(last_seen_minutes_ago),
+            expires_at: now + chrono::Duration::minutes(expires_in_minutes),
+        }
+    }
+
+    #[test]
+    fn test_idle_and_absolute_timeouts() {
+        let timeouts = SessionTimeouts::default();
+        let now = Utc::now();
+
+        assert!(timeouts.is_active(&session(5, 60), now));
+        assert!(!timeouts.is_active(&session(45, 60), now));
+        assert!(!timeouts.is_active(&session(5, -1), now));
+        assert!(needs_touch(&session(5, 60), now));
+        assert!(!needs_touch(&session(0, 60), now));
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use super::{Session, SessionFilter, SessionStore, StoreFuture};
+
+const SESSION_COLUMNS: &str =
+    "id, user_id, county_id, ip_address, user_agent, created_at, last_seen_at, expires_at";
+
+/// Sessions in the `user_sessions` table
+pub struct PgSessionStore {
+    pool: PgPool,
+}
+
+impl PgSessionStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl SessionStore for PgSessionStore {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn insert<'a>(&'a self, session: &'a Session) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                r#"
+                INSERT INTO user_sessions
+                    (id, user_id, county_id, ip_address, user_agent, created_at, last_seen_at, expires_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+            )
+            .bind(&session.id)
+            .bind(&session.user_id)
+            .bind(&session.county_id)
+            .bind(&session.ip_address)
+            .bind(&session.user_agent)
+            .bind(session.created_at)
+            .bind(session.last_seen_at)
+            .bind(session.expires_at)
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, id: &'a str) -> StoreFuture<'a, Option<Session>> {
+        Box::pin(async move {
+            let sql = format!("SELECT {} FROM user_sessions WHERE id = $1", SESSION_COLUMNS);
+            Ok(sqlx::query_as::<_, Session>(&sql).bind(id).fetch_optional(&self.pool).await?)
+        })
+    }
+
+    fn touch<'a>(&'a self, id: &'a str, at: DateTime<Utc>) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query("UPDATE user_sessions SET last_seen_at = $2 WHERE id = $1")
+                .bind(id)
+                .bind(at)
+                .execute(&self.pool)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn list_active<'a>(&'a self, filter: &'a SessionFilter, idle_cutoff: DateTime<Utc>) -> StoreFuture<'a, Vec<Session>> {
+        Box::pin(async move {
+            let sql = format!(
+                r#"
+                SELECT {} FROM user_sessions
+                WHERE expires_at > NOW() AND last_seen_at > $1
+                  AND ($2::VARCHAR IS NULL OR user_id = $2)
+                  AND ($3::VARCHAR IS NULL OR county_id = $3)
+                ORDER BY user_id, last_seen_at DESC
+                "#,
+                SESSION_COLUMNS
+            );
+            Ok(sqlx::query_as::<_, Session>(&sql)
+                .bind(idle_cutoff)
+                .bind(&filter.user_id)
+                .bind(&filter.county_id)
+                .fetch_all(&self.pool)
+                .await?)
+        })
+    }
+
+    fn delete<'a>(&'a self, id: &'a str) -> StoreFuture<'a, bool> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM user_sessions WHERE id = $1")
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn delete_for_user<'a>(&'a self, user_id: &'a str, county_id: Option<&'a str>) -> StoreFuture<'a, u64> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                "DELETE FROM user_sessions WHERE user_id = $1 AND ($2::VARCHAR IS NULL OR county_id = $2)",
+            )
+            .bind(user_id)
+            .bind(county_id)
+            .execute(&self.pool)
+            .await?;
+            Ok(result.rows_affected())
+        })
+    }
+
+    fn purge<'a>(&'a self, idle_cutoff: DateTime<Utc>) -> StoreFuture<'a, u64> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM user_sessions WHERE expires_at <= NOW() OR last_seen_at <= $1")
+                .bind(idle_cutoff)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected())
+        })
+    }
+}
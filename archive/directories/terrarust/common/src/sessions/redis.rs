@@ -0,0 +1,211 @@
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+
+use crate::errors::{Error, Result};
+use super::{Session, SessionFilter, SessionStore, StoreFuture};
+
+/// Sessions in Redis.
+///
+/// Each session is a JSON value under `{prefix}session:{id}` that Redis
+/// expires at the absolute timeout. The sets `{prefix}sessions` and
+/// `{prefix}user:{user_id}` index the ids; ids whose value has expired are
+/// dropped from them while listing and purging.
+pub struct RedisSessionStore {
+    client: redis::Client,
+    prefix: String,
+    connection: tokio::sync::OnceCell<ConnectionManager>,
+}
+
+impl RedisSessionStore {
+    /// Connect lazily to REDIS_URL, with keys under REDIS_SESSION_PREFIX
+    pub fn from_env() -> Result<Self> {
+        let url = std::env::var("REDIS_URL")
+            .map_err(|_| Error::Config("REDIS_URL is required for the Redis session store".to_string()))?;
+        let client = redis::Client::open(url).map_err(|e| Error::Config(format!("Invalid REDIS_URL: {}", e)))?;
+
+        Ok(Self {
+            client,
+            prefix: std::env::var("REDIS_SESSION_PREFIX").unwrap_or_else(|_| "terrafusion:".to_string()),
+            connection: tokio::sync::OnceCell::new(),
+        })
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager> {
+        self.connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+            .map_err(redis_error)
+    }
+
+    fn session_key(&self, id: &str) -> String {
+        format!("{}session:{}", self.prefix, id)
+    }
+
+    fn user_key(&self, user_id: &str) -> String {
+        format!("{}user:{}", self.prefix, user_id)
+    }
+
+    fn index_key(&self) -> String {
+        format!("{}sessions", self.prefix)
+    }
+
+    /// Load the sessions behind `ids`, removing ids that no longer exist
+    /// from the index sets
+    async fn load(&self, conn: &mut ConnectionManager, ids: Vec<String>) -> Result<Vec<Session>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let keys: Vec<String> = ids.iter().map(|id| self.session_key(id)).collect();
+        let values: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query_async(conn).await.map_err(redis_error)?;
+
+        let mut sessions = Vec::new();
+        let mut stale = Vec::new();
+        for (id, value) in ids.into_iter().zip(values) {
+            match value.and_then(|value| serde_json::from_str::<Session>(&value).ok()) {
+                Some(session) => sessions.push(session),
+                None => stale.push(id),
+            }
+        }
+        if !stale.is_empty() {
+            conn.srem::<_, _, ()>(self.index_key(), &stale).await.map_err(redis_error)?;
+        }
+        Ok(sessions)
+    }
+
+    async fn remove(&self, conn: &mut ConnectionManager, session: &Session) -> Result<bool> {
+        let deleted: u64 = conn.del(self.session_key(&session.id)).await.map_err(redis_error)?;
+        conn.srem::<_, _, ()>(self.index_key(), &session.id).await.map_err(redis_error)?;
+        conn.srem::<_, _, ()>(self.user_key(&session.user_id), &session.id).await.map_err(redis_error)?;
+        Ok(deleted > 0)
+    }
+}
+
+fn redis_error(e: redis::RedisError) -> Error {
+    Error::ExternalService(format!("Redis session store: {}", e))
+}
+
+fn encode(session: &Session) -> Result<String> {
+    serde_json::to_string(session).map_err(|e| Error::Serialization(e.to_string()))
+}
+
+impl SessionStore for RedisSessionStore {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    fn insert<'a>(&'a self, session: &'a Session) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let value = encode(session)?;
+            redis::pipe()
+                .atomic()
+                .set(self.session_key(&session.id), value)
+                .ignore()
+                .cmd("EXPIREAT")
+                .arg(self.session_key(&session.id))
+                .arg(session.expires_at.timestamp())
+                .ignore()
+                .sadd(self.index_key(), &session.id)
+                .ignore()
+                .sadd(self.user_key(&session.user_id), &session.id)
+                .ignore()
+                .query_async::<_, ()>(&mut conn)
+                .await
+                .map_err(redis_error)
+        })
+    }
+
+    fn get<'a>(&'a self, id: &'a str) -> StoreFuture<'a, Option<Session>> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let value: Option<String> = conn.get(self.session_key(id)).await.map_err(redis_error)?;
+            value
+                .map(|value| serde_json::from_str(&value).map_err(|e| Error::Serialization(e.to_string())))
+                .transpose()
+        })
+    }
+
+    fn touch<'a>(&'a self, id: &'a str, at: DateTime<Utc>) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let Some(mut session) = self.get(id).await? else {
+                return Ok(());
+            };
+            session.last_seen_at = at;
+            let mut conn = self.connection().await?;
+            // SET XX KEEPTTL: update in place without resurrecting a deleted session
+            redis::cmd("SET")
+                .arg(self.session_key(id))
+                .arg(encode(&session)?)
+                .arg("XX")
+                .arg("KEEPTTL")
+                .query_async::<_, ()>(&mut conn)
+                .await
+                .map_err(redis_error)
+        })
+    }
+
+    fn list_active<'a>(&'a self, filter: &'a SessionFilter, idle_cutoff: DateTime<Utc>) -> StoreFuture<'a, Vec<Session>> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let set = match &filter.user_id {
+                Some(user_id) => self.user_key(user_id),
+                None => self.index_key(),
+            };
+            let ids: Vec<String> = conn.smembers(set).await.map_err(redis_error)?;
+            let now = Utc::now();
+
+            let mut sessions: Vec<Session> = self
+                .load(&mut conn, ids)
+                .await?
+                .into_iter()
+                .filter(|session| session.expires_at > now && session.last_seen_at > idle_cutoff)
+                .filter(|session| filter.county_id.as_ref().map_or(true, |county| &session.county_id == county))
+                .collect();
+            sessions.sort_by(|a, b| a.user_id.cmp(&b.user_id).then(b.last_seen_at.cmp(&a.last_seen_at)));
+            Ok(sessions)
+        })
+    }
+
+    fn delete<'a>(&'a self, id: &'a str) -> StoreFuture<'a, bool> {
+        Box::pin(async move {
+            let Some(session) = self.get(id).await? else {
+                return Ok(false);
+            };
+            let mut conn = self.connection().await?;
+            self.remove(&mut conn, &session).await
+        })
+    }
+
+    fn delete_for_user<'a>(&'a self, user_id: &'a str, county_id: Option<&'a str>) -> StoreFuture<'a, u64> {
+        Box::pin(async move {
+            let mut conn = self.connection().await?;
+            let ids: Vec<String> = conn.smembers(self.user_key(user_id)).await.map_err(redis_error)?;
+
+            let mut removed = 0;
+            for session in self.load(&mut conn, ids).await? {
+                if county_id.map_or(true, |county| session.county_id == county) && self.remove(&mut conn, &session).await? {
+                    removed += 1;
+                }
+            }
+            Ok(removed)
+        })
+    }
+
+    fn purge<'a>(&'a self, idle_cutoff: DateTime<Utc>) -> StoreFuture<'a, u64> {
+        Box::pin(async move {
+            // Absolute expiry is handled by Redis itself; `load` clears the index
+            let mut conn = self.connection().await?;
+            let ids: Vec<String> = conn.smembers(self.index_key()).await.map_err(redis_error)?;
+
+            let mut removed = 0;
+            for session in self.load(&mut conn, ids).await? {
+                if session.last_seen_at <= idle_cutoff && self.remove(&mut conn, &session).await? {
+                    removed += 1;
+                }
+            }
+            Ok(removed)
+        })
+    }
+}
@@ -126,8 +126,9 @@ impl TelemetryService {
         // Create a text encoder
         let encoder = TextEncoder::new();
         
-        // Gather all metrics
-        let metrics = self.registry.gather();
+        // Gather service metrics plus those registered globally (e.g. database pools)
+        let mut metrics = self.registry.gather();
+        metrics.extend(prometheus::gather());
         
         // Encode metrics to string
         let mut buffer = Vec::new();
@@ -0,0 +1,174 @@
+//! Point-in-time views of synced data.
+//!
+//! Every applied sync diff stores the record as the source had it, so the
+//! state of an entity at a moment is its latest applied diff at or before
+//! that moment: its `source_data`, or nothing if that diff deleted it.
+//! Assessors use these views to show a parcel as it stood on a prior
+//! valuation roll, e.g. in appeal hearings.
+//!
+//! History only reaches back as far as retention keeps diffs. Payloads
+//! sealed at rest come back sealed; callers open them as they do for
+//! entity history.
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::errors::{Error, Result};
+
+/// Query and export parameter naming the moment to reconstruct
+pub const AS_OF_PARAMETER: &str = "as_of";
+
+/// An entity as of some moment, from the diff that last touched it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct HistoricalRecord {
+    pub county_id: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub change_type: String,
+    /// The record as synced; `None` once it was deleted
+    pub data: Option<Value>,
+    pub sync_operation_id: Uuid,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl HistoricalRecord {
+    /// Whether the entity existed at the moment
+    pub fn exists(&self) -> bool {
+        !is_removal(&self.change_type)
+    }
+}
+
+/// Whether a diff's change type removes the entity
+pub fn is_removal(change_type: &str) -> bool {
+    matches!(change_type.to_ascii_uppercase().as_str(), "DELETED" | "DELETE" | "REMOVED")
+}
+
+/// Parse an `as_of` value: an RFC 3339 timestamp, or a date meaning the
+/// end of that day in UTC. Moments in the future are refused.
+pub fn parse_as_of(value: &str) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    let as_of = if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        timestamp.with_timezone(&Utc)
+    } else if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        date.and_time(NaiveTime::from_hms_micro_opt(23, 59, 59, 999_999).expect("valid time")).and_utc()
+    } else {
+        return Err(Error::Validation(format!(
+            "as_of must be a date (YYYY-MM-DD) or an RFC 3339 timestamp, not {}",
+            value
+        )));
+    };
+    if as_of.date_naive() > Utc::now().date_naive() {
+        return Err(Error::Validation("as_of must not be in the future".to_string()));
+    }
+    Ok(as_of)
+}
+
+/// The `as_of` moment in export parameters, if any
+pub fn as_of_parameter(parameters: Option<&Value>) -> Result<Option<DateTime<Utc>>> {
+    match parameters.and_then(|parameters| parameters.get(AS_OF_PARAMETER)) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => parse_as_of(value).map(Some),
+        Some(_) => Err(Error::Validation("as_of must be a string".to_string())),
+    }
+}
+
+/// Point-in-time reads of sync diffs
+pub struct TemporalQueries;
+
+impl TemporalQueries {
+    /// An entity's latest applied diff at or before `as_of`; `None` when no
+    /// sync had touched it yet
+    pub async fn entity_as_of(
+        pool: &PgPool,
+        entity_type: &str,
+        entity_id: &str,
+        county_id: Option<&str>,
+        as_of: DateTime<Utc>,
+    ) -> Result<Option<HistoricalRecord>> {
+        let record = sqlx::query_as::<_, HistoricalRecord>(
+            r#"
+            SELECT o.county_id, d.entity_type, d.entity_id, d.change_type, d.source_data AS data,
+                   d.sync_operation_id, d.created_at AS changed_at
+            FROM sync_diffs d
+            JOIN sync_operations o ON o.id = d.sync_operation_id
+            WHERE d.entity_type = $1 AND d.entity_id = $2
+              AND ($3::text IS NULL OR o.county_id = $3)
+              AND upper(d.sync_status) = 'SYNCED'
+              AND d.created_at <= $4
+            ORDER BY d.created_at DESC, d.id DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(entity_type)
+        .bind(entity_id)
+        .bind(county_id)
+        .bind(as_of)
+        .fetch_optional(pool)
+        .await?;
+        Ok(record)
+    }
+
+    /// Every entity of the county's layer that existed at `as_of`, as synced
+    /// by the pairs feeding the layer (see `datasets::layer_for_target`)
+    pub async fn layer_as_of(
+        pool: &PgPool,
+        county_id: &str,
+        layer_id: &str,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<HistoricalRecord>> {
+        let records = sqlx::query_as::<_, HistoricalRecord>(
+            r#"
+            SELECT * FROM (
+                SELECT DISTINCT ON (d.entity_type, d.entity_id)
+                    o.county_id, d.entity_type, d.entity_id, d.change_type, d.source_data AS data,
+                    d.sync_operation_id, d.created_at AS changed_at
+                FROM sync_diffs d
+                JOIN sync_operations o ON o.id = d.sync_operation_id
+                JOIN sync_pairs p ON p.id = o.sync_pair_id
+                WHERE p.county_id = $1
+                  AND COALESCE(NULLIF(btrim(p.target_config->>'layer'), ''), NULLIF(btrim(p.target_config->>'table'), '')) = $2
+                  AND upper(d.sync_status) = 'SYNCED'
+                  AND d.created_at <= $3
+                ORDER BY d.entity_type, d.entity_id, d.created_at DESC, d.id DESC
+            ) latest
+            WHERE upper(change_type) NOT IN ('DELETED', 'DELETE', 'REMOVED')
+            ORDER BY entity_type, entity_id
+            "#,
+        )
+        .bind(county_id)
+        .bind(layer_id)
+        .bind(as_of)
+        .fetch_all(pool)
+        .await?;
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_as_of() {
+        let end_of_day = parse_as_of("2023-01-01").unwrap();
+        assert_eq!(end_of_day.to_rfc3339(), "2023-01-01T23:59:59.999999+00:00");
+        let timestamp = parse_as_of("2023-01-01T08:00:00-08:00").unwrap();
+        assert_eq!(timestamp.to_rfc3339(), "2023-01-01T16:00:00+00:00");
+
+        assert!(parse_as_of("January 1st").is_err());
+        assert!(parse_as_of("2999-01-01").is_err());
+        assert_eq!(as_of_parameter(Some(&json!({"compression": "zip"}))).unwrap(), None);
+        assert!(as_of_parameter(Some(&json!({"as_of": 20230101}))).is_err());
+    }
+
+    #[test]
+    fn test_removals() {
+        assert!(is_removal("DELETED"));
+        assert!(is_removal("deleted"));
+        assert!(!is_removal("MODIFIED"));
+    }
+}
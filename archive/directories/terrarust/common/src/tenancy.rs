@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{Error, Result};
+
+/// Header carrying the caller's county from the gateway to backend services
+pub const COUNTY_HEADER: &str = "X-County-ID";
+
+/// Header marking the caller as a platform administrator (cross-county access)
+pub const PLATFORM_ADMIN_HEADER: &str = "X-Platform-Admin";
+
+/// Header carrying the caller's roles (comma-separated) from the gateway to backend services
+pub const ROLES_HEADER: &str = "X-User-Roles";
+
+/// Value of `app.county_id` that disables row-level filtering
+pub const ALL_COUNTIES: &str = "*";
+
+/// The county a request is scoped to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CountyContext {
+    pub county_id: String,
+    /// Platform administrators may act on any county
+    pub is_platform_admin: bool,
+    /// Caller's roles, used for role-scoped feature flags
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+impl CountyContext {
+    /// Create a context for a single county
+    pub fn new(county_id: impl Into<String>) -> Self {
+        Self {
+            county_id: county_id.into(),
+            is_platform_admin: false,
+            roles: Vec::new(),
+        }
+    }
+
+    /// Create a context for a platform administrator
+    pub fn platform_admin(county_id: impl Into<String>) -> Self {
+        Self {
+            county_id: county_id.into(),
+            is_platform_admin: true,
+            roles: Vec::new(),
+        }
+    }
+
+    /// Attach the caller's roles
+    pub fn with_roles(mut self, roles: Vec<String>) -> Self {
+        self.roles = roles;
+        self
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    /// Whether this context may access the given county's data
+    pub fn can_access(&self, county_id: &str) -> bool {
+        self.is_platform_admin || self.county_id == county_id
+    }
+
+    /// Return an authorization error unless the county is accessible
+    pub fn ensure_access(&self, county_id: &str) -> Result<()> {
+        if self.can_access(county_id) {
+            Ok(())
+        } else {
+            Err(Error::Authorization(format!("Access to county {} is not permitted", county_id)))
+        }
+    }
+
+    /// Resolve the county to filter on, honoring an explicit request filter
+    /// only when the caller is allowed to see it
+    pub fn effective_county(&self, requested: Option<&str>) -> Result<Option<String>> {
+        match requested {
+            Some(county_id) => {
+                self.ensure_access(county_id)?;
+                Ok(Some(county_id.to_string()))
+            }
+            None if self.is_platform_admin => Ok(None),
+            None => Ok(Some(self.county_id.clone())),
+        }
+    }
+
+    /// Value for the `app.county_id` session setting used by RLS policies
+    pub fn session_value(&self) -> &str {
+        if self.is_platform_admin {
+            ALL_COUNTIES
+        } else {
+            &self.county_id
+        }
+    }
+}
+
+#[cfg(feature = "actix")]
+pub(crate) mod extractor {
+    use actix_web::{dev::Payload, error, FromRequest, HttpMessage, HttpRequest};
+    use std::future::{ready, Ready};
+
+    use super::{CountyContext, COUNTY_HEADER, PLATFORM_ADMIN_HEADER, ROLES_HEADER};
+
+    /// Resolves the county from request extensions (set by auth middleware)
+    /// or from the internal gateway headers
+    impl FromRequest for CountyContext {
+        type Error = actix_web::Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+            ready(county_from_request(req))
+        }
+    }
+
+    pub(crate) fn county_from_request(req: &HttpRequest) -> Result<CountyContext, actix_web::Error> {
+        if let Some(context) = req.extensions().get::<CountyContext>() {
+            return Ok(context.clone());
+        }
+
+        let county_id = req.headers()
+            .get(COUNTY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+
+        let is_platform_admin = req.headers()
+            .get(PLATFORM_ADMIN_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        let roles = req.headers()
+            .get(ROLES_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| {
+                value.split(',')
+                    .map(|role| role.trim().to_string())
+                    .filter(|role| !role.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        match county_id {
+            Some(county_id) => Ok(CountyContext { county_id, is_platform_admin, roles }),
+            None => Err(error::ErrorBadRequest(format!("Missing {} header", COUNTY_HEADER))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_county() {
+        let county = CountyContext::new("benton");
+        assert_eq!(county.effective_county(None).unwrap(), Some("benton".to_string()));
+        assert!(county.effective_county(Some("franklin")).is_err());
+
+        let admin = CountyContext::platform_admin("benton");
+        assert_eq!(admin.effective_county(None).unwrap(), None);
+        assert_eq!(admin.effective_county(Some("franklin")).unwrap(), Some("franklin".to_string()));
+    }
+}
@@ -0,0 +1,108 @@
+//! TLS certificates that can be replaced on disk while a server is running
+
+use openssl::ssl::{NameType, SniError, SslAcceptor, SslAcceptorBuilder, SslContext, SslFiletype, SslMethod};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use crate::errors::{Error, Result};
+
+/// Certificate and key files backing an acceptor, re-read when they change
+///
+/// The acceptor returned by [`ReloadingTls::acceptor`] swaps in the latest context from
+/// the SNI callback, so renewed certificates apply to new connections without a restart.
+/// Clients that do not send SNI keep the certificate loaded at startup.
+#[derive(Clone)]
+pub struct ReloadingTls {
+    cert_file: PathBuf,
+    key_file: PathBuf,
+    context: Arc<RwLock<SslContext>>,
+}
+
+impl ReloadingTls {
+    /// Load the initial certificate chain and private key
+    pub fn load(cert_file: impl Into<PathBuf>, key_file: impl Into<PathBuf>) -> Result<Self> {
+        let cert_file = cert_file.into();
+        let key_file = key_file.into();
+        let context = build_context(&cert_file, &key_file)?;
+
+        Ok(Self {
+            cert_file,
+            key_file,
+            context: Arc::new(RwLock::new(context)),
+        })
+    }
+
+    /// Acceptor builder for `HttpServer::bind_openssl`
+    pub fn acceptor(&self) -> Result<SslAcceptorBuilder> {
+        let mut builder = acceptor_builder(&self.cert_file, &self.key_file)?;
+        let context = self.context.clone();
+
+        builder.set_servername_callback(move |ssl, _alert| {
+            if ssl.servername(NameType::HOST_NAME).is_none() {
+                return Ok(());
+            }
+            let context = context.read().map_err(|_| SniError::ALERT_FATAL)?;
+            ssl.set_ssl_context(&context).map_err(|_| SniError::ALERT_FATAL)
+        });
+
+        Ok(builder)
+    }
+
+    /// Re-read the certificate files, keeping the current context if they are invalid
+    pub fn reload(&self) -> Result<()> {
+        let context = build_context(&self.cert_file, &self.key_file)?;
+        *self.context.write().map_err(|_| Error::Internal("TLS context lock poisoned".to_string()))? = context;
+
+        log::info!("Reloaded TLS certificate from {}", self.cert_file.display());
+        Ok(())
+    }
+
+    /// Poll the certificate and key for modifications and reload when they change
+    pub fn spawn_watcher(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let tls = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut last_modified = tls.modified_time();
+
+            loop {
+                ticker.tick().await;
+
+                let modified = tls.modified_time();
+                if modified == last_modified {
+                    continue;
+                }
+
+                // A renewal may have replaced only one of the two files so far; retry next tick
+                match tls.reload() {
+                    Ok(()) => last_modified = modified,
+                    Err(e) => log::warn!("TLS certificate not reloaded yet: {}", e),
+                }
+            }
+        })
+    }
+
+    fn modified_time(&self) -> (Option<SystemTime>, Option<SystemTime>) {
+        let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        (modified(&self.cert_file), modified(&self.key_file))
+    }
+}
+
+fn acceptor_builder(cert_file: &Path, key_file: &Path) -> Result<SslAcceptorBuilder> {
+    let tls_error = |e: openssl::error::ErrorStack| {
+        Error::Config(format!("Invalid TLS certificate {}: {}", cert_file.display(), e))
+    };
+
+    let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).map_err(tls_error)?;
+    builder.set_private_key_file(key_file, SslFiletype::PEM).map_err(tls_error)?;
+    builder.set_certificate_chain_file(cert_file).map_err(tls_error)?;
+    builder.check_private_key().map_err(tls_error)?;
+
+    Ok(builder)
+}
+
+fn build_context(cert_file: &Path, key_file: &Path) -> Result<SslContext> {
+    Ok(acceptor_builder(cert_file, key_file)?.build().into_context())
+}
@@ -0,0 +1,329 @@
+//! Usage accounting for chargeback in multi-county hosted deployments.
+//!
+//! Services record what each county (and, where a user started the work,
+//! each user) consumed into monthly totals in `usage_monthly`:
+//!
+//! - `sync_operations`: sync operations started
+//! - `sync_records`: records processed by sync operations, with the size of
+//!   their source payloads
+//! - `exports`: completed GIS exports, with the size of their files
+//! - `exported_features`: features written to completed exports
+//! - `storage`: export files kept, as the month's peak
+//!
+//! Recording is best-effort; a failed write is logged by the caller and
+//! never fails the work being accounted for.
+//!
+//! A county's `usage_quotas` cap the month's sync operations, exported
+//! features and exported gigabytes. Once a cap is reached new work is
+//! refused with a 429 until the next month, unless an administrator passes
+//! `"override_quota": true` with it.
+
+use std::fmt;
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::errors::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageMetric {
+    SyncOperations,
+    SyncRecords,
+    Exports,
+    ExportedFeatures,
+    Storage,
+}
+
+impl UsageMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageMetric::SyncOperations => "sync_operations",
+            UsageMetric::SyncRecords => "sync_records",
+            UsageMetric::Exports => "exports",
+            UsageMetric::ExportedFeatures => "exported_features",
+            UsageMetric::Storage => "storage",
+        }
+    }
+}
+
+impl fmt::Display for UsageMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// First day of the month `at` falls in
+pub fn month_start(at: DateTime<Utc>) -> NaiveDate {
+    NaiveDate::from_ymd_opt(at.year(), at.month(), 1).expect("first of the month is a valid date")
+}
+
+/// A month given as `YYYY-MM`
+pub fn parse_month(month: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d")
+        .map_err(|_| Error::Validation(format!("Invalid month '{}', expected YYYY-MM", month)))
+}
+
+/// Parameter with which an administrator runs work past a county's quotas
+pub const OVERRIDE_QUOTA_PARAMETER: &str = "override_quota";
+
+/// Whether job or operation parameters ask to override quotas
+pub fn quota_overridden(parameters: Option<&Value>) -> bool {
+    parameters
+        .and_then(|parameters| parameters.get(OVERRIDE_QUOTA_PARAMETER))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// A county's monthly limits; a missing limit is unlimited
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UsageQuotas {
+    pub max_sync_operations: Option<i64>,
+    pub max_exported_features: Option<i64>,
+    pub max_exported_gb: Option<f64>,
+}
+
+/// What a county has used so far in a month
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MonthToDate {
+    pub month: NaiveDate,
+    pub sync_operations: i64,
+    pub exported_features: i64,
+    pub exported_bytes: i64,
+}
+
+impl UsageQuotas {
+    /// Refuse a new sync operation once the month's operations are used up
+    pub fn check_sync_operation(&self, county_id: &str, usage: &MonthToDate) -> Result<()> {
+        match self.max_sync_operations {
+            Some(max) if usage.sync_operations >= max => Err(exceeded(
+                county_id,
+                format!("{} sync operations", max),
+                format!("{} started", usage.sync_operations),
+                usage.month,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Refuse a new export once the month's exported features or gigabytes
+    /// are used up
+    pub fn check_export(&self, county_id: &str, usage: &MonthToDate) -> Result<()> {
+        if let Some(max) = self.max_exported_features.filter(|max| usage.exported_features >= *max) {
+            return Err(exceeded(
+                county_id,
+                format!("{} exported features", max),
+                format!("{} exported", usage.exported_features),
+                usage.month,
+            ));
+        }
+        let exported_gb = usage.exported_bytes as f64 / BYTES_PER_GB;
+        match self.max_exported_gb {
+            Some(max) if exported_gb >= max => Err(exceeded(
+                county_id,
+                format!("{} GB of exports", max),
+                format!("{:.2} GB exported", exported_gb),
+                usage.month,
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn exceeded(county_id: &str, quota: String, used: String, month: NaiveDate) -> Error {
+    let resets = month.checked_add_months(Months::new(1)).unwrap_or(month);
+    Error::QuotaExceeded(format!(
+        "County {} has reached its monthly quota of {} ({}); it resets on {}. An administrator can pass \"{}\": true to run it anyway",
+        county_id, quota, used, resets, OVERRIDE_QUOTA_PARAMETER
+    ))
+}
+
+/// One month of one metric, for a county or one of its users
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UsageRow {
+    pub county_id: String,
+    /// Empty unless the report is broken down by user, and for usage no
+    /// user started
+    pub username: String,
+    pub month: NaiveDate,
+    pub metric: String,
+    pub quantity: i64,
+    pub bytes: i64,
+}
+
+/// Filter for usage reports
+#[derive(Debug, Clone, Default)]
+pub struct UsageFilter {
+    /// `None` for every county
+    pub county_id: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub by_user: bool,
+}
+
+/// Persistence for usage totals
+pub struct UsageQueries;
+
+impl UsageQueries {
+    /// Add to this month's total of a cumulative metric
+    pub async fn record(
+        pool: &PgPool,
+        county_id: &str,
+        username: Option<&str>,
+        metric: UsageMetric,
+        quantity: i64,
+        bytes: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO usage_monthly (county_id, username, month, metric, quantity, bytes)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (county_id, month, metric, username) DO UPDATE
+            SET quantity = usage_monthly.quantity + EXCLUDED.quantity,
+                bytes = usage_monthly.bytes + EXCLUDED.bytes,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(county_id)
+        .bind(username.unwrap_or_default())
+        .bind(month_start(Utc::now()))
+        .bind(metric.as_str())
+        .bind(quantity)
+        .bind(bytes)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a county's current storage, keeping the month's peak
+    pub async fn record_storage(pool: &PgPool, county_id: &str, files: i64, bytes: i64) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO usage_monthly (county_id, username, month, metric, quantity, bytes)
+            VALUES ($1, '', $2, $3, $4, $5)
+            ON CONFLICT (county_id, month, metric, username) DO UPDATE
+            SET quantity = GREATEST(usage_monthly.quantity, EXCLUDED.quantity),
+                bytes = GREATEST(usage_monthly.bytes, EXCLUDED.bytes),
+                updated_at = NOW()
+            "#,
+        )
+        .bind(county_id)
+        .bind(month_start(Utc::now()))
+        .bind(UsageMetric::Storage.as_str())
+        .bind(files)
+        .bind(bytes)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// A county's usage in the current month
+    pub async fn month_to_date(pool: &PgPool, county_id: &str) -> Result<MonthToDate> {
+        let month = month_start(Utc::now());
+        let totals: Vec<(String, i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT metric, SUM(quantity)::BIGINT, SUM(bytes)::BIGINT
+            FROM usage_monthly
+            WHERE county_id = $1 AND month = $2
+            GROUP BY metric
+            "#,
+        )
+        .bind(county_id)
+        .bind(month)
+        .fetch_all(pool)
+        .await?;
+
+        let mut usage = MonthToDate { month, ..Default::default() };
+        for (metric, quantity, bytes) in totals {
+            if metric == UsageMetric::SyncOperations.as_str() {
+                usage.sync_operations = quantity;
+            } else if metric == UsageMetric::ExportedFeatures.as_str() {
+                usage.exported_features = quantity;
+            } else if metric == UsageMetric::Exports.as_str() {
+                usage.exported_bytes = bytes;
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Monthly totals, oldest month first
+    pub async fn report(pool: &PgPool, filter: &UsageFilter) -> Result<Vec<UsageRow>> {
+        let rows = sqlx::query_as::<_, UsageRow>(
+            r#"
+            SELECT county_id,
+                   CASE WHEN $4 THEN username ELSE '' END AS username,
+                   month,
+                   metric,
+                   SUM(quantity)::BIGINT AS quantity,
+                   SUM(bytes)::BIGINT AS bytes
+            FROM usage_monthly
+            WHERE ($1::VARCHAR IS NULL OR county_id = $1)
+              AND ($2::DATE IS NULL OR month >= $2)
+              AND ($3::DATE IS NULL OR month <= $3)
+            GROUP BY county_id, 2, month, metric
+            ORDER BY month, county_id, metric, 2
+            "#,
+        )
+        .bind(filter.county_id.as_deref())
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.by_user)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_months() {
+        let at = Utc.with_ymd_and_hms(2024, 2, 29, 23, 59, 0).unwrap();
+        assert_eq!(month_start(at), NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+        assert_eq!(parse_month("2024-02").unwrap(), month_start(at));
+        assert!(parse_month("2024-13").is_err());
+        assert!(parse_month("February").is_err());
+    }
+
+    #[test]
+    fn test_quotas() {
+        let quotas = UsageQuotas {
+            max_sync_operations: Some(100),
+            max_exported_features: None,
+            max_exported_gb: Some(2.0),
+        };
+        let mut usage = MonthToDate {
+            month: parse_month("2024-12").unwrap(),
+            sync_operations: 99,
+            exported_features: 5_000_000,
+            exported_bytes: 1024 * 1024 * 1024,
+        };
+        assert!(quotas.check_sync_operation("benton", &usage).is_ok());
+        assert!(quotas.check_export("benton", &usage).is_ok());
+
+        usage.sync_operations = 100;
+        usage.exported_bytes *= 2;
+        let error = quotas.check_sync_operation("benton", &usage).unwrap_err();
+        assert_eq!(error.status_code(), 429);
+        assert!(error.to_string().contains("resets on 2025-01-01"));
+        assert!(quotas.check_export("benton", &usage).unwrap_err().to_string().contains("2 GB of exports"));
+        assert!(UsageQuotas::default().check_export("benton", &usage).is_ok());
+    }
+
+    #[test]
+    fn test_quota_overridden() {
+        assert!(quota_overridden(Some(&serde_json::json!({ "override_quota": true }))));
+        assert!(!quota_overridden(Some(&serde_json::json!({ "override_quota": "yes" }))));
+        assert!(!quota_overridden(None));
+    }
+}
@@ -2,20 +2,33 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 use crate::error::{Error, Result};
 use crate::models::gis_export::{CountyConfiguration, LayerDefinition, RateLimits};
 
 // Cache for county configurations to avoid repeated file reads
-static mut CONFIG_CACHE: Option<HashMap<String, CountyConfiguration>> = None;
+static mut CONFIG_CACHE: Option<HashMap<String, (CountyConfiguration, Instant)>> = None;
+
+// Cache TTL in seconds, adjustable at runtime through the reload handle
+static CACHE_TTL_SECONDS: AtomicU64 = AtomicU64::new(300);
+
+/// Set how long cached county configurations stay valid
+pub fn set_cache_ttl(ttl: Duration) {
+    CACHE_TTL_SECONDS.store(ttl.as_secs(), Ordering::Relaxed);
+}
 
 /// Load a county configuration from file or cache
 pub async fn load_county_configuration(county_id: &str) -> Result<CountyConfiguration> {
     // Check cache first
     unsafe {
         if let Some(cache) = &CONFIG_CACHE {
-            if let Some(config) = cache.get(county_id) {
-                return Ok(config.clone());
+            if let Some((config, loaded_at)) = cache.get(county_id) {
+                let ttl = Duration::from_secs(CACHE_TTL_SECONDS.load(Ordering::Relaxed));
+                if loaded_at.elapsed() < ttl {
+                    return Ok(config.clone());
+                }
             }
         }
     }
@@ -31,7 +44,7 @@ pub async fn load_county_configuration(county_id: &str) -> Result<CountyConfigur
         }
         
         if let Some(cache) = &mut CONFIG_CACHE {
-            cache.insert(county_id.to_string(), config.clone());
+            cache.insert(county_id.to_string(), (config.clone(), Instant::now()));
         }
     }
     
@@ -53,6 +66,43 @@ fn load_config_from_file<P: AsRef<Path>>(path: P) -> Result<CountyConfiguration>
     Ok(config)
 }
 
+/// IDs of the counties that have a configuration file, sorted
+pub fn list_configured_counties() -> Result<Vec<String>> {
+    let entries = match std::fs::read_dir("county_configs") {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::Internal(format!("Failed to read county config directory: {}", e))),
+    };
+    
+    let mut county_ids: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("config.json").is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    county_ids.sort();
+    Ok(county_ids)
+}
+
+/// Write a county configuration to its config file and drop the cached copy
+pub async fn save_county_configuration(config: &CountyConfiguration) -> Result<()> {
+    let config_dir = Path::new("county_configs").join(&config.county_id);
+    std::fs::create_dir_all(&config_dir)
+        .map_err(|e| Error::Internal(format!("Failed to create county config directory: {}", e)))?;
+    
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| Error::Internal(format!("Failed to serialize county config: {}", e)))?;
+    std::fs::write(config_dir.join("config.json"), content)
+        .map_err(|e| Error::Internal(format!("Failed to write county config file: {}", e)))?;
+    
+    unsafe {
+        if let Some(cache) = &mut CONFIG_CACHE {
+            cache.remove(&config.county_id);
+        }
+    }
+    
+    Ok(())
+}
+
 /// Apply county-specific default parameters to an export request
 pub fn apply_county_defaults(params: &mut serde_json::Value, county_config: &CountyConfiguration) {
     if let (Some(default_params), Some(request_params)) = (county_config.default_parameters.as_object(), params.as_object_mut()) {
@@ -136,5 +186,11 @@ pub fn generate_default_config(county_id: &str) -> CountyConfiguration {
             "include_metadata": true
         }),
         authentication_required: true,
+        export_redaction: Default::default(),
+        boundary: None,
+        publishing_targets: Vec::new(),
+        delivery_destinations: Vec::new(),
+        export_approval: None,
+        usage_quotas: Default::default(),
     }
 }
\ No newline at end of file
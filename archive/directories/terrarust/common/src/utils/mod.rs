@@ -1 +1,2 @@
-pub mod county_config;
\ No newline at end of file
+pub mod county_config;
+pub mod validation;
\ No newline at end of file
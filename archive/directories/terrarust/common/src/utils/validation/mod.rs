@@ -1,13 +1,14 @@
-use crate::error::{Error, Result};
+use serde::Serialize;
 use serde_json::Value;
-use std::collections::HashMap;
 
+#[derive(Debug, Serialize)]
 pub struct ValidationResult {
     pub is_valid: bool,
     pub errors: Vec<ValidationError>,
     pub warnings: Vec<ValidationWarning>,
 }
 
+#[derive(Debug, Serialize)]
 pub struct ValidationError {
     pub field: String,
     pub message: String,
@@ -15,6 +16,7 @@ pub struct ValidationError {
     pub details: Option<Value>,
 }
 
+#[derive(Debug, Serialize)]
 pub struct ValidationWarning {
     pub field: String,
     pub message: String,
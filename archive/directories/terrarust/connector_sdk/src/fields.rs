@@ -0,0 +1,159 @@
+//! Field descriptions shared by connectors and field mapping
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use terrafusion_common::geo::{parse, GeometryFormat};
+
+/// Records read to infer a schema when the system has no catalog
+pub const SCHEMA_SAMPLE_SIZE: usize = 50;
+
+/// Broad field types used to judge whether two fields can be mapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Date,
+    Timestamp,
+    Json,
+    Geometry,
+    Unknown,
+}
+
+impl FieldType {
+    /// Map a PostgreSQL `data_type`/`udt_name` to a field type
+    pub fn from_postgres(data_type: &str, udt_name: &str) -> Self {
+        match (data_type, udt_name) {
+            (_, "geometry") | (_, "geography") => Self::Geometry,
+            ("smallint", _) | ("integer", _) | ("bigint", _) => Self::Integer,
+            ("numeric", _) | ("real", _) | ("double precision", _) | ("money", _) => Self::Number,
+            ("boolean", _) => Self::Boolean,
+            ("date", _) => Self::Date,
+            (data_type, _) if data_type.starts_with("timestamp") => Self::Timestamp,
+            ("json", _) | ("jsonb", _) | ("ARRAY", _) => Self::Json,
+            ("character varying", _) | ("character", _) | ("text", _) | ("uuid", _) => Self::String,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Infer the type of a sampled value; CSV values arrive as strings
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::Bool(_) => Some(Self::Boolean),
+            Value::Number(n) if n.is_i64() || n.is_u64() => Some(Self::Integer),
+            Value::Number(_) => Some(Self::Number),
+            Value::Array(_) => Some(Self::Json),
+            Value::Object(map) if map.contains_key("type") && map.contains_key("coordinates") => Some(Self::Geometry),
+            Value::Object(_) => Some(Self::Json),
+            Value::String(text) => Some(infer_string_type(text)),
+        }
+    }
+}
+
+fn infer_string_type(text: &str) -> FieldType {
+    let text = text.trim();
+    if text.is_empty() {
+        FieldType::Unknown
+    } else if text.parse::<i64>().is_ok() {
+        FieldType::Integer
+    } else if text.parse::<f64>().is_ok() {
+        FieldType::Number
+    } else if text.eq_ignore_ascii_case("true") || text.eq_ignore_ascii_case("false") {
+        FieldType::Boolean
+    } else if chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d").is_ok() {
+        FieldType::Date
+    } else if chrono::DateTime::parse_from_rfc3339(text).is_ok() {
+        FieldType::Timestamp
+    } else if is_geometry_text(text) {
+        FieldType::Geometry
+    } else {
+        FieldType::String
+    }
+}
+
+/// WKT, or hex WKB as `row_to_json` renders PostGIS columns
+fn is_geometry_text(text: &str) -> bool {
+    matches!(parse::detect_format(text), Some(GeometryFormat::Wkt | GeometryFormat::Wkb))
+        && parse::parse_geometry(&Value::String(text.to_string())).is_ok()
+}
+
+/// A field exposed by a source or target system
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldInfo {
+    pub name: String,
+    pub field_type: FieldType,
+    pub nullable: bool,
+}
+
+/// Derive fields from sampled records, in first-seen order
+///
+/// A field whose samples disagree on type is reported as a string (or number
+/// when only integers and decimals are mixed).
+pub fn infer_fields(records: &[Value]) -> Vec<FieldInfo> {
+    let mut fields: Vec<(String, Option<FieldType>, bool)> = Vec::new();
+
+    for record in records.iter().filter_map(Value::as_object) {
+        for (name, value) in record {
+            let index = match fields.iter().position(|(field, _, _)| field == name) {
+                Some(index) => index,
+                None => {
+                    fields.push((name.clone(), None, false));
+                    fields.len() - 1
+                }
+            };
+            let (_, field_type, nullable) = &mut fields[index];
+            match FieldType::from_value(value) {
+                None | Some(FieldType::Unknown) => *nullable = true,
+                Some(observed) => {
+                    *field_type = Some(match *field_type {
+                        None => observed,
+                        Some(current) if current == observed => current,
+                        Some(FieldType::Integer) | Some(FieldType::Number)
+                            if matches!(observed, FieldType::Integer | FieldType::Number) => FieldType::Number,
+                        Some(_) => FieldType::String,
+                    })
+                }
+            }
+        }
+    }
+
+    // Fields missing from some records are nullable too
+    let record_count = records.len();
+    fields
+        .into_iter()
+        .map(|(name, field_type, nullable)| {
+            let present = records.iter().filter(|record| record.get(&name).is_some()).count();
+            FieldInfo {
+                nullable: nullable || present < record_count,
+                field_type: field_type.unwrap_or(FieldType::Unknown),
+                name,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_infer_fields() {
+        let fields = infer_fields(&[
+            json!({ "parcel_id": "12345", "acres": "1.5", "sale_date": "2023-04-01" }),
+            json!({ "parcel_id": "12346", "acres": "2", "owner": "Smith", "shape": "POINT(-119.2 46.2)" }),
+        ]);
+        let types: Vec<(&str, FieldType, bool)> =
+            fields.iter().map(|f| (f.name.as_str(), f.field_type, f.nullable)).collect();
+        assert_eq!(types, vec![
+            ("acres", FieldType::Number, false),
+            ("parcel_id", FieldType::Integer, false),
+            ("sale_date", FieldType::Date, true),
+            ("owner", FieldType::String, true),
+            ("shape", FieldType::Geometry, true),
+        ]);
+    }
+}
@@ -0,0 +1,84 @@
+//! SDK for TerraFusion sync connectors.
+//!
+//! A connector reads records from a county system (and describes its
+//! fields) so the sync service can sample, map and synchronize it. The sync
+//! service ships PostgreSQL, HTTP and CSV connectors; anything else is built
+//! against this crate:
+//!
+//! 1. implement [`Connector`] for the system,
+//! 2. implement [`ConnectorFactory`] to build it from a sync pair's
+//!    source or target config,
+//! 3. register the factory with [`export_connector!`], and
+//! 4. check it with [`testing::check_conformance`].
+//!
+//! A sync pair selects a plugin connector with `"connector": "<kind>"` in its
+//! config. The whole config object is handed to the factory.
+//!
+//! To ship a connector, publish it as a crate and add it to the sync
+//! service as an optional dependency behind a feature of the same name,
+//! with `use <crate> as _;` in `main.rs` under that feature so the linker
+//! keeps its registration.
+//!
+//! Connectors are linked into the sync service binary at build time rather
+//! than loaded from shared libraries, since Rust has no stable ABI for trait
+//! objects. The traits are the stable surface: they only change in a major
+//! release of this crate, and a connector built against an incompatible
+//! release fails to compile against the service instead of misbehaving at
+//! run time.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use serde_json::Value;
+
+pub use terrafusion_common::{Error, Result};
+
+pub mod fields;
+pub mod registry;
+pub mod testing;
+
+pub use fields::{infer_fields, FieldInfo, FieldType, SCHEMA_SAMPLE_SIZE};
+pub use registry::ConnectorRegistry;
+
+#[cfg(feature = "static-registry")]
+#[doc(hidden)]
+pub use inventory;
+
+/// Boxed future returned by connectors
+pub type ConnectorFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// A source or target system.
+///
+/// Errors are reported by stage, which the sync service's connectivity check
+/// relies on: `Error::ExternalService` when the system cannot be reached,
+/// `Error::Authentication` when credentials are rejected, and
+/// `Error::DataSync` when records cannot be read once connected. Timeouts
+/// are applied by the caller.
+pub trait Connector: Send + Sync {
+    /// Short name for reports, e.g. `"arcgis"`
+    fn kind(&self) -> &str;
+
+    /// Read up to `limit` records, each a JSON object
+    fn sample<'a>(&'a self, limit: usize) -> ConnectorFuture<'a, Vec<Value>>;
+
+    /// List the fields this system exposes. The default infers them from a
+    /// sample; connectors with a catalog should read it instead.
+    fn describe<'a>(&'a self) -> ConnectorFuture<'a, Vec<FieldInfo>> {
+        Box::pin(async move { Ok(infer_fields(&self.sample(SCHEMA_SAMPLE_SIZE).await?)) })
+    }
+}
+
+/// Builds connectors of one kind from sync pair configs
+pub trait ConnectorFactory: Send + Sync {
+    /// Value of the config's `connector` key that selects this factory
+    fn kind(&self) -> &'static str;
+
+    /// Other accepted spellings of `kind`
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Build a connector from a source or target config, rejecting missing
+    /// or invalid settings with `Error::Validation`
+    fn create(&self, config: &Value) -> Result<Box<dyn Connector>>;
+}
@@ -0,0 +1,101 @@
+//! Lookup of connector factories by kind
+//!
+//! With the `static-registry` feature (on by default) a connector crate
+//! registers its factory with [`export_connector!`](crate::export_connector),
+//! and linking the crate into the sync service is enough to make the kind
+//! available. Without it, factories are registered by hand.
+
+use serde_json::Value;
+
+use crate::{Connector, ConnectorFactory, Error, Result};
+
+/// A factory submitted with `export_connector!`
+#[cfg(feature = "static-registry")]
+pub struct ConnectorRegistration {
+    pub factory: &'static dyn ConnectorFactory,
+}
+
+#[cfg(feature = "static-registry")]
+inventory::collect!(ConnectorRegistration);
+
+/// Make a connector factory available to every binary the crate is linked
+/// into. The argument must be a constant, typically a unit struct:
+///
+/// ```ignore
+/// struct ArcGisFactory;
+/// impl ConnectorFactory for ArcGisFactory { /* ... */ }
+/// terrafusion_connector_sdk::export_connector!(ArcGisFactory);
+/// ```
+#[cfg(feature = "static-registry")]
+#[macro_export]
+macro_rules! export_connector {
+    ($factory:expr) => {
+        $crate::inventory::submit! {
+            $crate::registry::ConnectorRegistration { factory: &$factory }
+        }
+    };
+}
+
+/// Connector factories by kind
+#[derive(Default)]
+pub struct ConnectorRegistry {
+    factories: Vec<&'static dyn ConnectorFactory>,
+}
+
+impl ConnectorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every factory submitted with `export_connector!` in the linked crates
+    #[cfg(feature = "static-registry")]
+    pub fn linked() -> Result<Self> {
+        let mut registry = Self::new();
+        for registration in inventory::iter::<ConnectorRegistration> {
+            registry.register(registration.factory)?;
+        }
+        Ok(registry)
+    }
+
+    /// Add a factory; a kind or alias may only be claimed once
+    pub fn register(&mut self, factory: &'static dyn ConnectorFactory) -> Result<()> {
+        let names = std::iter::once(factory.kind()).chain(factory.aliases().iter().copied());
+        for name in names {
+            if let Some(existing) = self.get(name) {
+                return Err(Error::Config(format!(
+                    "Connector kind `{}` is registered by both `{}` and `{}`",
+                    name,
+                    existing.kind(),
+                    factory.kind()
+                )));
+            }
+        }
+        self.factories.push(factory);
+        Ok(())
+    }
+
+    /// Factory for a kind or alias, ignoring case
+    pub fn get(&self, kind: &str) -> Option<&'static dyn ConnectorFactory> {
+        self.factories.iter().copied().find(|factory| {
+            factory.kind().eq_ignore_ascii_case(kind)
+                || factory.aliases().iter().any(|alias| alias.eq_ignore_ascii_case(kind))
+        })
+    }
+
+    /// Registered kinds, in registration order
+    pub fn kinds(&self) -> Vec<&'static str> {
+        self.factories.iter().map(|factory| factory.kind()).collect()
+    }
+
+    /// Build the connector named by the config's `connector` key
+    pub fn create(&self, config: &Value) -> Result<Box<dyn Connector>> {
+        let kind = config
+            .get("connector")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::Validation("Config needs a `connector` type".to_string()))?;
+        let factory = self
+            .get(kind)
+            .ok_or_else(|| Error::Validation(format!("Unsupported connector type: {}", kind)))?;
+        factory.create(config)
+    }
+}
@@ -0,0 +1,174 @@
+//! Test harness for connector authors
+//!
+//! ```ignore
+//! #[tokio::test]
+//! async fn arcgis_connector_conforms() {
+//!     let connector = ArcGisFactory.create(&json!({ "connector": "arcgis", "url": server.uri() })).unwrap();
+//!     terrafusion_connector_sdk::testing::assert_conforms(connector.as_ref()).await;
+//! }
+//! ```
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+use crate::{Connector, ConnectorFuture, Error};
+
+/// Records sampled by the conformance checks
+const CONFORMANCE_SAMPLE: usize = 5;
+
+/// Check the behaviour the sync service relies on and list every problem
+/// found; an empty list means the connector conforms.
+///
+/// The connector must be able to reach its system (a local file, a mock
+/// server), since the checks read from it.
+pub async fn check_conformance(connector: &dyn Connector) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let kind = connector.kind();
+    if kind.is_empty() || kind.chars().any(char::is_whitespace) {
+        problems.push(format!("kind `{}` must be a non-empty word", kind));
+    }
+
+    match connector.sample(0).await {
+        Ok(records) if !records.is_empty() => {
+            problems.push(format!("sample(0) returned {} records", records.len()))
+        }
+        Ok(_) => {}
+        Err(e) => problems.push(format!("sample(0) failed: {}", describe_error(&e))),
+    }
+
+    let records = match connector.sample(CONFORMANCE_SAMPLE).await {
+        Ok(records) => records,
+        Err(e) => {
+            problems.push(format!("sample({}) failed: {}", CONFORMANCE_SAMPLE, describe_error(&e)));
+            return problems;
+        }
+    };
+    if records.len() > CONFORMANCE_SAMPLE {
+        problems.push(format!("sample({}) returned {} records", CONFORMANCE_SAMPLE, records.len()));
+    }
+    if let Some(index) = records.iter().position(|record| !record.is_object()) {
+        problems.push(format!("record {} is not a JSON object", index));
+    }
+
+    let fields = match connector.describe().await {
+        Ok(fields) => fields,
+        Err(e) => {
+            problems.push(format!("describe() failed: {}", describe_error(&e)));
+            return problems;
+        }
+    };
+    let mut names = HashSet::new();
+    for field in &fields {
+        if field.name.is_empty() {
+            problems.push("describe() returned a field without a name".to_string());
+        } else if !names.insert(field.name.as_str()) {
+            problems.push(format!("describe() returned `{}` more than once", field.name));
+        }
+    }
+    let undescribed: HashSet<&str> = records
+        .iter()
+        .filter_map(Value::as_object)
+        .flat_map(|record| record.keys())
+        .map(String::as_str)
+        .filter(|name| !names.contains(name))
+        .collect();
+    if !undescribed.is_empty() {
+        let mut undescribed: Vec<_> = undescribed.into_iter().collect();
+        undescribed.sort_unstable();
+        problems.push(format!("sampled fields missing from describe(): {}", undescribed.join(", ")));
+    }
+
+    problems
+}
+
+/// Panic with every conformance problem found
+pub async fn assert_conforms(connector: &dyn Connector) {
+    let problems = check_conformance(connector).await;
+    assert!(
+        problems.is_empty(),
+        "connector `{}` does not conform:\n  - {}",
+        connector.kind(),
+        problems.join("\n  - ")
+    );
+}
+
+/// Errors outside the staged variants are flagged, since the connectivity
+/// check cannot place them
+fn describe_error(error: &Error) -> String {
+    match error {
+        Error::ExternalService(_) | Error::Authentication(_) | Error::DataSync(_) => error.to_string(),
+        other => format!("{} (report failures as ExternalService, Authentication or DataSync)", other),
+    }
+}
+
+/// A connector over fixed records, for tests of code that consumes connectors
+#[derive(Debug, Clone)]
+pub struct MemoryConnector {
+    pub kind: String,
+    pub records: Vec<Value>,
+}
+
+impl MemoryConnector {
+    pub fn new(kind: impl Into<String>, records: Vec<Value>) -> Self {
+        Self { kind: kind.into(), records }
+    }
+}
+
+impl Connector for MemoryConnector {
+    fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    fn sample<'a>(&'a self, limit: usize) -> ConnectorFuture<'a, Vec<Value>> {
+        Box::pin(async move { Ok(self.records.iter().take(limit).cloned().collect()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConnectorFactory, ConnectorRegistry, FieldType, Result};
+    use serde_json::json;
+
+    struct MemoryFactory;
+
+    impl ConnectorFactory for MemoryFactory {
+        fn kind(&self) -> &'static str {
+            "memory"
+        }
+
+        fn aliases(&self) -> &'static [&'static str] {
+            &["mem"]
+        }
+
+        fn create(&self, config: &Value) -> Result<Box<dyn Connector>> {
+            let records = config
+                .get("records")
+                .and_then(Value::as_array)
+                .ok_or_else(|| Error::Validation("memory connector requires `records`".to_string()))?;
+            Ok(Box::new(MemoryConnector::new("memory", records.clone())))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_and_conformance() {
+        let mut registry = ConnectorRegistry::new();
+        registry.register(&MemoryFactory).unwrap();
+        assert!(registry.register(&MemoryFactory).is_err());
+
+        let connector = registry
+            .create(&json!({ "connector": "MEM", "records": [{ "parcel_id": 1 }, { "parcel_id": 2, "owner": null }] }))
+            .unwrap();
+        assert_eq!(check_conformance(connector.as_ref()).await, Vec::<String>::new());
+
+        let fields = connector.describe().await.unwrap();
+        assert_eq!(fields[0].field_type, FieldType::Integer);
+        assert!(fields[1].nullable);
+
+        assert!(registry.create(&json!({ "connector": "arcgis" })).is_err());
+        let problems = check_conformance(&MemoryConnector::new("bad kind", vec![json!([1, 2])])).await;
+        assert_eq!(problems.len(), 2);
+    }
+}
@@ -0,0 +1,146 @@
+//! Published exports described for open-data portals
+//!
+//! State aggregators harvest county catalogs as DCAT in JSON-LD: the
+//! listing is a `dcat:Catalog` and each published export a `dcat:Dataset`
+//! with one distribution, its download. Download links are built on
+//! EXPORT_PUBLIC_URL and are relative to the portal without it. As GeoJSON,
+//! an export is a Feature whose geometry is the area it covers.
+
+use serde_json::{json, Map, Value};
+
+use crate::models::PublishedExport;
+use crate::ExportFormat;
+
+fn context() -> Value {
+    json!({
+        "dcat": "http://www.w3.org/ns/dcat#",
+        "dct": "http://purl.org/dc/terms/",
+        "foaf": "http://xmlns.com/foaf/0.1/",
+        "spdx": "http://spdx.org/rdf/terms#",
+        "xsd": "http://www.w3.org/2001/XMLSchema#",
+    })
+}
+
+/// URL of a published export on the public portal
+pub fn export_url(base_url: &str, export: &PublishedExport) -> String {
+    format!("{}/public/exports/{}", base_url.trim_end_matches('/'), export.id)
+}
+
+fn layer_names(export: &PublishedExport) -> Vec<String> {
+    serde_json::from_value(export.layers.clone()).unwrap_or_default()
+}
+
+/// A published export as a `dcat:Dataset`, without a context
+pub fn dataset(base_url: &str, export: &PublishedExport) -> Value {
+    let url = export_url(base_url, export);
+    let media_type = export
+        .export_format
+        .parse::<ExportFormat>()
+        .map(|format| format.media_type())
+        .unwrap_or("application/octet-stream");
+
+    let mut distribution = json!({
+        "@type": "dcat:Distribution",
+        "dct:format": export.export_format,
+        "dcat:mediaType": media_type,
+        "dcat:downloadURL": { "@id": format!("{}/download", url) },
+    });
+    if let Some(file_size) = export.file_size {
+        distribution["dcat:byteSize"] = json!({ "@value": file_size.to_string(), "@type": "xsd:decimal" });
+    }
+    if let Some(checksum) = &export.checksum_sha256 {
+        distribution["spdx:checksum"] = json!({
+            "@type": "spdx:Checksum",
+            "spdx:algorithm": { "@id": "spdx:checksumAlgorithm_sha256" },
+            "spdx:checksumValue": checksum,
+        });
+    }
+
+    json!({
+        "@id": url,
+        "@type": "dcat:Dataset",
+        "dct:identifier": export.id,
+        "dct:title": export.title,
+        "dct:description": export.description,
+        "dct:publisher": { "@type": "foaf:Organization", "foaf:name": export.county_id },
+        "dct:issued": export.published_at,
+        "dct:modified": export.completed_at.unwrap_or(export.published_at),
+        "dcat:keyword": layer_names(export),
+        "dcat:landingPage": { "@id": url },
+        "dcat:distribution": [distribution],
+    })
+}
+
+/// JSON-LD document of one published export
+pub fn dataset_document(base_url: &str, export: &PublishedExport) -> Value {
+    let mut document = dataset(base_url, export);
+    document["@context"] = context();
+    document
+}
+
+/// JSON-LD catalog of a page of published exports
+pub fn catalog_document(base_url: &str, exports: &[PublishedExport]) -> Value {
+    json!({
+        "@context": context(),
+        "@id": format!("{}/public/exports", base_url.trim_end_matches('/')),
+        "@type": "dcat:Catalog",
+        "dct:title": "TerraFusion published exports",
+        "dcat:dataset": exports.iter().map(|export| dataset(base_url, export)).collect::<Vec<_>>(),
+    })
+}
+
+/// A published export as a GeoJSON Feature covering its area of interest
+pub fn feature(base_url: &str, export: &PublishedExport) -> Value {
+    let mut properties = match serde_json::to_value(export) {
+        Ok(Value::Object(properties)) => properties,
+        _ => Map::new(),
+    };
+    properties.insert("download_url".to_string(), json!(format!("{}/download", export_url(base_url, export))));
+    terrafusion_common::negotiation::feature(&export.id.to_string(), export.area_of_interest.clone(), properties)
+}
+
+pub fn feature_collection(base_url: &str, exports: &[PublishedExport]) -> Value {
+    json!({
+        "type": "FeatureCollection",
+        "features": exports.iter().map(|export| feature(base_url, export)).collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_dataset_and_feature() {
+        let export = PublishedExport {
+            id: Uuid::new_v4(),
+            county_id: "benton".to_string(),
+            title: "Parcels".to_string(),
+            description: None,
+            export_format: "shapefile".to_string(),
+            layers: json!(["parcels", "zoning"]),
+            file_size: Some(1024),
+            checksum_sha256: None,
+            completed_at: None,
+            published_at: Utc::now(),
+            download_count: 0,
+            area_of_interest: Some(json!({ "type": "Point", "coordinates": [-119.2, 46.2] })),
+        };
+
+        let document = dataset_document("https://gis.example.gov/", &export);
+        let url = format!("https://gis.example.gov/public/exports/{}", export.id);
+        assert_eq!(document["@id"], url.as_str());
+        assert_eq!(document["dcat:keyword"], json!(["parcels", "zoning"]));
+        let distribution = &document["dcat:distribution"][0];
+        assert_eq!(distribution["dcat:mediaType"], "application/zip");
+        assert_eq!(distribution["dcat:downloadURL"]["@id"], format!("{}/download", url).as_str());
+        assert!(distribution.get("spdx:checksum").is_none());
+
+        let feature = feature("", &export);
+        assert_eq!(feature["geometry"]["type"], "Point");
+        assert_eq!(feature["properties"]["title"], "Parcels");
+        assert_eq!(feature["properties"]["download_url"], format!("/public/exports/{}/download", export.id).as_str());
+    }
+}
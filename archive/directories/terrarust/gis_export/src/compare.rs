@@ -0,0 +1,290 @@
+//! Change reports between two runs of the same export
+//!
+//! Every export keeps a snapshot of the features it wrote, gzipped GeoJSON
+//! next to the export file, whatever format was delivered. Comparing two
+//! runs matches their snapshots' features by layer and `id` and reports
+//! what was added, removed or modified, so recipients of a recurring
+//! export can load only the changes.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use ::geojson::{Feature, JsonObject};
+use serde::Serialize;
+use serde_json::Value;
+use terrafusion_common::geo::{FeatureReader, FeatureWriter};
+use terrafusion_common::{Error, Result};
+
+/// Suffix of the feature snapshot kept beside each export
+pub const SNAPSHOT_SUFFIX: &str = "features.geojson.gz";
+
+/// Formats a change report can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Geojson,
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "geojson" => Ok(ReportFormat::Geojson),
+            "csv" => Ok(ReportFormat::Csv),
+            _ => Err(Error::Validation(format!("Unsupported report format {}; expected geojson or csv", value))),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Geojson => "geojson",
+            ReportFormat::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Modified => "modified",
+        }
+    }
+}
+
+/// An attribute's value before and after; `None` when it was absent
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AttributeDelta {
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// One feature that differs between the runs
+#[derive(Debug, Clone)]
+pub struct FeatureChange {
+    pub kind: ChangeKind,
+    /// The feature as in the newer run; as in the older for removals
+    pub feature: Feature,
+    pub geometry_changed: bool,
+    pub attributes: BTreeMap<String, AttributeDelta>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ChangeSummary {
+    pub added: u64,
+    pub removed: u64,
+    pub modified: u64,
+    pub unchanged: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChangeReport {
+    pub changes: Vec<FeatureChange>,
+    pub summary: ChangeSummary,
+}
+
+/// File name of the snapshot of a job's features
+pub fn snapshot_file_name(county_id: &str, job_id: uuid::Uuid) -> String {
+    format!("{}_{}.{}", county_id, job_id.simple(), SNAPSHOT_SUFFIX)
+}
+
+/// Write the features an export produced. Blocking.
+pub fn write_snapshot(path: &Path, features: &[Feature]) -> Result<()> {
+    let encoder = flate2::write::GzEncoder::new(BufWriter::new(File::create(path)?), flate2::Compression::fast());
+    let mut writer = FeatureWriter::new(encoder)?;
+    for feature in features {
+        writer.write(feature)?;
+    }
+    writer.finish()?.finish()?.flush()?;
+    Ok(())
+}
+
+/// Read a plaintext snapshot. Blocking.
+pub fn read_snapshot(path: &Path) -> Result<Vec<Feature>> {
+    let decoder = flate2::read::GzDecoder::new(File::open(path)?);
+    FeatureReader::new(BufReader::new(decoder)).collect()
+}
+
+/// What identifies a feature across runs: its layer and `id`
+fn feature_key(feature: &Feature) -> Option<(String, String)> {
+    let properties = feature.properties.as_ref();
+    let layer = properties.and_then(|p| p.get("layer")).map(key_text).unwrap_or_default();
+    let id = match properties.and_then(|p| p.get("id")) {
+        Some(id) if !id.is_null() => key_text(id),
+        _ => match feature.id.as_ref()? {
+            ::geojson::feature::Id::String(id) => id.clone(),
+            ::geojson::feature::Id::Number(id) => id.to_string(),
+        },
+    };
+    Some((layer, id))
+}
+
+fn key_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn attribute_deltas(old: Option<&JsonObject>, new: Option<&JsonObject>) -> BTreeMap<String, AttributeDelta> {
+    let empty = JsonObject::new();
+    let (old, new) = (old.unwrap_or(&empty), new.unwrap_or(&empty));
+    old.keys()
+        .chain(new.keys().filter(|key| !old.contains_key(*key)))
+        .filter(|key| old.get(*key) != new.get(*key))
+        .map(|key| (key.clone(), AttributeDelta { old: old.get(key).cloned(), new: new.get(key).cloned() }))
+        .collect()
+}
+
+/// Compare two runs' features. Features without a layer and `id` cannot be
+/// matched and are refused.
+pub fn diff(base: Vec<Feature>, current: Vec<Feature>) -> Result<ChangeReport> {
+    let keyed = |feature: Feature| -> Result<((String, String), Feature)> {
+        feature_key(&feature)
+            .map(|key| (key, feature))
+            .ok_or_else(|| Error::Validation("Exported features need an id to be compared".to_string()))
+    };
+
+    let mut base: HashMap<(String, String), Feature> = base.into_iter().map(keyed).collect::<Result<_>>()?;
+    let mut report = ChangeReport::default();
+
+    for feature in current {
+        let (key, feature) = keyed(feature)?;
+        let Some(old) = base.remove(&key) else {
+            report.summary.added += 1;
+            report.changes.push(FeatureChange {
+                kind: ChangeKind::Added,
+                feature,
+                geometry_changed: false,
+                attributes: BTreeMap::new(),
+            });
+            continue;
+        };
+
+        let geometry_changed = old.geometry != feature.geometry;
+        let attributes = attribute_deltas(old.properties.as_ref(), feature.properties.as_ref());
+        if !geometry_changed && attributes.is_empty() {
+            report.summary.unchanged += 1;
+            continue;
+        }
+        report.summary.modified += 1;
+        report.changes.push(FeatureChange { kind: ChangeKind::Modified, feature, geometry_changed, attributes });
+    }
+
+    // Whatever was not matched is gone, reported in a stable order
+    let mut removed: Vec<_> = base.into_iter().collect();
+    removed.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (_, feature) in removed {
+        report.summary.removed += 1;
+        report.changes.push(FeatureChange {
+            kind: ChangeKind::Removed,
+            feature,
+            geometry_changed: false,
+            attributes: BTreeMap::new(),
+        });
+    }
+    Ok(report)
+}
+
+/// Write the changes: GeoJSON features carrying `change`, `geometry_changed`
+/// and `changed_attributes`, or one CSV row per changed attribute. Blocking.
+pub fn write_report(path: &Path, report: &ChangeReport, format: ReportFormat) -> Result<()> {
+    match format {
+        ReportFormat::Geojson => {
+            let mut writer = FeatureWriter::create(path)?;
+            for change in &report.changes {
+                let mut feature = change.feature.clone();
+                let properties = feature.properties.get_or_insert_with(JsonObject::new);
+                properties.insert("change".to_string(), Value::from(change.kind.as_str()));
+                properties.insert("geometry_changed".to_string(), Value::from(change.geometry_changed));
+                properties.insert("changed_attributes".to_string(), serde_json::to_value(&change.attributes).map_err(|e| Error::Internal(e.to_string()))?);
+                writer.write(&feature)?;
+            }
+            writer.finish()?;
+        }
+        ReportFormat::Csv => {
+            let mut writer = csv::Writer::from_path(path).map_err(|e| Error::Internal(e.to_string()))?;
+            let mut write = |record: [&str; 6]| writer.write_record(record).map_err(|e| Error::Internal(e.to_string()));
+            write(["layer", "id", "change", "attribute", "old_value", "new_value"])?;
+            for change in &report.changes {
+                let (layer, id) = feature_key(&change.feature).unwrap_or_default();
+                if change.attributes.is_empty() && !change.geometry_changed {
+                    write([&layer, &id, change.kind.as_str(), "", "", ""])?;
+                }
+                if change.geometry_changed {
+                    write([&layer, &id, change.kind.as_str(), "geometry", "", ""])?;
+                }
+                for (attribute, delta) in &change.attributes {
+                    let old = delta.old.as_ref().map(key_text).unwrap_or_default();
+                    let new = delta.new.as_ref().map(key_text).unwrap_or_default();
+                    write([&layer, &id, change.kind.as_str(), attribute, &old, &new])?;
+                }
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn feature(id: u64, value: u64, x: f64) -> Feature {
+        serde_json::from_value(json!({
+            "type": "Feature",
+            "properties": {"layer": "parcels", "id": id, "assessed_value": value},
+            "geometry": {"type": "Point", "coordinates": [x, 46.2]}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff_finds_added_removed_and_modified() {
+        let base = vec![feature(1, 100, -119.1), feature(2, 200, -119.2), feature(3, 300, -119.3)];
+        let current = vec![feature(1, 100, -119.1), feature(2, 250, -119.2), feature(3, 300, -119.35), feature(4, 400, -119.4)];
+
+        let report = diff(base, current).unwrap();
+        assert_eq!(report.summary, ChangeSummary { added: 1, removed: 0, modified: 2, unchanged: 1 });
+
+        let value_change = &report.changes[0];
+        assert_eq!(value_change.kind, ChangeKind::Modified);
+        assert!(!value_change.geometry_changed);
+        assert_eq!(
+            value_change.attributes["assessed_value"],
+            AttributeDelta { old: Some(json!(200)), new: Some(json!(250)) }
+        );
+        assert!(report.changes[1].geometry_changed);
+        assert_eq!(report.changes[2].kind, ChangeKind::Added);
+
+        let report = diff(vec![feature(5, 1, -119.0)], Vec::new()).unwrap();
+        assert_eq!(report.summary.removed, 1);
+    }
+
+    #[test]
+    fn test_snapshot_and_csv_report_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join(snapshot_file_name("benton", uuid::Uuid::nil()));
+        write_snapshot(&snapshot, &[feature(1, 100, -119.1), feature(2, 200, -119.2)]).unwrap();
+        let base = read_snapshot(&snapshot).unwrap();
+        assert_eq!(base.len(), 2);
+
+        let report = diff(base, vec![feature(2, 250, -119.2)]).unwrap();
+        let csv_path = dir.path().join("changes.csv");
+        write_report(&csv_path, &report, ReportFormat::Csv).unwrap();
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        assert!(csv.contains("parcels,2,modified,assessed_value,200,250"));
+        assert!(csv.contains("parcels,1,removed,,,"));
+    }
+}
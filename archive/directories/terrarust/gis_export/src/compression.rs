@@ -0,0 +1,166 @@
+//! Compression of finished export files
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::ExportFormat;
+
+/// zstd level; favors speed since exports can be several GB
+const ZSTD_LEVEL: i32 = 3;
+
+/// How an export file is compressed for download
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    Zip,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Default when the job does not ask for one. Shapefiles are already
+    /// delivered as a ZIP, so they are left alone.
+    pub fn default_for(format: &ExportFormat) -> Self {
+        match format {
+            ExportFormat::Geojson | ExportFormat::Csv => Compression::Gzip,
+            ExportFormat::Kml | ExportFormat::Geopackage => Compression::Zip,
+            ExportFormat::Shapefile => Compression::None,
+        }
+    }
+
+    /// The `compression` job parameter, falling back to the format default
+    pub fn from_parameters(parameters: Option<&serde_json::Value>, format: &ExportFormat) -> Result<Self, String> {
+        match parameters.and_then(|p| p.get("compression")) {
+            None | Some(serde_json::Value::Null) => Ok(Self::default_for(format)),
+            Some(serde_json::Value::String(value)) => value.parse(),
+            Some(other) => Err(format!("compression must be a string, not {}", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Zip => "zip",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    /// Suffix appended to the format's extension, e.g. `geojson.gz`
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Zip => Some("zip"),
+            Compression::Gzip => Some("gz"),
+            Compression::Zstd => Some("zst"),
+        }
+    }
+
+    /// Recognize a compressed download by its file extension
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            Some("zip") => Compression::Zip,
+            _ => Compression::None,
+        }
+    }
+
+    /// Content-Type of the compressed file; `None` leaves it to the format
+    pub fn content_type(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Zip => Some("application/zip"),
+            Compression::Gzip => Some("application/gzip"),
+            Compression::Zstd => Some("application/zstd"),
+        }
+    }
+
+    /// Compress `path` into a sibling file and remove the original.
+    ///
+    /// Blocking; call from `spawn_blocking`. Returns the path to serve.
+    pub fn compress_file(&self, path: &Path) -> io::Result<PathBuf> {
+        let Some(extension) = self.extension() else {
+            return Ok(path.to_path_buf());
+        };
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "export path has no file name"))?;
+        let output_path = path.with_file_name(format!("{}.{}", file_name, extension));
+
+        let mut input = BufReader::new(File::open(path)?);
+        let output = BufWriter::new(File::create(&output_path)?);
+
+        match self {
+            Compression::None => unreachable!("handled above"),
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+                io::copy(&mut input, &mut encoder)?;
+                encoder.finish()?.flush()?;
+            }
+            Compression::Zstd => {
+                let mut encoder = zstd::stream::write::Encoder::new(output, ZSTD_LEVEL)?;
+                io::copy(&mut input, &mut encoder)?;
+                encoder.finish()?.flush()?;
+            }
+            Compression::Zip => {
+                let mut zip = zip::ZipWriter::new(output);
+                let options = zip::write::FileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated)
+                    .large_file(true);
+                zip.start_file(file_name, options)?;
+                io::copy(&mut input, &mut zip)?;
+                zip.finish()?.flush()?;
+            }
+        }
+
+        std::fs::remove_file(path)?;
+        Ok(output_path)
+    }
+}
+
+impl std::str::FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Compression::None),
+            "zip" => Ok(Compression::Zip),
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            _ => Err(format!("Unsupported compression: {} (use zip, gzip, zstd or none)", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_gzip_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("benton_export.geojson");
+        std::fs::write(&path, r#"{"type":"FeatureCollection","features":[]}"#).unwrap();
+
+        let compression = Compression::from_parameters(None, &ExportFormat::Geojson).unwrap();
+        let compressed = compression.compress_file(&path).unwrap();
+        assert_eq!(compressed.file_name().unwrap(), "benton_export.geojson.gz");
+        assert!(!path.exists());
+        assert_eq!(Compression::from_path(&compressed), Compression::Gzip);
+
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(File::open(&compressed).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert!(content.contains("FeatureCollection"));
+
+        let parameters = serde_json::json!({"compression": "rar"});
+        assert!(Compression::from_parameters(Some(&parameters), &ExportFormat::Csv).is_err());
+    }
+}
@@ -0,0 +1,420 @@
+//! Delivery of finished exports to FTP and SFTP sites
+//!
+//! Many title companies still pull county data from an FTP site. A county
+//! names its drop sites under `delivery_destinations` in its configuration,
+//! and a job lists the ones it goes to in its `deliver_to` parameter:
+//!
+//! ```json
+//! "delivery_destinations": [
+//!   { "name": "title-companies", "protocol": "sftp", "host": "sftp.county.gov", "username": "exports",
+//!     "private_key_path": "/etc/terrafusion/keys/exports",
+//!     "host_key": "SHA256:Nh0Me49Zh9fDw/VYUfq43IJmI1T+XrjiYONPND8GzaE", "directory": "/outgoing/parcels" },
+//!   { "name": "abstract-co", "protocol": "ftp", "host": "ftp.abstract.example", "username": "county",
+//!     "password_env": "ABSTRACT_FTP_PASSWORD", "tls": true, "directory": "/incoming" }
+//! ]
+//! ```
+//!
+//! The export file and its manifest are uploaded under a `.part` name and
+//! renamed when complete, so nobody pulls half a file. SFTP destinations pin
+//! the server's host key by its SHA-256 fingerprint as `ssh-keygen -lf`
+//! prints it; nothing is sent to a server presenting another key. Passwords
+//! and key passphrases are read from the named environment variables.
+//!
+//! Each destination is tried `attempts` times (default 3), waiting twice as
+//! long after each failure. The outcome is recorded on the job; a failed
+//! delivery does not fail the export.
+//!
+//! Exports can also be emailed to the addresses in an `email_to` parameter
+//! once complete, which is recorded with the deliveries.
+
+use std::fs::File;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use terrafusion_common::{Error, Result};
+
+const DEFAULT_ATTEMPTS: u32 = 3;
+const MAX_ATTEMPTS: u32 = 10;
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(5);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Stalled transfers fail after this long without progress
+const IO_TIMEOUT: Duration = Duration::from_secs(120);
+const PARTIAL_SUFFIX: &str = "part";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Ftp,
+    Sftp,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeliveryDestination {
+    pub name: String,
+    pub protocol: Protocol,
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    pub username: String,
+    #[serde(default)]
+    pub password_env: Option<String>,
+    /// SFTP only; used instead of a password when set
+    #[serde(default)]
+    pub private_key_path: Option<PathBuf>,
+    #[serde(default)]
+    pub passphrase_env: Option<String>,
+    /// SFTP only, required: `SHA256:` followed by the base64 fingerprint
+    #[serde(default)]
+    pub host_key: Option<String>,
+    /// FTP only: explicit FTPS (`AUTH TLS`)
+    #[serde(default)]
+    pub tls: bool,
+    #[serde(default = "default_directory")]
+    pub directory: String,
+    #[serde(default = "default_attempts")]
+    pub attempts: u32,
+}
+
+fn default_directory() -> String {
+    "/".to_string()
+}
+
+fn default_attempts() -> u32 {
+    DEFAULT_ATTEMPTS
+}
+
+/// Outcome of delivering an export to one destination, kept with the job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delivery {
+    pub destination: String,
+    pub delivered: bool,
+    pub attempts: u32,
+    /// Remote path of the export file, or why delivery failed
+    pub message: String,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl Delivery {
+    pub fn failed(destination: &str, attempts: u32, message: impl Into<String>) -> Self {
+        Self {
+            destination: destination.to_string(),
+            delivered: false,
+            attempts,
+            message: message.into(),
+            finished_at: Utc::now(),
+        }
+    }
+}
+
+/// Destination names in a job's `deliver_to` parameter
+pub fn requested_destinations(parameters: Option<&Value>) -> std::result::Result<Vec<String>, String> {
+    match parameters.and_then(|parameters| parameters.get("deliver_to")) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|_| format!("deliver_to must be a list of destination names, not {}", value)),
+    }
+}
+
+/// Addresses in a job's `email_to` parameter
+pub fn requested_recipients(parameters: Option<&Value>) -> std::result::Result<Vec<String>, String> {
+    let recipients: Vec<String> = match parameters.and_then(|parameters| parameters.get("email_to")) {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|_| format!("email_to must be a list of email addresses, not {}", value))?,
+    };
+    match recipients.iter().find(|address| !address.contains('@')) {
+        Some(address) => Err(format!("email_to has an invalid address: {}", address)),
+        None => Ok(recipients),
+    }
+}
+
+impl DeliveryDestination {
+    /// The destinations in a county's configuration
+    pub fn parse_all(values: &[Value]) -> Result<Vec<Self>> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let destination: Self = serde_json::from_value(value.clone())
+                    .map_err(|e| Error::Config(format!("Invalid delivery destination #{}: {}", i + 1, e)))?;
+                destination.validate()?;
+                Ok(destination)
+            })
+            .collect()
+    }
+
+    fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| Err(Error::Config(format!("Delivery destination {}: {}", self.name, reason)));
+        if !(1..=MAX_ATTEMPTS).contains(&self.attempts) {
+            return invalid(&format!("attempts must be between 1 and {}", MAX_ATTEMPTS));
+        }
+        match self.protocol {
+            Protocol::Sftp => {
+                if self.tls {
+                    return invalid("tls applies to FTP only; SFTP is always encrypted");
+                }
+                if self.password_env.is_none() && self.private_key_path.is_none() {
+                    return invalid("SFTP needs password_env or private_key_path");
+                }
+                match &self.host_key {
+                    Some(host_key) => host_key_fingerprint(host_key).map(|_| ()),
+                    None => invalid("SFTP needs the server's host_key fingerprint"),
+                }
+            }
+            Protocol::Ftp => {
+                if self.host_key.is_some() || self.private_key_path.is_some() {
+                    return invalid("host_key and private_key_path apply to SFTP only");
+                }
+                if self.password_env.is_none() {
+                    return invalid("FTP needs password_env");
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.protocol {
+            Protocol::Ftp => 21,
+            Protocol::Sftp => 22,
+        })
+    }
+
+    /// Upload the files, retrying with backoff. Failures are reported in the
+    /// result.
+    pub async fn deliver(&self, files: &[PathBuf]) -> Delivery {
+        let mut delay = FIRST_RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
+            let destination = self.clone();
+            let upload_files = files.to_vec();
+            let result = tokio::task::spawn_blocking(move || destination.upload(&upload_files))
+                .await
+                .unwrap_or_else(|e| Err(Error::Internal(format!("Delivery task failed: {}", e))));
+            match result {
+                Ok(remote_path) => {
+                    return Delivery {
+                        destination: self.name.clone(),
+                        delivered: true,
+                        attempts: attempt,
+                        message: remote_path,
+                        finished_at: Utc::now(),
+                    };
+                }
+                Err(e) if attempt < self.attempts => {
+                    log::warn!(
+                        "Delivery to {} failed (attempt {} of {}), retrying in {}s: {}",
+                        self.name,
+                        attempt,
+                        self.attempts,
+                        delay.as_secs(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Delivery::failed(&self.name, attempt, e.to_string()),
+            }
+        }
+    }
+
+    /// One upload attempt; returns the remote path of the first file. Blocking.
+    fn upload(&self, files: &[PathBuf]) -> Result<String> {
+        let uploads = files
+            .iter()
+            .map(|file| {
+                let name = file
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .ok_or_else(|| Error::Internal(format!("Export path {:?} has no file name", file)))?;
+                Ok((file.as_path(), remote_path(&self.directory, &name)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        match self.protocol {
+            Protocol::Sftp => self.upload_sftp(&uploads)?,
+            Protocol::Ftp => self.upload_ftp(&uploads)?,
+        }
+        Ok(uploads.first().map(|(_, remote)| remote.clone()).unwrap_or_default())
+    }
+
+    fn connect(&self) -> Result<TcpStream> {
+        let address = (self.host.as_str(), self.port())
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::ExternalService(format!("{} did not resolve", self.host)))?;
+        let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        Ok(stream)
+    }
+
+    fn upload_sftp(&self, uploads: &[(&Path, String)]) -> Result<()> {
+        let ssh_error = |e: ssh2::Error| Error::ExternalService(format!("SFTP {}: {}", self.host, e));
+
+        let mut session = ssh2::Session::new().map_err(ssh_error)?;
+        session.set_timeout(IO_TIMEOUT.as_millis() as u32);
+        session.set_tcp_stream(self.connect()?);
+        session.handshake().map_err(ssh_error)?;
+
+        // Checked before any credential is sent
+        let expected = host_key_fingerprint(self.host_key.as_deref().unwrap_or_default())?;
+        let presented = session
+            .host_key_hash(ssh2::HashType::Sha256)
+            .ok_or_else(|| Error::ExternalService(format!("SFTP {} presented no host key", self.host)))?;
+        if presented != expected.as_slice() {
+            return Err(Error::Authentication(format!(
+                "SFTP {} presented host key SHA256:{}, which does not match the configured host_key",
+                self.host,
+                base64::engine::general_purpose::STANDARD_NO_PAD.encode(presented)
+            )));
+        }
+
+        match &self.private_key_path {
+            Some(key) => {
+                let passphrase = self.passphrase_env.as_deref().map(secret).transpose()?;
+                session.userauth_pubkey_file(&self.username, None, key, passphrase.as_deref())
+            }
+            None => session.userauth_password(&self.username, &secret(self.password_env.as_deref().unwrap_or_default())?),
+        }
+        .map_err(ssh_error)?;
+
+        let sftp = session.sftp().map_err(ssh_error)?;
+        for (local, remote) in uploads {
+            let partial = format!("{}.{}", remote, PARTIAL_SUFFIX);
+            let mut remote_file = sftp.create(Path::new(&partial)).map_err(ssh_error)?;
+            std::io::copy(&mut File::open(local)?, &mut remote_file)?;
+            drop(remote_file);
+            // Most servers refuse to rename over an existing file
+            let _ = sftp.unlink(Path::new(remote));
+            sftp.rename(Path::new(&partial), Path::new(remote), None).map_err(ssh_error)?;
+        }
+        Ok(())
+    }
+
+    fn upload_ftp(&self, uploads: &[(&Path, String)]) -> Result<()> {
+        let ftp_error = |e: suppaftp::FtpError| Error::ExternalService(format!("FTP {}: {}", self.host, e));
+        let password = secret(self.password_env.as_deref().unwrap_or_default())?;
+
+        // Plain and TLS streams are different types with the same methods
+        macro_rules! put_files {
+            ($stream:expr) => {{
+                let mut stream = $stream;
+                stream.login(&self.username, &password).map_err(ftp_error)?;
+                stream.transfer_type(suppaftp::types::FileType::Binary).map_err(ftp_error)?;
+                for (local, remote) in uploads {
+                    let partial = format!("{}.{}", remote, PARTIAL_SUFFIX);
+                    stream.put_file(&partial, &mut File::open(local)?).map_err(ftp_error)?;
+                    let _ = stream.rm(remote);
+                    stream.rename(&partial, remote).map_err(ftp_error)?;
+                }
+                let _ = stream.quit();
+            }};
+        }
+
+        if self.tls {
+            let connector = suppaftp::native_tls::TlsConnector::new()
+                .map_err(|e| Error::ExternalService(format!("FTP {}: {}", self.host, e)))?;
+            let stream = suppaftp::NativeTlsFtpStream::connect_with_stream(self.connect()?)
+                .map_err(ftp_error)?
+                .into_secure(suppaftp::NativeTlsConnector::from(connector), &self.host)
+                .map_err(ftp_error)?;
+            put_files!(stream);
+        } else {
+            put_files!(suppaftp::FtpStream::connect_with_stream(self.connect()?).map_err(ftp_error)?);
+        }
+        Ok(())
+    }
+}
+
+/// Decoded `SHA256:<base64>` host key fingerprint
+fn host_key_fingerprint(host_key: &str) -> Result<Vec<u8>> {
+    let invalid = || Error::Config(format!("Invalid host_key {:?}; expected SHA256:<base64> as printed by ssh-keygen -lf", host_key));
+    let encoded = host_key.strip_prefix("SHA256:").ok_or_else(invalid)?;
+    let fingerprint = base64::engine::general_purpose::STANDARD_NO_PAD
+        .decode(encoded.trim_end_matches('='))
+        .map_err(|_| invalid())?;
+    if fingerprint.len() != 32 {
+        return Err(invalid());
+    }
+    Ok(fingerprint)
+}
+
+fn remote_path(directory: &str, name: &str) -> String {
+    format!("{}/{}", directory.trim_end_matches('/'), name)
+}
+
+fn secret(name: &str) -> Result<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| Error::Config(format!("{} is not set", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const HOST_KEY: &str = "SHA256:Nh0Me49Zh9fDw/VYUfq43IJmI1T+XrjiYONPND8GzaE";
+
+    fn sftp(overrides: Value) -> Value {
+        let mut destination = json!({ "name": "title-companies", "protocol": "sftp", "host": "sftp.county.gov",
+            "username": "exports", "password_env": "SFTP_PASSWORD", "host_key": HOST_KEY });
+        destination.as_object_mut().unwrap().extend(overrides.as_object().unwrap().clone());
+        destination
+    }
+
+    #[test]
+    fn test_parse_destinations() {
+        let destinations = DeliveryDestination::parse_all(&[
+            sftp(json!({})),
+            json!({ "name": "abstract-co", "protocol": "ftp", "host": "ftp.abstract.example", "username": "county",
+                    "password_env": "FTP_PASSWORD", "tls": true, "directory": "/incoming/" }),
+        ])
+        .unwrap();
+        assert_eq!((destinations[0].port(), destinations[0].attempts), (22, 3));
+        assert_eq!(destinations[1].port(), 21);
+
+        // Host keys are pinned, never trusted on first use
+        assert!(DeliveryDestination::parse_all(&[sftp(json!({ "host_key": null }))]).is_err());
+        assert!(DeliveryDestination::parse_all(&[sftp(json!({ "host_key": "SHA256:abc" }))]).is_err());
+        assert!(DeliveryDestination::parse_all(&[sftp(json!({ "tls": true }))]).is_err());
+        assert!(DeliveryDestination::parse_all(&[sftp(json!({ "attempts": 0 }))]).is_err());
+        assert!(DeliveryDestination::parse_all(&[sftp(json!({ "protocol": "scp" }))]).is_err());
+    }
+
+    #[test]
+    fn test_host_key_fingerprint() {
+        assert_eq!(host_key_fingerprint(HOST_KEY).unwrap().len(), 32);
+        assert_eq!(host_key_fingerprint(&format!("{}=", HOST_KEY)).unwrap(), host_key_fingerprint(HOST_KEY).unwrap());
+        assert!(host_key_fingerprint("MD5:16:27:ac:a5:76:28:2d:36:63:1b:56:4d:eb:df:a6:48").is_err());
+    }
+
+    #[test]
+    fn test_requested_destinations() {
+        assert_eq!(requested_destinations(None).unwrap(), Vec::<String>::new());
+        assert_eq!(
+            requested_destinations(Some(&json!({ "deliver_to": ["title-companies"] }))).unwrap(),
+            vec!["title-companies".to_string()]
+        );
+        assert!(requested_destinations(Some(&json!({ "deliver_to": "title-companies" }))).is_err());
+        assert_eq!(remote_path("/incoming/", "parcels.zip"), "/incoming/parcels.zip");
+    }
+
+    #[test]
+    fn test_requested_recipients() {
+        assert_eq!(requested_recipients(Some(&json!({ "deliver_to": ["abstract-co"] }))).unwrap(), Vec::<String>::new());
+        assert_eq!(
+            requested_recipients(Some(&json!({ "email_to": ["orders@titleco.example"] }))).unwrap(),
+            vec!["orders@titleco.example".to_string()]
+        );
+        assert!(requested_recipients(Some(&json!({ "email_to": ["orders"] }))).is_err());
+        assert!(requested_recipients(Some(&json!({ "email_to": "orders@titleco.example" }))).is_err());
+    }
+}
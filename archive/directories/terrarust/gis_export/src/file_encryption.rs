@@ -0,0 +1,227 @@
+//! Encryption of finished export files at rest
+//!
+//! Each file is encrypted with its own data key in 64 KiB AES-256-GCM
+//! chunks (the STREAM construction, so truncation or reordering is detected)
+//! and the data key is wrapped with the export key from the secrets
+//! provider. The wrapped key is bound to the file's plaintext name, which
+//! carries the county and job ID, so a file cannot be served for another job.
+//!
+//! Encrypted files get an `.enc` suffix and are decrypted while they are
+//! streamed to the client. Checksums and manifests describe the plaintext,
+//! which is what clients receive.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use aes_gcm::aead::stream::{DecryptorBE32, EncryptorBE32};
+use aes_gcm::aead::KeyInit;
+use aes_gcm::Aes256Gcm;
+use terrafusion_common::encryption::{self, Kek};
+use terrafusion_common::secrets::SecretsManager;
+
+/// Suffix of encrypted export files
+pub const ENCRYPTED_SUFFIX: &str = "enc";
+
+/// Secret holding the export key unless EXPORT_ENCRYPTION_KEY_SECRET names another
+const DEFAULT_KEY_SECRET: &str = "EXPORT_ENCRYPTION_KEY";
+
+const MAGIC: &[u8; 4] = b"TFEX";
+const FORMAT: u8 = 1;
+
+/// Plaintext bytes per chunk
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Each chunk carries a 16-byte tag
+const ENCRYPTED_CHUNK_SIZE: usize = CHUNK_SIZE + 16;
+/// Nonce prefix of the STREAM construction (12-byte nonce less 5 bytes of counter and flag)
+const NONCE_PREFIX_LEN: usize = 7;
+
+/// Loads the export key from the secrets provider
+#[derive(Clone)]
+pub struct ExportCipher {
+    secrets: SecretsManager,
+    secret_name: String,
+}
+
+impl ExportCipher {
+    /// A cipher when EXPORT_ENCRYPTION_ENABLED is true
+    pub fn from_env() -> terrafusion_common::Result<Option<Self>> {
+        let enabled = std::env::var("EXPORT_ENCRYPTION_ENABLED")
+            .map(|value| value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        if !enabled {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            secrets: SecretsManager::from_env()?,
+            secret_name: std::env::var("EXPORT_ENCRYPTION_KEY_SECRET").unwrap_or_else(|_| DEFAULT_KEY_SECRET.to_string()),
+        }))
+    }
+
+    pub async fn key(&self) -> terrafusion_common::Result<Kek> {
+        Kek::from_base64(&self.secrets.get_value(&self.secret_name).await?)
+    }
+}
+
+/// Whether a stored export file is encrypted
+pub fn is_encrypted(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some(ENCRYPTED_SUFFIX)
+}
+
+/// Path of the file as delivered, i.e. without the `.enc` suffix
+pub fn plaintext_path(path: &Path) -> PathBuf {
+    if is_encrypted(path) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+fn crypto_error(context: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, context.to_string())
+}
+
+fn key_aad(path: &Path) -> io::Result<Vec<u8>> {
+    plaintext_path(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned().into_bytes())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "export path has no file name"))
+}
+
+/// Fill `buffer` from `reader` up to its length; short only at end of file
+fn read_full(reader: &mut impl Read, buffer: &mut Vec<u8>, size: usize) -> io::Result<()> {
+    buffer.clear();
+    reader.take(size as u64).read_to_end(buffer)?;
+    Ok(())
+}
+
+/// Encrypt `path` into a sibling `.enc` file and remove the original.
+///
+/// Blocking; call from `spawn_blocking`. Returns the path to store.
+pub fn encrypt_file(kek: &Kek, path: &Path) -> io::Result<PathBuf> {
+    let output_path = PathBuf::from(format!("{}.{}", path.display(), ENCRYPTED_SUFFIX));
+
+    let data_key: [u8; 32] = rand::random();
+    let nonce_prefix: [u8; NONCE_PREFIX_LEN] = rand::random();
+    let wrapped_key = encryption::wrap_key(kek, &key_aad(path)?, &data_key)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+
+    let mut input = BufReader::new(File::open(path)?);
+    let mut output = BufWriter::new(File::create(&output_path)?);
+    output.write_all(MAGIC)?;
+    output.write_all(&[FORMAT])?;
+    output.write_all(&(wrapped_key.len() as u16).to_be_bytes())?;
+    output.write_all(wrapped_key.as_bytes())?;
+    output.write_all(&nonce_prefix)?;
+
+    let mut encryptor = EncryptorBE32::from_aead(Aes256Gcm::new(&data_key.into()), (&nonce_prefix).into());
+    let mut current = Vec::with_capacity(CHUNK_SIZE);
+    let mut next = Vec::with_capacity(CHUNK_SIZE);
+    read_full(&mut input, &mut current, CHUNK_SIZE)?;
+    loop {
+        // Read ahead so the final chunk is sealed as the last one
+        if current.len() == CHUNK_SIZE {
+            read_full(&mut input, &mut next, CHUNK_SIZE)?;
+        } else {
+            next.clear();
+        }
+        if next.is_empty() {
+            let sealed = encryptor.encrypt_last(current.as_slice()).map_err(|_| crypto_error("export encryption failed"))?;
+            output.write_all(&sealed)?;
+            break;
+        }
+        let sealed = encryptor.encrypt_next(current.as_slice()).map_err(|_| crypto_error("export encryption failed"))?;
+        output.write_all(&sealed)?;
+        std::mem::swap(&mut current, &mut next);
+    }
+    output.flush()?;
+
+    std::fs::remove_file(path)?;
+    Ok(output_path)
+}
+
+/// Decrypt an `.enc` file, handing each plaintext chunk to `sink` until it
+/// returns false (the client went away). Blocking.
+pub fn decrypt_file(kek: &Kek, path: &Path, mut sink: impl FnMut(Vec<u8>) -> bool) -> io::Result<()> {
+    let mut input = BufReader::new(File::open(path)?);
+
+    let mut header = [0u8; 7];
+    input.read_exact(&mut header)?;
+    if &header[..4] != MAGIC || header[4] != FORMAT {
+        return Err(crypto_error("not an encrypted export file"));
+    }
+    let mut wrapped_key = vec![0u8; u16::from_be_bytes([header[5], header[6]]) as usize];
+    input.read_exact(&mut wrapped_key)?;
+    let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+    input.read_exact(&mut nonce_prefix)?;
+
+    let wrapped_key = String::from_utf8(wrapped_key).map_err(|_| crypto_error("wrapped export key is not text"))?;
+    let data_key = encryption::unwrap_key(kek, &key_aad(path)?, &wrapped_key)
+        .map_err(|_| crypto_error("export key does not open this file"))?;
+
+    let mut decryptor = DecryptorBE32::from_aead(Aes256Gcm::new(&data_key.into()), (&nonce_prefix).into());
+    let mut current = Vec::with_capacity(ENCRYPTED_CHUNK_SIZE);
+    let mut next = Vec::with_capacity(ENCRYPTED_CHUNK_SIZE);
+    read_full(&mut input, &mut current, ENCRYPTED_CHUNK_SIZE)?;
+    loop {
+        if current.len() == ENCRYPTED_CHUNK_SIZE {
+            read_full(&mut input, &mut next, ENCRYPTED_CHUNK_SIZE)?;
+        } else {
+            next.clear();
+        }
+        if next.is_empty() {
+            let plaintext = decryptor.decrypt_last(current.as_slice()).map_err(|_| crypto_error("export file is corrupt or truncated"))?;
+            sink(plaintext);
+            return Ok(());
+        }
+        let plaintext = decryptor.decrypt_next(current.as_slice()).map_err(|_| crypto_error("export file is corrupt or truncated"))?;
+        if !sink(plaintext) {
+            return Ok(());
+        }
+        std::mem::swap(&mut current, &mut next);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kek(encoded: &str) -> Kek {
+        Kek::from_base64(encoded).unwrap()
+    }
+
+    fn decrypt(kek: &Kek, path: &Path) -> io::Result<Vec<u8>> {
+        let mut plaintext = Vec::new();
+        decrypt_file(kek, path, |chunk| {
+            plaintext.extend(chunk);
+            true
+        })?;
+        Ok(plaintext)
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = kek("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=");
+        let dir = tempfile::tempdir().unwrap();
+
+        // Exactly two chunks, a partial chunk, and an empty file
+        for (name, size) in [("a.csv", 2 * CHUNK_SIZE), ("b.csv", CHUNK_SIZE + 10), ("c.csv", 0)] {
+            let path = dir.path().join(name);
+            let content: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+            std::fs::write(&path, &content).unwrap();
+
+            let encrypted = encrypt_file(&key, &path).unwrap();
+            assert!(!path.exists());
+            assert!(is_encrypted(&encrypted));
+            assert_eq!(plaintext_path(&encrypted), path);
+            assert_eq!(decrypt(&key, &encrypted).unwrap(), content);
+        }
+
+        // Wrong key, or a file renamed to pass for another job, does not open
+        let other = kek("AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=");
+        let encrypted = dir.path().join("b.csv.enc");
+        assert!(decrypt(&other, &encrypted).is_err());
+        let renamed = dir.path().join("d.csv.enc");
+        std::fs::rename(&encrypted, &renamed).unwrap();
+        assert!(decrypt(&key, &renamed).is_err());
+    }
+}
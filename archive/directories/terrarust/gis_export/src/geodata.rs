@@ -0,0 +1,357 @@
+//! Reading uploaded county boundaries and layer seed data
+//!
+//! Uploads arrive as GeoJSON, a zipped Shapefile or a GeoPackage. GeoJSON is
+//! read directly; the other two go through GDAL, which also tells us the CRS
+//! from the `.prj` or the GeoPackage's spatial reference table. Everything
+//! is reprojected to WGS 84 before it is stored, so AOI checks, exports and
+//! map previews all work in one CRS.
+
+use std::io::Write;
+
+use geo::{Coord, Geometry, MapCoords, MultiPolygon};
+use serde_json::{json, Map, Value};
+use terrafusion_common::geo::{parse_geometry, Bbox, Boundary};
+use terrafusion_common::{Error, Result};
+
+/// The CRS everything is stored in
+pub const STORAGE_CRS: &str = "EPSG:4326";
+
+/// Features an upload may carry; larger datasets belong in a sync pair
+pub const MAX_FEATURES: usize = 500_000;
+
+/// File formats accepted for boundaries and layer seed data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadFormat {
+    GeoJson,
+    /// A `.zip` holding the `.shp`, `.shx`, `.dbf` and ideally `.prj`
+    ShapefileZip,
+    GeoPackage,
+}
+
+impl UploadFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "geojson" | "json" => Ok(UploadFormat::GeoJson),
+            "shapefile" | "shp" | "zip" => Ok(UploadFormat::ShapefileZip),
+            "geopackage" | "gpkg" => Ok(UploadFormat::GeoPackage),
+            _ => Err(Error::Validation(format!(
+                "Unsupported upload format {}; expected geojson, shapefile or geopackage",
+                value
+            ))),
+        }
+    }
+
+    /// Guess the format from the file's first bytes
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"PK\x03\x04") {
+            Some(UploadFormat::ShapefileZip)
+        } else if bytes.starts_with(b"SQLite format 3\0") {
+            Some(UploadFormat::GeoPackage)
+        } else if bytes.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'{') {
+            Some(UploadFormat::GeoJson)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UploadFormat::GeoJson => "geojson",
+            UploadFormat::ShapefileZip => "shapefile",
+            UploadFormat::GeoPackage => "geopackage",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            UploadFormat::GeoJson => "geojson",
+            UploadFormat::ShapefileZip => "zip",
+            UploadFormat::GeoPackage => "gpkg",
+        }
+    }
+}
+
+/// How to read an upload
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    /// CRS of the file, e.g. `EPSG:2927`, when the file does not say or
+    /// says it wrongly
+    pub source_crs: Option<String>,
+    /// Layer to read from a GeoPackage with several; the first by default
+    pub source_layer: Option<String>,
+}
+
+/// One feature of an upload, in WGS 84
+#[derive(Debug, Clone)]
+pub struct UploadedFeature {
+    pub properties: Map<String, Value>,
+    pub geometry: Geometry<f64>,
+}
+
+impl UploadedFeature {
+    pub fn geometry_geojson(&self) -> Value {
+        serde_json::to_value(::geojson::Geometry::new(::geojson::Value::from(&self.geometry))).unwrap_or(Value::Null)
+    }
+}
+
+/// An upload read, reprojected and checked
+#[derive(Debug, Clone)]
+pub struct GeoUpload {
+    pub format: UploadFormat,
+    /// CRS the file was in
+    pub source_crs: String,
+    pub features: Vec<UploadedFeature>,
+}
+
+impl GeoUpload {
+    pub fn bbox(&self) -> Option<Bbox> {
+        Bbox::of_all(self.features.iter().map(|feature| &feature.geometry))
+    }
+
+    /// Every polygon of the upload as one boundary. Counties with islands or
+    /// exclaves upload several polygons; anything else is refused.
+    pub fn boundary(&self) -> Result<MultiPolygon<f64>> {
+        let mut polygons = Vec::new();
+        for (index, feature) in self.features.iter().enumerate() {
+            match &feature.geometry {
+                Geometry::Polygon(polygon) => polygons.push(polygon.clone()),
+                Geometry::MultiPolygon(multi) => polygons.extend(multi.0.iter().cloned()),
+                _ => {
+                    return Err(Error::Validation(format!(
+                        "Feature {} is not a polygon; a boundary must be made of polygons",
+                        index + 1
+                    )))
+                }
+            }
+        }
+        Ok(MultiPolygon(polygons))
+    }
+
+    /// Features entirely outside the county boundary: their 1-based
+    /// position and why, at most `limit` of them
+    pub fn outside(&self, boundary: &Boundary, limit: usize) -> Vec<(usize, String)> {
+        self.features
+            .iter()
+            .enumerate()
+            .filter_map(|(index, feature)| boundary.check(&feature.geometry).map(|reason| (index + 1, reason)))
+            .take(limit)
+            .collect()
+    }
+}
+
+/// Read an upload and reproject it to WGS 84. Blocking: GDAL and PROJ do
+/// file and CPU work, so call it from `spawn_blocking`.
+pub fn read_upload(bytes: &[u8], format: UploadFormat, options: &ReadOptions) -> Result<GeoUpload> {
+    let (file_crs, features) = match format {
+        UploadFormat::GeoJson => read_geojson(bytes)?,
+        UploadFormat::ShapefileZip | UploadFormat::GeoPackage => {
+            let mut file = tempfile::Builder::new()
+                .suffix(&format!(".{}", format.extension()))
+                .tempfile()
+                .map_err(|e| Error::Internal(format!("Failed to buffer upload: {}", e)))?;
+            file.write_all(bytes)
+                .map_err(|e| Error::Internal(format!("Failed to buffer upload: {}", e)))?;
+            let path = file.path().to_string_lossy().to_string();
+            let path = match format {
+                UploadFormat::ShapefileZip => format!("/vsizip/{}", path),
+                _ => path,
+            };
+            read_with_gdal(&path, options.source_layer.as_deref())?
+        }
+    };
+
+    if features.is_empty() {
+        return Err(Error::Validation("The upload has no features".to_string()));
+    }
+    if features.len() > MAX_FEATURES {
+        return Err(Error::Validation(format!(
+            "The upload has {} features; at most {} are accepted",
+            features.len(),
+            MAX_FEATURES
+        )));
+    }
+
+    let source_crs = options.source_crs.clone().or(file_crs).unwrap_or_else(|| STORAGE_CRS.to_string());
+    let features = if source_crs == STORAGE_CRS { features } else { reproject(features, &source_crs)? };
+    check_coordinates(&features, &source_crs)?;
+    Ok(GeoUpload { format, source_crs, features })
+}
+
+/// A FeatureCollection, Feature or bare geometry. RFC 7946 GeoJSON is WGS 84;
+/// older files name another CRS in a `crs` member, which is honoured.
+fn read_geojson(bytes: &[u8]) -> Result<(Option<String>, Vec<UploadedFeature>)> {
+    let value: Value = serde_json::from_slice(bytes)
+        .map_err(|e| Error::Validation(format!("Invalid GeoJSON: {}", e)))?;
+    let crs = value.pointer("/crs/properties/name").and_then(Value::as_str).and_then(crs_from_name);
+
+    let features = match value.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => value
+            .get("features")
+            .and_then(Value::as_array)
+            .ok_or_else(|| Error::Validation("A FeatureCollection needs a features array".to_string()))?
+            .iter()
+            .enumerate()
+            .map(|(index, feature)| geojson_feature(index, feature))
+            .collect::<Result<Vec<_>>>()?,
+        Some("Feature") => vec![geojson_feature(0, &value)?],
+        _ => vec![UploadedFeature { properties: Map::new(), geometry: geojson_geometry(0, &value)? }],
+    };
+    Ok((crs, features))
+}
+
+fn geojson_feature(index: usize, feature: &Value) -> Result<UploadedFeature> {
+    let geometry = feature
+        .get("geometry")
+        .filter(|geometry| !geometry.is_null())
+        .ok_or_else(|| Error::Validation(format!("Feature {} has no geometry", index + 1)))?;
+    let properties = feature.get("properties").and_then(Value::as_object).cloned().unwrap_or_default();
+    Ok(UploadedFeature { properties, geometry: geojson_geometry(index, geometry)? })
+}
+
+fn geojson_geometry(index: usize, geometry: &Value) -> Result<Geometry<f64>> {
+    // Bare geometries are always GeoJSON objects here; go through the shared
+    // parser for its validation and messages
+    parse_geometry(geometry)
+        .map(|parsed| parsed.geometry)
+        .map_err(|e| Error::Validation(format!("Feature {}: {}", index + 1, e)))
+}
+
+/// `EPSG:2927` from the CRS names found in legacy GeoJSON
+fn crs_from_name(name: &str) -> Option<String> {
+    if name.ends_with("CRS84") {
+        return Some(STORAGE_CRS.to_string());
+    }
+    let code = name.rsplit(':').next()?;
+    (name.contains("EPSG") && !code.is_empty() && code.chars().all(|c| c.is_ascii_digit()))
+        .then(|| format!("EPSG:{}", code))
+}
+
+fn read_with_gdal(path: &str, source_layer: Option<&str>) -> Result<(Option<String>, Vec<UploadedFeature>)> {
+    let dataset = gdal::Dataset::open(path)
+        .map_err(|e| Error::Validation(format!("Cannot open the upload: {}", e)))?;
+    let mut layer = match source_layer {
+        Some(name) => dataset
+            .layer_by_name(name)
+            .map_err(|_| Error::Validation(format!("The upload has no layer named {}", name)))?,
+        None => dataset
+            .layer(0)
+            .map_err(|_| Error::Validation("The upload has no layers".to_string()))?,
+    };
+
+    let crs = layer.spatial_ref().and_then(|mut srs| {
+        // A .prj is usually ESRI WKT without an authority; look it up
+        let _ = srs.auto_identify_epsg();
+        srs.auth_code().ok().map(|code| format!("EPSG:{}", code))
+    });
+
+    let mut features = Vec::new();
+    for (index, feature) in layer.features().enumerate() {
+        let geometry = feature
+            .geometry()
+            .ok_or_else(|| Error::Validation(format!("Feature {} has no geometry", index + 1)))?;
+        let wkt = geometry
+            .wkt()
+            .map_err(|e| Error::Validation(format!("Feature {}: {}", index + 1, e)))?;
+        let geometry = parse_geometry(&Value::String(wkt))
+            .map_err(|e| Error::Validation(format!("Feature {}: {}", index + 1, e)))?
+            .geometry;
+        let properties = feature
+            .fields()
+            .map(|(name, value)| (name, value.map(field_json).unwrap_or(Value::Null)))
+            .collect();
+        features.push(UploadedFeature { properties, geometry });
+    }
+    Ok((crs, features))
+}
+
+fn field_json(value: gdal::vector::FieldValue) -> Value {
+    use gdal::vector::FieldValue;
+    match value {
+        FieldValue::IntegerValue(value) => json!(value),
+        FieldValue::Integer64Value(value) => json!(value),
+        FieldValue::RealValue(value) => json!(value),
+        FieldValue::StringValue(value) => json!(value),
+        FieldValue::IntegerListValue(values) => json!(values),
+        FieldValue::Integer64ListValue(values) => json!(values),
+        FieldValue::RealListValue(values) => json!(values),
+        FieldValue::StringListValue(values) => json!(values),
+        FieldValue::DateValue(date) => json!(date.to_string()),
+        FieldValue::DateTimeValue(datetime) => json!(datetime.to_rfc3339()),
+    }
+}
+
+fn reproject(features: Vec<UploadedFeature>, source_crs: &str) -> Result<Vec<UploadedFeature>> {
+    let proj = proj::Proj::new_known_crs(source_crs, STORAGE_CRS, None)
+        .map_err(|e| Error::Validation(format!("Cannot reproject from {}: {}", source_crs, e)))?;
+    features
+        .into_iter()
+        .enumerate()
+        .map(|(index, feature)| {
+            let geometry = feature
+                .geometry
+                .try_map_coords(|Coord { x, y }| proj.convert((x, y)).map(|(x, y)| Coord { x, y }))
+                .map_err(|e| Error::Validation(format!("Feature {}: cannot reproject: {}", index + 1, e)))?;
+            Ok(UploadedFeature { geometry, ..feature })
+        })
+        .collect()
+}
+
+/// After reprojection everything must be in degrees. Coordinates that are
+/// not usually mean the file's CRS was missing or wrong.
+fn check_coordinates(features: &[UploadedFeature], source_crs: &str) -> Result<()> {
+    let Some(bbox) = Bbox::of_all(features.iter().map(|feature| &feature.geometry)) else {
+        return Err(Error::Validation("The upload's geometries are all empty".to_string()));
+    };
+    if bbox.min_x < -180.0 || bbox.max_x > 180.0 || bbox.min_y < -90.0 || bbox.max_y > 90.0 {
+        return Err(Error::Validation(format!(
+            "Coordinates are outside WGS 84 after reading them as {}; give the file's CRS as source_crs",
+            source_crs
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BENTON: &str = r#"{
+        "type": "FeatureCollection",
+        "crs": {"type": "name", "properties": {"name": "urn:ogc:def:crs:OGC:1.3:CRS84"}},
+        "features": [
+            {"type": "Feature", "properties": {"name": "Benton"},
+             "geometry": {"type": "Polygon", "coordinates": [[[-119.9, 45.9], [-119.0, 45.9], [-119.0, 46.6], [-119.9, 46.6], [-119.9, 45.9]]]}}
+        ]
+    }"#;
+
+    #[test]
+    fn test_detects_formats_from_content() {
+        assert_eq!(UploadFormat::detect(b"PK\x03\x04rest"), Some(UploadFormat::ShapefileZip));
+        assert_eq!(UploadFormat::detect(b"SQLite format 3\0rest"), Some(UploadFormat::GeoPackage));
+        assert_eq!(UploadFormat::detect(b"\n  {\"type\":"), Some(UploadFormat::GeoJson));
+        assert_eq!(UploadFormat::detect(b"POLYGON"), None);
+        assert_eq!(UploadFormat::parse("GPKG").unwrap(), UploadFormat::GeoPackage);
+        assert!(UploadFormat::parse("kml").is_err());
+    }
+
+    #[test]
+    fn test_reads_geojson_boundary() {
+        let upload = read_upload(BENTON.as_bytes(), UploadFormat::GeoJson, &ReadOptions::default()).unwrap();
+        assert_eq!(upload.source_crs, STORAGE_CRS);
+        assert_eq!(upload.features.len(), 1);
+        assert_eq!(upload.features[0].properties["name"], "Benton");
+        assert_eq!(upload.boundary().unwrap().0.len(), 1);
+        assert_eq!(crs_from_name("urn:ogc:def:crs:EPSG::2927"), Some("EPSG:2927".to_string()));
+
+        let point = r#"{"type": "Point", "coordinates": [-119.2, 46.2]}"#;
+        let upload = read_upload(point.as_bytes(), UploadFormat::GeoJson, &ReadOptions::default()).unwrap();
+        assert!(upload.boundary().is_err());
+    }
+
+    #[test]
+    fn test_rejects_projected_coordinates_read_as_degrees() {
+        let projected = r#"{"type": "Point", "coordinates": [1950000.0, 350000.0]}"#;
+        let error = read_upload(projected.as_bytes(), UploadFormat::GeoJson, &ReadOptions::default()).unwrap_err();
+        assert!(error.to_string().contains("source_crs"));
+    }
+}
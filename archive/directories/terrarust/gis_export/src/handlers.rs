@@ -1,72 +1,150 @@
-use actix_web::{web, HttpResponse, Result, HttpRequest};
+use actix_web::{web, http::StatusCode, HttpResponse, HttpRequest};
 use actix_files::NamedFile;
 use uuid::Uuid;
 use crate::models::*;
 use crate::service::GisExportService;
+use crate::catalog;
+use crate::compression::Compression;
+use crate::file_encryption;
+use crate::tiles::{self, TileCoord};
 use std::sync::Arc;
+use terrafusion_common::conditional;
+use terrafusion_common::idempotency;
+use terrafusion_common::jobs::{Job, JobQueue, NewJob, Worker};
+use terrafusion_common::models::SortParams;
+use terrafusion_common::negotiation::{self, Representation};
+use terrafusion_common::pagination::Pagination;
+use terrafusion_common::usage;
+use terrafusion_common::{CountyContext, Error, Result};
 
 /// Application state containing the GIS export service
 pub struct AppState {
     pub gis_service: Arc<GisExportService>,
+    pub jobs: JobQueue,
+}
+
+/// Queue and kind of export processing jobs
+pub const EXPORT_QUEUE: &str = "gis_export";
+pub const PROCESS_EXPORT_JOB: &str = "gis_export.process";
+
+/// The `Error` the service raised, if any. Server-side failures are logged
+/// and reported as `context` so internals stay out of responses.
+fn service_error(e: anyhow::Error, context: &str) -> Error {
+    match e.downcast::<Error>() {
+        Ok(error) if error.status_code() < 500 => error,
+        Ok(error) => {
+            log::error!("{}: {}", context, error);
+            Error::Internal(context.to_string())
+        }
+        Err(e) => {
+            log::error!("{}: {}", context, e);
+            Error::Internal(context.to_string())
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ProcessExportPayload {
+    job_id: Uuid,
+}
+
+/// Worker that processes queued exports. Failures the service did not
+/// classify are retried; a retry of an export that already failed stops at
+/// the service's PENDING check.
+pub fn export_worker(jobs: &JobQueue, service: Arc<GisExportService>) -> Worker {
+    jobs.worker(&[EXPORT_QUEUE]).handle(PROCESS_EXPORT_JOB, move |job| {
+        let service = service.clone();
+        async move {
+            let payload: ProcessExportPayload = job.payload()?;
+            service
+                .process_job(payload.job_id)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.downcast::<Error>().unwrap_or_else(|e| Error::Internal(e.to_string())))
+        }
+    })
+}
+
+fn parse_job_id(job_id: &str) -> Result<Uuid> {
+    Uuid::parse_str(job_id).map_err(|_| Error::Validation("Invalid job ID format".to_string()))
 }
 
 /// Create a new GIS export job
 pub async fn create_job(
+    req: HttpRequest,
+    county: Option<CountyContext>,
     data: web::Data<AppState>,
     request: web::Json<CreateJobRequest>,
 ) -> Result<HttpResponse> {
-    match data.gis_service.create_job(request.into_inner()).await {
-        Ok(response) => Ok(HttpResponse::Created().json(response)),
-        Err(e) => {
-            log::error!("Failed to create export job: {}", e);
-            Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                "error": e.to_string()
-            })))
+    // Only the county's administrators may export past its monthly quota
+    let parameters = request.parameters.as_ref().map(|p| serde_json::json!(p));
+    if usage::quota_overridden(parameters.as_ref()) {
+        let is_admin = county.as_ref().is_some_and(|county| {
+            county.can_access(&request.county_id) && (county.is_platform_admin || county.has_role("admin"))
+        });
+        if !is_admin {
+            return Err(Error::Authorization(format!(
+                "Administrator role required for {}",
+                usage::OVERRIDE_QUOTA_PARAMETER
+            )));
         }
     }
+
+    if let Some(key) = req.headers().get(idempotency::IDEMPOTENCY_KEY_HEADER) {
+        let key = key.to_str().map_err(|_| {
+            Error::Validation(format!("Invalid {} header", idempotency::IDEMPOTENCY_KEY_HEADER))
+        })?;
+
+        let (stored, replayed) = data.gis_service
+            .create_job_once(request.into_inner(), key)
+            .await
+            .map_err(|e| service_error(e, "Failed to create export job"))?;
+        let status = StatusCode::from_u16(stored.status_code).unwrap_or(StatusCode::CREATED);
+        let mut response = HttpResponse::build(status);
+        if replayed {
+            response.insert_header(("Idempotent-Replayed", "true"));
+        }
+        return Ok(response.json(stored.body));
+    }
+
+    let response = data.gis_service
+        .create_job(request.into_inner())
+        .await
+        .map_err(|e| service_error(e, "Failed to create export job"))?;
+    Ok(HttpResponse::Created().json(response))
 }
 
-/// Get job status by ID
+/// Get job status by ID. Progress changes without a timestamp, so pollers
+/// revalidate by ETag alone.
 pub async fn get_job_status(
+    req: HttpRequest,
     data: web::Data<AppState>,
     path: web::Path<String>,
 ) -> Result<HttpResponse> {
-    let job_id_str = path.into_inner();
-    
-    let job_id = match Uuid::parse_str(&job_id_str) {
-        Ok(id) => id,
-        Err(_) => {
-            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Invalid job ID format"
-            })));
-        }
-    };
+    let job_id = parse_job_id(&path.into_inner())?;
 
     match data.gis_service.get_job_status(job_id).await {
-        Ok(response) => Ok(HttpResponse::Ok().json(response)),
+        Ok(response) => conditional::web::json(&req, &response, None),
         Err(e) => {
             log::error!("Failed to get job status: {}", e);
-            Ok(HttpResponse::NotFound().json(serde_json::json!({
-                "error": "Job not found"
-            })))
+            Err(Error::NotFound("Job not found".to_string()))
         }
     }
 }
 
 /// List export jobs with optional filtering
 pub async fn list_jobs(
+    req: HttpRequest,
     data: web::Data<AppState>,
     query: web::Query<ListJobsParams>,
+    sort: web::Query<SortParams>,
+    pagination: Pagination,
 ) -> Result<HttpResponse> {
-    match data.gis_service.list_jobs(query.into_inner()).await {
-        Ok(response) => Ok(HttpResponse::Ok().json(response)),
-        Err(e) => {
-            log::error!("Failed to list jobs: {}", e);
-            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to retrieve jobs"
-            })))
-        }
-    }
+    let response = data.gis_service
+        .list_jobs(query.into_inner(), &sort, pagination)
+        .await
+        .map_err(|e| service_error(e, "Failed to retrieve jobs"))?;
+    conditional::web::json(&req, &response, None)
 }
 
 /// Process a job (start the export)
@@ -74,116 +152,459 @@ pub async fn process_job(
     data: web::Data<AppState>,
     path: web::Path<String>,
 ) -> Result<HttpResponse> {
-    let job_id_str = path.into_inner();
-    
-    let job_id = match Uuid::parse_str(&job_id_str) {
-        Ok(id) => id,
-        Err(_) => {
-            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Invalid job ID format"
-            })));
-        }
-    };
-
-    // Start processing in background
-    let service = data.gis_service.clone();
-    tokio::spawn(async move {
-        if let Err(e) = service.process_job(job_id).await {
-            log::error!("Background job processing failed for {}: {}", job_id, e);
-        }
-    });
+    let job_id = parse_job_id(&path.into_inner())?;
+    let queued = enqueue_export(&data.jobs, job_id).await?;
 
     Ok(HttpResponse::Accepted().json(serde_json::json!({
         "message": "Job processing started",
-        "job_id": job_id
+        "job_id": job_id,
+        "queue_job_id": queued.id
     })))
 }
 
+/// Queue an export for a worker; asking twice while it is queued is harmless
+async fn enqueue_export(jobs: &JobQueue, job_id: Uuid) -> Result<Job> {
+    jobs.enqueue(
+        NewJob::new(EXPORT_QUEUE, PROCESS_EXPORT_JOB, serde_json::json!({ "job_id": job_id }))
+            .max_attempts(3)
+            .unique(format!("{}:{}", PROCESS_EXPORT_JOB, job_id)),
+    )
+    .await
+}
+
+/// Approve an export waiting for sign-off and queue it for processing
+pub async fn approve_job(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    request: web::Json<ReviewExportRequest>,
+) -> Result<HttpResponse> {
+    let job_id = parse_job_id(&path.into_inner())?;
+
+    let response = data.gis_service
+        .review_job(job_id, request.into_inner(), true)
+        .await
+        .map_err(|e| service_error(e, "Failed to approve export"))?;
+    enqueue_export(&data.jobs, job_id).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Reject an export waiting for sign-off
+pub async fn reject_job(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    request: web::Json<ReviewExportRequest>,
+) -> Result<HttpResponse> {
+    let job_id = parse_job_id(&path.into_inner())?;
+
+    let response = data.gis_service
+        .review_job(job_id, request.into_inner(), false)
+        .await
+        .map_err(|e| service_error(e, "Failed to reject export"))?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
 /// Cancel an export job
 pub async fn cancel_job(
     data: web::Data<AppState>,
     path: web::Path<String>,
 ) -> Result<HttpResponse> {
-    let job_id_str = path.into_inner();
-    
-    let job_id = match Uuid::parse_str(&job_id_str) {
-        Ok(id) => id,
-        Err(_) => {
-            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Invalid job ID format"
-            })));
-        }
-    };
+    let job_id = parse_job_id(&path.into_inner())?;
 
-    match data.gis_service.cancel_job(job_id).await {
-        Ok(response) => Ok(HttpResponse::Ok().json(response)),
-        Err(e) => {
-            log::error!("Failed to cancel job: {}", e);
-            Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                "error": e.to_string()
-            })))
-        }
-    }
+    let response = data.gis_service
+        .cancel_job(job_id)
+        .await
+        .map_err(|e| service_error(e, "Failed to cancel job"))?;
+    Ok(HttpResponse::Ok().json(response))
 }
 
 /// Download completed export file
 pub async fn download_export(
+    req: HttpRequest,
     data: web::Data<AppState>,
     path: web::Path<String>,
 ) -> Result<HttpResponse> {
-    let job_id_str = path.into_inner();
-    
-    let job_id = match Uuid::parse_str(&job_id_str) {
-        Ok(id) => id,
-        Err(_) => {
-            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Invalid job ID format"
-            })));
-        }
-    };
+    let job_id = parse_job_id(&path.into_inner())?;
 
     match data.gis_service.get_export_file(job_id).await {
         Ok(file_path) => {
-            match NamedFile::open(&file_path) {
-                Ok(file) => {
-                    // Get job details for proper filename
-                    if let Ok(job_status) = data.gis_service.get_job_status(job_id).await {
-                        let filename = format!("{}_{}.{}", 
-                            job_status.county_id,
-                            job_id.simple(),
-                            job_status.export_format
-                        );
-                        Ok(file.set_content_disposition(
-                            actix_web::http::header::ContentDisposition {
-                                disposition: actix_web::http::header::DispositionType::Attachment,
-                                parameters: vec![
-                                    actix_web::http::header::DispositionParam::Filename(filename)
-                                ],
-                            }
-                        ).into_response(&HttpRequest::from_parts(
-                            actix_web::dev::RequestHead::default(),
-                            actix_web::dev::Payload::None
-                        )))
-                    } else {
-                        Ok(file.into_response(&HttpRequest::from_parts(
-                            actix_web::dev::RequestHead::default(),
-                            actix_web::dev::Payload::None
-                        )))
-                    }
-                }
-                Err(e) => {
-                    log::error!("Failed to open export file: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Export file not accessible"
-                    })))
-                }
-            }
+            // Lets clients verify the transfer without fetching the manifest
+            let checksum = data.gis_service.get_job_status(job_id).await.ok().and_then(|job| job.checksum_sha256);
+            file_response(&req, &data, &file_path, &job_id.simple().to_string(), checksum).await
         }
         Err(e) => {
             log::error!("Failed to get export file: {}", e);
-            Ok(HttpResponse::NotFound().json(serde_json::json!({
-                "error": "Export file not found"
-            })))
+            Err(Error::NotFound("Export file not found".to_string()))
+        }
+    }
+}
+
+/// Send an export file as an attachment with its content type and checksum
+async fn file_response(
+    req: &HttpRequest,
+    data: &AppState,
+    file_path: &std::path::Path,
+    fallback_name: &str,
+    checksum: Option<String>,
+) -> Result<HttpResponse> {
+    if file_encryption::is_encrypted(file_path) {
+        return encrypted_file_response(data, file_path, fallback_name, checksum).await;
+    }
+
+    let file = NamedFile::open(file_path).map_err(|e| {
+        log::error!("Failed to open export file: {}", e);
+        Error::Internal("Export file not accessible".to_string())
+    })?;
+
+    // The stored name carries the format and compression, e.g. `.geojson.gz`
+    let filename = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| fallback_name.to_string());
+    let file = file.set_content_disposition(
+        actix_web::http::header::ContentDisposition {
+            disposition: actix_web::http::header::DispositionType::Attachment,
+            parameters: vec![
+                actix_web::http::header::DispositionParam::Filename(filename)
+            ],
+        }
+    );
+
+    let mut response = file.into_response(req);
+    if let Some(content_type) = Compression::from_path(file_path).content_type() {
+        response.headers_mut().insert(
+            actix_web::http::header::CONTENT_TYPE,
+            actix_web::http::header::HeaderValue::from_static(content_type),
+        );
+    }
+    if let Some(value) = checksum.and_then(|c| actix_web::http::header::HeaderValue::from_str(&c).ok()) {
+        response.headers_mut().insert(
+            actix_web::http::header::HeaderName::from_static("x-checksum-sha256"),
+            value,
+        );
+    }
+    Ok(response)
+}
+
+/// Stream an encrypted export, decrypting it on the way out. Range requests
+/// are not supported for these files.
+async fn encrypted_file_response(
+    data: &AppState,
+    file_path: &std::path::Path,
+    fallback_name: &str,
+    checksum: Option<String>,
+) -> Result<HttpResponse> {
+    let kek = data.gis_service
+        .export_key()
+        .await
+        .map_err(|e| service_error(e, "Export file not accessible"))?;
+
+    let plaintext_path = file_encryption::plaintext_path(file_path);
+    let filename = plaintext_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| fallback_name.to_string());
+    let content_type = Compression::from_path(&plaintext_path)
+        .content_type()
+        .map(|content_type| content_type.to_string())
+        .unwrap_or_else(|| {
+            let extension = plaintext_path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+            actix_files::file_extension_to_mime(extension).to_string()
+        });
+
+    let (sender, receiver) = tokio::sync::mpsc::channel::<std::result::Result<web::Bytes, actix_web::Error>>(8);
+    let path = file_path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let result = file_encryption::decrypt_file(&kek, &path, |chunk| {
+            // A send error means the client went away
+            sender.blocking_send(Ok(web::Bytes::from(chunk))).is_ok()
+        });
+        if let Err(e) = result {
+            log::error!("Failed to decrypt export file {:?}: {}", path, e);
+            let _ = sender.blocking_send(Err(actix_web::error::ErrorInternalServerError("Export file not accessible")));
+        }
+    });
+    let body = futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    });
+
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type(content_type)
+        .insert_header(actix_web::http::header::ContentDisposition {
+            disposition: actix_web::http::header::DispositionType::Attachment,
+            parameters: vec![actix_web::http::header::DispositionParam::Filename(filename)],
+        });
+    if let Some(checksum) = checksum {
+        response.insert_header(("x-checksum-sha256", checksum));
+    }
+    Ok(response.streaming(body))
+}
+
+/// Change report between two completed runs of the same export, as a
+/// GeoJSON or CSV download. The counts are also sent as headers.
+pub async fn compare_exports(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    request: web::Json<CompareExportsRequest>,
+) -> Result<HttpResponse> {
+    let (report_path, summary) = data.gis_service
+        .compare_exports(request.into_inner())
+        .await
+        .map_err(|e| service_error(e, "Failed to compare exports"))?;
+
+    let mut response = file_response(&req, &data, &report_path, "changes", None).await?;
+    for (name, count) in [
+        ("x-changes-added", summary.added),
+        ("x-changes-removed", summary.removed),
+        ("x-changes-modified", summary.modified),
+    ] {
+        response.headers_mut().insert(
+            actix_web::http::header::HeaderName::from_static(name),
+            actix_web::http::header::HeaderValue::from(count),
+        );
+    }
+    Ok(response)
+}
+
+/// Publish a completed export on the public portal
+pub async fn publish_export(
+    data: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    request: web::Json<PublishExportRequest>,
+) -> Result<HttpResponse> {
+    let published = data.gis_service
+        .publish_export(path.into_inner(), request.into_inner())
+        .await
+        .map_err(|e| service_error(e, "Failed to publish export"))?;
+    Ok(HttpResponse::Ok().json(published))
+}
+
+/// Query parameters for unpublishing
+#[derive(Debug, serde::Deserialize)]
+pub struct UnpublishParams {
+    /// County the caller administers; omitted for platform administrators
+    pub county_id: Option<String>,
+}
+
+/// Withdraw an export from the public portal
+pub async fn unpublish_export(
+    data: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    query: web::Query<UnpublishParams>,
+) -> Result<HttpResponse> {
+    let job_id = path.into_inner();
+    let removed = data.gis_service
+        .unpublish_export(job_id, query.county_id.as_deref())
+        .await
+        .map_err(|e| service_error(e, "Failed to unpublish export"))?;
+    if !removed {
+        return Err(Error::NotFound("Export is not published".to_string()));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Public listing of published exports, as JSON, a GeoJSON
+/// FeatureCollection, a DCAT catalog in JSON-LD or an HTML page
+pub async fn list_published(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<ListPublishedParams>,
+    pagination: Pagination,
+) -> Result<HttpResponse> {
+    let representation = Representation::from_request(&req, query.format.as_deref())?;
+    let page = data.gis_service
+        .list_published(&query, pagination)
+        .await
+        .map_err(|e| service_error(e, "Failed to list published exports"))?;
+
+    let base_url = data.gis_service.public_url();
+    let body = match representation {
+        Representation::Json => serde_json::json!(&page).to_string(),
+        Representation::GeoJson => catalog::feature_collection(base_url, &page.items).to_string(),
+        Representation::JsonLd => catalog::catalog_document(base_url, &page.items).to_string(),
+        Representation::Html => negotiation::html_document(
+            "Published exports",
+            &serde_json::json!(&page),
+            Some(&catalog::catalog_document(base_url, &page.items)),
+        ),
+    };
+    Ok(representation.respond(body))
+}
+
+/// Public details of one published export, in the same representations as
+/// the listing
+pub async fn get_published(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    query: web::Query<PublishedExportParams>,
+) -> Result<HttpResponse> {
+    let representation = Representation::from_request(&req, query.format.as_deref())?;
+    let published = data.gis_service
+        .get_published(path.into_inner())
+        .await
+        .map_err(|e| service_error(e, "Failed to load published export"))?;
+
+    let base_url = data.gis_service.public_url();
+    let body = match representation {
+        Representation::Json => serde_json::json!(&published).to_string(),
+        Representation::GeoJson => catalog::feature(base_url, &published).to_string(),
+        Representation::JsonLd => catalog::dataset_document(base_url, &published).to_string(),
+        Representation::Html => {
+            let mut details = serde_json::json!(&published);
+            details["download_url"] = format!("{}/download", catalog::export_url(base_url, &published)).into();
+            negotiation::html_document(
+                &published.title,
+                &details,
+                Some(&catalog::dataset_document(base_url, &published)),
+            )
+        }
+    };
+    Ok(representation.respond(body))
+}
+
+/// Public download of a published export
+pub async fn download_published(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse> {
+    let id = path.into_inner();
+    let (file_path, published) = data.gis_service
+        .get_published_file(id)
+        .await
+        .map_err(|e| service_error(e, "Published export file not available"))?;
+    file_response(&req, &data, &file_path, &id.simple().to_string(), published.checksum_sha256).await
+}
+
+/// Largest boundary or seed data file accepted
+const MAX_GEODATA_UPLOAD_BYTES: usize = 256 * 1024 * 1024;
+
+/// Boundaries and seed data replace what exports and AOI checks see, so
+/// only the county's administrators may upload them
+fn require_county_admin(county: Option<&CountyContext>, county_id: &str) -> Result<()> {
+    let is_admin = county.is_some_and(|county| {
+        county.can_access(county_id) && (county.is_platform_admin || county.has_role("admin"))
+    });
+    if !is_admin {
+        return Err(Error::Authorization(format!("Administrator role required for county {}", county_id)));
+    }
+    Ok(())
+}
+
+/// Upload the county's boundary as GeoJSON, a zipped Shapefile or a GeoPackage
+pub async fn upload_boundary(
+    county: Option<CountyContext>,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<GeodataUploadParams>,
+    body: web::Bytes,
+) -> Result<HttpResponse> {
+    let county_id = path.into_inner();
+    require_county_admin(county.as_ref(), &county_id)?;
+    if body.is_empty() {
+        return Err(Error::Validation("The request body must be the boundary file".to_string()));
+    }
+
+    let upload = data.gis_service
+        .upload_boundary(&county_id, body.to_vec(), query.into_inner())
+        .await
+        .map_err(|e| service_error(e, "Failed to upload county boundary"))?;
+    Ok(HttpResponse::Created().json(upload))
+}
+
+/// Replace a layer's seed features with the uploaded file's
+pub async fn upload_layer_features(
+    county: Option<CountyContext>,
+    data: web::Data<AppState>,
+    path: web::Path<(String, String)>,
+    query: web::Query<GeodataUploadParams>,
+    body: web::Bytes,
+) -> Result<HttpResponse> {
+    let (county_id, layer_id) = path.into_inner();
+    require_county_admin(county.as_ref(), &county_id)?;
+    if body.is_empty() {
+        return Err(Error::Validation("The request body must be the layer file".to_string()));
+    }
+
+    let upload = data.gis_service
+        .upload_layer_features(&county_id, &layer_id, body.to_vec(), query.into_inner())
+        .await
+        .map_err(|e| service_error(e, "Failed to upload layer features"))?;
+    Ok(HttpResponse::Created().json(upload))
+}
+
+/// The county's boundary and layer uploads
+pub async fn list_geodata_uploads(
+    county: Option<CountyContext>,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse> {
+    let county_id = path.into_inner();
+    require_county_admin(county.as_ref(), &county_id)?;
+
+    let uploads = data.gis_service
+        .list_geodata_uploads(&county_id)
+        .await
+        .map_err(|e| service_error(e, "Failed to list uploads"))?;
+    Ok(HttpResponse::Ok().json(uploads))
+}
+
+/// Vector tile preview of a county layer for the AOI picker and layer browser
+pub async fn map_tile(
+    county: Option<CountyContext>,
+    data: web::Data<AppState>,
+    path: web::Path<(String, String, u32, u32, String)>,
+) -> Result<HttpResponse> {
+    let (county_id, layer_id, z, x, y) = path.into_inner();
+    if !county.is_some_and(|county| county.can_access(&county_id)) {
+        return Err(Error::Authorization(format!("No access to county {}", county_id)));
+    }
+    let coord = TileCoord::parse(z, x, &y)?;
+
+    let tile = data.gis_service
+        .map_tile(&county_id, &layer_id, coord)
+        .await
+        .map_err(|e| service_error(e, "Failed to render map tile"))?;
+    if tile.is_empty() {
+        return Ok(HttpResponse::NoContent().finish());
+    }
+    Ok(HttpResponse::Ok()
+        .content_type(tiles::CONTENT_TYPE)
+        .insert_header(("Cache-Control", format!("private, max-age={}", data.gis_service.tile_ttl().as_secs())))
+        .body(tile.as_ref().clone()))
+}
+
+/// Attribute distributions and feature counts of a layer's uploaded data
+pub async fn layer_stats(
+    county: Option<CountyContext>,
+    data: web::Data<AppState>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse> {
+    let (county_id, layer_id) = path.into_inner();
+    if !county.is_some_and(|county| county.can_access(&county_id)) {
+        return Err(Error::Authorization(format!("No access to county {}", county_id)));
+    }
+
+    let stats = data.gis_service
+        .layer_stats(&county_id, &layer_id)
+        .await
+        .map_err(|e| service_error(e, "Failed to compute layer statistics"))?;
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+/// Integrity manifest of a completed export
+pub async fn download_manifest(
+    data: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse> {
+    let job_id = path.into_inner();
+    
+    match data.gis_service.get_manifest(job_id).await {
+        Ok(manifest) => Ok(HttpResponse::Ok().json(manifest)),
+        Err(e) => {
+            log::warn!("Manifest unavailable for job {}: {}", job_id, e);
+            Err(Error::NotFound("Export manifest not found".to_string()))
         }
     }
 }
@@ -208,25 +629,20 @@ pub async fn health_check() -> Result<HttpResponse> {
 /// Service metrics endpoint
 pub async fn metrics(data: web::Data<AppState>) -> Result<HttpResponse> {
     // Get basic job statistics
-    let job_stats = match data.gis_service.list_jobs(ListJobsParams {
-        county_id: None,
-        username: None,
-        status: None,
-        limit: Some(1000),
-        offset: Some(0),
-    }).await {
+    let recent = Pagination::Offset { offset: 0, per_page: 1000 };
+    let job_stats = match data.gis_service.list_jobs(ListJobsParams::default(), &SortParams::default(), recent).await {
         Ok(jobs) => {
-            let total_jobs = jobs.total;
-            let completed_jobs = jobs.jobs.iter()
+            let total_jobs = jobs.total.unwrap_or(0);
+            let completed_jobs = jobs.items.iter()
                 .filter(|j| j.status == "COMPLETED")
                 .count() as i64;
-            let failed_jobs = jobs.jobs.iter()
+            let failed_jobs = jobs.items.iter()
                 .filter(|j| j.status == "FAILED")
                 .count() as i64;
-            let pending_jobs = jobs.jobs.iter()
+            let pending_jobs = jobs.items.iter()
                 .filter(|j| j.status == "PENDING")
                 .count() as i64;
-            let processing_jobs = jobs.jobs.iter()
+            let processing_jobs = jobs.items.iter()
                 .filter(|j| j.status == "PROCESSING")
                 .count() as i64;
 
@@ -272,6 +688,25 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
             .route("/jobs/{job_id}", web::get().to(get_job_status))
             .route("/jobs/{job_id}/process", web::post().to(process_job))
             .route("/jobs/{job_id}/cancel", web::post().to(cancel_job))
+            .route("/jobs/{job_id}/approve", web::post().to(approve_job))
+            .route("/jobs/{job_id}/reject", web::post().to(reject_job))
             .route("/download/{job_id}", web::get().to(download_export))
+            .route("/download/{job_id}/manifest", web::get().to(download_manifest))
+            .route("/exports/compare", web::post().to(compare_exports))
+            .route("/jobs/{job_id}/publish", web::put().to(publish_export))
+            .route("/jobs/{job_id}/publish", web::delete().to(unpublish_export))
+            .route("/tiles/{county_id}/{layer_id}/{z}/{x}/{y}", web::get().to(map_tile))
+            .service(
+                web::scope("/counties/{county_id}")
+                    .app_data(web::PayloadConfig::new(MAX_GEODATA_UPLOAD_BYTES))
+                    .route("/boundary", web::post().to(upload_boundary))
+                    .route("/layers/{layer_id}/features", web::post().to(upload_layer_features))
+                    .route("/layers/{layer_id}/stats", web::get().to(layer_stats))
+                    .route("/uploads", web::get().to(list_geodata_uploads))
+            )
+            // Read-only portal, exposed without authentication by the gateway
+            .route("/public/exports", web::get().to(list_published))
+            .route("/public/exports/{id}", web::get().to(get_published))
+            .route("/public/exports/{id}/download", web::get().to(download_published))
     );
 }
\ No newline at end of file
@@ -0,0 +1,162 @@
+//! Attribute statistics of uploaded layer features
+//!
+//! PostGIS aggregates each attribute of the layer's `properties`; this
+//! module turns those rows into the summary the export wizard's filter
+//! builder and the data quality pages read.
+
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::FromRow;
+
+/// Attributes with at most this many distinct values get their value
+/// counts listed, so the filter builder can offer them as choices
+pub const MAX_LISTED_DISTINCT: i64 = 50;
+
+/// Most common values listed per attribute
+pub const TOP_VALUES: i64 = 10;
+
+/// One attribute's aggregates, as queried
+#[derive(Debug, Clone, FromRow)]
+pub struct AttributeRow {
+    pub name: String,
+    /// Features with a non-null value
+    pub non_null: i64,
+    pub distinct_count: i64,
+    /// JSON types seen: `number`, `string`, `boolean`, `object`, `array`
+    pub types: Vec<String>,
+    pub min_number: Option<f64>,
+    pub max_number: Option<f64>,
+    pub min_text: Option<String>,
+    pub max_text: Option<String>,
+}
+
+/// How often one value of an attribute occurs, as queried
+#[derive(Debug, Clone, FromRow)]
+pub struct ValueCountRow {
+    pub name: String,
+    pub value: Value,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeKind {
+    Number,
+    String,
+    Boolean,
+    /// Objects, arrays, or values of more than one type
+    Mixed,
+    /// No non-null values at all
+    Empty,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValueCount {
+    pub value: Value,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributeStats {
+    pub name: String,
+    pub kind: AttributeKind,
+    pub distinct_count: i64,
+    /// Share of the layer's features where the attribute is missing or null
+    pub null_rate: f64,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    /// Most common values, for attributes with few distinct values
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub top_values: Vec<ValueCount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerStats {
+    pub county_id: String,
+    pub layer_id: String,
+    pub feature_count: i64,
+    /// Feature counts per geometry type, e.g. `ST_Polygon`
+    pub geometry_types: Vec<ValueCount>,
+    /// `[min_x, min_y, max_x, max_y]` in WGS 84
+    pub extent: Option<[f64; 4]>,
+    pub attributes: Vec<AttributeStats>,
+}
+
+impl AttributeStats {
+    pub fn from_rows(row: AttributeRow, values: &[ValueCountRow], feature_count: i64) -> Self {
+        let kind = match row.types.as_slice() {
+            [] => AttributeKind::Empty,
+            [single] => match single.as_str() {
+                "number" => AttributeKind::Number,
+                "string" => AttributeKind::String,
+                "boolean" => AttributeKind::Boolean,
+                _ => AttributeKind::Mixed,
+            },
+            _ => AttributeKind::Mixed,
+        };
+        let (min, max) = match kind {
+            AttributeKind::Number => (row.min_number.map(Value::from), row.max_number.map(Value::from)),
+            AttributeKind::String => (row.min_text.map(Value::from), row.max_text.map(Value::from)),
+            _ => (None, None),
+        };
+        let null_rate = if feature_count > 0 {
+            (feature_count - row.non_null).max(0) as f64 / feature_count as f64
+        } else {
+            0.0
+        };
+        let top_values = if row.distinct_count <= MAX_LISTED_DISTINCT {
+            values
+                .iter()
+                .filter(|value| value.name == row.name)
+                .map(|value| ValueCount { value: value.value.clone(), count: value.count })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Self { name: row.name, kind, distinct_count: row.distinct_count, null_rate, min, max, top_values }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn row(name: &str, non_null: i64, distinct_count: i64, types: &[&str]) -> AttributeRow {
+        AttributeRow {
+            name: name.to_string(),
+            non_null,
+            distinct_count,
+            types: types.iter().map(|t| t.to_string()).collect(),
+            min_number: Some(1200.0),
+            max_number: Some(98000.0),
+            min_text: Some("A".to_string()),
+            max_text: Some("Z".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_attribute_stats_from_rows() {
+        let values = vec![
+            ValueCountRow { name: "zoning".to_string(), value: json!("R1"), count: 30 },
+            ValueCountRow { name: "zoning".to_string(), value: json!("C2"), count: 10 },
+        ];
+
+        let zoning = AttributeStats::from_rows(row("zoning", 40, 2, &["string"]), &values, 50);
+        assert_eq!(zoning.kind, AttributeKind::String);
+        assert_eq!(zoning.null_rate, 0.2);
+        assert_eq!(zoning.min, Some(json!("A")));
+        assert_eq!(zoning.top_values.len(), 2);
+
+        let value = AttributeStats::from_rows(row("assessed_value", 50, 480, &["number"]), &values, 50);
+        assert_eq!(value.kind, AttributeKind::Number);
+        assert_eq!(value.max, Some(json!(98000.0)));
+        assert!(value.top_values.is_empty());
+
+        let notes = AttributeStats::from_rows(row("notes", 5, 300, &["number", "string"]), &values, 50);
+        assert_eq!(notes.kind, AttributeKind::Mixed);
+        assert_eq!(notes.min, None);
+        assert_eq!(AttributeStats::from_rows(row("empty", 0, 0, &[]), &[], 0).null_rate, 0.0);
+    }
+}
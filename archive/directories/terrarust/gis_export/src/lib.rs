@@ -11,6 +11,18 @@ pub mod models;
 pub mod service;
 pub mod handlers;
 pub mod formats;
+pub mod compression;
+pub mod manifest;
+pub mod file_encryption;
+pub mod scanning;
+pub mod publishing;
+pub mod delivery;
+pub mod geodata;
+pub mod tiles;
+pub mod layer_stats;
+pub mod compare;
+pub mod result_cache;
+pub mod catalog;
 
 pub use service::GisExportService;
 pub use models::*;
@@ -46,6 +58,17 @@ impl ExportFormat {
             ExportFormat::Csv => "csv",
         }
     }
+
+    /// Media type of the downloaded file, as catalogs list it
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Shapefile => "application/zip",
+            ExportFormat::Geojson => "application/geo+json",
+            ExportFormat::Kml => "application/vnd.google-earth.kml+xml",
+            ExportFormat::Geopackage => "application/geopackage+sqlite3",
+            ExportFormat::Csv => "text/csv",
+        }
+    }
 }
 
 impl std::str::FromStr for ExportFormat {
@@ -70,6 +93,17 @@ pub struct GisExportConfig {
     pub database_url: String,
     pub max_concurrent_jobs: usize,
     pub job_timeout_seconds: u64,
+    /// Shared key for signing export manifests; manifests are unsigned without it
+    pub manifest_signing_key: Option<String>,
+    /// Largest area of interest a job may request, in km²; unlimited when unset
+    pub max_aoi_km2: Option<f64>,
+    /// Base URL of the download links in export emails
+    pub public_url: Option<String>,
+    /// Largest export attached to an email; bigger ones are sent as a link
+    pub email_attachment_max_bytes: u64,
+    /// How long a completed export may be reused for identical requests;
+    /// zero turns reuse off
+    pub result_cache_minutes: u64,
 }
 
 impl Default for GisExportConfig {
@@ -80,6 +114,19 @@ impl Default for GisExportConfig {
                 .unwrap_or_else(|_| "postgresql://localhost/terrafusion".to_string()),
             max_concurrent_jobs: 10,
             job_timeout_seconds: 3600, // 1 hour
+            manifest_signing_key: std::env::var("EXPORT_MANIFEST_SIGNING_KEY").ok().filter(|key| !key.is_empty()),
+            max_aoi_km2: std::env::var("EXPORT_MAX_AOI_KM2").ok().and_then(|value| value.parse().ok()),
+            public_url: std::env::var("EXPORT_PUBLIC_URL").ok().filter(|url| !url.is_empty()),
+            email_attachment_max_bytes: std::env::var("EXPORT_EMAIL_MAX_ATTACHMENT_MB")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .unwrap_or(10)
+                * 1024
+                * 1024,
+            result_cache_minutes: std::env::var("EXPORT_RESULT_CACHE_MINUTES")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(60),
         }
     }
 }
@@ -112,6 +159,7 @@ pub async fn health_check() -> Result<HttpResponse, actix_web::Error> {
             "kml",
             "geopackage",
             "csv"
-        ]
+        ],
+        "supported_compression": ["none", "zip", "gzip", "zstd"]
     })))
 }
\ No newline at end of file
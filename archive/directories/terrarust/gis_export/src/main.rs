@@ -1,6 +1,8 @@
-use actix_web::{web, App, HttpServer, middleware::Logger};
+use actix_web::{web, App, HttpServer, middleware::{Compress, Logger}};
 use env_logger::Env;
 use std::sync::Arc;
+use terrafusion_common::compression::{web::CompressionPolicy, CompressionSettings};
+use terrafusion_common::errors::web::ErrorEnvelopeMiddleware;
 
 mod models;
 mod service;
@@ -18,14 +20,44 @@ async fn main() -> std::io::Result<()> {
     let config = terrafusion_gis_export::GisExportConfig::default();
     
     // Initialize the GIS Export service
+    let notifier = terrafusion_common::notifications::Notifier::from_env()
+        .expect("Invalid notification settings");
+    // County security policies may require exports to be encrypted at rest and scanned
+    let export_cipher = terrafusion_gis_export::file_encryption::ExportCipher::from_env()
+        .expect("Invalid export encryption settings");
+    let scanner = terrafusion_gis_export::scanning::Scanner::from_env()
+        .expect("Invalid export scanning settings");
     let gis_service = match terrafusion_gis_export::init_service(config.clone()).await {
-        Ok(service) => Arc::new(service),
+        Ok(service) => {
+            let mut service = service.with_notifier(notifier.clone()).with_scanner(scanner);
+            if let Some(cipher) = export_cipher {
+                service = service.with_encryption(cipher);
+            }
+            Arc::new(service)
+        }
         Err(e) => {
             log::error!("Failed to initialize GIS Export service: {}", e);
             std::process::exit(1);
         }
     };
 
+    // Digests and disk alerts only matter when notifications are enabled
+    if notifier.settings().enabled {
+        notifier.spawn_digest();
+        notifier.spawn_disk_monitor(vec![config.storage_path.clone()], std::time::Duration::from_secs(300));
+    }
+
+    // Exports are processed by queue workers, which may run on any instance
+    let jobs = terrafusion_common::jobs::JobQueue::from_env(
+        terrafusion_common::jobs::PgJobBackend::new(gis_service.db_pool()),
+    )
+    .expect("Invalid job queue settings");
+    handlers::export_worker(&jobs, gis_service.clone()).spawn();
+    jobs.spawn_maintenance(std::time::Duration::from_secs(60));
+
+    // GeoJSON downloads and job listings are compressed; zipped exports are not
+    let compression = CompressionSettings::from_env().expect("Invalid response compression settings");
+
     let port = std::env::var("GIS_EXPORT_PORT")
         .unwrap_or_else(|_| "7000".to_string())
         .parse::<u16>()
@@ -38,8 +70,12 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(web::Data::new(AppState {
                 gis_service: gis_service.clone(),
+                jobs: jobs.clone(),
             }))
             .wrap(Logger::default())
+            .wrap(ErrorEnvelopeMiddleware)
+            .wrap(CompressionPolicy::new(compression))
+            .wrap(Compress::default())
             .configure(configure_routes)
     })
     .bind(("0.0.0.0", port))?
@@ -0,0 +1,154 @@
+//! Checksums and signed integrity manifests for export packages
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use terrafusion_common::geo::Bbox;
+use uuid::Uuid;
+
+/// Suffix of the manifest written next to each export file
+pub const MANIFEST_SUFFIX: &str = "manifest.json";
+
+/// Signature scheme recorded in the manifest
+const SIGNATURE_ALGORITHM: &str = "hmac-sha256";
+
+/// SHA-256 of a file, hex encoded. Blocking.
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestSignature {
+    pub algorithm: String,
+    pub value: String,
+}
+
+/// Malware scan an export passed before it was offered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestScan {
+    pub scanner: String,
+    pub scanned_at: DateTime<Utc>,
+}
+
+/// What an export package contains, for verification after transfer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub job_id: Uuid,
+    pub county_id: String,
+    pub export_format: String,
+    pub compression: String,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<ManifestFile>,
+    /// Number of features exported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature_count: Option<u64>,
+    /// Extent of the exported features
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<Bbox>,
+    /// Absent when no scanner is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan: Option<ManifestScan>,
+    /// HMAC over the manifest without this field; absent when no signing key is configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ManifestSignature>,
+}
+
+impl ExportManifest {
+    /// Sign with the shared key so consumers can tell the manifest was not altered
+    pub fn sign(&mut self, key: &[u8]) {
+        self.signature = Some(ManifestSignature {
+            algorithm: SIGNATURE_ALGORITHM.to_string(),
+            value: hex(&self.mac(key).finalize().into_bytes()),
+        });
+    }
+
+    /// Whether the signature matches `key`; unsigned manifests never verify
+    pub fn verify(&self, key: &[u8]) -> bool {
+        let Some(signature) = &self.signature else {
+            return false;
+        };
+        if signature.algorithm != SIGNATURE_ALGORITHM {
+            return false;
+        }
+
+        let expected = hex(&self.mac(key).finalize().into_bytes());
+        // Constant-time comparison of the hex strings
+        expected.len() == signature.value.len()
+            && expected
+                .bytes()
+                .zip(signature.value.bytes())
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+
+    fn mac(&self, key: &[u8]) -> Hmac<Sha256> {
+        let unsigned = Self { signature: None, ..self.clone() };
+        let payload = serde_json::to_vec(&unsigned).expect("manifest serializes");
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(&payload);
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        std::fs::write(&path, "id\n1\n").unwrap();
+
+        let mut manifest = ExportManifest {
+            job_id: Uuid::new_v4(),
+            county_id: "benton".to_string(),
+            export_format: "csv".to_string(),
+            compression: "none".to_string(),
+            created_at: Utc::now(),
+            files: vec![ManifestFile {
+                name: "export.csv".to_string(),
+                size: 5,
+                sha256: sha256_file(&path).unwrap(),
+            }],
+            feature_count: Some(1),
+            bbox: None,
+            scan: None,
+            signature: None,
+        };
+        assert_eq!(manifest.files[0].sha256.len(), 64);
+        assert!(!manifest.verify(b"secret"));
+
+        manifest.sign(b"secret");
+        assert!(manifest.verify(b"secret"));
+        assert!(!manifest.verify(b"other"));
+
+        manifest.files[0].size = 6;
+        assert!(!manifest.verify(b"secret"));
+    }
+}
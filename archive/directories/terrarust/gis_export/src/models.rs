@@ -10,21 +10,26 @@ use crate::ExportFormat;
 #[serde(rename_all = "UPPERCASE")]
 #[sqlx(type_name = "text")]
 pub enum JobStatus {
+    #[serde(rename = "PENDING_APPROVAL")]
+    PendingApproval,
     Pending,
     Processing,
     Completed,
     Failed,
     Cancelled,
+    Rejected,
 }
 
 impl std::fmt::Display for JobStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            JobStatus::PendingApproval => write!(f, "PENDING_APPROVAL"),
             JobStatus::Pending => write!(f, "PENDING"),
             JobStatus::Processing => write!(f, "PROCESSING"),
             JobStatus::Completed => write!(f, "COMPLETED"),
             JobStatus::Failed => write!(f, "FAILED"),
             JobStatus::Cancelled => write!(f, "CANCELLED"),
+            JobStatus::Rejected => write!(f, "REJECTED"),
         }
     }
 }
@@ -48,10 +53,26 @@ pub struct GisExportJob {
     pub created_at: DateTime<Utc>,
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
+    pub checksum_sha256: Option<String>,
+    pub manifest: Option<serde_json::Value>,
+    /// Results of pushing the export to the county's GIS portals
+    pub publications: Option<serde_json::Value>,
+    /// Results of delivering the export to FTP and SFTP destinations
+    pub deliveries: Option<serde_json::Value>,
+    /// Approver who signed off on or rejected the export
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_comment: Option<String>,
+    /// Hash of the request and data version, for reusing the result
+    pub result_fingerprint: Option<String>,
+    /// Earlier identical export whose file this job serves
+    pub reused_from: Option<Uuid>,
+    /// Data version of each layer the export was generated from
+    pub data_versions: Option<serde_json::Value>,
 }
 
 /// Request to create a new GIS export job
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CreateJobRequest {
     pub county_id: String,
     pub username: String,
@@ -92,25 +113,24 @@ pub struct JobStatusResponse {
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub progress_percent: Option<f32>,
+    /// SHA-256 of the downloadable file
+    pub checksum_sha256: Option<String>,
+    pub manifest_url: Option<String>,
+    pub publications: Option<serde_json::Value>,
+    pub deliveries: Option<serde_json::Value>,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_comment: Option<String>,
+    pub reused_from: Option<Uuid>,
+    pub data_versions: Option<serde_json::Value>,
 }
 
-/// List of export jobs with filtering
-#[derive(Debug, Serialize)]
-pub struct JobListResponse {
-    pub jobs: Vec<JobStatusResponse>,
-    pub total: i64,
-    pub limit: i64,
-    pub offset: i64,
-}
-
-/// Parameters for listing jobs
-#[derive(Debug, Deserialize)]
+/// Filters for listing jobs; paging comes from the shared `Pagination` extractor
+#[derive(Debug, Default, Deserialize)]
 pub struct ListJobsParams {
     pub county_id: Option<String>,
     pub username: Option<String>,
     pub status: Option<String>,
-    pub limit: Option<i64>,
-    pub offset: Option<i64>,
 }
 
 impl From<GisExportJob> for JobStatusResponse {
@@ -129,6 +149,15 @@ impl From<GisExportJob> for JobStatusResponse {
             started_at: job.started_at,
             completed_at: job.completed_at,
             progress_percent: None, // Calculate based on status if needed
+            manifest_url: job.manifest.as_ref().map(|_| format!("/api/v1/gis-export/download/{}/manifest", job.job_id)),
+            checksum_sha256: job.checksum_sha256,
+            publications: job.publications,
+            deliveries: job.deliveries,
+            reviewed_by: job.reviewed_by,
+            reviewed_at: job.reviewed_at,
+            review_comment: job.review_comment,
+            reused_from: job.reused_from,
+            data_versions: job.data_versions,
         }
     }
 }
@@ -150,6 +179,107 @@ impl From<GisExportJob> for CreateJobResponse {
     }
 }
 
+/// A completed export offered on the public portal
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PublishedExport {
+    pub id: Uuid,
+    pub county_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub export_format: String,
+    /// Layer names included in the file
+    pub layers: serde_json::Value,
+    pub file_size: Option<i64>,
+    pub checksum_sha256: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub published_at: DateTime<Utc>,
+    pub download_count: i64,
+    /// Area the export covers, as a GeoJSON geometry; shown as its geometry
+    /// in GeoJSON listings
+    #[serde(skip)]
+    pub area_of_interest: Option<serde_json::Value>,
+}
+
+/// Query parameters of a boundary or layer seed data upload; the file
+/// itself is the request body
+#[derive(Debug, Deserialize)]
+pub struct GeodataUploadParams {
+    /// `geojson`, `shapefile` (zipped) or `geopackage`; detected from the
+    /// file when absent
+    pub format: Option<String>,
+    /// CRS of the file when it does not carry one, e.g. `EPSG:2927`
+    pub source_crs: Option<String>,
+    /// GeoPackage layer to read; the first by default
+    pub source_layer: Option<String>,
+    pub file_name: Option<String>,
+    pub username: Option<String>,
+}
+
+/// A boundary or layer seed data file loaded into PostGIS
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct GeodataUpload {
+    pub id: Uuid,
+    pub county_id: String,
+    /// `boundary`, or the layer the features were loaded into
+    pub target: String,
+    pub format: String,
+    pub file_name: Option<String>,
+    pub source_crs: String,
+    pub feature_count: i32,
+    pub bbox: Option<serde_json::Value>,
+    pub uploaded_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Two completed runs of the same export to compare
+#[derive(Debug, Deserialize)]
+pub struct CompareExportsRequest {
+    /// The earlier run
+    pub base_job_id: Uuid,
+    /// The later run
+    pub job_id: Uuid,
+    /// `geojson` (default) or `csv`
+    pub format: Option<String>,
+    /// County the caller belongs to; `None` for platform administrators
+    pub county_id: Option<String>,
+}
+
+/// Request to publish a completed export
+#[derive(Debug, Deserialize)]
+pub struct PublishExportRequest {
+    pub title: String,
+    pub description: Option<String>,
+    pub published_by: String,
+    /// County the publisher administers; `None` for platform administrators
+    pub county_id: Option<String>,
+}
+
+/// An approver's decision on an export waiting for sign-off
+#[derive(Debug, Deserialize)]
+pub struct ReviewExportRequest {
+    pub reviewed_by: String,
+    /// County the approver administers; `None` for platform administrators
+    pub county_id: Option<String>,
+    /// Required when rejecting
+    pub comment: Option<String>,
+}
+
+/// Filters for the public export listing
+#[derive(Debug, Default, Deserialize)]
+pub struct ListPublishedParams {
+    pub county_id: Option<String>,
+    pub export_format: Option<String>,
+    /// `json`, `geojson`, `jsonld` or `html`; overrides the `Accept` header
+    pub format: Option<String>,
+}
+
+/// Representation of one published export
+#[derive(Debug, Default, Deserialize)]
+pub struct PublishedExportParams {
+    /// `json`, `geojson`, `jsonld` or `html`; overrides the `Accept` header
+    pub format: Option<String>,
+}
+
 /// Export processing statistics
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExportStats {
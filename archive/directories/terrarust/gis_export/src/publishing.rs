@@ -0,0 +1,443 @@
+//! Pushing completed exports to county GIS portals
+//!
+//! A county lists the portals its standard layers are served from under
+//! `publishing_targets` in its configuration:
+//!
+//! ```json
+//! "publishing_targets": [
+//!   { "type": "geoserver", "url": "https://gis.county.gov/geoserver", "workspace": "assessor",
+//!     "datastore": "parcels", "username": "publisher", "password_env": "GEOSERVER_PASSWORD",
+//!     "layers": ["parcels"] },
+//!   { "type": "arcgis_online", "username": "county_gis", "token_env": "AGOL_TOKEN",
+//!     "item_id": "4f0c2a9e7d5b4c1e8a6f3b2d1c0e9f8a", "layers": ["parcels"], "formats": ["shapefile"],
+//!     "publish": true, "service_name": "Parcels" }
+//! ]
+//! ```
+//!
+//! Jobs opt in with `"publish": true` in their parameters, as the nightly
+//! export templates do; an ad hoc export clipped to an area of interest must
+//! not replace a county-wide layer. A job goes to every target whose layers
+//! are exactly the job's and whose formats include the job's. The file is
+//! uploaded as exported, so the job must ask for `"compression": "none"`
+//! (shapefiles are already zipped).
+//!
+//! GeoServer gets the file through the REST API's datastore file upload,
+//! which creates the datastore and layer on the first push and overwrites
+//! them afterwards. ArcGIS Online overwrites `item_id`, or adds a new item
+//! when none is given, and with `publish` also overwrites the hosted feature
+//! layer published from it. Credentials are read from the named environment
+//! variables so county configuration files hold no secrets.
+
+use std::path::Path;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use terrafusion_common::http_client::{self, HttpClient, HttpClientSettings};
+use terrafusion_common::{Error, Result};
+
+use crate::compression::Compression;
+use crate::ExportFormat;
+
+const DEFAULT_PORTAL_URL: &str = "https://www.arcgis.com";
+
+/// Uploads of county-wide layers can be large
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(900);
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PublishingTarget {
+    Geoserver {
+        /// GeoServer base URL, without `/rest`
+        url: String,
+        workspace: String,
+        datastore: String,
+        username: String,
+        password_env: String,
+        layers: Vec<String>,
+        #[serde(default = "geoserver_formats")]
+        formats: Vec<ExportFormat>,
+    },
+    ArcgisOnline {
+        #[serde(default = "default_portal_url")]
+        portal_url: String,
+        /// Owner of the item
+        username: String,
+        token_env: String,
+        #[serde(default)]
+        item_id: Option<String>,
+        #[serde(default)]
+        title: Option<String>,
+        layers: Vec<String>,
+        #[serde(default = "arcgis_formats")]
+        formats: Vec<ExportFormat>,
+        /// Also overwrite the hosted feature layer
+        #[serde(default)]
+        publish: bool,
+        #[serde(default)]
+        service_name: Option<String>,
+    },
+}
+
+fn geoserver_formats() -> Vec<ExportFormat> {
+    vec![ExportFormat::Shapefile, ExportFormat::Geopackage]
+}
+
+fn arcgis_formats() -> Vec<ExportFormat> {
+    vec![ExportFormat::Shapefile, ExportFormat::Geojson, ExportFormat::Geopackage]
+}
+
+fn default_portal_url() -> String {
+    DEFAULT_PORTAL_URL.to_string()
+}
+
+/// Outcome of pushing an export to one target, kept with the job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Publication {
+    pub target: String,
+    pub succeeded: bool,
+    /// Item or layer updated, or why the push failed
+    pub message: String,
+    pub published_at: DateTime<Utc>,
+}
+
+/// A finished export as it is uploaded
+pub struct ExportFile<'a> {
+    pub path: &'a Path,
+    pub format: &'a ExportFormat,
+    pub compression: Compression,
+    pub layers: &'a [String],
+}
+
+impl PublishingTarget {
+    /// The targets in a county's configuration
+    pub fn parse_all(values: &[Value]) -> Result<Vec<Self>> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let target: Self = serde_json::from_value(value.clone())
+                    .map_err(|e| Error::Config(format!("Invalid publishing target #{}: {}", i + 1, e)))?;
+                target.validate()?;
+                Ok(target)
+            })
+            .collect()
+    }
+
+    fn validate(&self) -> Result<()> {
+        let unsupported = self.formats().iter().find(|format| match self {
+            PublishingTarget::Geoserver { .. } => geoserver_upload(format).is_none(),
+            PublishingTarget::ArcgisOnline { publish, .. } => *publish && arcgis_file_type(format).is_none(),
+        });
+        match unsupported {
+            Some(format) => Err(Error::Config(format!("{} cannot publish {} exports", self.name(), format.as_str()))),
+            None if self.layers().is_empty() => Err(Error::Config(format!("{} has no layers", self.name()))),
+            None => Ok(()),
+        }
+    }
+
+    fn layers(&self) -> &[String] {
+        match self {
+            PublishingTarget::Geoserver { layers, .. } | PublishingTarget::ArcgisOnline { layers, .. } => layers,
+        }
+    }
+
+    fn formats(&self) -> &[ExportFormat] {
+        match self {
+            PublishingTarget::Geoserver { formats, .. } | PublishingTarget::ArcgisOnline { formats, .. } => formats,
+        }
+    }
+
+    /// Target description recorded with the job
+    pub fn name(&self) -> String {
+        match self {
+            PublishingTarget::Geoserver { url, workspace, datastore, .. } => {
+                format!("geoserver:{}/{}:{}", url.trim_end_matches('/'), workspace, datastore)
+            }
+            PublishingTarget::ArcgisOnline { portal_url, item_id, username, .. } => match item_id {
+                Some(item_id) => format!("arcgis:{}/{}", portal_url.trim_end_matches('/'), item_id),
+                None => format!("arcgis:{}/{}", portal_url.trim_end_matches('/'), username),
+            },
+        }
+    }
+
+    /// Whether this target takes the export: the same layers, in any order,
+    /// in one of its formats
+    pub fn accepts(&self, layers: &[String], format: &ExportFormat) -> bool {
+        let mut ours: Vec<&String> = self.layers().iter().collect();
+        let mut theirs: Vec<&String> = layers.iter().collect();
+        ours.sort();
+        ours.dedup();
+        theirs.sort();
+        theirs.dedup();
+        ours == theirs && self.formats().contains(format)
+    }
+}
+
+/// GeoServer upload extension and content type of a format
+fn geoserver_upload(format: &ExportFormat) -> Option<(&'static str, &'static str)> {
+    match format {
+        ExportFormat::Shapefile => Some(("shp", "application/zip")),
+        ExportFormat::Geopackage => Some(("gpkg", "application/geopackage+sqlite3")),
+        _ => None,
+    }
+}
+
+/// ArcGIS item type of a format
+fn arcgis_item_type(format: &ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Shapefile => "Shapefile",
+        ExportFormat::Geojson => "GeoJson",
+        ExportFormat::Geopackage => "GeoPackage",
+        ExportFormat::Csv => "CSV",
+        ExportFormat::Kml => "KML",
+    }
+}
+
+/// `filetype` for publishing a hosted feature layer from an item
+fn arcgis_file_type(format: &ExportFormat) -> Option<&'static str> {
+    match format {
+        ExportFormat::Shapefile => Some("shapefile"),
+        ExportFormat::Geojson => Some("geojson"),
+        ExportFormat::Geopackage => Some("geoPackage"),
+        ExportFormat::Csv => Some("csv"),
+        ExportFormat::Kml => None,
+    }
+}
+
+fn secret(name: &str) -> Result<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| Error::Config(format!("{} is not set", name)))
+}
+
+/// Error in an ArcGIS REST response, which reports failures with HTTP 200
+fn arcgis_error(response: &Value) -> Option<String> {
+    let error = response.get("error")?;
+    let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+    let details: Vec<&str> = error
+        .get("details")
+        .and_then(Value::as_array)
+        .map(|details| details.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    Some(if details.is_empty() {
+        message.to_string()
+    } else {
+        format!("{} ({})", message, details.join("; "))
+    })
+}
+
+/// Sends exports to publishing targets
+#[derive(Debug, Clone)]
+pub struct Publisher {
+    client: HttpClient,
+}
+
+impl Default for Publisher {
+    fn default() -> Self {
+        Self {
+            client: HttpClientSettings::from_env()
+                .and_then(|settings| HttpClient::new("publishing", &settings.with_timeout(UPLOAD_TIMEOUT)))
+                .unwrap_or_else(|_| http_client::shared().clone()),
+        }
+    }
+}
+
+impl Publisher {
+    /// Push an export to a target. Failures are reported in the result.
+    pub async fn publish(&self, target: &PublishingTarget, export: &ExportFile<'_>) -> Publication {
+        let result = if export.compression != Compression::None {
+            Err(Error::Validation(format!(
+                "{} exports must use compression \"none\" to be published",
+                export.format.as_str()
+            )))
+        } else {
+            match target {
+                PublishingTarget::Geoserver { .. } => self.publish_geoserver(target, export).await,
+                PublishingTarget::ArcgisOnline { .. } => self.publish_arcgis(target, export).await,
+            }
+        };
+        let (succeeded, message) = match result {
+            Ok(message) => (true, message),
+            Err(e) => (false, e.to_string()),
+        };
+        Publication {
+            target: target.name(),
+            succeeded,
+            message,
+            published_at: Utc::now(),
+        }
+    }
+
+    async fn publish_geoserver(&self, target: &PublishingTarget, export: &ExportFile<'_>) -> Result<String> {
+        let PublishingTarget::Geoserver { url, workspace, datastore, username, password_env, .. } = target else {
+            unreachable!("not a GeoServer target");
+        };
+        let (extension, content_type) = geoserver_upload(export.format)
+            .ok_or_else(|| Error::Validation(format!("GeoServer cannot publish {} exports", export.format.as_str())))?;
+        let upload_url = geoserver_upload_url(url, workspace, datastore, extension);
+
+        let response = self
+            .client
+            .put(&upload_url)
+            .basic_auth(username, Some(secret(password_env)?))
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(tokio::fs::read(export.path).await?)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::ExternalService(format!("GeoServer returned {}: {}", status, body.trim())));
+        }
+        Ok(format!("Updated datastore {}:{}", workspace, datastore))
+    }
+
+    async fn publish_arcgis(&self, target: &PublishingTarget, export: &ExportFile<'_>) -> Result<String> {
+        let PublishingTarget::ArcgisOnline { portal_url, username, token_env, item_id, title, publish, service_name, .. } = target else {
+            unreachable!("not an ArcGIS Online target");
+        };
+        let item_type = arcgis_item_type(export.format);
+        let token = secret(token_env)?;
+        let content_url = format!("{}/sharing/rest/content/users/{}", portal_url.trim_end_matches('/'), username);
+        let file_name = export
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| format!("export.{}", export.format.file_extension()));
+        let title = title.clone().unwrap_or_else(|| export.layers.join(", "));
+
+        let file = Part::bytes(tokio::fs::read(export.path).await?).file_name(file_name);
+        let form = Form::new()
+            .text("f", "json")
+            .text("token", token.clone())
+            .text("title", title.clone())
+            .part("file", file);
+        let (upload_url, form) = match item_id {
+            Some(item_id) => (format!("{}/items/{}/update", content_url, item_id), form),
+            None => (format!("{}/addItem", content_url), form.text("type", item_type)),
+        };
+        let response: Value = self.client.post(&upload_url).multipart(form).send().await?.error_for_status()?.json().await?;
+        if let Some(error) = arcgis_error(&response) {
+            return Err(Error::ExternalService(format!("ArcGIS Online rejected the upload: {}", error)));
+        }
+        let uploaded = response
+            .get("id")
+            .and_then(Value::as_str)
+            .or(item_id.as_deref())
+            .ok_or_else(|| Error::ExternalService("ArcGIS Online did not return an item id".to_string()))?
+            .to_string();
+        if !publish {
+            return Ok(format!("Uploaded item {}", uploaded));
+        }
+
+        let file_type = arcgis_file_type(export.format)
+            .ok_or_else(|| Error::Validation(format!("{} items cannot be published as a hosted layer", item_type)))?;
+        let parameters = json!({ "name": service_name.clone().unwrap_or(title) }).to_string();
+        let overwrite = if item_id.is_some() { "true" } else { "false" };
+        let response: Value = self
+            .client
+            .post(format!("{}/publish", content_url))
+            .form(&[
+                ("f", "json"),
+                ("token", token.as_str()),
+                ("itemId", uploaded.as_str()),
+                ("filetype", file_type),
+                ("overwrite", overwrite),
+                ("publishParameters", parameters.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let service = response.get("services").and_then(|services| services.get(0));
+        if let Some(error) = arcgis_error(&response).or_else(|| service.and_then(arcgis_error)) {
+            return Err(Error::ExternalService(format!("ArcGIS Online could not publish item {}: {}", uploaded, error)));
+        }
+        let layer = service
+            .and_then(|service| service.get("serviceItemId"))
+            .and_then(Value::as_str)
+            .unwrap_or("unknown");
+        Ok(format!("Uploaded item {} and published hosted layer {}", uploaded, layer))
+    }
+}
+
+/// Datastore file upload that creates or overwrites the store and its layer
+fn geoserver_upload_url(url: &str, workspace: &str, datastore: &str, extension: &str) -> String {
+    format!(
+        "{}/rest/workspaces/{}/datastores/{}/file.{}?configure=all&update=overwrite",
+        url.trim_end_matches('/'),
+        workspace,
+        datastore,
+        extension
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets() -> Vec<PublishingTarget> {
+        PublishingTarget::parse_all(&[
+            json!({ "type": "geoserver", "url": "https://gis.example.gov/geoserver/", "workspace": "assessor",
+                    "datastore": "parcels", "username": "publisher", "password_env": "GEOSERVER_PASSWORD",
+                    "layers": ["parcels"] }),
+            json!({ "type": "arcgis_online", "username": "county_gis", "token_env": "AGOL_TOKEN",
+                    "layers": ["roads", "parcels"], "formats": ["geojson"], "publish": true }),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_targets() {
+        let targets = targets();
+        assert_eq!(targets[0].name(), "geoserver:https://gis.example.gov/geoserver/assessor:parcels");
+        assert_eq!(targets[1].name(), "arcgis:https://www.arcgis.com/county_gis");
+
+        // KML can be uploaded to ArcGIS Online but not published as a hosted layer
+        let kml = |publish: bool| {
+            json!({ "type": "arcgis_online", "username": "u", "token_env": "T", "layers": ["parcels"],
+                    "formats": ["kml"], "publish": publish })
+        };
+        assert!(PublishingTarget::parse_all(&[kml(false)]).is_ok());
+        assert!(PublishingTarget::parse_all(&[kml(true)]).is_err());
+        assert!(PublishingTarget::parse_all(&[json!({ "type": "geoserver", "url": "x", "workspace": "w",
+            "datastore": "d", "username": "u", "password_env": "P", "layers": ["parcels"], "formats": ["csv"] })])
+        .is_err());
+        assert!(PublishingTarget::parse_all(&[json!({ "type": "mapserver" })]).is_err());
+    }
+
+    #[test]
+    fn test_accepts() {
+        let targets = targets();
+        let layers = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        assert!(targets[0].accepts(&layers(&["parcels"]), &ExportFormat::Shapefile));
+        assert!(!targets[0].accepts(&layers(&["parcels"]), &ExportFormat::Csv));
+        assert!(!targets[0].accepts(&layers(&["parcels", "roads"]), &ExportFormat::Shapefile));
+        assert!(targets[1].accepts(&layers(&["parcels", "roads"]), &ExportFormat::Geojson));
+    }
+
+    #[test]
+    fn test_geoserver_upload_url() {
+        assert_eq!(
+            geoserver_upload_url("https://gis.example.gov/geoserver/", "assessor", "parcels", "shp"),
+            "https://gis.example.gov/geoserver/rest/workspaces/assessor/datastores/parcels/file.shp?configure=all&update=overwrite"
+        );
+    }
+
+    #[test]
+    fn test_arcgis_error() {
+        assert_eq!(arcgis_error(&json!({ "success": true, "id": "abc" })), None);
+        assert_eq!(
+            arcgis_error(&json!({ "error": { "code": 498, "message": "Invalid token.", "details": [] } })).as_deref(),
+            Some("Invalid token.")
+        );
+        assert_eq!(
+            arcgis_error(&json!({ "error": { "message": "Item does not exist", "details": ["id abc"] } })).as_deref(),
+            Some("Item does not exist (id abc)")
+        );
+    }
+}
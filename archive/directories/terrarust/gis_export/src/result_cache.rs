@@ -0,0 +1,112 @@
+//! Reuse of identical export results
+//!
+//! An export's output depends on its county, layers, area of interest,
+//! format and parameters, and on the data behind the layers. The first
+//! five make up the request fingerprint; the data version is each layer's
+//! dataset version plus the county's redaction policy. A job
+//! whose fingerprint matches an export completed within the freshness
+//! window serves that export's file instead of generating its own.
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use terrafusion_common::geo::normalize_geometry;
+use terrafusion_common::usage;
+use terrafusion_common::Result;
+
+/// Parameters that send the file somewhere. Jobs with them are never served
+/// from the cache, so every such job still does its own pushes and mail.
+const SIDE_EFFECT_PARAMETERS: &[&str] = &["publish", "deliver_to", "email_to"];
+
+/// Parameters that do not change the file
+const IGNORED_PARAMETERS: &[&str] = &[usage::OVERRIDE_QUOTA_PARAMETER, "reuse_results"];
+
+/// Whether a job may be answered with an earlier export's file. Jobs opt
+/// out with `"reuse_results": false`.
+pub fn reusable(parameters: Option<&Value>) -> bool {
+    let Some(parameters) = parameters.and_then(Value::as_object) else {
+        return true;
+    };
+    if parameters.get("reuse_results").and_then(Value::as_bool) == Some(false) {
+        return false;
+    }
+    !SIDE_EFFECT_PARAMETERS
+        .iter()
+        .any(|name| parameters.get(*name).is_some_and(|value| !value.is_null() && value != &Value::Bool(false)))
+}
+
+/// Hex SHA-256 of everything the export's output depends on. Layer order
+/// and the encoding of the area of interest (GeoJSON, WKT, WKB) do not
+/// change it.
+pub fn fingerprint(
+    county_id: &str,
+    layers: &[String],
+    area_of_interest: &Value,
+    export_format: &str,
+    parameters: Option<&Value>,
+    data_version: &str,
+) -> Result<String> {
+    let mut layers = layers.to_vec();
+    layers.sort();
+    layers.dedup();
+
+    let parameters = match parameters.and_then(Value::as_object) {
+        Some(parameters) => Value::Object(
+            parameters
+                .iter()
+                .filter(|(name, _)| !IGNORED_PARAMETERS.contains(&name.as_str()))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        ),
+        None => json!({}),
+    };
+
+    // serde_json maps are sorted, so equal requests serialize identically
+    let canonical = json!({
+        "county_id": county_id,
+        "layers": layers,
+        "area_of_interest": normalize_geometry(area_of_interest)?,
+        "export_format": export_format.to_lowercase(),
+        "parameters": parameters,
+        "data_version": data_version,
+    });
+
+    let digest = Sha256::digest(canonical.to_string().as_bytes());
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aoi() -> Value {
+        json!({"type": "Polygon", "coordinates": [[[-119.3, 46.2], [-119.2, 46.2], [-119.2, 46.3], [-119.3, 46.2]]]})
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_order_encoding_and_quota_override() {
+        let layers = vec!["parcels".to_string(), "roads".to_string()];
+        let reversed = vec!["roads".to_string(), "parcels".to_string()];
+        let wkt = json!("POLYGON((-119.3 46.2, -119.2 46.2, -119.2 46.3, -119.3 46.2))");
+        let parameters = json!({"compression": "zip"});
+        let overridden = json!({"compression": "zip", "override_quota": true});
+
+        let a = fingerprint("benton", &layers, &aoi(), "geojson", Some(&parameters), "parcels@1").unwrap();
+        let b = fingerprint("benton", &reversed, &wkt, "GeoJSON", Some(&overridden), "parcels@1").unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 64);
+
+        let newer_data = fingerprint("benton", &layers, &aoi(), "geojson", Some(&parameters), "parcels@2").unwrap();
+        assert_ne!(a, newer_data);
+        let csv = fingerprint("benton", &layers, &aoi(), "csv", Some(&parameters), "parcels@1").unwrap();
+        assert_ne!(a, csv);
+    }
+
+    #[test]
+    fn test_jobs_with_side_effects_are_not_reused() {
+        assert!(reusable(None));
+        assert!(reusable(Some(&json!({"compression": "zip", "publish": false}))));
+        assert!(!reusable(Some(&json!({"publish": true}))));
+        assert!(!reusable(Some(&json!({"email_to": ["gis@example.org"]}))));
+        assert!(!reusable(Some(&json!({"reuse_results": false}))));
+    }
+}
@@ -0,0 +1,282 @@
+//! Malware scanning of finished exports before they are offered for download
+//!
+//! EXPORT_SCANNER selects the hook:
+//! - `none` (default): exports are not scanned
+//! - `command`: EXPORT_SCAN_COMMAND runs with the file path in place of a
+//!   `{path}` argument (appended when there is none). Exit status 0 means
+//!   clean and 1 infected, as with `clamscan` and `clamdscan`; anything else
+//!   is a scanner failure. Arguments are split on whitespace, without quoting.
+//! - `icap`: the file is sent to the RESPMOD service at EXPORT_SCAN_ICAP_URL,
+//!   e.g. `icap://av.county.local:1344/avscan`. A 204 means clean; a 200 (the
+//!   server replaced the content) or an infection header means infected.
+//!
+//! A scan that fails or exceeds EXPORT_SCAN_TIMEOUT_SECONDS fails the export.
+
+use std::path::Path;
+use std::time::Duration;
+use terrafusion_common::{Error, Result};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+const DEFAULT_ICAP_PORT: u16 = 1344;
+
+/// Result of scanning one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    /// Infected, with the threat name when the scanner reported one
+    Infected(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ScanBackend {
+    Disabled,
+    Command { program: String, args: Vec<String> },
+    Icap { host: String, port: u16, uri: String },
+}
+
+/// The configured scanning hook
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    backend: ScanBackend,
+    timeout: Duration,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self {
+            backend: ScanBackend::Disabled,
+            timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+impl Scanner {
+    pub fn from_env() -> Result<Self> {
+        let timeout = std::env::var("EXPORT_SCAN_TIMEOUT_SECONDS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse::<u64>()
+            .map_err(|_| Error::Config("Invalid EXPORT_SCAN_TIMEOUT_SECONDS value".to_string()))?;
+        let required = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .ok_or_else(|| Error::Config(format!("{} is required by EXPORT_SCANNER", name)))
+        };
+
+        let backend = match std::env::var("EXPORT_SCANNER").unwrap_or_else(|_| "none".to_string()).to_lowercase().as_str() {
+            "none" => ScanBackend::Disabled,
+            "command" => ScanBackend::command(&required("EXPORT_SCAN_COMMAND")?)?,
+            "icap" => ScanBackend::icap(&required("EXPORT_SCAN_ICAP_URL")?)?,
+            other => return Err(Error::Config(format!("Unknown export scanner: {} (use none, command or icap)", other))),
+        };
+        if backend != ScanBackend::Disabled {
+            log::info!("Export files are scanned with {}", backend.name());
+        }
+
+        Ok(Self {
+            backend,
+            timeout: Duration::from_secs(timeout.max(1)),
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.backend != ScanBackend::Disabled
+    }
+
+    /// Scanner description recorded in export manifests
+    pub fn name(&self) -> String {
+        self.backend.name()
+    }
+
+    /// Scan a file; clean when scanning is disabled
+    pub async fn scan(&self, path: &Path) -> Result<ScanVerdict> {
+        let scan = async {
+            match &self.backend {
+                ScanBackend::Disabled => Ok(ScanVerdict::Clean),
+                ScanBackend::Command { program, args } => scan_command(program, args, path).await,
+                ScanBackend::Icap { host, port, uri } => scan_icap(host, *port, uri, path).await,
+            }
+        };
+        tokio::time::timeout(self.timeout, scan)
+            .await
+            .map_err(|_| Error::ExternalService(format!("Virus scan timed out after {}s", self.timeout.as_secs())))?
+    }
+}
+
+impl ScanBackend {
+    fn command(command: &str) -> Result<Self> {
+        let mut parts = command.split_whitespace().map(str::to_string);
+        let program = parts
+            .next()
+            .ok_or_else(|| Error::Config("EXPORT_SCAN_COMMAND is empty".to_string()))?;
+        Ok(ScanBackend::Command { program, args: parts.collect() })
+    }
+
+    fn icap(url: &str) -> Result<Self> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| Error::Config(format!("Invalid EXPORT_SCAN_ICAP_URL: {}", e)))?;
+        if parsed.scheme() != "icap" {
+            return Err(Error::Config("EXPORT_SCAN_ICAP_URL must be an icap:// URL".to_string()));
+        }
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| Error::Config("EXPORT_SCAN_ICAP_URL has no host".to_string()))?
+            .to_string();
+        Ok(ScanBackend::Icap {
+            host,
+            port: parsed.port().unwrap_or(DEFAULT_ICAP_PORT),
+            uri: url.to_string(),
+        })
+    }
+
+    fn name(&self) -> String {
+        match self {
+            ScanBackend::Disabled => "none".to_string(),
+            ScanBackend::Command { program, .. } => format!("command:{}", program),
+            ScanBackend::Icap { uri, .. } => uri.clone(),
+        }
+    }
+}
+
+async fn scan_command(program: &str, args: &[String], path: &Path) -> Result<ScanVerdict> {
+    let path = path.to_string_lossy();
+    let mut args: Vec<String> = args.iter().map(|arg| arg.replace("{path}", &path)).collect();
+    if !args.iter().any(|arg| arg.contains(path.as_ref())) {
+        args.push(path.to_string());
+    }
+
+    let output = tokio::process::Command::new(program)
+        .args(&args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| Error::ExternalService(format!("Failed to run virus scanner {}: {}", program, e)))?;
+
+    match output.status.code() {
+        Some(0) => Ok(ScanVerdict::Clean),
+        Some(1) => Ok(ScanVerdict::Infected(command_threat(&String::from_utf8_lossy(&output.stdout)))),
+        _ => Err(Error::ExternalService(format!(
+            "Virus scanner {} failed ({}): {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+/// Threat name from ClamAV-style output (`/path: Eicar-Signature FOUND`)
+fn command_threat(stdout: &str) -> String {
+    stdout
+        .lines()
+        .find_map(|line| line.strip_suffix(" FOUND"))
+        .and_then(|line| line.rsplit(": ").next())
+        .map(str::to_string)
+        .unwrap_or_else(|| "unknown threat".to_string())
+}
+
+async fn scan_icap(host: &str, port: u16, uri: &str, path: &Path) -> Result<ScanVerdict> {
+    let mut stream = tokio::net::TcpStream::connect((host, port))
+        .await
+        .map_err(|e| Error::ExternalService(format!("Failed to reach ICAP server {}: {}", uri, e)))?;
+
+    // The file goes out as the body of an encapsulated HTTP response
+    let http_head = "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\n\r\n";
+    let request = format!(
+        "RESPMOD {} ICAP/1.0\r\nHost: {}\r\nAllow: 204\r\nEncapsulated: res-hdr=0, res-body={}\r\n\r\n{}",
+        uri,
+        host,
+        http_head.len(),
+        http_head
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        stream.write_all(format!("{:x}\r\n", read).as_bytes()).await?;
+        stream.write_all(&buffer[..read]).await?;
+        stream.write_all(b"\r\n").await?;
+    }
+    stream.write_all(b"0\r\n\r\n").await?;
+    stream.flush().await?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line).await?;
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+        headers.push(line.trim_end().to_string());
+    }
+
+    icap_verdict(&status_line, &headers)
+}
+
+/// Interpret the status line and headers of an ICAP response
+fn icap_verdict(status_line: &str, headers: &[String]) -> Result<ScanVerdict> {
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| Error::ExternalService(format!("Invalid ICAP response: {}", status_line.trim())))?;
+
+    let infection = headers.iter().find_map(|header| {
+        let (name, value) = header.split_once(':')?;
+        let name = name.trim().to_lowercase();
+        (name == "x-infection-found" || name == "x-violations-found" || name == "x-virus-id").then(|| value.trim().to_string())
+    });
+    if let Some(found) = infection {
+        let threat = found
+            .split(';')
+            .find_map(|part| part.trim().strip_prefix("Threat="))
+            .unwrap_or(&found)
+            .to_string();
+        return Ok(ScanVerdict::Infected(threat));
+    }
+
+    match status {
+        204 => Ok(ScanVerdict::Clean),
+        200 => Ok(ScanVerdict::Infected("blocked by ICAP server".to_string())),
+        _ => Err(Error::ExternalService(format!("ICAP server returned {}", status_line.trim()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_settings() {
+        assert_eq!(
+            ScanBackend::command("clamdscan --no-summary {path}").unwrap(),
+            ScanBackend::Command {
+                program: "clamdscan".to_string(),
+                args: vec!["--no-summary".to_string(), "{path}".to_string()],
+            }
+        );
+        match ScanBackend::icap("icap://av.benton.local/avscan").unwrap() {
+            ScanBackend::Icap { host, port, .. } => assert_eq!((host.as_str(), port), ("av.benton.local", 1344)),
+            other => panic!("unexpected backend {:?}", other),
+        }
+        assert!(ScanBackend::icap("http://av.benton.local/avscan").is_err());
+    }
+
+    #[test]
+    fn test_verdicts() {
+        assert_eq!(command_threat("/exports/a.csv: Eicar-Signature FOUND\n"), "Eicar-Signature");
+
+        assert_eq!(icap_verdict("ICAP/1.0 204 No Content\r\n", &[]).unwrap(), ScanVerdict::Clean);
+        let headers = vec!["X-Infection-Found: Type=0; Resolution=2; Threat=Eicar-Test;".to_string()];
+        assert_eq!(
+            icap_verdict("ICAP/1.0 200 OK\r\n", &headers).unwrap(),
+            ScanVerdict::Infected("Eicar-Test".to_string())
+        );
+        assert!(icap_verdict("ICAP/1.0 500 Server Error\r\n", &[]).is_err());
+    }
+}
@@ -1,17 +1,70 @@
 use crate::models::*;
 use crate::{ExportFormat, GisExportConfig};
-use sqlx::{PgPool, Row};
+use crate::compare::{self, ChangeSummary, ReportFormat};
+use crate::compression::Compression;
+use crate::delivery::{self, Delivery, DeliveryDestination};
+use crate::file_encryption::{self, ExportCipher};
+use crate::geodata::{self, GeoUpload, ReadOptions, UploadFormat};
+use crate::layer_stats::{self, AttributeRow, AttributeStats, LayerStats, ValueCount, ValueCountRow};
+use crate::manifest::{self, ExportManifest, ManifestFile, ManifestScan, MANIFEST_SUFFIX};
+use crate::publishing::{ExportFile, Publication, Publisher, PublishingTarget};
+use crate::result_cache;
+use crate::scanning::{ScanVerdict, Scanner};
+use crate::tiles::{self, TileCache, TileCoord};
+use sqlx::PgPool;
 use uuid::Uuid;
 use chrono::Utc;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 use std::collections::HashMap;
 use anyhow::{Result, anyhow};
+use geo::Intersects;
+use terrafusion_common::datasets::{DatasetQueries, VersionSource};
+use terrafusion_common::encryption::{Envelope, Kek};
+use terrafusion_common::error::Error as CountyConfigError;
+use terrafusion_common::geo::{parse_geometry, Bbox, Boundary, FeatureWriter, Measurement, ParsedGeometry};
+use terrafusion_common::idempotency::{self, Claim, StoredResponse};
+use terrafusion_common::masking::Masker;
+use terrafusion_common::notifications::{DirectEmail, EmailAttachment, Notification, Notifier};
+use terrafusion_common::models::SortParams;
+use terrafusion_common::pagination::{Cursor, Page, Pagination};
+use terrafusion_common::temporal::{self, TemporalQueries};
+use terrafusion_common::usage::{self, UsageMetric, UsageQueries};
+use terrafusion_common::utils::county_config;
+
+/// Sortable export job fields; the first is the default
+const JOB_SORT_FIELDS: &[(&str, &str)] = &[
+    ("created_at", "created_at"),
+    ("completed_at", "completed_at"),
+    ("status", "status"),
+    ("file_size", "file_size"),
+    ("county_id", "county_id"),
+];
+
+/// Columns of a published export, joined with its job
+const PUBLISHED_EXPORT_SELECT: &str = r#"
+    SELECT
+        p.id, p.county_id, p.title, p.description, j.export_format, j.layers, j.file_size,
+        j.checksum_sha256, j.completed_at, p.published_at, p.download_count, j.area_of_interest
+    FROM published_exports p
+    JOIN gis_export_jobs j ON j.job_id = p.job_id
+"#;
+
+/// Uploaded features inserted per statement
+const FEATURE_INSERT_CHUNK: usize = 1000;
+
+/// Features outside the county boundary listed when an upload is refused
+const MAX_REPORTED_OUTSIDE: usize = 5;
 
 /// High-performance GIS Export Service
 pub struct GisExportService {
     config: GisExportConfig,
     db_pool: PgPool,
+    notifier: Option<Notifier>,
+    file_cipher: Option<ExportCipher>,
+    scanner: Scanner,
+    publisher: Publisher,
+    tile_cache: TileCache,
 }
 
 impl GisExportService {
@@ -28,20 +81,99 @@ impl GisExportService {
         Ok(Self {
             config,
             db_pool,
+            notifier: None,
+            file_cipher: None,
+            scanner: Scanner::default(),
+            publisher: Publisher::default(),
+            tile_cache: TileCache::default(),
         })
     }
 
+    /// Announce completed exports through the notification rules
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Encrypt finished export files at rest
+    pub fn with_encryption(mut self, cipher: ExportCipher) -> Self {
+        self.file_cipher = Some(cipher);
+        self
+    }
+
+    /// Scan finished exports before they become downloadable
+    pub fn with_scanner(mut self, scanner: Scanner) -> Self {
+        self.scanner = scanner;
+        self
+    }
+
+    /// Key for decrypting stored export files
+    pub async fn export_key(&self) -> Result<Kek> {
+        let cipher = self
+            .file_cipher
+            .as_ref()
+            .ok_or_else(|| anyhow!("Export file is encrypted but EXPORT_ENCRYPTION_ENABLED is off"))?;
+        Ok(cipher.key().await?)
+    }
+
+    /// The service's database pool, shared with the job queue
+    pub fn db_pool(&self) -> PgPool {
+        self.db_pool.clone()
+    }
     /// Create a new export job
     pub async fn create_job(&self, request: CreateJobRequest) -> Result<CreateJobResponse> {
         // Validate export format
         let export_format: ExportFormat = request.export_format.parse()
-            .map_err(|e| anyhow!("Invalid export format: {}", e))?;
+            .map_err(|e| terrafusion_common::Error::Validation(format!("Invalid export format: {}", e)))?;
+
+        // Validate compression so bad values fail now rather than after the export runs
+        let parameters_value = request.parameters.as_ref().map(|p| serde_json::json!(p));
+        Compression::from_parameters(parameters_value.as_ref(), &export_format)
+            .map_err(|e| terrafusion_common::Error::Validation(format!("Invalid compression: {}", e)))?;
+        delivery::requested_destinations(parameters_value.as_ref()).map_err(terrafusion_common::Error::Validation)?;
+        delivery::requested_recipients(parameters_value.as_ref()).map_err(terrafusion_common::Error::Validation)?;
+        temporal::as_of_parameter(parameters_value.as_ref())?;
 
         // Validate layers
         if request.layers.is_empty() {
-            return Err(anyhow!("At least one layer must be specified"));
+            return Err(terrafusion_common::Error::Validation("At least one layer must be specified".to_string()).into());
+        }
+
+        // WKT and WKB areas of interest are stored as GeoJSON like the rest
+        let area_of_interest = match &request.area_of_interest {
+            serde_json::Value::String(_) => parse_area_of_interest(&request.area_of_interest)?.to_geojson(),
+            other => other.clone(),
+        };
+
+        if let Some(max_km2) = self.config.max_aoi_km2 {
+            let parsed = parse_area_of_interest(&area_of_interest)?;
+            let area_km2 = Measurement::Geodesic.area_km2(&parsed.geometry);
+            if area_km2 > max_km2 {
+                return Err(terrafusion_common::Error::Validation(format!(
+                    "Area of interest is {:.1} km², above the {:.1} km² limit",
+                    area_km2, max_km2
+                ))
+                .into());
+            }
+        }
+
+        self.check_aoi_in_county(&request.county_id, &area_of_interest).await?;
+
+        if usage::quota_overridden(parameters_value.as_ref()) {
+            log::warn!("Export for county {} by {} overrides county quotas", request.county_id, request.username);
+        } else {
+            self.check_quota(&request.county_id).await?;
         }
 
+        let approval_reasons = self
+            .approval_reasons(&request.county_id, &area_of_interest, &request.layers)
+            .await?;
+        let (status, message) = if approval_reasons.is_empty() {
+            ("PENDING", "Export job created and queued for processing".to_string())
+        } else {
+            ("PENDING_APPROVAL", format!("Awaiting approval: {}", approval_reasons.join("; ")))
+        };
+
         // Generate unique job ID
         let job_id = Uuid::new_v4();
         let now = Utc::now();
@@ -51,6 +183,7 @@ impl GisExportService {
         let parameters_json = request.parameters.map(|p| serde_json::to_value(p)).transpose()?;
 
         // Insert job into database
+        let mut tx = self.db_pool.begin().await?;
         let job = sqlx::query_as::<_, GisExportJob>(
             r#"
             INSERT INTO gis_export_jobs (
@@ -64,20 +197,70 @@ impl GisExportService {
         .bind(&request.county_id)
         .bind(&request.username)
         .bind(export_format.as_str())
-        .bind(&request.area_of_interest)
+        .bind(&area_of_interest)
         .bind(layers_json)
         .bind(parameters_json)
-        .bind("PENDING")
-        .bind("Export job created and queued for processing")
+        .bind(status)
+        .bind(&message)
         .bind(now)
-        .fetch_one(&self.db_pool)
+        .fetch_one(&mut tx)
         .await?;
 
+        if !approval_reasons.is_empty() {
+            audit_job(
+                &mut tx,
+                &job,
+                "export_approval_requested",
+                &job.username,
+                &message,
+                None,
+                serde_json::json!({ "status": status, "reasons": approval_reasons }),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
         log::info!("Created GIS export job {} for county {}", job_id, request.county_id);
+        if !approval_reasons.is_empty() {
+            log::info!("GIS export job {} awaits approval: {}", job_id, approval_reasons.join("; "));
+            if let Some(notifier) = &self.notifier {
+                notifier.notify(Notification::approval_requested(&job.county_id, job_id, &job.username, &approval_reasons));
+            }
+        }
         
         Ok(job.into())
     }
 
+    /// Create a job at most once per idempotency key.
+    ///
+    /// Returns the response to send and whether it was replayed from an
+    /// earlier request with the same key.
+    pub async fn create_job_once(&self, request: CreateJobRequest, key: &str) -> Result<(StoredResponse, bool)> {
+        let scope = format!("{}:POST /jobs", request.county_id);
+        let body = serde_json::to_value(&request)?;
+
+        let claimed = match idempotency::claim(&self.db_pool, &scope, key, &body, idempotency::DEFAULT_TTL).await? {
+            Claim::Replay(stored) => return Ok((stored, true)),
+            Claim::New(claimed) => claimed,
+        };
+
+        match self.create_job(request).await {
+            Ok(response) => {
+                let stored = StoredResponse { status_code: 201, body: serde_json::to_value(&response)? };
+                if let Err(e) = claimed.complete(&self.db_pool, stored.status_code, &stored.body).await {
+                    log::error!("Failed to store idempotent response for key {}: {}", key, e);
+                }
+                Ok((stored, false))
+            }
+            Err(e) => {
+                if let Err(release_err) = claimed.release(&self.db_pool).await {
+                    log::error!("Failed to release idempotency key {}: {}", key, release_err);
+                }
+                Err(e)
+            }
+        }
+    }
+
     /// Get job status by ID
     pub async fn get_job_status(&self, job_id: Uuid) -> Result<JobStatusResponse> {
         let job = sqlx::query_as::<_, GisExportJob>(
@@ -86,69 +269,64 @@ impl GisExportService {
         .bind(job_id)
         .fetch_optional(&self.db_pool)
         .await?
-        .ok_or_else(|| anyhow!("Job not found: {}", job_id))?;
+        .ok_or_else(|| terrafusion_common::Error::NotFound(format!("Job not found: {}", job_id)))?;
 
         Ok(job.into())
     }
 
     /// List jobs with optional filtering
-    pub async fn list_jobs(&self, params: ListJobsParams) -> Result<JobListResponse> {
-        let limit = params.limit.unwrap_or(50).min(1000); // Cap at 1000
-        let offset = params.offset.unwrap_or(0);
+    pub async fn list_jobs(
+        &self,
+        params: ListJobsParams,
+        sort: &SortParams,
+        pagination: Pagination,
+    ) -> Result<Page<JobStatusResponse>> {
+        let order = sort.order_by(JOB_SORT_FIELDS, "job_id")?;
+        order.ensure_cursor_compatible(&pagination)?;
 
-        // Build dynamic query with filters
-        let mut query = "SELECT * FROM gis_export_jobs WHERE 1=1".to_string();
-        let mut bind_count = 0;
-        let mut binds: Vec<Box<dyn sqlx::Encode<'_, sqlx::Postgres> + Send + 'static>> = Vec::new();
 
-        if let Some(county_id) = &params.county_id {
-            bind_count += 1;
-            query.push_str(&format!(" AND county_id = ${}", bind_count));
-            binds.push(Box::new(county_id.clone()));
-        }
+        const FILTERS: &str = r#"
+            WHERE ($1::text IS NULL OR county_id = $1)
+            AND ($2::text IS NULL OR username = $2)
+            AND ($3::text IS NULL OR status = $3)
+        "#;
 
-        if let Some(username) = &params.username {
-            bind_count += 1;
-            query.push_str(&format!(" AND username = ${}", bind_count));
-            binds.push(Box::new(username.clone()));
+        let mut query = format!("SELECT * FROM gis_export_jobs {}", FILTERS);
+        if let Some(condition) = pagination.keyset_condition("created_at", "job_id", 4) {
+            query.push_str(&format!(" AND {}", condition));
         }
+        query.push_str(&format!(
+            " {} LIMIT {} OFFSET {}",
+            order.clause,
+            pagination.fetch_limit(),
+            pagination.offset()
+        ));
 
-        if let Some(status) = &params.status {
-            bind_count += 1;
-            query.push_str(&format!(" AND status = ${}", bind_count));
-            binds.push(Box::new(status.clone()));
+        let mut jobs_query = sqlx::query_as::<_, GisExportJob>(&query)
+            .bind(&params.county_id)
+            .bind(&params.username)
+            .bind(&params.status);
+        if let Some(after) = pagination.after() {
+            jobs_query = jobs_query.bind(after.sort_key).bind(after.id);
         }
+        let jobs = jobs_query.fetch_all(&self.db_pool).await?;
 
-        query.push_str(" ORDER BY created_at DESC");
-        
-        bind_count += 1;
-        query.push_str(&format!(" LIMIT ${}", bind_count));
-        binds.push(Box::new(limit));
-        
-        bind_count += 1;
-        query.push_str(&format!(" OFFSET ${}", bind_count));
-        binds.push(Box::new(offset));
-
-        // Execute query (simplified for now - in production would use proper parameter binding)
-        let jobs = sqlx::query_as::<_, GisExportJob>(&query)
-            .fetch_all(&self.db_pool)
-            .await?;
-
-        // Get total count for pagination
-        let total_query = "SELECT COUNT(*) as count FROM gis_export_jobs WHERE 1=1".to_string();
-        let total: i64 = sqlx::query(&total_query)
-            .fetch_one(&self.db_pool)
-            .await?
-            .get("count");
+        // Totals are only counted for offset pages; cursor pages skip the scan
+        let total = if pagination.is_cursor() {
+            None
+        } else {
+            let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM gis_export_jobs {}", FILTERS))
+                .bind(&params.county_id)
+                .bind(&params.username)
+                .bind(&params.status)
+                .fetch_one(&self.db_pool)
+                .await?;
+            Some(total)
+        };
 
         let job_responses: Vec<JobStatusResponse> = jobs.into_iter().map(|job| job.into()).collect();
 
-        Ok(JobListResponse {
-            jobs: job_responses,
-            total,
-            limit,
-            offset,
-        })
+        Ok(pagination.into_page(job_responses, total, |job| Cursor::new(job.created_at, job.job_id)))
     }
 
     /// Process an export job
@@ -160,11 +338,27 @@ impl GisExportService {
         .bind(job_id)
         .fetch_optional(&self.db_pool)
         .await?
-        .ok_or_else(|| anyhow!("Job not found: {}", job_id))?;
+        .ok_or_else(|| terrafusion_common::Error::NotFound(format!("Job not found: {}", job_id)))?;
 
         // Validate job can be processed
         if job.status != "PENDING" {
-            return Err(anyhow!("Job {} is not in PENDING status", job_id));
+            return Err(terrafusion_common::Error::Conflict(format!("Job {} is not in PENDING status", job_id)).into());
+        }
+
+        let layers: Vec<String> = serde_json::from_value(job.layers.clone())?;
+        let as_of = temporal::as_of_parameter(job.parameters.as_ref())?;
+        let data_versions = DatasetQueries::current(&self.db_pool, &job.county_id, &layers, as_of).await?;
+        let fingerprint = self.result_fingerprint(&job, &layers, &data_versions).await?;
+        sqlx::query("UPDATE gis_export_jobs SET result_fingerprint = $1, data_versions = $2 WHERE job_id = $3")
+            .bind(&fingerprint)
+            .bind(serde_json::to_value(&data_versions)?)
+            .bind(job_id)
+            .execute(&self.db_pool)
+            .await?;
+        if result_cache::reusable(job.parameters.as_ref()) {
+            if let Some(cached) = self.cached_result(&fingerprint).await? {
+                return self.complete_from_cache(&job, &cached).await;
+            }
         }
 
         // Update job to PROCESSING
@@ -179,10 +373,13 @@ impl GisExportService {
         .await?;
 
         log::info!("Processing GIS export job {}", job_id);
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(Notification::export_started(&job.county_id, job_id, &job.export_format));
+        }
 
         // Process the export
         match self.generate_export(&job).await {
-            Ok((file_path, file_size)) => {
+            Ok((file_path, file_size, manifest, publications, deliveries)) => {
                 // Update job as completed
                 let download_url = format!("/api/v1/gis-export/download/{}", job_id);
                 
@@ -190,8 +387,9 @@ impl GisExportService {
                     r#"
                     UPDATE gis_export_jobs 
                     SET status = $1, completed_at = $2, message = $3, file_path = $4, 
-                        file_size = $5, download_url = $6 
-                    WHERE job_id = $7
+                        file_size = $5, download_url = $6, checksum_sha256 = $7, manifest = $8,
+                        publications = $9, deliveries = $10
+                    WHERE job_id = $11
                     "#
                 )
                 .bind("COMPLETED")
@@ -199,12 +397,36 @@ impl GisExportService {
                 .bind("Export completed successfully")
                 .bind(file_path.to_string_lossy().to_string())
                 .bind(file_size as i64)
-                .bind(download_url)
+                .bind(&download_url)
+                .bind(&manifest.files[0].sha256)
+                .bind(serde_json::to_value(&manifest)?)
+                .bind((!publications.is_empty()).then(|| serde_json::to_value(&publications)).transpose()?)
+                .bind((!deliveries.is_empty()).then(|| serde_json::to_value(&deliveries)).transpose()?)
                 .bind(job_id)
                 .execute(&self.db_pool)
                 .await?;
 
                 log::info!("Completed GIS export job {}", job_id);
+                self.record_usage(&job, file_size, manifest.feature_count.unwrap_or(0)).await;
+
+                if let Some(notifier) = &self.notifier {
+                    notifier.notify(Notification::export_completed(
+                        &job.county_id,
+                        job_id,
+                        &job.export_format,
+                        Some(&download_url),
+                    ));
+                }
+
+                if let Some(delivery) = self.email_export(&job, &file_path, file_size, &download_url).await {
+                    sqlx::query(
+                        "UPDATE gis_export_jobs SET deliveries = COALESCE(deliveries, '[]'::jsonb) || $1 WHERE job_id = $2"
+                    )
+                    .bind(serde_json::to_value(vec![delivery])?)
+                    .bind(job_id)
+                    .execute(&self.db_pool)
+                    .await?;
+                }
             }
             Err(e) => {
                 // Update job as failed
@@ -219,6 +441,9 @@ impl GisExportService {
                 .await?;
 
                 log::error!("Failed GIS export job {}: {}", job_id, e);
+                if let Some(notifier) = &self.notifier {
+                    notifier.notify(Notification::export_failed(&job.county_id, job_id, &job.export_format, &e.to_string()));
+                }
                 return Err(e);
             }
         }
@@ -227,6 +452,102 @@ impl GisExportService {
         self.get_job_status(job_id).await
     }
 
+    /// Fingerprint of what the job's output depends on, including the
+    /// current version of the data behind its layers
+    async fn result_fingerprint(
+        &self,
+        job: &GisExportJob,
+        layers: &[String],
+        data_versions: &HashMap<String, i64>,
+    ) -> Result<String> {
+        let mut versions: Vec<String> = data_versions.iter().map(|(layer, version)| format!("{}@{}", layer, version)).collect();
+        versions.sort();
+        let redaction = match county_config::load_county_configuration(&job.county_id).await {
+            Ok(config) => serde_json::to_string(&config.export_redaction)?,
+            Err(CountyConfigError::NotFound(_)) => String::new(),
+            Err(e) => return Err(anyhow!("Failed to load redaction policy for county {}: {}", job.county_id, e)),
+        };
+
+        Ok(result_cache::fingerprint(
+            &job.county_id,
+            layers,
+            &job.area_of_interest,
+            &job.export_format,
+            job.parameters.as_ref(),
+            &format!("{};{}", versions.join(","), redaction),
+        )?)
+    }
+
+    /// The newest export with this fingerprint completed within the
+    /// freshness window whose file is still there
+    async fn cached_result(&self, fingerprint: &str) -> Result<Option<GisExportJob>> {
+        if self.config.result_cache_minutes == 0 {
+            return Ok(None);
+        }
+        let cached = sqlx::query_as::<_, GisExportJob>(
+            r#"
+            SELECT * FROM gis_export_jobs
+            WHERE result_fingerprint = $1 AND status = 'COMPLETED' AND reused_from IS NULL
+              AND completed_at > NOW() - make_interval(mins => $2)
+            ORDER BY completed_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(fingerprint)
+        .bind(self.config.result_cache_minutes as i32)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        match cached {
+            Some(job) if job.file_path.as_ref().is_some_and(|path| Path::new(path).exists()) => Ok(Some(job)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Complete a job with the file of an identical earlier export
+    async fn complete_from_cache(&self, job: &GisExportJob, cached: &GisExportJob) -> Result<JobStatusResponse> {
+        let download_url = format!("/api/v1/gis-export/download/{}", job.job_id);
+        sqlx::query(
+            r#"
+            UPDATE gis_export_jobs
+            SET status = 'COMPLETED', started_at = NOW(), completed_at = NOW(), message = $1,
+                file_path = $2, file_size = $3, download_url = $4, checksum_sha256 = $5,
+                manifest = $6, reused_from = $7
+            WHERE job_id = $8
+            "#,
+        )
+        .bind(format!("Export completed with the result of identical export {}", cached.job_id))
+        .bind(&cached.file_path)
+        .bind(cached.file_size)
+        .bind(&download_url)
+        .bind(&cached.checksum_sha256)
+        .bind(&cached.manifest)
+        .bind(cached.job_id)
+        .bind(job.job_id)
+        .execute(&self.db_pool)
+        .await?;
+
+        log::info!("Completed GIS export job {} with the result of job {}", job.job_id, cached.job_id);
+        let feature_count = cached
+            .manifest
+            .as_ref()
+            .and_then(|manifest| manifest.get("feature_count"))
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0);
+        self.record_usage(job, cached.file_size.unwrap_or(0) as u64, feature_count).await;
+
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(Notification::export_completed(
+                &job.county_id,
+                job.job_id,
+                &job.export_format,
+                Some(&download_url),
+            ));
+        }
+
+        self.get_job_status(job.job_id).await
+    }
+
     /// Cancel a job
     pub async fn cancel_job(&self, job_id: Uuid) -> Result<JobStatusResponse> {
         let job = sqlx::query_as::<_, GisExportJob>(
@@ -235,10 +556,10 @@ impl GisExportService {
         .bind(job_id)
         .fetch_optional(&self.db_pool)
         .await?
-        .ok_or_else(|| anyhow!("Job not found: {}", job_id))?;
+        .ok_or_else(|| terrafusion_common::Error::NotFound(format!("Job not found: {}", job_id)))?;
 
-        if job.status == "COMPLETED" {
-            return Err(anyhow!("Cannot cancel completed job"));
+        if job.status == "COMPLETED" || job.status == "REJECTED" {
+            return Err(terrafusion_common::Error::Conflict(format!("Cannot cancel {} job", job.status.to_lowercase())).into());
         }
 
         sqlx::query(
@@ -256,9 +577,115 @@ impl GisExportService {
         self.get_job_status(job_id).await
     }
 
+    /// Why the county's approval policy holds an export for sign-off; empty
+    /// when it can run right away. A county whose configuration cannot be
+    /// loaded does not get its exports through unreviewed.
+    /// Refuse new exports once the county has used its monthly quota of
+    /// exported features or gigabytes
+    async fn check_quota(&self, county_id: &str) -> Result<()> {
+        let quotas = match county_config::load_county_configuration(county_id).await {
+            Ok(config) => config.usage_quotas,
+            Err(CountyConfigError::NotFound(_)) => return Ok(()),
+            Err(e) => return Err(anyhow!("Failed to load usage quotas for county {}: {}", county_id, e)),
+        };
+        if quotas.max_exported_features.is_none() && quotas.max_exported_gb.is_none() {
+            return Ok(());
+        }
+        let usage = UsageQueries::month_to_date(&self.db_pool, county_id).await?;
+        quotas.check_export(county_id, &usage)?;
+        Ok(())
+    }
+
+    async fn approval_reasons(&self, county_id: &str, area_of_interest: &serde_json::Value, layers: &[String]) -> Result<Vec<String>> {
+        let policy = match county_config::load_county_configuration(county_id).await {
+            Ok(config) => config.export_approval,
+            Err(CountyConfigError::NotFound(_)) => None,
+            Err(e) => return Err(anyhow!("Failed to load export approval policy for county {}: {}", county_id, e)),
+        };
+        let Some(policy) = policy else {
+            return Ok(Vec::new());
+        };
+        let area_km2 = match policy.max_area_km2 {
+            Some(_) => Measurement::Geodesic.area_km2(&parse_area_of_interest(area_of_interest)?.geometry),
+            None => 0.0,
+        };
+        Ok(policy.reasons(area_km2, layers))
+    }
+
+    /// Approve or reject an export waiting for sign-off. An approved export
+    /// goes back to PENDING to be processed; a rejected one is final. The
+    /// requester cannot review their own export.
+    pub async fn review_job(&self, job_id: Uuid, review: ReviewExportRequest, approve: bool) -> Result<JobStatusResponse> {
+        let job = sqlx::query_as::<_, GisExportJob>("SELECT * FROM gis_export_jobs WHERE job_id = $1")
+            .bind(job_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| terrafusion_common::Error::NotFound(format!("Job not found: {}", job_id)))?;
+
+        if review.county_id.as_ref().is_some_and(|county_id| *county_id != job.county_id) {
+            return Err(terrafusion_common::Error::Authorization(format!(
+                "Export {} belongs to another county",
+                job_id
+            ))
+            .into());
+        }
+        if review.reviewed_by == job.username {
+            return Err(terrafusion_common::Error::Authorization(
+                "Exports cannot be reviewed by the user who requested them".to_string(),
+            )
+            .into());
+        }
+        let comment = review.comment.as_deref().map(str::trim).filter(|comment| !comment.is_empty());
+        let (status, message, event_type) = match (approve, comment) {
+            (true, _) => ("PENDING", format!("Approved by {}", review.reviewed_by), "export_approved"),
+            (false, Some(comment)) => ("REJECTED", format!("Rejected by {}: {}", review.reviewed_by, comment), "export_rejected"),
+            (false, None) => {
+                return Err(terrafusion_common::Error::Validation("A comment is required to reject an export".to_string()).into())
+            }
+        };
+
+        let now = Utc::now();
+        let mut tx = self.db_pool.begin().await?;
+        let updated = sqlx::query(
+            r#"
+            UPDATE gis_export_jobs
+            SET status = $1, message = $2, reviewed_by = $3, reviewed_at = $4, review_comment = $5,
+                completed_at = CASE WHEN $1 = 'REJECTED' THEN $4 ELSE completed_at END
+            WHERE job_id = $6 AND status = 'PENDING_APPROVAL'
+            "#
+        )
+        .bind(status)
+        .bind(&message)
+        .bind(&review.reviewed_by)
+        .bind(now)
+        .bind(comment)
+        .bind(job_id)
+        .execute(&mut tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(terrafusion_common::Error::Conflict(format!("Job {} is not awaiting approval", job_id)).into());
+        }
+        audit_job(
+            &mut tx,
+            &job,
+            event_type,
+            &review.reviewed_by,
+            &message,
+            Some(&job.status),
+            serde_json::json!({ "status": status, "comment": comment }),
+        )
+        .await?;
+        tx.commit().await?;
+
+        log::info!("GIS export job {}: {}", job_id, message);
+        self.get_job_status(job_id).await
+    }
+
     /// Generate the actual export file
-    async fn generate_export(&self, job: &GisExportJob) -> Result<(PathBuf, u64)> {
-        let export_format: ExportFormat = job.export_format.parse()?;
+    async fn generate_export(&self, job: &GisExportJob) -> Result<(PathBuf, u64, ExportManifest, Vec<Publication>, Vec<Delivery>)> {
+        let export_format: ExportFormat = job.export_format.parse().map_err(|e: String| anyhow!(e))?;
+        let compression = Compression::from_parameters(job.parameters.as_ref(), &export_format)
+            .map_err(|e| anyhow!(e))?;
         let layers: Vec<String> = serde_json::from_value(job.layers.clone())?;
 
         // Create filename
@@ -270,7 +697,15 @@ impl GisExportService {
         let file_path = self.config.storage_path.join(&filename);
 
         // Query geospatial data from database
-        let features = self.query_features(job, &layers).await?;
+        let mut features = self.query_features(job, &layers).await?;
+        self.redact_features(&job.county_id, &mut features).await?;
+        let bbox = features_bbox(&features);
+
+        // Kept for change reports against later runs of the export
+        let snapshot_path = self.config.storage_path.join(compare::snapshot_file_name(&job.county_id, job.job_id));
+        let snapshot = features.iter().map(geojson_feature).collect::<Result<Vec<_>>>()?;
+        let snapshot_file = snapshot_path.clone();
+        tokio::task::spawn_blocking(move || compare::write_snapshot(&snapshot_file, &snapshot)).await??;
 
         // Generate export based on format
         match export_format {
@@ -291,21 +726,812 @@ impl GisExportService {
             }
         }
 
-        // Get file size
-        let metadata = fs::metadata(&file_path).await?;
-        let file_size = metadata.len();
+        // Compress and checksum off the async runtime; large exports take a while
+        let (file_path, file_size, sha256) = tokio::task::spawn_blocking(move || -> std::io::Result<_> {
+            let file_path = compression.compress_file(&file_path)?;
+            let file_size = std::fs::metadata(&file_path)?.len();
+            let sha256 = manifest::sha256_file(&file_path)?;
+            Ok((file_path, file_size, sha256))
+        })
+        .await??;
+
+        // Nothing is recorded as downloadable until the scanner passes it
+        let scan = self.scan_export(job, &file_path).await?;
+        let file_name = file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("Export path has no file name"))?;
+        let manifest = ExportManifest {
+            job_id: job.job_id,
+            county_id: job.county_id.clone(),
+            export_format: export_format.as_str().to_string(),
+            compression: compression.as_str().to_string(),
+            created_at: Utc::now(),
+            files: vec![ManifestFile { name: file_name, size: file_size, sha256 }],
+            feature_count: Some(features.len() as u64),
+            bbox,
+            scan,
+            signature: None,
+        };
+        let manifest = self.write_manifest(manifest, &file_path).await?;
+
+        // Portals get the plaintext file; encryption protects only our copy
+        let publications = self
+            .push_to_targets(job, ExportFile { path: &file_path, format: &export_format, compression, layers: &layers })
+            .await;
+        let deliveries = self.deliver(job, &[file_path.clone(), manifest_path(&file_path, &manifest)]).await;
+
+        let file_path = match &self.file_cipher {
+            Some(cipher) => {
+                let kek = cipher.key().await?;
+                tokio::task::spawn_blocking(move || -> std::io::Result<PathBuf> {
+                    file_encryption::encrypt_file(&kek, &snapshot_path)?;
+                    file_encryption::encrypt_file(&kek, &file_path)
+                })
+                .await??
+            }
+            None => file_path,
+        };
+
+        Ok((file_path, file_size, manifest, publications, deliveries))
+    }
+
+    /// Run the scanning hook on a finished export. An infected file is
+    /// deleted and fails the export.
+    async fn scan_export(&self, job: &GisExportJob, file_path: &PathBuf) -> Result<Option<ManifestScan>> {
+        if !self.scanner.enabled() {
+            return Ok(None);
+        }
+
+        match self.scanner.scan(file_path).await? {
+            ScanVerdict::Clean => Ok(Some(ManifestScan {
+                scanner: self.scanner.name(),
+                scanned_at: Utc::now(),
+            })),
+            ScanVerdict::Infected(threat) => {
+                if let Err(e) = fs::remove_file(file_path).await {
+                    log::error!("Failed to remove infected export {:?}: {}", file_path, e);
+                }
+                log::warn!("Export job {} for county {} blocked by virus scan: {}", job.job_id, job.county_id, threat);
+                Err(terrafusion_common::Error::Validation(format!("Export blocked by virus scan: {}", threat)).into())
+            }
+        }
+    }
+
+    /// Push an export that asked for it (`"publish": true`) to the county's
+    /// publishing targets. A failed push is recorded and announced but does
+    /// not fail the export, which is still available for download.
+    async fn push_to_targets(&self, job: &GisExportJob, export: ExportFile<'_>) -> Vec<Publication> {
+        let requested = job
+            .parameters
+            .as_ref()
+            .and_then(|parameters| parameters.get("publish"))
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false);
+        if !requested {
+            return Vec::new();
+        }
+
+        let targets = match county_config::load_county_configuration(&job.county_id).await {
+            Ok(config) => PublishingTarget::parse_all(&config.publishing_targets).map_err(|e| e.to_string()),
+            Err(e) => Err(format!("Failed to load publishing targets: {}", e)),
+        };
+        let targets = match targets {
+            Ok(targets) => targets,
+            Err(message) => {
+                let failure = Publication {
+                    target: "county configuration".to_string(),
+                    succeeded: false,
+                    message,
+                    published_at: Utc::now(),
+                };
+                self.report_publication(job, &failure);
+                return vec![failure];
+            }
+        };
+
+        let mut publications = Vec::new();
+        for target in targets.iter().filter(|target| target.accepts(export.layers, export.format)) {
+            let publication = self.publisher.publish(target, &export).await;
+            self.report_publication(job, &publication);
+            publications.push(publication);
+        }
+        if publications.is_empty() {
+            log::warn!(
+                "Export job {} asked to be published, but no target of county {} takes {} exports of {:?}",
+                job.job_id,
+                job.county_id,
+                export.format.as_str(),
+                export.layers
+            );
+        }
+        publications
+    }
+
+    fn report_publication(&self, job: &GisExportJob, publication: &Publication) {
+        if publication.succeeded {
+            log::info!("Published export job {} to {}: {}", job.job_id, publication.target, publication.message);
+            return;
+        }
+        log::error!("Failed to publish export job {} to {}: {}", job.job_id, publication.target, publication.message);
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(Notification::publishing_failed(
+                &job.county_id,
+                job.job_id,
+                &publication.target,
+                &publication.message,
+            ));
+        }
+    }
+
+    /// Upload an export and its manifest to the destinations the job names
+    /// in `deliver_to`
+    async fn deliver(&self, job: &GisExportJob, files: &[PathBuf]) -> Vec<Delivery> {
+        let names = match delivery::requested_destinations(job.parameters.as_ref()) {
+            Ok(names) if names.is_empty() => return Vec::new(),
+            Ok(names) => names,
+            Err(e) => return vec![self.report_delivery(job, Delivery::failed("deliver_to", 0, e))],
+        };
+        let destinations = match county_config::load_county_configuration(&job.county_id).await {
+            Ok(config) => DeliveryDestination::parse_all(&config.delivery_destinations).map_err(|e| e.to_string()),
+            Err(e) => Err(format!("Failed to load delivery destinations: {}", e)),
+        };
+
+        let mut deliveries = Vec::new();
+        for name in &names {
+            let delivery = match &destinations {
+                Ok(destinations) => match destinations.iter().find(|destination| destination.name == *name) {
+                    Some(destination) => destination.deliver(files).await,
+                    None => Delivery::failed(name, 0, format!("County {} has no delivery destination {}", job.county_id, name)),
+                },
+                Err(e) => Delivery::failed(name, 0, e.clone()),
+            };
+            deliveries.push(self.report_delivery(job, delivery));
+        }
+        deliveries
+    }
+
+    /// Charge a completed export to its county and requester, and take a
+    /// snapshot of the county's export storage
+    async fn record_usage(&self, job: &GisExportJob, file_size: u64, feature_count: u64) {
+        let recorded = futures::try_join!(
+            UsageQueries::record(&self.db_pool, &job.county_id, Some(&job.username), UsageMetric::Exports, 1, file_size as i64),
+            UsageQueries::record(
+                &self.db_pool,
+                &job.county_id,
+                Some(&job.username),
+                UsageMetric::ExportedFeatures,
+                feature_count as i64,
+                0,
+            ),
+        );
+        if let Err(e) = recorded {
+            log::warn!("Failed to record usage for export {}: {}", job.job_id, e);
+        }
+
+        let stored: std::result::Result<(i64, i64), sqlx::Error> = sqlx::query_as(
+            "SELECT COUNT(*), COALESCE(SUM(file_size), 0)::BIGINT FROM gis_export_jobs WHERE county_id = $1 AND status = 'COMPLETED'"
+        )
+        .bind(&job.county_id)
+        .fetch_one(&self.db_pool)
+        .await;
+        let recorded = match stored {
+            Ok((files, bytes)) => UsageQueries::record_storage(&self.db_pool, &job.county_id, files, bytes).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = recorded {
+            log::warn!("Failed to record export storage for county {}: {}", job.county_id, e);
+        }
+    }
+
+    /// Email a completed export to the addresses in `email_to`: attached when
+    /// it is small enough, as a download link otherwise
+    async fn email_export(&self, job: &GisExportJob, file_path: &Path, file_size: u64, download_url: &str) -> Option<Delivery> {
+        let recipients = match delivery::requested_recipients(job.parameters.as_ref()) {
+            Ok(recipients) if recipients.is_empty() => return None,
+            Ok(recipients) => recipients,
+            Err(e) => return Some(self.report_delivery(job, Delivery::failed("email_to", 0, e))),
+        };
+        let destination = format!("email:{}", recipients.join(","));
+        let delivery = match self.send_export_email(job, recipients, file_path, file_size, download_url).await {
+            Ok(message) => Delivery {
+                destination,
+                delivered: true,
+                attempts: 1,
+                message,
+                finished_at: Utc::now(),
+            },
+            Err(e) => Delivery::failed(&destination, 1, e.to_string()),
+        };
+        Some(self.report_delivery(job, delivery))
+    }
+
+    async fn send_export_email(
+        &self,
+        job: &GisExportJob,
+        to: Vec<String>,
+        file_path: &Path,
+        file_size: u64,
+        download_url: &str,
+    ) -> Result<String> {
+        let notifier = self
+            .notifier
+            .as_ref()
+            .ok_or_else(|| anyhow!("Emailing exports requires the notifications SMTP settings"))?;
+        let plaintext_path = file_encryption::plaintext_path(file_path);
+        let file_name = plaintext_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("Export path has no file name"))?;
+        let layers: Vec<String> = serde_json::from_value(job.layers.clone()).unwrap_or_default();
+
+        let mut body = format!(
+            "The {} export of {} for county {} requested by {} is complete.\n\n",
+            job.export_format,
+            layers.join(", "),
+            job.county_id,
+            job.username
+        );
+        let attachment = if file_size <= self.config.email_attachment_max_bytes {
+            body.push_str(&format!("It is attached as {}.", file_name));
+            let content_type = Compression::from_path(&plaintext_path)
+                .content_type()
+                .map(|content_type| content_type.to_string())
+                .unwrap_or_else(|| {
+                    let extension = plaintext_path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+                    actix_files::file_extension_to_mime(extension).to_string()
+                });
+            Some(EmailAttachment {
+                file_name: file_name.clone(),
+                content_type,
+                content: self.read_export(file_path).await?,
+            })
+        } else {
+            let base_url = self.config.public_url.as_deref().unwrap_or_default().trim_end_matches('/');
+            body.push_str(&format!(
+                "At {:.1} MB it is too large to attach. Download it from:\n{}{}",
+                file_size as f64 / (1024.0 * 1024.0),
+                base_url,
+                download_url
+            ));
+            None
+        };
+        let attached = attachment.is_some();
+
+        let email = DirectEmail {
+            to,
+            subject: format!("{} export {} ready", job.export_format, job.job_id),
+            body,
+            attachment,
+        };
+        notifier.send_email(Some(&job.county_id), &email).await?;
+        Ok(if attached {
+            format!("Emailed {} as an attachment", file_name)
+        } else {
+            "Emailed the download link".to_string()
+        })
+    }
+
+    /// Contents of a stored export, decrypted if it is encrypted at rest
+    async fn read_export(&self, file_path: &Path) -> Result<Vec<u8>> {
+        if !file_encryption::is_encrypted(file_path) {
+            return Ok(fs::read(file_path).await?);
+        }
+        let kek = self.export_key().await?;
+        let path = file_path.to_path_buf();
+        let content = tokio::task::spawn_blocking(move || -> std::io::Result<Vec<u8>> {
+            let mut content = Vec::new();
+            file_encryption::decrypt_file(&kek, &path, |chunk| {
+                content.extend(chunk);
+                true
+            })?;
+            Ok(content)
+        })
+        .await??;
+        Ok(content)
+    }
+
+    fn report_delivery(&self, job: &GisExportJob, delivery: Delivery) -> Delivery {
+        if delivery.delivered {
+            log::info!("Delivered export job {} to {} as {}", job.job_id, delivery.destination, delivery.message);
+            return delivery;
+        }
+        log::error!(
+            "Failed to deliver export job {} to {} after {} attempt(s): {}",
+            job.job_id,
+            delivery.destination,
+            delivery.attempts,
+            delivery.message
+        );
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(Notification::delivery_failed(
+                &job.county_id,
+                job.job_id,
+                &delivery.destination,
+                delivery.attempts,
+                &delivery.message,
+            ));
+        }
+        delivery
+    }
+
+    /// Sign (when a key is configured) and write the manifest next to the export file
+    async fn write_manifest(&self, mut manifest: ExportManifest, file_path: &PathBuf) -> Result<ExportManifest> {
+        let manifest_path = manifest_path(file_path, &manifest);
+        match &self.config.manifest_signing_key {
+            Some(key) => manifest.sign(key.as_bytes()),
+            None => log::warn!("EXPORT_MANIFEST_SIGNING_KEY is not set; manifest for job {} is unsigned", manifest.job_id),
+        }
+
+        fs::write(&manifest_path, serde_json::to_vec_pretty(&manifest)?).await?;
+
+        Ok(manifest)
+    }
+
+    /// Manifest of a completed export
+    pub async fn get_manifest(&self, job_id: Uuid) -> Result<ExportManifest> {
+        let manifest: Option<serde_json::Value> = sqlx::query_scalar(
+            "SELECT manifest FROM gis_export_jobs WHERE job_id = $1 AND status = 'COMPLETED'"
+        )
+        .bind(job_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .flatten();
+
+        let manifest = manifest.ok_or_else(|| anyhow!("No manifest for job {}", job_id))?;
+        Ok(serde_json::from_value(manifest)?)
+    }
+
+    /// Refuse areas of interest that miss the county's uploaded boundary.
+    /// Counties without one are not checked.
+    async fn check_aoi_in_county(&self, county_id: &str, area_of_interest: &serde_json::Value) -> Result<()> {
+        let geometry = parse_area_of_interest(area_of_interest)?.to_geojson();
+        let intersects: Option<bool> = sqlx::query_scalar(
+            "SELECT ST_Intersects(geom, ST_SetSRID(ST_GeomFromGeoJSON($2), 4326)) FROM county_boundaries WHERE county_id = $1",
+        )
+        .bind(county_id)
+        .bind(geometry.to_string())
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        if intersects == Some(false) {
+            return Err(terrafusion_common::Error::Validation(format!(
+                "Area of interest is outside the boundary of county {}",
+                county_id
+            ))
+            .into());
+        }
+        Ok(())
+    }
 
-        Ok((file_path, file_size))
+    /// Read an uploaded file off the async runtime
+    async fn read_geodata(&self, bytes: Vec<u8>, params: &GeodataUploadParams) -> Result<GeoUpload> {
+        let format = match &params.format {
+            Some(format) => UploadFormat::parse(format)?,
+            None => UploadFormat::detect(&bytes).ok_or_else(|| {
+                terrafusion_common::Error::Validation(
+                    "Cannot tell the upload's format; give it as format".to_string(),
+                )
+            })?,
+        };
+        let options = ReadOptions {
+            source_crs: params.source_crs.clone(),
+            source_layer: params.source_layer.clone(),
+        };
+        let upload = tokio::task::spawn_blocking(move || geodata::read_upload(&bytes, format, &options)).await??;
+        Ok(upload)
+    }
+
+    /// Replace the county's boundary. It is also written to the county
+    /// configuration, where the sync boundary check reads it.
+    pub async fn upload_boundary(&self, county_id: &str, bytes: Vec<u8>, params: GeodataUploadParams) -> Result<GeodataUpload> {
+        let mut config = match county_config::load_county_configuration(county_id).await {
+            Ok(config) => config,
+            Err(CountyConfigError::NotFound(_)) => {
+                return Err(terrafusion_common::Error::NotFound(format!("County {} is not configured", county_id)).into())
+            }
+            Err(e) => return Err(anyhow!("Failed to load configuration for county {}: {}", county_id, e)),
+        };
+
+        let upload = self.read_geodata(bytes, &params).await?;
+        let boundary = geo::Geometry::MultiPolygon(upload.boundary()?);
+        let boundary_geojson = serde_json::to_value(::geojson::Geometry::new(::geojson::Value::from(&boundary)))?;
+
+        let id = Uuid::new_v4();
+        let mut tx = self.db_pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO county_boundaries (county_id, geom, upload_id, updated_at)
+            VALUES ($1, ST_Multi(ST_SetSRID(ST_GeomFromGeoJSON($2), 4326)), $3, NOW())
+            ON CONFLICT (county_id) DO UPDATE
+            SET geom = EXCLUDED.geom, upload_id = EXCLUDED.upload_id, updated_at = NOW()
+            "#,
+        )
+        .bind(county_id)
+        .bind(boundary_geojson.to_string())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        let record = self.record_upload(&mut tx, id, county_id, "boundary", &upload, params).await?;
+        tx.commit().await?;
+        self.tile_cache.invalidate(county_id, tiles::BOUNDARY_LAYER);
+
+        config.boundary = Some(boundary_geojson);
+        county_config::save_county_configuration(&config)
+            .await
+            .map_err(|e| anyhow!("Failed to save the boundary for county {}: {}", county_id, e))?;
+
+        log::info!("Uploaded boundary for county {} from {} ({})", county_id, upload.format.as_str(), upload.source_crs);
+        Ok(record)
+    }
+
+    /// Replace a layer's seed features. Features entirely outside the
+    /// county's boundary, when it has one, refuse the whole upload.
+    pub async fn upload_layer_features(
+        &self,
+        county_id: &str,
+        layer_id: &str,
+        bytes: Vec<u8>,
+        params: GeodataUploadParams,
+    ) -> Result<GeodataUpload> {
+        let config = match county_config::load_county_configuration(county_id).await {
+            Ok(config) => config,
+            Err(CountyConfigError::NotFound(_)) => {
+                return Err(terrafusion_common::Error::NotFound(format!("County {} is not configured", county_id)).into())
+            }
+            Err(e) => return Err(anyhow!("Failed to load configuration for county {}: {}", county_id, e)),
+        };
+        if !config.is_layer_available(layer_id) {
+            return Err(terrafusion_common::Error::Validation(format!(
+                "Layer {} is not configured for county {}",
+                layer_id, county_id
+            ))
+            .into());
+        }
+
+        let upload = self.read_geodata(bytes, &params).await?;
+        if let Some(boundary) = &config.boundary {
+            let boundary = Boundary::from_value(boundary)
+                .map_err(|e| anyhow!("Invalid boundary for county {}: {}", county_id, e))?;
+            let outside = upload.outside(&boundary, MAX_REPORTED_OUTSIDE);
+            if !outside.is_empty() {
+                let details: Vec<String> = outside
+                    .iter()
+                    .map(|(position, reason)| format!("feature {}: {}", position, reason))
+                    .collect();
+                return Err(terrafusion_common::Error::Validation(format!(
+                    "Features are outside the county boundary: {}",
+                    details.join("; ")
+                ))
+                .into());
+            }
+        }
+
+        let id = Uuid::new_v4();
+        let mut tx = self.db_pool.begin().await?;
+        sqlx::query("DELETE FROM layer_features WHERE county_id = $1 AND layer_id = $2")
+            .bind(county_id)
+            .bind(layer_id)
+            .execute(&mut *tx)
+            .await?;
+        for chunk in upload.features.chunks(FEATURE_INSERT_CHUNK) {
+            let properties: Vec<serde_json::Value> = chunk
+                .iter()
+                .map(|feature| serde_json::Value::Object(feature.properties.clone()))
+                .collect();
+            let geometries: Vec<String> = chunk.iter().map(|feature| feature.geometry_geojson().to_string()).collect();
+            sqlx::query(
+                r#"
+                INSERT INTO layer_features (county_id, layer_id, upload_id, properties, geom)
+                SELECT $1, $2, $3, f.properties, ST_SetSRID(ST_GeomFromGeoJSON(f.geometry), 4326)
+                FROM UNNEST($4::jsonb[], $5::text[]) AS f(properties, geometry)
+                "#,
+            )
+            .bind(county_id)
+            .bind(layer_id)
+            .bind(id)
+            .bind(&properties)
+            .bind(&geometries)
+            .execute(&mut *tx)
+            .await?;
+        }
+        let record = self.record_upload(&mut tx, id, county_id, layer_id, &upload, params).await?;
+        tx.commit().await?;
+        self.tile_cache.invalidate(county_id, layer_id);
+        DatasetQueries::bump(
+            &self.db_pool,
+            county_id,
+            layer_id,
+            VersionSource::Upload,
+            None,
+            Some(id),
+            upload.features.len() as i64,
+        )
+        .await?;
+
+        log::info!(
+            "Loaded {} features into layer {} for county {} from {} ({})",
+            upload.features.len(),
+            layer_id,
+            county_id,
+            upload.format.as_str(),
+            upload.source_crs
+        );
+        Ok(record)
+    }
+
+    async fn record_upload(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        id: Uuid,
+        county_id: &str,
+        target: &str,
+        upload: &GeoUpload,
+        params: GeodataUploadParams,
+    ) -> Result<GeodataUpload> {
+        let record = sqlx::query_as::<_, GeodataUpload>(
+            r#"
+            INSERT INTO geodata_uploads
+                (id, county_id, target, format, file_name, source_crs, feature_count, bbox, uploaded_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(county_id)
+        .bind(target)
+        .bind(upload.format.as_str())
+        .bind(params.file_name)
+        .bind(&upload.source_crs)
+        .bind(upload.features.len() as i32)
+        .bind(upload.bbox().map(|bbox| serde_json::json!(bbox)))
+        .bind(params.username.unwrap_or_else(|| "api_user".to_string()))
+        .fetch_one(&mut **tx)
+        .await?;
+        Ok(record)
+    }
+
+    /// The county's boundary and layer uploads, newest first
+    pub async fn list_geodata_uploads(&self, county_id: &str) -> Result<Vec<GeodataUpload>> {
+        let uploads = sqlx::query_as::<_, GeodataUpload>(
+            "SELECT * FROM geodata_uploads WHERE county_id = $1 ORDER BY created_at DESC LIMIT 100",
+        )
+        .bind(county_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+        Ok(uploads)
+    }
+
+    /// Fail with NotFound unless the county is configured with the layer
+    async fn require_layer(&self, county_id: &str, layer_id: &str) -> Result<()> {
+        let configured = match county_config::load_county_configuration(county_id).await {
+            Ok(config) => config.is_layer_available(layer_id),
+            Err(CountyConfigError::NotFound(_)) => false,
+            Err(e) => return Err(anyhow!("Failed to load configuration for county {}: {}", county_id, e)),
+        };
+        if !configured {
+            return Err(terrafusion_common::Error::NotFound(format!(
+                "Layer {} is not configured for county {}",
+                layer_id, county_id
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Feature counts, extent and attribute distributions of a layer's
+    /// uploaded features. Layers without uploads report no features.
+    pub async fn layer_stats(&self, county_id: &str, layer_id: &str) -> Result<LayerStats> {
+        self.require_layer(county_id, layer_id).await?;
+
+        let geometry_types: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT ST_GeometryType(geom), COUNT(*)
+            FROM layer_features
+            WHERE county_id = $1 AND layer_id = $2
+            GROUP BY 1
+            ORDER BY 2 DESC
+            "#,
+        )
+        .bind(county_id)
+        .bind(layer_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+        let feature_count = geometry_types.iter().map(|(_, count)| count).sum();
+
+        let extent: Option<(Option<f64>, Option<f64>, Option<f64>, Option<f64>)> = sqlx::query_as(
+            r#"
+            SELECT ST_XMin(e), ST_YMin(e), ST_XMax(e), ST_YMax(e)
+            FROM (SELECT ST_Extent(geom) AS e FROM layer_features WHERE county_id = $1 AND layer_id = $2) extent
+            "#,
+        )
+        .bind(county_id)
+        .bind(layer_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+        let extent = match extent {
+            Some((Some(min_x), Some(min_y), Some(max_x), Some(max_y))) => Some([min_x, min_y, max_x, max_y]),
+            _ => None,
+        };
+
+        let rows = sqlx::query_as::<_, AttributeRow>(
+            r#"
+            SELECT
+                p.key AS name,
+                COUNT(*) FILTER (WHERE jsonb_typeof(p.value) <> 'null') AS non_null,
+                COUNT(DISTINCT p.value) FILTER (WHERE jsonb_typeof(p.value) <> 'null') AS distinct_count,
+                COALESCE(
+                    array_agg(DISTINCT jsonb_typeof(p.value)) FILTER (WHERE jsonb_typeof(p.value) <> 'null'),
+                    '{}'
+                ) AS types,
+                MIN((p.value #>> '{}')::float8) FILTER (WHERE jsonb_typeof(p.value) = 'number') AS min_number,
+                MAX((p.value #>> '{}')::float8) FILTER (WHERE jsonb_typeof(p.value) = 'number') AS max_number,
+                MIN(p.value #>> '{}') FILTER (WHERE jsonb_typeof(p.value) = 'string') AS min_text,
+                MAX(p.value #>> '{}') FILTER (WHERE jsonb_typeof(p.value) = 'string') AS max_text
+            FROM layer_features f
+            CROSS JOIN LATERAL jsonb_each(f.properties) p
+            WHERE f.county_id = $1 AND f.layer_id = $2
+            GROUP BY p.key
+            ORDER BY p.key
+            "#,
+        )
+        .bind(county_id)
+        .bind(layer_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        // Value counts only for attributes with few enough values to list
+        let listed: Vec<String> = rows
+            .iter()
+            .filter(|row| row.distinct_count <= layer_stats::MAX_LISTED_DISTINCT)
+            .map(|row| row.name.clone())
+            .collect();
+        let values = if listed.is_empty() {
+            Vec::new()
+        } else {
+            sqlx::query_as::<_, ValueCountRow>(
+                r#"
+                SELECT name, value, count FROM (
+                    SELECT p.key AS name, p.value AS value, COUNT(*) AS count,
+                           ROW_NUMBER() OVER (PARTITION BY p.key ORDER BY COUNT(*) DESC, p.value) AS rank
+                    FROM layer_features f
+                    CROSS JOIN LATERAL jsonb_each(f.properties) p
+                    WHERE f.county_id = $1 AND f.layer_id = $2
+                      AND p.key = ANY($3) AND jsonb_typeof(p.value) <> 'null'
+                    GROUP BY p.key, p.value
+                ) counted
+                WHERE rank <= $4
+                ORDER BY name, count DESC
+                "#,
+            )
+            .bind(county_id)
+            .bind(layer_id)
+            .bind(&listed)
+            .bind(layer_stats::TOP_VALUES)
+            .fetch_all(&self.db_pool)
+            .await?
+        };
+
+        Ok(LayerStats {
+            county_id: county_id.to_string(),
+            layer_id: layer_id.to_string(),
+            feature_count,
+            geometry_types: geometry_types
+                .into_iter()
+                .map(|(geometry_type, count)| ValueCount { value: geometry_type.into(), count })
+                .collect(),
+            extent,
+            attributes: rows
+                .into_iter()
+                .map(|row| AttributeStats::from_rows(row, &values, feature_count))
+                .collect(),
+        })
+    }
+
+    /// Base URL of the public portal; empty when EXPORT_PUBLIC_URL is unset
+    pub fn public_url(&self) -> &str {
+        self.config.public_url.as_deref().unwrap_or_default().trim_end_matches('/')
+    }
+
+    /// How long clients may cache map tiles
+    pub fn tile_ttl(&self) -> std::time::Duration {
+        self.tile_cache.ttl()
+    }
+
+    /// A vector tile of a layer's uploaded features, or of the county
+    /// boundary for the `boundary` layer. Tiles outside the data are empty.
+    pub async fn map_tile(&self, county_id: &str, layer_id: &str, coord: TileCoord) -> Result<std::sync::Arc<Vec<u8>>> {
+        if let Some(tile) = self.tile_cache.get(county_id, layer_id, coord) {
+            return Ok(tile);
+        }
+
+        if layer_id != tiles::BOUNDARY_LAYER {
+            self.require_layer(county_id, layer_id).await?;
+        }
+
+        // Features are clipped to the tile in Web Mercator; the bbox test
+        // against the envelope in WGS 84 keeps the GIST index in use
+        let features = if layer_id == tiles::BOUNDARY_LAYER {
+            "SELECT county_id AS id, '{}'::jsonb AS properties, geom FROM county_boundaries WHERE county_id = $1"
+        } else {
+            "SELECT id::text AS id, properties, geom FROM layer_features WHERE county_id = $1 AND layer_id = $2"
+        };
+        let query = format!(
+            r#"
+            WITH bounds AS (SELECT ST_TileEnvelope($3, $4, $5) AS geom),
+            tile AS (
+                SELECT f.id, f.properties,
+                       ST_AsMVTGeom(ST_Transform(f.geom, 3857), bounds.geom, $6, $7, true) AS geom
+                FROM ({}) f, bounds
+                WHERE f.geom && ST_Transform(bounds.geom, 4326)
+            )
+            SELECT COALESCE(ST_AsMVT(tile.*, $2, $6, 'geom'), ''::bytea) FROM tile WHERE tile.geom IS NOT NULL
+            "#,
+            features
+        );
+        let tile: Vec<u8> = sqlx::query_scalar(&query)
+            .bind(county_id)
+            .bind(layer_id)
+            .bind(coord.z as i32)
+            .bind(coord.x as i32)
+            .bind(coord.y as i32)
+            .bind(tiles::EXTENT)
+            .bind(tiles::BUFFER)
+            .fetch_one(&self.db_pool)
+            .await?;
+
+        Ok(self.tile_cache.insert(county_id, layer_id, coord, tile))
     }
 
     /// Query features from database
     async fn query_features(&self, job: &GisExportJob, layers: &[String]) -> Result<Vec<HashMap<String, serde_json::Value>>> {
-        // For demonstration, generate sample data
-        // In production, this would query your actual geospatial database
+        if let Some(as_of) = temporal::as_of_parameter(job.parameters.as_ref())? {
+            return self.query_features_as_of(job, layers, as_of).await;
+        }
+        let area_of_interest = parse_area_of_interest(&job.area_of_interest)?.to_geojson();
         let mut features = Vec::new();
         
         for (i, layer) in layers.iter().enumerate() {
-            for j in 0..100 { // Generate 100 sample features per layer
+            let uploaded: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM layer_features WHERE county_id = $1 AND layer_id = $2)",
+            )
+            .bind(&job.county_id)
+            .bind(layer)
+            .fetch_one(&self.db_pool)
+            .await?;
+
+            if uploaded {
+                let rows: Vec<(i64, serde_json::Value, serde_json::Value)> = sqlx::query_as(
+                    r#"
+                    SELECT id, properties, ST_AsGeoJSON(geom)::jsonb
+                    FROM layer_features
+                    WHERE county_id = $1 AND layer_id = $2
+                      AND ST_Intersects(geom, ST_SetSRID(ST_GeomFromGeoJSON($3), 4326))
+                    ORDER BY id
+                    "#,
+                )
+                .bind(&job.county_id)
+                .bind(layer)
+                .bind(area_of_interest.to_string())
+                .fetch_all(&self.db_pool)
+                .await?;
+
+                for (id, properties, geometry) in rows {
+                    let mut feature: HashMap<String, serde_json::Value> = match properties {
+                        serde_json::Value::Object(properties) => properties.into_iter().collect(),
+                        _ => HashMap::new(),
+                    };
+                    feature.entry("id".to_string()).or_insert_with(|| id.into());
+                    feature.insert("layer".to_string(), serde_json::Value::String(layer.clone()));
+                    feature.insert("county_id".to_string(), serde_json::Value::String(job.county_id.clone()));
+                    feature.insert("geometry".to_string(), geometry);
+                    features.push(feature);
+                }
+                continue;
+            }
+
+            // Layers without uploaded seed data still export sample features
+            for j in 0..100 {
                 let mut feature = HashMap::new();
                 feature.insert("id".to_string(), serde_json::Value::Number((i * 100 + j).into()));
                 feature.insert("layer".to_string(), serde_json::Value::String(layer.clone()));
@@ -322,22 +1548,104 @@ impl GisExportService {
         Ok(features)
     }
 
-    /// Generate GeoJSON export
+    /// Features of the layers as synced at `as_of`, rebuilt from the sync
+    /// diff history. Records need a `geometry` (or `geom`) attribute in
+    /// GeoJSON, WKT or WKB to be exported.
+    async fn query_features_as_of(
+        &self,
+        job: &GisExportJob,
+        layers: &[String],
+        as_of: chrono::DateTime<Utc>,
+    ) -> Result<Vec<HashMap<String, serde_json::Value>>> {
+        let area_of_interest = parse_area_of_interest(&job.area_of_interest)?.geometry;
+        let mut features = Vec::new();
+
+        for layer in layers {
+            let records = TemporalQueries::layer_as_of(&self.db_pool, &job.county_id, layer, as_of).await?;
+            let mut skipped = 0;
+            for record in records {
+                let Some(data) = record.data else { continue };
+                // Only the sync service holds the keys to sealed payloads
+                if Envelope::parse(&data)?.is_some() {
+                    return Err(terrafusion_common::Error::Validation(format!(
+                        "History of layer {} is encrypted at rest and cannot be exported as of a date",
+                        layer
+                    ))
+                    .into());
+                }
+                let serde_json::Value::Object(mut properties) = data else { continue };
+                let geometry = ["geometry", "geom"]
+                    .iter()
+                    .filter_map(|name| properties.remove(*name))
+                    .find(|geometry| !geometry.is_null())
+                    .and_then(|geometry| parse_geometry(&geometry).ok());
+                let Some(geometry) = geometry else {
+                    skipped += 1;
+                    continue;
+                };
+                if !geometry.geometry.intersects(&area_of_interest) {
+                    continue;
+                }
+
+                let mut feature: HashMap<String, serde_json::Value> = properties.into_iter().collect();
+                feature.entry("id".to_string()).or_insert_with(|| record.entity_id.clone().into());
+                feature.insert("layer".to_string(), serde_json::Value::String(layer.clone()));
+                feature.insert("county_id".to_string(), serde_json::Value::String(job.county_id.clone()));
+                feature.insert("geometry".to_string(), geometry.to_geojson());
+                features.push(feature);
+            }
+            if skipped > 0 {
+                log::warn!("Skipped {} records of layer {} without a readable geometry", skipped, layer);
+            }
+        }
+
+        log::info!("Rebuilt {} features as of {} for export", features.len(), as_of.to_rfc3339());
+        Ok(features)
+    }
+
+    /// Apply the county's export redaction policy.
+    ///
+    /// Counties without a configuration file export unredacted; any other
+    /// failure to load the policy fails the export rather than risk leaking PII.
+    async fn redact_features(&self, county_id: &str, features: &mut [HashMap<String, serde_json::Value>]) -> Result<()> {
+        let policy = match county_config::load_county_configuration(county_id).await {
+            Ok(config) => config.export_redaction,
+            Err(CountyConfigError::NotFound(_)) => return Ok(()),
+            Err(e) => return Err(anyhow!("Failed to load redaction policy for county {}: {}", county_id, e)),
+        };
+        if policy.is_empty() {
+            return Ok(());
+        }
+
+        let masker = Masker::global();
+        for feature in features.iter_mut() {
+            let mut record: serde_json::Map<_, _> = feature.drain().collect();
+            let geometry = record.remove("geometry");
+            policy.apply(&mut record, masker);
+            feature.extend(record);
+            if let Some(geometry) = geometry {
+                feature.insert("geometry".to_string(), geometry);
+            }
+        }
+
+        log::info!("Applied {} redaction rules to {} features for county {}", policy.rules.len(), features.len(), county_id);
+        Ok(())
+    }
+
+    /// Generate GeoJSON export, streamed to the file a feature at a time
     async fn generate_geojson(&self, file_path: &PathBuf, features: &[HashMap<String, serde_json::Value>]) -> Result<()> {
-        let geojson = serde_json::json!({
-            "type": "FeatureCollection",
-            "features": features.iter().map(|f| {
-                serde_json::json!({
-                    "type": "Feature",
-                    "geometry": f.get("geometry").unwrap_or(&serde_json::Value::Null),
-                    "properties": f.iter()
-                        .filter(|(k, _)| *k != "geometry")
-                        .collect::<HashMap<_, _>>()
-                })
-            }).collect::<Vec<_>>()
-        });
+        let features = features.iter().map(geojson_feature).collect::<Result<Vec<_>>>()?;
+        let file_path = file_path.clone();
 
-        fs::write(file_path, serde_json::to_string_pretty(&geojson)?).await?;
+        tokio::task::spawn_blocking(move || -> terrafusion_common::Result<()> {
+            let mut writer = FeatureWriter::create(&file_path)?;
+            for feature in &features {
+                writer.write(feature)?;
+            }
+            writer.finish()?;
+            Ok(())
+        })
+        .await??;
         Ok(())
     }
 
@@ -404,6 +1712,120 @@ impl GisExportService {
         Ok(())
     }
 
+    /// Compare two completed runs of the same export and write the change
+    /// report next to the exports. Returns the report and its counts.
+    pub async fn compare_exports(&self, request: CompareExportsRequest) -> Result<(PathBuf, ChangeSummary)> {
+        let format = ReportFormat::parse(request.format.as_deref().unwrap_or("geojson"))?;
+        if request.base_job_id == request.job_id {
+            return Err(terrafusion_common::Error::Validation("Compare two different exports".to_string()).into());
+        }
+
+        let mut jobs = Vec::with_capacity(2);
+        for job_id in [request.base_job_id, request.job_id] {
+            let job = sqlx::query_as::<_, GisExportJob>("SELECT * FROM gis_export_jobs WHERE job_id = $1")
+                .bind(job_id)
+                .fetch_optional(&self.db_pool)
+                .await?
+                .filter(|job| request.county_id.as_ref().map_or(true, |county_id| &job.county_id == county_id))
+                .ok_or_else(|| terrafusion_common::Error::NotFound(format!("Job not found: {}", job_id)))?;
+            if job.status != "COMPLETED" {
+                return Err(terrafusion_common::Error::Conflict(format!("Export {} has not completed", job_id)).into());
+            }
+            jobs.push(job);
+        }
+        let (base, current) = (&jobs[0], &jobs[1]);
+
+        let layer_set = |job: &GisExportJob| -> Result<Vec<String>> {
+            let mut layers: Vec<String> = serde_json::from_value(job.layers.clone())?;
+            layers.sort();
+            Ok(layers)
+        };
+        if base.county_id != current.county_id || layer_set(base)? != layer_set(current)? {
+            return Err(terrafusion_common::Error::Validation(
+                "Only exports of the same county and layers can be compared".to_string(),
+            )
+            .into());
+        }
+
+        let base_features = self.load_snapshot(base).await?;
+        let current_features = self.load_snapshot(current).await?;
+        let report_path = self.config.storage_path.join(format!(
+            "{}_changes_{}_{}.{}",
+            current.county_id,
+            base.job_id.simple(),
+            current.job_id.simple(),
+            format.extension()
+        ));
+
+        let report_file = report_path.clone();
+        let summary = tokio::task::spawn_blocking(move || -> terrafusion_common::Result<ChangeSummary> {
+            let report = compare::diff(base_features, current_features)?;
+            compare::write_report(&report_file, &report, format)?;
+            Ok(report.summary)
+        })
+        .await??;
+
+        let report_path = match &self.file_cipher {
+            Some(cipher) => {
+                let kek = cipher.key().await?;
+                tokio::task::spawn_blocking(move || file_encryption::encrypt_file(&kek, &report_path)).await??
+            }
+            None => report_path,
+        };
+
+        log::info!(
+            "Compared exports {} and {} for county {}: {} added, {} removed, {} modified",
+            base.job_id,
+            current.job_id,
+            current.county_id,
+            summary.added,
+            summary.removed,
+            summary.modified
+        );
+        Ok((report_path, summary))
+    }
+
+    /// The features a completed export wrote, decrypting the snapshot when
+    /// exports are encrypted at rest
+    async fn load_snapshot(&self, job: &GisExportJob) -> Result<Vec<geojson::Feature>> {
+        // Reused results share the original export's snapshot
+        let snapshot_job_id = job.reused_from.unwrap_or(job.job_id);
+        let path = self.config.storage_path.join(compare::snapshot_file_name(&job.county_id, snapshot_job_id));
+        let encrypted_path = PathBuf::from(format!("{}.{}", path.display(), file_encryption::ENCRYPTED_SUFFIX));
+
+        if fs::try_exists(&path).await? {
+            return Ok(tokio::task::spawn_blocking(move || compare::read_snapshot(&path)).await??);
+        }
+        if !fs::try_exists(&encrypted_path).await? {
+            return Err(terrafusion_common::Error::Validation(format!(
+                "Export {} has no feature snapshot; exports from before change reports must be run again",
+                job.job_id
+            ))
+            .into());
+        }
+
+        let kek = self.export_key().await?;
+        let features = tokio::task::spawn_blocking(move || -> Result<Vec<geojson::Feature>> {
+            let mut plaintext = tempfile::NamedTempFile::new()?;
+            let mut write_error = None;
+            file_encryption::decrypt_file(&kek, &encrypted_path, |chunk| {
+                match std::io::Write::write_all(&mut plaintext, &chunk) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        write_error = Some(e);
+                        false
+                    }
+                }
+            })?;
+            if let Some(e) = write_error {
+                return Err(e.into());
+            }
+            Ok(compare::read_snapshot(plaintext.path())?)
+        })
+        .await??;
+        Ok(features)
+    }
+
     /// Get file path for download
     pub async fn get_export_file(&self, job_id: Uuid) -> Result<PathBuf> {
         let job = sqlx::query_as::<_, GisExportJob>(
@@ -412,10 +1834,10 @@ impl GisExportService {
         .bind(job_id)
         .fetch_optional(&self.db_pool)
         .await?
-        .ok_or_else(|| anyhow!("Job not found: {}", job_id))?;
+        .ok_or_else(|| terrafusion_common::Error::NotFound(format!("Job not found: {}", job_id)))?;
 
         if job.status != "COMPLETED" {
-            return Err(anyhow!("Export not ready for download"));
+            return Err(terrafusion_common::Error::Conflict("Export not ready for download".to_string()).into());
         }
 
         let file_path = job.file_path
@@ -423,9 +1845,229 @@ impl GisExportService {
         
         let path = PathBuf::from(file_path);
         if !path.exists() {
-            return Err(anyhow!("Export file not found"));
+            return Err(terrafusion_common::Error::NotFound("Export file not found".to_string()).into());
         }
 
         Ok(path)
     }
-}
\ No newline at end of file
+
+    /// Offer a completed export on the public portal, or update its title
+    /// and description if it is already published
+    pub async fn publish_export(&self, job_id: Uuid, request: PublishExportRequest) -> Result<PublishedExport> {
+        let title = request.title.trim();
+        if title.is_empty() || title.len() > 255 {
+            return Err(terrafusion_common::Error::Validation("title must be 1 to 255 characters".to_string()).into());
+        }
+
+        let job = sqlx::query_as::<_, GisExportJob>("SELECT * FROM gis_export_jobs WHERE job_id = $1")
+            .bind(job_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| terrafusion_common::Error::NotFound(format!("Job not found: {}", job_id)))?;
+
+        if let Some(county_id) = &request.county_id {
+            if *county_id != job.county_id {
+                return Err(terrafusion_common::Error::Authorization(format!(
+                    "Job {} belongs to another county",
+                    job_id
+                ))
+                .into());
+            }
+        }
+        if job.status != "COMPLETED" || job.file_path.is_none() {
+            return Err(terrafusion_common::Error::Validation("Only completed exports can be published".to_string()).into());
+        }
+
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO published_exports (id, job_id, county_id, title, description, published_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (job_id) DO UPDATE SET
+                title = EXCLUDED.title,
+                description = EXCLUDED.description,
+                published_by = EXCLUDED.published_by
+            RETURNING id
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(job_id)
+        .bind(&job.county_id)
+        .bind(title)
+        .bind(request.description.as_deref().map(str::trim).filter(|d| !d.is_empty()))
+        .bind(&request.published_by)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        log::info!("Export {} published as {} by {}", job_id, id, request.published_by);
+        self.get_published(id).await
+    }
+
+    /// Withdraw an export from the public portal; false if it was not published
+    pub async fn unpublish_export(&self, job_id: Uuid, county_id: Option<&str>) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM published_exports WHERE job_id = $1 AND ($2::text IS NULL OR county_id = $2)",
+        )
+        .bind(job_id)
+        .bind(county_id)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Published exports, newest first
+    pub async fn list_published(&self, params: &ListPublishedParams, pagination: Pagination) -> Result<Page<PublishedExport>> {
+        const FILTERS: &str = r#"
+            WHERE ($1::text IS NULL OR p.county_id = $1)
+            AND ($2::text IS NULL OR j.export_format = $2)
+        "#;
+
+        let mut query = format!("{} {}", PUBLISHED_EXPORT_SELECT, FILTERS);
+        if let Some(condition) = pagination.keyset_condition("p.published_at", "p.id", 3) {
+            query.push_str(&format!(" AND {}", condition));
+        }
+        query.push_str(&format!(
+            " ORDER BY p.published_at DESC, p.id DESC LIMIT {} OFFSET {}",
+            pagination.fetch_limit(),
+            pagination.offset()
+        ));
+
+        let mut exports_query = sqlx::query_as::<_, PublishedExport>(&query)
+            .bind(&params.county_id)
+            .bind(&params.export_format);
+        if let Some(after) = pagination.after() {
+            exports_query = exports_query.bind(after.sort_key).bind(after.id);
+        }
+        let exports = exports_query.fetch_all(&self.db_pool).await?;
+
+        let total = if pagination.is_cursor() {
+            None
+        } else {
+            let total: i64 = sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM published_exports p JOIN gis_export_jobs j ON j.job_id = p.job_id {}",
+                FILTERS
+            ))
+            .bind(&params.county_id)
+            .bind(&params.export_format)
+            .fetch_one(&self.db_pool)
+            .await?;
+            Some(total)
+        };
+
+        Ok(pagination.into_page(exports, total, |export| Cursor::new(export.published_at, export.id)))
+    }
+
+    /// One published export
+    pub async fn get_published(&self, id: Uuid) -> Result<PublishedExport> {
+        let export = sqlx::query_as::<_, PublishedExport>(&format!("{} WHERE p.id = $1", PUBLISHED_EXPORT_SELECT))
+            .bind(id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| terrafusion_common::Error::NotFound(format!("Published export not found: {}", id)))?;
+
+        Ok(export)
+    }
+
+    /// File behind a published export, counting the download
+    pub async fn get_published_file(&self, id: Uuid) -> Result<(PathBuf, PublishedExport)> {
+        let job_id: Uuid = sqlx::query_scalar(
+            "UPDATE published_exports SET download_count = download_count + 1 WHERE id = $1 RETURNING job_id",
+        )
+        .bind(id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| terrafusion_common::Error::NotFound(format!("Published export not found: {}", id)))?;
+
+        let path = self.get_export_file(job_id).await?;
+        let export = self.get_published(id).await?;
+        Ok((path, export))
+    }
+}
+
+/// Record an approval request or decision on a job in the audit log
+async fn audit_job(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    job: &GisExportJob,
+    event_type: &str,
+    username: &str,
+    description: &str,
+    previous_status: Option<&str>,
+    new_state: serde_json::Value,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (
+            id, event_type, resource_type, resource_id, description, username, county_id,
+            previous_state, new_state, severity, created_at
+        ) VALUES ($1, $2, 'gis_export_job', $3, $4, $5, $6, $7, $8, 'INFO', $9)
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(event_type)
+    .bind(job.job_id.to_string())
+    .bind(description)
+    .bind(username)
+    .bind(&job.county_id)
+    .bind(previous_status.map(|status| serde_json::json!({ "status": status })))
+    .bind(new_state)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await?;
+    Ok(())
+}
+
+/// Where an export's manifest is written
+fn manifest_path(file_path: &Path, manifest: &ExportManifest) -> PathBuf {
+    file_path.with_file_name(format!("{}.{}", manifest.files[0].name, MANIFEST_SUFFIX))
+}
+
+/// A queried feature as GeoJSON: `geometry` (GeoJSON, WKT or hex WKB)
+/// becomes the geometry and the other fields the properties
+fn geojson_feature(record: &HashMap<String, serde_json::Value>) -> Result<geojson::Feature> {
+    let geometry = match record.get("geometry") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(value) => Some(geojson::Geometry::new(geojson::Value::from(&parse_geometry(value)?.geometry))),
+    };
+    let properties = record
+        .iter()
+        .filter(|(key, _)| *key != "geometry")
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+
+    Ok(geojson::Feature {
+        bbox: None,
+        geometry,
+        id: None,
+        properties: Some(properties),
+        foreign_members: None,
+    })
+}
+
+/// The job's area of interest, given as GeoJSON, WKT or hex WKB
+fn parse_area_of_interest(value: &serde_json::Value) -> Result<ParsedGeometry> {
+    let parsed = parse_geometry(value).map_err(|e| {
+        terrafusion_common::Error::Validation(format!("area_of_interest must be a GeoJSON, WKT or WKB geometry: {}", e))
+    })?;
+    // Areas of interest are in degrees; an EWKT or EWKB SRID must agree
+    if let Some(srid) = parsed.srid {
+        if Measurement::for_crs(&format!("EPSG:{}", srid)).ok() != Some(Measurement::Geodesic) {
+            return Err(terrafusion_common::Error::Validation(format!(
+                "area_of_interest must be in WGS 84 (EPSG:4326), not EPSG:{}",
+                srid
+            ))
+            .into());
+        }
+    }
+    Ok(parsed)
+}
+
+/// Extent of the features' geometries; geometries that do not parse are left out
+fn features_bbox(features: &[HashMap<String, serde_json::Value>]) -> Option<Bbox> {
+    let geometries: Vec<geo::Geometry<f64>> = features
+        .iter()
+        .filter_map(|feature| feature.get("geometry"))
+        .filter_map(|value| parse_geometry(value).ok())
+        .map(|parsed| parsed.geometry)
+        .collect();
+    Bbox::of_all(&geometries)
+}
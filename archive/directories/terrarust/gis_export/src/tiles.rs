@@ -0,0 +1,163 @@
+//! Vector tile previews of county layers
+//!
+//! Tiles are Mapbox Vector Tiles in the XYZ scheme, cut by PostGIS from the
+//! uploaded layer features and county boundary. Rendering a tile scans its
+//! features, and map clients ask for the same tiles over and over while
+//! panning, so rendered tiles are kept in memory until they expire or the
+//! layer is uploaded again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use terrafusion_common::{Error, Result};
+
+/// Layer name serving the county's boundary rather than uploaded features
+pub const BOUNDARY_LAYER: &str = "boundary";
+
+/// Deepest zoom served; features are not generalized further than this
+pub const MAX_ZOOM: u32 = 22;
+
+/// Tile extent in MVT units and the buffer around it, the PostGIS defaults
+pub const EXTENT: i32 = 4096;
+pub const BUFFER: i32 = 64;
+
+pub const CONTENT_TYPE: &str = "application/vnd.mapbox-vector-tile";
+
+/// An XYZ tile address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileCoord {
+    pub z: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileCoord {
+    /// Check a tile address. `y` may carry the `.mvt` extension.
+    pub fn parse(z: u32, x: u32, y: &str) -> Result<Self> {
+        let y = y.strip_suffix(".mvt").unwrap_or(y);
+        let y: u32 = y
+            .parse()
+            .map_err(|_| Error::Validation(format!("Invalid tile row {}", y)))?;
+        if z > MAX_ZOOM {
+            return Err(Error::Validation(format!("Zoom {} is above the maximum of {}", z, MAX_ZOOM)));
+        }
+        let tiles = 1u32 << z;
+        if x >= tiles || y >= tiles {
+            return Err(Error::Validation(format!("Tile {}/{}/{} does not exist", z, x, y)));
+        }
+        Ok(Self { z, x, y })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct TileKey {
+    county_id: String,
+    layer_id: String,
+    coord: TileCoord,
+}
+
+/// Rendered tiles, per county, layer and address.
+///
+/// Bounded by a byte budget; when it is reached expired tiles go first and
+/// then the oldest.
+#[derive(Clone)]
+pub struct TileCache {
+    ttl: Duration,
+    max_bytes: usize,
+    entries: Arc<RwLock<HashMap<TileKey, (Instant, Arc<Vec<u8>>)>>>,
+}
+
+impl Default for TileCache {
+    fn default() -> Self {
+        let ttl = std::env::var("TILE_CACHE_TTL_SECONDS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(600);
+        let max_mb: usize = std::env::var("TILE_CACHE_MAX_MB")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(128);
+        Self::new(Duration::from_secs(ttl), max_mb * 1024 * 1024)
+    }
+}
+
+impl TileCache {
+    pub fn new(ttl: Duration, max_bytes: usize) -> Self {
+        Self { ttl, max_bytes, entries: Arc::new(RwLock::new(HashMap::new())) }
+    }
+
+    /// How long clients may keep a tile
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn get(&self, county_id: &str, layer_id: &str, coord: TileCoord) -> Option<Arc<Vec<u8>>> {
+        let key = TileKey { county_id: county_id.to_string(), layer_id: layer_id.to_string(), coord };
+        let entries = self.entries.read().expect("tile cache lock poisoned");
+        entries
+            .get(&key)
+            .filter(|(rendered_at, _)| rendered_at.elapsed() < self.ttl)
+            .map(|(_, tile)| tile.clone())
+    }
+
+    pub fn insert(&self, county_id: &str, layer_id: &str, coord: TileCoord, tile: Vec<u8>) -> Arc<Vec<u8>> {
+        let tile = Arc::new(tile);
+        if tile.len() > self.max_bytes {
+            return tile;
+        }
+        let key = TileKey { county_id: county_id.to_string(), layer_id: layer_id.to_string(), coord };
+        let mut entries = self.entries.write().expect("tile cache lock poisoned");
+        entries.retain(|_, (rendered_at, _)| rendered_at.elapsed() < self.ttl);
+
+        let mut size: usize = entries.values().map(|(_, tile)| tile.len()).sum();
+        while size + tile.len() > self.max_bytes {
+            let Some(oldest) = entries.iter().min_by_key(|(_, (rendered_at, _))| *rendered_at).map(|(key, _)| key.clone()) else {
+                break;
+            };
+            if let Some((_, evicted)) = entries.remove(&oldest) {
+                size -= evicted.len();
+            }
+        }
+        entries.insert(key, (Instant::now(), tile.clone()));
+        tile
+    }
+
+    /// Forget a layer's tiles after new data is uploaded for it
+    pub fn invalidate(&self, county_id: &str, layer_id: &str) {
+        let mut entries = self.entries.write().expect("tile cache lock poisoned");
+        entries.retain(|key, _| !(key.county_id == county_id && key.layer_id == layer_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_tile_addresses() {
+        assert_eq!(TileCoord::parse(3, 1, "5.mvt").unwrap(), TileCoord { z: 3, x: 1, y: 5 });
+        assert_eq!(TileCoord::parse(0, 0, "0").unwrap(), TileCoord { z: 0, x: 0, y: 0 });
+        assert!(TileCoord::parse(3, 8, "0.mvt").is_err());
+        assert!(TileCoord::parse(23, 0, "0.mvt").is_err());
+        assert!(TileCoord::parse(3, 1, "five.mvt").is_err());
+    }
+
+    #[test]
+    fn test_cache_evicts_and_invalidates() {
+        let cache = TileCache::new(Duration::from_secs(60), 10);
+        let coord = |x| TileCoord { z: 4, x, y: 0 };
+        cache.insert("benton", "parcels", coord(0), vec![0; 6]);
+        cache.insert("benton", "roads", coord(0), vec![0; 2]);
+        assert!(cache.get("benton", "parcels", coord(0)).is_some());
+
+        // Over budget: the oldest tile makes room
+        cache.insert("benton", "parcels", coord(1), vec![0; 6]);
+        assert!(cache.get("benton", "parcels", coord(0)).is_none());
+        assert!(cache.get("benton", "parcels", coord(1)).is_some());
+
+        cache.invalidate("benton", "parcels");
+        assert!(cache.get("benton", "parcels", coord(1)).is_none());
+        assert!(cache.get("benton", "roads", coord(0)).is_some());
+    }
+}
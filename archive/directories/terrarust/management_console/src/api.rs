@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use reqwest::{Method, RequestBuilder, Response};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// Client for the gateway's `/api/v1` routes, authenticated with an API key
+pub struct GatewayClient {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl GatewayClient {
+    pub fn new(base_url: &str, api_key: Option<String>) -> Result<Self> {
+        let api_key = api_key
+            .filter(|key| !key.is_empty())
+            .context("An API key is required; pass --api-key or set TERRAFUSION_API_KEY")?;
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            base_url: format!("{}/api/v1", base_url.trim_end_matches('/')),
+            api_key,
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.base_url, path))
+            .header("X-API-KEY", &self.api_key)
+    }
+
+    pub async fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Value> {
+        let response = self.request(Method::GET, path).query(query).send().await;
+        json_body(path, response).await
+    }
+
+    pub async fn post(&self, path: &str, body: Option<&Value>) -> Result<Value> {
+        let mut request = self.request(Method::POST, path);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        json_body(path, request.send().await).await
+    }
+
+    /// Stream a response body to `output`, or to the server-suggested file name in the
+    /// current directory; returns the path written
+    pub async fn download(&self, path: &str, output: Option<&Path>, fallback_name: &str) -> Result<PathBuf> {
+        let mut response = checked(path, self.request(Method::GET, path).send().await).await?;
+
+        let output = match output {
+            Some(output) if output.is_dir() => output.join(suggested_file_name(&response).unwrap_or_else(|| fallback_name.to_string())),
+            Some(output) => output.to_path_buf(),
+            None => PathBuf::from(suggested_file_name(&response).unwrap_or_else(|| fallback_name.to_string())),
+        };
+
+        let mut file = tokio::fs::File::create(&output)
+            .await
+            .with_context(|| format!("Failed to create {}", output.display()))?;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+
+        Ok(output)
+    }
+}
+
+async fn checked(path: &str, response: reqwest::Result<Response>) -> Result<Response> {
+    let response = response.with_context(|| format!("Gateway request to {} failed", path))?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|value| {
+            value
+                .get("error")
+                .or_else(|| value.get("message"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        })
+        .unwrap_or(body);
+    anyhow::bail!("{} returned HTTP {}: {}", path, status, message)
+}
+
+async fn json_body(path: &str, response: reqwest::Result<Response>) -> Result<Value> {
+    let response = checked(path, response).await?;
+    let text = response.text().await?;
+    if text.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(&text).with_context(|| format!("{} returned invalid JSON", path))
+}
+
+/// File name from a `Content-Disposition: attachment; filename="..."` header
+fn suggested_file_name(response: &Response) -> Option<String> {
+    let disposition = response.headers().get("content-disposition")?.to_str().ok()?;
+    let name = disposition
+        .split(';')
+        .find_map(|part| part.trim().strip_prefix("filename="))?
+        .trim_matches('"');
+
+    // Never let the server pick a directory
+    Path::new(name).file_name().map(|name| name.to_string_lossy().into_owned())
+}
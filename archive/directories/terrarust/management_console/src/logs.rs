@@ -0,0 +1,369 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, TimeZone, Utc};
+use clap::ValueEnum;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Services whose logs the console can read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Service {
+    Gateway,
+    Sync,
+    Gis,
+    Wrapper,
+}
+
+impl Service {
+    pub const ALL: [Service; 4] = [Service::Gateway, Service::Sync, Service::Gis, Service::Wrapper];
+
+    /// Log file written by the service wrapper for this service
+    fn file_name(self) -> &'static str {
+        match self {
+            Service::Gateway => "terrafusion-api-gateway.log",
+            Service::Sync => "terrafusion-sync-service.log",
+            Service::Gis => "terrafusion-gis-export.log",
+            Service::Wrapper => "terrafusion-service.log",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Service::Gateway => "gateway",
+            Service::Sync => "sync",
+            Service::Gis => "gis",
+            Service::Wrapper => "wrapper",
+        }
+    }
+}
+
+/// Log levels, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    fn parse(token: &str) -> Option<Self> {
+        match token.to_ascii_uppercase().as_str() {
+            "TRACE" => Some(Level::Trace),
+            "DEBUG" => Some(Level::Debug),
+            "INFO" | "INFORMATION" => Some(Level::Info),
+            "WARN" | "WARNING" => Some(Level::Warn),
+            "ERROR" | "CRITICAL" => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Filters applied to every log line
+#[derive(Debug, Default)]
+pub struct LogFilter {
+    pub min_level: Option<Level>,
+    pub since: Option<DateTime<Utc>>,
+    /// Case-insensitive substring match
+    pub pattern: Option<String>,
+}
+
+impl LogFilter {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        if let (Some(min), Some(level)) = (self.min_level, entry.level) {
+            if level < min {
+                return false;
+            }
+        }
+        if let (Some(since), Some(timestamp)) = (self.since, entry.timestamp) {
+            if timestamp < since {
+                return false;
+            }
+        }
+        match &self.pattern {
+            Some(pattern) => entry.text.to_lowercase().contains(&pattern.to_lowercase()),
+            None => true,
+        }
+    }
+}
+
+/// A single line with the level and timestamp it was logged at
+///
+/// Continuation lines (stack traces, multi-line messages) carry no level or timestamp of
+/// their own and inherit them from the line before.
+#[derive(Debug, Clone)]
+struct LogEntry {
+    service: Service,
+    level: Option<Level>,
+    timestamp: Option<DateTime<Utc>>,
+    text: String,
+}
+
+/// Level and timestamp of the most recent line of a file
+#[derive(Debug, Default, Clone, Copy)]
+struct LineContext {
+    level: Option<Level>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+impl LineContext {
+    fn entry(&mut self, service: Service, line: &str) -> LogEntry {
+        let (level, timestamp) = parse_line(line);
+        if level.is_some() || timestamp.is_some() {
+            self.level = level.or(self.level);
+            self.timestamp = timestamp.or(self.timestamp);
+        }
+        LogEntry {
+            service,
+            level: self.level,
+            timestamp: self.timestamp,
+            text: line.to_string(),
+        }
+    }
+}
+
+/// Extract level and timestamp from env_logger text lines or JSON log lines
+fn parse_line(line: &str) -> (Option<Level>, Option<DateTime<Utc>>) {
+    let trimmed = line.trim_start();
+
+    if trimmed.starts_with('{') {
+        if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
+            let field = |names: &[&str]| names.iter().find_map(|name| value.get(*name).and_then(Value::as_str));
+            let level = field(&["level", "severity"]).and_then(Level::parse);
+            let timestamp = field(&["timestamp", "time", "ts"]).and_then(parse_timestamp);
+            return (level, timestamp);
+        }
+    }
+
+    // Only the leading tokens hold metadata; later words belong to the message
+    let mut level = None;
+    let mut timestamp = None;
+    for token in trimmed.split_whitespace().take(4) {
+        let token = token.trim_matches(|c| c == '[' || c == ']');
+        if timestamp.is_none() {
+            timestamp = parse_timestamp(token);
+        }
+        if level.is_none() {
+            level = Level::parse(token);
+        }
+    }
+    (level, timestamp)
+}
+
+fn parse_timestamp(token: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(token)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+/// Parse `--since` as a relative duration (`30s`, `15m`, `2h`, `7d`), an RFC 3339 timestamp
+/// or a date
+pub fn parse_since(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if let Some(timestamp) = parse_timestamp(value) {
+        return Ok(timestamp);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).expect("midnight is valid")));
+    }
+
+    let (amount, unit) = value.split_at(value.len().saturating_sub(1));
+    let amount: i64 = amount
+        .parse()
+        .with_context(|| format!("Invalid --since value '{}'", value))?;
+    let duration = match unit {
+        "s" => ChronoDuration::seconds(amount),
+        "m" => ChronoDuration::minutes(amount),
+        "h" => ChronoDuration::hours(amount),
+        "d" => ChronoDuration::days(amount),
+        _ => anyhow::bail!("Invalid --since unit in '{}' (use s, m, h or d)", value),
+    };
+    Ok(now - duration)
+}
+
+/// Print the last `lines` matching lines across the selected services' log files
+pub async fn tail(
+    log_dir: &Path,
+    services: &[Service],
+    filter: &LogFilter,
+    lines: usize,
+    follow: bool,
+) -> Result<()> {
+    let mut sources = Vec::new();
+    let mut entries = Vec::new();
+
+    for &service in services {
+        let path = log_dir.join(service.file_name());
+        if !path.exists() {
+            eprintln!("No log file for {} at {}", service.label(), path.display());
+            continue;
+        }
+
+        let mut context = LineContext::default();
+        let reader = BufReader::new(File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?);
+        let mut recent = VecDeque::with_capacity(lines);
+        for line in reader.lines() {
+            let entry = context.entry(service, &line?);
+            if filter.matches(&entry) {
+                if recent.len() == lines {
+                    recent.pop_front();
+                }
+                recent.push_back(entry);
+            }
+        }
+        entries.extend(recent);
+
+        let offset = std::fs::metadata(&path)?.len();
+        sources.push(FollowedFile { service, path, offset, context, partial: String::new() });
+    }
+
+    if sources.is_empty() {
+        anyhow::bail!("No log files found in {}", log_dir.display());
+    }
+
+    // Interleave services chronologically; the sort is stable so lines within a file keep their order
+    entries.sort_by_key(|entry| entry.timestamp);
+    let skip = entries.len().saturating_sub(lines);
+    let prefix = services.len() > 1;
+    for entry in entries.iter().skip(skip) {
+        print_entry(entry, prefix);
+    }
+
+    if !follow {
+        return Ok(());
+    }
+
+    loop {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        for source in &mut sources {
+            for entry in source.read_new()? {
+                if filter.matches(&entry) {
+                    print_entry(&entry, prefix);
+                }
+            }
+        }
+    }
+}
+
+/// A log file being followed for appended lines
+struct FollowedFile {
+    service: Service,
+    path: PathBuf,
+    offset: u64,
+    context: LineContext,
+    /// Trailing text without a newline yet
+    partial: String,
+}
+
+impl FollowedFile {
+    fn read_new(&mut self) -> Result<Vec<LogEntry>> {
+        let len = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => return Ok(Vec::new()),
+        };
+        if len < self.offset {
+            // File was truncated or rotated; start again from the top
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if len == self.offset {
+            return Ok(Vec::new());
+        }
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        self.offset += buffer.len() as u64;
+
+        self.partial.push_str(&String::from_utf8_lossy(&buffer));
+        let mut entries = Vec::new();
+        while let Some(newline) = self.partial.find('\n') {
+            let line: String = self.partial.drain(..=newline).collect();
+            entries.push(self.context.entry(self.service, line.trim_end_matches(['\r', '\n'])));
+        }
+        Ok(entries)
+    }
+}
+
+fn print_entry(entry: &LogEntry, prefix: bool) {
+    if prefix {
+        println!("[{:<7}] {}", entry.service.label(), entry.text);
+    } else {
+        println!("{}", entry.text);
+    }
+}
+
+/// Print Service Control Manager events for the platform service from the Windows System log
+#[cfg(windows)]
+pub fn event_log(filter: &LogFilter, lines: usize) -> Result<()> {
+    let output = std::process::Command::new("wevtutil")
+        .args([
+            "qe",
+            "System",
+            "/q:*[System[Provider[@Name='Service Control Manager']]]",
+            "/rd:true",
+            "/f:text",
+            "/c:1000",
+        ])
+        .output()
+        .context("Failed to query the Windows event log")?;
+    if !output.status.success() {
+        anyhow::bail!("wevtutil failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut events: Vec<LogEntry> = text
+        .split("Event[")
+        .filter(|event| event.contains("TerraFusion"))
+        .map(|event| {
+            let field = |name: &str| {
+                event
+                    .lines()
+                    .find_map(|line| line.trim().strip_prefix(name).map(|value| value.trim().to_string()))
+            };
+            LogEntry {
+                service: Service::Wrapper,
+                level: field("Level:").as_deref().and_then(Level::parse),
+                timestamp: field("Date:").as_deref().and_then(parse_timestamp),
+                text: event.trim().to_string(),
+            }
+        })
+        .filter(|entry| filter.matches(entry))
+        .take(lines)
+        .collect();
+
+    // wevtutil returned newest first
+    events.reverse();
+    for event in &events {
+        println!("{}\n", event.text);
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn event_log(_filter: &LogFilter, _lines: usize) -> Result<()> {
+    anyhow::bail!("The Windows event log is only available on Windows")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_formats_and_since() {
+        let (level, timestamp) = parse_line("[2024-03-01T10:00:00Z WARN  sync_service] retrying");
+        assert_eq!(level, Some(Level::Warn));
+        assert!(timestamp.is_some());
+
+        let (level, _) = parse_line(r#"{"timestamp":"2024-03-01T10:00:00Z","level":"error","msg":"x"}"#);
+        assert_eq!(level, Some(Level::Error));
+
+        let now = parse_timestamp("2024-03-01T10:00:00Z").unwrap();
+        assert_eq!(parse_since("2h", now).unwrap(), parse_timestamp("2024-03-01T08:00:00Z").unwrap());
+        assert!(parse_since("2w", now).is_err());
+    }
+}
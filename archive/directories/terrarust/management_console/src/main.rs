@@ -1,33 +1,120 @@
 use clap::{Parser, Subcommand};
 use anyhow::Result;
+use std::path::PathBuf;
+use std::time::Duration;
+
+mod api;
+mod logs;
+mod operations;
+mod status;
 
 #[derive(Parser)]
 #[command(name = "terrafusion-console")]
 #[command(about = "TerraFusion Platform Management Console")]
 struct Cli {
+    /// API gateway base URL
+    #[arg(long, global = true, default_value = "http://localhost:8000")]
+    gateway_url: String,
+
+    /// API key for gateway API calls
+    #[arg(long, global = true, env = "TERRAFUSION_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Show system status
-    Status,
+    /// Show component health and Windows service state (exits 1 if any component is not up)
+    Status {
+        /// Sync service base URL
+        #[arg(long, default_value = "http://localhost:8001")]
+        sync_url: String,
+        /// GIS export service base URL
+        #[arg(long, default_value = "http://localhost:8002")]
+        gis_url: String,
+        /// Per-request timeout in seconds
+        #[arg(long, default_value = "5")]
+        timeout: u64,
+        /// Print machine-readable JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
     /// Start services
     Start,
     /// Stop services
     Stop,
-    /// View logs
-    Logs,
+    /// Tail and filter service logs
+    Logs {
+        /// Only show these services (repeatable; default: all)
+        #[arg(long, value_enum)]
+        service: Vec<logs::Service>,
+        /// Minimum level to show
+        #[arg(long, value_enum)]
+        level: Option<logs::Level>,
+        /// Only show lines newer than this (e.g. 15m, 2h, 1d, 2024-03-01 or an RFC 3339 timestamp)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only show lines containing this text (case-insensitive)
+        #[arg(long)]
+        grep: Option<String>,
+        /// Number of matching lines to show before following
+        #[arg(short = 'n', long, default_value = "100")]
+        lines: usize,
+        /// Keep printing new lines as they are written
+        #[arg(short, long)]
+        follow: bool,
+        /// Read service control events from the Windows event log instead of log files
+        #[arg(long, conflicts_with = "follow")]
+        event_log: bool,
+        /// Installation directory containing the logs folder
+        #[arg(long, default_value = "C:\\Program Files\\TerraFusion Platform")]
+        install_dir: PathBuf,
+    },
+    /// Manage sync jobs through the gateway API
+    Sync {
+        #[command(subcommand)]
+        command: operations::SyncCommand,
+    },
+    /// Manage GIS export jobs through the gateway API
+    Export {
+        #[command(subcommand)]
+        command: operations::ExportCommand,
+    },
 }
 
-fn main() -> Result<()> {
+#[tokio::main]
+async fn main() -> Result<()> {
     let cli = Cli::parse();
-    
+
     match cli.command {
-        Commands::Status => {
-            println!("TerraFusion Platform Status: Running");
-            println!("Services: All systems operational");
+        Commands::Status { sync_url, gis_url, timeout, json } => {
+            let components = vec![
+                status::Component {
+                    name: "api_gateway",
+                    health_url: format!("{}/system/health", cli.gateway_url.trim_end_matches('/')),
+                },
+                status::Component {
+                    name: "sync_service",
+                    health_url: format!("{}/system/health", sync_url.trim_end_matches('/')),
+                },
+                status::Component {
+                    name: "gis_export",
+                    health_url: format!("{}/system/health", gis_url.trim_end_matches('/')),
+                },
+            ];
+            let report = status::collect(components, Duration::from_secs(timeout)).await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                status::print_table(&report);
+            }
+
+            if !report.is_healthy() {
+                std::process::exit(1);
+            }
         },
         Commands::Start => {
             println!("Starting TerraFusion Platform services...");
@@ -35,10 +122,31 @@ fn main() -> Result<()> {
         Commands::Stop => {
             println!("Stopping TerraFusion Platform services...");
         },
-        Commands::Logs => {
-            println!("Displaying TerraFusion Platform logs...");
+        Commands::Logs { service, level, since, grep, lines, follow, event_log, install_dir } => {
+            let filter = logs::LogFilter {
+                min_level: level,
+                since: since
+                    .map(|since| logs::parse_since(&since, chrono::Utc::now()))
+                    .transpose()?,
+                pattern: grep,
+            };
+
+            if event_log {
+                logs::event_log(&filter, lines)?;
+            } else {
+                let services = if service.is_empty() { logs::Service::ALL.to_vec() } else { service };
+                logs::tail(&install_dir.join("logs"), &services, &filter, lines, follow).await?;
+            }
+        },
+        Commands::Sync { command } => {
+            let client = api::GatewayClient::new(&cli.gateway_url, cli.api_key)?;
+            operations::sync(&client, command).await?;
+        },
+        Commands::Export { command } => {
+            let client = api::GatewayClient::new(&cli.gateway_url, cli.api_key)?;
+            operations::export(&client, command).await?;
         },
     }
-    
+
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,256 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::api::GatewayClient;
+
+#[derive(Subcommand)]
+pub enum SyncCommand {
+    /// List recent sync jobs
+    List {
+        /// Only show jobs with this status
+        #[arg(long)]
+        status: Option<String>,
+        /// Maximum number of jobs to show
+        #[arg(long, default_value = "20")]
+        limit: u32,
+        /// Print raw JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Start a sync job for a sync pair
+    Start {
+        /// Sync pair ID
+        pair_id: String,
+        /// Sync type
+        #[arg(long, default_value = "incremental", value_parser = ["incremental", "full"])]
+        sync_type: String,
+    },
+    /// Cancel a running sync job
+    Cancel {
+        job_id: String,
+    },
+    /// Show details of a sync job
+    Show {
+        job_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ExportCommand {
+    /// List GIS export jobs
+    List {
+        /// Only show jobs with this status
+        #[arg(long)]
+        status: Option<String>,
+        /// Maximum number of jobs to show
+        #[arg(long, default_value = "20")]
+        limit: u32,
+        /// Print raw JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Create a GIS export job
+    Create {
+        /// County ID
+        #[arg(long)]
+        county: String,
+        /// Export format (e.g. shapefile, geojson, kml, geopackage)
+        #[arg(long, default_value = "geojson")]
+        format: String,
+        /// Layers to export (repeatable)
+        #[arg(long = "layer", required = true)]
+        layers: Vec<String>,
+        /// GeoJSON file containing the area-of-interest geometry
+        #[arg(long)]
+        area: Option<PathBuf>,
+        /// Wait for the job to finish
+        #[arg(long)]
+        wait: bool,
+        /// Download the result to this path once finished (implies --wait)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Download a finished GIS export
+    Download {
+        job_id: String,
+        /// Output file or directory (default: server-provided file name)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Run a `sync` subcommand
+pub async fn sync(client: &GatewayClient, command: SyncCommand) -> Result<()> {
+    match command {
+        SyncCommand::List { status, limit, json } => {
+            let mut query = vec![("limit", limit.to_string())];
+            if let Some(status) = status {
+                query.push(("status", status));
+            }
+            let body = client.get("/sync/jobs", &query).await?;
+            if json {
+                print_json(&body)?;
+            } else {
+                print_table(&body, &["id", "pair_id", "status", "started_at", "records_processed"]);
+            }
+        }
+        SyncCommand::Start { pair_id, sync_type } => {
+            let body = client
+                .post("/sync/jobs", Some(&json!({ "pair_id": pair_id, "sync_type": sync_type })))
+                .await?;
+            match job_id(&body) {
+                Some(id) => println!("Started sync job {}", id),
+                None => print_json(&body)?,
+            }
+        }
+        SyncCommand::Cancel { job_id } => {
+            client.post(&format!("/sync/jobs/{}/cancel", job_id), None).await?;
+            println!("Cancellation requested for sync job {}", job_id);
+        }
+        SyncCommand::Show { job_id } => {
+            print_json(&client.get(&format!("/sync/jobs/{}", job_id), &[]).await?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Run an `export` subcommand
+pub async fn export(client: &GatewayClient, command: ExportCommand) -> Result<()> {
+    match command {
+        ExportCommand::List { status, limit, json } => {
+            let mut query = vec![("limit", limit.to_string())];
+            if let Some(status) = status {
+                query.push(("status", status));
+            }
+            let body = client.get("/gis-export/jobs", &query).await?;
+            if json {
+                print_json(&body)?;
+            } else {
+                print_table(&body, &["id", "county_id", "export_format", "status", "created_at"]);
+            }
+        }
+        ExportCommand::Create { county, format, layers, area, wait, output } => {
+            let area_of_interest = match area {
+                Some(path) => {
+                    let text = tokio::fs::read_to_string(&path)
+                        .await
+                        .with_context(|| format!("Failed to read {}", path.display()))?;
+                    Some(serde_json::from_str::<Value>(&text).context("Area of interest is not valid GeoJSON")?)
+                }
+                None => None,
+            };
+
+            let body = client
+                .post("/gis-export/jobs", Some(&json!({
+                    "county_id": county,
+                    "export_format": format,
+                    "layers": layers,
+                    "area_of_interest": area_of_interest,
+                    "parameters": {},
+                })))
+                .await?;
+            let id = job_id(&body).context("Gateway did not return a job ID")?;
+            println!("Created export job {}", id);
+
+            if wait || output.is_some() {
+                let status = wait_for_export(client, &id).await?;
+                println!("Export job {} {}", id, status);
+                if status != "completed" {
+                    anyhow::bail!("Export job {} did not complete", id);
+                }
+                if let Some(output) = output {
+                    let path = download_export(client, &id, Some(output)).await?;
+                    println!("Saved {}", path.display());
+                }
+            }
+        }
+        ExportCommand::Download { job_id, output } => {
+            let path = download_export(client, &job_id, output).await?;
+            println!("Saved {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+async fn download_export(client: &GatewayClient, job_id: &str, output: Option<PathBuf>) -> Result<PathBuf> {
+    client
+        .download(&format!("/gis-export/download/{}", job_id), output.as_deref(), &format!("{}.zip", job_id))
+        .await
+}
+
+/// Poll an export job until it reaches a terminal status
+async fn wait_for_export(client: &GatewayClient, job_id: &str) -> Result<String> {
+    loop {
+        let body = client.get(&format!("/gis-export/jobs/{}", job_id), &[]).await?;
+        let status = body
+            .get("status")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown")
+            .to_lowercase();
+
+        if matches!(status.as_str(), "completed" | "failed" | "cancelled") {
+            return Ok(status);
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
+fn job_id(body: &Value) -> Option<String> {
+    ["id", "job_id", "operation_id"].iter().find_map(|key| match body.get(*key)? {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    })
+}
+
+fn print_json(body: &Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(body)?);
+    Ok(())
+}
+
+/// Print a list response as a table of the given columns
+///
+/// Accepts a bare array or the usual `jobs`/`operations`/`items`/`data` envelopes.
+fn print_table(body: &Value, columns: &[&str]) {
+    let rows = body.as_array().or_else(|| {
+        ["jobs", "operations", "exports", "items", "data"]
+            .iter()
+            .find_map(|key| body.get(*key).and_then(Value::as_array))
+    });
+    let Some(rows) = rows else {
+        println!("{}", body);
+        return;
+    };
+    if rows.is_empty() {
+        println!("No jobs found");
+        return;
+    }
+
+    let cell = |row: &Value, column: &str| match row.get(column) {
+        None | Some(Value::Null) => "-".to_string(),
+        Some(Value::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+    };
+    let widths: Vec<usize> = columns
+        .iter()
+        .map(|column| rows.iter().map(|row| cell(row, column).len()).max().unwrap_or(0).max(column.len()))
+        .collect();
+
+    let header: Vec<String> = columns
+        .iter()
+        .zip(&widths)
+        .map(|(column, width)| format!("{:<width$}", column.to_uppercase(), width = width))
+        .collect();
+    println!("{}", header.join("  "));
+    for row in rows {
+        let line: Vec<String> = columns
+            .iter()
+            .zip(&widths)
+            .map(|(column, width)| format!("{:<width$}", cell(row, column), width = width))
+            .collect();
+        println!("{}", line.join("  "));
+    }
+}
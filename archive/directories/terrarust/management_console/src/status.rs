@@ -0,0 +1,216 @@
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Windows service registered by the service wrapper
+pub const SERVICE_NAME: &str = "TerraFusionPlatform";
+
+/// A platform component and the health endpoint it exposes
+pub struct Component {
+    pub name: &'static str,
+    pub health_url: String,
+}
+
+/// Health of one component as reported by its endpoint
+#[derive(Debug, Serialize)]
+pub struct ComponentStatus {
+    pub name: String,
+    pub status: String,
+    pub version: Option<String>,
+    pub uptime_seconds: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+impl ComponentStatus {
+    pub fn is_up(&self) -> bool {
+        self.status == "up"
+    }
+}
+
+/// Full status report printed by `terrafusion-console status`
+#[derive(Debug, Serialize)]
+pub struct StatusReport {
+    /// State of the Windows service, when running on Windows
+    pub service_state: Option<String>,
+    pub components: Vec<ComponentStatus>,
+}
+
+impl StatusReport {
+    pub fn is_healthy(&self) -> bool {
+        self.components.iter().all(ComponentStatus::is_up)
+    }
+}
+
+/// Query every component and the Windows service state
+pub async fn collect(components: Vec<Component>, timeout: Duration) -> Result<StatusReport> {
+    let client = reqwest::Client::builder().timeout(timeout).build()?;
+
+    let mut statuses = Vec::with_capacity(components.len());
+    for component in components {
+        statuses.push(probe(&client, component).await);
+    }
+
+    Ok(StatusReport {
+        service_state: windows_service_state(),
+        components: statuses,
+    })
+}
+
+async fn probe(client: &reqwest::Client, component: Component) -> ComponentStatus {
+    let mut status = ComponentStatus {
+        name: component.name.to_string(),
+        status: "down".to_string(),
+        version: None,
+        uptime_seconds: None,
+        last_error: None,
+    };
+
+    let response = match client.get(&component.health_url).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            status.last_error = Some(format!("Unreachable at {}: {}", component.health_url, e));
+            return status;
+        }
+    };
+
+    let http_status = response.status();
+    let body: Value = match response.json().await {
+        Ok(body) => body,
+        Err(e) => {
+            status.last_error = Some(format!("HTTP {}: invalid health response ({})", http_status, e));
+            return status;
+        }
+    };
+
+    apply_health_body(&mut status, &body);
+    if !http_status.is_success() && status.last_error.is_none() {
+        status.last_error = Some(format!("HTTP {}", http_status));
+    }
+    status
+}
+
+/// Read status, version, uptime and the first failing sub-check from a health body
+///
+/// The gateway reports `healthy`/`unhealthy`, the backend services report the shared
+/// `HealthCheck` model with `up`/`down`/`degraded` and per-dependency entries.
+fn apply_health_body(status: &mut ComponentStatus, body: &Value) {
+    status.status = match body.get("status").and_then(Value::as_str) {
+        Some("healthy") | Some("up") => "up",
+        Some("degraded") => "degraded",
+        Some("unknown") | None => "unknown",
+        Some(_) => "down",
+    }
+    .to_string();
+    status.version = body.get("version").and_then(Value::as_str).map(str::to_string);
+    status.uptime_seconds = body.get("uptime_seconds").and_then(Value::as_u64);
+
+    status.last_error = body
+        .get("services")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .find(|service| service.get("status").and_then(Value::as_str) != Some("up"))
+        .map(|service| {
+            let name = service.get("name").and_then(Value::as_str).unwrap_or("dependency");
+            let detail = service
+                .get("message")
+                .and_then(Value::as_str)
+                .or_else(|| service.get("status").and_then(Value::as_str))
+                .unwrap_or("unknown");
+            format!("{}: {}", name, detail)
+        })
+        .or_else(|| body.get("error").and_then(Value::as_str).map(str::to_string));
+}
+
+/// Current state of the Windows service from `sc query`
+#[cfg(windows)]
+pub fn windows_service_state() -> Option<String> {
+    let output = std::process::Command::new("sc")
+        .args(["query", SERVICE_NAME])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return Some("NOT INSTALLED".to_string());
+    }
+
+    // Line looks like: "        STATE              : 4  RUNNING"
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.trim_start().starts_with("STATE"))
+        .and_then(|line| line.split_whitespace().last())
+        .map(str::to_string)
+}
+
+#[cfg(not(windows))]
+pub fn windows_service_state() -> Option<String> {
+    None
+}
+
+/// Print the report as an aligned table
+pub fn print_table(report: &StatusReport) {
+    if let Some(state) = &report.service_state {
+        println!("Windows service {}: {}", SERVICE_NAME, state);
+        println!();
+    }
+
+    println!("{:<14} {:<10} {:<10} {:<12} {}", "COMPONENT", "STATUS", "VERSION", "UPTIME", "LAST ERROR");
+    for component in &report.components {
+        println!(
+            "{:<14} {:<10} {:<10} {:<12} {}",
+            component.name,
+            component.status,
+            component.version.as_deref().unwrap_or("-"),
+            component.uptime_seconds.map(format_uptime).unwrap_or_else(|| "-".to_string()),
+            component.last_error.as_deref().unwrap_or("-"),
+        );
+    }
+}
+
+/// Render seconds as a compact `1d 2h 3m` string
+pub fn format_uptime(seconds: u64) -> String {
+    let days = seconds / 86_400;
+    let hours = (seconds % 86_400) / 3_600;
+    let minutes = (seconds % 3_600) / 60;
+
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_health_body_reports_failing_dependency() {
+        let mut status = ComponentStatus {
+            name: "sync".to_string(),
+            status: "down".to_string(),
+            version: None,
+            uptime_seconds: None,
+            last_error: None,
+        };
+        apply_health_body(&mut status, &json!({
+            "status": "degraded",
+            "version": "0.1.0",
+            "services": [
+                { "name": "database", "status": "up" },
+                { "name": "scheduler", "status": "down", "message": null }
+            ]
+        }));
+
+        assert_eq!(status.status, "degraded");
+        assert_eq!(status.version.as_deref(), Some("0.1.0"));
+        assert_eq!(status.last_error.as_deref(), Some("scheduler: down"));
+        assert_eq!(format_uptime(90_061), "1d 1h 1m");
+    }
+}
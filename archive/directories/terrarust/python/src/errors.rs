@@ -0,0 +1,28 @@
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::PyErr;
+use terrafusion_client::ClientError;
+
+create_exception!(terrafusion, TerraFusionError, PyException, "Base class of every error the client raises.");
+create_exception!(
+    terrafusion,
+    ApiError,
+    TerraFusionError,
+    "The API answered with an error. Arguments: message, HTTP status, error type, correlation id."
+);
+create_exception!(terrafusion, NotFoundError, ApiError, "The sync pair, operation or export does not exist.");
+create_exception!(terrafusion, WaitTimeout, TerraFusionError, "A wait ended before the operation or export finished.");
+
+/// The Python exception for a client error
+pub fn to_py(error: ClientError) -> PyErr {
+    match error {
+        ClientError::Api { ref error, .. } if error.code == 404 => {
+            NotFoundError::new_err((error.message.clone(), error.code, error.error_type.clone(), error.correlation_id.clone()))
+        }
+        ClientError::Api { error, .. } => {
+            ApiError::new_err((error.message, error.code, error.error_type, error.correlation_id))
+        }
+        ClientError::Timeout(message) => WaitTimeout::new_err(message),
+        other => TerraFusionError::new_err(other.to_string()),
+    }
+}
@@ -0,0 +1,33 @@
+use pyo3::exceptions::PyImportError;
+use pyo3::prelude::*;
+use pyo3::types::IntoPyDict;
+use serde::Serialize;
+
+/// Timestamp columns converted to `datetime64[ns, UTC]`, so they sort and
+/// resample without parsing on the Python side
+pub const OPERATION_TIMES: &[&str] = &["start_time", "end_time", "created_at", "updated_at"];
+pub const EXPORT_TIMES: &[&str] = &["created_at", "completed_at"];
+pub const DIFF_TIMES: &[&str] = &["created_at"];
+
+/// A pandas DataFrame with one row per record. pandas is imported only
+/// here, so the rest of the module works without it.
+pub fn data_frame<T: Serialize>(py: Python<'_>, records: &[T], time_columns: &[&str]) -> PyResult<PyObject> {
+    let pandas = py
+        .import("pandas")
+        .map_err(|_| PyImportError::new_err("DataFrame helpers need pandas; install it with `pip install terrafusion[pandas]`"))?;
+    let records = pythonize::pythonize(py, records)?;
+    let frame = pandas.getattr("DataFrame")?.call_method1("from_records", (records,))?;
+
+    let columns = frame.getattr("columns")?;
+    for column in time_columns {
+        if columns.contains(*column)? {
+            let converted = pandas.call_method(
+                "to_datetime",
+                (frame.get_item(*column)?,),
+                Some([("utc", true)].into_py_dict(py)),
+            )?;
+            frame.set_item(*column, converted)?;
+        }
+    }
+    Ok(frame.into())
+}
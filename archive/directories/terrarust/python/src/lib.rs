@@ -0,0 +1,431 @@
+//! Python bindings for the TerraFusion public API client.
+//!
+//! Built with maturin into a `terrafusion` module for county data teams who
+//! script syncs and exports from Python or Jupyter:
+//!
+//! ```python
+//! import terrafusion
+//!
+//! client = terrafusion.Client("https://terrafusion.example.org", api_key="tfk_...")
+//! operation = client.start_sync("5b1f...", wait=True)
+//! df = client.operations_frame(status="FAILED", since="2024-03-01")
+//! ```
+//!
+//! Calls block the calling thread but release the GIL while waiting on the
+//! network. Results are plain dicts and lists; the `*_frame` methods return
+//! pandas DataFrames with parsed timestamps.
+
+mod errors;
+mod frames;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use futures_util::{StreamExt, TryStreamExt};
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use terrafusion_client::{
+    Credentials, ExportJob, ExportJobFilter, NewExportJob, PageStream, SyncOperation,
+    SyncOperationFilter, SyncStatus,
+};
+use tokio::io::AsyncWriteExt;
+use tokio::runtime::Runtime;
+use uuid::Uuid;
+
+use errors::{to_py, WaitTimeout};
+
+/// Connection to the TerraFusion API gateway.
+///
+/// `url` and `api_key` default to the `TERRAFUSION_URL` and
+/// `TERRAFUSION_API_KEY` environment variables.
+#[pyclass(module = "terrafusion")]
+struct Client {
+    inner: terrafusion_client::Client,
+    runtime: Arc<Runtime>,
+}
+
+#[pymethods]
+impl Client {
+    #[new]
+    #[pyo3(signature = (url = None, api_key = None, timeout = 30.0))]
+    fn new(url: Option<String>, api_key: Option<String>, timeout: f64) -> PyResult<Self> {
+        let url = match url {
+            Some(url) => url,
+            None => std::env::var("TERRAFUSION_URL").map_err(|_| PyValueError::new_err("Pass url or set TERRAFUSION_URL"))?,
+        };
+        let credentials = match api_key {
+            Some(key) => Credentials::api_key(&key),
+            None => Credentials::from_env(),
+        }
+        .map_err(to_py)?;
+        let inner = terrafusion_client::Client::builder(url)
+            .credentials(credentials)
+            .timeout(Duration::from_secs_f64(timeout))
+            .user_agent(format!("terrafusion-python/{}", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(to_py)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .map_err(|e| PyOSError::new_err(e.to_string()))?;
+        Ok(Self { inner, runtime: Arc::new(runtime) })
+    }
+
+    /// One page of sync pairs, numbered from 1
+    #[pyo3(signature = (page = 1, per_page = 50))]
+    fn sync_pairs(&self, py: Python<'_>, page: u32, per_page: u32) -> PyResult<PyObject> {
+        let sync = self.inner.sync();
+        let list = self.block(py, async move { sync.list_sync_pairs(page, per_page).await })?;
+        to_python(py, &list.items)
+    }
+
+    fn get_sync_pair(&self, py: Python<'_>, pair_id: &str) -> PyResult<PyObject> {
+        let (sync, id) = (self.inner.sync(), uuid(pair_id)?);
+        to_python(py, &self.block(py, async move { sync.get_sync_pair(id).await })?)
+    }
+
+    /// Start syncing a pair. `parameters` are handed to its connectors.
+    /// With `wait=True` this returns the finished operation instead of the
+    /// acknowledgement.
+    #[pyo3(signature = (pair_id, parameters = None, wait = false, interval = 2.0, timeout = None))]
+    fn start_sync(
+        &self,
+        py: Python<'_>,
+        pair_id: &str,
+        parameters: Option<&PyAny>,
+        wait: bool,
+        interval: f64,
+        timeout: Option<f64>,
+    ) -> PyResult<PyObject> {
+        let parameters: Option<Value> = parameters.map(pythonize::depythonize).transpose()?;
+        let (sync, id) = (self.inner.sync(), uuid(pair_id)?);
+        let started = self.block(py, async move { sync.start_operation(id, parameters).await })?;
+        if wait {
+            return to_python(py, &self.wait_operation(py, started.operation_id, interval, timeout)?);
+        }
+        to_python(py, &started)
+    }
+
+    fn get_operation(&self, py: Python<'_>, operation_id: &str) -> PyResult<PyObject> {
+        let (sync, id) = (self.inner.sync(), uuid(operation_id)?);
+        to_python(py, &self.block(py, async move { sync.get_operation(id).await })?)
+    }
+
+    /// Poll an operation until it finishes; raises `WaitTimeout` after
+    /// `timeout` seconds
+    #[pyo3(signature = (operation_id, interval = 2.0, timeout = None))]
+    fn wait_for_operation(&self, py: Python<'_>, operation_id: &str, interval: f64, timeout: Option<f64>) -> PyResult<PyObject> {
+        let operation = self.wait_operation(py, uuid(operation_id)?, interval, timeout)?;
+        to_python(py, &operation)
+    }
+
+    fn cancel_operation(&self, py: Python<'_>, operation_id: &str) -> PyResult<PyObject> {
+        let (sync, id) = (self.inner.sync(), uuid(operation_id)?);
+        to_python(py, &self.block(py, async move { sync.cancel_operation(id).await })?)
+    }
+
+    /// Sync operations, newest first. `since` and `until` are dates or
+    /// ISO 8601 timestamps bounding the start time.
+    #[pyo3(signature = (sync_pair_id = None, status = None, since = None, until = None, limit = None))]
+    fn operations(
+        &self,
+        py: Python<'_>,
+        sync_pair_id: Option<&str>,
+        status: Option<&str>,
+        since: Option<&str>,
+        until: Option<&str>,
+        limit: Option<usize>,
+    ) -> PyResult<PyObject> {
+        let operations = self.list_operations(py, sync_pair_id, status, since, until, limit)?;
+        to_python(py, &operations)
+    }
+
+    /// `operations()` as a pandas DataFrame
+    #[pyo3(signature = (sync_pair_id = None, status = None, since = None, until = None, limit = None))]
+    fn operations_frame(
+        &self,
+        py: Python<'_>,
+        sync_pair_id: Option<&str>,
+        status: Option<&str>,
+        since: Option<&str>,
+        until: Option<&str>,
+        limit: Option<usize>,
+    ) -> PyResult<PyObject> {
+        let operations = self.list_operations(py, sync_pair_id, status, since, until, limit)?;
+        frames::data_frame(py, &operations, frames::OPERATION_TIMES)
+    }
+
+    /// Records an operation compared, with their change type and outcome
+    #[pyo3(signature = (operation_id, limit = None))]
+    fn operation_diffs(&self, py: Python<'_>, operation_id: &str, limit: Option<usize>) -> PyResult<PyObject> {
+        let diffs = self.collect(py, self.inner.sync().operation_diffs(uuid(operation_id)?, 500), limit)?;
+        to_python(py, &diffs)
+    }
+
+    /// `operation_diffs()` as a pandas DataFrame
+    #[pyo3(signature = (operation_id, limit = None))]
+    fn diffs_frame(&self, py: Python<'_>, operation_id: &str, limit: Option<usize>) -> PyResult<PyObject> {
+        let diffs = self.collect(py, self.inner.sync().operation_diffs(uuid(operation_id)?, 500), limit)?;
+        frames::data_frame(py, &diffs, frames::DIFF_TIMES)
+    }
+
+    /// Create a GIS export and queue it. `area_of_interest` is a GeoJSON
+    /// geometry (dict), WKT string or WKB hex in WGS 84. Exports of counties
+    /// that review them first stay `PENDING_APPROVAL` until approved.
+    #[pyo3(signature = (
+        county_id, layers, area_of_interest, export_format = "geojson", username = None, as_of = None,
+        parameters = None, wait = false, timeout = None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn create_export(
+        &self,
+        py: Python<'_>,
+        county_id: String,
+        layers: Vec<String>,
+        area_of_interest: &PyAny,
+        export_format: &str,
+        username: Option<String>,
+        as_of: Option<String>,
+        parameters: Option<&PyAny>,
+        wait: bool,
+        timeout: Option<f64>,
+    ) -> PyResult<PyObject> {
+        let mut parameters: HashMap<String, Value> =
+            parameters.map(pythonize::depythonize).transpose()?.unwrap_or_default();
+        if let Some(as_of) = as_of {
+            parameters.insert("as_of".to_string(), Value::String(as_of));
+        }
+        let job = NewExportJob {
+            county_id,
+            username: username.unwrap_or_else(current_user),
+            export_format: export_format.to_string(),
+            area_of_interest: pythonize::depythonize(area_of_interest)?,
+            layers,
+            parameters,
+        };
+
+        let gis = self.inner.gis_export();
+        let job = self.block(py, async move {
+            let job = gis.create_job(&job).await?;
+            if job.status == "PENDING" {
+                gis.process_job(job.job_id).await?;
+            }
+            Ok(job)
+        })?;
+        if wait {
+            return to_python(py, &self.wait_export(py, job.job_id, 5.0, timeout)?);
+        }
+        to_python(py, &job)
+    }
+
+    fn get_export(&self, py: Python<'_>, job_id: &str) -> PyResult<PyObject> {
+        let (gis, id) = (self.inner.gis_export(), uuid(job_id)?);
+        to_python(py, &self.block(py, async move { gis.get_job(id).await })?)
+    }
+
+    /// Poll an export until it finishes; raises `WaitTimeout` after
+    /// `timeout` seconds
+    #[pyo3(signature = (job_id, interval = 5.0, timeout = None))]
+    fn wait_for_export(&self, py: Python<'_>, job_id: &str, interval: f64, timeout: Option<f64>) -> PyResult<PyObject> {
+        let job = self.wait_export(py, uuid(job_id)?, interval, timeout)?;
+        to_python(py, &job)
+    }
+
+    fn cancel_export(&self, py: Python<'_>, job_id: &str) -> PyResult<PyObject> {
+        let (gis, id) = (self.inner.gis_export(), uuid(job_id)?);
+        to_python(py, &self.block(py, async move { gis.cancel_job(id).await })?)
+    }
+
+    /// Save a completed export to `path` and return the path
+    fn download_export(&self, py: Python<'_>, job_id: &str, path: PathBuf) -> PyResult<PathBuf> {
+        let (gis, id) = (self.inner.gis_export(), uuid(job_id)?);
+        let write_error = |e: std::io::Error| PyOSError::new_err(format!("Failed to write {}: {}", path.display(), e));
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                let mut body = Box::pin(gis.download_stream(id).await.map_err(to_py)?);
+                let mut file = tokio::fs::File::create(&path).await.map_err(write_error)?;
+                while let Some(chunk) = body.try_next().await.map_err(to_py)? {
+                    file.write_all(&chunk).await.map_err(write_error)?;
+                }
+                file.flush().await.map_err(write_error)
+            })
+        })?;
+        Ok(path)
+    }
+
+    /// Export jobs, newest first
+    #[pyo3(signature = (county_id = None, status = None, username = None, limit = None))]
+    fn exports(
+        &self,
+        py: Python<'_>,
+        county_id: Option<String>,
+        status: Option<String>,
+        username: Option<String>,
+        limit: Option<usize>,
+    ) -> PyResult<PyObject> {
+        let jobs = self.list_exports(py, county_id, status, username, limit)?;
+        to_python(py, &jobs)
+    }
+
+    /// `exports()` as a pandas DataFrame
+    #[pyo3(signature = (county_id = None, status = None, username = None, limit = None))]
+    fn exports_frame(
+        &self,
+        py: Python<'_>,
+        county_id: Option<String>,
+        status: Option<String>,
+        username: Option<String>,
+        limit: Option<usize>,
+    ) -> PyResult<PyObject> {
+        let jobs = self.list_exports(py, county_id, status, username, limit)?;
+        frames::data_frame(py, &jobs, frames::EXPORT_TIMES)
+    }
+}
+
+impl Client {
+    /// Run a client call to completion without holding the GIL
+    fn block<T, F>(&self, py: Python<'_>, call: F) -> PyResult<T>
+    where
+        T: Send,
+        F: Future<Output = terrafusion_client::Result<T>> + Send,
+    {
+        py.allow_threads(|| self.runtime.block_on(call)).map_err(to_py)
+    }
+
+    /// The first `limit` items of a list, or all of them
+    fn collect<T: Send>(&self, py: Python<'_>, items: PageStream<'static, T>, limit: Option<usize>) -> PyResult<Vec<T>> {
+        self.block(py, async move {
+            match limit {
+                Some(limit) => items.take(limit).try_collect().await,
+                None => items.try_collect().await,
+            }
+        })
+    }
+
+    fn list_operations(
+        &self,
+        py: Python<'_>,
+        sync_pair_id: Option<&str>,
+        status: Option<&str>,
+        since: Option<&str>,
+        until: Option<&str>,
+        limit: Option<usize>,
+    ) -> PyResult<Vec<SyncOperation>> {
+        let filter = SyncOperationFilter {
+            sync_pair_id: sync_pair_id.map(uuid).transpose()?,
+            status: status.map(sync_status).transpose()?,
+            from_date: since.map(timestamp).transpose()?,
+            to_date: until.map(timestamp).transpose()?,
+            per_page: Some(100),
+        };
+        self.collect(py, self.inner.sync().operations(filter), limit)
+    }
+
+    fn list_exports(
+        &self,
+        py: Python<'_>,
+        county_id: Option<String>,
+        status: Option<String>,
+        username: Option<String>,
+        limit: Option<usize>,
+    ) -> PyResult<Vec<ExportJob>> {
+        let filter = ExportJobFilter { county_id, status, username, per_page: Some(100) };
+        self.collect(py, self.inner.gis_export().jobs(filter), limit)
+    }
+
+    // The waits poll from Python's side of the GIL so Ctrl-C in a notebook
+    // stops them between polls
+
+    fn wait_operation(&self, py: Python<'_>, id: Uuid, interval: f64, timeout: Option<f64>) -> PyResult<SyncOperation> {
+        let started = Instant::now();
+        loop {
+            let sync = self.inner.sync();
+            let operation = self.block(py, async move { sync.get_operation(id).await })?;
+            if operation.status.is_finished() {
+                return Ok(operation);
+            }
+            self.pause(py, started, interval, timeout, || {
+                format!("Sync operation {} still {} after {}s", id, operation.status.as_str(), started.elapsed().as_secs())
+            })?;
+        }
+    }
+
+    fn wait_export(&self, py: Python<'_>, id: Uuid, interval: f64, timeout: Option<f64>) -> PyResult<ExportJob> {
+        let started = Instant::now();
+        loop {
+            let gis = self.inner.gis_export();
+            let job = self.block(py, async move { gis.get_job(id).await })?;
+            if job.is_finished() {
+                return Ok(job);
+            }
+            self.pause(py, started, interval, timeout, || {
+                format!("Export {} still {} after {}s", id, job.status, started.elapsed().as_secs())
+            })?;
+        }
+    }
+
+    fn pause(
+        &self,
+        py: Python<'_>,
+        started: Instant,
+        interval: f64,
+        timeout: Option<f64>,
+        describe: impl FnOnce() -> String,
+    ) -> PyResult<()> {
+        let interval = Duration::from_secs_f64(interval);
+        if let Some(timeout) = timeout {
+            if started.elapsed() + interval > Duration::from_secs_f64(timeout) {
+                return Err(WaitTimeout::new_err(describe()));
+            }
+        }
+        py.allow_threads(|| std::thread::sleep(interval));
+        py.check_signals()
+    }
+}
+
+fn to_python<T: Serialize + ?Sized>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    Ok(pythonize::pythonize(py, value)?)
+}
+
+fn uuid(id: &str) -> PyResult<Uuid> {
+    Uuid::parse_str(id).map_err(|_| PyValueError::new_err(format!("{} is not a valid id", id)))
+}
+
+fn sync_status(status: &str) -> PyResult<SyncStatus> {
+    serde_json::from_value(Value::String(status.to_uppercase()))
+        .map_err(|_| PyValueError::new_err(format!("Unknown sync status {}", status)))
+}
+
+/// A date (midnight UTC) or an RFC 3339 timestamp
+fn timestamp(value: &str) -> PyResult<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).expect("midnight exists").and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|_| PyValueError::new_err(format!("{} is not a date or ISO 8601 timestamp", value)))
+}
+
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "terrafusion-python".to_string())
+}
+
+#[pymodule]
+fn terrafusion(py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add_class::<Client>()?;
+    module.add("TerraFusionError", py.get_type::<errors::TerraFusionError>())?;
+    module.add("ApiError", py.get_type::<errors::ApiError>())?;
+    module.add("NotFoundError", py.get_type::<errors::NotFoundError>())?;
+    module.add("WaitTimeout", py.get_type::<errors::WaitTimeout>())?;
+    module.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
@@ -1,18 +1,98 @@
 use anyhow::Result;
+use clap::{Parser, Subcommand};
 use log::info;
+use std::io::Write;
+use std::path::PathBuf;
+
+#[cfg(windows)]
+mod service;
+mod supervisor;
+
+use supervisor::{platform_children, BackoffPolicy, Supervisor};
+
+#[derive(Parser)]
+#[command(name = "terrafusion-service")]
+#[command(about = "Windows service wrapper for TerraFusion Platform")]
+#[command(version = "1.0.0")]
+struct Cli {
+    /// Installation directory
+    #[arg(long, default_value = "C:\\Program Files\\TerraFusion Platform")]
+    install_dir: PathBuf,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Register the Windows service (auto-start)
+    Install,
+    /// Stop and remove the Windows service
+    Uninstall,
+    /// Service entry point, invoked by the Service Control Manager
+    Run,
+    /// Supervise the services in the foreground until Ctrl+C
+    Console,
+}
 
 fn main() -> Result<()> {
-    env_logger::init();
-    
+    let cli = Cli::parse();
+    init_logging(&cli);
+
     info!("TerraFusion Platform Service Wrapper starting...");
-    
-    // In a real implementation, this would:
-    // 1. Register as a Windows service
-    // 2. Start and manage the TerraFusion microservices
-    // 3. Handle service lifecycle events
-    
-    println!("TerraFusion Platform Service Wrapper v1.0.0");
-    println!("Service management functionality ready");
-    
-    Ok(())
-}
\ No newline at end of file
+
+    match cli.command {
+        #[cfg(windows)]
+        Commands::Install => service::install(&cli.install_dir),
+        #[cfg(windows)]
+        Commands::Uninstall => service::uninstall(),
+        #[cfg(windows)]
+        Commands::Run => service::run(cli.install_dir),
+        #[cfg(not(windows))]
+        Commands::Install | Commands::Uninstall | Commands::Run => {
+            anyhow::bail!("Windows service management is only available on Windows; use `console` instead")
+        }
+        Commands::Console => run_console(cli.install_dir),
+    }
+}
+
+/// Log to stderr in console mode and to the wrapper log file under the SCM
+fn init_logging(cli: &Cli) {
+    let mut builder = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+
+    if matches!(cli.command, Commands::Run) {
+        let log_dir = cli.install_dir.join("logs");
+        let log_file = std::fs::create_dir_all(&log_dir).and_then(|_| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_dir.join("terrafusion-service.log"))
+        });
+        if let Ok(file) = log_file {
+            builder.target(env_logger::Target::Pipe(Box::new(file)));
+        }
+    }
+
+    builder.format(|buf, record| {
+        writeln!(buf, "{} [{}] {}", buf.timestamp(), record.level(), record.args())
+    });
+    builder.init();
+}
+
+/// Run the supervisor attached to the terminal, for debugging and non-Windows hosts
+fn run_console(install_dir: PathBuf) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+
+    runtime.block_on(async {
+        let mut supervisor = Supervisor::new(install_dir.clone(), BackoffPolicy::default());
+        supervisor.start(platform_children(&install_dir))?;
+
+        info!("Supervising platform services; press Ctrl+C to stop");
+        tokio::signal::ctrl_c().await?;
+
+        supervisor.shutdown().await;
+        Ok(())
+    })
+}
@@ -0,0 +1,202 @@
+//! Windows Service Control Manager integration
+
+use anyhow::{Context, Result};
+use log::{error, info};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_dispatcher};
+
+use crate::supervisor::{platform_children, BackoffPolicy, Supervisor};
+
+/// Name registered with the Service Control Manager
+pub const SERVICE_NAME: &str = "TerraFusionPlatform";
+const SERVICE_DISPLAY_NAME: &str = "TerraFusion Platform";
+const SERVICE_DESCRIPTION: &str =
+    "Runs the TerraFusion API gateway, sync service and GIS export service";
+const SERVICE_TYPE: ServiceType = ServiceType::OWN_PROCESS;
+
+/// Install directory handed from the command line to the service entry point
+static INSTALL_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Register the wrapper with the Service Control Manager
+pub fn install(install_dir: &PathBuf) -> Result<()> {
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )
+    .context("Failed to connect to the Service Control Manager")?;
+
+    let executable_path = std::env::current_exe().context("Failed to locate service executable")?;
+    let service_info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from(SERVICE_DISPLAY_NAME),
+        service_type: SERVICE_TYPE,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path,
+        launch_arguments: vec![
+            OsString::from("--install-dir"),
+            install_dir.as_os_str().to_owned(),
+            OsString::from("run"),
+        ],
+        dependencies: vec![],
+        account_name: None,
+        account_password: None,
+    };
+
+    let service = manager
+        .create_service(&service_info, ServiceAccess::CHANGE_CONFIG)
+        .context("Failed to create service")?;
+    service
+        .set_description(SERVICE_DESCRIPTION)
+        .context("Failed to set service description")?;
+
+    info!("✅ Installed {} service", SERVICE_NAME);
+    Ok(())
+}
+
+/// Stop the service if it is running and remove it
+pub fn uninstall() -> Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .context("Failed to connect to the Service Control Manager")?;
+    let service = manager
+        .open_service(
+            SERVICE_NAME,
+            ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+        )
+        .context("Failed to open service")?;
+
+    if service.query_status()?.current_state != ServiceState::Stopped {
+        info!("Stopping {} before removal...", SERVICE_NAME);
+        service.stop().context("Failed to stop service")?;
+
+        for _ in 0..30 {
+            if service.query_status()?.current_state == ServiceState::Stopped {
+                break;
+            }
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    service.delete().context("Failed to delete service")?;
+    info!("✅ Uninstalled {} service", SERVICE_NAME);
+    Ok(())
+}
+
+/// Hand the process over to the Service Control Manager
+///
+/// Blocks until the service is stopped. Must be launched by the SCM, not from a console.
+pub fn run(install_dir: PathBuf) -> Result<()> {
+    let _ = INSTALL_DIR.set(install_dir);
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+        .context("Failed to start service dispatcher (is this running under the SCM?)")
+}
+
+define_windows_service!(ffi_service_main, service_main);
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        error!("Service failed: {:#}", e);
+    }
+}
+
+/// Control requests forwarded from the SCM handler thread
+enum ControlRequest {
+    Stop,
+    Pause,
+    Continue,
+}
+
+fn run_service() -> Result<()> {
+    let install_dir = INSTALL_DIR.get().cloned().context("Install directory not set")?;
+    let (control_tx, mut control_rx) = mpsc::unbounded_channel();
+
+    let event_handler = move |control_event| -> ServiceControlHandlerResult {
+        let request = match control_event {
+            ServiceControl::Interrogate => return ServiceControlHandlerResult::NoError,
+            ServiceControl::Stop | ServiceControl::Shutdown => ControlRequest::Stop,
+            ServiceControl::Pause => ControlRequest::Pause,
+            ServiceControl::Continue => ControlRequest::Continue,
+            _ => return ServiceControlHandlerResult::NotImplemented,
+        };
+        let _ = control_tx.send(request);
+        ServiceControlHandlerResult::NoError
+    };
+
+    let status_handle = service_control_handler::register(SERVICE_NAME, event_handler)
+        .context("Failed to register service control handler")?;
+    set_state(&status_handle, ServiceState::StartPending)?;
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .context("Failed to build runtime")?;
+
+    let result = runtime.block_on(async {
+        let mut supervisor = Supervisor::new(install_dir.clone(), BackoffPolicy::default());
+        supervisor.start(platform_children(&install_dir))?;
+        set_state(&status_handle, ServiceState::Running)?;
+
+        while let Some(request) = control_rx.recv().await {
+            match request {
+                ControlRequest::Stop => break,
+                ControlRequest::Pause => {
+                    set_state(&status_handle, ServiceState::PausePending)?;
+                    supervisor.pause();
+                    set_state(&status_handle, ServiceState::Paused)?;
+                }
+                ControlRequest::Continue => {
+                    set_state(&status_handle, ServiceState::ContinuePending)?;
+                    supervisor.resume();
+                    set_state(&status_handle, ServiceState::Running)?;
+                }
+            }
+        }
+
+        set_state(&status_handle, ServiceState::StopPending)?;
+        supervisor.shutdown().await;
+        Ok::<_, anyhow::Error>(())
+    });
+
+    let exit_code = if result.is_ok() { 0 } else { 1 };
+    status_handle.set_service_status(status(ServiceState::Stopped, ServiceExitCode::Win32(exit_code)))?;
+    result
+}
+
+fn set_state(handle: &ServiceStatusHandle, state: ServiceState) -> Result<()> {
+    handle
+        .set_service_status(status(state, ServiceExitCode::Win32(0)))
+        .context("Failed to report service status")
+}
+
+fn status(current_state: ServiceState, exit_code: ServiceExitCode) -> ServiceStatus {
+    let controls_accepted = match current_state {
+        ServiceState::Running | ServiceState::Paused => {
+            ServiceControlAccept::STOP | ServiceControlAccept::PAUSE_CONTINUE | ServiceControlAccept::SHUTDOWN
+        }
+        _ => ServiceControlAccept::empty(),
+    };
+    let wait_hint = match current_state {
+        ServiceState::StartPending | ServiceState::StopPending => Duration::from_secs(30),
+        _ => Duration::default(),
+    };
+
+    ServiceStatus {
+        service_type: SERVICE_TYPE,
+        current_state,
+        controls_accepted,
+        exit_code,
+        checkpoint: 0,
+        wait_hint,
+        process_id: None,
+    }
+}
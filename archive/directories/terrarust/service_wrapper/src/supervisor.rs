@@ -0,0 +1,296 @@
+use anyhow::{Context, Result};
+use log::{error, info, warn};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Desired state of the supervised services
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunState {
+    Running,
+    Paused,
+    Stopping,
+}
+
+/// A platform binary managed by the supervisor
+#[derive(Debug, Clone)]
+pub struct ChildSpec {
+    pub name: &'static str,
+    pub executable: PathBuf,
+    pub args: Vec<String>,
+}
+
+/// Restart delay policy for crashed children
+#[derive(Debug, Clone, Copy)]
+pub struct BackoffPolicy {
+    pub initial: Duration,
+    pub max: Duration,
+    /// A child that stays up this long is considered healthy again
+    pub reset_after: Duration,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+            reset_after: Duration::from_secs(120),
+        }
+    }
+}
+
+impl BackoffPolicy {
+    /// Delay before the next restart after `failures` consecutive crashes
+    pub fn delay(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(16);
+        self.initial
+            .checked_mul(1u32 << exponent)
+            .unwrap_or(self.max)
+            .min(self.max)
+    }
+}
+
+/// The gateway, sync and GIS export binaries under an install directory
+pub fn platform_children(install_dir: &Path) -> Vec<ChildSpec> {
+    let bin_dir = install_dir.join("bin");
+    let suffix = std::env::consts::EXE_SUFFIX;
+
+    ["terrafusion-api-gateway", "terrafusion-sync-service", "terrafusion-gis-export"]
+        .into_iter()
+        .map(|name| ChildSpec {
+            name,
+            executable: bin_dir.join(format!("{}{}", name, suffix)),
+            args: Vec::new(),
+        })
+        .collect()
+}
+
+/// Supervises the platform child processes
+pub struct Supervisor {
+    install_dir: PathBuf,
+    policy: BackoffPolicy,
+    state: watch::Sender<RunState>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    pub fn new(install_dir: PathBuf, policy: BackoffPolicy) -> Self {
+        let (state, _) = watch::channel(RunState::Running);
+        Self {
+            install_dir,
+            policy,
+            state,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Start supervising every child whose binary is installed
+    pub fn start(&mut self, children: Vec<ChildSpec>) -> Result<()> {
+        let environment = load_environment(&self.install_dir);
+        let log_dir = self.install_dir.join("logs");
+        std::fs::create_dir_all(&log_dir)
+            .with_context(|| format!("Failed to create log directory {}", log_dir.display()))?;
+
+        for spec in children {
+            if !spec.executable.exists() {
+                warn!("Skipping {}: {} not found", spec.name, spec.executable.display());
+                continue;
+            }
+
+            let launcher = Launcher {
+                spec,
+                working_dir: self.install_dir.clone(),
+                log_dir: log_dir.clone(),
+                environment: environment.clone(),
+            };
+            let state = self.state.subscribe();
+            self.tasks.push(tokio::spawn(supervise_child(launcher, self.policy, state)));
+        }
+
+        if self.tasks.is_empty() {
+            anyhow::bail!("No service binaries found under {}", self.install_dir.join("bin").display());
+        }
+
+        Ok(())
+    }
+
+    /// Stop all children but keep the supervisor alive
+    pub fn pause(&self) {
+        info!("Pausing platform services");
+        self.state.send_replace(RunState::Paused);
+    }
+
+    /// Restart children after a pause
+    pub fn resume(&self) {
+        info!("Resuming platform services");
+        self.state.send_replace(RunState::Running);
+    }
+
+    /// Stop all children and wait for their supervision tasks to finish
+    pub async fn shutdown(self) {
+        info!("Stopping platform services");
+        self.state.send_replace(RunState::Stopping);
+        for task in self.tasks {
+            let _ = task.await;
+        }
+    }
+}
+
+/// Everything needed to (re)spawn one child
+struct Launcher {
+    spec: ChildSpec,
+    working_dir: PathBuf,
+    log_dir: PathBuf,
+    environment: Vec<(String, String)>,
+}
+
+impl Launcher {
+    fn spawn(&self) -> Result<Child> {
+        let log_path = self.log_dir.join(format!("{}.log", self.spec.name));
+        let stdout = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+            .with_context(|| format!("Failed to open {}", log_path.display()))?;
+        let stderr = stdout.try_clone()?;
+
+        Command::new(&self.spec.executable)
+            .args(&self.spec.args)
+            .current_dir(&self.working_dir)
+            .envs(self.environment.iter().cloned())
+            .stdin(Stdio::null())
+            .stdout(Stdio::from(stdout))
+            .stderr(Stdio::from(stderr))
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", self.spec.executable.display()))
+    }
+}
+
+/// Keep one child running until the supervisor stops
+async fn supervise_child(launcher: Launcher, policy: BackoffPolicy, mut state: watch::Receiver<RunState>) {
+    let name = launcher.spec.name;
+    let mut failures = 0u32;
+
+    loop {
+        if !wait_until_running(&mut state).await {
+            return;
+        }
+
+        let started = Instant::now();
+        let mut child = match launcher.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                failures += 1;
+                let delay = policy.delay(failures);
+                error!("{}: {:#}; retrying in {:?}", name, e, delay);
+                if !sleep_unless_stopping(delay, &mut state).await {
+                    return;
+                }
+                continue;
+            }
+        };
+        info!("Started {} (pid {:?})", name, child.id());
+
+        tokio::select! {
+            status = child.wait() => {
+                if started.elapsed() >= policy.reset_after {
+                    failures = 0;
+                }
+                failures += 1;
+                let delay = policy.delay(failures);
+                match status {
+                    Ok(status) => warn!("{} exited with {}; restarting in {:?}", name, status, delay),
+                    Err(e) => warn!("{} could not be awaited: {}; restarting in {:?}", name, e, delay),
+                }
+                if !sleep_unless_stopping(delay, &mut state).await {
+                    return;
+                }
+            }
+            _ = wait_until_not_running(&mut state) => {
+                info!("Stopping {}", name);
+                if let Err(e) = child.kill().await {
+                    warn!("Failed to stop {}: {}", name, e);
+                }
+                failures = 0;
+            }
+        }
+    }
+}
+
+/// Block while paused; returns false once the supervisor is stopping
+async fn wait_until_running(state: &mut watch::Receiver<RunState>) -> bool {
+    loop {
+        match *state.borrow_and_update() {
+            RunState::Running => return true,
+            RunState::Stopping => return false,
+            RunState::Paused => {}
+        }
+        if state.changed().await.is_err() {
+            return false;
+        }
+    }
+}
+
+/// Resolve when the desired state leaves `Running`
+async fn wait_until_not_running(state: &mut watch::Receiver<RunState>) {
+    loop {
+        if state.changed().await.is_err() || *state.borrow() != RunState::Running {
+            return;
+        }
+    }
+}
+
+/// Sleep through a restart delay; returns false if a stop arrives meanwhile
+async fn sleep_unless_stopping(delay: Duration, state: &mut watch::Receiver<RunState>) -> bool {
+    let sleep = tokio::time::sleep(delay);
+    tokio::pin!(sleep);
+
+    loop {
+        tokio::select! {
+            _ = &mut sleep => return true,
+            changed = state.changed() => {
+                if changed.is_err() || *state.borrow() == RunState::Stopping {
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// Environment from the installer-written config files, passed to every child
+fn load_environment(install_dir: &Path) -> Vec<(String, String)> {
+    let config_dir = install_dir.join("config");
+    let mut environment = Vec::new();
+
+    for file in [".env", "database.env"] {
+        let path = config_dir.join(file);
+        if !path.exists() {
+            continue;
+        }
+        match dotenv::from_path_iter(&path) {
+            Ok(entries) => environment.extend(entries.filter_map(|entry| entry.ok())),
+            Err(e) => warn!("Failed to read {}: {}", path.display(), e),
+        }
+    }
+
+    environment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let policy = BackoffPolicy::default();
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(4), Duration::from_secs(8));
+        assert_eq!(policy.delay(10), Duration::from_secs(60));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,388 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::migrate::resolve_database_url;
+
+/// Name of the database dump inside a backup archive
+const DATABASE_DUMP: &str = "database.dump";
+/// Name of the manifest inside a backup archive
+const MANIFEST: &str = "manifest.json";
+/// Directories under the install dir captured in every backup
+const BACKED_UP_DIRS: [&str; 2] = ["config", "exports"];
+/// Prefix of backup archive file names; the suffix is a sortable timestamp
+const ARCHIVE_PREFIX: &str = "terrafusion-backup-";
+
+/// Contents of a backup archive with a checksum per entry
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupManifest {
+    created_at: DateTime<Utc>,
+    tool_version: String,
+    includes_database: bool,
+    files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestEntry {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+/// Options for `terrafusion-setup backup`
+pub struct BackupOptions {
+    pub output_dir: Option<PathBuf>,
+    pub keep: Option<usize>,
+    pub skip_database: bool,
+    pub database_url: Option<String>,
+}
+
+/// Options for `terrafusion-setup restore`
+pub struct RestoreOptions {
+    pub archive: PathBuf,
+    pub verify_only: bool,
+    pub skip_database: bool,
+    pub skip_files: bool,
+    pub force: bool,
+    pub database_url: Option<String>,
+}
+
+/// Dump the database, archive config and exports, verify the archive and apply retention
+pub async fn create_backup(install_dir: &PathBuf, options: BackupOptions) -> Result<PathBuf> {
+    let output_dir = options.output_dir.unwrap_or_else(|| install_dir.join("backups"));
+    std::fs::create_dir_all(&output_dir)
+        .with_context(|| format!("Failed to create backup directory {}", output_dir.display()))?;
+
+    let staging = tempfile::tempdir().context("Failed to create staging directory")?;
+    let dump_path = staging.path().join(DATABASE_DUMP);
+    if !options.skip_database {
+        let database_url = resolve_database_url(install_dir, options.database_url.as_deref()).await?;
+        info!("Dumping database...");
+        run_pg_tool(install_dir, "pg_dump", &[
+            "--format=custom".to_string(),
+            "--no-owner".to_string(),
+            format!("--file={}", dump_path.display()),
+            format!("--dbname={}", database_url),
+        ])?;
+    }
+
+    let created_at = Utc::now();
+    let archive_path = output_dir.join(format!("{}{}.zip", ARCHIVE_PREFIX, created_at.format("%Y%m%d-%H%M%S")));
+    info!("Writing archive {}...", archive_path.display());
+
+    let install_dir = install_dir.clone();
+    let dump = (!options.skip_database).then_some(dump_path);
+    let archive = archive_path.clone();
+    tokio::task::spawn_blocking(move || write_archive(&archive, &install_dir, dump.as_deref(), created_at))
+        .await??;
+
+    verify_archive(&archive_path).context("Backup archive failed verification")?;
+    info!("✅ Backup written to {}", archive_path.display());
+
+    if let Some(keep) = options.keep {
+        prune_backups(&output_dir, keep)?;
+    }
+
+    Ok(archive_path)
+}
+
+/// Verify an archive and restore its database dump and files
+pub async fn restore_backup(install_dir: &PathBuf, options: RestoreOptions) -> Result<()> {
+    let manifest = verify_archive(&options.archive)?;
+    info!(
+        "✅ Archive verified: {} file(s) from {}",
+        manifest.files.len(),
+        manifest.created_at.to_rfc3339()
+    );
+    if options.verify_only {
+        return Ok(());
+    }
+
+    if !options.force && crate::services::is_terrafusion_service_running().await {
+        anyhow::bail!("The TerraFusion Platform service is running; stop it first or pass --force");
+    }
+
+    let staging = tempfile::tempdir().context("Failed to create staging directory")?;
+    let archive = options.archive.clone();
+    let target = install_dir.clone();
+    let staging_path = staging.path().to_path_buf();
+    let skip_files = options.skip_files;
+    tokio::task::spawn_blocking(move || extract_archive(&archive, &target, &staging_path, skip_files))
+        .await??;
+
+    if !options.skip_database && manifest.includes_database {
+        let database_url = resolve_database_url(install_dir, options.database_url.as_deref()).await?;
+        info!("Restoring database...");
+        run_pg_tool(install_dir, "pg_restore", &[
+            "--clean".to_string(),
+            "--if-exists".to_string(),
+            "--no-owner".to_string(),
+            format!("--dbname={}", database_url),
+            staging.path().join(DATABASE_DUMP).display().to_string(),
+        ])?;
+    }
+
+    info!("✅ Restore from {} completed", options.archive.display());
+    Ok(())
+}
+
+/// Register a daily Windows scheduled task that runs `backup --keep N`
+pub fn schedule_backup(install_dir: &Path, time: &str, keep: usize) -> Result<()> {
+    chrono::NaiveTime::parse_from_str(time, "%H:%M")
+        .with_context(|| format!("Invalid schedule time '{}', expected HH:MM", time))?;
+
+    let exe = std::env::current_exe().context("Failed to locate setup executable")?;
+    let task_command = format!(
+        "\"{}\" --install-dir \"{}\" backup --keep {}",
+        exe.display(),
+        install_dir.display(),
+        keep
+    );
+
+    let output = Command::new("schtasks")
+        .args(["/Create", "/F", "/TN", "TerraFusion Backup", "/SC", "DAILY", "/ST", time, "/RU", "SYSTEM", "/TR"])
+        .arg(&task_command)
+        .output()
+        .context("Failed to run schtasks")?;
+    if !output.status.success() {
+        anyhow::bail!("Failed to schedule backup: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    info!("✅ Scheduled daily backup at {} keeping the last {} archive(s)", time, keep);
+    Ok(())
+}
+
+/// Delete all but the newest `keep` backup archives in a directory
+pub fn prune_backups(dir: &Path, keep: usize) -> Result<()> {
+    let mut archives: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| name.starts_with(ARCHIVE_PREFIX) && name.ends_with(".zip"))
+        })
+        .collect();
+
+    // Timestamped names sort chronologically
+    archives.sort();
+    let excess = archives.len().saturating_sub(keep);
+    for archive in archives.into_iter().take(excess) {
+        info!("Removing old backup {}", archive.display());
+        std::fs::remove_file(&archive)
+            .with_context(|| format!("Failed to remove {}", archive.display()))?;
+    }
+
+    Ok(())
+}
+
+fn write_archive(
+    archive_path: &Path,
+    install_dir: &Path,
+    database_dump: Option<&Path>,
+    created_at: DateTime<Utc>,
+) -> Result<()> {
+    let file = File::create(archive_path)
+        .with_context(|| format!("Failed to create {}", archive_path.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let mut entries = Vec::new();
+
+    if let Some(dump) = database_dump {
+        entries.push(add_file(&mut zip, dump, DATABASE_DUMP)?);
+    }
+
+    for dir in BACKED_UP_DIRS {
+        let root = install_dir.join(dir);
+        if !root.exists() {
+            warn!("⚠️ {} does not exist, skipping", root.display());
+            continue;
+        }
+        for path in walk_files(&root)? {
+            let relative = path.strip_prefix(install_dir)?;
+            let name = relative.to_string_lossy().replace('\\', "/");
+            entries.push(add_file(&mut zip, &path, &name)?);
+        }
+    }
+
+    let manifest = BackupManifest {
+        created_at,
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        includes_database: database_dump.is_some(),
+        files: entries,
+    };
+    zip.start_file(MANIFEST, FileOptions::default())?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+    zip.finish()?;
+
+    Ok(())
+}
+
+fn add_file(zip: &mut ZipWriter<File>, path: &Path, name: &str) -> Result<ManifestEntry> {
+    let size = std::fs::metadata(path)?.len();
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(size >= u32::MAX as u64);
+    zip.start_file(name, options)?;
+
+    let mut source = File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut writer = HashingWriter { inner: zip, hasher: Sha256::new() };
+    io::copy(&mut source, &mut writer)?;
+
+    Ok(ManifestEntry {
+        path: name.to_string(),
+        size,
+        sha256: format!("{:x}", writer.hasher.finalize()),
+    })
+}
+
+/// Writer that hashes everything passed through it
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn walk_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Check every manifest entry is present with a matching checksum
+fn verify_archive(archive_path: &Path) -> Result<BackupManifest> {
+    let file = File::open(archive_path)
+        .with_context(|| format!("Failed to open {}", archive_path.display()))?;
+    let mut zip = ZipArchive::new(file).context("Not a valid backup archive")?;
+
+    let manifest: BackupManifest = {
+        let entry = zip.by_name(MANIFEST).context("Backup archive has no manifest")?;
+        serde_json::from_reader(entry).context("Backup manifest is invalid")?
+    };
+
+    for expected in &manifest.files {
+        let mut entry = zip
+            .by_name(&expected.path)
+            .with_context(|| format!("{} is missing from the archive", expected.path))?;
+        let mut hasher = Sha256::new();
+        let mut buffer = [0u8; 64 * 1024];
+        loop {
+            // Reading also checks the zip CRC of the entry
+            let read = entry.read(&mut buffer)
+                .with_context(|| format!("{} is corrupt", expected.path))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        if format!("{:x}", hasher.finalize()) != expected.sha256 {
+            anyhow::bail!("Checksum mismatch for {}", expected.path);
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// Unpack files into the install dir and the database dump into `staging`
+fn extract_archive(archive_path: &Path, install_dir: &Path, staging: &Path, skip_files: bool) -> Result<()> {
+    let mut zip = ZipArchive::new(File::open(archive_path)?)?;
+
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let name = entry.name().to_string();
+        if name == MANIFEST {
+            continue;
+        }
+
+        let target = if name == DATABASE_DUMP {
+            staging.join(DATABASE_DUMP)
+        } else if skip_files {
+            continue;
+        } else {
+            // enclosed_name rejects absolute paths and `..` components
+            let relative = entry
+                .enclosed_name()
+                .with_context(|| format!("Refusing to extract unsafe path {}", name))?
+                .to_path_buf();
+            install_dir.join(relative)
+        };
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut output = File::create(&target)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        io::copy(&mut entry, &mut output)?;
+    }
+
+    if !skip_files {
+        info!("Restored configuration and exports into {}", install_dir.display());
+    }
+    Ok(())
+}
+
+/// Run a PostgreSQL client tool, preferring the bundled binaries
+fn run_pg_tool(install_dir: &Path, tool: &str, args: &[String]) -> Result<()> {
+    let bundled = install_dir
+        .join("database")
+        .join("bin")
+        .join(format!("{}{}", tool, std::env::consts::EXE_SUFFIX));
+    let program = if bundled.exists() { bundled } else { PathBuf::from(tool) };
+
+    let output = Command::new(&program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program.display()))?;
+    if !output.status.success() {
+        anyhow::bail!("{} failed: {}", tool, String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_keeps_newest_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        for stamp in ["20240101-020000", "20240102-020000", "20240103-020000"] {
+            File::create(dir.path().join(format!("{}{}.zip", ARCHIVE_PREFIX, stamp))).unwrap();
+        }
+        File::create(dir.path().join("notes.txt")).unwrap();
+
+        prune_backups(dir.path(), 2).unwrap();
+
+        assert!(!dir.path().join(format!("{}20240101-020000.zip", ARCHIVE_PREFIX)).exists());
+        assert!(dir.path().join(format!("{}20240103-020000.zip", ARCHIVE_PREFIX)).exists());
+        assert!(dir.path().join("notes.txt").exists());
+    }
+}
@@ -65,8 +65,14 @@ pub async fn create_database(
     // Create TerraFusion database and user
     create_terrafusion_database(&db_dir, county_id, db_password).await?;
     
-    // Run database migrations
-    run_database_migrations(&db_dir, county_id).await?;
+    // Run embedded database migrations
+    let database_url = format!(
+        "postgresql://terrafusion:{}@localhost:5433/terrafusion_{}",
+        db_password,
+        county_id.replace("-", "_")
+    );
+    crate::migrate::run_migrations(&database_url).await
+        .context("Database migration failed")?;
     
     // Stop temporary PostgreSQL instance
     stop_postgres_process(postgres_handle).await?;
@@ -193,42 +199,6 @@ async fn create_terrafusion_database(
     Ok(())
 }
 
-/// Run database migrations
-async fn run_database_migrations(db_dir: &PathBuf, county_id: &str) -> Result<()> {
-    info!("Running database migrations...");
-    
-    let psql_exe = db_dir.join("bin").join("psql.exe");
-    let schema_file = db_dir.join("schema.sql");
-    
-    // Check if schema file exists
-    if !schema_file.exists() {
-        warn!("Schema file not found, skipping migrations");
-        return Ok(());
-    }
-    
-    let result = Command::new(psql_exe)
-        .args(&[
-            "-h", "localhost",
-            "-p", "5433",
-            "-U", "terrafusion",
-            "-d", &format!("terrafusion_{}", county_id.replace("-", "_")),
-            "-f", &schema_file.to_string_lossy()
-        ])
-        .env("PGPASSWORD", "terrafusion")
-        .output()
-        .context("Failed to run migrations")?;
-    
-    if !result.status.success() {
-        anyhow::bail!(
-            "Database migration failed: {}",
-            String::from_utf8_lossy(&result.stderr)
-        );
-    }
-    
-    info!("Database migrations completed successfully");
-    Ok(())
-}
-
 /// Stop PostgreSQL process
 async fn stop_postgres_process(mut process: tokio::process::Child) -> Result<()> {
     info!("Stopping temporary PostgreSQL instance...");
@@ -8,6 +8,11 @@ mod services;
 mod firewall;
 mod config;
 mod validation;
+mod migrate;
+mod tenancy;
+mod backup;
+mod tls;
+mod wizard;
 
 #[derive(Parser)]
 #[command(name = "terrafusion-setup")]
@@ -53,7 +58,15 @@ enum Commands {
     },
     
     /// Validate system requirements
-    ValidateSystem,
+    ValidateSystem {
+        /// Write the requirement report as JSON
+        #[arg(long)]
+        report_json: Option<PathBuf>,
+
+        /// Write the requirement report as HTML
+        #[arg(long)]
+        report_html: Option<PathBuf>,
+    },
     
     /// Generate configuration files
     GenerateConfig {
@@ -66,19 +79,103 @@ enum Commands {
         admin_email: String,
     },
     
-    /// Complete installation setup
+    /// Manage database schema migrations
+    Migrate {
+        #[command(subcommand)]
+        action: migrate::MigrateAction,
+        
+        /// Database URL (defaults to DATABASE_URL or config/database.env)
+        #[arg(long)]
+        database_url: Option<String>,
+    },
+    
+    /// Configure county row-level security policies
+    ConfigureRls {
+        /// Remove the policies instead of creating them
+        #[arg(long)]
+        disable: bool,
+        
+        /// Print the SQL without executing it
+        #[arg(long)]
+        print: bool,
+        
+        /// Database URL (defaults to DATABASE_URL or config/database.env)
+        #[arg(long)]
+        database_url: Option<String>,
+    },
+    
+    /// Back up the database, configuration and exports to a verified archive
+    Backup {
+        /// Directory for backup archives (defaults to <install-dir>/backups)
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+        
+        /// Keep only the newest N archives after a successful backup (scheduled backups default to 7)
+        #[arg(long)]
+        keep: Option<usize>,
+        
+        /// Archive files only, without a database dump
+        #[arg(long)]
+        skip_database: bool,
+        
+        /// Register a daily scheduled backup at this time (HH:MM) instead of running one now
+        #[arg(long, value_name = "HH:MM")]
+        schedule: Option<String>,
+        
+        /// Database URL (defaults to DATABASE_URL or config/database.env)
+        #[arg(long)]
+        database_url: Option<String>,
+    },
+    
+    /// Restore the database, configuration and exports from a backup archive
+    Restore {
+        /// Backup archive to restore
+        archive: PathBuf,
+        
+        /// Only verify the archive's integrity
+        #[arg(long)]
+        verify_only: bool,
+        
+        /// Do not restore the database dump
+        #[arg(long)]
+        skip_database: bool,
+        
+        /// Do not restore configuration and exports
+        #[arg(long)]
+        skip_files: bool,
+        
+        /// Restore even if the platform service is running
+        #[arg(long)]
+        force: bool,
+        
+        /// Database URL (defaults to DATABASE_URL or config/database.env)
+        #[arg(long)]
+        database_url: Option<String>,
+    },
+    
+    /// Provision or renew the services' TLS certificate
+    ConfigureTls {
+        #[command(subcommand)]
+        mode: tls::TlsMode,
+    },
+    
+    /// Complete installation setup (starts an interactive wizard unless --county and --admin-email are given)
     Setup {
         /// County identifier
         #[arg(long)]
-        county: String,
+        county: Option<String>,
         
         /// Administrator email
         #[arg(long)]
-        admin_email: String,
+        admin_email: Option<String>,
         
         /// Web interface port
         #[arg(long, default_value = "8000")]
         port: u16,
+        
+        /// Run the interactive wizard even if all options are given
+        #[arg(long)]
+        interactive: bool,
     },
 }
 
@@ -118,9 +215,23 @@ async fn main() -> Result<()> {
             info!("Firewall configured successfully");
         },
         
-        Commands::ValidateSystem => {
-            info!("Validating system requirements...");
-            validation::validate_system_requirements(&cli.install_dir).await?;
+        Commands::ValidateSystem { report_json, report_html } => {
+            let report = validation::collect_requirement_report(&cli.install_dir).await;
+            report.log();
+
+            // Write the report before failing so it can be attached to a support ticket
+            if let Some(path) = report_json {
+                report.write_json(&path).await?;
+                info!("Requirement report written to {}", path.display());
+            }
+            if let Some(path) = report_html {
+                report.write_html(&path).await?;
+                info!("Requirement report written to {}", path.display());
+            }
+
+            if report.has_failures() {
+                anyhow::bail!("{} system requirement check(s) failed", report.count(validation::CheckStatus::Fail));
+            }
             info!("System validation completed successfully");
         },
         
@@ -130,9 +241,60 @@ async fn main() -> Result<()> {
             info!("Configuration generated successfully");
         },
         
-        Commands::Setup { county, admin_email, port } => {
-            info!("Running complete setup for county: {}", county);
-            run_complete_setup(&cli.install_dir, &county, &admin_email, port).await?;
+        Commands::Migrate { action, database_url } => {
+            info!("Running migration command: {:?}", action);
+            migrate::run_migrate_command(&cli.install_dir, database_url.as_deref(), action).await?;
+        },
+        
+        Commands::ConfigureRls { disable, print, database_url } => {
+            info!("Configuring row-level security policies...");
+            tenancy::configure_row_level_security(&cli.install_dir, database_url.as_deref(), disable, print).await?;
+        },
+        
+        Commands::Backup { output_dir, keep, skip_database, schedule, database_url } => {
+            if let Some(time) = schedule {
+                backup::schedule_backup(&cli.install_dir, &time, keep.unwrap_or(7))?;
+            } else {
+                info!("Creating backup...");
+                backup::create_backup(&cli.install_dir, backup::BackupOptions {
+                    output_dir,
+                    keep,
+                    skip_database,
+                    database_url,
+                }).await?;
+            }
+        },
+        
+        Commands::Restore { archive, verify_only, skip_database, skip_files, force, database_url } => {
+            info!("Restoring from {}", archive.display());
+            backup::restore_backup(&cli.install_dir, backup::RestoreOptions {
+                archive,
+                verify_only,
+                skip_database,
+                skip_files,
+                force,
+                database_url,
+            }).await?;
+        },
+        
+        Commands::ConfigureTls { mode } => {
+            info!("Configuring TLS certificate...");
+            tls::configure_tls(&cli.install_dir, mode).await?;
+        },
+        
+        Commands::Setup { county, admin_email, port, interactive } => {
+            let plan = match (county, admin_email) {
+                (Some(county), Some(admin_email)) if !interactive => wizard::SetupPlan {
+                    county,
+                    admin_email,
+                    port,
+                    db_password: None,
+                    tls: None,
+                },
+                (county, admin_email) => wizard::run_setup_wizard(county, admin_email, port)?,
+            };
+            info!("Running complete setup for county: {}", plan.county);
+            run_complete_setup(&cli.install_dir, &plan).await?;
             info!("Setup completed successfully");
         },
     }
@@ -141,48 +303,61 @@ async fn main() -> Result<()> {
 }
 
 /// Run complete setup process
-async fn run_complete_setup(
-    install_dir: &PathBuf,
-    county: &str,
-    admin_email: &str,
-    port: u16,
-) -> Result<()> {
-    // Step 1: Validate system requirements
-    info!("Step 1/6: Validating system requirements...");
+async fn run_complete_setup(install_dir: &PathBuf, plan: &wizard::SetupPlan) -> Result<()> {
+    let steps = if plan.tls.is_some() { 7 } else { 6 };
+    let mut step = 0;
+    let mut next_step = |description: &str| {
+        step += 1;
+        info!("Step {}/{}: {}", step, steps, description);
+    };
+    
+    // Validate system requirements
+    next_step("Validating system requirements...");
     validation::validate_system_requirements(install_dir).await
         .context("System validation failed")?;
     
-    // Step 2: Generate configuration
-    info!("Step 2/6: Generating configuration...");
-    config::generate_configuration(install_dir, county, admin_email).await
+    // Generate configuration
+    next_step("Generating configuration...");
+    config::generate_configuration(install_dir, &plan.county, &plan.admin_email).await
         .context("Configuration generation failed")?;
     
-    // Step 3: Create database
-    info!("Step 3/6: Setting up database...");
-    database::create_database(install_dir, county, None).await
+    // Create database
+    next_step("Setting up database...");
+    database::create_database(install_dir, &plan.county, plan.db_password.as_deref()).await
         .context("Database setup failed")?;
     
-    // Step 4: Configure firewall
-    info!("Step 4/6: Configuring firewall...");
-    firewall::configure_firewall_rules(port).await
+    // Provision the TLS certificate before services start
+    if let Some(mode) = &plan.tls {
+        next_step("Configuring TLS certificate...");
+        tls::configure_tls(install_dir, mode.clone()).await
+            .context("TLS configuration failed")?;
+    }
+    
+    // Configure firewall
+    next_step("Configuring firewall...");
+    firewall::configure_firewall_rules(plan.port).await
         .context("Firewall configuration failed")?;
     
-    // Step 5: Start services
-    info!("Step 5/6: Starting services...");
+    // Start services
+    next_step("Starting services...");
     services::start_all_services(install_dir).await
         .context("Service startup failed")?;
     
-    // Step 6: Validate installation
-    info!("Step 6/6: Validating installation...");
-    validation::validate_installation(install_dir, port).await
+    // Validate installation
+    next_step("Validating installation...");
+    validation::validate_installation(install_dir, plan.port).await
         .context("Installation validation failed")?;
     
     // Display success message with access information
+    let scheme = match plan.tls {
+        Some(tls::TlsMode::Csr { .. }) | None => "http",
+        Some(_) => "https",
+    };
     println!("\n🎉 TerraFusion Platform setup completed successfully!");
-    println!("🌐 Web Interface: http://localhost:{}", port);
-    println!("📧 Administrator: {}", admin_email);
-    println!("🏛️ County: {}", county);
+    println!("🌐 Web Interface: {}://localhost:{}", scheme, plan.port);
+    println!("📧 Administrator: {}", plan.admin_email);
+    println!("🏛️ County: {}", plan.county);
     println!("\nThe platform is now ready for use!");
     
     Ok(())
-}
\ No newline at end of file
+}
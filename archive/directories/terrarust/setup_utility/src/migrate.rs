@@ -0,0 +1,115 @@
+use anyhow::{Result, Context};
+use std::path::PathBuf;
+use tokio::fs;
+use log::{info, warn};
+use terrafusion_common::database::migrations::{Migrator, MigrationStatus};
+
+/// Migration actions available from the command line
+#[derive(clap::Subcommand, Debug, Clone, Copy)]
+pub enum MigrateAction {
+    /// Apply all pending migrations
+    Run,
+    /// List migrations and their status
+    Status,
+    /// Verify checksums of applied migrations
+    Verify,
+    /// Revert the most recently applied migration
+    Rollback,
+}
+
+/// Execute a migration action against the county database
+pub async fn run_migrate_command(
+    install_dir: &PathBuf,
+    database_url: Option<&str>,
+    action: MigrateAction,
+) -> Result<()> {
+    let database_url = resolve_database_url(install_dir, database_url).await?;
+    let migrator = connect(&database_url).await?;
+
+    match action {
+        MigrateAction::Run => {
+            let applied = migrator.run_pending_migrations().await
+                .context("Failed to apply migrations")?;
+            info!("✅ Applied {} migration(s)", applied.len());
+        }
+        MigrateAction::Status => {
+            migrator.init().await.context("Failed to initialize migrations table")?;
+            for migration in migrator.get_migrations().await? {
+                let marker = match migration.status {
+                    MigrationStatus::Completed => "✅",
+                    MigrationStatus::Failed => "❌",
+                    _ => "⏳",
+                };
+                println!(
+                    "{} {}_{} {} {}",
+                    marker,
+                    migration.version,
+                    migration.name,
+                    migration.status,
+                    migration.applied_at.map(|t| t.to_rfc3339()).unwrap_or_default()
+                );
+            }
+        }
+        MigrateAction::Verify => {
+            migrator.init().await.context("Failed to initialize migrations table")?;
+            let mismatches = migrator.verify_checksums().await?;
+            if mismatches.is_empty() {
+                info!("✅ All applied migrations match their embedded checksums");
+            } else {
+                for mismatch in &mismatches {
+                    warn!(
+                        "⚠️ {}_{}: applied {} but embedded {}",
+                        mismatch.version, mismatch.name, mismatch.applied_checksum, mismatch.embedded_checksum
+                    );
+                }
+                anyhow::bail!("{} migration(s) failed checksum verification", mismatches.len());
+            }
+        }
+        MigrateAction::Rollback => {
+            match migrator.rollback_last().await.context("Failed to roll back migration")? {
+                Some(migration) => info!("✅ Rolled back {}_{}", migration.version, migration.name),
+                None => info!("No applied migrations to roll back"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply all pending embedded migrations
+pub async fn run_migrations(database_url: &str) -> Result<()> {
+    let migrator = connect(database_url).await?;
+    let applied = migrator.run_pending_migrations().await
+        .context("Failed to apply migrations")?;
+
+    info!("Applied {} database migration(s)", applied.len());
+    Ok(())
+}
+
+async fn connect(database_url: &str) -> Result<Migrator> {
+    let pool = sqlx::PgPool::connect(database_url).await
+        .context("Failed to connect to database")?;
+
+    Ok(Migrator::with_embedded_migrations(pool))
+}
+
+/// Use the explicit URL, then DATABASE_URL, then the generated database.env
+pub(crate) async fn resolve_database_url(install_dir: &PathBuf, database_url: Option<&str>) -> Result<String> {
+    if let Some(url) = database_url {
+        return Ok(url.to_string());
+    }
+
+    if let Ok(url) = std::env::var("DATABASE_URL") {
+        return Ok(url);
+    }
+
+    let env_file = install_dir.join("config").join("database.env");
+    let content = fs::read_to_string(&env_file).await
+        .with_context(|| format!("No database URL given and {} is not readable", env_file.display()))?;
+
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("DATABASE_URL="))
+        .map(|url| url.trim().to_string())
+        .context("DATABASE_URL not found in database.env")
+}
@@ -157,7 +157,7 @@ async fn is_postgres_running() -> bool {
 }
 
 /// Check if TerraFusion service is running
-async fn is_terrafusion_service_running() -> bool {
+pub(crate) async fn is_terrafusion_service_running() -> bool {
     let output = Command::new("sc")
         .args(&["query", "TerraFusionPlatform"])
         .output();
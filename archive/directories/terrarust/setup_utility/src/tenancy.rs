@@ -0,0 +1,81 @@
+use anyhow::{Result, Context};
+use std::path::PathBuf;
+use log::info;
+use terrafusion_common::database::tenancy::COUNTY_SCOPED_TABLES;
+
+use crate::migrate::resolve_database_url;
+
+const POLICY_NAME: &str = "county_isolation";
+
+/// Enable or disable county row-level security policies
+pub async fn configure_row_level_security(
+    install_dir: &PathBuf,
+    database_url: Option<&str>,
+    disable: bool,
+    print_only: bool,
+) -> Result<()> {
+    let statements = if disable { disable_statements() } else { enable_statements() };
+
+    if print_only {
+        for statement in &statements {
+            println!("{};", statement);
+        }
+        return Ok(());
+    }
+
+    let database_url = resolve_database_url(install_dir, database_url).await?;
+    let pool = sqlx::PgPool::connect(&database_url).await
+        .context("Failed to connect to database")?;
+
+    let mut tx = pool.begin().await?;
+    for statement in &statements {
+        sqlx::query(statement).execute(&mut tx).await
+            .with_context(|| format!("Failed to execute: {}", statement))?;
+    }
+    tx.commit().await?;
+
+    if disable {
+        info!("✅ Row-level security disabled on {} tables", COUNTY_SCOPED_TABLES.len());
+    } else {
+        info!("✅ Row-level security enabled on {} tables", COUNTY_SCOPED_TABLES.len());
+    }
+
+    Ok(())
+}
+
+/// Policies match rows whose county equals `app.county_id`, or every row
+/// when the setting is `*` (platform administrators).
+///
+/// The table owner bypasses these policies, so background jobs running as
+/// the service account are unaffected; they apply to per-county and
+/// reporting roles that connect through `begin_scoped` transactions.
+fn enable_statements() -> Vec<String> {
+    let mut statements = Vec::new();
+
+    for table in COUNTY_SCOPED_TABLES {
+        statements.push(format!("ALTER TABLE {} ENABLE ROW LEVEL SECURITY", table));
+        statements.push(format!("DROP POLICY IF EXISTS {} ON {}", POLICY_NAME, table));
+        statements.push(format!(
+            "CREATE POLICY {policy} ON {table} \
+             USING (current_setting('app.county_id', true) = '*' \
+                 OR county_id = current_setting('app.county_id', true)) \
+             WITH CHECK (current_setting('app.county_id', true) = '*' \
+                 OR county_id = current_setting('app.county_id', true))",
+            policy = POLICY_NAME,
+            table = table,
+        ));
+    }
+
+    statements
+}
+
+fn disable_statements() -> Vec<String> {
+    let mut statements = Vec::new();
+
+    for table in COUNTY_SCOPED_TABLES {
+        statements.push(format!("DROP POLICY IF EXISTS {} ON {}", POLICY_NAME, table));
+        statements.push(format!("ALTER TABLE {} DISABLE ROW LEVEL SECURITY", table));
+    }
+
+    statements
+}
@@ -0,0 +1,391 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use instant_acme::{
+    Account, AccountCredentials, AuthorizationStatus, ChallengeType, Identifier, LetsEncrypt, NewAccount,
+    NewOrder, OrderStatus,
+};
+use log::{info, warn};
+use rcgen::{Certificate, CertificateParams, DistinguishedName, DnType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::time::{sleep, Duration};
+
+/// Ways of obtaining the services' TLS certificate
+#[derive(clap::Subcommand, Debug, Clone)]
+pub enum TlsMode {
+    /// Generate a self-signed certificate
+    SelfSigned {
+        /// Host name clients use to reach the platform
+        #[arg(long)]
+        hostname: String,
+        /// Additional DNS names or IP addresses
+        #[arg(long = "san")]
+        sans: Vec<String>,
+        /// Validity in days
+        #[arg(long, default_value = "825")]
+        days: i64,
+    },
+    /// Generate a key and certificate signing request for a county CA
+    Csr {
+        #[arg(long)]
+        hostname: String,
+        #[arg(long = "san")]
+        sans: Vec<String>,
+        /// Organization name for the request subject
+        #[arg(long)]
+        organization: Option<String>,
+    },
+    /// Install a certificate issued by a county CA for a previously generated CSR
+    Install {
+        /// PEM certificate (chain) returned by the CA
+        #[arg(long)]
+        cert: PathBuf,
+    },
+    /// Obtain or renew a certificate from Let's Encrypt (HTTP-01 on port 80)
+    LetsEncrypt {
+        #[arg(long)]
+        hostname: String,
+        /// Contact email for the ACME account
+        #[arg(long)]
+        email: String,
+        /// Use the Let's Encrypt staging environment
+        #[arg(long)]
+        staging: bool,
+        /// Only renew if the current certificate expires within this many days
+        #[arg(long)]
+        renew_within_days: Option<i64>,
+    },
+}
+
+/// Issuance details recorded next to the certificate, used for renewal
+#[derive(Debug, Serialize, Deserialize)]
+struct TlsMetadata {
+    mode: String,
+    hostname: String,
+    issued_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+struct CertPaths {
+    dir: PathBuf,
+    cert: PathBuf,
+    key: PathBuf,
+    pending_key: PathBuf,
+    csr: PathBuf,
+    metadata: PathBuf,
+    acme_account: PathBuf,
+}
+
+impl CertPaths {
+    fn new(install_dir: &Path) -> Self {
+        let dir = install_dir.join("certs");
+        Self {
+            cert: dir.join("server.crt"),
+            key: dir.join("server.key"),
+            pending_key: dir.join("pending.key"),
+            csr: dir.join("server.csr"),
+            metadata: dir.join("tls.json"),
+            acme_account: dir.join("acme-account.json"),
+            dir,
+        }
+    }
+}
+
+/// Provision a certificate and point the services' SSL settings at it
+pub async fn configure_tls(install_dir: &PathBuf, mode: TlsMode) -> Result<()> {
+    let paths = CertPaths::new(install_dir);
+    fs::create_dir_all(&paths.dir).await
+        .context("Failed to create certificate directory")?;
+
+    match mode {
+        TlsMode::SelfSigned { hostname, sans, days } => {
+            let mut params = certificate_params(&hostname, &sans, None);
+            let now = time::OffsetDateTime::now_utc();
+            params.not_before = now;
+            params.not_after = now + time::Duration::days(days);
+            let cert = Certificate::from_params(params).context("Failed to generate certificate")?;
+
+            write_key_pair(&paths, &cert.serialize_pem()?, &cert.serialize_private_key_pem()).await?;
+            write_metadata(&paths, "self-signed", &hostname, Some(Utc::now() + ChronoDuration::days(days))).await?;
+            info!("✅ Generated self-signed certificate for {}", hostname);
+        }
+        TlsMode::Csr { hostname, sans, organization } => {
+            let cert = Certificate::from_params(certificate_params(&hostname, &sans, organization.as_deref()))
+                .context("Failed to generate key pair")?;
+
+            fs::write(&paths.pending_key, cert.serialize_private_key_pem()).await?;
+            fs::write(&paths.csr, cert.serialize_request_pem()?).await?;
+            info!("✅ Certificate signing request written to {}", paths.csr.display());
+            info!("Submit it to the county CA, then run `configure-tls install --cert <issued.crt>`");
+            return Ok(());
+        }
+        TlsMode::Install { cert } => {
+            let cert_pem = fs::read_to_string(&cert).await
+                .with_context(|| format!("Failed to read {}", cert.display()))?;
+            if !cert_pem.contains("-----BEGIN CERTIFICATE-----") {
+                anyhow::bail!("{} is not a PEM certificate", cert.display());
+            }
+            let key_pem = fs::read_to_string(&paths.pending_key).await
+                .context("No pending key found; generate a CSR with `configure-tls csr` first")?;
+
+            write_key_pair(&paths, &cert_pem, &key_pem).await?;
+            fs::remove_file(&paths.pending_key).await.ok();
+            write_metadata(&paths, "ca", "", None).await?;
+            info!("✅ Installed CA-issued certificate");
+        }
+        TlsMode::LetsEncrypt { hostname, email, staging, renew_within_days } => {
+            if let Some(days) = renew_within_days {
+                if !renewal_due(&paths, days).await {
+                    info!("Certificate is not due for renewal");
+                    return Ok(());
+                }
+            }
+
+            let (cert_pem, key_pem) = obtain_acme_certificate(&paths, &hostname, &email, staging).await?;
+            write_key_pair(&paths, &cert_pem, &key_pem).await?;
+            // Let's Encrypt certificates are valid for 90 days
+            write_metadata(&paths, "letsencrypt", &hostname, Some(Utc::now() + ChronoDuration::days(90))).await?;
+            info!("✅ Obtained Let's Encrypt certificate for {}", hostname);
+        }
+    }
+
+    enable_ssl_settings(install_dir, &paths).await
+}
+
+fn certificate_params(hostname: &str, sans: &[String], organization: Option<&str>) -> CertificateParams {
+    let mut names = vec![hostname.to_string()];
+    names.extend(sans.iter().cloned());
+
+    let mut params = CertificateParams::new(names);
+    params.distinguished_name = DistinguishedName::new();
+    params.distinguished_name.push(DnType::CommonName, hostname);
+    if let Some(organization) = organization {
+        params.distinguished_name.push(DnType::OrganizationName, organization);
+    }
+    params
+}
+
+/// Replace the key and certificate, key first so a reload never pairs a new cert with an old key
+async fn write_key_pair(paths: &CertPaths, cert_pem: &str, key_pem: &str) -> Result<()> {
+    replace_file(&paths.key, key_pem).await?;
+    replace_file(&paths.cert, cert_pem).await?;
+    Ok(())
+}
+
+async fn replace_file(path: &Path, contents: &str) -> Result<()> {
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, contents).await
+        .with_context(|| format!("Failed to write {}", temp.display()))?;
+    fs::rename(&temp, path).await
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+async fn write_metadata(paths: &CertPaths, mode: &str, hostname: &str, expires_at: Option<DateTime<Utc>>) -> Result<()> {
+    let metadata = TlsMetadata {
+        mode: mode.to_string(),
+        hostname: hostname.to_string(),
+        issued_at: Utc::now(),
+        expires_at,
+    };
+    fs::write(&paths.metadata, serde_json::to_vec_pretty(&metadata)?).await?;
+    Ok(())
+}
+
+async fn renewal_due(paths: &CertPaths, within_days: i64) -> bool {
+    let metadata = match fs::read(&paths.metadata).await {
+        Ok(bytes) => serde_json::from_slice::<TlsMetadata>(&bytes).ok(),
+        Err(_) => None,
+    };
+
+    match metadata.and_then(|metadata| metadata.expires_at) {
+        Some(expires_at) => expires_at - Utc::now() < ChronoDuration::days(within_days),
+        None => true,
+    }
+}
+
+/// Point USE_SSL, SSL_CERT_FILE and SSL_KEY_FILE in config/.env at the certificate
+async fn enable_ssl_settings(install_dir: &Path, paths: &CertPaths) -> Result<()> {
+    let env_path = install_dir.join("config").join(".env");
+    let content = fs::read_to_string(&env_path).await.unwrap_or_default();
+    let was_enabled = content.lines().any(|line| line.trim() == "USE_SSL=true");
+
+    let content = set_env_values(&content, &[
+        ("USE_SSL", "true".to_string()),
+        ("SSL_CERT_FILE", paths.cert.display().to_string()),
+        ("SSL_KEY_FILE", paths.key.display().to_string()),
+    ]);
+    fs::write(&env_path, content).await
+        .with_context(|| format!("Failed to update {}", env_path.display()))?;
+
+    if was_enabled {
+        info!("Running services will pick up the new certificate automatically");
+    } else {
+        warn!("⚠️ HTTPS was not enabled before; restart the TerraFusion Platform service to apply it");
+    }
+    Ok(())
+}
+
+/// Set `KEY=value` lines in an env file, replacing existing keys and appending new ones
+fn set_env_values(content: &str, values: &[(&str, String)]) -> String {
+    let mut remaining: Vec<&(&str, String)> = values.iter().collect();
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let key = line.split('=').next().unwrap_or("").trim();
+            match remaining.iter().position(|(name, _)| *name == key) {
+                Some(index) => {
+                    let (name, value) = remaining.remove(index);
+                    format!("{}={}", name, value)
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect();
+
+    lines.extend(remaining.into_iter().map(|(name, value)| format!("{}={}", name, value)));
+    lines.join("\n") + "\n"
+}
+
+/// Run an ACME HTTP-01 order and return the certificate chain and private key
+async fn obtain_acme_certificate(
+    paths: &CertPaths,
+    hostname: &str,
+    email: &str,
+    staging: bool,
+) -> Result<(String, String)> {
+    let account = load_or_create_account(paths, email, staging).await?;
+
+    let identifiers = [Identifier::Dns(hostname.to_string())];
+    let mut order = account.new_order(&NewOrder { identifiers: &identifiers }).await
+        .context("Failed to create ACME order")?;
+
+    let mut challenges = HashMap::new();
+    let mut ready = Vec::new();
+    for authorization in order.authorizations().await? {
+        match authorization.status {
+            AuthorizationStatus::Pending => {}
+            AuthorizationStatus::Valid => continue,
+            status => anyhow::bail!("Unexpected authorization status {:?}", status),
+        }
+
+        let challenge = authorization
+            .challenges
+            .iter()
+            .find(|challenge| challenge.r#type == ChallengeType::Http01)
+            .context("Let's Encrypt offered no HTTP-01 challenge")?;
+        challenges.insert(challenge.token.clone(), order.key_authorization(challenge).as_str().to_string());
+        ready.push(challenge.url.clone());
+    }
+
+    let responder = spawn_challenge_responder(challenges).await?;
+    for url in &ready {
+        order.set_challenge_ready(url).await?;
+    }
+
+    let mut delay = Duration::from_secs(1);
+    let result = loop {
+        sleep(delay).await;
+        let state = order.refresh().await?;
+        match state.status {
+            OrderStatus::Ready => break Ok(()),
+            OrderStatus::Invalid => break Err(anyhow::anyhow!("Let's Encrypt rejected the challenge; is port 80 reachable for {}?", hostname)),
+            _ if delay > Duration::from_secs(60) => break Err(anyhow::anyhow!("Timed out waiting for Let's Encrypt validation")),
+            _ => delay *= 2,
+        }
+    };
+    responder.abort();
+    result?;
+
+    let cert = Certificate::from_params(certificate_params(hostname, &[], None))?;
+    order.finalize(&cert.serialize_request_der()?).await
+        .context("Failed to finalize ACME order")?;
+
+    let chain = loop {
+        match order.certificate().await? {
+            Some(chain) => break chain,
+            None => sleep(Duration::from_secs(1)).await,
+        }
+    };
+
+    Ok((chain, cert.serialize_private_key_pem()))
+}
+
+async fn load_or_create_account(paths: &CertPaths, email: &str, staging: bool) -> Result<Account> {
+    if let Ok(bytes) = fs::read(&paths.acme_account).await {
+        let credentials: AccountCredentials = serde_json::from_slice(&bytes)
+            .context("Invalid stored ACME account")?;
+        return Ok(Account::from_credentials(credentials).await?);
+    }
+
+    let directory = if staging { LetsEncrypt::Staging.url() } else { LetsEncrypt::Production.url() };
+    let contact = format!("mailto:{}", email);
+    let (account, credentials) = Account::create(
+        &NewAccount {
+            contact: &[&contact],
+            terms_of_service_agreed: true,
+            only_return_existing: false,
+        },
+        directory,
+        None,
+    )
+    .await
+    .context("Failed to register ACME account")?;
+
+    fs::write(&paths.acme_account, serde_json::to_vec_pretty(&credentials)?).await?;
+    Ok(account)
+}
+
+/// Serve `/.well-known/acme-challenge/<token>` on port 80 until aborted
+async fn spawn_challenge_responder(challenges: HashMap<String, String>) -> Result<tokio::task::JoinHandle<()>> {
+    let listener = TcpListener::bind("0.0.0.0:80").await
+        .context("Failed to listen on port 80 for the ACME challenge")?;
+    let challenges = Arc::new(challenges);
+
+    Ok(tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let challenges = challenges.clone();
+            tokio::spawn(async move {
+                let mut buffer = [0u8; 2048];
+                let read = stream.read(&mut buffer).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buffer[..read]);
+                let body = request
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|path| path.strip_prefix("/.well-known/acme-challenge/"))
+                    .and_then(|token| challenges.get(token));
+
+                let response = match body {
+                    Some(body) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    ),
+                    None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_env_values_replaces_and_appends() {
+        let content = "USE_SSL=false\nRUST_LOG=info\n";
+        let updated = set_env_values(content, &[
+            ("USE_SSL", "true".to_string()),
+            ("SSL_CERT_FILE", "certs/server.crt".to_string()),
+        ]);
+
+        assert_eq!(updated, "USE_SSL=true\nRUST_LOG=info\nSSL_CERT_FILE=certs/server.crt\n");
+    }
+}
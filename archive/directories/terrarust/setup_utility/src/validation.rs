@@ -1,36 +1,207 @@
 use anyhow::{Result, Context};
-use std::path::PathBuf;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
+use sysinfo::{DiskExt, System, SystemExt};
 use tokio::fs;
 use log::{info, warn, error};
 
+/// Minimum and recommended hardware for a county installation
+const MIN_CPU_CORES: usize = 2;
+const RECOMMENDED_CPU_CORES: usize = 4;
+const MIN_MEMORY_GB: u64 = 4;
+const RECOMMENDED_MEMORY_GB: u64 = 8;
+const MIN_DISK_GB: u64 = 10;
+const RECOMMENDED_DISK_GB: u64 = 50;
+const MIN_POSTGRES_MAJOR: u32 = 13;
+const REQUIRED_PORTS: [u16; 4] = [8000, 8001, 8002, 5433];
+
+/// Outcome of a single requirement check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+/// One line of the requirement report
+#[derive(Debug, Clone, Serialize)]
+pub struct RequirementCheck {
+    pub category: String,
+    pub name: String,
+    pub status: CheckStatus,
+    pub required: Option<String>,
+    pub found: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl RequirementCheck {
+    fn new(category: &str, name: &str, status: CheckStatus) -> Self {
+        Self {
+            category: category.to_string(),
+            name: name.to_string(),
+            status,
+            required: None,
+            found: None,
+            detail: None,
+        }
+    }
+
+    fn required(mut self, required: impl Into<String>) -> Self {
+        self.required = Some(required.into());
+        self
+    }
+
+    fn found(mut self, found: impl Into<String>) -> Self {
+        self.found = Some(found.into());
+        self
+    }
+
+    fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// Structured result of `validate-system`, suitable for attaching to support tickets
+#[derive(Debug, Clone, Serialize)]
+pub struct RequirementReport {
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub host_name: Option<String>,
+    pub os_version: Option<String>,
+    pub install_dir: PathBuf,
+    pub checks: Vec<RequirementCheck>,
+}
+
+impl RequirementReport {
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|check| check.status == status).count()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.count(CheckStatus::Fail) > 0
+    }
+
+    /// Log every check with the usual status markers
+    pub fn log(&self) {
+        for check in &self.checks {
+            let found = check.found.as_deref().unwrap_or("-");
+            match check.status {
+                CheckStatus::Pass => info!("✅ {} / {}: {}", check.category, check.name, found),
+                CheckStatus::Warn => warn!("⚠️ {} / {}: {} {}", check.category, check.name, found, check.detail.as_deref().unwrap_or("")),
+                CheckStatus::Fail => error!("❌ {} / {}: {} {}", check.category, check.name, found, check.detail.as_deref().unwrap_or("")),
+                CheckStatus::Skipped => info!("⏭️ {} / {}: {}", check.category, check.name, check.detail.as_deref().unwrap_or("skipped")),
+            }
+        }
+    }
+
+    pub async fn write_json(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?).await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub async fn write_html(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_html()).await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn to_html(&self) -> String {
+        let mut rows = String::new();
+        for check in &self.checks {
+            let (label, color) = match check.status {
+                CheckStatus::Pass => ("PASS", "#1e7e34"),
+                CheckStatus::Warn => ("WARN", "#b8860b"),
+                CheckStatus::Fail => ("FAIL", "#c82333"),
+                CheckStatus::Skipped => ("SKIPPED", "#6c757d"),
+            };
+            rows.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td style=\"color:{};font-weight:bold\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&check.category),
+                escape_html(&check.name),
+                color,
+                label,
+                escape_html(check.required.as_deref().unwrap_or("")),
+                escape_html(check.found.as_deref().unwrap_or("")),
+                escape_html(check.detail.as_deref().unwrap_or("")),
+            ));
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>TerraFusion System Requirements</title>\n\
+             <style>body{{font-family:Segoe UI,Arial,sans-serif;margin:2em}}table{{border-collapse:collapse;width:100%}}\
+             th,td{{border:1px solid #ccc;padding:6px 10px;text-align:left}}th{{background:#f0f0f0}}</style></head><body>\n\
+             <h1>TerraFusion Platform System Requirements</h1>\n\
+             <p>Generated {} on {} ({})<br>Install directory: {}</p>\n\
+             <p>{} passed, {} warnings, {} failed, {} skipped</p>\n\
+             <table><tr><th>Category</th><th>Check</th><th>Status</th><th>Required</th><th>Found</th><th>Detail</th></tr>\n{}</table>\n\
+             </body></html>\n",
+            self.generated_at.to_rfc3339(),
+            escape_html(self.host_name.as_deref().unwrap_or("unknown host")),
+            escape_html(self.os_version.as_deref().unwrap_or("unknown OS")),
+            escape_html(&self.install_dir.display().to_string()),
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Warn),
+            self.count(CheckStatus::Fail),
+            self.count(CheckStatus::Skipped),
+            rows,
+        )
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 /// Validate system requirements for TerraFusion Platform
-pub async fn validate_system_requirements(install_dir: &PathBuf) -> Result<()> {
-    info!("Validating system requirements...");
-    
-    // Check operating system
-    validate_operating_system().await?;
-    
-    // Check available disk space
-    validate_disk_space(install_dir).await?;
-    
-    // Check available memory
-    validate_memory().await?;
-    
-    // Check network connectivity
-    validate_network_connectivity().await?;
-    
-    // Check required ports availability
-    validate_port_availability().await?;
-    
-    // Check administrator privileges
-    validate_administrator_privileges().await?;
-    
-    // Check Windows services permissions
-    validate_service_permissions().await?;
-    
+///
+/// Returns the full report; fails if any check failed.
+pub async fn validate_system_requirements(install_dir: &PathBuf) -> Result<RequirementReport> {
+    let report = collect_requirement_report(install_dir).await;
+    report.log();
+
+    if report.has_failures() {
+        anyhow::bail!("{} system requirement check(s) failed", report.count(CheckStatus::Fail));
+    }
+
     info!("All system requirements validated successfully");
-    Ok(())
+    Ok(report)
+}
+
+/// Run every requirement check without failing early
+pub async fn collect_requirement_report(install_dir: &PathBuf) -> RequirementReport {
+    info!("Validating system requirements...");
+
+    let mut system = System::new_all();
+    system.refresh_all();
+
+    let mut checks = vec![
+        check_operating_system(&system),
+        check_cpu(&system),
+        check_memory(&system),
+        check_disk_space(&system, install_dir),
+    ];
+    checks.extend(check_ports());
+    checks.extend(check_postgres(install_dir).await);
+    checks.push(check_dotnet());
+    checks.push(check_gdal(install_dir));
+    checks.push(check_network_connectivity());
+    checks.push(check_administrator_privileges().await);
+    checks.push(check_service_permissions());
+
+    RequirementReport {
+        generated_at: chrono::Utc::now(),
+        host_name: system.host_name(),
+        os_version: system.long_os_version(),
+        install_dir: install_dir.clone(),
+        checks,
+    }
 }
 
 /// Validate the installation after setup is complete
@@ -59,209 +230,270 @@ pub async fn validate_installation(install_dir: &PathBuf, web_port: u16) -> Resu
     Ok(())
 }
 
-/// Validate operating system compatibility
-async fn validate_operating_system() -> Result<()> {
-    info!("Checking operating system compatibility...");
-    
-    // Check if running on Windows
-    if !cfg!(target_os = "windows") {
-        anyhow::bail!("TerraFusion Platform requires Windows Server 2016+ or Windows 10+");
-    }
-    
-    // Get Windows version information
-    let output = Command::new("cmd")
-        .args(&["/c", "ver"])
-        .output()
-        .context("Failed to get Windows version")?;
-    
-    if output.status.success() {
-        let version_info = String::from_utf8_lossy(&output.stdout);
-        info!("Operating system: {}", version_info.trim());
-        
-        // Basic version check (could be enhanced with more specific checks)
-        if version_info.contains("Windows") {
-            info!("✅ Operating system compatibility confirmed");
-        } else {
-            warn!("⚠️ Could not verify Windows version, proceeding with installation");
-        }
+fn check_operating_system(system: &System) -> RequirementCheck {
+    let version = system.long_os_version().unwrap_or_else(|| "unknown".to_string());
+    let check = RequirementCheck::new("System", "Operating system", CheckStatus::Pass)
+        .required("Windows Server 2016+ or Windows 10+")
+        .found(version);
+
+    if cfg!(target_os = "windows") {
+        check
     } else {
-        warn!("⚠️ Could not determine operating system version");
+        RequirementCheck { status: CheckStatus::Fail, ..check }
+            .detail("TerraFusion Platform requires Windows Server 2016+ or Windows 10+")
     }
-    
-    Ok(())
 }
 
-/// Validate available disk space
-async fn validate_disk_space(install_dir: &PathBuf) -> Result<()> {
-    info!("Checking available disk space...");
-    
-    // Get the drive letter from install directory
-    let drive = install_dir
-        .to_string_lossy()
-        .chars()
-        .take(2)
-        .collect::<String>();
-    
-    let output = Command::new("dir")
-        .args(&[&drive, "/-c"])
-        .output()
-        .context("Failed to check disk space")?;
-    
-    if output.status.success() {
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        
-        // Parse free space (simplified parsing)
-        if let Some(line) = output_str.lines().last() {
-            if line.contains("bytes free") {
-                info!("✅ Disk space information: {}", line.trim());
-                
-                // Extract free space in bytes (simplified extraction)
-                if let Some(free_space_str) = line.split_whitespace().nth(2) {
-                    if let Ok(free_space) = free_space_str.replace(",", "").parse::<u64>() {
-                        let free_space_gb = free_space / (1024 * 1024 * 1024);
-                        
-                        if free_space_gb < 10 {
-                            anyhow::bail!("Insufficient disk space. Required: 10GB, Available: {}GB", free_space_gb);
-                        }
-                        
-                        info!("✅ Sufficient disk space available: {}GB", free_space_gb);
-                    }
-                }
-            }
+fn check_cpu(system: &System) -> RequirementCheck {
+    let cores = system.physical_core_count().unwrap_or_else(|| system.cpus().len());
+    let status = grade(cores as u64, MIN_CPU_CORES as u64, RECOMMENDED_CPU_CORES as u64);
+
+    RequirementCheck::new("Hardware", "CPU cores", status)
+        .required(format!("{} ({} recommended)", MIN_CPU_CORES, RECOMMENDED_CPU_CORES))
+        .found(cores.to_string())
+}
+
+fn check_memory(system: &System) -> RequirementCheck {
+    let memory_gb = system.total_memory() / (1024 * 1024 * 1024);
+    let status = grade(memory_gb, MIN_MEMORY_GB, RECOMMENDED_MEMORY_GB);
+
+    RequirementCheck::new("Hardware", "Memory", status)
+        .required(format!("{}GB ({}GB recommended)", MIN_MEMORY_GB, RECOMMENDED_MEMORY_GB))
+        .found(format!("{}GB", memory_gb))
+}
+
+fn check_disk_space(system: &System, install_dir: &Path) -> RequirementCheck {
+    let check = RequirementCheck::new("Hardware", "Free disk space", CheckStatus::Pass)
+        .required(format!("{}GB ({}GB recommended)", MIN_DISK_GB, RECOMMENDED_DISK_GB));
+
+    // The disk holding the install dir is the one with the longest matching mount point
+    let disk = system
+        .disks()
+        .iter()
+        .filter(|disk| install_dir.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len());
+
+    match disk {
+        Some(disk) => {
+            let free_gb = disk.available_space() / (1024 * 1024 * 1024);
+            RequirementCheck { status: grade(free_gb, MIN_DISK_GB, RECOMMENDED_DISK_GB), ..check }
+                .found(format!("{}GB on {}", free_gb, disk.mount_point().display()))
         }
+        None => RequirementCheck { status: CheckStatus::Warn, ..check }
+            .detail("Could not determine the disk for the installation directory"),
+    }
+}
+
+/// Fail below the minimum, warn below the recommendation
+fn grade(value: u64, minimum: u64, recommended: u64) -> CheckStatus {
+    if value < minimum {
+        CheckStatus::Fail
+    } else if value < recommended {
+        CheckStatus::Warn
     } else {
-        warn!("⚠️ Could not check disk space, proceeding with installation");
+        CheckStatus::Pass
     }
-    
-    Ok(())
 }
 
-/// Validate available memory
-async fn validate_memory() -> Result<()> {
-    info!("Checking available memory...");
-    
-    let output = Command::new("wmic")
-        .args(&["computersystem", "get", "TotalPhysicalMemory", "/value"])
-        .output()
-        .context("Failed to check memory")?;
-    
-    if output.status.success() {
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        
-        for line in output_str.lines() {
-            if line.starts_with("TotalPhysicalMemory=") {
-                if let Some(memory_str) = line.split('=').nth(1) {
-                    if let Ok(memory_bytes) = memory_str.trim().parse::<u64>() {
-                        let memory_gb = memory_bytes / (1024 * 1024 * 1024);
-                        
-                        if memory_gb < 4 {
-                            anyhow::bail!("Insufficient memory. Required: 4GB, Available: {}GB", memory_gb);
-                        }
-                        
-                        info!("✅ Sufficient memory available: {}GB", memory_gb);
-                        return Ok(());
-                    }
-                }
+fn check_ports() -> Vec<RequirementCheck> {
+    REQUIRED_PORTS
+        .iter()
+        .map(|&port| {
+            let check = RequirementCheck::new("Network", &format!("Port {}", port), CheckStatus::Pass)
+                .required("available");
+            if is_port_in_use(port) {
+                RequirementCheck { status: CheckStatus::Fail, ..check }
+                    .found("in use")
+                    .detail("Free this port before installation")
+            } else {
+                check.found("available")
             }
+        })
+        .collect()
+}
+
+/// Check if a port is in use
+fn is_port_in_use(port: u16) -> bool {
+    std::net::TcpListener::bind(("0.0.0.0", port)).is_err()
+}
+
+/// Bundled PostgreSQL version, and PostGIS/pgvector availability when a database is configured
+async fn check_postgres(install_dir: &PathBuf) -> Vec<RequirementCheck> {
+    let mut checks = Vec::new();
+
+    let postgres = install_dir
+        .join("database")
+        .join("bin")
+        .join(format!("postgres{}", std::env::consts::EXE_SUFFIX));
+    let version_check = RequirementCheck::new("Database", "PostgreSQL version", CheckStatus::Pass)
+        .required(format!("{}+", MIN_POSTGRES_MAJOR));
+    checks.push(match command_output(&postgres.to_string_lossy(), &["--version"]) {
+        Some(output) => {
+            let major = parse_postgres_major(&output);
+            let status = match major {
+                Some(major) if major >= MIN_POSTGRES_MAJOR => CheckStatus::Pass,
+                Some(_) => CheckStatus::Fail,
+                None => CheckStatus::Warn,
+            };
+            RequirementCheck { status, ..version_check }.found(output.trim())
         }
+        None => RequirementCheck { status: CheckStatus::Warn, ..version_check }
+            .detail(format!("Bundled PostgreSQL not found at {}", postgres.display())),
+    });
+
+    let database_url = match crate::migrate::resolve_database_url(install_dir, None).await {
+        Ok(url) => url,
+        Err(_) => {
+            checks.push(
+                RequirementCheck::new("Database", "PostgreSQL extensions", CheckStatus::Skipped)
+                    .detail("No database configured yet"),
+            );
+            return checks;
+        }
+    };
+
+    let connect = tokio::time::timeout(Duration::from_secs(5), sqlx::PgPool::connect(&database_url)).await;
+    let pool = match connect {
+        Ok(Ok(pool)) => pool,
+        Ok(Err(e)) => {
+            checks.push(
+                RequirementCheck::new("Database", "Connection", CheckStatus::Fail).detail(e.to_string()),
+            );
+            return checks;
+        }
+        Err(_) => {
+            checks.push(
+                RequirementCheck::new("Database", "Connection", CheckStatus::Fail).detail("Timed out after 5s"),
+            );
+            return checks;
+        }
+    };
+
+    let available: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT name, installed_version FROM pg_available_extensions WHERE name IN ('postgis', 'vector')",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap_or_default();
+
+    for (extension, label, missing_status, detail) in [
+        ("postgis", "PostGIS extension", CheckStatus::Fail, "Required for GIS exports"),
+        ("vector", "pgvector extension", CheckStatus::Warn, "Optional; entity matching is disabled without it"),
+    ] {
+        let check = RequirementCheck::new("Database", label, CheckStatus::Pass).required("available");
+        checks.push(match available.iter().find(|(name, _)| name == extension) {
+            Some((_, Some(version))) => check.found(format!("installed {}", version)),
+            Some((_, None)) => check.found("available, not installed"),
+            None => RequirementCheck { status: missing_status, ..check }.found("missing").detail(detail),
+        });
     }
-    
-    warn!("⚠️ Could not verify memory requirements, proceeding with installation");
-    Ok(())
+
+    checks
 }
 
-/// Validate network connectivity
-async fn validate_network_connectivity() -> Result<()> {
-    info!("Checking network connectivity...");
-    
-    // Test basic network connectivity
-    let result = Command::new("ping")
-        .args(&["-n", "2", "8.8.8.8"])
-        .output()
-        .context("Failed to test network connectivity")?;
-    
-    if result.status.success() {
-        info!("✅ Network connectivity confirmed");
-    } else {
-        warn!("⚠️ Limited network connectivity detected, some features may not work");
-    }
-    
-    Ok(())
+fn parse_postgres_major(version_output: &str) -> Option<u32> {
+    // "postgres (PostgreSQL) 15.4"
+    version_output
+        .split_whitespace()
+        .last()?
+        .split('.')
+        .next()?
+        .parse()
+        .ok()
 }
 
-/// Validate port availability
-async fn validate_port_availability() -> Result<()> {
-    info!("Checking port availability...");
-    
-    let required_ports = vec![8000, 8001, 8002, 5433];
-    
-    for port in required_ports {
-        if is_port_in_use(port).await {
-            anyhow::bail!("Port {} is already in use. Please free this port before installation.", port);
-        } else {
-            info!("✅ Port {} is available", port);
+fn check_dotnet() -> RequirementCheck {
+    let check = RequirementCheck::new("Dependencies", ".NET runtime", CheckStatus::Pass).required("6.0+");
+    match command_output("dotnet", &["--list-runtimes"]) {
+        Some(output) => {
+            let runtimes: Vec<&str> = output
+                .lines()
+                .filter(|line| line.starts_with("Microsoft.NETCore.App"))
+                .filter_map(|line| line.split_whitespace().nth(1))
+                .collect();
+            let supported = runtimes
+                .iter()
+                .any(|version| version.split('.').next().and_then(|major| major.parse::<u32>().ok()) >= Some(6));
+            let status = if supported { CheckStatus::Pass } else { CheckStatus::Warn };
+            RequirementCheck { status, ..check }.found(if runtimes.is_empty() { "none".to_string() } else { runtimes.join(", ") })
         }
+        None => RequirementCheck { status: CheckStatus::Warn, ..check }
+            .found("not installed")
+            .detail("Needed by the county CAMA connectors"),
     }
-    
-    Ok(())
 }
 
-/// Check if a port is in use
-async fn is_port_in_use(port: u16) -> bool {
-    let output = Command::new("netstat")
-        .args(&["-an"])
-        .output();
-    
-    match output {
-        Ok(output) => {
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            let port_pattern = format!(":{}", port);
-            output_str.lines().any(|line| {
-                line.contains(&port_pattern) && 
-                (line.contains("LISTENING") || line.contains("ESTABLISHED"))
-            })
+fn check_gdal(install_dir: &Path) -> RequirementCheck {
+    let check = RequirementCheck::new("Dependencies", "GDAL", CheckStatus::Pass).required("3.0+");
+    let bundled = install_dir.join("gdal").join("bin").join(format!("gdalinfo{}", std::env::consts::EXE_SUFFIX));
+    let program = if bundled.exists() { bundled.to_string_lossy().into_owned() } else { "gdalinfo".to_string() };
+
+    match command_output(&program, &["--version"]) {
+        // "GDAL 3.6.2, released 2023/01/02"
+        Some(output) => {
+            let major = output
+                .split_whitespace()
+                .nth(1)
+                .and_then(|version| version.split('.').next())
+                .and_then(|major| major.parse::<u32>().ok());
+            let status = if major >= Some(3) { CheckStatus::Pass } else { CheckStatus::Fail };
+            RequirementCheck { status, ..check }.found(output.trim())
         }
-        Err(_) => false,
+        None => RequirementCheck { status: CheckStatus::Warn, ..check }
+            .found("not installed")
+            .detail("Shapefile and geopackage exports are unavailable without it"),
     }
 }
 
-/// Validate administrator privileges
-async fn validate_administrator_privileges() -> Result<()> {
-    info!("Checking administrator privileges...");
-    
+fn check_network_connectivity() -> RequirementCheck {
+    let check = RequirementCheck::new("Network", "Internet connectivity", CheckStatus::Pass);
+    let count_flag = if cfg!(windows) { "-n" } else { "-c" };
+    match Command::new("ping").args([count_flag, "2", "8.8.8.8"]).output() {
+        Ok(output) if output.status.success() => check.found("reachable"),
+        _ => RequirementCheck { status: CheckStatus::Warn, ..check }
+            .found("limited")
+            .detail("Updates and Let's Encrypt certificates need outbound access"),
+    }
+}
+
+async fn check_administrator_privileges() -> RequirementCheck {
+    let check = RequirementCheck::new("Permissions", "Administrator privileges", CheckStatus::Pass);
+
     // Try to create a test file in a restricted location
     let test_path = PathBuf::from("C:\\Windows\\Temp\\terrafusion_admin_test.txt");
-    
     match fs::write(&test_path, "test").await {
         Ok(_) => {
-            // Clean up test file
             let _ = fs::remove_file(&test_path).await;
-            info!("✅ Administrator privileges confirmed");
-            Ok(())
-        }
-        Err(_) => {
-            anyhow::bail!("Administrator privileges required. Please run the installer as Administrator.");
+            check.found("confirmed")
         }
+        Err(_) => RequirementCheck { status: CheckStatus::Fail, ..check }
+            .found("missing")
+            .detail("Run the installer as Administrator"),
     }
 }
 
-/// Validate Windows service permissions
-async fn validate_service_permissions() -> Result<()> {
-    info!("Checking Windows service permissions...");
-    
-    // Test if we can query services (requires appropriate permissions)
-    let output = Command::new("sc")
-        .args(&["query", "type=", "service", "state=", "all"])
-        .output()
-        .context("Failed to query Windows services")?;
-    
-    if output.status.success() {
-        info!("✅ Windows service permissions confirmed");
+fn check_service_permissions() -> RequirementCheck {
+    let check = RequirementCheck::new("Permissions", "Windows service management", CheckStatus::Pass);
+    match Command::new("sc").args(["query", "type=", "service", "state=", "all"]).output() {
+        Ok(output) if output.status.success() => check.found("confirmed"),
+        _ => RequirementCheck { status: CheckStatus::Fail, ..check }
+            .found("missing")
+            .detail("Insufficient permissions to manage Windows services"),
+    }
+}
+
+/// Run a program and return its stdout (or stderr, where some tools print versions)
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if stdout.trim().is_empty() {
+        Some(String::from_utf8_lossy(&output.stderr).to_string())
     } else {
-        anyhow::bail!("Insufficient permissions to manage Windows services");
+        Some(stdout)
     }
-    
-    Ok(())
 }
 
 /// Validate installation files are present
@@ -522,4 +754,31 @@ pub struct SystemInfo {
     pub memory_info: String,
     pub disk_info: String,
     pub network_info: String,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_grading_and_html() {
+        assert_eq!(grade(2, 4, 8), CheckStatus::Fail);
+        assert_eq!(grade(6, 4, 8), CheckStatus::Warn);
+        assert_eq!(grade(16, 4, 8), CheckStatus::Pass);
+        assert_eq!(parse_postgres_major("postgres (PostgreSQL) 15.4"), Some(15));
+
+        let report = RequirementReport {
+            generated_at: chrono::Utc::now(),
+            host_name: Some("<county-gis>".to_string()),
+            os_version: None,
+            install_dir: PathBuf::from("C:\\TerraFusion"),
+            checks: vec![
+                RequirementCheck::new("Hardware", "Memory", CheckStatus::Warn).found("6GB"),
+                RequirementCheck::new("Network", "Port 8000", CheckStatus::Fail).found("in use"),
+            ],
+        };
+        assert!(report.has_failures());
+        let html = report.to_html();
+        assert!(html.contains("&lt;county-gis&gt;"));
+        assert!(html.contains("0 passed, 1 warnings, 1 failed, 0 skipped"));
+    }
+}
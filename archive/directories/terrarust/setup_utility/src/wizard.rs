@@ -0,0 +1,199 @@
+use anyhow::Result;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, Input, Password, Select};
+
+use crate::tls::TlsMode;
+
+/// Answers collected for a complete installation
+#[derive(Debug, Clone)]
+pub struct SetupPlan {
+    pub county: String,
+    pub admin_email: String,
+    pub port: u16,
+    /// Database password; generated during database creation when `None`
+    pub db_password: Option<String>,
+    pub tls: Option<TlsMode>,
+}
+
+/// Walk the operator through the setup choices, validating each answer
+///
+/// Values already given on the command line are used as defaults.
+pub fn run_setup_wizard(county: Option<String>, admin_email: Option<String>, port: u16) -> Result<SetupPlan> {
+    let theme = ColorfulTheme::default();
+    println!("\n🏛️  TerraFusion Platform Setup\n");
+
+    let mut county_input = Input::<String>::with_theme(&theme)
+        .with_prompt("County ID (e.g. benton-wa)")
+        .validate_with(|input: &String| validate_county_id(input));
+    if let Some(county) = county {
+        county_input = county_input.with_initial_text(county);
+    }
+    let county = county_input.interact_text()?;
+
+    let mut email_input = Input::<String>::with_theme(&theme)
+        .with_prompt("Administrator email")
+        .validate_with(|input: &String| validate_email(input));
+    if let Some(email) = admin_email {
+        email_input = email_input.with_initial_text(email);
+    }
+    let admin_email = email_input.interact_text()?;
+
+    let port = Input::<u16>::with_theme(&theme)
+        .with_prompt("Web interface port")
+        .default(port)
+        .validate_with(|port: &u16| validate_port(*port))
+        .interact_text()?;
+
+    let db_password = if Confirm::with_theme(&theme)
+        .with_prompt("Generate a random database password?")
+        .default(true)
+        .interact()?
+    {
+        None
+    } else {
+        Some(
+            Password::with_theme(&theme)
+                .with_prompt("Database password")
+                .with_confirmation("Confirm password", "Passwords do not match")
+                .validate_with(|input: &String| validate_password(input))
+                .interact()?,
+        )
+    };
+
+    let tls = prompt_tls(&theme, &admin_email)?;
+
+    println!("\nSummary");
+    println!("  County:         {}", county);
+    println!("  Administrator:  {}", admin_email);
+    println!("  Web port:       {} (services use {}-{})", port, port, port + 2);
+    println!("  DB password:    {}", if db_password.is_some() { "provided" } else { "generated" });
+    println!("  TLS:            {}", tls_label(tls.as_ref()));
+    println!();
+
+    if !Confirm::with_theme(&theme).with_prompt("Proceed with installation?").default(true).interact()? {
+        anyhow::bail!("Setup cancelled");
+    }
+
+    Ok(SetupPlan { county, admin_email, port, db_password, tls })
+}
+
+fn prompt_tls(theme: &ColorfulTheme, admin_email: &str) -> Result<Option<TlsMode>> {
+    let choices = [
+        "No TLS (HTTP only)",
+        "Self-signed certificate",
+        "Let's Encrypt certificate",
+        "Certificate signing request for the county CA",
+    ];
+    let choice = Select::with_theme(theme)
+        .with_prompt("TLS certificate")
+        .items(&choices)
+        .default(0)
+        .interact()?;
+    if choice == 0 {
+        return Ok(None);
+    }
+
+    let hostname = Input::<String>::with_theme(theme)
+        .with_prompt("Host name clients will use")
+        .validate_with(|input: &String| validate_hostname(input))
+        .interact_text()?;
+
+    Ok(Some(match choice {
+        1 => TlsMode::SelfSigned { hostname, sans: vec!["localhost".to_string()], days: 825 },
+        2 => TlsMode::LetsEncrypt {
+            hostname,
+            email: admin_email.to_string(),
+            staging: false,
+            renew_within_days: None,
+        },
+        _ => TlsMode::Csr { hostname, sans: Vec::new(), organization: None },
+    }))
+}
+
+fn tls_label(tls: Option<&TlsMode>) -> &'static str {
+    match tls {
+        None => "disabled",
+        Some(TlsMode::SelfSigned { .. }) => "self-signed",
+        Some(TlsMode::LetsEncrypt { .. }) => "Let's Encrypt",
+        Some(TlsMode::Csr { .. }) | Some(TlsMode::Install { .. }) => "county CA (CSR)",
+    }
+}
+
+/// County IDs are lowercase names followed by a two-letter state, e.g. `benton-wa`
+pub fn validate_county_id(input: &str) -> std::result::Result<(), String> {
+    let parts: Vec<&str> = input.split('-').collect();
+    let valid = parts.len() >= 2
+        && parts.iter().all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase()))
+        && parts.last().map_or(false, |state| state.len() == 2);
+
+    if valid {
+        Ok(())
+    } else {
+        Err("Use lowercase county name and state, e.g. benton-wa".to_string())
+    }
+}
+
+pub fn validate_email(input: &str) -> std::result::Result<(), String> {
+    match input.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.') => Ok(()),
+        _ => Err("Enter a valid email address".to_string()),
+    }
+}
+
+/// The web port and the two service ports after it must be free
+fn validate_port(port: u16) -> std::result::Result<(), String> {
+    if port < 1024 || port > 65533 {
+        return Err("Choose a port between 1024 and 65533".to_string());
+    }
+    for candidate in port..=port + 2 {
+        if std::net::TcpListener::bind(("0.0.0.0", candidate)).is_err() {
+            return Err(format!("Port {} is already in use", candidate));
+        }
+    }
+    Ok(())
+}
+
+fn validate_password(input: &str) -> std::result::Result<(), String> {
+    if input.len() < 12 {
+        Err("Use at least 12 characters".to_string())
+    } else if input.contains('\'') {
+        Err("Single quotes are not supported".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_hostname(input: &str) -> std::result::Result<(), String> {
+    let valid = !input.is_empty()
+        && input.len() <= 253
+        && input.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err("Enter a valid host name".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wizard_validators() {
+        assert!(validate_county_id("benton-wa").is_ok());
+        assert!(validate_county_id("walla-walla-wa").is_ok());
+        assert!(validate_county_id("Benton-WA").is_err());
+        assert!(validate_county_id("benton").is_err());
+        assert!(validate_email("it@co.benton.wa.us").is_ok());
+        assert!(validate_email("it@localhost").is_err());
+        assert!(validate_hostname("gis.co.benton.wa.us").is_ok());
+        assert!(validate_hostname("-bad.example").is_err());
+    }
+}
@@ -1,6 +1,44 @@
 use std::env;
 use std::time::Duration;
 
+/// Which parts of the sync service a process runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceRole {
+    /// API, scheduler and queued operations in one process
+    All,
+    /// API and scheduler; queued operations are left to worker processes
+    Api,
+    /// Queued operations only, plus the system endpoints for probes
+    Worker,
+}
+
+impl ServiceRole {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "all" => Some(Self::All),
+            "api" => Some(Self::Api),
+            "worker" => Some(Self::Worker),
+            _ => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Api => "api",
+            Self::Worker => "worker",
+        }
+    }
+    
+    pub fn runs_api(&self) -> bool {
+        *self != Self::Worker
+    }
+    
+    pub fn runs_operations(&self) -> bool {
+        *self != Self::Api
+    }
+}
+
 /// Configuration for the Sync Service
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -9,6 +47,8 @@ pub struct Config {
     pub port: u16,
     pub worker_threads: usize,
     pub environment: String,
+    pub role: ServiceRole,
+    pub worker_heartbeat_seconds: u64,
     
     // Security configuration
     pub use_ssl: bool,
@@ -18,6 +58,7 @@ pub struct Config {
     // Database configuration
     pub database_url: String,
     pub database_pool_size: u32,
+    pub credential_check_interval_seconds: u64,
     
     // Sync configuration
     pub sync_batch_size: usize,
@@ -25,15 +66,42 @@ pub struct Config {
     pub max_concurrent_syncs: usize,
     pub retry_attempts: u32,
     pub retry_delay_seconds: u64,
+    pub cdc_poll_interval_seconds: u64,
+    pub email_poll_interval_seconds: u64,
     
     // Scheduler configuration
     pub scheduler_enabled: bool,
     pub scheduler_interval_seconds: u64,
     pub cleanup_interval_hours: u64,
+    pub payload_seal_interval_minutes: u64,
+    pub schema_check_interval_minutes: u64,
     
     // Metrics configuration
     pub metrics_enabled: bool,
     pub metrics_port: u16,
+    
+    // Runtime settings reload configuration
+    pub config_reload_interval_seconds: u64,
+    
+    // NarratorAI configuration
+    pub narrator_ai_url: Option<String>,
+    pub narrator_ai_timeout_seconds: u64,
+    
+    // Entity matching configuration
+    pub entity_matching_enabled: bool,
+    pub embedding_model: String,
+    pub similarity_match_threshold: f64,
+    
+    // Source and target connector configuration
+    pub connector_timeout_seconds: u64,
+    
+    // GIS export service, for pipeline export steps
+    pub gis_export_service_url: String,
+    
+    // Uploaded files for ad-hoc imports
+    pub import_dir: String,
+    pub max_import_bytes: u64,
+    pub import_retention_hours: u64,
 }
 
 impl Config {
@@ -52,6 +120,15 @@ impl Config {
         
         let environment = env::var("ENVIRONMENT").unwrap_or_else(|_| "development".to_string());
         
+        // Scale-out: API processes queue operations, worker processes run them
+        let role = ServiceRole::parse(&env::var("SYNC_SERVICE_ROLE").unwrap_or_else(|_| "all".to_string()))
+            .expect("SYNC_SERVICE_ROLE must be all, api or worker");
+        
+        let worker_heartbeat_seconds = env::var("WORKER_HEARTBEAT_SECONDS")
+            .unwrap_or_else(|_| "15".to_string())
+            .parse::<u64>()
+            .expect("WORKER_HEARTBEAT_SECONDS must be a valid integer");
+        
         // Security configuration
         let use_ssl = env::var("USE_SSL")
             .unwrap_or_else(|_| "false".to_string())
@@ -62,12 +139,18 @@ impl Config {
         let ssl_key_file = env::var("SSL_KEY_FILE").unwrap_or_else(|_| "certs/server.key".to_string());
         
         // Database configuration
-        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is required");
+        // May be empty when credentials come from a secrets provider
+        let database_url = env::var("DATABASE_URL").unwrap_or_default();
         let database_pool_size = env::var("DATABASE_POOL_SIZE")
             .unwrap_or_else(|_| "10".to_string())
             .parse::<u32>()
             .expect("DATABASE_POOL_SIZE must be a valid integer");
         
+        let credential_check_interval_seconds = env::var("DATABASE_CREDENTIAL_CHECK_SECONDS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse::<u64>()
+            .expect("DATABASE_CREDENTIAL_CHECK_SECONDS must be a valid integer");
+        
         // Sync configuration
         let sync_batch_size = env::var("SYNC_BATCH_SIZE")
             .unwrap_or_else(|_| "100".to_string())
@@ -94,6 +177,18 @@ impl Config {
             .parse::<u64>()
             .expect("SYNC_RETRY_DELAY_SECONDS must be a valid integer");
         
+        // Pairs streaming source changes are polled this often
+        let cdc_poll_interval_seconds = env::var("CDC_POLL_INTERVAL_SECONDS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u64>()
+            .expect("CDC_POLL_INTERVAL_SECONDS must be a valid integer");
+        
+        // Mailboxes feeding file sources are checked this often
+        let email_poll_interval_seconds = env::var("EMAIL_POLL_INTERVAL_SECONDS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse::<u64>()
+            .expect("EMAIL_POLL_INTERVAL_SECONDS must be a valid integer");
+        
         // Scheduler configuration
         let scheduler_enabled = env::var("SCHEDULER_ENABLED")
             .unwrap_or_else(|_| "true".to_string())
@@ -110,6 +205,18 @@ impl Config {
             .parse::<u64>()
             .expect("CLEANUP_INTERVAL_HOURS must be a valid integer");
         
+        // Diffs written since the last sweep stay in plaintext until the next one
+        let payload_seal_interval_minutes = env::var("PAYLOAD_SEAL_INTERVAL_MINUTES")
+            .unwrap_or_else(|_| "15".to_string())
+            .parse::<u64>()
+            .expect("PAYLOAD_SEAL_INTERVAL_MINUTES must be a valid integer");
+        
+        // Source and target schemas of active pairs are compared this often
+        let schema_check_interval_minutes = env::var("SCHEMA_CHECK_INTERVAL_MINUTES")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .expect("SCHEMA_CHECK_INTERVAL_MINUTES must be a valid integer");
+        
         // Metrics configuration
         let metrics_enabled = env::var("METRICS_ENABLED")
             .unwrap_or_else(|_| "true".to_string())
@@ -121,26 +228,91 @@ impl Config {
             .parse::<u16>()
             .expect("METRICS_PORT must be a valid port number");
         
+        // Runtime settings reload configuration
+        let config_reload_interval_seconds = env::var("CONFIG_RELOAD_INTERVAL_SECONDS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<u64>()
+            .expect("CONFIG_RELOAD_INTERVAL_SECONDS must be a valid integer");
+        
+        // NarratorAI configuration (operation summaries are skipped when unset)
+        let narrator_ai_url = env::var("NARRATOR_AI_URL").ok().filter(|url| !url.is_empty());
+        let narrator_ai_timeout_seconds = env::var("NARRATOR_AI_TIMEOUT_SECONDS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .expect("NARRATOR_AI_TIMEOUT_SECONDS must be a valid integer");
+        
+        // Entity matching configuration (requires NarratorAI and pgvector)
+        let entity_matching_enabled = env::var("ENTITY_MATCHING_ENABLED")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .expect("ENTITY_MATCHING_ENABLED must be true or false");
+        
+        let embedding_model = env::var("AI_EMBEDDING_MODEL")
+            .unwrap_or_else(|_| "nomic-embed-text".to_string());
+        
+        let similarity_match_threshold = env::var("SIMILARITY_MATCH_THRESHOLD")
+            .unwrap_or_else(|_| "0.92".to_string())
+            .parse::<f64>()
+            .expect("SIMILARITY_MATCH_THRESHOLD must be a number");
+        
+        // Limit for connectivity tests and previews against county systems
+        let connector_timeout_seconds = env::var("CONNECTOR_TIMEOUT_SECONDS")
+            .unwrap_or_else(|_| "15".to_string())
+            .parse::<u64>()
+            .expect("CONNECTOR_TIMEOUT_SECONDS must be a valid integer");
+        
+        let gis_export_service_url = env::var("GIS_EXPORT_SERVICE_URL")
+            .unwrap_or_else(|_| "http://localhost:8002".to_string());
+        
+        // Shared by every instance that runs operations
+        let import_dir = env::var("IMPORT_DIR").unwrap_or_else(|_| "data/imports".to_string());
+        let max_import_bytes = env::var("MAX_IMPORT_BYTES")
+            .unwrap_or_else(|_| (512 * 1024 * 1024).to_string())
+            .parse::<u64>()
+            .expect("MAX_IMPORT_BYTES must be a valid integer");
+        let import_retention_hours = env::var("IMPORT_RETENTION_HOURS")
+            .unwrap_or_else(|_| "72".to_string())
+            .parse::<u64>()
+            .expect("IMPORT_RETENTION_HOURS must be a valid integer");
+        
         Self {
             host,
             port,
             worker_threads,
             environment,
+            role,
+            worker_heartbeat_seconds,
             use_ssl,
             ssl_cert_file,
             ssl_key_file,
             database_url,
             database_pool_size,
+            credential_check_interval_seconds,
             sync_batch_size,
             sync_timeout_seconds,
             max_concurrent_syncs,
             retry_attempts,
             retry_delay_seconds,
+            cdc_poll_interval_seconds,
+            email_poll_interval_seconds,
             scheduler_enabled,
             scheduler_interval_seconds,
             cleanup_interval_hours,
+            payload_seal_interval_minutes,
+            schema_check_interval_minutes,
             metrics_enabled,
             metrics_port,
+            config_reload_interval_seconds,
+            narrator_ai_url,
+            narrator_ai_timeout_seconds,
+            entity_matching_enabled,
+            embedding_model,
+            similarity_match_threshold,
+            connector_timeout_seconds,
+            gis_export_service_url,
+            import_dir,
+            max_import_bytes,
+            import_retention_hours,
         }
     }
     
@@ -169,6 +341,16 @@ impl Config {
         Duration::from_secs(self.retry_delay_seconds)
     }
     
+    /// Get CDC poll interval as Duration
+    pub fn cdc_poll_interval(&self) -> Duration {
+        Duration::from_secs(self.cdc_poll_interval_seconds.max(1))
+    }
+    
+    /// Get email ingestion poll interval as Duration
+    pub fn email_poll_interval(&self) -> Duration {
+        Duration::from_secs(self.email_poll_interval_seconds.max(1))
+    }
+    
     /// Get scheduler interval as Duration
     pub fn scheduler_interval(&self) -> Duration {
         Duration::from_secs(self.scheduler_interval_seconds)
@@ -178,4 +360,49 @@ impl Config {
     pub fn cleanup_interval(&self) -> Duration {
         Duration::from_secs(self.cleanup_interval_hours * 3600)
     }
-}
\ No newline at end of file
+    
+    /// Get payload sealing interval as Duration
+    pub fn payload_seal_interval(&self) -> Duration {
+        Duration::from_secs(self.payload_seal_interval_minutes.max(1) * 60)
+    }
+    
+    /// Get schema drift check interval as Duration
+    pub fn schema_check_interval(&self) -> Duration {
+        Duration::from_secs(self.schema_check_interval_minutes.max(1) * 60)
+    }
+    
+    /// Get worker heartbeat interval as Duration
+    pub fn worker_heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.worker_heartbeat_seconds.max(1))
+    }
+    
+    /// Workers silent for three heartbeats are presumed gone
+    pub fn worker_stale_after(&self) -> Duration {
+        self.worker_heartbeat_interval() * 3
+    }
+    
+    /// Get database credential rotation check interval as Duration
+    pub fn credential_check_interval(&self) -> Duration {
+        Duration::from_secs(self.credential_check_interval_seconds)
+    }
+    
+    /// Get config file polling interval as Duration
+    pub fn config_reload_interval(&self) -> Duration {
+        Duration::from_secs(self.config_reload_interval_seconds)
+    }
+    
+    /// Get NarratorAI request timeout as Duration
+    pub fn narrator_ai_timeout(&self) -> Duration {
+        Duration::from_secs(self.narrator_ai_timeout_seconds)
+    }
+    
+    /// Get source/target connector timeout as Duration
+    pub fn connector_timeout(&self) -> Duration {
+        Duration::from_secs(self.connector_timeout_seconds)
+    }
+    
+    /// How long uploaded import files are kept
+    pub fn import_retention(&self) -> Duration {
+        Duration::from_secs(self.import_retention_hours.max(1) * 3600)
+    }
+}
@@ -1,5 +1,5 @@
-use actix_web::{web, HttpResponse, Responder};
-use common::error::Error;
+use actix_web::{web, HttpResponse};
+use terrafusion_common::{Error, Result};
 use common::models::sync_operation::SyncDiff;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -30,25 +30,20 @@ pub struct SyncDiffQuery {
 pub async fn get_all_diffs(
     query: web::Query<SyncDiffQuery>,
     state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse> {
     // In a real implementation, this would query the database
     // For now, return a stub response
-    HttpResponse::Ok().json(SyncDiffsResponse {
+    Ok(HttpResponse::Ok().json(SyncDiffsResponse {
         diffs: Vec::new(),
         total_count: 0,
-    })
+    }))
 }
 
 pub async fn get_diff(
     id: web::Path<Uuid>,
     state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse> {
     // In a real implementation, this would query the database
     // For now, return a stub response
-    HttpResponse::NotFound().json(web::Json(
-        serde_json::json!({
-            "error": format!("Sync diff not found: {}", id),
-            "status": 404
-        })
-    ))
+    Err(Error::NotFound(format!("Sync diff not found: {}", id)))
 }
\ No newline at end of file
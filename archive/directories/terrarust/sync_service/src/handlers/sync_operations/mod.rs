@@ -1,5 +1,5 @@
-use actix_web::{web, HttpResponse, Responder};
-use common::error::{Error, Result};
+use actix_web::{web, HttpResponse};
+use terrafusion_common::{Error, Result};
 use common::models::sync_operation::{SyncOperation, CreateSyncOperationParams};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -31,69 +31,46 @@ pub struct SyncOperationQuery {
 pub async fn get_all_operations(
     query: web::Query<SyncOperationQuery>,
     state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse> {
     // In a real implementation, this would query the database
     // For now, return a stub response
-    HttpResponse::Ok().json(SyncOperationsResponse {
+    Ok(HttpResponse::Ok().json(SyncOperationsResponse {
         operations: Vec::new(),
         total_count: 0,
-    })
+    }))
 }
 
 pub async fn start_operation(
     req: web::Json<CreateSyncOperationParams>,
     state: web::Data<AppState>,
-) -> impl Responder {
-    match state.sync_engine.start_sync_operation(req.0).await {
-        Ok(operation) => {
-            HttpResponse::Created().json(SyncOperationResponse {
-                operation,
-            })
-        },
-        Err(e) => {
-            log::error!("Failed to start sync operation: {}", e);
-            HttpResponse::BadRequest().json(web::Json(
-                serde_json::json!({
-                    "error": format!("Failed to start sync operation: {}", e),
-                    "status": 400
-                })
-            ))
-        }
-    }
+) -> Result<HttpResponse> {
+    let operation = state.sync_engine.start_sync_operation(req.0).await.map_err(|e| {
+        log::error!("Failed to start sync operation: {}", e);
+        Error::Validation(format!("Failed to start sync operation: {}", e))
+    })?;
+    Ok(HttpResponse::Created().json(SyncOperationResponse {
+        operation,
+    }))
 }
 
 pub async fn get_operation(
     id: web::Path<Uuid>,
     state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse> {
     // In a real implementation, this would query the database
     // For now, return a stub response
-    HttpResponse::NotFound().json(web::Json(
-        serde_json::json!({
-            "error": format!("Sync operation not found: {}", id),
-            "status": 404
-        })
-    ))
+    Err(Error::NotFound(format!("Sync operation not found: {}", id)))
 }
 
 pub async fn cancel_operation(
     id: web::Path<Uuid>,
     state: web::Data<AppState>,
-) -> impl Responder {
-    match state.sync_engine.cancel_sync_operation(*id).await {
-        Ok(operation) => {
-            HttpResponse::Ok().json(SyncOperationResponse {
-                operation,
-            })
-        },
-        Err(e) => {
-            log::error!("Failed to cancel sync operation {}: {}", id, e);
-            HttpResponse::BadRequest().json(web::Json(
-                serde_json::json!({
-                    "error": format!("Failed to cancel sync operation: {}", e),
-                    "status": 400
-                })
-            ))
-        }
-    }
+) -> Result<HttpResponse> {
+    let operation = state.sync_engine.cancel_sync_operation(*id).await.map_err(|e| {
+        log::error!("Failed to cancel sync operation {}: {}", id, e);
+        Error::Validation(format!("Failed to cancel sync operation: {}", e))
+    })?;
+    Ok(HttpResponse::Ok().json(SyncOperationResponse {
+        operation,
+    }))
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
-use actix_web::{web, HttpResponse, Responder};
-use common::error::Error;
+use actix_web::{web, HttpResponse};
+use terrafusion_common::{Error, Result};
 use common::models::sync_operation::{SyncPair, CreateSyncPairParams};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -48,19 +48,19 @@ pub struct ToggleSyncPairRequest {
 pub async fn get_all_pairs(
     query: web::Query<SyncPairQuery>,
     state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse> {
     // In a real implementation, this would query the database
     // For now, return a stub response
-    HttpResponse::Ok().json(SyncPairsResponse {
+    Ok(HttpResponse::Ok().json(SyncPairsResponse {
         sync_pairs: Vec::new(),
         total_count: 0,
-    })
+    }))
 }
 
 pub async fn create_pair(
     req: web::Json<CreateSyncPairParams>,
     state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse> {
     // In a real implementation, this would save to the database
     // For now, return a stub response
     let now = Utc::now();
@@ -84,51 +84,36 @@ pub async fn create_pair(
         metadata: req.metadata.clone(),
     };
     
-    HttpResponse::Created().json(SyncPairResponse {
+    Ok(HttpResponse::Created().json(SyncPairResponse {
         sync_pair,
-    })
+    }))
 }
 
 pub async fn get_pair(
     id: web::Path<Uuid>,
     state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse> {
     // In a real implementation, this would query the database
     // For now, return a stub response
-    HttpResponse::NotFound().json(web::Json(
-        serde_json::json!({
-            "error": format!("Sync pair not found: {}", id),
-            "status": 404
-        })
-    ))
+    Err(Error::NotFound(format!("Sync pair not found: {}", id)))
 }
 
 pub async fn update_pair(
     id: web::Path<Uuid>,
     req: web::Json<UpdateSyncPairRequest>,
     state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse> {
     // In a real implementation, this would update the database
     // For now, return a stub response
-    HttpResponse::NotFound().json(web::Json(
-        serde_json::json!({
-            "error": format!("Sync pair not found: {}", id),
-            "status": 404
-        })
-    ))
+    Err(Error::NotFound(format!("Sync pair not found: {}", id)))
 }
 
 pub async fn toggle_pair(
     id: web::Path<Uuid>,
     req: web::Json<ToggleSyncPairRequest>,
     state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse> {
     // In a real implementation, this would update the database
     // For now, return a stub response
-    HttpResponse::NotFound().json(web::Json(
-        serde_json::json!({
-            "error": format!("Sync pair not found: {}", id),
-            "status": 404
-        })
-    ))
+    Err(Error::NotFound(format!("Sync pair not found: {}", id)))
 }
\ No newline at end of file
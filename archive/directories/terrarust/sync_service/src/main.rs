@@ -3,7 +3,7 @@ use actix_web::middleware::{Logger, NormalizePath};
 use env_logger::Env;
 use dotenv::dotenv;
 use std::io;
-use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
+use terrafusion_common::errors::web::ErrorEnvelopeMiddleware;
 
 mod routes;
 mod handlers;
@@ -34,24 +34,123 @@ async fn main() -> io::Result<()> {
     // Load configuration
     let config = config::Config::from_env();
     
-    // Initialize database connection
-    let db_pool = terrafusion_common::database::create_pool_from_env().await
+    // Initialize database connection with credentials from the secrets provider
+    let secrets = terrafusion_common::secrets::SecretsManager::from_env()
+        .expect("Failed to initialize secrets provider");
+    let db_config = terrafusion_common::database::DbConfig::from_env()
+        .expect("Invalid database configuration");
+    let db_pool = terrafusion_common::database::RotatingPool::connect(secrets, db_config).await
         .expect("Failed to create database pool");
+    db_pool.spawn_rotation(config.credential_check_interval());
+    if config.metrics_enabled {
+        db_pool.spawn_metrics(std::time::Duration::from_secs(15));
+    }
+    
+    // Load hot-reloadable runtime settings and watch the config file
+    let runtime_config = terrafusion_common::config::ReloadHandle::from_env()
+        .expect("Failed to load runtime settings");
+    runtime_config.spawn_watcher(config.config_reload_interval());
     
     // Initialize services
-    let sync_engine = services::sync_engine::SyncEngine::new(db_pool.clone());
+    let plugins = services::connectors::plugin_kinds();
+    if !plugins.is_empty() {
+        log::info!("Connector plugins: {}", plugins.join(", "));
+    }
+    let mut sync_engine = services::sync_engine::SyncEngine::new(db_pool.clone());
+    if let Some(url) = &config.narrator_ai_url {
+        log::info!("Operation summaries via NarratorAI at {}", url);
+        let narrator = services::narrator::NarratorClient::new(url, config.narrator_ai_timeout());
+        
+        if config.entity_matching_enabled {
+            log::info!("Entity matching enabled (threshold {})", config.similarity_match_threshold);
+            sync_engine = sync_engine.with_entity_matcher(services::entity_matcher::EntityMatcher::new(
+                narrator.clone(),
+                db_pool.clone(),
+                config.embedding_model.clone(),
+                config.similarity_match_threshold,
+            ));
+        }
+        
+        sync_engine = sync_engine.with_narrator(narrator);
+    }
+    
+    // Alerts for failed operations and conflicts ([notifications] in TERRAFUSION_CONFIG)
+    let notifier = terrafusion_common::notifications::Notifier::from_env()
+        .expect("Invalid notification settings");
+    if notifier.settings().enabled {
+        notifier.spawn_digest();
+    }
+    sync_engine = sync_engine.with_notifier(notifier.clone());
+    
+    // Maintenance windows are shared through the database so every instance sees them
+    let maintenance = terrafusion_common::maintenance::MaintenanceHandle::new();
+    sync_engine = sync_engine.with_maintenance(maintenance.clone());
+    
+    // Instances coordinate operations and scheduler ticks through shared locks
+    let locks = terrafusion_common::locks::LockManager::from_env(
+        terrafusion_common::locks::PgLockBackend::rotating(db_pool.clone()),
+    )
+    .expect("Invalid lock settings");
+    sync_engine = sync_engine.with_locks(locks);
+    sync_engine.watch_settings(runtime_config.subscribe());
+    
+    let feature_flags = terrafusion_common::features::FeatureFlags::new();
+    
+    // Background jobs shared by every instance (JOB_QUEUE_BACKEND selects the broker)
+    let jobs = terrafusion_common::jobs::JobQueue::from_env(
+        terrafusion_common::jobs::PgJobBackend::rotating(db_pool.clone()),
+    )
+    .expect("Invalid job queue settings");
+    sync_engine = sync_engine.with_job_queue(jobs.clone());
+    
+    // Pair configs reference stored credentials, sealed with county keys from the secrets provider
+    let credentials = services::credentials::CredentialVault::from_env(db_pool.clone())
+        .expect("Invalid secrets provider settings");
+    sync_engine = sync_engine.with_credentials(credentials.clone());
+    
+    // Uploaded files stand in for a pair's source in previews and one-off operations
+    let imports = services::imports::ImportStore::new(
+        db_pool.clone(),
+        &config.import_dir,
+        config.max_import_bytes,
+        config.import_retention(),
+    );
+    sync_engine = sync_engine.with_imports(imports.clone());
+    
+    // Diffs are written in batches, through COPY unless DIFF_WRITE_MODE=insert
+    let diff_writes = terrafusion_common::database::diff_writer::DiffWriteSettings::from_env()
+        .expect("Invalid diff write settings");
+    sync_engine = sync_engine.with_diff_writes(diff_writes);
+    
+    // Monthly partitions of sync_diffs and audit_log are created ahead and dropped past retention
+    let partitions = terrafusion_common::database::partitions::PartitionSettings::from_env()
+        .expect("Invalid partition maintenance settings");
+    
+    // Sensitive diff payloads are sealed at rest when PAYLOAD_ENCRYPTION_ENABLED is set
+    let payloads = terrafusion_common::encryption::PayloadCipher::from_env(db_pool.clone())
+        .expect("Invalid payload encryption settings");
+    let workers = services::workers::WorkerRegistry::new(db_pool.clone(), config.role);
+    log::info!("Running as {} ({} role)", workers.worker_id(), config.role.as_str());
     
     // Create shared application state
     let app_state = web::Data::new(AppState {
         db_pool: db_pool.clone(),
         config: config.clone(),
         sync_engine: sync_engine.clone(),
+        runtime_config,
+        maintenance: maintenance.clone(),
+        feature_flags: feature_flags.clone(),
+        dashboard_cache: services::dashboard::DashboardCache::new(std::time::Duration::from_secs(30)),
+        retention: services::retention::RetentionJob::new(db_pool.clone()),
+        jobs: jobs.clone(),
+        workers: workers.clone(),
+        payloads: payloads.clone(),
+        credentials: credentials.clone(),
+        imports: imports.clone(),
     });
     
     // Run database migrations
-    let mut migrator = terrafusion_common::database::migrations::Migrator::new(db_pool.clone());
-    // Register migrations here
-    // migrations::register_all_migrations(&mut migrator);
+    let migrator = terrafusion_common::database::migrations::Migrator::with_embedded_migrations(db_pool.pool());
     
     // Run pending migrations
     match migrator.run_pending_migrations().await {
@@ -59,19 +158,84 @@ async fn main() -> io::Result<()> {
         Err(e) => log::error!("Database migration error: {}", e),
     }
     
-    // Initialize scheduler
-    let scheduler_handle = services::scheduler::start_scheduler(sync_engine, db_pool.clone())
-        .await
-        .expect("Failed to start scheduler");
+    if let Err(e) = maintenance.refresh(&db_pool.pool()).await {
+        log::error!("Failed to load maintenance status: {}", e);
+    }
+    maintenance.spawn_refresh(db_pool.clone(), config.config_reload_interval());
+    
+    if let Err(e) = feature_flags.refresh(&db_pool.pool()).await {
+        log::error!("Failed to load feature flags: {}", e);
+    }
+    feature_flags.spawn_refresh(db_pool.clone(), config.config_reload_interval());
+    terrafusion_common::idempotency::spawn_purge(db_pool.clone(), std::time::Duration::from_secs(3600));
+    // Retention and partition maintenance share the maintenance queue, so one worker handles both
+    let maintenance_worker = app_state.retention.register(jobs.worker(&[services::retention::RETENTION_QUEUE]));
+    services::partitions::PartitionMaintenance::new(db_pool.clone(), partitions)
+        .register(maintenance_worker)
+        .concurrency(1)
+        .spawn();
+    services::retention::RetentionJob::schedule(jobs.clone(), config.cleanup_interval());
+    services::partitions::PartitionMaintenance::schedule(jobs.clone(), config.cleanup_interval());
+    imports
+        .register(jobs.worker(&[services::imports::IMPORT_QUEUE]))
+        .concurrency(1)
+        .spawn();
+    services::imports::ImportStore::schedule(jobs.clone());
+    if let Some(cipher) = payloads {
+        services::payloads::PayloadSweep::new(db_pool.clone(), cipher)
+            .register(jobs.worker(&[services::payloads::PAYLOAD_QUEUE]))
+            .concurrency(1)
+            .spawn();
+        services::payloads::PayloadSweep::schedule(jobs.clone(), config.payload_seal_interval());
+    }
+    jobs.spawn_maintenance(std::time::Duration::from_secs(60));
+    
+    // Worker processes run queued operations; API-only processes leave them to workers
+    if config.role.runs_operations() {
+        sync_engine
+            .register(jobs.worker(&[services::sync_engine::SYNC_QUEUE]))
+            .concurrency(config.max_concurrent_syncs)
+            .spawn();
+        // Pairs in CDC mode stream source changes continuously; the pair locks
+        // keep workers from applying the same changes twice
+        services::cdc::CdcRunner::new(db_pool.clone(), sync_engine.clone()).spawn(config.cdc_poll_interval());
+        // Districts that email their spreadsheets feed file sources from a mailbox
+        services::email_ingest::EmailIngestRunner::new(db_pool.clone(), sync_engine.clone())
+            .spawn(config.email_poll_interval());
+        services::pipelines::PipelineRunner::new(db_pool.clone(), sync_engine.clone(), &config.gis_export_service_url)
+            .register(jobs.worker(&[services::pipelines::PIPELINE_QUEUE]))
+            .concurrency(config.max_concurrent_syncs)
+            .spawn();
+        // Mapped fields that vanish or change type are flagged before the next run trips over them
+        services::schema_drift::SchemaDriftCheck::new(db_pool.clone(), credentials.clone(), config.connector_timeout())
+            .with_notifier(notifier)
+            .register(jobs.worker(&[services::schema_drift::SCHEMA_QUEUE]))
+            .concurrency(1)
+            .spawn();
+        services::schema_drift::SchemaDriftCheck::schedule(jobs.clone(), config.schema_check_interval());
+    }
+    workers.spawn_heartbeat(sync_engine.clone(), config.worker_heartbeat_interval());
+    
+    // Initialize scheduler (API processes only; ticks are locked across instances)
+    let scheduler_handle = if config.role.runs_api() {
+        Some(
+            services::scheduler::start_scheduler(sync_engine, db_pool.clone())
+                .await
+                .expect("Failed to start scheduler"),
+        )
+    } else {
+        None
+    };
     
     log::info!("Starting Sync Service on {}:{}", config.host, config.port);
     
     // Configure and start HTTP server
     let server = if config.use_ssl {
-        // Configure SSL
-        let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
-        builder.set_private_key_file(&config.ssl_key_file, SslFiletype::PEM).unwrap();
-        builder.set_certificate_chain_file(&config.ssl_cert_file).unwrap();
+        // Configure SSL; renewed certificates are picked up without a restart
+        let tls = terrafusion_common::tls::ReloadingTls::load(&config.ssl_cert_file, &config.ssl_key_file)
+            .expect("Failed to load TLS certificate");
+        tls.spawn_watcher(config.config_reload_interval());
+        let builder = tls.acceptor().expect("Failed to configure TLS");
         
         // Start HTTPS server
         HttpServer::new(move || create_app(app_state.clone()))
@@ -91,7 +255,10 @@ async fn main() -> io::Result<()> {
     server_handle.await?;
     
     // Shutdown scheduler gracefully
-    scheduler_handle.shutdown().await;
+    if let Some(scheduler_handle) = scheduler_handle {
+        scheduler_handle.shutdown().await;
+    }
+    workers.deregister().await;
     
     Ok(())
 }
@@ -106,8 +273,11 @@ fn create_app(app_state: web::Data<AppState>) -> App<
 > {
     App::new()
         .wrap(Logger::default())
+        .wrap(ErrorEnvelopeMiddleware)
         .wrap(NormalizePath::trim())
         .app_data(app_state.clone())
+        // For the RequireFeature extractor
+        .app_data(web::Data::new(app_state.feature_flags.clone()))
         
         // API Routes
         .service(
@@ -125,23 +295,58 @@ fn create_app(app_state: web::Data<AppState>) -> App<
         .service(
             web::scope("/sync-pairs")
                 .configure(routes::sync_pairs::configure)
+                .configure(routes::hooks::configure)
         )
         .service(
             web::scope("/sync-operations")
                 .configure(routes::sync_operations::configure)
         )
+        .service(
+            web::scope("/pipelines")
+                .configure(routes::pipelines::configure)
+        )
+        
+        // Connector credentials
+        .service(
+            web::scope("/credentials")
+                .configure(routes::credentials::configure)
+        )
+        
+        // Uploaded files for one-off imports
+        .service(
+            web::scope("/imports")
+                .configure(routes::imports::configure)
+        )
+        
+        // Inbound webhooks from external systems, authenticated by hook secret
+        .service(
+            web::scope("/hooks")
+                .app_data(web::PayloadConfig::new(services::hooks::MAX_PAYLOAD_BYTES))
+                .configure(routes::hooks::configure_receiver)
+        )
         
         // Error handlers
         .app_data(web::JsonConfig::default().error_handler(|err, _req| {
             log::error!("JSON parsing error: {:?}", err);
-            actix_web::error::ErrorBadRequest(format!("JSON parsing error: {}", err))
+            terrafusion_common::Error::Validation(format!("JSON parsing error: {}", err)).into()
         }))
 }
 
 /// Application state shared across all handlers
 #[derive(Clone)]
 pub struct AppState {
-    pub db_pool: terrafusion_common::database::DbPool,
+    pub db_pool: terrafusion_common::database::RotatingPool,
     pub config: config::Config,
     pub sync_engine: services::sync_engine::SyncEngine,
+    pub runtime_config: terrafusion_common::config::ReloadHandle,
+    pub maintenance: terrafusion_common::maintenance::MaintenanceHandle,
+    pub feature_flags: terrafusion_common::features::FeatureFlags,
+    pub dashboard_cache: services::dashboard::DashboardCache,
+    pub retention: services::retention::RetentionJob,
+    pub jobs: terrafusion_common::jobs::JobQueue,
+    pub workers: services::workers::WorkerRegistry,
+    /// Present when payload encryption is enabled
+    pub payloads: Option<terrafusion_common::encryption::PayloadCipher>,
+    pub credentials: services::credentials::CredentialVault,
+    pub imports: services::imports::ImportStore,
 }
\ No newline at end of file
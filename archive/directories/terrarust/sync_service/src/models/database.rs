@@ -1,7 +1,46 @@
-use sqlx::{FromRow, Type};
+use sqlx::{Arguments, FromRow, Postgres, Transaction, Type};
+use sqlx::postgres::PgArguments;
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
+use terrafusion_common::database::tenancy::with_county_filter;
+use terrafusion_common::models::gis_export::ExportTemplate;
+use terrafusion_common::models::SortOrder;
+use terrafusion_common::pagination::Pagination;
+
+/// Sortable sync pair fields; the first is the default
+pub const SYNC_PAIR_SORT_FIELDS: &[(&str, &str)] = &[
+    ("created_at", "created_at"),
+    ("updated_at", "updated_at"),
+    ("name", "name"),
+    ("last_sync_time", "last_sync_time"),
+    ("county_id", "county_id"),
+];
+
+/// Sortable sync operation fields; the first is the default
+pub const SYNC_OPERATION_SORT_FIELDS: &[(&str, &str)] = &[
+    ("created_at", "created_at"),
+    ("start_time", "start_time"),
+    ("end_time", "end_time"),
+    ("status", "status"),
+    ("records_processed", "records_processed"),
+    ("records_failed", "records_failed"),
+];
+
+/// Sortable sync diff fields; the first is the default
+pub const SYNC_DIFF_SORT_FIELDS: &[(&str, &str)] = &[
+    ("created_at", "d.created_at"),
+    ("change_type", "d.change_type"),
+    ("sync_status", "d.sync_status"),
+];
+
+/// Sortable audit log fields; the first is the default
+pub const AUDIT_LOG_SORT_FIELDS: &[(&str, &str)] = &[
+    ("created_at", "created_at"),
+    ("event_type", "event_type"),
+    ("resource_type", "resource_type"),
+    ("severity", "severity"),
+];
 
 /// Database model for sync pairs
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
@@ -23,6 +62,37 @@ pub struct SyncPairRow {
     pub last_sync_status: Option<String>,
     pub created_by: String,
     pub updated_by: String,
+    pub field_mappings: serde_json::Value,
+    /// Set when a schema check finds mapped fields changed or gone
+    pub needs_attention: bool,
+    pub attention_reason: Option<String>,
+}
+
+/// Database model for export templates
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ExportTemplateRow {
+    pub id: Uuid,
+    pub county_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub export_format: String,
+    pub layers: serde_json::Value,
+    pub parameters: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<ExportTemplateRow> for ExportTemplate {
+    fn from(row: ExportTemplateRow) -> Self {
+        ExportTemplate {
+            county_id: row.county_id,
+            name: row.name,
+            description: row.description,
+            export_format: row.export_format,
+            layers: serde_json::from_value(row.layers).unwrap_or_default(),
+            parameters: row.parameters,
+        }
+    }
 }
 
 /// Database model for sync operations
@@ -41,6 +111,7 @@ pub struct SyncOperationRow {
     pub error_message: Option<String>,
     pub custom_parameters: Option<serde_json::Value>,
     pub initiated_by: String,
+    pub narrative: Option<String>,
 }
 
 /// Database model for sync records
@@ -78,6 +149,191 @@ pub struct SyncDiffRow {
     pub resolved_at: Option<DateTime<Utc>>,
 }
 
+/// Operation totals for one day or week of a sync pair's history
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct OperationHistoryRow {
+    pub bucket: DateTime<Utc>,
+    pub total: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+    pub canceled: i64,
+    pub avg_duration_seconds: Option<f64>,
+    pub records_processed: i64,
+    pub records_failed: i64,
+}
+
+/// How often a failure message occurred in a period
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct FailureReasonRow {
+    pub reason: String,
+    pub occurrences: i64,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// A sync diff for one entity, with the operation and pair it came from
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct EntityHistoryRow {
+    pub diff_id: Uuid,
+    pub sync_operation_id: Uuid,
+    pub sync_pair_id: Uuid,
+    pub sync_pair_name: String,
+    pub county_id: String,
+    pub change_type: String,
+    pub sync_status: String,
+    pub source_data: Option<serde_json::Value>,
+    pub target_data: Option<serde_json::Value>,
+    pub diff_details: Option<serde_json::Value>,
+    pub error_message: Option<String>,
+    pub initiated_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A sync operation with its pair's name, county and execution log
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct OperationDetailRow {
+    pub id: Uuid,
+    pub sync_pair_id: Uuid,
+    pub sync_pair_name: String,
+    pub county_id: String,
+    pub status: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub records_processed: Option<i32>,
+    pub records_succeeded: Option<i32>,
+    pub records_failed: Option<i32>,
+    pub error_message: Option<String>,
+    pub custom_parameters: Option<serde_json::Value>,
+    pub initiated_by: String,
+    pub narrative: Option<String>,
+    pub execution_logs: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One diff written by a sync operation
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct OperationDiffRow {
+    pub id: Uuid,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub change_type: String,
+    pub sync_status: String,
+    pub source_data: Option<serde_json::Value>,
+    pub target_data: Option<serde_json::Value>,
+    pub diff_details: Option<serde_json::Value>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Payloads of a sync diff that need sealing or moving to the county's active key
+#[derive(Debug, Clone, FromRow)]
+pub struct DiffPayloadRow {
+    pub id: Uuid,
+    pub county_id: String,
+    pub source_data: Option<serde_json::Value>,
+    pub target_data: Option<serde_json::Value>,
+    /// Active key version of the county
+    pub key_version: i32,
+}
+
+/// Number of rows in one status
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StatusCountRow {
+    pub status: String,
+    pub count: i64,
+}
+
+/// How long one county keeps sync history
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct RetentionPolicyRow {
+    pub county_id: String,
+    pub diff_retention_days: i32,
+    pub stats_retention_days: i32,
+    pub updated_by: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One global search result
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SearchHitRow {
+    pub id: Uuid,
+    pub title: String,
+    /// Matching text with the search terms wrapped in `**`
+    pub snippet: Option<String>,
+    pub county_id: String,
+    pub status: String,
+    pub rank: f32,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One audit log entry, without the before/after state snapshots
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AuditLogRow {
+    pub id: Uuid,
+    pub event_type: String,
+    pub resource_type: String,
+    pub resource_id: Option<String>,
+    pub description: String,
+    pub user_id: Option<String>,
+    pub username: Option<String>,
+    pub county_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub operation_id: Option<Uuid>,
+    pub correlation_id: Option<String>,
+    pub severity: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A sync service process as last reported by its heartbeat
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SyncWorkerRow {
+    pub worker_id: String,
+    pub hostname: String,
+    pub pid: i32,
+    pub role: String,
+    pub version: String,
+    pub started_at: DateTime<Utc>,
+    pub last_heartbeat_at: DateTime<Utc>,
+    pub current_operations: serde_json::Value,
+}
+
+/// The last schema seen on one side of a sync pair
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SchemaSnapshotRow {
+    pub sync_pair_id: Uuid,
+    pub side: String,
+    pub fields: serde_json::Value,
+    pub drift: serde_json::Value,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Filters for listing sync operations
+#[derive(Debug, Default)]
+pub struct SyncOperationFilter<'a> {
+    pub county_id: Option<&'a str>,
+    pub sync_pair_id: Option<Uuid>,
+    pub status: Option<&'a str>,
+    pub from_date: Option<DateTime<Utc>>,
+    pub to_date: Option<DateTime<Utc>>,
+}
+
+/// WHERE clause for `SyncOperationFilter`, binding its fields as $1-$5
+const SYNC_OPERATION_FILTERS: &str = r#"
+    WHERE ($1::text IS NULL OR county_id = $1)
+    AND ($2::uuid IS NULL OR sync_pair_id = $2)
+    AND ($3::text IS NULL OR upper(status) = upper($3))
+    AND ($4::timestamptz IS NULL OR start_time >= $4)
+    AND ($5::timestamptz IS NULL OR start_time < $5)
+"#;
+
+/// A pending or running operation of a sync pair
+#[derive(Debug, Clone, PartialEq, Serialize, FromRow)]
+pub struct ActiveOperationRow {
+    pub id: Uuid,
+    pub status: String,
+    pub start_time: DateTime<Utc>,
+    pub initiated_by: String,
+}
+
 /// Database queries for sync operations
 pub struct SyncOperationQueries;
 
@@ -140,6 +396,52 @@ impl SyncOperationQueries {
         Ok(())
     }
     
+    /// Pending and running operations of a sync pair, oldest first
+    pub async fn active_for_pair(pool: &sqlx::PgPool, sync_pair_id: Uuid) -> Result<Vec<ActiveOperationRow>, sqlx::Error> {
+        sqlx::query_as::<_, ActiveOperationRow>(
+            r#"
+            SELECT id, status, start_time, initiated_by
+            FROM sync_operations
+            WHERE sync_pair_id = $1 AND upper(status) IN ('PENDING', 'RUNNING')
+            ORDER BY start_time, created_at
+            "#,
+        )
+        .bind(sync_pair_id)
+        .fetch_all(pool)
+        .await
+    }
+    
+    /// Whether an operation has been canceled, e.g. by one superseding it
+    pub async fn is_canceled(pool: &sqlx::PgPool, operation_id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM sync_operations WHERE id = $1 AND upper(status) IN ('CANCELED', 'CANCELLED'))",
+        )
+        .bind(operation_id)
+        .fetch_one(pool)
+        .await
+    }
+    
+    /// Store the generated narrative for a finished operation
+    pub async fn update_narrative(
+        pool: &sqlx::PgPool,
+        operation_id: Uuid,
+        narrative: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE sync_operations 
+            SET narrative = $2, updated_at = NOW()
+            WHERE id = $1
+            "#,
+            operation_id,
+            narrative
+        )
+        .execute(pool)
+        .await?;
+        
+        Ok(())
+    }
+    
     /// Update sync operation progress
     pub async fn update_progress(
         pool: &sqlx::PgPool,
@@ -165,6 +467,67 @@ impl SyncOperationQueries {
         Ok(())
     }
     
+    /// Append a finished batch to the operation's execution log
+    pub async fn append_batch(
+        pool: &sqlx::PgPool,
+        operation_id: Uuid,
+        batch: &serde_json::Value,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE sync_operations
+            SET execution_logs = jsonb_set(
+                    COALESCE(execution_logs, '{}'::jsonb),
+                    '{batches}',
+                    COALESCE(execution_logs->'batches', '[]'::jsonb) || jsonb_build_array($2::jsonb)
+                ),
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(operation_id)
+        .bind(batch)
+        .execute(pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Batches logged for the operation, oldest first
+    pub async fn batches(
+        pool: &sqlx::PgPool,
+        operation_id: Uuid,
+    ) -> Result<Option<serde_json::Value>, sqlx::Error> {
+        let batches = sqlx::query_scalar::<_, Option<serde_json::Value>>(
+            "SELECT execution_logs->'batches' FROM sync_operations WHERE id = $1",
+        )
+        .bind(operation_id)
+        .fetch_optional(pool)
+        .await?;
+        
+        Ok(batches.flatten())
+    }
+    
+    /// An operation with everything its detail page shows
+    pub async fn detail(
+        pool: &sqlx::PgPool,
+        operation_id: Uuid,
+    ) -> Result<Option<OperationDetailRow>, sqlx::Error> {
+        sqlx::query_as::<_, OperationDetailRow>(
+            r#"
+            SELECT o.id, o.sync_pair_id, p.name AS sync_pair_name, o.county_id, o.status, o.start_time,
+                   o.end_time, o.records_processed, o.records_succeeded, o.records_failed, o.error_message,
+                   o.custom_parameters, o.initiated_by, o.narrative, o.execution_logs, o.created_at
+            FROM sync_operations o
+            JOIN sync_pairs p ON p.id = o.sync_pair_id
+            WHERE o.id = $1
+            "#,
+        )
+        .bind(operation_id)
+        .fetch_optional(pool)
+        .await
+    }
+    
     /// Get sync operation by ID
     pub async fn get_by_id(
         pool: &sqlx::PgPool,
@@ -181,32 +544,130 @@ impl SyncOperationQueries {
         Ok(operation)
     }
     
-    /// List sync operations with pagination and filtering
+    /// List sync operations newest first. The total is counted for offset
+    /// pages only.
     pub async fn list(
         pool: &sqlx::PgPool,
-        sync_pair_id: Option<Uuid>,
-        status: Option<&str>,
-        offset: i64,
+        filter: &SyncOperationFilter<'_>,
+        order: &SortOrder,
+        pagination: &Pagination,
+    ) -> Result<(Vec<SyncOperationRow>, Option<i64>), sqlx::Error> {
+        let mut sql = format!("SELECT * FROM sync_operations {}", SYNC_OPERATION_FILTERS);
+        if let Some(condition) = pagination.keyset_condition("created_at", "id", 6) {
+            sql.push_str(&format!(" AND {}", condition));
+        }
+        sql.push_str(&format!(
+            " {} LIMIT {} OFFSET {}",
+            order.clause,
+            pagination.fetch_limit(),
+            pagination.offset()
+        ));
+
+        let mut query = sqlx::query_as::<_, SyncOperationRow>(&sql)
+            .bind(filter.county_id)
+            .bind(filter.sync_pair_id)
+            .bind(filter.status)
+            .bind(filter.from_date)
+            .bind(filter.to_date);
+        if let Some(after) = pagination.after() {
+            query = query.bind(after.sort_key).bind(after.id);
+        }
+        let operations = query.fetch_all(pool).await?;
+
+        let total = if pagination.is_cursor() {
+            None
+        } else {
+            let count = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM sync_operations {}", SYNC_OPERATION_FILTERS))
+                .bind(filter.county_id)
+                .bind(filter.sync_pair_id)
+                .bind(filter.status)
+                .bind(filter.from_date)
+                .bind(filter.to_date)
+                .fetch_one(pool)
+                .await?;
+            Some(count)
+        };
+
+        Ok((operations, total))
+    }
+
+    /// Unpaged query and arguments for a CSV/XLSX download of `list`
+    pub fn report(filter: &SyncOperationFilter<'_>, order: &SortOrder) -> (String, PgArguments) {
+        let mut args = PgArguments::default();
+        args.add(filter.county_id.map(str::to_string));
+        args.add(filter.sync_pair_id);
+        args.add(filter.status.map(str::to_string));
+        args.add(filter.from_date);
+        args.add(filter.to_date);
+
+        (format!("SELECT * FROM sync_operations {} {}", SYNC_OPERATION_FILTERS, order.clause), args)
+    }
+}
+
+impl SyncOperationQueries {
+    /// Aggregate a sync pair's operations into `day` or `week` buckets
+    ///
+    /// Buckets start at UTC midnight; buckets with no operations are omitted.
+    pub async fn history(
+        pool: &sqlx::PgPool,
+        sync_pair_id: Uuid,
+        interval: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<OperationHistoryRow>, sqlx::Error> {
+        sqlx::query_as::<_, OperationHistoryRow>(
+            r#"
+            SELECT
+                date_trunc($2, start_time AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS bucket,
+                COUNT(*) AS total,
+                COUNT(*) FILTER (WHERE upper(status) = 'COMPLETED') AS succeeded,
+                COUNT(*) FILTER (WHERE upper(status) = 'FAILED') AS failed,
+                COUNT(*) FILTER (WHERE upper(status) IN ('CANCELED', 'CANCELLED')) AS canceled,
+                (AVG(EXTRACT(EPOCH FROM (end_time - start_time))) FILTER (WHERE end_time IS NOT NULL))::float8
+                    AS avg_duration_seconds,
+                COALESCE(SUM(records_processed), 0)::bigint AS records_processed,
+                COALESCE(SUM(records_failed), 0)::bigint AS records_failed
+            FROM sync_operations
+            WHERE sync_pair_id = $1 AND start_time >= $3 AND start_time < $4
+            GROUP BY bucket
+            ORDER BY bucket
+            "#,
+        )
+        .bind(sync_pair_id)
+        .bind(interval)
+        .bind(from)
+        .bind(to)
+        .fetch_all(pool)
+        .await
+    }
+    
+    /// Most frequent failure messages for a sync pair, by first line of the error
+    pub async fn failure_reasons(
+        pool: &sqlx::PgPool,
+        sync_pair_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
         limit: i64,
-    ) -> Result<Vec<SyncOperationRow>, sqlx::Error> {
-        let operations = sqlx::query_as!(
-            SyncOperationRow,
+    ) -> Result<Vec<FailureReasonRow>, sqlx::Error> {
+        sqlx::query_as::<_, FailureReasonRow>(
             r#"
-            SELECT * FROM sync_operations 
-            WHERE ($1::uuid IS NULL OR sync_pair_id = $1)
-            AND ($2::text IS NULL OR status = $2)
-            ORDER BY created_at DESC
-            LIMIT $3 OFFSET $4
+            SELECT
+                COALESCE(NULLIF(left(split_part(error_message, E'\n', 1), 200), ''), 'Unknown error') AS reason,
+                COUNT(*) AS occurrences,
+                MAX(start_time) AS last_seen
+            FROM sync_operations
+            WHERE sync_pair_id = $1 AND upper(status) = 'FAILED' AND start_time >= $2 AND start_time < $3
+            GROUP BY reason
+            ORDER BY occurrences DESC, last_seen DESC
+            LIMIT $4
             "#,
-            sync_pair_id,
-            status,
-            limit,
-            offset
         )
+        .bind(sync_pair_id)
+        .bind(from)
+        .bind(to)
+        .bind(limit)
         .fetch_all(pool)
-        .await?;
-        
-        Ok(operations)
+        .await
     }
 }
 
@@ -268,6 +729,33 @@ impl SyncPairQueries {
         Ok(sync_pair)
     }
     
+    /// List sync pairs visible to a county context
+    pub async fn list_for_county(
+        pool: &sqlx::PgPool,
+        county_id: Option<&str>,
+        is_active: Option<bool>,
+        order: &SortOrder,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SyncPairRow>, sqlx::Error> {
+        let sql = with_county_filter(
+            "SELECT * FROM sync_pairs WHERE ($1::boolean IS NULL OR is_active = $1)",
+            county_id,
+            4,
+        );
+        let sql = format!("{} {} LIMIT $2 OFFSET $3", sql, order.clause);
+
+        let mut query = sqlx::query_as::<_, SyncPairRow>(&sql)
+            .bind(is_active)
+            .bind(limit)
+            .bind(offset);
+        if let Some(county_id) = county_id {
+            query = query.bind(county_id);
+        }
+
+        query.fetch_all(pool).await
+    }
+    
     /// List active sync pairs due for sync
     pub async fn get_due_for_sync(
         pool: &sqlx::PgPool,
@@ -290,6 +778,103 @@ impl SyncPairQueries {
         Ok(sync_pairs)
     }
     
+    /// Active sync pairs that stream source changes instead of being scheduled
+    pub async fn list_cdc(pool: &sqlx::PgPool) -> Result<Vec<SyncPairRow>, sqlx::Error> {
+        sqlx::query_as::<_, SyncPairRow>(
+            "SELECT * FROM sync_pairs WHERE is_active = true AND source_config->'cdc' IS NOT NULL AND source_config->'cdc' <> 'null'::jsonb ORDER BY name",
+        )
+        .fetch_all(pool)
+        .await
+    }
+    
+    /// Active pairs whose file source is fed from a mailbox
+    pub async fn list_email_ingest(pool: &sqlx::PgPool) -> Result<Vec<SyncPairRow>, sqlx::Error> {
+        sqlx::query_as::<_, SyncPairRow>(
+            "SELECT * FROM sync_pairs WHERE is_active = true AND source_config->'email' IS NOT NULL AND source_config->'email' <> 'null'::jsonb ORDER BY name",
+        )
+        .fetch_all(pool)
+        .await
+    }
+    
+    /// Find a sync pair by name within a county
+    pub async fn find_by_name(
+        tx: &mut Transaction<'_, Postgres>,
+        county_id: &str,
+        name: &str,
+    ) -> Result<Option<SyncPairRow>, sqlx::Error> {
+        sqlx::query_as::<_, SyncPairRow>("SELECT * FROM sync_pairs WHERE county_id = $1 AND name = $2")
+            .bind(county_id)
+            .bind(name)
+            .fetch_optional(&mut *tx)
+            .await
+    }
+    
+    /// Insert a sync pair as part of a larger transaction
+    pub async fn insert(
+        tx: &mut Transaction<'_, Postgres>,
+        sync_pair: &SyncPairRow,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO sync_pairs (
+                id, created_at, updated_at, name, description, source_system,
+                source_config, target_system, target_config, county_id, is_active,
+                sync_interval_minutes, sync_conflict_strategy, created_by, updated_by
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            "#,
+        )
+        .bind(sync_pair.id)
+        .bind(sync_pair.created_at)
+        .bind(sync_pair.updated_at)
+        .bind(&sync_pair.name)
+        .bind(&sync_pair.description)
+        .bind(&sync_pair.source_system)
+        .bind(&sync_pair.source_config)
+        .bind(&sync_pair.target_system)
+        .bind(&sync_pair.target_config)
+        .bind(&sync_pair.county_id)
+        .bind(sync_pair.is_active)
+        .bind(sync_pair.sync_interval_minutes)
+        .bind(&sync_pair.sync_conflict_strategy)
+        .bind(&sync_pair.created_by)
+        .bind(&sync_pair.updated_by)
+        .execute(&mut *tx)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Overwrite the configurable fields of an existing sync pair
+    pub async fn update_config(
+        tx: &mut Transaction<'_, Postgres>,
+        sync_pair: &SyncPairRow,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE sync_pairs
+            SET description = $2, source_system = $3, source_config = $4, target_system = $5,
+                target_config = $6, is_active = $7, sync_interval_minutes = $8,
+                sync_conflict_strategy = $9, updated_at = $10, updated_by = $11
+            WHERE id = $1
+            "#,
+        )
+        .bind(sync_pair.id)
+        .bind(&sync_pair.description)
+        .bind(&sync_pair.source_system)
+        .bind(&sync_pair.source_config)
+        .bind(&sync_pair.target_system)
+        .bind(&sync_pair.target_config)
+        .bind(sync_pair.is_active)
+        .bind(sync_pair.sync_interval_minutes)
+        .bind(&sync_pair.sync_conflict_strategy)
+        .bind(sync_pair.updated_at)
+        .bind(&sync_pair.updated_by)
+        .execute(&mut *tx)
+        .await?;
+        
+        Ok(())
+    }
+    
     /// Update last sync time for a sync pair
     pub async fn update_last_sync(
         pool: &sqlx::PgPool,
@@ -312,4 +897,816 @@ impl SyncPairQueries {
         
         Ok(())
     }
-}
\ No newline at end of file
+    
+    /// Set whether a pair runs on schedule; returns false if it was already in that state
+    pub async fn set_active(
+        pool: &sqlx::PgPool,
+        sync_pair_id: Uuid,
+        is_active: bool,
+        updated_by: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE sync_pairs SET is_active = $2, updated_by = $3, updated_at = NOW() WHERE id = $1 AND is_active <> $2",
+        )
+        .bind(sync_pair_id)
+        .bind(is_active)
+        .bind(updated_by)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Replace a pair's field mappings
+    pub async fn update_field_mappings(
+        pool: &sqlx::PgPool,
+        sync_pair_id: Uuid,
+        field_mappings: &serde_json::Value,
+        updated_by: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE sync_pairs SET field_mappings = $2, updated_by = $3, updated_at = NOW() WHERE id = $1",
+        )
+        .bind(sync_pair_id)
+        .bind(field_mappings)
+        .bind(updated_by)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Flag a pair for attention with the reason, or clear the flag with `None`
+    pub async fn set_attention(
+        pool: &sqlx::PgPool,
+        sync_pair_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE sync_pairs SET needs_attention = $2 IS NOT NULL, attention_reason = $2 WHERE id = $1",
+        )
+        .bind(sync_pair_id)
+        .bind(reason)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+    
+    /// Active pairs, for the periodic schema check
+    pub async fn list_active(pool: &sqlx::PgPool) -> Result<Vec<SyncPairRow>, sqlx::Error> {
+        sqlx::query_as::<_, SyncPairRow>("SELECT * FROM sync_pairs WHERE is_active = true ORDER BY county_id, name")
+            .fetch_all(pool)
+            .await
+    }
+    
+    /// Whether the pair has an operation that has not finished
+    pub async fn has_running_operation(pool: &sqlx::PgPool, sync_pair_id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM sync_operations WHERE sync_pair_id = $1 AND upper(status) IN ('PENDING', 'RUNNING'))",
+        )
+        .bind(sync_pair_id)
+        .fetch_one(pool)
+        .await
+    }
+    
+    /// Delete a pair. Fails with a foreign key violation if it has sync history.
+    pub async fn delete(pool: &sqlx::PgPool, sync_pair_id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM sync_pairs WHERE id = $1")
+            .bind(sync_pair_id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Database queries for schema snapshots
+pub struct SchemaSnapshotQueries;
+
+impl SchemaSnapshotQueries {
+    /// Snapshots of both sides of a pair
+    pub async fn for_pair(pool: &sqlx::PgPool, sync_pair_id: Uuid) -> Result<Vec<SchemaSnapshotRow>, sqlx::Error> {
+        sqlx::query_as::<_, SchemaSnapshotRow>("SELECT * FROM schema_snapshots WHERE sync_pair_id = $1 ORDER BY side")
+            .bind(sync_pair_id)
+            .fetch_all(pool)
+            .await
+    }
+    
+    /// Record the schema seen on one side and what was wrong with it
+    pub async fn save(
+        pool: &sqlx::PgPool,
+        sync_pair_id: Uuid,
+        side: &str,
+        fields: &serde_json::Value,
+        drift: &serde_json::Value,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO schema_snapshots (sync_pair_id, side, fields, drift, checked_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            ON CONFLICT (sync_pair_id, side)
+            DO UPDATE SET fields = EXCLUDED.fields, drift = EXCLUDED.drift, checked_at = EXCLUDED.checked_at
+            "#,
+        )
+        .bind(sync_pair_id)
+        .bind(side)
+        .bind(fields)
+        .bind(drift)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Database queries for export templates
+pub struct ExportTemplateQueries;
+
+impl ExportTemplateQueries {
+    /// List export templates visible to a county context
+    pub async fn list_for_county(
+        pool: &sqlx::PgPool,
+        county_id: Option<&str>,
+    ) -> Result<Vec<ExportTemplate>, sqlx::Error> {
+        let sql = with_county_filter("SELECT * FROM export_templates WHERE 1=1", county_id, 1);
+        let sql = format!("{} ORDER BY county_id, name", sql);
+
+        let mut query = sqlx::query_as::<_, ExportTemplateRow>(&sql);
+        if let Some(county_id) = county_id {
+            query = query.bind(county_id);
+        }
+
+        Ok(query.fetch_all(pool).await?.into_iter().map(ExportTemplate::from).collect())
+    }
+    
+    /// Create or replace a template, matched by county and name
+    pub async fn upsert(
+        tx: &mut Transaction<'_, Postgres>,
+        template: &ExportTemplate,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO export_templates (id, county_id, name, description, export_format, layers, parameters)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (county_id, name) DO UPDATE
+            SET description = EXCLUDED.description, export_format = EXCLUDED.export_format,
+                layers = EXCLUDED.layers, parameters = EXCLUDED.parameters, updated_at = NOW()
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(&template.county_id)
+        .bind(&template.name)
+        .bind(&template.description)
+        .bind(&template.export_format)
+        .bind(serde_json::json!(template.layers))
+        .bind(if template.parameters.is_null() { serde_json::json!({}) } else { template.parameters.clone() })
+        .execute(&mut *tx)
+        .await?;
+        
+        Ok(())
+    }
+}
+
+/// Aggregates for the dashboard, optionally limited to one county
+pub struct DashboardQueries;
+
+impl DashboardQueries {
+    /// Sync operations started since `since`, counted by status
+    pub async fn operation_status_counts(
+        pool: &sqlx::PgPool,
+        county_id: Option<&str>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<StatusCountRow>, sqlx::Error> {
+        let sql = with_county_filter(
+            "SELECT upper(status) AS status, COUNT(*) AS count FROM sync_operations WHERE start_time >= $1",
+            county_id,
+            2,
+        );
+        let sql = format!("{} GROUP BY 1", sql);
+
+        let mut query = sqlx::query_as::<_, StatusCountRow>(&sql).bind(since);
+        if let Some(county_id) = county_id {
+            query = query.bind(county_id);
+        }
+
+        query.fetch_all(pool).await
+    }
+    
+    pub async fn active_sync_pairs(
+        pool: &sqlx::PgPool,
+        county_id: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        let sql = with_county_filter("SELECT COUNT(*) FROM sync_pairs WHERE is_active", county_id, 1);
+
+        let mut query = sqlx::query_scalar::<_, i64>(&sql);
+        if let Some(county_id) = county_id {
+            query = query.bind(county_id);
+        }
+
+        query.fetch_one(pool).await
+    }
+    
+    /// GIS export jobs created since `since`, counted by status
+    pub async fn export_status_counts(
+        pool: &sqlx::PgPool,
+        county_id: Option<&str>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<StatusCountRow>, sqlx::Error> {
+        let sql = with_county_filter(
+            "SELECT upper(status) AS status, COUNT(*) AS count FROM gis_export_jobs WHERE created_at >= $1",
+            county_id,
+            2,
+        );
+        let sql = format!("{} GROUP BY 1", sql);
+
+        let mut query = sqlx::query_as::<_, StatusCountRow>(&sql).bind(since);
+        if let Some(county_id) = county_id {
+            query = query.bind(county_id);
+        }
+
+        query.fetch_all(pool).await
+    }
+    
+    /// Export jobs not yet finished, regardless of age
+    pub async fn pending_exports(
+        pool: &sqlx::PgPool,
+        county_id: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        let sql = with_county_filter(
+            "SELECT COUNT(*) FROM gis_export_jobs WHERE upper(status) IN ('PENDING', 'PROCESSING', 'RUNNING')",
+            county_id,
+            1,
+        );
+
+        let mut query = sqlx::query_scalar::<_, i64>(&sql);
+        if let Some(county_id) = county_id {
+            query = query.bind(county_id);
+        }
+
+        query.fetch_one(pool).await
+    }
+}
+
+/// Database queries for sync diffs
+pub struct SyncDiffQueries;
+
+/// Condition on a payload column of `payloads_to_seal`: present, and either
+/// plaintext or sealed under another version than the active key `k`
+fn needs_sealing(column: &str) -> String {
+    format!(
+        "({col} IS NOT NULL AND jsonb_typeof({col}) <> 'null' AND CASE WHEN {col} ? '$encrypted' \
+         THEN {col}->>'key_version' <> COALESCE(k.version, 0)::text ELSE true END)",
+        col = column
+    )
+}
+
+/// Diffs for one entity, binding entity type, entity ID and county as $1-$3
+const ENTITY_HISTORY_SQL: &str = r#"
+    SELECT
+        d.id AS diff_id, d.sync_operation_id, o.sync_pair_id, p.name AS sync_pair_name, o.county_id,
+        d.change_type, d.sync_status, d.source_data, d.target_data, d.diff_details, d.error_message,
+        o.initiated_by, d.created_at
+    FROM sync_diffs d
+    JOIN sync_operations o ON o.id = d.sync_operation_id
+    JOIN sync_pairs p ON p.id = o.sync_pair_id
+    WHERE d.entity_type = $1 AND d.entity_id = $2
+    AND ($3::text IS NULL OR o.county_id = $3)
+"#;
+
+impl SyncDiffQueries {
+    /// Diffs with a payload in plaintext or sealed under a key version
+    /// other than the county's active one, oldest first
+    pub async fn payloads_to_seal(pool: &sqlx::PgPool, limit: i64) -> Result<Vec<DiffPayloadRow>, sqlx::Error> {
+        let sql = format!(
+            r#"
+            SELECT d.id, o.county_id, d.source_data, d.target_data, COALESCE(k.version, 0) AS key_version
+            FROM sync_diffs d
+            JOIN sync_operations o ON o.id = d.sync_operation_id
+            LEFT JOIN county_encryption_keys k ON k.county_id = o.county_id AND k.active
+            WHERE {} OR {}
+            ORDER BY d.created_at
+            LIMIT $1
+            "#,
+            needs_sealing("d.source_data"),
+            needs_sealing("d.target_data"),
+        );
+        sqlx::query_as::<_, DiffPayloadRow>(&sql).bind(limit).fetch_all(pool).await
+    }
+    
+    /// Replace a diff's payloads with their sealed form
+    pub async fn update_payloads(
+        pool: &sqlx::PgPool,
+        diff_id: Uuid,
+        source_data: Option<&serde_json::Value>,
+        target_data: Option<&serde_json::Value>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE sync_diffs SET source_data = $2, target_data = $3, updated_at = NOW() WHERE id = $1")
+            .bind(diff_id)
+            .bind(source_data)
+            .bind(target_data)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+    
+    /// Diffs written by one operation, optionally of one change type and
+    /// status. The total is counted for offset pages only.
+    pub async fn for_operation(
+        pool: &sqlx::PgPool,
+        operation_id: Uuid,
+        change_type: Option<&str>,
+        sync_status: Option<&str>,
+        order: &SortOrder,
+        pagination: &Pagination,
+    ) -> Result<(Vec<OperationDiffRow>, Option<i64>), sqlx::Error> {
+        const FILTERS: &str = r#"
+            WHERE d.sync_operation_id = $1
+            AND ($2::text IS NULL OR upper(d.change_type) = upper($2))
+            AND ($3::text IS NULL OR upper(d.sync_status) = upper($3))
+        "#;
+        let mut sql = format!(
+            "SELECT d.id, d.entity_type, d.entity_id, d.change_type, d.sync_status, d.source_data, d.target_data, \
+             d.diff_details, d.error_message, d.created_at FROM sync_diffs d {}",
+            FILTERS
+        );
+        if let Some(condition) = pagination.keyset_condition("d.created_at", "d.id", 4) {
+            sql.push_str(&format!(" AND {}", condition));
+        }
+        sql.push_str(&format!(
+            " {} LIMIT {} OFFSET {}",
+            order.clause,
+            pagination.fetch_limit(),
+            pagination.offset()
+        ));
+
+        let mut query = sqlx::query_as::<_, OperationDiffRow>(&sql)
+            .bind(operation_id)
+            .bind(change_type)
+            .bind(sync_status);
+        if let Some(after) = pagination.after() {
+            query = query.bind(after.sort_key).bind(after.id);
+        }
+        let diffs = query.fetch_all(pool).await?;
+
+        let total = if pagination.is_cursor() {
+            None
+        } else {
+            let count = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM sync_diffs d {}", FILTERS))
+                .bind(operation_id)
+                .bind(change_type)
+                .bind(sync_status)
+                .fetch_one(pool)
+                .await?;
+            Some(count)
+        };
+
+        Ok((diffs, total))
+    }
+    
+    /// Number of an operation's diffs of each change type
+    pub async fn change_type_counts(
+        pool: &sqlx::PgPool,
+        operation_id: Uuid,
+    ) -> Result<Vec<(String, i64)>, sqlx::Error> {
+        sqlx::query_as::<_, (String, i64)>(
+            "SELECT upper(change_type), COUNT(*) FROM sync_diffs WHERE sync_operation_id = $1 GROUP BY 1 ORDER BY 1",
+        )
+        .bind(operation_id)
+        .fetch_all(pool)
+        .await
+    }
+    
+    /// Diffs for one entity across all operations, newest first
+    pub async fn entity_history(
+        pool: &sqlx::PgPool,
+        entity_type: &str,
+        entity_id: &str,
+        county_id: Option<&str>,
+        order: &SortOrder,
+        pagination: &Pagination,
+    ) -> Result<Vec<EntityHistoryRow>, sqlx::Error> {
+        let mut sql = String::from(ENTITY_HISTORY_SQL);
+        if let Some(condition) = pagination.keyset_condition("d.created_at", "d.id", 4) {
+            sql.push_str(&format!(" AND {}", condition));
+        }
+        sql.push_str(&format!(
+            " {} LIMIT {} OFFSET {}",
+            order.clause,
+            pagination.fetch_limit(),
+            pagination.offset()
+        ));
+
+        let mut query = sqlx::query_as::<_, EntityHistoryRow>(&sql)
+            .bind(entity_type)
+            .bind(entity_id)
+            .bind(county_id);
+        if let Some(after) = pagination.after() {
+            query = query.bind(after.sort_key).bind(after.id);
+        }
+
+        query.fetch_all(pool).await
+    }
+
+    /// Unpaged query and arguments for a CSV/XLSX download of `entity_history`
+    pub fn entity_history_report(
+        entity_type: &str,
+        entity_id: &str,
+        county_id: Option<&str>,
+        order: &SortOrder,
+    ) -> (String, PgArguments) {
+        let mut args = PgArguments::default();
+        args.add(entity_type.to_string());
+        args.add(entity_id.to_string());
+        args.add(county_id.map(str::to_string));
+
+        (format!("{} {}", ENTITY_HISTORY_SQL, order.clause), args)
+    }
+}
+
+/// Retention policies and the deletes they drive
+pub struct RetentionQueries;
+
+impl RetentionQueries {
+    pub async fn list_policies(pool: &sqlx::PgPool) -> Result<Vec<RetentionPolicyRow>, sqlx::Error> {
+        sqlx::query_as::<_, RetentionPolicyRow>("SELECT * FROM retention_policies ORDER BY county_id")
+            .fetch_all(pool)
+            .await
+    }
+    
+    pub async fn upsert_policy(
+        pool: &sqlx::PgPool,
+        county_id: &str,
+        diff_retention_days: i32,
+        stats_retention_days: i32,
+        updated_by: &str,
+    ) -> Result<RetentionPolicyRow, sqlx::Error> {
+        sqlx::query_as::<_, RetentionPolicyRow>(
+            r#"
+            INSERT INTO retention_policies (county_id, diff_retention_days, stats_retention_days, updated_by, updated_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            ON CONFLICT (county_id) DO UPDATE
+            SET diff_retention_days = EXCLUDED.diff_retention_days,
+                stats_retention_days = EXCLUDED.stats_retention_days,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(county_id)
+        .bind(diff_retention_days)
+        .bind(stats_retention_days)
+        .bind(updated_by)
+        .fetch_one(pool)
+        .await
+    }
+    
+    /// Remove a county's policy so it falls back to the default
+    pub async fn delete_policy(pool: &sqlx::PgPool, county_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM retention_policies WHERE county_id = $1")
+            .bind(county_id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Counties that have any sync history
+    pub async fn counties(pool: &sqlx::PgPool) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar::<_, String>("SELECT DISTINCT county_id FROM sync_operations ORDER BY county_id")
+            .fetch_all(pool)
+            .await
+    }
+    
+    /// Delete up to `limit` of a county's diffs created before `cutoff`
+    pub async fn delete_diffs_batch(
+        pool: &sqlx::PgPool,
+        county_id: &str,
+        cutoff: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM sync_diffs WHERE id IN (
+                SELECT d.id FROM sync_diffs d
+                JOIN sync_operations o ON o.id = d.sync_operation_id
+                WHERE o.county_id = $1 AND d.created_at < $2
+                LIMIT $3
+            )
+            "#,
+        )
+        .bind(county_id)
+        .bind(cutoff)
+        .bind(limit)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+    
+    /// Fold per-operation stats of operations started before `cutoff` into
+    /// `sync_stats_daily` and delete them, in one statement. Returns the
+    /// number of operations rolled up.
+    pub async fn roll_up_stats(
+        pool: &sqlx::PgPool,
+        county_id: &str,
+        cutoff: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            WITH expired AS (
+                DELETE FROM sync_stats s
+                USING sync_operations o
+                WHERE s.sync_operation_id = o.id AND o.county_id = $1 AND o.start_time < $2
+                RETURNING o.county_id, (o.start_time AT TIME ZONE 'UTC')::date AS day, s.*
+            ),
+            rolled AS (
+                INSERT INTO sync_stats_daily (
+                    county_id, day, operations, total_records, added_count, modified_count, deleted_count,
+                    unchanged_count, error_count, validation_issues_count, duration_seconds
+                )
+                SELECT county_id, day, COUNT(*), SUM(total_records), SUM(added_count), SUM(modified_count),
+                    SUM(deleted_count), SUM(unchanged_count), SUM(error_count), SUM(validation_issues_count),
+                    SUM(duration_seconds)
+                FROM expired
+                GROUP BY county_id, day
+                ON CONFLICT (county_id, day) DO UPDATE
+                SET operations = sync_stats_daily.operations + EXCLUDED.operations,
+                    total_records = sync_stats_daily.total_records + EXCLUDED.total_records,
+                    added_count = sync_stats_daily.added_count + EXCLUDED.added_count,
+                    modified_count = sync_stats_daily.modified_count + EXCLUDED.modified_count,
+                    deleted_count = sync_stats_daily.deleted_count + EXCLUDED.deleted_count,
+                    unchanged_count = sync_stats_daily.unchanged_count + EXCLUDED.unchanged_count,
+                    error_count = sync_stats_daily.error_count + EXCLUDED.error_count,
+                    validation_issues_count = sync_stats_daily.validation_issues_count + EXCLUDED.validation_issues_count,
+                    duration_seconds = sync_stats_daily.duration_seconds + EXCLUDED.duration_seconds
+                RETURNING 1
+            )
+            SELECT COUNT(*) FROM expired
+            "#,
+        )
+        .bind(county_id)
+        .bind(cutoff)
+        .fetch_one(pool)
+        .await
+    }
+}
+
+/// Full-text search for the global search box
+pub struct SearchQueries;
+
+/// Options for `ts_headline` snippets. Snippets are plain text (not HTML),
+/// so matches are marked with `**` rather than tags.
+const HEADLINE_OPTIONS: &str = "StartSel=**, StopSel=**, MaxFragments=1, MaxWords=20, MinWords=5";
+
+impl SearchQueries {
+    /// Sync pairs whose name or description match
+    pub async fn sync_pairs(
+        pool: &sqlx::PgPool,
+        q: &str,
+        county_id: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<SearchHitRow>, sqlx::Error> {
+        sqlx::query_as::<_, SearchHitRow>(
+            r#"
+            SELECT
+                p.id, p.name AS title,
+                ts_headline('english', coalesce(p.description, ''), q, $4) AS snippet,
+                p.county_id,
+                CASE WHEN p.is_active THEN 'ACTIVE' ELSE 'INACTIVE' END AS status,
+                ts_rank(to_tsvector('english', p.name || ' ' || coalesce(p.description, '')), q) AS rank,
+                p.updated_at
+            FROM sync_pairs p
+            CROSS JOIN websearch_to_tsquery('english', $1) q
+            WHERE to_tsvector('english', p.name || ' ' || coalesce(p.description, '')) @@ q
+            AND ($2::text IS NULL OR p.county_id = $2)
+            ORDER BY rank DESC, p.updated_at DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(q)
+        .bind(county_id)
+        .bind(limit)
+        .bind(HEADLINE_OPTIONS)
+        .fetch_all(pool)
+        .await
+    }
+    
+    /// Sync operations whose error message matches, titled by their sync pair
+    pub async fn operations(
+        pool: &sqlx::PgPool,
+        q: &str,
+        county_id: Option<&str>,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<SearchHitRow>, sqlx::Error> {
+        sqlx::query_as::<_, SearchHitRow>(
+            r#"
+            SELECT
+                o.id, p.name AS title,
+                ts_headline('english', o.error_message, q, $5) AS snippet,
+                o.county_id, upper(o.status) AS status,
+                ts_rank(to_tsvector('english', o.error_message), q) AS rank,
+                o.updated_at
+            FROM sync_operations o
+            JOIN sync_pairs p ON p.id = o.sync_pair_id
+            CROSS JOIN websearch_to_tsquery('english', $1) q
+            WHERE o.error_message IS NOT NULL
+            AND to_tsvector('english', o.error_message) @@ q
+            AND ($2::text IS NULL OR o.county_id = $2)
+            AND ($3::text IS NULL OR upper(o.status) = upper($3))
+            ORDER BY rank DESC, o.updated_at DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(q)
+        .bind(county_id)
+        .bind(status)
+        .bind(limit)
+        .bind(HEADLINE_OPTIONS)
+        .fetch_all(pool)
+        .await
+    }
+    
+    /// Export jobs whose ID starts with `id_prefix`
+    pub async fn exports(
+        pool: &sqlx::PgPool,
+        id_prefix: &str,
+        county_id: Option<&str>,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<SearchHitRow>, sqlx::Error> {
+        sqlx::query_as::<_, SearchHitRow>(
+            r#"
+            SELECT
+                job_id AS id, upper(export_format) || ' export' AS title, message AS snippet,
+                county_id, upper(status) AS status, 1.0::real AS rank,
+                coalesce(completed_at, started_at, created_at) AS updated_at
+            FROM gis_export_jobs
+            WHERE job_id::text LIKE $1 || '%'
+            AND ($2::text IS NULL OR county_id = $2)
+            AND ($3::text IS NULL OR upper(status) = upper($3))
+            ORDER BY created_at DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(id_prefix)
+        .bind(county_id)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+/// Filters for listing the audit log
+#[derive(Debug, Default)]
+pub struct AuditLogFilter<'a> {
+    pub county_id: Option<&'a str>,
+    pub event_type: Option<&'a str>,
+    pub resource_type: Option<&'a str>,
+    pub resource_id: Option<&'a str>,
+    pub username: Option<&'a str>,
+    pub severity: Option<&'a str>,
+    pub from_date: Option<DateTime<Utc>>,
+    pub to_date: Option<DateTime<Utc>>,
+}
+
+/// Columns and WHERE clause for `AuditLogFilter`, binding its fields as $1-$8
+const AUDIT_LOG_QUERY: &str = r#"
+    SELECT
+        id, event_type, resource_type, resource_id, description, user_id, username, county_id,
+        ip_address, operation_id, correlation_id, severity, created_at
+    FROM audit_log
+    WHERE ($1::text IS NULL OR county_id = $1)
+    AND ($2::text IS NULL OR event_type = $2)
+    AND ($3::text IS NULL OR resource_type = $3)
+    AND ($4::text IS NULL OR resource_id = $4)
+    AND ($5::text IS NULL OR username = $5)
+    AND ($6::text IS NULL OR upper(severity) = upper($6))
+    AND ($7::timestamptz IS NULL OR created_at >= $7)
+    AND ($8::timestamptz IS NULL OR created_at < $8)
+"#;
+
+/// Read access to the audit log
+pub struct AuditQueries;
+
+impl AuditQueries {
+    /// List audit entries. The total is counted for offset pages only.
+    pub async fn list(
+        pool: &sqlx::PgPool,
+        filter: &AuditLogFilter<'_>,
+        order: &SortOrder,
+        pagination: &Pagination,
+    ) -> Result<(Vec<AuditLogRow>, Option<i64>), sqlx::Error> {
+        let mut sql = String::from(AUDIT_LOG_QUERY);
+        if let Some(condition) = pagination.keyset_condition("created_at", "id", 9) {
+            sql.push_str(&format!(" AND {}", condition));
+        }
+        sql.push_str(&format!(
+            " {} LIMIT {} OFFSET {}",
+            order.clause,
+            pagination.fetch_limit(),
+            pagination.offset()
+        ));
+
+        let mut query = sqlx::query_as::<_, AuditLogRow>(&sql)
+            .bind(filter.county_id)
+            .bind(filter.event_type)
+            .bind(filter.resource_type)
+            .bind(filter.resource_id)
+            .bind(filter.username)
+            .bind(filter.severity)
+            .bind(filter.from_date)
+            .bind(filter.to_date);
+        if let Some(after) = pagination.after() {
+            query = query.bind(after.sort_key).bind(after.id);
+        }
+        let entries = query.fetch_all(pool).await?;
+
+        let total = if pagination.is_cursor() {
+            None
+        } else {
+            let count = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM ({}) entries", AUDIT_LOG_QUERY))
+                .bind(filter.county_id)
+                .bind(filter.event_type)
+                .bind(filter.resource_type)
+                .bind(filter.resource_id)
+                .bind(filter.username)
+                .bind(filter.severity)
+                .bind(filter.from_date)
+                .bind(filter.to_date)
+                .fetch_one(pool)
+                .await?;
+            Some(count)
+        };
+
+        Ok((entries, total))
+    }
+
+    /// Unpaged query and arguments for a CSV/XLSX download of `list`
+    pub fn report(filter: &AuditLogFilter<'_>, order: &SortOrder) -> (String, PgArguments) {
+        let mut args = PgArguments::default();
+        args.add(filter.county_id.map(str::to_string));
+        args.add(filter.event_type.map(str::to_string));
+        args.add(filter.resource_type.map(str::to_string));
+        args.add(filter.resource_id.map(str::to_string));
+        args.add(filter.username.map(str::to_string));
+        args.add(filter.severity.map(str::to_string));
+        args.add(filter.from_date);
+        args.add(filter.to_date);
+
+        (format!("{} {}", AUDIT_LOG_QUERY, order.clause), args)
+    }
+}
+
+/// Registry of sync service processes
+pub struct WorkerQueries;
+
+impl WorkerQueries {
+    pub async fn heartbeat(pool: &sqlx::PgPool, worker: &SyncWorkerRow) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO sync_workers
+                (worker_id, hostname, pid, role, version, started_at, last_heartbeat_at, current_operations)
+            VALUES ($1, $2, $3, $4, $5, $6, NOW(), $7)
+            ON CONFLICT (worker_id) DO UPDATE
+            SET last_heartbeat_at = NOW(), current_operations = EXCLUDED.current_operations
+            "#,
+        )
+        .bind(&worker.worker_id)
+        .bind(&worker.hostname)
+        .bind(worker.pid)
+        .bind(&worker.role)
+        .bind(&worker.version)
+        .bind(worker.started_at)
+        .bind(&worker.current_operations)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+    
+    /// Workers that have reported since `since`, or every known worker
+    pub async fn list(pool: &sqlx::PgPool, since: Option<DateTime<Utc>>) -> Result<Vec<SyncWorkerRow>, sqlx::Error> {
+        sqlx::query_as::<_, SyncWorkerRow>(
+            r#"
+            SELECT * FROM sync_workers
+            WHERE ($1::timestamptz IS NULL OR last_heartbeat_at >= $1)
+            ORDER BY role, hostname, started_at
+            "#,
+        )
+        .bind(since)
+        .fetch_all(pool)
+        .await
+    }
+    
+    pub async fn remove(pool: &sqlx::PgPool, worker_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM sync_workers WHERE worker_id = $1")
+            .bind(worker_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+    
+    /// Forget workers silent since `before`; they stopped without deregistering
+    pub async fn remove_stale(pool: &sqlx::PgPool, before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM sync_workers WHERE last_heartbeat_at < $1")
+            .bind(before)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
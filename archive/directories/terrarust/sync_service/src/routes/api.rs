@@ -2,6 +2,36 @@ use actix_web::web;
 
 /// Configure API routes
 pub fn configure(cfg: &mut web::ServiceConfig) {
-    // API routes are handled by the individual route modules
-    // This is just a placeholder for any additional API-wide configuration
-}
\ No newline at end of file
+    // Configuration-as-code import and export
+    cfg.configure(super::config_bundle::configure);
+    
+    // Per-county feature flag administration
+    cfg.configure(super::feature_flags::configure);
+    
+    // Aggregate activity for the dashboard
+    cfg.configure(super::dashboard::configure);
+    
+    // Per-entity change history across sync operations
+    cfg.configure(super::entities::configure);
+    
+    // Data retention policies and manual runs
+    cfg.configure(super::retention::configure);
+    
+    // Payload encryption keys and rotation
+    cfg.configure(super::encryption::configure);
+    
+    // Background job queue visibility, retries and cancellation
+    cfg.configure(super::jobs::configure);
+    
+    // Global search box
+    cfg.configure(super::search::configure);
+    
+    // Audit trail listing and downloads
+    cfg.configure(super::audit::configure);
+    
+    // Monthly usage for chargeback
+    cfg.configure(super::usage::configure);
+    
+    // Per-layer data versions and changes since a version
+    cfg.configure(super::datasets::configure);
+}
@@ -0,0 +1,67 @@
+use actix_web::{web, HttpRequest, HttpResponse, get};
+use serde::Deserialize;
+use terrafusion_common::{CountyContext, Result};
+use terrafusion_common::models::SortParams;
+use terrafusion_common::pagination::{Cursor, Pagination};
+use crate::AppState;
+use crate::models::database::{AuditLogFilter, AuditLogRow, AuditQueries, AUDIT_LOG_SORT_FIELDS};
+use crate::services::reports::{self, ReportFormat};
+
+/// Configure audit log routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_audit_log);
+}
+
+/// Query parameters for the audit log
+#[derive(Debug, Deserialize)]
+pub struct AuditLogQuery {
+    pub county_id: Option<String>,
+    pub event_type: Option<String>,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    pub username: Option<String>,
+    pub severity: Option<String>,
+    pub from_date: Option<chrono::DateTime<chrono::Utc>>,
+    pub to_date: Option<chrono::DateTime<chrono::Utc>>,
+    /// `json`, `csv` or `xlsx`; overrides the `Accept` header
+    pub format: Option<String>,
+}
+
+/// List audit log entries, newest first
+///
+/// County users see their own county's entries. CSV and XLSX downloads
+/// cover every matching entry.
+#[get("/audit")]
+async fn list_audit_log(
+    req: HttpRequest,
+    query: web::Query<AuditLogQuery>,
+    sort: web::Query<SortParams>,
+    pagination: Pagination,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let format = ReportFormat::negotiate(&req, query.format.as_deref())?;
+    let county_id = county.effective_county(query.county_id.as_deref())?;
+    let order = sort.order_by(AUDIT_LOG_SORT_FIELDS, "id")?;
+    let filter = AuditLogFilter {
+        county_id: county_id.as_deref(),
+        event_type: query.event_type.as_deref(),
+        resource_type: query.resource_type.as_deref(),
+        resource_id: query.resource_id.as_deref(),
+        username: query.username.as_deref(),
+        severity: query.severity.as_deref(),
+        from_date: query.from_date,
+        to_date: query.to_date,
+    };
+    
+    if !format.is_json() {
+        let (sql, args) = AuditQueries::report(&filter, &order);
+        return reports::download::<AuditLogRow>(format, "audit-log", app_state.db_pool.read_pool(), sql, args, None).await;
+    }
+    
+    order.ensure_cursor_compatible(&pagination)?;
+    let (entries, total) = AuditQueries::list(&app_state.db_pool.read_pool(), &filter, &order, &pagination).await?;
+    let page = pagination.into_page(entries, total, |entry| Cursor::new(entry.created_at, entry.id));
+    
+    Ok(HttpResponse::Ok().json(page))
+}
@@ -0,0 +1,123 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder, get, post};
+use actix_web::http::header;
+use serde::Deserialize;
+use serde_json::json;
+use terrafusion_common::{CountyContext, Result};
+use crate::AppState;
+use crate::services::config_bundle::{
+    self, BundleFormat, ChangeAction, parse_bundle, plan_changes, redact_secrets, render_bundle,
+    restore_secrets, unresolved_secrets, validate_bundle,
+};
+
+/// Configure configuration bundle routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(import_bundle)
+       .service(export_bundle);
+}
+
+/// Import a JSON or YAML configuration bundle
+///
+/// With `?dry_run=true` the bundle is validated and diffed against the current
+/// state without writing anything.
+#[post("/import")]
+async fn import_bundle(
+    req: HttpRequest,
+    body: web::Bytes,
+    query: web::Query<ImportQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let content_type = req.headers().get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok());
+    let mut bundle = parse_bundle(&body, BundleFormat::from_content_type(content_type))?;
+    let dry_run = query.dry_run.unwrap_or(false);
+    
+    let county_ids = bundle.county_ids();
+    for county_id in &county_ids {
+        county.ensure_access(county_id)?;
+    }
+    
+    log::info!(
+        "Importing configuration bundle for {} county(ies){}",
+        county_ids.len(),
+        if dry_run { " (dry run)" } else { "" }
+    );
+    
+    let mut errors = validate_bundle(&bundle);
+    let current = config_bundle::load_current_state(
+        &app_state,
+        county.effective_county(None)?.as_deref(),
+        &county_ids,
+    )
+    .await?;
+    restore_secrets(&mut bundle, &current);
+    errors.extend(unresolved_secrets(&bundle));
+    
+    if !errors.is_empty() {
+        return Ok(HttpResponse::UnprocessableEntity().json(json!({
+            "valid": false,
+            "errors": errors,
+        })));
+    }
+    
+    let changes = plan_changes(&bundle, &current);
+    let count = |action: ChangeAction| changes.iter().filter(|change| change.action == action).count();
+    
+    if !dry_run {
+        config_bundle::apply_bundle(&app_state, &county, &bundle, &changes).await?;
+        log::info!(
+            "Configuration bundle applied: {} created, {} updated",
+            count(ChangeAction::Create),
+            count(ChangeAction::Update)
+        );
+    }
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "valid": true,
+        "dry_run": dry_run,
+        "summary": {
+            "create": count(ChangeAction::Create),
+            "update": count(ChangeAction::Update),
+            "unchanged": count(ChangeAction::Unchanged),
+        },
+        "changes": changes,
+    })))
+}
+
+/// Export the current configuration as a bundle, with secrets redacted
+#[get("/export")]
+async fn export_bundle(
+    query: web::Query<ExportQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let format = BundleFormat::from_name(query.format.as_deref())?;
+    let county_id = county.effective_county(query.county_id.as_deref())?;
+    
+    let mut bundle = config_bundle::load_current_state(&app_state, county_id.as_deref(), &Default::default()).await?;
+    redact_secrets(&mut bundle);
+    
+    let file_name = format!(
+        "terrafusion-config-{}.{}",
+        county_id.as_deref().unwrap_or("all"),
+        format.extension()
+    );
+    
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name)))
+        .body(render_bundle(&bundle, format)?))
+}
+
+/// Query parameters for importing a bundle
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    pub dry_run: Option<bool>,
+}
+
+/// Query parameters for exporting a bundle
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub county_id: Option<String>,
+    /// `json` (default) or `yaml`
+    pub format: Option<String>,
+}
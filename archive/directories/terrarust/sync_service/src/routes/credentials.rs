@@ -0,0 +1,175 @@
+use actix_web::{web, HttpResponse, Responder, get, post, delete};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+use terrafusion_common::{CountyContext, Error, Result};
+use crate::AppState;
+use crate::models::database::SyncPairQueries;
+use crate::services::connectors::{CheckStatus, Connector};
+use crate::services::credentials::{merge_credential, Credential, NewCredential};
+
+/// Configure credential routes under `/credentials`
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_credentials)
+       .service(create_credential)
+       .service(get_credential)
+       .service(rotate_credential)
+       .service(test_credential)
+       .service(delete_credential);
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CredentialListQuery {
+    pub county_id: Option<String>,
+}
+
+/// Body for rotating a credential
+#[derive(Debug, Deserialize)]
+pub struct RotateCredentialRequest {
+    pub values: Map<String, Value>,
+}
+
+/// Body for testing a credential
+#[derive(Debug, Default, Deserialize)]
+pub struct TestCredentialRequest {
+    /// Connector config to test with, without secrets; defaults to the
+    /// config of the first sync pair that uses the credential
+    #[serde(default)]
+    pub config: Option<Value>,
+}
+
+/// Credentials of the caller's county, without their values
+#[get("")]
+async fn list_credentials(
+    query: web::Query<CredentialListQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_admin(&county)?;
+    let county_id = county.effective_county(query.county_id.as_deref())?;
+    let credentials = app_state.credentials.list(county_id.as_deref()).await?;
+    Ok(web::Json(json!({ "credentials": credentials })))
+}
+
+/// Store a credential; the answer lists its keys but never its values
+#[post("")]
+async fn create_credential(
+    request: web::Json<NewCredential>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    ensure_admin(&county)?;
+    let credential = app_state.credentials.create(&county.county_id, &request).await?;
+    log::info!("Stored credential {} ({}) for county {}", credential.name, credential.id, credential.county_id);
+    Ok(HttpResponse::Created().json(credential))
+}
+
+/// A credential and the sync pairs that use it
+#[get("/{credential_id}")]
+async fn get_credential(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_admin(&county)?;
+    let credential = load_credential(&app_state, &county, path.into_inner()).await?;
+    let used_by = app_state.credentials.usage(credential.id).await?;
+    Ok(web::Json(json!({ "credential": credential, "used_by": used_by })))
+}
+
+/// Replace a credential's values for every pair that references it
+#[post("/{credential_id}/rotate")]
+async fn rotate_credential(
+    path: web::Path<Uuid>,
+    request: web::Json<RotateCredentialRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_admin(&county)?;
+    let credential = load_credential(&app_state, &county, path.into_inner()).await?;
+    let rotated = app_state.credentials.rotate(&credential, &request.values).await?;
+    let used_by = app_state.credentials.usage(rotated.id).await?;
+    Ok(web::Json(json!({ "credential": rotated, "used_by": used_by })))
+}
+
+/// Connect, authenticate and read one record with the credential
+#[post("/{credential_id}/test")]
+async fn test_credential(
+    path: web::Path<Uuid>,
+    request: Option<web::Json<TestCredentialRequest>>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_admin(&county)?;
+    let credential = load_credential(&app_state, &county, path.into_inner()).await?;
+    let config = match request.and_then(|request| request.into_inner().config) {
+        Some(config) => config,
+        None => config_of_first_use(&app_state, &credential).await?,
+    };
+
+    let config = merge_credential(&config, &app_state.credentials.values(&credential).await?);
+    let connector = Connector::from_config(&config)?;
+    let checks = connector.check(app_state.config.connector_timeout()).await;
+    let status = if checks.iter().any(|check| check.status == CheckStatus::Fail) {
+        "fail"
+    } else if checks.iter().any(|check| check.status == CheckStatus::Warn) {
+        "warn"
+    } else {
+        "pass"
+    };
+    app_state.credentials.record_test(credential.id, status).await?;
+
+    Ok(web::Json(json!({
+        "credential_id": credential.id,
+        "connector": connector.kind(),
+        "status": status,
+        "checks": checks,
+        "checked_at": chrono::Utc::now(),
+    })))
+}
+
+/// Delete a credential; refused while a sync pair references it
+#[delete("/{credential_id}")]
+async fn delete_credential(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    ensure_admin(&county)?;
+    let credential = load_credential(&app_state, &county, path.into_inner()).await?;
+    app_state.credentials.delete(&credential).await?;
+    log::info!("Deleted credential {} of county {}", credential.name, credential.county_id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// The source or target config of the first pair using the credential
+async fn config_of_first_use(app_state: &AppState, credential: &Credential) -> Result<Value> {
+    let no_config = || {
+        Error::Validation(format!(
+            "Credential {} is not used by any sync pair; pass a config to test it with",
+            credential.name
+        ))
+    };
+    let used = app_state.credentials.usage(credential.id).await?.into_iter().next().ok_or_else(no_config)?;
+    let pair = SyncPairQueries::get_by_id(&app_state.db_pool.read_pool(), used.sync_pair_id)
+        .await?
+        .ok_or_else(no_config)?;
+    Ok(if used.side == "source" { pair.source_config } else { pair.target_config })
+}
+
+/// The credential, if it belongs to a county the caller can see
+async fn load_credential(app_state: &AppState, county: &CountyContext, credential_id: Uuid) -> Result<Credential> {
+    app_state
+        .credentials
+        .get(credential_id)
+        .await?
+        .filter(|credential| county.can_access(&credential.county_id))
+        .ok_or_else(|| Error::NotFound("Credential not found".to_string()))
+}
+
+fn ensure_admin(county: &CountyContext) -> Result<()> {
+    if county.is_platform_admin || county.has_role("admin") {
+        return Ok(());
+    }
+    Err(Error::Authorization("Administrator role required to manage credentials".to_string()))
+}
@@ -0,0 +1,61 @@
+use actix_web::{web, Responder, get};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use terrafusion_common::{CountyContext, Result};
+use crate::AppState;
+use crate::models::database::DashboardQueries;
+use crate::services::dashboard::{DashboardStats, ExportStats, OperationStats, StatusBreakdown};
+
+/// Configure dashboard routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_dashboard_stats);
+}
+
+/// Query parameters for dashboard stats
+#[derive(Debug, Deserialize)]
+pub struct DashboardStatsQuery {
+    /// Platform admins may narrow to one county; others always see their own
+    pub county_id: Option<String>,
+}
+
+/// Sync and export activity for the caller's county
+#[get("/dashboard/stats")]
+async fn get_dashboard_stats(
+    query: web::Query<DashboardStatsQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let county_id = county.effective_county(query.county_id.as_deref())?;
+    
+    if let Some(stats) = app_state.dashboard_cache.get(county_id.as_deref()) {
+        return Ok(web::Json(stats));
+    }
+    
+    let pool = app_state.db_pool.read_pool();
+    let now = Utc::now();
+    let scope = county_id.as_deref();
+    
+    let (ops_24h, ops_7d, active_sync_pairs, exports_7d, pending_exports) = futures::try_join!(
+        DashboardQueries::operation_status_counts(&pool, scope, now - Duration::hours(24)),
+        DashboardQueries::operation_status_counts(&pool, scope, now - Duration::days(7)),
+        DashboardQueries::active_sync_pairs(&pool, scope),
+        DashboardQueries::export_status_counts(&pool, scope, now - Duration::days(7)),
+        DashboardQueries::pending_exports(&pool, scope),
+    )?;
+    
+    let stats = DashboardStats {
+        county_id,
+        generated_at: now,
+        active_sync_pairs,
+        operations: OperationStats {
+            last_24h: StatusBreakdown::from_rows(ops_24h),
+            last_7d: StatusBreakdown::from_rows(ops_7d),
+        },
+        exports: ExportStats {
+            last_7d: StatusBreakdown::from_rows(exports_7d),
+            pending: pending_exports,
+        },
+    };
+    
+    Ok(web::Json(app_state.dashboard_cache.insert(stats)))
+}
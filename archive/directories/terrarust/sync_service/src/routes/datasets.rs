@@ -0,0 +1,81 @@
+use actix_web::{web, Responder, get};
+use serde::Deserialize;
+use serde_json::json;
+use terrafusion_common::{CountyContext, Error, Result};
+use terrafusion_common::datasets::{DatasetQueries, VersionSource};
+use crate::AppState;
+
+/// Most changed records listed per request
+const MAX_CHANGES: i64 = 10_000;
+
+/// Configure dataset version routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_dataset_versions)
+        .service(get_dataset_changes);
+}
+
+/// Query parameters for changes since a version
+#[derive(Debug, Deserialize)]
+pub struct ChangesQuery {
+    /// Version the caller already has, e.g. from an export's `data_versions`
+    pub since: i64,
+    pub limit: Option<i64>,
+}
+
+/// Current data version of each of the county's layers
+#[get("/datasets/{county_id}")]
+async fn get_dataset_versions(
+    path: web::Path<String>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let county_id = path.into_inner();
+    county.ensure_access(&county_id)?;
+
+    let versions = DatasetQueries::latest(&app_state.db_pool.read_pool(), &county_id).await?;
+
+    Ok(web::Json(json!({
+        "county_id": county_id,
+        "layers": versions,
+    })))
+}
+
+/// What changed in a layer since a version. Sync versions list the records
+/// they changed; after a seed data upload the layer was replaced, so the
+/// caller has to reload it in full.
+#[get("/datasets/{county_id}/{layer_id}/changes")]
+async fn get_dataset_changes(
+    path: web::Path<(String, String)>,
+    query: web::Query<ChangesQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let (county_id, layer_id) = path.into_inner();
+    county.ensure_access(&county_id)?;
+    if query.since < 0 {
+        return Err(Error::Validation("since must not be negative".to_string()));
+    }
+    let limit = query.limit.unwrap_or(MAX_CHANGES).clamp(1, MAX_CHANGES);
+
+    let pool = app_state.db_pool.read_pool();
+    let versions = DatasetQueries::since(&pool, &county_id, &layer_id, query.since).await?;
+    let current_version = versions.last().map(|version| version.version).unwrap_or(query.since);
+    let full_reload = versions.iter().any(|version| version.source == VersionSource::Upload.as_str());
+    let changes = if full_reload || versions.is_empty() {
+        Vec::new()
+    } else {
+        DatasetQueries::changes_since(&pool, &county_id, &layer_id, query.since, limit + 1).await?
+    };
+    let truncated = changes.len() as i64 > limit;
+
+    Ok(web::Json(json!({
+        "county_id": county_id,
+        "layer_id": layer_id,
+        "since": query.since,
+        "current_version": current_version,
+        "full_reload": full_reload,
+        "versions": versions,
+        "changes": changes.into_iter().take(limit as usize).collect::<Vec<_>>(),
+        "truncated": truncated,
+    })))
+}
@@ -0,0 +1,78 @@
+use actix_web::{web, Responder, get, post};
+use serde::Deserialize;
+use serde_json::json;
+use terrafusion_common::{CountyContext, Error, Result};
+use terrafusion_common::encryption::PayloadCipher;
+use crate::AppState;
+use crate::services::payloads::PayloadSweep;
+
+/// Configure payload encryption routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_county_keys)
+       .service(rotate_county_key);
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RotateKeyRequest {
+    /// Secret in the secrets provider holding the new key
+    pub secret_name: String,
+}
+
+/// A county's keys are managed by its administrators or platform administrators
+fn ensure_key_admin(county: &CountyContext, county_id: &str) -> Result<()> {
+    county.ensure_access(county_id)?;
+    if county.is_platform_admin || county.has_role("admin") {
+        Ok(())
+    } else {
+        Err(Error::Authorization("Administrator role required".to_string()))
+    }
+}
+
+fn cipher(app_state: &AppState) -> Result<&PayloadCipher> {
+    app_state
+        .payloads
+        .as_ref()
+        .ok_or_else(|| Error::Validation("Payload encryption is not enabled".to_string()))
+}
+
+/// Key versions registered for a county and the one new payloads use
+#[get("/admin/encryption/counties/{county_id}/keys")]
+async fn list_county_keys(
+    path: web::Path<String>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let county_id = path.into_inner();
+    ensure_key_admin(&county, &county_id)?;
+    let cipher = cipher(&app_state)?;
+
+    Ok(web::Json(json!({
+        "county_id": county_id,
+        "active_version": cipher.active_version(&county_id).await?,
+        "keys": cipher.keys(&county_id).await?,
+    })))
+}
+
+/// Make a new key the county's active one and re-wrap its stored payloads
+#[post("/admin/encryption/counties/{county_id}/rotate")]
+async fn rotate_county_key(
+    path: web::Path<String>,
+    request: web::Json<RotateKeyRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let county_id = path.into_inner();
+    ensure_key_admin(&county, &county_id)?;
+    let secret_name = request.secret_name.trim();
+    if secret_name.is_empty() {
+        return Err(Error::Validation("secret_name is required".to_string()));
+    }
+
+    let key = cipher(&app_state)?.rotate(&county_id, secret_name).await?;
+    PayloadSweep::enqueue(&app_state.jobs).await?;
+
+    Ok(web::Json(json!({
+        "key": key,
+        "message": "Key rotated; stored payloads are being moved to the new key",
+    })))
+}
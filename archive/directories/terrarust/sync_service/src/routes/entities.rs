@@ -0,0 +1,172 @@
+use actix_web::{web, HttpRequest, HttpResponse, get};
+use serde::Deserialize;
+use terrafusion_common::{CountyContext, Error, Result};
+use terrafusion_common::models::SortParams;
+use terrafusion_common::negotiation::{self, Representation};
+use terrafusion_common::pagination::{Cursor, Pagination};
+use terrafusion_common::temporal::{self, TemporalQueries};
+use crate::AppState;
+use crate::models::database::{EntityHistoryRow, SyncDiffQueries, SYNC_DIFF_SORT_FIELDS};
+use crate::services::entity_history::EntityHistoryEntry;
+use crate::services::payloads::open_payloads;
+use crate::services::reports::{self, ReportFormat};
+
+/// Configure entity routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_entity_history)
+        .service(get_entity_as_of);
+}
+
+/// Query parameters for entity history
+#[derive(Debug, Deserialize)]
+pub struct EntityHistoryQuery {
+    pub county_id: Option<String>,
+    /// `json`, `csv` or `xlsx`; overrides the `Accept` header
+    pub format: Option<String>,
+}
+
+/// Query parameters for a point-in-time entity view
+#[derive(Debug, Deserialize)]
+pub struct EntityAsOfQuery {
+    pub county_id: Option<String>,
+    /// Date (`YYYY-MM-DD`, end of day UTC) or RFC 3339 timestamp; now when omitted
+    pub as_of: Option<String>,
+    /// `json`, `geojson`, `jsonld` or `html`; overrides the `Accept` header
+    pub format: Option<String>,
+    /// Field holding the entity's geometry in GeoJSON; `geometry` by default
+    pub geometry_field: Option<String>,
+}
+
+/// An entity as it existed at a moment, reconstructed from the diff that
+/// last touched it by then
+///
+/// Also served as a GeoJSON Feature, as schema.org JSON-LD for open-data
+/// aggregators, or as an HTML page, by `Accept` header or `?format=`.
+#[get("/entities/{entity_type}/{entity_id}")]
+async fn get_entity_as_of(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<EntityAsOfQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (entity_type, entity_id) = path.into_inner();
+    if entity_type.trim().is_empty() || entity_id.trim().is_empty() {
+        return Err(Error::Validation("Entity type and ID are required".to_string()));
+    }
+    let representation = Representation::from_request(&req, query.format.as_deref())?;
+    let as_of = match query.as_of.as_deref() {
+        Some(as_of) => temporal::parse_as_of(as_of)?,
+        None => chrono::Utc::now(),
+    };
+    let county_id = county.effective_county(query.county_id.as_deref())?;
+
+    let pool = app_state.db_pool.read_pool();
+    let record = TemporalQueries::entity_as_of(&pool, &entity_type, &entity_id, county_id.as_deref(), as_of)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("No history of {} {} as of {}", entity_type, entity_id, as_of.to_rfc3339())))?;
+    let mut data = record.data.clone().filter(|_| record.exists());
+    open_payloads(app_state.payloads.as_ref(), &record.county_id, vec![&mut data]).await?;
+
+    let entity = serde_json::json!({
+        "entity_type": entity_type,
+        "entity_id": entity_id,
+        "county_id": record.county_id,
+        "as_of": as_of,
+        "exists": record.exists(),
+        "data": data,
+        "change_type": record.change_type,
+        "sync_operation_id": record.sync_operation_id,
+        "changed_at": record.changed_at,
+    });
+    let data = data.unwrap_or(serde_json::Value::Null);
+    let geometry_field = query.geometry_field.as_deref().unwrap_or("geometry");
+
+    let body = match representation {
+        Representation::Json => entity.to_string(),
+        Representation::GeoJson => {
+            let mut feature = negotiation::record_feature(&entity_id, &data, geometry_field);
+            feature["properties"]["entity_type"] = entity["entity_type"].clone();
+            feature["properties"]["as_of"] = entity["as_of"].clone();
+            feature.to_string()
+        }
+        Representation::JsonLd => entity_linked_data(&entity, &data).to_string(),
+        Representation::Html => {
+            let title = format!("{} {} as of {}", entity_type, entity_id, as_of.format("%Y-%m-%d %H:%M UTC"));
+            negotiation::html_document(&title, &entity, Some(&entity_linked_data(&entity, &data)))
+        }
+    };
+    Ok(representation.respond(body))
+}
+
+/// The entity as a schema.org `Thing` whose fields are property values
+fn entity_linked_data(entity: &serde_json::Value, data: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "@context": negotiation::SCHEMA_ORG,
+        "@type": "Thing",
+        "identifier": entity["entity_id"],
+        "additionalType": entity["entity_type"],
+        "dateModified": entity["changed_at"],
+        "additionalProperty": negotiation::property_values(data),
+    })
+}
+
+/// Every sync diff that touched an entity, newest first
+///
+/// Also available as a CSV or XLSX download of the full history.
+#[get("/entities/{entity_type}/{entity_id}/history")]
+async fn get_entity_history(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<EntityHistoryQuery>,
+    sort: web::Query<SortParams>,
+    pagination: Pagination,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (entity_type, entity_id) = path.into_inner();
+    if entity_type.trim().is_empty() || entity_id.trim().is_empty() {
+        return Err(Error::Validation("Entity type and ID are required".to_string()));
+    }
+    
+    let format = ReportFormat::negotiate(&req, query.format.as_deref())?;
+    let county_id = county.effective_county(query.county_id.as_deref())?;
+    let order = sort.order_by(SYNC_DIFF_SORT_FIELDS, "d.id")?;
+    
+    if !format.is_json() {
+        let (sql, args) = SyncDiffQueries::entity_history_report(&entity_type, &entity_id, county_id.as_deref(), &order);
+        let name = format!("{}-{}-history", entity_type, entity_id);
+        return reports::download::<EntityHistoryRow>(format, &name, app_state.db_pool.read_pool(), sql, args, app_state.payloads.clone()).await;
+    }
+    
+    order.ensure_cursor_compatible(&pagination)?;
+    
+    let mut rows = SyncDiffQueries::entity_history(
+        &app_state.db_pool.read_pool(),
+        &entity_type,
+        &entity_id,
+        county_id.as_deref(),
+        &order,
+        &pagination,
+    )
+    .await?;
+    for row in rows.iter_mut() {
+        open_payloads(
+            app_state.payloads.as_ref(),
+            &row.county_id,
+            vec![&mut row.source_data, &mut row.target_data],
+        )
+        .await?;
+    }
+    let history: Vec<EntityHistoryEntry> = rows.into_iter().map(EntityHistoryEntry::from).collect();
+    let page = pagination.into_page(history, None, |entry| Cursor::new(entry.changed_at, entry.diff_id));
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "entity_type": entity_type,
+        "entity_id": entity_id,
+        "history": page.items,
+        "page": page.page,
+        "per_page": page.per_page,
+        "next_cursor": page.next_cursor
+    })))
+}
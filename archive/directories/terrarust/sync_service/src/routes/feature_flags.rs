@@ -0,0 +1,177 @@
+use actix_web::{web, HttpResponse, Responder, delete, get, put};
+use serde::Deserialize;
+use serde_json::json;
+use terrafusion_common::{CountyContext, Error, Result};
+use terrafusion_common::features::FeatureFlagQueries;
+use crate::AppState;
+
+/// Configure feature flag routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_feature_flags)
+       .service(upsert_feature_flag)
+       .service(delete_feature_flag)
+       .service(set_county_override)
+       .service(clear_county_override);
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeatureFlagRequest {
+    #[serde(default)]
+    pub description: String,
+    pub enabled: bool,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CountyOverrideRequest {
+    pub enabled: bool,
+}
+
+fn ensure_platform_admin(county: &CountyContext) -> Result<()> {
+    if county.is_platform_admin {
+        Ok(())
+    } else {
+        Err(Error::Authorization("Managing feature flags requires a platform administrator".to_string()))
+    }
+}
+
+/// County overrides may also be managed by that county's administrators
+fn ensure_county_admin(county: &CountyContext, county_id: &str) -> Result<()> {
+    county.ensure_access(county_id)?;
+    if county.is_platform_admin || county.has_role("admin") {
+        Ok(())
+    } else {
+        Err(Error::Authorization("Administrator role required".to_string()))
+    }
+}
+
+/// All flags with whether each is enabled for the caller.
+///
+/// Platform administrators also see every county override.
+#[get("/feature-flags")]
+async fn list_feature_flags(
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let snapshot = app_state.feature_flags.snapshot();
+    let mut flags: Vec<_> = snapshot.values().collect();
+    flags.sort_by(|a, b| a.key.cmp(&b.key));
+    
+    let flags: Vec<_> = flags
+        .into_iter()
+        .map(|flag| {
+            let mut entry = json!({
+                "key": flag.key,
+                "description": flag.description,
+                "enabled_for_caller": flag.is_enabled_for(&county),
+            });
+            if county.is_platform_admin {
+                entry["default_enabled"] = json!(flag.enabled);
+                entry["roles"] = json!(flag.roles);
+                entry["county_overrides"] = json!(flag.county_overrides);
+                entry["updated_by"] = json!(flag.updated_by);
+                entry["updated_at"] = json!(flag.updated_at);
+            } else if let Some(enabled) = flag.county_overrides.get(&county.county_id) {
+                entry["county_override"] = json!(enabled);
+            }
+            entry
+        })
+        .collect();
+    
+    Ok(web::Json(json!({
+        "county_id": county.county_id,
+        "flags": flags,
+    })))
+}
+
+/// Create or update a flag's default and role restriction
+#[put("/feature-flags/{key}")]
+async fn upsert_feature_flag(
+    path: web::Path<String>,
+    request: web::Json<FeatureFlagRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_platform_admin(&county)?;
+    let key = path.into_inner();
+    
+    FeatureFlagQueries::upsert(
+        &app_state.db_pool.pool(),
+        &key,
+        &request.description,
+        request.enabled,
+        &request.roles,
+        &county.county_id,
+    )
+    .await?;
+    app_state.feature_flags.refresh(&app_state.db_pool.pool()).await?;
+    
+    log::info!("Feature flag {} set to {} (roles: {:?})", key, request.enabled, request.roles);
+    
+    Ok(web::Json(app_state.feature_flags.snapshot().get(&key).cloned()))
+}
+
+/// Delete a flag; guarded features become unavailable everywhere
+#[delete("/feature-flags/{key}")]
+async fn delete_feature_flag(
+    path: web::Path<String>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_platform_admin(&county)?;
+    let key = path.into_inner();
+    
+    if !FeatureFlagQueries::delete(&app_state.db_pool.pool(), &key).await? {
+        return Err(Error::NotFound(format!("Feature flag {} not found", key)));
+    }
+    app_state.feature_flags.refresh(&app_state.db_pool.pool()).await?;
+    
+    log::info!("Feature flag {} deleted", key);
+    
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Turn a flag on or off for one county
+#[put("/feature-flags/{key}/counties/{county_id}")]
+async fn set_county_override(
+    path: web::Path<(String, String)>,
+    request: web::Json<CountyOverrideRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let (key, county_id) = path.into_inner();
+    ensure_county_admin(&county, &county_id)?;
+    
+    FeatureFlagQueries::set_override(
+        &app_state.db_pool.pool(),
+        &key,
+        &county_id,
+        Some(request.enabled),
+        &county.county_id,
+    )
+    .await?;
+    app_state.feature_flags.refresh(&app_state.db_pool.pool()).await?;
+    
+    log::info!("Feature flag {} set to {} for county {}", key, request.enabled, county_id);
+    
+    Ok(web::Json(app_state.feature_flags.snapshot().get(&key).cloned()))
+}
+
+/// Remove a county override so the flag's default applies again
+#[delete("/feature-flags/{key}/counties/{county_id}")]
+async fn clear_county_override(
+    path: web::Path<(String, String)>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let (key, county_id) = path.into_inner();
+    ensure_county_admin(&county, &county_id)?;
+    
+    FeatureFlagQueries::set_override(&app_state.db_pool.pool(), &key, &county_id, None, &county.county_id).await?;
+    app_state.feature_flags.refresh(&app_state.db_pool.pool()).await?;
+    
+    log::info!("Feature flag {} override removed for county {}", key, county_id);
+    
+    Ok(web::Json(app_state.feature_flags.snapshot().get(&key).cloned()))
+}
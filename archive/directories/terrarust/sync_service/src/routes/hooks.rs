@@ -0,0 +1,202 @@
+use actix_web::{web, http::StatusCode, HttpRequest, HttpResponse, Responder, get, post, delete};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use terrafusion_common::{CountyContext, Error, Result};
+use terrafusion_common::idempotency;
+use crate::AppState;
+use crate::models::database::{SyncPairQueries, SyncPairRow};
+use crate::services::hooks::{self, HookQueries, NewHook, Proof, SIGNATURE_HEADER, TOKEN_HEADER};
+
+/// Configure hook administration under `/sync-pairs`
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_hooks)
+       .service(create_hook)
+       .service(rotate_hook_secret)
+       .service(toggle_hook)
+       .service(delete_hook);
+}
+
+/// Configure the receiving endpoint under `/hooks`
+pub fn configure_receiver(cfg: &mut web::ServiceConfig) {
+    cfg.service(trigger_sync_pair);
+}
+
+/// Body for turning a hook on or off
+#[derive(Debug, Deserialize)]
+pub struct ToggleHookRequest {
+    pub is_active: bool,
+}
+
+/// Hooks of a sync pair, without their secrets
+#[get("/{sync_pair_id}/hooks")]
+async fn list_hooks(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let pair = load_pair(&app_state, &county, path.into_inner()).await?;
+    let hooks = HookQueries::list(&app_state.db_pool.read_pool(), pair.id).await?;
+    Ok(web::Json(json!({ "hooks": hooks })))
+}
+
+/// Create a hook. The response carries its secret, which is not shown again.
+#[post("/{sync_pair_id}/hooks")]
+async fn create_hook(
+    path: web::Path<Uuid>,
+    request: web::Json<NewHook>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    ensure_admin(&county)?;
+    let pair = load_pair(&app_state, &county, path.into_inner()).await?;
+    hooks::validate(&request)?;
+
+    let secret = hooks::generate_secret();
+    let sealed = hooks::seal_secret(app_state.payloads.as_ref(), &pair.county_id, &secret).await?;
+    let hook = HookQueries::create(&app_state.db_pool.pool(), pair.id, &pair.county_id, &request, &sealed).await?;
+    log::info!("Created hook {} ({}) for sync pair {}", hook.name, hook.id, pair.id);
+
+    Ok(HttpResponse::Created().json(json!({
+        "hook": hook,
+        "secret": secret,
+        "url": format!("/hooks/sync-pairs/{}/trigger", pair.id),
+    })))
+}
+
+/// Replace a hook's secret, e.g. after it leaked; the old one stops working at once
+#[post("/{sync_pair_id}/hooks/{hook_id}/rotate-secret")]
+async fn rotate_hook_secret(
+    path: web::Path<(Uuid, Uuid)>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_admin(&county)?;
+    let (sync_pair_id, hook_id) = path.into_inner();
+    let pair = load_pair(&app_state, &county, sync_pair_id).await?;
+
+    let secret = hooks::generate_secret();
+    let sealed = hooks::seal_secret(app_state.payloads.as_ref(), &pair.county_id, &secret).await?;
+    if !HookQueries::set_secret(&app_state.db_pool.pool(), pair.id, hook_id, &sealed).await? {
+        return Err(Error::NotFound(format!("Hook {} not found", hook_id)));
+    }
+    log::info!("Rotated the secret of hook {} of sync pair {}", hook_id, pair.id);
+    Ok(web::Json(json!({ "hook_id": hook_id, "secret": secret })))
+}
+
+/// Turn a hook on or off without losing its secret
+#[post("/{sync_pair_id}/hooks/{hook_id}/toggle")]
+async fn toggle_hook(
+    path: web::Path<(Uuid, Uuid)>,
+    request: web::Json<ToggleHookRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_admin(&county)?;
+    let (sync_pair_id, hook_id) = path.into_inner();
+    let pair = load_pair(&app_state, &county, sync_pair_id).await?;
+    if !HookQueries::set_active(&app_state.db_pool.pool(), pair.id, hook_id, request.is_active).await? {
+        return Err(Error::NotFound(format!("Hook {} not found", hook_id)));
+    }
+    Ok(web::Json(json!({ "hook_id": hook_id, "is_active": request.is_active })))
+}
+
+#[delete("/{sync_pair_id}/hooks/{hook_id}")]
+async fn delete_hook(
+    path: web::Path<(Uuid, Uuid)>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    ensure_admin(&county)?;
+    let (sync_pair_id, hook_id) = path.into_inner();
+    let pair = load_pair(&app_state, &county, sync_pair_id).await?;
+    if !HookQueries::delete(&app_state.db_pool.pool(), pair.id, hook_id).await? {
+        return Err(Error::NotFound(format!("Hook {} not found", hook_id)));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Start a sync of the pair on behalf of an external system.
+///
+/// There is no county context: the caller is whoever knows one of the
+/// pair's hook secrets. Deliveries whose payload fails the hook's
+/// conditions are accepted without starting anything, so the sender does
+/// not retry them. A redelivery with the same `Idempotency-Key` gets the
+/// original answer instead of a second operation.
+#[post("/sync-pairs/{sync_pair_id}/trigger")]
+async fn trigger_sync_pair(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    body: web::Bytes,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let sync_pair_id = path.into_inner();
+    let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok());
+    let proof = match (header(SIGNATURE_HEADER), header(TOKEN_HEADER)) {
+        (Some(signature), _) => Proof::Signature(signature),
+        (None, Some(token)) => Proof::Token(token),
+        (None, None) => {
+            return Err(Error::Authentication(format!("{} or {} header required", SIGNATURE_HEADER, TOKEN_HEADER)))
+        }
+    };
+
+    // Unknown pairs and wrong secrets look the same to the caller
+    let pool = app_state.db_pool.pool();
+    let hook = hooks::authenticate(&pool, app_state.payloads.as_ref(), sync_pair_id, &body, proof)
+        .await?
+        .ok_or_else(|| {
+            log::warn!("Rejected hook delivery for sync pair {}: no hook matches its credentials", sync_pair_id);
+            Error::Authentication("Invalid hook credentials".to_string())
+        })?;
+
+    let payload: Value = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body).map_err(|e| Error::Validation(format!("Payload is not valid JSON: {}", e)))?
+    };
+
+    if let Some(pointer) = hooks::unmet_condition(&hook.conditions, &payload) {
+        log::info!("Hook {} ignored a delivery: condition {} not met", hook.id, pointer);
+        return Ok(HttpResponse::Accepted().json(json!({
+            "triggered": false,
+            "reason": format!("Condition {} not met", pointer),
+        })));
+    }
+
+    let scope = format!("hook:{}", hook.id);
+    idempotency::web::run_once(&req, &pool, &scope, &payload, || async {
+        let parameters = hooks::map_payload(&hook.payload_mapping, &payload);
+        let operation_id = app_state
+            .sync_engine
+            .start_sync_operation(
+                sync_pair_id,
+                format!("hook:{}", hook.name),
+                (!parameters.is_empty()).then(|| Value::Object(parameters)),
+            )
+            .await?;
+        HookQueries::record_trigger(&pool, hook.id, operation_id).await?;
+        log::info!("Hook {} started sync operation {} of pair {}", hook.id, operation_id, sync_pair_id);
+
+        Ok((StatusCode::ACCEPTED, json!({
+            "triggered": true,
+            "operation_id": operation_id,
+            "status": "PENDING",
+        })))
+    })
+    .await
+}
+
+/// The sync pair, if it belongs to a county the caller can see
+async fn load_pair(app_state: &AppState, county: &CountyContext, sync_pair_id: Uuid) -> Result<SyncPairRow> {
+    SyncPairQueries::get_by_id(&app_state.db_pool.read_pool(), sync_pair_id)
+        .await?
+        .filter(|pair| county.can_access(&pair.county_id))
+        .ok_or_else(|| Error::NotFound("Sync pair not found".to_string()))
+}
+
+fn ensure_admin(county: &CountyContext) -> Result<()> {
+    if county.is_platform_admin || county.has_role("admin") {
+        return Ok(());
+    }
+    Err(Error::Authorization("Administrator role required to manage hooks".to_string()))
+}
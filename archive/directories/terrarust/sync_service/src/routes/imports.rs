@@ -0,0 +1,156 @@
+use actix_multipart::Multipart;
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Responder, get, post, delete};
+use futures::{StreamExt, TryStreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+use terrafusion_common::{CountyContext, Error, Result};
+use crate::AppState;
+use crate::services::imports::{ImportFile, ImportFormat};
+
+/// Configure import routes under `/imports`
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_imports)
+       .service(upload_import)
+       .service(get_import)
+       .service(delete_import);
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportListQuery {
+    pub county_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    /// Id for the import, chosen by the client so it can poll progress
+    /// while the upload is still running
+    pub upload_id: Option<Uuid>,
+    /// csv, geojson or shapefile; defaults to the file name's extension
+    pub format: Option<String>,
+    /// Filled in by the gateway with the signed-in user
+    pub uploaded_by: Option<String>,
+}
+
+/// Imports of the caller's county, newest first
+#[get("/files")]
+async fn list_imports(
+    query: web::Query<ImportListQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let county_id = county.effective_county(query.county_id.as_deref())?;
+    let imports = app_state.imports.list(county_id.as_deref()).await?;
+    Ok(web::Json(json!({ "imports": imports })))
+}
+
+/// Upload a file as multipart form data; the first part with a file name is
+/// the file. Answers once the file is stored and every record has been read.
+#[post("/files")]
+async fn upload_import(
+    req: HttpRequest,
+    query: web::Query<UploadQuery>,
+    mut multipart: Multipart,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    ensure_admin(&county)?;
+    let invalid = |e: actix_multipart::MultipartError| Error::Validation(format!("Invalid multipart upload: {}", e));
+
+    let mut upload = None;
+    while let Some(field) = multipart.try_next().await.map_err(invalid)? {
+        if let Some(name) = field.content_disposition().get_filename().map(str::to_string) {
+            upload = Some((name, field));
+            break;
+        }
+    }
+    let (file_name, field) = upload.ok_or_else(|| Error::Validation("The upload has no file part".to_string()))?;
+
+    let format = match &query.format {
+        Some(format) => ImportFormat::parse(format)?,
+        None => ImportFormat::from_file_name(&file_name).ok_or_else(|| {
+            Error::Validation(format!("Cannot tell the format of {}; pass format=csv, geojson or shapefile", file_name))
+        })?,
+    };
+    let bytes_expected = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i64>().ok());
+    let uploaded_by = query.uploaded_by.as_deref().unwrap_or("api_user");
+
+    let store = &app_state.imports;
+    let import = store
+        .begin(
+            &county.county_id,
+            query.upload_id.unwrap_or_else(Uuid::new_v4),
+            &file_name,
+            format,
+            bytes_expected,
+            uploaded_by,
+        )
+        .await?;
+    log::info!("Receiving {} upload {} ({}) for county {}", format.as_str(), import.id, file_name, import.county_id);
+
+    let outcome = async {
+        store.receive(&import, field.map(|chunk| chunk.map_err(invalid))).await?;
+        store.validate(&import).await
+    }
+    .await;
+    match outcome {
+        Ok(ready) => {
+            log::info!("Import {} is ready with {} records", ready.id, ready.record_count.unwrap_or_default());
+            Ok(HttpResponse::Created().json(ready))
+        }
+        Err(e) => {
+            log::warn!("Import {} failed: {}", import.id, e);
+            if let Err(fail) = store.fail(&import, &e.to_string()).await {
+                log::error!("Failed to record the failure of import {}: {}", import.id, fail);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// An import with its upload progress
+#[get("/files/{import_id}")]
+async fn get_import(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let import = load_import(&app_state, &county, path.into_inner()).await?;
+    let upload_percent = import.upload_percent();
+    Ok(web::Json(json!({ "import": import, "upload_percent": upload_percent })))
+}
+
+/// Delete an import and its files before it expires
+#[delete("/files/{import_id}")]
+async fn delete_import(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    ensure_admin(&county)?;
+    let import = load_import(&app_state, &county, path.into_inner()).await?;
+    app_state.imports.delete(&import).await?;
+    log::info!("Deleted import {} of county {}", import.id, import.county_id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// The import, if it belongs to a county the caller can see
+async fn load_import(app_state: &AppState, county: &CountyContext, import_id: Uuid) -> Result<ImportFile> {
+    app_state
+        .imports
+        .get(import_id)
+        .await?
+        .filter(|import| county.can_access(&import.county_id))
+        .ok_or_else(|| Error::NotFound("Import not found".to_string()))
+}
+
+fn ensure_admin(county: &CountyContext) -> Result<()> {
+    if county.is_platform_admin || county.has_role("admin") {
+        return Ok(());
+    }
+    Err(Error::Authorization("Administrator role required to upload imports".to_string()))
+}
@@ -0,0 +1,122 @@
+use actix_web::{web, HttpResponse, Responder, delete, get, post};
+use serde_json::json;
+use terrafusion_common::{CountyContext, Error, Result};
+use terrafusion_common::jobs::{JobFilter, JobStatus};
+use uuid::Uuid;
+use crate::AppState;
+
+/// Configure background job administration routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(job_stats)
+       .service(list_jobs)
+       .service(get_job)
+       .service(retry_job)
+       .service(cancel_job);
+}
+
+/// Jobs span every county, so only platform administrators see them
+fn ensure_platform_admin(county: &CountyContext) -> Result<()> {
+    if county.is_platform_admin {
+        Ok(())
+    } else {
+        Err(Error::Authorization("Background jobs require a platform administrator".to_string()))
+    }
+}
+
+fn parse_job_id(id: &str) -> Result<Uuid> {
+    Uuid::parse_str(id).map_err(|_| Error::Validation(format!("Invalid job ID: {}", id)))
+}
+
+/// Job counts per queue and status, with the oldest due job of each
+#[get("/admin/jobs/stats")]
+async fn job_stats(
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_platform_admin(&county)?;
+    let queues = app_state.jobs.backend().stats().await?;
+
+    Ok(web::Json(json!({
+        "backend": app_state.jobs.backend().name(),
+        "queues": queues,
+    })))
+}
+
+/// Jobs, newest first, filtered by `queue`, `kind` and `status`
+#[get("/admin/jobs")]
+async fn list_jobs(
+    query: web::Query<JobFilter>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_platform_admin(&county)?;
+    let jobs = app_state.jobs.backend().list(&query).await?;
+
+    Ok(web::Json(json!({
+        "jobs": jobs,
+        "limit": query.limit(),
+        "offset": query.offset(),
+    })))
+}
+
+#[get("/admin/jobs/{id}")]
+async fn get_job(
+    path: web::Path<String>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_platform_admin(&county)?;
+    let id = parse_job_id(&path)?;
+    let job = app_state
+        .jobs
+        .backend()
+        .get(id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Job {} not found", id)))?;
+
+    Ok(web::Json(job))
+}
+
+/// Queue a failed or cancelled job again with a fresh set of attempts
+#[post("/admin/jobs/{id}/retry")]
+async fn retry_job(
+    path: web::Path<String>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_platform_admin(&county)?;
+    let id = parse_job_id(&path)?;
+    let backend = app_state.jobs.backend();
+
+    if !backend.retry(id).await? {
+        return Err(match backend.get(id).await? {
+            Some(job) => Error::Conflict(format!("Job {} is {}; only failed or cancelled jobs can be retried", id, job.status.as_str())),
+            None => Error::NotFound(format!("Job {} not found", id)),
+        });
+    }
+    log::info!("Job {} requeued by an administrator", id);
+
+    Ok(HttpResponse::Accepted().json(json!({ "id": id, "status": JobStatus::Pending })))
+}
+
+/// Withdraw a job that has not started
+#[delete("/admin/jobs/{id}")]
+async fn cancel_job(
+    path: web::Path<String>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_platform_admin(&county)?;
+    let id = parse_job_id(&path)?;
+    let backend = app_state.jobs.backend();
+
+    if !backend.cancel(id).await? {
+        return Err(match backend.get(id).await? {
+            Some(job) => Error::Conflict(format!("Job {} is {}; only pending jobs can be cancelled", id, job.status.as_str())),
+            None => Error::NotFound(format!("Job {} not found", id)),
+        });
+    }
+    log::info!("Job {} cancelled by an administrator", id);
+
+    Ok(web::Json(json!({ "id": id, "status": JobStatus::Cancelled })))
+}
@@ -1,4 +1,19 @@
 pub mod api;
 pub mod system;
 pub mod sync_pairs;
-pub mod sync_operations;
\ No newline at end of file
+pub mod sync_operations;
+pub mod config_bundle;
+pub mod feature_flags;
+pub mod dashboard;
+pub mod entities;
+pub mod retention;
+pub mod jobs;
+pub mod search;
+pub mod audit;
+pub mod encryption;
+pub mod usage;
+pub mod datasets;
+pub mod hooks;
+pub mod pipelines;
+pub mod credentials;
+pub mod imports;
@@ -0,0 +1,271 @@
+use actix_web::{web, HttpResponse, Responder, get, post, put, delete};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use terrafusion_common::{CountyContext, Error, Result};
+use crate::AppState;
+use crate::models::database::SyncPairQueries;
+use crate::services::pipelines::{self, NewPipeline, Pipeline, PipelineQueries, PipelineRun, PipelineRunner, StepAction};
+
+/// Runs listed per pipeline unless the caller asks for fewer
+const DEFAULT_RUN_LIMIT: i64 = 20;
+const MAX_RUN_LIMIT: i64 = 100;
+
+/// Configure pipeline routes under `/pipelines`
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_pipelines)
+       .service(create_pipeline)
+       .service(get_pipeline)
+       .service(update_pipeline)
+       .service(toggle_pipeline)
+       .service(delete_pipeline)
+       .service(start_run)
+       .service(list_runs)
+       .service(get_run)
+       .service(cancel_run);
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PipelineListQuery {
+    pub county_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunListQuery {
+    pub limit: Option<i64>,
+}
+
+/// Body for turning a pipeline on or off
+#[derive(Debug, Deserialize)]
+pub struct TogglePipelineRequest {
+    pub is_active: bool,
+}
+
+/// Body for starting a run
+#[derive(Debug, Default, Deserialize)]
+pub struct StartRunRequest {
+    /// Filled in by the gateway with the signed-in user
+    #[serde(default)]
+    pub initiated_by: Option<String>,
+}
+
+#[get("")]
+async fn list_pipelines(
+    query: web::Query<PipelineListQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let county_id = county.effective_county(query.county_id.as_deref())?;
+    let pipelines = PipelineQueries::list(&app_state.db_pool.read_pool(), county_id.as_deref()).await?;
+    Ok(web::Json(json!({ "pipelines": pipelines })))
+}
+
+/// Create a pipeline in the caller's county
+#[post("")]
+async fn create_pipeline(
+    request: web::Json<NewPipeline>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    ensure_admin(&county)?;
+    check_steps(&app_state, &county.county_id, &request).await?;
+    let pipeline = PipelineQueries::create(&app_state.db_pool.pool(), &county.county_id, &request).await?;
+    log::info!("Created pipeline {} ({}) for county {}", pipeline.name, pipeline.id, pipeline.county_id);
+    Ok(HttpResponse::Created().json(pipeline))
+}
+
+#[get("/{pipeline_id}")]
+async fn get_pipeline(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let pipeline = load_pipeline(&app_state, &county, path.into_inner()).await?;
+    Ok(web::Json(pipeline))
+}
+
+/// Replace a pipeline's definition
+#[put("/{pipeline_id}")]
+async fn update_pipeline(
+    path: web::Path<Uuid>,
+    request: web::Json<NewPipeline>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_admin(&county)?;
+    let pipeline = load_pipeline(&app_state, &county, path.into_inner()).await?;
+    check_steps(&app_state, &pipeline.county_id, &request).await?;
+    let updated = PipelineQueries::update(&app_state.db_pool.pool(), pipeline.id, &request)
+        .await?
+        .ok_or_else(|| Error::NotFound("Pipeline not found".to_string()))?;
+    Ok(web::Json(updated))
+}
+
+#[post("/{pipeline_id}/toggle")]
+async fn toggle_pipeline(
+    path: web::Path<Uuid>,
+    request: web::Json<TogglePipelineRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_admin(&county)?;
+    let pipeline = load_pipeline(&app_state, &county, path.into_inner()).await?;
+    PipelineQueries::set_active(&app_state.db_pool.pool(), pipeline.id, request.is_active).await?;
+    Ok(web::Json(json!({ "pipeline_id": pipeline.id, "is_active": request.is_active })))
+}
+
+#[delete("/{pipeline_id}")]
+async fn delete_pipeline(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    ensure_admin(&county)?;
+    let pipeline = load_pipeline(&app_state, &county, path.into_inner()).await?;
+    PipelineQueries::delete(&app_state.db_pool.pool(), pipeline.id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Start a run of the pipeline; a worker picks it up from the queue
+#[post("/{pipeline_id}/runs")]
+async fn start_run(
+    path: web::Path<Uuid>,
+    request: Option<web::Json<StartRunRequest>>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    ensure_admin(&county)?;
+    let pipeline = load_pipeline(&app_state, &county, path.into_inner()).await?;
+    if !pipeline.is_active {
+        return Err(Error::Validation("Pipeline is not active".to_string()));
+    }
+
+    let initiated_by = request
+        .and_then(|request| request.into_inner().initiated_by)
+        .unwrap_or_else(|| "api_user".to_string());
+    let run = PipelineQueries::create_run(&app_state.db_pool.pool(), &pipeline, &initiated_by).await?;
+    PipelineRunner::enqueue(&app_state.jobs, &run).await?;
+    log::info!("Queued run {} of pipeline {}", run.id, pipeline.name);
+
+    Ok(HttpResponse::Accepted().json(run_view(&pipeline, &run)))
+}
+
+/// Recent runs, newest first
+#[get("/{pipeline_id}/runs")]
+async fn list_runs(
+    path: web::Path<Uuid>,
+    query: web::Query<RunListQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let pipeline = load_pipeline(&app_state, &county, path.into_inner()).await?;
+    let limit = query.limit.unwrap_or(DEFAULT_RUN_LIMIT).clamp(1, MAX_RUN_LIMIT);
+    let runs = PipelineQueries::list_runs(&app_state.db_pool.read_pool(), pipeline.id, limit).await?;
+    let runs: Vec<Value> = runs.iter().map(|run| run_view(&pipeline, run)).collect();
+    Ok(web::Json(json!({ "runs": runs })))
+}
+
+/// One run with the status of every step and the operation or export it started
+#[get("/{pipeline_id}/runs/{run_id}")]
+async fn get_run(
+    path: web::Path<(Uuid, Uuid)>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let (pipeline_id, run_id) = path.into_inner();
+    let pipeline = load_pipeline(&app_state, &county, pipeline_id).await?;
+    let run = load_run(&app_state, &pipeline, run_id).await?;
+    Ok(web::Json(run_view(&pipeline, &run)))
+}
+
+/// Stop a run. Running operations stop after their current batch; steps
+/// not yet started are canceled.
+#[post("/{pipeline_id}/runs/{run_id}/cancel")]
+async fn cancel_run(
+    path: web::Path<(Uuid, Uuid)>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    ensure_admin(&county)?;
+    let (pipeline_id, run_id) = path.into_inner();
+    let pipeline = load_pipeline(&app_state, &county, pipeline_id).await?;
+    let run = load_run(&app_state, &pipeline, run_id).await?;
+    if !PipelineQueries::cancel_run(&app_state.db_pool.pool(), run.id).await? {
+        return Err(Error::Conflict(format!("Pipeline run {} has already finished", run.id)));
+    }
+    Ok(web::Json(json!({ "run_id": run.id, "status": "CANCELED" })))
+}
+
+/// A run as the status view shows it: the run's fields with `steps`
+/// flattened to one entry per step, definition and progress together
+fn run_view(pipeline: &Pipeline, run: &PipelineRun) -> Value {
+    let definitions = run.steps["definition"].as_array().cloned().unwrap_or_default();
+    let progress = run.steps["runs"].as_array().cloned().unwrap_or_default();
+    let steps: Vec<Value> = definitions
+        .into_iter()
+        .zip(progress)
+        .map(|(definition, progress)| json!({ "definition": definition, "progress": progress }))
+        .collect();
+    let summary = |status: &str| steps.iter().filter(|step| step["progress"]["status"] == status).count();
+
+    json!({
+        "id": run.id,
+        "pipeline_id": pipeline.id,
+        "pipeline_name": pipeline.name,
+        "county_id": run.county_id,
+        "status": run.status,
+        "initiated_by": run.initiated_by,
+        "error_message": run.error_message,
+        "created_at": run.created_at,
+        "started_at": run.started_at,
+        "finished_at": run.finished_at,
+        "summary": {
+            "total": steps.len(),
+            "completed": summary("COMPLETED"),
+            "running": summary("RUNNING"),
+            "failed": summary("FAILED"),
+            "skipped": summary("SKIPPED"),
+        },
+        "steps": steps,
+    })
+}
+
+/// Sync steps must name pairs of the pipeline's county
+async fn check_steps(app_state: &AppState, county_id: &str, pipeline: &NewPipeline) -> Result<()> {
+    pipelines::validate(pipeline)?;
+    let pool = app_state.db_pool.read_pool();
+    for step in &pipeline.steps {
+        if let StepAction::Sync { sync_pair_id, .. } = &step.action {
+            let pair = SyncPairQueries::get_by_id(&pool, *sync_pair_id).await?;
+            if !pair.is_some_and(|pair| pair.county_id == county_id) {
+                return Err(Error::Validation(format!(
+                    "Step {} names sync pair {}, which does not exist in county {}",
+                    step.name, sync_pair_id, county_id
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The pipeline, if it belongs to a county the caller can see
+async fn load_pipeline(app_state: &AppState, county: &CountyContext, pipeline_id: Uuid) -> Result<Pipeline> {
+    PipelineQueries::get(&app_state.db_pool.read_pool(), pipeline_id)
+        .await?
+        .filter(|pipeline| county.can_access(&pipeline.county_id))
+        .ok_or_else(|| Error::NotFound("Pipeline not found".to_string()))
+}
+
+async fn load_run(app_state: &AppState, pipeline: &Pipeline, run_id: Uuid) -> Result<PipelineRun> {
+    PipelineQueries::get_run(&app_state.db_pool.pool(), run_id)
+        .await?
+        .filter(|run| run.pipeline_id == pipeline.id)
+        .ok_or_else(|| Error::NotFound("Pipeline run not found".to_string()))
+}
+
+fn ensure_admin(county: &CountyContext) -> Result<()> {
+    if county.is_platform_admin || county.has_role("admin") {
+        return Ok(());
+    }
+    Err(Error::Authorization("Administrator role required to manage pipelines".to_string()))
+}
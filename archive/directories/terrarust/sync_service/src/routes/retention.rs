@@ -0,0 +1,149 @@
+use actix_web::{web, HttpResponse, Responder, delete, get, post, put};
+use serde::Deserialize;
+use serde_json::json;
+use terrafusion_common::{CountyContext, Error, Result};
+use terrafusion_common::tenancy::ALL_COUNTIES;
+use crate::AppState;
+use crate::models::database::RetentionQueries;
+use crate::services::retention::RetentionPeriods;
+
+/// Longest retention a policy may set, about ten years
+const MAX_RETENTION_DAYS: i32 = 3650;
+
+/// Configure data retention routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_retention_policies)
+       .service(set_retention_policy)
+       .service(delete_retention_policy)
+       .service(run_retention);
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RetentionPolicyRequest {
+    pub diff_retention_days: i32,
+    pub stats_retention_days: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RetentionRunQuery {
+    pub county_id: Option<String>,
+}
+
+/// The `*` default and runs across all counties belong to platform
+/// administrators; a county's administrators manage that county
+fn ensure_retention_admin(county: &CountyContext, county_id: Option<&str>) -> Result<()> {
+    match county_id {
+        Some(county_id) if county_id != ALL_COUNTIES => {
+            county.ensure_access(county_id)?;
+            if county.is_platform_admin || county.has_role("admin") {
+                Ok(())
+            } else {
+                Err(Error::Authorization("Administrator role required".to_string()))
+            }
+        }
+        _ if county.is_platform_admin => Ok(()),
+        _ => Err(Error::Authorization("Platform-wide retention requires a platform administrator".to_string())),
+    }
+}
+
+/// Retention policies visible to the caller, with the periods in effect for their county
+#[get("/admin/retention/policies")]
+async fn list_retention_policies(
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let policies = RetentionQueries::list_policies(&app_state.db_pool.read_pool()).await?;
+    let effective = RetentionPeriods::for_county(&policies, &county.county_id);
+
+    let policies: Vec<_> = policies
+        .into_iter()
+        .filter(|p| county.is_platform_admin || p.county_id == county.county_id || p.county_id == ALL_COUNTIES)
+        .collect();
+
+    Ok(web::Json(json!({
+        "policies": policies,
+        "effective": effective,
+    })))
+}
+
+/// Set how long a county (or `*` for the default) keeps diffs and per-operation stats
+#[put("/admin/retention/policies/{county_id}")]
+async fn set_retention_policy(
+    path: web::Path<String>,
+    request: web::Json<RetentionPolicyRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let county_id = path.into_inner();
+    ensure_retention_admin(&county, Some(&county_id))?;
+
+    for (field, days) in [
+        ("diff_retention_days", request.diff_retention_days),
+        ("stats_retention_days", request.stats_retention_days),
+    ] {
+        if !(1..=MAX_RETENTION_DAYS).contains(&days) {
+            return Err(Error::Validation(format!("{} must be between 1 and {}", field, MAX_RETENTION_DAYS)));
+        }
+    }
+
+    let policy = RetentionQueries::upsert_policy(
+        &app_state.db_pool.pool(),
+        &county_id,
+        request.diff_retention_days,
+        request.stats_retention_days,
+        &county.county_id,
+    )
+    .await?;
+
+    log::info!(
+        "Retention policy for {} set to {} days of diffs, {} days of stats by {}",
+        county_id,
+        policy.diff_retention_days,
+        policy.stats_retention_days,
+        county.county_id
+    );
+
+    Ok(web::Json(policy))
+}
+
+/// Drop a county's policy so the default applies again
+#[delete("/admin/retention/policies/{county_id}")]
+async fn delete_retention_policy(
+    path: web::Path<String>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let county_id = path.into_inner();
+    if county_id == ALL_COUNTIES {
+        return Err(Error::Validation("The default retention policy cannot be deleted".to_string()));
+    }
+    ensure_retention_admin(&county, Some(&county_id))?;
+
+    if !RetentionQueries::delete_policy(&app_state.db_pool.pool(), &county_id).await? {
+        return Err(Error::NotFound(format!("No retention policy for {}", county_id)));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Run retention now instead of waiting for the background job
+#[post("/admin/retention/run")]
+async fn run_retention(
+    query: web::Query<RetentionRunQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let county_id = query.county_id.as_deref().filter(|id| *id != ALL_COUNTIES);
+    ensure_retention_admin(&county, county_id)?;
+
+    let started_at = chrono::Utc::now();
+    let reports = app_state.retention.run(county_id).await?;
+
+    Ok(web::Json(json!({
+        "started_at": started_at,
+        "finished_at": chrono::Utc::now(),
+        "diffs_deleted": reports.iter().map(|r| r.diffs_deleted).sum::<u64>(),
+        "stats_rolled_up": reports.iter().map(|r| r.stats_rolled_up).sum::<i64>(),
+        "counties": reports,
+    })))
+}
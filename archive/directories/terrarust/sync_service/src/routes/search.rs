@@ -0,0 +1,78 @@
+use actix_web::{web, Responder, get};
+use serde::Deserialize;
+use terrafusion_common::{CountyContext, Result};
+use crate::AppState;
+use crate::models::database::{SearchHitRow, SearchQueries};
+use crate::services::search::{id_prefix, normalize_query, SearchType};
+
+/// Configure search routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(search);
+}
+
+/// Query parameters for global search
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    pub q: String,
+    /// Comma-separated: `sync_pair`, `operation`, `export`; all when omitted
+    pub types: Option<String>,
+    pub county_id: Option<String>,
+    /// Applies to operations and exports
+    pub status: Option<String>,
+    /// Results per type
+    pub limit: Option<i64>,
+}
+
+/// Search sync pairs, failed operations and export jobs at once.
+///
+/// Results are grouped by type so the search box can show a section for each.
+#[get("/search")]
+async fn search(
+    params: web::Query<SearchParams>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let q = normalize_query(&params.q)?;
+    let types = SearchType::parse_list(params.types.as_deref())?;
+    let county_id = county.effective_county(params.county_id.as_deref())?;
+    let county_id = county_id.as_deref();
+    let status = params.status.as_deref();
+    let limit = params.limit.unwrap_or(10).clamp(1, 50);
+    let pool = app_state.db_pool.read_pool();
+
+    let search_pairs = async {
+        if types.contains(&SearchType::SyncPair) {
+            SearchQueries::sync_pairs(&pool, &q, county_id, limit).await
+        } else {
+            Ok(Vec::new())
+        }
+    };
+    let search_operations = async {
+        if types.contains(&SearchType::Operation) {
+            SearchQueries::operations(&pool, &q, county_id, status, limit).await
+        } else {
+            Ok(Vec::new())
+        }
+    };
+    let search_exports = async {
+        match id_prefix(&q) {
+            Some(prefix) if types.contains(&SearchType::Export) => {
+                SearchQueries::exports(&pool, &prefix, county_id, status, limit).await
+            }
+            _ => Ok(Vec::<SearchHitRow>::new()),
+        }
+    };
+
+    let (sync_pairs, operations, exports) = futures::try_join!(search_pairs, search_operations, search_exports)?;
+
+    Ok(web::Json(serde_json::json!({
+        "query": q,
+        "types": types,
+        "total": sync_pairs.len() + operations.len() + exports.len(),
+        "results": {
+            "sync_pairs": sync_pairs,
+            "operations": operations,
+            "exports": exports
+        }
+    })))
+}
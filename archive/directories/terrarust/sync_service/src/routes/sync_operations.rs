@@ -1,60 +1,137 @@
-use actix_web::{web, HttpResponse, Responder, get, post, delete};
+use actix_web::{web, http::StatusCode, HttpMessage, HttpRequest, HttpResponse, Responder, get, post, delete};
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
-use terrafusion_common::{Result, Error};
+use terrafusion_common::{CountyContext, Result, Error};
+use terrafusion_common::errors::{web::CorrelationId, ErrorEnvelope, ErrorResponse};
+use terrafusion_common::idempotency;
+use terrafusion_common::models::SortParams;
 use terrafusion_common::models::sync::*;
+use terrafusion_common::pagination::{Cursor, Pagination};
+use terrafusion_common::usage;
 use crate::AppState;
+use crate::models::database::{
+    OperationDetailRow, SyncDiffQueries, SyncOperationFilter, SyncOperationQueries, SyncOperationRow,
+    SYNC_DIFF_SORT_FIELDS, SYNC_OPERATION_SORT_FIELDS,
+};
+use crate::services::duplicates;
+use crate::services::operation_timeline;
+use crate::services::payloads::open_payloads;
+use crate::services::reports::{self, ReportFormat};
 
 /// Configure sync operations routes
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(list_sync_operations)
        .service(create_sync_operation)
        .service(get_sync_operation)
+       .service(get_sync_operation_detail)
+       .service(list_sync_operation_diffs)
        .service(cancel_sync_operation)
        .service(get_sync_operation_stats);
 }
 
 /// List sync operations with optional filtering
+///
+/// `Accept: text/csv` or `?format=csv|xlsx` downloads every matching
+/// operation instead of a page.
 #[get("")]
 async fn list_sync_operations(
+    req: HttpRequest,
     query: web::Query<SyncOperationQuery>,
+    sort: web::Query<SortParams>,
+    pagination: Pagination,
+    county: CountyContext,
     app_state: web::Data<AppState>,
-) -> Result<impl Responder> {
+) -> Result<HttpResponse> {
     log::info!("Listing sync operations with filters: {:?}", query);
     
-    // TODO: Implement database query with filters
-    let operations = Vec::<SyncOperation>::new();
+    let format = ReportFormat::negotiate(&req, query.format.as_deref())?;
+    let county_id = county.effective_county(query.county_id.as_deref())?;
+    let order = sort.order_by(SYNC_OPERATION_SORT_FIELDS, "id")?;
+    let filter = SyncOperationFilter {
+        county_id: county_id.as_deref(),
+        sync_pair_id: query.sync_pair_id,
+        status: query.status.as_deref(),
+        from_date: query.from_date,
+        to_date: query.to_date,
+    };
     
-    Ok(web::Json(serde_json::json!({
-        "operations": operations,
-        "total": 0,
-        "page": query.page.unwrap_or(1),
-        "per_page": query.per_page.unwrap_or(20)
-    })))
+    if !format.is_json() {
+        let (sql, args) = SyncOperationQueries::report(&filter, &order);
+        return reports::download::<SyncOperationRow>(format, "sync-operations", app_state.db_pool.read_pool(), sql, args, None).await;
+    }
+    
+    order.ensure_cursor_compatible(&pagination)?;
+    let (operations, total) = SyncOperationQueries::list(&app_state.db_pool.read_pool(), &filter, &order, &pagination).await?;
+    let page = pagination.into_page(operations, total, |op| Cursor::new(op.created_at, op.id));
+    
+    Ok(HttpResponse::Ok().json(page))
 }
 
 /// Create a new sync operation
+///
+/// Retries carrying the same `Idempotency-Key` get the original response
+/// instead of starting a second operation. When the pair already has an
+/// active operation its policy may refuse the request; the 409 then lists
+/// the active operations under `details`.
 #[post("")]
 async fn create_sync_operation(
+    req: HttpRequest,
     request: web::Json<CreateSyncOperationRequest>,
+    county: CountyContext,
     app_state: web::Data<AppState>,
-) -> Result<impl Responder> {
+) -> Result<HttpResponse> {
     log::info!("Creating sync operation for pair: {}", request.sync_pair_id);
     
-    // Start the sync operation using the sync engine
-    let operation_id = app_state.sync_engine.start_sync_operation(
-        request.sync_pair_id,
-        "api_user".to_string(), // TODO: Get from authentication context
-        request.custom_parameters.clone(),
-    ).await?;
+    // Only administrators may run past the county's monthly quota
+    if usage::quota_overridden(request.custom_parameters.as_ref()) && !county.is_platform_admin && !county.has_role("admin") {
+        return Err(Error::Authorization(format!(
+            "Administrator role required for {}",
+            usage::OVERRIDE_QUOTA_PARAMETER
+        )));
+    }
+    if duplicates::duplicate_allowed(request.custom_parameters.as_ref()) && !county.is_platform_admin && !county.has_role("admin") {
+        return Err(Error::Authorization(format!(
+            "Administrator role required for {}",
+            duplicates::ALLOW_DUPLICATE_PARAMETER
+        )));
+    }
     
-    log::info!("Created sync operation: {}", operation_id);
+    let scope = format!("{}:POST /sync-operations", county.county_id);
+    let body = serde_json::to_value(&*request).map_err(|e| Error::Serialization(e.to_string()))?;
     
-    Ok(web::Json(serde_json::json!({
-        "operation_id": operation_id,
-        "status": "PENDING",
-        "created_at": chrono::Utc::now()
-    })))
+    let response = idempotency::web::run_once(&req, &app_state.db_pool.pool(), &scope, &body, || async {
+        // Start the sync operation using the sync engine
+        let operation_id = app_state.sync_engine.start_sync_operation(
+            request.sync_pair_id,
+            "api_user".to_string(), // TODO: Get from authentication context
+            request.custom_parameters.clone(),
+        ).await?;
+        
+        log::info!("Created sync operation: {}", operation_id);
+        
+        Ok((StatusCode::OK, serde_json::json!({
+            "operation_id": operation_id,
+            "status": "PENDING",
+            "created_at": chrono::Utc::now()
+        })))
+    })
+    .await;
+    
+    match response {
+        Err(Error::Conflict(message)) => duplicate_conflict(&req, &app_state, request.sync_pair_id, message).await,
+        response => response,
+    }
+}
+
+/// A 409 for a pair that is busy, with its active operations as details
+async fn duplicate_conflict(req: &HttpRequest, app_state: &AppState, sync_pair_id: Uuid, message: String) -> Result<HttpResponse> {
+    let active = SyncOperationQueries::active_for_pair(&app_state.db_pool.read_pool(), sync_pair_id).await?;
+    let mut error = ErrorResponse::from_status(StatusCode::CONFLICT.as_u16(), message);
+    error.details = Some(serde_json::json!({ "active_operations": active }));
+    if let Some(CorrelationId(id)) = req.extensions().get::<CorrelationId>() {
+        error = error.with_correlation_id(id.clone());
+    }
+    Ok(HttpResponse::Conflict().json(ErrorEnvelope::from(error)))
 }
 
 /// Get a specific sync operation
@@ -80,6 +157,88 @@ async fn get_sync_operation(
     })))
 }
 
+/// Everything the operation detail page shows: the stored operation, its
+/// timeline from the execution log and the running totals per batch.
+/// Unlike `get_sync_operation` this also works for finished operations.
+#[get("/{operation_id}/detail")]
+async fn get_sync_operation_detail(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let operation = load_operation(&app_state, &county, path.into_inner()).await?;
+    let timeline = operation_timeline::build(&operation);
+    let duration_seconds = operation.end_time.map(|end| (end - operation.start_time).num_seconds());
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "operation": operation,
+        "stats": {
+            "records_processed": operation.records_processed.unwrap_or(0),
+            "records_succeeded": operation.records_succeeded.unwrap_or(0),
+            "records_failed": operation.records_failed.unwrap_or(0),
+            "duration_seconds": duration_seconds,
+            "batches": timeline.progress.len(),
+        },
+        "timeline": timeline.events,
+        "progress": timeline.progress,
+    })))
+}
+
+/// Page through the diffs an operation wrote, optionally of one change type
+/// (`CREATE`, `UPDATE`, `DELETE`) or sync status. `counts` covers every
+/// diff of the operation so the filters can show their totals.
+#[get("/{operation_id}/diffs")]
+async fn list_sync_operation_diffs(
+    path: web::Path<Uuid>,
+    query: web::Query<OperationDiffQuery>,
+    sort: web::Query<SortParams>,
+    pagination: Pagination,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let operation = load_operation(&app_state, &county, path.into_inner()).await?;
+    let order = sort.order_by(SYNC_DIFF_SORT_FIELDS, "d.id")?;
+    order.ensure_cursor_compatible(&pagination)?;
+    
+    let pool = app_state.db_pool.read_pool();
+    let (mut diffs, total) = SyncDiffQueries::for_operation(
+        &pool,
+        operation.id,
+        query.change_type.as_deref(),
+        query.sync_status.as_deref(),
+        &order,
+        &pagination,
+    )
+    .await?;
+    for diff in diffs.iter_mut() {
+        open_payloads(
+            app_state.payloads.as_ref(),
+            &operation.county_id,
+            vec![&mut diff.source_data, &mut diff.target_data],
+        )
+        .await?;
+    }
+    let counts: serde_json::Map<String, serde_json::Value> = SyncDiffQueries::change_type_counts(&pool, operation.id)
+        .await?
+        .into_iter()
+        .map(|(change_type, count)| (change_type, count.into()))
+        .collect();
+    
+    let page = pagination.into_page(diffs, total, |diff| Cursor::new(diff.created_at, diff.id));
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "page": page,
+        "counts": counts,
+    })))
+}
+
+/// The stored operation, if it belongs to a county the caller can see
+async fn load_operation(app_state: &AppState, county: &CountyContext, operation_id: Uuid) -> Result<OperationDetailRow> {
+    SyncOperationQueries::detail(&app_state.db_pool.read_pool(), operation_id)
+        .await?
+        .filter(|operation| county.can_access(&operation.county_id))
+        .ok_or_else(|| Error::NotFound(format!("Sync operation {} not found", operation_id)))
+}
+
 /// Cancel a running sync operation
 #[delete("/{operation_id}")]
 async fn cancel_sync_operation(
@@ -128,12 +287,20 @@ async fn get_sync_operation_stats(
 /// Query parameters for listing sync operations
 #[derive(Debug, Deserialize)]
 pub struct SyncOperationQuery {
+    pub county_id: Option<String>,
     pub sync_pair_id: Option<Uuid>,
     pub status: Option<String>,
     pub from_date: Option<chrono::DateTime<chrono::Utc>>,
     pub to_date: Option<chrono::DateTime<chrono::Utc>>,
-    pub page: Option<usize>,
-    pub per_page: Option<usize>,
+    /// `json`, `csv` or `xlsx`; overrides the `Accept` header
+    pub format: Option<String>,
+}
+
+/// Filters of an operation's diff browser
+#[derive(Debug, Deserialize)]
+pub struct OperationDiffQuery {
+    pub change_type: Option<String>,
+    pub sync_status: Option<String>,
 }
 
 /// Query parameters for statistics
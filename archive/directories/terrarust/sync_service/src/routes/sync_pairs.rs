@@ -1,47 +1,111 @@
-use actix_web::{web, HttpResponse, Responder, get, post, put, delete};
+use actix_web::{web, http::StatusCode, HttpRequest, HttpResponse, Responder, get, post, put, delete};
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
-use terrafusion_common::{Result, Error};
+use terrafusion_common::{CountyContext, Result, Error};
+use terrafusion_common::models::SortParams;
 use terrafusion_common::models::sync::*;
 use crate::AppState;
+use crate::models::database::{SchemaSnapshotQueries, SyncOperationQueries, SyncPairQueries, SyncPairRow, SYNC_PAIR_SORT_FIELDS};
+use crate::services::config_bundle::strategy_name;
+use crate::services::bulk::{self, BulkAction, BulkItemResult, BulkItemStatus, BulkSummary};
+use crate::services::connectors::{CheckStatus, Connector, ConnectivityCheck};
+use crate::services::history::{self, HistoryInterval};
+use crate::services::enrichment::Enricher;
+use crate::services::field_mapping::{self, apply_mappings, propose_mappings, MappingRule};
+use crate::services::schema_drift::SchemaDriftCheck;
+use terrafusion_common::conditional;
+use terrafusion_common::database::tenancy::begin_scoped;
+use terrafusion_common::idempotency;
+use terrafusion_common::utils::validation::validate_sync_pair_config;
 
 /// Configure sync pairs routes
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(list_sync_pairs)
        .service(create_sync_pair)
+       .service(bulk_sync_pair_action)
        .service(get_sync_pair)
        .service(update_sync_pair)
+       .service(update_field_mappings)
        .service(delete_sync_pair)
-       .service(toggle_sync_pair_status);
+       .service(toggle_sync_pair_status)
+       .service(validate_sync_pair)
+       .service(discover_field_mappings)
+       .service(preview_sync_pair)
+       .service(get_sync_pair_history)
+       .service(get_schema_drift)
+       .service(check_schema_drift)
+       .service(acknowledge_schema_drift);
 }
 
 /// List all sync pairs with optional filtering
+///
+/// Dashboards poll this; a request with a current `If-None-Match` gets 304.
 #[get("")]
 async fn list_sync_pairs(
+    req: HttpRequest,
     query: web::Query<SyncPairQuery>,
+    sort: web::Query<SortParams>,
+    county: CountyContext,
     app_state: web::Data<AppState>,
 ) -> Result<impl Responder> {
     log::info!("Listing sync pairs with filters: {:?}", query);
     
-    // TODO: Implement database query with filters
-    let sync_pairs = Vec::<SyncPair>::new();
+    let county_id = county.effective_county(query.county_id.as_deref())?;
+    let order = sort.order_by(SYNC_PAIR_SORT_FIELDS, "id")?;
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
     
-    Ok(web::Json(serde_json::json!({
+    let sync_pairs = SyncPairQueries::list_for_county(
+        &app_state.db_pool.read_pool(),
+        county_id.as_deref(),
+        query.is_active,
+        &order,
+        per_page as i64,
+        ((page - 1) * per_page) as i64,
+    )
+    .await?;
+    
+    let body = serde_json::json!({
+        "total": sync_pairs.len(),
         "sync_pairs": sync_pairs,
-        "total": 0,
-        "page": query.page.unwrap_or(1),
-        "per_page": query.per_page.unwrap_or(20)
-    })))
+        "page": page,
+        "per_page": per_page
+    });
+    conditional::web::json(&req, &body, None)
 }
 
 /// Create a new sync pair
 #[post("")]
 async fn create_sync_pair(
+    req: HttpRequest,
     request: web::Json<CreateSyncPairRequest>,
+    county: CountyContext,
     app_state: web::Data<AppState>,
-) -> Result<impl Responder> {
+) -> Result<HttpResponse> {
     log::info!("Creating sync pair: {}", request.name);
     
+    county.ensure_access(&request.county_id)?;
+    
+    let scope = format!("{}:POST /sync-pairs", county.county_id);
+    let body = serde_json::to_value(&*request).map_err(|e| Error::Serialization(e.to_string()))?;
+    
+    idempotency::web::run_once(&req, &app_state.db_pool.pool(), &scope, &body, || async {
+        let sync_pair = build_sync_pair(&request)?;
+        
+        let mut tx = begin_scoped(&app_state.db_pool.pool(), &county).await?;
+        SyncPairQueries::insert(&mut tx, &new_sync_pair_row(&sync_pair)).await?;
+        tx.commit().await?;
+        
+        log::info!("Created sync pair: {} with ID: {}", sync_pair.name, sync_pair.base.id);
+        
+        let body = serde_json::to_value(&sync_pair).map_err(|e| Error::Serialization(e.to_string()))?;
+        Ok((StatusCode::OK, body))
+    })
+    .await
+}
+
+/// Validate a creation request and build the new pair
+fn build_sync_pair(request: &CreateSyncPairRequest) -> Result<SyncPair> {
     // Validate the request
     if request.name.trim().is_empty() {
         return Err(Error::Validation("Sync pair name cannot be empty".to_string()));
@@ -83,24 +147,133 @@ async fn create_sync_pair(
         updated_by: "api_user".to_string(),
     };
     
-    // TODO: Save to database
+    Ok(sync_pair)
+}
+
+/// The stored form of a newly built pair; it starts without field mappings
+fn new_sync_pair_row(sync_pair: &SyncPair) -> SyncPairRow {
+    SyncPairRow {
+        id: sync_pair.base.id,
+        created_at: sync_pair.base.created_at,
+        updated_at: sync_pair.base.updated_at,
+        name: sync_pair.name.clone(),
+        description: sync_pair.description.clone(),
+        source_system: sync_pair.source_system.clone(),
+        source_config: sync_pair.source_config.clone(),
+        target_system: sync_pair.target_system.clone(),
+        target_config: sync_pair.target_config.clone(),
+        county_id: sync_pair.county_id.clone(),
+        is_active: sync_pair.is_active,
+        sync_interval_minutes: sync_pair.sync_interval_minutes,
+        sync_conflict_strategy: strategy_name(sync_pair.sync_conflict_strategy),
+        last_sync_time: None,
+        last_sync_status: None,
+        created_by: sync_pair.created_by.clone(),
+        updated_by: sync_pair.updated_by.clone(),
+        field_mappings: serde_json::json!([]),
+        needs_attention: false,
+        attention_reason: None,
+    }
+}
+
+/// Apply one action to many sync pairs, reporting the outcome of each.
+///
+/// Items are processed independently: one failure does not stop the rest.
+/// Pairs outside the caller's county are reported as not found.
+#[post("/bulk")]
+async fn bulk_sync_pair_action(
+    request: web::Json<BulkSyncPairRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let ids = bulk::unique_ids(&request.sync_pair_ids)?;
+    log::info!("Bulk {:?} of {} sync pairs for {}", request.action, ids.len(), county.county_id);
     
-    log::info!("Created sync pair: {} with ID: {}", sync_pair.name, sync_pair_id);
+    let mut results = Vec::with_capacity(ids.len());
+    for sync_pair_id in ids {
+        let result = match apply_bulk_action(request.action, sync_pair_id, &county, &app_state).await {
+            Ok(result) => result,
+            Err(e) => BulkItemResult::new(sync_pair_id, BulkItemStatus::Failed).with_message(e.to_string()),
+        };
+        results.push(result);
+    }
     
-    Ok(web::Json(sync_pair))
+    Ok(web::Json(serde_json::json!({
+        "action": request.action,
+        "summary": BulkSummary::from_results(&results),
+        "results": results
+    })))
+}
+
+async fn apply_bulk_action(
+    action: BulkAction,
+    sync_pair_id: Uuid,
+    county: &CountyContext,
+    app_state: &AppState,
+) -> Result<BulkItemResult> {
+    let pool = app_state.db_pool.pool();
+    let sync_pair = match SyncPairQueries::get_by_id(&pool, sync_pair_id).await? {
+        Some(sync_pair) if county.can_access(&sync_pair.county_id) => sync_pair,
+        _ => return Ok(BulkItemResult::new(sync_pair_id, BulkItemStatus::NotFound)),
+    };
+    
+    match action {
+        BulkAction::Activate | BulkAction::Deactivate => {
+            let is_active = action == BulkAction::Activate;
+            if SyncPairQueries::set_active(&pool, sync_pair_id, is_active, &county.county_id).await? {
+                Ok(BulkItemResult::new(sync_pair_id, BulkItemStatus::Succeeded))
+            } else {
+                let state = if is_active { "active" } else { "inactive" };
+                Ok(BulkItemResult::new(sync_pair_id, BulkItemStatus::Skipped).with_message(format!("Already {}", state)))
+            }
+        }
+        BulkAction::Delete => {
+            if SyncPairQueries::has_running_operation(&pool, sync_pair_id).await? {
+                return Ok(BulkItemResult::new(sync_pair_id, BulkItemStatus::Failed)
+                    .with_message("A sync operation is still running"));
+            }
+            match SyncPairQueries::delete(&pool, sync_pair_id).await {
+                Ok(_) => Ok(BulkItemResult::new(sync_pair_id, BulkItemStatus::Succeeded)),
+                Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23503") => {
+                    Ok(BulkItemResult::new(sync_pair_id, BulkItemStatus::Failed)
+                        .with_message("Sync pair has operation history; deactivate it instead"))
+                }
+                Err(e) => Err(e.into()),
+            }
+        }
+        BulkAction::Trigger => {
+            if !sync_pair.is_active {
+                return Ok(BulkItemResult::new(sync_pair_id, BulkItemStatus::Skipped)
+                    .with_message("Sync pair is not active"));
+            }
+            let operation_id = app_state.sync_engine
+                .start_sync_operation(sync_pair_id, county.county_id.clone(), None)
+                .await?;
+            let mut result = BulkItemResult::new(sync_pair_id, BulkItemStatus::Succeeded);
+            result.operation_id = Some(operation_id);
+            Ok(result)
+        }
+    }
 }
 
-/// Get a specific sync pair
+/// Get a specific sync pair, or 304 when the caller's copy is current
 #[get("/{sync_pair_id}")]
 async fn get_sync_pair(
+    req: HttpRequest,
     path: web::Path<Uuid>,
+    county: CountyContext,
     app_state: web::Data<AppState>,
-) -> Result<impl Responder> {
+) -> Result<HttpResponse> {
     let sync_pair_id = path.into_inner();
     log::info!("Getting sync pair: {}", sync_pair_id);
     
-    // TODO: Implement database query
-    Err(Error::NotFound("Sync pair not found".to_string()))
+    // Pairs in other counties are reported as missing rather than forbidden
+    match SyncPairQueries::get_by_id(&app_state.db_pool.read_pool(), sync_pair_id).await? {
+        Some(sync_pair) if county.can_access(&sync_pair.county_id) => {
+            conditional::web::json(&req, &sync_pair, Some(sync_pair.updated_at))
+        }
+        _ => Err(Error::NotFound("Sync pair not found".to_string())),
+    }
 }
 
 /// Update a sync pair
@@ -108,16 +281,121 @@ async fn get_sync_pair(
 async fn update_sync_pair(
     path: web::Path<Uuid>,
     request: web::Json<UpdateSyncPairRequest>,
+    county: CountyContext,
     app_state: web::Data<AppState>,
 ) -> Result<impl Responder> {
     let sync_pair_id = path.into_inner();
     log::info!("Updating sync pair: {}", sync_pair_id);
     
-    // TODO: Implement database update
+    let pool = app_state.db_pool.pool();
+    let mut sync_pair = match SyncPairQueries::get_by_id(&pool, sync_pair_id).await? {
+        Some(sync_pair) if county.can_access(&sync_pair.county_id) => sync_pair,
+        _ => return Err(Error::NotFound("Sync pair not found".to_string())),
+    };
+    
+    apply_update(&mut sync_pair, &request)?;
+    sync_pair.updated_at = chrono::Utc::now();
+    sync_pair.updated_by = county.county_id.clone();
+    
+    let mut tx = begin_scoped(&pool, &county).await?;
+    SyncPairQueries::update_config(&mut tx, &sync_pair).await?;
+    tx.commit().await?;
+    
+    Ok(web::Json(sync_pair))
+}
+
+/// Merge an update into a stored pair
+///
+/// Names are fixed once a pair exists, since config bundles match pairs by
+/// name; a pair is renamed by recreating it.
+fn apply_update(sync_pair: &mut SyncPairRow, request: &UpdateSyncPairRequest) -> Result<()> {
+    if request.name.as_deref().is_some_and(|name| name != sync_pair.name) {
+        return Err(Error::Validation("Sync pair name cannot be changed".to_string()));
+    }
+    for (field, system) in [("Source system", &request.source_system), ("Target system", &request.target_system)] {
+        if system.as_deref().is_some_and(|system| system.trim().is_empty()) {
+            return Err(Error::Validation(format!("{} cannot be empty", field)));
+        }
+    }
+    if request.sync_interval_minutes.is_some_and(|minutes| minutes < 1) {
+        return Err(Error::Validation("Sync interval must be at least one minute".to_string()));
+    }
+    
+    if let Some(description) = &request.description {
+        sync_pair.description = Some(description.clone()).filter(|description| !description.trim().is_empty());
+    }
+    if let Some(source_system) = &request.source_system {
+        sync_pair.source_system = source_system.clone();
+    }
+    if let Some(source_config) = &request.source_config {
+        sync_pair.source_config = source_config.clone();
+    }
+    if let Some(target_system) = &request.target_system {
+        sync_pair.target_system = target_system.clone();
+    }
+    if let Some(target_config) = &request.target_config {
+        sync_pair.target_config = target_config.clone();
+    }
+    if let Some(is_active) = request.is_active {
+        sync_pair.is_active = is_active;
+    }
+    if let Some(minutes) = request.sync_interval_minutes {
+        sync_pair.sync_interval_minutes = minutes;
+    }
+    if let Some(strategy) = request.sync_conflict_strategy {
+        sync_pair.sync_conflict_strategy = strategy_name(strategy);
+    }
+    Ok(())
+}
+
+/// Replace a sync pair's field mappings
+///
+/// The mappings are checked together with the pair's current configuration
+/// and refused with every problem listed if any check fails.
+#[put("/{sync_pair_id}/field-mappings")]
+async fn update_field_mappings(
+    path: web::Path<Uuid>,
+    request: web::Json<FieldMappingsRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let sync_pair_id = path.into_inner();
+    log::info!("Updating field mappings of sync pair: {}", sync_pair_id);
+    
+    let pool = app_state.db_pool.pool();
+    let sync_pair = match SyncPairQueries::get_by_id(&pool, sync_pair_id).await? {
+        Some(sync_pair) if county.can_access(&sync_pair.county_id) => sync_pair,
+        _ => return Err(Error::NotFound("Sync pair not found".to_string())),
+    };
+    
+    serde_json::from_value::<Vec<MappingRule>>(request.field_mappings.clone())
+        .map_err(|e| Error::Validation(format!("Invalid field mappings: {}", e)))?;
+    let validation = validate_sync_pair_config(
+        &sync_pair.source_system,
+        &sync_pair.target_system,
+        &sync_pair.source_config,
+        &sync_pair.target_config,
+        &request.field_mappings,
+    );
+    if !validation.is_valid {
+        let problems: Vec<String> = validation
+            .errors
+            .iter()
+            .map(|error| format!("{}: {}", error.field, error.message))
+            .collect();
+        return Err(Error::Validation(problems.join("; ")));
+    }
+    
+    SyncPairQueries::update_field_mappings(&pool, sync_pair_id, &request.field_mappings, &county.county_id).await?;
+    // New mappings are the answer to a drift alert; the next check judges them afresh
+    if sync_pair.needs_attention {
+        SyncPairQueries::set_attention(&pool, sync_pair_id, None).await?;
+    }
     
     Ok(web::Json(serde_json::json!({
-        "id": sync_pair_id,
-        "message": "Sync pair updated successfully"
+        "sync_pair_id": sync_pair_id,
+        "field_mappings": request.field_mappings,
+        "warnings": validation.warnings
     })))
 }
 
@@ -158,6 +436,296 @@ async fn toggle_sync_pair_status(
     })))
 }
 
+/// Validate a sync pair's configuration and test both ends
+///
+/// Runs the static config checks, then connects to the source and target,
+/// authenticates and reads one record from each. Connectivity tests are run
+/// even when the config has errors so every problem is reported at once.
+#[post("/{sync_pair_id}/validate")]
+async fn validate_sync_pair(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let sync_pair_id = path.into_inner();
+    log::info!("Validating sync pair: {}", sync_pair_id);
+    
+    let sync_pair = match SyncPairQueries::get_by_id(&app_state.db_pool.read_pool(), sync_pair_id).await? {
+        Some(sync_pair) if county.can_access(&sync_pair.county_id) => sync_pair,
+        _ => return Err(Error::NotFound("Sync pair not found".to_string())),
+    };
+    
+    let config = validate_sync_pair_config(
+        &sync_pair.source_system,
+        &sync_pair.target_system,
+        &sync_pair.source_config,
+        &sync_pair.target_config,
+        &sync_pair.field_mappings,
+    );
+    
+    let timeout = app_state.config.connector_timeout();
+    let credentials = &app_state.credentials;
+    let (source_config, target_config) = futures::join!(
+        credentials.resolve(&sync_pair.county_id, &sync_pair.source_config),
+        credentials.resolve(&sync_pair.county_id, &sync_pair.target_config),
+    );
+    let (source, target) = futures::join!(
+        test_endpoint(&sync_pair.source_system, source_config, timeout),
+        test_endpoint(&sync_pair.target_system, target_config, timeout),
+    );
+    
+    let passed = config.is_valid
+        && [&source, &target]
+            .iter()
+            .all(|endpoint| endpoint.checks.iter().all(|check| check.status != CheckStatus::Fail));
+    
+    Ok(web::Json(serde_json::json!({
+        "sync_pair_id": sync_pair_id,
+        "passed": passed,
+        "config": config,
+        "source": source,
+        "target": target,
+        "checked_at": chrono::Utc::now(),
+    })))
+}
+
+/// Test one side; a credential that cannot be resolved fails the configure step
+async fn test_endpoint(system: &str, config: Result<serde_json::Value>, timeout: std::time::Duration) -> EndpointValidation {
+    match config.and_then(|config| Connector::from_config(&config)) {
+        Ok(connector) => EndpointValidation {
+            system: system.to_string(),
+            connector: Some(connector.kind().to_string()),
+            checks: connector.check(timeout).await,
+        },
+        Err(e) => EndpointValidation {
+            system: system.to_string(),
+            connector: None,
+            checks: vec![ConnectivityCheck {
+                name: "configure",
+                status: CheckStatus::Fail,
+                message: Some(e.to_string()),
+                elapsed_ms: None,
+            }],
+        },
+    }
+}
+
+/// Connectivity results for one side of a sync pair
+#[derive(Debug, Serialize)]
+pub struct EndpointValidation {
+    pub system: String,
+    pub connector: Option<String>,
+    pub checks: Vec<ConnectivityCheck>,
+}
+
+/// Propose field mappings from the source and target schemas
+///
+/// Takes connector configs rather than a saved pair so it can be used while a
+/// pair is still being set up. The source may be an uploaded file, given as
+/// `{ "import_id": ... }`. The result is a draft for review, not saved.
+#[post("/discover-mappings")]
+async fn discover_field_mappings(
+    request: web::Json<DiscoverMappingsRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let source_config = app_state.imports.resolve(&county.county_id, &request.source_config).await?;
+    let source_config = app_state.credentials.resolve(&county.county_id, &source_config).await?;
+    let target_config = app_state.credentials.resolve(&county.county_id, &request.target_config).await?;
+    let source = Connector::from_config(&source_config)?;
+    let target = Connector::from_config(&target_config)?;
+    log::info!("Discovering field mappings from {} source to {} target", source.kind(), target.kind());
+    
+    let timeout = app_state.config.connector_timeout();
+    let (source_fields, target_fields) = futures::try_join!(source.describe(timeout), target.describe(timeout))?;
+    
+    let min_confidence = request
+        .min_confidence
+        .unwrap_or(field_mapping::DEFAULT_MIN_CONFIDENCE)
+        .clamp(0.0, 1.0);
+    let proposal = propose_mappings(&source_fields, &target_fields, min_confidence);
+    
+    Ok(web::Json(serde_json::json!({
+        "source_fields": source_fields,
+        "target_fields": target_fields,
+        "field_mappings": proposal.field_mappings,
+        "unmapped_source_fields": proposal.unmapped_source_fields,
+        "unmapped_target_fields": proposal.unmapped_target_fields,
+    })))
+}
+
+/// Request for field mapping discovery
+#[derive(Debug, Deserialize)]
+pub struct DiscoverMappingsRequest {
+    pub source_config: serde_json::Value,
+    pub target_config: serde_json::Value,
+    /// Minimum confidence (0.0 - 1.0) for a proposal to be included
+    pub min_confidence: Option<f64>,
+}
+
+/// Preview how field mappings transform the first few source records
+///
+/// Reads from the source only; nothing is written to the target. The source
+/// may be an uploaded file, given as `{ "import_id": ... }`. Records are
+/// enriched first when the source config has an `enrichment` section.
+#[post("/preview")]
+async fn preview_sync_pair(
+    request: web::Json<PreviewRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let limit = request.limit.unwrap_or(10).clamp(1, 100);
+    let source_config = app_state.imports.resolve(&county.county_id, &request.source_config).await?;
+    let source_config = app_state.credentials.resolve(&county.county_id, &source_config).await?;
+    let source = Connector::from_config(&source_config)?;
+    log::info!("Previewing {} records from {} source", limit, source.kind());
+    
+    let mut records = source.sample(limit, app_state.config.connector_timeout()).await?;
+    if let Some(mut enricher) = Enricher::from_source_config(&request.source_config)? {
+        enricher.enrich(&mut records).await;
+    }
+    
+    let previews: Vec<serde_json::Value> = records
+        .iter()
+        .map(|record| {
+            let (transformed, errors) = apply_mappings(record, &request.field_mappings);
+            serde_json::json!({
+                "source": record,
+                "transformed": transformed,
+                "errors": errors,
+            })
+        })
+        .collect();
+    let with_errors = previews
+        .iter()
+        .filter(|preview| preview["errors"].as_array().map_or(false, |errors| !errors.is_empty()))
+        .count();
+    
+    Ok(web::Json(serde_json::json!({
+        "records": previews,
+        "summary": {
+            "total": previews.len(),
+            "with_errors": with_errors,
+        },
+    })))
+}
+
+/// Request for a mapping preview
+#[derive(Debug, Deserialize)]
+pub struct PreviewRequest {
+    pub source_config: serde_json::Value,
+    #[serde(default)]
+    pub field_mappings: Vec<MappingRule>,
+    /// Number of source records to read (1-100, default 10)
+    pub limit: Option<usize>,
+}
+
+/// Operation history of a sync pair aggregated by day or week
+#[get("/{sync_pair_id}/history")]
+async fn get_sync_pair_history(
+    path: web::Path<Uuid>,
+    query: web::Query<HistoryQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let sync_pair_id = path.into_inner();
+    let interval = HistoryInterval::parse(query.interval.as_deref())?;
+    let to = query.to.unwrap_or_else(chrono::Utc::now);
+    let from = query.from.unwrap_or(to - interval.default_span());
+    
+    if from >= to {
+        return Err(Error::Validation("from must be before to".to_string()));
+    }
+    if to - from > chrono::Duration::days(MAX_HISTORY_DAYS) {
+        return Err(Error::Validation(format!("History is limited to {} days", MAX_HISTORY_DAYS)));
+    }
+    
+    let pool = app_state.db_pool.read_pool();
+    match SyncPairQueries::get_by_id(&pool, sync_pair_id).await? {
+        Some(sync_pair) if county.can_access(&sync_pair.county_id) => {}
+        _ => return Err(Error::NotFound("Sync pair not found".to_string())),
+    }
+    
+    let rows = SyncOperationQueries::history(&pool, sync_pair_id, interval.as_sql(), from, to).await?;
+    let failure_reasons = SyncOperationQueries::failure_reasons(&pool, sync_pair_id, from, to, 10).await?;
+    
+    Ok(web::Json(history::build_history(interval, from, to, rows, failure_reasons)))
+}
+
+/// Whether the pair needs attention and the schemas its last check saw
+#[get("/{sync_pair_id}/schema-drift")]
+async fn get_schema_drift(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let pool = app_state.db_pool.read_pool();
+    let sync_pair = load_sync_pair(&pool, &county, path.into_inner()).await?;
+    let snapshots = SchemaSnapshotQueries::for_pair(&pool, sync_pair.id).await?;
+    
+    Ok(web::Json(serde_json::json!({
+        "sync_pair_id": sync_pair.id,
+        "needs_attention": sync_pair.needs_attention,
+        "attention_reason": sync_pair.attention_reason,
+        "snapshots": snapshots,
+    })))
+}
+
+/// Check the pair's schemas now instead of waiting for the scheduled check
+#[post("/{sync_pair_id}/schema-drift/check")]
+async fn check_schema_drift(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let sync_pair = load_sync_pair(&app_state.db_pool.pool(), &county, path.into_inner()).await?;
+    let check = SchemaDriftCheck::new(
+        app_state.db_pool.clone(),
+        app_state.credentials.clone(),
+        app_state.config.connector_timeout(),
+    )
+        .check_pair(&sync_pair)
+        .await?;
+    Ok(web::Json(check))
+}
+
+/// Clear the attention flag, e.g. after a type change the mappings already handle
+#[post("/{sync_pair_id}/schema-drift/acknowledge")]
+async fn acknowledge_schema_drift(
+    path: web::Path<Uuid>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let pool = app_state.db_pool.pool();
+    let sync_pair = load_sync_pair(&pool, &county, path.into_inner()).await?;
+    SyncPairQueries::set_attention(&pool, sync_pair.id, None).await?;
+    log::info!("Schema drift on sync pair {} acknowledged by {}", sync_pair.name, county.county_id);
+    
+    Ok(web::Json(serde_json::json!({
+        "sync_pair_id": sync_pair.id,
+        "needs_attention": false,
+    })))
+}
+
+async fn load_sync_pair(pool: &sqlx::PgPool, county: &CountyContext, sync_pair_id: Uuid) -> Result<SyncPairRow> {
+    match SyncPairQueries::get_by_id(pool, sync_pair_id).await? {
+        Some(sync_pair) if county.can_access(&sync_pair.county_id) => Ok(sync_pair),
+        _ => Err(Error::NotFound("Sync pair not found".to_string())),
+    }
+}
+
+/// Longest period the history endpoint will aggregate
+const MAX_HISTORY_DAYS: i64 = 366;
+
+/// Query parameters for sync pair history
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// `day` (default) or `week`
+    pub interval: Option<String>,
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 /// Query parameters for listing sync pairs
 #[derive(Debug, Deserialize)]
 pub struct SyncPairQuery {
@@ -169,7 +737,19 @@ pub struct SyncPairQuery {
     pub per_page: Option<usize>,
 }
 
+/// Request for a bulk action
+#[derive(Debug, Deserialize)]
+pub struct BulkSyncPairRequest {
+    pub action: BulkAction,
+    pub sync_pair_ids: Vec<Uuid>,
+}
+
 /// Request for toggling sync pair status
+#[derive(Debug, Deserialize)]
+pub struct FieldMappingsRequest {
+    pub field_mappings: serde_json::Value,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ToggleStatusRequest {
     pub is_active: bool,
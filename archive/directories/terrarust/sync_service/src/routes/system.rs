@@ -1,15 +1,27 @@
-use actix_web::{web, HttpResponse, Responder, get};
+use actix_web::{web, HttpResponse, Responder, delete, get, post, put};
+use chrono::{DateTime, Utc};
+use prometheus::Encoder;
+use serde::Deserialize;
 use serde_json::json;
-use terrafusion_common::{Result, Error};
+use terrafusion_common::{CountyContext, Result, Error};
+use terrafusion_common::maintenance::{MaintenanceQueries, MaintenanceWindow};
+use terrafusion_common::tenancy::ALL_COUNTIES;
 use terrafusion_common::models::{HealthStatus, HealthCheck, ServiceHealth};
 use crate::AppState;
+use crate::models::database::WorkerQueries;
 
 /// Configure system routes
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(health_check)
        .service(metrics)
        .service(liveness_check)
-       .service(readiness_check);
+       .service(readiness_check)
+       .service(get_runtime_config)
+       .service(reload_runtime_config)
+       .service(get_maintenance)
+       .service(enable_maintenance)
+       .service(disable_maintenance)
+       .service(list_workers);
 }
 
 /// Health check endpoint
@@ -19,7 +31,7 @@ async fn health_check(app_state: web::Data<AppState>) -> Result<impl Responder>
     
     // Check database connectivity
     let db_status = match sqlx::query("SELECT 1")
-        .execute(&app_state.db_pool)
+        .execute(&app_state.db_pool.pool())
         .await
     {
         Ok(_) => HealthStatus::Up,
@@ -90,6 +102,14 @@ async fn metrics(app_state: web::Data<AppState>) -> Result<impl Responder> {
          sync_records_processed_total 0\n"
     );
     
+    // Append globally registered metrics (database pool usage, etc.)
+    app_state.db_pool.record_metrics();
+    let mut buffer = Vec::new();
+    prometheus::TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .map_err(|e| Error::Internal(format!("Failed to encode metrics: {}", e)))?;
+    let metrics_data = format!("{}\n{}", metrics_data, String::from_utf8_lossy(&buffer));
+    
     Ok(HttpResponse::Ok()
         .content_type("text/plain; charset=utf-8")
         .body(metrics_data))
@@ -109,7 +129,7 @@ async fn liveness_check() -> Result<impl Responder> {
 async fn readiness_check(app_state: web::Data<AppState>) -> Result<impl Responder> {
     // Check if the service is ready to handle requests
     let db_ready = sqlx::query("SELECT 1")
-        .execute(&app_state.db_pool)
+        .execute(&app_state.db_pool.pool())
         .await
         .is_ok();
     
@@ -124,4 +144,159 @@ async fn readiness_check(app_state: web::Data<AppState>) -> Result<impl Responde
     } else {
         Err(Error::ServiceUnavailable("Database not ready".to_string()))
     }
-}
\ No newline at end of file
+}
+
+/// Current runtime settings
+#[get("/config")]
+async fn get_runtime_config(app_state: web::Data<AppState>) -> Result<impl Responder> {
+    Ok(web::Json(json!({
+        "settings": *app_state.runtime_config.current(),
+        "timestamp": chrono::Utc::now()
+    })))
+}
+
+/// Reload runtime settings from the config file and environment
+#[post("/config/reload")]
+async fn reload_runtime_config(app_state: web::Data<AppState>) -> Result<impl Responder> {
+    let changed = app_state.runtime_config
+        .reload()
+        .map_err(|e| Error::Config(e.to_string()))?;
+    
+    Ok(web::Json(json!({
+        "reloaded": true,
+        "changed": changed,
+        "settings": *app_state.runtime_config.current(),
+        "timestamp": chrono::Utc::now()
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnableMaintenanceRequest {
+    /// County to put into maintenance; omit for the whole platform
+    pub county_id: Option<String>,
+    pub message: String,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub started_by: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceScopeQuery {
+    pub county_id: Option<String>,
+}
+
+/// Resolve the maintenance scope, allowing only platform admins to act on the whole platform
+fn maintenance_scope(county: &CountyContext, county_id: Option<&str>) -> Result<String> {
+    match county_id {
+        Some(county_id) => {
+            county.ensure_access(county_id)?;
+            Ok(county_id.to_string())
+        }
+        None if county.is_platform_admin => Ok(ALL_COUNTIES.to_string()),
+        None => Err(Error::Authorization("Platform maintenance requires a platform administrator".to_string())),
+    }
+}
+
+/// Maintenance windows plus how many operations are still draining
+async fn maintenance_body(app_state: &AppState) -> serde_json::Value {
+    json!({
+        "status": *app_state.maintenance.current(),
+        "running_operations": app_state.sync_engine.running_count().await,
+        "timestamp": Utc::now()
+    })
+}
+
+/// Current maintenance status
+#[get("/maintenance")]
+async fn get_maintenance(app_state: web::Data<AppState>) -> Result<impl Responder> {
+    app_state.maintenance.refresh(&app_state.db_pool.read_pool()).await?;
+    
+    Ok(web::Json(maintenance_body(&app_state).await))
+}
+
+/// Put a county or the whole platform into maintenance mode
+#[put("/maintenance")]
+async fn enable_maintenance(
+    request: web::Json<EnableMaintenanceRequest>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let request = request.into_inner();
+    let scope = maintenance_scope(&county, request.county_id.as_deref())?;
+    
+    if request.ends_at.map_or(false, |ends_at| ends_at <= Utc::now()) {
+        return Err(Error::Validation("ends_at must be in the future".to_string()));
+    }
+    
+    let window = MaintenanceWindow {
+        scope,
+        message: request.message,
+        started_by: request.started_by.unwrap_or_else(|| county.county_id.clone()),
+        started_at: Utc::now(),
+        ends_at: request.ends_at,
+    };
+    MaintenanceQueries::enable(&app_state.db_pool.pool(), &window).await?;
+    app_state.maintenance.refresh(&app_state.db_pool.pool()).await?;
+    
+    log::warn!("Maintenance mode enabled for {} by {}: {}", window.scope, window.started_by, window.message);
+    
+    Ok(web::Json(maintenance_body(&app_state).await))
+}
+
+/// End maintenance mode for a county or the whole platform
+#[delete("/maintenance")]
+async fn disable_maintenance(
+    query: web::Query<MaintenanceScopeQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let scope = maintenance_scope(&county, query.county_id.as_deref())?;
+    
+    if !MaintenanceQueries::disable(&app_state.db_pool.pool(), &scope).await? {
+        return Err(Error::NotFound(format!("No maintenance window for {}", scope)));
+    }
+    app_state.maintenance.refresh(&app_state.db_pool.pool()).await?;
+    
+    log::info!("Maintenance mode disabled for {}", scope);
+    
+    Ok(web::Json(maintenance_body(&app_state).await))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkersQuery {
+    /// Also list workers that have stopped heartbeating
+    #[serde(default)]
+    pub include_stale: bool,
+}
+
+/// Sync service processes with their role and the operations they are running
+#[get("/workers")]
+async fn list_workers(
+    query: web::Query<WorkersQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    if !county.is_platform_admin {
+        return Err(Error::Authorization("Listing workers requires a platform administrator".to_string()));
+    }
+    
+    let stale_after = chrono::Duration::from_std(app_state.config.worker_stale_after())
+        .map_err(|e| Error::Config(e.to_string()))?;
+    let active_since = Utc::now() - stale_after;
+    let since = (!query.include_stale).then_some(active_since);
+    let workers: Vec<_> = WorkerQueries::list(&app_state.db_pool.read_pool(), since)
+        .await?
+        .into_iter()
+        .map(|worker| {
+            let stale = worker.last_heartbeat_at < active_since;
+            let mut entry = json!(worker);
+            entry["stale"] = json!(stale);
+            entry
+        })
+        .collect();
+    
+    Ok(web::Json(json!({
+        "workers": workers,
+        "this_worker": app_state.workers.worker_id(),
+        "timestamp": Utc::now()
+    })))
+}
@@ -0,0 +1,58 @@
+use actix_web::{web, Responder, get};
+use serde::Deserialize;
+use serde_json::json;
+use terrafusion_common::{CountyContext, Error, Result};
+use terrafusion_common::usage::{parse_month, UsageFilter, UsageQueries};
+use crate::AppState;
+
+/// Configure usage reporting routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_usage);
+}
+
+/// Query parameters for usage reports
+#[derive(Debug, Deserialize)]
+pub struct UsageQuery {
+    /// First month, `YYYY-MM`
+    pub from: Option<String>,
+    /// Last month, `YYYY-MM`
+    pub to: Option<String>,
+    /// Platform admins may narrow to one county; others always see their own
+    pub county_id: Option<String>,
+    /// Break the totals down by the user who started the work
+    #[serde(default)]
+    pub by_user: bool,
+}
+
+/// Monthly usage totals for chargeback. County administrators see their
+/// county; platform administrators see every county.
+#[get("/usage")]
+async fn get_usage(
+    query: web::Query<UsageQuery>,
+    county: CountyContext,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    if !county.is_platform_admin && !county.has_role("admin") {
+        return Err(Error::Authorization("Administrator role required".to_string()));
+    }
+    let filter = UsageFilter {
+        county_id: county.effective_county(query.county_id.as_deref())?,
+        from: query.from.as_deref().map(parse_month).transpose()?,
+        to: query.to.as_deref().map(parse_month).transpose()?,
+        by_user: query.by_user,
+    };
+    if let (Some(from), Some(to)) = (filter.from, filter.to) {
+        if from > to {
+            return Err(Error::Validation("from must not be after to".to_string()));
+        }
+    }
+
+    let usage = UsageQueries::report(&app_state.db_pool.read_pool(), &filter).await?;
+
+    Ok(web::Json(json!({
+        "county_id": filter.county_id,
+        "from": filter.from,
+        "to": filter.to,
+        "usage": usage,
+    })))
+}
@@ -0,0 +1,187 @@
+//! Batched loading into target systems.
+//!
+//! The load stage writes an operation's differences in batches, each in one
+//! target transaction. A sync pair sets the batch size and semantics with a
+//! `batch` object in its target config:
+//!
+//! ```json
+//! "batch": { "size": 500, "mode": "all_or_nothing" }
+//! ```
+//!
+//! With `all_or_nothing` the first failed record rolls its batch back and
+//! every record in the batch counts as failed. With `best_effort` (the
+//! default) failed records are skipped and the rest of the batch commits.
+//!
+//! Each finished batch is appended to the operation's execution log with
+//! the range of differences it covered and how it ended. When a worker
+//! resumes an interrupted operation it skips the logged batches, and after a
+//! rollback the log names exactly which records were not applied.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use terrafusion_common::{Error, Result};
+
+use super::sync_engine::SyncDifference;
+
+/// Records per batch when a pair does not set one
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// Largest batch a pair may configure; bigger ones hold target locks too long
+const MAX_BATCH_SIZE: usize = 10_000;
+
+/// What a failed record does to the rest of its batch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchMode {
+    AllOrNothing,
+    #[default]
+    BestEffort,
+}
+
+/// Batch settings from the `batch` object of a target config
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatchSettings {
+    pub size: usize,
+    pub mode: BatchMode,
+}
+
+impl Default for BatchSettings {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_BATCH_SIZE,
+            mode: BatchMode::default(),
+        }
+    }
+}
+
+impl BatchSettings {
+    pub fn from_target_config(config: &serde_json::Value) -> Result<Self> {
+        let Some(batch) = config.get("batch") else {
+            return Ok(Self::default());
+        };
+        let settings: Self = serde_json::from_value(batch.clone())
+            .map_err(|e| Error::Validation(format!("Invalid batch settings: {}", e)))?;
+
+        if settings.size == 0 || settings.size > MAX_BATCH_SIZE {
+            return Err(Error::Validation(format!("batch.size must be between 1 and {}", MAX_BATCH_SIZE)));
+        }
+        Ok(settings)
+    }
+}
+
+/// How a batch ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchOutcome {
+    Committed,
+    RolledBack,
+}
+
+/// A finished batch, as recorded in the execution log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchLogEntry {
+    /// Position of the batch in the operation, from 1
+    pub batch: usize,
+    /// Index of the batch's first difference
+    pub start: usize,
+    pub count: usize,
+    pub first_source_id: String,
+    pub last_source_id: String,
+    pub mode: BatchMode,
+    pub outcome: BatchOutcome,
+    pub succeeded: usize,
+    pub failed: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl BatchLogEntry {
+    /// Whether the entry's boundaries still fall on the same records
+    fn covers(&self, differences: &[SyncDifference]) -> bool {
+        let source_id = |index: usize| differences.get(index).map(|difference| difference.source_id.as_str());
+        self.count > 0
+            && source_id(self.start) == Some(self.first_source_id.as_str())
+            && source_id(self.start + self.count - 1) == Some(self.last_source_id.as_str())
+    }
+}
+
+/// Logged batches that still line up with `differences`, and the index
+/// loading resumes from. Matching stops at the first batch whose boundaries
+/// moved (the source changed between attempts); it and everything after it
+/// are loaded again.
+pub fn resume_point<'a>(log: &'a [BatchLogEntry], differences: &[SyncDifference]) -> (&'a [BatchLogEntry], usize) {
+    let mut next = 0;
+    let mut matched = 0;
+    for entry in log {
+        if entry.start != next || !entry.covers(differences) {
+            break;
+        }
+        next += entry.count;
+        matched += 1;
+    }
+    (&log[..matched], next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::sync_engine::SyncOperationType;
+    use serde_json::json;
+
+    fn difference(source_id: &str) -> SyncDifference {
+        SyncDifference {
+            source_id: source_id.to_string(),
+            target_id: None,
+            operation_type: SyncOperationType::Create,
+            source_data: json!({ "id": source_id }),
+            target_data: None,
+            geometry_change: None,
+            boundary_violation: None,
+        }
+    }
+
+    fn entry(batch: usize, start: usize, first: &str, last: &str, count: usize) -> BatchLogEntry {
+        BatchLogEntry {
+            batch,
+            start,
+            count,
+            first_source_id: first.to_string(),
+            last_source_id: last.to_string(),
+            mode: BatchMode::AllOrNothing,
+            outcome: BatchOutcome::Committed,
+            succeeded: count,
+            failed: 0,
+            error: None,
+            finished_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_settings_from_target_config() {
+        assert_eq!(BatchSettings::from_target_config(&json!({})).unwrap(), BatchSettings::default());
+
+        let settings = BatchSettings::from_target_config(&json!({ "batch": { "size": 50, "mode": "all_or_nothing" } })).unwrap();
+        assert_eq!(settings, BatchSettings { size: 50, mode: BatchMode::AllOrNothing });
+
+        assert!(BatchSettings::from_target_config(&json!({ "batch": { "size": 0 } })).is_err());
+        assert!(BatchSettings::from_target_config(&json!({ "batch": { "mode": "sometimes" } })).is_err());
+    }
+
+    #[test]
+    fn test_resume_point_skips_matching_batches() {
+        let differences: Vec<_> = ["a", "b", "c", "d", "e"].iter().map(|id| difference(id)).collect();
+
+        let log = vec![entry(1, 0, "a", "b", 2), entry(2, 2, "c", "d", 2)];
+        let (done, next) = resume_point(&log, &differences);
+        assert_eq!((done.len(), next), (2, 4));
+
+        // The source changed under the second batch, so it is loaded again
+        let log = vec![entry(1, 0, "a", "b", 2), entry(2, 2, "c", "x", 2)];
+        let (done, next) = resume_point(&log, &differences);
+        assert_eq!((done.len(), next), (1, 2));
+
+        assert_eq!(resume_point(&[], &differences).1, 0);
+    }
+}
@@ -0,0 +1,322 @@
+//! Blue/green loading for PostgreSQL targets.
+//!
+//! A long sync writes its batches over minutes or hours, and readers of the
+//! target table see every batch as it commits. A pair can instead load into
+//! a copy of the table and swap it in once the whole load has succeeded, with
+//! a `blue_green` object in its target config:
+//!
+//! ```json
+//! "blue_green": { "swap": "rename", "keep_previous": true }
+//! ```
+//!
+//! - `rename` (the default) loads into `{table}__staging` and renames it over
+//!   the live table in one transaction. The replaced table is kept as
+//!   `{table}__previous` when `keep_previous` is set. Views and foreign keys
+//!   that reference the live table follow it to `__previous`, so targets
+//!   with dependent objects should use `view`.
+//! - `view` keeps the data in `{table}__blue` and `{table}__green` and makes
+//!   `{table}` a view over one of them. A load fills the other table and
+//!   repoints the view. The first load turns an existing table into
+//!   `{table}__blue`.
+//!
+//! The staging table starts as a copy of the live one, since an operation
+//! applies differences rather than a full reload. If any record fails, the
+//! staged load is dropped and the live table is left as it was.
+
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::{Connection, PgConnection};
+use terrafusion_common::models::sync::SyncPair;
+use terrafusion_common::{Error, Result};
+
+use super::connectors::{check_identifier, postgres_connect_error, Connector};
+
+/// How long a swap waits for readers to release the table before giving up
+const SWAP_LOCK_TIMEOUT: &str = "30s";
+
+/// How the staged table replaces the live one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapMode {
+    #[default]
+    Rename,
+    View,
+}
+
+/// Settings from the `blue_green` object of a target config
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlueGreenSettings {
+    pub swap: SwapMode,
+    /// Keep the replaced table as `{table}__previous` (rename mode only)
+    pub keep_previous: bool,
+}
+
+impl BlueGreenSettings {
+    /// Settings for a target config, or `None` when it loads in place
+    pub fn from_target_config(config: &Value) -> Result<Option<Self>> {
+        match config.get("blue_green") {
+            None | Some(Value::Null) | Some(Value::Bool(false)) => Ok(None),
+            Some(Value::Bool(true)) => Ok(Some(Self::default())),
+            Some(settings) => serde_json::from_value(settings.clone())
+                .map(Some)
+                .map_err(|e| Error::Validation(format!("Invalid blue_green settings: {}", e))),
+        }
+    }
+}
+
+/// Tables a blue/green load works with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StagingTables {
+    /// The table the load writes to
+    pub staging: String,
+    /// The table that holds the live data now, copied into `staging`
+    pub live: String,
+}
+
+/// Name of a table derived from the live one
+pub fn derived_table(table: &str, suffix: &str) -> Result<String> {
+    let name = format!("{}__{}", table, suffix);
+    check_identifier(&name).map_err(|_| {
+        Error::Validation(format!("Table name {} is too long for blue/green loading", table))
+    })?;
+    Ok(name)
+}
+
+/// The staging and live tables of a view-mode target, given the table the
+/// view reads from now, if it is a view yet
+pub fn view_tables(table: &str, current: Option<&str>) -> Result<StagingTables> {
+    let blue = derived_table(table, "blue")?;
+    let green = derived_table(table, "green")?;
+    Ok(match current {
+        Some(current) if current == green => StagingTables { staging: blue, live: green },
+        Some(current) => StagingTables { staging: green, live: current.to_string() },
+        None => StagingTables { staging: green, live: table.to_string() },
+    })
+}
+
+/// A load into a staging copy of a PostgreSQL target table
+#[derive(Debug, Clone)]
+pub struct BlueGreenLoad {
+    settings: BlueGreenSettings,
+    url: String,
+    schema: String,
+    table: String,
+    tables: Option<StagingTables>,
+}
+
+impl BlueGreenLoad {
+    /// The load for a pair configured for blue/green, or `None`
+    pub fn for_pair(sync_pair: &SyncPair) -> Result<Option<Self>> {
+        let Some(settings) = BlueGreenSettings::from_target_config(&sync_pair.target_config)? else {
+            return Ok(None);
+        };
+        match Connector::from_config(&sync_pair.target_config)? {
+            Connector::Postgres { url, schema, table } => {
+                // Fail on names too long to derive before any data moves
+                derived_table(&table, "staging")?;
+                derived_table(&table, "previous")?;
+                Ok(Some(Self { settings, url, schema, table, tables: None }))
+            }
+            other => Err(Error::Validation(format!(
+                "blue_green loading needs a postgres target, not {}",
+                other.kind()
+            ))),
+        }
+    }
+
+    /// Create the staging table as a copy of the live data and return the
+    /// pair with its target pointed at it. A resumed operation keeps the
+    /// staging table an earlier attempt filled.
+    pub async fn prepare(&mut self, sync_pair: &SyncPair, resuming: bool) -> Result<SyncPair> {
+        let mut conn = PgConnection::connect(&self.url).await.map_err(postgres_connect_error)?;
+        let tables = match self.settings.swap {
+            SwapMode::Rename => StagingTables {
+                staging: derived_table(&self.table, "staging")?,
+                live: self.table.clone(),
+            },
+            SwapMode::View => view_tables(&self.table, self.view_source(&mut conn).await?.as_deref())?,
+        };
+
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(format!(r#""{}"."{}""#, self.schema, tables.staging))
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|e| self.error("check the staging table", e))?;
+        if resuming && exists {
+            log::info!("Resuming blue/green load into {}.{}", self.schema, tables.staging);
+        } else {
+            let mut tx = conn.begin().await.map_err(|e| self.error("start the staging copy", e))?;
+            if exists {
+                // In view mode the table being replaced can own sequences the
+                // live side still draws from
+                self.adopt_sequences(&mut tx, &tables.staging, &tables.live).await?;
+                self.execute(&mut tx, format!(r#"DROP TABLE "{}"."{}""#, self.schema, tables.staging)).await?;
+            }
+            self.execute(
+                &mut tx,
+                format!(r#"CREATE TABLE "{0}"."{1}" (LIKE "{0}"."{2}" INCLUDING ALL)"#, self.schema, tables.staging, tables.live),
+            )
+            .await?;
+            self.execute(
+                &mut tx,
+                format!(r#"INSERT INTO "{0}"."{1}" SELECT * FROM "{0}"."{2}""#, self.schema, tables.staging, tables.live),
+            )
+            .await?;
+            tx.commit().await.map_err(|e| self.error("commit the staging copy", e))?;
+            log::info!("Staged {}.{} as {}", self.schema, tables.live, tables.staging);
+        }
+        let _ = conn.close().await;
+
+        let mut staged = sync_pair.clone();
+        staged.target_config["table"] = Value::String(tables.staging.clone());
+        self.tables = Some(tables);
+        Ok(staged)
+    }
+
+    /// Put the staged table in front of readers in one transaction
+    pub async fn swap(&self) -> Result<()> {
+        let tables = self.staged()?;
+        let mut conn = PgConnection::connect(&self.url).await.map_err(postgres_connect_error)?;
+        let mut tx = conn.begin().await.map_err(|e| self.error("start the swap", e))?;
+        let (schema, table) = (&self.schema, &self.table);
+
+        self.execute(&mut tx, format!("SET LOCAL lock_timeout = '{}'", SWAP_LOCK_TIMEOUT)).await?;
+        match self.settings.swap {
+            SwapMode::Rename => {
+                let previous = derived_table(table, "previous")?;
+                self.execute(&mut tx, format!(r#"DROP TABLE IF EXISTS "{}"."{}""#, schema, previous)).await?;
+                self.execute(&mut tx, format!(r#"ALTER TABLE "{}"."{}" RENAME TO "{}""#, schema, table, previous)).await?;
+                self.execute(&mut tx, format!(r#"ALTER TABLE "{}"."{}" RENAME TO "{}""#, schema, tables.staging, table)).await?;
+                self.adopt_sequences(&mut tx, &previous, table).await?;
+                if !self.settings.keep_previous {
+                    self.execute(&mut tx, format!(r#"DROP TABLE "{}"."{}""#, schema, previous)).await?;
+                }
+            }
+            SwapMode::View => {
+                if tables.live == *table {
+                    // First load: the live table becomes the blue side behind the view
+                    let blue = derived_table(table, "blue")?;
+                    self.execute(&mut tx, format!(r#"ALTER TABLE "{}"."{}" RENAME TO "{}""#, schema, table, blue)).await?;
+                }
+                self.execute(
+                    &mut tx,
+                    format!(r#"CREATE OR REPLACE VIEW "{0}"."{1}" AS SELECT * FROM "{0}"."{2}""#, schema, table, tables.staging),
+                )
+                .await?;
+            }
+        }
+        tx.commit().await.map_err(|e| self.error("commit the swap", e))?;
+        let _ = conn.close().await;
+        log::info!("Swapped {}.{} into {}.{}", schema, tables.staging, schema, table);
+        Ok(())
+    }
+
+    /// Drop a staged load that will not be swapped in
+    pub async fn discard(&self) {
+        let Ok(tables) = self.staged() else {
+            return;
+        };
+        let result = async {
+            let mut conn = PgConnection::connect(&self.url).await.map_err(postgres_connect_error)?;
+            sqlx::query(&format!(r#"DROP TABLE IF EXISTS "{}"."{}""#, self.schema, tables.staging))
+                .execute(&mut conn)
+                .await
+                .map_err(|e| self.error("drop the staging table", e))?;
+            let _ = conn.close().await;
+            Ok::<_, Error>(())
+        }
+        .await;
+        if let Err(e) = result {
+            log::warn!("Failed to discard staged load {}.{}: {}", self.schema, tables.staging, e);
+        }
+    }
+
+    fn staged(&self) -> Result<&StagingTables> {
+        self.tables
+            .as_ref()
+            .ok_or_else(|| Error::Internal("Blue/green load was not prepared".to_string()))
+    }
+
+    /// The table a view-mode target's view reads from, or `None` while the
+    /// target is still a plain table
+    async fn view_source(&self, conn: &mut PgConnection) -> Result<Option<String>> {
+        sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT table_name::text FROM information_schema.view_table_usage
+            WHERE view_schema = $1 AND view_name = $2 AND table_schema = $1
+            "#,
+        )
+        .bind(&self.schema)
+        .bind(&self.table)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| self.error("inspect the target view", e))
+    }
+
+    /// Hand the serial sequences owned by `from` to the same columns of
+    /// `to`. A copy made with `LIKE` shares the original's sequences, which
+    /// would otherwise be dropped along with it.
+    async fn adopt_sequences(&self, conn: &mut PgConnection, from: &str, to: &str) -> Result<()> {
+        let owned: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT quote_ident(sn.nspname) || '.' || quote_ident(s.relname), quote_ident(a.attname)
+            FROM pg_depend d
+            JOIN pg_class s ON s.oid = d.objid AND s.relkind = 'S'
+            JOIN pg_namespace sn ON sn.oid = s.relnamespace
+            JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+            WHERE d.classid = 'pg_class'::regclass
+              AND d.refobjid = $1::regclass
+              AND d.deptype = 'a'
+            "#,
+        )
+        .bind(format!(r#""{}"."{}""#, self.schema, from))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| self.error("look up owned sequences", e))?;
+
+        for (sequence, column) in owned {
+            self.execute(conn, format!(r#"ALTER SEQUENCE {} OWNED BY "{}"."{}".{}"#, sequence, self.schema, to, column))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn execute(&self, conn: &mut PgConnection, sql: String) -> Result<()> {
+        sqlx::query(&sql)
+            .execute(conn)
+            .await
+            .map(|_| ())
+            .map_err(|e| self.error("update the staging tables", e))
+    }
+
+    fn error(&self, action: &str, error: sqlx::Error) -> Error {
+        Error::DataSync(format!("Failed to {} for {}.{}: {}", action, self.schema, self.table, error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_settings_and_tables() {
+        assert_eq!(BlueGreenSettings::from_target_config(&json!({})).unwrap(), None);
+        assert_eq!(
+            BlueGreenSettings::from_target_config(&json!({ "blue_green": true })).unwrap(),
+            Some(BlueGreenSettings::default())
+        );
+        let settings = BlueGreenSettings::from_target_config(&json!({ "blue_green": { "swap": "view" } })).unwrap();
+        assert_eq!(settings.unwrap().swap, SwapMode::View);
+        assert!(BlueGreenSettings::from_target_config(&json!({ "blue_green": { "swap": "copy" } })).is_err());
+
+        assert!(derived_table(&"p".repeat(60), "staging").is_err());
+        assert_eq!(
+            view_tables("parcels", None).unwrap(),
+            StagingTables { staging: "parcels__green".to_string(), live: "parcels".to_string() }
+        );
+        assert_eq!(view_tables("parcels", Some("parcels__green")).unwrap().staging, "parcels__blue");
+        assert_eq!(view_tables("parcels", Some("parcels__blue")).unwrap().staging, "parcels__green");
+    }
+}
@@ -0,0 +1,180 @@
+//! County boundary enforcement on incoming geometries
+//!
+//! A sync pair can check that the geometry of every created or updated
+//! record lies in its county, which catches swapped coordinates and data
+//! exported in the wrong projection before it reaches the target. It opts in
+//! with a `boundary_check` object in the source config:
+//!
+//! ```json
+//! "boundary_check": { "mode": "reject", "field": "geometry", "crs": "EPSG:2927" }
+//! ```
+//!
+//! `flag` loads the record and notes the problem in its diff details;
+//! `reject` skips it and counts it as failed. The boundary comes from the
+//! county configuration. `crs` defaults to WGS 84.
+
+use serde::Deserialize;
+use serde_json::Value;
+use terrafusion_common::geo::{parse_geometry, Boundary};
+use terrafusion_common::models::sync::SyncPair;
+use terrafusion_common::utils::county_config;
+use terrafusion_common::{Error, Result};
+
+use super::sync_engine::{SyncDifference, SyncOperationType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoundaryMode {
+    Flag,
+    Reject,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BoundarySettings {
+    pub mode: BoundaryMode,
+    #[serde(default = "default_field")]
+    pub field: String,
+    #[serde(default)]
+    pub crs: Option<String>,
+}
+
+fn default_field() -> String {
+    "geometry".to_string()
+}
+
+impl BoundarySettings {
+    pub fn from_source_config(source_config: &Value) -> Result<Option<Self>> {
+        let Some(check) = source_config.get("boundary_check").filter(|value| !value.is_null()) else {
+            return Ok(None);
+        };
+        serde_json::from_value(check.clone())
+            .map(Some)
+            .map_err(|e| Error::Validation(format!("Invalid boundary_check settings: {}", e)))
+    }
+}
+
+/// A record turned away by a rejecting check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedRecord {
+    pub source_id: String,
+    pub reason: String,
+}
+
+/// The boundary check configured for one sync pair
+pub struct BoundaryCheck {
+    settings: BoundarySettings,
+    boundary: Boundary,
+}
+
+impl BoundaryCheck {
+    /// The pair's check with its county's boundary, or `None` when the
+    /// pair does not enable one
+    pub async fn for_pair(sync_pair: &SyncPair) -> Result<Option<Self>> {
+        let Some(settings) = BoundarySettings::from_source_config(&sync_pair.source_config)? else {
+            return Ok(None);
+        };
+        let county = county_config::load_county_configuration(&sync_pair.county_id)
+            .await
+            .map_err(|e| Error::Config(format!("County configuration for {} unavailable: {}", sync_pair.county_id, e)))?;
+        let boundary = county.boundary.as_ref().ok_or_else(|| {
+            Error::Validation(format!(
+                "boundary_check is enabled but county {} has no boundary configured",
+                sync_pair.county_id
+            ))
+        })?;
+        Self::new(settings, boundary).map(Some)
+    }
+
+    pub fn new(settings: BoundarySettings, boundary: &Value) -> Result<Self> {
+        let boundary = Boundary::from_value(boundary)
+            .map_err(|e| Error::Config(format!("Invalid county boundary: {}", e)))?;
+        let boundary = match settings.crs.as_deref() {
+            Some(crs) => boundary.to_crs(crs).map_err(|e| Error::Validation(format!("Invalid boundary_check.crs: {}", e)))?,
+            None => boundary,
+        };
+        Ok(Self { settings, boundary })
+    }
+
+    /// Check created and updated records. Flagged records keep a note of the
+    /// violation; rejected ones are removed and returned.
+    pub fn apply(&self, differences: Vec<SyncDifference>) -> (Vec<SyncDifference>, Vec<RejectedRecord>) {
+        let mut kept = Vec::with_capacity(differences.len());
+        let mut rejected = Vec::new();
+        for mut difference in differences {
+            let violation = match difference.operation_type {
+                SyncOperationType::Create | SyncOperationType::Update => self.violation(&difference.source_data),
+                _ => None,
+            };
+            match (violation, self.settings.mode) {
+                (None, _) => kept.push(difference),
+                (Some(reason), BoundaryMode::Flag) => {
+                    difference.boundary_violation = Some(reason);
+                    kept.push(difference);
+                }
+                (Some(reason), BoundaryMode::Reject) => rejected.push(RejectedRecord {
+                    source_id: difference.source_id,
+                    reason,
+                }),
+            }
+        }
+        (kept, rejected)
+    }
+
+    /// Why a record's geometry is out of bounds; records without a geometry
+    /// pass
+    fn violation(&self, record: &Value) -> Option<String> {
+        let geometry = record.get(&self.settings.field).filter(|value| !value.is_null())?;
+        match parse_geometry(geometry) {
+            Ok(parsed) => self.boundary.check(&parsed.geometry),
+            Err(e) => Some(format!("Geometry cannot be checked against the county boundary: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn difference(source_id: &str, geometry: &str) -> SyncDifference {
+        SyncDifference {
+            source_id: source_id.to_string(),
+            target_id: None,
+            operation_type: SyncOperationType::Create,
+            source_data: json!({ "id": source_id, "geometry": geometry }),
+            target_data: None,
+            geometry_change: None,
+            boundary_violation: None,
+        }
+    }
+
+    fn check(mode: &str) -> BoundaryCheck {
+        let settings = BoundarySettings::from_source_config(&json!({ "boundary_check": { "mode": mode } }))
+            .unwrap()
+            .unwrap();
+        BoundaryCheck::new(settings, &json!("POLYGON((-119.9 45.9, -119.0 45.9, -119.0 46.6, -119.9 46.6, -119.9 45.9))"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_settings() {
+        assert_eq!(BoundarySettings::from_source_config(&json!({})).unwrap(), None);
+        assert!(BoundarySettings::from_source_config(&json!({ "boundary_check": { "mode": "warn" } })).is_err());
+    }
+
+    #[test]
+    fn test_flag_and_reject() {
+        let differences = || vec![difference("1", "POINT(-119.28 46.21)"), difference("2", "POINT(46.21 -119.28)")];
+
+        let (kept, rejected) = check("flag").apply(differences());
+        assert_eq!((kept.len(), rejected.len()), (2, 0));
+        assert_eq!(kept[0].boundary_violation, None);
+        assert!(kept[1].boundary_violation.as_deref().unwrap().ends_with("look swapped"));
+        assert!(kept[1].diff_details().unwrap()["boundary_violation"].is_string());
+
+        let (kept, rejected) = check("reject").apply(differences());
+        assert_eq!(kept.len(), 1);
+        assert_eq!(rejected[0].source_id, "2");
+    }
+}
@@ -0,0 +1,113 @@
+//! Bulk actions on sync pairs, reported item by item
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use terrafusion_common::{Error, Result};
+
+/// Most pairs one bulk request may touch
+pub const MAX_BULK_ITEMS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkAction {
+    Activate,
+    Deactivate,
+    Delete,
+    /// Start a sync operation now
+    Trigger,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemStatus {
+    Succeeded,
+    /// Nothing to do, e.g. activating an active pair
+    Skipped,
+    Failed,
+    NotFound,
+}
+
+/// Outcome for one sync pair
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkItemResult {
+    pub sync_pair_id: Uuid,
+    pub status: BulkItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Operation started by `trigger`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<Uuid>,
+}
+
+impl BulkItemResult {
+    pub fn new(sync_pair_id: Uuid, status: BulkItemStatus) -> Self {
+        Self { sync_pair_id, status, message: None, operation_id: None }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+/// Counts per outcome, for a summary line in the UI
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BulkSummary {
+    pub succeeded: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub not_found: usize,
+}
+
+impl BulkSummary {
+    pub fn from_results(results: &[BulkItemResult]) -> Self {
+        results.iter().fold(Self::default(), |mut summary, result| {
+            match result.status {
+                BulkItemStatus::Succeeded => summary.succeeded += 1,
+                BulkItemStatus::Skipped => summary.skipped += 1,
+                BulkItemStatus::Failed => summary.failed += 1,
+                BulkItemStatus::NotFound => summary.not_found += 1,
+            }
+            summary
+        })
+    }
+}
+
+/// Drop repeated IDs, keeping the first occurrence, and enforce the size limit
+pub fn unique_ids(ids: &[Uuid]) -> Result<Vec<Uuid>> {
+    let mut seen = std::collections::HashSet::new();
+    let ids: Vec<Uuid> = ids.iter().copied().filter(|id| seen.insert(*id)).collect();
+
+    if ids.is_empty() {
+        return Err(Error::Validation("sync_pair_ids must not be empty".to_string()));
+    }
+    if ids.len() > MAX_BULK_ITEMS {
+        return Err(Error::Validation(format!("At most {} sync pairs per bulk request", MAX_BULK_ITEMS)));
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_ids_and_summary() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(unique_ids(&[a, b, a]).unwrap(), vec![a, b]);
+        assert!(unique_ids(&[]).is_err());
+        let too_many: Vec<Uuid> = (0..=MAX_BULK_ITEMS).map(|_| Uuid::new_v4()).collect();
+        assert!(unique_ids(&too_many).is_err());
+
+        let results = vec![
+            BulkItemResult::new(a, BulkItemStatus::Succeeded),
+            BulkItemResult::new(b, BulkItemStatus::Failed).with_message("has sync history"),
+            BulkItemResult::new(Uuid::new_v4(), BulkItemStatus::NotFound),
+        ];
+        let summary = BulkSummary::from_results(&results);
+        assert_eq!(summary, BulkSummary { succeeded: 1, skipped: 0, failed: 1, not_found: 1 });
+
+        let action: BulkAction = serde_json::from_str("\"deactivate\"").unwrap();
+        assert_eq!(action, BulkAction::Deactivate);
+    }
+}
@@ -0,0 +1,431 @@
+//! Change data capture from PostgreSQL sources.
+//!
+//! A sync pair whose source is a PostgreSQL table can stream its changes
+//! instead of waiting for scheduled full comparisons. It opts in with a
+//! `cdc` object in the source config:
+//!
+//! ```json
+//! "cdc": { "slot": "terrafusion_parcels", "max_changes": 1000 }
+//! ```
+//!
+//! The stream reads a logical replication slot through the `wal2json`
+//! output plugin, which the source server needs installed, along with
+//! `wal_level = logical` and a role allowed to use replication slots. The
+//! slot is created on first use. Every CDC_POLL_INTERVAL_SECONDS the runner
+//! peeks the pending changes of each CDC pair, loads them as one sync
+//! operation and then advances the slot past them. Changes are only
+//! consumed once every record was applied, so a failed load is retried from
+//! the same point (the target may see a change twice, never miss one).
+//!
+//! The scheduler skips CDC pairs; a manual run still does a full
+//! comparison, which is how a pair catches up after its slot is recreated.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use terrafusion_common::database::RotatingPool;
+use terrafusion_common::geo::normalize_geometry;
+use terrafusion_common::{Error, Result};
+use uuid::Uuid;
+
+use crate::models::database::{SyncPairQueries, SyncPairRow};
+use super::connectors::Connector;
+use super::sync_engine::{record_key, sync_pair_lock_key, SyncDifference, SyncEngine, SyncOperationType};
+
+/// Output plugin the slots are created with
+const OUTPUT_PLUGIN: &str = "wal2json";
+
+/// CDC settings from the `cdc` object of a source config
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CdcSettings {
+    /// Replication slot name; `terrafusion_<pair id>` when unset
+    #[serde(default)]
+    pub slot: Option<String>,
+    /// Changes read per poll; the slot may return a few more to finish a transaction
+    #[serde(default = "default_max_changes")]
+    pub max_changes: i32,
+}
+
+fn default_max_changes() -> i32 {
+    1000
+}
+
+impl CdcSettings {
+    /// Whether a source config asks for change data capture
+    pub fn enabled(source_config: &Value) -> bool {
+        matches!(source_config.get("cdc"), Some(cdc) if !cdc.is_null())
+    }
+
+    pub fn from_source_config(source_config: &Value) -> Result<Option<Self>> {
+        let Some(cdc) = source_config.get("cdc").filter(|cdc| !cdc.is_null()) else {
+            return Ok(None);
+        };
+        let settings: Self = serde_json::from_value(cdc.clone())
+            .map_err(|e| Error::Validation(format!("Invalid cdc settings: {}", e)))?;
+
+        if settings.max_changes < 1 {
+            return Err(Error::Validation("cdc.max_changes must be at least 1".to_string()));
+        }
+        if let Some(slot) = &settings.slot {
+            let valid = !slot.is_empty()
+                && slot.len() <= 63
+                && slot.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid {
+                return Err(Error::Validation(
+                    "cdc.slot may only contain lowercase letters, digits and underscores".to_string(),
+                ));
+            }
+        }
+        Ok(Some(settings))
+    }
+
+    fn slot_name(&self, sync_pair_id: Uuid) -> String {
+        self.slot.clone().unwrap_or_else(|| format!("terrafusion_{}", sync_pair_id.simple()))
+    }
+}
+
+/// A row change decoded by wal2json (format version 2)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChangeEvent {
+    /// `I`, `U` or `D`; `B` and `C` mark transaction boundaries
+    pub action: String,
+    #[serde(default)]
+    pub columns: Vec<ChangeColumn>,
+    /// Replica identity columns: the old key of an update or delete
+    #[serde(default)]
+    pub identity: Vec<ChangeColumn>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChangeColumn {
+    pub name: String,
+    /// Column type, e.g. `integer` or `geometry(MultiPolygon,2927)`
+    #[serde(default, rename = "type")]
+    pub column_type: String,
+    #[serde(default)]
+    pub value: Value,
+}
+
+impl ChangeColumn {
+    /// The value as loaded: PostGIS columns arrive as hex EWKB and are
+    /// converted to GeoJSON, like rows read by the connector
+    fn record_value(&self) -> Value {
+        let spatial = self.column_type.contains("geometry") || self.column_type.contains("geography");
+        if spatial && self.value.is_string() {
+            if let Ok(geometry) = normalize_geometry(&self.value) {
+                return geometry;
+            }
+        }
+        self.value.clone()
+    }
+}
+
+fn to_record(columns: &[ChangeColumn]) -> Value {
+    Value::Object(
+        columns
+            .iter()
+            .map(|column| (column.name.clone(), column.record_value()))
+            .collect::<Map<String, Value>>(),
+    )
+}
+
+impl ChangeEvent {
+    /// The change as a difference to load, or `None` for transaction
+    /// markers and rows without the key field
+    pub fn to_difference(&self, key_field: &str) -> Option<SyncDifference> {
+        let record = to_record(&self.columns);
+        let identity = to_record(&self.identity);
+
+        let (operation_type, source_data, key) = match self.action.as_str() {
+            "I" => (SyncOperationType::Create, record.clone(), record_key(&record, key_field)?),
+            "U" => {
+                let key = record_key(&record, key_field)?;
+                (SyncOperationType::Update, record, key)
+            }
+            "D" => (SyncOperationType::Delete, identity.clone(), record_key(&identity, key_field)?),
+            _ => return None,
+        };
+        // An update that changed the key still targets the row under its old key
+        let target_id = match operation_type {
+            SyncOperationType::Create => None,
+            _ => record_key(&identity, key_field).or_else(|| Some(key.clone())),
+        };
+
+        Some(SyncDifference {
+            source_id: key,
+            target_id,
+            operation_type,
+            source_data,
+            target_data: None,
+            geometry_change: None,
+            boundary_violation: None,
+        })
+    }
+}
+
+/// Pending changes peeked from a slot
+struct PeekedChanges {
+    differences: Vec<SyncDifference>,
+    /// Position to advance the slot to once the changes are applied
+    last_lsn: Option<String>,
+}
+
+/// A replication slot on one source table
+struct CdcSource {
+    /// Source config the slot was opened from; a changed config reopens it
+    source_config: Value,
+    settings: CdcSettings,
+    slot: String,
+    table: String,
+    key_field: String,
+    pool: PgPool,
+}
+
+impl CdcSource {
+    /// `source_config` is the pair's source config with its credential merged in
+    fn new(row: &SyncPairRow, source_config: Value, settings: CdcSettings) -> Result<Self> {
+        let Connector::Postgres { url, schema, table } = Connector::from_config(&source_config)? else {
+            return Err(Error::Validation("cdc requires a postgres source".to_string()));
+        };
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect_lazy(&url)
+            .map_err(|e| Error::Config(format!("Invalid source connection string: {}", e)))?;
+
+        Ok(Self {
+            slot: settings.slot_name(row.id),
+            settings,
+            table: format!("{}.{}", schema, table),
+            key_field: source_config
+                .get("key_field")
+                .and_then(Value::as_str)
+                .unwrap_or("id")
+                .to_string(),
+            source_config,
+            pool,
+        })
+    }
+
+    async fn ensure_slot(&self) -> Result<()> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM pg_replication_slots WHERE slot_name = $1)",
+        )
+        .bind(&self.slot)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(source_error)?;
+
+        if !exists {
+            sqlx::query("SELECT pg_create_logical_replication_slot($1, $2)")
+                .bind(&self.slot)
+                .bind(OUTPUT_PLUGIN)
+                .execute(&self.pool)
+                .await
+                .map_err(source_error)?;
+            log::info!("Created replication slot {} for {}", self.slot, self.table);
+        }
+        Ok(())
+    }
+
+    /// Read pending changes without consuming them
+    async fn peek(&self) -> Result<PeekedChanges> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            r#"
+            SELECT lsn::text, data
+            FROM pg_logical_slot_peek_changes($1, NULL, $2, 'format-version', '2', 'add-tables', $3)
+            "#,
+        )
+        .bind(&self.slot)
+        .bind(self.settings.max_changes)
+        .bind(&self.table)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(source_error)?;
+
+        let mut differences = Vec::new();
+        for (_, data) in &rows {
+            let event: ChangeEvent = serde_json::from_str(data)
+                .map_err(|e| Error::DataSync(format!("Unreadable change from slot {}: {}", self.slot, e)))?;
+            match event.to_difference(&self.key_field) {
+                Some(difference) => differences.push(difference),
+                None if matches!(event.action.as_str(), "I" | "U" | "D") => {
+                    log::warn!("Skipping change to {} without {} field", self.table, self.key_field);
+                }
+                None => {}
+            }
+        }
+
+        Ok(PeekedChanges {
+            differences,
+            last_lsn: rows.last().map(|(lsn, _)| lsn.clone()),
+        })
+    }
+
+    /// Consume the slot's changes up to `lsn`
+    async fn advance(&self, lsn: &str) -> Result<()> {
+        sqlx::query("SELECT pg_replication_slot_advance($1, $2::pg_lsn)")
+            .bind(&self.slot)
+            .bind(lsn)
+            .execute(&self.pool)
+            .await
+            .map_err(source_error)?;
+        Ok(())
+    }
+}
+
+fn source_error(error: sqlx::Error) -> Error {
+    Error::ExternalService(format!("CDC source: {}", error))
+}
+
+/// Streams changes of CDC sync pairs into the sync engine
+pub struct CdcRunner {
+    db_pool: RotatingPool,
+    engine: SyncEngine,
+    sources: HashMap<Uuid, CdcSource>,
+}
+
+impl CdcRunner {
+    pub fn new(db_pool: RotatingPool, engine: SyncEngine) -> Self {
+        Self {
+            db_pool,
+            engine,
+            sources: HashMap::new(),
+        }
+    }
+
+    /// Poll every CDC pair each `interval`
+    pub fn spawn(mut self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.poll().await {
+                    log::error!("Failed to list CDC sync pairs: {}", e);
+                }
+            }
+        })
+    }
+
+    async fn poll(&mut self) -> Result<()> {
+        let pairs = SyncPairQueries::list_cdc(&self.db_pool.pool()).await?;
+        self.sources.retain(|id, _| pairs.iter().any(|pair| pair.id == *id));
+
+        for pair in pairs {
+            if self.engine.in_maintenance(&pair.county_id) {
+                continue;
+            }
+            if let Err(e) = self.poll_pair(&pair).await {
+                log::error!("CDC for sync pair {} failed: {}", pair.name, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn poll_pair(&mut self, pair: &SyncPairRow) -> Result<()> {
+        let Some(settings) = CdcSettings::from_source_config(&pair.source_config)? else {
+            return Ok(());
+        };
+        // A rotated credential changes the resolved config, which reconnects
+        let source_config = self.engine.resolve_credentials(&pair.county_id, &pair.source_config).await?;
+        if self.sources.get(&pair.id).map(|source| &source.source_config) != Some(&source_config) {
+            let source = CdcSource::new(pair, source_config, settings)?;
+            source.ensure_slot().await?;
+            self.sources.insert(pair.id, source);
+        }
+        let source = &self.sources[&pair.id];
+
+        // The pair's lock keeps scheduled or manual runs and other instances out
+        let Some(lock) = self.engine.locks().try_acquire(&sync_pair_lock_key(pair.id)).await? else {
+            return Ok(());
+        };
+        let changes = source.peek().await?;
+        let Some(last_lsn) = changes.last_lsn else {
+            lock.release().await;
+            return Ok(());
+        };
+
+        if changes.differences.is_empty() {
+            lock.release().await;
+        } else {
+            log::info!("Applying {} changes to sync pair {}", changes.differences.len(), pair.name);
+            let stats = self.engine.apply_changes(pair.id, changes.differences, lock).await?;
+            if stats.total_records_failed > 0 {
+                log::warn!(
+                    "{} changes failed for sync pair {}; they will be retried",
+                    stats.total_records_failed,
+                    pair.name
+                );
+                return Ok(());
+            }
+        }
+        source.advance(&last_lsn).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(value: Value) -> ChangeEvent {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_settings_from_source_config() {
+        assert!(!CdcSettings::enabled(&json!({ "table": "parcels" })));
+        assert_eq!(CdcSettings::from_source_config(&json!({ "table": "parcels" })).unwrap(), None);
+
+        let settings = CdcSettings::from_source_config(&json!({ "cdc": {} })).unwrap().unwrap();
+        assert_eq!(settings.max_changes, 1000);
+        let id = Uuid::nil();
+        assert_eq!(settings.slot_name(id), format!("terrafusion_{}", id.simple()));
+
+        assert!(CdcSettings::from_source_config(&json!({ "cdc": { "slot": "Parcels-Slot" } })).is_err());
+    }
+
+    #[test]
+    fn test_change_event_to_difference() {
+        let insert = event(json!({
+            "action": "I",
+            "columns": [{ "name": "id", "value": 7 }, { "name": "situs", "value": "1 Main St" }]
+        }));
+        let difference = insert.to_difference("id").unwrap();
+        assert_eq!(difference.operation_type, SyncOperationType::Create);
+        assert_eq!(difference.source_id, "7");
+        assert_eq!(difference.target_id, None);
+        assert_eq!(difference.source_data["situs"], "1 Main St");
+
+        let rekeyed = event(json!({
+            "action": "U",
+            "columns": [{ "name": "id", "value": 8 }],
+            "identity": [{ "name": "id", "value": 7 }]
+        }));
+        let difference = rekeyed.to_difference("id").unwrap();
+        assert_eq!((difference.source_id.as_str(), difference.target_id.as_deref()), ("8", Some("7")));
+
+        let delete = event(json!({ "action": "D", "identity": [{ "name": "id", "value": 7 }] }));
+        assert_eq!(delete.to_difference("id").unwrap().operation_type, SyncOperationType::Delete);
+
+        assert!(event(json!({ "action": "B" })).to_difference("id").is_none());
+    }
+
+    #[test]
+    fn test_geometry_columns_become_geojson() {
+        let insert = event(json!({
+            "action": "I",
+            "columns": [
+                { "name": "id", "type": "integer", "value": 7 },
+                { "name": "shape", "type": "geometry(Point,4326)", "value": "0101000000CDCCCCCCCCCC5DC09A99999999194740" },
+                { "name": "note", "type": "text", "value": "0101000000CDCCCCCCCCCC5DC09A99999999194740" }
+            ]
+        }));
+        let record = insert.to_difference("id").unwrap().source_data;
+        assert_eq!(record["shape"], json!({ "type": "Point", "coordinates": [-119.2, 46.2] }));
+        assert!(record["note"].is_string());
+    }
+}
@@ -0,0 +1,561 @@
+//! Configuration bundles: county settings, sync pairs and export templates as one document
+//!
+//! Bundles are exported from one environment and imported into another, so a
+//! county's setup can be kept in version control and promoted from test to
+//! production. Secrets in connector configs are exported as a placeholder; a
+//! placeholder on import keeps the value already stored in the target.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashSet};
+use terrafusion_common::database::tenancy::begin_scoped;
+use terrafusion_common::models::SortParams;
+use terrafusion_common::models::gis_export::{CountyConfiguration, ExportTemplate};
+use terrafusion_common::utils::county_config;
+use terrafusion_common::{CountyContext, Error, Result};
+use uuid::Uuid;
+
+use crate::models::database::{ExportTemplateQueries, SyncPairQueries, SyncPairRow, SYNC_PAIR_SORT_FIELDS};
+use crate::models::{CreateSyncPairRequest, SyncConflictStrategy};
+use crate::AppState;
+
+/// Current bundle schema version
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Placeholder written in place of secret values on export
+pub const REDACTED: &str = "********";
+
+/// Config keys treated as secrets when exporting
+const SECRET_KEYS: &[&str] = &["password", "secret", "token", "api_key", "private_key", "credentials"];
+
+/// Upper bound on sync pairs read for a bundle
+const MAX_SYNC_PAIRS: i64 = 10_000;
+
+/// Everything needed to reproduce a county's configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(default)]
+    pub counties: Vec<CountyConfiguration>,
+    #[serde(default)]
+    pub sync_pairs: Vec<CreateSyncPairRequest>,
+    #[serde(default)]
+    pub export_templates: Vec<ExportTemplate>,
+}
+
+fn default_version() -> u32 {
+    BUNDLE_VERSION
+}
+
+impl Default for ConfigBundle {
+    fn default() -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            counties: Vec::new(),
+            sync_pairs: Vec::new(),
+            export_templates: Vec::new(),
+        }
+    }
+}
+
+impl ConfigBundle {
+    /// Every county referenced anywhere in the bundle
+    pub fn county_ids(&self) -> BTreeSet<String> {
+        self.counties
+            .iter()
+            .map(|county| county.county_id.clone())
+            .chain(self.sync_pairs.iter().map(|pair| pair.county_id.clone()))
+            .chain(self.export_templates.iter().map(|template| template.county_id.clone()))
+            .collect()
+    }
+}
+
+/// Serialization used for a bundle body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleFormat {
+    Json,
+    Yaml,
+}
+
+impl BundleFormat {
+    /// Pick the format from a request `Content-Type`, defaulting to JSON
+    pub fn from_content_type(content_type: Option<&str>) -> Self {
+        match content_type {
+            Some(content_type) if content_type.contains("yaml") => Self::Yaml,
+            _ => Self::Json,
+        }
+    }
+
+    pub fn from_name(name: Option<&str>) -> Result<Self> {
+        match name.map(str::to_lowercase).as_deref() {
+            None | Some("json") => Ok(Self::Json),
+            Some("yaml") | Some("yml") => Ok(Self::Yaml),
+            Some(other) => Err(Error::Validation(format!("Unsupported bundle format: {}", other))),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Yaml => "application/yaml",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+        }
+    }
+}
+
+pub fn parse_bundle(body: &[u8], format: BundleFormat) -> Result<ConfigBundle> {
+    match format {
+        BundleFormat::Json => serde_json::from_slice(body)
+            .map_err(|e| Error::Validation(format!("Invalid JSON bundle: {}", e))),
+        BundleFormat::Yaml => serde_yaml::from_slice(body)
+            .map_err(|e| Error::Validation(format!("Invalid YAML bundle: {}", e))),
+    }
+}
+
+pub fn render_bundle(bundle: &ConfigBundle, format: BundleFormat) -> Result<String> {
+    match format {
+        BundleFormat::Json => serde_json::to_string_pretty(bundle)
+            .map_err(|e| Error::Serialization(e.to_string())),
+        BundleFormat::Yaml => serde_yaml::to_string(bundle)
+            .map_err(|e| Error::Serialization(e.to_string())),
+    }
+}
+
+/// What importing a bundle entry would do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeAction {
+    Create,
+    Update,
+    Unchanged,
+}
+
+/// One entry of the dry-run diff
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleChange {
+    pub kind: &'static str,
+    pub key: String,
+    pub action: ChangeAction,
+    /// Top-level fields that differ from the current state (updates only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed_fields: Vec<String>,
+}
+
+/// Check a bundle for problems that would make it unsafe to apply
+///
+/// Returns one message per problem; an empty list means the bundle is valid.
+pub fn validate_bundle(bundle: &ConfigBundle) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    if bundle.version != BUNDLE_VERSION {
+        errors.push(format!("Unsupported bundle version {} (expected {})", bundle.version, BUNDLE_VERSION));
+    }
+
+    let mut seen = HashSet::new();
+    for county in &bundle.counties {
+        if county.county_id.trim().is_empty() {
+            errors.push("counties: county_id cannot be empty".to_string());
+            continue;
+        }
+        if !seen.insert(county.county_id.as_str()) {
+            errors.push(format!("counties: {} is listed more than once", county.county_id));
+        }
+        if !county.is_format_supported(&county.default_export_format) {
+            errors.push(format!(
+                "counties: {} default export format {} is not in available_export_formats",
+                county.county_id, county.default_export_format
+            ));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for pair in &bundle.sync_pairs {
+        let key = entry_key(&pair.county_id, &pair.name);
+        if pair.name.trim().is_empty() || pair.county_id.trim().is_empty() {
+            errors.push("sync_pairs: name and county_id are required".to_string());
+            continue;
+        }
+        if !seen.insert(key.clone()) {
+            errors.push(format!("sync_pairs: {} is listed more than once", key));
+        }
+        if pair.source_system.trim().is_empty() || pair.target_system.trim().is_empty() {
+            errors.push(format!("sync_pairs: {} needs a source_system and target_system", key));
+        }
+        if !pair.source_config.is_object() || !pair.target_config.is_object() {
+            errors.push(format!("sync_pairs: {} source_config and target_config must be objects", key));
+        }
+        if pair.sync_interval_minutes <= 0 {
+            errors.push(format!("sync_pairs: {} sync_interval_minutes must be positive", key));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for template in &bundle.export_templates {
+        let key = entry_key(&template.county_id, &template.name);
+        if template.name.trim().is_empty() || template.county_id.trim().is_empty() {
+            errors.push("export_templates: name and county_id are required".to_string());
+            continue;
+        }
+        if !seen.insert(key.clone()) {
+            errors.push(format!("export_templates: {} is listed more than once", key));
+        }
+        if template.layers.is_empty() {
+            errors.push(format!("export_templates: {} needs at least one layer", key));
+        }
+        if !(template.parameters.is_object() || template.parameters.is_null()) {
+            errors.push(format!("export_templates: {} parameters must be an object", key));
+        }
+
+        // Check against the county settings when they travel in the same bundle
+        if let Some(county) = bundle.counties.iter().find(|county| county.county_id == template.county_id) {
+            if !county.is_format_supported(&template.export_format) {
+                errors.push(format!(
+                    "export_templates: {} format {} is not enabled for {}",
+                    key, template.export_format, county.county_id
+                ));
+            }
+            for layer in template.layers.iter().filter(|layer| !county.is_layer_available(layer)) {
+                errors.push(format!("export_templates: {} layer {} is not available in {}", key, layer, county.county_id));
+            }
+        }
+    }
+
+    errors
+}
+
+/// Diff a bundle against the current state, entry by entry
+pub fn plan_changes(bundle: &ConfigBundle, current: &ConfigBundle) -> Vec<BundleChange> {
+    let mut changes = Vec::new();
+
+    for county in &bundle.counties {
+        let existing = current.counties.iter().find(|c| c.county_id == county.county_id);
+        changes.push(diff_entry("county", county.county_id.clone(), county, existing));
+    }
+    for pair in &bundle.sync_pairs {
+        let existing = current
+            .sync_pairs
+            .iter()
+            .find(|p| p.county_id == pair.county_id && p.name == pair.name);
+        changes.push(diff_entry("sync_pair", entry_key(&pair.county_id, &pair.name), pair, existing));
+    }
+    for template in &bundle.export_templates {
+        let existing = current
+            .export_templates
+            .iter()
+            .find(|t| t.county_id == template.county_id && t.name == template.name);
+        changes.push(diff_entry("export_template", entry_key(&template.county_id, &template.name), template, existing));
+    }
+
+    changes
+}
+
+fn diff_entry<T: Serialize>(kind: &'static str, key: String, new: &T, existing: Option<&T>) -> BundleChange {
+    let Some(existing) = existing else {
+        return BundleChange { kind, key, action: ChangeAction::Create, changed_fields: Vec::new() };
+    };
+
+    let new = serde_json::to_value(new).unwrap_or(Value::Null);
+    let existing = serde_json::to_value(existing).unwrap_or(Value::Null);
+    let changed_fields: Vec<String> = match (new.as_object(), existing.as_object()) {
+        (Some(new), Some(existing)) => new
+            .keys()
+            .chain(existing.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|field| new.get(*field) != existing.get(*field))
+            .cloned()
+            .collect(),
+        _ if new != existing => vec!["value".to_string()],
+        _ => Vec::new(),
+    };
+
+    let action = if changed_fields.is_empty() { ChangeAction::Unchanged } else { ChangeAction::Update };
+    BundleChange { kind, key, action, changed_fields }
+}
+
+fn entry_key(county_id: &str, name: &str) -> String {
+    format!("{}/{}", county_id, name)
+}
+
+/// Replace secret values in connector configs with [`REDACTED`]
+pub fn redact_secrets(bundle: &mut ConfigBundle) {
+    for pair in &mut bundle.sync_pairs {
+        redact_value(&mut pair.source_config);
+        redact_value(&mut pair.target_config);
+    }
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_key(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEYS.iter().any(|secret| key.contains(secret))
+}
+
+/// Put back stored secrets wherever the bundle carries the [`REDACTED`] placeholder
+pub fn restore_secrets(bundle: &mut ConfigBundle, current: &ConfigBundle) {
+    for pair in &mut bundle.sync_pairs {
+        let existing = current
+            .sync_pairs
+            .iter()
+            .find(|p| p.county_id == pair.county_id && p.name == pair.name);
+        if let Some(existing) = existing {
+            restore_value(&mut pair.source_config, &existing.source_config);
+            restore_value(&mut pair.target_config, &existing.target_config);
+        }
+    }
+}
+
+fn restore_value(value: &mut Value, stored: &Value) {
+    match value {
+        Value::String(text) if text == REDACTED => *value = stored.clone(),
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if let Some(stored) = stored.get(key) {
+                    restore_value(value, stored);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Placeholders that could not be restored because the entry is new
+pub fn unresolved_secrets(bundle: &ConfigBundle) -> Vec<String> {
+    fn contains_placeholder(value: &Value) -> bool {
+        match value {
+            Value::String(text) => text == REDACTED,
+            Value::Object(map) => map.values().any(contains_placeholder),
+            Value::Array(items) => items.iter().any(contains_placeholder),
+            _ => false,
+        }
+    }
+
+    bundle
+        .sync_pairs
+        .iter()
+        .filter(|pair| contains_placeholder(&pair.source_config) || contains_placeholder(&pair.target_config))
+        .map(|pair| format!("sync_pairs: {} has redacted secrets but does not exist yet", entry_key(&pair.county_id, &pair.name)))
+        .collect()
+}
+
+/// Read the stored configuration for a county, or every county for platform admins
+///
+/// `extra_counties` adds counties whose settings should be included even if they
+/// have no sync pairs or templates yet, e.g. those named in an incoming bundle.
+pub async fn load_current_state(
+    app_state: &AppState,
+    county_id: Option<&str>,
+    extra_counties: &BTreeSet<String>,
+) -> Result<ConfigBundle> {
+    let pool = app_state.db_pool.read_pool();
+    let order = SortParams::default().order_by(SYNC_PAIR_SORT_FIELDS, "id")?;
+
+    let sync_pairs: Vec<CreateSyncPairRequest> =
+        SyncPairQueries::list_for_county(&pool, county_id, None, &order, MAX_SYNC_PAIRS, 0)
+            .await?
+            .iter()
+            .map(sync_pair_from_row)
+            .collect();
+    let export_templates = ExportTemplateQueries::list_for_county(&pool, county_id).await?;
+
+    let mut county_ids: BTreeSet<String> = sync_pairs
+        .iter()
+        .map(|pair| pair.county_id.clone())
+        .chain(export_templates.iter().map(|template| template.county_id.clone()))
+        .chain(extra_counties.iter().cloned())
+        .collect();
+    if let Some(county_id) = county_id {
+        county_ids.insert(county_id.to_string());
+    }
+
+    let mut counties = Vec::new();
+    for county_id in &county_ids {
+        // Counties without a config file simply have no settings to export
+        if let Ok(config) = county_config::load_county_configuration(county_id).await {
+            counties.push(config);
+        }
+    }
+
+    Ok(ConfigBundle { version: BUNDLE_VERSION, counties, sync_pairs, export_templates })
+}
+
+/// Write every created or updated entry of a bundle
+///
+/// Database rows are written in one transaction; county settings files are
+/// written after it commits.
+pub async fn apply_bundle(
+    app_state: &AppState,
+    county: &CountyContext,
+    bundle: &ConfigBundle,
+    changes: &[BundleChange],
+) -> Result<()> {
+    let needs_write = |kind: &str, key: &str| {
+        changes
+            .iter()
+            .any(|change| change.kind == kind && change.key == key && change.action != ChangeAction::Unchanged)
+    };
+
+    let mut tx = begin_scoped(&app_state.db_pool.pool(), county).await?;
+
+    for pair in &bundle.sync_pairs {
+        if !needs_write("sync_pair", &entry_key(&pair.county_id, &pair.name)) {
+            continue;
+        }
+        let now = chrono::Utc::now();
+        match SyncPairQueries::find_by_name(&mut tx, &pair.county_id, &pair.name).await? {
+            Some(mut row) => {
+                row.description = pair.description.clone();
+                row.source_system = pair.source_system.clone();
+                row.source_config = pair.source_config.clone();
+                row.target_system = pair.target_system.clone();
+                row.target_config = pair.target_config.clone();
+                row.is_active = pair.is_active;
+                row.sync_interval_minutes = pair.sync_interval_minutes;
+                row.sync_conflict_strategy = strategy_name(pair.sync_conflict_strategy);
+                row.updated_at = now;
+                row.updated_by = "config_import".to_string();
+                SyncPairQueries::update_config(&mut tx, &row).await?;
+            }
+            None => {
+                let row = SyncPairRow {
+                    id: Uuid::new_v4(),
+                    created_at: now,
+                    updated_at: now,
+                    name: pair.name.clone(),
+                    description: pair.description.clone(),
+                    source_system: pair.source_system.clone(),
+                    source_config: pair.source_config.clone(),
+                    target_system: pair.target_system.clone(),
+                    target_config: pair.target_config.clone(),
+                    county_id: pair.county_id.clone(),
+                    is_active: pair.is_active,
+                    sync_interval_minutes: pair.sync_interval_minutes,
+                    sync_conflict_strategy: strategy_name(pair.sync_conflict_strategy),
+                    last_sync_time: None,
+                    last_sync_status: None,
+                    created_by: "config_import".to_string(),
+                    updated_by: "config_import".to_string(),
+                    field_mappings: serde_json::json!([]),
+                    needs_attention: false,
+                    attention_reason: None,
+                };
+                SyncPairQueries::insert(&mut tx, &row).await?;
+            }
+        }
+    }
+
+    for template in &bundle.export_templates {
+        if needs_write("export_template", &entry_key(&template.county_id, &template.name)) {
+            ExportTemplateQueries::upsert(&mut tx, template).await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    for config in &bundle.counties {
+        if needs_write("county", &config.county_id) {
+            county_config::save_county_configuration(config).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn sync_pair_from_row(row: &SyncPairRow) -> CreateSyncPairRequest {
+    CreateSyncPairRequest {
+        name: row.name.clone(),
+        description: row.description.clone(),
+        source_system: row.source_system.clone(),
+        source_config: row.source_config.clone(),
+        target_system: row.target_system.clone(),
+        target_config: row.target_config.clone(),
+        county_id: row.county_id.clone(),
+        is_active: row.is_active,
+        sync_interval_minutes: row.sync_interval_minutes,
+        sync_conflict_strategy: parse_strategy(&row.sync_conflict_strategy),
+    }
+}
+
+/// Stored strategies use the `TARGET_WINS` spelling from the seed data
+pub(crate) fn strategy_name(strategy: SyncConflictStrategy) -> String {
+    match strategy {
+        SyncConflictStrategy::SourceWins => "SOURCE_WINS",
+        SyncConflictStrategy::TargetWins => "TARGET_WINS",
+        SyncConflictStrategy::NewerWins => "NEWER_WINS",
+        SyncConflictStrategy::Manual => "MANUAL",
+    }
+    .to_string()
+}
+
+pub(crate) fn parse_strategy(name: &str) -> SyncConflictStrategy {
+    match name.replace('_', "").to_uppercase().as_str() {
+        "SOURCEWINS" => SyncConflictStrategy::SourceWins,
+        "TARGETWINS" => SyncConflictStrategy::TargetWins,
+        "NEWERWINS" => SyncConflictStrategy::NewerWins,
+        _ => SyncConflictStrategy::Manual,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pair(interval: i32, password: &str) -> CreateSyncPairRequest {
+        CreateSyncPairRequest {
+            name: "cama-to-gis".to_string(),
+            description: None,
+            source_system: "pacs".to_string(),
+            source_config: json!({ "host": "cama01", "password": password }),
+            target_system: "postgis".to_string(),
+            target_config: json!({}),
+            county_id: "benton-wa".to_string(),
+            is_active: true,
+            sync_interval_minutes: interval,
+            sync_conflict_strategy: SyncConflictStrategy::SourceWins,
+        }
+    }
+
+    #[test]
+    fn test_redacted_bundle_round_trip_and_diff() {
+        let current = ConfigBundle { sync_pairs: vec![pair(60, "hunter2")], ..Default::default() };
+
+        let mut exported = current.clone();
+        redact_secrets(&mut exported);
+        assert_eq!(exported.sync_pairs[0].source_config["password"], REDACTED);
+
+        let yaml = render_bundle(&exported, BundleFormat::Yaml).unwrap();
+        let mut imported = parse_bundle(yaml.as_bytes(), BundleFormat::Yaml).unwrap();
+        imported.sync_pairs[0].sync_interval_minutes = 15;
+        restore_secrets(&mut imported, &current);
+
+        assert!(validate_bundle(&imported).is_empty());
+        assert!(unresolved_secrets(&imported).is_empty());
+        let changes = plan_changes(&imported, &current);
+        assert_eq!(changes[0].action, ChangeAction::Update);
+        assert_eq!(changes[0].changed_fields, vec!["sync_interval_minutes".to_string()]);
+        assert_eq!(parse_strategy(&strategy_name(SyncConflictStrategy::TargetWins)), SyncConflictStrategy::TargetWins);
+    }
+}
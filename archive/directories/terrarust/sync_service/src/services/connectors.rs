@@ -0,0 +1,490 @@
+//! Connections to the county systems a sync pair reads from and writes to
+//!
+//! The connector is chosen from the pair's source or target config: an explicit
+//! `connector` key wins, otherwise it is inferred from `connection_string`, `url`
+//! or `path`. Kinds other than the built-in ones are looked up among the
+//! connectors linked in through the connector SDK.
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sqlx::{Connection, PgConnection};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use terrafusion_common::http_client;
+use terrafusion_common::geo::{parse, FeatureReader, GeometryFormat};
+use terrafusion_common::{Error, Result};
+use terrafusion_connector_sdk::{self as sdk, infer_fields, ConnectorRegistry, SCHEMA_SAMPLE_SIZE};
+
+pub use terrafusion_connector_sdk::{FieldInfo, FieldType};
+
+/// Kinds handled by `Connector` itself; plugins cannot replace them
+const BUILTIN_KINDS: &[&str] = &["postgres", "postgresql", "http", "rest", "csv", "file", "geojson", "shapefile"];
+
+lazy_static! {
+    static ref PLUGINS: ConnectorRegistry = {
+        let registry = ConnectorRegistry::linked().expect("Conflicting connector registrations");
+        for kind in registry.kinds() {
+            if BUILTIN_KINDS.contains(&kind) {
+                log::warn!("Connector plugin `{}` is shadowed by the built-in connector", kind);
+            }
+        }
+        registry
+    };
+}
+
+/// Kinds provided by linked connector plugins
+pub fn plugin_kinds() -> Vec<&'static str> {
+    PLUGINS.kinds()
+}
+
+/// A configured source or target system
+#[derive(Debug, Clone)]
+pub enum Connector {
+    /// A PostgreSQL table or view
+    Postgres { url: String, schema: String, table: String },
+    /// A JSON HTTP API returning a list of records
+    Http { url: String, api_key: Option<String>, token: Option<String>, records_path: Option<String> },
+    /// A CSV file with a header row
+    Csv { path: PathBuf },
+    /// A GeoJSON FeatureCollection; each feature's properties plus `geometry`
+    GeoJson { path: PathBuf },
+    /// A Shapefile's `.shp`, with its `.dbf` attributes and `.shx` index alongside
+    Shapefile { path: PathBuf },
+    /// A connector from a linked SDK plugin
+    Plugin(PluginConnector),
+}
+
+/// Shared handle to a plugin connector
+#[derive(Clone)]
+pub struct PluginConnector(Arc<dyn sdk::Connector>);
+
+impl fmt::Debug for PluginConnector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PluginConnector").field(&self.0.kind()).finish()
+    }
+}
+
+impl Connector {
+    /// Build the connector described by a source or target config
+    pub fn from_config(config: &Value) -> Result<Self> {
+        let get = |key: &str| config.get(key).and_then(Value::as_str).map(str::to_string);
+        let location = get("connection_string").or_else(|| get("url")).or_else(|| get("base_url"));
+
+        let kind = match get("connector") {
+            Some(kind) => kind.to_lowercase(),
+            None => match (&location, get("path")) {
+                (Some(url), _) if url.starts_with("postgres://") || url.starts_with("postgresql://") => "postgres".to_string(),
+                (Some(url), _) if url.starts_with("http://") || url.starts_with("https://") => "http".to_string(),
+                (None, Some(path)) => match path.rsplit('.').next().map(str::to_lowercase).as_deref() {
+                    Some("geojson") | Some("json") => "geojson".to_string(),
+                    Some("shp") => "shapefile".to_string(),
+                    _ => "csv".to_string(),
+                },
+                _ => {
+                    return Err(Error::Validation(
+                        "Config needs a connector type or a postgres://, http(s):// or file location".to_string(),
+                    ))
+                }
+            },
+        };
+
+        let required = |value: Option<String>, key: &str| {
+            value.ok_or_else(|| Error::Validation(format!("{} connector requires `{}`", kind, key)))
+        };
+
+        match kind.as_str() {
+            "postgres" | "postgresql" => {
+                let schema = get("schema").unwrap_or_else(|| "public".to_string());
+                let table = required(get("table"), "table")?;
+                check_identifier(&schema)?;
+                check_identifier(&table)?;
+                Ok(Self::Postgres { url: required(location, "connection_string")?, schema, table })
+            }
+            "http" | "rest" => Ok(Self::Http {
+                url: required(location, "url")?,
+                api_key: get("api_key"),
+                token: get("token"),
+                records_path: get("records_path"),
+            }),
+            "csv" | "file" => Ok(Self::Csv { path: PathBuf::from(required(get("path"), "path")?) }),
+            "geojson" => Ok(Self::GeoJson { path: PathBuf::from(required(get("path"), "path")?) }),
+            "shapefile" => Ok(Self::Shapefile { path: PathBuf::from(required(get("path"), "path")?) }),
+            _ => Ok(Self::Plugin(PluginConnector(Arc::from(PLUGINS.create(config)?)))),
+        }
+    }
+
+    /// Short name for reports
+    pub fn kind(&self) -> &str {
+        match self {
+            Self::Postgres { .. } => "postgres",
+            Self::Http { .. } => "http",
+            Self::Csv { .. } => "csv",
+            Self::GeoJson { .. } => "geojson",
+            Self::Shapefile { .. } => "shapefile",
+            Self::Plugin(plugin) => plugin.0.kind(),
+        }
+    }
+
+    /// Read up to `limit` records
+    ///
+    /// Failures are reported by stage: `ExternalService` when the system cannot be
+    /// reached, `Authentication` when credentials are rejected, and `DataSync`
+    /// when records cannot be read once connected.
+    pub async fn sample(&self, limit: usize, timeout: Duration) -> Result<Vec<Value>> {
+        tokio::time::timeout(timeout, self.fetch(limit))
+            .await
+            .map_err(|_| Error::ExternalService(format!("No response within {}s", timeout.as_secs())))?
+    }
+
+    async fn fetch(&self, limit: usize) -> Result<Vec<Value>> {
+        match self {
+            Self::Postgres { url, schema, table } => {
+                let mut conn = PgConnection::connect(url).await.map_err(postgres_connect_error)?;
+                let sql = format!(
+                    r#"SELECT row_to_json(t) FROM "{}"."{}" t LIMIT $1"#,
+                    schema, table
+                );
+                let records = sqlx::query_scalar::<_, Value>(&sql)
+                    .bind(limit as i64)
+                    .fetch_all(&mut conn)
+                    .await
+                    .map_err(|e| Error::DataSync(format!("Failed to read {}.{}: {}", schema, table, e)))?;
+                let _ = conn.close().await;
+                Ok(records.into_iter().map(wkb_to_geojson).collect())
+            }
+            Self::Http { url, api_key, token, records_path } => {
+                let mut request = http_client::shared().get(url);
+                if let Some(api_key) = api_key {
+                    request = request.header("X-API-KEY", api_key);
+                }
+                if let Some(token) = token {
+                    request = request.bearer_auth(token);
+                }
+
+                let response = request
+                    .send()
+                    .await
+                    .map_err(|e| Error::ExternalService(format!("Failed to reach {}: {}", url, e)))?;
+                let status = response.status();
+                if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+                    return Err(Error::Authentication(format!("{} rejected the credentials ({})", url, status)));
+                }
+                if !status.is_success() {
+                    return Err(Error::ExternalService(format!("{} returned {}", url, status)));
+                }
+
+                let body: Value = response
+                    .json()
+                    .await
+                    .map_err(|e| Error::DataSync(format!("Response from {} is not JSON: {}", url, e)))?;
+                let records = extract_records(&body, records_path.as_deref())
+                    .ok_or_else(|| Error::DataSync(format!("No record list found in response from {}", url)))?;
+                Ok(records.iter().take(limit).cloned().collect())
+            }
+            Self::Csv { .. } | Self::GeoJson { .. } | Self::Shapefile { .. } => {
+                let connector = self.clone();
+                tokio::task::spawn_blocking(move || {
+                    let mut records = Vec::new();
+                    connector.scan_file(limit, |record| {
+                        records.push(record);
+                        Ok(())
+                    })?;
+                    Ok(records)
+                })
+                .await
+                .map_err(|e| Error::Internal(format!("File reader stopped: {}", e)))?
+            }
+            Self::Plugin(plugin) => plugin.0.sample(limit).await,
+        }
+    }
+
+    /// Read a file connector's records one at a time, up to `limit`, and
+    /// return how many were read. Blocking; run it off the async workers.
+    pub(crate) fn scan_file(&self, limit: usize, mut visit: impl FnMut(Value) -> Result<()>) -> Result<usize> {
+        let mut count = 0;
+        match self {
+            Self::Csv { path } => {
+                let mut reader = csv::Reader::from_path(path).map_err(|e| match e.kind() {
+                    csv::ErrorKind::Io(io) => open_error(path, io.kind(), &e),
+                    _ => Error::ExternalService(format!("Failed to open {}: {}", path.display(), e)),
+                })?;
+                let headers = reader
+                    .headers()
+                    .map_err(|e| Error::DataSync(format!("Failed to read header of {}: {}", path.display(), e)))?
+                    .clone();
+
+                for row in reader.records().take(limit) {
+                    let row = row.map_err(|e| Error::DataSync(format!("Invalid row in {}: {}", path.display(), e)))?;
+                    let record: Map<String, Value> = headers
+                        .iter()
+                        .zip(row.iter())
+                        .map(|(header, value)| (header.to_string(), Value::String(value.to_string())))
+                        .collect();
+                    visit(Value::Object(record))?;
+                    count += 1;
+                }
+            }
+            Self::GeoJson { path } => {
+                let file = std::fs::File::open(path).map_err(|e| open_error(path, e.kind(), &e))?;
+                for feature in FeatureReader::new(std::io::BufReader::new(file)).take(limit) {
+                    let feature = feature.map_err(|e| Error::DataSync(format!("{}: {}", path.display(), e)))?;
+                    let mut record = feature.properties.unwrap_or_default();
+                    let geometry = feature.geometry.map(|geometry| json!(geometry)).unwrap_or(Value::Null);
+                    record.insert("geometry".to_string(), geometry);
+                    visit(Value::Object(record))?;
+                    count += 1;
+                }
+            }
+            Self::Shapefile { path } => {
+                std::fs::metadata(path).map_err(|e| open_error(path, e.kind(), &e))?;
+                let mut reader = shapefile::Reader::from_path(path)
+                    .map_err(|e| Error::DataSync(format!("Failed to open {}: {}", path.display(), e)))?;
+                for shape in reader.iter_shapes_and_records().take(limit) {
+                    let (shape, attributes) =
+                        shape.map_err(|e| Error::DataSync(format!("Invalid shape in {}: {}", path.display(), e)))?;
+                    let mut record: Map<String, Value> = attributes
+                        .into_iter()
+                        .map(|(name, value)| (name, dbase_json(value)))
+                        .collect();
+                    // Null shapes carry attributes only
+                    let geometry = geo::Geometry::<f64>::try_from(shape)
+                        .map(|geometry| json!(geojson::Geometry::new(geojson::Value::from(&geometry))))
+                        .unwrap_or(Value::Null);
+                    record.insert("geometry".to_string(), geometry);
+                    visit(Value::Object(record))?;
+                    count += 1;
+                }
+            }
+            _ => return Err(Error::Internal(format!("{} is not a file connector", self.kind()))),
+        }
+        Ok(count)
+    }
+
+    /// List the fields this system exposes
+    ///
+    /// PostgreSQL columns come from `information_schema`; for APIs and files
+    /// the fields and types are inferred from a sample of records. Plugins
+    /// describe themselves.
+    pub async fn describe(&self, timeout: Duration) -> Result<Vec<FieldInfo>> {
+        match self {
+            Self::Postgres { url, schema, table } => {
+                tokio::time::timeout(timeout, describe_postgres(url, schema, table))
+                    .await
+                    .map_err(|_| Error::ExternalService(format!("No response within {}s", timeout.as_secs())))?
+            }
+            Self::Plugin(plugin) => {
+                tokio::time::timeout(timeout, plugin.0.describe())
+                    .await
+                    .map_err(|_| Error::ExternalService(format!("No response within {}s", timeout.as_secs())))?
+            }
+            _ => Ok(infer_fields(&self.sample(SCHEMA_SAMPLE_SIZE, timeout).await?)),
+        }
+    }
+
+    /// Connect, authenticate and read one record, reporting each step
+    pub async fn check(&self, timeout: Duration) -> Vec<ConnectivityCheck> {
+        let started = Instant::now();
+        let result = self.sample(1, timeout).await;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+
+        let step = |name: &'static str, status: CheckStatus, message: Option<String>| ConnectivityCheck {
+            name,
+            status,
+            message,
+            elapsed_ms: (status != CheckStatus::Skipped).then_some(elapsed_ms),
+        };
+        let skipped = |name| step(name, CheckStatus::Skipped, None);
+
+        match result {
+            Ok(records) => vec![
+                step("connect", CheckStatus::Pass, None),
+                step("authenticate", CheckStatus::Pass, None),
+                if records.is_empty() {
+                    step("sample", CheckStatus::Warn, Some("Connected, but no records were returned".to_string()))
+                } else {
+                    step("sample", CheckStatus::Pass, Some("Read 1 record".to_string()))
+                },
+            ],
+            Err(Error::ExternalService(message)) => vec![
+                step("connect", CheckStatus::Fail, Some(message)),
+                skipped("authenticate"),
+                skipped("sample"),
+            ],
+            Err(Error::Authentication(message)) => vec![
+                step("connect", CheckStatus::Pass, None),
+                step("authenticate", CheckStatus::Fail, Some(message)),
+                skipped("sample"),
+            ],
+            Err(e) => vec![
+                step("connect", CheckStatus::Pass, None),
+                step("authenticate", CheckStatus::Pass, None),
+                step("sample", CheckStatus::Fail, Some(e.to_string())),
+            ],
+        }
+    }
+}
+
+/// `row_to_json` renders PostGIS columns as hex EWKB; load them as GeoJSON
+fn wkb_to_geojson(mut record: Value) -> Value {
+    if let Value::Object(fields) = &mut record {
+        for value in fields.values_mut() {
+            let wkb = matches!(value, Value::String(text) if parse::detect_format(text) == Some(GeometryFormat::Wkb));
+            if wkb {
+                if let Ok(geometry) = parse::normalize_geometry(value) {
+                    *value = geometry;
+                }
+            }
+        }
+    }
+    record
+}
+
+/// A file that cannot be opened: unreadable files are a permissions problem
+fn open_error(path: &Path, kind: std::io::ErrorKind, error: &dyn fmt::Display) -> Error {
+    if kind == std::io::ErrorKind::PermissionDenied {
+        Error::Authentication(format!("No permission to read {}", path.display()))
+    } else {
+        Error::ExternalService(format!("Failed to open {}: {}", path.display(), error))
+    }
+}
+
+/// A Shapefile attribute as JSON; dates become `YYYY-MM-DD`
+fn dbase_json(value: shapefile::dbase::FieldValue) -> Value {
+    use shapefile::dbase::FieldValue;
+    match value {
+        FieldValue::Character(text) => text.map(Value::String).unwrap_or(Value::Null),
+        FieldValue::Memo(text) => Value::String(text),
+        FieldValue::Numeric(number) => number.map(|number| json!(number)).unwrap_or(Value::Null),
+        FieldValue::Float(number) => number.map(|number| json!(number)).unwrap_or(Value::Null),
+        FieldValue::Logical(flag) => flag.map(Value::Bool).unwrap_or(Value::Null),
+        FieldValue::Integer(number) => json!(number),
+        FieldValue::Double(number) | FieldValue::Currency(number) => json!(number),
+        FieldValue::Date(date) => date
+            .map(|date| Value::String(format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day())))
+            .unwrap_or(Value::Null),
+        other => Value::String(format!("{:?}", other)),
+    }
+}
+
+async fn describe_postgres(url: &str, schema: &str, table: &str) -> Result<Vec<FieldInfo>> {
+    let mut conn = PgConnection::connect(url).await.map_err(postgres_connect_error)?;
+    let columns: Vec<(String, String, String, String)> = sqlx::query_as(
+        r#"
+        SELECT column_name, data_type, udt_name, is_nullable
+        FROM information_schema.columns
+        WHERE table_schema = $1 AND table_name = $2
+        ORDER BY ordinal_position
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|e| Error::DataSync(format!("Failed to read columns of {}.{}: {}", schema, table, e)))?;
+    let _ = conn.close().await;
+
+    if columns.is_empty() {
+        return Err(Error::DataSync(format!("Table {}.{} not found or has no columns", schema, table)));
+    }
+
+    Ok(columns
+        .into_iter()
+        .map(|(name, data_type, udt_name, is_nullable)| FieldInfo {
+            name,
+            field_type: FieldType::from_postgres(&data_type, &udt_name),
+            nullable: is_nullable == "YES",
+        })
+        .collect())
+}
+
+/// Outcome of one connectivity step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+/// Result of one connectivity step against a source or target
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectivityCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+}
+
+/// Invalid password (28P01) and rejected role (28000) are credential problems
+pub(crate) fn postgres_connect_error(error: sqlx::Error) -> Error {
+    match &error {
+        sqlx::Error::Database(db) if matches!(db.code().as_deref(), Some("28P01") | Some("28000")) => {
+            Error::Authentication(db.message().to_string())
+        }
+        _ => Error::ExternalService(error.to_string()),
+    }
+}
+
+/// Schema and table names are interpolated into SQL, so only plain identifiers are allowed
+pub(crate) fn check_identifier(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 63
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with(|c: char| c.is_ascii_digit());
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::Validation(format!("Invalid table or schema name: {}", name)))
+    }
+}
+
+/// Find the record array in an API response
+///
+/// Uses `records_path` (dot-separated) when given, otherwise a bare array or
+/// the usual `data`/`items`/`records`/`results` envelopes.
+fn extract_records<'a>(body: &'a Value, records_path: Option<&str>) -> Option<&'a Vec<Value>> {
+    if let Some(path) = records_path {
+        return path.split('.').try_fold(body, |value, key| value.get(key))?.as_array();
+    }
+    body.as_array().or_else(|| {
+        ["data", "items", "records", "results"]
+            .iter()
+            .find_map(|key| body.get(*key).and_then(Value::as_array))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_connector_from_config() {
+        let connector = Connector::from_config(&json!({
+            "connection_string": "postgres://cama@db01/pacs",
+            "table": "parcels",
+        }))
+        .unwrap();
+        assert_eq!(connector.kind(), "postgres");
+
+        let connector = Connector::from_config(&json!({ "url": "https://gis.example.gov/api/parcels" })).unwrap();
+        assert_eq!(connector.kind(), "http");
+
+        for (path, kind) in [("levies.csv", "csv"), ("parcels.GeoJSON", "geojson"), ("roads.shp", "shapefile")] {
+            assert_eq!(Connector::from_config(&json!({ "path": path })).unwrap().kind(), kind);
+        }
+
+        assert!(Connector::from_config(&json!({ "connection_string": "postgres://db01/pacs", "table": "parcels; DROP" })).is_err());
+        assert!(Connector::from_config(&json!({ "connection_string": "example_source_connection" })).is_err());
+        assert!(Connector::from_config(&json!({ "connector": "arcgis", "url": "https://gis.example.gov" })).is_err());
+
+        let body = json!({ "result": { "features": [{ "id": 1 }] } });
+        assert_eq!(extract_records(&body, Some("result.features")).map(Vec::len), Some(1));
+    }
+}
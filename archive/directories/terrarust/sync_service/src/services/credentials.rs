@@ -0,0 +1,282 @@
+//! Connector credentials kept out of sync pair configs.
+//!
+//! A source or target config can name a stored credential instead of
+//! carrying passwords and keys itself:
+//!
+//! ```json
+//! { "url": "https://cama.example.gov/api/parcels", "credential_id": "6f1c..." }
+//! ```
+//!
+//! The credential's key/value pairs, such as `connection_string`, `api_key`,
+//! `token` or `password`, are merged into the config whenever a connector is
+//! built from it, replacing keys of the same name. Values are sealed with the
+//! county's payload key, whose key-encryption key lives in the secrets
+//! provider (see `terrafusion_common::encryption`), and the API never returns
+//! them. Rotating a credential changes it for every pair that references it.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sqlx::FromRow;
+use terrafusion_common::database::tenancy::with_county_filter;
+use terrafusion_common::database::RotatingPool;
+use terrafusion_common::encryption::PayloadCipher;
+use terrafusion_common::secrets::SecretsManager;
+use terrafusion_common::{Error, Result};
+use uuid::Uuid;
+
+/// Config key that references a stored credential
+pub const CREDENTIAL_ID_KEY: &str = "credential_id";
+
+/// A stored credential; its values stay sealed
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Credential {
+    pub id: Uuid,
+    pub county_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Keys the credential supplies
+    pub fields: Value,
+    #[serde(skip)]
+    pub secret: Value,
+    pub version: i32,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub rotated_at: Option<DateTime<Utc>>,
+    pub last_tested_at: Option<DateTime<Utc>>,
+    pub last_test_status: Option<String>,
+}
+
+/// Body for storing a credential
+#[derive(Debug, Deserialize)]
+pub struct NewCredential {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Config keys and their secret values
+    pub values: Map<String, Value>,
+    /// Filled in by the gateway with the signed-in user
+    #[serde(default)]
+    pub created_by: Option<String>,
+}
+
+/// A side of a sync pair that references a credential
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct CredentialUse {
+    pub sync_pair_id: Uuid,
+    pub sync_pair_name: String,
+    pub side: String,
+}
+
+/// The credential a config references, if any
+pub fn credential_id(config: &Value) -> Result<Option<Uuid>> {
+    match config.get(CREDENTIAL_ID_KEY) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(id)) => Uuid::parse_str(id)
+            .map(Some)
+            .map_err(|_| Error::Validation(format!("{} must be a UUID, not {}", CREDENTIAL_ID_KEY, id))),
+        Some(other) => Err(Error::Validation(format!("{} must be a UUID, not {}", CREDENTIAL_ID_KEY, other))),
+    }
+}
+
+/// The config with the credential's values in place of the reference
+pub fn merge_credential(config: &Value, values: &Map<String, Value>) -> Value {
+    let mut merged = config.as_object().cloned().unwrap_or_default();
+    merged.remove(CREDENTIAL_ID_KEY);
+    for (key, value) in values {
+        merged.insert(key.clone(), value.clone());
+    }
+    Value::Object(merged)
+}
+
+fn check_values(values: &Map<String, Value>) -> Result<()> {
+    if values.is_empty() {
+        return Err(Error::Validation("A credential needs at least one value".to_string()));
+    }
+    if values.contains_key(CREDENTIAL_ID_KEY) {
+        return Err(Error::Validation(format!("A credential cannot set {}", CREDENTIAL_ID_KEY)));
+    }
+    Ok(())
+}
+
+fn field_names(values: &Map<String, Value>) -> Value {
+    json!(values.keys().collect::<Vec<_>>())
+}
+
+/// Stores credentials sealed and resolves references to them
+#[derive(Clone)]
+pub struct CredentialVault {
+    pool: RotatingPool,
+    cipher: PayloadCipher,
+}
+
+impl CredentialVault {
+    pub fn new(pool: RotatingPool, cipher: PayloadCipher) -> Self {
+        Self { pool, cipher }
+    }
+
+    /// A vault sealing with county keys from the provider selected by
+    /// SECRETS_PROVIDER. Unlike diff payloads, credentials are always sealed.
+    pub fn from_env(pool: RotatingPool) -> Result<Self> {
+        let cipher = PayloadCipher::new(pool.clone(), SecretsManager::from_env()?);
+        Ok(Self::new(pool, cipher))
+    }
+
+    /// Credentials of one county, or of every county for platform admins
+    pub async fn list(&self, county_id: Option<&str>) -> Result<Vec<Credential>> {
+        let sql = with_county_filter("SELECT * FROM connector_credentials WHERE TRUE", county_id, 1);
+        let mut query = sqlx::query_as::<_, Credential>(&format!("{} ORDER BY county_id, name", sql));
+        if let Some(county_id) = county_id {
+            query = query.bind(county_id);
+        }
+        Ok(query.fetch_all(&self.pool.read_pool()).await?)
+    }
+
+    pub async fn get(&self, credential_id: Uuid) -> Result<Option<Credential>> {
+        Ok(sqlx::query_as::<_, Credential>("SELECT * FROM connector_credentials WHERE id = $1")
+            .bind(credential_id)
+            .fetch_optional(&self.pool.pool())
+            .await?)
+    }
+
+    /// Seal and store a credential for the county
+    pub async fn create(&self, county_id: &str, credential: &NewCredential) -> Result<Credential> {
+        check_values(&credential.values)?;
+        let secret = self.cipher.seal(county_id, &Value::Object(credential.values.clone())).await?;
+        sqlx::query_as::<_, Credential>(
+            r#"
+            INSERT INTO connector_credentials (id, county_id, name, description, fields, secret, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(county_id)
+        .bind(&credential.name)
+        .bind(&credential.description)
+        .bind(field_names(&credential.values))
+        .bind(secret)
+        .bind(credential.created_by.as_deref().unwrap_or("api_user"))
+        .fetch_one(&self.pool.pool())
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.constraint() == Some("connector_credentials_county_id_name_key") => {
+                Error::Conflict(format!("The county already has a credential named {}", credential.name))
+            }
+            e => e.into(),
+        })
+    }
+
+    /// Replace a credential's values. Pairs referencing it use the new ones
+    /// from their next connection.
+    pub async fn rotate(&self, credential: &Credential, values: &Map<String, Value>) -> Result<Credential> {
+        check_values(values)?;
+        let secret = self.cipher.seal(&credential.county_id, &Value::Object(values.clone())).await?;
+        let rotated = sqlx::query_as::<_, Credential>(
+            r#"
+            UPDATE connector_credentials
+            SET fields = $2, secret = $3, version = version + 1, rotated_at = NOW(), updated_at = NOW(),
+                last_tested_at = NULL, last_test_status = NULL
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(credential.id)
+        .bind(field_names(values))
+        .bind(secret)
+        .fetch_one(&self.pool.pool())
+        .await?;
+        log::info!("Credential {} of county {} rotated to version {}", rotated.name, rotated.county_id, rotated.version);
+        Ok(rotated)
+    }
+
+    /// Delete a credential no pair references
+    pub async fn delete(&self, credential: &Credential) -> Result<()> {
+        let uses = self.usage(credential.id).await?;
+        if !uses.is_empty() {
+            let names: Vec<&str> = uses.iter().map(|used| used.sync_pair_name.as_str()).collect();
+            return Err(Error::Conflict(format!(
+                "Credential {} is used by sync pairs {}",
+                credential.name,
+                names.join(", ")
+            )));
+        }
+        sqlx::query("DELETE FROM connector_credentials WHERE id = $1")
+            .bind(credential.id)
+            .execute(&self.pool.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// Sync pairs whose source or target config references the credential
+    pub async fn usage(&self, credential_id: Uuid) -> Result<Vec<CredentialUse>> {
+        Ok(sqlx::query_as::<_, CredentialUse>(
+            r#"
+            SELECT id AS sync_pair_id, name AS sync_pair_name, 'source' AS side
+            FROM sync_pairs WHERE source_config->>'credential_id' = $1
+            UNION ALL
+            SELECT id, name, 'target'
+            FROM sync_pairs WHERE target_config->>'credential_id' = $1
+            ORDER BY sync_pair_name, side
+            "#,
+        )
+        .bind(credential_id.to_string())
+        .fetch_all(&self.pool.read_pool())
+        .await?)
+    }
+
+    /// Remember the outcome of the last connectivity test
+    pub async fn record_test(&self, credential_id: Uuid, status: &str) -> Result<()> {
+        sqlx::query("UPDATE connector_credentials SET last_tested_at = NOW(), last_test_status = $2 WHERE id = $1")
+            .bind(credential_id)
+            .bind(status)
+            .execute(&self.pool.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// The credential's values, unsealed
+    pub async fn values(&self, credential: &Credential) -> Result<Map<String, Value>> {
+        match self.cipher.open(&credential.county_id, credential.secret.clone()).await? {
+            Value::Object(values) => Ok(values),
+            _ => Err(Error::Internal(format!("Credential {} does not hold key/value pairs", credential.id))),
+        }
+    }
+
+    /// A source or target config of `county_id` ready for a connector: a
+    /// referenced credential is merged in, other configs are returned as they are
+    pub async fn resolve(&self, county_id: &str, config: &Value) -> Result<Value> {
+        let Some(credential_id) = credential_id(config)? else {
+            return Ok(config.clone());
+        };
+        let credential = self
+            .get(credential_id)
+            .await?
+            .filter(|credential| credential.county_id == county_id)
+            .ok_or_else(|| Error::NotFound(format!("Credential {} not found in county {}", credential_id, county_id)))?;
+        Ok(merge_credential(config, &self.values(&credential).await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_credential() {
+        let id = Uuid::new_v4();
+        let config = json!({ "url": "https://cama.example.gov/api", "credential_id": id.to_string(), "token": "stale" });
+        assert_eq!(credential_id(&config).unwrap(), Some(id));
+        assert_eq!(credential_id(&json!({ "url": "https://cama.example.gov/api" })).unwrap(), None);
+        assert!(credential_id(&json!({ "credential_id": "cama-prod" })).is_err());
+
+        let values = json!({ "token": "s3cret" }).as_object().cloned().unwrap();
+        assert_eq!(
+            merge_credential(&config, &values),
+            json!({ "url": "https://cama.example.gov/api", "token": "s3cret" })
+        );
+        assert!(check_values(&Map::new()).is_err());
+        assert!(check_values(&json!({ "credential_id": "x" }).as_object().cloned().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Aggregate sync and export activity for the dashboard
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use terrafusion_common::tenancy::ALL_COUNTIES;
+
+use crate::models::database::StatusCountRow;
+
+/// Counts by status over one period
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StatusBreakdown {
+    pub total: i64,
+    pub pending: i64,
+    pub running: i64,
+    pub completed: i64,
+    pub failed: i64,
+    pub canceled: i64,
+    /// Failed as a share of finished (completed + failed); `None` when nothing finished
+    pub failure_rate: Option<f64>,
+    /// Raw counts, including statuses not broken out above
+    pub by_status: BTreeMap<String, i64>,
+}
+
+impl StatusBreakdown {
+    pub fn from_rows(rows: Vec<StatusCountRow>) -> Self {
+        let mut breakdown = Self::default();
+        for row in rows {
+            breakdown.total += row.count;
+            match row.status.as_str() {
+                "PENDING" | "QUEUED" => breakdown.pending += row.count,
+                "RUNNING" | "PROCESSING" => breakdown.running += row.count,
+                "COMPLETED" => breakdown.completed += row.count,
+                "FAILED" => breakdown.failed += row.count,
+                "CANCELED" | "CANCELLED" => breakdown.canceled += row.count,
+                _ => {}
+            }
+            *breakdown.by_status.entry(row.status).or_default() += row.count;
+        }
+
+        let finished = breakdown.completed + breakdown.failed;
+        breakdown.failure_rate = (finished > 0)
+            .then(|| (breakdown.failed as f64 / finished as f64 * 10_000.0).round() / 10_000.0);
+        breakdown
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationStats {
+    pub last_24h: StatusBreakdown,
+    pub last_7d: StatusBreakdown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportStats {
+    pub last_7d: StatusBreakdown,
+    /// Jobs still queued or processing, of any age
+    pub pending: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardStats {
+    /// County the figures cover; `None` for the whole platform
+    pub county_id: Option<String>,
+    pub generated_at: DateTime<Utc>,
+    pub active_sync_pairs: i64,
+    pub operations: OperationStats,
+    pub exports: ExportStats,
+}
+
+/// Short-lived cache of computed stats, one entry per county scope.
+///
+/// The dashboard polls frequently and the aggregates scan a week of rows,
+/// so results are reused for `ttl`.
+#[derive(Clone)]
+pub struct DashboardCache {
+    ttl: Duration,
+    entries: Arc<RwLock<HashMap<String, (Instant, Arc<DashboardStats>)>>>,
+}
+
+impl DashboardCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, entries: Arc::new(RwLock::new(HashMap::new())) }
+    }
+
+    /// Fresh cached stats for the scope, if any
+    pub fn get(&self, county_id: Option<&str>) -> Option<Arc<DashboardStats>> {
+        let entries = self.entries.read().expect("dashboard cache lock poisoned");
+        entries
+            .get(county_id.unwrap_or(ALL_COUNTIES))
+            .filter(|(computed_at, _)| computed_at.elapsed() < self.ttl)
+            .map(|(_, stats)| stats.clone())
+    }
+
+    pub fn insert(&self, stats: DashboardStats) -> Arc<DashboardStats> {
+        let key = stats.county_id.clone().unwrap_or_else(|| ALL_COUNTIES.to_string());
+        let stats = Arc::new(stats);
+        let mut entries = self.entries.write().expect("dashboard cache lock poisoned");
+        entries.retain(|_, (computed_at, _)| computed_at.elapsed() < self.ttl);
+        entries.insert(key, (Instant::now(), stats.clone()));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(status: &str, count: i64) -> StatusCountRow {
+        StatusCountRow { status: status.to_string(), count }
+    }
+
+    #[test]
+    fn test_status_breakdown() {
+        let breakdown = StatusBreakdown::from_rows(vec![
+            row("COMPLETED", 7),
+            row("FAILED", 1),
+            row("RUNNING", 2),
+            row("CANCELLED", 1),
+        ]);
+        assert_eq!(breakdown.total, 11);
+        assert_eq!(breakdown.canceled, 1);
+        assert_eq!(breakdown.failure_rate, Some(0.125));
+
+        assert_eq!(StatusBreakdown::from_rows(vec![row("PENDING", 3)]).failure_rate, None);
+    }
+}
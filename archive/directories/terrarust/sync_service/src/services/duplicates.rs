@@ -0,0 +1,145 @@
+//! What happens when a sync pair is started while it already has an
+//! operation pending or running.
+//!
+//! A pair picks a policy with `on_duplicate` in its target config:
+//!
+//! - `reject` (the default) refuses the new operation with a 409 naming
+//!   the existing one.
+//! - `queue` accepts it and runs it once the current operation ends. Only
+//!   one operation waits at a time; a second request is refused, since
+//!   the waiting one will pick up the same changes.
+//! - `supersede` cancels the existing operations and runs the new one as
+//!   soon as they stop, which is at the end of the batch they are loading.
+//!
+//! An administrator can queue past a `reject` policy by passing
+//! `"allow_duplicate": true` in the operation's custom parameters.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use terrafusion_common::{Error, Result};
+use uuid::Uuid;
+
+use crate::models::database::ActiveOperationRow;
+
+/// Parameter with which an administrator starts an operation behind one
+/// that is already running
+pub const ALLOW_DUPLICATE_PARAMETER: &str = "allow_duplicate";
+
+/// Whether operation parameters ask to run despite an active operation
+pub fn duplicate_allowed(parameters: Option<&Value>) -> bool {
+    parameters
+        .and_then(|parameters| parameters.get(ALLOW_DUPLICATE_PARAMETER))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// A pair's policy for overlapping operations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    #[default]
+    Reject,
+    Queue,
+    Supersede,
+}
+
+impl DuplicatePolicy {
+    pub fn from_target_config(config: &Value) -> Result<Self> {
+        match config.get("on_duplicate") {
+            None | Some(Value::Null) => Ok(Self::default()),
+            Some(policy) => serde_json::from_value(policy.clone()).map_err(|_| {
+                Error::Validation("on_duplicate must be one of reject, queue or supersede".to_string())
+            }),
+        }
+    }
+}
+
+/// How a new operation gets past the pair's active ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+    /// Nothing else is active
+    Start,
+    /// Run after the active operation releases the pair
+    Queue,
+    /// Cancel these operations, then run
+    Supersede(Vec<Uuid>),
+}
+
+impl Admission {
+    /// Whether the new operation may have to wait for the pair's lock
+    pub fn waits(&self) -> bool {
+        !matches!(self, Self::Start)
+    }
+}
+
+/// Decide whether a new operation of `pair_name` may start, given the
+/// operations already active, oldest first
+pub fn admit(policy: DuplicatePolicy, active: &[ActiveOperationRow], allow_duplicate: bool, pair_name: &str) -> Result<Admission> {
+    let Some(current) = active.first() else {
+        return Ok(Admission::Start);
+    };
+    if allow_duplicate {
+        return Ok(Admission::Queue);
+    }
+    match policy {
+        DuplicatePolicy::Reject => Err(conflict(pair_name, current, "")),
+        DuplicatePolicy::Queue => match active.iter().find(|operation| operation.status.eq_ignore_ascii_case("PENDING")) {
+            Some(waiting) => Err(conflict(pair_name, waiting, ", which is already waiting to run")),
+            None => Ok(Admission::Queue),
+        },
+        DuplicatePolicy::Supersede => Ok(Admission::Supersede(active.iter().map(|operation| operation.id).collect())),
+    }
+}
+
+fn conflict(pair_name: &str, existing: &ActiveOperationRow, reason: &str) -> Error {
+    Error::Conflict(format!(
+        "Sync pair {} already has operation {} {} since {}, started by {}{}. Wait for it to finish or cancel it; an administrator can pass \"{}\": true to queue behind it",
+        pair_name,
+        existing.id,
+        existing.status.to_uppercase(),
+        existing.start_time.to_rfc3339(),
+        existing.initiated_by,
+        reason,
+        ALLOW_DUPLICATE_PARAMETER
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn operation(status: &str) -> ActiveOperationRow {
+        ActiveOperationRow {
+            id: Uuid::new_v4(),
+            status: status.to_string(),
+            start_time: Utc::now(),
+            initiated_by: "scheduler".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_admission() {
+        assert_eq!(DuplicatePolicy::from_target_config(&json!({})).unwrap(), DuplicatePolicy::Reject);
+        assert_eq!(
+            DuplicatePolicy::from_target_config(&json!({ "on_duplicate": "supersede" })).unwrap(),
+            DuplicatePolicy::Supersede
+        );
+        assert!(DuplicatePolicy::from_target_config(&json!({ "on_duplicate": "ignore" })).is_err());
+        assert!(duplicate_allowed(Some(&json!({ "allow_duplicate": true }))));
+
+        let running = operation("RUNNING");
+        assert_eq!(admit(DuplicatePolicy::Reject, &[], false, "CAMA").unwrap(), Admission::Start);
+        let error = admit(DuplicatePolicy::Reject, &[running.clone()], false, "CAMA").unwrap_err();
+        assert!(matches!(&error, Error::Conflict(message) if message.contains(&running.id.to_string())));
+        assert_eq!(admit(DuplicatePolicy::Reject, &[running.clone()], true, "CAMA").unwrap(), Admission::Queue);
+
+        assert_eq!(admit(DuplicatePolicy::Queue, &[running.clone()], false, "CAMA").unwrap(), Admission::Queue);
+        assert!(admit(DuplicatePolicy::Queue, &[running.clone(), operation("PENDING")], false, "CAMA").is_err());
+        assert_eq!(
+            admit(DuplicatePolicy::Supersede, &[running.clone()], false, "CAMA").unwrap(),
+            Admission::Supersede(vec![running.id])
+        );
+    }
+}
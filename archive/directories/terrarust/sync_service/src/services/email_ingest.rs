@@ -0,0 +1,534 @@
+//! Ingestion of spreadsheets that districts deliver by email.
+//!
+//! A sync pair whose source is a file can have that file refreshed from a
+//! mailbox instead of a share. It opts in with an `email` object next to the
+//! source's `path`:
+//!
+//! ```json
+//! "path": "/var/lib/terrafusion/inbox/district-7/levies.csv",
+//! "email": {
+//!     "host": "imap.county.gov",
+//!     "username": "gis-inbox@county.gov",
+//!     "password": "...",
+//!     "allowed_senders": ["clerk@district7.k12.wa.us", "@fire3.example.org"],
+//!     "attachment_patterns": ["levies*.csv"]
+//! }
+//! ```
+//!
+//! Every EMAIL_POLL_INTERVAL_SECONDS the runner reads the unseen messages
+//! of each such pair over IMAPS. Messages from senders not on the allow-list
+//! are rejected; an entry starting with `@` allows a whole domain. Matching
+//! attachments are kept under `archive_dir` (an `archive` directory next to
+//! the file by default), and the first one of the newest accepted message
+//! replaces the file the connector reads. One sync operation then loads it.
+//! Each message is recorded in the audit log with its sender, subject and
+//! the files kept, and marked as seen so it is read once.
+//!
+//! The From header is trusted as delivered, so the mailbox should only
+//! accept mail that passed the server's SPF and DKIM checks.
+
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Utc;
+use mailparse::{MailHeaderMap, ParsedMail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use terrafusion_common::database::RotatingPool;
+use terrafusion_common::{Error, Result};
+use uuid::Uuid;
+
+use crate::models::database::{SyncPairQueries, SyncPairRow};
+use super::sync_engine::SyncEngine;
+
+/// Messages read per pair and poll; the rest wait for the next poll
+const MAX_MESSAGES_PER_POLL: usize = 20;
+
+/// Longest a mailbox session may take before the poll gives up on it
+const MAILBOX_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Mailbox settings from the `email` object of a source config
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailIngestSettings {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    #[serde(default = "default_mailbox")]
+    pub mailbox: String,
+    /// Addresses, or `@domain` for every address of a domain
+    pub allowed_senders: Vec<String>,
+    /// File name patterns with `*` and `?`, matched case-insensitively
+    #[serde(default = "default_patterns")]
+    pub attachment_patterns: Vec<String>,
+    #[serde(default)]
+    pub archive_dir: Option<PathBuf>,
+}
+
+fn default_port() -> u16 {
+    993
+}
+
+fn default_mailbox() -> String {
+    "INBOX".to_string()
+}
+
+fn default_patterns() -> Vec<String> {
+    vec!["*.csv".to_string()]
+}
+
+impl EmailIngestSettings {
+    pub fn from_source_config(source_config: &Value) -> Result<Option<Self>> {
+        let Some(email) = source_config.get("email").filter(|email| !email.is_null()) else {
+            return Ok(None);
+        };
+        let settings: Self = serde_json::from_value(email.clone())
+            .map_err(|e| Error::Validation(format!("Invalid email settings: {}", e)))?;
+
+        if source_config.get("path").and_then(Value::as_str).is_none() {
+            return Err(Error::Validation("email ingestion requires a file source with a `path`".to_string()));
+        }
+        if settings.allowed_senders.iter().all(|sender| sender.trim().is_empty()) {
+            return Err(Error::Validation("email.allowed_senders must list at least one sender".to_string()));
+        }
+        if settings.attachment_patterns.is_empty() {
+            return Err(Error::Validation("email.attachment_patterns must not be empty".to_string()));
+        }
+        Ok(Some(settings))
+    }
+
+    /// Whether mail from `address` is accepted
+    pub fn allows(&self, address: &str) -> bool {
+        let address = address.trim().to_lowercase();
+        self.allowed_senders.iter().any(|allowed| {
+            let allowed = allowed.trim().to_lowercase();
+            match allowed.strip_prefix('@') {
+                Some(domain) => address.rsplit_once('@').map_or(false, |(_, host)| host == domain),
+                None => !allowed.is_empty() && address == allowed,
+            }
+        })
+    }
+
+    fn wants(&self, file_name: &str) -> bool {
+        self.attachment_patterns.iter().any(|pattern| matches_pattern(pattern, file_name))
+    }
+
+    fn archive_dir(&self, path: &Path) -> PathBuf {
+        self.archive_dir
+            .clone()
+            .unwrap_or_else(|| path.parent().unwrap_or_else(|| Path::new(".")).join("archive"))
+    }
+}
+
+/// Glob match with `*` for any run of characters and `?` for one, ignoring case
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match pattern.split_first() {
+            None => name.is_empty(),
+            Some(('*', rest)) => (0..=name.len()).any(|skip| matches(rest, &name[skip..])),
+            Some(('?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+            Some((c, rest)) => name.first() == Some(c) && matches(rest, &name[1..]),
+        }
+    }
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    matches(&pattern, &name)
+}
+
+/// The parts of a message ingestion cares about
+#[derive(Debug)]
+pub struct IncomingMessage {
+    pub message_id: Option<String>,
+    pub sender: Option<String>,
+    pub subject: Option<String>,
+    /// Attachments matching the patterns, as (file name, content)
+    pub attachments: Vec<(String, Vec<u8>)>,
+}
+
+impl IncomingMessage {
+    pub fn parse(raw: &[u8], settings: &EmailIngestSettings) -> Result<Self> {
+        let mail = mailparse::parse_mail(raw).map_err(|e| Error::DataSync(format!("Unreadable message: {}", e)))?;
+        let sender = mail
+            .headers
+            .get_first_value("From")
+            .and_then(|from| mailparse::addrparse(&from).ok())
+            .and_then(|addresses| {
+                addresses.iter().find_map(|address| match address {
+                    mailparse::MailAddr::Single(single) => Some(single.addr.clone()),
+                    mailparse::MailAddr::Group(group) => group.addrs.first().map(|single| single.addr.clone()),
+                })
+            });
+
+        let mut attachments = Vec::new();
+        collect_attachments(&mail, settings, &mut attachments)?;
+        Ok(Self {
+            message_id: mail.headers.get_first_value("Message-ID"),
+            sender,
+            subject: mail.headers.get_first_value("Subject"),
+            attachments,
+        })
+    }
+}
+
+fn collect_attachments(part: &ParsedMail<'_>, settings: &EmailIngestSettings, found: &mut Vec<(String, Vec<u8>)>) -> Result<()> {
+    let disposition = part.get_content_disposition();
+    let name = disposition
+        .params
+        .get("filename")
+        .or_else(|| part.ctype.params.get("name"))
+        .cloned();
+    // Multipart containers can carry a name too; only leaf parts hold files
+    if let Some(name) = name.filter(|_| part.subparts.is_empty()) {
+        if settings.wants(&name) {
+            let body = part
+                .get_body_raw()
+                .map_err(|e| Error::DataSync(format!("Unreadable attachment {}: {}", name, e)))?;
+            found.push((name, body));
+        }
+    }
+    for subpart in &part.subparts {
+        collect_attachments(subpart, settings, found)?;
+    }
+    Ok(())
+}
+
+/// An attachment kept in the archive directory
+#[derive(Debug, Clone, Serialize)]
+struct StoredFile {
+    name: String,
+    stored_as: String,
+    size: usize,
+    sha256: String,
+}
+
+/// What became of one message, as recorded in the audit log
+#[derive(Debug, Serialize)]
+struct Ingestion {
+    message_id: Option<String>,
+    sender: Option<String>,
+    subject: Option<String>,
+    /// `ingested`, `ignored` (nothing attached matched), `rejected` or `failed`
+    status: &'static str,
+    files: Vec<StoredFile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Ingestion {
+    fn severity(&self) -> &'static str {
+        match self.status {
+            "ingested" | "ignored" => "INFO",
+            "rejected" => "WARN",
+            _ => "ERROR",
+        }
+    }
+
+    fn description(&self, pair: &SyncPairRow) -> String {
+        let sender = self.sender.as_deref().unwrap_or("unknown sender");
+        match self.status {
+            "ingested" => format!("Ingested {} file(s) from {} for sync pair {}", self.files.len(), sender, pair.name),
+            "ignored" => format!("Email from {} for sync pair {} had no matching attachment", sender, pair.name),
+            "rejected" => format!("Rejected email from {} for sync pair {}: sender not allowed", sender, pair.name),
+            _ => format!("Could not ingest email from {} for sync pair {}", sender, pair.name),
+        }
+    }
+}
+
+/// Unseen messages, read without marking them, as (UID, raw message)
+fn fetch_unseen(settings: &EmailIngestSettings) -> Result<Vec<(u32, Vec<u8>)>> {
+    let mut session = login(settings)?;
+    let mut uids: Vec<u32> = session.uid_search("UNSEEN").map_err(imap_error)?.into_iter().collect();
+    uids.sort_unstable();
+    uids.truncate(MAX_MESSAGES_PER_POLL);
+
+    let mut messages = Vec::new();
+    if !uids.is_empty() {
+        let set = uids.iter().map(u32::to_string).collect::<Vec<_>>().join(",");
+        for fetch in session.uid_fetch(&set, "BODY.PEEK[]").map_err(imap_error)?.iter() {
+            if let (Some(uid), Some(body)) = (fetch.uid, fetch.body()) {
+                messages.push((uid, body.to_vec()));
+            }
+        }
+    }
+    let _ = session.logout();
+    messages.sort_by_key(|(uid, _)| *uid);
+    Ok(messages)
+}
+
+fn mark_seen(settings: &EmailIngestSettings, uids: &[u32]) -> Result<()> {
+    let mut session = login(settings)?;
+    let set = uids.iter().map(u32::to_string).collect::<Vec<_>>().join(",");
+    session.uid_store(&set, "+FLAGS (\\Seen)").map_err(imap_error)?;
+    let _ = session.logout();
+    Ok(())
+}
+
+fn login(settings: &EmailIngestSettings) -> Result<imap::Session<native_tls::TlsStream<TcpStream>>> {
+    let tls = native_tls::TlsConnector::new().map_err(|e| Error::Internal(format!("TLS setup failed: {}", e)))?;
+    let client = imap::connect((settings.host.as_str(), settings.port), &settings.host, &tls)
+        .map_err(|e| Error::ExternalService(format!("Failed to reach {}: {}", settings.host, e)))?;
+    let mut session = client
+        .login(&settings.username, &settings.password)
+        .map_err(|(e, _)| Error::Authentication(format!("{} rejected the mailbox credentials: {}", settings.host, e)))?;
+    session.select(&settings.mailbox).map_err(imap_error)?;
+    Ok(session)
+}
+
+fn imap_error(error: imap::Error) -> Error {
+    Error::ExternalService(format!("IMAP: {}", error))
+}
+
+/// Run blocking mailbox work off the async workers, with a deadline
+async fn mailbox<T, F>(settings: &EmailIngestSettings, work: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&EmailIngestSettings) -> Result<T> + Send + 'static,
+{
+    let settings = settings.clone();
+    let task = tokio::task::spawn_blocking(move || work(&settings));
+    tokio::time::timeout(MAILBOX_TIMEOUT, task)
+        .await
+        .map_err(|_| Error::ExternalService(format!("Mailbox did not answer within {}s", MAILBOX_TIMEOUT.as_secs())))?
+        .map_err(|e| Error::Internal(format!("Mailbox task failed: {}", e)))?
+}
+
+/// Keeps file sources with an `email` object fed from their mailboxes
+pub struct EmailIngestRunner {
+    db_pool: RotatingPool,
+    engine: SyncEngine,
+}
+
+impl EmailIngestRunner {
+    pub fn new(db_pool: RotatingPool, engine: SyncEngine) -> Self {
+        Self { db_pool, engine }
+    }
+
+    /// Poll every email-fed pair each `interval`
+    pub fn spawn(self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.poll().await {
+                    log::error!("Failed to list email-fed sync pairs: {}", e);
+                }
+            }
+        })
+    }
+
+    async fn poll(&self) -> Result<()> {
+        for pair in SyncPairQueries::list_email_ingest(&self.db_pool.pool()).await? {
+            if self.engine.in_maintenance(&pair.county_id) {
+                continue;
+            }
+            if let Err(e) = self.poll_pair(&pair).await {
+                log::error!("Email ingestion for sync pair {} failed: {}", pair.name, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn poll_pair(&self, pair: &SyncPairRow) -> Result<()> {
+        let Some(settings) = EmailIngestSettings::from_source_config(&pair.source_config)? else {
+            return Ok(());
+        };
+        let path = PathBuf::from(pair.source_config.get("path").and_then(Value::as_str).unwrap_or_default());
+        let messages = mailbox(&settings, fetch_unseen).await?;
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        let mut handled = Vec::new();
+        let mut ingestions = Vec::new();
+        let mut latest = None;
+        for (uid, raw) in messages {
+            match ingest(&settings, &path, uid, &raw).await {
+                Ok((ingestion, file)) => {
+                    latest = file.or(latest);
+                    ingestions.push(ingestion);
+                    handled.push(uid);
+                }
+                // Left unseen, so the message is read again on the next poll
+                Err(e) => log::error!("Failed to store email {} for sync pair {}: {}", uid, pair.name, e),
+            }
+        }
+        if !handled.is_empty() {
+            mailbox(&settings, move |settings| mark_seen(settings, &handled)).await?;
+        }
+
+        let mut operation_id = None;
+        if let Some(file) = latest {
+            replace_file(&file, &path).await?;
+            let initiated_by = ingestions
+                .iter()
+                .rev()
+                .find(|ingestion| ingestion.status == "ingested")
+                .and_then(|ingestion| ingestion.sender.as_deref())
+                .map_or_else(|| "email".to_string(), |sender| format!("email:{}", sender));
+            match self.engine.start_sync_operation(pair.id, initiated_by, None).await {
+                Ok(id) => {
+                    log::info!("Email delivery started sync operation {} of pair {}", id, pair.name);
+                    operation_id = Some(id);
+                }
+                Err(e) => log::warn!("Stored emailed file for sync pair {} but could not start a sync: {}", pair.name, e),
+            }
+        }
+
+        for ingestion in &ingestions {
+            let operation_id = operation_id.filter(|_| ingestion.status == "ingested");
+            if let Err(e) = audit(&self.db_pool.pool(), pair, ingestion, operation_id).await {
+                log::error!("Failed to audit email ingestion for sync pair {}: {}", pair.name, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check and store one message; also returns the archived file that should
+/// become the pair's source file
+async fn ingest(
+    settings: &EmailIngestSettings,
+    path: &Path,
+    uid: u32,
+    raw: &[u8],
+) -> Result<(Ingestion, Option<PathBuf>)> {
+    let message = match IncomingMessage::parse(raw, settings) {
+        Ok(message) => message,
+        Err(e) => {
+            let ingestion = Ingestion {
+                message_id: None,
+                sender: None,
+                subject: None,
+                status: "failed",
+                files: Vec::new(),
+                error: Some(e.to_string()),
+            };
+            return Ok((ingestion, None));
+        }
+    };
+
+    let mut ingestion = Ingestion {
+        message_id: message.message_id,
+        sender: message.sender,
+        subject: message.subject,
+        status: "ingested",
+        files: Vec::new(),
+        error: None,
+    };
+    if !ingestion.sender.as_deref().map_or(false, |sender| settings.allows(sender)) {
+        ingestion.status = "rejected";
+        return Ok((ingestion, None));
+    }
+    if message.attachments.is_empty() {
+        ingestion.status = "ignored";
+        return Ok((ingestion, None));
+    }
+
+    let archive = settings.archive_dir(path);
+    tokio::fs::create_dir_all(&archive).await?;
+    let mut first = None;
+    for (name, content) in &message.attachments {
+        let stored = archive.join(format!("{}-{}-{}", Utc::now().format("%Y%m%dT%H%M%S"), uid, safe_file_name(name)));
+        tokio::fs::write(&stored, content).await?;
+        ingestion.files.push(StoredFile {
+            name: name.clone(),
+            stored_as: stored.display().to_string(),
+            size: content.len(),
+            sha256: format!("{:x}", Sha256::digest(content)),
+        });
+        first.get_or_insert(stored);
+    }
+    Ok((ingestion, first))
+}
+
+/// Swap the connector's file for `source` in one step, so a sync never reads
+/// a half-written file
+async fn replace_file(source: &Path, path: &Path) -> Result<()> {
+    let staging = path.with_extension("ingest.tmp");
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::copy(source, &staging).await?;
+    tokio::fs::rename(&staging, path).await?;
+    Ok(())
+}
+
+pub(crate) fn safe_file_name(name: &str) -> String {
+    let name = Path::new(name).file_name().and_then(|name| name.to_str()).unwrap_or("attachment");
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect()
+}
+
+async fn audit(pool: &sqlx::PgPool, pair: &SyncPairRow, ingestion: &Ingestion, operation_id: Option<Uuid>) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (
+            id, event_type, resource_type, resource_id, description, username, county_id,
+            new_state, operation_id, severity, created_at
+        ) VALUES ($1, $2, 'sync_pair', $3, $4, $5, $6, $7, $8, $9, $10)
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(format!("email_{}", ingestion.status))
+    .bind(pair.id.to_string())
+    .bind(ingestion.description(pair))
+    .bind(ingestion.sender.as_deref())
+    .bind(&pair.county_id)
+    .bind(serde_json::to_value(ingestion)?)
+    .bind(operation_id)
+    .bind(ingestion.severity())
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_settings_and_messages() {
+        assert_eq!(EmailIngestSettings::from_source_config(&json!({ "path": "levies.csv" })).unwrap().map(|_| ()), None);
+        let config = json!({
+            "path": "/var/lib/terrafusion/inbox/levies.csv",
+            "email": {
+                "host": "imap.county.gov",
+                "username": "gis-inbox@county.gov",
+                "password": "secret",
+                "allowed_senders": ["clerk@district7.k12.wa.us", "@fire3.example.org"],
+            }
+        });
+        let settings = EmailIngestSettings::from_source_config(&config).unwrap().unwrap();
+        assert_eq!(settings.port, 993);
+        assert!(settings.allows("Clerk@District7.k12.wa.us"));
+        assert!(settings.allows("chief@fire3.example.org"));
+        assert!(!settings.allows("chief@notfire3.example.org"));
+        assert!(EmailIngestSettings::from_source_config(&json!({ "email": config["email"] })).is_err());
+
+        assert!(matches_pattern("levies*.CSV", "Levies 2025.csv"));
+        assert!(!matches_pattern("*.csv", "levies.xlsx"));
+
+        let raw = concat!(
+            "From: District Clerk <clerk@district7.k12.wa.us>\r\n",
+            "Subject: Levies\r\n",
+            "Message-ID: <1@district7>\r\n",
+            "Content-Type: multipart/mixed; boundary=b\r\n\r\n",
+            "--b\r\nContent-Type: text/plain\r\n\r\nAttached.\r\n",
+            "--b\r\nContent-Type: text/csv\r\nContent-Disposition: attachment; filename=\"levies.csv\"\r\n\r\n",
+            "parcel,amount\r\n1,100\r\n",
+            "--b--\r\n",
+        );
+        let message = IncomingMessage::parse(raw.as_bytes(), &settings).unwrap();
+        assert_eq!(message.sender.as_deref(), Some("clerk@district7.k12.wa.us"));
+        assert_eq!(message.attachments.len(), 1);
+        assert_eq!(message.attachments[0].0, "levies.csv");
+        assert_eq!(safe_file_name("../levies 2025.csv"), "levies_2025.csv");
+    }
+}
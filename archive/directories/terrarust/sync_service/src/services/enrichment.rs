@@ -0,0 +1,443 @@
+//! Enrichment of source records before they are compared and loaded
+//!
+//! Addresses typed into different county systems rarely agree on spelling
+//! ("123 North Main Street" vs "123 N MAIN ST"), which costs entity
+//! matching a lot of otherwise obvious matches. A sync pair opts in with an
+//! `enrichment` object in its source config:
+//!
+//! ```json
+//! "enrichment": {
+//!     "address_fields": ["situs_address", "mailing_address"],
+//!     "normalize": "usps",
+//!     "geocoder": { "provider": "census" },
+//!     "latitude_field": "latitude",
+//!     "longitude_field": "longitude"
+//! }
+//! ```
+//!
+//! `normalize: "usps"` rewrites each address field following USPS
+//! Publication 28: upper case, no punctuation, abbreviated directionals,
+//! street suffixes and unit designators. The optional geocoder looks up the
+//! first address field and attaches the coordinates to records that do not
+//! have them yet. Providers are `census` (the US Census Bureau geocoder,
+//! no key needed) and `http`, any JSON service given by a `url` with an
+//! `{address}` placeholder, `latitude_path`/`longitude_path` into the
+//! response and an optional `api_key`. A failed lookup leaves the record
+//! without coordinates; it never fails the sync.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use terrafusion_common::http_client::{self, HttpClient};
+use terrafusion_common::{Error, Result};
+
+const CENSUS_GEOCODER_URL: &str = "https://geocoding.geo.census.gov/geocoder/locations/onelineaddress";
+
+/// USPS directional abbreviations
+const DIRECTIONALS: &[(&str, &str)] = &[
+    ("NORTH", "N"),
+    ("SOUTH", "S"),
+    ("EAST", "E"),
+    ("WEST", "W"),
+    ("NORTHEAST", "NE"),
+    ("NORTHWEST", "NW"),
+    ("SOUTHEAST", "SE"),
+    ("SOUTHWEST", "SW"),
+];
+
+/// Common USPS street suffix abbreviations
+const SUFFIXES: &[(&str, &str)] = &[
+    ("ALLEY", "ALY"),
+    ("AVENUE", "AVE"),
+    ("AV", "AVE"),
+    ("BOULEVARD", "BLVD"),
+    ("CIRCLE", "CIR"),
+    ("COURT", "CT"),
+    ("COVE", "CV"),
+    ("CREEK", "CRK"),
+    ("CROSSING", "XING"),
+    ("DRIVE", "DR"),
+    ("EXPRESSWAY", "EXPY"),
+    ("HIGHWAY", "HWY"),
+    ("HOLLOW", "HOLW"),
+    ("LANE", "LN"),
+    ("LOOP", "LOOP"),
+    ("PARKWAY", "PKWY"),
+    ("PLACE", "PL"),
+    ("PLAZA", "PLZ"),
+    ("POINT", "PT"),
+    ("ROAD", "RD"),
+    ("ROUTE", "RTE"),
+    ("SQUARE", "SQ"),
+    ("STREET", "ST"),
+    ("STR", "ST"),
+    ("TERRACE", "TER"),
+    ("TRAIL", "TRL"),
+    ("VIEW", "VW"),
+    ("WAY", "WAY"),
+];
+
+/// USPS secondary unit designators
+const UNIT_DESIGNATORS: &[(&str, &str)] = &[
+    ("APARTMENT", "APT"),
+    ("APT", "APT"),
+    ("BUILDING", "BLDG"),
+    ("BLDG", "BLDG"),
+    ("FLOOR", "FL"),
+    ("FL", "FL"),
+    ("LOT", "LOT"),
+    ("ROOM", "RM"),
+    ("RM", "RM"),
+    ("SPACE", "SPC"),
+    ("SPC", "SPC"),
+    ("SUITE", "STE"),
+    ("STE", "STE"),
+    ("TRAILER", "TRLR"),
+    ("UNIT", "UNIT"),
+];
+
+/// The abbreviation for a token that is a table entry, spelled out or
+/// already abbreviated
+fn lookup(table: &[(&str, &'static str)], token: &str) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(name, abbreviation)| *name == token || *abbreviation == token)
+        .map(|(_, abbreviation)| *abbreviation)
+}
+
+/// Standardize a street address the way USPS Publication 28 writes it
+///
+/// Suffixes and directionals are only abbreviated in their own position, so
+/// "123 North Court Street" becomes "123 N COURT ST", not "123 N CT ST".
+pub fn normalize_address(address: &str) -> String {
+    let cleaned: String = address
+        .to_uppercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '#' | '-' | '/') { c } else { ' ' })
+        .collect();
+    let mut tokens: Vec<String> = cleaned.split_whitespace().map(str::to_string).collect();
+
+    // Street part, then the secondary unit ("APT 4", "# 12")
+    let unit_start = tokens
+        .iter()
+        .enumerate()
+        .skip(1)
+        .find(|(_, token)| token.starts_with('#') || lookup(UNIT_DESIGNATORS, token).is_some())
+        .map_or(tokens.len(), |(index, _)| index);
+    let street = &mut tokens[..unit_start];
+
+    let mut name_start = usize::from(street.first().is_some_and(|token| token.chars().any(|c| c.is_ascii_digit())));
+    let mut name_end = street.len();
+    // "900 EAST ST" is East Street: a directional followed by nothing but
+    // a suffix is the street name
+    let named_after = |start: usize| match &street[start..name_end] {
+        [only] => lookup(SUFFIXES, only).is_none(),
+        rest => !rest.is_empty(),
+    };
+    if name_end > name_start + 1 && named_after(name_start + 1) {
+        if let Some(direction) = lookup(DIRECTIONALS, &street[name_start]) {
+            street[name_start] = direction.to_string();
+            name_start += 1;
+        }
+    }
+    if name_end > name_start + 1 {
+        if let Some(direction) = lookup(DIRECTIONALS, &street[name_end - 1]) {
+            street[name_end - 1] = direction.to_string();
+            name_end -= 1;
+        }
+    }
+    if name_end > name_start + 1 {
+        if let Some(suffix) = lookup(SUFFIXES, &street[name_end - 1]) {
+            street[name_end - 1] = suffix.to_string();
+        }
+    }
+
+    if let Some(designator) = tokens.get(unit_start).and_then(|token| lookup(UNIT_DESIGNATORS, token)) {
+        tokens[unit_start] = designator.to_string();
+    }
+    tokens.join(" ")
+}
+
+/// Where coordinates are looked up
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "provider", rename_all = "lowercase", deny_unknown_fields)]
+pub enum GeocoderSettings {
+    Census {
+        #[serde(default = "default_census_benchmark")]
+        benchmark: String,
+    },
+    Http {
+        /// Request URL with an `{address}` placeholder
+        url: String,
+        latitude_path: String,
+        longitude_path: String,
+        #[serde(default)]
+        api_key: Option<String>,
+    },
+}
+
+fn default_census_benchmark() -> String {
+    "Public_AR_Current".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnrichmentSettings {
+    pub address_fields: Vec<String>,
+    /// Normalization rules; `usps` is the only set
+    #[serde(default)]
+    pub normalize: Option<String>,
+    #[serde(default)]
+    pub geocoder: Option<GeocoderSettings>,
+    #[serde(default = "default_latitude_field")]
+    pub latitude_field: String,
+    #[serde(default = "default_longitude_field")]
+    pub longitude_field: String,
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+fn default_latitude_field() -> String {
+    "latitude".to_string()
+}
+
+fn default_longitude_field() -> String {
+    "longitude".to_string()
+}
+
+fn default_timeout_seconds() -> u64 {
+    10
+}
+
+impl EnrichmentSettings {
+    pub fn from_source_config(source_config: &Value) -> Result<Option<Self>> {
+        let Some(enrichment) = source_config.get("enrichment").filter(|value| !value.is_null()) else {
+            return Ok(None);
+        };
+        let settings: Self = serde_json::from_value(enrichment.clone())
+            .map_err(|e| Error::Validation(format!("Invalid enrichment settings: {}", e)))?;
+
+        if settings.address_fields.is_empty() {
+            return Err(Error::Validation("enrichment.address_fields must name at least one field".to_string()));
+        }
+        if let Some(rules) = settings.normalize.as_deref().filter(|rules| *rules != "usps") {
+            return Err(Error::Validation(format!("Unknown enrichment.normalize rules: {} (use usps)", rules)));
+        }
+        if let Some(GeocoderSettings::Http { url, .. }) = &settings.geocoder {
+            if !url.contains("{address}") {
+                return Err(Error::Validation("enrichment.geocoder.url needs an {address} placeholder".to_string()));
+            }
+        }
+        Ok(Some(settings))
+    }
+}
+
+/// Counts reported after enriching a batch of records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EnrichmentSummary {
+    pub normalized: usize,
+    pub geocoded: usize,
+    pub geocode_failures: usize,
+}
+
+/// The enrichment stage configured for one sync pair
+pub struct Enricher {
+    settings: EnrichmentSettings,
+    client: HttpClient,
+    /// Lookups already made this run, by normalized address
+    cache: HashMap<String, Option<(f64, f64)>>,
+}
+
+impl Enricher {
+    pub fn new(settings: EnrichmentSettings) -> Self {
+        Self { settings, client: http_client::shared().clone(), cache: HashMap::new() }
+    }
+
+    /// The enricher for a sync pair, or `None` when it has no `enrichment`
+    pub fn from_source_config(source_config: &Value) -> Result<Option<Self>> {
+        Ok(EnrichmentSettings::from_source_config(source_config)?.map(Self::new))
+    }
+
+    /// Enrich records in place
+    pub async fn enrich(&mut self, records: &mut [Value]) -> EnrichmentSummary {
+        let mut summary = EnrichmentSummary::default();
+        for record in records.iter_mut() {
+            let Value::Object(fields) = record else { continue };
+            if self.settings.normalize.is_some() {
+                summary.normalized += self.normalize(fields);
+            }
+            if self.settings.geocoder.is_some() {
+                match self.geocode(fields).await {
+                    Some(true) => summary.geocoded += 1,
+                    Some(false) => summary.geocode_failures += 1,
+                    None => {}
+                }
+            }
+        }
+        summary
+    }
+
+    /// Normalize the address fields; returns how many changed
+    fn normalize(&self, fields: &mut Map<String, Value>) -> usize {
+        let mut changed = 0;
+        for name in &self.settings.address_fields {
+            if let Some(Value::String(address)) = fields.get_mut(name) {
+                let normalized = normalize_address(address);
+                if normalized != *address {
+                    *address = normalized;
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Attach coordinates to a record that has an address but none yet.
+    /// `None` when no lookup was needed, otherwise whether it succeeded.
+    async fn geocode(&mut self, fields: &mut Map<String, Value>) -> Option<bool> {
+        let has_coordinates = [&self.settings.latitude_field, &self.settings.longitude_field]
+            .iter()
+            .all(|field| fields.get(*field).is_some_and(|value| !value.is_null()));
+        if has_coordinates {
+            return None;
+        }
+        let address = fields
+            .get(&self.settings.address_fields[0])
+            .and_then(Value::as_str)
+            .map(normalize_address)
+            .filter(|address| !address.is_empty())?;
+
+        let location = match self.cache.get(&address) {
+            Some(location) => *location,
+            None => {
+                let location = self.lookup(&address).await.unwrap_or_else(|e| {
+                    log::warn!("Geocoding failed for {}: {}", address, e);
+                    None
+                });
+                self.cache.insert(address, location);
+                location
+            }
+        };
+        let Some((latitude, longitude)) = location else {
+            return Some(false);
+        };
+        fields.insert(self.settings.latitude_field.clone(), Value::from(latitude));
+        fields.insert(self.settings.longitude_field.clone(), Value::from(longitude));
+        Some(true)
+    }
+
+    async fn lookup(&self, address: &str) -> Result<Option<(f64, f64)>> {
+        let request = match self.settings.geocoder.as_ref() {
+            Some(GeocoderSettings::Census { benchmark }) => self
+                .client
+                .get(CENSUS_GEOCODER_URL)
+                .query(&[("address", address), ("benchmark", benchmark), ("format", "json")]),
+            Some(GeocoderSettings::Http { url, api_key, .. }) => {
+                let encoded: String = url::form_urlencoded::byte_serialize(address.as_bytes()).collect();
+                let request = self.client.get(url.replace("{address}", &encoded));
+                match api_key {
+                    Some(api_key) => request.header("X-API-KEY", api_key),
+                    None => request,
+                }
+            }
+            None => return Ok(None),
+        };
+        let request = request.timeout(Duration::from_secs(self.settings.timeout_seconds.max(1)));
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| Error::ExternalService(format!("Geocoder unreachable: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(Error::ExternalService(format!("Geocoder returned {}", response.status())));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| Error::ExternalService(format!("Geocoder response is not JSON: {}", e)))?;
+        Ok(self.location(&body))
+    }
+
+    /// Latitude and longitude from a geocoder response
+    fn location(&self, body: &Value) -> Option<(f64, f64)> {
+        match self.settings.geocoder.as_ref()? {
+            GeocoderSettings::Census { .. } => {
+                let coordinates = json_path(body, "result.addressMatches.0.coordinates")?;
+                Some((number(coordinates.get("y")?)?, number(coordinates.get("x")?)?))
+            }
+            GeocoderSettings::Http { latitude_path, longitude_path, .. } => Some((
+                number(json_path(body, latitude_path)?)?,
+                number(json_path(body, longitude_path)?)?,
+            )),
+        }
+    }
+}
+
+/// Follow a dotted path; numeric segments index arrays
+fn json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| match value {
+        Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => value.get(key),
+    })
+}
+
+/// A coordinate given as a number or numeric string
+fn number(value: &Value) -> Option<f64> {
+    value.as_f64().or_else(|| value.as_str()?.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_address() {
+        assert_eq!(normalize_address("123 North Main Street"), "123 N MAIN ST");
+        assert_eq!(normalize_address("123 north court street, apartment 4b"), "123 N COURT ST APT 4B");
+        assert_eq!(normalize_address("  4500 W. Canal Drive Suite 200 "), "4500 W CANAL DR STE 200");
+        assert_eq!(normalize_address("77 Avenue North #12"), "77 AVENUE N #12");
+        assert_eq!(normalize_address("900 East St"), "900 EAST ST");
+        assert_eq!(normalize_address("PO Box 1234"), "PO BOX 1234");
+    }
+
+    #[test]
+    fn test_settings_from_source_config() {
+        assert_eq!(EnrichmentSettings::from_source_config(&json!({ "table": "parcels" })).unwrap(), None);
+
+        let settings = EnrichmentSettings::from_source_config(&json!({
+            "enrichment": { "address_fields": ["situs"], "normalize": "usps", "geocoder": { "provider": "census" } }
+        }))
+        .unwrap()
+        .unwrap();
+        assert_eq!(settings.geocoder, Some(GeocoderSettings::Census { benchmark: "Public_AR_Current".to_string() }));
+        assert_eq!(settings.latitude_field, "latitude");
+
+        for invalid in [
+            json!({ "address_fields": [] }),
+            json!({ "address_fields": ["situs"], "normalize": "canada_post" }),
+            json!({ "address_fields": ["situs"], "geocoder": { "provider": "http", "url": "https://geo.local/find", "latitude_path": "lat", "longitude_path": "lon" } }),
+        ] {
+            assert!(EnrichmentSettings::from_source_config(&json!({ "enrichment": invalid })).is_err());
+        }
+    }
+
+    #[test]
+    fn test_geocoder_responses() {
+        let census = Enricher::new(EnrichmentSettings {
+            address_fields: vec!["situs".to_string()],
+            normalize: None,
+            geocoder: Some(GeocoderSettings::Census { benchmark: default_census_benchmark() }),
+            latitude_field: default_latitude_field(),
+            longitude_field: default_longitude_field(),
+            timeout_seconds: 1,
+        });
+        let body = json!({ "result": { "addressMatches": [{ "coordinates": { "x": -119.28, "y": 46.21 } }] } });
+        assert_eq!(census.location(&body), Some((46.21, -119.28)));
+        assert_eq!(census.location(&json!({ "result": { "addressMatches": [] } })), None);
+
+        assert_eq!(json_path(&json!({ "results": [{ "lat": "46.2" }] }), "results.0.lat").and_then(number), Some(46.2));
+    }
+}
@@ -0,0 +1,98 @@
+//! How one entity (e.g. a parcel) changed across sync operations
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::models::database::EntityHistoryRow;
+
+/// One attribute that differed between target and source
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    /// Value in the target before the sync
+    pub before: Option<Value>,
+    /// Value from the source
+    pub after: Option<Value>,
+}
+
+/// One entry in an entity's timeline
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityHistoryEntry {
+    pub diff_id: Uuid,
+    pub sync_operation_id: Uuid,
+    pub sync_pair_id: Uuid,
+    pub sync_pair_name: String,
+    pub county_id: String,
+    pub change_type: String,
+    pub sync_status: String,
+    pub changed_by: String,
+    pub changed_at: DateTime<Utc>,
+    pub changes: Vec<FieldChange>,
+    pub diff_details: Option<Value>,
+    pub error_message: Option<String>,
+}
+
+impl From<EntityHistoryRow> for EntityHistoryEntry {
+    fn from(row: EntityHistoryRow) -> Self {
+        let changes = changed_fields(row.target_data.as_ref(), row.source_data.as_ref());
+        Self {
+            diff_id: row.diff_id,
+            sync_operation_id: row.sync_operation_id,
+            sync_pair_id: row.sync_pair_id,
+            sync_pair_name: row.sync_pair_name,
+            county_id: row.county_id,
+            change_type: row.change_type,
+            sync_status: row.sync_status,
+            changed_by: row.initiated_by,
+            changed_at: row.created_at,
+            changes,
+            diff_details: row.diff_details,
+            error_message: row.error_message,
+        }
+    }
+}
+
+/// Top-level attributes whose values differ, sorted by name.
+///
+/// A missing side (insert or delete) reports every attribute of the other side.
+pub fn changed_fields(before: Option<&Value>, after: Option<&Value>) -> Vec<FieldChange> {
+    let empty = serde_json::Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter(|field| before.get(*field) != after.get(*field))
+        .map(|field| FieldChange {
+            field: field.clone(),
+            before: before.get(field).cloned(),
+            after: after.get(field).cloned(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_changed_fields() {
+        let before = json!({"parcel_id": "1-23", "owner": "Smith", "acres": 2.5});
+        let after = json!({"parcel_id": "1-23", "owner": "Jones", "zoning": "R1"});
+
+        let changes = changed_fields(Some(&before), Some(&after));
+        let fields: Vec<_> = changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["acres", "owner", "zoning"]);
+        assert_eq!(changes[1].before, Some(json!("Smith")));
+        assert_eq!(changes[2].before, None);
+
+        assert_eq!(changed_fields(None, Some(&after)).len(), 3);
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use terrafusion_common::Result;
+use terrafusion_common::database::RotatingPool;
+use terrafusion_common::database::migrations::checksum;
+use terrafusion_common::database::similarity::SimilarityStore;
+use terrafusion_common::models::sync::SyncPair;
+use super::narrator::NarratorClient;
+
+/// Texts sent to NarratorAI per embedding request
+const EMBED_CHUNK_SIZE: usize = 100;
+
+/// Finds likely duplicates of new source records among existing target
+/// records by comparing NarratorAI embeddings stored in pgvector.
+#[derive(Clone)]
+pub struct EntityMatcher {
+    narrator: NarratorClient,
+    db_pool: RotatingPool,
+    model: String,
+    threshold: f64,
+}
+
+impl EntityMatcher {
+    pub fn new(narrator: NarratorClient, db_pool: RotatingPool, model: String, threshold: f64) -> Self {
+        Self {
+            narrator,
+            db_pool,
+            model,
+            threshold,
+        }
+    }
+
+    // Fetch the pool per call so credential rotation is honored
+    fn store(&self) -> SimilarityStore {
+        SimilarityStore::new(self.db_pool.pool(), self.model.clone())
+    }
+
+    /// Match unkeyed source records to target records.
+    ///
+    /// Returns a map from source key to the matched target key. Target
+    /// embeddings are refreshed first, skipping records whose text is
+    /// unchanged since they were last embedded.
+    pub async fn match_records(
+        &self,
+        sync_pair: &SyncPair,
+        targets: &HashMap<String, &serde_json::Value>,
+        unmatched: &[(String, &serde_json::Value)],
+    ) -> Result<HashMap<String, String>> {
+        let store = self.store();
+        if unmatched.is_empty() || !store.is_available().await? {
+            return Ok(HashMap::new());
+        }
+
+        let entity_type = entity_type(sync_pair);
+        self.index_targets(&store, sync_pair, &entity_type, targets).await?;
+
+        let texts: Vec<String> = unmatched.iter().map(|(_, record)| describe_record(record)).collect();
+        let embeddings = self.embed(&texts).await?;
+
+        let mut matches = HashMap::new();
+        for ((source_key, _), embedding) in unmatched.iter().zip(embeddings) {
+            let similar = store
+                .find_similar(&entity_type, &sync_pair.county_id, &embedding, self.threshold, 1)
+                .await?;
+
+            if let Some(best) = similar.into_iter().next() {
+                log::info!(
+                    "Source record {} matches existing {} {} (similarity {:.3})",
+                    source_key, entity_type, best.entity_id, best.similarity
+                );
+                matches.insert(source_key.clone(), best.entity_id);
+            }
+        }
+
+        Ok(matches)
+    }
+
+    async fn index_targets(
+        &self,
+        store: &SimilarityStore,
+        sync_pair: &SyncPair,
+        entity_type: &str,
+        targets: &HashMap<String, &serde_json::Value>,
+    ) -> Result<()> {
+        let mut stale = Vec::new();
+        for (key, record) in targets {
+            let text = describe_record(record);
+            let hash = checksum(&text);
+            if store.content_hash(entity_type, key, &sync_pair.county_id).await?.as_deref() != Some(hash.as_str()) {
+                stale.push((key.clone(), text, hash));
+            }
+        }
+
+        if stale.is_empty() {
+            return Ok(());
+        }
+
+        log::debug!("Embedding {} changed {} records", stale.len(), entity_type);
+
+        let texts: Vec<String> = stale.iter().map(|(_, text, _)| text.clone()).collect();
+        let embeddings = self.embed(&texts).await?;
+
+        for ((key, _, hash), embedding) in stale.iter().zip(embeddings) {
+            store
+                .upsert(entity_type, key, &sync_pair.county_id, &embedding, hash, None)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for chunk in texts.chunks(EMBED_CHUNK_SIZE) {
+            embeddings.extend(self.narrator.embed(chunk).await?);
+        }
+        Ok(embeddings)
+    }
+}
+
+/// Entity type recorded with embeddings; set `entity_type` in the target config
+pub(crate) fn entity_type(sync_pair: &SyncPair) -> String {
+    sync_pair.target_config
+        .get("entity_type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("parcel")
+        .to_string()
+}
+
+/// Stable text form of a record's scalar fields, used as embedding input
+pub fn describe_record(record: &serde_json::Value) -> String {
+    match record.as_object() {
+        Some(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+
+            keys.into_iter()
+                .filter_map(|key| match &fields[key] {
+                    serde_json::Value::String(value) => Some(format!("{}: {}", key, value)),
+                    serde_json::Value::Number(value) => Some(format!("{}: {}", key, value)),
+                    serde_json::Value::Bool(value) => Some(format!("{}: {}", key, value)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("; ")
+        }
+        None => record.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_record_is_sorted_and_skips_nested() {
+        let record = serde_json::json!({
+            "situs": "123 Main St",
+            "acres": 0.25,
+            "geometry": { "type": "Point" }
+        });
+
+        assert_eq!(describe_record(&record), "acres: 0.25; situs: 123 Main St");
+    }
+}
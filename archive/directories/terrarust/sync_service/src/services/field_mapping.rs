@@ -0,0 +1,419 @@
+//! Field mappings: drafting them from source and target schemas, and applying them to records
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use terrafusion_common::geo::normalize_geometry;
+use terrafusion_common::masking::{MaskStrategy, Masker};
+
+use super::connectors::{FieldInfo, FieldType};
+
+/// Proposals below this confidence are left for the user to map by hand
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.6;
+
+/// Weight of name similarity in the confidence score; the rest is type compatibility
+const NAME_WEIGHT: f64 = 0.75;
+
+/// Name fragments that county systems abbreviate differently
+const SYNONYMS: &[(&str, &str)] = &[
+    ("id", "identifier"),
+    ("num", "number"),
+    ("no", "number"),
+    ("nbr", "number"),
+    ("addr", "address"),
+    ("apn", "parcel"),
+    ("pin", "parcel"),
+    ("acct", "account"),
+    ("amt", "amount"),
+    ("val", "value"),
+    ("dt", "date"),
+    ("yr", "year"),
+    ("desc", "description"),
+    ("sqft", "area"),
+    ("geom", "geometry"),
+    ("shape", "geometry"),
+];
+
+/// One proposed `field_mappings` entry
+#[derive(Debug, Clone, Serialize)]
+pub struct ProposedMapping {
+    pub source_field: String,
+    pub target_field: String,
+    pub source_type: FieldType,
+    pub target_type: FieldType,
+    /// 0.0 - 1.0; how sure the proposal is
+    pub confidence: f64,
+    /// Conversion needed when the types differ, e.g. `to_number`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transformation: Option<&'static str>,
+}
+
+/// Draft mappings plus the fields that could not be matched
+#[derive(Debug, Clone, Serialize)]
+pub struct MappingProposal {
+    pub field_mappings: Vec<ProposedMapping>,
+    pub unmapped_source_fields: Vec<String>,
+    pub unmapped_target_fields: Vec<String>,
+}
+
+/// Pair source and target fields by name similarity and type compatibility
+///
+/// Each field is used at most once; the strongest candidate pairs are chosen first.
+pub fn propose_mappings(source: &[FieldInfo], target: &[FieldInfo], min_confidence: f64) -> MappingProposal {
+    let mut candidates = Vec::new();
+    for source_field in source {
+        for target_field in target {
+            let Some(type_score) = type_compatibility(source_field.field_type, target_field.field_type) else {
+                continue;
+            };
+            let confidence = NAME_WEIGHT * name_similarity(&source_field.name, &target_field.name)
+                + (1.0 - NAME_WEIGHT) * type_score;
+            if confidence >= min_confidence {
+                candidates.push((confidence, source_field, target_field));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut used_sources = HashSet::new();
+    let mut used_targets = HashSet::new();
+    let mut field_mappings = Vec::new();
+    for (confidence, source_field, target_field) in candidates {
+        if used_sources.contains(&source_field.name) || used_targets.contains(&target_field.name) {
+            continue;
+        }
+        used_sources.insert(source_field.name.clone());
+        used_targets.insert(target_field.name.clone());
+        field_mappings.push(ProposedMapping {
+            source_field: source_field.name.clone(),
+            target_field: target_field.name.clone(),
+            source_type: source_field.field_type,
+            target_type: target_field.field_type,
+            confidence: (confidence * 100.0).round() / 100.0,
+            transformation: transformation(source_field.field_type, target_field.field_type),
+        });
+    }
+
+    // Present mappings in target order, which is how the UI lists them
+    field_mappings.sort_by_key(|mapping| target.iter().position(|field| field.name == mapping.target_field));
+
+    MappingProposal {
+        field_mappings,
+        unmapped_source_fields: source
+            .iter()
+            .filter(|field| !used_sources.contains(&field.name))
+            .map(|field| field.name.clone())
+            .collect(),
+        unmapped_target_fields: target
+            .iter()
+            .filter(|field| !used_targets.contains(&field.name))
+            .map(|field| field.name.clone())
+            .collect(),
+    }
+}
+
+/// How well a source type fits a target type, or `None` if it cannot be mapped
+fn type_compatibility(source: FieldType, target: FieldType) -> Option<f64> {
+    use FieldType::*;
+    match (source, target) {
+        _ if source == target => Some(1.0),
+        (Unknown, _) | (_, Unknown) => Some(0.5),
+        (Integer, Number) => Some(0.9),
+        (Number, Integer) => Some(0.6),
+        (Date, Timestamp) | (Timestamp, Date) => Some(0.8),
+        (_, String) => Some(0.7),
+        (String, _) => Some(0.4),
+        (Json, Geometry) | (Geometry, Json) => Some(0.6),
+        _ => None,
+    }
+}
+
+fn transformation(source: FieldType, target: FieldType) -> Option<&'static str> {
+    use FieldType::*;
+    match (source, target) {
+        _ if source == target => None,
+        (Unknown, _) | (_, Unknown) => None,
+        (_, String) => Some("to_string"),
+        (_, Integer) => Some("to_integer"),
+        (_, Number) => Some("to_number"),
+        (_, Boolean) => Some("to_boolean"),
+        (_, Date) => Some("to_date"),
+        (_, Timestamp) => Some("to_timestamp"),
+        (_, Geometry) => Some("to_geometry"),
+        (_, Json) => Some("to_json"),
+    }
+}
+
+/// A saved `field_mappings` entry as applied to records
+#[derive(Debug, Clone, Deserialize)]
+pub struct MappingRule {
+    /// Field in the source record; dots reach into nested objects
+    pub source_field: String,
+    pub target_field: String,
+    #[serde(default)]
+    pub transformation: Option<String>,
+    /// Used when the source value is missing or null
+    #[serde(default)]
+    pub default_value: Option<Value>,
+    #[serde(default)]
+    pub is_required: Option<bool>,
+}
+
+/// Build a target record from a source record, collecting per-field errors
+///
+/// A field whose transformation fails is left null so the rest of the record
+/// can still be inspected.
+pub fn apply_mappings(record: &Value, mappings: &[MappingRule]) -> (Value, Vec<String>) {
+    let mut target = Map::new();
+    let mut errors = Vec::new();
+
+    for mapping in mappings {
+        let value = mapping
+            .source_field
+            .split('.')
+            .try_fold(record, |value, key| value.get(key))
+            .filter(|value| !value.is_null())
+            .cloned()
+            .or_else(|| mapping.default_value.clone());
+
+        let value = match (value, mapping.transformation.as_deref()) {
+            (None, _) => {
+                if mapping.is_required.unwrap_or(false) {
+                    errors.push(format!("{}: required source field {} is missing", mapping.target_field, mapping.source_field));
+                }
+                Value::Null
+            }
+            (Some(value), None) => value,
+            (Some(value), Some(name)) => transform(&value, name).unwrap_or_else(|e| {
+                errors.push(format!("{}: {}", mapping.target_field, e));
+                Value::Null
+            }),
+        };
+        target.insert(mapping.target_field.clone(), value);
+    }
+
+    (Value::Object(target), errors)
+}
+
+/// Apply a named transformation to one value
+pub fn transform(value: &Value, name: &str) -> Result<Value, String> {
+    let text = || match value {
+        Value::String(text) => text.trim().to_string(),
+        other => other.to_string(),
+    };
+    let invalid = |kind: &str| format!("cannot convert {} to {}", value, kind);
+
+    match name {
+        "trim" => Ok(Value::String(text())),
+        "uppercase" => Ok(Value::String(text().to_uppercase())),
+        "lowercase" => Ok(Value::String(text().to_lowercase())),
+        "to_string" => Ok(Value::String(text())),
+        "to_integer" => match value {
+            Value::Number(n) if n.is_i64() => Ok(value.clone()),
+            _ => text()
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.fract() == 0.0)
+                .map(|n| Value::from(n as i64))
+                .ok_or_else(|| invalid("integer")),
+        },
+        "to_number" => match value {
+            Value::Number(_) => Ok(value.clone()),
+            _ => text()
+                .replace(',', "")
+                .parse::<f64>()
+                .ok()
+                .and_then(|n| serde_json::Number::from_f64(n))
+                .map(Value::Number)
+                .ok_or_else(|| invalid("number")),
+        },
+        "to_boolean" => match text().to_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "1" => Ok(Value::Bool(true)),
+            "false" | "f" | "no" | "n" | "0" => Ok(Value::Bool(false)),
+            _ => Err(invalid("boolean")),
+        },
+        "to_date" => ["%Y-%m-%d", "%m/%d/%Y", "%Y%m%d"]
+            .iter()
+            .find_map(|format| chrono::NaiveDate::parse_from_str(&text(), format).ok())
+            .or_else(|| chrono::DateTime::parse_from_rfc3339(&text()).ok().map(|t| t.date_naive()))
+            .map(|date| Value::String(date.format("%Y-%m-%d").to_string()))
+            .ok_or_else(|| invalid("date")),
+        "to_timestamp" => chrono::DateTime::parse_from_rfc3339(&text())
+            .map(|t| t.with_timezone(&chrono::Utc))
+            .or_else(|_| {
+                chrono::NaiveDateTime::parse_from_str(&text(), "%Y-%m-%d %H:%M:%S")
+                    .map(|t| chrono::TimeZone::from_utc_datetime(&chrono::Utc, &t))
+            })
+            .map(|t| Value::String(t.to_rfc3339()))
+            .map_err(|_| invalid("timestamp")),
+        "to_json" => match value {
+            Value::String(text) => serde_json::from_str(text).map_err(|_| invalid("JSON")),
+            other => Ok(other.clone()),
+        },
+        // GeoJSON, WKT or hex WKB, loaded as GeoJSON
+        "to_geometry" => normalize_geometry(value).map_err(|_| invalid("geometry")),
+        other => match MaskStrategy::from_transformation(other) {
+            Some(strategy) => Ok(Masker::global().mask(value, &strategy).unwrap_or(Value::Null)),
+            None => Err(format!("unknown transformation {}", other)),
+        },
+    }
+}
+
+/// 1.0 for names that normalize to the same tokens, falling back to the
+/// better of token overlap and edit distance
+fn name_similarity(a: &str, b: &str) -> f64 {
+    let a_tokens = tokens(a);
+    let b_tokens = tokens(b);
+    if a_tokens.is_empty() || b_tokens.is_empty() {
+        return 0.0;
+    }
+    if a_tokens == b_tokens {
+        return 1.0;
+    }
+
+    let a_set: HashSet<&String> = a_tokens.iter().collect();
+    let b_set: HashSet<&String> = b_tokens.iter().collect();
+    let overlap = a_set.intersection(&b_set).count() as f64 / a_set.union(&b_set).count() as f64;
+
+    let a_joined = a_tokens.concat();
+    let b_joined = b_tokens.concat();
+    let longest = a_joined.chars().count().max(b_joined.chars().count()) as f64;
+    let edit = 1.0 - levenshtein(&a_joined, &b_joined) as f64 / longest;
+
+    overlap.max(edit)
+}
+
+/// Split camelCase, snake_case and spaced names into lowercase tokens with synonyms expanded
+fn tokens(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            previous_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+        .into_iter()
+        .map(|word| {
+            SYNONYMS
+                .iter()
+                .find(|(short, _)| *short == word)
+                .map(|(_, long)| long.to_string())
+                .unwrap_or(word)
+        })
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let cost = usize::from(a_char != *b_char);
+            current[j + 1] = (previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, field_type: FieldType) -> FieldInfo {
+        FieldInfo { name: name.to_string(), field_type, nullable: true }
+    }
+
+    #[test]
+    fn test_propose_mappings() {
+        let source = [
+            field("ParcelNo", FieldType::String),
+            field("OWNER_NAME", FieldType::String),
+            field("AssessedVal", FieldType::Integer),
+            field("legacy_flag", FieldType::Boolean),
+        ];
+        let target = [
+            field("parcel_number", FieldType::String),
+            field("owner_name", FieldType::String),
+            field("assessed_value", FieldType::Number),
+            field("geometry", FieldType::Geometry),
+        ];
+
+        let proposal = propose_mappings(&source, &target, DEFAULT_MIN_CONFIDENCE);
+        let pairs: Vec<(&str, &str)> = proposal
+            .field_mappings
+            .iter()
+            .map(|m| (m.source_field.as_str(), m.target_field.as_str()))
+            .collect();
+        assert_eq!(pairs, vec![
+            ("ParcelNo", "parcel_number"),
+            ("OWNER_NAME", "owner_name"),
+            ("AssessedVal", "assessed_value"),
+        ]);
+        assert_eq!(proposal.field_mappings[2].transformation, Some("to_number"));
+        assert_eq!(proposal.unmapped_source_fields, vec!["legacy_flag".to_string()]);
+        assert_eq!(proposal.unmapped_target_fields, vec!["geometry".to_string()]);
+    }
+
+    #[test]
+    fn test_apply_mappings() {
+        let mappings: Vec<MappingRule> = serde_json::from_value(serde_json::json!([
+            { "source_field": "ParcelNo", "target_field": "parcel_number", "transformation": "trim" },
+            { "source_field": "Assessed", "target_field": "assessed_value", "transformation": "to_number" },
+            { "source_field": "SaleDate", "target_field": "sale_date", "transformation": "to_date" },
+            { "source_field": "Situs.City", "target_field": "city", "default_value": "UNKNOWN" },
+        ]))
+        .unwrap();
+        let record = serde_json::json!({
+            "ParcelNo": " 1-234 ",
+            "Assessed": "125,000",
+            "SaleDate": "not a date",
+            "Situs": {},
+        });
+
+        let (target, errors) = apply_mappings(&record, &mappings);
+        assert_eq!(target["parcel_number"], "1-234");
+        assert_eq!(target["assessed_value"], 125000.0);
+        assert_eq!(target["sale_date"], Value::Null);
+        assert_eq!(target["city"], "UNKNOWN");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_geometry_transformation() {
+        let point = serde_json::json!({"type": "Point", "coordinates": [-119.2, 46.2]});
+        assert_eq!(transform(&serde_json::json!("POINT(-119.2 46.2)"), "to_geometry").unwrap(), point);
+        assert_eq!(
+            transform(&serde_json::json!("0101000000CDCCCCCCCCCC5DC09A99999999194740"), "to_geometry").unwrap(),
+            point
+        );
+        assert_eq!(transform(&point.to_string().into(), "to_geometry").unwrap(), point);
+        assert!(transform(&serde_json::json!("12 Main St"), "to_geometry").is_err());
+    }
+
+    #[test]
+    fn test_masking_transformations() {
+        assert_eq!(transform(&serde_json::json!("123-45-6789"), "mask_partial").unwrap(), "***-**-6789");
+        assert_eq!(transform(&serde_json::json!("123-45-6789"), "redact").unwrap(), Value::Null);
+        assert_eq!(
+            transform(&serde_json::json!("Jane Smith"), "tokenize").unwrap(),
+            transform(&serde_json::json!("Jane Smith"), "tokenize").unwrap()
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Geometry-aware comparison of matched source and target records.
+//!
+//! Attribute values are compared for equality, but a parcel boundary that
+//! went through a reprojection or a different GIS rarely comes back with
+//! identical coordinates. The geometry field is instead compared by how far
+//! the shapes are apart (Hausdorff distance) and how much the area changed;
+//! only a change beyond either tolerance makes the record Modified. The
+//! thresholds come from an optional `geometry_diff` object in the pair's
+//! source config:
+//!
+//! ```json
+//! "geometry_diff": { "field": "geometry", "crs": "EPSG:2927", "hausdorff_tolerance_m": 0.5, "area_tolerance_pct": 0.5 }
+//! ```
+//!
+//! Geometries may be GeoJSON, WKT or WKB. Without a `crs`, GeoJSON's WGS 84
+//! is assumed. When either side has no geometry or it does not parse, the
+//! field falls back to plain equality.
+
+use geo::{Geometry, Simplify};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use terrafusion_common::geo::{parse_geometry, Bbox, Measurement};
+use terrafusion_common::{Error, Result};
+
+/// Simplification tolerance for previews, as a fraction of the extent's
+/// diagonal
+const PREVIEW_TOLERANCE: f64 = 0.002;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeometryDiffSettings {
+    /// Record field holding the geometry
+    pub field: String,
+    /// CRS of both sides' coordinates
+    pub crs: Option<String>,
+    /// Boundaries closer than this are the same
+    pub hausdorff_tolerance_m: f64,
+    /// Area changes smaller than this percentage are the same
+    pub area_tolerance_pct: f64,
+}
+
+impl Default for GeometryDiffSettings {
+    fn default() -> Self {
+        Self {
+            field: "geometry".to_string(),
+            crs: None,
+            hausdorff_tolerance_m: 0.5,
+            area_tolerance_pct: 0.5,
+        }
+    }
+}
+
+/// How a parcel's shape changed, for the diff viewer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GeometryChange {
+    pub field: String,
+    /// Farthest distance between the old and new boundary
+    pub hausdorff_m: f64,
+    pub area_before_m2: f64,
+    pub area_after_m2: f64,
+    pub area_delta_m2: f64,
+    /// Change relative to the old area; `None` when it had none
+    pub area_delta_pct: Option<f64>,
+    /// Simplified old (target) and new (source) shapes as a
+    /// FeatureCollection
+    pub preview: Value,
+}
+
+/// Outcome of comparing a matched source and target record
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordDiff {
+    Same,
+    Changed { geometry_change: Option<GeometryChange> },
+}
+
+impl GeometryDiffSettings {
+    pub fn from_source_config(source_config: &Value) -> Result<Self> {
+        let settings: Self = match source_config.get("geometry_diff").filter(|value| !value.is_null()) {
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|e| Error::Validation(format!("Invalid geometry_diff settings: {}", e)))?,
+            None => Self::default(),
+        };
+        if settings.hausdorff_tolerance_m < 0.0 || settings.area_tolerance_pct < 0.0 {
+            return Err(Error::Validation("geometry_diff tolerances cannot be negative".to_string()));
+        }
+        Measurement::for_optional_crs(settings.crs.as_deref())
+            .map_err(|e| Error::Validation(format!("Invalid geometry_diff.crs: {}", e)))?;
+        Ok(settings)
+    }
+
+    fn measurement(&self) -> Measurement {
+        // Checked in from_source_config
+        Measurement::for_optional_crs(self.crs.as_deref()).unwrap_or(Measurement::Geodesic)
+    }
+
+    /// Compare a source record with the target record it matched
+    pub fn diff(&self, source: &Value, target: &Value) -> RecordDiff {
+        let (Some(source_fields), Some(target_fields)) = (source.as_object(), target.as_object()) else {
+            return if source == target { RecordDiff::Same } else { RecordDiff::Changed { geometry_change: None } };
+        };
+        let attributes_changed = !same_attributes(source_fields, target_fields, &self.field);
+
+        let before = target_fields.get(&self.field).filter(|value| !value.is_null());
+        let after = source_fields.get(&self.field).filter(|value| !value.is_null());
+        let (geometry_changed, geometry_change) = match (before, after) {
+            (Some(before), Some(after)) if before != after => match self.compare(before, after) {
+                Some(change) => {
+                    let moved = change.hausdorff_m > self.hausdorff_tolerance_m
+                        || change.area_delta_pct.map_or(change.area_delta_m2 != 0.0, |pct| pct.abs() > self.area_tolerance_pct);
+                    (moved, moved.then_some(change))
+                }
+                None => (true, None),
+            },
+            (before, after) => (before != after, None),
+        };
+
+        if attributes_changed || geometry_changed {
+            RecordDiff::Changed { geometry_change }
+        } else {
+            RecordDiff::Same
+        }
+    }
+
+    /// Measure the change between two geometries; `None` if either does not
+    /// parse
+    fn compare(&self, before: &Value, after: &Value) -> Option<GeometryChange> {
+        let before = parse_geometry(before).ok()?.geometry;
+        let after = parse_geometry(after).ok()?.geometry;
+        let measurement = self.measurement();
+
+        let area_before_m2 = measurement.area_m2(&before);
+        let area_after_m2 = measurement.area_m2(&after);
+        let area_delta_m2 = area_after_m2 - area_before_m2;
+        Some(GeometryChange {
+            field: self.field.clone(),
+            hausdorff_m: measurement.hausdorff_m(&before, &after),
+            area_before_m2,
+            area_after_m2,
+            area_delta_m2,
+            area_delta_pct: (area_before_m2 > 0.0).then(|| area_delta_m2 / area_before_m2 * 100.0),
+            preview: preview(&before, &after),
+        })
+    }
+}
+
+/// Whether the records agree on every field but the geometry
+fn same_attributes(a: &Map<String, Value>, b: &Map<String, Value>, geometry_field: &str) -> bool {
+    let attributes = |fields: &Map<String, Value>| fields.keys().filter(|name| *name != geometry_field).count();
+    attributes(a) == attributes(b)
+        && a.iter()
+            .filter(|(name, _)| *name != geometry_field)
+            .all(|(name, value)| b.get(name) == Some(value))
+}
+
+/// Old and new shapes, simplified so the snippet stays small
+fn preview(before: &Geometry<f64>, after: &Geometry<f64>) -> Value {
+    let epsilon = Bbox::of_all([before, after])
+        .map(|extent| (extent.max_x - extent.min_x).hypot(extent.max_y - extent.min_y) * PREVIEW_TOLERANCE)
+        .unwrap_or(0.0);
+    let feature = |geometry: &Geometry<f64>, version: &str| {
+        json!({
+            "type": "Feature",
+            "geometry": ::geojson::Geometry::new(::geojson::Value::from(&simplify(geometry, epsilon))),
+            "properties": { "version": version },
+        })
+    };
+    json!({
+        "type": "FeatureCollection",
+        "features": [feature(before, "before"), feature(after, "after")],
+    })
+}
+
+fn simplify(geometry: &Geometry<f64>, epsilon: f64) -> Geometry<f64> {
+    match geometry {
+        Geometry::LineString(line) => Geometry::LineString(line.simplify(&epsilon)),
+        Geometry::MultiLineString(lines) => Geometry::MultiLineString(lines.simplify(&epsilon)),
+        Geometry::Polygon(polygon) => Geometry::Polygon(polygon.simplify(&epsilon)),
+        Geometry::MultiPolygon(polygons) => Geometry::MultiPolygon(polygons.simplify(&epsilon)),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parcel(geometry: &str, owner: &str) -> Value {
+        json!({ "parcel_id": "1-23", "owner": owner, "geometry": geometry })
+    }
+
+    #[test]
+    fn test_settings() {
+        let settings = GeometryDiffSettings::from_source_config(&json!({ "table": "parcels" })).unwrap();
+        assert_eq!(settings, GeometryDiffSettings::default());
+
+        let settings =
+            GeometryDiffSettings::from_source_config(&json!({ "geometry_diff": { "field": "shape", "crs": "EPSG:2927" } }))
+                .unwrap();
+        assert_eq!((settings.field.as_str(), settings.hausdorff_tolerance_m), ("shape", 0.5));
+
+        assert!(GeometryDiffSettings::from_source_config(&json!({ "geometry_diff": { "crs": "EPSG:27700" } })).is_err());
+        assert!(GeometryDiffSettings::from_source_config(&json!({ "geometry_diff": { "tolerance": 1 } })).is_err());
+    }
+
+    #[test]
+    fn test_boundary_within_tolerance_is_same() {
+        let settings = GeometryDiffSettings { crs: Some("EPSG:2927".to_string()), ..Default::default() };
+        let target = parcel("POLYGON((0 0, 100 0, 100 100, 0 100, 0 0))", "Smith");
+        // About 3 cm of coordinate noise
+        let source = parcel("POLYGON((0.1 0, 100 0, 100 100, 0 100, 0.1 0))", "Smith");
+        assert_eq!(settings.diff(&source, &target), RecordDiff::Same);
+
+        let sold = parcel("POLYGON((0.1 0, 100 0, 100 100, 0 100, 0.1 0))", "Jones");
+        assert_eq!(settings.diff(&sold, &target), RecordDiff::Changed { geometry_change: None });
+    }
+
+    #[test]
+    fn test_moved_boundary_is_modified() {
+        let settings = GeometryDiffSettings { crs: Some("EPSG:2927".to_string()), ..Default::default() };
+        let target = parcel("POLYGON((0 0, 100 0, 100 100, 0 100, 0 0))", "Smith");
+        let source = parcel("POLYGON((0 0, 110 0, 110 100, 0 100, 0 0))", "Smith");
+
+        let RecordDiff::Changed { geometry_change: Some(change) } = settings.diff(&source, &target) else {
+            panic!("boundary move not detected");
+        };
+        assert!((change.hausdorff_m - 10.0 * 1200.0 / 3937.0).abs() < 1e-6);
+        assert!((change.area_delta_pct.unwrap() - 10.0).abs() < 1e-9);
+        assert_eq!(change.preview["features"][0]["properties"]["version"], "before");
+        assert_eq!(change.preview["features"][1]["geometry"]["type"], "Polygon");
+    }
+}
@@ -0,0 +1,218 @@
+//! Sync pair history for the dashboard chart and weekly county reports
+
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use serde::Serialize;
+use terrafusion_common::{Error, Result};
+
+use crate::models::database::{FailureReasonRow, OperationHistoryRow};
+
+/// Bucket size for history aggregation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryInterval {
+    Day,
+    Week,
+}
+
+impl HistoryInterval {
+    pub fn parse(value: Option<&str>) -> Result<Self> {
+        match value.unwrap_or("day") {
+            "day" | "daily" => Ok(Self::Day),
+            "week" | "weekly" => Ok(Self::Week),
+            other => Err(Error::Validation(format!("interval must be day or week, not {}", other))),
+        }
+    }
+
+    /// Unit name for `date_trunc`
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+        }
+    }
+
+    /// Period shown when the caller does not give a start date
+    pub fn default_span(&self) -> Duration {
+        match self {
+            Self::Day => Duration::days(30),
+            Self::Week => Duration::weeks(12),
+        }
+    }
+
+    fn step(&self) -> Duration {
+        match self {
+            Self::Day => Duration::days(1),
+            Self::Week => Duration::weeks(1),
+        }
+    }
+
+    /// Start of the bucket containing `time`, matching `date_trunc` (weeks start Monday)
+    fn truncate(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let date = time.date_naive();
+        let date = match self {
+            Self::Day => date,
+            Self::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        };
+        Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).expect("midnight is a valid time"))
+    }
+}
+
+/// One point of the history chart
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryBucket {
+    pub start: DateTime<Utc>,
+    pub total: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+    pub canceled: i64,
+    /// Completed operations as a share of finished ones; `None` when nothing finished
+    pub success_rate: Option<f64>,
+    pub avg_duration_seconds: Option<f64>,
+    pub records_processed: i64,
+    pub records_failed: i64,
+}
+
+/// Totals over the whole period
+#[derive(Debug, Clone, Serialize)]
+pub struct HistorySummary {
+    pub total: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+    pub success_rate: Option<f64>,
+    pub avg_duration_seconds: Option<f64>,
+    pub records_processed: i64,
+    /// Change in records processed between the first and second half of the
+    /// period, as a percentage; `None` when the first half had no records
+    pub records_trend_percent: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncPairHistory {
+    pub interval: &'static str,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub summary: HistorySummary,
+    pub buckets: Vec<HistoryBucket>,
+    pub failure_reasons: Vec<FailureReasonRow>,
+}
+
+/// Build the chart series, adding empty buckets so the x-axis has no gaps
+pub fn build_history(
+    interval: HistoryInterval,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    rows: Vec<OperationHistoryRow>,
+    failure_reasons: Vec<FailureReasonRow>,
+) -> SyncPairHistory {
+    let mut buckets = Vec::new();
+    let mut start = interval.truncate(from);
+    while start < to {
+        let bucket = match rows.iter().find(|row| row.bucket == start) {
+            Some(row) => HistoryBucket {
+                start,
+                total: row.total,
+                succeeded: row.succeeded,
+                failed: row.failed,
+                canceled: row.canceled,
+                success_rate: success_rate(row.succeeded, row.failed),
+                avg_duration_seconds: row.avg_duration_seconds.map(round),
+                records_processed: row.records_processed,
+                records_failed: row.records_failed,
+            },
+            None => HistoryBucket {
+                start,
+                total: 0,
+                succeeded: 0,
+                failed: 0,
+                canceled: 0,
+                success_rate: None,
+                avg_duration_seconds: None,
+                records_processed: 0,
+                records_failed: 0,
+            },
+        };
+        buckets.push(bucket);
+        start = start + interval.step();
+    }
+
+    let summary = summarize(&buckets);
+    SyncPairHistory { interval: interval.as_sql(), from, to, summary, buckets, failure_reasons }
+}
+
+fn summarize(buckets: &[HistoryBucket]) -> HistorySummary {
+    let total = buckets.iter().map(|b| b.total).sum();
+    let succeeded = buckets.iter().map(|b| b.succeeded).sum();
+    let failed = buckets.iter().map(|b| b.failed).sum();
+    let records_processed = buckets.iter().map(|b| b.records_processed).sum();
+
+    // Weight each bucket's average by its finished operations
+    let (duration_sum, duration_count) = buckets
+        .iter()
+        .filter_map(|b| b.avg_duration_seconds.map(|avg| (avg, b.succeeded + b.failed)))
+        .fold((0.0, 0), |(sum, count), (avg, n)| (sum + avg * n as f64, count + n));
+    let avg_duration_seconds = (duration_count > 0).then(|| round(duration_sum / duration_count as f64));
+
+    let (first, second) = buckets.split_at(buckets.len() / 2);
+    let first: i64 = first.iter().map(|b| b.records_processed).sum();
+    let second: i64 = second.iter().map(|b| b.records_processed).sum();
+    let records_trend_percent = (first > 0).then(|| round((second - first) as f64 / first as f64 * 100.0));
+
+    HistorySummary {
+        total,
+        succeeded,
+        failed,
+        success_rate: success_rate(succeeded, failed),
+        avg_duration_seconds,
+        records_processed,
+        records_trend_percent,
+    }
+}
+
+fn success_rate(succeeded: i64, failed: i64) -> Option<f64> {
+    let finished = succeeded + failed;
+    (finished > 0).then(|| round(succeeded as f64 / finished as f64))
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_history_fills_gaps() {
+        let from = Utc.with_ymd_and_hms(2024, 3, 6, 15, 0, 0).unwrap(); // Wednesday
+        let to = Utc.with_ymd_and_hms(2024, 3, 27, 0, 0, 0).unwrap();
+        let rows = vec![
+            OperationHistoryRow {
+                bucket: Utc.with_ymd_and_hms(2024, 3, 4, 0, 0, 0).unwrap(),
+                total: 4,
+                succeeded: 3,
+                failed: 1,
+                canceled: 0,
+                avg_duration_seconds: Some(60.0),
+                records_processed: 100,
+                records_failed: 2,
+            },
+            OperationHistoryRow {
+                bucket: Utc.with_ymd_and_hms(2024, 3, 18, 0, 0, 0).unwrap(),
+                total: 2,
+                succeeded: 2,
+                failed: 0,
+                canceled: 0,
+                avg_duration_seconds: Some(30.0),
+                records_processed: 150,
+                records_failed: 0,
+            },
+        ];
+
+        let history = build_history(HistoryInterval::Week, from, to, rows, Vec::new());
+        assert_eq!(history.buckets.len(), 4);
+        assert_eq!(history.buckets[1].total, 0);
+        assert_eq!(history.buckets[0].success_rate, Some(0.75));
+        assert_eq!(history.summary.success_rate, Some(0.83));
+        assert_eq!(history.summary.avg_duration_seconds, Some(50.0));
+        assert_eq!(history.summary.records_trend_percent, Some(50.0));
+    }
+}
@@ -0,0 +1,320 @@
+//! Inbound webhooks that start a sync pair.
+//!
+//! A CAMA vendor's "records committed" event can run the matching sync pair
+//! right away instead of waiting for its schedule. Each hook belongs to one
+//! pair and has its own secret, shown once when the hook is created or its
+//! secret rotated. The vendor proves it knows the secret in one of two ways:
+//!
+//! - `X-TerraFusion-Signature: sha256=<hex>`, an HMAC-SHA256 of the raw
+//!   request body keyed with the secret (preferred; the secret never travels)
+//! - `X-TerraFusion-Hook-Token: <secret>`, for systems that cannot sign
+//!
+//! The JSON payload can narrow and parameterize the run. `conditions` maps
+//! JSON pointers to the values they must have, so one endpoint can receive
+//! every event type and only fire on some. `payload_mapping` copies values
+//! from the payload into the operation's custom parameters:
+//!
+//! ```json
+//! "conditions": { "/event": "records.committed" },
+//! "payload_mapping": { "since": "/committed_after", "batch_id": "/batch/id" }
+//! ```
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use sqlx::PgPool;
+use terrafusion_common::encryption::PayloadCipher;
+use terrafusion_common::{Error, Result};
+use uuid::Uuid;
+
+use super::payloads::open_payloads;
+
+pub const SIGNATURE_HEADER: &str = "X-TerraFusion-Signature";
+pub const TOKEN_HEADER: &str = "X-TerraFusion-Hook-Token";
+
+/// Largest delivery accepted; events name what changed, not the records
+pub const MAX_PAYLOAD_BYTES: usize = 256 * 1024;
+
+/// Marks a hook secret, as `tfk_` marks API keys
+const SECRET_PREFIX: &str = "tfh_";
+const SECRET_LENGTH: usize = 40;
+
+/// A hook as stored; `secret` is never serialized
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SyncPairHook {
+    pub id: Uuid,
+    pub sync_pair_id: Uuid,
+    pub county_id: String,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub secret: Value,
+    pub payload_mapping: Value,
+    pub conditions: Value,
+    pub is_active: bool,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_triggered_at: Option<DateTime<Utc>>,
+    pub last_operation_id: Option<Uuid>,
+}
+
+/// Body for creating a hook
+#[derive(Debug, Deserialize)]
+pub struct NewHook {
+    pub name: String,
+    #[serde(default = "empty_object")]
+    pub payload_mapping: Value,
+    #[serde(default = "empty_object")]
+    pub conditions: Value,
+    /// Filled in by the gateway with the signed-in user
+    #[serde(default)]
+    pub created_by: Option<String>,
+}
+
+fn empty_object() -> Value {
+    Value::Object(Map::new())
+}
+
+/// Credentials a delivery came with
+#[derive(Debug, Clone, Copy)]
+pub enum Proof<'a> {
+    Signature(&'a str),
+    Token(&'a str),
+}
+
+pub fn generate_secret() -> String {
+    format!("{}{}", SECRET_PREFIX, Alphanumeric.sample_string(&mut rand::thread_rng(), SECRET_LENGTH))
+}
+
+/// Whether `proof` shows knowledge of `secret` for this body
+pub fn verify(secret: &str, body: &[u8], proof: Proof<'_>) -> bool {
+    match proof {
+        Proof::Signature(header) => {
+            let Some(signature) = header.trim().strip_prefix("sha256=").and_then(decode_hex) else {
+                return false;
+            };
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+            mac.update(body);
+            mac.verify_slice(&signature).is_ok()
+        }
+        Proof::Token(token) => {
+            let (token, secret) = (token.trim().as_bytes(), secret.as_bytes());
+            token.len() == secret.len() && token.iter().zip(secret).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+        }
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// Check a new hook's name, and that `payload_mapping` and `conditions`
+/// are objects of JSON pointers
+pub fn validate(hook: &NewHook) -> Result<()> {
+    if hook.name.trim().is_empty() || hook.name.len() > 255 {
+        return Err(Error::Validation("Hook name must be 1-255 characters".to_string()));
+    }
+    validate_config(&hook.payload_mapping, &hook.conditions)
+}
+
+fn validate_config(payload_mapping: &Value, conditions: &Value) -> Result<()> {
+    let mapping = payload_mapping
+        .as_object()
+        .ok_or_else(|| Error::Validation("payload_mapping must be an object".to_string()))?;
+    for (parameter, pointer) in mapping {
+        if !matches!(pointer.as_str(), Some(pointer) if pointer.is_empty() || pointer.starts_with('/')) {
+            return Err(Error::Validation(format!(
+                "payload_mapping.{} must be a JSON pointer such as \"/batch/id\"",
+                parameter
+            )));
+        }
+    }
+    let conditions = conditions
+        .as_object()
+        .ok_or_else(|| Error::Validation("conditions must be an object".to_string()))?;
+    if let Some(pointer) = conditions.keys().find(|pointer| !pointer.is_empty() && !pointer.starts_with('/')) {
+        return Err(Error::Validation(format!("conditions key {} is not a JSON pointer", pointer)));
+    }
+    Ok(())
+}
+
+/// The first condition the payload fails, if any
+pub fn unmet_condition<'a>(conditions: &'a Value, payload: &Value) -> Option<&'a str> {
+    conditions
+        .as_object()?
+        .iter()
+        .find(|(pointer, expected)| payload.pointer(pointer) != Some(*expected))
+        .map(|(pointer, _)| pointer.as_str())
+}
+
+/// Custom parameters for the run: each mapped pointer the payload has
+pub fn map_payload(payload_mapping: &Value, payload: &Value) -> Map<String, Value> {
+    payload_mapping
+        .as_object()
+        .map(|mapping| {
+            mapping
+                .iter()
+                .filter_map(|(parameter, pointer)| {
+                    let value = payload.pointer(pointer.as_str()?)?;
+                    Some((parameter.clone(), value.clone()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Database access for hooks
+pub struct HookQueries;
+
+impl HookQueries {
+    pub async fn list(pool: &PgPool, sync_pair_id: Uuid) -> Result<Vec<SyncPairHook>> {
+        Ok(sqlx::query_as::<_, SyncPairHook>(
+            "SELECT * FROM sync_pair_hooks WHERE sync_pair_id = $1 ORDER BY created_at",
+        )
+        .bind(sync_pair_id)
+        .fetch_all(pool)
+        .await?)
+    }
+
+    pub async fn create(
+        pool: &PgPool,
+        sync_pair_id: Uuid,
+        county_id: &str,
+        hook: &NewHook,
+        secret: &Value,
+    ) -> Result<SyncPairHook> {
+        sqlx::query_as::<_, SyncPairHook>(
+            r#"
+            INSERT INTO sync_pair_hooks (id, sync_pair_id, county_id, name, secret, payload_mapping, conditions, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(sync_pair_id)
+        .bind(county_id)
+        .bind(&hook.name)
+        .bind(secret)
+        .bind(&hook.payload_mapping)
+        .bind(&hook.conditions)
+        .bind(&hook.created_by)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.constraint() == Some("sync_pair_hooks_sync_pair_id_name_key") => {
+                Error::Conflict(format!("The sync pair already has a hook named {}", hook.name))
+            }
+            e => e.into(),
+        })
+    }
+
+    pub async fn set_secret(pool: &PgPool, sync_pair_id: Uuid, hook_id: Uuid, secret: &Value) -> Result<bool> {
+        let result = sqlx::query("UPDATE sync_pair_hooks SET secret = $3 WHERE id = $1 AND sync_pair_id = $2")
+            .bind(hook_id)
+            .bind(sync_pair_id)
+            .bind(secret)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn set_active(pool: &PgPool, sync_pair_id: Uuid, hook_id: Uuid, is_active: bool) -> Result<bool> {
+        let result = sqlx::query("UPDATE sync_pair_hooks SET is_active = $3 WHERE id = $1 AND sync_pair_id = $2")
+            .bind(hook_id)
+            .bind(sync_pair_id)
+            .bind(is_active)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete(pool: &PgPool, sync_pair_id: Uuid, hook_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM sync_pair_hooks WHERE id = $1 AND sync_pair_id = $2")
+            .bind(hook_id)
+            .bind(sync_pair_id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn record_trigger(pool: &PgPool, hook_id: Uuid, operation_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE sync_pair_hooks SET last_triggered_at = NOW(), last_operation_id = $2 WHERE id = $1")
+            .bind(hook_id)
+            .bind(operation_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Store a new secret sealed with the county's key when payload encryption
+/// is on, as a plain JSON string otherwise
+pub async fn seal_secret(cipher: Option<&PayloadCipher>, county_id: &str, secret: &str) -> Result<Value> {
+    let value = Value::String(secret.to_string());
+    match cipher {
+        Some(cipher) => cipher.seal(county_id, &value).await,
+        None => Ok(value),
+    }
+}
+
+/// The active hook of a pair whose secret the delivery proves, if any
+pub async fn authenticate(
+    pool: &PgPool,
+    cipher: Option<&PayloadCipher>,
+    sync_pair_id: Uuid,
+    body: &[u8],
+    proof: Proof<'_>,
+) -> Result<Option<SyncPairHook>> {
+    for hook in HookQueries::list(pool, sync_pair_id).await? {
+        if !hook.is_active {
+            continue;
+        }
+        let mut secret = Some(hook.secret.clone());
+        open_payloads(cipher, &hook.county_id, vec![&mut secret]).await?;
+        match secret.as_ref().and_then(Value::as_str) {
+            Some(secret) if verify(secret, body, proof) => return Ok(Some(hook)),
+            Some(_) => {}
+            None => log::warn!("Hook {} has an unreadable secret; rotate it", hook.id),
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_verify_accepts_signature_and_token_of_the_secret_only() {
+        let body = br#"{"event":"records.committed"}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"tfh_secret").unwrap();
+        mac.update(body);
+        let signature: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+
+        assert!(verify("tfh_secret", body, Proof::Signature(&format!("sha256={}", signature))));
+        assert!(!verify("tfh_other", body, Proof::Signature(&format!("sha256={}", signature))));
+        assert!(!verify("tfh_secret", b"{}", Proof::Signature(&format!("sha256={}", signature))));
+        assert!(!verify("tfh_secret", body, Proof::Signature(&signature)));
+        assert!(verify("tfh_secret", body, Proof::Token("tfh_secret")));
+        assert!(!verify("tfh_secret", body, Proof::Token("tfh_secre")));
+    }
+
+    #[test]
+    fn test_conditions_and_mapping_read_json_pointers() {
+        let payload = json!({ "event": "records.committed", "batch": { "id": 42 } });
+        let conditions = json!({ "/event": "records.committed" });
+        assert_eq!(unmet_condition(&conditions, &payload), None);
+        assert_eq!(unmet_condition(&json!({ "/event": "records.deleted" }), &payload), Some("/event"));
+
+        let mapping = json!({ "batch_id": "/batch/id", "since": "/committed_after" });
+        assert!(validate_config(&mapping, &conditions).is_ok());
+        assert!(validate_config(&json!({ "batch_id": "batch.id" }), &conditions).is_err());
+        assert_eq!(map_payload(&mapping, &payload), json!({ "batch_id": 42 }).as_object().unwrap().clone());
+    }
+}
@@ -0,0 +1,570 @@
+//! Files uploaded for ad-hoc imports.
+//!
+//! A one-off load, such as a district's spreadsheet or a consultant's
+//! GeoJSON, does not need a sync pair of its own. The file (CSV, GeoJSON or
+//! a zipped Shapefile) is uploaded as multipart form data, stored under
+//! IMPORT_DIR and checked by reading every record. Once it is `ready` it can
+//! stand in for a pair's source:
+//!
+//! - in a preview or mapping discovery, as `{ "import_id": "..." }` in place
+//!   of the source config
+//! - in a sync operation, as `source_import_id` among the operation's custom
+//!   parameters; the pair's other source settings, such as `key_field`,
+//!   still apply
+//!
+//! The import is recorded as soon as its upload starts and its received
+//! byte count is updated as the file arrives, so a client that picks its
+//! own `upload_id` can poll the import while a large file is uploading.
+//! Imports expire after IMPORT_RETENTION_HOURS and a maintenance job deletes
+//! them with their files. Workers read the files too, so IMPORT_DIR must be
+//! shared between instances.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use terrafusion_common::database::tenancy::with_county_filter;
+use terrafusion_common::database::RotatingPool;
+use terrafusion_common::jobs::{JobQueue, NewJob, Worker};
+use terrafusion_common::{Error, Result};
+use terrafusion_connector_sdk::{infer_fields, SCHEMA_SAMPLE_SIZE};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+use super::connectors::{Connector, FieldInfo};
+use super::credentials::CREDENTIAL_ID_KEY;
+use super::email_ingest::safe_file_name;
+
+/// Queue and kind of the purge of expired imports
+pub const IMPORT_QUEUE: &str = "maintenance";
+pub const IMPORT_PURGE_JOB: &str = "imports.purge";
+
+/// Config key that references an import
+pub const IMPORT_ID_KEY: &str = "import_id";
+
+/// Custom parameter naming the import a sync operation loads
+pub const SOURCE_IMPORT_PARAMETER: &str = "source_import_id";
+
+/// Received bytes between progress updates
+const PROGRESS_INTERVAL_BYTES: u64 = 8 * 1024 * 1024;
+
+/// A zipped Shapefile may unpack to this many times the upload limit
+const MAX_EXTRACTED_FACTOR: u64 = 4;
+
+/// Source config keys that say where records come from; an import replaces them
+const CONNECTION_KEYS: &[&str] = &[
+    "connector",
+    "connection_string",
+    "url",
+    "base_url",
+    "path",
+    "schema",
+    "table",
+    "api_key",
+    "token",
+    "records_path",
+    CREDENTIAL_ID_KEY,
+    IMPORT_ID_KEY,
+];
+
+/// File formats accepted for imports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Csv,
+    GeoJson,
+    /// A `.zip` holding the `.shp`, `.shx` and `.dbf`
+    Shapefile,
+}
+
+impl ImportFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "geojson" | "json" => Ok(Self::GeoJson),
+            "shapefile" | "shp" | "zip" => Ok(Self::Shapefile),
+            _ => Err(Error::Validation(format!(
+                "Unsupported import format {}; expected csv, geojson or shapefile",
+                value
+            ))),
+        }
+    }
+
+    /// The format a file name's extension suggests
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let (_, extension) = name.rsplit_once('.')?;
+        match extension.to_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "geojson" | "json" => Some(Self::GeoJson),
+            "zip" => Some(Self::Shapefile),
+            _ => None,
+        }
+    }
+
+    /// Also the kind of connector that reads the file
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::GeoJson => "geojson",
+            Self::Shapefile => "shapefile",
+        }
+    }
+}
+
+/// An uploaded file
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ImportFile {
+    pub id: Uuid,
+    pub county_id: String,
+    pub file_name: String,
+    pub format: String,
+    /// `uploading`, `validating`, `ready` or `failed`
+    pub status: String,
+    pub bytes_expected: Option<i64>,
+    pub bytes_received: i64,
+    pub sha256: Option<String>,
+    #[serde(skip)]
+    pub storage_path: String,
+    #[serde(skip)]
+    pub source_path: Option<String>,
+    pub record_count: Option<i64>,
+    pub fields: Option<Value>,
+    pub error_message: Option<String>,
+    pub uploaded_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ImportFile {
+    pub fn is_ready(&self) -> bool {
+        self.status == "ready"
+    }
+
+    /// How far the upload has got, when the client declared its size
+    pub fn upload_percent(&self) -> Option<u8> {
+        upload_percent(self.bytes_received, self.bytes_expected)
+    }
+
+    /// The connector config that reads the file
+    pub fn source_config(&self) -> Result<Value> {
+        let path = self
+            .source_path
+            .as_deref()
+            .filter(|_| self.is_ready())
+            .ok_or_else(|| Error::Validation(format!("Import {} is {} and cannot be read yet", self.id, self.status)))?;
+        Ok(json!({ "connector": self.format, "path": path }))
+    }
+}
+
+/// Percentage of `expected` bytes received. The expected size is the whole
+/// request, form boundaries included, so the file itself ends just short of 100.
+pub fn upload_percent(received: i64, expected: Option<i64>) -> Option<u8> {
+    let expected = expected.filter(|expected| *expected > 0)?;
+    Some((received.max(0) * 100 / expected).min(100) as u8)
+}
+
+/// The import a sync operation's custom parameters name, if any
+pub fn source_import_id(custom_parameters: Option<&Value>) -> Result<Option<Uuid>> {
+    match custom_parameters.and_then(|parameters| parameters.get(SOURCE_IMPORT_PARAMETER)) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(id)) => Uuid::parse_str(id)
+            .map(Some)
+            .map_err(|_| Error::Validation(format!("{} must be a UUID, not {}", SOURCE_IMPORT_PARAMETER, id))),
+        Some(other) => Err(Error::Validation(format!("{} must be a UUID, not {}", SOURCE_IMPORT_PARAMETER, other))),
+    }
+}
+
+/// A source config with the import in place of its connection settings
+pub fn as_source(config: &Value, import: &ImportFile) -> Result<Value> {
+    let mut merged = config.as_object().cloned().unwrap_or_default();
+    merged.retain(|key, _| !CONNECTION_KEYS.contains(&key.as_str()));
+    if let Value::Object(connection) = import.source_config()? {
+        merged.extend(connection);
+    }
+    Ok(Value::Object(merged))
+}
+
+/// Stores uploaded files and resolves references to them
+#[derive(Clone)]
+pub struct ImportStore {
+    pool: RotatingPool,
+    dir: PathBuf,
+    max_bytes: u64,
+    retention: Duration,
+}
+
+impl ImportStore {
+    pub fn new(pool: RotatingPool, dir: impl Into<PathBuf>, max_bytes: u64, retention: Duration) -> Self {
+        Self { pool, dir: dir.into(), max_bytes, retention }
+    }
+
+    /// Imports of one county, or of every county for platform admins, newest first
+    pub async fn list(&self, county_id: Option<&str>) -> Result<Vec<ImportFile>> {
+        let sql = with_county_filter("SELECT * FROM import_files WHERE TRUE", county_id, 1);
+        let mut query = sqlx::query_as::<_, ImportFile>(&format!("{} ORDER BY created_at DESC", sql));
+        if let Some(county_id) = county_id {
+            query = query.bind(county_id);
+        }
+        Ok(query.fetch_all(&self.pool.read_pool()).await?)
+    }
+
+    pub async fn get(&self, import_id: Uuid) -> Result<Option<ImportFile>> {
+        Ok(sqlx::query_as::<_, ImportFile>("SELECT * FROM import_files WHERE id = $1")
+            .bind(import_id)
+            .fetch_optional(&self.pool.pool())
+            .await?)
+    }
+
+    /// Record an upload that is starting
+    pub async fn begin(
+        &self,
+        county_id: &str,
+        import_id: Uuid,
+        file_name: &str,
+        format: ImportFormat,
+        bytes_expected: Option<i64>,
+        uploaded_by: &str,
+    ) -> Result<ImportFile> {
+        let storage_path = self
+            .dir
+            .join(safe_file_name(county_id))
+            .join(import_id.to_string())
+            .join(safe_file_name(file_name));
+        let expires_at = Utc::now() + chrono::Duration::from_std(self.retention).unwrap_or_else(|_| chrono::Duration::days(3));
+        sqlx::query_as::<_, ImportFile>(
+            r#"
+            INSERT INTO import_files (id, county_id, file_name, format, bytes_expected, storage_path, uploaded_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#,
+        )
+        .bind(import_id)
+        .bind(county_id)
+        .bind(file_name)
+        .bind(format.as_str())
+        .bind(bytes_expected)
+        .bind(storage_path.display().to_string())
+        .bind(uploaded_by)
+        .bind(expires_at)
+        .fetch_one(&self.pool.pool())
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.constraint() == Some("import_files_pkey") => {
+                Error::Conflict(format!("Upload {} already exists", import_id))
+            }
+            e => e.into(),
+        })
+    }
+
+    /// Write the file as it arrives, updating the received byte count as it goes
+    pub async fn receive<S, B>(&self, import: &ImportFile, mut chunks: S) -> Result<()>
+    where
+        S: Stream<Item = Result<B>> + Unpin,
+        B: AsRef<[u8]>,
+    {
+        let path = Path::new(&import.storage_path);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut hasher = Sha256::new();
+        let mut received = 0u64;
+        let mut reported = 0u64;
+
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            let chunk = chunk.as_ref();
+            received += chunk.len() as u64;
+            if received > self.max_bytes {
+                return Err(Error::Validation(format!("The file is larger than {} bytes", self.max_bytes)));
+            }
+            hasher.update(chunk);
+            file.write_all(chunk).await?;
+            if received - reported >= PROGRESS_INTERVAL_BYTES {
+                self.set_received(import.id, received).await?;
+                reported = received;
+            }
+        }
+        file.flush().await?;
+        if received == 0 {
+            return Err(Error::Validation("The uploaded file is empty".to_string()));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE import_files
+            SET status = 'validating', bytes_received = $2, sha256 = $3, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(import.id)
+        .bind(received as i64)
+        .bind(format!("{:x}", hasher.finalize()))
+        .execute(&self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    async fn set_received(&self, import_id: Uuid, received: u64) -> Result<()> {
+        sqlx::query("UPDATE import_files SET bytes_received = $2, updated_at = NOW() WHERE id = $1")
+            .bind(import_id)
+            .bind(received as i64)
+            .execute(&self.pool.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// Read every record of a received file and mark the import ready, with
+    /// its record count and the fields found in a sample
+    pub async fn validate(&self, import: &ImportFile) -> Result<ImportFile> {
+        let format = ImportFormat::parse(&import.format)?;
+        let path = PathBuf::from(&import.storage_path);
+        let max_extracted = self.max_bytes.saturating_mul(MAX_EXTRACTED_FACTOR);
+        let (source_path, record_count, fields) =
+            tokio::task::spawn_blocking(move || check_file(format, &path, max_extracted))
+                .await
+                .map_err(|e| Error::Internal(format!("Import check stopped: {}", e)))??;
+
+        Ok(sqlx::query_as::<_, ImportFile>(
+            r#"
+            UPDATE import_files
+            SET status = 'ready', source_path = $2, record_count = $3, fields = $4, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(import.id)
+        .bind(source_path.display().to_string())
+        .bind(record_count)
+        .bind(json!(fields))
+        .fetch_one(&self.pool.pool())
+        .await?)
+    }
+
+    /// Mark an upload failed and drop what was stored of it. The record stays
+    /// until it expires, so a client polling it sees why.
+    pub async fn fail(&self, import: &ImportFile, message: &str) -> Result<()> {
+        remove_files(import).await;
+        sqlx::query("UPDATE import_files SET status = 'failed', error_message = $2, updated_at = NOW() WHERE id = $1")
+            .bind(import.id)
+            .bind(message)
+            .execute(&self.pool.pool())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, import: &ImportFile) -> Result<()> {
+        remove_files(import).await;
+        sqlx::query("DELETE FROM import_files WHERE id = $1")
+            .bind(import.id)
+            .execute(&self.pool.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// A ready import of `county_id`
+    pub async fn ready_import(&self, county_id: &str, import_id: Uuid) -> Result<ImportFile> {
+        let import = self
+            .get(import_id)
+            .await?
+            .filter(|import| import.county_id == county_id)
+            .ok_or_else(|| Error::NotFound(format!("Import {} not found in county {}", import_id, county_id)))?;
+        if !import.is_ready() {
+            return Err(Error::Validation(format!("Import {} is {} and cannot be used", import.id, import.status)));
+        }
+        Ok(import)
+    }
+
+    /// A source config of `county_id` ready for a connector: a referenced
+    /// import replaces the connection, other configs are returned as they are
+    pub async fn resolve(&self, county_id: &str, config: &Value) -> Result<Value> {
+        let import_id = match config.get(IMPORT_ID_KEY) {
+            None | Some(Value::Null) => return Ok(config.clone()),
+            Some(value) => value
+                .as_str()
+                .and_then(|id| Uuid::parse_str(id).ok())
+                .ok_or_else(|| Error::Validation(format!("{} must be a UUID, not {}", IMPORT_ID_KEY, value)))?,
+        };
+        as_source(config, &self.ready_import(county_id, import_id).await?)
+    }
+
+    /// Delete expired imports and their files, returning how many
+    pub async fn purge_expired(&self) -> Result<usize> {
+        let expired = sqlx::query_as::<_, ImportFile>("SELECT * FROM import_files WHERE expires_at < NOW()")
+            .fetch_all(&self.pool.pool())
+            .await?;
+        for import in &expired {
+            self.delete(import).await?;
+        }
+        if !expired.is_empty() {
+            log::info!("Deleted {} expired imports", expired.len());
+        }
+        Ok(expired.len())
+    }
+
+    /// Handle purges on `worker`
+    pub fn register(&self, worker: Worker) -> Worker {
+        let store = self.clone();
+        worker.handle(IMPORT_PURGE_JOB, move |_| {
+            let store = store.clone();
+            async move { store.purge_expired().await.map(|_| ()) }
+        })
+    }
+
+    /// Enqueue a purge every hour, once per hour across instances
+    pub fn schedule(queue: JobQueue) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(3600));
+            loop {
+                ticker.tick().await;
+                let next = (Utc::now().timestamp() / 3600 + 1) * 3600;
+                let Some(run_at) = DateTime::from_timestamp(next, 0) else {
+                    continue;
+                };
+                let job = NewJob::new(IMPORT_QUEUE, IMPORT_PURGE_JOB, json!({}))
+                    .run_at(run_at)
+                    .max_attempts(1)
+                    .unique(format!("{}:{}", IMPORT_PURGE_JOB, next));
+                if let Err(e) = queue.enqueue(job).await {
+                    log::error!("Failed to schedule import purge: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// The import's directory holds the upload and anything unpacked from it
+async fn remove_files(import: &ImportFile) {
+    let Some(dir) = Path::new(&import.storage_path).parent() else {
+        return;
+    };
+    if let Err(e) = tokio::fs::remove_dir_all(dir).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to remove files of import {}: {}", import.id, e);
+        }
+    }
+}
+
+/// Read every record of an uploaded file. Returns the file connectors read,
+/// the record count and the fields of a sample. Blocking.
+fn check_file(format: ImportFormat, path: &Path, max_extracted: u64) -> Result<(PathBuf, i64, Vec<FieldInfo>)> {
+    let source = match format {
+        ImportFormat::Shapefile => extract_shapefile(path, max_extracted)?,
+        _ => path.to_path_buf(),
+    };
+    let connector = Connector::from_config(&json!({ "connector": format.as_str(), "path": source }))?;
+
+    let mut sample = Vec::new();
+    let count = connector
+        .scan_file(usize::MAX, |record| {
+            if sample.len() < SCHEMA_SAMPLE_SIZE {
+                sample.push(record);
+            }
+            Ok(())
+        })
+        .map_err(|e| Error::Validation(format!("Not a valid {} file: {}", format.as_str(), e)))?;
+    if count == 0 {
+        return Err(Error::Validation("The file holds no records".to_string()));
+    }
+    Ok((source, count as i64, infer_fields(&sample)))
+}
+
+/// Unpack the Shapefile parts of a zip next to it and return the `.shp`
+fn extract_shapefile(zip_path: &Path, max_extracted: u64) -> Result<PathBuf> {
+    let invalid = |e: zip::result::ZipError| Error::Validation(format!("Not a valid zip archive: {}", e));
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(zip_path)?).map_err(invalid)?;
+    let dir = zip_path.parent().unwrap_or_else(|| Path::new("."));
+
+    let mut shp = None;
+    let mut remaining = max_extracted;
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).map_err(invalid)?;
+        // Skip folders and the resource forks macOS adds to archives
+        if entry.is_dir() || entry.name().starts_with("__MACOSX") {
+            continue;
+        }
+        let name = safe_file_name(entry.name());
+        let extension = name.rsplit_once('.').map(|(_, extension)| extension.to_lowercase());
+        if !matches!(extension.as_deref(), Some("shp" | "shx" | "dbf" | "prj" | "cpg")) {
+            continue;
+        }
+
+        let target = dir.join(&name);
+        let mut out = std::fs::File::create(&target)?;
+        let written = std::io::copy(&mut entry.take(remaining + 1), &mut out)?;
+        if written > remaining {
+            return Err(Error::Validation(format!("The zip unpacks to more than {} bytes", max_extracted)));
+        }
+        remaining -= written;
+
+        if extension.as_deref() == Some("shp") {
+            if shp.is_some() {
+                return Err(Error::Validation("The zip holds more than one Shapefile; import them one at a time".to_string()));
+            }
+            shp = Some(target);
+        }
+    }
+
+    let shp = shp.ok_or_else(|| Error::Validation("The zip holds no .shp file".to_string()))?;
+    for part in ["shx", "dbf"] {
+        if !shp.with_extension(part).exists() {
+            return Err(Error::Validation(format!("The zip is missing the Shapefile's .{} file", part)));
+        }
+    }
+    Ok(shp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_as_source() {
+        assert_eq!(ImportFormat::from_file_name("Levies 2025.CSV"), Some(ImportFormat::Csv));
+        assert_eq!(ImportFormat::from_file_name("parcels.zip"), Some(ImportFormat::Shapefile));
+        assert_eq!(ImportFormat::from_file_name("parcels"), None);
+        assert_eq!(upload_percent(50, Some(200)), Some(25));
+        assert_eq!(upload_percent(250, Some(200)), Some(100));
+        assert_eq!(upload_percent(50, None), None);
+
+        let id = Uuid::new_v4();
+        assert_eq!(source_import_id(Some(&json!({ "source_import_id": id.to_string() }))).unwrap(), Some(id));
+        assert_eq!(source_import_id(Some(&json!({ "override_quota": true }))).unwrap(), None);
+        assert!(source_import_id(Some(&json!({ "source_import_id": 7 }))).is_err());
+
+        let now = Utc::now();
+        let mut import = ImportFile {
+            id,
+            county_id: "benton".to_string(),
+            file_name: "levies.csv".to_string(),
+            format: "csv".to_string(),
+            status: "validating".to_string(),
+            bytes_expected: None,
+            bytes_received: 1024,
+            sha256: None,
+            storage_path: "/imports/benton/levies.csv".to_string(),
+            source_path: Some("/imports/benton/levies.csv".to_string()),
+            record_count: None,
+            fields: None,
+            error_message: None,
+            uploaded_by: "clerk@benton.example.gov".to_string(),
+            created_at: now,
+            updated_at: now,
+            expires_at: now,
+        };
+        let config = json!({ "connection_string": "postgres://cama@db01/pacs", "table": "levies", "key_field": "levy_id" });
+        assert!(as_source(&config, &import).is_err());
+
+        import.status = "ready".to_string();
+        assert_eq!(
+            as_source(&config, &import).unwrap(),
+            json!({ "connector": "csv", "path": "/imports/benton/levies.csv", "key_field": "levy_id" })
+        );
+    }
+}
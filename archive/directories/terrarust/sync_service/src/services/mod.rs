@@ -1,3 +1,33 @@
 pub mod sync_engine;
 pub mod scheduler;
-pub mod conflict_resolver;
\ No newline at end of file
+pub mod conflict_resolver;
+pub mod narrator;
+pub mod entity_matcher;
+pub mod config_bundle;
+pub mod connectors;
+pub mod field_mapping;
+pub mod history;
+pub mod dashboard;
+pub mod entity_history;
+pub mod retention;
+pub mod partitions;
+pub mod search;
+pub mod bulk;
+pub mod reports;
+pub mod workers;
+pub mod throttle;
+pub mod batch;
+pub mod cdc;
+pub mod email_ingest;
+pub mod geometry_diff;
+pub mod enrichment;
+pub mod boundary_check;
+pub mod payloads;
+pub mod operation_timeline;
+pub mod hooks;
+pub mod duplicates;
+pub mod pipelines;
+pub mod blue_green;
+pub mod schema_drift;
+pub mod credentials;
+pub mod imports;
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use serde::Deserialize;
+use terrafusion_common::http_client::{HttpClient, HttpClientSettings};
+use terrafusion_common::{Result, Error};
+use terrafusion_common::models::sync::SyncStats;
+
+/// Maximum number of distinct failure reasons passed to the summarizer
+const MAX_FAILURE_REASONS: usize = 5;
+
+/// Client for the NarratorAI summarization service
+#[derive(Clone)]
+pub struct NarratorClient {
+    client: HttpClient,
+    base_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SummarizeResponse {
+    result: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+impl NarratorClient {
+    /// Create a client for the NarratorAI service at `base_url`
+    pub fn new(base_url: &str, timeout: Duration) -> Self {
+        let client = HttpClientSettings::from_env()
+            .and_then(|settings| HttpClient::new("narrator", &settings.with_timeout(timeout)))
+            .expect("Failed to create NarratorAI HTTP client");
+
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Produce a plain-English narrative for a finished operation
+    pub async fn summarize_operation(&self, digest: &OperationDigest) -> Result<String> {
+        let response = self.client
+            .post(&format!("{}/api/v1/summarize", self.base_url))
+            .json(&serde_json::json!({
+                "text": digest.to_text(),
+                "context": "sync_operation",
+                "temperature": 0.2
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Error::ExternalService(format!(
+                "NarratorAI returned status {}",
+                response.status()
+            )));
+        }
+
+        let body: SummarizeResponse = response.json().await?;
+        Ok(body.result.trim().to_string())
+    }
+
+    /// Embed texts with NarratorAI's embedding model, one vector per text
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let response = self.client
+            .post(&format!("{}/api/v1/embed", self.base_url))
+            .json(&serde_json::json!({ "texts": texts }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Error::ExternalService(format!(
+                "NarratorAI returned status {}",
+                response.status()
+            )));
+        }
+
+        let body: EmbedResponse = response.json().await?;
+        if body.embeddings.len() != texts.len() {
+            return Err(Error::ExternalService(format!(
+                "NarratorAI returned {} embeddings for {} texts",
+                body.embeddings.len(),
+                texts.len()
+            )));
+        }
+
+        Ok(body.embeddings)
+    }
+}
+
+/// Counts and failure reasons collected while an operation runs
+#[derive(Debug, Clone, Default)]
+pub struct OperationDigest {
+    pub sync_pair_name: String,
+    pub source_system: String,
+    pub target_system: String,
+    pub created: u64,
+    pub updated: u64,
+    pub deleted: u64,
+    pub conflicts: u64,
+    pub failure_reasons: HashMap<String, u64>,
+    /// Size of the source payloads loaded, for usage accounting
+    pub payload_bytes: u64,
+}
+
+impl OperationDigest {
+    /// Record a failed record, grouping identical error messages
+    pub fn record_failure(&mut self, reason: &str) {
+        *self.failure_reasons.entry(reason.to_string()).or_insert(0) += 1;
+    }
+
+    /// Render the digest and final stats as the summarizer's input text
+    pub fn to_text(&self) -> String {
+        let failed: u64 = self.failure_reasons.values().sum();
+
+        let mut text = format!(
+            "Sync pair: {} ({} -> {})\nRecords created: {}\nRecords updated: {}\nRecords deleted: {}\nConflicts: {}\nRecords failed: {}\n",
+            self.sync_pair_name,
+            self.source_system,
+            self.target_system,
+            self.created,
+            self.updated,
+            self.deleted,
+            self.conflicts,
+            failed,
+        );
+
+        let mut reasons: Vec<_> = self.failure_reasons.iter().collect();
+        reasons.sort_by(|a, b| b.1.cmp(a.1));
+
+        for (reason, count) in reasons.into_iter().take(MAX_FAILURE_REASONS) {
+            text.push_str(&format!("Failure reason ({} records): {}\n", count, reason));
+        }
+
+        text
+    }
+
+    /// Narrative used when the NarratorAI service is unavailable
+    pub fn fallback_narrative(&self, stats: &SyncStats) -> String {
+        let mut narrative = format!(
+            "{} records processed: {} succeeded, {} failed.",
+            stats.total_records_processed,
+            stats.total_records_succeeded,
+            stats.total_records_failed,
+        );
+
+        if let Some((reason, _)) = self.failure_reasons.iter().max_by_key(|(_, count)| **count) {
+            narrative.push_str(&format!(" Most common failure: {}.", reason));
+        }
+
+        narrative
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_groups_failure_reasons() {
+        let mut digest = OperationDigest {
+            sync_pair_name: "County Parcels Sync".to_string(),
+            updated: 1245,
+            ..Default::default()
+        };
+        digest.record_failure("missing geometry");
+        digest.record_failure("missing geometry");
+        digest.record_failure("invalid parcel id");
+
+        let text = digest.to_text();
+        assert!(text.contains("Records updated: 1245"));
+        assert!(text.contains("Records failed: 3"));
+        assert!(text.contains("Failure reason (2 records): missing geometry"));
+    }
+}
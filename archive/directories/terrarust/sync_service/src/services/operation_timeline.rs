@@ -0,0 +1,190 @@
+//! The event timeline and progress curve of one sync operation, read back
+//! from its execution log for the operation detail page
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::models::database::OperationDetailRow;
+use crate::services::batch::{BatchLogEntry, BatchOutcome};
+
+/// One entry in an operation's timeline
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEvent {
+    pub at: DateTime<Utc>,
+    /// e.g. `operation_created`, `batch_committed`, `operation_failed`
+    pub kind: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+/// Running totals after a batch finished
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgressPoint {
+    pub at: DateTime<Utc>,
+    pub batch: usize,
+    pub processed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationTimeline {
+    pub events: Vec<TimelineEvent>,
+    pub progress: Vec<ProgressPoint>,
+}
+
+/// Merge the logged events, the finished batches and the operation's own
+/// start and end into one timeline, oldest first. Log entries whose time
+/// does not parse are dropped rather than failing the page.
+pub fn build(operation: &OperationDetailRow) -> OperationTimeline {
+    let log = operation.execution_logs.as_ref();
+    let mut events = logged_events(log);
+
+    if !events.iter().any(|event| event.kind == "operation_started") {
+        events.push(TimelineEvent {
+            at: operation.start_time,
+            kind: "operation_started".to_string(),
+            message: format!("Started by {}", operation.initiated_by),
+            details: None,
+        });
+    }
+
+    let batches: Vec<BatchLogEntry> = log
+        .and_then(|log| log.get("batches"))
+        .and_then(|batches| serde_json::from_value(batches.clone()).ok())
+        .unwrap_or_default();
+
+    let mut progress = Vec::with_capacity(batches.len());
+    let (mut processed, mut succeeded, mut failed) = (0, 0, 0);
+    for entry in &batches {
+        let (kind, verb) = match entry.outcome {
+            BatchOutcome::Committed => ("batch_committed", "committed"),
+            BatchOutcome::RolledBack => ("batch_rolled_back", "rolled back"),
+        };
+        events.push(TimelineEvent {
+            at: entry.finished_at,
+            kind: kind.to_string(),
+            message: format!(
+                "Batch {} {}: {} succeeded, {} failed ({} to {})",
+                entry.batch, verb, entry.succeeded, entry.failed, entry.first_source_id, entry.last_source_id
+            ),
+            details: entry.error.as_ref().map(|error| Value::String(error.clone())),
+        });
+
+        processed += entry.count;
+        succeeded += entry.succeeded;
+        failed += entry.failed;
+        progress.push(ProgressPoint {
+            at: entry.finished_at,
+            batch: entry.batch,
+            processed,
+            succeeded,
+            failed,
+        });
+    }
+
+    if let Some(end_time) = operation.end_time {
+        events.push(TimelineEvent {
+            at: end_time,
+            kind: format!("operation_{}", operation.status.to_lowercase()),
+            message: operation
+                .error_message
+                .clone()
+                .unwrap_or_else(|| format!("Operation {}", operation.status.to_lowercase())),
+            details: None,
+        });
+    }
+
+    // Stable, so events logged at the same instant keep their order
+    events.sort_by_key(|event| event.at);
+    OperationTimeline { events, progress }
+}
+
+fn logged_events(log: Option<&Value>) -> Vec<TimelineEvent> {
+    log.and_then(|log| log.get("events"))
+        .and_then(Value::as_array)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| {
+                    let at = entry.get("time")?.as_str()?.parse::<DateTime<Utc>>().ok()?;
+                    let kind = entry.get("event").and_then(Value::as_str).unwrap_or("event").to_string();
+                    let (message, details) = match entry.get("details") {
+                        Some(Value::String(text)) => (text.clone(), None),
+                        Some(Value::Null) | None => (kind.replace('_', " "), None),
+                        Some(other) => (kind.replace('_', " "), Some(other.clone())),
+                    };
+                    Some(TimelineEvent { at, kind, message, details })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn operation(execution_logs: Value) -> OperationDetailRow {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        OperationDetailRow {
+            id: Uuid::new_v4(),
+            sync_pair_id: Uuid::new_v4(),
+            sync_pair_name: "Parcels".to_string(),
+            county_id: "benton".to_string(),
+            status: "FAILED".to_string(),
+            start_time: start,
+            end_time: Some(start + Duration::minutes(5)),
+            records_processed: Some(150),
+            records_succeeded: Some(140),
+            records_failed: Some(10),
+            error_message: Some("Target connection lost".to_string()),
+            custom_parameters: None,
+            initiated_by: "clerk@benton.example".to_string(),
+            narrative: None,
+            execution_logs: Some(execution_logs),
+            created_at: start,
+        }
+    }
+
+    fn batch(batch: usize, start: usize, succeeded: usize, failed: usize, minute: u32) -> Value {
+        json!({
+            "batch": batch,
+            "start": start,
+            "count": succeeded + failed,
+            "first_source_id": format!("P{}", start),
+            "last_source_id": format!("P{}", start + succeeded + failed - 1),
+            "mode": "all_or_nothing",
+            "outcome": if failed == 0 { "committed" } else { "rolled_back" },
+            "succeeded": succeeded,
+            "failed": failed,
+            "finished_at": Utc.with_ymd_and_hms(2024, 3, 1, 12, minute, 0).unwrap().to_rfc3339(),
+        })
+    }
+
+    #[test]
+    fn test_timeline_merges_events_batches_and_end_in_time_order() {
+        let timeline = build(&operation(json!({
+            "events": [
+                { "time": "2024-03-01T11:59:00Z", "event": "operation_created", "details": "Sync operation created" },
+                { "time": "not a time", "event": "garbled" }
+            ],
+            "batches": [batch(1, 0, 100, 0, 2), batch(2, 100, 40, 10, 4)]
+        })));
+
+        let kinds: Vec<&str> = timeline.events.iter().map(|event| event.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            vec!["operation_created", "operation_started", "batch_committed", "batch_rolled_back", "operation_failed"]
+        );
+        assert_eq!(timeline.events.last().unwrap().message, "Target connection lost");
+
+        let last = timeline.progress.last().unwrap();
+        assert_eq!((last.batch, last.processed, last.succeeded, last.failed), (2, 150, 140, 10));
+    }
+}
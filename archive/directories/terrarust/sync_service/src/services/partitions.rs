@@ -0,0 +1,165 @@
+//! Partition maintenance for the monthly-partitioned history tables.
+//!
+//! Each run creates the coming months of `sync_diffs` and `audit_log` and
+//! drops months that are wholly past retention. Diff months go once they
+//! are older than the longest `diff_retention_days` of any policy; counties
+//! with shorter policies still have their rows deleted by `RetentionJob`
+//! until then. Audit months are only dropped when `AUDIT_LOG_RETENTION_DAYS`
+//! is set.
+
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use prometheus::{register_int_counter_vec, register_int_gauge, IntCounterVec, IntGauge};
+use serde::Serialize;
+use terrafusion_common::{Result, database::RotatingPool};
+use terrafusion_common::database::partitions::{self, PartitionSettings, PARTITIONED_TABLES};
+use terrafusion_common::jobs::{JobQueue, NewJob, Worker};
+
+use crate::models::database::{RetentionPolicyRow, RetentionQueries};
+use crate::services::retention::RETENTION_QUEUE;
+
+pub const PARTITION_JOB: &str = "partitions.maintain";
+
+lazy_static! {
+    /// Monthly partitions dropped, by table
+    pub static ref PARTITIONS_DROPPED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "partitions_dropped_total",
+        "History table partitions dropped past retention",
+        &["table"]
+    ).expect("Failed to register partitions_dropped_total");
+
+    pub static ref PARTITION_LAST_SUCCESS_TIMESTAMP: IntGauge = register_int_gauge!(
+        "partition_maintenance_last_success_timestamp_seconds",
+        "Unix time partition maintenance last completed"
+    ).expect("Failed to register partition_maintenance_last_success_timestamp_seconds");
+}
+
+/// What one run did to one table
+#[derive(Debug, Clone, Serialize)]
+pub struct PartitionReport {
+    pub table: String,
+    pub ensured: Vec<String>,
+    pub dropped: Vec<String>,
+    pub rows_dropped: i64,
+}
+
+/// Months of `table` older than this can be dropped, `None` to keep all
+fn drop_cutoff(
+    table: &str,
+    settings: &PartitionSettings,
+    policies: &[RetentionPolicyRow],
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let days = match table {
+        "sync_diffs" => policies.iter().map(|p| p.diff_retention_days).max()? as i64,
+        "audit_log" => settings.audit_log_retention_days? as i64,
+        _ => return None,
+    };
+    Some(now - chrono::Duration::days(days))
+}
+
+/// Creates and drops monthly partitions on a schedule
+#[derive(Clone)]
+pub struct PartitionMaintenance {
+    pool: RotatingPool,
+    settings: PartitionSettings,
+}
+
+impl PartitionMaintenance {
+    pub fn new(pool: RotatingPool, settings: PartitionSettings) -> Self {
+        Self { pool, settings }
+    }
+
+    pub async fn run(&self) -> Result<Vec<PartitionReport>> {
+        let pool = self.pool.pool();
+        let policies = RetentionQueries::list_policies(&pool).await?;
+        let now = Utc::now();
+
+        let mut reports = Vec::new();
+        for &table in PARTITIONED_TABLES {
+            let ensured = partitions::ensure_partitions(&pool, table, now, self.settings.months_ahead).await?;
+            let dropped = match drop_cutoff(table, &self.settings, &policies, now) {
+                Some(cutoff) => partitions::drop_partitions_before(&pool, table, cutoff).await?,
+                None => Vec::new(),
+            };
+
+            let rows_dropped = dropped.iter().map(|(_, rows)| rows).sum();
+            PARTITIONS_DROPPED_TOTAL.with_label_values(&[table]).inc_by(dropped.len() as u64);
+            for (partition, rows) in &dropped {
+                log::info!("Dropped partition {} ({} rows) past retention", partition, rows);
+            }
+            reports.push(PartitionReport {
+                table: table.to_string(),
+                ensured,
+                dropped: dropped.into_iter().map(|(partition, _)| partition).collect(),
+                rows_dropped,
+            });
+        }
+
+        PARTITION_LAST_SUCCESS_TIMESTAMP.set(Utc::now().timestamp());
+        Ok(reports)
+    }
+
+    /// Handle scheduled partition maintenance on `worker`
+    pub fn register(&self, worker: Worker) -> Worker {
+        let job = self.clone();
+        worker.handle(PARTITION_JOB, move |_| {
+            let job = job.clone();
+            async move { job.run().await.map(|_| ()) }
+        })
+    }
+
+    /// Enqueue maintenance at every multiple of `interval`, once per slot
+    /// across instances, as `RetentionJob::schedule` does
+    pub fn schedule(queue: JobQueue, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let slot = interval.as_secs().max(1) as i64;
+
+            loop {
+                ticker.tick().await;
+                let next = (Utc::now().timestamp() / slot + 1) * slot;
+                let Some(run_at) = DateTime::from_timestamp(next, 0) else {
+                    continue;
+                };
+                let job = NewJob::new(RETENTION_QUEUE, PARTITION_JOB, serde_json::json!({}))
+                    .run_at(run_at)
+                    .max_attempts(3)
+                    .unique(format!("{}:{}", PARTITION_JOB, next));
+                if let Err(e) = queue.enqueue(job).await {
+                    log::error!("Failed to schedule partition maintenance: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(county_id: &str, diff_retention_days: i32) -> RetentionPolicyRow {
+        RetentionPolicyRow {
+            county_id: county_id.to_string(),
+            diff_retention_days,
+            stats_retention_days: 365,
+            updated_by: "test".to_string(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_drop_cutoff() {
+        let now = Utc::now();
+        let settings = PartitionSettings::default();
+        let policies = vec![policy("*", 90), policy("benton", 400)];
+
+        assert_eq!(drop_cutoff("sync_diffs", &settings, &policies, now), Some(now - chrono::Duration::days(400)));
+        assert_eq!(drop_cutoff("sync_diffs", &settings, &[], now), None);
+        assert_eq!(drop_cutoff("audit_log", &settings, &policies, now), None);
+
+        let settings = PartitionSettings { audit_log_retention_days: Some(2555), ..settings };
+        assert_eq!(drop_cutoff("audit_log", &settings, &policies, now), Some(now - chrono::Duration::days(2555)));
+    }
+}
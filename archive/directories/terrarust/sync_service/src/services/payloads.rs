@@ -0,0 +1,140 @@
+//! Encryption of sync diff payloads at rest.
+//!
+//! When PAYLOAD_ENCRYPTION_ENABLED is set, a maintenance job seals the
+//! `source_data` and `target_data` of sync diffs with their county's active
+//! key (see `terrafusion_common::encryption`). The same job moves sealed
+//! payloads onto a county's new key after a rotation, so it runs both on a
+//! schedule and right after each rotation. Reads decrypt through
+//! `open_payloads`; rows the sweep has not reached yet are still plaintext
+//! and pass through unchanged.
+
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use terrafusion_common::{Result, database::RotatingPool};
+use terrafusion_common::encryption::PayloadCipher;
+use terrafusion_common::jobs::{JobQueue, NewJob, Worker};
+
+use crate::models::database::SyncDiffQueries;
+
+/// Diffs sealed per query
+const SWEEP_BATCH_SIZE: i64 = 500;
+
+/// Queue and kind of payload sweeps
+pub const PAYLOAD_QUEUE: &str = "maintenance";
+pub const PAYLOAD_JOB: &str = "payloads.seal";
+
+/// Seals diff payloads still in plaintext or on a retired key
+#[derive(Clone)]
+pub struct PayloadSweep {
+    pool: RotatingPool,
+    cipher: PayloadCipher,
+}
+
+impl PayloadSweep {
+    pub fn new(pool: RotatingPool, cipher: PayloadCipher) -> Self {
+        Self { pool, cipher }
+    }
+
+    /// Seal every pending diff and return how many were updated. A payload
+    /// that cannot be sealed fails the run so the job retries it later.
+    pub async fn run(&self) -> Result<u64> {
+        let pool = self.pool.pool();
+        let mut updated = 0;
+        loop {
+            let rows = SyncDiffQueries::payloads_to_seal(&pool, SWEEP_BATCH_SIZE).await?;
+            let mut batch_updated = 0;
+            for row in &rows {
+                let source = self.reseal(&row.county_id, row.key_version, &row.source_data).await?;
+                let target = self.reseal(&row.county_id, row.key_version, &row.target_data).await?;
+                if source.is_none() && target.is_none() {
+                    continue;
+                }
+                SyncDiffQueries::update_payloads(
+                    &pool,
+                    row.id,
+                    source.as_ref().or(row.source_data.as_ref()),
+                    target.as_ref().or(row.target_data.as_ref()),
+                )
+                .await?;
+                batch_updated += 1;
+            }
+            updated += batch_updated;
+
+            // A batch that changed nothing would come back unchanged
+            if rows.len() < SWEEP_BATCH_SIZE as usize || batch_updated == 0 {
+                break;
+            }
+        }
+
+        if updated > 0 {
+            log::info!("Sealed payloads of {} sync diffs", updated);
+        }
+        Ok(updated)
+    }
+
+    async fn reseal(&self, county_id: &str, version: i32, payload: &Option<Value>) -> Result<Option<Value>> {
+        match payload {
+            Some(value) if !value.is_null() => self.cipher.reseal(county_id, version, value).await,
+            _ => Ok(None),
+        }
+    }
+
+    /// Handle payload sweeps on `worker`
+    pub fn register(&self, worker: Worker) -> Worker {
+        let sweep = self.clone();
+        worker.handle(PAYLOAD_JOB, move |_| {
+            let sweep = sweep.clone();
+            async move { sweep.run().await.map(|_| ()) }
+        })
+    }
+
+    /// Enqueue a sweep to run now, e.g. after a key rotation
+    pub async fn enqueue(queue: &JobQueue) -> Result<()> {
+        let job = NewJob::new(PAYLOAD_QUEUE, PAYLOAD_JOB, serde_json::json!({})).max_attempts(3);
+        queue.enqueue(job).await?;
+        Ok(())
+    }
+
+    /// Enqueue a sweep at every multiple of `interval`, once per slot across
+    /// instances (as with retention runs)
+    pub fn schedule(queue: JobQueue, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let slot = interval.as_secs().max(1) as i64;
+
+            loop {
+                ticker.tick().await;
+                let next = (Utc::now().timestamp() / slot + 1) * slot;
+                let Some(run_at) = DateTime::from_timestamp(next, 0) else {
+                    continue;
+                };
+                let job = NewJob::new(PAYLOAD_QUEUE, PAYLOAD_JOB, serde_json::json!({}))
+                    .run_at(run_at)
+                    .max_attempts(3)
+                    .unique(format!("{}:{}", PAYLOAD_JOB, next));
+                if let Err(e) = queue.enqueue(job).await {
+                    log::error!("Failed to schedule payload sealing: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Decrypt payloads of one county in place. Without a cipher they are left
+/// as stored.
+pub async fn open_payloads(
+    cipher: Option<&PayloadCipher>,
+    county_id: &str,
+    payloads: Vec<&mut Option<Value>>,
+) -> Result<()> {
+    let Some(cipher) = cipher else {
+        return Ok(());
+    };
+    for payload in payloads {
+        if let Some(value) = payload.take() {
+            *payload = Some(cipher.open(county_id, value).await?);
+        }
+    }
+    Ok(())
+}
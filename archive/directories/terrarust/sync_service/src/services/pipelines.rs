@@ -0,0 +1,856 @@
+//! Pipelines: sync pairs and exports run in dependency order.
+//!
+//! A county that syncs parcels, then improvements, then publishes an export
+//! defines the chain once and runs it as a whole:
+//!
+//! ```json
+//! {
+//!   "name": "Nightly assessor refresh",
+//!   "on_failure": "stop",
+//!   "steps": [
+//!     { "name": "parcels", "type": "sync", "sync_pair_id": "…" },
+//!     { "name": "improvements", "type": "sync", "sync_pair_id": "…", "depends_on": ["parcels"] },
+//!     { "name": "export", "type": "export", "export_format": "geojson",
+//!       "layers": ["parcels"], "depends_on": ["parcels", "improvements"], "on_failure": "continue" }
+//!   ]
+//! }
+//! ```
+//!
+//! Steps start as soon as every step they depend on has completed, so
+//! independent steps run side by side. When a step fails, its policy (or
+//! the pipeline's) decides what happens next. With `stop` no further steps
+//! start, though steps already running finish. With `continue` only the
+//! steps that depend on the failed one are skipped.
+//!
+//! A run is a job on the `pipelines` queue. The state of every step is
+//! saved as it changes, so a run picked up by another worker after a
+//! restart waits for the operations already started instead of starting
+//! them again.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sqlx::PgPool;
+use terrafusion_common::database::tenancy::with_county_filter;
+use terrafusion_common::database::RotatingPool;
+use terrafusion_common::http_client::{self, HttpClient};
+use terrafusion_common::jobs::{JobQueue, NewJob, Worker};
+use terrafusion_common::{Error, Result};
+use uuid::Uuid;
+
+use crate::models::database::SyncOperationQueries;
+use super::sync_engine::SyncEngine;
+
+/// Queue and kind of pipeline run jobs; the job id is the run id
+pub const PIPELINE_QUEUE: &str = "pipelines";
+pub const PIPELINE_RUN_JOB: &str = "pipeline.run";
+
+/// More steps than this is a sign the pipeline should be split
+const MAX_STEPS: usize = 50;
+
+/// How often a run checks on the operations and exports it started
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// What a failed step does to the rest of its run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    #[default]
+    Stop,
+    Continue,
+}
+
+impl FailurePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stop => "stop",
+            Self::Continue => "continue",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "stop" => Some(Self::Stop),
+            "continue" => Some(Self::Continue),
+            _ => None,
+        }
+    }
+}
+
+/// The work a step does
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepAction {
+    /// Run a sync pair of the pipeline's county
+    Sync {
+        sync_pair_id: Uuid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        custom_parameters: Option<Value>,
+    },
+    /// Create and process a GIS export of the pipeline's county
+    Export {
+        export_format: String,
+        layers: Vec<String>,
+        #[serde(default)]
+        area_of_interest: Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parameters: Option<Map<String, Value>>,
+    },
+}
+
+/// One step of a pipeline definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineStep {
+    pub name: String,
+    #[serde(flatten)]
+    pub action: StepAction,
+    /// Names of the steps that must complete first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Overrides the pipeline's policy when this step fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<FailurePolicy>,
+}
+
+/// A pipeline as stored
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Pipeline {
+    pub id: Uuid,
+    pub county_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub steps: Value,
+    pub on_failure: String,
+    pub is_active: bool,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Pipeline {
+    pub fn parsed_steps(&self) -> Result<Vec<PipelineStep>> {
+        serde_json::from_value(self.steps.clone())
+            .map_err(|e| Error::Serialization(format!("Invalid steps in pipeline {}: {}", self.id, e)))
+    }
+
+    pub fn failure_policy(&self) -> FailurePolicy {
+        FailurePolicy::parse(&self.on_failure).unwrap_or_default()
+    }
+}
+
+/// Body for creating or replacing a pipeline
+#[derive(Debug, Deserialize)]
+pub struct NewPipeline {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub steps: Vec<PipelineStep>,
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+    /// Filled in by the gateway with the signed-in user
+    #[serde(default)]
+    pub created_by: Option<String>,
+}
+
+/// Where a step of a run stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Skipped,
+    Canceled,
+}
+
+impl StepStatus {
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, Self::Pending | Self::Running)
+    }
+}
+
+/// Progress of one step within a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepRun {
+    pub name: String,
+    pub status: StepStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export_job_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl StepRun {
+    fn pending(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            status: StepStatus::Pending,
+            operation_id: None,
+            export_job_id: None,
+            started_at: None,
+            finished_at: None,
+            error: None,
+        }
+    }
+
+    fn finish(&mut self, status: StepStatus, error: Option<String>) {
+        self.status = status;
+        self.finished_at = Some(Utc::now());
+        self.error = error;
+    }
+}
+
+/// A run as stored
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PipelineRun {
+    pub id: Uuid,
+    pub pipeline_id: Uuid,
+    pub county_id: String,
+    pub status: String,
+    pub initiated_by: String,
+    pub steps: Value,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl PipelineRun {
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status.as_str(), "COMPLETED" | "FAILED" | "CANCELED")
+    }
+}
+
+/// Check a pipeline's name and steps
+pub fn validate(pipeline: &NewPipeline) -> Result<()> {
+    if pipeline.name.trim().is_empty() || pipeline.name.len() > 255 {
+        return Err(Error::Validation("Pipeline name must be 1-255 characters".to_string()));
+    }
+    execution_order(&pipeline.steps).map(|_| ())
+}
+
+/// Step indexes with every step after the steps it depends on. Fails on
+/// duplicate names, unknown dependencies and cycles.
+pub fn execution_order(steps: &[PipelineStep]) -> Result<Vec<usize>> {
+    if steps.is_empty() || steps.len() > MAX_STEPS {
+        return Err(Error::Validation(format!("A pipeline needs 1-{} steps", MAX_STEPS)));
+    }
+    let mut index = HashMap::new();
+    for (i, step) in steps.iter().enumerate() {
+        if step.name.trim().is_empty() || step.name.len() > 255 {
+            return Err(Error::Validation("Step names must be 1-255 characters".to_string()));
+        }
+        if index.insert(step.name.as_str(), i).is_some() {
+            return Err(Error::Validation(format!("More than one step is named {}", step.name)));
+        }
+        if let StepAction::Export { export_format, layers, .. } = &step.action {
+            if export_format.trim().is_empty() || layers.is_empty() {
+                return Err(Error::Validation(format!("Export step {} needs an export_format and layers", step.name)));
+            }
+        }
+    }
+
+    let mut waiting_on = vec![0usize; steps.len()];
+    let mut dependents = vec![Vec::new(); steps.len()];
+    for (i, step) in steps.iter().enumerate() {
+        let unique: HashSet<&str> = step.depends_on.iter().map(String::as_str).collect();
+        for dependency in unique {
+            let &d = index.get(dependency).ok_or_else(|| {
+                Error::Validation(format!("Step {} depends on unknown step {}", step.name, dependency))
+            })?;
+            if d == i {
+                return Err(Error::Validation(format!("Step {} depends on itself", step.name)));
+            }
+            waiting_on[i] += 1;
+            dependents[d].push(i);
+        }
+    }
+
+    let mut order: Vec<usize> = (0..steps.len()).filter(|&i| waiting_on[i] == 0).collect();
+    let mut next = 0;
+    while next < order.len() {
+        for &dependent in &dependents[order[next]] {
+            waiting_on[dependent] -= 1;
+            if waiting_on[dependent] == 0 {
+                order.push(dependent);
+            }
+        }
+        next += 1;
+    }
+    if order.len() < steps.len() {
+        let cycle: Vec<&str> = (0..steps.len())
+            .filter(|&i| waiting_on[i] > 0)
+            .map(|i| steps[i].name.as_str())
+            .collect();
+        return Err(Error::Validation(format!("Steps {} depend on each other", cycle.join(", "))));
+    }
+    Ok(order)
+}
+
+/// Skip the pending steps that can no longer run and return the ones that
+/// are ready to start. `order` comes from `execution_order`, so a skip
+/// carries through to every step downstream in one pass.
+pub fn advance(
+    steps: &[PipelineStep],
+    runs: &mut [StepRun],
+    order: &[usize],
+    default_policy: FailurePolicy,
+) -> Vec<usize> {
+    let index: HashMap<&str, usize> = steps.iter().enumerate().map(|(i, step)| (step.name.as_str(), i)).collect();
+    let halted_by = steps
+        .iter()
+        .zip(runs.iter())
+        .find(|(step, run)| {
+            run.status == StepStatus::Failed && step.on_failure.unwrap_or(default_policy) == FailurePolicy::Stop
+        })
+        .map(|(step, _)| step.name.clone());
+
+    let mut ready = Vec::new();
+    for &i in order {
+        if runs[i].status != StepStatus::Pending {
+            continue;
+        }
+        if let Some(failed) = &halted_by {
+            runs[i].finish(StepStatus::Skipped, Some(format!("Step {} failed and stopped the pipeline", failed)));
+            continue;
+        }
+        let statuses: Vec<(&str, StepStatus)> = steps[i]
+            .depends_on
+            .iter()
+            .filter_map(|name| index.get(name.as_str()).map(|&d| (name.as_str(), runs[d].status)))
+            .collect();
+        if let Some((blocked_by, _)) = statuses
+            .iter()
+            .find(|(_, status)| status.is_finished() && *status != StepStatus::Completed)
+        {
+            runs[i].finish(StepStatus::Skipped, Some(format!("Step {} did not complete", blocked_by)));
+        } else if statuses.iter().all(|(_, status)| *status == StepStatus::Completed) {
+            ready.push(i);
+        }
+    }
+    ready
+}
+
+/// Final status of a run whose steps have all finished, or `None`
+pub fn run_outcome(runs: &[StepRun]) -> Option<&'static str> {
+    if runs.iter().any(|run| !run.status.is_finished()) {
+        return None;
+    }
+    if runs.iter().any(|run| run.status == StepStatus::Canceled) {
+        Some("CANCELED")
+    } else if runs.iter().all(|run| run.status == StepStatus::Completed) {
+        Some("COMPLETED")
+    } else {
+        Some("FAILED")
+    }
+}
+
+/// Database access for pipelines and their runs
+pub struct PipelineQueries;
+
+impl PipelineQueries {
+    pub async fn list(pool: &PgPool, county_id: Option<&str>) -> Result<Vec<Pipeline>> {
+        let sql = with_county_filter("SELECT * FROM pipelines WHERE TRUE", county_id, 1);
+        let mut query = sqlx::query_as::<_, Pipeline>(&format!("{} ORDER BY name", sql));
+        if let Some(county_id) = county_id {
+            query = query.bind(county_id);
+        }
+        Ok(query.fetch_all(pool).await?)
+    }
+
+    pub async fn get(pool: &PgPool, pipeline_id: Uuid) -> Result<Option<Pipeline>> {
+        Ok(sqlx::query_as::<_, Pipeline>("SELECT * FROM pipelines WHERE id = $1")
+            .bind(pipeline_id)
+            .fetch_optional(pool)
+            .await?)
+    }
+
+    pub async fn create(pool: &PgPool, county_id: &str, pipeline: &NewPipeline) -> Result<Pipeline> {
+        sqlx::query_as::<_, Pipeline>(
+            r#"
+            INSERT INTO pipelines (id, county_id, name, description, steps, on_failure, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(county_id)
+        .bind(&pipeline.name)
+        .bind(&pipeline.description)
+        .bind(json!(pipeline.steps))
+        .bind(pipeline.on_failure.as_str())
+        .bind(&pipeline.created_by)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| duplicate_name(e, &pipeline.name))
+    }
+
+    /// Replace a pipeline's definition; runs in progress keep the steps they started with
+    pub async fn update(pool: &PgPool, pipeline_id: Uuid, pipeline: &NewPipeline) -> Result<Option<Pipeline>> {
+        sqlx::query_as::<_, Pipeline>(
+            r#"
+            UPDATE pipelines
+            SET name = $2, description = $3, steps = $4, on_failure = $5, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(pipeline_id)
+        .bind(&pipeline.name)
+        .bind(&pipeline.description)
+        .bind(json!(pipeline.steps))
+        .bind(pipeline.on_failure.as_str())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| duplicate_name(e, &pipeline.name))
+    }
+
+    pub async fn set_active(pool: &PgPool, pipeline_id: Uuid, is_active: bool) -> Result<bool> {
+        let result = sqlx::query("UPDATE pipelines SET is_active = $2, updated_at = NOW() WHERE id = $1")
+            .bind(pipeline_id)
+            .bind(is_active)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete(pool: &PgPool, pipeline_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM pipelines WHERE id = $1")
+            .bind(pipeline_id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// A new run with every step pending. The steps are copied into the run,
+    /// so editing the pipeline does not change a run in progress.
+    pub async fn create_run(pool: &PgPool, pipeline: &Pipeline, initiated_by: &str) -> Result<PipelineRun> {
+        let steps: Vec<StepRun> = pipeline.parsed_steps()?.iter().map(|step| StepRun::pending(&step.name)).collect();
+        Ok(sqlx::query_as::<_, PipelineRun>(
+            r#"
+            INSERT INTO pipeline_runs (id, pipeline_id, county_id, initiated_by, steps)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(pipeline.id)
+        .bind(&pipeline.county_id)
+        .bind(initiated_by)
+        .bind(json!({ "definition": pipeline.steps, "on_failure": pipeline.on_failure, "runs": steps }))
+        .fetch_one(pool)
+        .await?)
+    }
+
+    pub async fn get_run(pool: &PgPool, run_id: Uuid) -> Result<Option<PipelineRun>> {
+        Ok(sqlx::query_as::<_, PipelineRun>("SELECT * FROM pipeline_runs WHERE id = $1")
+            .bind(run_id)
+            .fetch_optional(pool)
+            .await?)
+    }
+
+    pub async fn list_runs(pool: &PgPool, pipeline_id: Uuid, limit: i64) -> Result<Vec<PipelineRun>> {
+        Ok(sqlx::query_as::<_, PipelineRun>(
+            "SELECT * FROM pipeline_runs WHERE pipeline_id = $1 ORDER BY created_at DESC LIMIT $2",
+        )
+        .bind(pipeline_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?)
+    }
+
+    async fn mark_running(pool: &PgPool, run_id: Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE pipeline_runs SET status = 'RUNNING', started_at = COALESCE(started_at, NOW()) WHERE id = $1 AND status = 'PENDING'",
+        )
+        .bind(run_id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn save_steps(pool: &PgPool, run_id: Uuid, steps: &Value) -> Result<()> {
+        sqlx::query("UPDATE pipeline_runs SET steps = $2 WHERE id = $1")
+            .bind(run_id)
+            .bind(steps)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn finish_run(pool: &PgPool, run_id: Uuid, status: &str, error_message: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE pipeline_runs SET status = $2, error_message = $3, finished_at = NOW() WHERE id = $1")
+            .bind(run_id)
+            .bind(status)
+            .bind(error_message)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Ask a run to stop. Its worker cancels the operations and exports it
+    /// started on its next check.
+    pub async fn cancel_run(pool: &PgPool, run_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE pipeline_runs SET status = 'CANCELED' WHERE id = $1 AND status IN ('PENDING', 'RUNNING')",
+        )
+        .bind(run_id)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn run_status(pool: &PgPool, run_id: Uuid) -> Result<Option<String>> {
+        Ok(sqlx::query_scalar::<_, String>("SELECT status FROM pipeline_runs WHERE id = $1")
+            .bind(run_id)
+            .fetch_optional(pool)
+            .await?)
+    }
+}
+
+fn duplicate_name(error: sqlx::Error, name: &str) -> Error {
+    match error {
+        sqlx::Error::Database(db) if db.constraint() == Some("pipelines_county_id_name_key") => {
+            Error::Conflict(format!("The county already has a pipeline named {}", name))
+        }
+        e => e.into(),
+    }
+}
+
+/// What a run stores in its `steps` column: the definition it runs and how
+/// far each step got
+#[derive(Debug, Serialize, Deserialize)]
+struct RunState {
+    definition: Vec<PipelineStep>,
+    #[serde(default)]
+    on_failure: FailurePolicy,
+    runs: Vec<StepRun>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueuedRun {
+    pipeline_id: Uuid,
+}
+
+/// Answer of the GIS export service about a job
+#[derive(Debug, Deserialize)]
+struct ExportJob {
+    job_id: Uuid,
+    status: String,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// Runs pipelines claimed from the job queue
+#[derive(Clone)]
+pub struct PipelineRunner {
+    db_pool: RotatingPool,
+    engine: SyncEngine,
+    client: HttpClient,
+    gis_export_url: String,
+}
+
+impl PipelineRunner {
+    pub fn new(db_pool: RotatingPool, engine: SyncEngine, gis_export_url: &str) -> Self {
+        Self {
+            db_pool,
+            engine,
+            client: http_client::shared().clone(),
+            gis_export_url: gis_export_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Queue a run for a worker
+    pub async fn enqueue(jobs: &JobQueue, run: &PipelineRun) -> Result<()> {
+        jobs.enqueue(
+            NewJob::new(PIPELINE_QUEUE, PIPELINE_RUN_JOB, json!({ "pipeline_id": run.pipeline_id }))
+                .id(run.id)
+                .max_attempts(5),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub fn register(&self, worker: Worker) -> Worker {
+        let runner = self.clone();
+        worker.handle(PIPELINE_RUN_JOB, move |job| {
+            let runner = runner.clone();
+            async move {
+                let queued: QueuedRun = job.payload()?;
+                if job.attempts > 1 {
+                    log::warn!("Resuming run {} of pipeline {} (attempt {})", job.id, queued.pipeline_id, job.attempts);
+                }
+                runner.run(job.id).await
+            }
+        })
+    }
+
+    /// Drive a run until every step has finished or the run is canceled
+    pub async fn run(&self, run_id: Uuid) -> Result<()> {
+        let pool = self.db_pool.pool();
+        let Some(run) = PipelineQueries::get_run(&pool, run_id).await? else {
+            log::warn!("Pipeline run {} no longer exists", run_id);
+            return Ok(());
+        };
+        if run.is_finished() {
+            return Ok(());
+        }
+        let mut state: RunState = serde_json::from_value(run.steps.clone())
+            .map_err(|e| Error::Serialization(format!("Invalid state of pipeline run {}: {}", run_id, e)))?;
+        let order = execution_order(&state.definition)?;
+        PipelineQueries::mark_running(&pool, run_id).await?;
+        log::info!("Running pipeline run {} ({} steps)", run_id, state.definition.len());
+
+        loop {
+            if PipelineQueries::run_status(&pool, run_id).await?.as_deref() == Some("CANCELED") {
+                self.cancel_steps(&mut state).await;
+                PipelineQueries::save_steps(&pool, run_id, &json!(state)).await?;
+                PipelineQueries::finish_run(&pool, run_id, "CANCELED", None).await?;
+                log::info!("Pipeline run {} canceled", run_id);
+                return Ok(());
+            }
+
+            for i in 0..state.runs.len() {
+                if state.runs[i].status == StepStatus::Running {
+                    self.check_step(&state.definition[i], &mut state.runs[i]).await?;
+                }
+            }
+            for i in advance(&state.definition, &mut state.runs, &order, state.on_failure) {
+                self.start_step(&run, &state.definition[i], &mut state.runs[i]).await;
+            }
+            PipelineQueries::save_steps(&pool, run_id, &json!(state)).await?;
+
+            if let Some(outcome) = run_outcome(&state.runs) {
+                let failed: Vec<&str> = state
+                    .runs
+                    .iter()
+                    .filter(|step| step.status == StepStatus::Failed)
+                    .map(|step| step.name.as_str())
+                    .collect();
+                let error = (!failed.is_empty()).then(|| format!("Failed steps: {}", failed.join(", ")));
+                PipelineQueries::finish_run(&pool, run_id, outcome, error.as_deref()).await?;
+                log::info!("Pipeline run {} finished: {}", run_id, outcome);
+                return Ok(());
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Start a step; a step that cannot start fails right away
+    async fn start_step(&self, run: &PipelineRun, step: &PipelineStep, state: &mut StepRun) {
+        state.started_at = Some(Utc::now());
+        let initiated_by = format!("pipeline:{}", run.id);
+        let started = match &step.action {
+            StepAction::Sync { sync_pair_id, custom_parameters } => self
+                .engine
+                .start_sync_operation(*sync_pair_id, initiated_by, custom_parameters.clone())
+                .await
+                .map(|operation_id| state.operation_id = Some(operation_id)),
+            StepAction::Export { .. } => self
+                .start_export(run, step)
+                .await
+                .map(|job_id| state.export_job_id = Some(job_id)),
+        };
+        match started {
+            Ok(()) => {
+                state.status = StepStatus::Running;
+                log::info!("Pipeline run {} started step {}", run.id, step.name);
+            }
+            Err(e) => {
+                log::warn!("Pipeline run {} could not start step {}: {}", run.id, step.name, e);
+                state.finish(StepStatus::Failed, Some(e.to_string()));
+            }
+        }
+    }
+
+    /// Record the outcome of a running step once its operation or export ends
+    async fn check_step(&self, step: &PipelineStep, state: &mut StepRun) -> Result<()> {
+        if let Some(operation_id) = state.operation_id {
+            let Some(operation) = SyncOperationQueries::get_by_id(&self.db_pool.pool(), operation_id).await? else {
+                return Ok(());
+            };
+            match operation.status.to_uppercase().as_str() {
+                "COMPLETED" => state.finish(StepStatus::Completed, None),
+                "FAILED" => state.finish(StepStatus::Failed, operation.error_message),
+                "CANCELED" | "CANCELLED" => state.finish(
+                    StepStatus::Failed,
+                    Some(operation.error_message.unwrap_or_else(|| "Sync operation was canceled".to_string())),
+                ),
+                _ => {}
+            }
+        } else if let Some(job_id) = state.export_job_id {
+            let job = match self.export_job(job_id).await {
+                Ok(job) => job,
+                Err(e) => {
+                    // The export service may be restarting; try again next time
+                    log::warn!("Could not check export {} of step {}: {}", job_id, step.name, e);
+                    return Ok(());
+                }
+            };
+            match job.status.as_str() {
+                "COMPLETED" => state.finish(StepStatus::Completed, None),
+                "FAILED" | "REJECTED" | "CANCELLED" => state.finish(
+                    StepStatus::Failed,
+                    Some(job.message.unwrap_or_else(|| format!("Export {}", job.status.to_lowercase()))),
+                ),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Cancel what a canceled run still has going and mark its steps
+    async fn cancel_steps(&self, state: &mut RunState) {
+        for step in state.runs.iter_mut() {
+            match step.status {
+                StepStatus::Pending => step.finish(StepStatus::Canceled, None),
+                StepStatus::Running => {
+                    if let Some(operation_id) = step.operation_id {
+                        // Whichever instance runs the operation stops it after its current batch
+                        let canceled = SyncOperationQueries::update_status(
+                            &self.db_pool.pool(),
+                            operation_id,
+                            "CANCELED",
+                            Some(Utc::now()),
+                            Some("Pipeline run canceled"),
+                        )
+                        .await;
+                        if let Err(e) = canceled {
+                            log::warn!("Failed to cancel sync operation {}: {}", operation_id, e);
+                        }
+                    }
+                    if let Some(job_id) = step.export_job_id {
+                        let url = format!("{}/gis-export/jobs/{}/cancel", self.gis_export_url, job_id);
+                        if let Err(e) = self.client.post(&url).send().await {
+                            log::warn!("Failed to cancel export {}: {}", job_id, e);
+                        }
+                    }
+                    step.finish(StepStatus::Canceled, None);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Create the export and queue it. Exports that need sign-off wait for
+    /// it; the step stays running until the export ends either way.
+    async fn start_export(&self, run: &PipelineRun, step: &PipelineStep) -> Result<Uuid> {
+        let StepAction::Export { export_format, layers, area_of_interest, parameters } = &step.action else {
+            return Err(Error::Internal(format!("Step {} is not an export", step.name)));
+        };
+        let response = self
+            .client
+            .post(format!("{}/gis-export/jobs", self.gis_export_url))
+            .json(&json!({
+                "county_id": run.county_id,
+                "username": run.initiated_by,
+                "export_format": export_format,
+                "area_of_interest": area_of_interest,
+                "layers": layers,
+                "parameters": parameters,
+            }))
+            .send()
+            .await?;
+        let job: ExportJob = export_response(response).await?;
+
+        if job.status == "PENDING" {
+            let response = self
+                .client
+                .post(format!("{}/gis-export/jobs/{}/process", self.gis_export_url, job.job_id))
+                .send()
+                .await?;
+            export_response::<Value>(response).await?;
+        }
+        Ok(job.job_id)
+    }
+
+    async fn export_job(&self, job_id: Uuid) -> Result<ExportJob> {
+        let response = self
+            .client
+            .get(format!("{}/gis-export/jobs/{}", self.gis_export_url, job_id))
+            .send()
+            .await?;
+        export_response(response).await
+    }
+}
+
+/// The body of a successful export service response, or its error message
+async fn export_response<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    if !status.is_success() {
+        let message = body["error"]["message"].as_str().unwrap_or("no details");
+        return Err(Error::ExternalService(format!("GIS export service returned {}: {}", status, message)));
+    }
+    serde_json::from_value(body).map_err(|e| Error::ExternalService(format!("Invalid GIS export service response: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(name: &str, depends_on: &[&str], on_failure: Option<FailurePolicy>) -> PipelineStep {
+        PipelineStep {
+            name: name.to_string(),
+            action: StepAction::Sync { sync_pair_id: Uuid::nil(), custom_parameters: None },
+            depends_on: depends_on.iter().map(|name| name.to_string()).collect(),
+            on_failure,
+        }
+    }
+
+    #[test]
+    fn test_execution_order() {
+        let parsed: PipelineStep = serde_json::from_value(json!({
+            "name": "export", "type": "export", "export_format": "geojson", "layers": ["parcels"], "depends_on": ["parcels"]
+        }))
+        .unwrap();
+        assert!(matches!(parsed.action, StepAction::Export { .. }));
+
+        let steps = vec![step("export", &["parcels", "improvements"], None), step("improvements", &["parcels"], None), step("parcels", &[], None)];
+        assert_eq!(execution_order(&steps).unwrap(), vec![2, 1, 0]);
+
+        assert!(execution_order(&[]).is_err());
+        assert!(execution_order(&[step("a", &[], None), step("a", &[], None)]).is_err());
+        assert!(execution_order(&[step("a", &["b"], None)]).is_err());
+        assert!(execution_order(&[step("a", &["b"], None), step("b", &["a"], None)]).is_err());
+    }
+
+    #[test]
+    fn test_advance() {
+        let steps = vec![
+            step("parcels", &[], Some(FailurePolicy::Continue)),
+            step("improvements", &["parcels"], None),
+            step("export", &["improvements"], None),
+            step("owners", &[], None),
+        ];
+        let order = execution_order(&steps).unwrap();
+        let mut runs: Vec<StepRun> = steps.iter().map(|step| StepRun::pending(&step.name)).collect();
+        assert_eq!(advance(&steps, &mut runs, &order, FailurePolicy::Stop), vec![0, 3]);
+
+        // A failure with `continue` skips everything downstream, but not independent steps
+        runs[0].status = StepStatus::Failed;
+        runs[3].status = StepStatus::Running;
+        assert!(advance(&steps, &mut runs, &order, FailurePolicy::Stop).is_empty());
+        assert_eq!(runs[1].status, StepStatus::Skipped);
+        assert_eq!(runs[2].status, StepStatus::Skipped);
+        assert_eq!(run_outcome(&runs), None);
+        runs[3].status = StepStatus::Completed;
+        assert_eq!(run_outcome(&runs), Some("FAILED"));
+
+        // With `stop` nothing else starts
+        let mut runs: Vec<StepRun> = steps.iter().map(|step| StepRun::pending(&step.name)).collect();
+        runs[3].status = StepStatus::Failed;
+        assert!(advance(&steps, &mut runs, &order, FailurePolicy::Stop).is_empty());
+        assert!(runs.iter().all(|run| run.status.is_finished()));
+    }
+}
@@ -0,0 +1,365 @@
+//! CSV and XLSX downloads of list endpoints, for reports opened in Excel
+
+use actix_web::{http::header, web::Bytes, HttpRequest, HttpResponse};
+use futures::{SinkExt, TryStreamExt};
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::PgPool;
+use terrafusion_common::{Error, Result};
+use terrafusion_common::encryption::PayloadCipher;
+use crate::models::database::{AuditLogRow, EntityHistoryRow, SyncOperationRow};
+use crate::services::payloads;
+
+const XLSX_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// Rows buffered between the database and the client while streaming CSV
+const CSV_CHANNEL_CAPACITY: usize = 64;
+
+/// Representation requested for a list endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    Xlsx,
+}
+
+impl ReportFormat {
+    /// `?format=` wins; otherwise the `Accept` header decides, defaulting to JSON
+    pub fn negotiate(req: &HttpRequest, format: Option<&str>) -> Result<Self> {
+        if let Some(format) = format {
+            return match format.to_lowercase().as_str() {
+                "json" => Ok(ReportFormat::Json),
+                "csv" => Ok(ReportFormat::Csv),
+                "xlsx" => Ok(ReportFormat::Xlsx),
+                other => Err(Error::Validation(format!("Unsupported format: {} (use json, csv or xlsx)", other))),
+            };
+        }
+
+        let accept = req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        Ok(if accept.contains("text/csv") {
+            ReportFormat::Csv
+        } else if accept.contains(XLSX_CONTENT_TYPE) {
+            ReportFormat::Xlsx
+        } else {
+            ReportFormat::Json
+        })
+    }
+
+    pub fn is_json(&self) -> bool {
+        *self == ReportFormat::Json
+    }
+
+    /// Most rows in one download. XLSX is built in memory, so it gets less.
+    pub fn row_limit(&self) -> i64 {
+        match self {
+            ReportFormat::Json | ReportFormat::Csv => 1_000_000,
+            ReportFormat::Xlsx => 100_000,
+        }
+    }
+}
+
+/// One spreadsheet cell
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Number(f64),
+    Empty,
+}
+
+impl From<String> for Cell {
+    fn from(value: String) -> Self {
+        Cell::Text(value)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(value: &str) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Cell::Empty)
+    }
+}
+
+impl From<i32> for Cell {
+    fn from(value: i32) -> Self {
+        Cell::Number(value as f64)
+    }
+}
+
+impl From<chrono::DateTime<chrono::Utc>> for Cell {
+    fn from(value: chrono::DateTime<chrono::Utc>) -> Self {
+        Cell::Text(value.to_rfc3339())
+    }
+}
+
+impl From<uuid::Uuid> for Cell {
+    fn from(value: uuid::Uuid) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+impl From<serde_json::Value> for Cell {
+    fn from(value: serde_json::Value) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+impl Cell {
+    /// CSV text. Values Excel would evaluate as a formula are prefixed with
+    /// `'` so a crafted record cannot run one on the clerk's machine.
+    fn to_csv(&self) -> String {
+        match self {
+            Cell::Text(text) if text.starts_with(['=', '+', '-', '@', '\t', '\r']) => format!("'{}", text),
+            Cell::Text(text) => text.clone(),
+            Cell::Number(number) => number.to_string(),
+            Cell::Empty => String::new(),
+        }
+    }
+}
+
+/// A database row that can be written as a spreadsheet line
+pub trait ReportRow {
+    const COLUMNS: &'static [&'static str];
+
+    fn cells(&self) -> Vec<Cell>;
+
+    /// County and payload columns that may be stored encrypted
+    fn encrypted_payloads(&mut self) -> Option<(&str, Vec<&mut Option<serde_json::Value>>)> {
+        None
+    }
+}
+
+impl ReportRow for SyncOperationRow {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "sync_pair_id", "status", "start_time", "end_time", "records_processed",
+        "records_succeeded", "records_failed", "initiated_by", "error_message", "created_at",
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.sync_pair_id.into(),
+            self.status.as_str().into(),
+            self.start_time.into(),
+            self.end_time.into(),
+            self.records_processed.into(),
+            self.records_succeeded.into(),
+            self.records_failed.into(),
+            self.initiated_by.as_str().into(),
+            self.error_message.clone().into(),
+            self.created_at.into(),
+        ]
+    }
+}
+
+impl ReportRow for EntityHistoryRow {
+    const COLUMNS: &'static [&'static str] = &[
+        "diff_id", "sync_operation_id", "sync_pair_id", "sync_pair_name", "county_id", "change_type",
+        "sync_status", "source_data", "target_data", "diff_details", "error_message", "initiated_by", "changed_at",
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.diff_id.into(),
+            self.sync_operation_id.into(),
+            self.sync_pair_id.into(),
+            self.sync_pair_name.as_str().into(),
+            self.county_id.as_str().into(),
+            self.change_type.as_str().into(),
+            self.sync_status.as_str().into(),
+            self.source_data.clone().into(),
+            self.target_data.clone().into(),
+            self.diff_details.clone().into(),
+            self.error_message.clone().into(),
+            self.initiated_by.as_str().into(),
+            self.created_at.into(),
+        ]
+    }
+
+    fn encrypted_payloads(&mut self) -> Option<(&str, Vec<&mut Option<serde_json::Value>>)> {
+        Some((self.county_id.as_str(), vec![&mut self.source_data, &mut self.target_data]))
+    }
+}
+
+impl ReportRow for AuditLogRow {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "created_at", "severity", "event_type", "resource_type", "resource_id", "description",
+        "username", "user_id", "county_id", "ip_address", "operation_id", "correlation_id",
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.created_at.into(),
+            self.severity.as_str().into(),
+            self.event_type.as_str().into(),
+            self.resource_type.as_str().into(),
+            self.resource_id.clone().into(),
+            self.description.as_str().into(),
+            self.username.clone().into(),
+            self.user_id.clone().into(),
+            self.county_id.clone().into(),
+            self.ip_address.clone().into(),
+            self.operation_id.into(),
+            self.correlation_id.clone().into(),
+        ]
+    }
+}
+
+fn csv_line<I: IntoIterator<Item = String>>(fields: I) -> Result<Bytes> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(fields)
+        .map_err(|e| Error::Internal(format!("Failed to write CSV row: {}", e)))?;
+    let line = writer
+        .into_inner()
+        .map_err(|e| Error::Internal(format!("Failed to write CSV row: {}", e)))?;
+    Ok(Bytes::from(line))
+}
+
+async fn open_row<T: ReportRow>(cipher: Option<&PayloadCipher>, row: &mut T) -> Result<()> {
+    match row.encrypted_payloads() {
+        Some((county_id, fields)) => payloads::open_payloads(cipher, county_id, fields).await,
+        None => Ok(()),
+    }
+}
+
+/// Run `sql` (without a LIMIT) and send its rows as a CSV or XLSX download.
+///
+/// CSV is streamed row by row as the query yields them; XLSX is assembled
+/// once all rows are read. Encrypted payloads are decrypted with `cipher`.
+pub async fn download<T>(
+    format: ReportFormat,
+    name: &str,
+    pool: PgPool,
+    sql: String,
+    args: PgArguments,
+    cipher: Option<PayloadCipher>,
+) -> Result<HttpResponse>
+where
+    T: ReportRow + for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin + 'static,
+{
+    let sql = format!("{} LIMIT {}", sql, format.row_limit());
+    let filename = format!("{}-{}", safe_filename(name), chrono::Utc::now().format("%Y%m%d-%H%M%S"));
+
+    match format {
+        ReportFormat::Json => Err(Error::Internal("JSON listings are not downloads".to_string())),
+        ReportFormat::Csv => {
+            let header_line = csv_line(T::COLUMNS.iter().map(|c| c.to_string()))?;
+            let report_name = name.to_string();
+            let (mut sender, receiver) = futures::channel::mpsc::channel::<std::result::Result<Bytes, actix_web::Error>>(CSV_CHANNEL_CAPACITY);
+
+            tokio::spawn(async move {
+                if sender.send(Ok(header_line)).await.is_err() {
+                    return;
+                }
+                let mut rows = sqlx::query_as_with::<_, T, _>(&sql, args).fetch(&pool);
+                loop {
+                    let line = match rows.try_next().await {
+                        Ok(Some(mut row)) => match open_row(cipher.as_ref(), &mut row).await {
+                            Ok(()) => csv_line(row.cells().iter().map(Cell::to_csv)),
+                            Err(e) => Err(e),
+                        },
+                        Ok(None) => break,
+                        Err(e) => Err(e.into()),
+                    };
+                    let failed = line.is_err();
+                    let line = line.map_err(|e| {
+                        log::error!("CSV report {} failed: {}", report_name, e);
+                        actix_web::error::ErrorInternalServerError("Report generation failed")
+                    });
+                    // A send error means the client went away
+                    if sender.send(line).await.is_err() || failed {
+                        break;
+                    }
+                }
+            });
+
+            Ok(HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.csv\"", filename)))
+                .streaming(receiver))
+        }
+        ReportFormat::Xlsx => {
+            let mut rows = sqlx::query_as_with::<_, T, _>(&sql, args).fetch_all(&pool).await?;
+            for row in rows.iter_mut() {
+                open_row(cipher.as_ref(), row).await?;
+            }
+            let workbook = tokio::task::spawn_blocking(move || build_workbook(&rows))
+                .await
+                .map_err(|e| Error::Internal(format!("XLSX task failed: {}", e)))??;
+
+            Ok(HttpResponse::Ok()
+                .content_type(XLSX_CONTENT_TYPE)
+                .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.xlsx\"", filename)))
+                .body(workbook))
+        }
+    }
+}
+
+/// Keep names built from path segments from breaking the Content-Disposition header
+fn safe_filename(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+fn build_workbook<T: ReportRow>(rows: &[T]) -> Result<Vec<u8>> {
+    use rust_xlsxwriter::{Format, Workbook};
+
+    let xlsx_error = |e: rust_xlsxwriter::XlsxError| Error::Internal(format!("Failed to build XLSX: {}", e));
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    let bold = Format::new().set_bold();
+
+    for (col, column) in T::COLUMNS.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *column, &bold).map_err(xlsx_error)?;
+    }
+    for (index, row) in rows.iter().enumerate() {
+        let line = index as u32 + 1;
+        for (col, cell) in row.cells().into_iter().enumerate() {
+            match cell {
+                Cell::Text(text) => sheet.write_string(line, col as u16, &text).map(|_| ()),
+                Cell::Number(number) => sheet.write_number(line, col as u16, number).map(|_| ()),
+                Cell::Empty => Ok(()),
+            }
+            .map_err(xlsx_error)?;
+        }
+    }
+    sheet.set_freeze_panes(1, 0).map_err(xlsx_error)?;
+
+    workbook.save_to_buffer().map_err(xlsx_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_negotiation_and_csv_cells() {
+        let req = TestRequest::default().insert_header((header::ACCEPT, "text/csv")).to_http_request();
+        assert_eq!(ReportFormat::negotiate(&req, None).unwrap(), ReportFormat::Csv);
+        assert_eq!(ReportFormat::negotiate(&req, Some("XLSX")).unwrap(), ReportFormat::Xlsx);
+        assert!(ReportFormat::negotiate(&req, Some("pdf")).is_err());
+
+        let req = TestRequest::default().to_http_request();
+        assert!(ReportFormat::negotiate(&req, None).unwrap().is_json());
+
+        assert_eq!(Cell::from("=HYPERLINK(\"x\")").to_csv(), "'=HYPERLINK(\"x\")");
+        assert_eq!(Cell::from(Some(42)).to_csv(), "42");
+        assert_eq!(Cell::from(None::<String>).to_csv(), "");
+
+        assert_eq!(safe_filename("parcel-R\"12/3"), "parcel-R_12_3");
+
+        let line = csv_line(vec!["a,b".to_string(), "c".to_string()]).unwrap();
+        assert_eq!(&line[..], b"\"a,b\",c\n");
+    }
+}
@@ -0,0 +1,234 @@
+//! Data retention: deletes old sync diffs and rolls per-operation stats
+//! into daily totals, following per-county policies. Whole months past
+//! every policy are dropped as partitions by `partitions::PartitionMaintenance`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use prometheus::{register_histogram, register_int_counter_vec, register_int_gauge, Histogram, IntCounterVec, IntGauge};
+use serde::Serialize;
+use terrafusion_common::{Error, Result, database::RotatingPool};
+use terrafusion_common::jobs::{JobQueue, NewJob, Worker};
+use terrafusion_common::tenancy::ALL_COUNTIES;
+
+use crate::models::database::{RetentionPolicyRow, RetentionQueries};
+
+/// Rows deleted per statement, so large backlogs don't hold long locks
+const DELETE_BATCH_SIZE: i64 = 5_000;
+
+/// Queue and kind of scheduled retention runs
+pub const RETENTION_QUEUE: &str = "maintenance";
+pub const RETENTION_JOB: &str = "retention.run";
+
+lazy_static! {
+    /// Rows removed by retention, by table and county
+    pub static ref RETENTION_ROWS_RECLAIMED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "retention_rows_reclaimed_total",
+        "Rows deleted by the data retention job",
+        &["table", "county_id"]
+    ).expect("Failed to register retention_rows_reclaimed_total");
+
+    pub static ref RETENTION_RUN_DURATION_SECONDS: Histogram = register_histogram!(
+        "retention_run_duration_seconds",
+        "Duration of data retention runs",
+        vec![0.1, 0.5, 1.0, 5.0, 15.0, 60.0, 300.0, 900.0]
+    ).expect("Failed to register retention_run_duration_seconds");
+
+    pub static ref RETENTION_LAST_SUCCESS_TIMESTAMP: IntGauge = register_int_gauge!(
+        "retention_last_success_timestamp_seconds",
+        "Unix time the data retention job last completed"
+    ).expect("Failed to register retention_last_success_timestamp_seconds");
+}
+
+/// Retention periods in effect for one county
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RetentionPeriods {
+    pub diff_retention_days: i32,
+    pub stats_retention_days: i32,
+}
+
+impl RetentionPeriods {
+    /// The county's own policy, else the `*` default. `None` when neither
+    /// exists, in which case nothing is deleted.
+    pub fn for_county(policies: &[RetentionPolicyRow], county_id: &str) -> Option<Self> {
+        policies
+            .iter()
+            .find(|p| p.county_id == county_id)
+            .or_else(|| policies.iter().find(|p| p.county_id == ALL_COUNTIES))
+            .map(|p| Self {
+                diff_retention_days: p.diff_retention_days,
+                stats_retention_days: p.stats_retention_days,
+            })
+    }
+
+    pub fn diff_cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - chrono::Duration::days(self.diff_retention_days as i64)
+    }
+
+    pub fn stats_cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - chrono::Duration::days(self.stats_retention_days as i64)
+    }
+}
+
+/// What one run reclaimed for one county
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionReport {
+    pub county_id: String,
+    pub periods: RetentionPeriods,
+    pub diffs_deleted: u64,
+    pub stats_rolled_up: i64,
+}
+
+/// Runs retention on a schedule or on demand, never two runs at once
+#[derive(Clone)]
+pub struct RetentionJob {
+    pool: RotatingPool,
+    running: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl RetentionJob {
+    pub fn new(pool: RotatingPool) -> Self {
+        Self {
+            pool,
+            running: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// Apply retention to one county, or to every county with sync history
+    pub async fn run(&self, county_id: Option<&str>) -> Result<Vec<RetentionReport>> {
+        let _guard = self
+            .running
+            .try_lock()
+            .map_err(|_| Error::Conflict("A retention run is already in progress".to_string()))?;
+
+        let started = Instant::now();
+        let pool = self.pool.pool();
+        let policies = RetentionQueries::list_policies(&pool).await?;
+        let counties = match county_id {
+            Some(county_id) => vec![county_id.to_string()],
+            None => RetentionQueries::counties(&pool).await?,
+        };
+
+        let now = Utc::now();
+        let mut reports = Vec::new();
+        for county_id in counties {
+            let Some(periods) = RetentionPeriods::for_county(&policies, &county_id) else {
+                log::debug!("No retention policy applies to county {}", county_id);
+                continue;
+            };
+
+            let mut diffs_deleted = 0;
+            loop {
+                let deleted = RetentionQueries::delete_diffs_batch(
+                    &pool,
+                    &county_id,
+                    periods.diff_cutoff(now),
+                    DELETE_BATCH_SIZE,
+                )
+                .await?;
+                diffs_deleted += deleted;
+                if deleted < DELETE_BATCH_SIZE as u64 {
+                    break;
+                }
+            }
+            let stats_rolled_up = RetentionQueries::roll_up_stats(&pool, &county_id, periods.stats_cutoff(now)).await?;
+
+            RETENTION_ROWS_RECLAIMED_TOTAL
+                .with_label_values(&["sync_diffs", &county_id])
+                .inc_by(diffs_deleted);
+            RETENTION_ROWS_RECLAIMED_TOTAL
+                .with_label_values(&["sync_stats", &county_id])
+                .inc_by(stats_rolled_up.max(0) as u64);
+
+            if diffs_deleted > 0 || stats_rolled_up > 0 {
+                log::info!(
+                    "Retention for {}: deleted {} diffs, rolled up stats for {} operations",
+                    county_id,
+                    diffs_deleted,
+                    stats_rolled_up
+                );
+            }
+            reports.push(RetentionReport { county_id, periods, diffs_deleted, stats_rolled_up });
+        }
+
+        RETENTION_RUN_DURATION_SECONDS.observe(started.elapsed().as_secs_f64());
+        RETENTION_LAST_SUCCESS_TIMESTAMP.set(Utc::now().timestamp());
+
+        Ok(reports)
+    }
+
+    /// Handle scheduled retention jobs on `worker`. A run that finds a
+    /// manual run in progress counts as done.
+    pub fn register(&self, worker: Worker) -> Worker {
+        let job = self.clone();
+        worker.handle(RETENTION_JOB, move |_| {
+            let job = job.clone();
+            async move {
+                match job.run(None).await {
+                    Ok(_) => Ok(()),
+                    Err(Error::Conflict(_)) => {
+                        log::debug!("Skipping scheduled retention; a manual run is in progress");
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        })
+    }
+
+    /// Enqueue a retention run at every multiple of `interval`.
+    ///
+    /// Every instance schedules the same upcoming slot under the same unique
+    /// key, so each slot runs once however many instances there are.
+    pub fn schedule(queue: JobQueue, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let slot = interval.as_secs().max(1) as i64;
+
+            loop {
+                ticker.tick().await;
+                let next = (Utc::now().timestamp() / slot + 1) * slot;
+                let Some(run_at) = DateTime::from_timestamp(next, 0) else {
+                    continue;
+                };
+                let job = NewJob::new(RETENTION_QUEUE, RETENTION_JOB, serde_json::json!({}))
+                    .run_at(run_at)
+                    .max_attempts(3)
+                    .unique(format!("{}:{}", RETENTION_JOB, next));
+                if let Err(e) = queue.enqueue(job).await {
+                    log::error!("Failed to schedule data retention: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(county_id: &str, diff_retention_days: i32) -> RetentionPolicyRow {
+        RetentionPolicyRow {
+            county_id: county_id.to_string(),
+            diff_retention_days,
+            stats_retention_days: 365,
+            updated_by: "test".to_string(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_periods_for_county() {
+        let policies = vec![policy("*", 90), policy("benton", 30)];
+
+        assert_eq!(RetentionPeriods::for_county(&policies, "benton").unwrap().diff_retention_days, 30);
+        let periods = RetentionPeriods::for_county(&policies, "franklin").unwrap();
+        assert_eq!(periods.diff_retention_days, 90);
+
+        let now = Utc::now();
+        assert_eq!(now - periods.diff_cutoff(now), chrono::Duration::days(90));
+
+        assert!(RetentionPeriods::for_county(&policies[1..], "franklin").is_none());
+    }
+}
@@ -2,16 +2,19 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::{interval, sleep};
 use tokio::sync::RwLock;
-use chrono::{DateTime, Utc};
 use uuid::Uuid;
-use terrafusion_common::{Result, Error, database::DbPool};
+use terrafusion_common::{Result, Error, database::RotatingPool};
 use terrafusion_common::models::sync::*;
+use super::cdc::CdcSettings;
 use super::sync_engine::SyncEngine;
 
+/// Lock taken for each tick, so only one instance schedules at a time
+const SCHEDULER_LOCK: &str = "scheduler:tick";
+
 /// Scheduler for automatic sync operations
 #[derive(Clone)]
 pub struct Scheduler {
-    db_pool: DbPool,
+    db_pool: RotatingPool,
     sync_engine: SyncEngine,
     is_running: Arc<RwLock<bool>>,
     interval_duration: Duration,
@@ -31,7 +34,7 @@ impl SchedulerHandle {
 
 impl Scheduler {
     /// Create a new scheduler
-    pub fn new(db_pool: DbPool, sync_engine: SyncEngine, interval_seconds: u64) -> Self {
+    pub fn new(db_pool: RotatingPool, sync_engine: SyncEngine, interval_seconds: u64) -> Self {
         Self {
             db_pool,
             sync_engine,
@@ -61,12 +64,12 @@ impl Scheduler {
             loop {
                 tokio::select! {
                     _ = interval_timer.tick() => {
-                        if let Err(e) = scheduler.run_scheduled_syncs().await {
-                            log::error!("Error running scheduled syncs: {}", e);
-                        }
-                        
-                        if let Err(e) = scheduler.cleanup_old_operations().await {
-                            log::error!("Error cleaning up old operations: {}", e);
+                        let locks = scheduler.sync_engine.locks();
+                        match locks.with_lock(SCHEDULER_LOCK, scheduler.run_scheduled_syncs()).await {
+                            Ok(Some(Ok(()))) => {}
+                            Ok(Some(Err(e))) => log::error!("Error running scheduled syncs: {}", e),
+                            Ok(None) => log::debug!("Another instance is running this scheduler tick"),
+                            Err(e) => log::error!("Failed to take the scheduler lock: {}", e),
                         }
                     }
                     _ = &mut shutdown_receiver => {
@@ -103,6 +106,17 @@ impl Scheduler {
         log::info!("Found {} sync pairs due for execution", due_sync_pairs.len());
         
         for sync_pair in due_sync_pairs {
+            // Hold new work while the county is in maintenance; it runs once maintenance ends
+            if self.sync_engine.in_maintenance(&sync_pair.county_id) {
+                log::debug!("County {} is in maintenance mode, skipping {}", sync_pair.county_id, sync_pair.name);
+                continue;
+            }
+            
+            // CDC pairs are kept current by their change stream
+            if CdcSettings::enabled(&sync_pair.source_config) {
+                continue;
+            }
+            
             // Check if there's already a running sync for this pair
             if self.is_sync_pair_running(sync_pair.base.id).await? {
                 log::debug!("Sync pair {} is already running, skipping", sync_pair.name);
@@ -125,6 +139,12 @@ impl Scheduler {
                     // Update last sync time
                     self.update_sync_pair_last_sync(sync_pair.base.id).await?;
                 }
+                Err(Error::Conflict(_)) => {
+                    log::debug!("Sync pair {} is running on another instance, skipping", sync_pair.name);
+                }
+                Err(Error::QuotaExceeded(reason)) => {
+                    log::warn!("Skipping scheduled sync for pair {}: {}", sync_pair.name, reason);
+                }
                 Err(e) => {
                     log::error!(
                         "Failed to start scheduled sync for pair {}: {}",
@@ -138,33 +158,6 @@ impl Scheduler {
         Ok(())
     }
     
-    /// Clean up old sync operations and records
-    async fn cleanup_old_operations(&self) -> Result<()> {
-        log::debug!("Running cleanup of old sync operations");
-        
-        // Define retention periods
-        let operation_retention_days = 30;
-        let record_retention_days = 7;
-        
-        // Calculate cutoff dates
-        let operation_cutoff = Utc::now() - chrono::Duration::days(operation_retention_days);
-        let record_cutoff = Utc::now() - chrono::Duration::days(record_retention_days);
-        
-        // Clean up old operations
-        let operations_deleted = self.delete_old_operations(operation_cutoff).await?;
-        if operations_deleted > 0 {
-            log::info!("Cleaned up {} old sync operations", operations_deleted);
-        }
-        
-        // Clean up old sync records
-        let records_deleted = self.delete_old_sync_records(record_cutoff).await?;
-        if records_deleted > 0 {
-            log::info!("Cleaned up {} old sync records", records_deleted);
-        }
-        
-        Ok(())
-    }
-    
     /// Check if the scheduler is running
     pub async fn is_running(&self) -> bool {
         *self.is_running.read().await
@@ -189,7 +182,7 @@ impl Scheduler {
             )
             "#
         )
-        .fetch_all(&self.db_pool)
+        .fetch_all(&self.db_pool.pool())
         .await
         .map_err(|e| Error::Database(e.into()))
         */
@@ -208,24 +201,12 @@ impl Scheduler {
         // For now, do nothing
         Ok(())
     }
-    
-    async fn delete_old_operations(&self, cutoff_date: DateTime<Utc>) -> Result<i64> {
-        // This would delete old sync operations and their related records
-        // For now, return 0
-        Ok(0)
-    }
-    
-    async fn delete_old_sync_records(&self, cutoff_date: DateTime<Utc>) -> Result<i64> {
-        // This would delete old sync records
-        // For now, return 0
-        Ok(0)
-    }
 }
 
 /// Start the scheduler service
 pub async fn start_scheduler(
     sync_engine: SyncEngine,
-    db_pool: DbPool,
+    db_pool: RotatingPool,
 ) -> Result<SchedulerHandle> {
     let interval_seconds = std::env::var("SCHEDULER_INTERVAL_SECONDS")
         .unwrap_or_else(|_| "60".to_string())
@@ -0,0 +1,310 @@
+//! Schema drift detection for connected systems.
+//!
+//! A column can be dropped, renamed or retyped in a source or target system
+//! without anyone telling the sync service, and the pair then fails on its
+//! next run, often in the middle of the night. A maintenance job describes
+//! both sides of every active pair on a schedule and compares the fields its
+//! mappings use with what each system exposes now and with the schema seen
+//! at the previous check. A mapped field that is gone or has changed type
+//! marks the pair `needs_attention` and raises a `schema_drift` alert.
+//!
+//! The flag stays until the pair's field mappings are replaced or someone
+//! acknowledges it; a missing field flags the pair again on the next check.
+//! A side that cannot be reached is skipped, since connectivity problems
+//! already surface through the operations themselves.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use terrafusion_common::{Error, Result, database::RotatingPool};
+use terrafusion_common::jobs::{JobQueue, NewJob, Worker};
+use terrafusion_common::notifications::{Notification, Notifier};
+use uuid::Uuid;
+
+use crate::models::database::{SchemaSnapshotQueries, SyncPairQueries, SyncPairRow};
+use super::connectors::{Connector, FieldInfo, FieldType};
+use super::credentials::CredentialVault;
+use super::field_mapping::MappingRule;
+
+/// Queue and kind of schema checks
+pub const SCHEMA_QUEUE: &str = "maintenance";
+pub const SCHEMA_CHECK_JOB: &str = "schema_drift.check";
+
+/// Side of a sync pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Source,
+    Target,
+}
+
+impl Side {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Source => "source",
+            Self::Target => "target",
+        }
+    }
+}
+
+/// A mapped field that no longer matches its system
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Drift {
+    /// The system no longer has the field
+    Missing { field: String },
+    /// The field's type differs from the one seen at the previous check
+    TypeChanged { field: String, from: FieldType, to: FieldType },
+}
+
+impl Drift {
+    /// One line for alerts and the pair's attention reason
+    pub fn describe(&self, side: Side) -> String {
+        match self {
+            Self::Missing { field } => format!("{} field {} no longer exists", side.as_str(), field),
+            Self::TypeChanged { field, from, to } => format!(
+                "{} field {} changed from {} to {}",
+                side.as_str(),
+                field,
+                type_name(*from),
+                type_name(*to)
+            ),
+        }
+    }
+}
+
+fn type_name(field_type: FieldType) -> String {
+    serde_json::to_value(field_type)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Fields the mappings read from the source or write to the target, in
+/// mapping order. Nested source paths are checked by their top-level field.
+pub fn mapped_fields(mappings: &[MappingRule], side: Side) -> Vec<String> {
+    let mut fields: Vec<String> = Vec::new();
+    for mapping in mappings {
+        let field = match side {
+            Side::Source => mapping.source_field.split('.').next().unwrap_or_default(),
+            Side::Target => mapping.target_field.as_str(),
+        };
+        if !field.is_empty() && !fields.iter().any(|seen| seen == field) {
+            fields.push(field.to_string());
+        }
+    }
+    fields
+}
+
+/// Compare the mapped fields with the fields a system exposes now and, if
+/// the pair was checked before, with the schema seen then
+pub fn detect_drift(mapped: &[String], previous: Option<&[FieldInfo]>, current: &[FieldInfo]) -> Vec<Drift> {
+    let find = |fields: &[FieldInfo], name: &str| fields.iter().find(|field| field.name == name).map(|field| field.field_type);
+
+    mapped
+        .iter()
+        .filter_map(|name| {
+            let Some(now) = find(current, name) else {
+                return Some(Drift::Missing { field: name.clone() });
+            };
+            // Inferred schemas report Unknown for fields that were null in the sample
+            let before = previous.and_then(|fields| find(fields, name))?;
+            let changed = before != now && before != FieldType::Unknown && now != FieldType::Unknown;
+            changed.then(|| Drift::TypeChanged { field: name.clone(), from: before, to: now })
+        })
+        .collect()
+}
+
+/// Outcome of checking one pair
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaCheck {
+    pub sync_pair_id: Uuid,
+    pub needs_attention: bool,
+    pub problems: Vec<String>,
+    /// Sides that could not be described, with the reason
+    pub skipped: Vec<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Compares the schemas of active pairs with their field mappings
+#[derive(Clone)]
+pub struct SchemaDriftCheck {
+    pool: RotatingPool,
+    credentials: CredentialVault,
+    timeout: Duration,
+    notifier: Option<Notifier>,
+}
+
+impl SchemaDriftCheck {
+    pub fn new(pool: RotatingPool, credentials: CredentialVault, timeout: Duration) -> Self {
+        Self { pool, credentials, timeout, notifier: None }
+    }
+
+    /// Alert through the notification rules when a pair is flagged
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Check every active pair and return how many need attention. One
+    /// pair's failure does not stop the rest.
+    pub async fn run(&self) -> Result<usize> {
+        let pairs = SyncPairQueries::list_active(&self.pool.read_pool()).await?;
+        let mut flagged = 0;
+        for pair in &pairs {
+            match self.check_pair(pair).await {
+                Ok(check) if check.needs_attention => flagged += 1,
+                Ok(_) => {}
+                Err(e) => log::warn!("Schema check of sync pair {} failed: {}", pair.name, e),
+            }
+        }
+        if flagged > 0 {
+            log::warn!("Schema drift found in {} of {} active sync pairs", flagged, pairs.len());
+        }
+        Ok(flagged)
+    }
+
+    /// Describe both sides of a pair, record what was seen and flag the pair
+    /// if a mapped field has drifted
+    pub async fn check_pair(&self, pair: &SyncPairRow) -> Result<SchemaCheck> {
+        let mappings: Vec<MappingRule> = serde_json::from_value(pair.field_mappings.clone())
+            .map_err(|e| Error::Validation(format!("Invalid field mappings on {}: {}", pair.name, e)))?;
+        let pool = self.pool.pool();
+
+        let mut previous: HashMap<String, Vec<FieldInfo>> = HashMap::new();
+        for snapshot in SchemaSnapshotQueries::for_pair(&pool, pair.id).await? {
+            if let Ok(fields) = serde_json::from_value(snapshot.fields) {
+                previous.insert(snapshot.side, fields);
+            }
+        }
+
+        let mut problems = Vec::new();
+        let mut skipped = Vec::new();
+        for side in [Side::Source, Side::Target] {
+            let config = match side {
+                Side::Source => &pair.source_config,
+                Side::Target => &pair.target_config,
+            };
+            let connector = self
+                .credentials
+                .resolve(&pair.county_id, config)
+                .await
+                .and_then(|config| Connector::from_config(&config));
+            let described = match connector {
+                Ok(connector) => connector.describe(self.timeout).await,
+                Err(e) => Err(e),
+            };
+            let fields = match described {
+                Ok(fields) => fields,
+                Err(e) => {
+                    log::debug!("Skipping schema check of {} {}: {}", pair.name, side.as_str(), e);
+                    skipped.push(format!("{}: {}", side.as_str(), e));
+                    continue;
+                }
+            };
+
+            let drift = detect_drift(
+                &mapped_fields(&mappings, side),
+                previous.get(side.as_str()).map(Vec::as_slice),
+                &fields,
+            );
+            problems.extend(drift.iter().map(|drift| drift.describe(side)));
+            SchemaSnapshotQueries::save(&pool, pair.id, side.as_str(), &json!(fields), &json!(drift)).await?;
+        }
+
+        if !problems.is_empty() {
+            let reason = problems.join("; ");
+            // Alert once per distinct problem rather than on every check
+            if pair.attention_reason.as_deref() != Some(reason.as_str()) {
+                SyncPairQueries::set_attention(&pool, pair.id, Some(&reason)).await?;
+                log::warn!("Sync pair {} needs attention: {}", pair.name, reason);
+                if let Some(notifier) = &self.notifier {
+                    notifier.notify(Notification::schema_drift(&pair.county_id, &pair.name, pair.id, &problems));
+                }
+            }
+        }
+
+        Ok(SchemaCheck {
+            sync_pair_id: pair.id,
+            needs_attention: !problems.is_empty() || pair.needs_attention,
+            problems,
+            skipped,
+            checked_at: Utc::now(),
+        })
+    }
+
+    /// Handle schema checks on `worker`
+    pub fn register(&self, worker: Worker) -> Worker {
+        let check = self.clone();
+        worker.handle(SCHEMA_CHECK_JOB, move |_| {
+            let check = check.clone();
+            async move { check.run().await.map(|_| ()) }
+        })
+    }
+
+    /// Enqueue a check at every multiple of `interval`, once per slot across
+    /// instances
+    pub fn schedule(queue: JobQueue, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let slot = interval.as_secs().max(1) as i64;
+
+            loop {
+                ticker.tick().await;
+                let next = (Utc::now().timestamp() / slot + 1) * slot;
+                let Some(run_at) = DateTime::from_timestamp(next, 0) else {
+                    continue;
+                };
+                let job = NewJob::new(SCHEMA_QUEUE, SCHEMA_CHECK_JOB, json!({}))
+                    .run_at(run_at)
+                    .max_attempts(1)
+                    .unique(format!("{}:{}", SCHEMA_CHECK_JOB, next));
+                if let Err(e) = queue.enqueue(job).await {
+                    log::error!("Failed to schedule schema checks: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, field_type: FieldType) -> FieldInfo {
+        FieldInfo { name: name.to_string(), field_type, nullable: true }
+    }
+
+    #[test]
+    fn test_detect_drift() {
+        let mappings: Vec<MappingRule> = serde_json::from_value(json!([
+            { "source_field": "parcel_id", "target_field": "apn" },
+            { "source_field": "situs.city", "target_field": "city" },
+            { "source_field": "assessed_value", "target_field": "value" },
+        ]))
+        .unwrap();
+        let source = mapped_fields(&mappings, Side::Source);
+        assert_eq!(source, vec!["parcel_id", "situs", "assessed_value"]);
+
+        let before = vec![
+            field("parcel_id", FieldType::String),
+            field("situs", FieldType::Json),
+            field("assessed_value", FieldType::Number),
+        ];
+        assert!(detect_drift(&source, None, &before).is_empty());
+        assert!(detect_drift(&source, Some(&before), &before).is_empty());
+
+        let after = vec![field("parcel_id", FieldType::Integer), field("situs", FieldType::Unknown)];
+        let drift = detect_drift(&source, Some(&before), &after);
+        assert_eq!(
+            drift,
+            vec![
+                Drift::TypeChanged { field: "parcel_id".to_string(), from: FieldType::String, to: FieldType::Integer },
+                Drift::Missing { field: "assessed_value".to_string() },
+            ]
+        );
+        assert_eq!(drift[0].describe(Side::Source), "source field parcel_id changed from string to integer");
+    }
+}
@@ -0,0 +1,86 @@
+//! Parsing for the global search box
+
+use std::collections::BTreeSet;
+use serde::Serialize;
+use terrafusion_common::{Error, Result};
+
+const MIN_QUERY_LENGTH: usize = 2;
+const MAX_QUERY_LENGTH: usize = 200;
+
+/// Shortest export ID prefix worth matching; shorter hex strings match too much
+const MIN_ID_PREFIX: usize = 4;
+
+/// What a search can return
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchType {
+    SyncPair,
+    Operation,
+    Export,
+}
+
+impl SearchType {
+    pub const ALL: [SearchType; 3] = [SearchType::SyncPair, SearchType::Operation, SearchType::Export];
+
+    /// Parse a comma-separated `types` parameter; empty means every type
+    pub fn parse_list(value: Option<&str>) -> Result<BTreeSet<SearchType>> {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+            return Ok(Self::ALL.into_iter().collect());
+        };
+
+        value
+            .split(',')
+            .map(|name| match name.trim() {
+                "sync_pair" | "sync_pairs" => Ok(SearchType::SyncPair),
+                "operation" | "operations" => Ok(SearchType::Operation),
+                "export" | "exports" => Ok(SearchType::Export),
+                other => Err(Error::Validation(format!(
+                    "Unknown search type: {} (use sync_pair, operation or export)",
+                    other
+                ))),
+            })
+            .collect()
+    }
+}
+
+/// Validate and normalize the free-text query
+pub fn normalize_query(q: &str) -> Result<String> {
+    let q = q.split_whitespace().collect::<Vec<_>>().join(" ");
+    let length = q.chars().count();
+    if !(MIN_QUERY_LENGTH..=MAX_QUERY_LENGTH).contains(&length) {
+        return Err(Error::Validation(format!(
+            "Search query must be {} to {} characters",
+            MIN_QUERY_LENGTH, MAX_QUERY_LENGTH
+        )));
+    }
+    Ok(q)
+}
+
+/// Lowercased UUID prefix when the query could be (part of) an export ID
+pub fn id_prefix(q: &str) -> Option<String> {
+    let q = q.trim().to_lowercase();
+    let plausible = q.len() >= MIN_ID_PREFIX
+        && q.len() <= 36
+        && q.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+    plausible.then_some(q)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_parsing() {
+        assert_eq!(SearchType::parse_list(None).unwrap().len(), 3);
+        let types = SearchType::parse_list(Some("exports, sync_pair")).unwrap();
+        assert_eq!(types.into_iter().collect::<Vec<_>>(), vec![SearchType::SyncPair, SearchType::Export]);
+        assert!(SearchType::parse_list(Some("users")).is_err());
+
+        assert_eq!(normalize_query("  parcel   timeout ").unwrap(), "parcel timeout");
+        assert!(normalize_query("a").is_err());
+
+        assert_eq!(id_prefix("3F2A9C1E-"), Some("3f2a9c1e-".to_string()));
+        assert_eq!(id_prefix("abc"), None);
+        assert_eq!(id_prefix("parcel"), None);
+    }
+}
@@ -1,22 +1,69 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::collections::HashMap;
-use tokio::sync::{RwLock, Semaphore};
+use tokio::sync::{watch, RwLock, Semaphore};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
-use terrafusion_common::{Result, Error, database::DbPool};
+use serde::{Deserialize, Serialize};
+use terrafusion_common::{Result, Error, database::RotatingPool};
 use terrafusion_common::models::sync::*;
+use terrafusion_common::config::RuntimeSettings;
+use terrafusion_common::database::diff_writer::{DiffWriteSettings, DiffWriter, NewSyncDiff};
+use terrafusion_common::datasets::{self, DatasetQueries, VersionSource};
+use terrafusion_common::jobs::{JobQueue, NewJob, Worker};
+use terrafusion_common::locks::{LockGuard, LockManager, PgLockBackend};
+use terrafusion_common::maintenance::MaintenanceHandle;
+use terrafusion_common::notifications::{Notification, Notifier};
+use terrafusion_common::usage::{self, UsageMetric, UsageQueries};
+use terrafusion_common::utils::county_config;
 use crate::config::Config;
+use crate::models::database::SyncOperationQueries;
+use super::blue_green::BlueGreenLoad;
+use super::boundary_check::BoundaryCheck;
+use super::credentials::{self, CredentialVault};
+use super::enrichment::Enricher;
+use super::entity_matcher::{self, EntityMatcher};
+use super::geometry_diff::{GeometryChange, GeometryDiffSettings, RecordDiff};
+use super::imports::{self, ImportFile, ImportStore};
+use super::narrator::{NarratorClient, OperationDigest};
+use super::batch::{resume_point, BatchLogEntry, BatchMode, BatchOutcome, BatchSettings};
+use super::duplicates::{self, Admission, DuplicatePolicy};
+use super::throttle::{is_overload, TargetThrottle, TargetThrottles};
+
+/// Queue and kind of jobs that run sync operations
+pub const SYNC_QUEUE: &str = "sync";
+pub const SYNC_OPERATION_JOB: &str = "sync.operation";
+
+/// How often an operation queued behind another checks whether the pair is free
+const QUEUED_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Payload of a queued operation; the job id is the operation id
+#[derive(Debug, Deserialize)]
+struct QueuedOperation {
+    sync_pair_id: Uuid,
+}
 
 /// Core synchronization engine for TerraFusion platform
 #[derive(Clone)]
 pub struct SyncEngine {
-    db_pool: DbPool,
+    db_pool: RotatingPool,
     running_operations: Arc<RwLock<HashMap<Uuid, SyncOperationHandle>>>,
     semaphore: Arc<Semaphore>,
+    max_concurrent: Arc<AtomicUsize>,
+    narrator: Option<NarratorClient>,
+    entity_matcher: Option<EntityMatcher>,
+    notifier: Option<Notifier>,
+    maintenance: MaintenanceHandle,
+    locks: LockManager,
+    jobs: Option<JobQueue>,
+    throttles: TargetThrottles,
+    credentials: Option<CredentialVault>,
+    imports: Option<ImportStore>,
+    diff_writes: DiffWriteSettings,
 }
 
 /// Handle for a running sync operation
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SyncOperationHandle {
     pub operation_id: Uuid,
     pub sync_pair_id: Uuid,
@@ -29,16 +76,158 @@ pub struct SyncOperationHandle {
 
 impl SyncEngine {
     /// Create a new sync engine
-    pub fn new(db_pool: DbPool) -> Self {
+    pub fn new(db_pool: RotatingPool) -> Self {
         let max_concurrent = std::env::var("MAX_CONCURRENT_SYNCS")
             .unwrap_or_else(|_| "5".to_string())
             .parse::<usize>()
             .unwrap_or(5);
             
         Self {
+            locks: LockManager::new(Arc::new(PgLockBackend::rotating(db_pool.clone()))),
             db_pool,
             running_operations: Arc::new(RwLock::new(HashMap::new())),
             semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent: Arc::new(AtomicUsize::new(max_concurrent)),
+            narrator: None,
+            entity_matcher: None,
+            notifier: None,
+            maintenance: MaintenanceHandle::new(),
+            jobs: None,
+            throttles: TargetThrottles::default(),
+            credentials: None,
+            imports: None,
+            diff_writes: DiffWriteSettings::default(),
+        }
+    }
+    
+    /// Generate NarratorAI summaries for completed operations
+    pub fn with_narrator(mut self, narrator: NarratorClient) -> Self {
+        self.narrator = Some(narrator);
+        self
+    }
+    
+    /// Match new source records to existing targets by embedding similarity
+    pub fn with_entity_matcher(mut self, entity_matcher: EntityMatcher) -> Self {
+        self.entity_matcher = Some(entity_matcher);
+        self
+    }
+    
+    /// Alert on failed operations and conflicts through the notification rules
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+    
+    /// Refuse new operations for counties in maintenance mode
+    pub fn with_maintenance(mut self, maintenance: MaintenanceHandle) -> Self {
+        self.maintenance = maintenance;
+        self
+    }
+    
+    /// Use locks from another backend than the database's advisory locks
+    pub fn with_locks(mut self, locks: LockManager) -> Self {
+        self.locks = locks;
+        self
+    }
+    
+    /// Queue operations for worker processes instead of running them here
+    pub fn with_job_queue(mut self, jobs: JobQueue) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+    
+    /// Resolve `credential_id` references in pair configs from the vault
+    pub fn with_credentials(mut self, credentials: CredentialVault) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+    
+    /// A source or target config with its stored credential merged in
+    pub async fn resolve_credentials(&self, county_id: &str, config: &serde_json::Value) -> Result<serde_json::Value> {
+        match &self.credentials {
+            Some(vault) => vault.resolve(county_id, config).await,
+            None if credentials::credential_id(config)?.is_some() => {
+                Err(Error::Config("Stored credentials are not available in this process".to_string()))
+            }
+            None => Ok(config.clone()),
+        }
+    }
+    
+    /// Let operations load an uploaded file in place of the pair's source
+    pub fn with_imports(mut self, imports: ImportStore) -> Self {
+        self.imports = Some(imports);
+        self
+    }
+    
+    /// Batch size and method for writing the diffs of each operation
+    pub fn with_diff_writes(mut self, settings: DiffWriteSettings) -> Self {
+        self.diff_writes = settings;
+        self
+    }
+    
+    /// The ready import an operation of `county_id` names as its source
+    async fn source_import(&self, county_id: &str, import_id: Uuid) -> Result<ImportFile> {
+        match &self.imports {
+            Some(store) => store.ready_import(county_id, import_id).await,
+            None => Err(Error::Config("Imported files are not available in this process".to_string())),
+        }
+    }
+    
+    /// Locks shared with the other instances of the service
+    pub fn locks(&self) -> &LockManager {
+        &self.locks
+    }
+    
+    /// Whether new operations for the county are on hold
+    pub fn in_maintenance(&self, county_id: &str) -> bool {
+        self.maintenance.is_active(Some(county_id))
+    }
+    
+    /// Operations running in this process
+    pub async fn running_snapshot(&self) -> Vec<SyncOperationHandle> {
+        let running = self.running_operations.read().await;
+        let mut handles: Vec<_> = running.values().cloned().collect();
+        handles.sort_by_key(|handle| handle.start_time);
+        handles
+    }
+    
+    /// Number of operations still running (drained during maintenance)
+    pub async fn running_count(&self) -> usize {
+        self.running_operations.read().await.len()
+    }
+    
+    /// Follow runtime settings and resize the concurrency cap when it changes
+    pub fn watch_settings(&self, mut settings: watch::Receiver<Arc<RuntimeSettings>>) {
+        let engine = self.clone();
+        engine.resize_concurrency(settings.borrow().max_concurrent_syncs);
+        
+        tokio::spawn(async move {
+            while settings.changed().await.is_ok() {
+                let max_concurrent = settings.borrow().max_concurrent_syncs;
+                engine.resize_concurrency(max_concurrent);
+            }
+        });
+    }
+    
+    /// Grow or shrink the number of concurrent sync permits
+    fn resize_concurrency(&self, new_max: usize) {
+        let old_max = self.max_concurrent.swap(new_max, Ordering::SeqCst);
+        
+        if new_max > old_max {
+            self.semaphore.add_permits(new_max - old_max);
+        } else if new_max < old_max {
+            // Retire permits as running operations release them
+            let semaphore = self.semaphore.clone();
+            let surplus = (old_max - new_max) as u32;
+            tokio::spawn(async move {
+                if let Ok(permits) = semaphore.acquire_many(surplus).await {
+                    permits.forget();
+                }
+            });
+        }
+        
+        if new_max != old_max {
+            log::info!("Sync concurrency cap changed from {} to {}", old_max, new_max);
         }
     }
     
@@ -60,33 +249,232 @@ impl SyncEngine {
             return Err(Error::Validation("Sync pair is not active".to_string()));
         }
         
-        // Create new sync operation record
-        let operation_id = Uuid::new_v4();
-        let operation = SyncOperation {
-            base: terrafusion_common::models::BaseModel {
-                id: operation_id,
-                created_at: Utc::now(),
-                updated_at: Utc::now(),
-            },
-            sync_pair_id,
-            status: SyncStatus::Pending,
-            start_time: Utc::now(),
-            end_time: None,
-            records_processed: None,
-            records_succeeded: None,
-            records_failed: None,
-            error_message: None,
-            custom_parameters,
-            initiated_by,
-        };
+        if let Some(window) = self.maintenance.current().active_for(Some(&sync_pair.county_id)) {
+            return Err(Error::ServiceUnavailable(format!("Maintenance in progress: {}", window.message)));
+        }
+        
+        if usage::quota_overridden(custom_parameters.as_ref()) {
+            log::warn!("Sync operation for pair {} started by {} overrides county quotas", sync_pair.name, initiated_by);
+        } else {
+            self.check_quota(&sync_pair.county_id).await?;
+        }
+        
+        // A missing or unfinished upload is refused now rather than when a worker picks the operation up
+        if let Some(import_id) = imports::source_import_id(custom_parameters.as_ref())? {
+            self.source_import(&sync_pair.county_id, import_id).await?;
+        }
+        
+        // An active operation of the pair means rejecting, queueing or superseding
+        let policy = DuplicatePolicy::from_target_config(&sync_pair.target_config)?;
+        let active = SyncOperationQueries::active_for_pair(&self.db_pool.pool(), sync_pair_id).await?;
+        let allow_duplicate = duplicates::duplicate_allowed(custom_parameters.as_ref());
+        let admission = duplicates::admit(policy, &active, allow_duplicate, &sync_pair.name)?;
+        if allow_duplicate && admission.waits() {
+            log::warn!("Sync operation for pair {} started by {} queues behind an active one", sync_pair.name, initiated_by);
+        }
+        
+        let operation = new_operation(sync_pair_id, initiated_by, custom_parameters);
+        let operation_id = operation.base.id;
+        if let Admission::Supersede(superseded) = &admission {
+            for &existing in superseded {
+                self.supersede(existing, operation_id).await?;
+            }
+        }
+        
+        // With a job queue the operation runs on whichever worker claims it
+        if let Some(jobs) = &self.jobs {
+            self.create_sync_operation(&operation).await?;
+            self.count_operation(&sync_pair.county_id, &operation).await;
+            jobs.enqueue(
+                NewJob::new(SYNC_QUEUE, SYNC_OPERATION_JOB, serde_json::json!({ "sync_pair_id": sync_pair_id }))
+                    .id(operation_id),
+            )
+            .await?;
+            log::info!("Queued sync operation {} for pair {}", operation_id, sync_pair.name);
+            return Ok(operation_id);
+        }
+        
+        // One operation per pair across every instance; held until the operation ends
+        let lock = self.locks.try_acquire(&sync_pair_lock_key(sync_pair_id)).await?;
+        if lock.is_none() && !admission.waits() {
+            return Err(Error::Conflict(format!("Sync pair {} is already running", sync_pair.name)));
+        }
         
         // Save operation to database
         self.create_sync_operation(&operation).await?;
+        self.count_operation(&sync_pair.county_id, &operation).await;
+        
+        // Start the sync process in background, once the pair is free
+        let engine = self.clone();
+        tokio::spawn(async move {
+            match lock {
+                Some(lock) => {
+                    let _ = engine.run_operation(operation_id, sync_pair, lock, None).await;
+                }
+                None => engine.run_when_free(operation_id, sync_pair).await,
+            }
+        });
+        
+        Ok(operation_id)
+    }
+    
+    /// Cancel an operation that `replacement` supersedes. A queued one is
+    /// dropped; a running one stops after its current batch, on whichever
+    /// instance runs it.
+    async fn supersede(&self, operation_id: Uuid, replacement: Uuid) -> Result<()> {
+        if let Some(jobs) = &self.jobs {
+            jobs.backend().cancel(operation_id).await?;
+        }
+        SyncOperationQueries::update_status(
+            &self.db_pool.pool(),
+            operation_id,
+            "CANCELED",
+            Some(Utc::now()),
+            Some(&format!("Superseded by operation {}", replacement)),
+        )
+        .await?;
+        if let Some(handle) = self.running_operations.write().await.get_mut(&operation_id) {
+            handle.status = SyncStatus::Canceled;
+        }
+        log::info!("Sync operation {} superseded by {}", operation_id, replacement);
+        Ok(())
+    }
+    
+    /// Run an operation queued behind the pair's current one as soon as
+    /// that releases the pair's lock, unless it is canceled while waiting
+    async fn run_when_free(&self, operation_id: Uuid, sync_pair: SyncPair) {
+        let key = sync_pair_lock_key(sync_pair.base.id);
+        loop {
+            tokio::time::sleep(QUEUED_POLL_INTERVAL).await;
+            if self.cancel_requested(operation_id).await {
+                log::info!("Queued sync operation {} canceled before it started", operation_id);
+                return;
+            }
+            match self.locks.try_acquire(&key).await {
+                Ok(Some(lock)) => {
+                    let _ = self.run_operation(operation_id, sync_pair, lock, None).await;
+                    return;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log::error!("Queued sync operation {} could not wait for pair {}: {}", operation_id, sync_pair.name, e);
+                    let _ = self.fail_sync_operation(operation_id, e.to_string()).await;
+                    return;
+                }
+            }
+        }
+    }
+    
+    /// Whether the operation was canceled, here or by another instance
+    async fn cancel_requested(&self, operation_id: Uuid) -> bool {
+        let canceled_here = self
+            .running_operations
+            .read()
+            .await
+            .get(&operation_id)
+            .map_or(false, |handle| handle.status == SyncStatus::Canceled);
+        if canceled_here {
+            return true;
+        }
+        match SyncOperationQueries::is_canceled(&self.db_pool.pool(), operation_id).await {
+            Ok(canceled) => canceled,
+            Err(e) => {
+                log::warn!("Failed to check whether operation {} was canceled: {}", operation_id, e);
+                false
+            }
+        }
+    }
+    
+    /// Refuse the operation when the county has used its monthly quota of
+    /// sync operations
+    async fn check_quota(&self, county_id: &str) -> Result<()> {
+        let quotas = match county_config::load_county_configuration(county_id).await {
+            Ok(county) => county.usage_quotas,
+            Err(terrafusion_common::error::Error::NotFound(_)) => return Ok(()),
+            Err(e) => return Err(Error::Config(format!("County configuration for {} unavailable: {}", county_id, e))),
+        };
+        if quotas.max_sync_operations.is_none() {
+            return Ok(());
+        }
+        let usage = UsageQueries::month_to_date(&self.db_pool.pool(), county_id).await?;
+        quotas.check_sync_operation(county_id, &usage)
+    }
+    
+    /// Count a started operation against its county's quota
+    async fn count_operation(&self, county_id: &str, operation: &SyncOperation) {
+        let result = UsageQueries::record(
+            &self.db_pool.pool(),
+            county_id,
+            Some(&operation.initiated_by),
+            UsageMetric::SyncOperations,
+            1,
+            0,
+        )
+        .await;
+        if let Err(e) = result {
+            log::warn!("Failed to record usage for operation {}: {}", operation.base.id, e);
+        }
+    }
+    
+    /// Run an operation claimed from the job queue. Failures of the sync
+    /// itself are recorded on the operation; the job only fails (and is
+    /// retried) when the pair is busy or its configuration cannot be loaded.
+    pub async fn run_queued_operation(&self, operation_id: Uuid, sync_pair_id: Uuid) -> Result<()> {
+        let sync_pair = self.get_sync_pair(sync_pair_id).await?;
+        let lock = self.locks
+            .try_acquire(&sync_pair_lock_key(sync_pair_id))
+            .await?
+            .ok_or_else(|| Error::ServiceUnavailable(format!("Sync pair {} is already running", sync_pair.name)))?;
+        
+        let _ = self.run_operation(operation_id, sync_pair, lock, None).await;
+        Ok(())
+    }
+    
+    /// Run an operation that loads `changes` captured from the source
+    /// instead of comparing the two systems. The caller holds the pair's
+    /// lock; it is released when the operation ends.
+    pub async fn apply_changes(
+        &self,
+        sync_pair_id: Uuid,
+        changes: Vec<SyncDifference>,
+        lock: LockGuard,
+    ) -> Result<SyncStats> {
+        let sync_pair = self.get_sync_pair(sync_pair_id).await?;
+        let operation = new_operation(sync_pair_id, "cdc".to_string(), None);
+        let operation_id = operation.base.id;
+        self.create_sync_operation(&operation).await?;
         
-        // Create operation handle
+        self.run_operation(operation_id, sync_pair, lock, Some(changes)).await
+    }
+    
+    /// Run queued operations on `worker`
+    pub fn register(&self, worker: Worker) -> Worker {
+        let engine = self.clone();
+        worker.handle(SYNC_OPERATION_JOB, move |job| {
+            let engine = engine.clone();
+            async move {
+                let queued: QueuedOperation = job.payload()?;
+                if job.attempts > 1 {
+                    log::warn!("Resuming sync operation {} (attempt {})", job.id, job.attempts);
+                }
+                engine.run_queued_operation(job.id, queued.sync_pair_id).await
+            }
+        })
+    }
+    
+    /// Execute an operation, record its outcome and release the pair's lock.
+    /// `changes` replaces extraction and comparison when the differences are
+    /// already known.
+    async fn run_operation(
+        &self,
+        operation_id: Uuid,
+        sync_pair: SyncPair,
+        lock: LockGuard,
+        changes: Option<Vec<SyncDifference>>,
+    ) -> Result<SyncStats> {
         let handle = SyncOperationHandle {
             operation_id,
-            sync_pair_id,
+            sync_pair_id: sync_pair.base.id,
             status: SyncStatus::Running,
             start_time: Utc::now(),
             records_processed: 0,
@@ -100,29 +488,63 @@ impl SyncEngine {
             running.insert(operation_id, handle);
         }
         
-        // Start the sync process in background
-        let engine = self.clone();
-        tokio::spawn(async move {
-            let result = engine.execute_sync_operation(operation_id, sync_pair).await;
-            
-            // Update operation status based on result
-            match result {
-                Ok(stats) => {
-                    let _ = engine.complete_sync_operation(operation_id, stats).await;
+        let county_id = sync_pair.county_id.clone();
+        let sync_pair_name = sync_pair.name.clone();
+        let layer_id = datasets::layer_for_target(&sync_pair.target_config);
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(Notification::operation_started(&county_id, &sync_pair_name, operation_id));
+        }
+        let result = self.execute_sync_operation(operation_id, sync_pair, changes).await;
+        
+        // Update operation status based on result
+        let canceled = result.is_err() && self.cancel_requested(operation_id).await;
+        let outcome = match result {
+            Ok((stats, digest)) => {
+                let _ = self.complete_sync_operation(operation_id, stats.clone()).await;
+                self.narrate_operation(operation_id, &stats, &digest).await;
+                self.record_usage(operation_id, &county_id, &stats, &digest).await;
+                if let Some(layer_id) = &layer_id {
+                    self.bump_dataset_version(operation_id, &county_id, layer_id, &stats).await;
                 }
-                Err(e) => {
-                    let _ = engine.fail_sync_operation(operation_id, e.to_string()).await;
+                
+                if let Some(notifier) = &self.notifier {
+                    notifier.notify(Notification::operation_completed(
+                        &county_id,
+                        &sync_pair_name,
+                        operation_id,
+                        stats.total_records_succeeded,
+                        stats.total_records_failed,
+                    ));
+                    if digest.conflicts > 0 {
+                        notifier.notify(Notification::conflicts_found(
+                            &county_id,
+                            &digest.sync_pair_name,
+                            operation_id,
+                            digest.conflicts,
+                        ));
+                    }
                 }
+                Ok(stats)
             }
-            
-            // Remove from running operations
-            {
-                let mut running = engine.running_operations.write().await;
-                running.remove(&operation_id);
+            // A canceled operation keeps its status and reason
+            Err(e) if canceled => Err(e),
+            Err(e) => {
+                let _ = self.fail_sync_operation(operation_id, e.to_string()).await;
+                
+                if let Some(notifier) = &self.notifier {
+                    notifier.notify(Notification::operation_failed(&county_id, &sync_pair_name, operation_id, &e.to_string()));
+                }
+                Err(e)
             }
-        });
+        };
         
-        Ok(operation_id)
+        // Remove from running operations
+        {
+            let mut running = self.running_operations.write().await;
+            running.remove(&operation_id);
+        }
+        lock.release().await;
+        outcome
     }
     
     /// Execute the actual sync operation
@@ -130,12 +552,35 @@ impl SyncEngine {
         &self,
         operation_id: Uuid,
         sync_pair: SyncPair,
-    ) -> Result<SyncStats> {
+        changes: Option<Vec<SyncDifference>>,
+    ) -> Result<(SyncStats, OperationDigest)> {
         log::info!("Starting sync operation {} for pair {}", operation_id, sync_pair.name);
         
         // Update status to running
         self.update_sync_operation_status(operation_id, SyncStatus::Running).await?;
         
+        // Everything below connects with the stored credentials in place
+        let mut sync_pair = sync_pair;
+        sync_pair.source_config = self.resolve_credentials(&sync_pair.county_id, &sync_pair.source_config).await?;
+        sync_pair.target_config = self.resolve_credentials(&sync_pair.county_id, &sync_pair.target_config).await?;
+        
+        // A one-off operation reads an uploaded file instead of the source system
+        let custom_parameters = SyncOperationQueries::get_by_id(&self.db_pool.pool(), operation_id)
+            .await?
+            .and_then(|operation| operation.custom_parameters);
+        if let Some(import_id) = imports::source_import_id(custom_parameters.as_ref())? {
+            let import = self.source_import(&sync_pair.county_id, import_id).await?;
+            log::info!("Sync operation {} reads import {} ({})", operation_id, import.id, import.file_name);
+            sync_pair.source_config = imports::as_source(&sync_pair.source_config, &import)?;
+        }
+        
+        // Checked before extracting, so bad load settings fail fast
+        let throttle = self.throttles.for_pair(&sync_pair).await?;
+        let batching = BatchSettings::from_target_config(&sync_pair.target_config)?;
+        let mut enricher = Enricher::from_source_config(&sync_pair.source_config)?;
+        let boundary_check = BoundaryCheck::for_pair(&sync_pair).await?;
+        let mut blue_green = BlueGreenLoad::for_pair(&sync_pair)?;
+        
         // Initialize stats
         let mut stats = SyncStats {
             total_operations: 1,
@@ -151,48 +596,169 @@ impl SyncEngine {
             unresolved_conflicts: 0,
         };
         
-        // Step 1: Extract data from source system
-        log::info!("Extracting data from source system: {}", sync_pair.source_system);
-        let source_data = self.extract_source_data(&sync_pair).await?;
+        let mut digest = OperationDigest {
+            sync_pair_name: sync_pair.name.clone(),
+            source_system: sync_pair.source_system.clone(),
+            target_system: sync_pair.target_system.clone(),
+            ..Default::default()
+        };
         
-        // Step 2: Extract data from target system for comparison
-        log::info!("Extracting data from target system: {}", sync_pair.target_system);
-        let target_data = self.extract_target_data(&sync_pair).await?;
+        let differences = match changes {
+            // Captured source changes need no comparison
+            Some(changes) => changes,
+            None => {
+                // Step 1: Extract data from source system
+                log::info!("Extracting data from source system: {}", sync_pair.source_system);
+                let mut source_data = self.extract_source_data(&sync_pair).await?;
+                
+                // Standardized addresses and coordinates make entity matching find more
+                if let Some(enricher) = enricher.as_mut() {
+                    let summary = enricher.enrich(&mut source_data).await;
+                    log::info!(
+                        "Enriched source records: {} addresses normalized, {} geocoded, {} not found",
+                        summary.normalized,
+                        summary.geocoded,
+                        summary.geocode_failures
+                    );
+                }
+                
+                // Step 2: Extract data from target system for comparison
+                log::info!("Extracting data from target system: {}", sync_pair.target_system);
+                let target_data = self.extract_target_data(&sync_pair).await?;
+                
+                // Step 3: Compare and identify differences
+                log::info!("Comparing source and target data");
+                self.compare_data(&source_data, &target_data, &sync_pair).await?
+            }
+        };
         
-        // Step 3: Compare and identify differences
-        log::info!("Comparing source and target data");
-        let differences = self.compare_data(&source_data, &target_data, &sync_pair).await?;
+        // Out-of-county geometries are flagged, or rejected before loading
+        let differences = match &boundary_check {
+            Some(check) => {
+                let (kept, rejected) = check.apply(differences);
+                for record in &rejected {
+                    log::warn!("Rejected source record {}: {}", record.source_id, record.reason);
+                    digest.record_failure(&record.reason);
+                }
+                stats.total_records_processed += rejected.len() as i64;
+                stats.total_records_failed += rejected.len() as i64;
+                kept
+            }
+            None => differences,
+        };
+        let rejected = stats.total_records_failed;
         
-        // Step 4: Process each difference
-        log::info!("Processing {} differences", differences.len());
-        for diff in differences {
-            stats.total_records_processed += 1;
+        // Step 4: Load the differences in batches, skipping any a previous
+        // attempt already finished
+        let batch_log = self.batch_log(operation_id).await?;
+        let (done, resume_at) = resume_point(&batch_log, &differences);
+        if resume_at > 0 {
+            log::info!(
+                "Resuming operation {} after {} logged batches ({} records)",
+                operation_id,
+                done.len(),
+                resume_at
+            );
+        }
+        for entry in done {
+            stats.total_records_processed += entry.count as i64;
+            stats.total_records_succeeded += entry.succeeded as i64;
+            stats.total_records_failed += entry.failed as i64;
+        }
+        
+        // Blue/green pairs load into a staging copy of the target table
+        let load_pair = match blue_green.as_mut() {
+            Some(load) => load.prepare(&sync_pair, resume_at > 0).await?,
+            None => sync_pair.clone(),
+        };
+        
+        log::info!(
+            "Loading {} differences in batches of {}",
+            differences.len() - resume_at,
+            batching.size
+        );
+        // Diffs are buffered across batches and written a batch of diffs at a time
+        let entity_type = entity_matcher::entity_type(&sync_pair);
+        let mut diffs = DiffWriter::new(self.db_pool.pool(), self.diff_writes);
+        let mut start = resume_at;
+        for (number, batch) in differences[resume_at..].chunks(batching.size).enumerate() {
+            let load = self.load_batch(operation_id, &load_pair, throttle.as_deref(), batching.mode, batch).await;
             
-            match self.process_sync_record(operation_id, &diff, &sync_pair).await {
-                Ok(_) => {
-                    stats.total_records_succeeded += 1;
-                    
-                    // Update running operation stats
-                    self.update_operation_handle_stats(
-                        operation_id,
-                        stats.total_records_processed as u32,
-                        stats.total_records_succeeded as u32,
-                        stats.total_records_failed as u32,
-                    ).await;
+            stats.total_records_processed += batch.len() as i64;
+            digest.payload_bytes += batch.iter().map(|difference| payload_size(&difference.source_data)).sum::<u64>();
+            let (succeeded, failed) = match load.outcome {
+                BatchOutcome::Committed => {
+                    for operation_type in &load.loaded {
+                        match operation_type {
+                            SyncOperationType::Create => digest.created += 1,
+                            SyncOperationType::Update => digest.updated += 1,
+                            SyncOperationType::Delete => digest.deleted += 1,
+                            SyncOperationType::Conflict => digest.conflicts += 1,
+                        }
+                    }
+                    (load.loaded.len(), load.errors.len())
                 }
-                Err(e) => {
-                    stats.total_records_failed += 1;
-                    log::error!("Failed to process sync record: {}", e);
-                    
-                    // Update running operation stats
-                    self.update_operation_handle_stats(
-                        operation_id,
-                        stats.total_records_processed as u32,
-                        stats.total_records_succeeded as u32,
-                        stats.total_records_failed as u32,
-                    ).await;
+                BatchOutcome::RolledBack => (0, batch.len()),
+            };
+            stats.total_records_succeeded += succeeded as i64;
+            stats.total_records_failed += failed as i64;
+            for error in &load.errors {
+                digest.record_failure(error);
+            }
+            for (index, difference) in batch.iter().enumerate() {
+                diffs.push(sync_diff(operation_id, &entity_type, difference, load.record_error(index))).await?;
+            }
+            
+            let entry = BatchLogEntry {
+                batch: done.len() + number + 1,
+                start,
+                count: batch.len(),
+                first_source_id: batch[0].source_id.clone(),
+                last_source_id: batch[batch.len() - 1].source_id.clone(),
+                mode: batching.mode,
+                outcome: load.outcome,
+                succeeded,
+                failed,
+                error: load.errors.first().cloned(),
+                finished_at: Utc::now(),
+            };
+            // Without the boundary a resumed attempt would apply the batch twice
+            self.log_batch(operation_id, &entry).await?;
+            start += batch.len();
+            
+            self.update_operation_handle_stats(
+                operation_id,
+                stats.total_records_processed as u32,
+                stats.total_records_succeeded as u32,
+                stats.total_records_failed as u32,
+            ).await;
+            
+            // Canceled or superseded operations stop between batches
+            if self.cancel_requested(operation_id).await {
+                log::info!("Sync operation {} canceled after {} records", operation_id, stats.total_records_processed);
+                if let Err(e) = diffs.flush().await {
+                    log::error!("Failed to write diffs of canceled operation {}: {}", operation_id, e);
                 }
+                if let Some(load) = &blue_green {
+                    load.discard().await;
+                }
+                return Err(Error::Conflict(format!("Sync operation {} was canceled", operation_id)));
+            }
+        }
+        diffs.flush().await?;
+        log::debug!("Recorded {} diffs for operation {}", diffs.written(), operation_id);
+        
+        if let Some(load) = &blue_green {
+            // Records rejected before loading never reached the staging table
+            let load_failures = stats.total_records_failed - rejected;
+            if load_failures > 0 {
+                load.discard().await;
+                return Err(Error::DataSync(format!(
+                    "{} records failed to load; the staged load was discarded and {} is unchanged",
+                    load_failures, sync_pair.name
+                )));
             }
+            load.swap().await?;
         }
         
         log::info!(
@@ -209,26 +775,101 @@ impl SyncEngine {
             stats.successful_operations = 1;
         }
         
-        Ok(stats)
+        Ok((stats, digest))
+    }
+    
+    /// Charge the records the operation processed to its county and, when a
+    /// user started it, to that user
+    async fn record_usage(&self, operation_id: Uuid, county_id: &str, stats: &SyncStats, digest: &OperationDigest) {
+        if stats.total_records_processed == 0 {
+            return;
+        }
+        let pool = self.db_pool.pool();
+        let initiated_by = match SyncOperationQueries::get_by_id(&pool, operation_id).await {
+            Ok(operation) => operation.map(|operation| operation.initiated_by),
+            Err(e) => {
+                log::warn!("Could not look up who started operation {}: {}", operation_id, e);
+                None
+            }
+        };
+        let result = UsageQueries::record(
+            &pool,
+            county_id,
+            initiated_by.as_deref(),
+            UsageMetric::SyncRecords,
+            stats.total_records_processed,
+            digest.payload_bytes as i64,
+        )
+        .await;
+        if let Err(e) = result {
+            log::warn!("Failed to record usage for operation {}: {}", operation_id, e);
+        }
+    }
+    
+    /// Give the layer the pair feeds a new data version when the operation
+    /// changed any of its records
+    async fn bump_dataset_version(&self, operation_id: Uuid, county_id: &str, layer_id: &str, stats: &SyncStats) {
+        if stats.total_records_succeeded == 0 {
+            return;
+        }
+        let result = DatasetQueries::bump(
+            &self.db_pool.pool(),
+            county_id,
+            layer_id,
+            VersionSource::Sync,
+            Some(operation_id),
+            None,
+            stats.total_records_succeeded,
+        )
+        .await;
+        if let Err(e) = result {
+            log::warn!("Failed to record a new version of {}/{} for operation {}: {}", county_id, layer_id, operation_id, e);
+        }
+    }
+    
+    /// Summarize a completed operation and store the narrative on it.
+    ///
+    /// Falls back to a templated sentence when NarratorAI is not configured
+    /// or fails, so the dashboard always has something to show.
+    async fn narrate_operation(&self, operation_id: Uuid, stats: &SyncStats, digest: &OperationDigest) {
+        let narrative = match &self.narrator {
+            Some(narrator) => match narrator.summarize_operation(digest).await {
+                Ok(narrative) if !narrative.is_empty() => narrative,
+                Ok(_) => digest.fallback_narrative(stats),
+                Err(e) => {
+                    log::warn!("NarratorAI summary failed for operation {}: {}", operation_id, e);
+                    digest.fallback_narrative(stats)
+                }
+            },
+            None => digest.fallback_narrative(stats),
+        };
+        
+        if let Err(e) = SyncOperationQueries::update_narrative(&self.db_pool.pool(), operation_id, &narrative).await {
+            log::error!("Failed to store narrative for operation {}: {}", operation_id, e);
+        }
     }
     
-    /// Cancel a running sync operation
+    /// Cancel a running sync operation, or a queued one no worker has claimed
     pub async fn cancel_sync_operation(&self, operation_id: Uuid) -> Result<()> {
         // Check if operation is running
-        {
-            let running = self.running_operations.read().await;
-            if !running.contains_key(&operation_id) {
-                return Err(Error::NotFound("Sync operation not found or not running".to_string()));
+        let running_here = self.running_operations.read().await.contains_key(&operation_id);
+        if !running_here {
+            let dequeued = match &self.jobs {
+                Some(jobs) => jobs.backend().cancel(operation_id).await?,
+                None => false,
+            };
+            if !dequeued {
+                return Err(Error::NotFound("Sync operation not found or not running on this instance".to_string()));
             }
         }
         
         // Update status to canceled
         self.update_sync_operation_status(operation_id, SyncStatus::Canceled).await?;
         
-        // Remove from running operations
-        {
-            let mut running = self.running_operations.write().await;
-            running.remove(&operation_id);
+        // A running operation stops after its current batch and then leaves
+        // the running operations
+        if let Some(handle) = self.running_operations.write().await.get_mut(&operation_id) {
+            handle.status = SyncStatus::Canceled;
         }
         
         log::info!("Sync operation {} canceled", operation_id);
@@ -279,11 +920,227 @@ impl SyncEngine {
         target_data: &[serde_json::Value],
         sync_pair: &SyncPair,
     ) -> Result<Vec<SyncDifference>> {
-        // This would implement the actual comparison logic
-        // For now, return empty differences
         log::debug!("Comparing {} source records with {} target records", 
                    source_data.len(), target_data.len());
-        Ok(Vec::new())
+        
+        let key_field = sync_pair.source_config
+            .get("key_field")
+            .and_then(|v| v.as_str())
+            .unwrap_or("id");
+        let geometry_diff = GeometryDiffSettings::from_source_config(&sync_pair.source_config)?;
+        
+        let targets: HashMap<String, &serde_json::Value> = target_data
+            .iter()
+            .filter_map(|record| record_key(record, key_field).map(|key| (key, record)))
+            .collect();
+        
+        let mut differences = Vec::new();
+        let mut unmatched = Vec::new();
+        
+        for source in source_data {
+            let Some(key) = record_key(source, key_field) else {
+                log::warn!("Skipping source record without {} field", key_field);
+                continue;
+            };
+            
+            match targets.get(&key) {
+                Some(target) => {
+                    // Boundaries within tolerance of each other count as unchanged
+                    if let RecordDiff::Changed { geometry_change } = geometry_diff.diff(source, target) {
+                        differences.push(SyncDifference {
+                            source_id: key.clone(),
+                            target_id: Some(key),
+                            operation_type: SyncOperationType::Update,
+                            source_data: source.clone(),
+                            target_data: Some((*target).clone()),
+                            geometry_change,
+                            boundary_violation: None,
+                        });
+                    }
+                }
+                None => unmatched.push((key, source)),
+            }
+        }
+        
+        // Entity matching: records with no key match may still be an existing
+        // property under a different identifier
+        let matches = match &self.entity_matcher {
+            Some(matcher) => matcher
+                .match_records(sync_pair, &targets, &unmatched)
+                .await
+                .unwrap_or_else(|e| {
+                    log::warn!("Entity matching unavailable, treating unmatched records as new: {}", e);
+                    HashMap::new()
+                }),
+            None => HashMap::new(),
+        };
+        
+        for (key, source) in unmatched {
+            let difference = match matches.get(&key) {
+                Some(target_id) => {
+                    let target = targets.get(target_id).copied();
+                    let geometry_change = match target.map(|target| geometry_diff.diff(source, target)) {
+                        Some(RecordDiff::Changed { geometry_change }) => geometry_change,
+                        _ => None,
+                    };
+                    SyncDifference {
+                        source_id: key,
+                        target_id: Some(target_id.clone()),
+                        operation_type: SyncOperationType::Update,
+                        source_data: source.clone(),
+                        target_data: target.cloned(),
+                        geometry_change,
+                        boundary_violation: None,
+                    }
+                }
+                None => SyncDifference {
+                    source_id: key,
+                    target_id: None,
+                    operation_type: SyncOperationType::Create,
+                    source_data: source.clone(),
+                    target_data: None,
+                    geometry_change: None,
+                    boundary_violation: None,
+                },
+            };
+            differences.push(difference);
+        }
+        
+        Ok(differences)
+    }
+    
+    /// Load one batch in a single target transaction. An all-or-nothing
+    /// batch stops and rolls back at its first failed record; a best-effort
+    /// batch skips failed records and commits the rest.
+    async fn load_batch(
+        &self,
+        operation_id: Uuid,
+        sync_pair: &SyncPair,
+        throttle: Option<&TargetThrottle>,
+        mode: BatchMode,
+        batch: &[SyncDifference],
+    ) -> BatchLoad {
+        let mut load = BatchLoad {
+            outcome: BatchOutcome::Committed,
+            loaded: Vec::with_capacity(batch.len()),
+            errors: Vec::new(),
+            record_errors: Vec::with_capacity(batch.len()),
+        };
+        if let Err(e) = self.begin_batch(sync_pair).await {
+            load.outcome = BatchOutcome::RolledBack;
+            load.errors.push(e.to_string());
+            return load;
+        }
+        
+        for difference in batch {
+            match self.load_record(operation_id, difference, sync_pair, throttle).await {
+                Ok(()) => {
+                    load.loaded.push(difference.operation_type);
+                    load.record_errors.push(None);
+                }
+                Err(e) => {
+                    log::error!("Failed to process sync record {}: {}", difference.source_id, e);
+                    load.errors.push(e.to_string());
+                    load.record_errors.push(Some(e.to_string()));
+                    if mode == BatchMode::AllOrNothing {
+                        break;
+                    }
+                }
+            }
+        }
+        
+        let committed = if mode == BatchMode::AllOrNothing && !load.errors.is_empty() {
+            false
+        } else {
+            match self.commit_batch(sync_pair).await {
+                Ok(()) => true,
+                Err(e) => {
+                    load.errors.push(format!("Commit failed: {}", e));
+                    false
+                }
+            }
+        };
+        if !committed {
+            load.outcome = BatchOutcome::RolledBack;
+            if let Err(e) = self.rollback_batch(sync_pair).await {
+                log::error!("Failed to roll back batch for operation {}: {}", operation_id, e);
+            }
+        }
+        load
+    }
+    
+    /// Write one record through the target's throttle, pausing and retrying
+    /// while the target reports overload
+    async fn load_record(
+        &self,
+        operation_id: Uuid,
+        difference: &SyncDifference,
+        sync_pair: &SyncPair,
+        throttle: Option<&TargetThrottle>,
+    ) -> Result<()> {
+        let Some(throttle) = throttle else {
+            return self.process_sync_record(operation_id, difference, sync_pair).await;
+        };
+        let settings = throttle.settings();
+        let mut retries = 0;
+        
+        loop {
+            let permit = throttle.acquire().await;
+            let result = match settings.write_timeout() {
+                Some(timeout) => tokio::time::timeout(timeout, self.process_sync_record(operation_id, difference, sync_pair))
+                    .await
+                    .unwrap_or_else(|_| Err(Error::ServiceUnavailable(format!(
+                        "{} did not respond within {}s",
+                        sync_pair.target_system,
+                        timeout.as_secs()
+                    )))),
+                None => self.process_sync_record(operation_id, difference, sync_pair).await,
+            };
+            drop(permit);
+            
+            match result {
+                Ok(()) => {
+                    throttle.succeeded().await;
+                    return Ok(());
+                }
+                Err(e) if settings.adaptive && is_overload(&e) => {
+                    let pause = throttle.overloaded(tokio::time::Instant::now()).await;
+                    if retries >= settings.max_retries {
+                        return Err(e);
+                    }
+                    retries += 1;
+                    log::warn!(
+                        "{} is overloaded ({}); pausing writes for {:?} before retry {} of {} for record {}",
+                        sync_pair.target_system,
+                        e,
+                        pause,
+                        retries,
+                        settings.max_retries,
+                        difference.source_id
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    
+    /// Open the target transaction a batch is written in
+    async fn begin_batch(&self, sync_pair: &SyncPair) -> Result<()> {
+        // This would begin a transaction on the target system's connection
+        log::debug!("Beginning batch on {}", sync_pair.target_system);
+        Ok(())
+    }
+    
+    async fn commit_batch(&self, sync_pair: &SyncPair) -> Result<()> {
+        // This would commit the target system's transaction
+        log::debug!("Committing batch on {}", sync_pair.target_system);
+        Ok(())
+    }
+    
+    async fn rollback_batch(&self, sync_pair: &SyncPair) -> Result<()> {
+        // This would roll back the target system's transaction
+        log::debug!("Rolling back batch on {}", sync_pair.target_system);
+        Ok(())
     }
     
     /// Process a single sync record
@@ -296,6 +1153,9 @@ impl SyncEngine {
         // This would implement the actual sync logic
         // Including conflict resolution based on sync_pair.sync_conflict_strategy
         log::debug!("Processing sync record for operation {}", operation_id);
+        if let Some(details) = difference.diff_details() {
+            log::debug!("Record {} changed shape: {}", difference.source_id, details);
+        }
         Ok(())
     }
     
@@ -330,6 +1190,21 @@ impl SyncEngine {
         Err(Error::NotFound("Sync operation not found".to_string()))
     }
     
+    /// Batches finished by earlier attempts at the operation
+    async fn batch_log(&self, operation_id: Uuid) -> Result<Vec<BatchLogEntry>> {
+        match SyncOperationQueries::batches(&self.db_pool.pool(), operation_id).await? {
+            Some(batches) => serde_json::from_value(batches)
+                .map_err(|e| Error::Serialization(format!("Invalid batch log for operation {}: {}", operation_id, e))),
+            None => Ok(Vec::new()),
+        }
+    }
+    
+    async fn log_batch(&self, operation_id: Uuid, entry: &BatchLogEntry) -> Result<()> {
+        let entry = serde_json::to_value(entry).map_err(|e| Error::Serialization(e.to_string()))?;
+        SyncOperationQueries::append_batch(&self.db_pool.pool(), operation_id, &entry).await?;
+        Ok(())
+    }
+    
     async fn update_operation_handle_stats(
         &self,
         operation_id: Uuid,
@@ -346,6 +1221,89 @@ impl SyncEngine {
     }
 }
 
+/// Result of loading one batch
+struct BatchLoad {
+    outcome: BatchOutcome,
+    /// Operation types of the records written, applied only if committed
+    loaded: Vec<SyncOperationType>,
+    errors: Vec<String>,
+    /// Per record attempted, in batch order, why it failed
+    record_errors: Vec<Option<String>>,
+}
+
+impl BatchLoad {
+    /// Why record `index` of the batch did not reach the target, or `None`
+    /// if it did. A rolled-back batch fails every record in it.
+    fn record_error(&self, index: usize) -> Option<String> {
+        match (self.outcome, self.record_errors.get(index).cloned().flatten()) {
+            (_, Some(error)) => Some(error),
+            (BatchOutcome::Committed, None) => None,
+            (BatchOutcome::RolledBack, None) => Some(format!(
+                "Batch rolled back: {}",
+                self.errors.first().map(String::as_str).unwrap_or("unknown error")
+            )),
+        }
+    }
+}
+
+/// The diff recorded for a record of a loaded batch
+fn sync_diff(operation_id: Uuid, entity_type: &str, difference: &SyncDifference, error: Option<String>) -> NewSyncDiff {
+    NewSyncDiff {
+        id: Uuid::new_v4(),
+        sync_operation_id: operation_id,
+        entity_id: difference.source_id.clone(),
+        entity_type: entity_type.to_string(),
+        change_type: difference.operation_type.change_type().to_string(),
+        source_data: Some(difference.source_data.clone()),
+        target_data: difference.target_data.clone(),
+        diff_details: difference.diff_details(),
+        sync_status: if error.is_some() { "FAILED" } else { "SYNCED" }.to_string(),
+        error_message: error,
+        created_at: Utc::now(),
+    }
+}
+
+/// Serialized size of a record's payload
+fn payload_size(data: &serde_json::Value) -> u64 {
+    serde_json::to_vec(data).map(|bytes| bytes.len() as u64).unwrap_or(0)
+}
+
+/// A pending operation record
+fn new_operation(sync_pair_id: Uuid, initiated_by: String, custom_parameters: Option<serde_json::Value>) -> SyncOperation {
+    SyncOperation {
+        base: terrafusion_common::models::BaseModel {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        },
+        sync_pair_id,
+        status: SyncStatus::Pending,
+        start_time: Utc::now(),
+        end_time: None,
+        records_processed: None,
+        records_succeeded: None,
+        records_failed: None,
+        error_message: None,
+        custom_parameters,
+        initiated_by,
+        narrative: None,
+    }
+}
+
+/// Lock held while an operation for the pair runs
+pub fn sync_pair_lock_key(sync_pair_id: Uuid) -> String {
+    format!("sync_pair:{}", sync_pair_id)
+}
+
+/// Record identifier as a string, accepting string or numeric keys
+pub fn record_key(record: &serde_json::Value, key_field: &str) -> Option<String> {
+    match record.get(key_field)? {
+        serde_json::Value::String(key) => Some(key.clone()),
+        serde_json::Value::Number(key) => Some(key.to_string()),
+        _ => None,
+    }
+}
+
 /// Represents a difference between source and target data
 #[derive(Debug, Clone)]
 pub struct SyncDifference {
@@ -354,6 +1312,24 @@ pub struct SyncDifference {
     pub operation_type: SyncOperationType,
     pub source_data: serde_json::Value,
     pub target_data: Option<serde_json::Value>,
+    /// Set when an update moved the record's boundary beyond tolerance
+    pub geometry_change: Option<GeometryChange>,
+    /// Set when a flagging boundary check found the geometry outside the county
+    pub boundary_violation: Option<String>,
+}
+
+impl SyncDifference {
+    /// `diff_details` recorded with the diff, read by the diff viewer
+    pub fn diff_details(&self) -> Option<serde_json::Value> {
+        let mut details = serde_json::Map::new();
+        if let Some(change) = &self.geometry_change {
+            details.insert("geometry_change".to_string(), serde_json::json!(change));
+        }
+        if let Some(violation) = &self.boundary_violation {
+            details.insert("boundary_violation".to_string(), violation.clone().into());
+        }
+        (!details.is_empty()).then_some(serde_json::Value::Object(details))
+    }
 }
 
 /// Type of sync operation needed
@@ -363,4 +1339,16 @@ pub enum SyncOperationType {
     Update,
     Delete,
     Conflict,
+}
+
+impl SyncOperationType {
+    /// `change_type` of the diff recorded for the record
+    pub fn change_type(&self) -> &'static str {
+        match self {
+            Self::Create => "ADDED",
+            Self::Update => "MODIFIED",
+            Self::Delete => "DELETED",
+            Self::Conflict => "CONFLICT",
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,281 @@
+//! Load throttling toward target systems.
+//!
+//! Some county targets, older CAMA databases in particular, fall over under
+//! bulk writes. A sync pair caps its load stage with a `throttle` object in
+//! the target config:
+//!
+//! ```json
+//! "throttle": {
+//!     "max_requests_per_second": 5,
+//!     "max_concurrent_writes": 2,
+//!     "adaptive": true,
+//!     "max_backoff_seconds": 60,
+//!     "max_retries": 3,
+//!     "write_timeout_seconds": 30
+//! }
+//! ```
+//!
+//! Pairs that write to the same target system in a county share a throttle.
+//! When the target refuses a write as overloaded (HTTP 429 or 503, or a
+//! timeout), an adaptive throttle halves its rate, pauses every writer and
+//! retries the record; each success afterwards raises the rate a little
+//! until it is back at the configured maximum. Limits apply per process, so
+//! with several sync workers each one enforces them on its own.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use terrafusion_common::models::sync::SyncPair;
+use terrafusion_common::{Error, Result};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// First pause after an overload; doubles on each further overload
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Lowest fraction of the configured rate an adaptive throttle drops to
+const MIN_RATE_FACTOR: f64 = 0.1;
+
+/// Fraction of the configured rate won back by each successful write
+const RECOVERY_STEP: f64 = 0.05;
+
+/// Throttle settings from the `throttle` object of a target config
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThrottleSettings {
+    /// Writes started per second; unlimited when unset
+    pub max_requests_per_second: Option<f64>,
+    /// Writes in flight at once; unlimited when unset
+    pub max_concurrent_writes: Option<usize>,
+    /// Slow down and retry when the target reports overload
+    pub adaptive: bool,
+    pub max_backoff_seconds: u64,
+    /// Retries of one record after overloads before it is counted as failed
+    pub max_retries: u32,
+    /// Writes taking longer than this count as an overload
+    pub write_timeout_seconds: Option<u64>,
+}
+
+impl Default for ThrottleSettings {
+    fn default() -> Self {
+        Self {
+            max_requests_per_second: None,
+            max_concurrent_writes: None,
+            adaptive: true,
+            max_backoff_seconds: 60,
+            max_retries: 3,
+            write_timeout_seconds: None,
+        }
+    }
+}
+
+impl ThrottleSettings {
+    /// Settings for a target config, or `None` when it has no `throttle`
+    pub fn from_target_config(config: &serde_json::Value) -> Result<Option<Self>> {
+        let Some(throttle) = config.get("throttle") else {
+            return Ok(None);
+        };
+        let settings: Self = serde_json::from_value(throttle.clone())
+            .map_err(|e| Error::Validation(format!("Invalid throttle settings: {}", e)))?;
+
+        if let Some(rate) = settings.max_requests_per_second {
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(Error::Validation("throttle.max_requests_per_second must be positive".to_string()));
+            }
+        }
+        if settings.max_concurrent_writes == Some(0) {
+            return Err(Error::Validation("throttle.max_concurrent_writes must be at least 1".to_string()));
+        }
+        if settings.max_backoff_seconds == 0 || settings.write_timeout_seconds == Some(0) {
+            return Err(Error::Validation("throttle durations must be at least one second".to_string()));
+        }
+        Ok(Some(settings))
+    }
+
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.write_timeout_seconds.map(Duration::from_secs)
+    }
+
+    fn max_backoff(&self) -> Duration {
+        Duration::from_secs(self.max_backoff_seconds)
+    }
+}
+
+struct Pacing {
+    /// Earliest start of the next write
+    next_at: Instant,
+    /// Share of the configured rate currently allowed
+    rate_factor: f64,
+    backoff: Duration,
+}
+
+/// Paces writes to one target system
+pub struct TargetThrottle {
+    settings: ThrottleSettings,
+    writes: Option<Arc<Semaphore>>,
+    pacing: Mutex<Pacing>,
+}
+
+/// A write slot; held for the duration of the write
+pub struct WritePermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl TargetThrottle {
+    pub fn new(settings: ThrottleSettings) -> Self {
+        Self {
+            writes: settings.max_concurrent_writes.map(|max| Arc::new(Semaphore::new(max))),
+            settings,
+            pacing: Mutex::new(Pacing {
+                next_at: Instant::now(),
+                rate_factor: 1.0,
+                backoff: INITIAL_BACKOFF,
+            }),
+        }
+    }
+
+    pub fn settings(&self) -> &ThrottleSettings {
+        &self.settings
+    }
+
+    /// Wait for a free write slot and then for the write's turn
+    pub async fn acquire(&self) -> WritePermit {
+        let permit = match &self.writes {
+            Some(writes) => Some(writes.clone().acquire_owned().await.expect("throttle semaphore is never closed")),
+            None => None,
+        };
+        let wait = self.reserve(Instant::now()).await;
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        WritePermit { _permit: permit }
+    }
+
+    /// Take the next start time and return how long until it arrives
+    async fn reserve(&self, now: Instant) -> Duration {
+        let mut pacing = self.pacing.lock().await;
+        let start = pacing.next_at.max(now);
+        pacing.next_at = start + self.interval(pacing.rate_factor);
+        start - now
+    }
+
+    fn interval(&self, rate_factor: f64) -> Duration {
+        match self.settings.max_requests_per_second {
+            Some(rate) => Duration::from_secs_f64(1.0 / (rate * rate_factor)),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Record a write the target accepted
+    pub async fn succeeded(&self) {
+        let mut pacing = self.pacing.lock().await;
+        pacing.rate_factor = (pacing.rate_factor + RECOVERY_STEP).min(1.0);
+        pacing.backoff = INITIAL_BACKOFF;
+    }
+
+    /// Record a write refused as overload: slow down, hold every writer for
+    /// the backoff and return it
+    pub async fn overloaded(&self, now: Instant) -> Duration {
+        let mut pacing = self.pacing.lock().await;
+        let pause = pacing.backoff;
+        pacing.next_at = pacing.next_at.max(now + pause);
+        pacing.rate_factor = (pacing.rate_factor / 2.0).max(MIN_RATE_FACTOR);
+        pacing.backoff = (pause * 2).min(self.settings.max_backoff());
+        pause
+    }
+}
+
+/// Whether an error means the target is overloaded rather than the write
+/// being wrong
+pub fn is_overload(error: &Error) -> bool {
+    match error {
+        Error::ServiceUnavailable(_) => true,
+        Error::HttpClient(e) => {
+            e.is_timeout()
+                || matches!(
+                    e.status().map(|status| status.as_u16()),
+                    Some(429) | Some(503)
+                )
+        }
+        Error::Sqlx(sqlx::Error::PoolTimedOut) => true,
+        Error::Io(e) => e.kind() == std::io::ErrorKind::TimedOut,
+        _ => false,
+    }
+}
+
+/// Throttles of the target systems this process writes to
+#[derive(Clone, Default)]
+pub struct TargetThrottles {
+    throttles: Arc<Mutex<HashMap<String, Arc<TargetThrottle>>>>,
+}
+
+impl TargetThrottles {
+    /// Throttle for the pair's target, or `None` when the pair sets none.
+    /// A throttle is replaced when the pair's settings have changed.
+    pub async fn for_pair(&self, sync_pair: &SyncPair) -> Result<Option<Arc<TargetThrottle>>> {
+        let Some(settings) = ThrottleSettings::from_target_config(&sync_pair.target_config)? else {
+            return Ok(None);
+        };
+        let key = format!("{}:{}", sync_pair.county_id, sync_pair.target_system);
+
+        let mut throttles = self.throttles.lock().await;
+        match throttles.get(&key) {
+            Some(throttle) if throttle.settings() == &settings => Ok(Some(throttle.clone())),
+            _ => {
+                let throttle = Arc::new(TargetThrottle::new(settings));
+                throttles.insert(key, throttle.clone());
+                Ok(Some(throttle))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_settings_from_target_config() {
+        assert_eq!(ThrottleSettings::from_target_config(&json!({ "table": "parcels" })).unwrap(), None);
+
+        let settings = ThrottleSettings::from_target_config(&json!({
+            "throttle": { "max_requests_per_second": 5, "max_concurrent_writes": 2 }
+        }))
+        .unwrap()
+        .unwrap();
+        assert_eq!(settings.max_requests_per_second, Some(5.0));
+        assert_eq!(settings.max_concurrent_writes, Some(2));
+        assert!(settings.adaptive);
+
+        assert!(ThrottleSettings::from_target_config(&json!({ "throttle": { "max_requests_per_second": 0 } })).is_err());
+        assert!(ThrottleSettings::from_target_config(&json!({ "throttle": { "max_rps": 5 } })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pacing_backs_off_and_recovers() {
+        let throttle = TargetThrottle::new(ThrottleSettings {
+            max_requests_per_second: Some(8.0),
+            max_backoff_seconds: 2,
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        assert_eq!(throttle.reserve(now).await, Duration::ZERO);
+        assert_eq!(throttle.reserve(now).await, Duration::from_millis(125));
+
+        // Overloads pause everyone, halve the rate and double the backoff up to its cap
+        assert_eq!(throttle.overloaded(now).await, Duration::from_secs(1));
+        assert_eq!(throttle.overloaded(now).await, Duration::from_secs(2));
+        assert_eq!(throttle.overloaded(now).await, Duration::from_secs(2));
+        assert_eq!(throttle.reserve(now).await, Duration::from_secs(2));
+        assert_eq!(throttle.reserve(now).await, Duration::from_secs(3));
+
+        throttle.succeeded().await;
+        let pacing = throttle.pacing.lock().await;
+        assert!((pacing.rate_factor - 0.175).abs() < 1e-9);
+        assert_eq!(pacing.backoff, INITIAL_BACKOFF);
+    }
+}
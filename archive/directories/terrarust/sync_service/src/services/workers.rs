@@ -0,0 +1,81 @@
+//! Registry of sync service processes.
+//!
+//! Every process, whatever its role, reports itself and the operations it
+//! is running to `sync_workers` on each heartbeat. `/system/workers` lists
+//! the registry; a process that stops heartbeating is shown as stale, and
+//! the job queue hands its unfinished operations to another worker once
+//! their leases run out.
+
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use terrafusion_common::database::RotatingPool;
+use uuid::Uuid;
+
+use crate::config::ServiceRole;
+use crate::models::database::{SyncWorkerRow, WorkerQueries};
+use super::sync_engine::SyncEngine;
+
+/// How long silent workers stay listed before they are forgotten
+const FORGET_AFTER_HOURS: i64 = 24;
+
+/// This process's entry in the worker registry
+#[derive(Clone)]
+pub struct WorkerRegistry {
+    pool: RotatingPool,
+    worker: SyncWorkerRow,
+}
+
+impl WorkerRegistry {
+    pub fn new(pool: RotatingPool, role: ServiceRole) -> Self {
+        let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
+        let worker_id = format!("{}-{}", hostname, &Uuid::new_v4().simple().to_string()[..8]);
+
+        Self {
+            pool,
+            worker: SyncWorkerRow {
+                worker_id,
+                hostname,
+                pid: std::process::id() as i32,
+                role: role.as_str().to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                started_at: Utc::now(),
+                last_heartbeat_at: Utc::now(),
+                current_operations: serde_json::json!([]),
+            },
+        }
+    }
+
+    pub fn worker_id(&self) -> &str {
+        &self.worker.worker_id
+    }
+
+    /// Report the operations `engine` is running every `interval`
+    pub fn spawn_heartbeat(&self, engine: SyncEngine, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+                let mut worker = registry.worker.clone();
+                worker.current_operations = serde_json::json!(engine.running_snapshot().await);
+
+                let pool = registry.pool.pool();
+                if let Err(e) = WorkerQueries::heartbeat(&pool, &worker).await {
+                    log::warn!("Failed to record worker heartbeat: {}", e);
+                }
+                let forget_before: DateTime<Utc> = Utc::now() - chrono::Duration::hours(FORGET_AFTER_HOURS);
+                if let Err(e) = WorkerQueries::remove_stale(&pool, forget_before).await {
+                    log::warn!("Failed to remove stale workers: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Leave the registry on shutdown
+    pub async fn deregister(&self) {
+        if let Err(e) = WorkerQueries::remove(&self.pool.pool(), self.worker_id()).await {
+            log::warn!("Failed to deregister worker {}: {}", self.worker_id(), e);
+        }
+    }
+}